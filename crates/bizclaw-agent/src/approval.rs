@@ -0,0 +1,165 @@
+//! Human-in-the-loop approval for sensitive tool calls.
+//!
+//! Tools listed in `[autonomy] require_approval` don't run until a human
+//! decides, nor does `execute_code` below `full` autonomy. The agent files an [`ApprovalRequest`] with the shared
+//! [`ApprovalQueue`] and waits; decisions come from the gateway
+//! (`/api/v1/approvals`) or Telegram inline buttons. Requests nobody
+//! answers within `approval_timeout_secs` are denied.
+
+use bizclaw_core::config::AutonomyConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+
+/// Whether calls to `tool` wait for a human under `autonomy`.
+pub fn required(autonomy: &AutonomyConfig, tool: &str) -> bool {
+    // Code snippets aren't isolated from the filesystem or network.
+    autonomy.require_approval.iter().any(|t| t == tool || t == "*")
+        || (tool == "execute_code" && autonomy.level != "full")
+}
+
+/// A tool call waiting for a human decision.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    /// Orchestrator name of the agent that wants to run the tool.
+    pub agent: String,
+    pub tool: String,
+    pub arguments: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A human decision on an [`ApprovalRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approve,
+    Deny(String),
+}
+
+struct Pending {
+    request: ApprovalRequest,
+    decide: oneshot::Sender<Decision>,
+}
+
+/// Pending approvals shared by agents and the places humans answer them.
+/// Cloning gives another handle to the same queue.
+#[derive(Clone)]
+pub struct ApprovalQueue {
+    pending: Arc<Mutex<HashMap<String, Pending>>>,
+    events: broadcast::Sender<ApprovalRequest>,
+}
+
+impl ApprovalQueue {
+    pub fn new() -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            events: broadcast::channel(64).0,
+        }
+    }
+
+    /// Receive each new request as it is filed (e.g. to prompt on Telegram).
+    pub fn subscribe(&self) -> broadcast::Receiver<ApprovalRequest> {
+        self.events.subscribe()
+    }
+
+    /// Requests still waiting for a decision, oldest first.
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let mut requests: Vec<ApprovalRequest> = pending.values().map(|p| p.request.clone()).collect();
+        requests.sort_by_key(|r| r.created_at);
+        requests
+    }
+
+    /// Answer request `id`. Returns false if it is unknown or already decided.
+    pub fn decide(&self, id: &str, decision: Decision) -> bool {
+        let entry = self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        match entry {
+            Some(pending) => {
+                tracing::info!("🛂 {} for {} ({}): {decision:?}", pending.request.id, pending.request.tool, pending.request.agent);
+                pending.decide.send(decision).is_ok()
+            }
+            None => false,
+        }
+    }
+
+    /// File a request for `agent` to run `tool` and wait for the decision,
+    /// denying it if none arrives within `timeout`.
+    pub async fn request(&self, agent: &str, tool: &str, arguments: &str, timeout: Duration) -> Decision {
+        let request = ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent: agent.to_string(),
+            tool: tool.to_string(),
+            arguments: arguments.to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        let id = request.id.clone();
+        let (decide, decision) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.clone(), Pending { request: request.clone(), decide });
+        tracing::info!("🛂 Approval needed: {agent} wants to run {tool} ({id})");
+        let _ = self.events.send(request);
+
+        match tokio::time::timeout(timeout, decision).await {
+            Ok(Ok(decision)) => decision,
+            Ok(Err(_)) => Decision::Deny("the approval request was dropped".into()),
+            Err(_) => {
+                self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+                Decision::Deny(format!("no decision within {}s", timeout.as_secs()))
+            }
+        }
+    }
+}
+
+impl Default for ApprovalQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_waits_for_decision() {
+        let queue = ApprovalQueue::new();
+        let mut events = queue.subscribe();
+        let approver = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let request = events.recv().await.unwrap();
+                assert_eq!(queue.pending().len(), 1);
+                assert!(queue.decide(&request.id, Decision::Approve));
+                assert!(!queue.decide(&request.id, Decision::Approve));
+            })
+        };
+
+        let decision = queue.request("ops", "shell", r#"{"command":"ls"}"#, Duration::from_secs(5)).await;
+        assert_eq!(decision, Decision::Approve);
+        approver.await.unwrap();
+        assert!(queue.pending().is_empty());
+    }
+
+    #[test]
+    fn test_required() {
+        let mut autonomy = AutonomyConfig::default();
+        assert!(required(&autonomy, "execute_code"));
+        assert!(!required(&autonomy, "shell"));
+        autonomy.level = "full".into();
+        assert!(!required(&autonomy, "execute_code"));
+        autonomy.require_approval = vec!["*".into()];
+        assert!(required(&autonomy, "shell"));
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_request_is_denied() {
+        let queue = ApprovalQueue::new();
+        let decision = queue.request("ops", "shell", "{}", Duration::from_millis(10)).await;
+        assert!(matches!(decision, Decision::Deny(_)));
+        assert!(queue.pending().is_empty());
+    }
+}
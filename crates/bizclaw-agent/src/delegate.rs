@@ -0,0 +1,147 @@
+//! Agent-as-tool delegation — lets one orchestrator agent call another by
+//! name with a sub-prompt and a budget.
+//!
+//! The tool never touches the orchestrator (its caller is already running
+//! inside it, usually behind the gateway's lock). It sends a
+//! [`DelegateRequest`] over a channel instead; the orchestrator serves the
+//! request while it waits on the calling agent (see
+//! [`crate::orchestrator::Orchestrator::enable_delegation`]).
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use serde::Deserialize;
+use tokio::sync::{mpsc, oneshot};
+
+/// Limits for one delegated run. Unset fields keep the target agent's own
+/// settings; set fields can only lower them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct DelegateBudget {
+    /// Tool rounds the target may use.
+    pub max_rounds: Option<u32>,
+    /// Max tokens per model reply.
+    pub max_tokens: Option<u32>,
+    /// Seconds the run may take before it fails.
+    pub max_secs: Option<u64>,
+}
+
+/// A sub-prompt for another agent, answered through `reply`.
+pub struct DelegateRequest {
+    pub from: String,
+    pub to: String,
+    pub prompt: String,
+    pub budget: DelegateBudget,
+    pub reply: oneshot::Sender<Result<String>>,
+}
+
+/// `call_agent` — ask another agent and use its answer as the tool result.
+pub struct DelegateTool {
+    from: String,
+    /// (name, description) of the agents that can be called.
+    agents: Vec<(String, String)>,
+    requests: mpsc::UnboundedSender<DelegateRequest>,
+}
+
+impl DelegateTool {
+    pub fn new(
+        from: &str,
+        agents: Vec<(String, String)>,
+        requests: mpsc::UnboundedSender<DelegateRequest>,
+    ) -> Self {
+        Self {
+            from: from.to_string(),
+            agents,
+            requests,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CallAgentArgs {
+    agent: String,
+    prompt: String,
+    #[serde(flatten)]
+    budget: DelegateBudget,
+}
+
+#[async_trait]
+impl Tool for DelegateTool {
+    fn name(&self) -> &str {
+        "call_agent"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        let agents: Vec<String> = self
+            .agents
+            .iter()
+            .map(|(name, description)| format!("{name} ({description})"))
+            .collect();
+        ToolDefinition {
+            name: "call_agent".to_string(),
+            description: format!(
+                "Ask another agent to handle a sub-task and return its answer. Available agents: {}",
+                agents.join(", ")
+            ),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "agent": {
+                        "type": "string",
+                        "enum": self.agents.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+                        "description": "Name of the agent to call"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Self-contained instruction, including any text the agent needs"
+                    },
+                    "max_rounds": {
+                        "type": "integer",
+                        "description": "Max tool rounds the agent may use (optional)"
+                    },
+                    "max_tokens": {
+                        "type": "integer",
+                        "description": "Max tokens for the agent's reply (optional)"
+                    },
+                    "max_secs": {
+                        "type": "integer",
+                        "description": "Max seconds the agent may take (optional)"
+                    }
+                },
+                "required": ["agent", "prompt"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: CallAgentArgs = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(format!("Invalid args: {e}")))?;
+
+        let (reply, answer) = oneshot::channel();
+        self.requests
+            .send(DelegateRequest {
+                from: self.from.clone(),
+                to: args.agent.clone(),
+                prompt: args.prompt,
+                budget: args.budget,
+                reply,
+            })
+            .map_err(|_| BizClawError::Tool("Orchestrator is not running".into()))?;
+        let answer = answer
+            .await
+            .map_err(|_| BizClawError::Tool(format!("Agent '{}' did not answer", args.agent)))?;
+
+        Ok(match answer {
+            Ok(output) => ToolResult {
+                tool_call_id: String::new(),
+                output,
+                success: true,
+            },
+            Err(e) => ToolResult {
+                tool_call_id: String::new(),
+                output: format!("Agent '{}' failed: {e}", args.agent),
+                success: false,
+            },
+        })
+    }
+}
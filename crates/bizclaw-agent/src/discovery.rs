@@ -104,7 +104,7 @@ pub fn search_agents<'a>(agents: &'a [AgentInfo], query: &str) -> Vec<&'a AgentI
         })
         .collect();
 
-    scored.sort_by(|a, b| b.1.cmp(&a.1));
+    scored.sort_by_key(|b| std::cmp::Reverse(b.1));
     scored.into_iter().map(|(a, _)| a).collect()
 }
 
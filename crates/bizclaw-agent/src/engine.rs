@@ -1,5 +1,6 @@
 //! Agent engine internals — core processing pipeline.
 
+use bizclaw_core::traits::tokenizer::{ApproxTokenizer, count_message_tokens};
 use bizclaw_core::types::{Message, ProviderResponse};
 
 /// Format a provider response for display.
@@ -18,12 +19,10 @@ pub fn format_response(response: &ProviderResponse) -> String {
     }
 }
 
-/// Estimate token count for a message list (rough: 4 chars ≈ 1 token).
+/// Estimate token count for a message list (tiktoken-style, see
+/// [`ApproxTokenizer`]).
 pub fn estimate_tokens(messages: &[Message]) -> usize {
-    messages
-        .iter()
-        .map(|m| m.content.len() / 4 + 5) // content + overhead per message
-        .sum()
+    count_message_tokens(&ApproxTokenizer, messages)
 }
 
 /// Check if conversation needs compaction.
@@ -0,0 +1,239 @@
+//! Per-agent provider fallback.
+//!
+//! Unlike the global `FailoverProvider` (health-tracked chain behind a single
+//! provider), this retries the *same conversation* on the agent's own ordered
+//! fallback list — only when the primary fails with an error another provider
+//! could plausibly avoid (transient, rate-limited, auth). With
+//! `[LLM.failover]` enabled the fallbacks live in the agent's
+//! `FailoverProvider` instead and this list is empty.
+
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::Result;
+use bizclaw_core::traits::Provider;
+use bizclaw_core::traits::provider::{ChatChunkSink, GenerateParams};
+use bizclaw_core::types::{Message, ProviderResponse, ToolDefinition};
+
+/// A fallback provider with the model to request from it.
+pub struct FallbackProvider {
+    pub provider: Box<dyn Provider>,
+    pub model: String,
+}
+
+/// Build the fallback list from `[LLM] fallback_providers`, skipping entries
+/// that fail to initialize.
+pub fn from_config(config: &BizClawConfig) -> Vec<FallbackProvider> {
+    if config.llm.failover.enabled {
+        return Vec::new();
+    }
+    config
+        .llm
+        .fallback_providers
+        .iter()
+        .filter_map(|fb| {
+            match bizclaw_providers::create_fallback_provider(config, fb) {
+                Ok((provider, model)) => Some(FallbackProvider { provider, model }),
+                Err(e) => {
+                    tracing::warn!("⚠️ Fallback provider '{}' unavailable: {e}", fb.provider);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Send a chat request to the primary, then to each fallback in order while
+/// the error is failover-eligible. Fallbacks get tools per
+/// [`bizclaw_providers::fallback_tools`].
+pub async fn chat_with_fallback(
+    primary: &dyn Provider,
+    fallbacks: &[FallbackProvider],
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    params: &GenerateParams,
+) -> Result<ProviderResponse> {
+    chat_stream_with_fallback(primary, fallbacks, messages, tools, params, None).await
+}
+
+/// Like [`chat_with_fallback`], sending the reply text to `sink` as it is
+/// generated (see [`Provider::chat_stream`]).
+pub async fn chat_stream_with_fallback(
+    primary: &dyn Provider,
+    fallbacks: &[FallbackProvider],
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    params: &GenerateParams,
+    sink: Option<&ChatChunkSink>,
+) -> Result<ProviderResponse> {
+    let mut err = match chat(primary, messages, tools, params, sink).await {
+        Ok(resp) => return Ok(resp),
+        Err(e) => e,
+    };
+
+    for fb in fallbacks {
+        if !err.is_failover_eligible() {
+            break;
+        }
+        tracing::warn!(
+            "🔄 Provider fallback: {} failed ({err}) → trying {} ({})",
+            primary.name(),
+            fb.provider.name(),
+            fb.model
+        );
+        let fb_params = GenerateParams {
+            model: fb.model.clone(),
+            ..params.clone()
+        };
+        let fb_tools = bizclaw_providers::fallback_tools(fb.provider.as_ref(), tools);
+        match chat(fb.provider.as_ref(), messages, fb_tools, &fb_params, sink).await {
+            Ok(resp) => {
+                tracing::info!("✅ Fallback {} answered", fb.provider.name());
+                return Ok(resp);
+            }
+            Err(e) => err = e,
+        }
+    }
+    Err(err)
+}
+
+async fn chat(
+    provider: &dyn Provider,
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    params: &GenerateParams,
+    sink: Option<&ChatChunkSink>,
+) -> Result<ProviderResponse> {
+    let result = match sink {
+        Some(sink) => provider.chat_stream(messages, tools, params, sink).await,
+        None => provider.chat(messages, tools, params).await,
+    };
+    if result.is_err() {
+        bizclaw_core::metrics::increment("bizclaw_provider_errors_total", &[("provider", provider.name())]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::error::BizClawError;
+    use bizclaw_core::types::ModelInfo;
+    use std::sync::Mutex;
+
+    /// Provider that fails with a fixed error, or answers with its name + model.
+    struct Mock {
+        name: &'static str,
+        fail: Option<fn() -> BizClawError>,
+        seen_models: Mutex<Vec<String>>,
+    }
+
+    impl Mock {
+        fn ok(name: &'static str) -> Self {
+            Self { name, fail: None, seen_models: Mutex::new(vec![]) }
+        }
+        fn failing(name: &'static str, fail: fn() -> BizClawError) -> Self {
+            Self { name, fail: Some(fail), seen_models: Mutex::new(vec![]) }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for Mock {
+        fn name(&self) -> &str {
+            self.name
+        }
+        async fn chat(
+            &self,
+            messages: &[Message],
+            _tools: &[ToolDefinition],
+            params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            self.seen_models.lock().unwrap().push(params.model.clone());
+            if let Some(fail) = self.fail {
+                return Err(fail());
+            }
+            Ok(ProviderResponse {
+                content: Some(format!(
+                    "{}:{}:{}",
+                    self.name,
+                    params.model,
+                    messages.last().map(|m| m.content.as_str()).unwrap_or_default()
+                )),
+                tool_calls: vec![],
+                finish_reason: Some("stop".into()),
+                usage: None,
+            })
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(self.fail.is_none())
+        }
+    }
+
+    fn params() -> GenerateParams {
+        GenerateParams { model: "primary-model".into(), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_primary_fails_secondary_answers() {
+        let primary = Mock::failing("openai", || BizClawError::Transient("503".into()));
+        let fallbacks = vec![FallbackProvider {
+            provider: Box::new(Mock::ok("groq")),
+            model: "llama-3.3-70b".into(),
+        }];
+        let messages = vec![Message::system("sys"), Message::user("hello")];
+
+        let resp = chat_with_fallback(&primary, &fallbacks, &messages, &[], &params())
+            .await
+            .unwrap();
+        assert_eq!(resp.content.as_deref(), Some("groq:llama-3.3-70b:hello"));
+        assert_eq!(*primary.seen_models.lock().unwrap(), vec!["primary-model"]);
+    }
+
+    #[tokio::test]
+    async fn test_walks_chain_in_order() {
+        let primary = Mock::failing("openai", || BizClawError::RateLimited("429".into()));
+        let fallbacks = vec![
+            FallbackProvider {
+                provider: Box::new(Mock::failing("anthropic", || {
+                    BizClawError::AuthFailed("401".into())
+                })),
+                model: "claude".into(),
+            },
+            FallbackProvider { provider: Box::new(Mock::ok("ollama")), model: "llama3.2".into() },
+        ];
+        let messages = vec![Message::user("hi")];
+
+        let resp = chat_with_fallback(&primary, &fallbacks, &messages, &[], &params())
+            .await
+            .unwrap();
+        assert_eq!(resp.content.as_deref(), Some("ollama:llama3.2:hi"));
+    }
+
+    #[tokio::test]
+    async fn test_non_eligible_error_does_not_fail_over() {
+        let primary = Mock::failing("openai", || BizClawError::Provider("400 bad request".into()));
+        let secondary = Mock::ok("groq");
+        let fallbacks = vec![FallbackProvider { provider: Box::new(secondary), model: "m".into() }];
+
+        let err = chat_with_fallback(&primary, &fallbacks, &[Message::user("x")], &[], &params())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BizClawError::Provider(_)));
+    }
+
+    #[tokio::test]
+    async fn test_streams_fallback_reply() {
+        let primary = Mock::failing("openai", || BizClawError::Transient("503".into()));
+        let fallbacks = vec![FallbackProvider { provider: Box::new(Mock::ok("groq")), model: "m".into() }];
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let resp = chat_stream_with_fallback(&primary, &fallbacks, &[Message::user("hi")], &[], &params(), Some(&tx))
+            .await
+            .unwrap();
+        assert_eq!(resp.content.as_deref(), Some("groq:m:hi"));
+        assert_eq!(rx.try_recv().unwrap(), "groq:m:hi");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -2,26 +2,38 @@
 //! The core agent engine — orchestrates providers, channels, memory, and tools.
 //!
 //! ## Features (BizClaw agent features):
-//! - **Multi-round tool calling**: Up to 3 rounds of tool → LLM → tool loops
-//! - **Memory retrieval (RAG)**: FTS5-powered search of past conversations
-//! - **Knowledge base integration**: Auto-search uploaded documents for context
+//! - **Multi-round tool calling**: Configurable rounds of tool → LLM → tool loops,
+//!   with repeated identical tool calls detected and cut short
+//! - **Retrieval (RAG)**: Knowledge base, past conversations and custom retrievers,
+//!   filtered, deduplicated and optionally reranked (see [`rag`])
 //! - **Auto-compaction**: Summarizes long conversations to prevent context overflow
-//! - **Session management**: Thread isolation via session_id
+//! - **Session management**: Thread isolation via session_id, one conversation
+//!   per chat on a shared agent (see [`sessions`]), with forked branches and
+//!   edit/regenerate of earlier turns
 //! - **Context tracking**: Monitor conversation length and estimate token usage
 
+pub mod approval;
 pub mod context;
+pub mod delegate;
 pub mod discovery;
 pub mod engine;
+pub mod fallback;
 pub mod orchestrator;
 pub mod proactive;
+pub mod progress;
+pub mod rag;
+pub mod routing;
+pub mod sessions;
+pub mod structured;
 
 use bizclaw_core::config::BizClawConfig;
-use bizclaw_core::error::Result;
+use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Provider;
-use bizclaw_core::traits::SecurityPolicy;
+use bizclaw_core::traits::tokenizer::{Tokenizer, count_message_tokens};
+use bizclaw_core::traits::tool::{CancellationToken, ToolOutputSink};
 use bizclaw_core::traits::memory::MemoryBackend;
-use bizclaw_core::traits::provider::GenerateParams;
-use bizclaw_core::types::{Message, OutgoingMessage};
+use bizclaw_core::traits::provider::{ChatChunkSink, GenerateParams};
+use bizclaw_core::types::{ImageInput, Message, OutgoingMessage, ProviderResponse, Role, ToolDefinition};
 
 /// Prompt cache — caches serialized system prompt + tool definitions to avoid
 /// re-serializing on every request.
@@ -62,12 +74,16 @@ impl PromptCache {
     }
 }
 
+/// Extra attempts [`Agent::process_structured`] makes after a reply that
+/// doesn't match the schema.
+pub const STRUCTURED_RETRIES: usize = 2;
+
 /// Context statistics for monitoring.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ContextStats {
     /// Number of messages in conversation
     pub message_count: usize,
-    /// Estimated token count (rough: 4 chars ≈ 1 token)
+    /// Token count from the provider's tokenizer
     pub estimated_tokens: usize,
     /// Context utilization percentage (based on max_context)
     pub utilization_pct: f32,
@@ -75,39 +91,190 @@ pub struct ContextStats {
     pub max_context: usize,
     /// Number of tool rounds executed in last request
     pub last_tool_rounds: usize,
+    /// Number of tool calls requested in last request, counting those
+    /// refused over the tool call limit
+    pub last_tool_calls: usize,
     /// Whether auto-compaction was triggered
     pub compacted: bool,
+    /// Tool that was called repeatedly with identical arguments, if the
+    /// last request was cut short by loop detection
+    pub tool_loop: Option<String>,
     /// Current session ID
     pub session_id: String,
 }
 
+/// A session forked from another with [`Agent::fork_session`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Branch {
+    /// Session ID of the branch
+    pub session_id: String,
+    /// Session it was forked from
+    pub parent: String,
+    /// Messages (counting the system prompt) copied from the parent
+    pub fork_at: usize,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Tool arguments normalized for loop detection, so key order and
+/// whitespace differences still count as the same call.
+fn canonical_arguments(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| arguments.trim().to_string())
+}
+
+/// Provider named by `memory.embedding_provider` ("none" disables vector
+/// memory). Falls back to keyword-only memory if it can't be created.
+fn embedding_provider(config: &BizClawConfig) -> Option<Box<dyn Provider>> {
+    let name = config.memory.embedding_provider.as_str();
+    if name.is_empty() || name == "none" {
+        return None;
+    }
+    let mut embed_config = config.clone();
+    embed_config.llm.provider = name.to_string();
+    bizclaw_providers::create_provider(&embed_config)
+        .inspect_err(|e| tracing::warn!("Embedding provider '{name}' unavailable, memory search is keyword-only: {e}"))
+        .ok()
+}
+
+/// Shell tool gated by the autonomy policy and confined to the workspace
+/// (`workspace`, or the working directory) when `workspace_only` is set.
+fn secured_shell_tool(config: &BizClawConfig, workspace: Option<std::path::PathBuf>) -> Box<dyn bizclaw_core::traits::Tool> {
+    let workspace = if config.autonomy.workspace_only {
+        workspace.or_else(|| std::env::current_dir().ok())
+    } else {
+        None
+    };
+    let shell = bizclaw_tools::shell::ShellTool::with_config(bizclaw_tools::shell::ShellConfig {
+        workspace,
+        max_output_bytes: config.autonomy.shell_max_output_bytes,
+    })
+    .with_security(std::sync::Arc::new(
+        bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone()),
+    ));
+    Box::new(shell)
+}
+
+/// File tools (`file`, `edit_file`, `glob`, `grep`, `document_reader`,
+/// `csv_query`) gated by the autonomy policy's paths and, with
+/// `workspace_only`, confined to `workspace` (or the working directory).
+fn secured_file_tools(config: &BizClawConfig, workspace: Option<std::path::PathBuf>) -> Vec<Box<dyn bizclaw_core::traits::Tool>> {
+    let security: std::sync::Arc<dyn bizclaw_core::traits::SecurityPolicy> =
+        std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone()));
+    let mut guard = bizclaw_tools::path_guard::PathGuard::new().with_security(security.clone());
+    let mut csv = bizclaw_tools::csv_query::CsvQueryTool::new().with_security(security);
+    if config.autonomy.workspace_only
+        && let Some(dir) = workspace.or_else(|| std::env::current_dir().ok())
+    {
+        guard = guard.with_workspace(dir.clone());
+        csv = csv.with_workspace(dir);
+    }
+    vec![
+        Box::new(bizclaw_tools::file::FileTool::new().with_path_guard(guard.clone())),
+        Box::new(bizclaw_tools::edit_file::EditFileTool::new().with_path_guard(guard.clone())),
+        Box::new(bizclaw_tools::glob_find::GlobTool::new().with_path_guard(guard.clone())),
+        Box::new(bizclaw_tools::grep_search::GrepTool::new().with_path_guard(guard.clone())),
+        Box::new(bizclaw_tools::document_reader::DocumentReaderTool::new().with_path_guard(guard)),
+        Box::new(csv),
+    ]
+}
+
+/// Browse tool limited to the domains the autonomy policy allows.
+fn secured_browse_tool(config: &BizClawConfig) -> Box<dyn bizclaw_core::traits::Tool> {
+    Box::new(bizclaw_tools::browse::BrowseTool::new().with_security(std::sync::Arc::new(
+        bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone()),
+    )))
+}
+
+/// Code execution tool with the `[autonomy.code]` limits, refused in
+/// readonly mode.
+fn secured_code_tool(config: &BizClawConfig) -> Box<dyn bizclaw_core::traits::Tool> {
+    Box::new(
+        bizclaw_tools::execute_code::ExecuteCodeTool::with_config(config.autonomy.code.clone())
+            .with_security(std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(
+                config.autonomy.clone(),
+            ))),
+    )
+}
+
+/// The built-in tools with the `[autonomy]` policy applied: shell, file,
+/// browse and code tools are gated and, with `workspace_only`, confined to
+/// the working directory. `execute_code` is left out unless
+/// `[autonomy.code] enabled` is set.
+pub fn secured_tools(config: &BizClawConfig) -> bizclaw_tools::ToolRegistry {
+    let mut tools = bizclaw_tools::ToolRegistry::with_defaults();
+    tools.replace(secured_shell_tool(config, None));
+    tools.replace(secured_browse_tool(config));
+    if config.autonomy.code.enabled {
+        tools.replace(secured_code_tool(config));
+    } else {
+        tools.remove("execute_code");
+    }
+    for tool in secured_file_tools(config, None) {
+        tools.replace(tool);
+    }
+    tools
+}
+
 /// The BizClaw agent — processes messages using LLM providers and tools.
 pub struct Agent {
     config: BizClawConfig,
     provider: Box<dyn Provider>,
+    /// Counts tokens as the provider's model does.
+    tokenizer: std::sync::Arc<dyn Tokenizer>,
+    /// Ordered providers retried when the primary fails mid-conversation.
+    fallback_providers: Vec<fallback::FallbackProvider>,
     memory: Box<dyn MemoryBackend>,
     tools: bizclaw_tools::ToolRegistry,
-    security: bizclaw_security::DefaultSecurityPolicy,
+    /// Receives streamed tool output (shell stdout/stderr) while tools run.
+    tool_output_sink: Option<ToolOutputSink>,
+    /// Cancels running tool executions (each runs under a child token).
+    cancel: CancellationToken,
+    /// Checks tool call arguments against `[[autonomy.argument_filters]]`.
+    security: std::sync::Arc<dyn bizclaw_core::traits::SecurityPolicy>,
+    /// Where tool calls needing approval are sent, with this agent's name.
+    approvals: Option<(approval::ApprovalQueue, String)>,
+    /// Where tool and compaction events are published, with this agent's name.
+    events: Option<(bizclaw_core::events::EventBus, String)>,
+    /// When set, the only tools offered to the model and executed.
+    tool_allowlist: Option<Vec<String>>,
+    /// When set, the most tool calls executed per request.
+    tool_call_limit: Option<usize>,
     conversation: Vec<Message>,
     prompt_cache: PromptCache,
     /// Current session ID for memory isolation
     session_id: String,
+    /// Persist history after each message; set once a session is attached.
+    persist_history: bool,
+    /// Sessions forked while this agent ran, oldest first.
+    branches: Vec<Branch>,
+    /// Conversations of the other chats this agent answers (see [`Agent::switch_session`]).
+    sessions: sessions::SessionPool,
     /// Knowledge base for RAG (optional, shared with gateway)
-    knowledge:
-        Option<std::sync::Arc<tokio::sync::Mutex<Option<bizclaw_knowledge::KnowledgeStore>>>>,
+    knowledge: Option<rag::SharedKnowledge>,
+    /// Retrievers queried alongside the built-in knowledge and memory ones.
+    retrievers: Vec<Box<dyn rag::Retriever>>,
+    /// Prompt templates (name, text) appended to the system prompt.
+    prompt_templates: Vec<(String, String)>,
     /// Context statistics from last process() call
     last_stats: ContextStats,
     /// 3-Tier Memory: daily log manager for persisting compaction summaries
     daily_log: bizclaw_memory::brain::DailyLogManager,
+    /// When memory was last pruned (see `memory.prune_interval_mins`).
+    last_prune: std::time::Instant,
+    /// Knowledge chunks cited in the last answer.
+    last_citations: Vec<rag::Citation>,
 }
 
 impl Agent {
-    /// Create a new agent from configuration (sync, no MCP).
+    /// Create a new agent from configuration (sync, no MCP). Agents added
+    /// to an orchestrator get MCP tools from its shared pool instead (see
+    /// [`orchestrator::Orchestrator::set_mcp_pool`]).
     pub fn new(config: BizClawConfig) -> Result<Self> {
-        let provider = bizclaw_providers::create_provider(&config)?;
-        let memory = bizclaw_memory::create_memory(&config.memory)?;
-        let tools = bizclaw_tools::ToolRegistry::with_defaults();
-        let security = bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone());
+        let provider = bizclaw_providers::create_provider_chain(&config)?;
+        let fallback_providers = fallback::from_config(&config);
+        let memory = bizclaw_memory::create_memory(&config.memory, embedding_provider(&config))?;
+        let tools = secured_tools(&config);
 
         // 3-Tier Memory: assemble brain context from workspace files
         let brain_ws = bizclaw_memory::brain::BrainWorkspace::default();
@@ -127,39 +294,56 @@ impl Agent {
         let conversation = vec![Message::system(&system_prompt)];
 
         Ok(Self {
+            sessions: sessions::SessionPool::new(config.memory.session_idle_mins, config.memory.max_sessions),
+            security: std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone())),
             config,
+            tokenizer: provider.tokenizer(),
             provider,
+            fallback_providers,
             memory,
             tools,
-            security,
+            tool_output_sink: None,
+            cancel: CancellationToken::new(),
+            approvals: None,
+            events: None,
+            tool_allowlist: None,
+            tool_call_limit: None,
             conversation,
             prompt_cache,
             session_id: "default".to_string(),
+            persist_history: false,
+            branches: vec![],
             knowledge: None,
+            retrievers: vec![],
+            prompt_templates: vec![],
             last_stats: ContextStats {
                 message_count: 1,
                 estimated_tokens: 0,
                 utilization_pct: 0.0,
                 max_context: 128000,
                 last_tool_rounds: 0,
+                last_tool_calls: 0,
                 compacted: false,
+                tool_loop: None,
                 session_id: "default".to_string(),
             },
             daily_log,
+            last_prune: std::time::Instant::now(),
+            last_citations: vec![],
         })
     }
 
     /// Create a new agent with MCP server support (async).
     pub async fn new_with_mcp(config: BizClawConfig) -> Result<Self> {
-        // CRITICAL: create_provider is sync and can block (e.g., brain GGUF loading).
+        // CRITICAL: create_provider_chain is sync and can block (e.g., brain GGUF loading).
         // Run it on a blocking thread so it doesn't stall the tokio runtime.
         let config_clone = config.clone();
         let provider = tokio::task::spawn_blocking(move || {
-            bizclaw_providers::create_provider(&config_clone)
+            bizclaw_providers::create_provider_chain(&config_clone)
         }).await.map_err(|e| bizclaw_core::error::BizClawError::Other(format!("spawn: {e}")))??;
-        let memory = bizclaw_memory::create_memory(&config.memory)?;
-        let mut tools = bizclaw_tools::ToolRegistry::with_defaults();
-        let security = bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone());
+        let fallback_providers = fallback::from_config(&config);
+        let memory = bizclaw_memory::create_memory(&config.memory, embedding_provider(&config))?;
+        let mut tools = secured_tools(&config);
 
         // Connect MCP servers and register their tools
         if !config.mcp_servers.is_empty() {
@@ -167,17 +351,8 @@ impl Agent {
                 "🔗 Connecting {} MCP server(s)...",
                 config.mcp_servers.len()
             );
-            let mcp_configs: Vec<bizclaw_mcp::McpServerConfig> = config
-                .mcp_servers
-                .iter()
-                .map(|e| bizclaw_mcp::McpServerConfig {
-                    name: e.name.clone(),
-                    command: e.command.clone(),
-                    args: e.args.clone(),
-                    env: e.env.clone(),
-                    enabled: e.enabled,
-                })
-                .collect();
+            let mcp_configs: Vec<bizclaw_mcp::McpServerConfig> =
+                config.mcp_servers.iter().map(Into::into).collect();
 
             let results = tokio::time::timeout(
                 std::time::Duration::from_secs(10),
@@ -217,40 +392,230 @@ impl Agent {
         let conversation = vec![Message::system(&system_prompt)];
 
         Ok(Self {
+            sessions: sessions::SessionPool::new(config.memory.session_idle_mins, config.memory.max_sessions),
+            security: std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone())),
             config,
+            tokenizer: provider.tokenizer(),
             provider,
+            fallback_providers,
             memory,
             tools,
-            security,
+            tool_output_sink: None,
+            cancel: CancellationToken::new(),
+            approvals: None,
+            events: None,
+            tool_allowlist: None,
+            tool_call_limit: None,
             conversation,
             prompt_cache,
             session_id: "default".to_string(),
+            persist_history: false,
+            branches: vec![],
             knowledge: None,
+            retrievers: vec![],
+            prompt_templates: vec![],
             daily_log,
+            last_prune: std::time::Instant::now(),
+            last_citations: vec![],
             last_stats: ContextStats {
                 message_count: 1,
                 estimated_tokens: 0,
                 utilization_pct: 0.0,
                 max_context: 128000,
                 last_tool_rounds: 0,
+                last_tool_calls: 0,
                 compacted: false,
+                tool_loop: None,
                 session_id: "default".to_string(),
             },
         })
     }
 
     /// Attach a knowledge base for RAG-enhanced responses.
-    pub fn set_knowledge(
-        &mut self,
-        kb: std::sync::Arc<tokio::sync::Mutex<Option<bizclaw_knowledge::KnowledgeStore>>>,
-    ) {
+    pub fn set_knowledge(&mut self, kb: rag::SharedKnowledge) {
         self.knowledge = Some(kb);
     }
 
-    /// Set the current session ID for memory isolation.
-    pub fn set_session(&mut self, session_id: &str) {
+    /// Limit knowledge retrieval to `namespaces` (all when empty).
+    pub fn set_knowledge_namespaces(&mut self, namespaces: Vec<String>) {
+        self.config.knowledge.namespaces = namespaces;
+    }
+
+    /// Namespaces knowledge retrieval is limited to (empty = all).
+    pub fn knowledge_namespaces(&self) -> &[String] {
+        &self.config.knowledge.namespaces
+    }
+
+    /// Limit tool rounds per message, overriding `autonomy.max_tool_rounds`.
+    pub fn set_max_tool_rounds(&mut self, rounds: u32) {
+        self.config.autonomy.max_tool_rounds = rounds;
+    }
+
+    /// Tool rounds allowed per message.
+    pub fn max_tool_rounds(&self) -> u32 {
+        self.config.autonomy.max_tool_rounds
+    }
+
+    /// Query `retriever` for context on every message, alongside the
+    /// retrievers named in `rag.retrievers`.
+    pub fn add_retriever(&mut self, retriever: Box<dyn rag::Retriever>) {
+        self.retrievers.push(retriever);
+    }
+
+    /// Add `tool`, replacing any tool with the same name.
+    pub fn register_tool(&mut self, tool: Box<dyn bizclaw_core::traits::Tool>) {
+        self.tools.replace(tool);
+        self.prompt_cache.cached_tool_defs = self.tools.list();
+    }
+
+    /// Run shell commands and file tools in `dir`, the agent's own workspace,
+    /// instead of the process's working directory. Only applies with
+    /// `autonomy.workspace_only`; otherwise the tools aren't confined.
+    pub fn set_workspace(&mut self, dir: std::path::PathBuf) {
+        if self.config.autonomy.workspace_only {
+            self.register_tool(secured_shell_tool(&self.config, Some(dir.clone())));
+            for tool in secured_file_tools(&self.config, Some(dir)) {
+                self.register_tool(tool);
+            }
+        }
+    }
+
+    /// Stream tool output (e.g. shell stdout/stderr lines) to `sink` while
+    /// tools run (None = stop streaming).
+    pub fn set_tool_output_sink(&mut self, sink: Option<ToolOutputSink>) {
+        self.tool_output_sink = sink;
+    }
+
+    /// Use `token` to cancel tool executions from outside the agent. A
+    /// cancelled token fails every later tool call until it is replaced.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    /// Token cancelling this agent's running tool executions.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Send tool calls listed in `[autonomy] require_approval` to `queue`,
+    /// filed under `agent_name`. Without a queue such calls are denied.
+    pub fn set_approvals(&mut self, queue: approval::ApprovalQueue, agent_name: &str) {
+        self.approvals = Some((queue, agent_name.to_string()));
+    }
+
+    /// Offer and run only `tools` (None = every registered tool), e.g. for
+    /// a playbook phase limited to a few tools.
+    pub fn set_tool_allowlist(&mut self, tools: Option<Vec<String>>) {
+        self.tool_allowlist = tools;
+    }
+
+    /// Execute at most `limit` tool calls per request (None = no limit);
+    /// further calls are refused and the model must answer.
+    pub fn set_tool_call_limit(&mut self, limit: Option<usize>) {
+        self.tool_call_limit = limit;
+    }
+
+    fn tool_allowed(&self, name: &str) -> bool {
+        self.tool_allowlist.as_ref().is_none_or(|allowed| allowed.iter().any(|t| t == name))
+    }
+
+    /// Publish tool calls and compactions to `bus`, as `agent_name`.
+    pub fn set_event_bus(&mut self, bus: bizclaw_core::events::EventBus, agent_name: &str) {
+        self.events = Some((bus, agent_name.to_string()));
+    }
+
+    fn publish(&self, event: impl FnOnce(String) -> bizclaw_core::events::Event) {
+        if let Some((bus, agent)) = &self.events {
+            bus.publish(event(agent.clone()));
+        }
+    }
+
+    /// Run the security policy's argument filters over a tool call.
+    async fn check_tool_arguments(&self, tool: &str, arguments: &str) -> Result<()> {
+        let args = serde_json::from_str(arguments).unwrap_or(serde_json::Value::Null);
+        self.security.check_tool_arguments(tool, &args).await
+    }
+
+    /// Ask a human about `tool` if it needs approval (see
+    /// [`approval::required`]). Returns the message for the model when the
+    /// call must not run.
+    async fn approval_denial(&self, tool: &str, arguments: &str) -> Option<String> {
+        if !approval::required(&self.config.autonomy, tool) {
+            return None;
+        }
+        let Some((queue, agent)) = &self.approvals else {
+            return Some(format!("{tool} requires human approval, but no approval channel is configured."));
+        };
+        let timeout = std::time::Duration::from_secs(self.config.autonomy.approval_timeout_secs);
+        match queue.request(agent, tool, arguments, timeout).await {
+            approval::Decision::Approve => None,
+            approval::Decision::Deny(reason) => Some(format!(
+                "Denied by a human: {reason}. Do not retry this call; tell the user it was not approved."
+            )),
+        }
+    }
+
+    /// Timeout for one execution of `tool`.
+    fn tool_timeout(&self, tool: &str) -> std::time::Duration {
+        let autonomy = &self.config.autonomy;
+        let secs = autonomy.tool_timeouts.get(tool).copied().unwrap_or(autonomy.tool_timeout_secs);
+        std::time::Duration::from_secs(secs)
+    }
+
+    /// Switch to `session_id`, restoring its saved history when the memory
+    /// backend knows it. From then on history is saved after each message.
+    pub async fn set_session(&mut self, session_id: &str) {
+        if let Err(e) = self.load_session(session_id).await {
+            tracing::warn!("Failed to restore session '{session_id}': {e}");
+        }
+    }
+
+    /// Make `session_id` the active conversation, for an agent answering
+    /// several chats. The current conversation is parked in the session
+    /// pool; a session not parked there continues from its saved history
+    /// when history is persisted, and starts fresh otherwise.
+    pub async fn switch_session(&mut self, session_id: &str) {
+        if self.session_id == session_id {
+            return;
+        }
+        let current = std::mem::replace(&mut self.session_id, session_id.to_string());
+        let messages = self.conversation.split_off(1);
+        self.sessions.park(&current, messages);
+        self.last_stats.session_id = session_id.to_string();
+        if let Some(messages) = self.sessions.take(session_id) {
+            self.conversation.extend(messages);
+        } else if self.persist_history {
+            self.set_session(session_id).await;
+        }
+        self.last_stats.message_count = self.conversation.len();
+    }
+
+    /// The session pool holding this agent's inactive conversations.
+    pub fn sessions(&self) -> &sessions::SessionPool {
+        &self.sessions
+    }
+
+    /// Switch to `session_id` and replace the conversation (after the system
+    /// prompt) with its saved history. Returns whether history was found.
+    pub async fn load_session(&mut self, session_id: &str) -> Result<bool> {
         self.session_id = session_id.to_string();
         self.last_stats.session_id = session_id.to_string();
+        self.persist_history = true;
+        self.conversation.truncate(1);
+        let Some(messages) = self.memory.load_conversation(session_id).await? else {
+            return Ok(false);
+        };
+        tracing::info!("📂 Restored {} message(s) for session '{session_id}'", messages.len());
+        self.conversation.extend(messages);
+        self.last_stats.message_count = self.conversation.len();
+        Ok(true)
+    }
+
+    /// Save the conversation (without the system prompt) under the current session.
+    pub async fn save_session(&self) -> Result<()> {
+        self.memory
+            .save_conversation(&self.session_id, &self.conversation[1..])
+            .await
     }
 
     /// Get current session ID.
@@ -258,10 +623,316 @@ impl Agent {
         &self.session_id
     }
 
+    /// Copy the first `at` messages of the conversation (the system prompt
+    /// counts, so `at >= 1`) into a new session and switch to it. The
+    /// current session keeps its full history. Returns the branch's ID.
+    pub async fn fork_session(&mut self, at: usize) -> Result<String> {
+        if at == 0 || at > self.conversation.len() {
+            return Err(BizClawError::Other(format!(
+                "Cannot fork at message {at}: conversation has {} message(s)",
+                self.conversation.len()
+            )));
+        }
+        if self.persist_history {
+            self.save_session().await?;
+        }
+        let parent = std::mem::take(&mut self.session_id);
+        let branch_id = format!("{parent}~{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        self.conversation.truncate(at);
+        self.session_id = branch_id.clone();
+        self.last_stats.session_id = branch_id.clone();
+        self.last_stats.message_count = self.conversation.len();
+        self.persist_history = true;
+        self.save_session().await?;
+        self.branches.push(Branch {
+            session_id: branch_id.clone(),
+            parent,
+            fork_at: at,
+            created_at: chrono::Utc::now(),
+        });
+        Ok(branch_id)
+    }
+
+    /// Sessions forked with [`Agent::fork_session`], oldest first. Switch
+    /// between them with [`Agent::set_session`].
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    /// Replace the user message at `index` with `content`, drop everything
+    /// after it and answer again. Fork first to keep the original turn.
+    pub async fn edit_message(&mut self, index: usize, content: &str) -> Result<String> {
+        let original = self.rewind_to(index)?;
+        self.process_inner(content, original.images, None, None).await
+    }
+
+    /// Drop the user message at `index` together with everything after it
+    /// (its reply and tool turns), updating saved history.
+    pub async fn delete_message(&mut self, index: usize) -> Result<()> {
+        self.rewind_to(index)?;
+        if self.persist_history {
+            self.save_session().await?;
+        }
+        Ok(())
+    }
+
+    /// Answer the last user message again, discarding the previous reply.
+    pub async fn regenerate(&mut self) -> Result<String> {
+        let index = self
+            .conversation
+            .iter()
+            .rposition(|m| m.role == Role::User)
+            .ok_or_else(|| BizClawError::Other("No user message to regenerate".into()))?;
+        let original = self.rewind_to(index)?;
+        self.process_inner(&original.content, original.images, None, None).await
+    }
+
+    /// Truncate the conversation back to before the user message at
+    /// `index`, including the context retrieved for it, and return that
+    /// message.
+    fn rewind_to(&mut self, index: usize) -> Result<Message> {
+        if self.conversation.get(index).is_none_or(|m| m.role != Role::User) {
+            return Err(BizClawError::Other(format!("Message {index} is not a user message")));
+        }
+        let original = self.conversation[index].clone();
+        let mut start = index;
+        while start > 1 && self.conversation[start - 1].role == Role::System {
+            start -= 1;
+        }
+        self.conversation.truncate(start);
+        self.last_stats.message_count = self.conversation.len();
+        Ok(original)
+    }
+
     /// Process a user message and generate a response.
     ///
     /// Uses Think-Act-Observe loop with Quality Gate evaluation.
     pub async fn process(&mut self, user_message: &str) -> Result<String> {
+        self.process_inner(user_message, vec![], None, None).await
+    }
+
+    /// Like [`Agent::process`], emitting tool-round progress events to `progress`.
+    pub async fn process_with_progress(
+        &mut self,
+        user_message: &str,
+        progress: &progress::ProgressSink,
+    ) -> Result<String> {
+        self.process_inner(user_message, vec![], Some(progress), None).await
+    }
+
+    /// Like [`Agent::process_with_progress`], also sending the reply text
+    /// to `chunks` as the provider generates it. Text generated before a
+    /// tool round is streamed too; the returned response is the final answer.
+    pub async fn process_streaming(
+        &mut self,
+        user_message: &str,
+        progress: &progress::ProgressSink,
+        chunks: &ChatChunkSink,
+    ) -> Result<String> {
+        self.process_inner(user_message, vec![], Some(progress), Some(chunks)).await
+    }
+
+    /// Like [`Agent::process_with_progress`] for a message with attached
+    /// images. Providers without vision get a note about the images instead.
+    pub async fn process_with_images(
+        &mut self,
+        user_message: &str,
+        images: Vec<ImageInput>,
+        progress: Option<&progress::ProgressSink>,
+    ) -> Result<String> {
+        self.process_inner(user_message, images, progress, None).await
+    }
+
+    /// Answer a sub-prompt from another agent (see [`delegate`]) in a
+    /// scratch conversation, within `budget`. The agent's own conversation,
+    /// limits and session history are left as they were.
+    pub async fn process_delegated(
+        &mut self,
+        prompt: &str,
+        budget: &delegate::DelegateBudget,
+    ) -> Result<String> {
+        let scratch = vec![self.conversation[0].clone()];
+        let conversation = std::mem::replace(&mut self.conversation, scratch);
+        let persist_history = std::mem::replace(&mut self.persist_history, false);
+        let max_rounds = self.config.autonomy.max_tool_rounds;
+        let max_tokens = self.config.brain.max_tokens;
+        if let Some(rounds) = budget.max_rounds {
+            self.config.autonomy.max_tool_rounds = rounds.min(max_rounds);
+        }
+        if let Some(tokens) = budget.max_tokens {
+            self.config.brain.max_tokens = tokens.min(max_tokens);
+        }
+
+        // Timed out here rather than by the caller, so the conversation
+        // is always restored
+        let result = match budget.max_secs {
+            Some(secs) => tokio::time::timeout(std::time::Duration::from_secs(secs), self.process(prompt))
+                .await
+                .unwrap_or_else(|_| Err(BizClawError::Timeout(format!("no answer within {secs}s")))),
+            None => self.process(prompt).await,
+        };
+
+        self.conversation = conversation;
+        self.persist_history = persist_history;
+        self.config.autonomy.max_tool_rounds = max_rounds;
+        self.config.brain.max_tokens = max_tokens;
+        result
+    }
+
+    /// Answer `user_message` with a JSON value matching `schema`. The
+    /// provider is asked for JSON (JSON mode, or a schema grammar on the
+    /// local brain); replies that don't parse or validate are sent back
+    /// with the problems, up to [`STRUCTURED_RETRIES`] more times. Tools
+    /// are not offered. On failure the conversation is left as it was;
+    /// on success the exchange is saved like [`Agent::process`] saves it.
+    pub async fn process_structured(
+        &mut self,
+        user_message: &str,
+        schema: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let start = self.conversation.len();
+        let (context, _) = self.retrieve_context(user_message, false).await;
+        self.conversation.extend(context);
+        self.conversation.push(Message::user(user_message));
+        self.last_citations.clear();
+
+        let value = match self.structured_reply(schema).await {
+            Ok(value) => value,
+            Err(e) => {
+                self.conversation.truncate(start);
+                self.last_stats.message_count = self.conversation.len();
+                return Err(e);
+            }
+        };
+        self.conversation.push(Message::assistant(value.to_string()));
+        self.save_memory(user_message, &value.to_string()).await;
+        self.prune_memory_if_due().await;
+        if self.persist_history
+            && let Err(e) = self.save_session().await
+        {
+            tracing::warn!("Failed to save session history: {e}");
+        }
+        self.last_stats.message_count = self.conversation.len();
+        Ok(value)
+    }
+
+    /// The provider rounds of [`Agent::process_structured`], run on a copy
+    /// of the conversation.
+    async fn structured_reply(&self, schema: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut attempt = self.conversation.clone();
+        attempt.push(Message::system(format!(
+            "Reply with only a JSON value matching this JSON schema, no other text:\n{schema}"
+        )));
+        let params = GenerateParams {
+            model: self.config.default_model.clone(),
+            temperature: self.config.default_temperature,
+            max_tokens: self.config.brain.max_tokens,
+            response_schema: Some(schema.clone()),
+            ..Default::default()
+        };
+
+        let mut problems = Vec::new();
+        for round in 0..=STRUCTURED_RETRIES {
+            let resp = fallback::chat_with_fallback(
+                self.provider.as_ref(),
+                &self.fallback_providers,
+                &attempt,
+                &[],
+                &params,
+            )
+            .await?;
+            let reply = resp.content.unwrap_or_default();
+            let value = structured::extract_json(&reply);
+            problems = match &value {
+                Some(value) => structured::validate(value, schema),
+                None => vec!["the reply is not valid JSON".to_string()],
+            };
+            if let Some(value) = value.filter(|_| problems.is_empty()) {
+                return Ok(value);
+            }
+
+            tracing::info!("🧾 Structured reply rejected (attempt {}): {}", round + 1, problems.join("; "));
+            attempt.push(Message::assistant(&reply));
+            attempt.push(Message::user(format!(
+                "That reply does not match the schema:\n- {}\nReply again with only the corrected JSON.",
+                problems.join("\n- ")
+            )));
+        }
+        Err(bizclaw_core::error::BizClawError::Provider(format!(
+            "No schema-valid reply after {} attempts: {}",
+            STRUCTURED_RETRIES + 1,
+            problems.join("; ")
+        )))
+    }
+
+    /// One provider call for a caller that keeps its own conversation and
+    /// runs its own tools (e.g. an OpenAI-compatible client): `messages`
+    /// after the agent's system prompt, `tools` offered as given. Tool
+    /// calls are returned, not executed, and the agent's conversation,
+    /// memory and tools are left alone.
+    pub async fn complete(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        chunks: Option<&ChatChunkSink>,
+    ) -> Result<ProviderResponse> {
+        let mut prompt = vec![Message::system(self.system_prompt())];
+        prompt.extend_from_slice(messages);
+        let params = GenerateParams {
+            model: self.config.default_model.clone(),
+            temperature: temperature.unwrap_or(self.config.default_temperature),
+            max_tokens: max_tokens.unwrap_or(self.config.brain.max_tokens),
+            ..Default::default()
+        };
+        fallback::chat_stream_with_fallback(
+            self.provider.as_ref(),
+            &self.fallback_providers,
+            &prompt,
+            tools,
+            &params,
+            chunks,
+        )
+        .await
+    }
+
+    async fn process_inner(
+        &mut self,
+        user_message: &str,
+        images: Vec<ImageInput>,
+        progress: Option<&progress::ProgressSink>,
+        chunks: Option<&ChatChunkSink>,
+    ) -> Result<String> {
+        let started = std::time::Instant::now();
+        let result = self.run_turn(user_message, images, progress, chunks).await;
+        let agent = self.metrics_name();
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        bizclaw_core::metrics::observe(
+            "bizclaw_agent_process_seconds",
+            &[("agent", &agent), ("outcome", outcome)],
+            started.elapsed().as_secs_f64(),
+        );
+        result
+    }
+
+    /// Agent label for metrics: the name it publishes events under.
+    fn metrics_name(&self) -> String {
+        self.events.as_ref().map_or_else(|| "default".to_string(), |(_, agent)| agent.clone())
+    }
+
+    async fn run_turn(
+        &mut self,
+        user_message: &str,
+        images: Vec<ImageInput>,
+        progress: Option<&progress::ProgressSink>,
+        chunks: Option<&ChatChunkSink>,
+    ) -> Result<String> {
+        let emit = |event: progress::ProgressEvent| {
+            if let Some(sink) = progress {
+                let _ = sink.send(event);
+            }
+        };
         let mut compacted = false;
         let estimated_tokens = self.estimate_tokens();
         let max_context = self.config.brain.context_length as usize;
@@ -273,22 +944,23 @@ impl Agent {
             compacted = true;
         }
 
-        // Knowledge RAG
-        if let Some(kb_ctx) = self.search_knowledge(user_message).await {
-            self.conversation.push(Message::system(format!(
-                "[Knowledge Base]\n{kb_ctx}\n[End knowledge]"
-            )));
-        }
+        // Retrieval: knowledge base, past conversations, custom retrievers
+        let (context, provided) = self.retrieve_context(user_message, self.config.rag.citations).await;
+        self.conversation.extend(context);
+        self.last_citations.clear();
 
-        // Memory retrieval
-        if let Some(mem_ctx) = self.retrieve_memory(user_message).await {
-            self.conversation.push(Message::system(format!(
-                "[Past conversations]\n{mem_ctx}\n[End past]"
+        if images.is_empty() {
+            self.conversation.push(Message::user(user_message));
+        } else if self.provider.supports_vision() {
+            self.conversation.push(Message::user_with_images(user_message, images));
+        } else {
+            tracing::info!("🖼️ {} can't view images, sending a note instead", self.provider.name());
+            self.conversation.push(Message::user(format!(
+                "{user_message}\n\n[The user attached {} image(s), which this model cannot view.]",
+                images.len()
             )));
         }
 
-        self.conversation.push(Message::user(user_message));
-
         // Trim conversation
         if self.conversation.len() > 41 {
             let system = self.conversation[0].clone();
@@ -298,28 +970,59 @@ impl Agent {
             self.conversation.push(system);
             self.conversation.extend(tail);
         }
+        // Drop the oldest turns while the prompt leaves no room for the reply
+        let budget = max_context.saturating_sub(self.config.brain.max_tokens as usize);
+        if budget > 0 {
+            while self.conversation.len() > 2 && self.estimate_tokens() > budget {
+                self.conversation.remove(1);
+            }
+        }
+        // A tool result can't lead the history without the call it answers
+        while self.conversation.len() > 2 && self.conversation[1].role == bizclaw_core::types::Role::Tool {
+            self.conversation.remove(1);
+        }
 
-        let tool_defs = self.prompt_cache.tool_defs(&self.tools).to_vec();
+        let mut tool_defs = self.prompt_cache.tool_defs(&self.tools).to_vec();
+        tool_defs.retain(|def| self.tool_allowed(&def.name));
         let params = GenerateParams {
             model: self.config.default_model.clone(),
             temperature: self.config.default_temperature,
             max_tokens: self.config.brain.max_tokens,
             top_p: 0.9,
             stop: vec![],
+            min_p: None,
+            mirostat: None,
+            response_schema: None,
         };
 
         // Think-Act-Observe Loop
-        const MAX_ROUNDS: usize = 5;
+        let max_rounds = self.config.autonomy.max_tool_rounds as usize;
+        let max_identical = self.config.autonomy.max_identical_tool_calls;
         let mut final_content = String::new();
         let mut tool_rounds = 0;
-
-        for round in 0..=MAX_ROUNDS {
-            let tools = if round < MAX_ROUNDS { &tool_defs } else { &vec![] };
-            tracing::debug!("🧠 Think round {}/{}", round + 1, MAX_ROUNDS);
-
-            let resp = self.provider.chat(&self.conversation, tools, &params).await?;
-
-            if resp.tool_calls.is_empty() {
+        let mut call_counts: std::collections::HashMap<(String, String), u32> =
+            std::collections::HashMap::new();
+        let mut tool_loop: Option<String> = None;
+        let mut tool_calls = 0;
+
+        for round in 0..=max_rounds {
+            // Out of rounds, looping or out of tool calls: offer no tools so the model answers.
+            let calls_left = self.tool_call_limit.is_none_or(|limit| tool_calls < limit);
+            let offer_tools = round < max_rounds && tool_loop.is_none() && calls_left;
+            let tools = if offer_tools { &tool_defs } else { &vec![] };
+            tracing::debug!("🧠 Think round {}/{}", round + 1, max_rounds);
+
+            let resp = fallback::chat_stream_with_fallback(
+                self.provider.as_ref(),
+                &self.fallback_providers,
+                &self.conversation,
+                tools,
+                &params,
+                chunks,
+            )
+            .await?;
+
+            if resp.tool_calls.is_empty() || !offer_tools {
                 final_content = resp.content.unwrap_or_else(|| "I'm not sure how to respond.".into());
                 self.conversation.push(Message::assistant(&final_content));
                 break;
@@ -328,31 +1031,113 @@ impl Agent {
             // ACT
             tool_rounds = round + 1;
             tracing::info!("⚡ Act round {}: {} tool(s)", tool_rounds, resp.tool_calls.len());
+            emit(progress::ProgressEvent::Round { round: tool_rounds });
 
             let mut results = Vec::new();
             for tc in &resp.tool_calls {
-                tracing::info!("  → {}", tc.function.name);
-                if tc.function.name == "shell"
-                    && let Ok(args) = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
-                    && let Some(cmd) = args["command"].as_str()
-                    && !self.security.check_command(cmd).await?
-                {
-                    results.push(Message::tool(format!("Permission denied: '{cmd}'"), &tc.id));
+                let key = (tc.function.name.clone(), canonical_arguments(&tc.function.arguments));
+                let count = call_counts.entry(key).or_insert(0);
+                *count += 1;
+                if max_identical > 0 && *count > max_identical {
+                    tracing::warn!(
+                        "🔁 Tool loop: {} called {} times with identical arguments",
+                        tc.function.name, count
+                    );
+                    results.push(Message::tool(
+                        format!(
+                            "Loop detected: {} was already called {max_identical} time(s) with these arguments. \
+                             Do not call it again; answer with the results you have.",
+                            tc.function.name
+                        ),
+                        &tc.id,
+                    ));
+                    tool_loop = Some(tc.function.name.clone());
+                    continue;
+                }
+                tool_calls += 1;
+                if self.tool_call_limit.is_some_and(|limit| tool_calls > limit) {
+                    results.push(Message::tool(
+                        "Tool call budget exhausted. Answer with the results you have.",
+                        &tc.id,
+                    ));
                     continue;
                 }
-                if let Some(tool) = self.tools.get(&tc.function.name) {
-                    match tool.execute(&tc.function.arguments).await {
+                tracing::info!("  → {}", tc.function.name);
+                emit(progress::ProgressEvent::ToolStarted {
+                    round: tool_rounds,
+                    tool: tc.function.name.clone(),
+                });
+                let success = if !self.tool_allowed(&tc.function.name) {
+                    tracing::warn!("🚫 {} is not allowed here", tc.function.name);
+                    results.push(Message::tool(format!("Not allowed: {}", tc.function.name), &tc.id));
+                    false
+                } else if let Err(invalid) =
+                    self.tools.check_arguments(&tc.function.name, &tc.function.arguments)
+                {
+                    tracing::warn!("🧩 Invalid arguments for {}: {}", tc.function.name, tc.function.arguments);
+                    results.push(Message::tool(invalid, &tc.id));
+                    false
+                } else if let Err(denied) = self.check_tool_arguments(&tc.function.name, &tc.function.arguments).await {
+                    tracing::warn!("🛡️ {} refused by argument filter: {}", tc.function.name, denied);
+                    results.push(Message::tool(format!("Denied: {denied}"), &tc.id));
+                    false
+                } else if let Some(denied) =
+                    self.approval_denial(&tc.function.name, &tc.function.arguments).await
+                {
+                    tracing::warn!("🛂 {} not approved", tc.function.name);
+                    results.push(Message::tool(denied, &tc.id));
+                    false
+                } else if let Some(tool) = self.tools.get(&tc.function.name) {
+                    let timeout = self.tool_timeout(&tc.function.name);
+                    let token = self.cancel.child_token();
+                    let run = tool.execute_cancellable(
+                        &tc.function.arguments,
+                        self.tool_output_sink.as_ref(),
+                        &token,
+                    );
+                    let executed = match tokio::time::timeout(timeout, run).await {
+                        Ok(executed) => executed,
+                        Err(_) => {
+                            token.cancel();
+                            tracing::warn!("⏱️ {} timed out after {}s", tc.function.name, timeout.as_secs());
+                            Err(bizclaw_core::error::BizClawError::Tool(format!(
+                                "{} timed out after {}s",
+                                tc.function.name,
+                                timeout.as_secs()
+                            )))
+                        }
+                    };
+                    match executed {
                         Ok(r) => {
                             let out = if r.output.len() > 4000 {
-                                format!("{}...[truncated]", &r.output[..4000])
+                                format!("{}...[truncated]", orchestrator::safe_truncate(&r.output, 4000))
                             } else { r.output };
                             results.push(Message::tool(&out, &tc.id));
+                            r.success
+                        }
+                        Err(e) => {
+                            results.push(Message::tool(format!("Error: {e}"), &tc.id));
+                            false
                         }
-                        Err(e) => results.push(Message::tool(format!("Error: {e}"), &tc.id)),
                     }
                 } else {
                     results.push(Message::tool(format!("Not found: {}", tc.function.name), &tc.id));
-                }
+                    false
+                };
+                emit(progress::ProgressEvent::ToolFinished {
+                    round: tool_rounds,
+                    tool: tc.function.name.clone(),
+                    success,
+                });
+                self.publish(|agent| bizclaw_core::events::Event::ToolExecuted {
+                    agent,
+                    tool: tc.function.name.clone(),
+                    success,
+                });
+                bizclaw_core::metrics::increment(
+                    "bizclaw_tool_calls_total",
+                    &[("tool", &tc.function.name), ("success", if success { "true" } else { "false" })],
+                );
             }
 
             // OBSERVE
@@ -361,6 +1146,7 @@ impl Agent {
                 content: resp.content.clone().unwrap_or_default(),
                 name: None, tool_call_id: None,
                 tool_calls: Some(resp.tool_calls.clone()),
+                images: vec![],
             });
             for r in results { self.conversation.push(r); }
             tracing::debug!("🔍 Observe — looping to Think");
@@ -382,6 +1168,7 @@ impl Agent {
                     let epar = GenerateParams {
                         model: gate.evaluator_model.clone().unwrap_or(self.config.default_model.clone()),
                         temperature: 0.3, max_tokens: 500, top_p: 0.9, stop: vec![],
+                        min_p: None, mirostat: None, response_schema: None,
                     };
                     match self.provider.chat(&em, &[], &epar).await {
                         Ok(er) => {
@@ -403,14 +1190,22 @@ impl Agent {
                 }
             }
 
+        self.last_citations = rag::cited(&final_content, &provided);
+
         // Save memory + update stats
         self.save_memory(user_message, &final_content).await;
+        self.prune_memory_if_due().await;
+        if self.persist_history
+            && let Err(e) = self.save_session().await
+        {
+            tracing::warn!("Failed to save session history: {e}");
+        }
         let new_tokens = self.estimate_tokens();
         self.last_stats = ContextStats {
             message_count: self.conversation.len(),
             estimated_tokens: new_tokens,
             utilization_pct: new_tokens as f32 / max_context as f32 * 100.0,
-            max_context, last_tool_rounds: tool_rounds, compacted,
+            max_context, last_tool_rounds: tool_rounds, last_tool_calls: tool_calls, compacted, tool_loop,
             session_id: self.session_id.clone(),
         };
 
@@ -418,114 +1213,49 @@ impl Agent {
     }
 
 
-    /// Search the knowledge base for relevant context.
-    async fn search_knowledge(&self, query: &str) -> Option<String> {
-        let kb_arc = self.knowledge.as_ref()?;
-        let kb_lock = kb_arc.lock().await;
-        let kb = kb_lock.as_ref()?;
-
-        let results = kb.search(query, 3);
-        if results.is_empty() {
-            return None;
-        }
-
-        let mut context = String::new();
-        for (i, r) in results.iter().enumerate() {
-            let entry = format!("{}. [{}] {}\n", i + 1, r.doc_name, r.content);
-            if context.len() + entry.len() > 1500 {
-                break;
+    /// Context messages for `query` from the retrieval pipeline, and the
+    /// knowledge chunks they number for citing.
+    async fn retrieve_context(&self, query: &str, cite: bool) -> (Vec<Message>, Vec<rag::Citation>) {
+        let config = &self.config.rag;
+        let knowledge = self
+            .knowledge
+            .clone()
+            .map(|kb| rag::KnowledgeRetriever(kb, self.config.knowledge.namespaces.clone()));
+        let memory = rag::MemoryRetriever(self.memory.as_ref());
+
+        let mut retrievers: Vec<&dyn rag::Retriever> = Vec::new();
+        for name in &config.retrievers {
+            match name.as_str() {
+                "knowledge" => retrievers.extend(knowledge.as_ref().map(|k| k as &dyn rag::Retriever)),
+                "memory" if self.config.memory.auto_save => retrievers.push(&memory),
+                "memory" => {}
+                other => tracing::warn!("Unknown retriever '{other}' in rag.retrievers"),
             }
-            context.push_str(&entry);
         }
-
-        tracing::debug!(
-            "Knowledge RAG: {} results, {} chars",
-            results.len(),
-            context.len()
-        );
-        Some(context)
-    }
-
-    /// Retrieve relevant past conversations from memory (FTS5-powered).
-    async fn retrieve_memory(&self, user_message: &str) -> Option<String> {
-        if !self.config.memory.auto_save {
-            return None;
+        retrievers.extend(self.retrievers.iter().map(|r| r.as_ref()));
+        if retrievers.is_empty() {
+            return (vec![], vec![]);
         }
 
-        // Extract meaningful keywords (skip common words)
-        let stop_words: std::collections::HashSet<&str> = [
-            "the", "a", "an", "is", "are", "was", "were", "be", "been", "being", "have", "has",
-            "had", "do", "does", "did", "will", "would", "could", "should", "may", "might",
-            "shall", "can", "need", "dare", "ought", "i", "me", "my", "you", "your", "he", "she",
-            "it", "we", "they", "this", "that", "these", "those", "what", "which", "who", "how",
-            "and", "but", "or", "not", "no", "of", "in", "on", "at", "to", "for", "with", "from",
-            "by", "as", "if", "then", "so", "than", "tôi", "bạn", "là", "có", "và", "của", "với",
-            "cho", "để", "không", "được", "này", "đó", "một", "các", "những",
-        ]
-        .iter()
-        .copied()
-        .collect();
-
-        let keywords: Vec<&str> = user_message
-            .split(|c: char| !c.is_alphanumeric() && c != '_')
-            .filter(|w| w.len() > 2 && !stop_words.contains(&w.to_lowercase().as_str()))
-            .take(5)
-            .collect();
-
-        if keywords.is_empty() {
-            return None;
+        let mut pipeline = rag::RetrievalPipeline::new(config);
+        if config.rerank {
+            pipeline = pipeline.with_reranker(self.provider.as_ref(), &self.config.default_model);
         }
-
-        // Search memory with combined keywords for better FTS5 results
-        let combined_query = keywords.join(" ");
-        let mut relevant = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-
-        match self.memory.search(&combined_query, 5).await {
-            Ok(results) => {
-                for r in results {
-                    if seen.insert(r.entry.id.clone()) {
-                        relevant.push(r.entry.content.clone());
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::debug!("Memory search failed: {e}");
-            }
-        }
-
-        if relevant.is_empty() {
-            return None;
-        }
-
-        let mut context = String::new();
-        let mut total_len = 0;
-        for (i, memory) in relevant.iter().take(5).enumerate() {
-            let entry = format!("{}. {}\n", i + 1, memory);
-            if total_len + entry.len() > 2000 {
-                break;
-            }
-            context.push_str(&entry);
-            total_len += entry.len();
-        }
-
-        tracing::debug!(
-            "Memory RAG: {} results, {} chars",
-            relevant.len(),
-            total_len
-        );
-        Some(context)
+        let passages = pipeline.run(query, &retrievers).await;
+        rag::render(&passages, config.max_chars, cite)
     }
 
     /// Save interaction to memory with session ID.
     async fn save_memory(&self, user_msg: &str, assistant_msg: &str) {
         if self.config.memory.auto_save {
+            let content = format!("User: {user_msg}\nAssistant: {assistant_msg}");
             let entry = bizclaw_core::traits::memory::MemoryEntry {
                 id: uuid::Uuid::new_v4().to_string(),
-                content: format!("User: {user_msg}\nAssistant: {assistant_msg}"),
                 metadata: serde_json::json!({
                     "session_id": self.session_id,
+                    "importance": bizclaw_memory::retention::importance(&content),
                 }),
+                content,
                 embedding: None,
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
@@ -536,6 +1266,21 @@ impl Agent {
         }
     }
 
+    /// Expire and merge old memories once `memory.prune_interval_mins` has
+    /// passed since the last run, summarizing with this agent's model.
+    async fn prune_memory_if_due(&mut self) {
+        let interval = self.config.memory.prune_interval_mins;
+        if interval == 0 || self.last_prune.elapsed() < std::time::Duration::from_secs(interval * 60) {
+            return;
+        }
+        self.last_prune = std::time::Instant::now();
+        let policy = bizclaw_memory::retention::RetentionPolicy::from_config(&self.config.memory);
+        let summarizer = (self.provider.as_ref(), self.config.default_model.as_str());
+        if let Err(e) = bizclaw_memory::retention::prune(self.memory.as_ref(), &policy, Some(summarizer)).await {
+            tracing::warn!("Memory pruning failed: {e}");
+        }
+    }
+
     /// Public wrapper to save streamed conversations to memory.
     pub async fn save_memory_public(&self, user_msg: &str, assistant_msg: &str) {
         self.save_memory(user_msg, assistant_msg).await;
@@ -590,6 +1335,12 @@ impl Agent {
             old_count + 10,
             self.conversation.len()
         );
+        let messages_after = self.conversation.len();
+        self.publish(|agent| bizclaw_core::events::Event::ContextCompacted {
+            agent,
+            messages_before: old_count + 10,
+            messages_after,
+        });
 
         // 3-Tier Memory: persist compaction summary to daily log
         if let Err(e) = self.daily_log.save_compaction(&summary) {
@@ -597,16 +1348,9 @@ impl Agent {
         }
     }
 
-    /// Estimate token count (rough heuristic: 1 token ≈ 4 chars for English, 2 chars for CJK).
+    /// Tokens in the conversation, counted with the provider's tokenizer.
     fn estimate_tokens(&self) -> usize {
-        self.conversation
-            .iter()
-            .map(|m| {
-                let chars = m.content.len();
-                // Rough estimate: mix of English and Vietnamese
-                chars / 3
-            })
-            .sum()
+        count_message_tokens(self.tokenizer.as_ref(), &self.conversation)
     }
 
     /// Process incoming message and create an outgoing response.
@@ -614,7 +1358,7 @@ impl Agent {
         &mut self,
         msg: &bizclaw_core::types::IncomingMessage,
     ) -> Result<OutgoingMessage> {
-        let response = self.process(&msg.content).await?;
+        let response = self.process_inner(&msg.content, msg.images.clone(), None, None).await?;
         Ok(OutgoingMessage {
             thread_id: msg.thread_id.clone(),
             content: response,
@@ -642,18 +1386,41 @@ impl Agent {
     /// Updates both the config and the first message in conversation history.
     pub fn set_system_prompt(&mut self, prompt: &str) {
         self.config.identity.system_prompt = prompt.to_string();
-        // Also update the system message in conversation (always at index 0)
-        if !self.conversation.is_empty() {
-            // Rebuild with brain context same as new()
-            let brain_ws = bizclaw_memory::brain::BrainWorkspace::default();
-            let brain_context = brain_ws.assemble_brain();
-            let full_prompt = if brain_context.trim().is_empty() {
-                prompt.to_string()
-            } else {
-                format!("{}\n\n{}", prompt, brain_context)
-            };
-            self.conversation[0] = Message::system(&full_prompt);
+        self.rebuild_system_message();
+    }
+
+    /// Follow the instructions of prompt templates (e.g. ones fetched from
+    /// MCP servers), given as (name, text), after the system prompt.
+    /// Replaces any templates set before.
+    pub fn set_prompt_templates(&mut self, templates: Vec<(String, String)>) {
+        self.prompt_templates = templates;
+        self.rebuild_system_message();
+    }
+
+    /// Names of the prompt templates in use.
+    pub fn prompt_template_names(&self) -> Vec<&str> {
+        self.prompt_templates.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Rebuild the system message (always at index 0) from the system
+    /// prompt, brain context and prompt templates.
+    fn rebuild_system_message(&mut self) {
+        if self.conversation.is_empty() {
+            return;
+        }
+        // Rebuild with brain context same as new()
+        let brain_ws = bizclaw_memory::brain::BrainWorkspace::default();
+        let brain_context = brain_ws.assemble_brain();
+        let prompt = &self.config.identity.system_prompt;
+        let mut full_prompt = if brain_context.trim().is_empty() {
+            prompt.to_string()
+        } else {
+            format!("{}\n\n{}", prompt, brain_context)
+        };
+        for (name, text) in &self.prompt_templates {
+            full_prompt.push_str(&format!("\n\n## Prompt template: {name}\n{text}"));
         }
+        self.conversation[0] = Message::system(&full_prompt);
     }
 
     /// Get total tool count (native + MCP).
@@ -675,4 +1442,628 @@ impl Agent {
     pub fn context_stats(&self) -> &ContextStats {
         &self.last_stats
     }
+
+    /// Knowledge chunks the last answer cited with `[n]` markers.
+    pub fn last_citations(&self) -> &[rag::Citation] {
+        &self.last_citations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::types::{
+        FunctionCall, ModelInfo, ProviderResponse, ToolCall, ToolDefinition, ToolResult,
+    };
+    use progress::ProgressEvent;
+    use std::sync::Mutex;
+
+    /// Provider that replays a fixed sequence of responses.
+    struct Scripted(Mutex<std::collections::VecDeque<ProviderResponse>>);
+
+    #[async_trait]
+    impl Provider for Scripted {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            Ok(self.0.lock().unwrap().pop_front().unwrap_or_else(|| ProviderResponse::text("done")))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    struct Echo(&'static str);
+
+    #[async_trait]
+    impl bizclaw_core::traits::Tool for Echo {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: self.0.into(),
+                description: String::new(),
+                parameters: serde_json::json!({"type": "object"}),
+            }
+        }
+        async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+            Ok(ToolResult {
+                tool_call_id: String::new(),
+                output: arguments.to_string(),
+                success: true,
+            })
+        }
+    }
+
+    /// Tool that never finishes on its own.
+    struct Hang;
+
+    #[async_trait]
+    impl bizclaw_core::traits::Tool for Hang {
+        fn name(&self) -> &str {
+            "hang"
+        }
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "hang".into(),
+                description: String::new(),
+                parameters: serde_json::json!({"type": "object"}),
+            }
+        }
+        async fn execute(&self, _arguments: &str) -> Result<ToolResult> {
+            std::future::pending().await
+        }
+    }
+
+    fn call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.into(),
+            r#type: "function".into(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: "{}".into(),
+            },
+        }
+    }
+
+    pub(crate) fn test_agent(responses: Vec<ProviderResponse>) -> Agent {
+        let mut tools = bizclaw_tools::ToolRegistry::new();
+        tools.register(Box::new(Echo("web_search")));
+        tools.register(Box::new(Echo("http_request")));
+        tools.register(Box::new(Hang));
+        let prompt_cache = PromptCache::new("sys", &tools);
+        Agent {
+            config: BizClawConfig::default(),
+            tokenizer: std::sync::Arc::new(bizclaw_core::traits::tokenizer::ApproxTokenizer),
+            provider: Box::new(Scripted(Mutex::new(responses.into()))),
+            fallback_providers: vec![],
+            memory: Box::new(bizclaw_memory::noop::NoopMemory),
+            tools,
+            tool_output_sink: None,
+            cancel: CancellationToken::new(),
+            security: std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(Default::default())),
+            approvals: None,
+            events: None,
+            tool_allowlist: None,
+            tool_call_limit: None,
+            conversation: vec![Message::system("sys")],
+            prompt_cache,
+            session_id: "test".into(),
+            persist_history: false,
+            branches: vec![],
+            sessions: sessions::SessionPool::new(0, 0),
+            knowledge: None,
+            retrievers: vec![],
+            prompt_templates: vec![],
+            last_stats: ContextStats {
+                message_count: 1,
+                estimated_tokens: 0,
+                utilization_pct: 0.0,
+                max_context: 128000,
+                last_tool_rounds: 0,
+                last_tool_calls: 0,
+                compacted: false,
+                tool_loop: None,
+                session_id: "test".into(),
+            },
+            daily_log: bizclaw_memory::brain::DailyLogManager::new(std::env::temp_dir()),
+            last_prune: std::time::Instant::now(),
+            last_citations: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_long_tool_output_truncated_at_char_boundary() {
+        // A 3-byte character straddles the 4000-byte cut
+        let arguments = format!("{{\"q\":\"{}ệệ\"}}", "a".repeat(3993));
+        let mut long_call = call("c1", "web_search");
+        long_call.function.arguments = arguments;
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![long_call]),
+            ProviderResponse::text("done"),
+        ]);
+        assert_eq!(agent.process("search").await.unwrap(), "done");
+        let output = &agent.conversation()[3].content;
+        assert!(output.ends_with("a...[truncated]"), "{}", &output[output.len() - 20..]);
+    }
+
+    #[tokio::test]
+    async fn test_progress_events_for_two_tool_rounds() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::with_tool_calls(vec![
+                call("c2", "http_request"),
+                call("c3", "missing_tool"),
+            ]),
+            ProviderResponse::text("Here is the answer."),
+        ]);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let answer = agent.process_with_progress("look it up", &tx).await.unwrap();
+        assert_eq!(answer, "Here is the answer.");
+        drop(tx);
+
+        let mut events = Vec::new();
+        while let Some(e) = rx.recv().await {
+            events.push(e);
+        }
+        let started = |round, tool: &str| ProgressEvent::ToolStarted { round, tool: tool.into() };
+        let finished = |round, tool: &str, success| ProgressEvent::ToolFinished {
+            round,
+            tool: tool.into(),
+            success,
+        };
+        assert_eq!(
+            events,
+            vec![
+                ProgressEvent::Round { round: 1 },
+                started(1, "web_search"),
+                finished(1, "web_search", true),
+                ProgressEvent::Round { round: 2 },
+                started(2, "http_request"),
+                finished(2, "http_request", true),
+                started(2, "missing_tool"),
+                finished(2, "missing_tool", false),
+            ]
+        );
+        assert_eq!(agent.context_stats().last_tool_rounds, 2);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_sends_reply_text() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::text("Here is the answer."),
+        ]);
+        let (progress, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (chunks, mut chunks_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let answer = agent.process_streaming("look it up", &progress, &chunks).await.unwrap();
+        assert_eq!(answer, "Here is the answer.");
+        assert_eq!(progress_rx.recv().await, Some(ProgressEvent::Round { round: 1 }));
+        assert_eq!(chunks_rx.recv().await.as_deref(), Some("Here is the answer."));
+        assert!(chunks_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_returns_tool_calls_without_running_them() {
+        let agent = test_agent(vec![ProviderResponse::with_tool_calls(vec![call("c1", "get_weather")])]);
+        let (chunks, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let resp = agent
+            .complete(&[Message::user("weather in Hanoi?")], &[], None, None, Some(&chunks))
+            .await
+            .unwrap();
+        assert_eq!(resp.tool_calls.len(), 1);
+        assert_eq!(resp.tool_calls[0].function.name, "get_weather");
+        assert_eq!(resp.finish_reason.as_deref(), Some("tool_calls"));
+        assert!(agent.conversation().iter().all(|m| m.role != Role::User));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_published_to_event_bus() {
+        use bizclaw_core::events::{Event, EventBus};
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search"), call("c2", "missing_tool")]),
+            ProviderResponse::text("done"),
+        ]);
+        let bus = EventBus::default();
+        let mut rx = bus.subscribe();
+        agent.set_event_bus(bus, "researcher");
+
+        agent.process("look it up").await.unwrap();
+        let executed = |tool: &str, success| Event::ToolExecuted {
+            agent: "researcher".into(),
+            tool: tool.into(),
+            success,
+        };
+        assert_eq!(rx.recv().await.unwrap(), executed("web_search", true));
+        assert_eq!(rx.recv().await.unwrap(), executed("missing_tool", false));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_allowlist_blocks_other_tools() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::text("done"),
+        ]);
+        agent.set_tool_allowlist(Some(vec!["shell".into()]));
+
+        agent.process("look it up").await.unwrap();
+        let result = agent
+            .conversation()
+            .iter()
+            .find(|m| m.tool_call_id.as_deref() == Some("c1"))
+            .unwrap();
+        assert_eq!(result.content, "Not allowed: web_search");
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_limit() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search"), call("c2", "web_search")]),
+            ProviderResponse::text("done"),
+        ]);
+        agent.set_tool_call_limit(Some(1));
+
+        assert_eq!(agent.process("look it up").await.unwrap(), "done");
+        assert_eq!(agent.context_stats().last_tool_calls, 2);
+        let refused = agent
+            .conversation()
+            .iter()
+            .find(|m| m.tool_call_id.as_deref() == Some("c2"))
+            .unwrap();
+        assert!(refused.content.starts_with("Tool call budget exhausted"));
+    }
+
+    #[tokio::test]
+    async fn test_identical_tool_calls_stop_as_loop() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::with_tool_calls(vec![call("c2", "web_search")]),
+            ProviderResponse::with_tool_calls(vec![call("c3", "web_search")]),
+            ProviderResponse::text("Nothing new found."),
+        ]);
+
+        let answer = agent.process("look it up").await.unwrap();
+        assert_eq!(answer, "Nothing new found.");
+        let stats = agent.context_stats();
+        assert_eq!(stats.tool_loop.as_deref(), Some("web_search"));
+        assert_eq!(stats.last_tool_rounds, 3);
+        let loop_note = agent
+            .conversation()
+            .iter()
+            .find(|m| m.tool_call_id.as_deref() == Some("c3"))
+            .unwrap();
+        assert!(loop_note.content.starts_with("Loop detected"));
+    }
+
+    #[tokio::test]
+    async fn test_max_tool_rounds_from_config() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::with_tool_calls(vec![call("c2", "http_request")]),
+        ]);
+        agent.config.autonomy.max_tool_rounds = 1;
+
+        agent.process("look it up").await.unwrap();
+        assert_eq!(agent.context_stats().last_tool_rounds, 1);
+        assert_eq!(agent.context_stats().tool_loop, None);
+        assert!(!agent.conversation().iter().any(|m| m.tool_call_id.as_deref() == Some("c2")));
+    }
+
+    fn tool_reply<'a>(agent: &'a Agent, id: &str) -> &'a str {
+        let reply = agent.conversation().iter().find(|m| m.tool_call_id.as_deref() == Some(id));
+        &reply.unwrap().content
+    }
+
+    #[tokio::test]
+    async fn test_hanging_tool_times_out() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "hang")]),
+            ProviderResponse::text("It did not finish."),
+        ]);
+        agent.config.autonomy.tool_timeouts.insert("hang".into(), 1);
+
+        let answer = agent.process("wait for it").await.unwrap();
+        assert_eq!(answer, "It did not finish.");
+        assert!(tool_reply(&agent, "c1").contains("hang timed out after 1s"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_token_fails_tool() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "hang")]),
+            ProviderResponse::text("Stopped."),
+        ]);
+        let token = CancellationToken::new();
+        agent.set_cancel_token(token.clone());
+        token.cancel();
+
+        agent.process("wait for it").await.unwrap();
+        assert!(tool_reply(&agent, "c1").contains("hang cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_history_trimmed_to_token_budget() {
+        let mut agent = test_agent(vec![ProviderResponse::text("ok")]);
+        agent.config.brain.context_length = 300;
+        agent.config.brain.max_tokens = 100;
+        for i in 0..6 {
+            agent.conversation.push(Message::user(format!("turn{i} {}", "word ".repeat(40))));
+        }
+
+        agent.process("question").await.unwrap();
+        let conversation = agent.conversation();
+        assert!(conversation[1].content.starts_with("turn"));
+        assert!(!conversation[1].content.starts_with("turn0"));
+        assert!(conversation.iter().any(|m| m.content == "question"));
+        let stats = agent.context_stats();
+        assert_eq!(stats.estimated_tokens, count_message_tokens(agent.tokenizer.as_ref(), conversation));
+        assert!(stats.estimated_tokens <= 200 + 8, "{}", stats.estimated_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tool_arguments_returned_to_model() {
+        let mut bad = call("c1", "web_search");
+        bad.function.arguments = "[1, 2]".into();
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![bad]),
+            ProviderResponse::with_tool_calls(vec![call("c2", "web_search")]),
+            ProviderResponse::text("ok"),
+        ]);
+        agent.process("search").await.unwrap();
+
+        let reply: serde_json::Value = serde_json::from_str(tool_reply(&agent, "c1")).unwrap();
+        assert_eq!(reply["error"], "invalid_arguments");
+        assert_eq!(reply["problems"][0], "$: expected object, got [1,2]");
+        // The corrected call ran.
+        assert_eq!(tool_reply(&agent, "c2"), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_tool_waits_for_human_approval() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "http_request"), call("c2", "web_search")]),
+            ProviderResponse::with_tool_calls(vec![call("c3", "http_request")]),
+            ProviderResponse::text("ok"),
+        ]);
+        agent.config.autonomy.require_approval = vec!["http_request".into()];
+        let queue = approval::ApprovalQueue::new();
+        agent.set_approvals(queue.clone(), "ops");
+
+        let mut requests = queue.subscribe();
+        let human = tokio::spawn(async move {
+            let first = requests.recv().await.unwrap();
+            assert_eq!((first.agent.as_str(), first.tool.as_str()), ("ops", "http_request"));
+            queue.decide(&first.id, approval::Decision::Approve);
+            let second = requests.recv().await.unwrap();
+            queue.decide(&second.id, approval::Decision::Deny("not today".into()));
+        });
+        agent.process("fetch").await.unwrap();
+        human.await.unwrap();
+
+        assert_eq!(tool_reply(&agent, "c1"), "{}");
+        assert_eq!(tool_reply(&agent, "c2"), "{}");
+        assert!(tool_reply(&agent, "c3").starts_with("Denied by a human: not today."));
+    }
+
+    #[tokio::test]
+    async fn test_argument_filter_denies_call() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search"), {
+                let mut c = call("c2", "web_search");
+                c.function.arguments = r#"{"query": "passwords site:intranet"}"#.into();
+                c
+            }]),
+            ProviderResponse::text("ok"),
+        ]);
+        agent.security = std::sync::Arc::new(bizclaw_security::DefaultSecurityPolicy::new(
+            bizclaw_core::config::AutonomyConfig {
+                argument_filters: vec![bizclaw_core::config::ArgumentFilter {
+                    tool: "web_search".into(),
+                    argument: "query".into(),
+                    deny: vec!["(?i)password".into()],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ));
+        agent.process("search").await.unwrap();
+        assert_eq!(tool_reply(&agent, "c1"), "{}");
+        assert!(tool_reply(&agent, "c2").starts_with("Denied: "));
+    }
+
+    #[tokio::test]
+    async fn test_approval_required_without_queue_is_denied() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::with_tool_calls(vec![call("c1", "web_search")]),
+            ProviderResponse::text("ok"),
+        ]);
+        agent.config.autonomy.require_approval = vec!["*".into()];
+        agent.process("search").await.unwrap();
+        assert!(tool_reply(&agent, "c1").contains("no approval channel is configured"));
+    }
+
+    #[tokio::test]
+    async fn test_images_become_note_without_vision() {
+        let mut agent = test_agent(vec![ProviderResponse::text("I can't see it.")]);
+        let images = vec![ImageInput::base64("image/jpeg", "/9j/4AAQ")];
+        agent.process_with_images("What is this?", images, None).await.unwrap();
+
+        let user = agent.conversation().iter().find(|m| m.role == bizclaw_core::types::Role::User).unwrap();
+        assert!(user.images.is_empty());
+        assert!(user.content.ends_with("[The user attached 1 image(s), which this model cannot view.]"));
+    }
+
+    #[tokio::test]
+    async fn test_session_history_survives_new_agent() {
+        let dir = std::env::temp_dir().join(format!("bizclaw_session_{}", uuid::Uuid::new_v4()));
+        let db = dir.join("memory.db");
+        let sqlite = || Box::new(bizclaw_memory::sqlite::SqliteMemory::open(&db).unwrap());
+
+        let mut agent = test_agent(vec![ProviderResponse::text("Hi there.")]);
+        agent.memory = sqlite();
+        assert!(!agent.load_session("s1").await.unwrap());
+        agent.process("hello").await.unwrap();
+
+        let mut restarted = test_agent(vec![]);
+        restarted.memory = sqlite();
+        restarted.set_session("s1").await;
+        let contents: Vec<_> = restarted.conversation().iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["sys", "hello", "Hi there."]);
+        assert_eq!(restarted.session_id(), "s1");
+
+        restarted.set_session("s2").await;
+        assert_eq!(restarted.conversation().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_switch_session_isolates_chats() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::text("Hi Lan."),
+            ProviderResponse::text("Hi Minh."),
+            ProviderResponse::text("You're Lan."),
+        ]);
+        let user_turns = |agent: &Agent| -> Vec<String> {
+            agent
+                .conversation()
+                .iter()
+                .filter(|m| m.role == bizclaw_core::types::Role::User)
+                .map(|m| m.content.clone())
+                .collect()
+        };
+
+        agent.switch_session("telegram:1").await;
+        agent.process("I'm Lan").await.unwrap();
+        agent.switch_session("telegram:2").await;
+        agent.process("I'm Minh").await.unwrap();
+        assert_eq!(user_turns(&agent), ["I'm Minh"]);
+
+        agent.switch_session("telegram:1").await;
+        agent.process("Who am I?").await.unwrap();
+        assert_eq!(user_turns(&agent), ["I'm Lan", "Who am I?"]);
+        assert_eq!(agent.session_id(), "telegram:1");
+        assert_eq!(agent.context_stats().session_id, "telegram:1");
+        let mut parked = agent.sessions().session_ids();
+        parked.sort();
+        assert_eq!(parked, ["telegram:2", "test"]);
+    }
+
+    /// Retriever with one citable knowledge chunk.
+    struct Handbook;
+
+    #[async_trait]
+    impl rag::Retriever for Handbook {
+        fn name(&self) -> &str {
+            "knowledge"
+        }
+        async fn retrieve(&self, _query: &str, _limit: usize) -> Result<Vec<rag::Passage>> {
+            Ok(vec![rag::Passage {
+                source: "knowledge".into(),
+                label: Some("handbook.md".into()),
+                content: "Annual leave is 12 days.".into(),
+                score: 3.0,
+                chunk: Some(rag::ChunkRef {
+                    doc_name: "handbook.md".into(),
+                    chunk_idx: 4,
+                }),
+            }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_answer_citations() {
+        let mut agent = test_agent(vec![
+            ProviderResponse::text("You get 12 days of leave [1]."),
+            ProviderResponse::text("Hello!"),
+        ]);
+        agent.config.memory.auto_save = false;
+        agent.add_retriever(Box::new(Handbook));
+
+        agent.process("How much leave do I get?").await.unwrap();
+        assert!(agent.conversation()[1].content.contains("Cite the passages you use"));
+        let citations = agent.last_citations();
+        assert_eq!(citations.len(), 1);
+        assert_eq!((citations[0].doc_name.as_str(), citations[0].chunk_idx), ("handbook.md", 4));
+
+        // An answer without markers cites nothing
+        agent.process("Hi").await.unwrap();
+        assert!(agent.last_citations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fork_then_edit_and_regenerate() {
+        let dir = std::env::temp_dir().join(format!("bizclaw_branch_{}", uuid::Uuid::new_v4()));
+        let db = dir.join("memory.db");
+        let mut agent = test_agent(vec![
+            ProviderResponse::text("A1"),
+            ProviderResponse::text("A2"),
+            ProviderResponse::text("B2"),
+            ProviderResponse::text("C2"),
+        ]);
+        agent.memory = Box::new(bizclaw_memory::sqlite::SqliteMemory::open(&db).unwrap());
+        agent.config.memory.auto_save = false;
+        agent.set_session("main").await;
+        agent.process("q1").await.unwrap();
+        agent.process("q2").await.unwrap();
+
+        let branch = agent.fork_session(5).await.unwrap();
+        assert_eq!(agent.session_id(), branch);
+        assert_eq!(agent.branches()[0].parent, "main");
+        assert_eq!(agent.edit_message(3, "q2 edited").await.unwrap(), "B2");
+        assert_eq!(agent.regenerate().await.unwrap(), "C2");
+        let contents: Vec<_> = agent.conversation().iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["sys", "q1", "A1", "q2 edited", "C2"]);
+        assert!(agent.edit_message(2, "not a user message").await.is_err());
+
+        agent.delete_message(1).await.unwrap();
+        agent.set_session("main").await;
+        assert_eq!(agent.conversation().len(), 5);
+        assert_eq!(agent.conversation()[3].content, "q2");
+        agent.set_session(&branch).await;
+        assert_eq!(agent.conversation().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_structured_retries_until_schema_matches() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        });
+        let mut agent = test_agent(vec![
+            ProviderResponse::text("Hanoi, I think."),
+            ProviderResponse::text(r#"{"city": 1}"#),
+            ProviderResponse::text(r#"```json
+{"city": "Hanoi"}
+```"#),
+        ]);
+
+        let value = agent.process_structured("Where is Hoan Kiem lake?", &schema).await.unwrap();
+        assert_eq!(value, serde_json::json!({"city": "Hanoi"}));
+        // Failed attempts stay out of the conversation
+        let contents: Vec<_> = agent.conversation().iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["sys", "Where is Hoan Kiem lake?", r#"{"city":"Hanoi"}"#]);
+
+        let mut stubborn = test_agent(vec![ProviderResponse::text("{}"); 3]);
+        let err = stubborn.process_structured("Where?", &schema).await.unwrap_err();
+        assert!(err.to_string().contains("missing required property 'city'"), "{err}");
+        // A failed call leaves nothing behind
+        let contents: Vec<_> = stubborn.conversation().iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["sys"]);
+    }
 }
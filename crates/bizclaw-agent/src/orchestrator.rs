@@ -2,26 +2,38 @@
 //!
 //! ## Features:
 //! - Named agents with independent configs, tools, memory
-//! - Message routing to specific agents
+//! - Per-agent locks — different agents answer concurrently (see [`Orchestrator`])
+//! - Message routing to specific agents, or by intent (see [`Orchestrator::route`])
 //! - **Agent Delegation** — sync/async inter-agent task delegation with permission links
+//! - **Agents as Tools** — agents call each other mid-turn via `call_agent` (see [`crate::delegate`])
 //! - **Agent Teams** — shared task boards with dependencies, team mailbox
 //! - **Agent Handoff** — conversation control transfer between agents
 //! - **Evaluate Loop** — generator-evaluator feedback cycles for quality-gated output
+//! - **Pipelines** — each agent's output fed to the next, with per-step timeouts
 //! - **Quality Gates** — hook-based output validation
+//! - **Shared MCP pool** — each MCP server connected once, its tools given to every agent
 //! - Broadcast messages to all agents
 //! - Agent roles and specializations
 
 use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::provider::ChatChunkSink;
 use bizclaw_core::types::*;
 use bizclaw_db::store::DataStore;
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard, mpsc};
 
 use crate::Agent;
+use crate::approval::ApprovalQueue;
+use crate::delegate::{DelegateBudget, DelegateRequest, DelegateTool};
+use crate::progress::ProgressSink;
+use crate::routing::{Route, RouteVia, Router};
 
 /// Safely truncate a string at a character boundary (UTF-8 safe).
 /// Avoids panic on Vietnamese/CJK multi-byte characters.
-fn safe_truncate(s: &str, max_bytes: usize) -> &str {
+pub(crate) fn safe_truncate(s: &str, max_bytes: usize) -> &str {
     if s.len() <= max_bytes {
         return s;
     }
@@ -32,6 +44,26 @@ fn safe_truncate(s: &str, max_bytes: usize) -> &str {
     &s[..end]
 }
 
+/// Prompt for step `i` of a pipeline, whose input came from `from`.
+fn pipeline_prompt(config: &PipelineConfig, i: usize, from: &str, input: &str) -> String {
+    let step = &config.steps[i];
+    let total = config.steps.len();
+    if i == 0 && step.instruction.is_empty() {
+        return input.to_string();
+    }
+    let mut prompt = format!("[Pipeline step {}/{total}", i + 1);
+    if i > 0 {
+        prompt.push_str(&format!(", input from agent '{from}']\nOriginal request: {}", config.input));
+    } else {
+        prompt.push(']');
+    }
+    if !step.instruction.is_empty() {
+        prompt.push_str(&format!("\nTask: {}", step.instruction));
+    }
+    prompt.push_str(&format!("\n\nInput:\n---\n{input}\n---"));
+    prompt
+}
+
 /// A named agent instance with metadata.
 pub struct NamedAgent {
     pub agent: Agent,
@@ -44,18 +76,160 @@ pub struct NamedAgent {
     pub quality_gates: Vec<QualityGate>,
     /// Max delegation load this agent can handle concurrently.
     pub max_delegation_load: u32,
+    /// `call_agent` requests from this agent's delegate tool.
+    delegations: mpsc::UnboundedReceiver<DelegateRequest>,
+}
+
+/// An agent behind its own lock, so different agents process messages
+/// concurrently while messages to one agent queue up.
+#[derive(Clone)]
+struct AgentSlot {
+    named: Arc<Mutex<NamedAgent>>,
+    /// Sender for the agent's `call_agent` requests.
+    delegate_tx: mpsc::UnboundedSender<DelegateRequest>,
+    /// The agent's [`Orchestrator::list_agents`] entry as of the last time
+    /// it was idle, shown while it's busy.
+    summary: Arc<std::sync::Mutex<serde_json::Value>>,
+}
+
+impl AgentSlot {
+    /// Update the cached summary from `named`.
+    fn remember(&self, named: &NamedAgent) {
+        *self.summary.lock().unwrap_or_else(|e| e.into_inner()) = summarize(named);
+    }
+
+    /// The agent's summary, current if the agent is idle.
+    fn summary(&self) -> serde_json::Value {
+        match self.named.try_lock() {
+            Ok(named) => {
+                self.remember(&named);
+                let mut summary = summarize(&named);
+                summary["busy"] = false.into();
+                summary
+            }
+            Err(_) => {
+                let mut summary = self.summary.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                summary["busy"] = true.into();
+                summary
+            }
+        }
+    }
+}
+
+/// An agent's [`Orchestrator::list_agents`] entry, without `is_default`.
+fn summarize(a: &NamedAgent) -> serde_json::Value {
+    serde_json::json!({
+        "name": a.name,
+        "role": a.role,
+        "description": a.description,
+        "active": a.active,
+        "provider": a.agent.provider_name(),
+        "model": a.agent.model_name(),
+        "system_prompt": a.agent.system_prompt(),
+        "tools": a.agent.tool_count(),
+        "messages_processed": a.message_count,
+        "conversation_length": a.agent.conversation().len(),
+        "sessions": a.agent.sessions().len() + 1,
+        "quality_gates": a.quality_gates.len(),
+        "max_delegation_load": a.max_delegation_load,
+        "knowledge_namespaces": a.agent.knowledge_namespaces(),
+        "max_tool_rounds": a.agent.max_tool_rounds(),
+        "mcp_prompts": a.agent.prompt_template_names(),
+    })
+}
+
+/// Register the tools of `pool`'s MCP servers on `agent`, and have it
+/// search their resources when there are any.
+fn give_mcp_pool(agent: &mut Agent, pool: &Arc<bizclaw_mcp::McpPool>) {
+    for tool in pool.tools() {
+        agent.register_tool(tool);
+    }
+    if pool.resource_texts().next().is_some() {
+        agent.add_retriever(Box::new(crate::rag::McpResourceRetriever(pool.clone())));
+    }
+}
+
+/// How long a `call_agent` request waits for a target agent busy with
+/// another conversation. Two agents calling each other from separate
+/// conversations would otherwise wait on each other forever.
+const BUSY_WAIT: Duration = Duration::from_secs(60);
+
+/// Tool restrictions for one message (see [`Orchestrator::send_to_restricted`]).
+struct ToolLimits {
+    allowed: Vec<String>,
+    max_calls: Option<usize>,
+}
+
+/// An agent under [`ToolLimits`], lifted when dropped — also when the
+/// message is abandoned mid-turn.
+struct Limited<'a> {
+    agent: &'a mut Agent,
+    limited: bool,
+}
+
+impl<'a> Limited<'a> {
+    fn new(agent: &'a mut Agent, limits: Option<ToolLimits>) -> Self {
+        let limited = limits.is_some();
+        if let Some(limits) = limits {
+            agent.set_tool_allowlist(Some(limits.allowed));
+            agent.set_tool_call_limit(limits.max_calls);
+        }
+        Self { agent, limited }
+    }
+}
+
+impl std::ops::Deref for Limited<'_> {
+    type Target = Agent;
+    fn deref(&self) -> &Agent {
+        self.agent
+    }
+}
+
+impl std::ops::DerefMut for Limited<'_> {
+    fn deref_mut(&mut self) -> &mut Agent {
+        self.agent
+    }
+}
+
+impl Drop for Limited<'_> {
+    fn drop(&mut self) {
+        if self.limited {
+            self.agent.set_tool_allowlist(None);
+            self.agent.set_tool_call_limit(None);
+        }
+    }
 }
 
 /// Multi-Agent Orchestrator — manages a pool of agents with full orchestration.
+///
+/// Each agent has its own lock. Clones are cheap and share the agents and
+/// the message log, so callers can take a clone out of a lock around the
+/// orchestrator and process messages without holding that lock.
+#[derive(Clone)]
 pub struct Orchestrator {
-    agents: HashMap<String, NamedAgent>,
+    agents: Arc<std::sync::RwLock<HashMap<String, AgentSlot>>>,
     default_agent: Option<String>,
     /// Inter-agent message log.
-    pub message_log: Vec<AgentMessage>,
+    message_log: Arc<std::sync::Mutex<Vec<AgentMessage>>>,
     /// Data store for orchestration state (delegations, teams, handoffs, traces).
     store: Option<Arc<dyn DataStore>>,
     /// Lane configuration for workload isolation.
     pub lane_config: LaneConfig,
+    /// Approval queue given to every agent (see [`Orchestrator::set_approvals`]).
+    approvals: Option<ApprovalQueue>,
+    /// Event bus given to every agent (see [`Orchestrator::set_event_bus`]).
+    events: Option<bizclaw_core::events::EventBus>,
+    /// MCP connections whose tools every agent gets (see [`Orchestrator::set_mcp_pool`]).
+    mcp_pool: Option<Arc<bizclaw_mcp::McpPool>>,
+    /// Picks agents for messages not addressed to one (see [`Orchestrator::route`]).
+    router: Arc<Router>,
+    /// Knowledge cited in the last answer sent through this instance.
+    citations: Vec<crate::rag::Citation>,
+    /// Tool calls made for the last message sent through this instance.
+    tool_calls: usize,
+    /// Chat whose conversation messages sent through this instance continue
+    /// (see [`Orchestrator::with_session`]).
+    session: Option<String>,
 }
 
 /// A message between agents or from user.
@@ -72,22 +246,26 @@ impl Orchestrator {
     /// Create a new empty orchestrator.
     pub fn new() -> Self {
         Self {
-            agents: HashMap::new(),
+            agents: Default::default(),
             default_agent: None,
-            message_log: Vec::new(),
+            message_log: Default::default(),
             store: None,
             lane_config: LaneConfig::default(),
+            approvals: None,
+            events: None,
+            mcp_pool: None,
+            router: Default::default(),
+            citations: Vec::new(),
+            tool_calls: 0,
+            session: None,
         }
     }
 
     /// Create orchestrator with a data store for persistent orchestration state.
     pub fn with_store(store: Arc<dyn DataStore>) -> Self {
         Self {
-            agents: HashMap::new(),
-            default_agent: None,
-            message_log: Vec::new(),
             store: Some(store),
-            lane_config: LaneConfig::default(),
+            ..Self::new()
         }
     }
 
@@ -107,40 +285,148 @@ impl Orchestrator {
         })
     }
 
+    fn read_agents(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, AgentSlot>> {
+        self.agents.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_agents(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, AgentSlot>> {
+        self.agents.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn slot(&self, name: &str) -> Option<AgentSlot> {
+        self.read_agents().get(name).cloned()
+    }
+
+    /// Every agent's slot, for locking each in turn.
+    fn slots(&self) -> Vec<AgentSlot> {
+        self.read_agents().values().cloned().collect()
+    }
+
+    fn log(&self, message: AgentMessage) {
+        self.message_log.lock().unwrap_or_else(|e| e.into_inner()).push(message);
+    }
+
     /// Add an agent to the orchestrator.
-    pub fn add_agent(&mut self, name: &str, role: &str, description: &str, agent: Agent) {
-        let is_first = self.agents.is_empty();
-        self.agents.insert(
-            name.to_string(),
-            NamedAgent {
-                agent,
-                name: name.to_string(),
-                role: role.to_string(),
-                description: description.to_string(),
-                active: true,
-                message_count: 0,
-                quality_gates: Vec::new(),
-                max_delegation_load: 10,
-            },
-        );
+    pub fn add_agent(&mut self, name: &str, role: &str, description: &str, mut agent: Agent) {
+        if let Some(queue) = &self.approvals {
+            agent.set_approvals(queue.clone(), name);
+        }
+        if let Some(bus) = &self.events {
+            agent.set_event_bus(bus.clone(), name);
+        }
+        if let Some(pool) = &self.mcp_pool {
+            give_mcp_pool(&mut agent, pool);
+        }
+        let (delegate_tx, delegations) = mpsc::unbounded_channel();
+        let named = NamedAgent {
+            agent,
+            name: name.to_string(),
+            role: role.to_string(),
+            description: description.to_string(),
+            active: true,
+            message_count: 0,
+            quality_gates: Vec::new(),
+            max_delegation_load: 10,
+            delegations,
+        };
+        let slot = AgentSlot {
+            summary: Arc::new(std::sync::Mutex::new(summarize(&named))),
+            named: Arc::new(Mutex::new(named)),
+            delegate_tx,
+        };
+        let is_first = {
+            let mut agents = self.write_agents();
+            let is_first = agents.is_empty();
+            agents.insert(name.to_string(), slot);
+            is_first
+        };
         if is_first {
             self.default_agent = Some(name.to_string());
         }
     }
 
+    /// Send tool calls that need human approval from every agent, current
+    /// and future, to `queue`.
+    pub async fn set_approvals(&mut self, queue: ApprovalQueue) {
+        for slot in self.slots() {
+            let mut named = slot.named.lock().await;
+            let name = named.name.clone();
+            named.agent.set_approvals(queue.clone(), &name);
+        }
+        self.approvals = Some(queue);
+    }
+
+    /// Publish tool and compaction events from every agent, current and
+    /// future, to `bus`.
+    pub async fn set_event_bus(&mut self, bus: bizclaw_core::events::EventBus) {
+        for slot in self.slots() {
+            let mut named = slot.named.lock().await;
+            let name = named.name.clone();
+            named.agent.set_event_bus(bus.clone(), &name);
+        }
+        self.events = Some(bus);
+    }
+
+    /// Give the tools of `pool`'s MCP servers to every agent, current and
+    /// future, and search their resources for context. Tools from a
+    /// previous pool stay registered, so set it once.
+    pub async fn set_mcp_pool(&mut self, pool: Arc<bizclaw_mcp::McpPool>) {
+        for slot in self.slots() {
+            let mut named = slot.named.lock().await;
+            give_mcp_pool(&mut named.agent, &pool);
+        }
+        self.mcp_pool = Some(pool);
+    }
+
+    /// Follow the MCP prompt templates `refs` (`server/prompt`) in
+    /// `agent_name`'s system prompt, replacing those set before. Fails
+    /// without changing anything when a template can't be fetched.
+    pub async fn set_agent_prompts(&self, agent_name: &str, refs: &[String]) -> Result<()> {
+        let slot = self
+            .slot(agent_name)
+            .ok_or_else(|| BizClawError::AgentNotFound(agent_name.to_string()))?;
+        let mut templates = Vec::new();
+        for prompt_ref in refs {
+            let Some((server, name)) = prompt_ref.split_once('/') else {
+                return Err(BizClawError::Other(format!(
+                    "MCP prompt '{prompt_ref}' must be written as server/prompt"
+                )));
+            };
+            let pool = self
+                .mcp_pool
+                .as_ref()
+                .ok_or_else(|| BizClawError::Other("No MCP servers connected".into()))?;
+            let text = pool
+                .get_prompt(server, name, serde_json::json!({}))
+                .await
+                .map_err(BizClawError::Other)?;
+            templates.push((prompt_ref.clone(), text));
+        }
+        let mut named = slot.named.lock().await;
+        named.agent.set_prompt_templates(templates);
+        slot.remember(&named);
+        Ok(())
+    }
+
+    /// The shared MCP pool, if one is set.
+    pub fn mcp_pool(&self) -> Option<&Arc<bizclaw_mcp::McpPool>> {
+        self.mcp_pool.as_ref()
+    }
+
     /// Save agent metadata to a JSON file for persistence across restarts.
     pub fn save_agents_metadata(&self, path: &std::path::Path) {
         let metadata: Vec<serde_json::Value> = self
-            .agents
-            .values()
-            .map(|a| {
+            .slots()
+            .iter()
+            .map(|slot| {
+                let a = slot.summary();
                 serde_json::json!({
-                    "name": a.name,
-                    "role": a.role,
-                    "description": a.description,
-                    "provider": a.agent.provider_name(),
-                    "model": a.agent.model_name(),
-                    "system_prompt": a.agent.system_prompt(),
+                    "name": a["name"],
+                    "role": a["role"],
+                    "description": a["description"],
+                    "provider": a["provider"],
+                    "model": a["model"],
+                    "system_prompt": a["system_prompt"],
                 })
             })
             .collect();
@@ -158,24 +444,99 @@ impl Orchestrator {
         }
     }
 
-    /// Remove an agent.
+    /// Remove an agent. A message it is processing still finishes.
     pub fn remove_agent(&mut self, name: &str) -> bool {
-        let removed = self.agents.remove(name).is_some();
+        let mut agents = self.agents.write().unwrap_or_else(|e| e.into_inner());
+        let removed = agents.remove(name).is_some();
         if self.default_agent.as_deref() == Some(name) {
-            self.default_agent = self.agents.keys().next().cloned();
+            self.default_agent = agents.keys().next().cloned();
         }
         removed
     }
 
     /// Set the default agent.
     pub fn set_default(&mut self, name: &str) {
-        if self.agents.contains_key(name) {
+        if self.has_agent(name) {
             self.default_agent = Some(name.to_string());
         }
     }
 
+    /// Send messages through this instance in the conversation of chat
+    /// `session_id` (e.g. `telegram:12345`), so each chat an agent answers
+    /// keeps its own context. Without it, messages continue the agent's
+    /// default session. Meant for a clone made per request.
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session = Some(session_id.into());
+        self
+    }
+
     /// Send a message to a specific agent, respecting any active handoff.
+    /// Waits while the agent is busy with another message.
     pub async fn send_to(&mut self, agent_name: &str, message: &str) -> Result<String> {
+        self.send_to_inner(agent_name, message, vec![], None, None, None).await
+    }
+
+    /// Like [`Orchestrator::send_to`], streaming tool-round progress events.
+    pub async fn send_to_with_progress(
+        &mut self,
+        agent_name: &str,
+        message: &str,
+        progress: &ProgressSink,
+    ) -> Result<String> {
+        self.send_to_inner(agent_name, message, vec![], Some(progress), None, None).await
+    }
+
+    /// Like [`Orchestrator::send_to_with_progress`], also streaming the
+    /// reply text to `chunks` (see [`Agent::process_streaming`]).
+    pub async fn send_to_streaming(
+        &mut self,
+        agent_name: &str,
+        message: &str,
+        progress: &ProgressSink,
+        chunks: &ChatChunkSink,
+    ) -> Result<String> {
+        self.send_to_inner(agent_name, message, vec![], Some(progress), Some(chunks), None).await
+    }
+
+    /// Like [`Orchestrator::send_to`] for a message with attached images
+    /// (e.g. a Telegram photo), optionally streaming progress events.
+    pub async fn send_to_with_images(
+        &mut self,
+        agent_name: &str,
+        message: &str,
+        images: Vec<ImageInput>,
+        progress: Option<&ProgressSink>,
+    ) -> Result<String> {
+        self.send_to_inner(agent_name, message, images, progress, None, None).await
+    }
+
+    /// Like [`Orchestrator::send_to`], with the agent limited to
+    /// `allowed_tools` and at most `max_tool_calls` tool calls for this
+    /// message only. Other messages to the agent wait until it's done, so
+    /// they never see the limits.
+    pub async fn send_to_restricted(
+        &mut self,
+        agent_name: &str,
+        message: &str,
+        allowed_tools: Vec<String>,
+        max_tool_calls: Option<usize>,
+    ) -> Result<String> {
+        let limits = ToolLimits {
+            allowed: allowed_tools,
+            max_calls: max_tool_calls,
+        };
+        self.send_to_inner(agent_name, message, vec![], None, None, Some(limits)).await
+    }
+
+    async fn send_to_inner(
+        &mut self,
+        agent_name: &str,
+        message: &str,
+        images: Vec<ImageInput>,
+        progress: Option<&ProgressSink>,
+        chunks: Option<&ChatChunkSink>,
+        limits: Option<ToolLimits>,
+    ) -> Result<String> {
         // Check for active handoff — route to handoff target if present
         let actual_agent = if let Some(store) = &self.store {
             if let Ok(Some(handoff)) = store.active_handoff(agent_name).await {
@@ -192,13 +553,29 @@ impl Orchestrator {
             agent_name.to_string()
         };
 
-        let named = self.agents.get_mut(&actual_agent).ok_or_else(|| {
+        let slot = self.slot(&actual_agent).ok_or_else(|| {
             BizClawError::AgentNotFound(format!("Agent '{}' not found", actual_agent))
         })?;
-
+        let mut named = slot.named.lock().await;
         named.message_count += 1;
         let start = std::time::Instant::now();
-        let response = named.agent.process(message).await?;
+        let NamedAgent { agent, delegations, .. } = &mut *named;
+        agent
+            .switch_session(self.session.as_deref().unwrap_or(crate::sessions::DEFAULT_SESSION))
+            .await;
+        let mut agent = Limited::new(agent, limits);
+        let result = self
+            .serve_delegations(
+                std::slice::from_ref(&actual_agent),
+                delegations,
+                agent.process_inner(message, images, progress, chunks),
+            )
+            .await;
+        self.citations = agent.last_citations().to_vec();
+        self.tool_calls = agent.context_stats().last_tool_calls;
+        drop(agent);
+        slot.remember(&named);
+        let response = result?;
         let latency = start.elapsed().as_millis() as u64;
 
         // Record LLM trace if store is available
@@ -214,8 +591,10 @@ impl Orchestrator {
             trace.total_tokens = stats.estimated_tokens as u32;
             let _ = store.record_trace(&trace).await;
         }
+        let gates = named.quality_gates.clone();
+        drop(named);
 
-        self.message_log.push(AgentMessage {
+        self.log(AgentMessage {
             from: "user".to_string(),
             to: actual_agent.to_string(),
             content: message.to_string(),
@@ -224,11 +603,53 @@ impl Orchestrator {
         });
 
         // Run quality gates if configured
-        let response = self.run_quality_gates(&actual_agent, &response).await?;
+        let response = self.run_quality_gates(&actual_agent, &gates, &response).await?;
 
         Ok(response)
     }
 
+    /// Route messages with `router` (see [`Orchestrator::route`]).
+    pub fn set_router(&mut self, router: Router) {
+        self.router = Arc::new(router);
+    }
+
+    /// Whether [`Orchestrator::route`] can pick anything but the default agent.
+    pub fn routing_enabled(&self) -> bool {
+        self.router.is_enabled()
+    }
+
+    /// Pick the agent to answer `message`: the first matching keyword
+    /// rule, else the classifier's choice, else the default agent. `None`
+    /// without agents.
+    pub async fn route(&self, message: &str) -> Option<Route> {
+        let summaries: Vec<serde_json::Value> = self
+            .slots()
+            .iter()
+            .map(AgentSlot::summary)
+            .filter(|a| a["active"].as_bool().unwrap_or(true))
+            .collect();
+        let field = |a: &'_ serde_json::Value, key: &str| a[key].as_str().unwrap_or("").to_string();
+        let owned: Vec<(String, String, String)> = summaries
+            .iter()
+            .map(|a| (field(a, "name"), field(a, "role"), field(a, "description")))
+            .collect();
+        let mut candidates: Vec<(&str, &str, &str)> = owned
+            .iter()
+            .map(|(name, role, description)| (name.as_str(), role.as_str(), description.as_str()))
+            .collect();
+        candidates.sort();
+        if let Some(route) = self.router.match_rules(message, &candidates) {
+            return Some(route);
+        }
+        if let Some(route) = self.router.classify(message, &candidates).await {
+            return Some(route);
+        }
+        self.default_agent.clone().map(|agent| Route {
+            agent,
+            via: RouteVia::Default,
+        })
+    }
+
     /// Send to the default agent.
     pub async fn send(&mut self, message: &str) -> Result<String> {
         let default = self.default_agent.clone().ok_or_else(|| {
@@ -259,12 +680,17 @@ impl Orchestrator {
         mode: DelegationMode,
     ) -> Result<String> {
         // Verify both agents exist
-        if !self.agents.contains_key(from_agent) {
+        if !self.has_agent(from_agent) {
             return Err(BizClawError::AgentNotFound(from_agent.to_string()));
         }
-        if !self.agents.contains_key(to_agent) {
-            return Err(BizClawError::AgentNotFound(to_agent.to_string()));
-        }
+        let slot = self
+            .slot(to_agent)
+            .ok_or_else(|| BizClawError::AgentNotFound(to_agent.to_string()))?;
+        let delegate_prompt = format!(
+            "[Delegation from agent '{from_agent}']\n\
+             Task: {task}\n\
+             Please process this task and return a clear result."
+        );
 
         // Check permission links (if store is available)
         if let Some(store) = &self.store {
@@ -279,11 +705,7 @@ impl Orchestrator {
 
             // Check concurrency limits
             let active_count = store.active_delegation_count(to_agent).await?;
-            let max_load = self
-                .agents
-                .get(to_agent)
-                .map(|a| a.max_delegation_load)
-                .unwrap_or(10);
+            let max_load = slot.named.lock().await.max_delegation_load;
             if active_count >= max_load {
                 return Err(BizClawError::Delegation(format!(
                     "Agent '{}' at max delegation load ({}/{})",
@@ -301,16 +723,11 @@ impl Orchestrator {
                 .await?;
 
             // Process the task
-            let to = self.agents.get_mut(to_agent).ok_or_else(|| {
-                BizClawError::AgentNotFound(to_agent.to_string())
-            })?;
-            to.message_count += 1;
-            let delegate_prompt = format!(
-                "[Delegation from agent '{from_agent}']\n\
-                 Task: {task}\n\
-                 Please process this task and return a clear result."
-            );
-            let result = to.agent.process(&delegate_prompt).await;
+            let result = {
+                let mut to = slot.named.lock().await;
+                to.message_count += 1;
+                to.agent.process(&delegate_prompt).await
+            };
 
             match &result {
                 Ok(response) => {
@@ -336,7 +753,7 @@ impl Orchestrator {
             }
 
             let response = result?;
-            self.message_log.push(AgentMessage {
+            self.log(AgentMessage {
                 from: from_agent.to_string(),
                 to: to_agent.to_string(),
                 content: task.to_string(),
@@ -346,17 +763,12 @@ impl Orchestrator {
             Ok(response)
         } else {
             // Fallback: no store, simple delegation (backward compatible)
-            let to = self.agents.get_mut(to_agent).ok_or_else(|| {
-                BizClawError::AgentNotFound(to_agent.to_string())
-            })?;
-            to.message_count += 1;
-            let delegate_prompt = format!(
-                "[Delegation from agent '{from_agent}']\n\
-                 Task: {task}\n\
-                 Please process this task and return a clear result."
-            );
-            let response = to.agent.process(&delegate_prompt).await?;
-            self.message_log.push(AgentMessage {
+            let response = {
+                let mut to = slot.named.lock().await;
+                to.message_count += 1;
+                to.agent.process(&delegate_prompt).await?
+            };
+            self.log(AgentMessage {
                 from: from_agent.to_string(),
                 to: to_agent.to_string(),
                 content: task.to_string(),
@@ -367,6 +779,94 @@ impl Orchestrator {
         }
     }
 
+    // ── Agents as Tools ────────────────────────────────────
+
+    /// Give every agent a `call_agent` tool for calling the other agents
+    /// mid-turn. Calls are served while the caller runs through
+    /// [`Orchestrator::send_to`]; call again after adding agents.
+    pub async fn enable_delegation(&mut self) {
+        let slots = self.slots();
+        let mut directory = Vec::new();
+        for slot in &slots {
+            let named = slot.named.lock().await;
+            directory.push((named.name.clone(), named.description.clone()));
+        }
+        for slot in &slots {
+            let mut named = slot.named.lock().await;
+            let name = named.name.clone();
+            let others = directory.iter().filter(|(n, _)| *n != name).cloned().collect();
+            named
+                .agent
+                .register_tool(Box::new(DelegateTool::new(&name, others, slot.delegate_tx.clone())));
+        }
+    }
+
+    /// Drive `run` (the last agent in `chain` processing) to completion,
+    /// answering its `call_agent` requests from `delegations` in the
+    /// meantime. The requesting agent is parked waiting for its reply, so
+    /// serving them here cannot deadlock.
+    async fn serve_delegations(
+        &self,
+        chain: &[String],
+        delegations: &mut mpsc::UnboundedReceiver<DelegateRequest>,
+        run: impl Future<Output = Result<String>> + Send,
+    ) -> Result<String> {
+        tokio::pin!(run);
+        loop {
+            tokio::select! {
+                result = &mut run => break result,
+                Some(request) = delegations.recv() => {
+                    Box::pin(self.serve_delegation(chain, request)).await;
+                }
+            }
+        }
+    }
+
+    /// Run one `call_agent` request on its target agent. `chain` is the
+    /// agents already processing in this conversation, outermost first.
+    async fn serve_delegation(&self, chain: &[String], request: DelegateRequest) {
+        let DelegateRequest { from, to, prompt, budget, reply } = request;
+        if reply.is_closed() {
+            return;
+        }
+        if chain.contains(&to) {
+            let _ = reply.send(Err(BizClawError::Delegation(format!(
+                "Agent '{to}' is busy earlier in this call chain ({})",
+                chain.join(" → ")
+            ))));
+            return;
+        }
+        let Some(slot) = self.slot(&to) else {
+            let _ = reply.send(Err(BizClawError::AgentNotFound(to)));
+            return;
+        };
+        let Ok(mut named) = tokio::time::timeout(BUSY_WAIT, slot.named.lock()).await else {
+            let _ = reply.send(Err(BizClawError::Delegation(format!(
+                "Agent '{to}' is busy with another conversation"
+            ))));
+            return;
+        };
+
+        tracing::info!("🤝 {from} → {to}: delegated call ({budget:?})");
+        named.message_count += 1;
+        let chain = [chain, std::slice::from_ref(&to)].concat();
+        let NamedAgent { agent, delegations, .. } = &mut *named;
+        let result = self
+            .serve_delegations(&chain, delegations, agent.process_delegated(&prompt, &budget))
+            .await;
+        slot.remember(&named);
+        drop(named);
+
+        self.log(AgentMessage {
+            from,
+            to,
+            content: prompt,
+            response: result.as_ref().ok().cloned(),
+            timestamp: chrono::Utc::now(),
+        });
+        let _ = reply.send(result);
+    }
+
     // ── Agent Handoff ──────────────────────────────────────
 
     /// Handoff conversation control from one agent to another.
@@ -378,10 +878,10 @@ impl Orchestrator {
         reason: Option<&str>,
     ) -> Result<()> {
         let store = self.require_store()?;
-        if !self.agents.contains_key(from_agent) {
+        if !self.has_agent(from_agent) {
             return Err(BizClawError::AgentNotFound(from_agent.to_string()));
         }
-        if !self.agents.contains_key(to_agent) {
+        if !self.has_agent(to_agent) {
             return Err(BizClawError::AgentNotFound(to_agent.to_string()));
         }
 
@@ -409,10 +909,10 @@ impl Orchestrator {
 
     /// Run an evaluate loop — generator creates output, evaluator validates it.
     pub async fn evaluate_loop(&mut self, config: &EvaluateConfig) -> Result<EvaluateResult> {
-        if !self.agents.contains_key(&config.generator) {
+        if !self.has_agent(&config.generator) {
             return Err(BizClawError::AgentNotFound(config.generator.clone()));
         }
-        if !self.agents.contains_key(&config.evaluator) {
+        if !self.has_agent(&config.evaluator) {
             return Err(BizClawError::AgentNotFound(config.evaluator.clone()));
         }
 
@@ -440,10 +940,9 @@ impl Orchestrator {
             };
 
             let generator = self
-                .agents
-                .get_mut(&config.generator)
+                .slot(&config.generator)
                 .ok_or_else(|| BizClawError::AgentNotFound(config.generator.clone()))?;
-            last_output = generator.agent.process(&gen_prompt).await?;
+            last_output = generator.named.lock().await.agent.process(&gen_prompt).await?;
 
             // Step 2: Evaluate
             let eval_prompt = format!(
@@ -461,10 +960,9 @@ impl Orchestrator {
             );
 
             let evaluator = self
-                .agents
-                .get_mut(&config.evaluator)
+                .slot(&config.evaluator)
                 .ok_or_else(|| BizClawError::AgentNotFound(config.evaluator.clone()))?;
-            let eval_response = evaluator.agent.process(&eval_prompt).await?;
+            let eval_response = evaluator.named.lock().await.agent.process(&eval_prompt).await?;
 
             if eval_response.trim().starts_with("APPROVED") {
                 return Ok(EvaluateResult {
@@ -505,30 +1003,105 @@ impl Orchestrator {
         })
     }
 
+    // ── Pipelines ──────────────────────────────────────────
+
+    /// Run `config`'s steps in order, each agent getting the previous
+    /// step's output. Steps run in scratch conversations within their
+    /// timeout, and may call other agents; the first failing step stops
+    /// the pipeline. The result holds every step that ran.
+    pub async fn run_pipeline(&mut self, config: &PipelineConfig) -> Result<PipelineResult> {
+        if config.steps.is_empty() {
+            return Err(BizClawError::Delegation("Pipeline has no steps".into()));
+        }
+        if let Some(step) = config.steps.iter().find(|s| !self.has_agent(&s.agent)) {
+            return Err(BizClawError::AgentNotFound(step.agent.clone()));
+        }
+
+        let mut input = config.input.clone();
+        let mut from = "user".to_string();
+        let mut steps = Vec::new();
+        for (i, step) in config.steps.iter().enumerate() {
+            let prompt = pipeline_prompt(config, i, &from, &input);
+            let budget = DelegateBudget {
+                max_secs: Some(step.timeout_secs.max(1)),
+                ..Default::default()
+            };
+            let slot = self
+                .slot(&step.agent)
+                .ok_or_else(|| BizClawError::AgentNotFound(step.agent.clone()))?;
+            let mut named = slot.named.lock().await;
+            named.message_count += 1;
+            let start = std::time::Instant::now();
+            let NamedAgent { agent, delegations, .. } = &mut *named;
+            let result = self
+                .serve_delegations(
+                    std::slice::from_ref(&step.agent),
+                    delegations,
+                    agent.process_delegated(&prompt, &budget),
+                )
+                .await;
+            slot.remember(&named);
+            drop(named);
+            let duration_ms = start.elapsed().as_millis() as u64;
+
+            self.log(AgentMessage {
+                from: from.clone(),
+                to: step.agent.clone(),
+                content: prompt,
+                response: result.as_ref().ok().cloned(),
+                timestamp: chrono::Utc::now(),
+            });
+            match result {
+                Ok(output) => {
+                    steps.push(PipelineStepResult {
+                        agent: step.agent.clone(),
+                        output: Some(output.clone()),
+                        error: None,
+                        duration_ms,
+                    });
+                    input = output;
+                    from = step.agent.clone();
+                }
+                Err(e) => {
+                    tracing::warn!("Pipeline stopped at step {} ({}): {e}", i + 1, step.agent);
+                    steps.push(PipelineStepResult {
+                        agent: step.agent.clone(),
+                        output: None,
+                        error: Some(e.to_string()),
+                        duration_ms,
+                    });
+                    return Ok(PipelineResult { completed: false, output: input, steps });
+                }
+            }
+        }
+        Ok(PipelineResult { completed: true, output: input, steps })
+    }
+
     // ── Quality Gates ──────────────────────────────────────
 
     /// Set quality gates for an agent.
-    pub fn set_quality_gates(&mut self, agent_name: &str, gates: Vec<QualityGate>) {
-        if let Some(named) = self.agents.get_mut(agent_name) {
+    pub async fn set_quality_gates(&mut self, agent_name: &str, gates: Vec<QualityGate>) {
+        if let Some(slot) = self.slot(agent_name) {
+            let mut named = slot.named.lock().await;
             named.quality_gates = gates;
+            slot.remember(&named);
         }
     }
 
-    /// Run quality gates on agent output.
-    async fn run_quality_gates(&mut self, agent_name: &str, output: &str) -> Result<String> {
-        let gates: Vec<QualityGate> = self
-            .agents
-            .get(agent_name)
-            .map(|a| a.quality_gates.clone())
-            .unwrap_or_default();
-
+    /// Run `agent_name`'s quality `gates` on its output.
+    async fn run_quality_gates(
+        &self,
+        agent_name: &str,
+        gates: &[QualityGate],
+        output: &str,
+    ) -> Result<String> {
         if gates.is_empty() {
             return Ok(output.to_string());
         }
 
         let current_output = output.to_string();
 
-        for gate in &gates {
+        for gate in gates {
             match gate.gate_type {
                 QualityGateType::Command => {
                     // Run shell command — exit 0 = pass
@@ -559,10 +1132,7 @@ impl Orchestrator {
                     if gate.target == agent_name {
                         continue;
                     }
-                    if self.agents.contains_key(&gate.target) {
-                        let reviewer = self.agents.get_mut(&gate.target).ok_or_else(|| {
-                            BizClawError::AgentNotFound(gate.target.clone())
-                        })?;
+                    if let Some(reviewer) = self.slot(&gate.target) {
                         let review_prompt = format!(
                             "[Quality Gate Review]\n\
                              Event: {}\n\
@@ -573,7 +1143,7 @@ impl Orchestrator {
                              Respond APPROVED or REJECTED: <reason>",
                             gate.event, current_output
                         );
-                        let review = reviewer.agent.process(&review_prompt).await?;
+                        let review = reviewer.named.lock().await.agent.process(&review_prompt).await?;
                         if review.trim().starts_with("REJECTED") && gate.block_on_failure {
                             return Err(BizClawError::QualityGate(format!(
                                 "Agent gate '{}' rejected: {}",
@@ -607,7 +1177,7 @@ impl Orchestrator {
         role: TeamRole,
     ) -> Result<()> {
         let store = self.require_store()?;
-        if !self.agents.contains_key(agent_name) {
+        if !self.has_agent(agent_name) {
             return Err(BizClawError::AgentNotFound(agent_name.to_string()));
         }
         let mut team = store
@@ -752,7 +1322,7 @@ impl Orchestrator {
 
     /// Broadcast a message to all active agents and collect responses.
     pub async fn broadcast(&mut self, message: &str) -> Vec<(String, Result<String>)> {
-        let agent_names: Vec<String> = self.agents.keys().cloned().collect();
+        let agent_names: Vec<String> = self.read_agents().keys().cloned().collect();
         let mut results = Vec::new();
 
         for name in agent_names {
@@ -763,33 +1333,22 @@ impl Orchestrator {
         results
     }
 
-    /// List all agents with their status.
+    /// List all agents with their status. Agents busy with a message are
+    /// marked `busy` and show their status from before it.
     pub fn list_agents(&self) -> Vec<serde_json::Value> {
-        self.agents
-            .values()
-            .map(|a| {
-                serde_json::json!({
-                    "name": a.name,
-                    "role": a.role,
-                    "description": a.description,
-                    "active": a.active,
-                    "provider": a.agent.provider_name(),
-                    "model": a.agent.model_name(),
-                    "system_prompt": a.agent.system_prompt(),
-                    "tools": a.agent.tool_count(),
-                    "messages_processed": a.message_count,
-                    "conversation_length": a.agent.conversation().len(),
-                    "is_default": self.default_agent.as_deref() == Some(&a.name),
-                    "quality_gates": a.quality_gates.len(),
-                    "max_delegation_load": a.max_delegation_load,
-                })
+        self.slots()
+            .iter()
+            .map(|slot| {
+                let mut summary = slot.summary();
+                summary["is_default"] = (self.default_agent.as_deref() == summary["name"].as_str()).into();
+                summary
             })
             .collect()
     }
 
     /// Get total agent count.
     pub fn agent_count(&self) -> usize {
-        self.agents.len()
+        self.read_agents().len()
     }
 
     /// Get the default agent name.
@@ -800,6 +1359,8 @@ impl Orchestrator {
     /// Get recent message log (last N entries).
     pub fn recent_messages(&self, limit: usize) -> Vec<serde_json::Value> {
         self.message_log
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
             .iter()
             .rev()
             .take(limit)
@@ -815,47 +1376,63 @@ impl Orchestrator {
             .collect()
     }
 
-    /// Get a mutable reference to an agent.
-    pub fn get_agent_mut(&mut self, name: &str) -> Option<&mut Agent> {
-        self.agents.get_mut(name).map(|a| &mut a.agent)
+    /// Knowledge chunks cited in the answer to the last message sent through
+    /// this instance with [`Orchestrator::send_to`], by whichever agent
+    /// answered it.
+    pub fn last_citations(&self) -> &[crate::rag::Citation] {
+        &self.citations
+    }
+
+    /// Tool calls made for the last message sent through this instance.
+    pub fn last_tool_calls(&self) -> usize {
+        self.tool_calls
+    }
+
+    /// Lock an agent for changes, waiting while it processes a message.
+    pub async fn get_agent_mut(&self, name: &str) -> Option<OwnedMappedMutexGuard<NamedAgent, Agent>> {
+        let slot = self.slot(name)?;
+        let named = slot.named.lock_owned().await;
+        Some(OwnedMutexGuard::map(named, |named| &mut named.agent))
     }
 
     /// Update agent metadata (role, description).
-    pub fn update_agent(
+    pub async fn update_agent(
         &mut self,
         name: &str,
         role: Option<&str>,
         description: Option<&str>,
     ) -> bool {
-        if let Some(named) = self.agents.get_mut(name) {
-            if let Some(r) = role {
-                named.role = r.to_string();
-            }
-            if let Some(d) = description {
-                named.description = d.to_string();
-            }
-            true
-        } else {
-            false
+        let Some(slot) = self.slot(name) else {
+            return false;
+        };
+        let mut named = slot.named.lock().await;
+        if let Some(r) = role {
+            named.role = r.to_string();
         }
+        if let Some(d) = description {
+            named.description = d.to_string();
+        }
+        slot.remember(&named);
+        true
     }
 
     /// Check if an agent exists.
     pub fn has_agent(&self, name: &str) -> bool {
-        self.agents.contains_key(name)
+        self.read_agents().contains_key(name)
     }
 
     /// Generate AGENTS.md content for agent discovery.
     pub fn agents_discovery_md(&self) -> String {
         let mut md = String::from("# Available Agents\n\n");
-        for a in self.agents.values() {
+        for slot in self.slots() {
+            let a = slot.summary();
             md.push_str(&format!(
                 "## {}\n- **Role**: {}\n- **Description**: {}\n- **Provider**: {}/{}\n\n",
-                a.name,
-                a.role,
-                a.description,
-                a.agent.provider_name(),
-                a.agent.model_name()
+                a["name"].as_str().unwrap_or_default(),
+                a["role"].as_str().unwrap_or_default(),
+                a["description"].as_str().unwrap_or_default(),
+                a["provider"].as_str().unwrap_or_default(),
+                a["model"].as_str().unwrap_or_default()
             ));
         }
         md
@@ -872,6 +1449,7 @@ impl Default for Orchestrator {
 mod tests {
     use super::*;
     use bizclaw_core::config::BizClawConfig;
+    use bizclaw_core::types::{FunctionCall, ProviderResponse, ToolCall};
 
     fn make_test_agent() -> Agent {
         Agent::new(BizClawConfig::default()).expect("test agent creation failed")
@@ -882,7 +1460,7 @@ mod tests {
         let orch = Orchestrator::new();
         assert_eq!(orch.agent_count(), 0);
         assert!(orch.default_agent_name().is_none());
-        assert!(orch.message_log.is_empty());
+        assert!(orch.recent_messages(1).is_empty());
     }
 
     #[test]
@@ -949,12 +1527,12 @@ mod tests {
         assert_eq!(orch.default_agent_name(), Some("b"));
     }
 
-    #[test]
-    fn test_update_agent() {
+    #[tokio::test]
+    async fn test_update_agent() {
         let mut orch = Orchestrator::new();
         orch.add_agent("x", "assistant", "Original", make_test_agent());
 
-        let updated = orch.update_agent("x", Some("coder"), Some("Updated desc"));
+        let updated = orch.update_agent("x", Some("coder"), Some("Updated desc")).await;
         assert!(updated);
 
         let agents = orch.list_agents();
@@ -963,10 +1541,10 @@ mod tests {
         assert_eq!(agent["description"], "Updated desc");
     }
 
-    #[test]
-    fn test_update_nonexistent_agent() {
+    #[tokio::test]
+    async fn test_update_nonexistent_agent() {
         let mut orch = Orchestrator::new();
-        let updated = orch.update_agent("ghost", Some("role"), None);
+        let updated = orch.update_agent("ghost", Some("role"), None).await;
         assert!(!updated);
     }
 
@@ -1010,13 +1588,13 @@ mod tests {
         assert!(msgs.is_empty());
     }
 
-    #[test]
-    fn test_get_agent_mut() {
+    #[tokio::test]
+    async fn test_get_agent_mut() {
         let mut orch = Orchestrator::new();
         orch.add_agent("mutable", "assistant", "M", make_test_agent());
 
-        assert!(orch.get_agent_mut("mutable").is_some());
-        assert!(orch.get_agent_mut("nonexistent").is_none());
+        assert!(orch.get_agent_mut("mutable").await.is_some());
+        assert!(orch.get_agent_mut("nonexistent").await.is_none());
     }
 
     #[test]
@@ -1025,6 +1603,74 @@ mod tests {
         assert_eq!(orch.agent_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_pipeline_feeds_each_output_to_the_next_agent() {
+        let mut orch = Orchestrator::new();
+        orch.add_agent(
+            "researcher",
+            "researcher",
+            "Research",
+            crate::tests::test_agent(vec![ProviderResponse::text("notes: sales up 12%")]),
+        );
+        orch.add_agent(
+            "writer",
+            "writer",
+            "Writing",
+            crate::tests::test_agent(vec![
+                ProviderResponse::text("Sales grew 12% this quarter."),
+                ProviderResponse::with_tool_calls(vec![ToolCall {
+                    id: "call-hang".into(),
+                    r#type: "function".into(),
+                    function: FunctionCall { name: "hang".into(), arguments: "{}".into() },
+                }]),
+            ]),
+        );
+        let config = PipelineConfig {
+            input: "Q3 sales".into(),
+            steps: vec![
+                PipelineStep::new("researcher", "Collect facts"),
+                PipelineStep::new("writer", "Write one sentence"),
+                PipelineStep { timeout_secs: 1, ..PipelineStep::new("writer", "Review it") },
+            ],
+        };
+
+        let result = orch.run_pipeline(&config).await.unwrap();
+        // The review step hangs in a tool and times out
+        assert!(!result.completed);
+        assert_eq!(result.output, "Sales grew 12% this quarter.");
+        let outputs: Vec<Option<&str>> = result.steps.iter().map(|s| s.output.as_deref()).collect();
+        assert_eq!(outputs, [Some("notes: sales up 12%"), Some("Sales grew 12% this quarter."), None]);
+        assert!(result.steps[2].error.as_deref().unwrap().contains("1s"));
+
+        let handed = orch.message_log.lock().unwrap()[1].clone();
+        assert_eq!((handed.from.as_str(), handed.to.as_str()), ("researcher", "writer"));
+        assert!(handed.content.contains("Original request: Q3 sales"));
+        assert!(handed.content.contains("notes: sales up 12%"));
+        // Steps run in scratch conversations
+        assert_eq!(orch.get_agent_mut("writer").await.unwrap().conversation().len(), 1);
+
+        let missing = PipelineConfig { input: "x".into(), steps: vec![PipelineStep::new("nobody", "")] };
+        assert!(orch.run_pipeline(&missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_route_by_rule_then_default() {
+        let mut orch = Orchestrator::new();
+        assert!(orch.route("hi").await.is_none());
+        orch.add_agent("support", "support", "Orders", make_test_agent());
+        orch.add_agent("sales", "sales", "Pricing", make_test_agent());
+        orch.set_router(Router::new(vec![bizclaw_core::config::RoutingRule {
+            agent: "sales".into(),
+            keywords: vec!["price".into()],
+        }]));
+        assert!(orch.routing_enabled());
+
+        let route = orch.route("What's the price of the Pro plan?").await.unwrap();
+        assert_eq!(route.agent, "sales");
+        let route = orch.route("Where is my order?").await.unwrap();
+        assert_eq!((route.agent.as_str(), route.via), ("support", RouteVia::Default));
+    }
+
     #[test]
     fn test_has_agent() {
         let mut orch = Orchestrator::new();
@@ -1053,4 +1699,280 @@ mod tests {
         let orch = Orchestrator::with_store(store);
         assert!(orch.store().is_some());
     }
+
+    fn call_agent(agent: &str, prompt: &str) -> ProviderResponse {
+        ProviderResponse::with_tool_calls(vec![ToolCall {
+            id: format!("call-{agent}"),
+            r#type: "function".into(),
+            function: FunctionCall {
+                name: "call_agent".into(),
+                arguments: serde_json::json!({"agent": agent, "prompt": prompt, "max_rounds": 1})
+                    .to_string(),
+            },
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_agent_calls_agent_as_tool() {
+        let mut orch = Orchestrator::new();
+        orch.add_agent(
+            "research",
+            "researcher",
+            "Research",
+            crate::tests::test_agent(vec![
+                call_agent("summarizer", "Summarize: long text"),
+                ProviderResponse::text("Report based on the summary."),
+            ]),
+        );
+        orch.add_agent(
+            "summarizer",
+            "writer",
+            "Cheap summarizer",
+            crate::tests::test_agent(vec![ProviderResponse::text("short summary")]),
+        );
+        orch.enable_delegation().await;
+
+        let answer = orch.send_to("research", "Research X").await.unwrap();
+        assert_eq!(answer, "Report based on the summary.");
+
+        let research = orch.get_agent_mut("research").await.unwrap();
+        let tool_reply = research.conversation().iter().find(|m| m.role == Role::Tool).unwrap();
+        assert_eq!(tool_reply.content, "short summary");
+
+        // The summarizer answered in a scratch conversation.
+        assert_eq!(orch.get_agent_mut("summarizer").await.unwrap().conversation().len(), 1);
+        let log = orch.message_log.lock().unwrap()[0].clone();
+        assert_eq!((log.from.as_str(), log.to.as_str()), ("research", "summarizer"));
+    }
+
+    #[tokio::test]
+    async fn test_delegation_cycle_fails_instead_of_deadlocking() {
+        let mut orch = Orchestrator::new();
+        orch.add_agent(
+            "a",
+            "assistant",
+            "A",
+            crate::tests::test_agent(vec![call_agent("b", "help"), ProviderResponse::text("a done")]),
+        );
+        orch.add_agent(
+            "b",
+            "assistant",
+            "B",
+            crate::tests::test_agent(vec![call_agent("a", "help back"), ProviderResponse::text("b done")]),
+        );
+        orch.enable_delegation().await;
+
+        let answer = orch.send_to("a", "go").await.unwrap();
+        assert_eq!(answer, "a done");
+
+        // b's call back into a was refused as a cycle.
+        let log = orch.message_log.lock().unwrap().clone();
+        let b_calls: Vec<&AgentMessage> = log.iter().filter(|m| m.from == "a").collect();
+        assert_eq!(b_calls[0].response.as_deref(), Some("b done"));
+        assert!(orch.has_agent("a") && orch.has_agent("b"));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_keep_chats_apart() {
+        let mut orch = Orchestrator::new();
+        orch.add_agent(
+            "support",
+            "support",
+            "Support",
+            crate::tests::test_agent(vec![
+                ProviderResponse::text("Hi A."),
+                ProviderResponse::text("Hi B."),
+                ProviderResponse::text("Still A."),
+            ]),
+        );
+        orch.clone().with_session("zalo:a").send_to("support", "I'm A").await.unwrap();
+        orch.clone().with_session("zalo:b").send_to("support", "I'm B").await.unwrap();
+        let answer = orch.clone().with_session("zalo:a").send_to("support", "Again").await.unwrap();
+        assert_eq!(answer, "Still A.");
+
+        let agent = orch.get_agent_mut("support").await.unwrap();
+        let user_turns: Vec<&str> = agent
+            .conversation()
+            .iter()
+            .filter(|m| m.role == Role::User)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(user_turns, ["I'm A", "Again"]);
+        drop(agent);
+        // zalo:a, zalo:b and the agent's own session
+        assert_eq!(orch.list_agents()[0]["sessions"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_agents_process_concurrently() {
+        let mut orch = Orchestrator::new();
+        let hang = ProviderResponse::with_tool_calls(vec![ToolCall {
+            id: "call-hang".into(),
+            r#type: "function".into(),
+            function: FunctionCall { name: "hang".into(), arguments: "{}".into() },
+        }]);
+        orch.add_agent("slow", "assistant", "Slow", crate::tests::test_agent(vec![hang]));
+        orch.add_agent(
+            "fast",
+            "assistant",
+            "Fast",
+            crate::tests::test_agent(vec![ProviderResponse::text("quick answer")]),
+        );
+
+        let mut busy = orch.clone();
+        let slow = tokio::spawn(async move { busy.send_to("slow", "think hard").await });
+        let is_busy = |orch: &Orchestrator| {
+            orch.list_agents().iter().any(|a| a["name"] == "slow" && a["busy"] == true)
+        };
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !is_busy(&orch) {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // Another agent answers while the slow one is stuck
+        let mut other = orch.clone();
+        let answer = tokio::time::timeout(Duration::from_secs(5), other.send_to("fast", "hi"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(answer, "quick answer");
+        // A second message to the busy agent waits its turn
+        let queued = tokio::time::timeout(Duration::from_millis(200), other.send_to("slow", "again")).await;
+        assert!(queued.is_err());
+
+        slow.abort();
+        let _ = slow.await;
+        assert!(!is_busy(&orch));
+        assert_eq!(orch.recent_messages(10).len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mcp_pool_tools_shared_by_all_agents() {
+        // A stdio MCP server answering in order: initialize, initialized,
+        // tools/list, then every tool call.
+        let script = r#"read l; echo '{"jsonrpc":"2.0","id":1,"result":{}}'
+read l; echo '{"jsonrpc":"2.0","id":2,"result":{}}'
+read l; echo '{"jsonrpc":"2.0","id":3,"result":{"tools":[{"name":"lookup","description":"Look up"}]}}'
+while read l; do echo '{"jsonrpc":"2.0","id":0,"result":{"content":[{"type":"text","text":"found"}]}}'; done"#;
+        let pool = bizclaw_mcp::McpPool::connect(&[bizclaw_mcp::McpServerConfig {
+            name: "crm".into(),
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            env: Default::default(),
+            url: String::new(),
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }])
+        .await;
+        assert_eq!(pool.status()[0]["tools_count"], 1);
+
+        let lookup = || {
+            ProviderResponse::with_tool_calls(vec![ToolCall {
+                id: "call-lookup".into(),
+                r#type: "function".into(),
+                function: FunctionCall { name: "lookup".into(), arguments: "{}".into() },
+            }])
+        };
+        let mut orch = Orchestrator::new();
+        orch.add_agent(
+            "before",
+            "assistant",
+            "Added before the pool",
+            crate::tests::test_agent(vec![lookup(), ProviderResponse::text("done")]),
+        );
+        orch.set_mcp_pool(Arc::new(pool)).await;
+        orch.add_agent(
+            "after",
+            "assistant",
+            "Added after the pool",
+            crate::tests::test_agent(vec![lookup(), ProviderResponse::text("done")]),
+        );
+
+        for name in ["before", "after"] {
+            orch.send_to(name, "Find Acme").await.unwrap();
+            let agent = orch.get_agent_mut(name).await.unwrap();
+            let tool_reply = agent.conversation().iter().find(|m| m.role == Role::Tool).unwrap();
+            assert_eq!(tool_reply.content, "found", "{name}");
+        }
+        assert_eq!(orch.mcp_pool().unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mcp_resources_and_prompts_reach_agents() {
+        // Answers in order: initialize, initialized, tools/list,
+        // resources/list, prompts/list, resources/read, then prompts/get.
+        let script = r#"read l; echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"resources":{},"prompts":{}}}}'
+read l; echo '{"jsonrpc":"2.0","id":2,"result":{}}'
+read l; echo '{"jsonrpc":"2.0","id":3,"result":{"tools":[]}}'
+read l; echo '{"jsonrpc":"2.0","id":4,"result":{"resources":[{"uri":"kb://returns","name":"Returns policy"}]}}'
+read l; echo '{"jsonrpc":"2.0","id":5,"result":{"prompts":[{"name":"formal"}]}}'
+read l; echo '{"jsonrpc":"2.0","id":6,"result":{"contents":[{"uri":"kb://returns","text":"Refunds are paid within 5 days."}]}}'
+while read l; do echo '{"jsonrpc":"2.0","id":0,"result":{"messages":[{"role":"user","content":{"type":"text","text":"Address customers formally."}}]}}'; done"#;
+        let pool = bizclaw_mcp::McpPool::connect(&[bizclaw_mcp::McpServerConfig {
+            name: "kb".into(),
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            env: Default::default(),
+            url: String::new(),
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }])
+        .await;
+
+        let mut orch = Orchestrator::new();
+        orch.set_mcp_pool(Arc::new(pool)).await;
+        orch.add_agent(
+            "support",
+            "assistant",
+            "Support",
+            crate::tests::test_agent(vec![ProviderResponse::text("5 days")]),
+        );
+        assert!(orch.set_agent_prompts("support", &["kb/formal".into()]).await.is_ok());
+        assert!(orch.set_agent_prompts("support", &["formal".into()]).await.is_err());
+        assert!(orch.set_agent_prompts("nobody", &[]).await.is_err());
+        assert_eq!(orch.list_agents()[0]["mcp_prompts"][0], "kb/formal");
+
+        orch.send_to("support", "When are refunds paid?").await.unwrap();
+        let agent = orch.get_agent_mut("support").await.unwrap();
+        let conversation = agent.conversation();
+        assert!(conversation[0].content.ends_with("## Prompt template: kb/formal\nAddress customers formally."));
+        let context = conversation.iter().find(|m| m.content.starts_with("[MCP resources]")).unwrap();
+        assert!(context.content.contains("[kb: Returns policy] Refunds are paid within 5 days."));
+    }
+
+    #[test]
+    fn test_failed_mcp_server_adds_no_tools() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let pool = rt.block_on(bizclaw_mcp::McpPool::connect(&[bizclaw_mcp::McpServerConfig {
+            name: "missing".into(),
+            command: "/nonexistent/mcp-server".into(),
+            args: vec![],
+            env: Default::default(),
+            url: String::new(),
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }]));
+        let status = pool.status();
+        assert_eq!(status[0]["connected"], false);
+        assert!(status[0]["error"].as_str().unwrap().contains("spawn"));
+
+        let mut orch = Orchestrator::new();
+        orch.add_agent("a", "assistant", "A", make_test_agent());
+        rt.block_on(async {
+            let before = orch.get_agent_mut("a").await.unwrap().tool_count();
+            orch.set_mcp_pool(Arc::new(pool)).await;
+            assert_eq!(orch.get_agent_mut("a").await.unwrap().tool_count(), before);
+        });
+    }
 }
@@ -0,0 +1,105 @@
+//! Progress events emitted while the agent works through tool rounds.
+//!
+//! Channels that can edit a sent message (Telegram) render these into a
+//! placeholder so users see "🔧 running web_search…" instead of dead air,
+//! then replace the placeholder with the final answer.
+
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A step in the Think-Act-Observe loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The model asked for tools — tool round `round` (1-based) begins.
+    Round { round: usize },
+    /// A tool call started.
+    ToolStarted { round: usize, tool: String },
+    /// A tool call finished (`success` is false on error or unknown tool).
+    ToolFinished {
+        round: usize,
+        tool: String,
+        success: bool,
+    },
+}
+
+/// Receives progress events during `Agent::process_with_progress`.
+pub type ProgressSink = UnboundedSender<ProgressEvent>;
+
+/// Accumulates progress events into a status text for an editable message.
+#[derive(Debug, Default)]
+pub struct ProgressView {
+    round: usize,
+    lines: Vec<(String, String)>,
+}
+
+impl ProgressView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply an event.
+    pub fn apply(&mut self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::Round { round } => {
+                self.round = *round;
+                self.lines.clear();
+            }
+            ProgressEvent::ToolStarted { tool, .. } => {
+                self.lines.push((tool.clone(), format!("🔧 running {tool}…")));
+            }
+            ProgressEvent::ToolFinished { tool, success, .. } => {
+                let line = if *success {
+                    format!("✅ {tool}")
+                } else {
+                    format!("⚠️ {tool} failed")
+                };
+                match self.lines.iter_mut().rev().find(|(name, _)| name == tool) {
+                    Some(entry) => entry.1 = line,
+                    None => self.lines.push((tool.clone(), line)),
+                }
+            }
+        }
+    }
+
+    /// Current status text.
+    pub fn render(&self) -> String {
+        let mut out = if self.round > 0 {
+            format!("🧠 Working… (round {})", self.round)
+        } else {
+            "🧠 Thinking…".to_string()
+        };
+        for (_, line) in &self.lines {
+            out.push('\n');
+            out.push_str(line);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_view_render() {
+        let mut view = ProgressView::new();
+        assert_eq!(view.render(), "🧠 Thinking…");
+
+        view.apply(&ProgressEvent::Round { round: 1 });
+        view.apply(&ProgressEvent::ToolStarted {
+            round: 1,
+            tool: "web_search".into(),
+        });
+        assert_eq!(view.render(), "🧠 Working… (round 1)\n🔧 running web_search…");
+
+        view.apply(&ProgressEvent::ToolFinished {
+            round: 1,
+            tool: "web_search".into(),
+            success: false,
+        });
+        assert_eq!(view.render(), "🧠 Working… (round 1)\n⚠️ web_search failed");
+
+        // A new round starts a fresh tool list.
+        view.apply(&ProgressEvent::Round { round: 2 });
+        assert_eq!(view.render(), "🧠 Working… (round 2)");
+    }
+}
@@ -0,0 +1,545 @@
+//! Retrieval pipeline — gathers context for a user message from several
+//! retrievers, then filters, deduplicates and optionally reranks the hits
+//! before they are injected into the prompt.
+
+use async_trait::async_trait;
+use bizclaw_core::config::RagConfig;
+use bizclaw_core::error::Result;
+use bizclaw_core::traits::memory::MemoryBackend;
+use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::types::Message;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Knowledge base shared with the gateway.
+pub type SharedKnowledge = Arc<tokio::sync::Mutex<Option<bizclaw_knowledge::KnowledgeStore>>>;
+
+/// A single retrieved hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passage {
+    /// Retriever that produced it (see [`Retriever::name`]).
+    pub source: String,
+    /// Origin within the source, e.g. the document name.
+    pub label: Option<String>,
+    pub content: String,
+    /// Relevance, higher is better. Scales differ per retriever until the
+    /// pipeline normalizes them.
+    pub score: f32,
+    /// Knowledge chunk the passage was taken from, making it citable.
+    pub chunk: Option<ChunkRef>,
+}
+
+/// A chunk of a knowledge base document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkRef {
+    pub doc_name: String,
+    pub chunk_idx: usize,
+}
+
+/// A knowledge chunk cited in an answer.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Citation {
+    /// Number of the passage in the injected context, as in `[2]`.
+    pub marker: usize,
+    pub doc_name: String,
+    pub chunk_idx: usize,
+    /// Relevance, 1.0 for the best hit.
+    pub score: f32,
+}
+
+/// A source of context passages.
+#[async_trait]
+pub trait Retriever: Send + Sync {
+    /// Source name; also heads its block in the injected context.
+    fn name(&self) -> &str;
+
+    /// Up to `limit` passages relevant to `query`.
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<Passage>>;
+}
+
+/// Uploaded documents in the given namespaces (all when empty), searched
+/// with FTS5/BM25.
+pub struct KnowledgeRetriever(pub SharedKnowledge, pub Vec<String>);
+
+#[async_trait]
+impl Retriever for KnowledgeRetriever {
+    fn name(&self) -> &str {
+        "knowledge"
+    }
+
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<Passage>> {
+        let store = self.0.lock().await;
+        let Some(kb) = store.as_ref() else {
+            return Ok(vec![]);
+        };
+        Ok(kb
+            .search_in(query, limit, &self.1)
+            .into_iter()
+            .map(|r| Passage {
+                source: "knowledge".into(),
+                label: Some(r.citation()),
+                // BM25 is negative, more negative = more relevant
+                score: -r.score as f32,
+                chunk: Some(ChunkRef {
+                    doc_name: r.doc_name,
+                    chunk_idx: r.chunk_idx,
+                }),
+                content: r.content,
+            })
+            .collect())
+    }
+}
+
+/// Past conversations saved in the memory backend.
+pub struct MemoryRetriever<'a>(pub &'a dyn MemoryBackend);
+
+/// Content words of `text` for a keyword search (stop words dropped).
+fn keywords(text: &str) -> Vec<&str> {
+    const STOP_WORDS: &[&str] = &[
+        "the", "a", "an", "is", "are", "was", "were", "be", "been", "being", "have", "has",
+        "had", "do", "does", "did", "will", "would", "could", "should", "may", "might",
+        "shall", "can", "need", "dare", "ought", "i", "me", "my", "you", "your", "he", "she",
+        "it", "we", "they", "this", "that", "these", "those", "what", "which", "who", "how",
+        "and", "but", "or", "not", "no", "of", "in", "on", "at", "to", "for", "with", "from",
+        "by", "as", "if", "then", "so", "than", "tôi", "bạn", "là", "có", "và", "của", "với",
+        "cho", "để", "không", "được", "này", "đó", "một", "các", "những",
+    ];
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() > 2 && !STOP_WORDS.contains(&w.to_lowercase().as_str()))
+        .take(5)
+        .collect()
+}
+
+#[async_trait]
+impl Retriever for MemoryRetriever<'_> {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<Passage>> {
+        let keywords = keywords(query);
+        if keywords.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .0
+            .search(&keywords.join(" "), limit)
+            .await?
+            .into_iter()
+            .map(|r| Passage {
+                source: "memory".into(),
+                label: None,
+                content: r.entry.content,
+                score: r.score,
+                chunk: None,
+            })
+            .collect())
+    }
+}
+
+/// Text resources of the MCP servers in a pool, matched by keyword.
+pub struct McpResourceRetriever(pub Arc<bizclaw_mcp::McpPool>);
+
+/// Characters per searched piece of an MCP resource.
+const RESOURCE_CHUNK_CHARS: usize = 800;
+
+/// `text` cut at paragraph breaks into pieces of about `max_chars`.
+fn paragraphs(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.len() + paragraph.len() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[async_trait]
+impl Retriever for McpResourceRetriever {
+    fn name(&self) -> &str {
+        "mcp"
+    }
+
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<Passage>> {
+        let keywords: Vec<String> = keywords(query).iter().map(|k| k.to_lowercase()).collect();
+        if keywords.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut hits: Vec<Passage> = self
+            .0
+            .resource_texts()
+            .flat_map(|(resource, text)| {
+                paragraphs(text, RESOURCE_CHUNK_CHARS).into_iter().filter_map(|chunk| {
+                    let lower = chunk.to_lowercase();
+                    let score: usize = keywords.iter().map(|k| lower.matches(k.as_str()).count()).sum();
+                    (score > 0).then(|| Passage {
+                        source: "mcp".into(),
+                        label: Some(format!("{}: {}", resource.server_name, resource.name)),
+                        content: chunk,
+                        score: score as f32,
+                        chunk: None,
+                    })
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+/// Runs retrievers and turns their hits into one ranked, deduplicated list.
+pub struct RetrievalPipeline<'a> {
+    config: &'a RagConfig,
+    /// Provider and model asked to rerank, if enabled.
+    reranker: Option<(&'a dyn Provider, &'a str)>,
+}
+
+impl<'a> RetrievalPipeline<'a> {
+    pub fn new(config: &'a RagConfig) -> Self {
+        Self {
+            config,
+            reranker: None,
+        }
+    }
+
+    /// Let `model` on `provider` reorder and filter the hits.
+    pub fn with_reranker(mut self, provider: &'a dyn Provider, model: &'a str) -> Self {
+        self.reranker = Some((provider, model));
+        self
+    }
+
+    /// Retrieve from every retriever, best passages first. A failing
+    /// retriever is skipped.
+    pub async fn run(&self, query: &str, retrievers: &[&dyn Retriever]) -> Vec<Passage> {
+        let mut passages = Vec::new();
+        for retriever in retrievers {
+            match retriever.retrieve(query, self.config.per_source).await {
+                Ok(hits) => passages.extend(normalize(hits, self.config.min_score)),
+                Err(e) => tracing::debug!("Retriever '{}' failed: {e}", retriever.name()),
+            }
+        }
+        // Stable: on equal scores earlier retrievers stay first
+        passages.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let mut passages = dedup(passages, self.config.dedup_threshold);
+
+        if let Some((provider, model)) = self.reranker
+            && passages.len() > 1
+        {
+            passages = rerank(provider, model, query, passages).await;
+        }
+        passages.truncate(self.config.max_passages);
+        tracing::debug!("RAG: {} passage(s) for injection", passages.len());
+        passages
+    }
+}
+
+/// Scale scores to the best hit (1.0) and drop those below `min_score`.
+/// Sources without meaningful scores (all ≤ 0) count every hit as 1.0.
+fn normalize(mut hits: Vec<Passage>, min_score: f32) -> Vec<Passage> {
+    let best = hits.iter().map(|p| p.score).fold(0.0f32, f32::max);
+    for hit in &mut hits {
+        hit.score = if best > 0.0 { hit.score / best } else { 1.0 };
+    }
+    hits.retain(|p| p.score >= min_score);
+    hits
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Drop passages whose word overlap with a better one reaches `threshold`.
+fn dedup(passages: Vec<Passage>, threshold: f32) -> Vec<Passage> {
+    let mut kept: Vec<(Passage, HashSet<String>)> = Vec::new();
+    for passage in passages {
+        let words = word_set(&passage.content);
+        let duplicate = kept.iter().any(|(_, other)| {
+            let union = words.union(other).count();
+            union == 0 || words.intersection(other).count() as f32 / union as f32 >= threshold
+        });
+        if !duplicate {
+            kept.push((passage, words));
+        }
+    }
+    kept.into_iter().map(|(p, _)| p).collect()
+}
+
+/// Ask the LLM which passages help answer `query`, most useful first.
+/// Keeps the original order when the reply can't be used.
+async fn rerank(
+    provider: &dyn Provider,
+    model: &str,
+    query: &str,
+    passages: Vec<Passage>,
+) -> Vec<Passage> {
+    let listing: String = passages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("[{}] {}\n", i + 1, p.content.chars().take(400).collect::<String>()))
+        .collect();
+    let prompt = format!(
+        "QUESTION: {query}\n\nPASSAGES:\n{listing}\n\
+         List the numbers of the passages that help answer the question, most useful first, \
+         comma-separated. Reply NONE if none do."
+    );
+    let messages = [Message::system("You rank search results."), Message::user(prompt)];
+    let params = GenerateParams {
+        model: model.to_string(),
+        temperature: 0.0,
+        max_tokens: 60,
+        ..Default::default()
+    };
+    let reply = match provider.chat(&messages, &[], &params).await {
+        Ok(r) => r.content.unwrap_or_default(),
+        Err(e) => {
+            tracing::debug!("Rerank failed: {e}");
+            return passages;
+        }
+    };
+    if reply.trim().eq_ignore_ascii_case("none") {
+        return vec![];
+    }
+
+    let mut order: Vec<usize> = Vec::new();
+    for n in reply.split(|c: char| !c.is_ascii_digit()).filter_map(|n| n.parse::<usize>().ok()) {
+        if (1..=passages.len()).contains(&n) && !order.contains(&(n - 1)) {
+            order.push(n - 1);
+        }
+    }
+    if order.is_empty() {
+        return passages;
+    }
+    order.into_iter().map(|i| passages[i].clone()).collect()
+}
+
+/// Context messages for `passages`, one system message per source, within
+/// `max_chars` of passage text, and the citable passages among them. With
+/// `cite`, the model is asked to mark what it uses with `[n]`.
+pub fn render(passages: &[Passage], max_chars: usize, cite: bool) -> (Vec<Message>, Vec<Citation>) {
+    let mut sources: Vec<(&str, String)> = Vec::new();
+    let mut citations = Vec::new();
+    let mut used = 0;
+    for passage in passages {
+        let label = passage.label.as_ref().map(|l| format!("[{l}] ")).unwrap_or_default();
+        let line_len = label.len() + passage.content.len();
+        if used + line_len > max_chars {
+            continue;
+        }
+        used += line_len;
+        let body = match sources.iter_mut().find(|(s, _)| *s == passage.source) {
+            Some((_, body)) => body,
+            None => {
+                sources.push((&passage.source, String::new()));
+                &mut sources.last_mut().unwrap().1
+            }
+        };
+        let n = body.lines().count() + 1;
+        body.push_str(&format!("{n}. {label}{}\n", passage.content.replace('\n', " ")));
+        if let Some(chunk) = &passage.chunk {
+            citations.push(Citation {
+                marker: n,
+                doc_name: chunk.doc_name.clone(),
+                chunk_idx: chunk.chunk_idx,
+                score: passage.score,
+            });
+        }
+    }
+
+    let messages = sources
+        .into_iter()
+        .map(|(source, mut body)| {
+            if cite && source == "knowledge" {
+                body.push_str("Cite the passages you use by number, e.g. [1] or [1][3].\n");
+            }
+            let (open, close) = match source {
+                "knowledge" => ("[Knowledge Base]".to_string(), "[End knowledge]".to_string()),
+                "memory" => ("[Past conversations]".to_string(), "[End past]".to_string()),
+                "mcp" => ("[MCP resources]".to_string(), "[End MCP resources]".to_string()),
+                other => (format!("[{other}]"), format!("[End {other}]")),
+            };
+            Message::system(format!("{open}\n{body}{close}"))
+        })
+        .collect();
+    (messages, citations)
+}
+
+/// The citations among `provided` that `answer` marks with `[n]` (or
+/// `[n, m]`), in order of first mention.
+pub fn cited(answer: &str, provided: &[Citation]) -> Vec<Citation> {
+    let mut found: Vec<Citation> = Vec::new();
+    for group in answer.split('[').skip(1) {
+        let Some((inside, _)) = group.split_once(']') else { continue };
+        if !inside.chars().all(|c| c.is_ascii_digit() || c == ',' || c == ' ') {
+            continue;
+        }
+        for n in inside.split(',').filter_map(|n| n.trim().parse::<usize>().ok()) {
+            if let Some(citation) = provided.iter().find(|c| c.marker == n)
+                && !found.contains(citation)
+            {
+                found.push(citation.clone());
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::{ModelInfo, ProviderResponse, ToolDefinition};
+
+    struct Fixed(&'static str, Vec<(&'static str, f32)>);
+
+    #[async_trait]
+    impl Retriever for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+        async fn retrieve(&self, _query: &str, limit: usize) -> Result<Vec<Passage>> {
+            Ok(self
+                .1
+                .iter()
+                .take(limit)
+                .map(|&(content, score)| Passage {
+                    source: self.0.into(),
+                    label: None,
+                    content: content.into(),
+                    score,
+                    chunk: None,
+                })
+                .collect())
+        }
+    }
+
+    struct Reply(&'static str);
+
+    #[async_trait]
+    impl Provider for Reply {
+        fn name(&self) -> &str {
+            "reply"
+        }
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            Ok(ProviderResponse::text(self.0))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn contents(passages: &[Passage]) -> Vec<&str> {
+        passages.iter().map(|p| p.content.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_threshold_and_cross_source_dedup() {
+        let config = RagConfig {
+            min_score: 0.3,
+            ..Default::default()
+        };
+        let docs = Fixed("knowledge", vec![("Shop opens at 9am daily", 8.0), ("Parking is free", 1.0)]);
+        let past = Fixed("memory", vec![("The shop opens at 9am daily.", 2.0), ("Refunds take 5 days", 1.5)]);
+
+        let passages = RetrievalPipeline::new(&config).run("opening hours", &[&docs, &past]).await;
+        // "Parking" falls under 0.3 of the best knowledge hit; the memory
+        // copy of the opening hours is a duplicate
+        assert_eq!(contents(&passages), ["Shop opens at 9am daily", "Refunds take 5 days"]);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_reorders_and_filters() {
+        let config = RagConfig::default();
+        let docs = Fixed("knowledge", vec![("alpha", 3.0), ("beta", 2.0), ("gamma", 1.0)]);
+
+        let passages = RetrievalPipeline::new(&config)
+            .with_reranker(&Reply("3, 1"), "m")
+            .run("q", &[&docs])
+            .await;
+        assert_eq!(contents(&passages), ["gamma", "alpha"]);
+
+        let kept = RetrievalPipeline::new(&config)
+            .with_reranker(&Reply("not sure"), "m")
+            .run("q", &[&docs])
+            .await;
+        assert_eq!(contents(&kept), ["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn test_render_groups_by_source_within_budget() {
+        let passage = |source: &str, label: Option<&str>, content: &str| Passage {
+            source: source.into(),
+            label: label.map(Into::into),
+            content: content.into(),
+            score: 1.0,
+            chunk: None,
+        };
+        let passages = [
+            passage("knowledge", Some("faq.md"), "Open 9-5"),
+            passage("memory", None, "User asked about hours"),
+            passage("knowledge", None, "x".repeat(100).as_str()),
+            passage("knowledge", None, "Closed Sunday"),
+        ];
+        let (messages, _) = render(&passages, 60, false);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].content,
+            "[Knowledge Base]\n1. [faq.md] Open 9-5\n2. Closed Sunday\n[End knowledge]"
+        );
+        assert_eq!(messages[1].content, "[Past conversations]\n1. User asked about hours\n[End past]");
+    }
+
+    #[test]
+    fn test_citations_follow_markers() {
+        let passage = |label: &str, content: &str, chunk: Option<usize>| Passage {
+            source: "knowledge".into(),
+            label: Some(label.into()),
+            content: content.into(),
+            score: 0.5,
+            chunk: chunk.map(|chunk_idx| ChunkRef {
+                doc_name: label.into(),
+                chunk_idx,
+            }),
+        };
+        let passages = [
+            passage("leave.md", "Annual leave is 12 days", Some(0)),
+            passage("notes", "Uncitable note", None),
+            passage("remote.md", "Remote work needs approval", Some(3)),
+        ];
+        let (messages, provided) = render(&passages, 1000, true);
+        assert!(messages[0].content.contains("3. [remote.md] Remote work needs approval\nCite"));
+        assert_eq!(provided.iter().map(|c| c.marker).collect::<Vec<_>>(), [1, 3]);
+
+        let answer = "You get 12 days [1]; remote work needs approval [3, 1]. See [notes] and [2].";
+        let citations = cited(answer, &provided);
+        assert_eq!(citations.len(), 2);
+        assert_eq!((citations[0].doc_name.as_str(), citations[0].chunk_idx), ("leave.md", 0));
+        assert_eq!((citations[1].doc_name.as_str(), citations[1].chunk_idx), ("remote.md", 3));
+        assert!(cited("No markers here.", &provided).is_empty());
+    }
+
+    #[test]
+    fn test_resource_paragraphs_grouped_within_size() {
+        let text = "Returns are free.\n\nShipping takes 3 days.\n\n\n".to_string() + &"x".repeat(30);
+        assert_eq!(paragraphs(&text, 40), ["Returns are free.\n\nShipping takes 3 days.", &"x".repeat(30)]);
+        assert!(paragraphs("  \n\n ", 40).is_empty());
+    }
+}
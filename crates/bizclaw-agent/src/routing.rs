@@ -0,0 +1,232 @@
+//! Intent routing — picks the agent that should answer a message.
+//!
+//! Used for channels that aren't bound to one agent. Keyword rules from
+//! `[routing]` are tried first; if none matches, a cheap classifier model
+//! (when configured) picks among the agents by role and description;
+//! otherwise the orchestrator's default agent answers.
+
+use bizclaw_core::config::{BizClawConfig, RoutingRule};
+use bizclaw_core::traits::Provider;
+use bizclaw_core::traits::provider::GenerateParams;
+use bizclaw_core::types::Message;
+
+/// An agent the router may pick, as `(name, role, description)`.
+pub type Candidate<'a> = (&'a str, &'a str, &'a str);
+
+/// Where a message should go, and why.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Route {
+    pub agent: String,
+    #[serde(flatten)]
+    pub via: RouteVia,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "via", rename_all = "snake_case")]
+pub enum RouteVia {
+    /// A keyword rule matched.
+    Rule { keyword: String },
+    /// The classifier model picked the agent.
+    Classifier,
+    /// Nothing matched; the default agent.
+    Default,
+}
+
+/// Keyword rules plus an optional LLM classifier.
+#[derive(Default)]
+pub struct Router {
+    rules: Vec<RoutingRule>,
+    classifier: Option<(Box<dyn Provider>, String)>,
+}
+
+impl Router {
+    /// Router with keyword `rules` only.
+    pub fn new(rules: Vec<RoutingRule>) -> Self {
+        Self { rules, classifier: None }
+    }
+
+    /// Router for `config.routing`; without `enabled`, one that matches
+    /// nothing. The classifier is left out if its provider can't be created.
+    pub fn from_config(config: &BizClawConfig) -> Self {
+        let routing = &config.routing;
+        if !routing.enabled {
+            return Self::default();
+        }
+        let router = Self::new(routing.rules.clone());
+        let name = routing.classifier_provider.as_str();
+        if name.is_empty() {
+            return router;
+        }
+        let mut classifier_config = config.clone();
+        classifier_config.llm.provider = name.to_string();
+        match bizclaw_providers::create_provider(&classifier_config) {
+            Ok(provider) => router.with_classifier(provider, &routing.classifier_model),
+            Err(e) => {
+                tracing::warn!("Routing classifier '{name}' unavailable, using keyword rules only: {e}");
+                router
+            }
+        }
+    }
+
+    /// Ask `model` on `provider` to pick an agent when no rule matches.
+    pub fn with_classifier(mut self, provider: Box<dyn Provider>, model: &str) -> Self {
+        self.classifier = Some((provider, model.to_string()));
+        self
+    }
+
+    /// Whether the router can pick anything but the default agent.
+    pub fn is_enabled(&self) -> bool {
+        !self.rules.is_empty() || self.classifier.is_some()
+    }
+
+    /// The first rule for one of `agents` with a keyword in `message`.
+    pub fn match_rules(&self, message: &str, agents: &[Candidate<'_>]) -> Option<Route> {
+        let message = message.to_lowercase();
+        self.rules
+            .iter()
+            .filter(|rule| agents.iter().any(|(name, _, _)| *name == rule.agent))
+            .find_map(|rule| {
+                let keyword = rule
+                    .keywords
+                    .iter()
+                    .find(|k| !k.trim().is_empty() && contains_words(&message, &k.trim().to_lowercase()))?;
+                Some(Route {
+                    agent: rule.agent.clone(),
+                    via: RouteVia::Rule { keyword: keyword.clone() },
+                })
+            })
+    }
+
+    /// The agent the classifier picks for `message`, if it names one of
+    /// `agents`.
+    pub async fn classify(&self, message: &str, agents: &[Candidate<'_>]) -> Option<Route> {
+        let (provider, model) = self.classifier.as_ref()?;
+        if agents.len() < 2 {
+            return None;
+        }
+        let listing: String = agents
+            .iter()
+            .map(|(name, role, description)| format!("- {name} ({role}): {description}\n"))
+            .collect();
+        let prompt = format!(
+            "AGENTS:\n{listing}\nMESSAGE: {}\n\n\
+             Which agent should answer this message? Reply with the agent name only, or NONE.",
+            message.chars().take(2000).collect::<String>()
+        );
+        let messages = [Message::system("You route customer messages to the right agent."), Message::user(prompt)];
+        let params = GenerateParams {
+            model: model.clone(),
+            temperature: 0.0,
+            max_tokens: 20,
+            ..Default::default()
+        };
+        let reply = match provider.chat(&messages, &[], &params).await {
+            Ok(r) => r.content.unwrap_or_default(),
+            Err(e) => {
+                tracing::debug!("Routing classification failed: {e}");
+                return None;
+            }
+        };
+        let reply = reply.trim().trim_matches(|c: char| c == '"' || c == '\'' || c == '.' || c == '`');
+        let agent = agents
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(reply))
+            .or_else(|| {
+                // Longest name first, so "sales-lead" wins over "sales"
+                let reply = reply.to_lowercase();
+                let mut named: Vec<_> = agents
+                    .iter()
+                    .filter(|(name, _, _)| contains_words(&reply, &name.to_lowercase()))
+                    .collect();
+                named.sort_by_key(|(name, _, _)| std::cmp::Reverse(name.len()));
+                named.first().copied()
+            })?;
+        Some(Route {
+            agent: agent.0.to_string(),
+            via: RouteVia::Classifier,
+        })
+    }
+}
+
+/// Whether `phrase` occurs in `text` as whole words (both lowercase).
+fn contains_words(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + phrase.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::error::Result;
+    use bizclaw_core::types::{ModelInfo, ProviderResponse, ToolDefinition};
+
+    struct Reply(&'static str);
+
+    #[async_trait]
+    impl Provider for Reply {
+        fn name(&self) -> &str {
+            "reply"
+        }
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            Ok(ProviderResponse::text(self.0))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    const AGENTS: &[Candidate<'static>] = &[
+        ("sales", "sales", "Quotes and pricing"),
+        ("sales-lead", "sales", "Large accounts"),
+        ("support", "support", "Orders and refunds"),
+    ];
+
+    fn rule(agent: &str, keywords: &[&str]) -> RoutingRule {
+        RoutingRule {
+            agent: agent.into(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_keyword_rules() {
+        let router = Router::new(vec![
+            rule("missing", &["price"]),
+            rule("sales", &["price", "báo giá"]),
+            rule("support", &["refund"]),
+        ]);
+        let route = router.match_rules("Cho tôi BÁO GIÁ nhé", AGENTS).unwrap();
+        assert_eq!(route.agent, "sales");
+        assert_eq!(route.via, RouteVia::Rule { keyword: "báo giá".into() });
+        // Rules for agents that don't exist are skipped
+        assert_eq!(router.match_rules("What's the price?", AGENTS).unwrap().agent, "sales");
+        assert_eq!(router.match_rules("I want a refund.", AGENTS).unwrap().agent, "support");
+        // Whole words only
+        assert!(router.match_rules("refunding is slow; prices", AGENTS).is_none());
+        assert!(router.is_enabled() && !Router::default().is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_classifier_picks_a_listed_agent() {
+        let classify = |reply| Router::default().with_classifier(Box::new(Reply(reply)), "cheap");
+        assert_eq!(classify("support").classify("Where is my order?", AGENTS).await.unwrap().agent, "support");
+        let route = classify("Agent: `sales-lead`.").classify("We need 500 seats", AGENTS).await.unwrap();
+        assert_eq!((route.agent.as_str(), route.via), ("sales-lead", RouteVia::Classifier));
+        assert!(classify("NONE").classify("hello", AGENTS).await.is_none());
+        assert!(classify("billing").classify("hello", AGENTS).await.is_none());
+        // Nothing to choose between
+        assert!(classify("sales").classify("hello", &AGENTS[..1]).await.is_none());
+    }
+}
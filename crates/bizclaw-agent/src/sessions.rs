@@ -0,0 +1,137 @@
+//! Session pool — one conversation per chat on a shared agent.
+//!
+//! An agent answering several chats (Telegram users, WhatsApp numbers, the
+//! dashboard) parks each inactive chat's conversation here, so every user
+//! gets their own context while the provider, tools and memory are loaded
+//! once (see [`crate::Agent::switch_session`]). Sessions idle longer than
+//! `memory.session_idle_mins` are evicted, and beyond `memory.max_sessions`
+//! the least recently used go first. An evicted session comes back from
+//! saved history when the agent persists it, and starts fresh otherwise.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use bizclaw_core::types::Message;
+
+/// Session used when a message doesn't name one.
+pub const DEFAULT_SESSION: &str = "default";
+
+/// A parked conversation, without the system prompt.
+struct Parked {
+    messages: Vec<Message>,
+    last_active: Instant,
+}
+
+/// Conversations of an agent's inactive sessions.
+pub struct SessionPool {
+    parked: HashMap<String, Parked>,
+    /// Evict sessions idle this long (`None` = never).
+    idle_ttl: Option<Duration>,
+    /// Most sessions parked at once (0 = unlimited).
+    max_sessions: usize,
+}
+
+impl SessionPool {
+    /// Pool evicting sessions idle `idle_mins` minutes (0 = never) and
+    /// keeping at most `max_sessions` (0 = unlimited).
+    pub fn new(idle_mins: u64, max_sessions: usize) -> Self {
+        Self {
+            parked: HashMap::new(),
+            idle_ttl: (idle_mins > 0).then(|| Duration::from_secs(idle_mins * 60)),
+            max_sessions,
+        }
+    }
+
+    /// Park the conversation of `session_id`, evicting idle sessions and,
+    /// over the limit, the least recently used.
+    pub fn park(&mut self, session_id: &str, messages: Vec<Message>) {
+        self.parked.insert(
+            session_id.to_string(),
+            Parked {
+                messages,
+                last_active: Instant::now(),
+            },
+        );
+        self.evict_idle();
+        while self.max_sessions > 0 && self.parked.len() > self.max_sessions {
+            let Some(oldest) = self
+                .parked
+                .iter()
+                .min_by_key(|(_, p)| p.last_active)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            tracing::debug!("Session '{oldest}' evicted (over {} sessions)", self.max_sessions);
+            self.parked.remove(&oldest);
+        }
+    }
+
+    /// Take the parked conversation of `session_id`, unless it's idle past
+    /// the TTL.
+    pub fn take(&mut self, session_id: &str) -> Option<Vec<Message>> {
+        let parked = self.parked.remove(session_id)?;
+        (!self.is_idle(&parked)).then_some(parked.messages)
+    }
+
+    /// Drop sessions idle past the TTL. Returns how many were dropped.
+    pub fn evict_idle(&mut self) -> usize {
+        let before = self.parked.len();
+        let ttl = self.idle_ttl;
+        self.parked
+            .retain(|_, p| ttl.is_none_or(|ttl| p.last_active.elapsed() < ttl));
+        let evicted = before - self.parked.len();
+        if evicted > 0 {
+            tracing::debug!("{evicted} idle session(s) evicted");
+        }
+        evicted
+    }
+
+    fn is_idle(&self, parked: &Parked) -> bool {
+        self.idle_ttl.is_some_and(|ttl| parked.last_active.elapsed() >= ttl)
+    }
+
+    /// IDs of the parked sessions.
+    pub fn session_ids(&self) -> Vec<&str> {
+        self.parked.keys().map(String::as_str).collect()
+    }
+
+    /// Number of parked sessions.
+    pub fn len(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Whether no session is parked.
+    pub fn is_empty(&self) -> bool {
+        self.parked.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_session_evicted_over_limit() {
+        let mut pool = SessionPool::new(0, 2);
+        pool.park("a", vec![Message::user("from a")]);
+        pool.park("b", vec![Message::user("from b")]);
+        pool.park("c", vec![Message::user("from c")]);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.take("a").is_none());
+        assert_eq!(pool.take("c").unwrap()[0].content, "from c");
+        assert_eq!(pool.session_ids(), ["b"]);
+    }
+
+    #[test]
+    fn test_idle_sessions_evicted() {
+        let mut pool = SessionPool::new(1, 0);
+        pool.park("a", vec![]);
+        pool.parked.get_mut("a").unwrap().last_active -= Duration::from_secs(61);
+        pool.park("b", vec![]);
+        assert_eq!(pool.session_ids(), ["b"]);
+        pool.parked.get_mut("b").unwrap().last_active -= Duration::from_secs(61);
+        assert!(pool.take("b").is_none());
+        assert!(pool.is_empty());
+    }
+}
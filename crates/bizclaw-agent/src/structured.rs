@@ -0,0 +1,41 @@
+//! Structured output — pulling JSON out of model replies and checking it
+//! against a JSON schema (see [`crate::Agent::process_structured`]).
+
+pub use bizclaw_core::schema::validate;
+use serde_json::Value;
+
+/// The JSON value in a model reply: the whole reply, a fenced code block,
+/// or the span from the first `{`/`[` to the last matching bracket.
+pub fn extract_json(reply: &str) -> Option<Value> {
+    let text = reply.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    if let Some(start) = text.find("```") {
+        let fenced = &text[start + 3..];
+        let body = fenced.find('\n').map_or(fenced, |nl| &fenced[nl + 1..]);
+        if let Some(end) = body.find("```")
+            && let Ok(value) = serde_json::from_str(body[..end].trim())
+        {
+            return Some(value);
+        }
+    }
+    let start = text.find(['{', '['])?;
+    let close = if text[start..].starts_with('{') { '}' } else { ']' };
+    let end = text.rfind(close)?;
+    serde_json::from_str(text.get(start..=end)?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_json_from_prose_and_fences() {
+        assert_eq!(extract_json(r#"{"a": 1}"#), Some(json!({"a": 1})));
+        assert_eq!(extract_json("Sure!\n```json\n[1, 2]\n```\nDone."), Some(json!([1, 2])));
+        assert_eq!(extract_json(r#"Here you go: {"a": {"b": true}} — enjoy"#), Some(json!({"a": {"b": true}})));
+        assert_eq!(extract_json("no json here"), None);
+    }
+}
@@ -0,0 +1,78 @@
+//! Long-context decode attention: per-head gather + online softmax vs. the
+//! tiled kernel reading the KV cache in place.
+//!
+//!     cargo bench -p bizclaw-brain --bench attention
+//!
+//! One layer of a LLaMA-7B-like shape (32 query heads, 8 KV heads, 128 dims).
+
+use bizclaw_brain::attention::{attention, attention_fused};
+use std::time::{Duration, Instant};
+
+const N_HEADS: usize = 32;
+const N_KV_HEADS: usize = 8;
+const HEAD_DIM: usize = 128;
+
+fn best_of(iters: usize, mut f: impl FnMut()) -> Duration {
+    f();
+    (0..iters)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let kv_dim = N_KV_HEADS * HEAD_DIM;
+    let group = N_HEADS / N_KV_HEADS;
+    for seq_len in [1024, 4096, 8192] {
+        let keys: Vec<f32> = (0..seq_len * kv_dim).map(|i| (i as f32 * 0.37).sin()).collect();
+        let values: Vec<f32> = (0..seq_len * kv_dim).map(|i| (i as f32 * 0.11).cos()).collect();
+        let q: Vec<f32> = (0..N_HEADS * HEAD_DIM).map(|i| (i as f32 * 1.3).sin()).collect();
+        let mut out = vec![0.0f32; N_HEADS * HEAD_DIM];
+
+        // Previous decode path: copy each head's K/V out of the cache, then attend
+        let gathered = best_of(5, || {
+            for h in 0..N_HEADS {
+                let kv_h = h / group;
+                let mut head_keys = vec![0.0f32; seq_len * HEAD_DIM];
+                let mut head_values = vec![0.0f32; seq_len * HEAD_DIM];
+                for t in 0..seq_len {
+                    let start = t * kv_dim + kv_h * HEAD_DIM;
+                    head_keys[t * HEAD_DIM..(t + 1) * HEAD_DIM]
+                        .copy_from_slice(&keys[start..start + HEAD_DIM]);
+                    head_values[t * HEAD_DIM..(t + 1) * HEAD_DIM]
+                        .copy_from_slice(&values[start..start + HEAD_DIM]);
+                }
+                let q_h = &q[h * HEAD_DIM..(h + 1) * HEAD_DIM];
+                let out_h = &mut out[h * HEAD_DIM..(h + 1) * HEAD_DIM];
+                attention(out_h, q_h, &head_keys, &head_values, seq_len, HEAD_DIM);
+            }
+        });
+
+        let fused = best_of(5, || {
+            for kv_h in 0..N_KV_HEADS {
+                let heads = kv_h * group * HEAD_DIM..(kv_h + 1) * group * HEAD_DIM;
+                attention_fused::<f32>(
+                    &mut out[heads.clone()],
+                    &q[heads],
+                    &keys,
+                    &values,
+                    seq_len,
+                    HEAD_DIM,
+                    kv_dim,
+                    kv_h * HEAD_DIM,
+                );
+            }
+        });
+
+        println!(
+            "seq {seq_len:>5}: gathered {:>8.2} ms  fused {:>8.2} ms  x{:.2}",
+            gathered.as_secs_f64() * 1e3,
+            fused.as_secs_f64() * 1e3,
+            gathered.as_secs_f64() / fused.as_secs_f64()
+        );
+    }
+}
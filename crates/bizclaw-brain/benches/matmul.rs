@@ -0,0 +1,96 @@
+//! Row-parallel matmul throughput, serial vs. the brain thread pool.
+//!
+//!     cargo bench -p bizclaw-brain --bench matmul [-- <threads>]
+//!
+//! Shapes are TinyLlama-1.1B's attention, FFN and LM-head projections.
+
+use bizclaw_brain::gguf::GgmlType;
+use bizclaw_brain::{quant, simd, thread_pool};
+use std::time::{Duration, Instant};
+
+const SHAPES: [(&str, usize, usize); 3] = [
+    ("attn 2048x2048", 2048, 2048),
+    ("ffn 5632x2048", 5632, 2048),
+    ("lm_head 32000x2048", 32000, 2048),
+];
+
+/// Best of `iters` runs (least disturbed by other load).
+fn best_of(iters: usize, mut f: impl FnMut()) -> Duration {
+    f(); // warm caches and the pool
+    (0..iters)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Deterministic values in [-0.5, 0.5).
+fn values(n: usize, mut seed: u32) -> Vec<f32> {
+    (0..n)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 - 0.5
+        })
+        .collect()
+}
+
+/// Q4_0 rows: f16 scale + 16 bytes of nibbles per 32 weights.
+fn q4_0_matrix(rows: usize, cols: usize) -> Vec<u8> {
+    let scale = half::f16::from_f32(0.01).to_le_bytes();
+    let nibbles = values(rows * cols / 2, 7);
+    let mut data = Vec::with_capacity(rows * cols / 32 * 18);
+    for block in nibbles.chunks(16) {
+        data.extend(scale);
+        data.extend(block.iter().map(|v| ((v + 0.5) * 255.0) as u8));
+    }
+    data
+}
+
+fn report(name: &str, kind: &str, rows: usize, cols: usize, serial: Duration, parallel: Duration) {
+    let gflops = |d: Duration| 2.0 * (rows * cols) as f64 / d.as_secs_f64() / 1e9;
+    println!(
+        "{name:<20} {kind:<4} serial {:>8.2} ms ({:>5.1} GFLOP/s)  parallel {:>8.2} ms ({:>5.1} GFLOP/s)  x{:.2}",
+        serial.as_secs_f64() * 1e3,
+        gflops(serial),
+        parallel.as_secs_f64() * 1e3,
+        gflops(parallel),
+        serial.as_secs_f64() / parallel.as_secs_f64(),
+    );
+}
+
+fn main() {
+    let threads = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    println!("matmul: serial vs {threads} threads");
+
+    for (name, rows, cols) in SHAPES {
+        let x = values(cols, 3);
+        let mut out = vec![0.0f32; rows];
+
+        let mat = values(rows * cols, 11);
+        let run = |out: &mut [f32]| simd::matmul_simd(out, &mat, &x, rows, cols);
+        thread_pool::configure(1).unwrap();
+        let serial = best_of(5, || run(&mut out));
+        thread_pool::configure(threads).unwrap();
+        let parallel = best_of(5, || run(&mut out));
+        report(name, "f32", rows, cols, serial, parallel);
+        drop(mat);
+
+        let q = q4_0_matrix(rows, cols);
+        let run = |out: &mut [f32]| {
+            quant::matmul_quantized(out, &q, &x, rows, cols, GgmlType::Q4_0).unwrap()
+        };
+        thread_pool::configure(1).unwrap();
+        let serial = best_of(5, || run(&mut out));
+        thread_pool::configure(threads).unwrap();
+        let parallel = best_of(5, || run(&mut out));
+        report(name, "q4_0", rows, cols, serial, parallel);
+    }
+}
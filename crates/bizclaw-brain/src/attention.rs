@@ -1,7 +1,10 @@
 //! Flash Attention — online softmax attention computation.
 //!
 //! Computes attention scores incrementally without materializing
-//! the full QK^T matrix, saving O(seq_len) memory.
+//! the full QK^T matrix, saving O(seq_len) memory. [`attention_fused`] is
+//! the tiled variant used for decode, reading the KV cache in place.
+
+use crate::dtype::Activation;
 
 /// Compute single-head attention output for a single query position.
 /// Uses online softmax (flash attention) — no intermediate score buffer.
@@ -11,11 +14,13 @@
 /// value_cache: all value vectors [seq_len x head_dim]
 /// seq_len: current sequence length (how many KV entries are valid)
 /// head_dim: dimension per head
-pub fn attention(
+///
+/// Keys/values may be stored as f32 or bf16; accumulation is always f32.
+pub fn attention<A: Activation>(
     output: &mut [f32],
     q: &[f32],
-    key_cache: &[f32],
-    value_cache: &[f32],
+    key_cache: &[A],
+    value_cache: &[A],
     seq_len: usize,
     head_dim: usize,
 ) {
@@ -46,7 +51,7 @@ pub fn attention(
         // Compute score = q · k / sqrt(d)
         let mut dot = 0.0f32;
         for i in 0..head_dim {
-            dot += q[i] * k[i];
+            dot += q[i] * k[i].to_f32();
         }
         let score = dot * scale;
 
@@ -63,7 +68,7 @@ pub fn attention(
         // Rescale existing output and add new value contribution
         let v_offset = t * head_dim;
         for i in 0..head_dim {
-            output[i] = output[i] * scale_old + exp_score * value_cache[v_offset + i];
+            output[i] = output[i] * scale_old + exp_score * value_cache[v_offset + i].to_f32();
         }
 
         running_max = new_max;
@@ -78,6 +83,160 @@ pub fn attention(
     }
 }
 
+/// KV positions scored per tile of [`attention_fused`].
+pub const ATTN_BLOCK: usize = 64;
+
+/// Tiled online-softmax attention for the `n_q` query heads that share one
+/// KV head (a GQA group), reading keys/values in place from the cache.
+///
+/// Streams the cache in tiles of [`ATTN_BLOCK`] positions: each tile's
+/// scores go to a small buffer, the running max/normalizer and output are
+/// rescaled once per tile instead of once per position, and every K/V row
+/// is read once for the whole group rather than once per query head.
+///
+/// q / output: [n_q x head_dim]
+/// key_cache / value_cache: `seq_len` rows of `kv_stride` floats; this KV
+/// head's vector starts at `kv_offset` within each row
+///
+/// K/V are rounded through `A` as they are read, so results match the
+/// gathered [`attention`] path in every compute dtype.
+pub fn attention_fused<A: Activation>(
+    output: &mut [f32],
+    q: &[f32],
+    key_cache: &[f32],
+    value_cache: &[f32],
+    seq_len: usize,
+    head_dim: usize,
+    kv_stride: usize,
+    kv_offset: usize,
+) {
+    debug_assert_eq!(q.len(), output.len());
+    debug_assert!(head_dim > 0 && q.len().is_multiple_of(head_dim));
+    output.iter_mut().for_each(|v| *v = 0.0);
+    if seq_len == 0 {
+        return;
+    }
+
+    let n_q = q.len() / head_dim;
+    let scale = 1.0 / (head_dim as f32).sqrt();
+    let mut running_max = vec![f32::NEG_INFINITY; n_q];
+    let mut running_sum = vec![0.0f32; n_q];
+    let mut scores = vec![0.0f32; n_q * ATTN_BLOCK];
+    let mut row = vec![0.0f32; head_dim];
+    let load = |src: &[f32], row: &mut [f32], t: usize| {
+        let start = t * kv_stride + kv_offset;
+        for (r, &v) in row.iter_mut().zip(&src[start..start + head_dim]) {
+            *r = A::from_f32(v).to_f32();
+        }
+    };
+
+    for block_start in (0..seq_len).step_by(ATTN_BLOCK) {
+        let block = ATTN_BLOCK.min(seq_len - block_start);
+
+        // Scores for the tile, all heads of the group per key row
+        for j in 0..block {
+            load(key_cache, &mut row, block_start + j);
+            for h in 0..n_q {
+                let q_h = &q[h * head_dim..(h + 1) * head_dim];
+                scores[h * ATTN_BLOCK + j] = crate::simd::dot_product_simd(q_h, &row) * scale;
+            }
+        }
+
+        // One rescale per head per tile, then exponentiate in place
+        for h in 0..n_q {
+            let s = &mut scores[h * ATTN_BLOCK..h * ATTN_BLOCK + block];
+            let block_max = s.iter().fold(f32::NEG_INFINITY, |m, &v| m.max(v));
+            let new_max = running_max[h].max(block_max);
+            let correction = (running_max[h] - new_max).exp();
+            running_sum[h] *= correction;
+            output[h * head_dim..(h + 1) * head_dim]
+                .iter_mut()
+                .for_each(|o| *o *= correction);
+            running_sum[h] += crate::simd::exp_shift_sum(s, new_max);
+            running_max[h] = new_max;
+        }
+
+        // Weighted values, each value row read once for the group
+        for j in 0..block {
+            load(value_cache, &mut row, block_start + j);
+            for h in 0..n_q {
+                let p = scores[h * ATTN_BLOCK + j];
+                let out = &mut output[h * head_dim..(h + 1) * head_dim];
+                for (o, &v) in out.iter_mut().zip(&row) {
+                    *o += p * v;
+                }
+            }
+        }
+    }
+
+    for h in 0..n_q {
+        if running_sum[h] > 0.0 {
+            let inv_sum = 1.0 / running_sum[h];
+            output[h * head_dim..(h + 1) * head_dim]
+                .iter_mut()
+                .for_each(|o| *o *= inv_sum);
+        }
+    }
+}
+
+/// Two-pass attention with a log-sum-exp softmax over materialized scores.
+///
+/// Slower than [`attention`] (needs a `seq_len` score buffer) but recovers from
+/// NaN/Inf scores instead of propagating them into the residual stream. With
+/// `f64_acc`, dot products and the softmax are computed in f64, so scores that
+/// overflow f32 on long or very peaked contexts stay finite.
+/// Returns `false` if non-finite scores had to be recovered from.
+pub fn attention_stable<A: Activation>(
+    output: &mut [f32],
+    q: &[f32],
+    key_cache: &[A],
+    value_cache: &[A],
+    seq_len: usize,
+    head_dim: usize,
+    f64_acc: bool,
+) -> bool {
+    debug_assert_eq!(q.len(), head_dim);
+    debug_assert_eq!(output.len(), head_dim);
+    output.iter_mut().for_each(|v| *v = 0.0);
+    if seq_len == 0 {
+        return true;
+    }
+
+    let keys = |t: usize| &key_cache[t * head_dim..(t + 1) * head_dim];
+    let (weights, clean): (Vec<f32>, bool) = if f64_acc {
+        let scale = 1.0 / (head_dim as f64).sqrt();
+        let mut scores: Vec<f64> = (0..seq_len)
+            .map(|t| {
+                let k = keys(t);
+                (0..head_dim).map(|i| q[i] as f64 * k[i].to_f32() as f64).sum::<f64>() * scale
+            })
+            .collect();
+        let clean = crate::tensor::stable_softmax_f64(&mut scores);
+        (scores.into_iter().map(|w| w as f32).collect(), clean)
+    } else {
+        let scale = 1.0 / (head_dim as f32).sqrt();
+        let mut scores: Vec<f32> = (0..seq_len)
+            .map(|t| {
+                let k = keys(t);
+                (0..head_dim).map(|i| q[i] * k[i].to_f32()).sum::<f32>() * scale
+            })
+            .collect();
+        let clean = crate::tensor::stable_softmax(&mut scores);
+        (scores, clean)
+    };
+
+    for (t, &w) in weights.iter().enumerate() {
+        if w == 0.0 {
+            continue;
+        }
+        let v = &value_cache[t * head_dim..(t + 1) * head_dim];
+        for i in 0..head_dim {
+            output[i] += w * v[i].to_f32();
+        }
+    }
+    clean
+}
+
 /// Multi-head attention: apply attention for all heads in parallel.
 pub fn multi_head_attention(
     output: &mut [f32],
@@ -221,10 +380,98 @@ mod tests {
         let q = vec![1.0, 0.0, 0.0, 0.0];
         let mut output = vec![1.0; head_dim];
 
-        attention(&mut output, &q, &[], &[], 0, head_dim);
+        attention::<f32>(&mut output, &q, &[], &[], 0, head_dim);
 
         for v in &output {
             assert_eq!(*v, 0.0);
         }
     }
+
+    #[test]
+    fn test_attention_stable_large_scores() {
+        let head_dim = 4;
+        // q·k = 1e40 overflows f32 → naive online softmax yields NaN (inf - inf).
+        let q = vec![1e20f32, 0.0, 0.0, 0.0];
+        let key_cache = vec![1e20f32, 0.0, 0.0, 0.0, 1e10, 0.0, 0.0, 0.0];
+        let value_cache = vec![1.0f32, 2.0, 3.0, 4.0, -1.0, -2.0, -3.0, -4.0];
+
+        let mut naive = vec![0.0; head_dim];
+        attention(&mut naive, &q, &key_cache, &value_cache, 2, head_dim);
+        assert!(naive.iter().any(|v| !v.is_finite()));
+
+        // f32 stable: the +Inf score is recovered, all weight goes to that key.
+        let mut out = vec![0.0; head_dim];
+        assert!(!attention_stable(&mut out, &q, &key_cache, &value_cache, 2, head_dim, false));
+        assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0]);
+
+        // f64: scores stay finite, first key dominates.
+        let mut out = vec![0.0; head_dim];
+        assert!(attention_stable(&mut out, &q, &key_cache, &value_cache, 2, head_dim, true));
+        assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_attention_stable_matches_online() {
+        let head_dim = 4;
+        let q = vec![1.0, 0.5, -0.3, 0.2];
+        let key_cache = vec![1.0, 0.0, 0.2, 0.0, 0.0, 1.0, 0.0, 0.3, 0.5, 0.5, -0.5, 0.1];
+        let value_cache = vec![1.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 0.2, 0.0, 1.0, 0.0];
+        let mut online = vec![0.0; head_dim];
+        attention(&mut online, &q, &key_cache, &value_cache, 3, head_dim);
+        for f64_acc in [false, true] {
+            let mut out = vec![0.0; head_dim];
+            assert!(attention_stable(&mut out, &q, &key_cache, &value_cache, 3, head_dim, f64_acc));
+            for (a, b) in online.iter().zip(&out) {
+                assert!((a - b).abs() < 1e-5);
+            }
+        }
+    }
+
+    /// Gather one KV head into contiguous `A` rows, as the unfused path does.
+    fn gather<A: Activation>(cache: &[f32], seq_len: usize, head_dim: usize, stride: usize, offset: usize) -> Vec<A> {
+        (0..seq_len)
+            .flat_map(|t| cache[t * stride + offset..t * stride + offset + head_dim].iter())
+            .map(|&v| A::from_f32(v))
+            .collect()
+    }
+
+    fn check_fused_matches_gathered<A: Activation>(tol: f32) {
+        let (head_dim, n_kv, group) = (8, 2, 3);
+        let stride = n_kv * head_dim;
+        // Not a multiple of ATTN_BLOCK: exercises the partial last tile
+        let seq_len = 2 * ATTN_BLOCK + 21;
+        let wave = |n: usize, k: f32| (0..n).map(|i| (i as f32 * k).sin()).collect::<Vec<f32>>();
+        let keys = wave(seq_len * stride, 0.37);
+        let values = wave(seq_len * stride, 0.11);
+        let q = wave(group * head_dim, 1.3).iter().map(|v| v * 3.0).collect::<Vec<f32>>();
+
+        for kv_h in 0..n_kv {
+            let mut fused = vec![0.0f32; group * head_dim];
+            attention_fused::<A>(&mut fused, &q, &keys, &values, seq_len, head_dim, stride, kv_h * head_dim);
+
+            let head_keys = gather::<A>(&keys, seq_len, head_dim, stride, kv_h * head_dim);
+            let head_values = gather::<A>(&values, seq_len, head_dim, stride, kv_h * head_dim);
+            for h in 0..group {
+                let mut expected = vec![0.0f32; head_dim];
+                let q_h = &q[h * head_dim..(h + 1) * head_dim];
+                attention(&mut expected, q_h, &head_keys, &head_values, seq_len, head_dim);
+                for (a, b) in fused[h * head_dim..(h + 1) * head_dim].iter().zip(&expected) {
+                    assert!((a - b).abs() < tol, "kv head {kv_h}, q head {h}: {a} vs {b}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fused_attention_matches_gathered() {
+        check_fused_matches_gathered::<f32>(1e-5);
+        check_fused_matches_gathered::<half::bf16>(1e-5);
+    }
+
+    #[test]
+    fn test_fused_attention_empty() {
+        let mut output = vec![1.0f32; 8];
+        attention_fused::<f32>(&mut output, &[1.0; 8], &[], &[], 0, 4, 4, 0);
+        assert_eq!(output, vec![0.0; 8]);
+    }
 }
@@ -0,0 +1,106 @@
+//! GGUF model catalog and downloads.
+//!
+//! Used by `bizclaw brain download` and the gateway's `model_download` job.
+//! Downloads go to `<file>.part` and are renamed when complete, so an
+//! interrupted download never looks like an installed model.
+
+use bizclaw_core::error::{BizClawError, Result};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// A model that can be downloaded by name.
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogModel {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub url: &'static str,
+    pub filename: &'static str,
+    /// Approximate download size.
+    pub size_mb: u32,
+}
+
+/// Models known by name.
+pub const CATALOG: &[CatalogModel] = &[
+    CatalogModel {
+        name: "tinyllama-1.1b",
+        aliases: &["tinyllama"],
+        url: "https://huggingface.co/TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF/resolve/main/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf",
+        filename: "tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf",
+        size_mb: 638,
+    },
+    CatalogModel {
+        name: "phi-2",
+        aliases: &[],
+        url: "https://huggingface.co/TheBloke/phi-2-GGUF/resolve/main/phi-2.Q4_K_M.gguf",
+        filename: "phi-2.Q4_K_M.gguf",
+        size_mb: 1600,
+    },
+    CatalogModel {
+        name: "llama-3.2-1b",
+        aliases: &["llama3.2"],
+        url: "https://huggingface.co/bartowski/Llama-3.2-1B-Instruct-GGUF/resolve/main/Llama-3.2-1B-Instruct-Q4_K_M.gguf",
+        filename: "Llama-3.2-1B-Instruct-Q4_K_M.gguf",
+        size_mb: 750,
+    },
+];
+
+/// URL and file name for a catalog model name or alias, or a direct URL to
+/// a `.gguf` file (named after its last path segment).
+pub fn resolve(model: &str) -> Option<(String, String)> {
+    if let Some(m) = CATALOG.iter().find(|m| m.name == model || m.aliases.contains(&model)) {
+        return Some((m.url.to_string(), m.filename.to_string()));
+    }
+    if model.starts_with("http://") || model.starts_with("https://") {
+        let last = model.split(['?', '#']).next().unwrap_or(model).rsplit('/').next().unwrap_or("");
+        let filename = if last.ends_with(".gguf") { last.to_string() } else { "custom-model.gguf".to_string() };
+        return Some((model.to_string(), filename));
+    }
+    None
+}
+
+/// Download `url` to `dest`, calling `on_progress(downloaded, total)` after
+/// each chunk. Returns the bytes written.
+pub async fn download(url: &str, dest: &Path, on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send)) -> Result<u64> {
+    let http = |e: reqwest::Error| BizClawError::Http(format!("Download failed: {e}"));
+    let mut response = reqwest::Client::new()
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(http)?;
+    let total = response.content_length();
+
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let part = dest.with_extension("gguf.part");
+    let mut file = tokio::fs::File::create(&part).await?;
+    let mut downloaded: u64 = 0;
+    while let Some(chunk) = response.chunk().await.map_err(http)? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+    file.flush().await?;
+    drop(file);
+    tokio::fs::rename(&part, dest).await?;
+    Ok(downloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let (url, file) = resolve("tinyllama").unwrap();
+        assert!(url.ends_with(&file));
+        assert_eq!(resolve("phi-2").unwrap().1, "phi-2.Q4_K_M.gguf");
+        assert_eq!(
+            resolve("https://example.com/models/qwen2-0.5b.Q4_K_M.gguf?download=true").unwrap().1,
+            "qwen2-0.5b.Q4_K_M.gguf"
+        );
+        assert_eq!(resolve("https://example.com/model").unwrap().1, "custom-model.gguf");
+        assert!(resolve("gpt-17").is_none());
+    }
+}
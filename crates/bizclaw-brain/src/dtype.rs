@@ -0,0 +1,92 @@
+//! Activation storage types for the forward pass.
+//!
+//! Activations can be kept in `f32` (default) or `bf16`. bf16 keeps the f32
+//! exponent range with an 8-bit mantissa, so storing the residual stream and
+//! attention gather buffers in it halves their memory while every dot product
+//! still accumulates in f32.
+//!
+//! Memory per forward step (TinyLlama 1.1B: dim 2048, 32 heads × 64, ctx 2048):
+//! the per-head K/V gather buffers are `2 × seq_len × head_dim` elements —
+//! 1 MiB in f32 vs 512 KiB in bf16 at full context — and the residual stream
+//! drops from 8 KiB to 4 KiB.
+
+pub use bizclaw_core::config::ComputeDtype;
+use half::bf16;
+
+/// A scalar type activations can be stored in. Arithmetic is always done in f32.
+pub trait Activation: Copy + Default + Send + Sync + 'static {
+    fn from_f32(v: f32) -> Self;
+    fn to_f32(self) -> f32;
+}
+
+impl Activation for f32 {
+    #[inline]
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl Activation for bf16 {
+    #[inline]
+    fn from_f32(v: f32) -> Self {
+        bf16::from_f32(v)
+    }
+    #[inline]
+    fn to_f32(self) -> f32 {
+        bf16::to_f32(self)
+    }
+}
+
+/// Convert stored activations into an f32 buffer.
+#[inline]
+pub fn load<A: Activation>(src: &[A], dst: &mut [f32]) {
+    debug_assert_eq!(src.len(), dst.len());
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d = s.to_f32();
+    }
+}
+
+/// Store f32 values into an activation buffer (rounding for bf16).
+#[inline]
+pub fn store<A: Activation>(src: &[f32], dst: &mut [A]) {
+    debug_assert_eq!(src.len(), dst.len());
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d = A::from_f32(s);
+    }
+}
+
+/// Bytes per stored activation element.
+pub fn element_size(dtype: ComputeDtype) -> usize {
+    match dtype {
+        ComputeDtype::F32 => std::mem::size_of::<f32>(),
+        ComputeDtype::Bf16 => std::mem::size_of::<bf16>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bf16_round_trip_precision() {
+        let values = [0.0f32, 1.0, -2.5, 2.71, 1e-3, -65504.0, 1e20];
+        let mut stored = [bf16::ZERO; 7];
+        store(&values, &mut stored);
+        let mut back = [0.0f32; 7];
+        load(&stored, &mut back);
+        for (v, b) in values.iter().zip(&back) {
+            // 8-bit mantissa → relative error ≤ 2^-8
+            assert!((v - b).abs() <= v.abs() / 256.0, "{v} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_element_size() {
+        assert_eq!(element_size(ComputeDtype::F32), 4);
+        assert_eq!(element_size(ComputeDtype::Bf16), 2);
+    }
+}
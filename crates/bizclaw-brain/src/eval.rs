@@ -0,0 +1,73 @@
+//! Perplexity evaluation over a text corpus.
+//!
+//! Mirrors llama.cpp's `perplexity` tool so the numbers are comparable: the
+//! tokenized text is cut into windows of `n_ctx` tokens (the remainder is
+//! dropped), each window starts with BOS, and only the second half of each
+//! window is scored, so every scored token sees at least `n_ctx / 2` tokens
+//! of context.
+
+/// Result of [`crate::BrainEngine::evaluate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evaluation {
+    /// Window size the text was scored with.
+    pub n_ctx: usize,
+    /// Windows evaluated.
+    pub chunks: usize,
+    /// Tokens scored across all windows.
+    pub tokens: usize,
+    /// Mean negative log-likelihood per scored token (nats).
+    pub nll: f64,
+}
+
+impl Evaluation {
+    /// exp(mean NLL).
+    pub fn perplexity(&self) -> f64 {
+        self.nll.exp()
+    }
+}
+
+/// Positions of a window whose logits are scored: position `j` predicts
+/// token `j + 1`, starting halfway through the window.
+pub fn scored_positions(n_ctx: usize) -> std::ops::Range<usize> {
+    n_ctx / 2..n_ctx.saturating_sub(1)
+}
+
+/// Negative log-likelihood of `target` under `logits`, via a log-sum-exp
+/// in f64.
+pub fn token_nll(logits: &[f32], target: u32) -> f64 {
+    let max = logits.iter().fold(f32::NEG_INFINITY, |m, &v| m.max(v)) as f64;
+    let sum: f64 = logits.iter().map(|&v| (v as f64 - max).exp()).sum();
+    max + sum.ln() - logits[target as usize] as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_nll_matches_softmax() {
+        let logits = [1.0f32, 2.0, 3.0, 0.5];
+        let sum: f64 = logits.iter().map(|&v| (v as f64).exp()).sum();
+        for target in 0..4 {
+            let p = (logits[target] as f64).exp() / sum;
+            assert!((token_nll(&logits, target as u32) + p.ln()).abs() < 1e-12);
+        }
+        // Uniform logits: perplexity equals the vocabulary size
+        let uniform = Evaluation {
+            n_ctx: 8,
+            chunks: 1,
+            tokens: 3,
+            nll: token_nll(&[7.0; 50], 3),
+        };
+        assert!((uniform.perplexity() - 50.0).abs() < 1e-9);
+        // Large logits stay finite
+        assert!(token_nll(&[1e4, 0.0], 1).is_finite());
+    }
+
+    #[test]
+    fn test_scores_second_half_of_window() {
+        assert_eq!(scored_positions(512), 256..511);
+        assert_eq!(scored_positions(8).len(), 3);
+        assert!(scored_positions(1).is_empty());
+    }
+}
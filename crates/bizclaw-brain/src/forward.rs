@@ -1,13 +1,26 @@
-//! LLaMA transformer forward pass.
+//! Transformer forward pass for LLaMA-family models.
 //!
-//! Implements the complete LLaMA-2/3 transformer architecture:
+//! Implements the LLaMA-2/3 transformer architecture:
 //! Embedding → N × (RMSNorm → Attention → RMSNorm → FFN) → RMSNorm → LM Head
 //!
+//! plus the per-architecture variations described by
+//! [`crate::model::Architecture`]: Q/K/V biases (Qwen2), fused QKV and
+//! gate/up projections (Phi-3), scaled embeddings and GeGLU (Gemma), RoPE
+//! pairing, and sliding-window attention (Mistral).
+//!
 //! Reads weights from mmap, dequantizes on-the-fly, computes the forward
 //! pass, and produces logits for the next token.
 
+use crate::dtype::{self, Activation, ComputeDtype};
+use crate::gguf::GgmlType;
+use crate::lora::{LoraDelta, LoraSet};
+use crate::model::{FfnActivation, Pooling};
+use crate::SoftmaxMode;
+use crate::tensor::{MatmulOffload, OffloadId};
 use crate::{kv_cache::KvCache, mmap::MmapModel, model::ModelParams, quant, rope, tensor};
 use bizclaw_core::error::{BizClawError, Result};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Transformer weights — indices into the GGUF tensor list.
 pub struct TransformerWeights {
@@ -17,8 +30,23 @@ pub struct TransformerWeights {
     pub output_norm: Option<usize>,
     // LM head (output projection)
     pub output: Option<usize>,
+    // No `output.weight`: the LM head reuses the token embedding matrix
+    pub tied_embeddings: bool,
     // Per-layer weight indices
     pub layers: Vec<LayerWeights>,
+    // LoRA deltas applied on top of the base weights
+    pub lora: LoraSet,
+    // Projections uploaded to a GPU (see [`offload_layers`])
+    pub offload: Option<OffloadedWeights>,
+}
+
+/// Layer projections resident on an offload device.
+pub struct OffloadedWeights {
+    pub device: Arc<dyn MatmulOffload>,
+    /// Base tensor index → weight on the device.
+    pub tensors: HashMap<usize, OffloadId>,
+    /// Number of (trailing) layers offloaded.
+    pub n_layers: usize,
 }
 
 /// Weights for a single transformer layer.
@@ -27,10 +55,14 @@ pub struct LayerWeights {
     pub attn_q: Option<usize>,
     pub attn_k: Option<usize>,
     pub attn_v: Option<usize>,
+    pub attn_qkv: Option<usize>, // fused [q; k; v] (Phi-3)
+    pub attn_q_bias: Option<usize>,
+    pub attn_k_bias: Option<usize>,
+    pub attn_v_bias: Option<usize>,
     pub attn_output: Option<usize>,
     pub ffn_norm: Option<usize>,
-    pub ffn_gate: Option<usize>, // gate_proj (SiLU activation)
-    pub ffn_up: Option<usize>,   // up_proj
+    pub ffn_gate: Option<usize>, // gate_proj; absent when fused into ffn_up
+    pub ffn_up: Option<usize>,   // up_proj, or [gate; up] (Phi-3)
     pub ffn_down: Option<usize>, // down_proj
 }
 
@@ -48,6 +80,10 @@ impl TransformerWeights {
                 attn_q: find(&format!("blk.{l}.attn_q.weight")),
                 attn_k: find(&format!("blk.{l}.attn_k.weight")),
                 attn_v: find(&format!("blk.{l}.attn_v.weight")),
+                attn_qkv: find(&format!("blk.{l}.attn_qkv.weight")),
+                attn_q_bias: find(&format!("blk.{l}.attn_q.bias")),
+                attn_k_bias: find(&format!("blk.{l}.attn_k.bias")),
+                attn_v_bias: find(&format!("blk.{l}.attn_v.bias")),
                 attn_output: find(&format!("blk.{l}.attn_output.weight")),
                 ffn_norm: find(&format!("blk.{l}.ffn_norm.weight")),
                 ffn_gate: find(&format!("blk.{l}.ffn_gate.weight")),
@@ -56,18 +92,82 @@ impl TransformerWeights {
             });
         }
 
+        let token_embd = find("token_embd.weight");
+        let output = find("output.weight");
         Self {
-            token_embd: find("token_embd.weight"),
+            token_embd,
             output_norm: find("output_norm.weight"),
-            output: find("output.weight"),
+            output,
+            tied_embeddings: output.is_none() && token_embd.is_some(),
             layers,
+            lora: LoraSet::default(),
+            offload: None,
+        }
+    }
+
+    /// LM head tensor. For tied embeddings this is `token_embd.weight`: GGUF
+    /// stores it as [vocab × dim] rows, so `logits = E · x` uses it as-is.
+    pub fn lm_head(&self) -> Option<usize> {
+        if self.tied_embeddings {
+            self.token_embd
+        } else {
+            self.output
+        }
+    }
+}
+
+/// Upload the attention and FFN projections of the last `n_layers` layers
+/// to `device` (llama.cpp's `n_gpu_layers`); their matmuls then run there
+/// while norms, RoPE, attention and the LM head stay on the CPU. Returns the
+/// number of layers offloaded.
+pub fn offload_layers(
+    model: &MmapModel,
+    weights: &mut TransformerWeights,
+    params: &ModelParams,
+    device: Arc<dyn MatmulOffload>,
+    n_layers: usize,
+) -> Result<usize> {
+    let dim = params.dim as usize;
+    let hidden_dim = params.hidden_dim as usize;
+    let q_dim = params.q_dim() as usize;
+    let kv_dim = params.kv_dim() as usize;
+    let first = weights.layers.len().saturating_sub(n_layers);
+
+    let mut tensors = HashMap::new();
+    for layer in &weights.layers[first..] {
+        let mut mats = vec![(layer.attn_output, dim, q_dim), (layer.ffn_down, dim, hidden_dim)];
+        if layer.attn_q.is_none() {
+            mats.push((layer.attn_qkv, q_dim + 2 * kv_dim, dim));
+        } else {
+            mats.extend([(layer.attn_q, q_dim, dim), (layer.attn_k, kv_dim, dim), (layer.attn_v, kv_dim, dim)]);
+        }
+        if layer.ffn_gate.is_some() {
+            mats.extend([(layer.ffn_gate, hidden_dim, dim), (layer.ffn_up, hidden_dim, dim)]);
+        } else {
+            mats.push((layer.ffn_up, 2 * hidden_dim, dim));
+        }
+        for (idx, rows, cols) in mats {
+            if let Some(idx) = idx {
+                let weight = dequant_weight(model, idx, rows * cols)?;
+                tensors.insert(idx, device.upload(&weight, rows, cols)?);
+            }
         }
     }
+
+    let n_layers = weights.layers.len() - first;
+    weights.offload = Some(OffloadedWeights {
+        device,
+        tensors,
+        n_layers,
+    });
+    Ok(n_layers)
 }
 
-/// Run a single-token forward pass through the LLaMA transformer.
+/// Run a single-token forward pass through the transformer.
 ///
-/// Returns logits of shape [vocab_size].
+/// Returns logits of shape [vocab_size]. `dtype` selects how the residual
+/// stream and attention gather buffers are stored (see [`crate::dtype`]);
+/// `softmax` selects the attention softmax numerics.
 pub fn forward(
     model: &MmapModel,
     weights: &TransformerWeights,
@@ -76,169 +176,693 @@ pub fn forward(
     token: u32,
     pos: usize,
     logits: &mut [f32],
+    dtype: ComputeDtype,
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    forward_batch(model, weights, params, kv_cache, &[token], pos, logits, dtype, softmax)
+}
+
+/// Run a batched forward pass over `tokens`, placed at positions
+/// `start_pos..start_pos + tokens.len()`.
+///
+/// Used for prompt prefill: every weight matrix is dequantized once per
+/// batch instead of once per token, K/V for all positions are written to the
+/// cache, and each token attends causally to its prefix. Only the last
+/// token's logits are computed, since that is all decoding needs.
+pub fn forward_batch(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    start_pos: usize,
+    logits: &mut [f32],
+    dtype: ComputeDtype,
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    match dtype {
+        ComputeDtype::F32 => forward_impl::<f32>(
+            model, weights, params, kv_cache, tokens, start_pos, logits, softmax,
+        ),
+        ComputeDtype::Bf16 => forward_impl::<half::bf16>(
+            model, weights, params, kv_cache, tokens, start_pos, logits, softmax,
+        ),
+    }
+}
+
+fn forward_impl<A: Activation>(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    start_pos: usize,
+    logits: &mut [f32],
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    let x = run_layers::<A>(model, weights, params, kv_cache, tokens, start_pos, softmax)?;
+
+    // ---- Step 3–4: Final RMSNorm + LM head, last token only ----
+    let dim = params.dim as usize;
+    let mut out = vec![0.0f32; dim];
+    final_norm(model, weights, params, &x[x.len() - dim..], &mut out)?;
+    Mat::load(model, weights, weights.lm_head(), params.vocab_size as usize, dim, false)?.matvec(logits, &out)?;
+
+    Ok(())
+}
+
+/// Like [`forward_batch`], but writes logits for every token of the batch
+/// into `logits` [n x vocab_size] (row `i` predicts the token after
+/// `tokens[i]`). Used to score text, e.g. for perplexity.
+pub fn forward_batch_all(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    start_pos: usize,
+    logits: &mut [f32],
+    dtype: ComputeDtype,
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    match dtype {
+        ComputeDtype::F32 => all_logits_impl::<f32>(
+            model, weights, params, kv_cache, tokens, start_pos, logits, softmax,
+        ),
+        ComputeDtype::Bf16 => all_logits_impl::<half::bf16>(
+            model, weights, params, kv_cache, tokens, start_pos, logits, softmax,
+        ),
+    }
+}
+
+fn all_logits_impl<A: Activation>(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    start_pos: usize,
+    logits: &mut [f32],
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    let x = run_layers::<A>(model, weights, params, kv_cache, tokens, start_pos, softmax)?;
+    let dim = params.dim as usize;
+    let vocab_size = params.vocab_size as usize;
+    if logits.len() != tokens.len() * vocab_size {
+        return Err(BizClawError::Brain(format!(
+            "Logits buffer holds {} values, expected {} x {vocab_size}",
+            logits.len(),
+            tokens.len()
+        )));
+    }
+    let lm_head = Mat::load(model, weights, weights.lm_head(), vocab_size, dim, tokens.len() > 1)?;
+    let mut out = vec![0.0f32; dim];
+    for (row, token_logits) in x.chunks(dim).zip(logits.chunks_mut(vocab_size)) {
+        final_norm(model, weights, params, row, &mut out)?;
+        lm_head.matvec(token_logits, &out)?;
+    }
+    Ok(())
+}
+
+/// Run a batched forward pass over `tokens` and pool the final hidden
+/// states (after the output RMSNorm) into `embedding` [dim].
+///
+/// Pooling follows the model's `pooling_type`, defaulting to the mean over
+/// all tokens. Models marked non-causal attend over the whole batch, so the
+/// text must fit in a single call.
+pub fn embed_batch(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    embedding: &mut [f32],
+    dtype: ComputeDtype,
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    match dtype {
+        ComputeDtype::F32 => {
+            embed_impl::<f32>(model, weights, params, kv_cache, tokens, embedding, softmax)
+        }
+        ComputeDtype::Bf16 => {
+            embed_impl::<half::bf16>(model, weights, params, kv_cache, tokens, embedding, softmax)
+        }
+    }
+}
+
+fn embed_impl<A: Activation>(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    embedding: &mut [f32],
+    softmax: SoftmaxMode,
+) -> Result<()> {
+    let x = run_layers::<A>(model, weights, params, kv_cache, tokens, 0, softmax)?;
+    let dim = params.dim as usize;
+    let n = tokens.len();
+    let mut row = vec![0.0f32; dim];
+    let pooled = match params.pooling.unwrap_or(Pooling::Mean) {
+        Pooling::Mean => 0..n,
+        Pooling::Cls => 0..1,
+        Pooling::Last => n - 1..n,
+    };
+    let scale = 1.0 / pooled.len() as f32;
+    embedding.fill(0.0);
+    for i in pooled {
+        final_norm(model, weights, params, &x[i * dim..(i + 1) * dim], &mut row)?;
+        for (e, &v) in embedding.iter_mut().zip(&row) {
+            *e += v * scale;
+        }
+    }
+    Ok(())
+}
+
+/// Output RMSNorm of one residual row into `out`.
+fn final_norm<A: Activation>(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    row: &[A],
+    out: &mut [f32],
 ) -> Result<()> {
+    let mut xf = vec![0.0f32; row.len()];
+    dtype::load(row, &mut xf);
+    if let Some(norm_idx) = weights.output_norm {
+        let norm_w = dequant_weight(model, norm_idx, row.len())?;
+        tensor::rmsnorm(out, &xf, &norm_w, params.rms_norm_eps);
+    } else {
+        out.copy_from_slice(&xf);
+    }
+    Ok(())
+}
+
+/// Embeddings and transformer layers for a batch; returns the residual
+/// streams [n x dim]. Each token attends causally to its prefix, or to the
+/// whole batch on non-causal (embedding) models.
+fn run_layers<A: Activation>(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    params: &ModelParams,
+    kv_cache: &mut KvCache,
+    tokens: &[u32],
+    start_pos: usize,
+    softmax: SoftmaxMode,
+) -> Result<Vec<A>> {
+    if tokens.is_empty() {
+        return Err(BizClawError::Brain("Empty prefill batch".into()));
+    }
+
+    let n = tokens.len();
     let dim = params.dim as usize;
     let hidden_dim = params.hidden_dim as usize;
     let n_heads = params.n_heads as usize;
     let n_kv_heads = params.n_kv_heads as usize;
     let head_dim = params.head_dim as usize;
-    let kv_dim = n_kv_heads * head_dim;
-    let vocab_size = params.vocab_size as usize;
+    let rope_dim = params.rope_dim as usize;
+    let q_dim = params.q_dim() as usize;
+    let rope_style = params.arch.rope_style();
+    // A single decode token reads quantized weights in place; a batch
+    // dequantizes each matrix once and reuses it for every token.
+    let dense = n > 1;
 
-    // ---- Step 1: Token embedding lookup ----
-    let mut x = vec![0.0f32; dim];
-    if let Some(embd_idx) = weights.token_embd {
-        let embd_tensor = &model.gguf.tensors[embd_idx];
-        let embd_data = model.tensor_data(embd_idx)?;
-        let offset = token as usize * dim;
-        let row_bytes =
-            dim * embd_tensor.ggml_type.type_size() / embd_tensor.ggml_type.block_size();
-
-        // If embedding is F32, direct copy. Otherwise dequantize.
-        if embd_tensor.ggml_type == crate::gguf::GgmlType::F32 {
-            let byte_offset = offset * 4;
-            for i in 0..dim {
-                let o = byte_offset + i * 4;
-                if o + 4 <= embd_data.len() {
-                    x[i] = f32::from_le_bytes([
-                        embd_data[o],
-                        embd_data[o + 1],
-                        embd_data[o + 2],
-                        embd_data[o + 3],
-                    ]);
-                }
-            }
-        } else {
-            let row_offset = token as usize * row_bytes;
-            if row_offset + row_bytes <= embd_data.len() {
-                quant::dequantize_row(
-                    &embd_data[row_offset..],
-                    &mut x,
-                    dim,
-                    embd_tensor.ggml_type,
-                )?;
-            }
+    // ---- Step 1: Embeddings → per-token residual streams [n x dim] ----
+    let mut xf = vec![0.0f32; dim]; // f32 view of one residual row
+    let mut x = vec![A::default(); n * dim];
+    for (i, &token) in tokens.iter().enumerate() {
+        embed_token(model, weights, token, &mut xf)?;
+        if params.arch.scales_embeddings() {
+            let scale = (dim as f32).sqrt();
+            xf.iter_mut().for_each(|v| *v *= scale);
         }
-    } else {
-        return Err(BizClawError::Brain("Missing token_embd.weight".into()));
+        dtype::store(&xf, &mut x[i * dim..(i + 1) * dim]);
     }
 
-    // Scratch buffers
-    let mut xb = vec![0.0f32; dim]; // after RMSNorm
-    let mut xb2 = vec![0.0f32; dim]; // second residual
-    let mut q = vec![0.0f32; dim]; // query
-    let mut k = vec![0.0f32; kv_dim]; // key
-    let mut v = vec![0.0f32; kv_dim]; // value
-    let mut att_out = vec![0.0f32; dim]; // attention output
-    let mut hb = vec![0.0f32; hidden_dim]; // FFN hidden
-    let mut hb2 = vec![0.0f32; hidden_dim]; // FFN gate
+    // Scratch buffers (per-token rows where the whole batch must be held)
+    let mut xb = vec![0.0f32; n * dim]; // after RMSNorm
+    let mut q = vec![0.0f32; n * q_dim];
+    let mut k = vec![0.0f32; params.kv_dim() as usize];
+    let mut v = vec![0.0f32; params.kv_dim() as usize];
+    let mut att_out = vec![0.0f32; q_dim];
+    let mut xb2 = vec![0.0f32; dim];
+    let mut hb = vec![0.0f32; hidden_dim]; // FFN gate
+    let mut hb2 = vec![0.0f32; hidden_dim]; // FFN up
+    // Dequantized K/V rows when the cache is not stored as f32
+    let mut kv_scratch = (Vec::new(), Vec::new());
 
-    // ---- Step 2: Transformer layers ----
+    // ---- Step 2: Transformer layers, one weight load per layer ----
     for l in 0..params.n_layers as usize {
         let layer = &weights.layers[l];
 
-        // 2a. Attention RMSNorm
-        if let Some(norm_idx) = layer.attn_norm {
-            let norm_w = dequant_weight(model, norm_idx, dim)?;
-            tensor::rmsnorm(&mut xb, &x, &norm_w, params.rms_norm_eps);
-        } else {
-            xb.copy_from_slice(&x);
-        }
-
-        // 2b. Q/K/V projections
-        matmul_weight(model, layer.attn_q, &xb, &mut q, dim, dim)?;
-        matmul_weight(model, layer.attn_k, &xb, &mut k, kv_dim, dim)?;
-        matmul_weight(model, layer.attn_v, &xb, &mut v, kv_dim, dim)?;
-
-        // 2c. RoPE on Q and K
-        rope::apply_rope_multi_head(&mut q, pos, n_heads, head_dim, params.rope_theta);
-        rope::apply_rope_multi_head(&mut k, pos, n_kv_heads, head_dim, params.rope_theta);
-
-        // 2d. Store K/V in cache
-        kv_cache.key_at_mut(l, pos).copy_from_slice(&k);
-        kv_cache.value_at_mut(l, pos).copy_from_slice(&v);
-
-        let seq_len = pos + 1;
-
-        // 2e. Multi-head attention (with GQA)
-        let kv_keys = kv_cache.keys(l, seq_len);
-        let kv_values = kv_cache.values(l, seq_len);
-
-        for h in 0..n_heads {
-            let kv_h = h * n_kv_heads / n_heads; // GQA: map query head to kv head
-            let q_slice = &q[h * head_dim..(h + 1) * head_dim];
-
-            // Build key/value slices for this kv head
-            let mut head_keys = vec![0.0f32; seq_len * head_dim];
-            let mut head_values = vec![0.0f32; seq_len * head_dim];
-            for t in 0..seq_len {
-                let k_start = t * kv_dim + kv_h * head_dim;
-                let v_start = t * kv_dim + kv_h * head_dim;
-                head_keys[t * head_dim..(t + 1) * head_dim]
-                    .copy_from_slice(&kv_keys[k_start..k_start + head_dim]);
-                head_values[t * head_dim..(t + 1) * head_dim]
-                    .copy_from_slice(&kv_values[v_start..v_start + head_dim]);
-            }
+        // 2a. Attention RMSNorm for every token
+        let attn_norm = layer.attn_norm.map(|idx| dequant_weight(model, idx, dim)).transpose()?;
+        rmsnorm_rows(&x, &mut xb, &mut xf, attn_norm.as_deref(), params.rms_norm_eps);
 
-            // Attention for this head
-            let mut head_out = vec![0.0f32; head_dim];
-            crate::attention::attention(
-                &mut head_out,
-                q_slice,
-                &head_keys,
-                &head_values,
-                seq_len,
-                head_dim,
+        // 2b–2d. Q/K/V + RoPE, K/V into the cache for all positions
+        let mut qkv = QkvProjection::load(model, weights, layer, params, dense)?;
+        for i in 0..n {
+            let pos = start_pos + i;
+            let q_i = &mut q[i * q_dim..(i + 1) * q_dim];
+            qkv.apply(&xb[i * dim..(i + 1) * dim], q_i, &mut k, &mut v)?;
+            rope::apply_rope_multi_head(
+                q_i, pos, n_heads, head_dim, rope_dim, params.rope_theta, rope_style,
+            );
+            rope::apply_rope_multi_head(
+                &mut k, pos, n_kv_heads, head_dim, rope_dim, params.rope_theta, rope_style,
             );
+            kv_cache.store_key(l, pos, &k);
+            kv_cache.store_value(l, pos, &v);
+        }
+        drop(qkv);
 
-            // Copy to full output
-            att_out[h * head_dim..(h + 1) * head_dim].copy_from_slice(&head_out);
+        // 2e–2g. Causal attention, output projection, residual
+        let wo = Mat::load(model, weights, layer.attn_output, dim, q_dim, dense)?;
+        for i in 0..n {
+            let q_i = &q[i * q_dim..(i + 1) * q_dim];
+            let last = if params.causal { start_pos + i } else { start_pos + n - 1 };
+            attend::<A>(kv_cache, &mut kv_scratch, params, l, last, q_i, &mut att_out, softmax);
+            wo.matvec(&mut xb2, &att_out)?;
+            residual_add(&mut x[i * dim..(i + 1) * dim], &xb2);
         }
+        drop(wo);
 
-        // 2f. Output projection
-        matmul_weight(model, layer.attn_output, &att_out, &mut xb2, dim, dim)?;
+        // 2h. FFN RMSNorm for every token
+        let ffn_norm = layer.ffn_norm.map(|idx| dequant_weight(model, idx, dim)).transpose()?;
+        rmsnorm_rows(&x, &mut xb, &mut xf, ffn_norm.as_deref(), params.rms_norm_eps);
+
+        // 2i–2j. Gated FFN (SwiGLU / GeGLU), residual
+        let mut ffn = GatedFfn::load(model, weights, layer, params, dense)?;
+        for i in 0..n {
+            ffn.apply(&xb[i * dim..(i + 1) * dim], &mut xb2, &mut hb, &mut hb2)?;
+            residual_add(&mut x[i * dim..(i + 1) * dim], &xb2);
+        }
+    }
 
-        // 2g. Residual connection
-        tensor::elementwise_add(&mut x, &xb2);
+    Ok(x)
+}
 
-        // 2h. FFN RMSNorm
-        if let Some(norm_idx) = layer.ffn_norm {
-            let norm_w = dequant_weight(model, norm_idx, dim)?;
-            tensor::rmsnorm(&mut xb, &x, &norm_w, params.rms_norm_eps);
+/// Evict cache positions `n_keep..n_keep + n_discard` (StreamingLLM: the
+/// first `n_keep` act as attention sinks) and re-rotate the keys that slide
+/// down so they carry their new positions. Returns the new cache length.
+pub fn shift_kv_cache(
+    kv_cache: &mut KvCache,
+    params: &ModelParams,
+    n_keep: usize,
+    n_discard: usize,
+    len: usize,
+) -> usize {
+    let new_len = kv_cache.discard(n_keep, n_discard, len);
+    let mut scratch = Vec::new();
+    let mut key = vec![0.0f32; params.kv_dim() as usize];
+    for l in 0..params.n_layers as usize {
+        for pos in n_keep..new_len {
+            key.copy_from_slice(kv_cache.keys(l, pos..pos + 1, &mut scratch));
+            rope::shift_rope_multi_head(
+                &mut key,
+                -(n_discard as i64),
+                params.n_kv_heads as usize,
+                params.head_dim as usize,
+                params.rope_dim as usize,
+                params.rope_theta,
+                params.arch.rope_style(),
+            );
+            kv_cache.store_key(l, pos, &key);
+        }
+    }
+    new_len
+}
+
+/// A [rows x cols] weight matrix, either read in place through the fused
+/// quantized kernels, dequantized to f32 once (F32/F16 tensors, batches), or
+/// held by an offload device, plus any LoRA deltas on it.
+struct Mat<'m> {
+    weight: MatWeight<'m>,
+    lora: &'m [LoraDelta],
+}
+
+enum MatWeight<'m> {
+    Quantized {
+        data: &'m [u8],
+        ggml_type: GgmlType,
+        rows: usize,
+        cols: usize,
+    },
+    Dense {
+        weight: Vec<f32>,
+        rows: usize,
+        cols: usize,
+    },
+    Offloaded {
+        device: &'m dyn MatmulOffload,
+        id: OffloadId,
+    },
+}
+
+impl<'m> Mat<'m> {
+    fn load(
+        model: &'m MmapModel,
+        weights: &'m TransformerWeights,
+        tensor_idx: Option<usize>,
+        rows: usize,
+        cols: usize,
+        dense: bool,
+    ) -> Result<Self> {
+        let idx = tensor_idx.ok_or_else(|| BizClawError::Brain("Missing weight tensor".into()))?;
+        let ggml_type = model.gguf.tensors[idx].ggml_type;
+        let offloaded = weights
+            .offload
+            .as_ref()
+            .and_then(|o| Some((o.device.as_ref(), *o.tensors.get(&idx)?)));
+        let weight = if let Some((device, id)) = offloaded {
+            MatWeight::Offloaded { device, id }
+        } else if dense || matches!(ggml_type, GgmlType::F32 | GgmlType::F16) {
+            let weight = dequant_weight(model, idx, rows * cols)?;
+            MatWeight::Dense { weight, rows, cols }
         } else {
-            xb.copy_from_slice(&x);
+            MatWeight::Quantized {
+                data: model.tensor_data(idx)?,
+                ggml_type,
+                rows,
+                cols,
+            }
+        };
+        Ok(Self {
+            weight,
+            lora: weights.lora.for_tensor(idx),
+        })
+    }
+
+    /// output[rows] = self @ input[cols]
+    fn matvec(&self, output: &mut [f32], input: &[f32]) -> Result<()> {
+        match self.weight {
+            MatWeight::Quantized {
+                data,
+                ggml_type,
+                rows,
+                cols,
+            } => quant::matmul_quantized(output, data, input, rows, cols, ggml_type)?,
+            MatWeight::Dense {
+                ref weight,
+                rows,
+                cols,
+            } => crate::simd::matmul_simd(output, weight, input, rows, cols),
+            MatWeight::Offloaded { device, id } => device.matvec(id, input, output)?,
         }
+        for delta in self.lora {
+            delta.apply(output, input);
+        }
+        Ok(())
+    }
+}
 
-        // 2i. FFN: SwiGLU
-        // gate = silu(xb @ gate_proj)
-        // up   = xb @ up_proj
-        // down = (gate * up) @ down_proj
-        matmul_weight(model, layer.ffn_gate, &xb, &mut hb, hidden_dim, dim)?;
-        matmul_weight(model, layer.ffn_up, &xb, &mut hb2, hidden_dim, dim)?;
+/// Q/K/V projections of one layer: separate matrices, or Phi-3's single
+/// [q_dim + 2·kv_dim x dim] matrix, each with an optional bias (Qwen2).
+struct QkvProjection<'m> {
+    mats: QkvMats<'m>,
+    bias: [Option<Vec<f32>>; 3],
+    fused_out: Vec<f32>,
+}
 
-        tensor::silu(&mut hb);
-        tensor::elementwise_mul(&mut hb, &hb2);
+enum QkvMats<'m> {
+    Separate([Mat<'m>; 3]),
+    Fused(Mat<'m>),
+}
 
-        matmul_weight(model, layer.ffn_down, &hb, &mut xb2, dim, hidden_dim)?;
+impl<'m> QkvProjection<'m> {
+    fn load(
+        model: &'m MmapModel,
+        weights: &'m TransformerWeights,
+        layer: &LayerWeights,
+        params: &ModelParams,
+        dense: bool,
+    ) -> Result<Self> {
+        let dim = params.dim as usize;
+        let q_dim = params.q_dim() as usize;
+        let kv_dim = params.kv_dim() as usize;
+        let (mats, fused_out) = if layer.attn_qkv.is_some() && layer.attn_q.is_none() {
+            let rows = q_dim + 2 * kv_dim;
+            let mat = Mat::load(model, weights, layer.attn_qkv, rows, dim, dense)?;
+            (QkvMats::Fused(mat), vec![0.0f32; rows])
+        } else {
+            let mats = [
+                Mat::load(model, weights, layer.attn_q, q_dim, dim, dense)?,
+                Mat::load(model, weights, layer.attn_k, kv_dim, dim, dense)?,
+                Mat::load(model, weights, layer.attn_v, kv_dim, dim, dense)?,
+            ];
+            (QkvMats::Separate(mats), Vec::new())
+        };
+        let bias = |idx: Option<usize>, n| idx.map(|i| dequant_weight(model, i, n)).transpose();
+        Ok(Self {
+            mats,
+            bias: [
+                bias(layer.attn_q_bias, q_dim)?,
+                bias(layer.attn_k_bias, kv_dim)?,
+                bias(layer.attn_v_bias, kv_dim)?,
+            ],
+            fused_out,
+        })
+    }
 
-        // 2j. Residual connection
-        tensor::elementwise_add(&mut x, &xb2);
+    fn apply(&mut self, x: &[f32], q: &mut [f32], k: &mut [f32], v: &mut [f32]) -> Result<()> {
+        match &self.mats {
+            QkvMats::Separate([wq, wk, wv]) => {
+                wq.matvec(q, x)?;
+                wk.matvec(k, x)?;
+                wv.matvec(v, x)?;
+            }
+            QkvMats::Fused(w) => {
+                w.matvec(&mut self.fused_out, x)?;
+                let (fq, rest) = self.fused_out.split_at(q.len());
+                let (fk, fv) = rest.split_at(k.len());
+                q.copy_from_slice(fq);
+                k.copy_from_slice(fk);
+                v.copy_from_slice(fv);
+            }
+        }
+        for (out, bias) in [q, k, v].into_iter().zip(&self.bias) {
+            if let Some(b) = bias {
+                tensor::elementwise_add(out, b);
+            }
+        }
+        Ok(())
     }
+}
 
-    // ---- Step 3: Final RMSNorm ----
-    if let Some(norm_idx) = weights.output_norm {
-        let norm_w = dequant_weight(model, norm_idx, dim)?;
-        tensor::rmsnorm(&mut xb, &x, &norm_w, params.rms_norm_eps);
-    } else {
-        xb.copy_from_slice(&x);
+/// Gated FFN of one layer: down(act(gate·x) * up·x). Phi-3 stores gate and
+/// up as one [2·hidden x dim] `ffn_up` matrix, gate rows first.
+struct GatedFfn<'m> {
+    gate: Option<Mat<'m>>,
+    up: Mat<'m>,
+    down: Mat<'m>,
+    activation: FfnActivation,
+    fused_out: Vec<f32>,
+}
+
+impl<'m> GatedFfn<'m> {
+    fn load(
+        model: &'m MmapModel,
+        weights: &'m TransformerWeights,
+        layer: &LayerWeights,
+        params: &ModelParams,
+        dense: bool,
+    ) -> Result<Self> {
+        let dim = params.dim as usize;
+        let hidden_dim = params.hidden_dim as usize;
+        let (gate, up, fused_out) = if layer.ffn_gate.is_some() {
+            (
+                Some(Mat::load(model, weights, layer.ffn_gate, hidden_dim, dim, dense)?),
+                Mat::load(model, weights, layer.ffn_up, hidden_dim, dim, dense)?,
+                Vec::new(),
+            )
+        } else {
+            (
+                None,
+                Mat::load(model, weights, layer.ffn_up, 2 * hidden_dim, dim, dense)?,
+                vec![0.0f32; 2 * hidden_dim],
+            )
+        };
+        Ok(Self {
+            gate,
+            up,
+            down: Mat::load(model, weights, layer.ffn_down, dim, hidden_dim, dense)?,
+            activation: params.arch.ffn_activation(),
+            fused_out,
+        })
+    }
+
+    /// `out = FFN(x)`; `hb`/`hb2` are [hidden] scratch rows.
+    fn apply(&mut self, x: &[f32], out: &mut [f32], hb: &mut [f32], hb2: &mut [f32]) -> Result<()> {
+        match &self.gate {
+            Some(gate) => {
+                gate.matvec(hb, x)?;
+                self.up.matvec(hb2, x)?;
+            }
+            None => {
+                self.up.matvec(&mut self.fused_out, x)?;
+                let (g, u) = self.fused_out.split_at(hb.len());
+                hb.copy_from_slice(g);
+                hb2.copy_from_slice(u);
+            }
+        }
+        match self.activation {
+            FfnActivation::Silu => tensor::silu(hb),
+            FfnActivation::Gelu => tensor::gelu(hb),
+        }
+        tensor::elementwise_mul(hb, hb2);
+        self.down.matvec(out, hb)
     }
+}
 
-    // ---- Step 4: LM Head → logits ----
-    matmul_weight(model, weights.output, &xb, logits, vocab_size, dim)?;
+/// RMSNorm each `dim`-sized row of the stored stream `x` into `out`
+/// (plain copy when the model has no norm weight).
+fn rmsnorm_rows<A: Activation>(
+    x: &[A],
+    out: &mut [f32],
+    scratch: &mut [f32],
+    norm_w: Option<&[f32]>,
+    eps: f32,
+) {
+    let dim = scratch.len();
+    for (row, out_row) in x.chunks_exact(dim).zip(out.chunks_exact_mut(dim)) {
+        dtype::load(row, scratch);
+        match norm_w {
+            Some(w) => tensor::rmsnorm(out_row, scratch, w, eps),
+            None => out_row.copy_from_slice(scratch),
+        }
+    }
+}
 
+/// Look up a token's embedding row into `x` (length `dim`).
+fn embed_token(
+    model: &MmapModel,
+    weights: &TransformerWeights,
+    token: u32,
+    x: &mut [f32],
+) -> Result<()> {
+    let dim = x.len();
+    let Some(embd_idx) = weights.token_embd else {
+        return Err(BizClawError::Brain("Missing token_embd.weight".into()));
+    };
+    let embd_tensor = &model.gguf.tensors[embd_idx];
+    let embd_data = model.tensor_data(embd_idx)?;
+    let offset = token as usize * dim;
+    let row_bytes = dim * embd_tensor.ggml_type.type_size() / embd_tensor.ggml_type.block_size();
+
+    // If embedding is F32, direct copy. Otherwise dequantize.
+    if embd_tensor.ggml_type == crate::gguf::GgmlType::F32 {
+        let byte_offset = offset * 4;
+        for i in 0..dim {
+            let o = byte_offset + i * 4;
+            if o + 4 <= embd_data.len() {
+                x[i] = f32::from_le_bytes([
+                    embd_data[o],
+                    embd_data[o + 1],
+                    embd_data[o + 2],
+                    embd_data[o + 3],
+                ]);
+            }
+        }
+    } else {
+        let row_offset = token as usize * row_bytes;
+        if row_offset + row_bytes <= embd_data.len() {
+            quant::dequantize_row(&embd_data[row_offset..], x, dim, embd_tensor.ggml_type)?;
+        }
+    }
     Ok(())
 }
 
+/// Causal multi-head attention (with GQA) for the query at `pos` against the
+/// cached keys/values at positions `0..=pos` of layer `l`, limited to the
+/// last `sliding_window` positions when the model sets one. A quantized
+/// cache is dequantized into `scratch` (keys, values) first.
+fn attend<A: Activation>(
+    kv_cache: &KvCache,
+    scratch: &mut (Vec<f32>, Vec<f32>),
+    params: &ModelParams,
+    l: usize,
+    pos: usize,
+    q: &[f32],
+    att_out: &mut [f32],
+    softmax: SoftmaxMode,
+) {
+    let n_heads = params.n_heads as usize;
+    let n_kv_heads = params.n_kv_heads as usize;
+    let head_dim = params.head_dim as usize;
+    let kv_dim = n_kv_heads * head_dim;
+    let first = match params.sliding_window {
+        Some(window) => (pos + 1).saturating_sub(window as usize),
+        None => 0,
+    };
+    let seq_len = pos + 1 - first;
+
+    let kv_keys = kv_cache.keys(l, first..pos + 1, &mut scratch.0);
+    let kv_values = kv_cache.values(l, first..pos + 1, &mut scratch.1);
+
+    // Fast path: tiled kernel straight over the cache, one call per GQA group
+    if softmax == SoftmaxMode::Fast {
+        let group = n_heads / n_kv_heads;
+        for kv_h in 0..n_kv_heads {
+            let heads = kv_h * group * head_dim..(kv_h + 1) * group * head_dim;
+            crate::attention::attention_fused::<A>(
+                &mut att_out[heads.clone()],
+                &q[heads],
+                kv_keys,
+                kv_values,
+                seq_len,
+                head_dim,
+                kv_dim,
+                kv_h * head_dim,
+            );
+        }
+        return;
+    }
+
+    // Stable modes: gather each head's keys/values and score them all at once
+    for h in 0..n_heads {
+        let kv_h = h * n_kv_heads / n_heads; // GQA: map query head to kv head
+        let q_slice = &q[h * head_dim..(h + 1) * head_dim];
+
+        // Build key/value slices for this kv head
+        let mut head_keys = vec![A::default(); seq_len * head_dim];
+        let mut head_values = vec![A::default(); seq_len * head_dim];
+        for t in 0..seq_len {
+            let start = t * kv_dim + kv_h * head_dim;
+            dtype::store(
+                &kv_keys[start..start + head_dim],
+                &mut head_keys[t * head_dim..(t + 1) * head_dim],
+            );
+            dtype::store(
+                &kv_values[start..start + head_dim],
+                &mut head_values[t * head_dim..(t + 1) * head_dim],
+            );
+        }
+
+        // Stable softmax over the materialized score row
+        let head_out = &mut att_out[h * head_dim..(h + 1) * head_dim];
+        let clean = crate::attention::attention_stable(
+            head_out,
+            q_slice,
+            &head_keys,
+            &head_values,
+            seq_len,
+            head_dim,
+            softmax == SoftmaxMode::StableF64,
+        );
+        if !clean {
+            tracing::warn!("Non-finite attention scores at layer {l}, head {h}, pos {pos}");
+        }
+    }
+}
+
+/// Residual add into the stored stream: x[i] = x[i] + delta[i] (f32 math).
+fn residual_add<A: Activation>(x: &mut [A], delta: &[f32]) {
+    debug_assert_eq!(x.len(), delta.len());
+    for (v, &d) in x.iter_mut().zip(delta) {
+        *v = A::from_f32(v.to_f32() + d);
+    }
+}
+
 /// Dequantize a full weight tensor to f32.
 fn dequant_weight(model: &MmapModel, tensor_idx: usize, n_elements: usize) -> Result<Vec<f32>> {
     let data = model.tensor_data(tensor_idx)?;
@@ -248,26 +872,785 @@ fn dequant_weight(model: &MmapModel, tensor_idx: usize, n_elements: usize) -> Re
     Ok(output)
 }
 
-/// Matrix-vector multiply using a weight tensor from mmap.
-/// output[rows] = weight[rows x cols] @ input[cols]
-fn matmul_weight(
-    model: &MmapModel,
-    tensor_idx: Option<usize>,
-    input: &[f32],
-    output: &mut [f32],
-    rows: usize,
-    cols: usize,
-) -> Result<()> {
-    let idx = tensor_idx.ok_or_else(|| BizClawError::Brain("Missing weight tensor".into()))?;
-    let data = model.tensor_data(idx)?;
-    let tensor = &model.gguf.tensors[idx];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv_cache::KvCacheDtype;
+    use std::io::Write;
 
-    // Dequantize entire weight matrix
-    let n_elements = rows * cols;
-    let mut weight = vec![0.0f32; n_elements];
-    quant::dequantize_row(data, &mut weight, n_elements, tensor.ggml_type)?;
+    const DIM: usize = 16;
+    const HIDDEN: usize = 32;
+    const HEADS: usize = 4;
+    const KV_HEADS: usize = 2;
+    const LAYERS: usize = 2;
+    const VOCAB: usize = 24;
 
-    // MatMul
-    tensor::matmul(output, &weight, input, rows, cols);
-    Ok(())
+    fn put_str(buf: &mut Vec<u8>, s: &str) {
+        buf.extend((s.len() as u64).to_le_bytes());
+        buf.extend(s.as_bytes());
+    }
+
+    fn put_u32_kv(buf: &mut Vec<u8>, key: &str, v: u32) {
+        put_str(buf, key);
+        buf.extend(4u32.to_le_bytes());
+        buf.extend(v.to_le_bytes());
+    }
+
+    /// How the fixture provides its LM head.
+    #[derive(Clone, Copy, PartialEq)]
+    enum LmHead {
+        /// Independent random `output.weight`.
+        Separate,
+        /// No `output.weight` (tied to `token_embd.weight`).
+        Tied,
+        /// `output.weight` present but a byte copy of `token_embd.weight`.
+        CopyOfEmbd,
+    }
+
+    /// Architecture knobs of a fixture model.
+    #[derive(Clone, Copy)]
+    struct Variant {
+        arch: &'static str,
+        lm_head: LmHead,
+        /// Phi-3 layout: `attn_qkv` and a [gate; up] `ffn_up`.
+        fused: bool,
+        /// Qwen2 Q/K/V biases.
+        bias: bool,
+        /// `attention.key_length`, when it differs from DIM / HEADS.
+        head_dim: Option<usize>,
+        sliding_window: Option<u32>,
+    }
+
+    impl Variant {
+        fn arch(arch: &'static str) -> Self {
+            Self {
+                arch,
+                lm_head: LmHead::Separate,
+                fused: false,
+                bias: false,
+                head_dim: None,
+                sliding_window: None,
+            }
+        }
+    }
+
+    /// Deterministic pseudo-random values for a logical tensor, keyed by
+    /// name so fused and separate layouts hold identical weights.
+    fn tensor_values(name: &str, n: usize) -> Vec<f32> {
+        let mut seed = name.bytes().fold(0x811C_9DC5u32, |h, b| (h ^ b as u32).wrapping_mul(0x0100_0193));
+        let is_norm = name.contains("norm");
+        (0..n)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                let r = (seed as f32 / u32::MAX as f32) - 0.5;
+                if is_norm { 1.0 + 0.1 * r } else { 0.6 * r }
+            })
+            .collect()
+    }
+
+    /// Write a tiny all-F32 LLaMA GGUF with deterministic pseudo-random weights.
+    fn write_tiny_model(path: &std::path::Path, lm_head: LmHead) {
+        write_model(path, Variant { lm_head, ..Variant::arch("llama") });
+    }
+
+    fn write_model(path: &std::path::Path, variant: Variant) {
+        let head_dim = variant.head_dim.unwrap_or(DIM / HEADS);
+        let q_dim = HEADS * head_dim;
+        let kv_dim = KV_HEADS * head_dim;
+
+        // (tensor name, GGUF dims [cols, rows], logical parts concatenated by rows)
+        let mut tensors: Vec<(String, Vec<usize>, Vec<String>)> = Vec::new();
+        let mut add = |name: String, dims: Vec<usize>, parts: Vec<String>| tensors.push((name, dims, parts));
+        let rows_of = |part: &str| match part.rsplit('.').nth(1) {
+            Some("attn_q") => q_dim,
+            Some("attn_k" | "attn_v") => kv_dim,
+            _ => HIDDEN, // ffn_gate / ffn_up halves
+        };
+        add("token_embd.weight".into(), vec![DIM, VOCAB], vec!["token_embd.weight".into()]);
+        for l in 0..LAYERS {
+            let blk = |n: &str| format!("blk.{l}.{n}");
+            add(blk("attn_norm.weight"), vec![DIM], vec![blk("attn_norm.weight")]);
+            let (q, k, v) = (blk("attn_q.weight"), blk("attn_k.weight"), blk("attn_v.weight"));
+            if variant.fused {
+                add(blk("attn_qkv.weight"), vec![DIM, q_dim + 2 * kv_dim], vec![q, k, v]);
+            } else {
+                add(q.clone(), vec![DIM, q_dim], vec![q]);
+                add(k.clone(), vec![DIM, kv_dim], vec![k]);
+                add(v.clone(), vec![DIM, kv_dim], vec![v]);
+            }
+            if variant.bias {
+                for (name, n) in [("attn_q.bias", q_dim), ("attn_k.bias", kv_dim), ("attn_v.bias", kv_dim)] {
+                    add(blk(name), vec![n], vec![blk(name)]);
+                }
+            }
+            add(blk("attn_output.weight"), vec![q_dim, DIM], vec![blk("attn_output.weight")]);
+            add(blk("ffn_norm.weight"), vec![DIM], vec![blk("ffn_norm.weight")]);
+            let (gate, up) = (blk("ffn_gate.weight"), blk("ffn_up.weight"));
+            if variant.fused {
+                add(up.clone(), vec![DIM, 2 * HIDDEN], vec![gate, up]);
+            } else {
+                add(gate.clone(), vec![DIM, HIDDEN], vec![gate]);
+                add(up.clone(), vec![DIM, HIDDEN], vec![up]);
+            }
+            add(blk("ffn_down.weight"), vec![HIDDEN, DIM], vec![blk("ffn_down.weight")]);
+        }
+        add("output_norm.weight".into(), vec![DIM], vec!["output_norm.weight".into()]);
+        match variant.lm_head {
+            LmHead::Separate => add("output.weight".into(), vec![DIM, VOCAB], vec!["output.weight".into()]),
+            LmHead::CopyOfEmbd => add("output.weight".into(), vec![DIM, VOCAB], vec!["token_embd.weight".into()]),
+            LmHead::Tied => {}
+        }
+
+        let arch = variant.arch;
+        let mut kvs = vec![
+            ("embedding_length", DIM as u32),
+            ("feed_forward_length", HIDDEN as u32),
+            ("block_count", LAYERS as u32),
+            ("attention.head_count", HEADS as u32),
+            ("attention.head_count_kv", KV_HEADS as u32),
+            ("vocab_size", VOCAB as u32),
+        ];
+        if let Some(h) = variant.head_dim {
+            kvs.push(("attention.key_length", h as u32));
+        }
+        if let Some(w) = variant.sliding_window {
+            kvs.push(("attention.sliding_window", w));
+        }
+
+        let mut buf = Vec::new();
+        buf.extend(0x46554747u32.to_le_bytes());
+        buf.extend(3u32.to_le_bytes());
+        buf.extend((tensors.len() as u64).to_le_bytes());
+        buf.extend((kvs.len() as u64 + 1).to_le_bytes());
+        put_str(&mut buf, "general.architecture");
+        buf.extend(8u32.to_le_bytes());
+        put_str(&mut buf, arch);
+        for (key, v) in kvs {
+            put_u32_kv(&mut buf, &format!("{arch}.{key}"), v);
+        }
+
+        let mut offset = 0u64;
+        for (name, dims, _) in &tensors {
+            put_str(&mut buf, name);
+            buf.extend((dims.len() as u32).to_le_bytes());
+            for &d in dims {
+                buf.extend((d as u64).to_le_bytes());
+            }
+            buf.extend(0u32.to_le_bytes()); // F32
+            buf.extend(offset.to_le_bytes());
+            let bytes = dims.iter().product::<usize>() as u64 * 4;
+            offset += bytes.div_ceil(32) * 32;
+        }
+        buf.resize(buf.len().div_ceil(32) * 32, 0);
+
+        for (_, dims, parts) in &tensors {
+            let n: usize = dims.iter().product();
+            for part in parts {
+                let len = if parts.len() == 1 { n } else { dims[0] * rows_of(part) };
+                for w in tensor_values(part, len) {
+                    buf.extend(w.to_le_bytes());
+                }
+            }
+            buf.resize(buf.len().div_ceil(32) * 32, 0);
+        }
+        std::fs::File::create(path).unwrap().write_all(&buf).unwrap();
+    }
+
+    fn run(model: &MmapModel, tokens: &[u32], dtype: ComputeDtype) -> Vec<Vec<f32>> {
+        run_with_cache(model, tokens, dtype, KvCacheDtype::F32)
+    }
+
+    fn run_with_cache(
+        model: &MmapModel,
+        tokens: &[u32],
+        dtype: ComputeDtype,
+        cache_dtype: KvCacheDtype,
+    ) -> Vec<Vec<f32>> {
+        let params = ModelParams::from_gguf(&model.gguf);
+        let weights = TransformerWeights::from_gguf(model, &params);
+        let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, params.head_dim as usize, cache_dtype);
+        let mut all = Vec::new();
+        for (pos, &t) in tokens.iter().enumerate() {
+            let mut logits = vec![0.0f32; VOCAB];
+            forward(model, &weights, &params, &mut cache, t, pos, &mut logits, dtype, SoftmaxMode::Fast)
+                .unwrap();
+            all.push(logits);
+        }
+        all
+    }
+
+    #[test]
+    fn test_bf16_matches_f32_within_tolerance() {
+        let path = std::env::temp_dir().join(format!("bizclaw-tiny-{}.gguf", std::process::id()));
+        write_tiny_model(&path, LmHead::Separate);
+        let model = MmapModel::load(&path).unwrap();
+        let tokens = [1u32, 5, 9, 3, 17, 3, 22, 8];
+
+        let f32_logits = run(&model, &tokens, ComputeDtype::F32);
+        let bf16_logits = run(&model, &tokens, ComputeDtype::Bf16);
+
+        for (step, (a, b)) in f32_logits.iter().zip(&bf16_logits).enumerate() {
+            let scale = a.iter().fold(0.0f32, |m, v| m.max(v.abs())).max(1e-3);
+            let max_err = a.iter().zip(b).fold(0.0f32, |m, (x, y)| m.max((x - y).abs()));
+            assert!(max_err / scale < 0.05, "step {step}: rel err {}", max_err / scale);
+            assert!(b.iter().all(|v| v.is_finite()));
+        }
+        // bf16 rounding must actually be in effect
+        assert_ne!(f32_logits, bf16_logits);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_batch_prefill_matches_sequential() {
+        let path = std::env::temp_dir().join(format!("bizclaw-prefill-{}.gguf", std::process::id()));
+        write_tiny_model(&path, LmHead::Separate);
+        let model = MmapModel::load(&path).unwrap();
+        let params = ModelParams::from_gguf(&model.gguf);
+        let weights = TransformerWeights::from_gguf(&model, &params);
+        let tokens = [1u32, 5, 9, 3, 17, 3, 22, 8, 4];
+        let sequential = run(&model, &tokens, ComputeDtype::F32);
+
+        // Whole prompt in one pass, and in uneven chunks of 4
+        for chunk in [tokens.len(), 4] {
+            let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, params.head_dim as usize, KvCacheDtype::F32);
+            let mut logits = vec![0.0f32; VOCAB];
+            let mut pos = 0;
+            for batch in tokens.chunks(chunk) {
+                forward_batch(
+                    &model, &weights, &params, &mut cache, batch, pos, &mut logits,
+                    ComputeDtype::F32, SoftmaxMode::Fast,
+                )
+                .unwrap();
+                pos += batch.len();
+            }
+            let expected = sequential.last().unwrap();
+            for (a, b) in logits.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-4, "chunk {chunk}: {a} vs {b}");
+            }
+
+            // The cache must be usable for decoding the next token
+            let mut next = vec![0.0f32; VOCAB];
+            forward(&model, &weights, &params, &mut cache, 7, pos, &mut next, ComputeDtype::F32, SoftmaxMode::Fast)
+                .unwrap();
+            let mut all = tokens.to_vec();
+            all.push(7);
+            let reference = run(&model, &all, ComputeDtype::F32);
+            for (a, b) in next.iter().zip(reference.last().unwrap()) {
+                assert!((a - b).abs() < 1e-4, "decode after chunk {chunk}: {a} vs {b}");
+            }
+        }
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_tied_embeddings_use_token_embd_as_lm_head() {
+        let dir = std::env::temp_dir();
+        let tied_path = dir.join(format!("bizclaw-tied-{}.gguf", std::process::id()));
+        let copy_path = dir.join(format!("bizclaw-tied-copy-{}.gguf", std::process::id()));
+        write_tiny_model(&tied_path, LmHead::Tied);
+        write_tiny_model(&copy_path, LmHead::CopyOfEmbd);
+        let tied = MmapModel::load(&tied_path).unwrap();
+        let copy = MmapModel::load(&copy_path).unwrap();
+
+        let params = ModelParams::from_gguf(&tied.gguf);
+        let weights = TransformerWeights::from_gguf(&tied, &params);
+        assert!(weights.output.is_none());
+        assert!(weights.tied_embeddings);
+        assert_eq!(weights.lm_head(), weights.token_embd);
+        let copy_weights = TransformerWeights::from_gguf(&copy, &params);
+        assert!(!copy_weights.tied_embeddings);
+
+        let tokens = [1u32, 5, 9, 3, 17];
+        let tied_logits = run(&tied, &tokens, ComputeDtype::F32);
+        let copy_logits = run(&copy, &tokens, ComputeDtype::F32);
+        for (a, b) in tied_logits.iter().zip(&copy_logits) {
+            assert!(a.iter().all(|v| v.is_finite()));
+            assert!(a.iter().any(|v| v.abs() > 1e-3), "logits should not be degenerate");
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() <= 1e-5 * x.abs().max(1.0), "{x} vs {y}");
+            }
+        }
+
+        let engine = crate::BrainEngine::load(&tied_path).unwrap();
+        assert!(engine.model_info().unwrap().contains("tied embeddings"));
+        let engine = crate::BrainEngine::load(&copy_path).unwrap();
+        assert!(!engine.model_info().unwrap().contains("tied embeddings"));
+
+        let _ = std::fs::remove_file(tied_path);
+        let _ = std::fs::remove_file(copy_path);
+    }
+
+    #[test]
+    fn test_context_shift_keeps_generating() {
+        let path = std::env::temp_dir().join(format!("bizclaw-shift-{}.gguf", std::process::id()));
+        write_tiny_model(&path, LmHead::Separate);
+        let mut engine = crate::BrainEngine::new(crate::BrainConfig {
+            context_length: 16,
+            max_tokens: 64,
+            temperature: 0.0,
+            ..Default::default()
+        });
+        engine.load_model(&path).unwrap();
+        engine.model.as_mut().unwrap().stop_ids.clear();
+        let prompt: Vec<u32> = (0..24).map(|i| i % (VOCAB as u32 - 1) + 1).collect();
+
+        // Over-long prompt, then twice the context of output
+        engine.generate_inner(prompt.clone(), 3, 40, None, None, None).unwrap();
+        let usage = engine.last_usage().unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (24, 40));
+
+        engine.config.context_shift = false;
+        assert!(engine.generate_inner(prompt.clone(), 3, 40, None, None, None).is_err());
+        engine.generate_inner(prompt[..8].to_vec(), 3, 40, None, None, None).unwrap();
+        assert!(engine.last_usage().unwrap().completion_tokens <= 9);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_session_restore_continues_generation() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("bizclaw-session-{}.gguf", std::process::id()));
+        let session = dir.join(format!("bizclaw-session-{}.bcss", std::process::id()));
+        write_tiny_model(&path, LmHead::Separate);
+        let config = crate::BrainConfig { context_length: 64, temperature: 1.0, ..Default::default() };
+        let open = || {
+            let mut engine = crate::BrainEngine::new(config.clone());
+            engine.load_model(&path).unwrap();
+            engine.model.as_mut().unwrap().stop_ids.clear();
+            engine
+        };
+        let history = |engine: &crate::BrainEngine| engine.model.as_ref().unwrap().history.clone();
+
+        let mut original = open();
+        let system: Vec<u32> = vec![1, 5, 9, 3, 17, 3, 22, 8, 11];
+        original.generate_inner(system.clone(), 1, 4, None, None, None).unwrap();
+        // The prompt and all but the last sampled token are cached
+        assert_eq!(history(&original).len(), system.len() + 3);
+        original.save_session(&session).unwrap();
+
+        let mut prompt = system.clone();
+        prompt.extend([4, 6, 2]);
+        original.generate_inner(prompt.clone(), 1, 8, None, None, None).unwrap();
+
+        let mut restored = open();
+        restored.load_session(&session).unwrap();
+        restored.generate_inner(prompt, 1, 8, None, None, None).unwrap();
+        // Same cache, same reuse and the same random stream
+        assert_eq!(history(&restored), history(&original));
+
+        // A session from another model is rejected
+        let other_path = dir.join(format!("bizclaw-session-other-{}.gguf", std::process::id()));
+        write_tiny_model(&other_path, LmHead::Tied);
+        let mut other = crate::BrainEngine::new(config.clone());
+        other.load_model(&other_path).unwrap();
+        assert!(other.load_session(&session).is_err());
+
+        for p in [path, session, other_path] {
+            let _ = std::fs::remove_file(p);
+        }
+    }
+
+    /// Logits of the last token after prefilling `tokens` in one batch.
+    fn prefill(model: &MmapModel, tokens: &[u32]) -> Vec<f32> {
+        let params = ModelParams::from_gguf(&model.gguf);
+        let weights = TransformerWeights::from_gguf(model, &params);
+        let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, params.head_dim as usize, KvCacheDtype::F32);
+        let mut logits = vec![0.0f32; VOCAB];
+        forward_batch(
+            model, &weights, &params, &mut cache, tokens, 0, &mut logits,
+            ComputeDtype::F32, SoftmaxMode::Fast,
+        )
+        .unwrap();
+        logits
+    }
+
+    fn assert_close(a: &[f32], b: &[f32], what: &str) {
+        assert!(a.iter().all(|v| v.is_finite()), "{what}: non-finite logits");
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() <= 1e-4 * x.abs().max(1.0), "{what}: {x} vs {y}");
+        }
+    }
+
+    fn load_variant(tag: &str, variant: Variant) -> (std::path::PathBuf, MmapModel) {
+        let path = std::env::temp_dir().join(format!("bizclaw-{tag}-{}.gguf", std::process::id()));
+        write_model(&path, variant);
+        let model = MmapModel::load(&path).unwrap();
+        (path, model)
+    }
+
+    #[test]
+    fn test_phi3_fused_projections_match_separate() {
+        // Qwen2 without biases is the unfused equivalent of Phi-3 (both NeoX RoPE)
+        let (sep_path, separate) = load_variant("qwen2-plain", Variant::arch("qwen2"));
+        let (fused_path, fused) = load_variant("phi3", Variant { fused: true, ..Variant::arch("phi3") });
+        let params = ModelParams::from_gguf(&fused.gguf);
+        let weights = TransformerWeights::from_gguf(&fused, &params);
+        assert!(weights.layers[0].attn_qkv.is_some() && weights.layers[0].ffn_gate.is_none());
+
+        let tokens = [1u32, 5, 9, 3, 17, 3];
+        let expected = run(&separate, &tokens, ComputeDtype::F32);
+        let actual = run(&fused, &tokens, ComputeDtype::F32);
+        for (a, b) in actual.iter().zip(&expected) {
+            assert_close(a, b, "phi3 decode");
+        }
+        assert_close(&prefill(&fused, &tokens), expected.last().unwrap(), "phi3 prefill");
+
+        let _ = std::fs::remove_file(sep_path);
+        let _ = std::fs::remove_file(fused_path);
+    }
+
+    #[test]
+    fn test_qwen2_biases_are_applied() {
+        let (plain_path, plain) = load_variant("qwen2-nobias", Variant::arch("qwen2"));
+        let (bias_path, biased) = load_variant("qwen2-bias", Variant { bias: true, ..Variant::arch("qwen2") });
+        let tokens = [4u32, 8, 15, 16, 23];
+        let with_bias = run(&biased, &tokens, ComputeDtype::F32);
+        let without = run(&plain, &tokens, ComputeDtype::F32);
+        assert_ne!(with_bias.last(), without.last());
+        assert_close(&prefill(&biased, &tokens), with_bias.last().unwrap(), "qwen2 prefill");
+
+        let _ = std::fs::remove_file(plain_path);
+        let _ = std::fs::remove_file(bias_path);
+    }
+
+    #[test]
+    fn test_gemma_wide_heads_and_geglu() {
+        // 4 heads of 8 project the 16-wide stream to a 32-wide query
+        let (path, model) = load_variant(
+            "gemma",
+            Variant { lm_head: LmHead::Tied, head_dim: Some(8), ..Variant::arch("gemma") },
+        );
+        let params = ModelParams::from_gguf(&model.gguf);
+        assert_eq!(params.q_dim(), 32);
+        let tokens = [2u32, 7, 1, 8, 2, 8];
+        let logits = run(&model, &tokens, ComputeDtype::F32);
+        assert!(logits.iter().flatten().any(|v| v.abs() > 1e-3));
+        assert_close(&prefill(&model, &tokens), logits.last().unwrap(), "gemma prefill");
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_sliding_window_limits_attention() {
+        let (full_path, full) = load_variant("mistral-full", Variant::arch("mistral"));
+        let (wide_path, wide) =
+            load_variant("mistral-wide", Variant { sliding_window: Some(64), ..Variant::arch("mistral") });
+        let (narrow_path, narrow) =
+            load_variant("mistral-narrow", Variant { sliding_window: Some(2), ..Variant::arch("mistral") });
+        let tokens = [3u32, 1, 4, 1, 5, 9];
+        let reference = run(&full, &tokens, ComputeDtype::F32);
+        let windowed = run(&narrow, &tokens, ComputeDtype::F32);
+        assert_eq!(run(&wide, &tokens, ComputeDtype::F32), reference);
+        // Identical while the window covers the prefix, different afterwards
+        assert_eq!(windowed[..2], reference[..2]);
+        assert_ne!(windowed[5], reference[5]);
+        assert_close(&prefill(&narrow, &tokens), &windowed[5], "windowed prefill");
+
+        for path in [full_path, wide_path, narrow_path] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_shift_kv_cache_matches_fresh_prefill() {
+        for arch in ["llama", "qwen2"] {
+            let (path, model) = load_variant(&format!("shift-{arch}"), Variant::arch(arch));
+            let params = ModelParams::from_gguf(&model.gguf);
+            let weights = TransformerWeights::from_gguf(&model, &params);
+            let fill = |tokens: &[u32]| {
+                let mut cache = KvCache::new(LAYERS, 16, KV_HEADS, params.head_dim as usize, KvCacheDtype::F32);
+                let mut logits = vec![0.0f32; VOCAB];
+                forward_batch(
+                    &model, &weights, &params, &mut cache, tokens, 0, &mut logits,
+                    ComputeDtype::F32, SoftmaxMode::Fast,
+                )
+                .unwrap();
+                cache
+            };
+
+            // Keep 2 sinks, evict 3 tokens, slide the last 3 down
+            let mut shifted = fill(&[3, 1, 4, 1, 5, 9, 2, 6]);
+            assert_eq!(shift_kv_cache(&mut shifted, &params, 2, 3, 8), 5);
+            let fresh = fill(&[3, 1, 9, 2, 6]);
+
+            // Layer 0 K/V depend only on the token and its position
+            let (mut a, mut b) = (Vec::new(), Vec::new());
+            assert_close(shifted.keys(0, 0..5, &mut a), fresh.keys(0, 0..5, &mut b), arch);
+            assert_close(shifted.values(0, 0..5, &mut a), fresh.values(0, 0..5, &mut b), arch);
+
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_quantized_kv_cache_tracks_f32() {
+        let (path, model) = load_variant("kv-quant", Variant::arch("llama"));
+        let tokens = [1u32, 5, 9, 3, 17, 3, 22, 8];
+        let reference = run(&model, &tokens, ComputeDtype::F32);
+        for (cache_dtype, tol) in [(KvCacheDtype::F16, 0.01), (KvCacheDtype::Q8_0, 0.05)] {
+            let logits = run_with_cache(&model, &tokens, ComputeDtype::F32, cache_dtype);
+            for (step, (a, b)) in reference.iter().zip(&logits).enumerate() {
+                let scale = a.iter().fold(0.0f32, |m, v| m.max(v.abs())).max(1e-3);
+                let max_err = a.iter().zip(b).fold(0.0f32, |m, (x, y)| m.max((x - y).abs()));
+                assert!(max_err / scale < tol, "{cache_dtype:?} step {step}: rel err {}", max_err / scale);
+            }
+            // Stable attention reads the same dequantized rows
+            let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, DIM / HEADS, cache_dtype);
+            let params = ModelParams::from_gguf(&model.gguf);
+            let weights = TransformerWeights::from_gguf(&model, &params);
+            let mut stable = vec![0.0f32; VOCAB];
+            forward_batch(
+                &model, &weights, &params, &mut cache, &tokens, 0, &mut stable,
+                ComputeDtype::F32, SoftmaxMode::Stable,
+            )
+            .unwrap();
+            assert_close(&stable, logits.last().unwrap(), "stable softmax");
+        }
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_embedding_pooling_and_causality() {
+        let (path, model) = load_variant("embed", Variant::arch("llama"));
+        let mut params = ModelParams::from_gguf(&model.gguf);
+        let weights = TransformerWeights::from_gguf(&model, &params);
+        let embed = |params: &ModelParams, tokens: &[u32]| {
+            let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, DIM / HEADS, KvCacheDtype::F32);
+            let mut out = vec![0.0f32; DIM];
+            embed_batch(
+                &model, &weights, params, &mut cache, tokens, &mut out,
+                ComputeDtype::F32, SoftmaxMode::Fast,
+            )
+            .unwrap();
+            out
+        };
+        let tokens = [1u32, 5, 9];
+
+        // Causal: the first state sees only itself; the mean averages all rows
+        let single = embed(&params, &tokens[..1]);
+        params.pooling = Some(Pooling::Cls);
+        let cls = embed(&params, &tokens);
+        assert_close(&cls, &single, "cls");
+        params.pooling = Some(Pooling::Last);
+        let rows: Vec<Vec<f32>> = (1..=3).map(|k| embed(&params, &tokens[..k])).collect();
+        params.pooling = None;
+        let mean = embed(&params, &tokens);
+        let expected: Vec<f32> = (0..DIM).map(|d| rows.iter().map(|r| r[d]).sum::<f32>() / 3.0).collect();
+        assert_close(&mean, &expected, "mean");
+
+        // Bidirectional: the first token now sees the rest of the text
+        params.pooling = Some(Pooling::Cls);
+        params.causal = false;
+        assert_ne!(embed(&params, &tokens), cls);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_all_logits_match_sequential_decode() {
+        let (path, model) = load_variant("all-logits", Variant::arch("qwen2"));
+        let params = ModelParams::from_gguf(&model.gguf);
+        let weights = TransformerWeights::from_gguf(&model, &params);
+        let tokens = [2u32, 11, 4, 19, 7];
+        let expected = run(&model, &tokens, ComputeDtype::F32);
+
+        let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, DIM / HEADS, KvCacheDtype::F32);
+        let mut logits = vec![0.0f32; 3 * VOCAB];
+        for (start, part) in [(0, &tokens[..3]), (3, &tokens[3..])] {
+            let out = &mut logits[..part.len() * VOCAB];
+            forward_batch_all(
+                &model, &weights, &params, &mut cache, part, start, out,
+                ComputeDtype::F32, SoftmaxMode::Fast,
+            )
+            .unwrap();
+            for (i, row) in out.chunks(VOCAB).enumerate() {
+                assert_close(row, &expected[start + i], "all logits");
+            }
+        }
+        let mut short = vec![0.0f32; VOCAB];
+        assert!(forward_batch_all(
+            &model, &weights, &params, &mut cache, &tokens[..2], 0, &mut short,
+            ComputeDtype::F32, SoftmaxMode::Fast,
+        )
+        .is_err());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// `(target tensor, rank, cols, A, B)` of one adapter entry.
+    type AdapterDelta<'a> = (&'a str, usize, usize, Vec<f32>, Vec<f32>);
+
+    /// Write an all-F32 LoRA adapter GGUF.
+    fn write_adapter(path: &std::path::Path, alpha: f32, deltas: &[AdapterDelta]) {
+        let mut tensors: Vec<(String, [usize; 2], &[f32])> = Vec::new();
+        for (target, rank, cols, a, b) in deltas {
+            tensors.push((format!("{target}.lora_a"), [*cols, *rank], a));
+            tensors.push((format!("{target}.lora_b"), [*rank, b.len() / rank], b));
+        }
+
+        let mut buf = Vec::new();
+        buf.extend(0x46554747u32.to_le_bytes());
+        buf.extend(3u32.to_le_bytes());
+        buf.extend((tensors.len() as u64).to_le_bytes());
+        buf.extend(4u64.to_le_bytes());
+        for (key, value) in [("general.architecture", "llama"), ("general.type", "adapter"), ("adapter.type", "lora")] {
+            put_str(&mut buf, key);
+            buf.extend(8u32.to_le_bytes());
+            put_str(&mut buf, value);
+        }
+        put_str(&mut buf, "adapter.lora.alpha");
+        buf.extend(6u32.to_le_bytes());
+        buf.extend(alpha.to_le_bytes());
+
+        let mut offset = 0u64;
+        for (name, dims, values) in &tensors {
+            put_str(&mut buf, name);
+            buf.extend(2u32.to_le_bytes());
+            for &d in dims {
+                buf.extend((d as u64).to_le_bytes());
+            }
+            buf.extend(0u32.to_le_bytes()); // F32
+            buf.extend(offset.to_le_bytes());
+            offset += (values.len() as u64 * 4).div_ceil(32) * 32;
+        }
+        buf.resize(buf.len().div_ceil(32) * 32, 0);
+        for (_, _, values) in &tensors {
+            for w in values.iter() {
+                buf.extend(w.to_le_bytes());
+            }
+            buf.resize(buf.len().div_ceil(32) * 32, 0);
+        }
+        std::fs::File::create(path).unwrap().write_all(&buf).unwrap();
+    }
+
+    #[test]
+    fn test_lora_matches_merged_weights() {
+        let (path, model) = load_variant("lora-base", Variant::arch("llama"));
+        let params = ModelParams::from_gguf(&model.gguf);
+        let rank = 2;
+        let deltas: Vec<_> = [("blk.0.attn_q.weight", DIM, DIM), ("blk.1.ffn_down.weight", DIM, HIDDEN)]
+            .into_iter()
+            .map(|(target, rows, cols)| {
+                let a = tensor_values(&format!("{target}.a"), rank * cols);
+                let b = tensor_values(&format!("{target}.b"), rows * rank);
+                (target, rank, cols, a, b)
+            })
+            .collect();
+        let adapter_path = std::env::temp_dir().join(format!("bizclaw-lora-{}.gguf", std::process::id()));
+        // alpha / rank = 2, times a user scale of 0.5
+        write_adapter(&adapter_path, 4.0, &deltas);
+        let mut weights = TransformerWeights::from_gguf(&model, &params);
+        weights.lora.add_adapter(&adapter_path, &model, 0.5).unwrap();
+        assert_eq!(weights.lora.len(), 2);
+
+        // Reference: the base file with W + B·A written into the adapted tensors
+        let mut bytes = std::fs::read(&path).unwrap();
+        for (target, rank, cols, a, b) in &deltas {
+            let info = model.gguf.tensors.iter().find(|t| t.name == *target).unwrap();
+            let start = (model.gguf.data_offset + info.offset) as usize;
+            for (i, w) in bytes[start..start + b.len() / rank * cols * 4].chunks_mut(4).enumerate() {
+                let (r, c) = (i / cols, i % cols);
+                let ba: f32 = (0..*rank).map(|k| b[r * rank + k] * a[k * cols + c]).sum();
+                let merged = f32::from_le_bytes(w.try_into().unwrap()) + ba;
+                w.copy_from_slice(&merged.to_le_bytes());
+            }
+        }
+        let merged_path = std::env::temp_dir().join(format!("bizclaw-lora-merged-{}.gguf", std::process::id()));
+        std::fs::write(&merged_path, bytes).unwrap();
+        let tokens = [3u32, 14, 7, 21, 2];
+        let expected = run(&MmapModel::load(&merged_path).unwrap(), &tokens, ComputeDtype::F32);
+        assert_ne!(expected, run(&model, &tokens, ComputeDtype::F32));
+
+        // Batched prefill (dense weights) with the adapter applied on the fly
+        let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, DIM / HEADS, KvCacheDtype::F32);
+        let mut logits = vec![0.0f32; tokens.len() * VOCAB];
+        forward_batch_all(
+            &model, &weights, &params, &mut cache, &tokens, 0, &mut logits,
+            ComputeDtype::F32, SoftmaxMode::Fast,
+        )
+        .unwrap();
+        for (row, exp) in logits.chunks(VOCAB).zip(&expected) {
+            assert_close(row, exp, "lora");
+        }
+
+        // Adapters must match the model's tensors
+        let bad = [("blk.9.attn_q.weight", rank, DIM, vec![0.0; rank * DIM], vec![0.0; DIM * rank])];
+        write_adapter(&adapter_path, 4.0, &bad);
+        assert!(LoraSet::default().add_adapter(&adapter_path, &model, 1.0).is_err());
+        let bad = [("blk.0.attn_q.weight", rank, HIDDEN, vec![0.0; rank * HIDDEN], vec![0.0; DIM * rank])];
+        write_adapter(&adapter_path, 4.0, &bad);
+        assert!(LoraSet::default().add_adapter(&adapter_path, &model, 1.0).is_err());
+
+        for p in [path, adapter_path, merged_path] {
+            let _ = std::fs::remove_file(p);
+        }
+    }
+
+    /// Offload device that keeps f32 weights in memory and counts matvecs.
+    #[derive(Default)]
+    struct CpuOffload {
+        weights: std::sync::Mutex<Vec<(Vec<f32>, usize, usize)>>,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl MatmulOffload for CpuOffload {
+        fn name(&self) -> String {
+            "test".into()
+        }
+
+        fn upload(&self, weight: &[f32], rows: usize, cols: usize) -> Result<OffloadId> {
+            let mut weights = self.weights.lock().unwrap();
+            weights.push((weight.to_vec(), rows, cols));
+            Ok(OffloadId(weights.len() - 1))
+        }
+
+        fn matvec(&self, id: OffloadId, input: &[f32], output: &mut [f32]) -> Result<()> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let (weight, rows, cols) = &self.weights.lock().unwrap()[id.0];
+            tensor::matmul(output, weight, input, *rows, *cols);
+            Ok(())
+        }
+
+        fn memory_usage(&self) -> usize {
+            self.weights.lock().unwrap().iter().map(|(w, _, _)| w.len() * 4).sum()
+        }
+    }
+
+    #[test]
+    fn test_offloaded_layers_match_cpu() {
+        let (path, model) = load_variant("offload", Variant::arch("llama"));
+        let params = ModelParams::from_gguf(&model.gguf);
+        let tokens = [6u32, 1, 13, 2, 9];
+        let expected = run(&model, &tokens, ComputeDtype::F32);
+
+        let device = Arc::new(CpuOffload::default());
+        let mut weights = TransformerWeights::from_gguf(&model, &params);
+        // More layers than the model has: all of them go
+        assert_eq!(offload_layers(&model, &mut weights, &params, device.clone(), 8).unwrap(), LAYERS);
+        let mut weights = TransformerWeights::from_gguf(&model, &params);
+        assert_eq!(offload_layers(&model, &mut weights, &params, device.clone(), 1).unwrap(), 1);
+        // q, k, v, o, gate, up, down of the last layer only
+        let offloaded = weights.offload.as_ref().unwrap();
+        assert_eq!(offloaded.tensors.len(), 7);
+        assert!(offloaded.tensors.contains_key(&weights.layers[1].ffn_down.unwrap()));
+        assert!(!offloaded.tensors.contains_key(&weights.layers[0].ffn_down.unwrap()));
+
+        let calls_before = device.calls.load(std::sync::atomic::Ordering::Relaxed);
+        let mut cache = KvCache::new(LAYERS, 64, KV_HEADS, DIM / HEADS, KvCacheDtype::F32);
+        for (pos, &t) in tokens.iter().enumerate() {
+            let mut logits = vec![0.0f32; VOCAB];
+            forward(&model, &weights, &params, &mut cache, t, pos, &mut logits, ComputeDtype::F32, SoftmaxMode::Fast)
+                .unwrap();
+            assert_close(&logits, &expected[pos], "offload decode");
+        }
+        assert_eq!(device.calls.load(std::sync::atomic::Ordering::Relaxed) - calls_before, 7 * tokens.len());
+
+        let _ = std::fs::remove_file(path);
+    }
 }
@@ -11,6 +11,7 @@
 //! - Tensor data
 
 use bizclaw_core::error::{BizClawError, Result};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::io::{Read, Seek};
 
@@ -166,6 +167,102 @@ impl GgmlType {
             _ => 0,
         }
     }
+
+    /// Short display name (llama.cpp naming, e.g. "Q4_K").
+    pub fn name(&self) -> &'static str {
+        match self {
+            GgmlType::F32 => "F32",
+            GgmlType::F16 => "F16",
+            GgmlType::Q4_0 => "Q4_0",
+            GgmlType::Q4_1 => "Q4_1",
+            GgmlType::Q5_0 => "Q5_0",
+            GgmlType::Q5_1 => "Q5_1",
+            GgmlType::Q8_0 => "Q8_0",
+            GgmlType::Q8_1 => "Q8_1",
+            GgmlType::Q2K => "Q2_K",
+            GgmlType::Q3K => "Q3_K",
+            GgmlType::Q4K => "Q4_K",
+            GgmlType::Q5K => "Q5_K",
+            GgmlType::Q6K => "Q6_K",
+            GgmlType::Q8K => "Q8_K",
+            GgmlType::IQ2XXS => "IQ2_XXS",
+            GgmlType::IQ2XS => "IQ2_XS",
+            GgmlType::IQ3XXS => "IQ3_XXS",
+            GgmlType::IQ1S => "IQ1_S",
+            GgmlType::IQ4NL => "IQ4_NL",
+            GgmlType::IQ3S => "IQ3_S",
+            GgmlType::IQ2S => "IQ2_S",
+            GgmlType::IQ4XS => "IQ4_XS",
+        }
+    }
+}
+
+/// Name of a `general.file_type` value (llama.cpp `LLAMA_FTYPE_*`).
+pub fn file_type_name(ftype: u32) -> Option<&'static str> {
+    Some(match ftype {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        _ => return None,
+    })
+}
+
+/// Quantization mix of a model — dominant type plus per-role breakdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantSummary {
+    /// Declared file type from `general.file_type` (e.g. "Q4_K_M").
+    pub file_type: Option<String>,
+    /// Type covering the most weight elements.
+    pub dominant: GgmlType,
+    /// Dominant type per tensor role: embd, attn, ffn, output.
+    pub mix: Vec<(String, GgmlType)>,
+}
+
+impl QuantSummary {
+    /// Label shown to users: declared file type, else the dominant type.
+    pub fn label(&self) -> &str {
+        self.file_type.as_deref().unwrap_or(self.dominant.name())
+    }
+
+    /// Whether the declared file type agrees with the tensors actually stored.
+    /// "Q4_K_M" is consistent with a Q4_K-dominant model.
+    pub fn is_consistent(&self) -> bool {
+        match &self.file_type {
+            Some(ft) => {
+                let dominant = self.dominant.name();
+                ft == dominant || ft.starts_with(&format!("{dominant}_"))
+            }
+            None => true,
+        }
+    }
+}
+
+impl std::fmt::Display for QuantSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())?;
+        let mix: Vec<String> = self
+            .mix
+            .iter()
+            .map(|(role, t)| format!("{role} {}", t.name()))
+            .collect();
+        if !mix.is_empty() {
+            write!(f, ": {}", mix.join(", "))?;
+        }
+        Ok(())
+    }
 }
 
 /// Information about a tensor stored in the GGUF file.
@@ -278,6 +375,13 @@ impl GgufFile {
         })
     }
 
+    /// Parse only the header (metadata + tensor index) of a GGUF file on disk.
+    pub fn open(path: &std::path::Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| BizClawError::GgufParse(format!("Failed to open {}: {e}", path.display())))?;
+        Self::parse(&mut std::io::BufReader::new(file))
+    }
+
     /// Get model architecture name.
     pub fn architecture(&self) -> Option<&str> {
         self.metadata.get("general.architecture")?.as_str()
@@ -288,6 +392,67 @@ impl GgufFile {
         self.metadata.get("general.name")?.as_str()
     }
 
+    /// Summarize the quantization mix from tensor types.
+    /// Returns `None` for files without tensors.
+    pub fn quant_summary(&self) -> Option<QuantSummary> {
+        fn role(name: &str) -> Option<&'static str> {
+            if name.contains("norm") {
+                None // norms are always F32 — not informative
+            } else if name.starts_with("token_embd") {
+                Some("embd")
+            } else if name.starts_with("output") {
+                Some("output")
+            } else if name.contains(".attn_") {
+                Some("attn")
+            } else if name.contains(".ffn_") {
+                Some("ffn")
+            } else {
+                None
+            }
+        }
+
+        fn heaviest(counts: &[(GgmlType, u64)]) -> Option<GgmlType> {
+            counts.iter().max_by_key(|(_, n)| *n).map(|(t, _)| *t)
+        }
+
+        fn add(counts: &mut Vec<(GgmlType, u64)>, t: GgmlType, n: u64) {
+            match counts.iter_mut().find(|(ct, _)| *ct == t) {
+                Some((_, c)) => *c += n,
+                None => counts.push((t, n)),
+            }
+        }
+
+        let mut overall: Vec<(GgmlType, u64)> = Vec::new();
+        let roles = ["embd", "attn", "ffn", "output"];
+        let mut per_role: Vec<Vec<(GgmlType, u64)>> = vec![Vec::new(); roles.len()];
+
+        for t in &self.tensors {
+            let n = t.n_elements();
+            let Some(r) = role(&t.name) else { continue };
+            add(&mut overall, t.ggml_type, n);
+            if let Some(i) = roles.iter().position(|x| *x == r) {
+                add(&mut per_role[i], t.ggml_type, n);
+            }
+        }
+
+        let dominant = heaviest(&overall)?;
+        let mix = roles
+            .iter()
+            .zip(per_role.iter())
+            .filter_map(|(r, counts)| heaviest(counts).map(|t| (r.to_string(), t)))
+            .collect();
+        let file_type = self
+            .get_u32("general.file_type")
+            .and_then(file_type_name)
+            .map(String::from);
+
+        Some(QuantSummary {
+            file_type,
+            dominant,
+            mix,
+        })
+    }
+
     /// Get a u32 metadata value with a key prefix.
     pub fn get_u32(&self, key: &str) -> Option<u32> {
         self.metadata.get(key)?.as_u32()
@@ -299,6 +464,124 @@ impl GgufFile {
     }
 }
 
+/// Structured model details read from a GGUF header, for model listings
+/// and `bizclaw brain info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct GgufInfo {
+    pub version: u32,
+    /// `general.name`.
+    pub name: Option<String>,
+    /// `general.architecture`.
+    pub architecture: Option<String>,
+    pub file_size: u64,
+    /// Total weight elements across all tensors.
+    pub parameters: u64,
+    /// `{arch}.vocab_size`, else the tokenizer's token count.
+    pub vocab_size: Option<u32>,
+    /// `{arch}.context_length` (training context).
+    pub context_length: Option<u32>,
+    pub embedding_length: Option<u32>,
+    pub block_count: Option<u32>,
+    pub head_count: Option<u32>,
+    pub head_count_kv: Option<u32>,
+    /// Quantization label, e.g. "Q4_K_M".
+    pub quant: Option<String>,
+    /// Dominant type per tensor role, e.g. "Q4_K_M: embd Q4_K, attn Q4_K, ...".
+    pub quant_mix: Option<String>,
+    /// Tensor count per storage type, most elements first.
+    pub tensor_types: Vec<(String, usize)>,
+    pub tensors: Vec<TensorSummary>,
+    /// `tokenizer.ggml.model` (e.g. "llama", "gpt2").
+    pub tokenizer: Option<String>,
+    /// Raw `tokenizer.chat_template` source.
+    pub chat_template: Option<String>,
+    /// Prompt format the engine will use for chat.
+    pub chat_format: &'static str,
+}
+
+/// One tensor of [`GgufInfo`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TensorSummary {
+    pub name: String,
+    pub ggml_type: &'static str,
+    pub dims: Vec<u64>,
+    pub size_bytes: u64,
+}
+
+/// Read the header of the GGUF file at `path` into a [`GgufInfo`], without
+/// mapping the tensor data.
+pub fn inspect(path: &std::path::Path) -> Result<GgufInfo> {
+    let gguf = GgufFile::open(path)?;
+    let file_size = std::fs::metadata(path)
+        .map_err(|e| BizClawError::GgufParse(format!("Failed to stat {}: {e}", path.display())))?
+        .len();
+    Ok(gguf.info(file_size))
+}
+
+impl GgufFile {
+    /// Structured details of this file; `file_size` is the size on disk.
+    pub fn info(&self, file_size: u64) -> GgufInfo {
+        let arch = self.architecture().map(String::from);
+        let prefix = arch.as_deref().unwrap_or("llama");
+        let arch_u32 = |key: &str| self.get_u32(&format!("{prefix}.{key}"));
+        let meta_str = |key: &str| self.metadata.get(key).and_then(|v| v.as_str()).map(String::from);
+        let vocab_size = arch_u32("vocab_size").or_else(|| match self.metadata.get("tokenizer.ggml.tokens") {
+            Some(GgufValue::Array(tokens)) => Some(tokens.len() as u32),
+            _ => None,
+        });
+
+        let mut by_type: Vec<(GgmlType, usize, u64)> = Vec::new();
+        for t in &self.tensors {
+            match by_type.iter_mut().find(|(ty, _, _)| *ty == t.ggml_type) {
+                Some((_, count, elements)) => {
+                    *count += 1;
+                    *elements += t.n_elements();
+                }
+                None => by_type.push((t.ggml_type, 1, t.n_elements())),
+            }
+        }
+        by_type.sort_by_key(|&(_, _, elements)| std::cmp::Reverse(elements));
+
+        let quant = self.quant_summary();
+        let chat_template = meta_str("tokenizer.chat_template");
+        let chat_format =
+            crate::tokenizer::ChatTemplate::detect(chat_template.as_deref(), prefix).name();
+
+        GgufInfo {
+            version: self.version,
+            name: self.model_name().map(String::from),
+            architecture: arch.clone(),
+            file_size,
+            parameters: self.tensors.iter().map(|t| t.n_elements()).sum(),
+            vocab_size,
+            context_length: arch_u32("context_length"),
+            embedding_length: arch_u32("embedding_length"),
+            block_count: arch_u32("block_count"),
+            head_count: arch_u32("attention.head_count"),
+            head_count_kv: arch_u32("attention.head_count_kv"),
+            quant: quant.as_ref().map(|q| q.label().to_string()),
+            quant_mix: quant.as_ref().map(|q| q.to_string()),
+            tensor_types: by_type
+                .iter()
+                .map(|(ty, count, _)| (ty.name().to_string(), *count))
+                .collect(),
+            tensors: self
+                .tensors
+                .iter()
+                .map(|t| TensorSummary {
+                    name: t.name.clone(),
+                    ggml_type: t.ggml_type.name(),
+                    dims: t.dims.clone(),
+                    size_bytes: t.size_bytes(),
+                })
+                .collect(),
+            tokenizer: meta_str("tokenizer.ggml.model"),
+            chat_template,
+            chat_format,
+        }
+    }
+}
+
 // ===== Low-level reading helpers =====
 
 fn read_u8<R: Read>(r: &mut R) -> Result<u8> {
@@ -420,3 +703,103 @@ fn read_value<R: Read>(r: &mut R) -> Result<GgufValue> {
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tensor(name: &str, dims: &[u64], ggml_type: GgmlType) -> TensorInfo {
+        TensorInfo {
+            name: name.into(),
+            n_dims: dims.len() as u32,
+            dims: dims.to_vec(),
+            ggml_type,
+            offset: 0,
+        }
+    }
+
+    /// Tensor layout of a typical llama.cpp Q4_K_M export.
+    fn q4_k_m_fixture() -> GgufFile {
+        let mut metadata = HashMap::new();
+        metadata.insert("general.file_type".to_string(), GgufValue::U32(15));
+        GgufFile {
+            version: 3,
+            metadata,
+            tensors: vec![
+                tensor("token_embd.weight", &[256, 1000], GgmlType::Q4K),
+                tensor("blk.0.attn_norm.weight", &[256], GgmlType::F32),
+                tensor("blk.0.attn_q.weight", &[256, 256], GgmlType::Q4K),
+                tensor("blk.0.attn_k.weight", &[256, 64], GgmlType::Q4K),
+                tensor("blk.0.attn_v.weight", &[256, 64], GgmlType::Q6K),
+                tensor("blk.0.attn_output.weight", &[256, 256], GgmlType::Q4K),
+                tensor("blk.0.ffn_gate.weight", &[256, 768], GgmlType::Q4K),
+                tensor("blk.0.ffn_up.weight", &[256, 768], GgmlType::Q4K),
+                tensor("blk.0.ffn_down.weight", &[768, 256], GgmlType::Q6K),
+                tensor("output_norm.weight", &[256], GgmlType::F32),
+                tensor("output.weight", &[256, 1000], GgmlType::F16),
+            ],
+            data_offset: 0,
+            alignment: 32,
+        }
+    }
+
+    #[test]
+    fn test_quant_summary_matches_tensor_types() {
+        let summary = q4_k_m_fixture().quant_summary().unwrap();
+        assert_eq!(summary.dominant, GgmlType::Q4K);
+        assert!(summary.is_consistent());
+        assert_eq!(
+            summary.to_string(),
+            "Q4_K_M: embd Q4_K, attn Q4_K, ffn Q4_K, output F16"
+        );
+    }
+
+    #[test]
+    fn test_quant_summary_without_file_type() {
+        let mut gguf = q4_k_m_fixture();
+        gguf.metadata.clear();
+        let summary = gguf.quant_summary().unwrap();
+        assert_eq!(summary.label(), "Q4_K");
+    }
+
+    #[test]
+    fn test_info_reports_model_details() {
+        let mut gguf = q4_k_m_fixture();
+        for (key, value) in [
+            ("general.architecture", GgufValue::String("qwen2".into())),
+            ("general.name", GgufValue::String("Tiny Qwen".into())),
+            ("qwen2.context_length", GgufValue::U32(32768)),
+            ("qwen2.block_count", GgufValue::U32(1)),
+            ("tokenizer.ggml.model", GgufValue::String("gpt2".into())),
+            (
+                "tokenizer.ggml.tokens",
+                GgufValue::Array(vec![GgufValue::String("a".into()); 1000]),
+            ),
+        ] {
+            gguf.metadata.insert(key.to_string(), value);
+        }
+        let info = gguf.info(4096);
+        assert_eq!(info.architecture.as_deref(), Some("qwen2"));
+        assert_eq!(info.name.as_deref(), Some("Tiny Qwen"));
+        assert_eq!(info.context_length, Some(32768));
+        assert_eq!(info.vocab_size, Some(1000));
+        assert_eq!(info.quant.as_deref(), Some("Q4_K_M"));
+        // No template in the file: Qwen2's usual format
+        assert_eq!(info.chat_template, None);
+        assert_eq!(info.chat_format, "chatml");
+        assert_eq!(info.tensor_types[0], ("Q4_K".to_string(), 6));
+        assert_eq!(info.tensors.len(), 11);
+        assert_eq!(info.tensors[0].ggml_type, "Q4_K");
+        let expected: u64 = 256 * 1000 * 2 + 256 * 256 * 2 + 256 * 64 * 2 + 256 * 768 * 3 + 512;
+        assert_eq!(info.parameters, expected);
+        assert!(serde_json::to_value(&info).is_ok());
+    }
+
+    #[test]
+    fn test_quant_summary_inconsistent_file_type() {
+        let mut gguf = q4_k_m_fixture();
+        gguf.metadata
+            .insert("general.file_type".to_string(), GgufValue::U32(7)); // Q8_0
+        assert!(!gguf.quant_summary().unwrap().is_consistent());
+    }
+}
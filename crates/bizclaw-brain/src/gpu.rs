@@ -0,0 +1,338 @@
+//! wgpu offload backend (Vulkan, Metal, DX12) for [`MatmulOffload`].
+//!
+//! Weights are stored on the device as f16 pairs packed into `u32`s and
+//! unpacked with WGSL's `unpack2x16float`, so no shader-f16 support is
+//! needed. Each matvec runs one 64-thread workgroup per output row with a
+//! shared-memory reduction, then reads the result back; calls are
+//! synchronous, matching the CPU kernels they replace.
+
+use crate::tensor::{MatmulOffload, OffloadId};
+use bizclaw_core::error::{BizClawError, Result};
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Dims {
+    rows: u32,
+    // u32 words per row: ceil(cols / 2)
+    pairs: u32,
+    // workgroups per dispatch row (rows beyond 65535 spill into y)
+    stride: u32,
+}
+
+@group(0) @binding(0) var<storage, read> weight: array<u32>;
+@group(0) @binding(1) var<storage, read> input: array<f32>;
+@group(0) @binding(2) var<storage, read_write> output: array<f32>;
+@group(0) @binding(3) var<uniform> dims: Dims;
+
+var<workgroup> partial: array<f32, 64>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(workgroup_id) wg: vec3<u32>, @builtin(local_invocation_index) lid: u32) {
+    let row = wg.y * dims.stride + wg.x;
+    var sum = 0.0;
+    if (row < dims.rows) {
+        let base = row * dims.pairs;
+        for (var j = lid; j < dims.pairs; j += 64u) {
+            let w = unpack2x16float(weight[base + j]);
+            sum += w.x * input[2u * j] + w.y * input[2u * j + 1u];
+        }
+    }
+    partial[lid] = sum;
+    workgroupBarrier();
+    for (var s = 32u; s > 0u; s >>= 1u) {
+        if (lid < s) {
+            partial[lid] += partial[lid + s];
+        }
+        workgroupBarrier();
+    }
+    if (lid == 0u && row < dims.rows) {
+        output[row] = partial[0];
+    }
+}
+"#;
+
+/// Most workgroups per dispatch dimension guaranteed by WebGPU.
+const MAX_GROUPS: u32 = 65535;
+
+/// One uploaded weight.
+struct GpuMatrix {
+    weight: wgpu::Buffer,
+    dims: wgpu::Buffer,
+    rows: usize,
+    cols: usize,
+}
+
+/// Per-call buffers, grown to the largest matrix seen.
+#[derive(Default)]
+struct Scratch {
+    input: Option<wgpu::Buffer>,
+    output: Option<wgpu::Buffer>,
+    readback: Option<wgpu::Buffer>,
+}
+
+/// Reuse `slot` if it holds at least `size` bytes, else replace it.
+fn scratch_buffer<'a>(
+    device: &wgpu::Device,
+    slot: &'a mut Option<wgpu::Buffer>,
+    size: u64,
+    usage: wgpu::BufferUsages,
+) -> &'a wgpu::Buffer {
+    if slot.as_ref().is_none_or(|b| b.size() < size) {
+        *slot = Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bizclaw-scratch"),
+            size,
+            usage,
+            mapped_at_creation: false,
+        }));
+    }
+    slot.as_ref().expect("scratch buffer was just created")
+}
+
+/// A GPU picked through wgpu, running f16 matvecs.
+pub struct WgpuOffload {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter_name: String,
+    matrices: Mutex<Vec<GpuMatrix>>,
+    scratch: Mutex<Scratch>,
+}
+
+impl WgpuOffload {
+    /// Open the highest-performance adapter wgpu finds. Software
+    /// rasterizers (llvmpipe, WARP) are refused: they are slower than the
+    /// CPU kernels.
+    pub fn new() -> Result<Self> {
+        Self::open(false)
+    }
+
+    fn open(allow_software: bool) -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let adapter = futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .map_err(|e| BizClawError::Brain(format!("No GPU adapter: {e}")))?;
+        let info = adapter.get_info();
+        if info.device_type == wgpu::DeviceType::Cpu && !allow_software {
+            return Err(BizClawError::Brain(format!(
+                "Only a software adapter is available ({})",
+                info.name
+            )));
+        }
+
+        let (device, queue) = futures::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("bizclaw-brain"),
+            // Large FFN matrices need more than the default 128 MB bindings
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .map_err(|e| BizClawError::Brain(format!("Failed to open GPU {}: {e}", info.name)))?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bizclaw-matvec"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bizclaw-matvec"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            adapter_name: format!("{} ({:?})", info.name, info.backend),
+            matrices: Mutex::new(Vec::new()),
+            scratch: Mutex::new(Scratch::default()),
+        })
+    }
+}
+
+impl MatmulOffload for WgpuOffload {
+    fn name(&self) -> String {
+        self.adapter_name.clone()
+    }
+
+    fn upload(&self, weight: &[f32], rows: usize, cols: usize) -> Result<OffloadId> {
+        debug_assert_eq!(weight.len(), rows * cols);
+        let pairs = cols.div_ceil(2);
+        let limits = self.device.limits();
+        let bytes = (rows * pairs * 4) as u64;
+        if bytes > limits.max_storage_buffer_binding_size {
+            return Err(BizClawError::Brain(format!(
+                "Weight [{rows} x {cols}] ({} MB) exceeds the GPU's {} MB buffer limit",
+                bytes / 1024 / 1024,
+                limits.max_storage_buffer_binding_size / 1024 / 1024
+            )));
+        }
+
+        // Rows padded to an even length; .x of each pair is the low half
+        let mut packed = Vec::with_capacity(rows * pairs * 4);
+        for row in weight.chunks(cols) {
+            for pair in row.chunks(2) {
+                let lo = half::f16::from_f32(pair[0]).to_bits() as u32;
+                let hi = pair.get(1).map_or(0, |&v| half::f16::from_f32(v).to_bits() as u32);
+                packed.extend_from_slice(&(lo | (hi << 16)).to_le_bytes());
+            }
+        }
+        let weight = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("bizclaw-weight"),
+            contents: &packed,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let groups = rows as u32;
+        let dims: Vec<u8> = [groups, pairs as u32, groups.min(MAX_GROUPS), 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let dims = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("bizclaw-dims"),
+            contents: &dims,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut matrices = self.matrices.lock().unwrap_or_else(|e| e.into_inner());
+        matrices.push(GpuMatrix {
+            weight,
+            dims,
+            rows,
+            cols,
+        });
+        Ok(OffloadId(matrices.len() - 1))
+    }
+
+    fn matvec(&self, id: OffloadId, input: &[f32], output: &mut [f32]) -> Result<()> {
+        let matrices = self.matrices.lock().unwrap_or_else(|e| e.into_inner());
+        let m = matrices
+            .get(id.0)
+            .ok_or_else(|| BizClawError::Brain(format!("Unknown GPU weight {}", id.0)))?;
+        debug_assert_eq!(input.len(), m.cols);
+        debug_assert_eq!(output.len(), m.rows);
+
+        let mut input_bytes: Vec<u8> = input.iter().flat_map(|v| v.to_le_bytes()).collect();
+        if m.cols % 2 == 1 {
+            input_bytes.extend_from_slice(&0f32.to_le_bytes());
+        }
+        let out_bytes = (m.rows * 4) as u64;
+
+        let mut scratch = self.scratch.lock().unwrap_or_else(|e| e.into_inner());
+        let Scratch {
+            input: input_slot,
+            output: output_slot,
+            readback: readback_slot,
+        } = &mut *scratch;
+        let input_buf = scratch_buffer(
+            &self.device,
+            input_slot,
+            input_bytes.len() as u64,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let output_buf = scratch_buffer(
+            &self.device,
+            output_slot,
+            out_bytes,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let readback = scratch_buffer(
+            &self.device,
+            readback_slot,
+            out_bytes,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+        self.queue.write_buffer(input_buf, 0, &input_bytes);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bizclaw-matvec"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: m.weight.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: input_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: output_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: m.dims.as_entire_binding(),
+                },
+            ],
+        });
+
+        let rows = m.rows as u32;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(rows.min(MAX_GROUPS), rows.div_ceil(MAX_GROUPS), 1);
+        }
+        encoder.copy_buffer_to_buffer(output_buf, 0, readback, 0, out_bytes);
+        self.queue.submit([encoder.finish()]);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        readback.map_async(wgpu::MapMode::Read, ..out_bytes, move |r| {
+            let _ = tx.send(r);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| BizClawError::Brain(format!("GPU wait failed: {e}")))?;
+        rx.recv()
+            .map_err(|_| BizClawError::Brain("GPU readback was dropped".into()))?
+            .map_err(|e| BizClawError::Brain(format!("GPU readback failed: {e}")))?;
+        {
+            let data = readback.get_mapped_range(..out_bytes);
+            for (o, b) in output.iter_mut().zip(data.chunks_exact(4)) {
+                *o = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+            }
+        }
+        readback.unmap();
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> usize {
+        let matrices = self.matrices.lock().unwrap_or_else(|e| e.into_inner());
+        matrices.iter().map(|m| m.weight.size() as usize).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matvec_matches_cpu() {
+        // Any adapter will do for correctness; machines without one skip
+        let Ok(gpu) = WgpuOffload::open(true) else {
+            return;
+        };
+        let (rows, cols) = (70, 33);
+        let weight: Vec<f32> = (0..rows * cols).map(|i| ((i * 7) as f32).sin() * 0.5).collect();
+        let input: Vec<f32> = (0..cols).map(|i| (i as f32 * 0.3).cos()).collect();
+        let id = gpu.upload(&weight, rows, cols).unwrap();
+
+        let mut expected = vec![0.0f32; rows];
+        crate::tensor::matmul(&mut expected, &weight, &input, rows, cols);
+        let mut got = vec![0.0f32; rows];
+        gpu.matvec(id, &input, &mut got).unwrap();
+        for (g, e) in got.iter().zip(&expected) {
+            // f16 weights
+            assert!((g - e).abs() < 1e-2, "{g} vs {e}");
+        }
+    }
+}
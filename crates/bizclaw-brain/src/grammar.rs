@@ -1,16 +1,112 @@
 //! Grammar-Constrained JSON Decoding.
 //!
-//! Pre-analyzes vocabulary tokens at load time for JSON structure properties
-//! (brace delta, bracket delta, quote parity). During generation, masks logits
-//! to guarantee syntactically valid JSON — essential for tool calling with small models.
+//! Pre-analyzes vocabulary tokens at load time (decoded bytes plus cheap
+//! structure properties: brace delta, bracket delta, quote parity). During
+//! generation an incremental JSON parser runs every candidate token against
+//! the current parse state and masks the logits of tokens that would make the
+//! output invalid — essential for tool calling with small models.
+//!
+//! An optional JSON schema narrows the grammar further. Supported keywords:
+//! `type`, `properties`, `required`, `additionalProperties: false`, `items`
+//! and string `enum`s; anything else is accepted but not enforced.
+//!
+//! Arbitrary grammars can be supplied in llama.cpp's GBNF format
+//! ([`GbnfRules`] + [`GbnfGrammar`]); both grammar kinds implement
+//! [`TokenGrammar`], which the sampler uses to mask logits.
+
+use bizclaw_core::error::{BizClawError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Longest run of whitespace allowed between JSON tokens, so a small model
+/// can't stall forever emitting newlines.
+const MAX_WS_RUN: u8 = 32;
+
+/// A grammar that constrains which tokens may be sampled next.
+pub trait TokenGrammar: Send {
+    /// Mask logits of tokens that would violate the grammar to -inf.
+    fn apply_mask(&self, logits: &mut [f32]);
+    /// Update state after a token is selected.
+    /// Returns false if the token is not a valid continuation.
+    fn accept_token(&mut self, token_id: usize) -> bool;
+    /// Nothing more can be generated.
+    fn is_complete(&self) -> bool;
+    /// Decode generated tokens to text using the grammar's byte view.
+    fn decode(&self, tokens: &[u32]) -> String;
+}
+
+/// Decoded bytes of every vocabulary token, shared by all grammars.
+#[derive(Debug, Clone)]
+pub struct TokenTable {
+    /// `None` for control tokens that never produce text.
+    bytes: Arc<Vec<Option<Vec<u8>>>>,
+    /// End-of-sequence token, allowed once the grammar can end.
+    eos_id: Option<u32>,
+}
+
+impl TokenTable {
+    /// Decode a raw vocabulary (no special tokens known).
+    pub fn new(vocab: &[String]) -> Self {
+        let bytes = vocab
+            .iter()
+            .map(|piece| Some(piece_bytes(piece)).filter(|b| !b.is_empty()))
+            .collect();
+        Self::from_bytes(bytes, None)
+    }
+
+    /// Decode a loaded tokenizer's vocabulary; BOS/EOS/PAD never produce text.
+    pub fn for_tokenizer(tokenizer: &crate::tokenizer::BpeTokenizer) -> Self {
+        let bytes = (0..tokenizer.vocab_size() as u32)
+            .map(|id| {
+                if tokenizer.is_special(id) {
+                    None
+                } else {
+                    Some(piece_bytes(tokenizer.decode_token(id))).filter(|b| !b.is_empty())
+                }
+            })
+            .collect();
+        Self::from_bytes(bytes, Some(tokenizer.eos_id))
+    }
+
+    fn from_bytes(bytes: Vec<Option<Vec<u8>>>, eos_id: Option<u32>) -> Self {
+        Self {
+            bytes: Arc::new(bytes),
+            eos_id,
+        }
+    }
+
+    /// Text bytes of a token (`None` for control or unknown tokens).
+    fn get(&self, token_id: usize) -> Option<&[u8]> {
+        self.bytes.get(token_id).and_then(|b| b.as_deref())
+    }
+
+    fn is_eos(&self, token_id: usize) -> bool {
+        self.eos_id == Some(token_id as u32)
+    }
+
+    /// Decode tokens to text (invalid UTF-8 is replaced).
+    pub fn decode(&self, tokens: &[u32]) -> String {
+        let bytes: Vec<u8> = tokens
+            .iter()
+            .filter_map(|&t| self.get(t as usize))
+            .flatten()
+            .copied()
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
 
 /// JSON grammar state machine for constrained decoding.
 #[derive(Debug, Clone)]
 pub struct JsonGrammar {
     /// Pre-computed token properties: (brace_delta, bracket_delta, quote_parity)
-    token_props: Vec<TokenJsonProps>,
-    /// Current grammar state
-    state: JsonState,
+    token_props: Arc<Vec<TokenJsonProps>>,
+    tokens: TokenTable,
+    /// Compiled schema (node 0 is the root).
+    schema: Arc<Schema>,
+    /// Current parse state
+    parser: Parser,
 }
 
 /// Pre-computed JSON properties per vocabulary token.
@@ -24,144 +120,1253 @@ pub struct TokenJsonProps {
     pub is_whitespace_only: bool,
 }
 
-/// JSON parsing state — tracks structure validity.
-#[derive(Debug, Clone)]
-#[derive(Default)]
-pub struct JsonState {
-    pub brace_depth: i32,
-    pub bracket_depth: i32,
-    pub in_string: bool,
-    pub expect_value: bool,
-    pub started: bool,
-    pub completed: bool,
+impl TokenJsonProps {
+    /// Analyze a token's bytes, assuming it starts outside a string.
+    fn analyze(bytes: &[u8]) -> Self {
+        let mut props = TokenJsonProps::default();
+        let mut in_str = false;
+        let mut prev_escape = false;
+
+        for &b in bytes {
+            if in_str {
+                if b == b'"' && !prev_escape {
+                    in_str = false;
+                    props.quote_toggle = !props.quote_toggle;
+                }
+                prev_escape = b == b'\\' && !prev_escape;
+            } else {
+                match b {
+                    b'{' => props.brace_delta += 1,
+                    b'}' => props.brace_delta -= 1,
+                    b'[' => props.bracket_delta += 1,
+                    b']' => props.bracket_delta -= 1,
+                    b'"' => {
+                        in_str = true;
+                        props.quote_toggle = !props.quote_toggle;
+                    }
+                    b':' => props.is_colon = true,
+                    b',' => props.is_comma = true,
+                    _ => {}
+                }
+            }
+        }
+        props.is_whitespace_only = bytes.iter().all(|b| is_ws(*b));
+        props
+    }
 }
 
+/// Decode a SentencePiece vocabulary entry to the bytes it emits:
+/// `▁` is a space and `<0xNN>` is a raw byte.
+pub fn piece_bytes(piece: &str) -> Vec<u8> {
+    if let Some(hex) = piece.strip_prefix("<0x").and_then(|s| s.strip_suffix('>'))
+        && hex.len() == 2
+        && let Ok(b) = u8::from_str_radix(hex, 16)
+    {
+        return vec![b];
+    }
+    piece.replace('\u{2581}', " ").into_bytes()
+}
 
 impl JsonGrammar {
     /// Analyze all tokens in vocabulary for JSON properties (done once at load).
     pub fn new(vocab: &[String]) -> Self {
-        let token_props: Vec<TokenJsonProps> = vocab
+        Self::from_tokens(TokenTable::new(vocab))
+    }
+
+    /// Build from a loaded tokenizer's vocabulary.
+    pub fn for_tokenizer(tokenizer: &crate::tokenizer::BpeTokenizer) -> Self {
+        Self::from_tokens(TokenTable::for_tokenizer(tokenizer))
+    }
+
+    pub fn from_tokens(tokens: TokenTable) -> Self {
+        let token_props = tokens
+            .bytes
             .iter()
-            .map(|token| {
-                let mut props = TokenJsonProps::default();
-                let mut in_str = false;
-                let mut prev_escape = false;
-
-                for ch in token.chars() {
-                    if in_str {
-                        if ch == '"' && !prev_escape {
-                            in_str = false;
-                            props.quote_toggle = !props.quote_toggle;
-                        }
-                        prev_escape = ch == '\\' && !prev_escape;
-                    } else {
-                        match ch {
-                            '{' => props.brace_delta += 1,
-                            '}' => props.brace_delta -= 1,
-                            '[' => props.bracket_delta += 1,
-                            ']' => props.bracket_delta -= 1,
-                            '"' => {
-                                in_str = true;
-                                props.quote_toggle = !props.quote_toggle;
-                            }
-                            ':' => props.is_colon = true,
-                            ',' => props.is_comma = true,
-                            _ => {}
-                        }
-                    }
-                }
-                props.is_whitespace_only = token.trim().is_empty();
-                props
-            })
+            .map(|b| b.as_deref().map(TokenJsonProps::analyze).unwrap_or_default())
             .collect();
-
         Self {
-            token_props,
-            state: JsonState::default(),
+            token_props: Arc::new(token_props),
+            tokens,
+            schema: Arc::new(Schema::any_container()),
+            parser: Parser::new(),
         }
     }
 
+    /// The vocabulary this grammar masks.
+    pub fn tokens(&self) -> &TokenTable {
+        &self.tokens
+    }
+
+    /// A fresh grammar over the same vocabulary, constrained by a JSON schema.
+    pub fn with_schema(&self, schema: &Value) -> Result<Self> {
+        Ok(Self {
+            token_props: self.token_props.clone(),
+            tokens: self.tokens.clone(),
+            schema: Arc::new(Schema::compile(schema)?),
+            parser: Parser::new(),
+        })
+    }
+
+    /// Reset grammar state for new generation.
+    pub fn reset(&mut self) {
+        self.parser = Parser::new();
+    }
+}
+
+impl TokenGrammar for JsonGrammar {
     /// Mask logits to only allow tokens that maintain valid JSON.
-    pub fn apply_mask(&self, logits: &mut [f32]) {
-        if self.state.completed {
+    fn apply_mask(&self, logits: &mut [f32]) {
+        if self.parser.expect == Expect::End {
             return; // JSON is complete, let EOS through
         }
 
-        for (i, logit) in logits.iter_mut().enumerate() {
-            if i >= self.token_props.len() {
-                break;
-            }
-            let props = &self.token_props[i];
+        let (brace_depth, bracket_depth) = self.parser.depths();
+        let in_string = matches!(self.parser.lex, Lex::Str { .. });
+        let can_end = self.parser.can_end();
 
-            let allowed = self.is_token_allowed(props);
+        for (i, logit) in logits.iter_mut().enumerate() {
+            let allowed = match self.tokens.get(i) {
+                Some(bytes) => {
+                    let props = &self.token_props[i];
+                    // Cheap reject before simulating: closes more than is open
+                    let overcloses = !in_string
+                        && (brace_depth + props.brace_delta < 0
+                            || bracket_depth + props.bracket_delta < 0);
+                    !overcloses && self.parser.clone().feed(bytes, &self.schema)
+                }
+                None => can_end && self.tokens.is_eos(i),
+            };
             if !allowed {
                 *logit = f32::NEG_INFINITY;
             }
         }
     }
 
-    /// Check if a token is structurally valid given current state.
-    fn is_token_allowed(&self, props: &TokenJsonProps) -> bool {
-        // In a string: almost anything is allowed
-        if self.state.in_string {
+    fn accept_token(&mut self, token_id: usize) -> bool {
+        match self.tokens.get(token_id) {
+            Some(bytes) => self.parser.feed(bytes, &self.schema),
+            None => false,
+        }
+    }
+
+    /// Check if JSON generation is complete.
+    fn is_complete(&self) -> bool {
+        self.parser.expect == Expect::End
+    }
+
+    fn decode(&self, tokens: &[u32]) -> String {
+        self.tokens.decode(tokens)
+    }
+}
+
+// ── Schema ─────────────────────────────────────────────────
+
+const T_OBJECT: u8 = 1 << 0;
+const T_ARRAY: u8 = 1 << 1;
+const T_STRING: u8 = 1 << 2;
+const T_NUMBER: u8 = 1 << 3;
+const T_INTEGER: u8 = 1 << 4;
+const T_BOOLEAN: u8 = 1 << 5;
+const T_NULL: u8 = 1 << 6;
+
+/// Schema compiled to an index-linked node list, so parse state can refer to
+/// nodes by index and stay cheap to clone.
+#[derive(Debug, Default)]
+struct Schema {
+    nodes: Vec<SchemaNode>,
+}
+
+#[derive(Debug, Default)]
+struct SchemaNode {
+    /// Allowed value types (0 = any).
+    types: u8,
+    /// Property names (JSON-escaped, without quotes) and their schemas.
+    properties: Vec<(Vec<u8>, Option<usize>)>,
+    /// Indices into `properties` that must be present.
+    required: Vec<usize>,
+    /// `additionalProperties` is not `false`.
+    additional: bool,
+    items: Option<usize>,
+    /// Allowed string values (JSON-escaped, without quotes).
+    enum_values: Option<Vec<Vec<u8>>>,
+}
+
+impl Schema {
+    /// Without a schema the document must be an object or an array.
+    fn any_container() -> Self {
+        Self {
+            nodes: vec![SchemaNode {
+                types: T_OBJECT | T_ARRAY,
+                additional: true,
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn compile(schema: &Value) -> Result<Self> {
+        let mut compiled = Self::default();
+        compiled.add(schema)?;
+        Ok(compiled)
+    }
+
+    fn add(&mut self, schema: &Value) -> Result<usize> {
+        let idx = self.nodes.len();
+        self.nodes.push(SchemaNode {
+            additional: true,
+            ..Default::default()
+        });
+        let obj = match schema {
+            Value::Object(obj) => obj,
+            Value::Bool(true) => return Ok(idx),
+            other => {
+                return Err(BizClawError::Brain(format!("Unsupported JSON schema: {other}")));
+            }
+        };
+
+        let mut node = SchemaNode {
+            additional: obj.get("additionalProperties") != Some(&Value::Bool(false)),
+            ..Default::default()
+        };
+
+        match obj.get("type") {
+            None => {}
+            Some(Value::String(t)) => node.types = type_bit(t)?,
+            Some(Value::Array(ts)) => {
+                for t in ts {
+                    node.types |= type_bit(t.as_str().unwrap_or_default())?;
+                }
+            }
+            Some(other) => {
+                return Err(BizClawError::Brain(format!("Invalid schema type: {other}")));
+            }
+        }
+
+        if let Some(Value::Object(props)) = obj.get("properties") {
+            for (name, sub) in props {
+                let sub = self.add(sub)?;
+                node.properties.push((escaped(name), Some(sub)));
+            }
+        }
+        if let Some(Value::Array(required)) = obj.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                let raw = escaped(name);
+                let pos = match node.properties.iter().position(|(n, _)| *n == raw) {
+                    Some(pos) => pos,
+                    None => {
+                        node.properties.push((raw, None));
+                        node.properties.len() - 1
+                    }
+                };
+                node.required.push(pos);
+            }
+        }
+        if let Some(items) = obj.get("items") {
+            node.items = Some(self.add(items)?);
+        }
+        // Only string enums are enforced
+        if let Some(Value::Array(values)) = obj.get("enum")
+            && values.iter().all(Value::is_string)
+        {
+            node.enum_values = Some(values.iter().filter_map(Value::as_str).map(escaped).collect());
+            node.types = T_STRING;
+        }
+
+        self.nodes[idx] = node;
+        Ok(idx)
+    }
+}
+
+fn type_bit(name: &str) -> Result<u8> {
+    Ok(match name {
+        "object" => T_OBJECT,
+        "array" => T_ARRAY,
+        "string" => T_STRING,
+        "number" => T_NUMBER,
+        "integer" => T_INTEGER,
+        "boolean" => T_BOOLEAN,
+        "null" => T_NULL,
+        other => return Err(BizClawError::Brain(format!("Unknown schema type '{other}'"))),
+    })
+}
+
+/// JSON-escaped form of a string, without the surrounding quotes.
+fn escaped(s: &str) -> Vec<u8> {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted.as_bytes()[1..quoted.len() - 1].to_vec()
+}
+
+// ── Incremental parser ─────────────────────────────────────
+
+/// What the parser expects next (outside of a lexeme).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    /// Right after `[`: a value or `]`.
+    ValueOrClose,
+    /// Right after `{`: a key or `}`.
+    KeyOrClose,
+    Key,
+    Colon,
+    CommaOrClose,
+    End,
+}
+
+/// Lexeme currently being scanned.
+#[derive(Debug, Clone)]
+enum Lex {
+    None,
+    Str {
+        key: bool,
+        escape: Escape,
+        /// Raw bytes so far — kept only when the schema needs them.
+        buf: Option<Vec<u8>>,
+    },
+    Num {
+        state: Num,
+        integer: bool,
+    },
+    Lit {
+        text: &'static [u8],
+        matched: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    No,
+    Backslash,
+    Hex(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Num {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    E,
+    ESign,
+    Exp,
+}
+
+impl Num {
+    fn is_terminal(self) -> bool {
+        matches!(self, Num::Zero | Num::Int | Num::Frac | Num::Exp)
+    }
+
+    fn next(self, b: u8, integer: bool) -> Option<Num> {
+        Some(match (self, b) {
+            (Num::Minus, b'0') => Num::Zero,
+            (Num::Minus, b'1'..=b'9') | (Num::Int, b'0'..=b'9') => Num::Int,
+            (Num::Zero | Num::Int, b'.') if !integer => Num::Dot,
+            (Num::Zero | Num::Int | Num::Frac, b'e' | b'E') if !integer => Num::E,
+            (Num::Dot | Num::Frac, b'0'..=b'9') => Num::Frac,
+            (Num::E, b'+' | b'-') => Num::ESign,
+            (Num::E | Num::ESign | Num::Exp, b'0'..=b'9') => Num::Exp,
+            _ => return None,
+        })
+    }
+}
+
+/// An open object or array.
+#[derive(Debug, Clone)]
+struct Frame {
+    object: bool,
+    schema: Option<usize>,
+    /// Schema properties already present (object frames).
+    seen: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Parser {
+    stack: Vec<Frame>,
+    expect: Expect,
+    lex: Lex,
+    /// Schema for the next value.
+    pending: Option<usize>,
+    ws_run: u8,
+}
+
+fn is_ws(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            expect: Expect::Value,
+            lex: Lex::None,
+            pending: Some(0),
+            ws_run: 0,
+        }
+    }
+
+    /// Open brace and bracket counts.
+    fn depths(&self) -> (i32, i32) {
+        let braces = self.stack.iter().filter(|f| f.object).count() as i32;
+        (braces, self.stack.len() as i32 - braces)
+    }
+
+    /// A top-level scalar that may end here (numbers have no terminator).
+    fn can_end(&self) -> bool {
+        self.expect == Expect::End
+            || (self.stack.is_empty()
+                && matches!(self.lex, Lex::Num { state, .. } if state.is_terminal()))
+    }
+
+    fn feed(&mut self, bytes: &[u8], schema: &Schema) -> bool {
+        bytes.iter().all(|&b| self.step(b, schema))
+    }
+
+    fn step(&mut self, b: u8, schema: &Schema) -> bool {
+        match &mut self.lex {
+            Lex::None => {}
+            Lex::Str { .. } => return self.step_string(b, schema),
+            Lex::Num { state, integer } => {
+                if let Some(next) = state.next(b, *integer) {
+                    *state = next;
+                    return true;
+                }
+                if !state.is_terminal() {
+                    return false;
+                }
+                // The number ended; `b` belongs to the enclosing structure
+                self.lex = Lex::None;
+                self.finish_value();
+            }
+            Lex::Lit { text, matched } => {
+                if text[*matched] != b {
+                    return false;
+                }
+                *matched += 1;
+                if *matched == text.len() {
+                    self.lex = Lex::None;
+                    self.finish_value();
+                }
+                return true;
+            }
+        }
+
+        if is_ws(b) {
+            if self.ws_run >= MAX_WS_RUN {
+                return false;
+            }
+            self.ws_run += 1;
             return true;
         }
+        self.ws_run = 0;
 
-        // Starting: must begin with { or [
-        if !self.state.started {
-            return props.brace_delta > 0 || props.bracket_delta > 0;
+        match self.expect {
+            Expect::End => false,
+            Expect::ValueOrClose if b == b']' => self.close(schema),
+            Expect::Value | Expect::ValueOrClose => self.start_value(b, schema),
+            Expect::KeyOrClose if b == b'}' => self.close(schema),
+            Expect::KeyOrClose | Expect::Key => {
+                if b != b'"' {
+                    return false;
+                }
+                let has_props = self
+                    .node(schema, self.top_schema())
+                    .is_some_and(|n| !n.properties.is_empty());
+                self.lex = Lex::Str {
+                    key: true,
+                    escape: Escape::No,
+                    buf: has_props.then(Vec::new),
+                };
+                true
+            }
+            Expect::Colon => {
+                if b != b':' {
+                    return false;
+                }
+                self.expect = Expect::Value;
+                true
+            }
+            Expect::CommaOrClose => {
+                let object = self.stack.last().is_some_and(|f| f.object);
+                match b {
+                    b',' if object => {
+                        self.expect = Expect::Key;
+                        true
+                    }
+                    b',' => {
+                        self.pending = self.node(schema, self.top_schema()).and_then(|n| n.items);
+                        self.expect = Expect::Value;
+                        true
+                    }
+                    b'}' if object => self.close(schema),
+                    b']' if !object => self.close(schema),
+                    _ => false,
+                }
+            }
         }
+    }
+
+    fn node<'s>(&self, schema: &'s Schema, idx: Option<usize>) -> Option<&'s SchemaNode> {
+        idx.map(|i| &schema.nodes[i])
+    }
 
-        // Would close more braces/brackets than open
-        let new_brace = self.state.brace_depth + props.brace_delta;
-        let new_bracket = self.state.bracket_depth + props.bracket_delta;
-        if new_brace < 0 || new_bracket < 0 {
+    fn top_schema(&self) -> Option<usize> {
+        self.stack.last().and_then(|f| f.schema)
+    }
+
+    fn start_value(&mut self, b: u8, schema: &Schema) -> bool {
+        let node = self.node(schema, self.pending);
+        let types = node.map(|n| n.types).unwrap_or(0);
+        let kind = match b {
+            b'{' => T_OBJECT,
+            b'[' => T_ARRAY,
+            b'"' => T_STRING,
+            b'-' | b'0'..=b'9' => T_NUMBER,
+            b't' | b'f' => T_BOOLEAN,
+            b'n' => T_NULL,
+            _ => return false,
+        };
+        let integer = types & T_INTEGER != 0 && types & T_NUMBER == 0;
+        let allowed = types == 0 || types & kind != 0 || (kind == T_NUMBER && integer);
+        if !allowed {
             return false;
         }
 
-        // Whitespace is always fine
-        if props.is_whitespace_only {
+        match b {
+            b'{' => {
+                self.stack.push(Frame {
+                    object: true,
+                    schema: self.pending,
+                    seen: Vec::new(),
+                });
+                self.expect = Expect::KeyOrClose;
+            }
+            b'[' => {
+                self.stack.push(Frame {
+                    object: false,
+                    schema: self.pending,
+                    seen: Vec::new(),
+                });
+                self.pending = node.and_then(|n| n.items);
+                self.expect = Expect::ValueOrClose;
+            }
+            b'"' => {
+                self.lex = Lex::Str {
+                    key: false,
+                    escape: Escape::No,
+                    buf: node.and_then(|n| n.enum_values.as_ref()).map(|_| Vec::new()),
+                };
+            }
+            b't' | b'f' | b'n' => {
+                let text: &'static [u8] = match b {
+                    b't' => b"true",
+                    b'f' => b"false",
+                    _ => b"null",
+                };
+                self.lex = Lex::Lit { text, matched: 1 };
+            }
+            _ => {
+                let state = match b {
+                    b'-' => Num::Minus,
+                    b'0' => Num::Zero,
+                    _ => Num::Int,
+                };
+                self.lex = Lex::Num { state, integer };
+            }
+        }
+        true
+    }
+
+    fn step_string(&mut self, b: u8, schema: &Schema) -> bool {
+        let Lex::Str { key, escape, buf } = &mut self.lex else {
+            return false;
+        };
+        let key = *key;
+        match *escape {
+            Escape::Backslash => match b {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => *escape = Escape::No,
+                b'u' => *escape = Escape::Hex(4),
+                _ => return false,
+            },
+            Escape::Hex(n) => {
+                if !b.is_ascii_hexdigit() {
+                    return false;
+                }
+                *escape = if n == 1 { Escape::No } else { Escape::Hex(n - 1) };
+            }
+            Escape::No => match b {
+                b'"' => {
+                    let buf = buf.take();
+                    self.lex = Lex::None;
+                    return self.end_string(key, buf, schema);
+                }
+                b'\\' => *escape = Escape::Backslash,
+                0..=0x1f => return false,
+                _ => {}
+            },
+        }
+        if let Some(buf) = buf {
+            buf.push(b);
+            let buf = buf.clone();
+            return self
+                .string_candidates(key, schema)
+                .is_none_or(|mut c| c.any(|v| v.starts_with(&buf)));
+        }
+        true
+    }
+
+    /// Allowed raw values for the current string, if the schema restricts it.
+    fn string_candidates<'s>(
+        &self,
+        key: bool,
+        schema: &'s Schema,
+    ) -> Option<Box<dyn Iterator<Item = &'s [u8]> + 's>> {
+        if key {
+            let frame = self.stack.last()?;
+            let node = self.node(schema, frame.schema)?;
+            if node.additional {
+                return None;
+            }
+            let seen = frame.seen.clone();
+            Some(Box::new(
+                node.properties
+                    .iter()
+                    .enumerate()
+                    .filter(move |(i, _)| !seen.contains(i))
+                    .map(|(_, (name, _))| name.as_slice()),
+            ))
+        } else {
+            let values = self.node(schema, self.pending)?.enum_values.as_ref()?;
+            Some(Box::new(values.iter().map(Vec::as_slice)))
+        }
+    }
+
+    fn end_string(&mut self, key: bool, buf: Option<Vec<u8>>, schema: &Schema) -> bool {
+        if !key {
+            if let Some(buf) = buf
+                && !self
+                    .string_candidates(false, schema)
+                    .is_none_or(|mut c| c.any(|v| v == buf.as_slice()))
+            {
+                return false;
+            }
+            self.finish_value();
             return true;
         }
 
+        self.pending = None;
+        if let Some(buf) = buf {
+            let Some(frame) = self.stack.last_mut() else {
+                return false;
+            };
+            let Some(node) = frame.schema.map(|i| &schema.nodes[i]) else {
+                return false;
+            };
+            match node.properties.iter().position(|(name, _)| *name == buf) {
+                Some(pos) if frame.seen.contains(&pos) => return false, // duplicate key
+                Some(pos) => {
+                    frame.seen.push(pos);
+                    self.pending = node.properties[pos].1;
+                }
+                None if !node.additional => return false,
+                None => {}
+            }
+        }
+        self.expect = Expect::Colon;
         true
     }
 
-    /// Update state after a token is selected.
-    pub fn accept_token(&mut self, token_id: usize) {
-        if token_id >= self.token_props.len() {
-            return;
+    fn close(&mut self, schema: &Schema) -> bool {
+        let Some(frame) = self.stack.last() else {
+            return false;
+        };
+        if frame.object
+            && let Some(node) = self.node(schema, frame.schema)
+            && !node.required.iter().all(|r| frame.seen.contains(r))
+        {
+            return false;
         }
-        let props = &self.token_props[token_id];
+        self.stack.pop();
+        self.finish_value();
+        true
+    }
+
+    fn finish_value(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::End
+        } else {
+            Expect::CommaOrClose
+        };
+    }
+}
 
-        self.state.brace_depth += props.brace_delta;
-        self.state.bracket_depth += props.bracket_delta;
+// ── GBNF ───────────────────────────────────────────────────
 
-        if props.quote_toggle {
-            self.state.in_string = !self.state.in_string;
+/// Rules nested deeper than this while expanding (without consuming a
+/// character) are treated as left recursion and dropped.
+const MAX_EXPAND_DEPTH: usize = 256;
+
+/// One element of a GBNF sequence.
+#[derive(Debug, Clone, PartialEq)]
+enum Elem {
+    /// A character matching any of the inclusive ranges (or none, if negated).
+    Chars { ranges: Vec<(char, char)>, negated: bool },
+    /// Reference to another rule.
+    Rule(usize),
+}
+
+impl Elem {
+    fn literal(c: char) -> Self {
+        Elem::Chars {
+            ranges: vec![(c, c)],
+            negated: false,
         }
+    }
 
-        if !self.state.started && (props.brace_delta > 0 || props.bracket_delta > 0) {
-            self.state.started = true;
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Elem::Chars { ranges, negated } => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+            Elem::Rule(_) => false,
         }
+    }
+}
+
+/// A parsed GBNF grammar (llama.cpp format).
+///
+/// Supports rules (`name ::= ...`), alternation `|`, grouping `( )`, string
+/// literals, character classes (`[a-z]`, `[^"]`), `.`, rule references,
+/// repetition `* + ?` and `{m}`, `{m,}`, `{m,n}`, and `#` comments.
+/// Generation starts from the `root` rule.
+#[derive(Debug)]
+pub struct GbnfRules {
+    /// rule → alternatives → sequence
+    rules: Vec<Vec<Vec<Elem>>>,
+    root: usize,
+}
 
-        if self.state.started
-            && self.state.brace_depth == 0
-            && self.state.bracket_depth == 0
-            && !self.state.in_string
+impl GbnfRules {
+    /// Parse GBNF source text.
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut parser = GbnfParser {
+            chars: src.chars().collect(),
+            pos: 0,
+            names: HashMap::new(),
+            rules: Vec::new(),
+            defined: Vec::new(),
+        };
+        parser.parse_rules()?;
+
+        if let Some((name, _)) = parser
+            .names
+            .iter()
+            .find(|(_, id)| !parser.defined[**id])
         {
-            self.state.completed = true;
+            return Err(gbnf_error(format!("undefined rule '{name}'")));
         }
+        let root = *parser
+            .names
+            .get("root")
+            .ok_or_else(|| gbnf_error("missing 'root' rule".into()))?;
+        Ok(Self {
+            rules: parser.rules,
+            root,
+        })
     }
 
-    /// Check if JSON generation is complete.
-    pub fn is_complete(&self) -> bool {
-        self.state.completed
+    fn alternatives(&self, rule: usize) -> &[Vec<Elem>] {
+        &self.rules[rule]
     }
+}
 
-    /// Reset grammar state for new generation.
-    pub fn reset(&mut self) {
-        self.state = JsonState::default();
+fn gbnf_error(msg: String) -> BizClawError {
+    BizClawError::Brain(format!("Invalid GBNF grammar: {msg}"))
+}
+
+struct GbnfParser {
+    chars: Vec<char>,
+    pos: usize,
+    names: HashMap<String, usize>,
+    rules: Vec<Vec<Vec<Elem>>>,
+    /// Whether each rule id has a body (named rules can be referenced first).
+    defined: Vec<bool>,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+impl GbnfParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, msg: &str) -> BizClawError {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count()
+            + 1;
+        gbnf_error(format!("{msg} (line {line})"))
+    }
+
+    /// Skip whitespace (including newlines) and `#` comments.
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_name_char) {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Whether a `name ::=` rule definition starts at the cursor.
+    fn at_definition(&mut self) -> bool {
+        let save = self.pos;
+        let found = self.parse_name().is_some() && {
+            self.skip_space();
+            self.chars[self.pos..].starts_with(&[':', ':', '='])
+        };
+        self.pos = save;
+        found
+    }
+
+    fn rule_id(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.names.get(name) {
+            return id;
+        }
+        let id = self.new_rule();
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    fn new_rule(&mut self) -> usize {
+        self.rules.push(Vec::new());
+        self.defined.push(false);
+        self.rules.len() - 1
+    }
+
+    fn define(&mut self, id: usize, alternatives: Vec<Vec<Elem>>) {
+        self.rules[id] = alternatives;
+        self.defined[id] = true;
+    }
+
+    fn parse_rules(&mut self) -> Result<()> {
+        loop {
+            self.skip_space();
+            if self.peek().is_none() {
+                return Ok(());
+            }
+            let name = self.parse_name().ok_or_else(|| self.error("expected rule name"))?;
+            self.skip_space();
+            if !self.chars[self.pos..].starts_with(&[':', ':', '=']) {
+                return Err(self.error(&format!("expected '::=' after '{name}'")));
+            }
+            self.pos += 3;
+            let id = self.rule_id(&name);
+            if self.defined[id] {
+                return Err(self.error(&format!("rule '{name}' defined twice")));
+            }
+            let alternatives = self.parse_alternatives()?;
+            self.define(id, alternatives);
+        }
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Elem>>> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Elem>> {
+        let mut seq = Vec::new();
+        // Start of the last item, so a repetition operator can wrap it
+        let mut last_start = None;
+        loop {
+            self.skip_space();
+            let Some(c) = self.peek() else { break };
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    last_start = Some(seq.len());
+                    loop {
+                        match self.peek() {
+                            None => return Err(self.error("unterminated string literal")),
+                            Some('"') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(_) => {
+                                let c = self.parse_char()?;
+                                seq.push(Elem::literal(c));
+                            }
+                        }
+                    }
+                }
+                '[' => {
+                    self.pos += 1;
+                    last_start = Some(seq.len());
+                    seq.push(self.parse_class()?);
+                }
+                '.' => {
+                    self.pos += 1;
+                    last_start = Some(seq.len());
+                    seq.push(Elem::Chars {
+                        ranges: Vec::new(),
+                        negated: true,
+                    });
+                }
+                '(' => {
+                    self.pos += 1;
+                    let alternatives = self.parse_alternatives()?;
+                    self.skip_space();
+                    if self.peek() != Some(')') {
+                        return Err(self.error("expected ')'"));
+                    }
+                    self.pos += 1;
+                    let id = self.new_rule();
+                    self.define(id, alternatives);
+                    last_start = Some(seq.len());
+                    seq.push(Elem::Rule(id));
+                }
+                '*' | '+' | '?' | '{' => {
+                    let start = last_start
+                        .take()
+                        .ok_or_else(|| self.error(&format!("'{c}' without a preceding item")))?;
+                    self.pos += 1;
+                    let (min, max) = match c {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        '?' => (0, Some(1)),
+                        _ => self.parse_braces()?,
+                    };
+                    let item = seq.split_off(start);
+                    seq.extend(self.repeat(item, min, max));
+                }
+                c if is_name_char(c) => {
+                    if self.at_definition() {
+                        break;
+                    }
+                    let name = self.parse_name().unwrap_or_default();
+                    last_start = Some(seq.len());
+                    let id = self.rule_id(&name);
+                    seq.push(Elem::Rule(id));
+                }
+                _ => break,
+            }
+        }
+        Ok(seq)
+    }
+
+    /// `{m}`, `{m,}` or `{m,n}` (after the opening brace).
+    fn parse_braces(&mut self) -> Result<(usize, Option<usize>)> {
+        let number = |p: &mut Self| -> Option<usize> {
+            p.skip_space();
+            let start = p.pos;
+            while p.peek().is_some_and(|c| c.is_ascii_digit()) {
+                p.pos += 1;
+            }
+            p.chars[start..p.pos].iter().collect::<String>().parse().ok()
+        };
+        let min = number(self).ok_or_else(|| self.error("expected repetition count"))?;
+        self.skip_space();
+        let max = match self.peek() {
+            Some('}') => Some(min),
+            Some(',') => {
+                self.pos += 1;
+                let max = number(self);
+                self.skip_space();
+                max
+            }
+            _ => return Err(self.error("expected ',' or '}'")),
+        };
+        if self.peek() != Some('}') {
+            return Err(self.error("expected '}'"));
+        }
+        self.pos += 1;
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("repetition max is below min"));
+        }
+        Ok((min, max))
+    }
+
+    /// Desugar `item{min,max}` into plain elements plus helper rules.
+    fn repeat(&mut self, item: Vec<Elem>, min: usize, max: Option<usize>) -> Vec<Elem> {
+        let mut out = Vec::new();
+        for _ in 0..min {
+            out.extend(item.iter().cloned());
+        }
+        match max {
+            // rest ::= item rest | ε
+            None => {
+                let id = self.new_rule();
+                let mut more = item;
+                more.push(Elem::Rule(id));
+                self.define(id, vec![more, Vec::new()]);
+                out.push(Elem::Rule(id));
+            }
+            // opt_k ::= item opt_{k-1} | ε — nested so matching stays unambiguous
+            Some(max) if max > min => {
+                let mut inner: Option<usize> = None;
+                for _ in min..max {
+                    let id = self.new_rule();
+                    let mut seq = item.clone();
+                    seq.extend(inner.map(Elem::Rule));
+                    self.define(id, vec![seq, Vec::new()]);
+                    inner = Some(id);
+                }
+                out.extend(inner.map(Elem::Rule));
+            }
+            Some(_) => {}
+        }
+        out
+    }
+
+    /// Character class body (after `[`).
+    fn parse_class(&mut self) -> Result<Elem> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated character class")),
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => {
+                    let lo = self.parse_char()?;
+                    let hi = if self.peek() == Some('-')
+                        && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']')
+                    {
+                        self.pos += 1;
+                        self.parse_char()?
+                    } else {
+                        lo
+                    };
+                    ranges.push((lo, hi));
+                }
+            }
+        }
+        Ok(Elem::Chars { ranges, negated })
+    }
+
+    /// One possibly escaped character inside a literal or class.
+    fn parse_char(&mut self) -> Result<char> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end of input"))?;
+        self.pos += 1;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let e = self.peek().ok_or_else(|| self.error("unexpected end of input"))?;
+        self.pos += 1;
+        let hex_len = match e {
+            'n' => return Ok('\n'),
+            'r' => return Ok('\r'),
+            't' => return Ok('\t'),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            other => return Ok(other), // \\ \" \[ \] \- ...
+        };
+        let end = (self.pos + hex_len).min(self.chars.len());
+        let hex: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("invalid escape '\\{e}{hex}'")))
+    }
+}
+
+/// Position in a rule: next element of `rules[rule][alt]` to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct RulePos {
+    rule: u32,
+    alt: u32,
+    idx: u32,
+}
+
+/// A pushdown stack; the top always points at a character element.
+/// An empty stack means the root rule has been fully matched.
+type GbnfStack = Vec<RulePos>;
+
+/// Token-level matcher for a [`GbnfRules`] grammar.
+///
+/// Tracks every parse that is still possible (a set of pushdown stacks) and
+/// decodes token bytes as UTF-8 before matching characters.
+#[derive(Debug, Clone)]
+pub struct GbnfGrammar {
+    rules: Arc<GbnfRules>,
+    tokens: TokenTable,
+    stacks: Vec<GbnfStack>,
+    /// Bytes of a UTF-8 character split across tokens.
+    partial: Vec<u8>,
+}
+
+impl GbnfGrammar {
+    pub fn new(rules: Arc<GbnfRules>, tokens: TokenTable) -> Self {
+        let mut stacks = Vec::new();
+        for alt in 0..rules.alternatives(rules.root).len() {
+            let pos = RulePos {
+                rule: rules.root as u32,
+                alt: alt as u32,
+                idx: 0,
+            };
+            expand(&rules, Vec::new(), pos, &mut stacks, 0);
+        }
+        stacks.sort();
+        stacks.dedup();
+        Self {
+            rules,
+            tokens,
+            stacks,
+            partial: Vec::new(),
+        }
+    }
+
+    /// The input so far is a complete match of `root`.
+    fn can_end(&self) -> bool {
+        self.partial.is_empty() && self.stacks.iter().any(|s| s.is_empty())
+    }
+
+    fn feed(&mut self, bytes: &[u8]) -> bool {
+        bytes.iter().all(|&b| self.step_byte(b))
+    }
+
+    fn step_byte(&mut self, b: u8) -> bool {
+        if self.partial.is_empty() && b.is_ascii() {
+            return self.step_char(b as char);
+        }
+        self.partial.push(b);
+        let want = match self.partial[0] {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return false,
+        };
+        if self.partial.len() < want {
+            return self.partial[1..].iter().all(|b| b & 0xC0 == 0x80);
+        }
+        let Some(c) = std::str::from_utf8(&self.partial)
+            .ok()
+            .and_then(|s| s.chars().next())
+        else {
+            return false;
+        };
+        self.partial.clear();
+        self.step_char(c)
+    }
+
+    fn step_char(&mut self, c: char) -> bool {
+        let mut next = Vec::new();
+        for stack in &self.stacks {
+            let Some((&top, rest)) = stack.split_last() else {
+                continue;
+            };
+            let elem = &self.rules.alternatives(top.rule as usize)[top.alt as usize][top.idx as usize];
+            if elem.matches(c) {
+                let pos = RulePos {
+                    idx: top.idx + 1,
+                    ..top
+                };
+                expand(&self.rules, rest.to_vec(), pos, &mut next, 0);
+            }
+        }
+        next.sort();
+        next.dedup();
+        self.stacks = next;
+        !self.stacks.is_empty()
+    }
+}
+
+/// Advance `stack` to `pos`, expanding rule references until every resulting
+/// stack has a character element on top (or is empty = accepted).
+fn expand(rules: &GbnfRules, mut stack: GbnfStack, pos: RulePos, out: &mut Vec<GbnfStack>, depth: usize) {
+    if depth > MAX_EXPAND_DEPTH {
+        return;
+    }
+    let seq = &rules.alternatives(pos.rule as usize)[pos.alt as usize];
+    let Some(elem) = seq.get(pos.idx as usize) else {
+        // Sequence finished: resume the caller
+        match stack.pop() {
+            Some(parent) => expand(rules, stack, parent, out, depth + 1),
+            None => out.push(stack),
+        }
+        return;
+    };
+    match elem {
+        Elem::Chars { .. } => {
+            stack.push(pos);
+            out.push(stack);
+        }
+        Elem::Rule(rule) => {
+            // Tail position: nothing to resume, so don't grow the stack
+            // (keeps right-recursive repetitions bounded).
+            if (pos.idx as usize) + 1 < seq.len() {
+                stack.push(RulePos {
+                    idx: pos.idx + 1,
+                    ..pos
+                });
+            }
+            for alt in 0..rules.alternatives(*rule).len() {
+                let callee = RulePos {
+                    rule: *rule as u32,
+                    alt: alt as u32,
+                    idx: 0,
+                };
+                expand(rules, stack.clone(), callee, out, depth + 1);
+            }
+        }
+    }
+}
+
+impl TokenGrammar for GbnfGrammar {
+    fn apply_mask(&self, logits: &mut [f32]) {
+        let can_end = self.can_end();
+        for (i, logit) in logits.iter_mut().enumerate() {
+            let allowed = match self.tokens.get(i) {
+                Some(bytes) => self.clone().feed(bytes),
+                None => can_end && self.tokens.is_eos(i),
+            };
+            if !allowed {
+                *logit = f32::NEG_INFINITY;
+            }
+        }
+    }
+
+    fn accept_token(&mut self, token_id: usize) -> bool {
+        let tokens = self.tokens.clone();
+        match tokens.get(token_id) {
+            Some(bytes) => self.feed(bytes),
+            None => false,
+        }
+    }
+
+    /// Only the accepted state remains — no further character can match.
+    fn is_complete(&self) -> bool {
+        self.partial.is_empty() && self.stacks.iter().all(|s| s.is_empty())
+    }
+
+    fn decode(&self, tokens: &[u32]) -> String {
+        self.tokens.decode(tokens)
     }
 }
 
@@ -169,6 +1374,21 @@ impl JsonGrammar {
 mod tests {
     use super::*;
 
+    fn vocab(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|t| t.to_string()).collect()
+    }
+
+    /// Feed text one byte-token at a time (vocab = all printable ASCII).
+    fn accepts(grammar: &JsonGrammar, text: &str) -> bool {
+        let mut g = grammar.clone();
+        text.bytes().all(|b| g.accept_token(b as usize))
+    }
+
+    fn ascii_grammar() -> JsonGrammar {
+        let vocab: Vec<String> = (0u8..128).map(|b| (b as char).to_string()).collect();
+        JsonGrammar::new(&vocab)
+    }
+
     #[test]
     fn test_grammar_token_analysis() {
         let vocab = vec![
@@ -230,4 +1450,190 @@ mod tests {
         assert!(logits[2] == f32::NEG_INFINITY); // hello
         assert!(logits[3].is_finite()); // [
     }
+
+    #[test]
+    fn test_grammar_accepts_valid_rejects_invalid() {
+        let g = ascii_grammar();
+        assert!(accepts(&g, r#"{"a": [1, -2.5e3, true, null, "x\"y"], "b": {}}"#));
+        assert!(accepts(&g, "[]"));
+        assert!(!accepts(&g, r#"{"a" 1}"#)); // missing colon
+        assert!(!accepts(&g, r#"{"a": 1,}"#)); // trailing comma
+        assert!(!accepts(&g, "[01]")); // leading zero
+        assert!(!accepts(&g, "[tru]"));
+        assert!(!accepts(&g, "\"top-level string\"")); // root must be a container
+        assert!(!accepts(&g, "{}}"));
+    }
+
+    #[test]
+    fn test_grammar_mask_mid_document() {
+        let v = vocab(&["{", "}", "\"k\"", ":", "1", ",", "\"v\"", "]", "▁", "</s>"]);
+        let mut g = JsonGrammar::new(&v);
+        assert!(g.accept_token(0));
+        assert!(g.accept_token(2));
+
+        let mut logits = vec![0.0; v.len()];
+        g.apply_mask(&mut logits);
+        let allowed: Vec<usize> = (0..v.len()).filter(|&i| logits[i].is_finite()).collect();
+        assert_eq!(allowed, vec![3, 8]); // ':' or a space (▁)
+
+        assert!(g.accept_token(3));
+        assert!(g.accept_token(4));
+        let mut logits = vec![0.0; v.len()];
+        g.apply_mask(&mut logits);
+        let allowed: Vec<usize> = (0..v.len()).filter(|&i| logits[i].is_finite()).collect();
+        assert_eq!(allowed, vec![1, 4, 5, 8]); // '}', more digits, ',', space
+    }
+
+    #[test]
+    fn test_piece_bytes() {
+        assert_eq!(piece_bytes("▁{\""), b" {\"");
+        assert_eq!(piece_bytes("<0x0A>"), b"\n");
+        assert_eq!(piece_bytes("hello"), b"hello");
+    }
+
+    #[test]
+    fn test_schema_constrains_keys_types_and_enums() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "role": {"enum": ["admin", "user"]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name", "role"],
+            "additionalProperties": false
+        });
+        let g = ascii_grammar().with_schema(&schema).unwrap();
+
+        assert!(accepts(&g, r#"{"name": "Ann", "role": "admin", "tags": ["a"]}"#));
+        assert!(accepts(&g, r#"{"role":"user","age":42,"name":""}"#));
+        assert!(!accepts(&g, r#"{"name": "Ann"}"#)); // missing required role
+        assert!(!accepts(&g, r#"{"nick": "A"}"#)); // unknown key
+        assert!(!accepts(&g, r#"{"name": 1}"#)); // wrong type
+        assert!(!accepts(&g, r#"{"age": 4.2}"#)); // integer only
+        assert!(!accepts(&g, r#"{"role": "root"}"#)); // not in enum
+        assert!(!accepts(&g, r#"{"tags": [1]}"#)); // items must be strings
+        assert!(!accepts(&g, r#"{"name": "a", "name": "b"}"#)); // duplicate key
+        assert!(!accepts(&g, "[]")); // root must be an object
+
+        assert!(ascii_grammar().with_schema(&serde_json::json!({"type": "date"})).is_err());
+    }
+
+    #[test]
+    fn test_eos_allowed_only_when_scalar_root_can_end() {
+        let v = vocab(&["4", "2", ".", "</s>"]);
+        let base = JsonGrammar::from_tokens(TokenTable::from_bytes(
+            v.iter().map(|p| Some(piece_bytes(p))).take(3).chain([None]).collect(),
+            Some(3),
+        ));
+        let mut g = base.with_schema(&serde_json::json!({"type": "number"})).unwrap();
+
+        let mut logits = vec![0.0; 4];
+        g.apply_mask(&mut logits);
+        assert_eq!(logits[3], f32::NEG_INFINITY);
+
+        assert!(g.accept_token(0));
+        let mut logits = vec![0.0; 4];
+        g.apply_mask(&mut logits);
+        assert!(logits.iter().all(|l| l.is_finite()));
+
+        assert!(g.accept_token(2)); // "4." needs a digit before it can end
+        let mut logits = vec![0.0; 4];
+        g.apply_mask(&mut logits);
+        assert_eq!(logits[2], f32::NEG_INFINITY);
+        assert_eq!(logits[3], f32::NEG_INFINITY);
+    }
+
+    fn gbnf(src: &str, tokens: TokenTable) -> GbnfGrammar {
+        GbnfGrammar::new(Arc::new(GbnfRules::parse(src).unwrap()), tokens)
+    }
+
+    fn gbnf_accepts(src: &str, text: &str) -> bool {
+        let vocab: Vec<String> = (0u8..128).map(|b| (b as char).to_string()).collect();
+        let mut g = gbnf(src, TokenTable::new(&vocab));
+        text.bytes().all(|b| g.accept_token(b as usize)) && g.can_end()
+    }
+
+    #[test]
+    fn test_gbnf_parse_and_match() {
+        let src = r#"
+            # greeting grammar
+            root     ::= greeting " " name ("!" | "?")?
+            greeting ::= "hi" | "hello"
+            name     ::= [A-Z] [a-z]{1,5}
+        "#;
+        assert!(gbnf_accepts(src, "hello Bob!"));
+        assert!(gbnf_accepts(src, "hi Al"));
+        assert!(gbnf_accepts(src, "hi Alexis?"));
+        assert!(!gbnf_accepts(src, "hey Bob"));
+        assert!(!gbnf_accepts(src, "hello bob"));
+        assert!(!gbnf_accepts(src, "hi Alexisss")); // more than 5 lowercase
+        assert!(!gbnf_accepts(src, "hi A")); // needs at least 1 lowercase
+
+        let quoted = r#"root ::= "\"" ( [^"\\\n] | "\\" . )* "\"" [0-9]+"#;
+        assert!(gbnf_accepts(quoted, r#""a \" b"42"#));
+        assert!(!gbnf_accepts(quoted, "\"a\nb\"1"));
+        assert!(!gbnf_accepts(quoted, r#""ab""#)); // needs digits
+    }
+
+    #[test]
+    fn test_gbnf_mask_and_eos() {
+        let vocab = ["hi", "hello", " ", "Bob", "!", "x"];
+        let tokens = TokenTable::from_bytes(
+            vocab.iter().map(|v| Some(v.as_bytes().to_vec())).chain([None]).collect(),
+            Some(6),
+        );
+        let mut g = gbnf(r#"root ::= ("hi" | "hello") " " "Bob" "!"?"#, tokens);
+        let allowed = |g: &GbnfGrammar| {
+            let mut logits = vec![0.0f32; 7];
+            g.apply_mask(&mut logits);
+            (0..7).filter(|&i| logits[i].is_finite()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(allowed(&g), vec![0, 1]);
+        assert!(g.accept_token(0));
+        assert_eq!(allowed(&g), vec![2]);
+        assert!(g.accept_token(2));
+        assert!(g.accept_token(3));
+        // "!" or stop here
+        assert_eq!(allowed(&g), vec![4, 6]);
+        assert!(!g.is_complete());
+        assert!(g.accept_token(4));
+        assert!(g.is_complete());
+    }
+
+    #[test]
+    fn test_gbnf_utf8_split_across_tokens() {
+        let tokens = TokenTable::from_bytes(vec![Some(vec![0xC3]), Some(vec![0xA9])], None);
+        let mut g = gbnf(r#"root ::= "é"+"#, tokens);
+        assert!(g.accept_token(0));
+        assert!(!g.can_end());
+        assert!(g.accept_token(1));
+        assert!(g.can_end());
+        assert!(!g.accept_token(1)); // continuation byte without a lead byte
+    }
+
+    #[test]
+    fn test_gbnf_parse_errors() {
+        for (src, msg) in [
+            ("root ::= item", "undefined rule 'item'"),
+            ("start ::= \"a\"", "missing 'root'"),
+            ("root ::= \"a", "unterminated string"),
+            ("root ::= * \"a\"", "without a preceding item"),
+            ("root ::= \"a\"{3,1}", "below min"),
+            ("root ::= \"a\"\nroot ::= \"b\"", "defined twice"),
+        ] {
+            let err = GbnfRules::parse(src).unwrap_err().to_string();
+            assert!(err.contains(msg), "{src:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_gbnf_long_repetition_stays_bounded() {
+        // Right recursion from `*` must not grow the stacks per character.
+        let src = "root ::= [a-z]* \".\"";
+        let text = "a".repeat(2000) + ".";
+        assert!(gbnf_accepts(src, &text));
+    }
 }
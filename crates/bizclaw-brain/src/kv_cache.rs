@@ -1,4 +1,5 @@
-//! KV Cache — both f32 (compatible) and FP16 (memory-optimised) variants.
+//! KV Cache — f32, FP16 and q8_0 storage for inference, plus the FP16
+//! persistence variant.
 //!
 //! FP16 variant halves memory (88MB → 44MB for typical models).
 //! Includes KV Cache Persistence (save/load .bckv files)
@@ -7,12 +8,196 @@
 use std::io::{Read, Write};
 use std::path::Path;
 
-// ── f32 KV Cache (backward compatible) ──────────────────────
+// ── Inference KV Cache (f32 / f16 / q8_0 storage) ──────────
 
-/// Standard f32 KV Cache for transformer inference.
+pub use bizclaw_core::config::KvCacheDtype;
+
+/// Elements per q8_0 block; each block carries one f16 scale.
+const Q8_BLOCK: usize = 32;
+
+/// Backing storage: one `kv_dim` row per (layer, position).
+enum KvStore {
+    F32(Vec<f32>),
+    F16(Vec<u16>),
+    /// Symmetric 8-bit blocks of `Q8_BLOCK` along each row, f16 scales.
+    Q8_0 { quants: Vec<i8>, scales: Vec<u16> },
+}
+
+impl KvStore {
+    fn new(dtype: KvCacheDtype, rows: usize, kv_dim: usize) -> Self {
+        match dtype {
+            KvCacheDtype::F32 => Self::F32(vec![0.0; rows * kv_dim]),
+            KvCacheDtype::F16 => Self::F16(vec![0; rows * kv_dim]),
+            KvCacheDtype::Q8_0 => Self::Q8_0 {
+                quants: vec![0; rows * kv_dim],
+                scales: vec![0; rows * kv_dim.div_ceil(Q8_BLOCK)],
+            },
+        }
+    }
+
+    fn store(&mut self, row: usize, kv_dim: usize, data: &[f32]) {
+        debug_assert_eq!(data.len(), kv_dim);
+        let range = row * kv_dim..(row + 1) * kv_dim;
+        match self {
+            Self::F32(buf) => buf[range].copy_from_slice(data),
+            Self::F16(buf) => {
+                for (dst, &v) in buf[range].iter_mut().zip(data) {
+                    *dst = fp32_to_fp16(v);
+                }
+            }
+            Self::Q8_0 { quants, scales } => {
+                let n_blocks = kv_dim.div_ceil(Q8_BLOCK);
+                let blocks = data.chunks(Q8_BLOCK).zip(quants[range].chunks_mut(Q8_BLOCK));
+                for ((src, dst), scale) in blocks.zip(&mut scales[row * n_blocks..(row + 1) * n_blocks]) {
+                    let amax = src.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+                    // Quantize against the f16-rounded scale the reader will see
+                    *scale = fp32_to_fp16(amax / 127.0);
+                    let d = fp16_to_fp32(*scale);
+                    let id = if d > 0.0 { 1.0 / d } else { 0.0 };
+                    for (q, &v) in dst.iter_mut().zip(src) {
+                        *q = (v * id).round().clamp(-127.0, 127.0) as i8;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Dequantize `rows` into `out` (`rows.len() * kv_dim` values).
+    fn load(&self, rows: std::ops::Range<usize>, kv_dim: usize, out: &mut [f32]) {
+        let range = rows.start * kv_dim..rows.end * kv_dim;
+        match self {
+            Self::F32(buf) => out.copy_from_slice(&buf[range]),
+            Self::F16(buf) => {
+                for (dst, &h) in out.iter_mut().zip(&buf[range]) {
+                    *dst = fp16_to_fp32(h);
+                }
+            }
+            Self::Q8_0 { quants, scales } => {
+                let n_blocks = kv_dim.div_ceil(Q8_BLOCK);
+                for (r, row) in rows.enumerate() {
+                    let row_out = &mut out[r * kv_dim..(r + 1) * kv_dim];
+                    let row_q = &quants[row * kv_dim..(row + 1) * kv_dim];
+                    let row_scales = &scales[row * n_blocks..(row + 1) * n_blocks];
+                    let blocks = row_out.chunks_mut(Q8_BLOCK).zip(row_q.chunks(Q8_BLOCK));
+                    for ((dst, src), &scale) in blocks.zip(row_scales) {
+                        let d = fp16_to_fp32(scale);
+                        for (o, &q) in dst.iter_mut().zip(src) {
+                            *o = q as f32 * d;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move `src` rows so they start at row `dst`.
+    fn copy_rows(&mut self, src: std::ops::Range<usize>, dst: usize, kv_dim: usize) {
+        let scaled = |width: usize| (src.start * width..src.end * width, dst * width);
+        match self {
+            Self::F32(buf) => {
+                let (s, d) = scaled(kv_dim);
+                buf.copy_within(s, d);
+            }
+            Self::F16(buf) => {
+                let (s, d) = scaled(kv_dim);
+                buf.copy_within(s, d);
+            }
+            Self::Q8_0 { quants, scales } => {
+                let (s, d) = scaled(kv_dim);
+                quants.copy_within(s, d);
+                let (s, d) = scaled(kv_dim.div_ceil(Q8_BLOCK));
+                scales.copy_within(s, d);
+            }
+        }
+    }
+
+    /// Write `rows` in their stored encoding, little-endian.
+    fn write_rows(&self, rows: std::ops::Range<usize>, kv_dim: usize, out: &mut impl Write) -> std::io::Result<()> {
+        let range = rows.start * kv_dim..rows.end * kv_dim;
+        match self {
+            Self::F32(buf) => {
+                let bytes: Vec<u8> = buf[range].iter().flat_map(|v| v.to_le_bytes()).collect();
+                out.write_all(&bytes)
+            }
+            Self::F16(buf) => {
+                let bytes: Vec<u8> = buf[range].iter().flat_map(|v| v.to_le_bytes()).collect();
+                out.write_all(&bytes)
+            }
+            Self::Q8_0 { quants, scales } => {
+                let n_blocks = kv_dim.div_ceil(Q8_BLOCK);
+                let bytes: Vec<u8> = quants[range].iter().map(|&q| q as u8).collect();
+                out.write_all(&bytes)?;
+                let bytes: Vec<u8> = scales[rows.start * n_blocks..rows.end * n_blocks]
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect();
+                out.write_all(&bytes)
+            }
+        }
+    }
+
+    /// Read `rows` written by [`KvStore::write_rows`] for the same dtype.
+    fn read_rows(&mut self, rows: std::ops::Range<usize>, kv_dim: usize, input: &mut impl Read) -> std::io::Result<()> {
+        let range = rows.start * kv_dim..rows.end * kv_dim;
+        match self {
+            Self::F32(buf) => {
+                let mut bytes = vec![0u8; range.len() * 4];
+                input.read_exact(&mut bytes)?;
+                for (dst, c) in buf[range].iter_mut().zip(bytes.chunks_exact(4)) {
+                    *dst = f32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+                }
+            }
+            Self::F16(buf) => {
+                let mut bytes = vec![0u8; range.len() * 2];
+                input.read_exact(&mut bytes)?;
+                for (dst, c) in buf[range].iter_mut().zip(bytes.chunks_exact(2)) {
+                    *dst = u16::from_le_bytes([c[0], c[1]]);
+                }
+            }
+            Self::Q8_0 { quants, scales } => {
+                let n_blocks = kv_dim.div_ceil(Q8_BLOCK);
+                let mut bytes = vec![0u8; range.len()];
+                input.read_exact(&mut bytes)?;
+                for (dst, &b) in quants[range].iter_mut().zip(&bytes) {
+                    *dst = b as i8;
+                }
+                let scale_range = rows.start * n_blocks..rows.end * n_blocks;
+                let mut bytes = vec![0u8; scale_range.len() * 2];
+                input.read_exact(&mut bytes)?;
+                for (dst, c) in scales[scale_range].iter_mut().zip(bytes.chunks_exact(2)) {
+                    *dst = u16::from_le_bytes([c[0], c[1]]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::F32(buf) => buf.fill(0.0),
+            Self::F16(buf) => buf.fill(0),
+            Self::Q8_0 { quants, scales } => {
+                quants.fill(0);
+                scales.fill(0);
+            }
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        match self {
+            Self::F32(buf) => buf.len() * std::mem::size_of::<f32>(),
+            Self::F16(buf) => buf.len() * std::mem::size_of::<u16>(),
+            Self::Q8_0 { quants, scales } => quants.len() + scales.len() * std::mem::size_of::<u16>(),
+        }
+    }
+}
+
+/// KV cache for transformer inference. Rows are written as f32 and stored
+/// at the configured [`KvCacheDtype`]; reads dequantize back to f32.
 pub struct KvCache {
-    key_cache: Vec<f32>,
-    value_cache: Vec<f32>,
+    key_cache: KvStore,
+    value_cache: KvStore,
+    dtype: KvCacheDtype,
     n_layers: usize,
     max_seq_len: usize,
     kv_dim: usize,
@@ -20,12 +205,19 @@ pub struct KvCache {
 }
 
 impl KvCache {
-    pub fn new(n_layers: usize, max_seq_len: usize, n_kv_heads: usize, head_dim: usize) -> Self {
+    pub fn new(
+        n_layers: usize,
+        max_seq_len: usize,
+        n_kv_heads: usize,
+        head_dim: usize,
+        dtype: KvCacheDtype,
+    ) -> Self {
         let kv_dim = n_kv_heads * head_dim;
-        let total = n_layers * max_seq_len * kv_dim;
+        let rows = n_layers * max_seq_len;
         Self {
-            key_cache: vec![0.0; total],
-            value_cache: vec![0.0; total],
+            key_cache: KvStore::new(dtype, rows, kv_dim),
+            value_cache: KvStore::new(dtype, rows, kv_dim),
+            dtype,
             n_layers,
             max_seq_len,
             kv_dim,
@@ -33,24 +225,59 @@ impl KvCache {
         }
     }
 
-    pub fn key_at_mut(&mut self, layer: usize, pos: usize) -> &mut [f32] {
-        let offset = (layer * self.max_seq_len + pos) * self.kv_dim;
-        &mut self.key_cache[offset..offset + self.kv_dim]
+    pub fn dtype(&self) -> KvCacheDtype {
+        self.dtype
     }
 
-    pub fn value_at_mut(&mut self, layer: usize, pos: usize) -> &mut [f32] {
-        let offset = (layer * self.max_seq_len + pos) * self.kv_dim;
-        &mut self.value_cache[offset..offset + self.kv_dim]
+    fn row(&self, layer: usize, pos: usize) -> usize {
+        layer * self.max_seq_len + pos
     }
 
-    pub fn keys(&self, layer: usize, seq_len: usize) -> &[f32] {
-        let offset = layer * self.max_seq_len * self.kv_dim;
-        &self.key_cache[offset..offset + seq_len * self.kv_dim]
+    pub fn store_key(&mut self, layer: usize, pos: usize, key: &[f32]) {
+        let row = self.row(layer, pos);
+        self.key_cache.store(row, self.kv_dim, key);
     }
 
-    pub fn values(&self, layer: usize, seq_len: usize) -> &[f32] {
-        let offset = layer * self.max_seq_len * self.kv_dim;
-        &self.value_cache[offset..offset + seq_len * self.kv_dim]
+    pub fn store_value(&mut self, layer: usize, pos: usize, value: &[f32]) {
+        let row = self.row(layer, pos);
+        self.value_cache.store(row, self.kv_dim, value);
+    }
+
+    /// Keys of `positions` in `layer` as f32: borrowed in place from an f32
+    /// cache, otherwise dequantized into `scratch`.
+    pub fn keys<'a>(
+        &'a self,
+        layer: usize,
+        positions: std::ops::Range<usize>,
+        scratch: &'a mut Vec<f32>,
+    ) -> &'a [f32] {
+        self.read(&self.key_cache, layer, positions, scratch)
+    }
+
+    /// Values of `positions` in `layer` as f32; see [`KvCache::keys`].
+    pub fn values<'a>(
+        &'a self,
+        layer: usize,
+        positions: std::ops::Range<usize>,
+        scratch: &'a mut Vec<f32>,
+    ) -> &'a [f32] {
+        self.read(&self.value_cache, layer, positions, scratch)
+    }
+
+    fn read<'a>(
+        &self,
+        store: &'a KvStore,
+        layer: usize,
+        positions: std::ops::Range<usize>,
+        scratch: &'a mut Vec<f32>,
+    ) -> &'a [f32] {
+        let rows = self.row(layer, positions.start)..self.row(layer, positions.end);
+        if let KvStore::F32(buf) = store {
+            return &buf[rows.start * self.kv_dim..rows.end * self.kv_dim];
+        }
+        scratch.resize(rows.len() * self.kv_dim, 0.0);
+        store.load(rows, self.kv_dim, scratch);
+        scratch
     }
 
     pub fn advance(&mut self) {
@@ -60,14 +287,85 @@ impl KvCache {
         self.pos
     }
 
+    /// Number of positions the cache holds.
+    pub fn capacity(&self) -> usize {
+        self.max_seq_len
+    }
+
+    /// Evict positions `n_keep..n_keep + n_discard` of every layer, moving
+    /// the rest of the first `len` positions down to close the gap.
+    /// Returns the new length. Moved keys keep their old RoPE rotation;
+    /// see [`crate::forward::shift_kv_cache`].
+    pub fn discard(&mut self, n_keep: usize, n_discard: usize, len: usize) -> usize {
+        debug_assert!(n_keep + n_discard <= len && len <= self.max_seq_len);
+        for layer in 0..self.n_layers {
+            let src = self.row(layer, n_keep + n_discard)..self.row(layer, len);
+            let dst = self.row(layer, n_keep);
+            self.key_cache.copy_rows(src.clone(), dst, self.kv_dim);
+            self.value_cache.copy_rows(src, dst, self.kv_dim);
+        }
+        len - n_discard
+    }
+
     pub fn reset(&mut self) {
-        self.key_cache.fill(0.0);
-        self.value_cache.fill(0.0);
+        self.key_cache.clear();
+        self.value_cache.clear();
         self.pos = 0;
     }
 
     pub fn memory_usage(&self) -> usize {
-        (self.key_cache.len() + self.value_cache.len()) * std::mem::size_of::<f32>()
+        self.key_cache.bytes() + self.value_cache.bytes()
+    }
+
+    /// Write the first `len` positions of every layer in the stored dtype,
+    /// after a shape header that [`KvCache::read_positions`] checks.
+    pub fn write_positions(&self, len: usize, out: &mut impl Write) -> std::io::Result<()> {
+        debug_assert!(len <= self.max_seq_len);
+        out.write_all(&[self.dtype as u8])?;
+        out.write_all(&(self.n_layers as u32).to_le_bytes())?;
+        out.write_all(&(self.kv_dim as u32).to_le_bytes())?;
+        out.write_all(&(len as u32).to_le_bytes())?;
+        for layer in 0..self.n_layers {
+            let rows = self.row(layer, 0)..self.row(layer, len);
+            self.key_cache.write_rows(rows.clone(), self.kv_dim, out)?;
+            self.value_cache.write_rows(rows, self.kv_dim, out)?;
+        }
+        Ok(())
+    }
+
+    /// Restore positions saved by [`KvCache::write_positions`] from a cache
+    /// of the same dtype and shape. Returns the number of positions.
+    pub fn read_positions(&mut self, input: &mut impl Read) -> std::io::Result<usize> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let mut dtype = [0u8; 1];
+        input.read_exact(&mut dtype)?;
+        let mut buf4 = [0u8; 4];
+        input.read_exact(&mut buf4)?;
+        let n_layers = u32::from_le_bytes(buf4) as usize;
+        input.read_exact(&mut buf4)?;
+        let kv_dim = u32::from_le_bytes(buf4) as usize;
+        input.read_exact(&mut buf4)?;
+        let len = u32::from_le_bytes(buf4) as usize;
+
+        if dtype[0] != self.dtype as u8 || n_layers != self.n_layers || kv_dim != self.kv_dim {
+            return Err(invalid(format!(
+                "KV cache shape mismatch: saved {n_layers} layers × {kv_dim} (dtype {}), cache has {} × {} ({:?})",
+                dtype[0], self.n_layers, self.kv_dim, self.dtype
+            )));
+        }
+        if len > self.max_seq_len {
+            return Err(invalid(format!(
+                "Saved KV cache holds {len} positions but the context holds {}",
+                self.max_seq_len
+            )));
+        }
+        for layer in 0..self.n_layers {
+            let rows = self.row(layer, 0)..self.row(layer, len);
+            self.key_cache.read_rows(rows.clone(), self.kv_dim, input)?;
+            self.value_cache.read_rows(rows, self.kv_dim, input)?;
+        }
+        self.pos = len;
+        Ok(len)
     }
 }
 
@@ -351,9 +649,94 @@ impl RopeTable {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_positions_round_trip() {
+        for dtype in [KvCacheDtype::F32, KvCacheDtype::F16, KvCacheDtype::Q8_0] {
+            let mut cache = KvCache::new(2, 8, 1, 40, dtype);
+            for layer in 0..2 {
+                for pos in 0..5 {
+                    let row: Vec<f32> = (0..40).map(|i| (layer * 100 + pos * 10 + i) as f32 * 0.01).collect();
+                    cache.store_key(layer, pos, &row);
+                    cache.store_value(layer, pos, &row.iter().map(|v| -v).collect::<Vec<_>>());
+                }
+            }
+            let mut bytes = Vec::new();
+            cache.write_positions(5, &mut bytes).unwrap();
+
+            let mut restored = KvCache::new(2, 8, 1, 40, dtype);
+            assert_eq!(restored.read_positions(&mut bytes.as_slice()).unwrap(), 5);
+            let (mut a, mut b) = (Vec::new(), Vec::new());
+            for layer in 0..2 {
+                assert_eq!(cache.keys(layer, 0..5, &mut a), restored.keys(layer, 0..5, &mut b), "{dtype:?}");
+                assert_eq!(cache.values(layer, 0..5, &mut a), restored.values(layer, 0..5, &mut b), "{dtype:?}");
+            }
+
+            // Other shapes, dtypes and shorter contexts are rejected
+            assert!(KvCache::new(3, 8, 1, 40, dtype).read_positions(&mut bytes.as_slice()).is_err());
+            assert!(KvCache::new(2, 4, 1, 40, dtype).read_positions(&mut bytes.as_slice()).is_err());
+            let other = if dtype == KvCacheDtype::F32 { KvCacheDtype::F16 } else { KvCacheDtype::F32 };
+            assert!(KvCache::new(2, 8, 1, 40, other).read_positions(&mut bytes.as_slice()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_discard_closes_gap_in_every_layer() {
+        for dtype in [KvCacheDtype::F32, KvCacheDtype::F16, KvCacheDtype::Q8_0] {
+            let mut cache = KvCache::new(2, 8, 1, 2, dtype);
+            for layer in 0..2 {
+                for pos in 0..6 {
+                    let v = (layer * 10 + pos) as f32;
+                    cache.store_key(layer, pos, &[v, -v]);
+                    cache.store_value(layer, pos, &[v + 0.5, 0.0]);
+                }
+            }
+            // Keep 1 sink, drop positions 1..3, keep 3..6
+            assert_eq!(cache.discard(1, 2, 6), 4);
+            let mut scratch = Vec::new();
+            for layer in 0..2 {
+                let expect: Vec<f32> = [0, 3, 4, 5].iter().map(|&p| (layer * 10 + p) as f32).collect();
+                let keys: Vec<f32> = cache.keys(layer, 0..4, &mut scratch).iter().step_by(2).copied().collect();
+                let values: Vec<f32> =
+                    cache.values(layer, 0..4, &mut scratch).iter().step_by(2).copied().collect();
+                for (got, want) in keys.iter().zip(&expect) {
+                    assert!((got - want).abs() <= 0.1, "{dtype:?}: key {got} vs {want}");
+                }
+                for (got, want) in values.iter().zip(&expect) {
+                    assert!((got - (want + 0.5)).abs() <= 0.1, "{dtype:?}: value {got} vs {want}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantized_kv_roundtrip_and_memory() {
+        // 2 heads of 40: the second q8_0 block of each row is partial
+        let row: Vec<f32> = (0..80).map(|i| ((i * 37 % 23) as f32 - 11.0) * 0.173).collect();
+        let f32_bytes = KvCache::new(2, 16, 2, 40, KvCacheDtype::F32).memory_usage();
+        for (dtype, tol, ratio) in [(KvCacheDtype::F16, 2e-3, 2.0), (KvCacheDtype::Q8_0, 1.2e-2, 3.5)] {
+            let mut cache = KvCache::new(2, 16, 2, 40, dtype);
+            cache.store_key(1, 7, &row);
+            let mut scratch = Vec::new();
+            let got = cache.keys(1, 7..8, &mut scratch);
+            let max_err = got.iter().zip(&row).fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+            assert!(max_err <= tol, "{dtype:?}: max err {max_err}");
+            assert!(f32_bytes as f64 / cache.memory_usage() as f64 >= ratio, "{dtype:?}");
+            cache.reset();
+            assert!(cache.keys(1, 7..8, &mut scratch).iter().all(|&v| v == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_kv_cache_dtype_config_names() {
+        let parse = |s: &str| serde_json::from_str::<KvCacheDtype>(s).unwrap();
+        assert_eq!(parse("\"f32\""), KvCacheDtype::F32);
+        assert_eq!(parse("\"f16\""), KvCacheDtype::F16);
+        assert_eq!(parse("\"q8_0\""), KvCacheDtype::Q8_0);
+    }
+
     #[test]
     fn test_fp16_roundtrip() {
-        let values = [0.0f32, 1.0, -1.0, 0.5, 3.14, -0.001, 65504.0];
+        let values = [0.0f32, 1.0, -1.0, 0.5, 3.25, -0.001, 65504.0];
         for &v in &values {
             let fp16 = fp32_to_fp16(v);
             let back = fp16_to_fp32(fp16);
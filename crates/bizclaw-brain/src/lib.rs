@@ -11,13 +11,20 @@
 )]
 
 pub mod attention;
+pub mod download;
+pub mod dtype;
+pub mod eval;
 pub mod forward;
+#[cfg(feature = "gpu")]
+pub mod gpu;
 pub mod gguf;
 pub mod grammar;
 pub mod kv_cache;
 pub mod llamacpp;
+pub mod lora;
 pub mod mmap;
 pub mod model;
+pub mod pool;
 pub mod quant;
 pub mod rope;
 pub mod sampler;
@@ -26,9 +33,15 @@ pub mod tensor;
 pub mod thread_pool;
 pub mod tokenizer;
 
+pub use bizclaw_core::config::{MirostatConfig, SoftmaxMode};
 use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::metrics;
+use bizclaw_core::types::{Message, Role, Usage};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Brain engine configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +52,87 @@ pub struct BrainConfig {
     pub temperature: f32,
     pub top_p: f32,
     pub json_mode: bool,
+    /// Activation precision used by the forward pass.
+    #[serde(default)]
+    pub compute_dtype: dtype::ComputeDtype,
+    /// Softmax numerics for attention and sampling.
+    #[serde(default)]
+    pub softmax: SoftmaxMode,
+    /// KV cache storage precision (f16 / q8_0 trade accuracy for memory).
+    #[serde(default)]
+    pub kv_cache_dtype: kv_cache::KvCacheDtype,
+    /// Page-cache warm-up of the model weights after load.
+    #[serde(default)]
+    pub preload: mmap::Preload,
+    /// Prompt tokens per batched prefill pass; each pass dequantizes the
+    /// weights once for the whole chunk. 1 disables batching.
+    #[serde(default = "default_prefill_chunk")]
+    pub prefill_chunk: u32,
+    /// Path to a GBNF grammar that constrains all generation.
+    #[serde(default)]
+    pub grammar_path: Option<String>,
+    /// Inline GBNF grammar; takes precedence over `grammar_path`.
+    #[serde(default)]
+    pub grammar_string: Option<String>,
+    /// Min-p cutoff relative to the top token's probability (0 = off).
+    #[serde(default)]
+    pub min_p: f32,
+    /// Mirostat v2 sampling, replacing top-k/top-p/min-p when set.
+    #[serde(default)]
+    pub mirostat: Option<MirostatConfig>,
+    /// Logit bias by text, resolved to tokens at load (see
+    /// [`tokenizer::BpeTokenizer::tokens_for`]).
+    #[serde(default)]
+    pub logit_bias: HashMap<String, f32>,
+    /// Token IDs that are never sampled.
+    #[serde(default)]
+    pub banned_tokens: Vec<u32>,
+    /// Strings whose tokens are never sampled, resolved at load.
+    #[serde(default)]
+    pub banned_strings: Vec<String>,
+    /// When the KV cache (`context_length` positions) fills, evict old
+    /// entries and keep going (StreamingLLM) instead of stopping.
+    #[serde(default)]
+    pub streaming_kv: bool,
+    /// Leading tokens never evicted in streaming mode (attention sinks).
+    #[serde(default = "default_kv_sink_tokens")]
+    pub kv_sink_tokens: u32,
+    /// Without `streaming_kv`: when the context fills, keep the system prompt
+    /// (BOS for raw prompts), drop half of the rest and re-rotate the
+    /// survivors (see [`forward::shift_kv_cache`]). Over-long prompts lose
+    /// their oldest non-system blocks the same way.
+    #[serde(default = "default_context_shift")]
+    pub context_shift: bool,
+    /// GGUF LoRA adapters applied on the fly, in order (see [`lora`]).
+    #[serde(default)]
+    pub lora_paths: Vec<String>,
+    /// Multiplier on every adapter's `alpha / rank` scale.
+    #[serde(default = "default_lora_scale")]
+    pub lora_scale: f32,
+    /// Run the projections of the last `n_gpu_layers` layers on the GPU
+    /// (`gpu` feature; see [`forward::offload_layers`]).
+    #[serde(default)]
+    pub n_gpu_layers: u32,
+}
+
+/// Leading bytes of a [`BrainEngine::save_session`] file.
+const SESSION_MAGIC: &[u8; 4] = b"BCSS";
+const SESSION_VERSION: u32 = 1;
+
+fn default_prefill_chunk() -> u32 {
+    64
+}
+
+fn default_kv_sink_tokens() -> u32 {
+    4
+}
+
+fn default_context_shift() -> bool {
+    true
+}
+
+fn default_lora_scale() -> f32 {
+    1.0
 }
 
 impl Default for BrainConfig {
@@ -50,15 +144,100 @@ impl Default for BrainConfig {
             temperature: 0.7,
             top_p: 0.9,
             json_mode: false,
+            compute_dtype: dtype::ComputeDtype::F32,
+            softmax: SoftmaxMode::Fast,
+            kv_cache_dtype: kv_cache::KvCacheDtype::F32,
+            preload: mmap::Preload::None,
+            prefill_chunk: default_prefill_chunk(),
+            grammar_path: None,
+            grammar_string: None,
+            min_p: 0.0,
+            mirostat: None,
+            logit_bias: HashMap::new(),
+            banned_tokens: Vec::new(),
+            banned_strings: Vec::new(),
+            streaming_kv: false,
+            kv_sink_tokens: default_kv_sink_tokens(),
+            context_shift: default_context_shift(),
+            lora_paths: Vec::new(),
+            lora_scale: default_lora_scale(),
+            n_gpu_layers: 0,
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::BrainConfig> for BrainConfig {
+    /// Engine settings from the `[brain]` section of the BizClaw config.
+    fn from(config: &bizclaw_core::config::BrainConfig) -> Self {
+        Self {
+            threads: config.threads,
+            max_tokens: config.max_tokens,
+            context_length: config.context_length,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            json_mode: config.json_mode,
+            compute_dtype: config.compute_dtype,
+            softmax: config.softmax,
+            kv_cache_dtype: config.kv_cache_dtype,
+            preload: config.preload,
+            prefill_chunk: config.prefill_chunk,
+            grammar_path: config.grammar_path.clone(),
+            grammar_string: config.grammar_string.clone(),
+            min_p: config.min_p,
+            mirostat: config.mirostat,
+            logit_bias: config.logit_bias.clone(),
+            banned_tokens: config.banned_tokens.clone(),
+            banned_strings: config.banned_strings.clone(),
+            streaming_kv: config.streaming_kv,
+            kv_sink_tokens: config.kv_sink_tokens,
+            context_shift: config.context_shift,
+            lora_paths: config.lora_paths.clone(),
+            lora_scale: config.lora_scale,
+            n_gpu_layers: config.n_gpu_layers,
+        }
+    }
+}
+
+/// Parse the GBNF grammar configured via `grammar_string` or `grammar_path`.
+fn load_user_grammar(config: &BrainConfig) -> Result<Option<Arc<grammar::GbnfRules>>> {
+    let src = match (&config.grammar_string, &config.grammar_path) {
+        (Some(src), _) => src.clone(),
+        (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+            BizClawError::Brain(format!("Failed to read grammar '{path}': {e}"))
+        })?,
+        (None, None) => return Ok(None),
+    };
+    if config.json_mode {
+        tracing::warn!("Both a GBNF grammar and json_mode are set — using the GBNF grammar");
+    }
+    Ok(Some(Arc::new(grammar::GbnfRules::parse(&src)?)))
+}
+
+/// The GPU for layer offload, if this build and machine have one.
+#[cfg(feature = "gpu")]
+fn gpu_device() -> Option<Arc<dyn tensor::MatmulOffload>> {
+    match gpu::WgpuOffload::new() {
+        Ok(device) => Some(Arc::new(device)),
+        Err(e) => {
+            tracing::warn!("GPU offload unavailable ({e}); running on the CPU");
+            None
         }
     }
 }
 
+#[cfg(not(feature = "gpu"))]
+fn gpu_device() -> Option<Arc<dyn tensor::MatmulOffload>> {
+    tracing::warn!("n_gpu_layers is set but this build has no `gpu` feature; running on the CPU");
+    None
+}
+
 /// The main brain engine for local LLM inference.
 pub struct BrainEngine {
     config: BrainConfig,
     /// Loaded model (mmap)
     model: Option<LoadedModel>,
+    /// Token counts of the last generation
+    last_usage: Option<Usage>,
 }
 
 /// A loaded model ready for inference.
@@ -70,11 +249,20 @@ struct LoadedModel {
     /// Weight indices
     weights: forward::TransformerWeights,
     /// BPE tokenizer
-    tokenizer: tokenizer::BpeTokenizer,
+    tokenizer: Arc<tokenizer::BpeTokenizer>,
+    /// Token IDs that end generation (EOS and end-of-turn markers)
+    stop_ids: Vec<u32>,
     /// KV cache for generation
     kv_cache: kv_cache::KvCache,
+    /// Tokens whose keys and values fill the KV cache from position 0;
+    /// generations reuse the prefix their prompt shares with it
+    history: Vec<u32>,
     /// Sampler
     sampler: sampler::Sampler,
+    /// JSON grammar analysis of the vocabulary (for constrained decoding)
+    grammar: grammar::JsonGrammar,
+    /// User GBNF grammar from the config, applied to every generation
+    gbnf: Option<Arc<grammar::GbnfRules>>,
     /// Model file path
     path: PathBuf,
 }
@@ -82,19 +270,17 @@ struct LoadedModel {
 impl BrainEngine {
     /// Create a new brain engine (model not yet loaded).
     pub fn new(config: BrainConfig) -> Self {
+        simd::init();
         Self {
             config,
             model: None,
+            last_usage: None,
         }
     }
 
     /// Load a model from a GGUF file.
     pub fn load(model_path: &Path) -> Result<Self> {
-        let config = BrainConfig::default();
-        let mut engine = Self {
-            config,
-            model: None,
-        };
+        let mut engine = Self::new(BrainConfig::default());
         engine.load_model(model_path)?;
         Ok(engine)
     }
@@ -102,12 +288,39 @@ impl BrainEngine {
     /// Load a GGUF model into the engine.
     pub fn load_model(&mut self, model_path: &Path) -> Result<()> {
         tracing::info!("Loading model from: {}", model_path.display());
+        thread_pool::configure(self.config.threads as usize)?;
 
         let mmap_model = mmap::MmapModel::load(model_path)?;
+        mmap_model.preload(self.config.preload);
         let params = model::ModelParams::from_gguf(&mmap_model.gguf);
 
+        if let Some(t) = mmap_model
+            .gguf
+            .tensors
+            .iter()
+            .find(|t| !quant::is_supported(t.ggml_type))
+        {
+            return Err(BizClawError::Brain(format!(
+                "Tensor '{}' uses unsupported quantization {} (supported: F32, F16, Q4_0, Q8_0, Q4_K, Q5_K, Q6_K)",
+                t.name,
+                t.ggml_type.name()
+            )));
+        }
+
+        if let Some(quant) = mmap_model.gguf.quant_summary() {
+            tracing::info!("Quantization: {quant}");
+            if !quant.is_consistent() {
+                tracing::warn!(
+                    "general.file_type says {} but most weights are {}",
+                    quant.label(),
+                    quant.dominant.name()
+                );
+            }
+        }
+
         tracing::info!(
-            "Model params: dim={}, layers={}, heads={}, kv_heads={}, vocab={}",
+            "Model params: arch={}, dim={}, layers={}, heads={}, kv_heads={}, vocab={}",
+            params.arch.name(),
             params.dim,
             params.n_layers,
             params.n_heads,
@@ -115,12 +328,37 @@ impl BrainEngine {
             params.vocab_size
         );
 
+        let gbnf = load_user_grammar(&self.config)?;
+
         // Build weight index
-        let weights = forward::TransformerWeights::from_gguf(&mmap_model, &params);
+        let mut weights = forward::TransformerWeights::from_gguf(&mmap_model, &params);
+        for path in &self.config.lora_paths {
+            weights
+                .lora
+                .add_adapter(Path::new(path), &mmap_model, self.config.lora_scale)?;
+        }
+        if self.config.n_gpu_layers > 0
+            && let Some(device) = gpu_device()
+        {
+            let name = device.name();
+            let n = forward::offload_layers(
+                &mmap_model,
+                &mut weights,
+                &params,
+                Arc::clone(&device),
+                self.config.n_gpu_layers as usize,
+            )?;
+            tracing::info!(
+                "GPU offload: {n}/{} layers on {name} ({:.1} MB)",
+                params.n_layers,
+                device.memory_usage() as f64 / 1024.0 / 1024.0
+            );
+        }
         tracing::info!(
-            "Weights mapped: embd={}, output={}, layers={}",
+            "Weights mapped: embd={}, output={}, tied_embeddings={}, layers={}",
             weights.token_embd.is_some(),
             weights.output.is_some(),
+            weights.tied_embeddings,
             weights.layers.len()
         );
 
@@ -133,16 +371,18 @@ impl BrainEngine {
 
         tracing::info!("Tokenizer loaded: vocab_size={}", tokenizer.vocab_size());
 
-        // Create KV cache
+        // Create KV cache, bounded by the configured context length
         let kv_cache = kv_cache::KvCache::new(
             params.n_layers as usize,
-            params.max_seq_len as usize,
+            params.max_seq_len.min(self.config.context_length).max(1) as usize,
             params.n_kv_heads as usize,
             params.head_dim as usize,
+            self.config.kv_cache_dtype,
         );
         tracing::info!(
-            "KV cache: {:.1} MB",
-            kv_cache.memory_usage() as f64 / 1024.0 / 1024.0
+            "KV cache: {:.1} MB ({:?})",
+            kv_cache.memory_usage() as f64 / 1024.0 / 1024.0,
+            self.config.kv_cache_dtype
         );
 
         // Create sampler
@@ -152,15 +392,25 @@ impl BrainEngine {
             top_k: 40,
             repeat_penalty: 1.1,
             repeat_last_n: 64,
+            softmax: self.config.softmax,
+            min_p: self.config.min_p,
+            mirostat: self.config.mirostat,
+            ..self.resolve_logit_bias(&tokenizer)
         });
 
+        let grammar = grammar::JsonGrammar::for_tokenizer(&tokenizer);
+
         self.model = Some(LoadedModel {
             mmap_model,
             params,
             weights,
-            tokenizer,
+            stop_ids: tokenizer.stop_ids(),
+            tokenizer: Arc::new(tokenizer),
             kv_cache,
+            history: Vec::new(),
             sampler,
+            grammar,
+            gbnf,
             path: model_path.to_path_buf(),
         });
 
@@ -168,82 +418,654 @@ impl BrainEngine {
         Ok(())
     }
 
+    /// Token-level `logit_bias` / `banned_tokens` of a sampler config, from
+    /// the text-keyed [`BrainConfig`] fields.
+    fn resolve_logit_bias(&self, tokenizer: &tokenizer::BpeTokenizer) -> sampler::SamplerConfig {
+        let mut config = sampler::SamplerConfig::default();
+        for (text, &bias) in &self.config.logit_bias {
+            for id in tokenizer.tokens_for(text) {
+                *config.logit_bias.entry(id).or_insert(0.0) += bias;
+            }
+        }
+        config.banned_tokens.extend(&self.config.banned_tokens);
+        for text in &self.config.banned_strings {
+            config.banned_tokens.extend(tokenizer.tokens_for(text));
+        }
+        if !config.logit_bias.is_empty() || !config.banned_tokens.is_empty() {
+            tracing::info!(
+                "Sampler: {} biased tokens, {} banned tokens",
+                config.logit_bias.len(),
+                config.banned_tokens.len()
+            );
+        }
+        config
+    }
+
+    /// Token IDs for `text` in the loaded vocabulary, for building
+    /// `logit_bias` / `banned_tokens` in a per-call sampler config.
+    pub fn tokens_for(&self, text: &str) -> Option<Vec<u32>> {
+        Some(self.model.as_ref()?.tokenizer.tokens_for(text))
+    }
+
     /// Check if a model is loaded.
     pub fn is_loaded(&self) -> bool {
         self.model.is_some()
     }
 
+    /// Path of the loaded model file.
+    pub fn model_path(&self) -> Option<&Path> {
+        self.model.as_ref().map(|m| m.path.as_path())
+    }
+
+    /// Approximate resident size of the loaded model: the mapped file, the
+    /// KV cache and any LoRA deltas.
+    pub fn memory_usage(&self) -> usize {
+        self.model.as_ref().map_or(0, |m| {
+            m.mmap_model.file_size() + m.kv_cache.memory_usage() + m.weights.lora.memory_usage()
+        })
+    }
+
     /// Generate text completion using the loaded model.
+    /// The configured GBNF grammar, or JSON with `json_mode`, constrains the output.
     pub fn generate(&mut self, prompt: &str, max_tokens: u32) -> Result<String> {
+        let grammar = self.default_grammar()?;
+        let input_tokens = self.encode_prompt(prompt, &[])?;
+        self.generate_inner(input_tokens, 1, max_tokens, grammar, None, None)
+    }
+
+    /// [`Self::generate`] with a per-call sampler configuration, passing the
+    /// text to `on_text` as it is generated (see [`Self::chat_streaming`]).
+    pub fn generate_streaming(
+        &mut self,
+        prompt: &str,
+        max_tokens: u32,
+        sampling: Option<sampler::SamplerConfig>,
+        on_text: &mut dyn FnMut(&str) -> bool,
+    ) -> Result<String> {
+        let grammar = self.default_grammar()?;
+        let input_tokens = self.encode_prompt(prompt, &[])?;
+        let sampler = sampling.map(sampler::Sampler::new);
+        self.generate_inner(input_tokens, 1, max_tokens, grammar, sampler, Some(on_text))
+    }
+
+    /// Generate the assistant's reply to a conversation, formatted with the
+    /// model's chat template (see [`tokenizer::ChatTemplate`]). Constrained
+    /// like [`Self::generate`].
+    pub fn chat(&mut self, messages: &[Message], max_tokens: u32) -> Result<String> {
+        self.chat_with_sampling(messages, max_tokens, None)
+    }
+
+    /// [`Self::chat`] with a per-call sampler configuration instead of the
+    /// one built from [`BrainConfig`] (see [`Self::sampler_config`]).
+    pub fn chat_with_sampling(
+        &mut self,
+        messages: &[Message],
+        max_tokens: u32,
+        sampling: Option<sampler::SamplerConfig>,
+    ) -> Result<String> {
+        self.chat_inner(messages, max_tokens, sampling, None, None)
+    }
+
+    /// [`Self::chat_with_sampling`] constrained to JSON matching `schema`
+    /// (see [`grammar`] for the supported keywords) instead of the default
+    /// grammar.
+    pub fn chat_with_schema(
+        &mut self,
+        messages: &[Message],
+        max_tokens: u32,
+        sampling: Option<sampler::SamplerConfig>,
+        schema: &serde_json::Value,
+    ) -> Result<String> {
+        self.chat_inner(messages, max_tokens, sampling, Some(schema), None)
+    }
+
+    /// [`Self::chat_with_sampling`], passing the reply to `on_text` piece by
+    /// piece as it is generated. Pieces end on character boundaries and
+    /// concatenate to the returned text; `on_text` returns false to stop
+    /// generating.
+    pub fn chat_streaming(
+        &mut self,
+        messages: &[Message],
+        max_tokens: u32,
+        sampling: Option<sampler::SamplerConfig>,
+        on_text: &mut dyn FnMut(&str) -> bool,
+    ) -> Result<String> {
+        self.chat_inner(messages, max_tokens, sampling, None, Some(on_text))
+    }
+
+    fn chat_inner(
+        &mut self,
+        messages: &[Message],
+        max_tokens: u32,
+        sampling: Option<sampler::SamplerConfig>,
+        schema: Option<&serde_json::Value>,
+        on_text: Option<&mut dyn FnMut(&str) -> bool>,
+    ) -> Result<String> {
+        let template = self
+            .model
+            .as_ref()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?
+            .tokenizer
+            .chat_template;
+        let grammar = match schema {
+            Some(schema) => Some(Box::new(self.json_grammar(Some(schema))?) as Box<dyn grammar::TokenGrammar>),
+            None => self.default_grammar()?,
+        };
+        let prompt = template.render(messages);
+        let input_tokens = self.encode_prompt(&prompt, template.special_tokens())?;
+        // Context shifts never drop the leading system messages
+        let n_system = messages.iter().take_while(|m| m.role == Role::System).count();
+        let n_keep = if n_system > 0 {
+            let system = self.encode_prompt(&template.render(&messages[..n_system]), template.special_tokens())?;
+            system.iter().zip(&input_tokens).take_while(|(a, b)| a == b).count()
+        } else {
+            1
+        };
+        let sampler = sampling.map(sampler::Sampler::new);
+        self.generate_inner(input_tokens, n_keep, max_tokens, grammar, sampler, on_text)
+    }
+
+    /// Embed `text` as a unit-length vector of the model's hidden size: the
+    /// pooled final hidden state (see [`forward::embed_batch`]). Dedicated
+    /// GGUF embedding models set their own pooling; chat models mean-pool.
+    pub fn embed(&mut self, text: &str) -> Result<Vec<f32>> {
+        let mut tokens = self.encode_prompt(text, &[])?;
         let model = self
             .model
             .as_mut()
             .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        let capacity = model.kv_cache.capacity();
+        if tokens.len() > capacity {
+            tracing::warn!("Embedding input truncated from {} to {capacity} tokens", tokens.len());
+            tokens.truncate(capacity);
+        }
 
-        // Tokenize prompt
-        let mut input_tokens = vec![model.tokenizer.bos_id];
-        input_tokens.extend(model.tokenizer.encode(prompt));
+        let mut embedding = vec![0.0f32; model.params.dim as usize];
+        model.history.clear();
+        forward::embed_batch(
+            &model.mmap_model,
+            &model.weights,
+            &model.params,
+            &mut model.kv_cache,
+            &tokens,
+            &mut embedding,
+            self.config.compute_dtype,
+            self.config.softmax,
+        )?;
+        let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            embedding.iter_mut().for_each(|v| *v /= norm);
+        }
+        Ok(embedding)
+    }
 
-        let total_len = input_tokens.len();
+    /// Perplexity of the loaded model over `text`, scored in windows of
+    /// `n_ctx` tokens (default and maximum: the KV cache size). See
+    /// [`eval`] for the windowing, which matches llama.cpp's `perplexity`.
+    pub fn evaluate(&mut self, text: &str, n_ctx: Option<usize>) -> Result<eval::Evaluation> {
+        let model = self
+            .model
+            .as_mut()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        let capacity = model.kv_cache.capacity();
+        let n_ctx = n_ctx.unwrap_or(capacity).min(capacity);
+        let tokens = model.tokenizer.encode(text);
+        let n_chunks = tokens.len() / n_ctx.max(1);
+        if n_ctx < 4 || n_chunks == 0 {
+            return Err(BizClawError::Brain(format!(
+                "Evaluation needs at least one window of {n_ctx} tokens (n_ctx >= 4); text has {}",
+                tokens.len()
+            )));
+        }
+
+        let vocab_size = model.params.vocab_size as usize;
+        let batch = (self.config.prefill_chunk.max(1) as usize).min(n_ctx);
+        model.history.clear();
+        let mut logits = vec![0.0f32; batch * vocab_size];
+        let scored = eval::scored_positions(n_ctx);
+        let mut nll = 0.0f64;
+        let mut count = 0usize;
+        for (c, chunk) in tokens.chunks_exact(n_ctx).enumerate() {
+            let mut window = chunk.to_vec();
+            window[0] = model.tokenizer.bos_id;
+            let mut pos = 0;
+            for part in window.chunks(batch) {
+                let part_logits = &mut logits[..part.len() * vocab_size];
+                forward::forward_batch_all(
+                    &model.mmap_model,
+                    &model.weights,
+                    &model.params,
+                    &mut model.kv_cache,
+                    part,
+                    pos,
+                    part_logits,
+                    self.config.compute_dtype,
+                    self.config.softmax,
+                )?;
+                for (i, row) in part_logits.chunks(vocab_size).enumerate() {
+                    let j = pos + i;
+                    if scored.contains(&j) {
+                        nll += eval::token_nll(row, window[j + 1]);
+                        count += 1;
+                    }
+                }
+                pos += part.len();
+            }
+            tracing::info!(
+                "Perplexity chunk {}/{n_chunks}: {:.4}",
+                c + 1,
+                (nll / count as f64).exp()
+            );
+        }
+
+        Ok(eval::Evaluation {
+            n_ctx,
+            chunks: n_chunks,
+            tokens: count,
+            nll: nll / count as f64,
+        })
+    }
+
+    /// Prompt and completion token counts of the last generation.
+    pub fn last_usage(&self) -> Option<&Usage> {
+        self.last_usage.as_ref()
+    }
+
+    /// Sampler configuration of the loaded model, derived from [`BrainConfig`].
+    pub fn sampler_config(&self) -> Option<&sampler::SamplerConfig> {
+        Some(self.model.as_ref()?.sampler.config())
+    }
+
+    /// The grammar every generation is held to: the configured GBNF grammar,
+    /// else JSON in `json_mode`.
+    fn default_grammar(&self) -> Result<Option<Box<dyn grammar::TokenGrammar>>> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        Ok(match &model.gbnf {
+            Some(rules) => Some(Box::new(grammar::GbnfGrammar::new(
+                rules.clone(),
+                model.grammar.tokens().clone(),
+            ))),
+            None if self.config.json_mode => Some(Box::new(self.json_grammar(None)?)),
+            None => None,
+        })
+    }
+
+    /// BOS followed by the tokenized prompt.
+    fn encode_prompt(&self, prompt: &str, specials: &[&str]) -> Result<Vec<u32>> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        let mut input_tokens = vec![model.tokenizer.bos_id];
+        input_tokens.extend(model.tokenizer.encode_with_special(prompt, specials));
         tracing::debug!(
             "Generate: prompt_len={}, input_tokens={}",
             prompt.len(),
-            total_len
+            input_tokens.len()
         );
+        Ok(input_tokens)
+    }
+
+    /// Generate text constrained by a GBNF grammar given as source text.
+    pub fn generate_with_grammar(
+        &mut self,
+        prompt: &str,
+        gbnf: &str,
+        max_tokens: u32,
+    ) -> Result<String> {
+        let rules = Arc::new(grammar::GbnfRules::parse(gbnf)?);
+        let tokens = self.json_grammar(None)?.tokens().clone();
+        let grammar = grammar::GbnfGrammar::new(rules, tokens);
+        let input_tokens = self.encode_prompt(prompt, &[])?;
+        self.generate_inner(input_tokens, 1, max_tokens, Some(Box::new(grammar)), None, None)
+    }
+
+    /// Save the KV cache, the tokens it holds and the sampler's RNG state to
+    /// `path`, so another process can [`Self::load_session`] and skip
+    /// prefilling a long shared prompt (e.g. the system prompt) again.
+    pub fn save_session(&self, path: &Path) -> Result<()> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        out.write_all(SESSION_MAGIC)?;
+        out.write_all(&SESSION_VERSION.to_le_bytes())?;
+        out.write_all(&(model.mmap_model.file_size() as u64).to_le_bytes())?;
+        out.write_all(&model.params.vocab_size.to_le_bytes())?;
+        out.write_all(&model.sampler.rng_state().to_le_bytes())?;
+        out.write_all(&(model.history.len() as u32).to_le_bytes())?;
+        for token in &model.history {
+            out.write_all(&token.to_le_bytes())?;
+        }
+        model.kv_cache.write_positions(model.history.len(), &mut out)?;
+        out.flush()?;
+        tracing::debug!("Saved session: {} tokens to {}", model.history.len(), path.display());
+        Ok(())
+    }
+
+    /// Restore a [`Self::save_session`] file written with the same model and
+    /// KV cache settings. Generations whose prompt starts with the restored
+    /// tokens only prefill the rest.
+    pub fn load_session(&mut self, path: &Path) -> Result<()> {
+        let model = self
+            .model
+            .as_mut()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        let mut input = std::io::BufReader::new(std::fs::File::open(path)?);
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        let mut buf4 = [0u8; 4];
+        input.read_exact(&mut buf4)?;
+        if &magic != SESSION_MAGIC || u32::from_le_bytes(buf4) != SESSION_VERSION {
+            return Err(BizClawError::Brain(format!("{} is not a BizClaw session file", path.display())));
+        }
+        let mut buf8 = [0u8; 8];
+        input.read_exact(&mut buf8)?;
+        let file_size = u64::from_le_bytes(buf8);
+        input.read_exact(&mut buf4)?;
+        let vocab_size = u32::from_le_bytes(buf4);
+        if file_size != model.mmap_model.file_size() as u64 || vocab_size != model.params.vocab_size {
+            return Err(BizClawError::Brain(format!(
+                "Session {} was saved with a different model",
+                path.display()
+            )));
+        }
+        input.read_exact(&mut buf8)?;
+        let rng_state = u64::from_le_bytes(buf8);
+        input.read_exact(&mut buf4)?;
+        // Checked before allocating, so a corrupt count can't ask for gigabytes
+        let n_tokens = u32::from_le_bytes(buf4) as usize;
+        if n_tokens > model.kv_cache.capacity() {
+            return Err(BizClawError::Brain(format!(
+                "Session {} has {n_tokens} tokens, more than the {} the context holds",
+                path.display(),
+                model.kv_cache.capacity()
+            )));
+        }
+        let mut tokens = vec![0u8; n_tokens * 4];
+        input.read_exact(&mut tokens)?;
+        let tokens: Vec<u32> = tokens
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+
+        // The cache is only trusted up to the history, so a failed read
+        // leaves nothing to reuse
+        model.history.clear();
+        let len = model
+            .kv_cache
+            .read_positions(&mut input)
+            .map_err(|e| BizClawError::Brain(format!("Session {}: {e}", path.display())))?;
+        if len != tokens.len() {
+            return Err(BizClawError::Brain(format!(
+                "Session {} has {} tokens but {len} cached positions",
+                path.display(),
+                tokens.len()
+            )));
+        }
+        model.history = tokens;
+        model.sampler.set_rng_state(rng_state);
+        tracing::debug!("Loaded session: {len} tokens from {}", path.display());
+        Ok(())
+    }
+
+    /// Evict KV entries after the first `n_keep` so `needed` more positions
+    /// fit, dropping at least `min_discard` at a time so shifts stay rare.
+    /// Returns the new position.
+    fn make_room(
+        model: &mut LoadedModel,
+        capacity: usize,
+        n_keep: usize,
+        min_discard: usize,
+        pos: usize,
+        needed: usize,
+    ) -> usize {
+        if pos + needed <= capacity {
+            return pos;
+        }
+        let overflow = pos + needed - capacity;
+        let n_discard = overflow.max(min_discard).min(pos - n_keep);
+        tracing::debug!("KV cache full: evicting {n_discard} tokens after the first {n_keep}");
+        model.history.drain(n_keep..n_keep + n_discard);
+        forward::shift_kv_cache(&mut model.kv_cache, &model.params, n_keep, n_discard, pos)
+    }
+
+    /// Text of generated `tokens`; grammars decode with their own token table.
+    fn decode_output(
+        model: &LoadedModel,
+        grammar: Option<&dyn grammar::TokenGrammar>,
+        tokens: &[u32],
+    ) -> String {
+        match grammar {
+            Some(g) => g.decode(tokens),
+            None => model.tokenizer.decode(tokens),
+        }
+    }
+
+    /// Run `input_tokens` and sample up to `max_tokens`. The first `n_keep`
+    /// tokens (BOS, or the system prompt) survive context shifts.
+    fn generate_inner(
+        &mut self,
+        input_tokens: Vec<u32>,
+        n_keep: usize,
+        max_tokens: u32,
+        mut grammar: Option<Box<dyn grammar::TokenGrammar>>,
+        sampler: Option<sampler::Sampler>,
+        mut on_text: Option<&mut dyn FnMut(&str) -> bool>,
+    ) -> Result<String> {
+        let model = self
+            .model
+            .as_mut()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
 
         let mut output_tokens = Vec::new();
         let max_gen = max_tokens.min(self.config.max_tokens) as usize;
         let mut logits = vec![0.0f32; model.params.vocab_size as usize];
+        // Fresh per-generation sampler state (Mirostat μ)
+        let mut sampler = sampler.unwrap_or_else(|| model.sampler.clone());
+        sampler.reset();
+        let mut window = sampler.new_window();
+        window.extend(&input_tokens);
 
-        for step in 0..total_len + max_gen {
-            // Get the token to process
-            let token = if step < total_len {
-                input_tokens[step]
-            } else if let Some(&last) = output_tokens.last() {
-                last
-            } else {
-                break;
-            };
+        let capacity = model.kv_cache.capacity();
+        let streaming = self.config.streaming_kv;
+        let shifting = streaming || self.config.context_shift;
+        // Streaming keeps the attention sinks and evicts a quarter of the
+        // window at a time; a context shift keeps `n_keep` and drops half
+        let (n_keep, min_discard) = if streaming {
+            let n_sink = (self.config.kv_sink_tokens as usize).min(capacity / 2);
+            (n_sink, (capacity - n_sink) / 4)
+        } else {
+            let n_keep = n_keep.min(capacity / 2);
+            (n_keep, (capacity - n_keep) / 2)
+        };
+        let mut prompt = &input_tokens[..];
+        let truncated;
+        if !streaming && input_tokens.len() >= capacity {
+            if !shifting {
+                return Err(BizClawError::Brain(format!(
+                    "Prompt has {} tokens but the context holds {capacity}",
+                    input_tokens.len()
+                )));
+            }
+            truncated = truncate_prompt(&input_tokens, n_keep, capacity);
+            tracing::warn!(
+                "Prompt has {} tokens but the context holds {capacity}: keeping {}",
+                input_tokens.len(),
+                truncated.len()
+            );
+            prompt = &truncated;
+        }
 
-            // Run forward pass
-            forward::forward(
+        // Prefill the prompt in batches; leaves logits for the last prompt token
+        let mut chunk = self.config.prefill_chunk.max(1) as usize;
+        if streaming {
+            chunk = chunk.min(((capacity - n_keep) / 2).max(1));
+        }
+        // Skip the prefix already in the cache from the last generation (or a
+        // restored session); the last prompt token always runs for its logits
+        let reused = model
+            .history
+            .iter()
+            .zip(prompt)
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(prompt.len().saturating_sub(1));
+        if reused > 0 {
+            tracing::debug!("Reusing {reused} cached prompt tokens");
+        }
+        model.history.truncate(reused);
+        let mut pos = reused;
+        for batch in prompt[reused..].chunks(chunk) {
+            pos = Self::make_room(model, capacity, n_keep, min_discard, pos, batch.len());
+            forward::forward_batch(
                 &model.mmap_model,
                 &model.weights,
                 &model.params,
                 &mut model.kv_cache,
-                token,
-                step,
+                batch,
+                pos,
                 &mut logits,
+                self.config.compute_dtype,
+                self.config.softmax,
             )?;
+            model.history.extend_from_slice(batch);
+            pos += batch.len();
+        }
 
-            // Only sample after processing all input tokens
-            if step >= total_len - 1 {
-                let all_tokens: Vec<u32> = input_tokens
-                    .iter()
-                    .chain(output_tokens.iter())
-                    .copied()
-                    .collect();
-                let next_token = model.sampler.sample(&mut logits, &all_tokens);
-
-                // Check for EOS
-                if next_token == model.tokenizer.eos_id {
+        // Decode one token at a time; timed for the tokens/sec gauge
+        let started = std::time::Instant::now();
+        // Text already passed to `on_text`
+        let mut streamed = String::new();
+        let mut stopped = false;
+        while output_tokens.len() < max_gen {
+            let next_token = match &grammar {
+                Some(g) => sampler.sample_constrained(&mut logits, &window, g.as_ref()),
+                None => sampler.sample_with_window(&mut logits, &window),
+            };
+
+            // Check for EOS / end of turn
+            if model.stop_ids.contains(&next_token) {
+                break;
+            }
+
+            output_tokens.push(next_token);
+            window.push(next_token);
+            if let Some(g) = grammar.as_mut() {
+                g.accept_token(next_token as usize);
+            }
+            if let Some(emit) = on_text.as_mut() {
+                // Hold back pieces that end inside a multi-byte character
+                let text = Self::decode_output(model, grammar.as_deref(), &output_tokens);
+                if !text.ends_with('\u{FFFD}')
+                    && let Some(new) = text.strip_prefix(streamed.as_str())
+                    && !new.is_empty()
+                {
+                    let more = emit(new);
+                    streamed = text;
+                    if !more {
+                        stopped = true;
+                        break;
+                    }
+                }
+            }
+            if grammar.as_ref().is_some_and(|g| g.is_complete()) {
+                break;
+            }
+            if output_tokens.len() == max_gen {
+                break;
+            }
+            if pos >= capacity {
+                if !shifting {
+                    tracing::warn!("Context full ({capacity} tokens), stopping generation");
                     break;
                 }
-
-                output_tokens.push(next_token);
+                pos = Self::make_room(model, capacity, n_keep, min_discard, pos, 1);
             }
+
+            forward::forward(
+                &model.mmap_model,
+                &model.weights,
+                &model.params,
+                &mut model.kv_cache,
+                next_token,
+                pos,
+                &mut logits,
+                self.config.compute_dtype,
+                self.config.softmax,
+            )?;
+            model.history.push(next_token);
+            pos += 1;
         }
+        // The next generation continues this one's random stream
+        model.sampler.set_rng_state(sampler.rng_state());
 
         // Decode output tokens
-        let output = model.tokenizer.decode(&output_tokens);
+        let output = Self::decode_output(model, grammar.as_deref(), &output_tokens);
+        if let Some(emit) = on_text.as_mut()
+            && !stopped
+            && let Some(rest) = output.strip_prefix(streamed.as_str())
+            && !rest.is_empty()
+        {
+            emit(rest);
+        }
         tracing::debug!("Generated {} tokens", output_tokens.len());
+        let elapsed = started.elapsed().as_secs_f64();
+        metrics::add("bizclaw_brain_prompt_tokens_total", &[], input_tokens.len() as f64);
+        metrics::add("bizclaw_brain_generated_tokens_total", &[], output_tokens.len() as f64);
+        if elapsed > 0.0 && !output_tokens.is_empty() {
+            metrics::set("bizclaw_brain_tokens_per_second", &[], output_tokens.len() as f64 / elapsed);
+        }
+        self.last_usage = Some(Usage {
+            prompt_tokens: input_tokens.len() as u32,
+            completion_tokens: output_tokens.len() as u32,
+            total_tokens: (input_tokens.len() + output_tokens.len()) as u32,
+        });
         Ok(output)
     }
 
     /// Generate with JSON grammar constraint.
     pub fn generate_json(&mut self, prompt: &str) -> Result<serde_json::Value> {
-        let text = self.generate(prompt, self.config.max_tokens)?;
-        Ok(serde_json::json!({"response": text}))
+        self.generate_json_inner(prompt, None)
+    }
+
+    /// Generate JSON that also conforms to `schema` (see [`grammar`] for the
+    /// supported keywords).
+    pub fn generate_json_with_schema(
+        &mut self,
+        prompt: &str,
+        schema: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.generate_json_inner(prompt, Some(schema))
+    }
+
+    fn generate_json_inner(
+        &mut self,
+        prompt: &str,
+        schema: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let grammar = self.json_grammar(schema)?;
+        let input_tokens = self.encode_prompt(prompt, &[])?;
+        let text = self.generate_inner(input_tokens, 1, self.config.max_tokens, Some(Box::new(grammar)), None, None)?;
+        serde_json::from_str(&text).map_err(|e| {
+            BizClawError::Brain(format!("Incomplete JSON after {} tokens ({e}): {text}", self.config.max_tokens))
+        })
+    }
+
+    /// Fresh JSON grammar over the loaded vocabulary.
+    fn json_grammar(&self, schema: Option<&serde_json::Value>) -> Result<grammar::JsonGrammar> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
+        match schema {
+            Some(schema) => model.grammar.with_schema(schema),
+            None => Ok(model.grammar.clone()),
+        }
     }
 
     /// Get the brain config.
@@ -251,16 +1073,75 @@ impl BrainEngine {
         &self.config
     }
 
+    /// The loaded model's tokenizer, for counting tokens outside the engine.
+    pub fn tokenizer(&self) -> Option<Arc<tokenizer::BpeTokenizer>> {
+        self.model.as_ref().map(|m| Arc::clone(&m.tokenizer))
+    }
+
     /// Get model info if loaded.
     pub fn model_info(&self) -> Option<String> {
         self.model.as_ref().map(|m| {
+            let quant = m
+                .mmap_model
+                .gguf
+                .quant_summary()
+                .map(|q| format!(", {q}"))
+                .unwrap_or_default();
+            let tied = if m.weights.tied_embeddings {
+                ", tied embeddings"
+            } else {
+                ""
+            };
+            let gpu = m
+                .weights
+                .offload
+                .as_ref()
+                .map(|o| format!(", {} layers on GPU", o.n_layers))
+                .unwrap_or_default();
             format!(
-                "{} ({}MB, {} layers, {} heads)",
+                "{} ({}, {}MB, {} layers, {} heads{}{}{})",
                 m.path.file_name().unwrap_or_default().to_string_lossy(),
+                m.params.arch.name(),
                 m.mmap_model.file_size() / 1024 / 1024,
                 m.params.n_layers,
                 m.params.n_heads,
+                quant,
+                tied,
+                gpu,
             )
         })
     }
+
+    /// Get the quantization summary of the loaded model.
+    pub fn quant_summary(&self) -> Option<gguf::QuantSummary> {
+        self.model.as_ref()?.mmap_model.gguf.quant_summary()
+    }
+}
+
+/// Fit an over-long prompt into `capacity` positions the way llama.cpp does:
+/// keep the first `n_keep` tokens and drop whole blocks of half the remaining
+/// room from the start of the rest, leaving space to generate.
+fn truncate_prompt(tokens: &[u32], n_keep: usize, capacity: usize) -> Vec<u32> {
+    let block = ((capacity - n_keep) / 2).max(1);
+    let erased = (tokens.len() - n_keep - block) / block;
+    let mut kept = tokens[..n_keep].to_vec();
+    kept.extend_from_slice(&tokens[n_keep + erased * block..]);
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_prompt_keeps_prefix_and_tail() {
+        let tokens: Vec<u32> = (0..100).collect();
+        let kept = truncate_prompt(&tokens, 4, 32);
+        assert!(kept.len() < 32);
+        assert_eq!(&kept[..4], &[0, 1, 2, 3]);
+        assert_eq!(*kept.last().unwrap(), 99);
+        // The tail is contiguous and ends the prompt
+        let tail = &kept[4..];
+        assert!(tail.windows(2).all(|w| w[1] == w[0] + 1));
+    }
 }
@@ -7,9 +7,16 @@
 //! - Optimized SIMD kernels
 //!
 //! Falls back to pure Rust BrainEngine when llama.cpp is not available.
+//!
+//! [`LlamaCppManager`] instead runs llama.cpp's own `llama-server` as a
+//! supervised child process and serves it through the `llamacpp` provider.
 
 use bizclaw_core::error::{BizClawError, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
 
 /// Check if llama.cpp shared library is available on the system.
 pub fn is_llamacpp_available() -> bool {
@@ -252,6 +259,436 @@ impl SmartBrainEngine {
     }
 }
 
+// ── Supervised llama-server ────────────────────────────────
+
+pub use bizclaw_core::config::LlamaCppConfig;
+
+/// GitHub API endpoint for the newest llama.cpp release.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/ggml-org/llama.cpp/releases/latest";
+
+/// File name of the server binary on this platform.
+const SERVER_BINARY: &str = if cfg!(windows) { "llama-server.exe" } else { "llama-server" };
+
+/// Failed health checks in a row before a running server is restarted.
+const MAX_FAILED_CHECKS: u32 = 3;
+
+/// `/v1` base URL of the managed server while it is up; the `llamacpp`
+/// provider connects here when no endpoint is configured.
+static MANAGED_BASE_URL: RwLock<Option<String>> = RwLock::new(None);
+
+/// Base URL of the running [`LlamaCppManager`] server, if any.
+pub fn managed_base_url() -> Option<String> {
+    MANAGED_BASE_URL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn register(base_url: Option<String>) {
+    *MANAGED_BASE_URL.write().unwrap_or_else(|e| e.into_inner()) = base_url;
+}
+
+/// Lifecycle of a managed server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerState {
+    Healthy,
+    /// Crashed or unhealthy; being respawned with backoff.
+    Restarting,
+    /// Gave up after `max_restarts` failed restarts.
+    Failed,
+    Stopped,
+}
+
+/// Snapshot of a managed server (see [`LlamaCppManager::status`]).
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    pub state: ServerState,
+    /// Restarts since the manager started.
+    pub restarts: u32,
+    pub pid: Option<u32>,
+}
+
+/// Runs `llama-server` as a child process: finds or downloads the binary,
+/// waits for `/health`, restarts the server when it exits or stops
+/// answering, and registers it as the `llamacpp` provider's endpoint.
+///
+/// Dropping the manager stops the server.
+pub struct LlamaCppManager {
+    base_url: String,
+    status: Arc<Mutex<ServerStatus>>,
+    shutdown: watch::Sender<bool>,
+    supervisor: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// How to (re)spawn the server and check on it.
+struct Launch {
+    binary: PathBuf,
+    args: Vec<String>,
+    health_url: String,
+    client: reqwest::Client,
+    config: LlamaCppConfig,
+}
+
+impl LlamaCppManager {
+    /// Start serving `model` and wait until it is healthy.
+    pub async fn start(config: &LlamaCppConfig, model: &Path) -> Result<Self> {
+        if !model.exists() {
+            return Err(BizClawError::Brain(format!(
+                "Model file not found: {}",
+                model.display()
+            )));
+        }
+        let binary = resolve_binary(config).await?;
+        let origin = format!("http://{}:{}", config.host, config.port);
+        let launch = Launch {
+            binary,
+            args: server_args(config, model),
+            health_url: format!("{origin}/health"),
+            client: reqwest::Client::new(),
+            config: config.clone(),
+        };
+
+        tracing::info!("🚀 Starting llama-server: {} {}", launch.binary.display(), launch.args.join(" "));
+        let mut child = launch.spawn()?;
+        if let Err(e) = launch.wait_healthy(&mut child).await {
+            let _ = child.kill().await;
+            return Err(e);
+        }
+
+        let base_url = format!("{origin}/v1");
+        register(Some(base_url.clone()));
+        tracing::info!("✅ llama-server ready at {base_url}");
+
+        let status = Arc::new(Mutex::new(ServerStatus {
+            state: ServerState::Healthy,
+            restarts: 0,
+            pid: child.id(),
+        }));
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let supervisor = tokio::spawn(supervise(launch, child, Arc::clone(&status), shutdown_rx));
+        Ok(Self {
+            base_url,
+            status,
+            shutdown,
+            supervisor: Some(supervisor),
+        })
+    }
+
+    /// OpenAI-compatible base URL (`http://host:port/v1`).
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Stop the server and wait for it to exit.
+    pub async fn stop(mut self) {
+        let _ = self.shutdown.send(true);
+        if let Some(supervisor) = self.supervisor.take() {
+            let _ = supervisor.await;
+        }
+    }
+}
+
+impl Drop for LlamaCppManager {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+impl Launch {
+    fn spawn(&self) -> Result<tokio::process::Child> {
+        tokio::process::Command::new(&self.binary)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| BizClawError::Brain(format!("Failed to run {}: {e}", self.binary.display())))
+    }
+
+    async fn healthy(&self) -> bool {
+        self.client
+            .get(&self.health_url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .is_ok_and(|r| r.status().is_success())
+    }
+
+    /// Poll `/health` until the model is loaded (503 while loading).
+    async fn wait_healthy(&self, child: &mut tokio::process::Child) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.config.startup_timeout_secs);
+        loop {
+            if let Some(exit) = child.try_wait()? {
+                return Err(BizClawError::Brain(format!("llama-server exited during startup ({exit})")));
+            }
+            if self.healthy().await {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BizClawError::Brain(format!(
+                    "llama-server not healthy after {}s",
+                    self.config.startup_timeout_secs
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+}
+
+/// Watch the running server until shutdown, restarting it when it exits or
+/// fails [`MAX_FAILED_CHECKS`] health checks in a row.
+async fn supervise(
+    launch: Launch,
+    mut child: tokio::process::Child,
+    status: Arc<Mutex<ServerStatus>>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let set = |state: ServerState, pid: Option<u32>| {
+        let mut s = status.lock().unwrap_or_else(|e| e.into_inner());
+        s.state = state;
+        s.pid = pid;
+        if state == ServerState::Restarting {
+            s.restarts += 1;
+        }
+    };
+    let period = Duration::from_secs(launch.config.health_interval_secs.max(1));
+    let mut failed_checks = 0;
+    loop {
+        let down = tokio::select! {
+            _ = shutdown.changed() => break,
+            exit = child.wait() => {
+                tracing::warn!("llama-server exited ({exit:?}), restarting");
+                true
+            }
+            _ = tokio::time::sleep(period) => {
+                if launch.healthy().await {
+                    failed_checks = 0;
+                } else {
+                    failed_checks += 1;
+                    tracing::warn!("llama-server health check failed ({failed_checks}/{MAX_FAILED_CHECKS})");
+                }
+                failed_checks >= MAX_FAILED_CHECKS
+            }
+        };
+        if !down {
+            continue;
+        }
+        failed_checks = 0;
+        let _ = child.kill().await;
+        register(None);
+
+        let mut restarted = None;
+        for attempt in 0..launch.config.max_restarts {
+            set(ServerState::Restarting, None);
+            let backoff = Duration::from_secs(1 << attempt.min(6));
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            let mut next = match launch.spawn() {
+                Ok(next) => next,
+                Err(e) => {
+                    tracing::warn!("llama-server restart {} failed: {e}", attempt + 1);
+                    continue;
+                }
+            };
+            match launch.wait_healthy(&mut next).await {
+                Ok(()) => {
+                    restarted = Some(next);
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!("llama-server restart {} failed: {e}", attempt + 1);
+                    let _ = next.kill().await;
+                }
+            }
+        }
+        match restarted {
+            Some(next) => {
+                child = next;
+                set(ServerState::Healthy, child.id());
+                register(Some(format!("http://{}:{}/v1", launch.config.host, launch.config.port)));
+                tracing::info!("✅ llama-server restarted");
+            }
+            None if *shutdown.borrow() => break,
+            None => {
+                tracing::error!("llama-server failed {} restarts, giving up", launch.config.max_restarts);
+                set(ServerState::Failed, None);
+                return;
+            }
+        }
+    }
+    let _ = child.kill().await;
+    register(None);
+    set(ServerState::Stopped, None);
+}
+
+/// `llama-server` command line for `model`.
+fn server_args(config: &LlamaCppConfig, model: &Path) -> Vec<String> {
+    let mut args = vec![
+        "-m".into(),
+        model.to_string_lossy().into_owned(),
+        "--host".into(),
+        config.host.clone(),
+        "--port".into(),
+        config.port.to_string(),
+        "-c".into(),
+        config.context_length.to_string(),
+        "-ngl".into(),
+        config.n_gpu_layers.to_string(),
+    ];
+    args.extend(config.extra_args.iter().cloned());
+    args
+}
+
+/// The server binary: `binary_path`, else `llama-server` on PATH or under
+/// `~/.bizclaw/bin`, else (with `auto_download`) the latest release.
+pub async fn resolve_binary(config: &LlamaCppConfig) -> Result<PathBuf> {
+    if !config.binary_path.is_empty() {
+        return Ok(PathBuf::from(&config.binary_path));
+    }
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).map(|dir| dir.join(SERVER_BINARY)).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if let Some(found) = on_path.into_iter().find(|p| p.is_file()) {
+        return Ok(found);
+    }
+    let bin_dir = bizclaw_core::BizClawConfig::home_dir().join("bin");
+    if let Some(found) = find_file(&bin_dir, SERVER_BINARY, 4) {
+        return Ok(found);
+    }
+    if !config.auto_download {
+        return Err(BizClawError::Brain(format!(
+            "{SERVER_BINARY} not found on PATH or in {}; set llamacpp.binary_path or enable auto_download",
+            bin_dir.display()
+        )));
+    }
+    download_server(&bin_dir).await
+}
+
+/// Depth-limited search for a file named `name` under `dir`.
+fn find_file(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let mut subdirs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if entry.file_name() == name {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs.iter().find_map(|d| find_file(d, name, depth - 1))
+}
+
+/// Release asset for `os`/`arch` (`std::env::consts` names), preferring
+/// GPU (Vulkan) builds; macOS builds use Metal.
+fn pick_asset<'a>(names: &[&'a str], os: &str, arch: &str) -> Option<&'a str> {
+    let builds: &[&str] = match (os, arch) {
+        ("linux", "x86_64") => &["-bin-ubuntu-vulkan-x64.", "-bin-ubuntu-x64."],
+        ("linux", "aarch64") => &["-bin-ubuntu-arm64."],
+        ("macos", "aarch64") => &["-bin-macos-arm64."],
+        ("macos", "x86_64") => &["-bin-macos-x64."],
+        ("windows", "x86_64") => &["-bin-win-vulkan-x64.", "-bin-win-cpu-x64."],
+        ("windows", "aarch64") => &["-bin-win-cpu-arm64."],
+        _ => &[],
+    };
+    let archive = |n: &&str| n.ends_with(".zip") || n.ends_with(".tar.gz");
+    builds
+        .iter()
+        .find_map(|b| names.iter().find(|n| n.contains(b) && archive(n)))
+        .copied()
+}
+
+/// Download and unpack the latest llama.cpp release for this platform into
+/// `dir`, returning the server binary.
+async fn download_server(dir: &Path) -> Result<PathBuf> {
+    let http = |e: reqwest::Error| BizClawError::Http(format!("llama.cpp download: {e}"));
+    let client = reqwest::Client::builder().user_agent("bizclaw").build().map_err(http)?;
+    let release: serde_json::Value = client
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(http)?
+        .json()
+        .await
+        .map_err(http)?;
+    let assets: Vec<(&str, &str)> = release["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| Some((a["name"].as_str()?, a["browser_download_url"].as_str()?)))
+        .collect();
+    let names: Vec<&str> = assets.iter().map(|(n, _)| *n).collect();
+    let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+    let name = pick_asset(&names, os, arch).ok_or_else(|| {
+        BizClawError::Brain(format!("No llama.cpp release build for {os}/{arch}; set llamacpp.binary_path"))
+    })?;
+    let url = assets.iter().find(|(n, _)| *n == name).map(|(_, u)| *u).unwrap_or_default();
+
+    tracing::info!("⬇️  Downloading {name}");
+    std::fs::create_dir_all(dir)?;
+    let archive = dir.join(name);
+    let bytes = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(http)?
+        .bytes()
+        .await
+        .map_err(http)?;
+    tokio::fs::write(&archive, &bytes).await?;
+
+    let target = dir.join(name.trim_end_matches(".zip").trim_end_matches(".tar.gz"));
+    std::fs::create_dir_all(&target)?;
+    extract(&archive, &target).await?;
+    let _ = std::fs::remove_file(&archive);
+
+    let binary = find_file(&target, SERVER_BINARY, 4).ok_or_else(|| {
+        BizClawError::Brain(format!("{name} does not contain {SERVER_BINARY}"))
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755))?;
+    }
+    tracing::info!("✅ llama-server installed: {}", binary.display());
+    Ok(binary)
+}
+
+/// Unpack with the system's `unzip` or `tar` (bsdtar also reads zip).
+async fn extract(archive: &Path, dir: &Path) -> Result<()> {
+    let run = |program: &str, args: Vec<&std::ffi::OsStr>| {
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(args).stdout(Stdio::null()).stderr(Stdio::null());
+        cmd
+    };
+    let (archive, dir) = (archive.as_os_str(), dir.as_os_str());
+    if archive.to_string_lossy().ends_with(".zip")
+        && run("unzip", vec!["-o".as_ref(), "-q".as_ref(), archive, "-d".as_ref(), dir])
+            .status()
+            .await
+            .is_ok_and(|s| s.success())
+    {
+        return Ok(());
+    }
+    let status = run("tar", vec!["-xf".as_ref(), archive, "-C".as_ref(), dir]).status().await?;
+    if !status.success() {
+        return Err(BizClawError::Brain(format!(
+            "Could not unpack {} (needs unzip or tar)",
+            archive.to_string_lossy()
+        )));
+    }
+    Ok(())
+}
+
 /// Install instructions for llama.cpp on various platforms.
 pub fn install_instructions() -> String {
     r#"
@@ -282,3 +719,59 @@ pub fn install_instructions() -> String {
 "#
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_asset() {
+        let names = [
+            "llama-b6000-bin-macos-arm64.zip",
+            "llama-b6000-bin-ubuntu-x64.zip",
+            "llama-b6000-bin-ubuntu-vulkan-x64.zip",
+            "llama-b6000-bin-win-cpu-x64.zip",
+            "llama-b6000-xcframework.zip",
+        ];
+        assert_eq!(
+            pick_asset(&names, "linux", "x86_64"),
+            Some("llama-b6000-bin-ubuntu-vulkan-x64.zip")
+        );
+        assert_eq!(pick_asset(&names[..2], "linux", "x86_64"), Some("llama-b6000-bin-ubuntu-x64.zip"));
+        assert_eq!(pick_asset(&names, "macos", "aarch64"), Some("llama-b6000-bin-macos-arm64.zip"));
+        assert_eq!(pick_asset(&names, "windows", "x86_64"), Some("llama-b6000-bin-win-cpu-x64.zip"));
+        assert_eq!(pick_asset(&names, "linux", "aarch64"), None);
+    }
+
+    #[test]
+    fn test_server_args() {
+        let config = LlamaCppConfig {
+            port: 9000,
+            n_gpu_layers: 0,
+            extra_args: vec!["--flash-attn".into()],
+            ..Default::default()
+        };
+        let args = server_args(&config, Path::new("/m/model.gguf"));
+        assert_eq!(&args[..2], ["-m", "/m/model.gguf"]);
+        assert!(args.windows(2).any(|w| w == ["--port", "9000"]));
+        assert!(args.windows(2).any(|w| w == ["-ngl", "0"]));
+        assert_eq!(args.last().map(String::as_str), Some("--flash-attn"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_start_fails_when_server_exits() {
+        let model = std::env::temp_dir().join("bizclaw_llamacpp_test.gguf");
+        std::fs::write(&model, b"GGUF").unwrap();
+        let config = LlamaCppConfig {
+            binary_path: "/bin/false".into(),
+            port: 1,
+            startup_timeout_secs: 5,
+            ..Default::default()
+        };
+        let err = LlamaCppManager::start(&config, &model).await.err().unwrap();
+        assert!(err.to_string().contains("exited during startup"), "{err}");
+        assert_eq!(managed_base_url(), None);
+        std::fs::remove_file(&model).ok();
+    }
+}
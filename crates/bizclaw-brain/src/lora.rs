@@ -0,0 +1,219 @@
+//! LoRA adapters in llama.cpp's GGUF adapter format.
+//!
+//! An adapter stores, for each adapted weight `X.weight`, a pair
+//! `X.weight.lora_a` [rank x cols] and `X.weight.lora_b` [rows x rank].
+//! The adapted projection is `W·x + scale·B·(A·x)` with
+//! `scale = user_scale · alpha / rank`. Deltas are applied on the fly next
+//! to the base matmul rather than merged, so the base weights stay mmapped
+//! and quantized and adapters can be swapped without reloading the model.
+
+use crate::mmap::MmapModel;
+use crate::{quant, tensor};
+use bizclaw_core::error::{BizClawError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Magic of the legacy pre-GGUF adapter format (`ggla`, little-endian).
+const GGLA_MAGIC: &[u8; 4] = b"algg";
+
+/// One low-rank update `scale·B·A` to a [rows x cols] weight.
+#[derive(Debug, Clone)]
+pub struct LoraDelta {
+    /// [rank x cols], row-major.
+    pub a: Vec<f32>,
+    /// [rows x rank], row-major.
+    pub b: Vec<f32>,
+    pub rank: usize,
+    pub rows: usize,
+    pub cols: usize,
+    pub scale: f32,
+}
+
+impl LoraDelta {
+    /// `out[rows] += scale · B · (A · x[cols])`.
+    pub fn apply(&self, out: &mut [f32], x: &[f32]) {
+        let mut ax = vec![0.0f32; self.rank];
+        crate::simd::matmul_simd(&mut ax, &self.a, x, self.rank, self.cols);
+        for (o, row) in out.iter_mut().zip(self.b.chunks(self.rank)) {
+            *o += self.scale * tensor::dot_product(row, &ax);
+        }
+    }
+}
+
+/// Loaded adapters, keyed by the base model's tensor index.
+#[derive(Debug, Default)]
+pub struct LoraSet {
+    deltas: HashMap<usize, Vec<LoraDelta>>,
+}
+
+impl LoraSet {
+    /// Deltas for base tensor `idx` (empty when it is not adapted).
+    pub fn for_tensor(&self, idx: usize) -> &[LoraDelta] {
+        self.deltas.get(&idx).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Number of adapted base tensors.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Bytes held by the dequantized A/B matrices.
+    pub fn memory_usage(&self) -> usize {
+        self.deltas
+            .values()
+            .flatten()
+            .map(|d| (d.a.len() + d.b.len()) * std::mem::size_of::<f32>())
+            .sum()
+    }
+
+    /// Load the adapter at `path` against `base`, scaled by `scale`.
+    /// Adapters stack: deltas from several files on the same weight add up.
+    pub fn add_adapter(&mut self, path: &Path, base: &MmapModel, scale: f32) -> Result<()> {
+        let mut magic = [0u8; 4];
+        if std::fs::File::open(path)
+            .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+            .is_ok()
+            && &magic == GGLA_MAGIC
+        {
+            return Err(BizClawError::Brain(format!(
+                "{} is a legacy ggla adapter; convert it to GGUF with llama.cpp's convert_lora_to_gguf.py",
+                path.display()
+            )));
+        }
+
+        let adapter = MmapModel::load(path)?;
+        let gguf = &adapter.gguf;
+        let kind = |key: &str| gguf.metadata.get(key).and_then(|v| v.as_str());
+        if kind("general.type").is_some_and(|t| t != "adapter")
+            || kind("adapter.type").is_some_and(|t| t != "lora")
+        {
+            return Err(BizClawError::Brain(format!(
+                "{} is not a LoRA adapter",
+                path.display()
+            )));
+        }
+        if let (Some(theirs), Some(ours)) = (gguf.architecture(), base.gguf.architecture())
+            && theirs != ours
+        {
+            return Err(BizClawError::Brain(format!(
+                "Adapter {} targets '{theirs}', model is '{ours}'",
+                path.display()
+            )));
+        }
+        let alpha = gguf.get_f32("adapter.lora.alpha");
+
+        let mut added = 0;
+        for (a_idx, a_info) in gguf.tensors.iter().enumerate() {
+            let Some(target) = a_info.name.strip_suffix(".lora_a") else {
+                continue;
+            };
+            let b_name = format!("{target}.lora_b");
+            let b_idx = gguf
+                .tensors
+                .iter()
+                .position(|t| t.name == b_name)
+                .ok_or_else(|| BizClawError::Brain(format!("Adapter is missing {b_name}")))?;
+            if target.starts_with("token_embd") {
+                tracing::warn!("Skipping LoRA on {target}: embedding adapters are not supported");
+                continue;
+            }
+            let base_idx = base
+                .gguf
+                .tensors
+                .iter()
+                .position(|t| t.name == target)
+                .ok_or_else(|| {
+                    BizClawError::Brain(format!("Adapter targets {target}, which the model lacks"))
+                })?;
+
+            let b_info = &gguf.tensors[b_idx];
+            let base_dims = &base.gguf.tensors[base_idx].dims;
+            let (cols, rows) = (base_dims[0] as usize, *base_dims.get(1).unwrap_or(&1) as usize);
+            let rank = *a_info.dims.get(1).unwrap_or(&1) as usize;
+            if a_info.dims[0] as usize != cols
+                || b_info.dims[0] as usize != rank
+                || *b_info.dims.get(1).unwrap_or(&1) as usize != rows
+            {
+                return Err(BizClawError::Brain(format!(
+                    "LoRA shapes for {target} do not match the model: A {:?}, B {:?}, base {:?}",
+                    a_info.dims, b_info.dims, base_dims
+                )));
+            }
+
+            let delta = LoraDelta {
+                a: dequant(&adapter, a_idx, rank * cols)?,
+                b: dequant(&adapter, b_idx, rows * rank)?,
+                rank,
+                rows,
+                cols,
+                scale: scale * alpha.unwrap_or(rank as f32) / rank as f32,
+            };
+            self.deltas.entry(base_idx).or_default().push(delta);
+            added += 1;
+        }
+
+        if added == 0 {
+            return Err(BizClawError::Brain(format!(
+                "{} contains no LoRA tensors",
+                path.display()
+            )));
+        }
+        tracing::info!("LoRA adapter {}: {added} tensors, scale {scale}", path.display());
+        Ok(())
+    }
+}
+
+fn dequant(model: &MmapModel, idx: usize, n_elements: usize) -> Result<Vec<f32>> {
+    let mut out = vec![0.0f32; n_elements];
+    quant::dequantize_row(
+        model.tensor_data(idx)?,
+        &mut out,
+        n_elements,
+        model.gguf.tensors[idx].ggml_type,
+    )?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_matches_merged_weight() {
+        // W' = W + scale·B·A for a 3x4 weight with a rank-2 update
+        let (rows, cols, rank) = (3, 4, 2);
+        let w: Vec<f32> = (0..rows * cols).map(|i| i as f32 * 0.1 - 0.5).collect();
+        let a: Vec<f32> = (0..rank * cols).map(|i| (i as f32 * 0.7).sin()).collect();
+        let b: Vec<f32> = (0..rows * rank).map(|i| (i as f32 * 1.3).cos()).collect();
+        let delta = LoraDelta { a: a.clone(), b: b.clone(), rank, rows, cols, scale: 0.5 };
+        let x = [0.3f32, -1.0, 2.0, 0.25];
+
+        let mut merged = w.clone();
+        for r in 0..rows {
+            for c in 0..cols {
+                let ba: f32 = (0..rank).map(|k| b[r * rank + k] * a[k * cols + c]).sum();
+                merged[r * cols + c] += 0.5 * ba;
+            }
+        }
+        let mut expected = vec![0.0f32; rows];
+        tensor::matmul(&mut expected, &merged, &x, rows, cols);
+
+        let mut out = vec![0.0f32; rows];
+        tensor::matmul(&mut out, &w, &x, rows, cols);
+        delta.apply(&mut out, &x);
+        for (o, e) in out.iter().zip(&expected) {
+            assert!((o - e).abs() < 1e-5, "{o} vs {e}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_tensor_has_no_deltas() {
+        let set = LoraSet::default();
+        assert!(set.is_empty());
+        assert!(set.for_tensor(7).is_empty());
+    }
+}
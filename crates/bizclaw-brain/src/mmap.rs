@@ -3,20 +3,29 @@
 //! Uses mmap to load model weights directly from disk without copying
 //! them into process memory. This is critical for running on devices
 //! with limited RAM (e.g., Raspberry Pi with 512MB).
+//!
+//! Pages are faulted in on first use, so without a [`Preload`] warm-up the
+//! first token after load pays for reading the whole model from disk.
 
 use bizclaw_core::error::{BizClawError, Result};
 use memmap2::Mmap;
 use std::fs::File;
 use std::path::Path;
+use std::sync::Arc;
 
 use crate::gguf::GgufFile;
 
+pub use bizclaw_core::config::Preload;
+
+/// Stride of the touch pass; one read per page faults the page in.
+const PAGE_SIZE: usize = 4096;
+
 /// A memory-mapped GGUF model file.
 pub struct MmapModel {
     /// The parsed GGUF header with metadata and tensor index.
     pub gguf: GgufFile,
-    /// Memory-mapped file data.
-    mmap: Mmap,
+    /// Memory-mapped file data, shared with a background preload thread.
+    mmap: Arc<Mmap>,
 }
 
 impl MmapModel {
@@ -54,7 +63,60 @@ impl MmapModel {
             mmap.len() as f64 / (1024.0 * 1024.0)
         );
 
-        Ok(Self { gguf, mmap })
+        Ok(Self {
+            gguf,
+            mmap: Arc::new(mmap),
+        })
+    }
+
+    /// Warm the page cache for the tensor data. Returns the touch thread
+    /// for [`Preload::Background`]; it holds its own reference to the
+    /// mapping, so it may outlive this model.
+    pub fn preload(&self, mode: Preload) -> Option<std::thread::JoinHandle<()>> {
+        let start = (self.gguf.data_offset as usize).min(self.mmap.len());
+        let len = self.mmap.len() - start;
+        match mode {
+            Preload::None => None,
+            Preload::Advise => {
+                #[cfg(unix)]
+                if let Err(e) = self.mmap.advise_range(memmap2::Advice::WillNeed, start, len) {
+                    tracing::warn!("madvise(WILLNEED) failed: {e}");
+                }
+                #[cfg(not(unix))]
+                tracing::debug!("madvise is unavailable on this platform; skipping preload");
+                None
+            }
+            Preload::Background => {
+                let mmap = Arc::clone(&self.mmap);
+                let spawned = std::thread::Builder::new()
+                    .name("bizclaw-preload".into())
+                    .spawn(move || {
+                        let started = std::time::Instant::now();
+                        let pages = touch_pages(&mmap[start..]);
+                        tracing::info!(
+                            "Background preload: {pages} pages in {:.2}s",
+                            started.elapsed().as_secs_f64()
+                        );
+                    });
+                match spawned {
+                    Ok(handle) => Some(handle),
+                    Err(e) => {
+                        tracing::warn!("Failed to spawn preload thread: {e}");
+                        None
+                    }
+                }
+            }
+            Preload::Eager => {
+                let started = std::time::Instant::now();
+                let pages = touch_pages(&self.mmap[start..]);
+                tracing::info!(
+                    "Preloaded {pages} pages ({:.1} MB) in {:.2}s",
+                    len as f64 / (1024.0 * 1024.0),
+                    started.elapsed().as_secs_f64()
+                );
+                None
+            }
+        }
     }
 
     /// Get a raw byte slice for a tensor's data.
@@ -110,3 +172,53 @@ impl MmapModel {
         self.gguf.tensors.len()
     }
 }
+
+/// Read one byte per page of `data` so every page is resident. Returns the
+/// number of pages touched.
+fn touch_pages(data: &[u8]) -> usize {
+    let mut sum = 0u8;
+    let mut pages = 0;
+    for offset in (0..data.len()).step_by(PAGE_SIZE) {
+        sum = sum.wrapping_add(data[offset]);
+        pages += 1;
+    }
+    std::hint::black_box(sum);
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A GGUF header with no metadata or tensors, followed by `data_len`
+    /// bytes of payload.
+    fn write_empty_gguf(path: &Path, data_len: usize) {
+        let mut buf = b"GGUF".to_vec();
+        buf.extend(3u32.to_le_bytes());
+        buf.extend(0u64.to_le_bytes()); // tensors
+        buf.extend(0u64.to_le_bytes()); // metadata
+        buf.resize(buf.len().next_multiple_of(32) + data_len, 7);
+        std::fs::write(path, buf).unwrap();
+    }
+
+    #[test]
+    fn test_touch_pages_counts_partial_page() {
+        assert_eq!(touch_pages(&[]), 0);
+        assert_eq!(touch_pages(&vec![1u8; PAGE_SIZE]), 1);
+        assert_eq!(touch_pages(&vec![1u8; PAGE_SIZE * 2 + 1]), 3);
+    }
+
+    #[test]
+    fn test_every_preload_mode_completes() {
+        let path = std::env::temp_dir().join(format!("bizclaw-preload-{}.gguf", std::process::id()));
+        write_empty_gguf(&path, 3 * PAGE_SIZE);
+        let model = MmapModel::load(&path).unwrap();
+        assert!(model.preload(Preload::None).is_none());
+        assert!(model.preload(Preload::Advise).is_none());
+        assert!(model.preload(Preload::Eager).is_none());
+        let handle = model.preload(Preload::Background).expect("touch thread");
+        drop(model);
+        handle.join().unwrap();
+        let _ = std::fs::remove_file(path);
+    }
+}
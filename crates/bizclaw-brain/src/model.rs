@@ -1,28 +1,135 @@
-//! LLaMA model forward pass.
+//! Transformer model hyperparameters.
 //!
-//! Implements the transformer architecture for LLaMA-family models.
-//! Reads weights from mmap, dequantizes on-the-fly, and computes
-//! the forward pass producing logits for the next token.
+//! Reads GGUF metadata for LLaMA-family models and the architectures that
+//! share its layout with small variations (Mistral, Qwen2, Phi-3, Gemma).
+//! The variations themselves are described by [`Architecture`] and applied
+//! in the forward pass.
+
+use crate::rope::RopeStyle;
+
+/// Model architecture, detected from `general.architecture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture {
+    /// LLaMA-2/3 and anything converted with the LLaMA layout.
+    Llama,
+    /// Mistral: LLaMA layout, optional sliding-window attention.
+    Mistral,
+    /// Qwen2: biased Q/K/V projections, NeoX RoPE.
+    Qwen2,
+    /// Phi-3: fused `attn_qkv` and fused gate/up `ffn_up`, NeoX RoPE.
+    Phi3,
+    /// Gemma: embeddings scaled by √dim, GeGLU FFN, explicit head size, NeoX RoPE.
+    Gemma,
+}
+
+impl Architecture {
+    /// Map a `general.architecture` value. Unknown names fall back to LLaMA.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "llama" => Self::Llama,
+            "mistral" => Self::Mistral,
+            "qwen2" => Self::Qwen2,
+            "phi3" => Self::Phi3,
+            "gemma" => Self::Gemma,
+            other => {
+                tracing::warn!("Unknown architecture '{other}', assuming LLaMA layout");
+                Self::Llama
+            }
+        }
+    }
+
+    /// Canonical GGUF name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Llama => "llama",
+            Self::Mistral => "mistral",
+            Self::Qwen2 => "qwen2",
+            Self::Phi3 => "phi3",
+            Self::Gemma => "gemma",
+        }
+    }
+
+    /// RoPE pairing. llama.cpp permutes LLaMA/Mistral Q/K rows at conversion
+    /// so they rotate adjacent pairs; the others keep the HF half split.
+    pub fn rope_style(&self) -> RopeStyle {
+        match self {
+            Self::Llama | Self::Mistral => RopeStyle::Normal,
+            Self::Qwen2 | Self::Phi3 | Self::Gemma => RopeStyle::Neox,
+        }
+    }
+
+    /// FFN gate activation.
+    pub fn ffn_activation(&self) -> FfnActivation {
+        match self {
+            Self::Gemma => FfnActivation::Gelu,
+            _ => FfnActivation::Silu,
+        }
+    }
+
+    /// Whether token embeddings are multiplied by √dim before the first layer.
+    pub fn scales_embeddings(&self) -> bool {
+        *self == Self::Gemma
+    }
+}
+
+/// Activation applied to the FFN gate before multiplying with the up projection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfnActivation {
+    /// SwiGLU.
+    Silu,
+    /// GeGLU (tanh approximation).
+    Gelu,
+}
+
+/// How per-token hidden states are pooled into one embedding
+/// (`{arch}.pooling_type`, set by embedding-model conversions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Average over all tokens.
+    Mean,
+    /// First token (BERT-style `[CLS]`, here the BOS token).
+    Cls,
+    /// Last token (decoder embedding models such as e5-mistral).
+    Last,
+}
+
+impl Pooling {
+    /// Map llama.cpp's `pooling_type` enum; none / rank yield `None`.
+    pub fn from_gguf(value: u32) -> Option<Self> {
+        match value {
+            1 => Some(Self::Mean),
+            2 => Some(Self::Cls),
+            3 => Some(Self::Last),
+            _ => None,
+        }
+    }
+}
 
 /// Model hyperparameters extracted from GGUF metadata.
 #[derive(Debug, Clone)]
 pub struct ModelParams {
+    pub arch: Architecture,
     pub vocab_size: u32,
     pub dim: u32,        // embedding dimension
     pub hidden_dim: u32, // FFN hidden dimension
     pub n_layers: u32,
     pub n_heads: u32,
     pub n_kv_heads: u32, // for GQA (Grouped Query Attention)
-    pub head_dim: u32,   // dim / n_heads
+    pub head_dim: u32,   // attention.key_length, else dim / n_heads
+    pub rope_dim: u32,   // leading dims of each head that RoPE rotates
     pub max_seq_len: u32,
     pub rope_theta: f32,
     pub rms_norm_eps: f32,
+    pub sliding_window: Option<u32>, // attend to at most this many positions
+    pub pooling: Option<Pooling>,    // set for dedicated embedding models
+    pub causal: bool,                // false: every token attends to the whole batch
 }
 
 impl Default for ModelParams {
     fn default() -> Self {
         // TinyLlama 1.1B defaults
         Self {
+            arch: Architecture::Llama,
             vocab_size: 32000,
             dim: 2048,
             hidden_dim: 5632,
@@ -30,9 +137,13 @@ impl Default for ModelParams {
             n_heads: 32,
             n_kv_heads: 4,
             head_dim: 64,
+            rope_dim: 64,
             max_seq_len: 2048,
             rope_theta: 10000.0,
             rms_norm_eps: 1e-5,
+            sliding_window: None,
+            pooling: None,
+            causal: true,
         }
     }
 }
@@ -40,8 +151,10 @@ impl Default for ModelParams {
 impl ModelParams {
     /// Extract model parameters from GGUF metadata.
     pub fn from_gguf(gguf: &crate::gguf::GgufFile) -> Self {
-        let arch = gguf.architecture().unwrap_or("llama");
-        let prefix = format!("{arch}.");
+        let arch_name = gguf.architecture().unwrap_or("llama");
+        let arch = Architecture::from_name(arch_name);
+        // Keys are namespaced by the name in the file, even for unknown archs
+        let prefix = format!("{arch_name}.");
 
         let dim = gguf
             .get_u32(&format!("{prefix}embedding_length"))
@@ -52,8 +165,12 @@ impl ModelParams {
         let n_kv_heads = gguf
             .get_u32(&format!("{prefix}attention.head_count_kv"))
             .unwrap_or(n_heads);
+        let head_dim = gguf
+            .get_u32(&format!("{prefix}attention.key_length"))
+            .unwrap_or(dim / n_heads);
 
         Self {
+            arch,
             vocab_size: gguf
                 .get_u32(&format!("{prefix}vocab_size"))
                 .or_else(|| {
@@ -73,7 +190,11 @@ impl ModelParams {
             n_layers: gguf.get_u32(&format!("{prefix}block_count")).unwrap_or(22),
             n_heads,
             n_kv_heads,
-            head_dim: dim / n_heads,
+            head_dim,
+            rope_dim: gguf
+                .get_u32(&format!("{prefix}rope.dimension_count"))
+                .unwrap_or(head_dim)
+                .min(head_dim),
             max_seq_len: gguf
                 .get_u32(&format!("{prefix}context_length"))
                 .unwrap_or(2048),
@@ -83,6 +204,120 @@ impl ModelParams {
             rms_norm_eps: gguf
                 .get_f32(&format!("{prefix}attention.layer_norm_rms_epsilon"))
                 .unwrap_or(1e-5),
+            sliding_window: gguf
+                .get_u32(&format!("{prefix}attention.sliding_window"))
+                .filter(|&w| w > 0),
+            pooling: gguf
+                .get_u32(&format!("{prefix}pooling_type"))
+                .and_then(Pooling::from_gguf),
+            causal: gguf
+                .metadata
+                .get(&format!("{prefix}attention.causal"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        }
+    }
+
+    /// Width of the concatenated query heads (differs from `dim` on Gemma-7B).
+    pub fn q_dim(&self) -> u32 {
+        self.n_heads * self.head_dim
+    }
+
+    /// Width of the concatenated key (or value) heads.
+    pub fn kv_dim(&self) -> u32 {
+        self.n_kv_heads * self.head_dim
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gguf::{GgufFile, GgufValue};
+    use std::collections::HashMap;
+
+    fn gguf(arch: &str, kvs: &[(&str, u32)]) -> GgufFile {
+        let mut metadata = HashMap::new();
+        metadata.insert("general.architecture".to_string(), GgufValue::String(arch.into()));
+        for (key, v) in kvs {
+            metadata.insert(format!("{arch}.{key}"), GgufValue::U32(*v));
+        }
+        GgufFile {
+            version: 3,
+            metadata,
+            tensors: vec![],
+            data_offset: 0,
+            alignment: 32,
+        }
+    }
+
+    #[test]
+    fn test_detects_architecture_and_variants() {
+        for (name, arch, rope, act) in [
+            ("llama", Architecture::Llama, RopeStyle::Normal, FfnActivation::Silu),
+            ("mistral", Architecture::Mistral, RopeStyle::Normal, FfnActivation::Silu),
+            ("qwen2", Architecture::Qwen2, RopeStyle::Neox, FfnActivation::Silu),
+            ("phi3", Architecture::Phi3, RopeStyle::Neox, FfnActivation::Silu),
+            ("gemma", Architecture::Gemma, RopeStyle::Neox, FfnActivation::Gelu),
+        ] {
+            let params = ModelParams::from_gguf(&gguf(name, &[("embedding_length", 64)]));
+            assert_eq!(params.arch, arch);
+            assert_eq!(arch.name(), name);
+            assert_eq!(arch.rope_style(), rope);
+            assert_eq!(arch.ffn_activation(), act);
+            assert_eq!(arch.scales_embeddings(), arch == Architecture::Gemma);
         }
+        assert_eq!(Architecture::from_name("falcon-x"), Architecture::Llama);
+    }
+
+    #[test]
+    fn test_reads_arch_prefixed_hyperparameters() {
+        // Gemma-7B: 16 heads of 256 over a 3072-wide stream
+        let params = ModelParams::from_gguf(&gguf(
+            "gemma",
+            &[
+                ("embedding_length", 3072),
+                ("attention.head_count", 16),
+                ("attention.head_count_kv", 16),
+                ("attention.key_length", 256),
+                ("block_count", 28),
+            ],
+        ));
+        assert_eq!(params.head_dim, 256);
+        assert_eq!(params.rope_dim, 256);
+        assert_eq!(params.q_dim(), 4096);
+        assert_eq!(params.kv_dim(), 4096);
+        assert_eq!(params.n_layers, 28);
+        assert_eq!(params.sliding_window, None);
+
+        let params = ModelParams::from_gguf(&gguf(
+            "mistral",
+            &[
+                ("embedding_length", 4096),
+                ("attention.head_count", 32),
+                ("attention.head_count_kv", 8),
+                ("attention.sliding_window", 4096),
+                ("rope.dimension_count", 64),
+            ],
+        ));
+        assert_eq!(params.head_dim, 128);
+        assert_eq!(params.rope_dim, 64);
+        assert_eq!(params.kv_dim(), 1024);
+        assert_eq!(params.sliding_window, Some(4096));
+    }
+
+    #[test]
+    fn test_reads_embedding_metadata() {
+        let params = ModelParams::from_gguf(&gguf("llama", &[("embedding_length", 64)]));
+        assert_eq!(params.pooling, None);
+        assert!(params.causal);
+
+        let mut file = gguf("qwen2", &[("pooling_type", 3)]);
+        file.metadata.insert("qwen2.attention.causal".into(), GgufValue::Bool(false));
+        let params = ModelParams::from_gguf(&file);
+        assert_eq!(params.pooling, Some(Pooling::Last));
+        assert!(!params.causal);
+        assert_eq!(Pooling::from_gguf(1), Some(Pooling::Mean));
+        assert_eq!(Pooling::from_gguf(2), Some(Pooling::Cls));
+        assert_eq!(Pooling::from_gguf(0), None);
     }
 }
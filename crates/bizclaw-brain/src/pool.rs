@@ -0,0 +1,169 @@
+//! Pool of loaded models with LRU eviction.
+//!
+//! Each model gets its own [`BrainEngine`] (mmap, KV cache, sampler),
+//! keyed by canonical file path. Loading a model that does not fit the
+//! memory budget evicts the least recently used ones first; the model being
+//! requested is always kept, even when it alone exceeds the budget.
+//! Evicted engines stay alive until callers holding them finish.
+
+use crate::{BrainConfig, BrainEngine};
+use bizclaw_core::error::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A loaded engine shared between callers; async callers `.lock().await`,
+/// sync ones `blocking_lock()`.
+pub type SharedEngine = Arc<tokio::sync::Mutex<BrainEngine>>;
+
+struct Slot {
+    path: PathBuf,
+    engine: SharedEngine,
+    /// Resident size estimate from [`BrainEngine::memory_usage`].
+    bytes: usize,
+    /// Pool clock value at the last [`ModelPool::get`].
+    last_used: u64,
+}
+
+/// Loaded models keyed by path, bounded by a memory budget.
+pub struct ModelPool {
+    config: BrainConfig,
+    /// Budget in bytes; 0 keeps a single model.
+    budget: usize,
+    slots: Mutex<Vec<Slot>>,
+    clock: AtomicU64,
+}
+
+impl ModelPool {
+    /// Create an empty pool. Every engine is built from `config`;
+    /// `budget_bytes` of 0 holds one model at a time.
+    pub fn new(config: BrainConfig, budget_bytes: usize) -> Self {
+        Self {
+            config,
+            budget: budget_bytes,
+            slots: Mutex::new(Vec::new()),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// The engine for `path`, loading it (and evicting least recently used
+    /// models to stay within budget) on a miss.
+    pub fn get(&self, path: &Path) -> Result<SharedEngine> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let now = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        // Held across the load so concurrent misses don't load a model twice
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(slot) = slots.iter_mut().find(|s| s.path == key) {
+            slot.last_used = now;
+            return Ok(Arc::clone(&slot.engine));
+        }
+
+        let mut engine = BrainEngine::new(self.config.clone());
+        engine.load_model(&key)?;
+        let bytes = engine.memory_usage();
+        let engine = Arc::new(tokio::sync::Mutex::new(engine));
+        slots.push(Slot {
+            path: key,
+            engine: Arc::clone(&engine),
+            bytes,
+            last_used: now,
+        });
+
+        let usage: Vec<(usize, u64)> = slots.iter().map(|s| (s.bytes, s.last_used)).collect();
+        let mut evict = lru_evictions(&usage, slots.len() - 1, self.budget);
+        evict.sort_unstable_by(|a, b| b.cmp(a));
+        for i in evict {
+            let slot = slots.remove(i);
+            tracing::info!(
+                "Model pool: evicted {} ({} MB)",
+                slot.path.display(),
+                slot.bytes / 1024 / 1024
+            );
+        }
+        Ok(engine)
+    }
+
+    /// The engine for `path` if loaded, without marking it used.
+    pub fn peek(&self, path: &Path) -> Option<SharedEngine> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.iter().find(|s| s.path == key).map(|s| Arc::clone(&s.engine))
+    }
+
+    /// Whether the model at `path` is currently loaded.
+    pub fn contains(&self, path: &Path) -> bool {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.iter().any(|s| s.path == key)
+    }
+
+    /// Loaded model paths, most recently used first.
+    pub fn loaded(&self) -> Vec<PathBuf> {
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let mut by_use: Vec<&Slot> = slots.iter().collect();
+        by_use.sort_by_key(|s| std::cmp::Reverse(s.last_used));
+        by_use.into_iter().map(|s| s.path.clone()).collect()
+    }
+
+    /// Estimated bytes held by loaded models.
+    pub fn memory_usage(&self) -> usize {
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.iter().map(|s| s.bytes).sum()
+    }
+
+    /// Drop the model at `path` from the pool. Returns whether it was loaded.
+    pub fn unload(&self, path: &Path) -> bool {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let before = slots.len();
+        slots.retain(|s| s.path != key);
+        slots.len() != before
+    }
+}
+
+/// Indices of `(bytes, last_used)` entries to evict, least recently used
+/// first, until the rest fit in `budget` (0: keep only `keep`). The entry
+/// at `keep` is never evicted.
+fn lru_evictions(slots: &[(usize, u64)], keep: usize, budget: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..slots.len()).filter(|&i| i != keep).collect();
+    order.sort_by_key(|&i| slots[i].1);
+    let mut total: usize = slots.iter().map(|s| s.0).sum();
+    let mut evict = Vec::new();
+    for i in order {
+        if budget > 0 && total <= budget {
+            break;
+        }
+        total -= slots[i].0;
+        evict.push(i);
+    }
+    evict
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_evicts_oldest_until_within_budget() {
+        // (bytes, last_used); entry 3 was just loaded
+        let slots = [(400, 5), (300, 2), (200, 7), (500, 9)];
+        assert_eq!(lru_evictions(&slots, 3, 2000), Vec::<usize>::new());
+        assert_eq!(lru_evictions(&slots, 3, 1100), vec![1]);
+        assert_eq!(lru_evictions(&slots, 3, 800), vec![1, 0]);
+        // The requested model stays even when it alone is over budget
+        assert_eq!(lru_evictions(&slots, 3, 100), vec![1, 0, 2]);
+        // No budget: hold a single model
+        assert_eq!(lru_evictions(&slots, 3, 0), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_failed_load_leaves_pool_unchanged() {
+        let pool = ModelPool::new(BrainConfig::default(), 0);
+        let missing = Path::new("/nonexistent/bizclaw-pool.gguf");
+        assert!(pool.get(missing).is_err());
+        assert!(!pool.contains(missing));
+        assert!(pool.loaded().is_empty());
+        assert_eq!(pool.memory_usage(), 0);
+        assert!(!pool.unload(missing));
+    }
+}
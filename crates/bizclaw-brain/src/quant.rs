@@ -1,8 +1,17 @@
 //! Quantization kernels — dequantize quantized weight blocks to f32.
 //!
-//! Supports Q4_0, Q4_K_M, Q6_K, Q8_0 formats used by GGUF models.
+//! Supports F32, F16, Q4_0, Q8_0 and the K-quants Q4_K, Q5_K and Q6_K (the
+//! building blocks of Q4_K_M/S and Q5_K_M/S files). Besides full-row
+//! dequantization, each format has a fused dot-product kernel that multiplies
+//! a quantized row with f32 activations one 32-value group at a time, so
+//! matmuls never materialize the dequantized weight matrix.
 
-use bizclaw_core::error::Result;
+use crate::gguf::GgmlType;
+use crate::simd;
+use bizclaw_core::error::{BizClawError, Result};
+
+/// Values per K-quant super-block.
+const QK_K: usize = 256;
 
 /// Dequantize Q4_0 block (18 bytes → 32 f32 values).
 /// Format: scale (f16, 2 bytes) + 16 bytes of 4-bit quantized values.
@@ -34,16 +43,262 @@ pub fn dequantize_q8_0(block: &[u8], output: &mut [f32]) {
     }
 }
 
+/// 6-bit scale and min for sub-block `j` of a Q4_K/Q5_K super-block
+/// (packed into 12 bytes: 8 sub-blocks × (6-bit scale, 6-bit min)).
+#[inline]
+fn scale_min_k4(j: usize, scales: &[u8]) -> (f32, f32) {
+    if j < 4 {
+        ((scales[j] & 63) as f32, (scales[j + 4] & 63) as f32)
+    } else {
+        let d = (scales[j + 4] & 0xF) | ((scales[j - 4] >> 6) << 4);
+        let m = (scales[j + 4] >> 4) | ((scales[j] >> 6) << 4);
+        (d as f32, m as f32)
+    }
+}
+
+/// Unpack the 32 quants of sub-block `sub` (0..8) of a Q4_K block.
+#[inline]
+fn unpack_q4_k(block: &[u8], sub: usize, q: &mut [i8; 32]) {
+    let qs = &block[16 + (sub / 2) * 32..][..32];
+    let shift = (sub % 2) * 4;
+    for (q, &b) in q.iter_mut().zip(qs) {
+        *q = ((b >> shift) & 0xF) as i8;
+    }
+}
+
+/// Unpack the 32 quants of sub-block `sub` (0..8) of a Q5_K block.
+#[inline]
+fn unpack_q5_k(block: &[u8], sub: usize, q: &mut [i8; 32]) {
+    let qh = &block[16..48];
+    let qs = &block[48 + (sub / 2) * 32..][..32];
+    let shift = (sub % 2) * 4;
+    for l in 0..32 {
+        let hi = ((qh[l] >> sub) & 1) << 4;
+        q[l] = (((qs[l] >> shift) & 0xF) | hi) as i8;
+    }
+}
+
+/// Unpack quants `32 * seg .. 32 * seg + 32` (seg 0..8) of a Q6_K block,
+/// already centered to -32..31.
+#[inline]
+fn unpack_q6_k(block: &[u8], seg: usize, q: &mut [i8; 32]) {
+    let half = seg / 4; // which 128-value half
+    let part = seg % 4; // which 32-value quarter of that half
+    let ql = &block[half * 64 + (part % 2) * 32..][..32];
+    let qh = &block[128 + half * 32..][..32];
+    let lo_shift = if part < 2 { 0 } else { 4 };
+    let hi_shift = part * 2;
+    for l in 0..32 {
+        let v = ((ql[l] >> lo_shift) & 0xF) | (((qh[l] >> hi_shift) & 3) << 4);
+        q[l] = v as i8 - 32;
+    }
+}
+
+/// Dequantize Q4_K block (144 bytes → 256 f32 values).
+/// Format: d (f16) + dmin (f16) + 12 bytes of packed 6-bit sub-block
+/// scales/mins + 128 bytes of 4-bit quants. value = d·sc·q − dmin·m
+pub fn dequantize_q4_k(block: &[u8], output: &mut [f32]) {
+    debug_assert!(block.len() >= 144);
+    debug_assert!(output.len() >= QK_K);
+
+    let d = half::f16::from_le_bytes([block[0], block[1]]).to_f32();
+    let dmin = half::f16::from_le_bytes([block[2], block[3]]).to_f32();
+    let mut q = [0i8; 32];
+    for sub in 0..8 {
+        let (sc, m) = scale_min_k4(sub, &block[4..16]);
+        unpack_q4_k(block, sub, &mut q);
+        for (o, &q) in output[sub * 32..(sub + 1) * 32].iter_mut().zip(&q) {
+            *o = d * sc * q as f32 - dmin * m;
+        }
+    }
+}
+
+/// Dequantize Q5_K block (176 bytes → 256 f32 values).
+/// Format: d (f16) + dmin (f16) + 12 bytes of scales/mins + 32 bytes of
+/// high bits + 128 bytes of low 4-bit quants.
+pub fn dequantize_q5_k(block: &[u8], output: &mut [f32]) {
+    debug_assert!(block.len() >= 176);
+    debug_assert!(output.len() >= QK_K);
+
+    let d = half::f16::from_le_bytes([block[0], block[1]]).to_f32();
+    let dmin = half::f16::from_le_bytes([block[2], block[3]]).to_f32();
+    let mut q = [0i8; 32];
+    for sub in 0..8 {
+        let (sc, m) = scale_min_k4(sub, &block[4..16]);
+        unpack_q5_k(block, sub, &mut q);
+        for (o, &q) in output[sub * 32..(sub + 1) * 32].iter_mut().zip(&q) {
+            *o = d * sc * q as f32 - dmin * m;
+        }
+    }
+}
+
+/// Dequantize Q6_K block (210 bytes → 256 f32 values).
+/// Format: 128 bytes of low 4 bits + 64 bytes of high 2 bits + 16 signed
+/// 8-bit scales (one per 16 values) + d (f16). value = d·sc·(q − 32)
+pub fn dequantize_q6_k(block: &[u8], output: &mut [f32]) {
+    debug_assert!(block.len() >= 210);
+    debug_assert!(output.len() >= QK_K);
+
+    let scales = &block[192..208];
+    let d = half::f16::from_le_bytes([block[208], block[209]]).to_f32();
+    let mut q = [0i8; 32];
+    for seg in 0..8 {
+        unpack_q6_k(block, seg, &mut q);
+        for (l, &q) in q.iter().enumerate() {
+            let sc = scales[seg * 2 + l / 16] as i8 as f32;
+            output[seg * 32 + l] = d * sc * q as f32;
+        }
+    }
+}
+
+/// Fused dot product of one Q4_0 block with 32 activations.
+fn dot_q4_0(block: &[u8], x: &[f32]) -> f32 {
+    let d = half::f16::from_le_bytes([block[0], block[1]]).to_f32();
+    let mut q = [0i8; 32];
+    for i in 0..16 {
+        let byte = block[2 + i];
+        q[i * 2] = (byte & 0x0F) as i8 - 8;
+        q[i * 2 + 1] = (byte >> 4) as i8 - 8;
+    }
+    d * simd::dot_i8_f32(&q, x)
+}
+
+/// Fused dot product of one Q8_0 block with 32 activations.
+fn dot_q8_0(block: &[u8], x: &[f32]) -> f32 {
+    let d = half::f16::from_le_bytes([block[0], block[1]]).to_f32();
+    let mut q = [0i8; 32];
+    for (q, &b) in q.iter_mut().zip(&block[2..34]) {
+        *q = b as i8;
+    }
+    d * simd::dot_i8_f32(&q, x)
+}
+
+/// Fused dot product of one Q4_K/Q5_K block with 256 activations:
+/// Σ_sub d·sc·(q·x) − dmin·m·Σx.
+fn dot_k_scale_min(block: &[u8], x: &[f32], unpack: fn(&[u8], usize, &mut [i8; 32])) -> f32 {
+    let d = half::f16::from_le_bytes([block[0], block[1]]).to_f32();
+    let dmin = half::f16::from_le_bytes([block[2], block[3]]).to_f32();
+    let mut q = [0i8; 32];
+    let mut sum = 0.0f32;
+    for sub in 0..8 {
+        let xs = &x[sub * 32..(sub + 1) * 32];
+        let (sc, m) = scale_min_k4(sub, &block[4..16]);
+        unpack(block, sub, &mut q);
+        let x_sum: f32 = xs.iter().sum();
+        sum += d * sc * simd::dot_i8_f32(&q, xs) - dmin * m * x_sum;
+    }
+    sum
+}
+
+/// Fused dot product of one Q6_K block with 256 activations.
+fn dot_q6_k(block: &[u8], x: &[f32]) -> f32 {
+    let scales = &block[192..208];
+    let d = half::f16::from_le_bytes([block[208], block[209]]).to_f32();
+    let mut q = [0i8; 32];
+    let mut sum = 0.0f32;
+    for seg in 0..8 {
+        let xs = &x[seg * 32..(seg + 1) * 32];
+        unpack_q6_k(block, seg, &mut q);
+        let sc0 = scales[seg * 2] as i8 as f32;
+        let sc1 = scales[seg * 2 + 1] as i8 as f32;
+        sum += sc0 * simd::dot_i8_f32(&q[..16], &xs[..16])
+            + sc1 * simd::dot_i8_f32(&q[16..], &xs[16..]);
+    }
+    d * sum
+}
+
+/// Whether `ggml_type` can be dequantized / used in matmuls.
+pub fn is_supported(ggml_type: GgmlType) -> bool {
+    matches!(
+        ggml_type,
+        GgmlType::F32
+            | GgmlType::F16
+            | GgmlType::Q4_0
+            | GgmlType::Q8_0
+            | GgmlType::Q4K
+            | GgmlType::Q5K
+            | GgmlType::Q6K
+    )
+}
+
+/// Bytes occupied by `n_elements` values of `ggml_type`.
+fn row_bytes(ggml_type: GgmlType, n_elements: usize) -> usize {
+    n_elements / ggml_type.block_size() * ggml_type.type_size()
+}
+
+fn check_len(data: &[u8], n_elements: usize, ggml_type: GgmlType) -> Result<()> {
+    if !n_elements.is_multiple_of(ggml_type.block_size()) {
+        return Err(BizClawError::Brain(format!(
+            "{} values is not a multiple of the {} block size",
+            n_elements,
+            ggml_type.name()
+        )));
+    }
+    let needed = row_bytes(ggml_type, n_elements);
+    if data.len() < needed {
+        return Err(BizClawError::Brain(format!(
+            "Truncated {} tensor data: {} bytes, need {needed}",
+            ggml_type.name(),
+            data.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Dot product of a quantized row (`x.len()` values) with f32 activations,
+/// without dequantizing the row first.
+pub fn dot_row(data: &[u8], x: &[f32], ggml_type: GgmlType) -> Result<f32> {
+    let n = x.len();
+    check_len(data, n, ggml_type)?;
+    let bs = ggml_type.block_size();
+    let ts = ggml_type.type_size();
+    let blocks = data.chunks_exact(ts).zip(x.chunks_exact(bs));
+
+    Ok(match ggml_type {
+        GgmlType::Q4_0 => blocks.map(|(b, x)| dot_q4_0(b, x)).sum(),
+        GgmlType::Q8_0 => blocks.map(|(b, x)| dot_q8_0(b, x)).sum(),
+        GgmlType::Q4K => blocks.map(|(b, x)| dot_k_scale_min(b, x, unpack_q4_k)).sum(),
+        GgmlType::Q5K => blocks.map(|(b, x)| dot_k_scale_min(b, x, unpack_q5_k)).sum(),
+        GgmlType::Q6K => blocks.map(|(b, x)| dot_q6_k(b, x)).sum(),
+        _ => {
+            let mut row = vec![0.0f32; n];
+            dequantize_row(data, &mut row, n, ggml_type)?;
+            simd::dot_product_simd(&row, x)
+        }
+    })
+}
+
+/// Quantized matrix-vector multiply.
+/// output[rows] = weight[rows x cols] @ input[cols], with `data` holding the
+/// weight rows in `ggml_type` blocks. Rows are split across the brain
+/// thread pool (see [`crate::thread_pool`]).
+pub fn matmul_quantized(
+    output: &mut [f32],
+    data: &[u8],
+    input: &[f32],
+    rows: usize,
+    cols: usize,
+    ggml_type: GgmlType,
+) -> Result<()> {
+    debug_assert_eq!(input.len(), cols);
+    debug_assert_eq!(output.len(), rows);
+    let rb = row_bytes(ggml_type, cols);
+    check_len(data, rows * cols, ggml_type)?;
+    crate::thread_pool::try_par_rows(output, cols, |i| {
+        dot_row(&data[i * rb..(i + 1) * rb], input, ggml_type)
+    })
+}
+
 /// Dequantize a full row of quantized data to f32.
 /// Dispatches to the correct dequantization kernel based on type.
 pub fn dequantize_row(
     data: &[u8],
     output: &mut [f32],
     n_elements: usize,
-    ggml_type: crate::gguf::GgmlType,
+    ggml_type: GgmlType,
 ) -> Result<()> {
     match ggml_type {
-        crate::gguf::GgmlType::F32 => {
+        GgmlType::F32 => {
             // Direct copy from bytes to f32
             for i in 0..n_elements {
                 let offset = i * 4;
@@ -57,7 +312,7 @@ pub fn dequantize_row(
                 }
             }
         }
-        crate::gguf::GgmlType::F16 => {
+        GgmlType::F16 => {
             for i in 0..n_elements {
                 let offset = i * 2;
                 if offset + 2 <= data.len() {
@@ -65,33 +320,28 @@ pub fn dequantize_row(
                 }
             }
         }
-        crate::gguf::GgmlType::Q4_0 => {
-            let block_size = 32;
-            let type_size = 18;
-            let n_blocks = n_elements / block_size;
-            for b in 0..n_blocks {
-                let block_data = &data[b * type_size..];
-                dequantize_q4_0(block_data, &mut output[b * block_size..]);
-            }
-        }
-        crate::gguf::GgmlType::Q8_0 => {
-            let block_size = 32;
-            let type_size = 34;
-            let n_blocks = n_elements / block_size;
-            for b in 0..n_blocks {
-                let block_data = &data[b * type_size..];
-                dequantize_q8_0(block_data, &mut output[b * block_size..]);
+        GgmlType::Q4_0 | GgmlType::Q8_0 | GgmlType::Q4K | GgmlType::Q5K | GgmlType::Q6K => {
+            check_len(data, n_elements, ggml_type)?;
+            let kernel: fn(&[u8], &mut [f32]) = match ggml_type {
+                GgmlType::Q4_0 => dequantize_q4_0,
+                GgmlType::Q8_0 => dequantize_q8_0,
+                GgmlType::Q4K => dequantize_q4_k,
+                GgmlType::Q5K => dequantize_q5_k,
+                _ => dequantize_q6_k,
+            };
+            let bs = ggml_type.block_size();
+            for (block, out) in data
+                .chunks_exact(ggml_type.type_size())
+                .zip(output[..n_elements].chunks_exact_mut(bs))
+            {
+                kernel(block, out);
             }
         }
         _ => {
-            // For unsupported types, fill with zeros
-            tracing::warn!(
-                "Unsupported quantization type: {:?}, filling with zeros",
-                ggml_type
-            );
-            for v in output.iter_mut().take(n_elements) {
-                *v = 0.0;
-            }
+            return Err(BizClawError::Brain(format!(
+                "Unsupported quantization type: {}",
+                ggml_type.name()
+            )));
         }
     }
     Ok(())
@@ -116,4 +366,163 @@ mod tests {
         assert!((output[0] - 1.0).abs() < 0.01);
         assert!((output[1] - 2.0).abs() < 0.01);
     }
+
+    /// Deterministic pseudo-random bytes.
+    fn noise(n: usize, seed: u32) -> Vec<u8> {
+        let mut x = seed.max(1);
+        (0..n)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    /// Random block with small, finite f16 scales at the given offsets.
+    fn random_block(ty: GgmlType, seed: u32, f16_at: &[usize]) -> Vec<u8> {
+        let mut block = noise(ty.type_size(), seed);
+        for (k, &off) in f16_at.iter().enumerate() {
+            let v = half::f16::from_f32(0.01 + 0.003 * k as f32);
+            block[off..off + 2].copy_from_slice(&v.to_le_bytes());
+        }
+        block
+    }
+
+    // Straight transcriptions of the ggml reference loops.
+
+    fn reference_q4_k(x: &[u8], y: &mut [f32]) {
+        let d = half::f16::from_le_bytes([x[0], x[1]]).to_f32();
+        let min = half::f16::from_le_bytes([x[2], x[3]]).to_f32();
+        let (scales, mut q) = (&x[4..16], &x[16..]);
+        let (mut is, mut yi) = (0, 0);
+        for _ in (0..QK_K).step_by(64) {
+            let (sc, m) = scale_min_k4(is, scales);
+            let (d1, m1) = (d * sc, min * m);
+            let (sc, m) = scale_min_k4(is + 1, scales);
+            let (d2, m2) = (d * sc, min * m);
+            for l in 0..32 {
+                y[yi] = d1 * (q[l] & 0xF) as f32 - m1;
+                yi += 1;
+            }
+            for l in 0..32 {
+                y[yi] = d2 * (q[l] >> 4) as f32 - m2;
+                yi += 1;
+            }
+            q = &q[32..];
+            is += 2;
+        }
+    }
+
+    fn reference_q5_k(x: &[u8], y: &mut [f32]) {
+        let d = half::f16::from_le_bytes([x[0], x[1]]).to_f32();
+        let min = half::f16::from_le_bytes([x[2], x[3]]).to_f32();
+        let (scales, qh, mut ql) = (&x[4..16], &x[16..48], &x[48..]);
+        let (mut is, mut yi, mut u1, mut u2) = (0, 0, 1u8, 2u8);
+        for _ in (0..QK_K).step_by(64) {
+            let (sc, m) = scale_min_k4(is, scales);
+            let (d1, m1) = (d * sc, min * m);
+            let (sc, m) = scale_min_k4(is + 1, scales);
+            let (d2, m2) = (d * sc, min * m);
+            for l in 0..32 {
+                let hi = if qh[l] & u1 != 0 { 16 } else { 0 };
+                y[yi] = d1 * ((ql[l] & 0xF) + hi) as f32 - m1;
+                yi += 1;
+            }
+            for l in 0..32 {
+                let hi = if qh[l] & u2 != 0 { 16 } else { 0 };
+                y[yi] = d2 * ((ql[l] >> 4) + hi) as f32 - m2;
+                yi += 1;
+            }
+            ql = &ql[32..];
+            is += 2;
+            u1 = u1.wrapping_shl(2);
+            u2 = u2.wrapping_shl(2);
+        }
+    }
+
+    fn reference_q6_k(x: &[u8], y: &mut [f32]) {
+        let d = half::f16::from_le_bytes([x[208], x[209]]).to_f32();
+        for n in 0..2 {
+            let ql = &x[n * 64..];
+            let qh = &x[128 + n * 32..];
+            let sc = &x[192 + n * 8..];
+            let y = &mut y[n * 128..];
+            for l in 0..32 {
+                let is = l / 16;
+                let q1 = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
+                let q2 = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
+                let q3 = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
+                let q4 = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
+                y[l] = d * sc[is] as i8 as f32 * q1 as f32;
+                y[l + 32] = d * sc[is + 2] as i8 as f32 * q2 as f32;
+                y[l + 64] = d * sc[is + 4] as i8 as f32 * q3 as f32;
+                y[l + 96] = d * sc[is + 6] as i8 as f32 * q4 as f32;
+            }
+        }
+    }
+
+    type Dequantize = fn(&[u8], &mut [f32]);
+
+    #[test]
+    fn test_k_quants_match_reference() {
+        let cases: [(GgmlType, &[usize], Dequantize); 3] = [
+            (GgmlType::Q4K, &[0, 2], reference_q4_k),
+            (GgmlType::Q5K, &[0, 2], reference_q5_k),
+            (GgmlType::Q6K, &[208], reference_q6_k),
+        ];
+        for (ty, f16_at, reference) in cases {
+            for seed in [1, 7, 99] {
+                let block = random_block(ty, seed, f16_at);
+                let mut expected = vec![0.0f32; QK_K];
+                reference(&block, &mut expected);
+                let mut got = vec![0.0f32; QK_K];
+                dequantize_row(&block, &mut got, QK_K, ty).unwrap();
+                assert_eq!(got, expected, "{} seed {seed}", ty.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fused_dot_matches_dequantized() {
+        let cases: [(GgmlType, &[usize]); 5] = [
+            (GgmlType::Q4_0, &[0]),
+            (GgmlType::Q8_0, &[0]),
+            (GgmlType::Q4K, &[0, 2]),
+            (GgmlType::Q5K, &[0, 2]),
+            (GgmlType::Q6K, &[208]),
+        ];
+        let (rows, cols) = (3, 512);
+        let x: Vec<f32> = (0..cols).map(|i| ((i * 37 % 101) as f32 - 50.0) / 25.0).collect();
+        for (ty, f16_at) in cases {
+            let blocks_per_row = cols / ty.block_size();
+            let data: Vec<u8> = (0..rows * blocks_per_row)
+                .flat_map(|b| random_block(ty, b as u32 + 3, f16_at))
+                .collect();
+
+            let mut weight = vec![0.0f32; rows * cols];
+            dequantize_row(&data, &mut weight, rows * cols, ty).unwrap();
+            let mut expected = vec![0.0f32; rows];
+            crate::tensor::matmul(&mut expected, &weight, &x, rows, cols);
+
+            let mut got = vec![0.0f32; rows];
+            matmul_quantized(&mut got, &data, &x, rows, cols, ty).unwrap();
+            for (e, g) in expected.iter().zip(&got) {
+                assert!((e - g).abs() <= 1e-3 * e.abs().max(1.0), "{}: {e} vs {g}", ty.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_and_truncated_data_error() {
+        let mut out = vec![0.0f32; 256];
+        assert!(dequantize_row(&[0u8; 84], &mut out, 256, GgmlType::Q2K).is_err());
+        assert!(!is_supported(GgmlType::Q2K));
+        assert!(is_supported(GgmlType::Q5K));
+
+        let err = dequantize_row(&[0u8; 100], &mut out, 256, GgmlType::Q4K).unwrap_err();
+        assert!(err.to_string().contains("Truncated"), "{err}");
+        assert!(dot_row(&[0u8; 144], &[0.0; 100], GgmlType::Q4K).is_err());
+    }
 }
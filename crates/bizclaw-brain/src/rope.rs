@@ -2,21 +2,51 @@
 //!
 //! Applied to query and key vectors to encode position information.
 
+/// Which element pairs of a head RoPE rotates together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RopeStyle {
+    /// Adjacent pairs `(x[2i], x[2i+1])` — llama.cpp's LLaMA layout.
+    Normal,
+    /// Split halves `(x[i], x[i + n/2])` — GPT-NeoX / HF layout.
+    Neox,
+}
+
 /// Apply RoPE to a vector in-place.
 /// `pos` is the token position, `dim` is the embedding dimension,
 /// `head_dim` is the dimension per attention head.
 pub fn apply_rope(vec: &mut [f32], pos: usize, head_dim: usize, rope_theta: f32) {
-    let half_dim = head_dim / 2;
+    apply_rope_styled(vec, pos, head_dim, rope_theta, RopeStyle::Neox);
+}
+
+/// Apply RoPE to the first `rope_dim` elements of one head in-place; the
+/// remaining elements (partial rotary) are left untouched.
+pub fn apply_rope_styled(
+    vec: &mut [f32],
+    pos: usize,
+    rope_dim: usize,
+    rope_theta: f32,
+    style: RopeStyle,
+) {
+    rotate(vec, pos as f32, rope_dim, rope_theta, style);
+}
+
+/// Rotate by `pos` positions, which may be negative (undoing a rotation).
+fn rotate(vec: &mut [f32], pos: f32, rope_dim: usize, rope_theta: f32, style: RopeStyle) {
+    let half_dim = rope_dim / 2;
     for i in 0..half_dim {
-        let freq = 1.0 / rope_theta.powf(2.0 * i as f32 / head_dim as f32);
-        let angle = pos as f32 * freq;
+        let freq = 1.0 / rope_theta.powf(2.0 * i as f32 / rope_dim as f32);
+        let angle = pos * freq;
         let cos = angle.cos();
         let sin = angle.sin();
 
-        let x0 = vec[i];
-        let x1 = vec[i + half_dim];
-        vec[i] = x0 * cos - x1 * sin;
-        vec[i + half_dim] = x0 * sin + x1 * cos;
+        let (a, b) = match style {
+            RopeStyle::Normal => (2 * i, 2 * i + 1),
+            RopeStyle::Neox => (i, i + half_dim),
+        };
+        let x0 = vec[a];
+        let x1 = vec[b];
+        vec[a] = x0 * cos - x1 * sin;
+        vec[b] = x0 * sin + x1 * cos;
     }
 }
 
@@ -26,12 +56,31 @@ pub fn apply_rope_multi_head(
     pos: usize,
     n_heads: usize,
     head_dim: usize,
+    rope_dim: usize,
     rope_theta: f32,
+    style: RopeStyle,
 ) {
     for h in 0..n_heads {
         let start = h * head_dim;
         let end = start + head_dim;
-        apply_rope(&mut vec[start..end], pos, head_dim, rope_theta);
+        apply_rope_styled(&mut vec[start..end], pos, rope_dim, rope_theta, style);
+    }
+}
+
+/// Move already-rotated heads by `delta` positions: rotations compose, so
+/// a key rotated for position `p` becomes the key for `p + delta`.
+pub fn shift_rope_multi_head(
+    vec: &mut [f32],
+    delta: i64,
+    n_heads: usize,
+    head_dim: usize,
+    rope_dim: usize,
+    rope_theta: f32,
+    style: RopeStyle,
+) {
+    for h in 0..n_heads {
+        let start = h * head_dim;
+        rotate(&mut vec[start..start + head_dim], delta as f32, rope_dim, rope_theta, style);
     }
 }
 
@@ -49,4 +98,46 @@ mod tests {
             assert!((a - b).abs() < 1e-5);
         }
     }
+
+    #[test]
+    fn test_normal_style_is_neox_on_interleaved_layout() {
+        // Normal rotates (x[2i], x[2i+1]); NeoX rotates (x[i], x[i+n/2]).
+        // De-interleaving the input must make the two agree.
+        let head: Vec<f32> = (0..8).map(|i| i as f32 * 0.3 - 1.0).collect();
+        let mut normal = head.clone();
+        apply_rope_styled(&mut normal, 5, 8, 10000.0, RopeStyle::Normal);
+
+        let mut neox: Vec<f32> = (0..4).map(|i| head[2 * i]).chain((0..4).map(|i| head[2 * i + 1])).collect();
+        apply_rope_styled(&mut neox, 5, 8, 10000.0, RopeStyle::Neox);
+        for i in 0..4 {
+            assert!((normal[2 * i] - neox[i]).abs() < 1e-5);
+            assert!((normal[2 * i + 1] - neox[i + 4]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_partial_rotary_leaves_tail_untouched() {
+        let mut vec = vec![1.0f32; 8];
+        apply_rope_multi_head(&mut vec, 3, 2, 4, 2, 10000.0, RopeStyle::Neox);
+        for h in 0..2 {
+            assert_ne!(vec[h * 4], 1.0);
+            assert_eq!(&vec[h * 4 + 2..h * 4 + 4], &[1.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_shift_moves_rotation_to_new_position() {
+        let head: Vec<f32> = (0..16).map(|i| (i as f32 * 0.7).sin()).collect();
+        for style in [RopeStyle::Normal, RopeStyle::Neox] {
+            let mut shifted = head.clone();
+            apply_rope_multi_head(&mut shifted, 900, 2, 8, 8, 10000.0, style);
+            shift_rope_multi_head(&mut shifted, -300, 2, 8, 8, 10000.0, style);
+
+            let mut direct = head.clone();
+            apply_rope_multi_head(&mut direct, 600, 2, 8, 8, 10000.0, style);
+            for (a, b) in shifted.iter().zip(&direct) {
+                assert!((a - b).abs() < 1e-3, "{style:?}: {a} vs {b}");
+            }
+        }
+    }
 }
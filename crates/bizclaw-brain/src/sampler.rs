@@ -1,6 +1,11 @@
-//! Temperature + Top-p/Top-k sampling for token generation.
+//! Token sampling: temperature, top-k, min-p and top-p truncation, or
+//! Mirostat v2 in place of the truncation steps.
 
+use crate::{MirostatConfig, SoftmaxMode};
+use crate::grammar::TokenGrammar;
+use crate::tensor;
 use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Sampler configuration.
 #[derive(Debug, Clone)]
@@ -10,6 +15,16 @@ pub struct SamplerConfig {
     pub top_k: u32,
     pub repeat_penalty: f32,
     pub repeat_last_n: usize,
+    /// Softmax numerics; non-`Fast` modes fall back to argmax on NaN/Inf logits.
+    pub softmax: SoftmaxMode,
+    /// Drop tokens whose probability is below `min_p` × the top token's (0 = off).
+    pub min_p: f32,
+    /// Mirostat v2; when set, replaces top-k, min-p and top-p.
+    pub mirostat: Option<MirostatConfig>,
+    /// Added to the logit of each token before any other step.
+    pub logit_bias: HashMap<u32, f32>,
+    /// Tokens that are never sampled.
+    pub banned_tokens: HashSet<u32>,
 }
 
 impl Default for SamplerConfig {
@@ -20,36 +35,186 @@ impl Default for SamplerConfig {
             top_k: 40,
             repeat_penalty: 1.1,
             repeat_last_n: 64,
+            softmax: SoftmaxMode::Fast,
+            min_p: 0.0,
+            mirostat: None,
+            logit_bias: HashMap::new(),
+            banned_tokens: HashSet::new(),
+        }
+    }
+}
+
+impl SamplerConfig {
+    /// Apply `logit_bias` and force `banned_tokens` to -inf. Ids outside
+    /// the vocabulary are ignored.
+    pub fn apply_logit_bias(&self, logits: &mut [f32]) {
+        for (&token, &bias) in &self.logit_bias {
+            if let Some(l) = logits.get_mut(token as usize) {
+                *l += bias;
+            }
+        }
+        for &token in &self.banned_tokens {
+            if let Some(l) = logits.get_mut(token as usize) {
+                *l = f32::NEG_INFINITY;
+            }
+        }
+    }
+}
+
+/// Sliding window of recent tokens with per-token occurrence counts.
+///
+/// Updated incrementally on every append/evict, so applying the repeat
+/// penalty costs O(unique tokens in window) instead of rescanning the window.
+#[derive(Debug, Clone, Default)]
+pub struct RepeatWindow {
+    capacity: usize,
+    tokens: VecDeque<u32>,
+    counts: HashMap<u32, u32>,
+}
+
+impl RepeatWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tokens: VecDeque::with_capacity(capacity),
+            counts: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Append a token, evicting the oldest once the window is full.
+    pub fn push(&mut self, token: u32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.tokens.len() == self.capacity
+            && let Some(old) = self.tokens.pop_front()
+            && let Some(c) = self.counts.get_mut(&old)
+        {
+            *c -= 1;
+            if *c == 0 {
+                self.counts.remove(&old);
+            }
+        }
+        self.tokens.push_back(token);
+        *self.counts.entry(token).or_insert(0) += 1;
+    }
+
+    pub fn extend(&mut self, tokens: &[u32]) {
+        let skip = tokens.len().saturating_sub(self.capacity);
+        for &t in &tokens[skip..] {
+            self.push(t);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Penalize every token in the window once per occurrence.
+    pub fn apply_penalty(&self, logits: &mut [f32], penalty: f32) {
+        if penalty == 1.0 {
+            return;
+        }
+        for (&token_id, &count) in &self.counts {
+            let idx = token_id as usize;
+            if idx < logits.len() {
+                let factor = penalty.powi(count as i32);
+                if logits[idx] > 0.0 {
+                    logits[idx] /= factor;
+                } else {
+                    logits[idx] *= factor;
+                }
+            }
         }
     }
 }
 
 /// Token sampler — selects next token from logits.
+///
+/// Holds Mirostat's running threshold, so use one sampler per generation
+/// (clone a configured one, or call [`Sampler::reset`]).
+#[derive(Debug, Clone)]
 pub struct Sampler {
     config: SamplerConfig,
+    /// Mirostat μ: maximum surprise (bits) a candidate may have.
+    mirostat_mu: f32,
+    /// xorshift64* state for drawing tokens; never zero.
+    rng: u64,
 }
 
 impl Sampler {
     pub fn new(config: SamplerConfig) -> Self {
-        Self { config }
+        let mirostat_mu = config.mirostat.map_or(0.0, |m| 2.0 * m.tau);
+        Self {
+            config,
+            mirostat_mu,
+            rng: rand::thread_rng().r#gen::<u64>() | 1,
+        }
+    }
+
+    pub fn config(&self) -> &SamplerConfig {
+        &self.config
+    }
+
+    /// RNG state, for persisting a session (see [`Self::set_rng_state`]).
+    pub fn rng_state(&self) -> u64 {
+        self.rng
+    }
+
+    /// Continue the random stream of a sampler with this [`Self::rng_state`].
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng = state.max(1);
+    }
+
+    /// Uniform draw in [0, 1).
+    fn next_f32(&mut self) -> f32 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Restore per-generation state (Mirostat μ = 2τ).
+    pub fn reset(&mut self) {
+        self.mirostat_mu = self.config.mirostat.map_or(0.0, |m| 2.0 * m.tau);
+    }
+
+    /// Empty repeat-penalty window sized by `repeat_last_n`.
+    pub fn new_window(&self) -> RepeatWindow {
+        RepeatWindow::new(self.config.repeat_last_n)
     }
 
     /// Sample a token from logits.
-    pub fn sample(&self, logits: &mut [f32], last_tokens: &[u32]) -> u32 {
+    pub fn sample(&mut self, logits: &mut [f32], last_tokens: &[u32]) -> u32 {
+        let mut window = self.new_window();
+        window.extend(last_tokens);
+        self.sample_with_window(logits, &window)
+    }
+
+    /// Sample a token that keeps `grammar`'s output valid: disallowed tokens
+    /// are masked to -inf before the usual sampling pipeline.
+    pub fn sample_constrained(
+        &mut self,
+        logits: &mut [f32],
+        window: &RepeatWindow,
+        grammar: &dyn TokenGrammar,
+    ) -> u32 {
+        grammar.apply_mask(logits);
+        self.sample_with_window(logits, window)
+    }
+
+    /// Sample a token from logits, penalizing tokens in an incrementally
+    /// maintained window (see [`RepeatWindow`]).
+    pub fn sample_with_window(&mut self, logits: &mut [f32], window: &RepeatWindow) -> u32 {
+        // Apply logit bias and bans
+        self.config.apply_logit_bias(logits);
+
         // Apply repeat penalty
-        if self.config.repeat_penalty != 1.0 {
-            let n = last_tokens.len().min(self.config.repeat_last_n);
-            for &token_id in &last_tokens[last_tokens.len().saturating_sub(n)..] {
-                let idx = token_id as usize;
-                if idx < logits.len() {
-                    if logits[idx] > 0.0 {
-                        logits[idx] /= self.config.repeat_penalty;
-                    } else {
-                        logits[idx] *= self.config.repeat_penalty;
-                    }
-                }
-            }
-        }
+        window.apply_penalty(logits, self.config.repeat_penalty);
 
         // Apply temperature
         if self.config.temperature > 0.0 && self.config.temperature != 1.0 {
@@ -64,11 +229,24 @@ impl Sampler {
             return argmax(logits);
         }
 
+        // Corrupted logits (NaN/+Inf) would make the distribution garbage —
+        // recover with the best finite-or-infinite candidate instead.
+        if self.config.softmax != SoftmaxMode::Fast
+            && logits.iter().any(|v| v.is_nan() || *v == f32::INFINITY)
+        {
+            tracing::warn!("Non-finite logits — falling back to argmax");
+            return argmax_ignoring_nan(logits);
+        }
+
         // Create sorted indices
         let mut indices: Vec<(usize, f32)> =
             logits.iter().enumerate().map(|(i, &v)| (i, v)).collect();
         indices.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
+        if let Some(mirostat) = self.config.mirostat {
+            return self.sample_mirostat(&indices, mirostat);
+        }
+
         // Top-K filtering
         let top_k = if self.config.top_k > 0 {
             (self.config.top_k as usize).min(indices.len())
@@ -78,14 +256,16 @@ impl Sampler {
         indices.truncate(top_k);
 
         // Softmax
-        let max_logit = indices[0].1;
-        let mut probs: Vec<(usize, f32)> = indices
-            .iter()
-            .map(|&(i, v)| (i, (v - max_logit).exp()))
-            .collect();
-        let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
-        for p in probs.iter_mut() {
-            p.1 /= sum;
+        let Some(mut probs) = self.softmax(&indices) else {
+            return indices[0].0 as u32;
+        };
+
+        // Min-P: relative to the most likely token
+        if self.config.min_p > 0.0 {
+            let threshold = probs[0].1 * self.config.min_p;
+            let keep = probs.iter().take_while(|&&(_, p)| p >= threshold).count();
+            probs.truncate(keep.max(1));
+            renormalize(&mut probs);
         }
 
         // Top-P (nucleus) sampling
@@ -100,28 +280,87 @@ impl Sampler {
                 }
             }
             probs.truncate(cutoff);
-
-            // Re-normalize
-            let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
-            for p in probs.iter_mut() {
-                p.1 /= sum;
-            }
+            renormalize(&mut probs);
         }
 
-        // Random sampling
-        let mut rng = rand::thread_rng();
-        let r: f32 = rng.r#gen();
-        let mut cumulative = 0.0;
-        for &(idx, prob) in &probs {
-            cumulative += prob;
-            if r < cumulative {
-                return idx as u32;
+        pick(&probs, self.next_f32())
+    }
+
+    /// Mirostat v2: keep candidates whose surprise −log2(p) is within μ,
+    /// sample among them, then move μ toward the target τ by the observed
+    /// surprise error.
+    fn sample_mirostat(&mut self, sorted: &[(usize, f32)], mirostat: MirostatConfig) -> u32 {
+        let Some(mut probs) = self.softmax(sorted) else {
+            return sorted[0].0 as u32;
+        };
+        let keep = probs
+            .iter()
+            .take_while(|&&(_, p)| -p.log2() <= self.mirostat_mu)
+            .count();
+        probs.truncate(keep.max(1));
+        renormalize(&mut probs);
+
+        let token = pick(&probs, self.next_f32());
+        let p = probs
+            .iter()
+            .find(|&&(i, _)| i as u32 == token)
+            .map_or(1.0, |&(_, p)| p);
+        let surprise = -p.log2();
+        self.mirostat_mu -= mirostat.eta * (surprise - mirostat.tau);
+        token
+    }
+
+    /// Softmax over descending-sorted candidates in the configured numerics.
+    /// `None` when the stable modes find no usable distribution.
+    fn softmax(&self, indices: &[(usize, f32)]) -> Option<Vec<(usize, f32)>> {
+        match self.config.softmax {
+            SoftmaxMode::Fast => {
+                let max_logit = indices[0].1;
+                let mut probs: Vec<(usize, f32)> = indices
+                    .iter()
+                    .map(|&(i, v)| (i, (v - max_logit).exp()))
+                    .collect();
+                renormalize(&mut probs);
+                Some(probs)
+            }
+            SoftmaxMode::Stable => {
+                let mut p: Vec<f32> = indices.iter().map(|&(_, v)| v).collect();
+                if !tensor::stable_softmax(&mut p) {
+                    return None;
+                }
+                Some(indices.iter().map(|&(i, _)| i).zip(p).collect())
+            }
+            SoftmaxMode::StableF64 => {
+                let mut p: Vec<f64> = indices.iter().map(|&(_, v)| v as f64).collect();
+                if !tensor::stable_softmax_f64(&mut p) {
+                    return None;
+                }
+                Some(indices.iter().map(|&(i, _)| i).zip(p.into_iter().map(|p| p as f32)).collect())
             }
         }
+    }
+}
+
+/// Scale probabilities to sum to 1.
+fn renormalize(probs: &mut [(usize, f32)]) {
+    let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
+    for p in probs.iter_mut() {
+        p.1 /= sum;
+    }
+}
 
-        // Fallback
-        probs.last().map(|&(idx, _)| idx as u32).unwrap_or(0)
+/// Draw a token from a normalized distribution with uniform `r` in [0, 1).
+fn pick(probs: &[(usize, f32)], r: f32) -> u32 {
+    let mut cumulative = 0.0;
+    for &(idx, prob) in probs {
+        cumulative += prob;
+        if r < cumulative {
+            return idx as u32;
+        }
     }
+
+    // Fallback
+    probs.last().map(|&(idx, _)| idx as u32).unwrap_or(0)
 }
 
 /// Return the index of the maximum value (greedy decoding).
@@ -133,3 +372,231 @@ fn argmax(values: &[f32]) -> u32 {
         .map(|(i, _)| i as u32)
         .unwrap_or(0)
 }
+
+/// Argmax treating NaN as -Inf (so a NaN can never win).
+fn argmax_ignoring_nan(values: &[f32]) -> u32 {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| !v.is_nan())
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, _)| i as u32)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference implementation: rescan the last `n` tokens every step.
+    fn brute_force_penalty(logits: &mut [f32], last_tokens: &[u32], n: usize, penalty: f32) {
+        let n = last_tokens.len().min(n);
+        for &token_id in &last_tokens[last_tokens.len().saturating_sub(n)..] {
+            let idx = token_id as usize;
+            if idx < logits.len() {
+                if logits[idx] > 0.0 {
+                    logits[idx] /= penalty;
+                } else {
+                    logits[idx] *= penalty;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_matches_rescan() {
+        let vocab = 50;
+        let window_n = 16;
+        let penalty = 1.3;
+        let base: Vec<f32> = (0..vocab).map(|i| (i as f32 - 25.0) * 0.37).collect();
+
+        // Deterministic stream with heavy repetition and out-of-vocab ids.
+        let stream: Vec<u32> = (0..500u32)
+            .map(|i| if i % 97 == 0 { 1000 } else { (i * 7 + i / 5) % 13 })
+            .collect();
+
+        let mut window = RepeatWindow::new(window_n);
+        for step in 0..stream.len() {
+            window.push(stream[step]);
+            let history = &stream[..=step];
+
+            let mut expected = base.clone();
+            brute_force_penalty(&mut expected, history, window_n, penalty);
+            let mut actual = base.clone();
+            window.apply_penalty(&mut actual, penalty);
+
+            assert_eq!(window.len(), history.len().min(window_n));
+            for (e, a) in expected.iter().zip(&actual) {
+                assert!((e - a).abs() <= e.abs() * 1e-5, "step {step}: {e} vs {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_evicts_counts() {
+        let mut window = RepeatWindow::new(2);
+        window.extend(&[5, 5, 7]);
+        let mut logits = vec![1.0f32; 8];
+        window.apply_penalty(&mut logits, 2.0);
+        assert_eq!(logits[5], 0.5);
+        assert_eq!(logits[7], 0.5);
+
+        window.push(7);
+        let mut logits = vec![1.0f32; 8];
+        window.apply_penalty(&mut logits, 2.0);
+        assert_eq!(logits[5], 1.0);
+        assert_eq!(logits[7], 0.25);
+    }
+
+    #[test]
+    fn test_zero_window_is_noop() {
+        let mut window = RepeatWindow::new(0);
+        window.extend(&[1, 2, 3]);
+        assert!(window.is_empty());
+        let mut logits = vec![1.0f32; 4];
+        window.apply_penalty(&mut logits, 2.0);
+        assert_eq!(logits, vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_stable_softmax_sampling_recovers_from_overflow() {
+        let sampler = |softmax| {
+            Sampler::new(SamplerConfig {
+                repeat_penalty: 1.0,
+                softmax,
+                ..Default::default()
+            })
+        };
+        let window = RepeatWindow::new(0);
+
+        // 3e38 / 0.7 overflows to +Inf after temperature scaling.
+        for mode in [SoftmaxMode::Stable, SoftmaxMode::StableF64] {
+            let mut logits = vec![1.0f32, 3.0e38, -2.0, 2.0e38];
+            assert_eq!(sampler(mode).sample_with_window(&mut logits, &window), 1);
+
+            let mut logits = vec![f32::NAN, 0.5, 9.0, f32::NAN];
+            assert_eq!(sampler(mode).sample_with_window(&mut logits, &window), 2);
+        }
+
+        // Huge but finite logits: the dominant token always wins, no NaN.
+        let mut logits = vec![1.0e30f32, 0.0, -1.0e30];
+        assert_eq!(
+            sampler(SoftmaxMode::StableF64).sample_with_window(&mut logits, &window),
+            0
+        );
+    }
+
+    #[test]
+    fn test_sample_constrained_respects_grammar() {
+        let vocab: Vec<String> = ["hello", "}", "{", "["].iter().map(|t| t.to_string()).collect();
+        let grammar = crate::grammar::JsonGrammar::new(&vocab);
+        let mut sampler = Sampler::new(SamplerConfig::default());
+        let window = RepeatWindow::new(0);
+
+        // The model strongly prefers free text, but only { or [ may start JSON.
+        for _ in 0..20 {
+            let mut logits = vec![10.0f32, 9.0, 0.0, -1.0];
+            let token = sampler.sample_constrained(&mut logits, &window, &grammar);
+            assert!(token == 2 || token == 3, "sampled {token}");
+        }
+    }
+
+    #[test]
+    fn test_min_p_drops_unlikely_tokens() {
+        let mut sampler = Sampler::new(SamplerConfig {
+            temperature: 1.0,
+            top_p: 1.0,
+            top_k: 0,
+            repeat_penalty: 1.0,
+            min_p: 0.2,
+            ..Default::default()
+        });
+        let window = RepeatWindow::new(0);
+        // p ≈ [0.50, 0.30, 0.18, 0.02]: only the last is below 0.2 × 0.50
+        let base = [0.5f32.ln(), 0.3f32.ln(), 0.18f32.ln(), 0.02f32.ln()];
+        let mut seen = [false; 4];
+        for _ in 0..400 {
+            let mut logits = base.to_vec();
+            seen[sampler.sample_with_window(&mut logits, &window) as usize] = true;
+        }
+        assert_eq!(seen, [true, true, true, false]);
+    }
+
+    #[test]
+    fn test_mirostat_truncates_and_adapts_mu() {
+        let mirostat = MirostatConfig { tau: 1.0, eta: 0.5 };
+        let mut sampler = Sampler::new(SamplerConfig {
+            temperature: 1.0,
+            repeat_penalty: 1.0,
+            mirostat: Some(mirostat),
+            ..Default::default()
+        });
+        let window = RepeatWindow::new(0);
+        assert_eq!(sampler.mirostat_mu, 2.0);
+
+        // Surprises: 1 bit, 2 bits, ~6.6 bits. μ = 2 excludes the last.
+        let base = [0.5f32.ln(), 0.25f32.ln(), 0.01f32.ln(), 0.24f32.ln()];
+        for _ in 0..50 {
+            let mut logits = base.to_vec();
+            let token = sampler.sample_with_window(&mut logits, &window);
+            assert_ne!(token, 2);
+            assert!(sampler.mirostat_mu.is_finite());
+        }
+        sampler.reset();
+        assert_eq!(sampler.mirostat_mu, 2.0);
+
+        // μ below every candidate's surprise still keeps the top token
+        sampler.mirostat_mu = 0.1;
+        let mut logits = base.to_vec();
+        assert_eq!(sampler.sample_with_window(&mut logits, &window), 0);
+    }
+
+    #[test]
+    fn test_logit_bias_and_bans() {
+        let mut sampler = Sampler::new(SamplerConfig {
+            temperature: 0.0,
+            repeat_penalty: 1.0,
+            logit_bias: HashMap::from([(2, 5.0), (99, 100.0)]),
+            banned_tokens: HashSet::from([0, 1000]),
+            ..Default::default()
+        });
+        let window = RepeatWindow::new(0);
+
+        // Token 0 would win greedily but is banned; the bias lifts 2 over 1
+        let mut logits = vec![10.0f32, 4.0, 0.0];
+        assert_eq!(sampler.sample_with_window(&mut logits, &window), 2);
+        assert_eq!(logits, vec![f32::NEG_INFINITY, 4.0, 5.0]);
+
+        // Bans hold under random sampling too
+        sampler.config.temperature = 1.0;
+        for _ in 0..50 {
+            let mut logits = vec![10.0f32, 0.0, 0.0];
+            assert_ne!(sampler.sample_with_window(&mut logits, &window), 0);
+        }
+    }
+
+    #[test]
+    fn test_rng_state_replays_draws() {
+        let config = SamplerConfig {
+            temperature: 1.0,
+            top_p: 1.0,
+            top_k: 0,
+            repeat_penalty: 1.0,
+            ..Default::default()
+        };
+        let mut sampler = Sampler::new(config.clone());
+        let window = RepeatWindow::new(0);
+        let base = [0.0f32; 16];
+        let draw = |s: &mut Sampler| -> Vec<u32> {
+            (0..32).map(|_| s.sample_with_window(&mut base.to_vec(), &window)).collect()
+        };
+
+        let state = sampler.rng_state();
+        let first = draw(&mut sampler);
+        let mut restored = Sampler::new(config);
+        restored.set_rng_state(state);
+        assert_eq!(draw(&mut restored), first);
+        // Uniform logits: a stuck RNG would repeat one token
+        assert!(first.iter().any(|&t| t != first[0]));
+    }
+}
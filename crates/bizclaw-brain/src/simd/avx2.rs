@@ -1,13 +1,35 @@
-//! x86 AVX2 SIMD intrinsics for dot product.
+//! x86 AVX2 + FMA SIMD intrinsics for dot product and softmax.
 //!
 //! Available on Intel Haswell+ (2013), AMD Zen+ (2018).
-//! Processes 8 floats per iteration (256-bit vectors).
+//! Processes 8 floats per iteration (256-bit vectors). Callers must check
+//! for `avx2` and `fma` at runtime (see [`super::SimdLevel`]).
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+#[cfg(target_arch = "x86_64")]
+use super::{EXP_HI, EXP_LO, EXP_POLY, LN2_HI, LN2_LO, LOG2_E};
+
+/// Horizontal sum of 8 lanes.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+fn hsum256(v: __m256) -> f32 {
+    // [a, b, c, d | e, f, g, h]
+    let hi128 = _mm256_extractf128_ps(v, 1); // [e, f, g, h]
+    let lo128 = _mm256_castps256_ps128(v); // [a, b, c, d]
+    let sum128 = _mm_add_ps(lo128, hi128); // [a+e, b+f, c+g, d+h]
+    let hi64 = _mm_movehl_ps(sum128, sum128);
+    let sum64 = _mm_add_ps(sum128, hi64);
+    let hi32 = _mm_shuffle_ps(sum64, sum64, 1);
+    _mm_cvtss_f32(_mm_add_ss(sum64, hi32))
+}
+
 /// AVX2-accelerated dot product (8 floats per iteration).
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA.
 #[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
 pub fn dot_product_avx2(a: &[f32], b: &[f32]) -> f32 {
     debug_assert_eq!(a.len(), b.len());
     let n = a.len();
@@ -23,16 +45,7 @@ pub fn dot_product_avx2(a: &[f32], b: &[f32]) -> f32 {
             sum_vec = _mm256_fmadd_ps(va, vb, sum_vec); // fused multiply-add
         }
 
-        // Horizontal sum of 8 lanes
-        // [a, b, c, d | e, f, g, h]
-        let hi128 = _mm256_extractf128_ps(sum_vec, 1); // [e, f, g, h]
-        let lo128 = _mm256_castps256_ps128(sum_vec); // [a, b, c, d]
-        let sum128 = _mm_add_ps(lo128, hi128); // [a+e, b+f, c+g, d+h]
-        let hi64 = _mm_movehl_ps(sum128, sum128);
-        let sum64 = _mm_add_ps(sum128, hi64);
-        let hi32 = _mm_shuffle_ps(sum64, sum64, 1);
-        let total = _mm_add_ss(sum64, hi32);
-        let mut sum = _mm_cvtss_f32(total);
+        let mut sum = hsum256(sum_vec);
 
         // Tail
         for i in (chunks * 8)..n {
@@ -43,8 +56,111 @@ pub fn dot_product_avx2(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// AVX2 dot product of i8 quants with f32 activations (8 quants per iteration).
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+pub fn dot_i8_f32_avx2(q: &[i8], x: &[f32]) -> f32 {
+    debug_assert_eq!(q.len(), x.len());
+    let n = q.len();
+
+    unsafe {
+        let mut sum_vec = _mm256_setzero_ps();
+        let chunks = n / 8;
+
+        for i in 0..chunks {
+            let offset = i * 8;
+            let v = _mm_loadl_epi64(q.as_ptr().add(offset) as *const __m128i);
+            let vq = _mm256_cvtepi32_ps(_mm256_cvtepi8_epi32(v)); // widen 8 × i8 → f32
+            let vx = _mm256_loadu_ps(x.as_ptr().add(offset));
+            sum_vec = _mm256_fmadd_ps(vq, vx, sum_vec);
+        }
+
+        let mut sum = hsum256(sum_vec);
+
+        // Tail
+        for i in (chunks * 8)..n {
+            sum += q[i] as f32 * x[i];
+        }
+
+        sum
+    }
+}
+
+/// `exp` of 8 lanes: range reduction to `r` in [-ln2/2, ln2/2], a degree-7
+/// polynomial, then scaling by 2^n through the exponent bits. Lanes below
+/// [`EXP_LO`] (including -Inf) give 0.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+fn exp256(x: __m256) -> __m256 {
+    let below = _mm256_cmp_ps::<_CMP_LT_OQ>(x, _mm256_set1_ps(EXP_LO));
+    let x = _mm256_min_ps(_mm256_max_ps(x, _mm256_set1_ps(EXP_LO)), _mm256_set1_ps(EXP_HI));
+    let n = _mm256_round_ps::<{ _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC }>(_mm256_mul_ps(
+        x,
+        _mm256_set1_ps(LOG2_E),
+    ));
+    let r = _mm256_fnmadd_ps(n, _mm256_set1_ps(LN2_HI), x);
+    let r = _mm256_fnmadd_ps(n, _mm256_set1_ps(LN2_LO), r);
+
+    let mut p = _mm256_set1_ps(EXP_POLY[0]);
+    for &c in &EXP_POLY[1..] {
+        p = _mm256_fmadd_ps(p, r, _mm256_set1_ps(c));
+    }
+    let y = _mm256_fmadd_ps(p, _mm256_mul_ps(r, r), _mm256_add_ps(r, _mm256_set1_ps(1.0)));
+
+    let bits = _mm256_slli_epi32::<23>(_mm256_add_epi32(_mm256_cvtps_epi32(n), _mm256_set1_epi32(127)));
+    _mm256_andnot_ps(below, _mm256_mul_ps(y, _mm256_castsi256_ps(bits)))
+}
+
+/// AVX2 softmax core: `values[i] = exp(values[i] - shift)`, returning the sum.
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+pub fn exp_shift_sum_avx2(values: &mut [f32], shift: f32) -> f32 {
+    let n = values.len();
+
+    unsafe {
+        let vshift = _mm256_set1_ps(shift);
+        let mut sum_vec = _mm256_setzero_ps();
+        let chunks = n / 8;
+
+        for i in 0..chunks {
+            let ptr = values.as_mut_ptr().add(i * 8);
+            let e = exp256(_mm256_sub_ps(_mm256_loadu_ps(ptr), vshift));
+            _mm256_storeu_ps(ptr, e);
+            sum_vec = _mm256_add_ps(sum_vec, e);
+        }
+
+        let mut sum = hsum256(sum_vec);
+
+        // Tail
+        for v in &mut values[chunks * 8..] {
+            *v = (*v - shift).exp();
+            sum += *v;
+        }
+
+        sum
+    }
+}
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn dot_i8_f32_avx2(q: &[i8], x: &[f32]) -> f32 {
+    super::dot_i8_f32_scalar(q, x)
+}
+
 /// Scalar fallback.
 #[cfg(not(target_arch = "x86_64"))]
 pub fn dot_product_avx2(a: &[f32], b: &[f32]) -> f32 {
     crate::tensor::dot_product(a, b)
 }
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn exp_shift_sum_avx2(values: &mut [f32], shift: f32) -> f32 {
+    super::exp_shift_sum_scalar(values, shift)
+}
@@ -0,0 +1,152 @@
+//! x86 AVX-512 SIMD intrinsics for dot product and softmax.
+//!
+//! Available on Intel Skylake-SP+ / Ice Lake+ and AMD Zen 4+.
+//! Processes 16 floats per iteration (512-bit vectors). Callers must check
+//! for `avx512f` at runtime (see [`super::SimdLevel`]).
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+#[cfg(target_arch = "x86_64")]
+use super::{EXP_HI, EXP_LO, EXP_POLY, LN2_HI, LN2_LO, LOG2_E};
+
+/// AVX-512 dot product (16 floats per iteration).
+///
+/// # Safety
+/// The CPU must support AVX-512F.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub fn dot_product_avx512(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len());
+    let n = a.len();
+
+    unsafe {
+        let mut sum_vec = _mm512_setzero_ps();
+        let chunks = n / 16;
+
+        for i in 0..chunks {
+            let offset = i * 16;
+            let va = _mm512_loadu_ps(a.as_ptr().add(offset));
+            let vb = _mm512_loadu_ps(b.as_ptr().add(offset));
+            sum_vec = _mm512_fmadd_ps(va, vb, sum_vec);
+        }
+
+        let mut sum = _mm512_reduce_add_ps(sum_vec);
+
+        // Tail
+        for i in (chunks * 16)..n {
+            sum += a[i] * b[i];
+        }
+
+        sum
+    }
+}
+
+/// AVX-512 dot product of i8 quants with f32 activations (16 quants per
+/// iteration — half a Q4_0/Q8_0/K-quant sub-block).
+///
+/// # Safety
+/// The CPU must support AVX-512F.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub fn dot_i8_f32_avx512(q: &[i8], x: &[f32]) -> f32 {
+    debug_assert_eq!(q.len(), x.len());
+    let n = q.len();
+
+    unsafe {
+        let mut sum_vec = _mm512_setzero_ps();
+        let chunks = n / 16;
+
+        for i in 0..chunks {
+            let offset = i * 16;
+            let v = _mm_loadu_si128(q.as_ptr().add(offset) as *const __m128i);
+            let vq = _mm512_cvtepi32_ps(_mm512_cvtepi8_epi32(v)); // widen 16 × i8 → f32
+            let vx = _mm512_loadu_ps(x.as_ptr().add(offset));
+            sum_vec = _mm512_fmadd_ps(vq, vx, sum_vec);
+        }
+
+        let mut sum = _mm512_reduce_add_ps(sum_vec);
+
+        // Tail
+        for i in (chunks * 16)..n {
+            sum += q[i] as f32 * x[i];
+        }
+
+        sum
+    }
+}
+
+/// `exp` of 16 lanes, as in [`super::avx2`]. Lanes below [`EXP_LO`]
+/// (including -Inf) give 0.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+fn exp512(x: __m512) -> __m512 {
+    let below = _mm512_cmp_ps_mask::<_CMP_LT_OQ>(x, _mm512_set1_ps(EXP_LO));
+    let x = _mm512_min_ps(_mm512_max_ps(x, _mm512_set1_ps(EXP_LO)), _mm512_set1_ps(EXP_HI));
+    let n = _mm512_roundscale_ps::<{ _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC }>(_mm512_mul_ps(
+        x,
+        _mm512_set1_ps(LOG2_E),
+    ));
+    let r = _mm512_fnmadd_ps(n, _mm512_set1_ps(LN2_HI), x);
+    let r = _mm512_fnmadd_ps(n, _mm512_set1_ps(LN2_LO), r);
+
+    let mut p = _mm512_set1_ps(EXP_POLY[0]);
+    for &c in &EXP_POLY[1..] {
+        p = _mm512_fmadd_ps(p, r, _mm512_set1_ps(c));
+    }
+    let y = _mm512_fmadd_ps(p, _mm512_mul_ps(r, r), _mm512_add_ps(r, _mm512_set1_ps(1.0)));
+
+    let bits = _mm512_slli_epi32::<23>(_mm512_add_epi32(_mm512_cvtps_epi32(n), _mm512_set1_epi32(127)));
+    _mm512_maskz_mov_ps(!below, _mm512_mul_ps(y, _mm512_castsi512_ps(bits)))
+}
+
+/// AVX-512 softmax core: `values[i] = exp(values[i] - shift)`, returning the sum.
+///
+/// # Safety
+/// The CPU must support AVX-512F.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub fn exp_shift_sum_avx512(values: &mut [f32], shift: f32) -> f32 {
+    let n = values.len();
+
+    unsafe {
+        let vshift = _mm512_set1_ps(shift);
+        let mut sum_vec = _mm512_setzero_ps();
+        let chunks = n / 16;
+
+        for i in 0..chunks {
+            let ptr = values.as_mut_ptr().add(i * 16);
+            let e = exp512(_mm512_sub_ps(_mm512_loadu_ps(ptr), vshift));
+            _mm512_storeu_ps(ptr, e);
+            sum_vec = _mm512_add_ps(sum_vec, e);
+        }
+
+        let mut sum = _mm512_reduce_add_ps(sum_vec);
+
+        // Tail
+        for v in &mut values[chunks * 16..] {
+            *v = (*v - shift).exp();
+            sum += *v;
+        }
+
+        sum
+    }
+}
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn dot_product_avx512(a: &[f32], b: &[f32]) -> f32 {
+    crate::tensor::dot_product(a, b)
+}
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn dot_i8_f32_avx512(q: &[i8], x: &[f32]) -> f32 {
+    super::dot_i8_f32_scalar(q, x)
+}
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn exp_shift_sum_avx512(values: &mut [f32], shift: f32) -> f32 {
+    super::exp_shift_sum_scalar(values, shift)
+}
@@ -6,45 +6,192 @@
 //! Supported architectures:
 //! - ARM64 (aarch64): NEON — 128-bit vectors (Raspberry Pi 4/5, Apple Silicon)
 //! - x86_64 + SSE2: 128-bit vectors (all x86_64 CPUs)
-//! - x86_64 + AVX2: 256-bit vectors (Intel Haswell+, AMD Zen+)
+//! - x86_64 + AVX2/FMA: 256-bit vectors (Intel Haswell+, AMD Zen+)
+//! - x86_64 + AVX-512: 512-bit vectors (Intel Skylake-SP+, AMD Zen 4+)
+//!
+//! x86 kernels are picked from the running CPU's features, not the build
+//! target, so a generic `x86_64` build still uses AVX2 or AVX-512 where
+//! available. The level is detected once ([`init`], called at engine init).
 
 pub mod avx2;
+pub mod avx512;
 pub mod neon;
 pub mod sse2;
 
+use std::sync::OnceLock;
+
+/// Widest kernel set the running CPU supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SimdLevel {
+    Scalar,
+    Neon,
+    Sse2,
+    /// AVX2 with fused multiply-add.
+    Avx2,
+    Avx512,
+}
+
+impl SimdLevel {
+    /// Probe the CPU (uncached; see [`level`]).
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                SimdLevel::Avx512
+            } else if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                SimdLevel::Avx2
+            } else {
+                SimdLevel::Sse2
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            SimdLevel::Neon
+        }
+
+        #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+        {
+            SimdLevel::Scalar
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SimdLevel::Scalar => "scalar",
+            SimdLevel::Neon => "neon",
+            SimdLevel::Sse2 => "sse2",
+            SimdLevel::Avx2 => "avx2+fma",
+            SimdLevel::Avx512 => "avx512",
+        }
+    }
+}
+
+impl std::fmt::Display for SimdLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+
+/// Detect and log the kernel set; later calls return the cached level.
+pub fn init() -> SimdLevel {
+    *LEVEL.get_or_init(|| {
+        let level = SimdLevel::detect();
+        tracing::info!("SIMD kernels: {level}");
+        level
+    })
+}
+
+/// Kernel set in use (detected on first use if [`init`] has not run).
+#[inline]
+pub fn level() -> SimdLevel {
+    init()
+}
+
+// Constants of the vectorized `exp` (Cephes `expf`): inputs are clamped to
+// [EXP_LO, EXP_HI], reduced by n·ln2 (split in two for precision), and the
+// remainder goes through EXP_POLY.
+const EXP_LO: f32 = -87.336_55;
+const EXP_HI: f32 = 88.0;
+const LOG2_E: f32 = std::f32::consts::LOG2_E;
+const LN2_HI: f32 = 0.693_359_4;
+const LN2_LO: f32 = -2.121_944_4e-4;
+const EXP_POLY: [f32; 6] = [
+    1.987_569_1e-4,
+    1.398_199_9e-3,
+    8.333_452e-3,
+    4.166_579_6e-2,
+    1.666_666_5e-1,
+    0.5,
+];
+
 /// Accelerated dot product — dispatches to best SIMD available.
 pub fn dot_product_simd(a: &[f32], b: &[f32]) -> f32 {
     debug_assert_eq!(a.len(), b.len());
 
-    #[cfg(target_arch = "aarch64")]
-    {
-        neon::dot_product_neon(a, b)
+    // SAFETY (x86 arms): `level` only reports features the CPU has
+    match level() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 => unsafe { avx512::dot_product_avx512(a, b) },
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => unsafe { avx2::dot_product_avx2(a, b) },
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => sse2::dot_product_sse2(a, b),
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => neon::dot_product_neon(a, b),
+        _ => crate::tensor::dot_product(a, b),
     }
+}
 
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-    {
-        avx2::dot_product_avx2(a, b)
+/// Dot product of unpacked quantized values with f32 activations:
+/// sum(q[i] as f32 * x[i]). Inner loop of the fused quantized kernels.
+pub fn dot_i8_f32(q: &[i8], x: &[f32]) -> f32 {
+    debug_assert_eq!(q.len(), x.len());
+
+    // SAFETY (x86 arms): `level` only reports features the CPU has
+    match level() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 => unsafe { avx512::dot_i8_f32_avx512(q, x) },
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => unsafe { avx2::dot_i8_f32_avx2(q, x) },
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Sse2 => sse2::dot_i8_f32_sse2(q, x),
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => neon::dot_i8_f32_neon(q, x),
+        _ => dot_i8_f32_scalar(q, x),
+    }
+}
+
+/// Scalar reference for [`dot_i8_f32`].
+pub fn dot_i8_f32_scalar(q: &[i8], x: &[f32]) -> f32 {
+    q.iter().zip(x).map(|(&q, &x)| q as f32 * x).sum()
+}
+
+/// Softmax core: `values[i] = exp(values[i] - shift)`, returning their sum.
+/// The vector kernels approximate `exp` to about 2 ulp; inputs are expected
+/// to be finite or -Inf.
+pub fn exp_shift_sum(values: &mut [f32], shift: f32) -> f32 {
+    // SAFETY (x86 arms): `level` only reports features the CPU has
+    match level() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 => unsafe { avx512::exp_shift_sum_avx512(values, shift) },
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => unsafe { avx2::exp_shift_sum_avx2(values, shift) },
+        _ => exp_shift_sum_scalar(values, shift),
     }
+}
 
-    #[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
-    {
-        sse2::dot_product_sse2(a, b)
+/// Scalar reference for [`exp_shift_sum`].
+pub fn exp_shift_sum_scalar(values: &mut [f32], shift: f32) -> f32 {
+    let mut sum = 0.0f32;
+    for v in values.iter_mut() {
+        *v = (*v - shift).exp();
+        sum += *v;
     }
+    sum
+}
 
-    // Fallback
-    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
-    {
-        crate::tensor::dot_product(a, b)
+/// Accelerated softmax (no NaN/+Inf handling; see
+/// [`crate::tensor::stable_softmax`] for that).
+pub fn softmax_simd(values: &mut [f32]) {
+    if values.is_empty() {
+        return;
     }
+    let max = values.iter().fold(f32::NEG_INFINITY, |m, &v| m.max(v));
+    let inv_sum = 1.0 / exp_shift_sum(values, max);
+    values.iter_mut().for_each(|v| *v *= inv_sum);
 }
 
-/// Accelerated matmul using SIMD dot product.
+/// Accelerated matmul using SIMD dot product, rows split across the
+/// brain thread pool (see [`crate::thread_pool`]).
 /// output[rows] = mat[rows x cols] @ vec[cols]
 pub fn matmul_simd(output: &mut [f32], mat: &[f32], vec: &[f32], rows: usize, cols: usize) {
-    for i in 0..rows {
-        let row = &mat[i * cols..(i + 1) * cols];
-        output[i] = dot_product_simd(row, vec);
-    }
+    debug_assert_eq!(output.len(), rows);
+    crate::thread_pool::par_rows(&mut output[..rows], cols, |i| {
+        dot_product_simd(&mat[i * cols..(i + 1) * cols], vec)
+    });
 }
 
 /// Accelerated RMSNorm using SIMD reductions.
@@ -72,6 +219,83 @@ mod tests {
         assert!((result - 36.0).abs() < 1e-4, "got {result}");
     }
 
+    #[test]
+    fn test_dot_i8_f32_matches_scalar() {
+        // 37 elements: full vector chunks plus a tail
+        let q: Vec<i8> = (0..37).map(|i| (i * 29 % 256) as u8 as i8).collect();
+        let x: Vec<f32> = (0..37).map(|i| (i as f32 - 18.0) * 0.25).collect();
+        let expected = dot_i8_f32_scalar(&q, &x);
+        let got = dot_i8_f32(&q, &x);
+        assert!((got - expected).abs() < 1e-3, "got {got}, expected {expected}");
+        assert!((sse2::dot_i8_f32_sse2(&q, &x) - expected).abs() < 1e-3);
+    }
+
+    /// Every kernel the test machine supports, against the scalar code.
+    #[test]
+    fn test_kernels_match_scalar_at_every_supported_level() {
+        let a: Vec<f32> = (0..67).map(|i| ((i * 7) as f32).sin()).collect();
+        let b: Vec<f32> = (0..67).map(|i| ((i * 3) as f32).cos() * 2.0).collect();
+        let q: Vec<i8> = (0..67).map(|i| (i * 29 % 256) as u8 as i8).collect();
+        let logits: Vec<f32> = (0..67)
+            .map(|i| if i == 5 { f32::NEG_INFINITY } else { (i as f32 - 40.0) * 2.5 })
+            .collect();
+        let dot = crate::tensor::dot_product(&a, &b);
+        let dot_q = dot_i8_f32_scalar(&q, &b);
+        let mut exp = logits.clone();
+        let exp_sum = exp_shift_sum_scalar(&mut exp, 20.0);
+
+        type Kernels = (fn(&[f32], &[f32]) -> f32, fn(&[i8], &[f32]) -> f32, fn(&mut [f32], f32) -> f32);
+        let mut levels: Vec<(&str, Kernels)> = vec![];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                levels.push((
+                    "avx512",
+                    (
+                        |a, b| unsafe { avx512::dot_product_avx512(a, b) },
+                        |q, x| unsafe { avx512::dot_i8_f32_avx512(q, x) },
+                        |v, s| unsafe { avx512::exp_shift_sum_avx512(v, s) },
+                    ),
+                ));
+            }
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                levels.push((
+                    "avx2",
+                    (
+                        |a, b| unsafe { avx2::dot_product_avx2(a, b) },
+                        |q, x| unsafe { avx2::dot_i8_f32_avx2(q, x) },
+                        |v, s| unsafe { avx2::exp_shift_sum_avx2(v, s) },
+                    ),
+                ));
+            }
+        }
+        levels.push(("dispatch", (dot_product_simd, dot_i8_f32, exp_shift_sum)));
+
+        for (name, (dot_f, dot_q_f, exp_f)) in levels {
+            assert!((dot_f(&a, &b) - dot).abs() < 1e-4, "{name} dot");
+            assert!((dot_q_f(&q, &b) - dot_q).abs() < 1e-2, "{name} dot_i8");
+            let mut got = logits.clone();
+            let sum = exp_f(&mut got, 20.0);
+            assert!((sum - exp_sum).abs() <= exp_sum * 1e-5, "{name} exp sum {sum} vs {exp_sum}");
+            // Results that would be subnormal flush to zero
+            for (g, e) in got.iter().zip(&exp) {
+                assert!((g - e).abs() <= e * 2e-6 + f32::MIN_POSITIVE, "{name} exp {g} vs {e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_softmax_simd_matches_tensor_softmax() {
+        let mut got: Vec<f32> = (0..40).map(|i| (i as f32 * 0.37).sin() * 8.0).collect();
+        let mut expected = got.clone();
+        softmax_simd(&mut got);
+        crate::tensor::softmax(&mut expected);
+        for (g, e) in got.iter().zip(&expected) {
+            assert!((g - e).abs() < 1e-6, "{g} vs {e}");
+        }
+        assert!(SimdLevel::detect() == level());
+    }
+
     #[test]
     fn test_matmul_simd() {
         let mat = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
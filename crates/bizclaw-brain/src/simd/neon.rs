@@ -35,6 +35,42 @@ pub fn dot_product_neon(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// NEON dot product of i8 quants with f32 activations (8 quants per iteration).
+#[cfg(target_arch = "aarch64")]
+pub fn dot_i8_f32_neon(q: &[i8], x: &[f32]) -> f32 {
+    debug_assert_eq!(q.len(), x.len());
+    let n = q.len();
+
+    unsafe {
+        let mut sum_vec = vdupq_n_f32(0.0);
+        let chunks = n / 8;
+
+        for i in 0..chunks {
+            let offset = i * 8;
+            let w = vmovl_s8(vld1_s8(q.as_ptr().add(offset))); // i8 → i16
+            let lo = vcvtq_f32_s32(vmovl_s16(vget_low_s16(w)));
+            let hi = vcvtq_f32_s32(vmovl_high_s16(w));
+            sum_vec = vfmaq_f32(sum_vec, lo, vld1q_f32(x.as_ptr().add(offset)));
+            sum_vec = vfmaq_f32(sum_vec, hi, vld1q_f32(x.as_ptr().add(offset + 4)));
+        }
+
+        let mut sum = vaddvq_f32(sum_vec);
+
+        // Handle remaining elements
+        for i in (chunks * 8)..n {
+            sum += q[i] as f32 * x[i];
+        }
+
+        sum
+    }
+}
+
+/// Scalar fallback for non-aarch64.
+#[cfg(not(target_arch = "aarch64"))]
+pub fn dot_i8_f32_neon(q: &[i8], x: &[f32]) -> f32 {
+    super::dot_i8_f32_scalar(q, x)
+}
+
 /// Scalar fallback for non-aarch64.
 #[cfg(not(target_arch = "aarch64"))]
 pub fn dot_product_neon(a: &[f32], b: &[f32]) -> f32 {
@@ -57,6 +93,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_neon_dot_i8_f32() {
+        let q: Vec<i8> = (-10..11).collect(); // 21 elements: chunks + tail
+        let x: Vec<f32> = (0..21).map(|i| i as f32 * 0.5).collect();
+        let expected = crate::simd::dot_i8_f32_scalar(&q, &x);
+        assert!((dot_i8_f32_neon(&q, &x) - expected).abs() < 1e-3);
+    }
+
     #[test]
     fn test_neon_dot_product_odd_length() {
         let a = vec![1.0, 2.0, 3.0, 4.0, 5.0]; // 5 elements (not multiple of 4)
@@ -39,6 +39,54 @@ pub fn dot_product_sse2(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// SSE2 dot product of i8 quants with f32 activations (16 quants per iteration).
+/// SSE2 has no i8→i32 widening, so values are sign-extended by unpacking each
+/// byte into the high half of a wider lane and shifting it back down.
+#[cfg(target_arch = "x86_64")]
+pub fn dot_i8_f32_sse2(q: &[i8], x: &[f32]) -> f32 {
+    debug_assert_eq!(q.len(), x.len());
+    let n = q.len();
+
+    unsafe {
+        let mut sum_vec = _mm_setzero_ps();
+        let chunks = n / 16;
+
+        for i in 0..chunks {
+            let offset = i * 16;
+            let v = _mm_loadu_si128(q.as_ptr().add(offset) as *const __m128i);
+            let lo16 = _mm_srai_epi16(_mm_unpacklo_epi8(v, v), 8);
+            let hi16 = _mm_srai_epi16(_mm_unpackhi_epi8(v, v), 8);
+            for (k, w) in [lo16, hi16].into_iter().enumerate() {
+                let base = offset + k * 8;
+                let a = _mm_cvtepi32_ps(_mm_srai_epi32(_mm_unpacklo_epi16(w, w), 16));
+                let b = _mm_cvtepi32_ps(_mm_srai_epi32(_mm_unpackhi_epi16(w, w), 16));
+                let xa = _mm_loadu_ps(x.as_ptr().add(base));
+                let xb = _mm_loadu_ps(x.as_ptr().add(base + 4));
+                sum_vec = _mm_add_ps(sum_vec, _mm_mul_ps(a, xa));
+                sum_vec = _mm_add_ps(sum_vec, _mm_mul_ps(b, xb));
+            }
+        }
+
+        let hi = _mm_movehl_ps(sum_vec, sum_vec);
+        let sum2 = _mm_add_ps(sum_vec, hi);
+        let hi2 = _mm_shuffle_ps(sum2, sum2, 1);
+        let mut sum = _mm_cvtss_f32(_mm_add_ss(sum2, hi2));
+
+        // Tail
+        for i in (chunks * 16)..n {
+            sum += q[i] as f32 * x[i];
+        }
+
+        sum
+    }
+}
+
+/// Scalar fallback.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn dot_i8_f32_sse2(q: &[i8], x: &[f32]) -> f32 {
+    super::dot_i8_f32_scalar(q, x)
+}
+
 /// Scalar fallback.
 #[cfg(not(target_arch = "x86_64"))]
 pub fn dot_product_sse2(a: &[f32], b: &[f32]) -> f32 {
@@ -1,6 +1,31 @@
 //! Tensor operations — matmul, rmsnorm, softmax, silu.
 //!
-//! Pure Rust implementations with future SIMD acceleration.
+//! Pure Rust implementations with future SIMD acceleration, plus the
+//! [`MatmulOffload`] hook for running large matmuls on another device.
+
+use bizclaw_core::error::Result;
+
+/// Handle to a weight matrix uploaded to a [`MatmulOffload`] device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OffloadId(pub usize);
+
+/// A device (GPU) that holds weight matrices and runs matrix-vector
+/// products against them. The forward pass routes the projections of
+/// offloaded layers here (see [`crate::forward::offload_layers`]); the wgpu
+/// backend lives in `gpu` behind the `gpu` feature.
+pub trait MatmulOffload: Send + Sync {
+    /// Adapter name for logs.
+    fn name(&self) -> String;
+
+    /// Copy a [rows x cols] row-major weight to the device.
+    fn upload(&self, weight: &[f32], rows: usize, cols: usize) -> Result<OffloadId>;
+
+    /// output[rows] = W · input[cols] for an uploaded weight.
+    fn matvec(&self, id: OffloadId, input: &[f32], output: &mut [f32]) -> Result<()>;
+
+    /// Bytes of weights held on the device.
+    fn memory_usage(&self) -> usize;
+}
 
 /// RMS normalization (Root Mean Square Layer Normalization).
 /// Used in LLaMA instead of LayerNorm.
@@ -65,6 +90,15 @@ pub fn silu(values: &mut [f32]) {
     }
 }
 
+/// GELU activation (tanh approximation): 0.5x(1 + tanh(√(2/π)(x + 0.044715x³)))
+pub fn gelu(values: &mut [f32]) {
+    const SQRT_2_OVER_PI: f32 = 0.797_884_6;
+    for v in values.iter_mut() {
+        let x = *v;
+        *v = 0.5 * x * (1.0 + (SQRT_2_OVER_PI * (x + 0.044715 * x * x * x)).tanh());
+    }
+}
+
 /// Element-wise multiply: a[i] *= b[i]
 pub fn elementwise_mul(a: &mut [f32], b: &[f32]) {
     debug_assert_eq!(a.len(), b.len());
@@ -82,6 +116,102 @@ pub fn elementwise_add(a: &mut [f32], b: &[f32]) {
 }
 
 /// Copy values from src to dst.
+/// Float ops needed by the stable softmax (implemented for f32 and f64).
+trait SoftmaxFloat:
+    Copy
+    + PartialOrd
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Div<Output = Self>
+    + std::iter::Sum
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const INF: Self;
+    const NEG_INF: Self;
+    fn exp(self) -> Self;
+    fn is_nan(self) -> bool;
+    fn from_count(n: usize) -> Self;
+}
+
+macro_rules! impl_softmax_float {
+    ($t:ty) => {
+        impl SoftmaxFloat for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const INF: Self = <$t>::INFINITY;
+            const NEG_INF: Self = <$t>::NEG_INFINITY;
+            fn exp(self) -> Self {
+                <$t>::exp(self)
+            }
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+            fn from_count(n: usize) -> Self {
+                n as $t
+            }
+        }
+    };
+}
+impl_softmax_float!(f32);
+impl_softmax_float!(f64);
+
+fn stable_softmax_impl<T: SoftmaxFloat>(values: &mut [T]) -> bool {
+    let mut clean = true;
+    let mut n_pos_inf = 0usize;
+    let mut max = T::NEG_INF;
+    for v in values.iter_mut() {
+        if v.is_nan() {
+            *v = T::NEG_INF;
+            clean = false;
+        } else if *v == T::INF {
+            n_pos_inf += 1;
+        }
+        if *v > max {
+            max = *v;
+        }
+    }
+
+    // +Inf scores take all the mass (shared equally).
+    if n_pos_inf > 0 {
+        let share = T::ONE / T::from_count(n_pos_inf);
+        for v in values.iter_mut() {
+            *v = if *v == T::INF { share } else { T::ZERO };
+        }
+        return false;
+    }
+    // Nothing usable (all NaN / -Inf).
+    if max == T::NEG_INF {
+        values.iter_mut().for_each(|v| *v = T::ZERO);
+        return false;
+    }
+
+    // log-sum-exp shifted by the max: p_i = exp(v_i - max) / Σ exp(v_j - max).
+    // Dividing (rather than subtracting max + ln Σ) keeps precision when
+    // |max| is huge and ln Σ would be absorbed by rounding.
+    let sum: T = values.iter().map(|&v| (v - max).exp()).sum();
+    for v in values.iter_mut() {
+        *v = (*v - max).exp() / sum;
+    }
+    clean
+}
+
+/// Numerically-stable softmax via log-sum-exp.
+///
+/// NaN entries get zero probability; if any entry is +Inf those entries
+/// share all the mass; if nothing is usable the output is all zeros.
+/// -Inf (masked) entries are normal and simply get zero probability.
+/// Returns `false` when the input contained NaN/+Inf or nothing usable, so callers
+/// can recover (e.g. fall back to argmax) instead of trusting the result.
+pub fn stable_softmax(values: &mut [f32]) -> bool {
+    stable_softmax_impl(values)
+}
+
+/// [`stable_softmax`] with f64 accumulation.
+pub fn stable_softmax_f64(values: &mut [f64]) -> bool {
+    stable_softmax_impl(values)
+}
+
 pub fn copy(dst: &mut [f32], src: &[f32]) {
     debug_assert_eq!(dst.len(), src.len());
     dst.copy_from_slice(src);
@@ -100,6 +230,46 @@ mod tests {
         assert!(v[2] > v[1] && v[1] > v[0]);
     }
 
+    #[test]
+    fn test_gelu() {
+        let mut v = vec![-3.0f32, 0.0, 1.0, 3.0];
+        gelu(&mut v);
+        assert!((v[0] + 0.003_637).abs() < 1e-5);
+        assert_eq!(v[1], 0.0);
+        assert!((v[2] - 0.841_192).abs() < 1e-5);
+        assert!((v[3] - 2.996_363).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_stable_softmax_large_logits() {
+        // Naive exp() overflows to Inf here; log-sum-exp stays finite.
+        let mut v = vec![3.0e38f32, 2.9e38, -3.0e38, 1.0];
+        assert!(stable_softmax(&mut v));
+        assert!(v.iter().all(|p| p.is_finite()));
+        assert!((v.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert_eq!(v[0], 1.0);
+
+        let mut v = vec![1e300f64, 1e300, 0.0];
+        assert!(stable_softmax_f64(&mut v));
+        assert!((v[0] - 0.5).abs() < 1e-12 && v[2] == 0.0);
+    }
+
+    #[test]
+    fn test_stable_softmax_recovers_non_finite() {
+        let mut v = vec![f32::NAN, 2.0, f32::INFINITY, f32::INFINITY];
+        assert!(!stable_softmax(&mut v));
+        assert_eq!(v, vec![0.0, 0.0, 0.5, 0.5]);
+
+        let mut v = vec![f32::NAN, 1.0, 3.0];
+        assert!(!stable_softmax(&mut v));
+        assert!(v.iter().all(|p| p.is_finite()));
+        assert!((v.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+
+        let mut v = vec![f32::NAN, f32::NEG_INFINITY];
+        assert!(!stable_softmax(&mut v));
+        assert_eq!(v, vec![0.0, 0.0]);
+    }
+
     #[test]
     fn test_rmsnorm() {
         let input = vec![1.0, 2.0, 3.0, 4.0];
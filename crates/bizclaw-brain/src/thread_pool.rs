@@ -1,6 +1,78 @@
 //! Multi-threaded matrix multiply using rayon.
+//!
+//! The row-parallel kernels ([`par_rows`], [`try_par_rows`]) run on a pool
+//! sized by [`configure`] (`BrainConfig.threads`); until it is configured,
+//! or with one thread, they run on the calling thread.
 
+use bizclaw_core::error::{BizClawError, Result};
 use rayon::prelude::*;
+use std::sync::{Arc, RwLock};
+
+/// Below this many multiply-adds a matmul stays on the calling thread:
+/// splitting costs more than it saves.
+const PARALLEL_MIN_WORK: usize = 1 << 15;
+
+/// Row chunks per worker, so uneven rows still balance.
+const CHUNKS_PER_THREAD: usize = 4;
+
+static POOL: RwLock<Option<Arc<rayon::ThreadPool>>> = RwLock::new(None);
+
+/// Size the worker pool shared by the row-parallel kernels. `threads` ≤ 1
+/// makes them serial.
+pub fn configure(threads: usize) -> Result<()> {
+    let pool = if threads > 1 {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("brain-{i}"))
+            .build()
+            .map_err(|e| BizClawError::Brain(format!("Failed to start {threads} threads: {e}")))?;
+        Some(Arc::new(pool))
+    } else {
+        None
+    };
+    *POOL.write().unwrap_or_else(|e| e.into_inner()) = pool;
+    Ok(())
+}
+
+fn pool() -> Option<Arc<rayon::ThreadPool>> {
+    POOL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Compute `output[i] = row(i)` for every row, split across the configured
+/// workers. `cols` is the per-row work, used to skip threading small jobs.
+pub fn par_rows(output: &mut [f32], cols: usize, row: impl Fn(usize) -> f32 + Sync) {
+    let result: Result<()> = try_par_rows(output, cols, |i| Ok(row(i)));
+    debug_assert!(result.is_ok());
+}
+
+/// Fallible [`par_rows`]: stops at the first error.
+pub fn try_par_rows(
+    output: &mut [f32],
+    cols: usize,
+    row: impl Fn(usize) -> Result<f32> + Sync,
+) -> Result<()> {
+    let rows = output.len();
+    let pool = pool().filter(|_| rows > 1 && rows * cols >= PARALLEL_MIN_WORK);
+    let Some(pool) = pool else {
+        for (i, out) in output.iter_mut().enumerate() {
+            *out = row(i)?;
+        }
+        return Ok(());
+    };
+
+    let chunk = rows.div_ceil(pool.current_num_threads() * CHUNKS_PER_THREAD);
+    pool.install(|| {
+        output
+            .par_chunks_mut(chunk)
+            .enumerate()
+            .try_for_each(|(c, out)| {
+                for (j, o) in out.iter_mut().enumerate() {
+                    *o = row(c * chunk + j)?;
+                }
+                Ok(())
+            })
+    })
+}
 
 /// Parallel matrix-vector multiply: output = mat * vec.
 /// mat is [rows x cols] in row-major order.
@@ -18,7 +90,7 @@ pub fn matmul_parallel(output: &mut [f32], mat: &[f32], vec_in: &[f32], rows: us
 
 /// Get the number of available threads.
 pub fn num_threads() -> usize {
-    rayon::current_num_threads()
+    pool().map_or(1, |p| p.current_num_threads())
 }
 
 #[cfg(test)]
@@ -34,4 +106,31 @@ mod tests {
         assert!((output[0] - 6.0).abs() < 1e-6);
         assert!((output[1] - 15.0).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_par_rows_matches_serial_and_propagates_errors() {
+        // The pool is global; other tests only ever see identical results
+        configure(3).unwrap();
+        let (rows, cols) = (1001, 64); // above the threshold, uneven chunks
+        let value = |i: usize| (i * 31 % 97) as f32 - 48.0;
+
+        let mut output = vec![0.0f32; rows];
+        par_rows(&mut output, cols, value);
+        assert!(output.iter().enumerate().all(|(i, &v)| v == value(i)));
+
+        let mut output = vec![0.0f32; rows];
+        let err = try_par_rows(&mut output, cols, |i| {
+            if i == 700 {
+                Err(BizClawError::Brain("row 700".into()))
+            } else {
+                Ok(value(i))
+            }
+        });
+        assert!(err.is_err());
+
+        // Small jobs stay serial but produce the same values
+        let mut output = vec![0.0f32; 8];
+        par_rows(&mut output, 4, value);
+        assert!(output.iter().enumerate().all(|(i, &v)| v == value(i)));
+    }
 }
@@ -1,11 +1,90 @@
-//! BPE (Byte Pair Encoding) tokenizer for LLaMA models.
+//! Tokenizers for GGUF models: SentencePiece BPE (LLaMA, Mistral, Gemma),
+//! SentencePiece Unigram (T5-style vocabularies) and score-ordered BPE.
 //!
-//! Reads vocabulary and merge rules from GGUF metadata and converts
-//! text to/from token IDs.
+//! Reads vocabulary, scores and token types from GGUF metadata and converts
+//! text to/from token IDs the way llama.cpp does, including `<0xNN>` byte
+//! fallback and splitting out added tokens. Also renders chat messages into
+//! the prompt format the model was tuned on (see [`ChatTemplate`]).
 
 use crate::gguf::GgufValue;
 use bizclaw_core::error::{BizClawError, Result};
-use std::collections::HashMap;
+use bizclaw_core::types::{Message, Role};
+use std::collections::{BinaryHeap, HashMap};
+
+/// SentencePiece's escaped space.
+const SPACE: &str = "\u{2581}";
+
+/// Segmentation algorithm, from `tokenizer.ggml.model`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerModel {
+    /// SentencePiece BPE (`llama`): merge characters by score, with
+    /// `<0xNN>` byte fallback for pieces outside the vocabulary.
+    Spm,
+    /// SentencePiece Unigram (`t5`): the segmentation with the highest
+    /// total score.
+    Unigram,
+    /// Any other vocabulary (`gpt2`, ...): merge bytes by score.
+    Bpe,
+}
+
+impl TokenizerModel {
+    fn from_name(name: Option<&str>) -> Self {
+        match name {
+            None | Some("llama") => Self::Spm,
+            Some("t5") => Self::Unigram,
+            Some(_) => Self::Bpe,
+        }
+    }
+}
+
+/// Kind of a vocabulary entry (`tokenizer.ggml.token_type`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    Normal,
+    Unknown,
+    /// BOS, EOS, turn markers: never produced by plain text, decode to nothing.
+    Control,
+    /// Added tokens: always matched as a whole, decode verbatim.
+    UserDefined,
+    Unused,
+    /// `<0xNN>` byte-fallback pieces.
+    Byte,
+}
+
+impl TokenType {
+    /// llama.cpp's numbering; files without types get `Byte` for `<0xNN>`
+    /// pieces and `Normal` otherwise.
+    fn new(code: Option<u32>, piece: &str) -> Self {
+        match code {
+            Some(2) => Self::Unknown,
+            Some(3) => Self::Control,
+            Some(4) => Self::UserDefined,
+            Some(5) => Self::Unused,
+            Some(6) => Self::Byte,
+            Some(_) => Self::Normal,
+            None if byte_piece(piece).is_some() => Self::Byte,
+            None => Self::Normal,
+        }
+    }
+}
+
+/// The byte a `<0xNN>` piece stands for.
+fn byte_piece(piece: &str) -> Option<u8> {
+    let hex = piece.strip_prefix("<0x")?.strip_suffix('>')?;
+    if hex.len() != 2 {
+        return None;
+    }
+    u8::from_str_radix(hex, 16).ok()
+}
+
+/// User-defined tokens, longest first (the order llama.cpp splits them out).
+fn added_tokens(vocab: &[String], types: &[TokenType]) -> Vec<u32> {
+    let mut added: Vec<u32> = (0..vocab.len() as u32)
+        .filter(|&id| types[id as usize] == TokenType::UserDefined && !vocab[id as usize].is_empty())
+        .collect();
+    added.sort_by_key(|&id| std::cmp::Reverse(vocab[id as usize].len()));
+    added
+}
 
 /// BPE tokenizer for LLaMA-family models.
 pub struct BpeTokenizer {
@@ -15,10 +94,65 @@ pub struct BpeTokenizer {
     token_to_id: HashMap<String, u32>,
     /// Token scores (used for BPE merge priority).
     scores: Vec<f32>,
+    /// Kind of each token.
+    types: Vec<TokenType>,
+    /// Segmentation algorithm.
+    pub model: TokenizerModel,
+    /// Prefix text with a space (SentencePiece `add_dummy_prefix`).
+    add_space_prefix: bool,
+    /// Collapse runs of spaces and trim them (Unigram normalization).
+    remove_extra_whitespaces: bool,
+    /// User-defined tokens, longest first, split out of all text.
+    added: Vec<u32>,
+    /// Longest vocabulary entry in bytes.
+    max_token_len: usize,
     /// Special token IDs.
     pub bos_id: u32,
     pub eos_id: u32,
     pub pad_id: u32,
+    pub unk_id: u32,
+    /// Prompt format for chat messages.
+    pub chat_template: ChatTemplate,
+}
+
+/// A run of raw text, or a special token split out of it.
+enum Fragment<'a> {
+    Text(&'a str),
+    Token(u32),
+}
+
+/// Candidate merge of two adjacent SentencePiece symbols. Higher scores pop
+/// first, ties go to the leftmost pair.
+#[derive(PartialEq)]
+struct Bigram {
+    score: f32,
+    left: usize,
+    right: usize,
+    len: usize,
+}
+
+impl Eq for Bigram {}
+
+impl PartialOrd for Bigram {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Bigram {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.left.cmp(&self.left))
+    }
+}
+
+/// A SentencePiece symbol: a byte range of the input in a linked list.
+struct Symbol {
+    start: usize,
+    len: usize,
+    prev: Option<usize>,
+    next: Option<usize>,
 }
 
 impl BpeTokenizer {
@@ -51,6 +185,17 @@ impl BpeTokenizer {
             })
             .unwrap_or_else(|| vec![0.0; vocab.len()]);
 
+        // Extract token types
+        let codes: Vec<u32> = match metadata.get("tokenizer.ggml.token_type") {
+            Some(GgufValue::Array(arr)) => arr.iter().filter_map(|v| v.as_u32()).collect(),
+            _ => Vec::new(),
+        };
+        let types: Vec<TokenType> = vocab
+            .iter()
+            .enumerate()
+            .map(|(i, piece)| TokenType::new(codes.get(i).copied(), piece))
+            .collect();
+
         // Build reverse mapping
         let token_to_id: HashMap<String, u32> = vocab
             .iter()
@@ -58,6 +203,15 @@ impl BpeTokenizer {
             .map(|(i, t)| (t.clone(), i as u32))
             .collect();
 
+        let model = TokenizerModel::from_name(
+            metadata.get("tokenizer.ggml.model").and_then(|v| v.as_str()),
+        );
+        let flag = |key: &str, default: bool| {
+            metadata.get(key).and_then(|v| v.as_bool()).unwrap_or(default)
+        };
+        let add_space_prefix = flag("tokenizer.ggml.add_space_prefix", model != TokenizerModel::Bpe);
+        let remove_extra_whitespaces = flag("tokenizer.ggml.remove_extra_whitespaces", false);
+
         // Extract special tokens
         let bos_id = metadata
             .get("tokenizer.ggml.bos_token_id")
@@ -71,21 +225,43 @@ impl BpeTokenizer {
             .get("tokenizer.ggml.padding_token_id")
             .and_then(|v| v.as_u32())
             .unwrap_or(0);
+        let unk_id = metadata
+            .get("tokenizer.ggml.unknown_token_id")
+            .and_then(|v| v.as_u32())
+            .unwrap_or(0);
+
+        let chat_template = ChatTemplate::detect(
+            metadata.get("tokenizer.chat_template").and_then(|v| v.as_str()),
+            metadata
+                .get("general.architecture")
+                .and_then(|v| v.as_str())
+                .unwrap_or("llama"),
+        );
 
         tracing::info!(
-            "Tokenizer loaded: vocab_size={}, bos={}, eos={}",
+            "Tokenizer loaded: vocab_size={}, model={:?}, bos={}, eos={}, chat_template={:?}",
             vocab.len(),
+            model,
             bos_id,
-            eos_id
+            eos_id,
+            chat_template
         );
 
         Ok(Self {
+            added: added_tokens(&vocab, &types),
+            max_token_len: vocab.iter().map(|t| t.len()).max().unwrap_or(0),
             vocab,
             token_to_id,
             scores,
+            types,
+            model,
+            add_space_prefix,
+            remove_extra_whitespaces,
             bos_id,
             eos_id,
             pad_id,
+            unk_id,
+            chat_template,
         })
     }
 
@@ -97,20 +273,256 @@ impl BpeTokenizer {
             .enumerate()
             .map(|(i, t)| (t.clone(), i as u32))
             .collect();
+        let types = vec![TokenType::Control, TokenType::Control, TokenType::Control, TokenType::Normal];
         Self {
             scores: vec![0.0; vocab.len()],
+            added: Vec::new(),
+            max_token_len: vocab.iter().map(|t| t.len()).max().unwrap_or(0),
             vocab,
             token_to_id,
+            types,
+            model: TokenizerModel::Bpe,
+            add_space_prefix: false,
+            remove_extra_whitespaces: false,
             bos_id: 1,
             eos_id: 2,
             pad_id: 0,
+            unk_id: 0,
+            chat_template: ChatTemplate::Llama2,
         }
     }
 
-    /// Encode text into token IDs using BPE.
+    /// Encode text into token IDs. Added (user-defined) tokens are matched
+    /// as a whole; control tokens such as `<s>` are plain text.
     pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.encode_with_special(text, &[])
+    }
+
+    /// Encode text in which the given special-token strings (e.g. chat
+    /// template markers) map to their single vocabulary ID instead of being
+    /// split into bytes. Specials missing from the vocabulary encode as text.
+    pub fn encode_with_special(&self, text: &str, specials: &[&str]) -> Vec<u32> {
+        let mut split: Vec<u32> = specials.iter().filter_map(|s| self.token_id(s)).collect();
+        split.extend(&self.added);
+        split.sort_by_key(|&id| std::cmp::Reverse(self.vocab[id as usize].len()));
+        split.dedup();
+
+        let mut tokens = Vec::new();
+        // SentencePiece prefixes a space at the start and after each special
+        let mut prev_special = true;
+        for fragment in self.partition(text, &split) {
+            match fragment {
+                Fragment::Token(id) => {
+                    tokens.push(id);
+                    prev_special = true;
+                }
+                Fragment::Text(text) => {
+                    match self.model {
+                        TokenizerModel::Spm => {
+                            let prefix = if self.add_space_prefix && prev_special { " " } else { "" };
+                            let escaped = format!("{prefix}{text}").replace(' ', SPACE);
+                            self.encode_spm(&escaped, &mut tokens);
+                        }
+                        TokenizerModel::Unigram => self.encode_unigram(&self.normalize(text), &mut tokens),
+                        TokenizerModel::Bpe => self.encode_bpe(text, &mut tokens),
+                    }
+                    prev_special = false;
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Split `text` on every occurrence of the `split` tokens, longest token
+    /// first (llama.cpp's special-token partition).
+    fn partition<'a>(&self, text: &'a str, split: &[u32]) -> Vec<Fragment<'a>> {
+        let mut fragments = vec![Fragment::Text(text)];
+        for &id in split {
+            let special = self.vocab[id as usize].as_str();
+            let mut next = Vec::with_capacity(fragments.len());
+            for fragment in fragments {
+                let Fragment::Text(mut rest) = fragment else {
+                    next.push(fragment);
+                    continue;
+                };
+                while let Some(at) = rest.find(special) {
+                    if at > 0 {
+                        next.push(Fragment::Text(&rest[..at]));
+                    }
+                    next.push(Fragment::Token(id));
+                    rest = &rest[at + special.len()..];
+                }
+                if !rest.is_empty() {
+                    next.push(Fragment::Text(rest));
+                }
+            }
+            fragments = next;
+        }
+        fragments
+    }
+
+    /// SentencePiece BPE over whitespace-escaped `text`: start from single
+    /// characters and repeatedly merge the adjacent pair that forms the
+    /// highest-scoring vocabulary entry. Characters left outside the
+    /// vocabulary become `<0xNN>` byte tokens.
+    fn encode_spm(&self, text: &str, out: &mut Vec<u32>) {
+        let mut symbols: Vec<Symbol> = text
+            .char_indices()
+            .enumerate()
+            .map(|(i, (start, c))| Symbol {
+                start,
+                len: c.len_utf8(),
+                prev: i.checked_sub(1),
+                next: Some(i + 1),
+            })
+            .collect();
+        let Some(last) = symbols.last_mut() else {
+            return;
+        };
+        last.next = None;
+
+        let mut queue = BinaryHeap::new();
+        for right in 1..symbols.len() {
+            self.push_bigram(text, &symbols, right - 1, right, &mut queue);
+        }
+        while let Some(bigram) = queue.pop() {
+            let (left, right) = (bigram.left, bigram.right);
+            // Skip pairs invalidated by an earlier merge
+            if symbols[left].len == 0
+                || symbols[right].len == 0
+                || symbols[left].len + symbols[right].len != bigram.len
+            {
+                continue;
+            }
+            symbols[left].len += symbols[right].len;
+            symbols[right].len = 0;
+            symbols[left].next = symbols[right].next;
+            if let Some(next) = symbols[right].next {
+                symbols[next].prev = Some(left);
+            }
+            if let Some(prev) = symbols[left].prev {
+                self.push_bigram(text, &symbols, prev, left, &mut queue);
+            }
+            if let Some(next) = symbols[left].next {
+                self.push_bigram(text, &symbols, left, next, &mut queue);
+            }
+        }
+
+        let mut at = Some(0);
+        while let Some(i) = at {
+            let piece = &text[symbols[i].start..symbols[i].start + symbols[i].len];
+            match self.token_id(piece) {
+                Some(id) => out.push(id),
+                None => out.extend(piece.bytes().map(|b| self.byte_token(b))),
+            }
+            at = symbols[i].next;
+        }
+    }
+
+    fn push_bigram(&self, text: &str, symbols: &[Symbol], left: usize, right: usize, queue: &mut BinaryHeap<Bigram>) {
+        let start = symbols[left].start;
+        let len = symbols[left].len + symbols[right].len;
+        if let Some(id) = self.token_id(&text[start..start + len]) {
+            queue.push(Bigram {
+                score: self.scores.get(id as usize).copied().unwrap_or(0.0),
+                left,
+                right,
+                len,
+            });
+        }
+    }
+
+    /// Byte-fallback token for `byte`: `<0xNN>`, else the byte as a
+    /// one-character piece, else UNK.
+    fn byte_token(&self, byte: u8) -> u32 {
+        self.token_id(&format!("<0x{byte:02X}>"))
+            .or_else(|| self.token_id(std::str::from_utf8(&[byte]).ok()?))
+            .unwrap_or(self.unk_id)
+    }
+
+    /// Unigram normalization without a precompiled charsmap: spaces become
+    /// `▁`, a `▁` is prefixed before the first word with `add_space_prefix`,
+    /// and with `remove_extra_whitespaces` runs of spaces collapse to one
+    /// `▁` before each word.
+    fn normalize(&self, text: &str) -> String {
+        let mut normalized = String::with_capacity(text.len() + SPACE.len());
+        let mut prepended = false;
+        let mut in_word = false;
+        for c in text.chars() {
+            if c != ' ' {
+                if !in_word {
+                    in_word = true;
+                    if (self.add_space_prefix && !prepended) || self.remove_extra_whitespaces {
+                        normalized.push_str(SPACE);
+                        prepended = true;
+                    }
+                }
+                normalized.push(c);
+            } else {
+                in_word = false;
+                if !self.remove_extra_whitespaces {
+                    normalized.push_str(SPACE);
+                }
+            }
+        }
+        normalized
+    }
+
+    /// Unigram segmentation: Viterbi over every vocabulary piece starting at
+    /// each character, maximising the summed piece scores. Characters no
+    /// piece covers become UNK (runs merged into one), scored below any
+    /// real piece.
+    fn encode_unigram(&self, text: &str, out: &mut Vec<u32>) {
+        let min_score = (0..self.vocab.len())
+            .filter(|&id| self.types[id] == TokenType::Normal)
+            .map(|id| self.scores.get(id).copied().unwrap_or(0.0))
+            .fold(f32::INFINITY, f32::min);
+        let unknown_score = if min_score.is_finite() { min_score - 10.0 } else { -10.0 };
+
+        // best[i] = (token, start, score) of the best segmentation of text[..i]
+        let mut best: Vec<(u32, usize, f64)> = vec![(self.unk_id, 0, f64::NEG_INFINITY); text.len() + 1];
+        best[0].2 = 0.0;
+        for (start, c) in text.char_indices() {
+            let base = best[start].2;
+            let char_end = start + c.len_utf8();
+            let mut covered = false;
+            let limit = (start + self.max_token_len).min(text.len());
+            for end in (char_end..=limit).filter(|&e| text.is_char_boundary(e)) {
+                let Some(id) = self.token_id(&text[start..end]) else {
+                    continue;
+                };
+                let score = match self.types[id as usize] {
+                    TokenType::Normal | TokenType::Unused => self.scores.get(id as usize).copied().unwrap_or(0.0),
+                    TokenType::UserDefined => 0.0,
+                    _ => continue,
+                };
+                if base + score as f64 > best[end].2 {
+                    best[end] = (id, start, base + score as f64);
+                }
+                covered |= end == char_end;
+            }
+            if !covered && base + unknown_score as f64 > best[char_end].2 {
+                best[char_end] = (self.unk_id, start, base + unknown_score as f64);
+            }
+        }
+
+        let mut segmented = Vec::new();
+        let mut end = text.len();
+        while end > 0 {
+            let (id, start, _) = best[end];
+            if !(id == self.unk_id && segmented.last() == Some(&self.unk_id)) {
+                segmented.push(id);
+            }
+            end = start;
+        }
+        out.extend(segmented.iter().rev());
+    }
+
+    /// Byte-level BPE: one token per byte, then repeatedly apply the
+    /// highest-scoring merge of adjacent tokens.
+    fn encode_bpe(&self, text: &str, out: &mut Vec<u32>) {
         if text.is_empty() {
-            return vec![];
+            return;
         }
 
         // Step 1: UTF-8 byte-level encoding — each byte becomes a token
@@ -172,10 +584,55 @@ impl BpeTokenizer {
             tokens.remove(best_idx + 1);
         }
 
-        tokens
+        out.extend(tokens);
+    }
+
+    /// Token IDs to bias or ban for `text`: the vocabulary entries spelling
+    /// it exactly, with or without a word-start marker (`▁` SentencePiece,
+    /// `Ġ` GPT-2). Falls back to the tokens `text` encodes to when no single
+    /// entry matches.
+    pub fn tokens_for(&self, text: &str) -> Vec<u32> {
+        let trimmed = text.trim_start();
+        let exact: Vec<u32> = [
+            text.to_string(),
+            format!("\u{2581}{trimmed}"),
+            format!("\u{0120}{trimmed}"),
+        ]
+        .iter()
+        .filter_map(|t| self.token_id(t))
+        .fold(Vec::new(), |mut ids, id| {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+            ids
+        });
+        if exact.is_empty() {
+            self.encode(text)
+        } else {
+            exact
+        }
+    }
+
+    /// Look up the ID of an exact vocabulary entry.
+    pub fn token_id(&self, token: &str) -> Option<u32> {
+        self.token_to_id.get(token).copied()
     }
 
-    /// Decode a single token ID to string.
+    /// IDs that end an assistant turn: EOS plus the template's end-of-turn
+    /// markers that exist in the vocabulary.
+    pub fn stop_ids(&self) -> Vec<u32> {
+        let mut ids = vec![self.eos_id];
+        ids.extend(
+            self.chat_template
+                .stop_tokens()
+                .iter()
+                .filter_map(|s| self.token_id(s)),
+        );
+        ids.dedup();
+        ids
+    }
+
+    /// Decode a single token ID to its raw vocabulary piece.
     pub fn decode_token(&self, id: u32) -> &str {
         self.vocab
             .get(id as usize)
@@ -183,13 +640,32 @@ impl BpeTokenizer {
             .unwrap_or("<unk>")
     }
 
-    /// Decode a sequence of token IDs to text.
+    /// Kind of a token (`Normal` for IDs outside the vocabulary).
+    pub fn token_type(&self, id: u32) -> TokenType {
+        self.types.get(id as usize).copied().unwrap_or(TokenType::Normal)
+    }
+
+    /// Decode a sequence of token IDs to text, as llama.cpp detokenizes:
+    /// control tokens produce nothing, byte pieces are reassembled into
+    /// UTF-8, `▁` is a space and the space prefix added by
+    /// [`Self::encode`] is dropped from the first token.
     pub fn decode(&self, tokens: &[u32]) -> String {
-        tokens
-            .iter()
-            .map(|&id| self.decode_token(id))
-            .collect::<Vec<&str>>()
-            .join("")
+        if self.model == TokenizerModel::Bpe {
+            return tokens.iter().map(|&id| self.decode_token(id)).collect();
+        }
+        let mut bytes = Vec::new();
+        for (i, &id) in tokens.iter().enumerate() {
+            let piece = self.decode_token(id);
+            let text = match self.token_type(id) {
+                TokenType::Control | TokenType::Unused => continue,
+                TokenType::Byte => byte_piece(piece).into_iter().collect(),
+                TokenType::Normal => piece.replace(SPACE, " ").into_bytes(),
+                TokenType::Unknown | TokenType::UserDefined => piece.as_bytes().to_vec(),
+            };
+            let skip = usize::from(i == 0 && self.add_space_prefix && text.first() == Some(&b' '));
+            bytes.extend_from_slice(&text[skip..]);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
     }
 
     /// Get vocabulary size.
@@ -197,8 +673,340 @@ impl BpeTokenizer {
         self.vocab.len()
     }
 
-    /// Check if a token is a special token.
+    /// Check if a token is a special token: BOS/EOS/PAD or any control token.
     pub fn is_special(&self, id: u32) -> bool {
-        id == self.bos_id || id == self.eos_id || id == self.pad_id
+        id == self.bos_id || id == self.eos_id || id == self.pad_id || self.token_type(id) == TokenType::Control
+    }
+}
+
+/// Chat prompt format.
+///
+/// GGUF files carry the format as a Jinja template in
+/// `tokenizer.chat_template`; rather than executing Jinja, the template is
+/// recognised by its turn markers and rendered natively. BOS is not part of
+/// the rendered text — the engine prepends the BOS token itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatTemplate {
+    /// `[INST] ... [/INST]` with a `<<SYS>>` block (LLaMA-2, Mistral).
+    Llama2,
+    /// `<|im_start|>role ... <|im_end|>` (Qwen, many fine-tunes).
+    ChatMl,
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`.
+    Llama3,
+    /// `<start_of_turn>user|model ... <end_of_turn>`; no system role.
+    Gemma,
+}
+
+impl ChatTemplate {
+    /// Detect the format from a `tokenizer.chat_template` source, falling
+    /// back to the architecture's usual format when it is absent or unknown.
+    pub fn detect(template: Option<&str>, arch: &str) -> Self {
+        if let Some(src) = template {
+            if src.contains("<|im_start|>") {
+                return Self::ChatMl;
+            }
+            if src.contains("<|start_header_id|>") {
+                return Self::Llama3;
+            }
+            if src.contains("<start_of_turn>") {
+                return Self::Gemma;
+            }
+            if src.contains("[INST]") {
+                return Self::Llama2;
+            }
+            tracing::warn!("Unrecognised tokenizer.chat_template, using the {arch} default");
+        }
+        match arch {
+            "qwen2" => Self::ChatMl,
+            "gemma" => Self::Gemma,
+            _ => Self::Llama2,
+        }
+    }
+
+    /// Short format name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Llama2 => "llama2",
+            Self::ChatMl => "chatml",
+            Self::Llama3 => "llama3",
+            Self::Gemma => "gemma",
+        }
+    }
+
+    /// Marker strings that must be encoded as single special tokens.
+    pub fn special_tokens(&self) -> &'static [&'static str] {
+        match self {
+            Self::Llama2 => &["[INST]", "[/INST]", "</s>", "<s>"],
+            Self::ChatMl => &["<|im_start|>", "<|im_end|>"],
+            Self::Llama3 => &["<|start_header_id|>", "<|end_header_id|>", "<|eot_id|>"],
+            Self::Gemma => &["<start_of_turn>", "<end_of_turn>"],
+        }
+    }
+
+    /// Markers that end the assistant's turn.
+    pub fn stop_tokens(&self) -> &'static [&'static str] {
+        match self {
+            Self::Llama2 => &["</s>"],
+            Self::ChatMl => &["<|im_end|>"],
+            Self::Llama3 => &["<|eot_id|>"],
+            Self::Gemma => &["<end_of_turn>"],
+        }
+    }
+
+    /// Render a conversation, ending with the prompt for the assistant's reply.
+    pub fn render(&self, messages: &[Message]) -> String {
+        let mut prompt = String::new();
+        match self {
+            Self::Llama2 => {
+                let mut open = false; // inside an unanswered [INST]
+                for msg in messages {
+                    if !open && msg.role != Role::Assistant {
+                        prompt.push_str("[INST] ");
+                        open = true;
+                    }
+                    match msg.role {
+                        Role::System => {
+                            prompt.push_str(&format!("<<SYS>>\n{}\n<</SYS>>\n\n", msg.content));
+                        }
+                        Role::User => prompt.push_str(&format!("{} ", msg.content)),
+                        Role::Tool => prompt.push_str(&format!("Tool result: {} ", msg.content)),
+                        Role::Assistant => {
+                            if open {
+                                prompt.push_str("[/INST]");
+                                open = false;
+                            }
+                            prompt.push_str(&format!(" {} </s><s>", msg.content));
+                        }
+                    }
+                }
+                if !open {
+                    prompt.push_str("[INST] ");
+                }
+                prompt.push_str("[/INST]");
+            }
+            Self::ChatMl => {
+                for msg in messages {
+                    prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", msg.role, msg.content));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+            }
+            Self::Llama3 => {
+                for msg in messages {
+                    let role = match &msg.role {
+                        Role::Tool => "ipython".to_string(),
+                        role => role.to_string(),
+                    };
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{role}<|end_header_id|>\n\n{}<|eot_id|>",
+                        msg.content.trim()
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+            }
+            Self::Gemma => {
+                // No system turn: fold system text into the next user turn
+                let mut pending_system = String::new();
+                for msg in messages {
+                    match msg.role {
+                        Role::System => {
+                            pending_system.push_str(msg.content.trim());
+                            pending_system.push_str("\n\n");
+                        }
+                        Role::Assistant => prompt.push_str(&format!(
+                            "<start_of_turn>model\n{}<end_of_turn>\n",
+                            msg.content.trim()
+                        )),
+                        Role::User | Role::Tool => {
+                            let label = if msg.role == Role::Tool { "Tool result: " } else { "" };
+                            prompt.push_str(&format!(
+                                "<start_of_turn>user\n{}{label}{}<end_of_turn>\n",
+                                std::mem::take(&mut pending_system),
+                                msg.content.trim()
+                            ));
+                        }
+                    }
+                }
+                if !pending_system.is_empty() {
+                    prompt.push_str(&format!(
+                        "<start_of_turn>user\n{}<end_of_turn>\n",
+                        pending_system.trim_end()
+                    ));
+                }
+                prompt.push_str("<start_of_turn>model\n");
+            }
+        }
+        prompt
+    }
+}
+
+impl bizclaw_core::traits::Tokenizer for BpeTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenizer(tokens: &[&str], chat_template: &str) -> BpeTokenizer {
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "tokenizer.ggml.tokens".to_string(),
+            GgufValue::Array(tokens.iter().map(|t| GgufValue::String(t.to_string())).collect()),
+        );
+        metadata.insert("tokenizer.ggml.eos_token_id".to_string(), GgufValue::U32(2));
+        metadata.insert(
+            "tokenizer.chat_template".to_string(),
+            GgufValue::String(chat_template.into()),
+        );
+        // Markers directly followed by text, without SentencePiece's space
+        metadata.insert("tokenizer.ggml.add_space_prefix".to_string(), GgufValue::Bool(false));
+        BpeTokenizer::from_gguf(&metadata).unwrap()
+    }
+
+    /// A `model` tokenizer from `(piece, score, token_type)` entries, with
+    /// `<unk>`, `<s>` and `</s>` as IDs 0–2.
+    fn scored(model: &str, entries: &[(&str, f32, u32)], extra: &[(&str, GgufValue)]) -> BpeTokenizer {
+        let mut all = vec![("<unk>", 0.0, 2), ("<s>", 0.0, 3), ("</s>", 0.0, 3)];
+        all.extend_from_slice(entries);
+        let array = |f: &dyn Fn(&(&str, f32, u32)) -> GgufValue| GgufValue::Array(all.iter().map(f).collect());
+        let mut metadata = HashMap::from([
+            ("tokenizer.ggml.model".to_string(), GgufValue::String(model.into())),
+            ("tokenizer.ggml.tokens".to_string(), array(&|e| GgufValue::String(e.0.into()))),
+            ("tokenizer.ggml.scores".to_string(), array(&|e| GgufValue::F32(e.1))),
+            ("tokenizer.ggml.token_type".to_string(), array(&|e| GgufValue::I32(e.2 as i32))),
+        ]);
+        for (key, value) in extra {
+            metadata.insert(key.to_string(), value.clone());
+        }
+        BpeTokenizer::from_gguf(&metadata).unwrap()
+    }
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::system("Be brief."),
+            Message::user("Hi"),
+            Message::assistant("Hello!"),
+            Message::user("2+2?"),
+        ]
+    }
+
+    #[test]
+    fn test_detects_template_from_metadata_and_arch() {
+        let chatml = "{% for m in messages %}<|im_start|>{{ m.role }}\n{{ m.content }}<|im_end|>{% endfor %}";
+        assert_eq!(ChatTemplate::detect(Some(chatml), "llama"), ChatTemplate::ChatMl);
+        let llama3 = "{{ '<|start_header_id|>' + m.role + '<|end_header_id|>' }}";
+        assert_eq!(ChatTemplate::detect(Some(llama3), "llama"), ChatTemplate::Llama3);
+        let gemma = "{{ '<start_of_turn>' + role + '\n' }}";
+        assert_eq!(ChatTemplate::detect(Some(gemma), "llama"), ChatTemplate::Gemma);
+        assert_eq!(ChatTemplate::detect(Some("[INST] x [/INST]"), "qwen2"), ChatTemplate::Llama2);
+        assert_eq!(ChatTemplate::detect(None, "qwen2"), ChatTemplate::ChatMl);
+        assert_eq!(ChatTemplate::detect(None, "gemma"), ChatTemplate::Gemma);
+        assert_eq!(ChatTemplate::detect(Some("{{ unknown }}"), "llama"), ChatTemplate::Llama2);
+    }
+
+    #[test]
+    fn test_renders_each_format() {
+        let msgs = conversation();
+        assert_eq!(
+            ChatTemplate::ChatMl.render(&msgs),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\nHello!<|im_end|>\n<|im_start|>user\n2+2?<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatTemplate::Llama3.render(&msgs),
+            "<|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\nHello!<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\n2+2?<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+        assert_eq!(
+            ChatTemplate::Gemma.render(&msgs),
+            "<start_of_turn>user\nBe brief.\n\nHi<end_of_turn>\n\
+             <start_of_turn>model\nHello!<end_of_turn>\n\
+             <start_of_turn>user\n2+2?<end_of_turn>\n<start_of_turn>model\n"
+        );
+        assert_eq!(
+            ChatTemplate::Llama2.render(&msgs),
+            "[INST] <<SYS>>\nBe brief.\n<</SYS>>\n\nHi [/INST] Hello! </s><s>[INST] 2+2? [/INST]"
+        );
+    }
+
+    #[test]
+    fn test_special_markers_encode_as_single_tokens() {
+        let tok = tokenizer(
+            &["<unk>", "<s>", "</s>", "<|im_start|>", "<|im_end|>", "h", "i", "hi", "\n", "\u{2581}hi"],
+            "<|im_start|>",
+        );
+        assert_eq!(tok.chat_template, ChatTemplate::ChatMl);
+        let specials = tok.chat_template.special_tokens();
+        assert_eq!(
+            tok.encode_with_special("<|im_start|>hi<|im_end|>\n", specials),
+            vec![3, 7, 4, 8]
+        );
+        assert_eq!(tok.tokens_for("hi"), vec![7, 9]);
+        assert_eq!(tok.tokens_for("ih"), vec![6, 5]);
+
+        // Without the specials, markers are just text
+        assert!(!tok.encode("<|im_start|>").contains(&3));
+        assert_eq!(tok.stop_ids(), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_spm_merges_by_score_with_byte_fallback() {
+        let tok = scored(
+            "llama",
+            &[
+                ("<0x0A>", 0.0, 6), ("<0xC3>", 0.0, 6), ("<0xA9>", 0.0, 6), // 3-5
+                ("\u{2581}", -6.0, 1), ("h", -6.0, 1), ("e", -6.0, 1), ("l", -6.0, 1), ("o", -6.0, 1), // 6-10
+                ("\u{2581}h", -5.0, 1), ("ll", -3.0, 1), ("\u{2581}he", -4.0, 1), ("llo", -2.0, 1), // 11-14
+                ("\u{2581}hello", -1.0, 1), ("a", -6.0, 1), ("aa", -2.0, 1), ("<tool>", 0.0, 4), // 15-18
+            ],
+            &[],
+        );
+        assert_eq!(tok.model, TokenizerModel::Spm);
+        // ▁ h e l l o: ll, llo, ▁h, ▁he, then ▁hello; "\n" and "é" fall back to bytes
+        assert_eq!(tok.encode("hello\né"), vec![15, 3, 4, 5]);
+        // Equal scores merge leftmost first
+        assert_eq!(tok.encode("aaa"), vec![6, 17, 16]);
+        // Added tokens are split out of plain text (a space is prefixed
+        // after them); control tokens are not
+        assert_eq!(tok.encode("hello<tool>hello"), vec![15, 18, 15]);
+        assert!(!tok.encode("<s>").contains(&1));
+
+        for text in ["hello\né", "aaa  hello"] {
+            assert_eq!(tok.decode(&tok.encode(text)), text);
+        }
+        // As in llama.cpp, the space prefixed after an added token stays
+        assert_eq!(tok.decode(&tok.encode("hello<tool>hello")), "hello<tool> hello");
+        assert_eq!(tok.decode(&[15, 2, 15]), "hello hello");
+        assert!(!tok.is_special(18) && tok.is_special(2));
+    }
+
+    #[test]
+    fn test_unigram_picks_best_total_score() {
+        let entries = [
+            ("\u{2581}", -1.0, 1), ("a", -2.0, 1), ("b", -2.0, 1), ("c", -2.0, 1), // 3-6
+            ("ab", -1.0, 1), ("\u{2581}a", -1.5, 1), ("bc", -1.0, 1), ("\u{2581}ab", -5.0, 1), // 7-10
+        ];
+        let tok = scored("t5", &entries, &[]);
+        assert_eq!(tok.model, TokenizerModel::Unigram);
+        // [▁a, bc] = -2.5 beats the longest-first [▁ab, c] = -7
+        assert_eq!(tok.encode("abc"), vec![8, 9]);
+        // Uncovered characters are UNK, one per run
+        assert_eq!(tok.encode("axxb"), vec![8, 0, 5]);
+        assert_eq!(tok.encode("a b"), vec![8, 3, 5]);
+        assert_eq!(tok.decode(&tok.encode("abc a")), "abc a");
+
+        let collapse = scored(
+            "t5",
+            &entries,
+            &[("tokenizer.ggml.remove_extra_whitespaces", GgufValue::Bool(true))],
+        );
+        assert_eq!(collapse.normalize("  a   b "), "\u{2581}a\u{2581}b");
+        assert_eq!(tok.normalize(" a"), "\u{2581}\u{2581}a");
     }
 }
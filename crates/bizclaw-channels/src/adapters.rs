@@ -1,4 +1,5 @@
 //! Multi-channel adapter stubs for additional messaging platforms.
+//! They are registered by type name in [`crate::registry`].
 //!
 //! Each channel follows the same `Channel` trait pattern as existing channels.
 //! These provide the configuration + parsing layer — actual API integration
@@ -55,6 +56,7 @@ impl LineChannel {
                         },
                         timestamp: chrono::Utc::now(),
                         reply_to: event["replyToken"].as_str().map(String::from),
+                        images: vec![],
                     });
                 }
             }
@@ -132,6 +134,7 @@ impl TeamsChannel {
             },
             timestamp: chrono::Utc::now(),
             reply_to: payload["replyToId"].as_str().map(String::from),
+            images: vec![],
         })
     }
 }
@@ -333,6 +336,7 @@ impl MessengerChannel {
                                 thread_type: ThreadType::Direct,
                                 timestamp: chrono::Utc::now(),
                                 reply_to: None,
+                                images: vec![],
                             });
                         }
                     }
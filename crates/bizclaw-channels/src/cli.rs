@@ -63,6 +63,7 @@ impl Channel for CliChannel {
                             thread_type: ThreadType::Direct,
                             timestamp: chrono::Utc::now(),
                             reply_to: None,
+                            images: vec![],
                         };
                     }
                     Ok(None) => break,
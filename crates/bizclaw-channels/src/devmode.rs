@@ -0,0 +1,236 @@
+//! Dev-mode inbound — receive channel events on a laptop behind NAT.
+//!
+//! ⚠️ DEV ONLY. Nothing here is meant for production deployments:
+//! - Discord already pushes events over an outbound Gateway WebSocket.
+//! - Slack falls back to polling `conversations.history` (no public URL needed).
+//! - Webhook-only channels get a temporary public URL via the `[tunnel]` config.
+
+use bizclaw_core::config::TunnelConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::tunnel::Tunnel;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Gateway routes that external platforms must reach to deliver events.
+pub const WEBHOOK_ROUTES: &[(&str, &str)] = &[
+    ("whatsapp", "/api/v1/webhook/whatsapp"),
+    ("webhook", "/api/v1/webhook/inbound"),
+];
+
+/// How a channel receives messages while running in dev mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevInbound {
+    /// Outbound persistent connection (Discord Gateway) — works behind NAT as-is.
+    Gateway,
+    /// Periodic polling shim (Slack history, Telegram getUpdates).
+    Polling,
+    /// Platform pushes webhooks — needs a tunnel.
+    Tunnel,
+}
+
+/// Pick the dev-mode inbound strategy for a channel.
+pub fn inbound_strategy(channel: &str) -> Option<DevInbound> {
+    match channel {
+        "discord" => Some(DevInbound::Gateway),
+        "slack" | "telegram" => Some(DevInbound::Polling),
+        "whatsapp" | "webhook" => Some(DevInbound::Tunnel),
+        _ => None,
+    }
+}
+
+/// Temporary tunnel exposing the local gateway — built from `[tunnel]` config.
+pub struct DevTunnel {
+    provider: String,
+    local_port: u16,
+    public_url: Option<String>,
+    child: Option<tokio::process::Child>,
+}
+
+impl DevTunnel {
+    /// Build a tunnel from config. `manual` needs `public_url`; `cloudflared`
+    /// and `ngrok` resolve their URL once [`DevTunnel::start`] is called.
+    pub fn from_config(config: &TunnelConfig, local_port: u16) -> Result<Self> {
+        let public_url = match config.provider.as_str() {
+            "manual" => {
+                if config.public_url.is_empty() {
+                    return Err(BizClawError::Config(
+                        "tunnel.public_url is required for the 'manual' tunnel provider".into(),
+                    ));
+                }
+                Some(config.public_url.trim_end_matches('/').to_string())
+            }
+            "cloudflared" | "ngrok" => None,
+            "none" | "" => {
+                return Err(BizClawError::Config(
+                    "No tunnel configured — set [tunnel] provider to manual, cloudflared or ngrok"
+                        .into(),
+                ));
+            }
+            other => {
+                return Err(BizClawError::Config(format!(
+                    "Unknown tunnel provider: {other}"
+                )));
+            }
+        };
+
+        Ok(Self {
+            provider: config.provider.clone(),
+            local_port,
+            public_url,
+            child: None,
+        })
+    }
+
+    /// Spawn the tunnel process (if any) and wait for its public URL.
+    pub async fn start(&mut self) -> Result<&str> {
+        if self.public_url.is_none() {
+            let local = format!("http://localhost:{}", self.local_port);
+            let mut cmd = match self.provider.as_str() {
+                "cloudflared" => {
+                    let mut c = tokio::process::Command::new("cloudflared");
+                    c.args(["tunnel", "--url", &local]);
+                    c
+                }
+                _ => {
+                    let mut c = tokio::process::Command::new("ngrok");
+                    c.args(["http", &self.local_port.to_string(), "--log", "stderr"]);
+                    c
+                }
+            };
+            let mut child = cmd
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| {
+                    BizClawError::Channel(format!("Failed to start {}: {e}", self.provider))
+                })?;
+
+            let stderr = child
+                .stderr
+                .take()
+                .ok_or_else(|| BizClawError::Channel("Tunnel stderr unavailable".into()))?;
+            let mut lines = BufReader::new(stderr).lines();
+            let url = tokio::time::timeout(std::time::Duration::from_secs(30), async {
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(url) = extract_public_url(&self.provider, &line) {
+                        return Some(url);
+                    }
+                }
+                None
+            })
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(|| {
+                BizClawError::Timeout(format!("{} did not report a public URL", self.provider))
+            })?;
+
+            tracing::warn!("⚠️ Dev tunnel ({}) open: {url} — do not use in production", self.provider);
+            self.public_url = Some(url);
+            self.child = Some(child);
+        }
+        Ok(self.public_url.as_deref().unwrap_or_default())
+    }
+
+    /// Public URL for every webhook route, keyed by channel name.
+    pub fn webhook_urls(&self) -> Vec<(String, String)> {
+        let Some(base) = self.public_url.as_deref() else {
+            return vec![];
+        };
+        WEBHOOK_ROUTES
+            .iter()
+            .map(|(channel, path)| (channel.to_string(), format!("{base}{path}")))
+            .collect()
+    }
+
+    /// Stop the tunnel process.
+    pub async fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill().await;
+        }
+    }
+}
+
+impl Tunnel for DevTunnel {
+    fn name(&self) -> &str {
+        &self.provider
+    }
+
+    fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+}
+
+/// Extract the public URL from a line of tunnel process output.
+fn extract_public_url(provider: &str, line: &str) -> Option<String> {
+    let marker = match provider {
+        "cloudflared" => ".trycloudflare.com",
+        _ => "url=https://",
+    };
+    if !line.contains(marker) {
+        return None;
+    }
+    let start = line.find("https://")?;
+    let url: String = line[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '"' && *c != '|')
+        .collect();
+    Some(url.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_tunnel_url_mapping() {
+        let config = TunnelConfig {
+            provider: "manual".into(),
+            public_url: "https://dev-box.example.com/".into(),
+        };
+        let tunnel = DevTunnel::from_config(&config, 3000).unwrap();
+        assert_eq!(tunnel.public_url(), Some("https://dev-box.example.com"));
+        let urls = tunnel.webhook_urls();
+        assert!(urls.contains(&(
+            "whatsapp".into(),
+            "https://dev-box.example.com/api/v1/webhook/whatsapp".into()
+        )));
+        assert!(urls.contains(&(
+            "webhook".into(),
+            "https://dev-box.example.com/api/v1/webhook/inbound".into()
+        )));
+    }
+
+    #[test]
+    fn test_tunnel_config_errors() {
+        assert!(DevTunnel::from_config(&TunnelConfig::default(), 3000).is_err());
+        let manual_without_url = TunnelConfig {
+            provider: "manual".into(),
+            public_url: String::new(),
+        };
+        assert!(DevTunnel::from_config(&manual_without_url, 3000).is_err());
+    }
+
+    #[test]
+    fn test_extract_public_url() {
+        let line = "2024-01-01T00:00:00Z INF |  https://quiet-fox-12.trycloudflare.com  |";
+        assert_eq!(
+            extract_public_url("cloudflared", line).as_deref(),
+            Some("https://quiet-fox-12.trycloudflare.com")
+        );
+        let line = r#"t=2024 lvl=info msg="started tunnel" url=https://ab12.ngrok-free.app"#;
+        assert_eq!(
+            extract_public_url("ngrok", line).as_deref(),
+            Some("https://ab12.ngrok-free.app")
+        );
+        assert!(extract_public_url("ngrok", "lvl=info msg=starting").is_none());
+    }
+
+    #[test]
+    fn test_inbound_strategy() {
+        assert_eq!(inbound_strategy("discord"), Some(DevInbound::Gateway));
+        assert_eq!(inbound_strategy("slack"), Some(DevInbound::Polling));
+        assert_eq!(inbound_strategy("whatsapp"), Some(DevInbound::Tunnel));
+        assert_eq!(inbound_strategy("cli"), None);
+    }
+}
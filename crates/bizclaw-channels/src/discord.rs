@@ -1,7 +1,8 @@
 //! Discord Bot channel — REST API + Gateway WebSocket.
 //!
-//! Connects to Discord Gateway for real-time events (messages, reactions, etc.)
-//! and uses REST API for sending messages.
+//! Connects to Discord Gateway for real-time events (messages, threads,
+//! slash commands) and uses REST API for sending messages, opening threads
+//! and answering slash commands.
 
 use async_trait::async_trait;
 use bizclaw_core::error::{BizClawError, Result};
@@ -9,6 +10,7 @@ use bizclaw_core::traits::Channel;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -31,6 +33,18 @@ fn default_intents() -> u64 {
     (1 << 0) | (1 << 9) | (1 << 12) | (1 << 15)
 }
 
+impl DiscordConfig {
+    /// Enabled bot with the default intents (guilds, guild and direct
+    /// messages, message content).
+    pub fn new(bot_token: impl Into<String>) -> Self {
+        Self {
+            bot_token: bot_token.into(),
+            enabled: true,
+            intents: default_intents(),
+        }
+    }
+}
+
 /// Discord Bot channel.
 pub struct DiscordChannel {
     config: DiscordConfig,
@@ -60,23 +74,26 @@ impl DiscordChannel {
         }
     }
 
-    /// Send a message to a channel.
+    /// Send a message to a channel, split into several if it's longer
+    /// than Discord allows.
     pub async fn send_message(&self, channel_id: &str, content: &str) -> Result<()> {
         let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
-        let body = serde_json::json!({ "content": content });
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| BizClawError::Channel(format!("Discord send failed: {e}")))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        for chunk in split_message(content, MAX_MESSAGE_LEN) {
+            let body = serde_json::json!({ "content": chunk });
+            let response = self
+                .client
+                .post(&url)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| BizClawError::Channel(format!("Discord send failed: {e}")))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+            }
         }
         Ok(())
     }
@@ -125,27 +142,55 @@ impl DiscordChannel {
     /// Start Gateway WebSocket connection — returns a stream of IncomingMessages.
     /// Auto-reconnects on disconnect with exponential backoff.
     pub fn start_gateway(self) -> DiscordGatewayStream {
+        DiscordGatewayStream {
+            rx: self.spawn_gateway(),
+        }
+    }
+
+    /// Like [`DiscordChannel::start_gateway`], also yielding slash-command
+    /// interactions and the message details needed to reply in threads.
+    pub fn start_gateway_events(self) -> DiscordEventStream {
+        DiscordEventStream {
+            rx: self.spawn_gateway(),
+        }
+    }
+
+    /// Run the Gateway connection until the receiver is dropped or the
+    /// token is rejected. Resumes the session after a disconnect when
+    /// Discord allows it, otherwise identifies again.
+    fn spawn_gateway(self) -> tokio::sync::mpsc::UnboundedReceiver<DiscordEvent> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         tokio::spawn(async move {
             let channel = self;
             let mut backoff_secs: u64 = 5;
+            // Kept across reconnects to resume the session
+            let mut session: Option<(String, String)> = None; // (session_id, resume_gateway_url)
+            let mut seq: Option<u64> = None;
+            let mut bot_id = String::new();
+            let mut threads: HashSet<String> = HashSet::new();
 
             // ═══ Reconnect loop ═══
             loop {
+                if tx.is_closed() {
+                    return;
+                }
                 tracing::info!("Discord Gateway connecting...");
 
-                // Get gateway URL
-                let gateway_url = match channel.get_gateway_url().await {
-                    Ok(url) => url,
-                    Err(e) => {
-                        tracing::error!(
-                            "Failed to get gateway URL: {e}, retrying in {backoff_secs}s..."
-                        );
-                        tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
-                        backoff_secs = (backoff_secs * 2).min(60);
-                        continue;
-                    }
+                // Resume URL, or a fresh gateway URL
+                let gateway_url = match &session {
+                    Some((_, resume_url)) => format!("{resume_url}/?v=10&encoding=json"),
+                    None => match channel.get_gateway_url().await {
+                        Ok(url) => url,
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to get gateway URL: {e}, retrying in {backoff_secs}s..."
+                            );
+                            tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
+                            backoff_secs = (backoff_secs * 2).min(60);
+                            continue;
+                        }
+                    },
                 };
 
                 // Connect WebSocket
@@ -156,6 +201,7 @@ impl DiscordChannel {
                         tracing::error!(
                             "Gateway WebSocket failed: {e}, retrying in {backoff_secs}s..."
                         );
+                        session = None;
                         tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
                         backoff_secs = (backoff_secs * 2).min(60);
                         continue;
@@ -169,9 +215,7 @@ impl DiscordChannel {
                 use futures::{SinkExt, StreamExt};
                 use tokio_tungstenite::tungstenite::Message as WsMsg;
 
-                let mut heartbeat_interval_ms: u64 = 41250;
-                let mut seq: Option<u64> = None;
-                let mut identified = false;
+                let mut heartbeat = heartbeat_timer(41250);
 
                 loop {
                     tokio::select! {
@@ -190,12 +234,21 @@ impl DiscordChannel {
 
                                     match op {
                                         10 => {
-                                            heartbeat_interval_ms = payload["d"]["heartbeat_interval"]
+                                            let interval_ms = payload["d"]["heartbeat_interval"]
                                                 .as_u64().unwrap_or(41250);
-                                            tracing::debug!("Gateway Hello: heartbeat={}ms", heartbeat_interval_ms);
+                                            tracing::debug!("Gateway Hello: heartbeat={}ms", interval_ms);
+                                            heartbeat = heartbeat_timer(interval_ms);
 
-                                            if !identified {
-                                                let identify = serde_json::json!({
+                                            let hello_reply = match (&session, seq) {
+                                                (Some((session_id, _)), Some(s)) => serde_json::json!({
+                                                    "op": 6,
+                                                    "d": {
+                                                        "token": channel.config.bot_token,
+                                                        "session_id": session_id,
+                                                        "seq": s
+                                                    }
+                                                }),
+                                                _ => serde_json::json!({
                                                     "op": 2,
                                                     "d": {
                                                         "token": channel.config.bot_token,
@@ -206,67 +259,97 @@ impl DiscordChannel {
                                                             "device": "bizclaw"
                                                         }
                                                     }
-                                                });
-                                                let _ = ws.send(WsMsg::Text(identify.to_string())).await;
-                                                identified = true;
-                                            }
+                                                }),
+                                            };
+                                            let _ = ws.send(WsMsg::Text(hello_reply.to_string())).await;
                                         }
                                         11 => { tracing::trace!("Heartbeat ACK"); }
+                                        1 => {
+                                            // Server asks for an immediate heartbeat
+                                            let beat = serde_json::json!({"op": 1, "d": seq});
+                                            let _ = ws.send(WsMsg::Text(beat.to_string())).await;
+                                        }
                                         0 => {
                                             let event_name = payload["t"].as_str().unwrap_or("");
-                                            match event_name {
+                                            let d = &payload["d"];
+                                            let event = match event_name {
                                                 "READY" => {
-                                                    let user = payload["d"]["user"]["username"]
+                                                    bot_id = d["user"]["id"].as_str().unwrap_or("").to_string();
+                                                    session = d["session_id"].as_str().zip(d["resume_gateway_url"].as_str())
+                                                        .map(|(id, url)| (id.to_string(), url.to_string()));
+                                                    let user = d["user"]["username"]
                                                         .as_str().unwrap_or("unknown");
                                                     tracing::info!("Discord Gateway READY as {user}");
+                                                    None
                                                 }
-                                                "MESSAGE_CREATE" => {
-                                                    let d = &payload["d"];
-                                                    if d["author"]["bot"].as_bool().unwrap_or(false) {
-                                                        continue;
+                                                "RESUMED" => {
+                                                    tracing::info!("Discord Gateway session resumed");
+                                                    None
+                                                }
+                                                "GUILD_CREATE" => {
+                                                    for thread in d["threads"].as_array().into_iter().flatten() {
+                                                        if let Some(id) = thread["id"].as_str() {
+                                                            threads.insert(id.to_string());
+                                                        }
                                                     }
-
-                                                    let msg = IncomingMessage {
-                                                        channel: "discord".into(),
-                                                        thread_id: d["channel_id"].as_str()
-                                                            .unwrap_or("").into(),
-                                                        sender_id: d["author"]["id"].as_str()
-                                                            .unwrap_or("").into(),
-                                                        sender_name: d["author"]["username"].as_str()
-                                                            .map(String::from),
-                                                        content: d["content"].as_str()
-                                                            .unwrap_or("").into(),
-                                                        thread_type: if d["guild_id"].is_null() {
-                                                            ThreadType::Direct
-                                                        } else {
-                                                            ThreadType::Group
-                                                        },
-                                                        timestamp: chrono::Utc::now(),
-                                                        reply_to: d["referenced_message"]["id"]
-                                                            .as_str().map(String::from),
-                                                    };
-
-                                                    if tx.send(msg).is_err() {
-                                                        tracing::info!("Discord stream closed (receiver dropped)");
-                                                        return; // Stop completely
+                                                    None
+                                                }
+                                                "THREAD_CREATE" | "THREAD_UPDATE" => {
+                                                    if let Some(id) = d["id"].as_str() {
+                                                        threads.insert(id.to_string());
+                                                    }
+                                                    None
+                                                }
+                                                "THREAD_DELETE" => {
+                                                    if let Some(id) = d["id"].as_str() {
+                                                        threads.remove(id);
                                                     }
+                                                    None
+                                                }
+                                                "MESSAGE_CREATE" => {
+                                                    parse_message(d, &bot_id, &threads).map(DiscordEvent::Message)
+                                                }
+                                                "INTERACTION_CREATE" => {
+                                                    parse_interaction(d).map(DiscordEvent::Interaction)
+                                                }
+                                                _ => {
+                                                    tracing::trace!("Ignoring event: {event_name}");
+                                                    None
                                                 }
-                                                _ => { tracing::trace!("Ignoring event: {event_name}"); }
+                                            };
+                                            if let Some(event) = event
+                                                && tx.send(event).is_err()
+                                            {
+                                                tracing::info!("Discord stream closed (receiver dropped)");
+                                                return; // Stop completely
                                             }
                                         }
                                         7 => {
                                             tracing::warn!("Gateway requesting reconnect");
-                                            break; // → outer reconnect loop
+                                            break; // → outer reconnect loop, resuming
                                         }
                                         9 => {
-                                            tracing::warn!("Invalid session, re-identifying");
-                                            identified = false;
+                                            // d = true: the session can still be resumed
+                                            if !payload["d"].as_bool().unwrap_or(false) {
+                                                tracing::warn!("Invalid session, re-identifying");
+                                                session = None;
+                                                seq = None;
+                                            }
+                                            break;
                                         }
                                         _ => {}
                                     }
                                 }
-                                Some(Ok(WsMsg::Close(_))) => {
-                                    tracing::warn!("Discord Gateway closed by server");
+                                Some(Ok(WsMsg::Close(frame))) => {
+                                    let code = frame.as_ref().map(|f| u16::from(f.code)).unwrap_or(0);
+                                    if FATAL_CLOSE_CODES.contains(&code) {
+                                        tracing::error!(
+                                            "Discord Gateway closed with {code}: {} — not reconnecting",
+                                            frame.map(|f| f.reason.to_string()).unwrap_or_default()
+                                        );
+                                        return;
+                                    }
+                                    tracing::warn!("Discord Gateway closed by server ({code})");
                                     break; // → reconnect
                                 }
                                 Some(Err(e)) => {
@@ -277,12 +360,16 @@ impl DiscordChannel {
                                 _ => {}
                             }
                         }
-                        _ = tokio::time::sleep(tokio::time::Duration::from_millis(heartbeat_interval_ms)) => {
-                            let heartbeat = serde_json::json!({
+                        _ = heartbeat.tick() => {
+                            if tx.is_closed() {
+                                tracing::info!("Discord stream closed (receiver dropped)");
+                                return;
+                            }
+                            let beat = serde_json::json!({
                                 "op": 1,
                                 "d": seq,
                             });
-                            if ws.send(WsMsg::Text(heartbeat.to_string())).await.is_err() {
+                            if ws.send(WsMsg::Text(beat.to_string())).await.is_err() {
                                 tracing::error!("Heartbeat send failed");
                                 break; // → reconnect
                             }
@@ -298,25 +385,291 @@ impl DiscordChannel {
             } // end reconnect loop
         });
 
-        DiscordGatewayStream { rx }
+        rx
+    }
+
+    /// Register the bot's slash commands globally (replacing earlier ones).
+    pub async fn register_commands(&self, application_id: &str, commands: &[serde_json::Value]) -> Result<()> {
+        let url = format!("https://discord.com/api/v10/applications/{application_id}/commands");
+        let response = self
+            .client
+            .put(&url)
+            .json(commands)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Register commands failed: {e}")))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        }
+        Ok(())
+    }
+
+    /// Acknowledge a slash command; Discord shows "thinking…" until
+    /// [`DiscordChannel::edit_interaction_response`] sends the answer.
+    pub async fn defer_interaction(&self, interaction: &DiscordInteraction) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/interactions/{}/{}/callback",
+            interaction.id, interaction.token
+        );
+        self.client
+            .post(&url)
+            .json(&serde_json::json!({ "type": 5 }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Interaction callback failed: {e}")))?;
+        Ok(())
+    }
+
+    /// Replace the deferred "thinking…" reply of a slash command.
+    pub async fn edit_interaction_response(&self, interaction: &DiscordInteraction, content: &str) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/webhooks/{}/{}/messages/@original",
+            interaction.application_id, interaction.token
+        );
+        let content = split_message(content, MAX_MESSAGE_LEN).into_iter().next().unwrap_or_default();
+        let response = self
+            .client
+            .patch(&url)
+            .json(&serde_json::json!({ "content": content }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Interaction reply failed: {e}")))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        }
+        Ok(())
+    }
+
+    /// Start a public thread on a message. Returns the thread's channel ID.
+    pub async fn create_thread(&self, channel_id: &str, message_id: &str, name: &str) -> Result<String> {
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages/{message_id}/threads");
+        let name: String = name.chars().take(100).collect();
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "name": name, "auto_archive_duration": 1440 }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Create thread failed: {e}")))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Invalid response: {e}")))?;
+        body["id"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| BizClawError::Channel("No thread ID".into()))
+    }
+}
+
+/// Close codes after which reconnecting can't help (bad token, intents).
+const FATAL_CLOSE_CODES: &[u16] = &[4004, 4010, 4011, 4012, 4013, 4014];
+
+/// Longest message Discord accepts.
+pub const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Heartbeat timer whose first tick is one interval away.
+fn heartbeat_timer(interval_ms: u64) -> tokio::time::Interval {
+    let period = tokio::time::Duration::from_millis(interval_ms.max(1000));
+    tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+}
+
+/// Split `text` into chunks of at most `max` characters, preferring to
+/// break at newlines.
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.chars().count() > max {
+        let limit = rest.char_indices().nth(max).map_or(rest.len(), |(i, _)| i);
+        let cut = rest[..limit].rfind('\n').filter(|&i| i > 0).unwrap_or(limit);
+        chunks.push(rest[..cut].to_string());
+        rest = rest[cut..].trim_start_matches('\n');
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+/// Slash commands registered for every bot: `/ask` and `/new`.
+pub fn slash_commands() -> Vec<serde_json::Value> {
+    vec![
+        serde_json::json!({
+            "name": "ask",
+            "description": "Ask the agent",
+            "type": 1,
+            "options": [{"type": 3, "name": "prompt", "description": "What to ask", "required": true}]
+        }),
+        serde_json::json!({
+            "name": "new",
+            "description": "Start a new conversation",
+            "type": 1
+        }),
+    ]
+}
+
+/// Something the bot received over the Gateway.
+#[derive(Debug, Clone)]
+pub enum DiscordEvent {
+    Message(DiscordIncoming),
+    Interaction(DiscordInteraction),
+}
+
+/// A user message, with what's needed to reply in a thread.
+#[derive(Debug, Clone)]
+pub struct DiscordIncoming {
+    /// `thread_id` is the channel (or thread) the message was posted in;
+    /// mentions of the bot are stripped from `content`.
+    pub message: IncomingMessage,
+    pub message_id: String,
+    pub guild_id: Option<String>,
+    /// Posted inside a thread.
+    pub in_thread: bool,
+    /// The message mentions the bot.
+    pub mentions_bot: bool,
+}
+
+/// A slash command invocation.
+#[derive(Debug, Clone)]
+pub struct DiscordInteraction {
+    pub id: String,
+    pub token: String,
+    pub application_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+    pub username: String,
+    /// Command name, e.g. "ask".
+    pub command: String,
+    /// Option name → value.
+    pub options: Vec<(String, String)>,
+}
+
+impl DiscordInteraction {
+    /// Value of option `name`.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
     }
 }
 
+/// Parse a MESSAGE_CREATE payload. Messages from bots are skipped.
+pub fn parse_message(d: &serde_json::Value, bot_id: &str, threads: &HashSet<String>) -> Option<DiscordIncoming> {
+    if d["author"]["bot"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let channel_id = d["channel_id"].as_str()?;
+    let mentions_bot = !bot_id.is_empty()
+        && d["mentions"]
+            .as_array()
+            .is_some_and(|users| users.iter().any(|u| u["id"].as_str() == Some(bot_id)));
+    let mut content = d["content"].as_str().unwrap_or("").to_string();
+    if !bot_id.is_empty() {
+        content = content
+            .replace(&format!("<@{bot_id}>"), "")
+            .replace(&format!("<@!{bot_id}>"), "");
+    }
+    let guild_id = d["guild_id"].as_str().map(String::from);
+
+    Some(DiscordIncoming {
+        message: IncomingMessage {
+            channel: "discord".into(),
+            thread_id: channel_id.into(),
+            sender_id: d["author"]["id"].as_str().unwrap_or("").into(),
+            sender_name: d["author"]["username"].as_str().map(String::from),
+            content: content.trim().to_string(),
+            thread_type: if guild_id.is_none() {
+                ThreadType::Direct
+            } else {
+                ThreadType::Group
+            },
+            timestamp: chrono::Utc::now(),
+            reply_to: d["referenced_message"]["id"].as_str().map(String::from),
+            images: vec![],
+        },
+        message_id: d["id"].as_str().unwrap_or("").into(),
+        guild_id,
+        in_thread: threads.contains(channel_id),
+        mentions_bot,
+    })
+}
+
+/// Parse an INTERACTION_CREATE payload. Only slash commands are returned.
+pub fn parse_interaction(d: &serde_json::Value) -> Option<DiscordInteraction> {
+    // 2 = APPLICATION_COMMAND
+    if d["type"].as_u64() != Some(2) {
+        return None;
+    }
+    // Guild invocations carry `member.user`, DMs carry `user`
+    let user = if d["member"]["user"].is_object() {
+        &d["member"]["user"]
+    } else {
+        &d["user"]
+    };
+    let options = d["data"]["options"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|o| {
+            let value = o["value"].as_str().map(String::from).unwrap_or_else(|| o["value"].to_string());
+            Some((o["name"].as_str()?.to_string(), value))
+        })
+        .collect();
+    Some(DiscordInteraction {
+        id: d["id"].as_str()?.into(),
+        token: d["token"].as_str()?.into(),
+        application_id: d["application_id"].as_str().unwrap_or("").into(),
+        channel_id: d["channel_id"].as_str().unwrap_or("").into(),
+        user_id: user["id"].as_str().unwrap_or("").into(),
+        username: user["username"].as_str().unwrap_or("").into(),
+        command: d["data"]["name"].as_str()?.into(),
+        options,
+    })
+}
+
 /// Stream of incoming Discord messages from Gateway.
 pub struct DiscordGatewayStream {
-    rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
+    rx: tokio::sync::mpsc::UnboundedReceiver<DiscordEvent>,
 }
 
 impl Stream for DiscordGatewayStream {
     type Item = IncomingMessage;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.rx.poll_recv(cx)
+        loop {
+            match std::task::ready!(self.rx.poll_recv(cx)) {
+                Some(DiscordEvent::Message(incoming)) => return Poll::Ready(Some(incoming.message)),
+                Some(DiscordEvent::Interaction(_)) => continue,
+                None => return Poll::Ready(None),
+            }
+        }
     }
 }
 
 impl Unpin for DiscordGatewayStream {}
 
+/// Stream of Discord messages and slash commands from Gateway.
+pub struct DiscordEventStream {
+    rx: tokio::sync::mpsc::UnboundedReceiver<DiscordEvent>,
+}
+
+impl Stream for DiscordEventStream {
+    type Item = DiscordEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Unpin for DiscordEventStream {}
+
 #[async_trait]
 impl Channel for DiscordChannel {
     fn name(&self) -> &str {
@@ -371,3 +724,62 @@ pub struct DiscordMessage {
     pub content: String,
     pub guild_id: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_strips_mention() {
+        let threads = HashSet::from(["t1".to_string()]);
+        let d = serde_json::json!({
+            "id": "m1",
+            "channel_id": "c1",
+            "guild_id": "g1",
+            "content": "<@42> what's new?",
+            "author": {"id": "u1", "username": "ann"},
+            "mentions": [{"id": "42"}]
+        });
+        let incoming = parse_message(&d, "42", &threads).unwrap();
+        assert!(incoming.mentions_bot);
+        assert!(!incoming.in_thread);
+        assert_eq!(incoming.message.content, "what's new?");
+        assert_eq!(incoming.message.thread_type, ThreadType::Group);
+
+        let in_thread = serde_json::json!({"id": "m2", "channel_id": "t1", "content": "hi", "author": {"id": "u1"}});
+        assert!(parse_message(&in_thread, "42", &threads).unwrap().in_thread);
+        let from_bot = serde_json::json!({"channel_id": "c1", "content": "x", "author": {"id": "9", "bot": true}});
+        assert!(parse_message(&from_bot, "42", &threads).is_none());
+    }
+
+    #[test]
+    fn test_parse_slash_command() {
+        let d = serde_json::json!({
+            "type": 2,
+            "id": "i1",
+            "token": "tok",
+            "application_id": "42",
+            "channel_id": "c1",
+            "member": {"user": {"id": "u1", "username": "ann"}},
+            "data": {"name": "ask", "options": [{"name": "prompt", "type": 3, "value": "status?"}]}
+        });
+        let interaction = parse_interaction(&d).unwrap();
+        assert_eq!(interaction.command, "ask");
+        assert_eq!(interaction.option("prompt"), Some("status?"));
+        assert_eq!(interaction.username, "ann");
+        // Button presses and other interaction types are ignored
+        assert!(parse_interaction(&serde_json::json!({"type": 3, "id": "i2", "token": "t"})).is_none());
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 2000), vec!["short"]);
+        let long = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+        let chunks = split_message(&long, 2000);
+        assert_eq!(chunks, vec!["a".repeat(1500), "b".repeat(1500)]);
+        let unbroken = "é".repeat(4500);
+        let chunks = split_message(&unbroken, 2000);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 2000));
+    }
+}
@@ -1,8 +1,13 @@
-//! Email Channel — async IMAP polling + SMTP sending.
+//! Email Channel — async IMAP listener + SMTP sending.
 //!
 //! Reads emails via async-imap (native async), routes them to the AI agent,
 //! and sends replies via SMTP (async lettre). Supports Gmail, Outlook, custom
 //! servers.
+//!
+//! The listener waits with IMAP IDLE when the server supports it and polls
+//! otherwise. Emails are threaded by Message-ID / References: the
+//! `thread_id` of an incoming message is the thread's first Message-ID, and
+//! replies to that thread keep its subject and References chain.
 
 use async_trait::async_trait;
 use bizclaw_core::error::{BizClawError, Result};
@@ -10,6 +15,7 @@ use bizclaw_core::traits::Channel;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
@@ -55,6 +61,9 @@ fn default_true() -> bool {
     true
 }
 
+/// Re-issue IDLE before servers drop it (RFC 2177 allows 29 minutes).
+const IDLE_TIMEOUT_SECS: u64 = 25 * 60;
+
 impl Default for EmailConfig {
     fn default() -> Self {
         Self {
@@ -83,11 +92,67 @@ pub struct ParsedEmail {
     pub subject: String,
     pub body_text: String,
     pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    /// Message-IDs of the earlier emails in the thread, oldest first.
+    pub references: Vec<String>,
+}
+
+impl ParsedEmail {
+    /// The thread this email belongs to: the first Message-ID in
+    /// References, else In-Reply-To, else its own Message-ID.
+    pub fn thread_id(&self) -> String {
+        self.references
+            .first()
+            .or(self.in_reply_to.as_ref())
+            .or(self.message_id.as_ref())
+            .cloned()
+            .unwrap_or_else(|| self.from.clone())
+    }
+
+    /// References for a reply: this email's References plus its Message-ID.
+    pub fn reply_references(&self) -> Vec<String> {
+        let mut refs = self.references.clone();
+        if refs.is_empty()
+            && let Some(parent) = &self.in_reply_to
+        {
+            refs.push(parent.clone());
+        }
+        if let Some(id) = &self.message_id
+            && !refs.contains(id)
+        {
+            refs.push(id.clone());
+        }
+        refs
+    }
+
+    pub fn to_incoming(&self) -> IncomingMessage {
+        IncomingMessage {
+            channel: "email".into(),
+            thread_id: self.thread_id(),
+            sender_id: self.from.clone(),
+            sender_name: self.from_name.clone(),
+            content: format!("📧 Subject: {}\n\n{}", self.subject, self.body_text),
+            thread_type: ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: self.message_id.clone(),
+            images: vec![],
+        }
+    }
+}
+
+/// What's needed to reply inside an email thread.
+#[derive(Debug, Clone)]
+struct EmailThread {
+    address: String,
+    subject: String,
+    references: Vec<String>,
 }
 
 /// Type alias for the TLS IMAP stream used throughout this module.
 type ImapTlsStream =
     async_imap::Client<tokio_native_tls::TlsStream<tokio::net::TcpStream>>;
+type ImapSession =
+    async_imap::Session<tokio_native_tls::TlsStream<tokio::net::TcpStream>>;
 
 /// Create TLS-wrapped IMAP connection (async, tokio-native).
 async fn connect_imap_tls(
@@ -112,11 +177,14 @@ async fn connect_imap_tls(
     Ok(async_imap::Client::new(tls_stream))
 }
 
-/// Email channel — async IMAP reading + SMTP sending.
+/// Email channel — async IMAP reading + SMTP sending. Clones share the
+/// known threads, so a clone can reply to mail the listener received.
+#[derive(Clone)]
 pub struct EmailChannel {
     config: EmailConfig,
     connected: bool,
     last_seen_uid: Arc<Mutex<u32>>,
+    threads: Arc<Mutex<HashMap<String, EmailThread>>>,
 }
 
 impl EmailChannel {
@@ -125,9 +193,15 @@ impl EmailChannel {
             config,
             connected: false,
             last_seen_uid: Arc::new(Mutex::new(0)),
+            threads: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// The mailbox address this channel reads and sends as.
+    pub fn address(&self) -> &str {
+        &self.config.email
+    }
+
     /// Fetch unread emails (async IMAP).
     pub async fn fetch_unread(&self) -> Result<Vec<ParsedEmail>> {
         imap_fetch_async(
@@ -143,6 +217,31 @@ impl EmailChannel {
         .await
     }
 
+    /// Reply inside a thread the listener received mail in.
+    pub async fn reply(&self, thread_id: &str, body: &str) -> Result<()> {
+        let thread = self
+            .threads
+            .lock()
+            .unwrap()
+            .get(thread_id)
+            .cloned()
+            .ok_or_else(|| BizClawError::Channel(format!("Unknown email thread: {thread_id}")))?;
+        self.deliver(&thread.address, &reply_subject(&thread.subject), body, &thread.references)
+            .await
+    }
+
+    /// Remember how to reply to the thread of `email`.
+    fn remember(&self, email: &ParsedEmail) {
+        self.threads.lock().unwrap().insert(
+            email.thread_id(),
+            EmailThread {
+                address: email.from.clone(),
+                subject: email.subject.clone(),
+                references: email.reply_references(),
+            },
+        );
+    }
+
     /// Send email via SMTP (async).
     pub async fn send_email(
         &self,
@@ -151,6 +250,13 @@ impl EmailChannel {
         body: &str,
         in_reply_to: Option<&str>,
     ) -> Result<()> {
+        let references: Vec<String> = in_reply_to.into_iter().map(String::from).collect();
+        self.deliver(to, subject, body, &references).await
+    }
+
+    /// Send an email; `references` is the thread so far, its last entry
+    /// becomes In-Reply-To.
+    async fn deliver(&self, to: &str, subject: &str, body: &str, references: &[String]) -> Result<()> {
         use lettre::{
             AsyncSmtpTransport, AsyncTransport, Message as LettreMessage, message::Mailbox,
             message::header::ContentType, transport::smtp::authentication::Credentials,
@@ -171,8 +277,10 @@ impl EmailChannel {
             .subject(subject)
             .header(ContentType::TEXT_PLAIN);
 
-        if let Some(reply_id) = in_reply_to {
-            builder = builder.in_reply_to(reply_id.to_string());
+        if let Some(reply_id) = references.last() {
+            builder = builder
+                .in_reply_to(angle_addr(reply_id))
+                .references(references.iter().map(|r| angle_addr(r)).collect::<Vec<_>>().join(" "));
         }
 
         let email = builder
@@ -197,46 +305,133 @@ impl EmailChannel {
         Ok(())
     }
 
-    /// Start IMAP polling loop — returns a stream of IncomingMessages.
+    /// Start the inbox listener — returns a stream of IncomingMessages.
     pub fn start_polling(self) -> EmailPollingStream {
+        self.start_listener()
+    }
+
+    /// Start the inbox listener: IMAP IDLE when the server supports it,
+    /// otherwise polling every `poll_interval_secs`. Reconnects with
+    /// backoff after errors; stops when the stream is dropped. Use a clone
+    /// of this channel to [`EmailChannel::reply`].
+    pub fn start_listener(&self) -> EmailPollingStream {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let config = self.config.clone();
-        let last_seen = self.last_seen_uid.clone();
+        let mut ch = self.clone();
+        ch.connected = true;
 
         tokio::spawn(async move {
-            let ch = EmailChannel {
-                config,
-                connected: true,
-                last_seen_uid: last_seen,
-            };
+            let mut backoff_secs: u64 = 5;
             loop {
-                match ch.fetch_unread().await {
-                    Ok(emails) => {
-                        for em in emails {
-                            let incoming = IncomingMessage {
-                                channel: "email".into(),
-                                thread_id: em.from.clone(),
-                                sender_id: em.from.clone(),
-                                sender_name: em.from_name.clone(),
-                                content: format!("📧 Subject: {}\n\n{}", em.subject, em.body_text),
-                                thread_type: ThreadType::Direct,
-                                timestamp: chrono::Utc::now(),
-                                reply_to: em.message_id,
-                            };
-                            if tx.send(incoming).is_err() {
-                                return;
-                            }
+                match ch.listen_session(&tx).await {
+                    Ok(()) => return, // receiver dropped
+                    Err(e) => {
+                        tracing::error!("IMAP listener ({}): {e}, retrying in {backoff_secs}s...", ch.config.email);
+                        tokio::select! {
+                            _ = tokio::time::sleep(std::time::Duration::from_secs(backoff_secs)) => {}
+                            _ = tx.closed() => return,
                         }
+                        backoff_secs = (backoff_secs * 2).min(300);
                     }
-                    Err(e) => tracing::error!("IMAP poll: {e}"),
                 }
-                tokio::time::sleep(std::time::Duration::from_secs(ch.config.poll_interval_secs))
-                    .await;
             }
         });
 
         EmailPollingStream { rx }
     }
+
+    /// One IMAP session: deliver new mail, then wait for more. Returns
+    /// `Ok` once the receiver is gone.
+    async fn listen_session(
+        &self,
+        tx: &tokio::sync::mpsc::UnboundedSender<IncomingMessage>,
+    ) -> Result<()> {
+        let mut session = open_session(
+            &self.config.imap_host,
+            self.config.imap_port,
+            &self.config.email,
+            &self.config.password,
+            &self.config.mailbox,
+        )
+        .await?;
+        let idle = session
+            .capabilities()
+            .await
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false);
+        tracing::info!(
+            "📧 Listening to {} ({})",
+            self.config.email,
+            if idle { "IMAP IDLE" } else { "polling" }
+        );
+
+        loop {
+            let emails = fetch_new(
+                &mut session,
+                self.config.unread_only,
+                self.config.mark_as_read,
+                &self.last_seen_uid,
+            )
+            .await?;
+            for em in emails {
+                self.remember(&em);
+                if tx.send(em.to_incoming()).is_err() {
+                    session.logout().await.ok();
+                    return Ok(());
+                }
+            }
+
+            if idle {
+                let mut handle = session.idle();
+                handle
+                    .init()
+                    .await
+                    .map_err(|e| BizClawError::Channel(format!("IDLE: {e}")))?;
+                let (wait, interrupt) =
+                    handle.wait_with_timeout(std::time::Duration::from_secs(IDLE_TIMEOUT_SECS));
+                tokio::select! {
+                    result = wait => {
+                        result.map_err(|e| BizClawError::Channel(format!("IDLE: {e}")))?;
+                    }
+                    _ = tx.closed() => return Ok(()),
+                }
+                drop(interrupt);
+                session = handle
+                    .done()
+                    .await
+                    .map_err(|e| BizClawError::Channel(format!("IDLE done: {e}")))?;
+            } else {
+                tokio::select! {
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(self.config.poll_interval_secs.max(5))) => {}
+                    _ = tx.closed() => {
+                        session.logout().await.ok();
+                        return Ok(());
+                    }
+                }
+                session
+                    .noop()
+                    .await
+                    .map_err(|e| BizClawError::Channel(format!("NOOP: {e}")))?;
+            }
+        }
+    }
+}
+
+/// `Re: ` + subject, unless it already is a reply.
+fn reply_subject(subject: &str) -> String {
+    if subject.to_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {subject}")
+    }
+}
+
+/// Message-IDs are written in angle brackets; mail-parser strips them.
+fn angle_addr(id: &str) -> String {
+    if id.starts_with('<') {
+        id.to_string()
+    } else {
+        format!("<{id}>")
+    }
 }
 
 /// Stream of incoming email messages.
@@ -281,6 +476,10 @@ impl Channel for EmailChannel {
     }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        // Known thread: reply inside it
+        if self.threads.lock().unwrap().contains_key(&message.thread_id) {
+            return self.reply(&message.thread_id, &message.content).await;
+        }
         let subject = message
             .reply_to
             .as_deref()
@@ -312,8 +511,20 @@ async fn imap_fetch_async(
     mark_as_read: bool,
     last_seen_uid: &Arc<Mutex<u32>>,
 ) -> Result<Vec<ParsedEmail>> {
-    use futures::StreamExt;
+    let mut session = open_session(host, port, email, password, mailbox).await?;
+    let emails = fetch_new(&mut session, unread_only, mark_as_read, last_seen_uid).await?;
+    session.logout().await.ok();
+    Ok(emails)
+}
 
+/// Log in and select `mailbox`.
+async fn open_session(
+    host: &str,
+    port: u16,
+    email: &str,
+    password: &str,
+    mailbox: &str,
+) -> Result<ImapSession> {
     let client = connect_imap_tls(host, port).await?;
     let mut session = client
         .login(email, password)
@@ -324,6 +535,17 @@ async fn imap_fetch_async(
         .select(mailbox)
         .await
         .map_err(|e| BizClawError::Channel(format!("Select: {e}")))?;
+    Ok(session)
+}
+
+/// Fetch emails newer than `last_seen_uid` in the selected mailbox.
+async fn fetch_new(
+    session: &mut ImapSession,
+    unread_only: bool,
+    mark_as_read: bool,
+    last_seen_uid: &Arc<Mutex<u32>>,
+) -> Result<Vec<ParsedEmail>> {
+    use futures::StreamExt;
 
     let search = if unread_only { "UNSEEN" } else { "ALL" };
     let uids = session
@@ -335,7 +557,6 @@ async fn imap_fetch_async(
     let new_uids: Vec<u32> = uids.into_iter().filter(|&u| u > last).collect();
 
     if new_uids.is_empty() {
-        session.logout().await.ok();
         return Ok(vec![]);
     }
 
@@ -375,7 +596,6 @@ async fn imap_fetch_async(
     }
 
     *last_seen_uid.lock().unwrap() = max_uid;
-    session.logout().await.ok();
     tracing::info!("📧 Fetched {} email(s)", emails.len());
     Ok(emails)
 }
@@ -410,6 +630,14 @@ fn parse_email_bytes(raw: &[u8], uid: u32) -> Option<ParsedEmail> {
         });
 
     let message_id = parsed.message_id().map(String::from);
+    let in_reply_to = parsed.in_reply_to().as_text().map(String::from);
+    let references = parsed
+        .references()
+        .as_text_list()
+        .unwrap_or_default()
+        .into_iter()
+        .map(String::from)
+        .collect();
 
     Some(ParsedEmail {
         uid,
@@ -418,6 +646,8 @@ fn parse_email_bytes(raw: &[u8], uid: u32) -> Option<ParsedEmail> {
         subject,
         body_text: body_text.chars().take(4000).collect(),
         message_id,
+        in_reply_to,
+        references,
     })
 }
 
@@ -434,3 +664,48 @@ fn strip_html(html: &str) -> String {
     }
     out.trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLY: &str = "From: Ann <ann@acme.test>\r\n\
+To: bot@bizclaw.test\r\n\
+Subject: Re: Invoice 42\r\n\
+Message-ID: <c@acme.test>\r\n\
+In-Reply-To: <b@bizclaw.test>\r\n\
+References: <a@acme.test> <b@bizclaw.test>\r\n\
+\r\n\
+Still unpaid?\r\n";
+
+    #[test]
+    fn test_thread_from_references() {
+        let email = parse_email_bytes(REPLY.as_bytes(), 7).unwrap();
+        assert_eq!(email.from, "ann@acme.test");
+        assert_eq!(email.in_reply_to.as_deref(), Some("b@bizclaw.test"));
+        // The thread is named after its first message
+        assert_eq!(email.thread_id(), "a@acme.test");
+        assert_eq!(email.reply_references(), vec!["a@acme.test", "b@bizclaw.test", "c@acme.test"]);
+
+        let incoming = email.to_incoming();
+        assert_eq!(incoming.thread_id, "a@acme.test");
+        assert_eq!(incoming.sender_id, "ann@acme.test");
+        assert_eq!(incoming.reply_to.as_deref(), Some("c@acme.test"));
+    }
+
+    #[test]
+    fn test_first_email_starts_thread() {
+        let raw = "From: bob@acme.test\r\nSubject: Hello\r\nMessage-ID: <x@acme.test>\r\n\r\nHi\r\n";
+        let email = parse_email_bytes(raw.as_bytes(), 1).unwrap();
+        assert_eq!(email.thread_id(), "x@acme.test");
+        assert_eq!(email.reply_references(), vec!["x@acme.test"]);
+
+        let channel = EmailChannel::new(EmailConfig::default());
+        channel.remember(&email);
+        let thread = channel.clone().threads.lock().unwrap()["x@acme.test"].clone();
+        assert_eq!(thread.address, "bob@acme.test");
+        assert_eq!(reply_subject(&thread.subject), "Re: Hello");
+        assert_eq!(reply_subject("RE: Hello"), "RE: Hello");
+        assert_eq!(angle_addr("x@acme.test"), "<x@acme.test>");
+    }
+}
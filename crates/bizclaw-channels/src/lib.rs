@@ -4,11 +4,16 @@
 //! 25+ channels supported — comprehensive multi-platform architecture.
 
 pub mod cli;
+pub mod devmode;
 pub mod discord;
 pub mod email;
+pub mod middleware;
+pub mod registry;
+pub mod sms;
 pub mod telegram;
 pub mod webhook;
 pub mod whatsapp;
+pub mod whatsapp_client;
 pub mod zalo;
 pub mod slack;
 pub mod adapters;
@@ -0,0 +1,434 @@
+//! Channel middleware — checks every channel message passes through.
+//!
+//! Incoming messages and replies run through a [`Pipeline`] of stages, in
+//! order; any stage can rewrite the message or reject it. The stages built
+//! from config are: allowlist, per-sender rate limit, profanity filter and
+//! transcript logging (last, so only accepted messages are logged).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bizclaw_core::config::ChannelConfig;
+use bizclaw_core::traits::memory::{MemoryBackend, MemoryEntry};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+
+/// What a stage decided about a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// Hand the (possibly rewritten) message to the next stage.
+    Pass,
+    /// Drop the message. `notice` is sent back to the sender, if any.
+    Reject { reason: String, notice: Option<String> },
+}
+
+/// A dropped message: the stage that dropped it and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub stage: String,
+    pub reason: String,
+    /// Text to send back to the sender, if the stage has one.
+    pub notice: Option<String>,
+}
+
+/// One pipeline stage. Both hooks pass by default.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Check or rewrite an incoming message.
+    async fn on_inbound(&self, _msg: &mut IncomingMessage) -> Verdict {
+        Verdict::Pass
+    }
+
+    /// Check or rewrite a reply sent on `channel`.
+    async fn on_outbound(&self, _channel: &str, _msg: &mut OutgoingMessage) -> Verdict {
+        Verdict::Pass
+    }
+}
+
+/// Ordered middleware stages, shared by all channels. Stages can be
+/// replaced at runtime when the config changes.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: RwLock<Vec<Arc<dyn Middleware>>>,
+}
+
+impl Pipeline {
+    pub fn new(stages: Vec<Arc<dyn Middleware>>) -> Self {
+        Self {
+            stages: RwLock::new(stages),
+        }
+    }
+
+    /// Pipeline for the channel config; `memory` receives transcripts when
+    /// they are enabled.
+    pub fn from_config(config: &ChannelConfig, memory: Option<Arc<dyn MemoryBackend>>) -> Self {
+        Self::new(stages_from_config(config, memory))
+    }
+
+    /// Replace the stages (e.g. after a config change).
+    pub fn set_stages(&self, stages: Vec<Arc<dyn Middleware>>) {
+        *self.stages.write().unwrap() = stages;
+    }
+
+    /// Stage names, in order.
+    pub fn stage_names(&self) -> Vec<String> {
+        self.stages().iter().map(|s| s.name().to_string()).collect()
+    }
+
+    fn stages(&self) -> Vec<Arc<dyn Middleware>> {
+        self.stages.read().unwrap().clone()
+    }
+
+    /// Run an incoming message through every stage.
+    pub async fn inbound(&self, mut msg: IncomingMessage) -> Result<IncomingMessage, Rejection> {
+        for stage in self.stages() {
+            if let Verdict::Reject { reason, notice } = stage.on_inbound(&mut msg).await {
+                return Err(Rejection {
+                    stage: stage.name().to_string(),
+                    reason,
+                    notice,
+                });
+            }
+        }
+        Ok(msg)
+    }
+
+    /// Run a reply on `channel` through every stage.
+    pub async fn outbound(&self, channel: &str, mut msg: OutgoingMessage) -> Result<OutgoingMessage, Rejection> {
+        for stage in self.stages() {
+            if let Verdict::Reject { reason, notice } = stage.on_outbound(channel, &mut msg).await {
+                return Err(Rejection {
+                    stage: stage.name().to_string(),
+                    reason,
+                    notice,
+                });
+            }
+        }
+        Ok(msg)
+    }
+}
+
+/// The stages configured in `config`, in pipeline order.
+pub fn stages_from_config(config: &ChannelConfig, memory: Option<Arc<dyn MemoryBackend>>) -> Vec<Arc<dyn Middleware>> {
+    let mut stages: Vec<Arc<dyn Middleware>> = Vec::new();
+
+    let mut allowlist = Allowlist::new();
+    if let Some(tg) = &config.telegram {
+        allowlist = allowlist.allow("telegram", tg.allowed_chat_ids.iter().map(|id| id.to_string()));
+    }
+    if let Some(dc) = &config.discord {
+        allowlist = allowlist.allow("discord", dc.allowed_channel_ids.iter().map(|id| id.to_string()));
+    }
+    if let Some(zalo) = &config.zalo {
+        let ids = zalo.allowlist.user_ids.iter().chain(&zalo.allowlist.group_ids).cloned();
+        allowlist = allowlist.allow("zalo", ids);
+    }
+    if !allowlist.is_empty() {
+        stages.push(Arc::new(allowlist));
+    }
+
+    let mw = &config.middleware;
+    if mw.rate_limit_per_minute > 0 || mw.rate_limit_per_hour > 0 {
+        stages.push(Arc::new(RateLimit::new(mw.rate_limit_per_minute, mw.rate_limit_per_hour)));
+    }
+    if let Some(filter) = ProfanityFilter::new(&mw.blocked_words, mw.block_profanity) {
+        stages.push(Arc::new(filter));
+    }
+    if mw.transcripts
+        && let Some(memory) = memory
+    {
+        stages.push(Arc::new(TranscriptLog::new(memory)));
+    }
+    stages
+}
+
+// ─── Allowlist ────────────────────────────────────────
+
+/// Only accepts messages from listed chats or senders, per channel.
+/// Channels without a list accept everyone.
+#[derive(Debug, Default)]
+pub struct Allowlist {
+    allowed: HashMap<String, HashSet<String>>,
+}
+
+impl Allowlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept only `ids` (chat/thread or sender ids) on `channel`. An empty
+    /// list leaves the channel open.
+    pub fn allow(mut self, channel: &str, ids: impl IntoIterator<Item = String>) -> Self {
+        let ids: HashSet<String> = ids.into_iter().collect();
+        if !ids.is_empty() {
+            self.allowed.entry(channel.to_string()).or_default().extend(ids);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty()
+    }
+}
+
+#[async_trait]
+impl Middleware for Allowlist {
+    fn name(&self) -> &str {
+        "allowlist"
+    }
+
+    async fn on_inbound(&self, msg: &mut IncomingMessage) -> Verdict {
+        match self.allowed.get(&msg.channel) {
+            Some(ids) if !ids.contains(&msg.thread_id) && !ids.contains(&msg.sender_id) => Verdict::Reject {
+                reason: format!("{} is not on the {} allowlist", msg.thread_id, msg.channel),
+                notice: None,
+            },
+            _ => Verdict::Pass,
+        }
+    }
+}
+
+// ─── Rate limit ───────────────────────────────────────
+
+/// Caps the messages each sender sends per minute and per hour.
+pub struct RateLimit {
+    per_minute: u32,
+    per_hour: u32,
+    /// Recent message times per `channel:sender`.
+    hits: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl RateLimit {
+    /// `0` disables a limit.
+    pub fn new(per_minute: u32, per_hour: u32) -> Self {
+        Self {
+            per_minute,
+            per_hour,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a message from `key` at `now`; false when over a limit
+    /// (rejected messages aren't counted).
+    fn check(&self, key: &str, now: Instant) -> bool {
+        let mut hits = self.hits.lock().unwrap();
+        let times = hits.entry(key.to_string()).or_default();
+        while times
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(3600))
+        {
+            times.pop_front();
+        }
+        let last_minute = times
+            .iter()
+            .filter(|t| now.duration_since(**t) < Duration::from_secs(60))
+            .count();
+        let over = (self.per_minute > 0 && last_minute >= self.per_minute as usize)
+            || (self.per_hour > 0 && times.len() >= self.per_hour as usize);
+        if !over {
+            times.push_back(now);
+        }
+        !over
+    }
+}
+
+#[async_trait]
+impl Middleware for RateLimit {
+    fn name(&self) -> &str {
+        "rate_limit"
+    }
+
+    async fn on_inbound(&self, msg: &mut IncomingMessage) -> Verdict {
+        let key = format!("{}:{}", msg.channel, msg.sender_id);
+        if self.check(&key, Instant::now()) {
+            Verdict::Pass
+        } else {
+            Verdict::Reject {
+                reason: format!("{} is over the rate limit", msg.sender_id),
+                notice: Some("⏳ You're sending messages too fast — please wait a moment.".into()),
+            }
+        }
+    }
+}
+
+// ─── Profanity filter ─────────────────────────────────
+
+/// Masks blocked words with `*`, or drops incoming messages holding one.
+/// Replies are always masked.
+pub struct ProfanityFilter {
+    pattern: regex::Regex,
+    block: bool,
+}
+
+impl ProfanityFilter {
+    /// `None` when there are no words to filter.
+    pub fn new(words: &[String], block: bool) -> Option<Self> {
+        let words: Vec<String> = words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(regex::escape)
+            .collect();
+        if words.is_empty() {
+            return None;
+        }
+        let pattern = regex::Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|"))).ok()?;
+        Some(Self { pattern, block })
+    }
+
+    fn mask(&self, text: &str) -> String {
+        self.pattern
+            .replace_all(text, |c: &regex::Captures| "*".repeat(c[0].chars().count()))
+            .into_owned()
+    }
+}
+
+#[async_trait]
+impl Middleware for ProfanityFilter {
+    fn name(&self) -> &str {
+        "profanity"
+    }
+
+    async fn on_inbound(&self, msg: &mut IncomingMessage) -> Verdict {
+        if !self.pattern.is_match(&msg.content) {
+            return Verdict::Pass;
+        }
+        if self.block {
+            return Verdict::Reject {
+                reason: "blocked word".into(),
+                notice: Some("⚠️ Your message was blocked by the content filter.".into()),
+            };
+        }
+        msg.content = self.mask(&msg.content);
+        Verdict::Pass
+    }
+
+    async fn on_outbound(&self, _channel: &str, msg: &mut OutgoingMessage) -> Verdict {
+        msg.content = self.mask(&msg.content);
+        Verdict::Pass
+    }
+}
+
+// ─── Transcript log ───────────────────────────────────
+
+/// Saves every message and reply to memory, tagged `"type": "transcript"`.
+pub struct TranscriptLog {
+    memory: Arc<dyn MemoryBackend>,
+}
+
+impl TranscriptLog {
+    pub fn new(memory: Arc<dyn MemoryBackend>) -> Self {
+        Self { memory }
+    }
+
+    async fn save(&self, channel: &str, thread_id: &str, from: &str, direction: &str, content: &str) {
+        let now = chrono::Utc::now();
+        let entry = MemoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: format!("[{channel}] {from}: {content}"),
+            metadata: serde_json::json!({
+                "type": "transcript",
+                "channel": channel,
+                "thread_id": thread_id,
+                "from": from,
+                "direction": direction,
+            }),
+            embedding: None,
+            created_at: now,
+            updated_at: now,
+        };
+        if let Err(e) = self.memory.save(entry).await {
+            tracing::warn!("[{channel}] Transcript not saved: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for TranscriptLog {
+    fn name(&self) -> &str {
+        "transcript"
+    }
+
+    async fn on_inbound(&self, msg: &mut IncomingMessage) -> Verdict {
+        let from = msg.sender_name.as_deref().unwrap_or(&msg.sender_id);
+        self.save(&msg.channel, &msg.thread_id, from, "inbound", &msg.content).await;
+        Verdict::Pass
+    }
+
+    async fn on_outbound(&self, channel: &str, msg: &mut OutgoingMessage) -> Verdict {
+        self.save(channel, &msg.thread_id, "assistant", "outbound", &msg.content).await;
+        Verdict::Pass
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::ThreadType;
+
+    fn incoming(channel: &str, sender: &str, content: &str) -> IncomingMessage {
+        IncomingMessage {
+            channel: channel.into(),
+            thread_id: format!("chat-{sender}"),
+            sender_id: sender.into(),
+            sender_name: None,
+            content: content.into(),
+            thread_type: ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: None,
+            images: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_from_config() {
+        let mut config = ChannelConfig {
+            telegram: Some(bizclaw_core::config::TelegramChannelConfig {
+                enabled: true,
+                bot_token: "t".into(),
+                allowed_chat_ids: vec![42],
+            }),
+            ..Default::default()
+        };
+        config.middleware.rate_limit_per_minute = 2;
+        config.middleware.blocked_words = vec!["darn".into()];
+        let pipeline = Pipeline::from_config(&config, None);
+        assert_eq!(pipeline.stage_names(), vec!["allowlist", "rate_limit", "profanity"]);
+
+        // Telegram only accepts chat 42; other channels are open
+        assert_eq!(pipeline.inbound(incoming("telegram", "7", "hi")).await.unwrap_err().stage, "allowlist");
+        let mut msg = incoming("telegram", "7", "Darn it");
+        msg.thread_id = "42".into();
+        assert_eq!(pipeline.inbound(msg).await.unwrap().content, "**** it");
+
+        // Third message in a minute from the same sender is rejected
+        pipeline.inbound(incoming("slack", "u1", "one")).await.unwrap();
+        pipeline.inbound(incoming("slack", "u1", "two")).await.unwrap();
+        let rejected = pipeline.inbound(incoming("slack", "u1", "three")).await.unwrap_err();
+        assert_eq!(rejected.stage, "rate_limit");
+        assert!(rejected.notice.is_some());
+        pipeline.inbound(incoming("slack", "u2", "one")).await.unwrap();
+
+        let reply = OutgoingMessage {
+            thread_id: "42".into(),
+            content: "darn right".into(),
+            thread_type: ThreadType::Direct,
+            reply_to: None,
+        };
+        assert_eq!(pipeline.outbound("telegram", reply).await.unwrap().content, "**** right");
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let limit = RateLimit::new(0, 2);
+        let start = Instant::now();
+        assert!(limit.check("k", start));
+        assert!(limit.check("k", start + Duration::from_secs(61)));
+        assert!(!limit.check("k", start + Duration::from_secs(120)));
+        // The first message falls out of the hour window
+        assert!(limit.check("k", start + Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,331 @@
+//! Channel registry — build channels by type name from instance config.
+//!
+//! Each channel type registers a [`ChannelFactory`] that describes its
+//! config fields and builds the channel from a channel instance's JSON
+//! `config`. The gateway connects any registered type without
+//! channel-specific code; [`ChannelRegistry::with_builtin`] registers the
+//! adapters in [`crate::adapters`].
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Channel;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::adapters;
+
+/// One config field of a channel type, for forms and validation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigField {
+    pub key: String,
+    pub label: String,
+    pub required: bool,
+    /// Masked when instances are listed.
+    pub secret: bool,
+}
+
+impl ConfigField {
+    /// A required, non-secret field.
+    pub fn new(key: &str, label: &str) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            required: true,
+            secret: false,
+        }
+    }
+
+    pub fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+/// Builds channels of one type.
+pub trait ChannelFactory: Send + Sync {
+    /// Type name used in channel instances (e.g. "line").
+    fn channel_type(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    fn config_schema(&self) -> Vec<ConfigField>;
+
+    /// Build a channel from an instance's `config` (not yet connected).
+    fn create(&self, config: &serde_json::Value) -> Result<Box<dyn Channel>>;
+}
+
+/// Deserialize an instance config into a channel's config struct.
+pub fn parse_config<C: DeserializeOwned>(channel_type: &str, config: &serde_json::Value) -> Result<C> {
+    serde_json::from_value(config.clone())
+        .map_err(|e| BizClawError::Config(format!("{channel_type} channel config: {e}")))
+}
+
+/// Factory from a build function — enough for most channels.
+pub struct FnFactory {
+    channel_type: String,
+    description: String,
+    schema: Vec<ConfigField>,
+    build: fn(&serde_json::Value) -> Result<Box<dyn Channel>>,
+}
+
+impl FnFactory {
+    pub fn new(
+        channel_type: &str,
+        description: &str,
+        schema: Vec<ConfigField>,
+        build: fn(&serde_json::Value) -> Result<Box<dyn Channel>>,
+    ) -> Self {
+        Self {
+            channel_type: channel_type.into(),
+            description: description.into(),
+            schema,
+            build,
+        }
+    }
+}
+
+impl ChannelFactory for FnFactory {
+    fn channel_type(&self) -> &str {
+        &self.channel_type
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        self.schema.clone()
+    }
+
+    fn create(&self, config: &serde_json::Value) -> Result<Box<dyn Channel>> {
+        (self.build)(config)
+    }
+}
+
+/// Registered channel types, by name. Types can be added at runtime.
+#[derive(Default)]
+pub struct ChannelRegistry {
+    factories: RwLock<BTreeMap<String, Arc<dyn ChannelFactory>>>,
+}
+
+impl ChannelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the adapter channels (LINE, Teams, Signal, Matrix,
+    /// Viber, Messenger and generic webhooks).
+    pub fn with_builtin() -> Self {
+        let registry = Self::new();
+        for factory in builtin_factories() {
+            registry.register(factory);
+        }
+        registry
+    }
+
+    /// Register a channel type, replacing any factory with the same name.
+    pub fn register(&self, factory: Arc<dyn ChannelFactory>) {
+        let name = factory.channel_type().to_string();
+        self.factories.write().unwrap().insert(name, factory);
+    }
+
+    pub fn get(&self, channel_type: &str) -> Option<Arc<dyn ChannelFactory>> {
+        self.factories.read().unwrap().get(channel_type).cloned()
+    }
+
+    pub fn contains(&self, channel_type: &str) -> bool {
+        self.factories.read().unwrap().contains_key(channel_type)
+    }
+
+    /// Type name, description and config schema of every registered type.
+    pub fn channel_types(&self) -> Vec<serde_json::Value> {
+        self.factories
+            .read()
+            .unwrap()
+            .values()
+            .map(|f| {
+                serde_json::json!({
+                    "type": f.channel_type(),
+                    "description": f.description(),
+                    "config": f.config_schema(),
+                })
+            })
+            .collect()
+    }
+
+    /// Build a channel of `channel_type`, checking required fields first.
+    pub fn create(&self, channel_type: &str, config: &serde_json::Value) -> Result<Box<dyn Channel>> {
+        let factory = self
+            .get(channel_type)
+            .ok_or_else(|| BizClawError::Channel(format!("Unknown channel type: {channel_type}")))?;
+        let missing: Vec<String> = factory
+            .config_schema()
+            .into_iter()
+            .filter(|field| field.required)
+            .filter(|field| config[&field.key].as_str().is_none_or(|v| v.trim().is_empty()))
+            .map(|field| field.key)
+            .collect();
+        if !missing.is_empty() {
+            return Err(BizClawError::Config(format!(
+                "{channel_type} channel is missing: {}",
+                missing.join(", ")
+            )));
+        }
+        factory.create(config)
+    }
+}
+
+fn builtin_factories() -> Vec<Arc<dyn ChannelFactory>> {
+    vec![
+        Arc::new(FnFactory::new(
+            "line",
+            "LINE Messaging API",
+            vec![
+                ConfigField::new("channel_access_token", "Channel access token").secret(),
+                ConfigField::new("channel_secret", "Channel secret").secret(),
+            ],
+            |config| {
+                let config = parse_config("line", config)?;
+                Ok(Box::new(adapters::LineChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "teams",
+            "Microsoft Teams (Bot Framework)",
+            vec![
+                ConfigField::new("app_id", "App ID"),
+                ConfigField::new("app_password", "App password").secret(),
+            ],
+            |config| {
+                let config = parse_config("teams", config)?;
+                Ok(Box::new(adapters::TeamsChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "signal",
+            "Signal (via signal-cli REST)",
+            vec![
+                ConfigField::new("api_url", "signal-cli REST URL"),
+                ConfigField::new("phone_number", "Phone number"),
+            ],
+            |config| {
+                let config = parse_config("signal", config)?;
+                Ok(Box::new(adapters::SignalChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "matrix",
+            "Matrix/Element (Client-Server API)",
+            vec![
+                ConfigField::new("homeserver_url", "Homeserver URL"),
+                ConfigField::new("access_token", "Access token").secret(),
+                ConfigField::new("user_id", "User ID"),
+            ],
+            |config| {
+                let config = parse_config("matrix", config)?;
+                Ok(Box::new(adapters::MatrixChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "viber",
+            "Viber Bot API",
+            vec![
+                ConfigField::new("auth_token", "Auth token").secret(),
+                ConfigField::new("bot_name", "Bot name"),
+            ],
+            |config| {
+                let config = parse_config("viber", config)?;
+                Ok(Box::new(adapters::ViberChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "messenger",
+            "Facebook Messenger Platform",
+            vec![
+                ConfigField::new("page_access_token", "Page access token").secret(),
+                ConfigField::new("verify_token", "Verify token").secret(),
+            ],
+            |config| {
+                let config = parse_config("messenger", config)?;
+                Ok(Box::new(adapters::MessengerChannel::new(config)))
+            },
+        )),
+        Arc::new(FnFactory::new(
+            "generic_webhook",
+            "Webhook + REST platforms (Mattermost, Google Chat, DingTalk, Feishu, Webex…)",
+            vec![
+                ConfigField::new("name", "Platform name"),
+                ConfigField::new("incoming_url", "Incoming URL").optional(),
+                ConfigField::new("outgoing_url", "Outgoing URL"),
+                ConfigField::new("auth_header", "Auth header").optional(),
+                ConfigField::new("auth_value", "Auth value").secret().optional(),
+            ],
+            |config| {
+                let mut config = config.clone();
+                if let Some(obj) = config.as_object_mut() {
+                    for key in ["incoming_url", "auth_header", "auth_value"] {
+                        obj.entry(key).or_insert_with(|| serde_json::json!(""));
+                    }
+                }
+                let config = parse_config("generic_webhook", &config)?;
+                Ok(Box::new(adapters::GenericWebhookChannel::new(config)))
+            },
+        )),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry_creates_channels() {
+        let registry = ChannelRegistry::with_builtin();
+        assert!(registry.contains("line"));
+        assert!(!registry.contains("telegram"));
+
+        let channel = registry
+            .create("line", &serde_json::json!({"channel_access_token": "t", "channel_secret": "s"}))
+            .unwrap();
+        assert_eq!(channel.name(), "line");
+        assert!(!channel.is_connected());
+
+        let channel = registry
+            .create("generic_webhook", &serde_json::json!({"name": "mattermost", "outgoing_url": "http://x"}))
+            .unwrap();
+        assert_eq!(channel.name(), "mattermost");
+    }
+
+    #[test]
+    fn test_create_checks_schema() {
+        let registry = ChannelRegistry::with_builtin();
+        let err = registry
+            .create("matrix", &serde_json::json!({"homeserver_url": "https://m.org", "user_id": ""}))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("access_token, user_id"), "{err}");
+        assert!(registry.create("nope", &serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_register_custom_type() {
+        let registry = ChannelRegistry::new();
+        registry.register(Arc::new(FnFactory::new(
+            "cli",
+            "Command-line interface",
+            vec![],
+            |_| Ok(Box::new(crate::cli::CliChannel::new())),
+        )));
+        let types = registry.channel_types();
+        assert_eq!(types.len(), 1);
+        assert_eq!(types[0]["type"], "cli");
+        assert_eq!(registry.create("cli", &serde_json::json!({})).unwrap().name(), "cli");
+    }
+}
@@ -2,6 +2,9 @@
 //!
 //! Implements Slack Socket Mode + Events API for receiving messages,
 //! and Web API for sending responses.
+//!
+//! Socket Mode needs an app-level token (`xapp-...`) and no public URL; the
+//! Events API posts to the gateway and is verified with the signing secret.
 
 use async_trait::async_trait;
 use bizclaw_core::error::{BizClawError, Result};
@@ -9,6 +12,11 @@ use bizclaw_core::traits::Channel;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Requests older than this are rejected as replays (seconds).
+const MAX_REQUEST_AGE_SECS: i64 = 300;
 
 /// Slack channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,7 +67,7 @@ impl SlackChannel {
     }
 
     /// Send a message to a Slack channel or thread.
-    async fn post_message(&self, channel: &str, text: &str, thread_ts: Option<&str>) -> Result<()> {
+    pub async fn post_message(&self, channel: &str, text: &str, thread_ts: Option<&str>) -> Result<()> {
         let mut body = serde_json::json!({
             "channel": channel,
             "text": text,
@@ -83,6 +91,146 @@ impl SlackChannel {
         Ok(())
     }
 
+    /// Who the bot token belongs to (`auth.test`).
+    pub async fn auth_test(&self) -> Result<SlackIdentity> {
+        let resp = self.client
+            .post("https://slack.com/api/auth.test")
+            .header("Authorization", format!("Bearer {}", self.config.bot_token))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Slack auth test: {e}")))?;
+
+        let body: serde_json::Value = resp.json().await
+            .map_err(|e| BizClawError::Channel(format!("Slack response: {e}")))?;
+
+        if body["ok"].as_bool() != Some(true) {
+            return Err(BizClawError::AuthFailed(
+                format!("Slack auth failed: {}", body["error"].as_str().unwrap_or("unknown"))
+            ));
+        }
+        Ok(SlackIdentity {
+            user_id: body["user_id"].as_str().unwrap_or("").into(),
+            user: body["user"].as_str().unwrap_or("bot").into(),
+            team_id: body["team_id"].as_str().unwrap_or("").into(),
+            team: body["team"].as_str().unwrap_or("").into(),
+        })
+    }
+
+    /// Open a Socket Mode connection (`apps.connections.open`), returning
+    /// the WebSocket URL.
+    pub async fn open_socket_url(&self) -> Result<String> {
+        if self.config.app_token.is_empty() {
+            return Err(BizClawError::Channel("Slack app_token required for Socket Mode".into()));
+        }
+        let resp = self.client
+            .post("https://slack.com/api/apps.connections.open")
+            .header("Authorization", format!("Bearer {}", self.config.app_token))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Slack Socket Mode: {e}")))?;
+
+        let body: serde_json::Value = resp.json().await
+            .map_err(|e| BizClawError::Channel(format!("Slack response: {e}")))?;
+
+        if body["ok"].as_bool() != Some(true) {
+            return Err(BizClawError::Channel(format!(
+                "Slack Socket Mode failed: {}",
+                body["error"].as_str().unwrap_or("unknown")
+            )));
+        }
+        body["url"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| BizClawError::Channel("No Socket Mode URL".into()))
+    }
+
+    /// Start a Socket Mode loop — every envelope is acknowledged and its
+    /// message events delivered. Reconnects when Slack asks to or the
+    /// connection drops; stops when the stream is dropped.
+    pub fn start_socket_mode(self, bot_user_id: String) -> SlackSocketStream {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            use futures::{SinkExt, StreamExt};
+            use tokio_tungstenite::tungstenite::Message as WsMsg;
+
+            let slack = self;
+            let mut backoff_secs: u64 = 5;
+
+            loop {
+                if tx.is_closed() {
+                    return;
+                }
+                let url = match slack.open_socket_url().await {
+                    Ok(url) => url,
+                    Err(e) => {
+                        tracing::error!("{e}, retrying in {backoff_secs}s...");
+                        tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
+                        backoff_secs = (backoff_secs * 2).min(60);
+                        continue;
+                    }
+                };
+                let mut ws = match tokio_tungstenite::connect_async(&url).await {
+                    Ok((ws, _)) => ws,
+                    Err(e) => {
+                        tracing::error!("Slack Socket Mode connect failed: {e}, retrying in {backoff_secs}s...");
+                        tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
+                        backoff_secs = (backoff_secs * 2).min(60);
+                        continue;
+                    }
+                };
+                backoff_secs = 5;
+                let mut alive = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+                loop {
+                    tokio::select! {
+                        msg = ws.next() => {
+                            let text = match msg {
+                                Some(Ok(WsMsg::Text(text))) => text,
+                                Some(Ok(WsMsg::Close(_))) | Some(Err(_)) | None => break,
+                                _ => continue,
+                            };
+                            let Ok(envelope) = serde_json::from_str::<serde_json::Value>(&text) else {
+                                continue;
+                            };
+                            // Slack redelivers envelopes that aren't acknowledged
+                            if let Some(id) = envelope["envelope_id"].as_str() {
+                                let ack = serde_json::json!({ "envelope_id": id });
+                                let _ = ws.send(WsMsg::Text(ack.to_string())).await;
+                            }
+                            match envelope["type"].as_str().unwrap_or("") {
+                                "hello" => tracing::info!("💬 Slack Socket Mode connected"),
+                                "disconnect" => {
+                                    tracing::info!("Slack asked to reconnect ({})", envelope["reason"].as_str().unwrap_or(""));
+                                    break;
+                                }
+                                "events_api" => {
+                                    if let Some(incoming) = parse_incoming(&envelope["payload"], &bot_user_id)
+                                        && tx.send(incoming).is_err()
+                                    {
+                                        tracing::info!("Slack Socket Mode stopped (receiver dropped)");
+                                        return;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        _ = alive.tick() => {
+                            if tx.is_closed() {
+                                tracing::info!("Slack Socket Mode stopped (receiver dropped)");
+                                return;
+                            }
+                        }
+                    }
+                }
+                tracing::warn!("Slack Socket Mode disconnected, reconnecting...");
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            }
+        });
+
+        SlackSocketStream { rx }
+    }
+
     /// Parse a Slack Events API payload.
     pub fn parse_event(&self, payload: &serde_json::Value) -> Option<IncomingMessage> {
         let event = payload.get("event")?;
@@ -110,37 +258,293 @@ impl SlackChannel {
             },
             timestamp: chrono::Utc::now(),
             reply_to: event["thread_ts"].as_str().map(String::from),
+            images: vec![],
         })
     }
-}
-
-#[async_trait]
-impl Channel for SlackChannel {
-    fn name(&self) -> &str { "slack" }
 
-    async fn connect(&mut self) -> Result<()> {
-        if self.config.bot_token.is_empty() {
-            return Err(BizClawError::Channel("Slack bot_token required".into()));
+    /// Fetch messages newer than `oldest` from a channel via `conversations.history`.
+    /// Returns the parsed messages (oldest first) and the newest timestamp seen.
+    pub async fn fetch_history(
+        &self,
+        channel: &str,
+        oldest: Option<&str>,
+    ) -> Result<(Vec<IncomingMessage>, Option<String>)> {
+        let mut query = vec![("channel", channel.to_string()), ("limit", "50".into())];
+        if let Some(ts) = oldest {
+            query.push(("oldest", ts.to_string()));
         }
-        // Verify token with auth.test
+
         let resp = self.client
-            .post("https://slack.com/api/auth.test")
+            .get("https://slack.com/api/conversations.history")
             .header("Authorization", format!("Bearer {}", self.config.bot_token))
+            .query(&query)
             .send()
             .await
-            .map_err(|e| BizClawError::Channel(format!("Slack auth test: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("Slack history error: {e}")))?;
 
         let body: serde_json::Value = resp.json().await
             .map_err(|e| BizClawError::Channel(format!("Slack response: {e}")))?;
 
         if body["ok"].as_bool() != Some(true) {
-            return Err(BizClawError::AuthFailed(
-                format!("Slack auth failed: {}", body["error"].as_str().unwrap_or("unknown"))
-            ));
+            return Err(BizClawError::Channel(format!(
+                "Slack history failed: {}",
+                body["error"].as_str().unwrap_or("unknown")
+            )));
         }
 
+        Ok(self.parse_history(channel, &body))
+    }
+
+    /// Parse a `conversations.history` response into incoming messages (oldest first).
+    pub fn parse_history(
+        &self,
+        channel: &str,
+        body: &serde_json::Value,
+    ) -> (Vec<IncomingMessage>, Option<String>) {
+        let messages = body["messages"].as_array().cloned().unwrap_or_default();
+        let newest_ts = messages
+            .iter()
+            .filter_map(|m| m["ts"].as_str())
+            .max_by(|a, b| {
+                let a: f64 = a.parse().unwrap_or(0.0);
+                let b: f64 = b.parse().unwrap_or(0.0);
+                a.total_cmp(&b)
+            })
+            .map(String::from);
+
+        // History is returned newest first — reverse to deliver in order.
+        let parsed = messages
+            .iter()
+            .rev()
+            .filter_map(|m| {
+                let mut event = m.clone();
+                event["channel"] = serde_json::Value::String(channel.to_string());
+                if event.get("type").is_none() {
+                    event["type"] = "message".into();
+                }
+                self.parse_event(&serde_json::json!({ "event": event }))
+            })
+            .collect();
+
+        (parsed, newest_ts)
+    }
+
+    /// Start a polling loop on one channel — dev-mode inbound for machines
+    /// without a public URL. Only messages posted after the loop starts are delivered.
+    pub fn start_polling(self, channel: String, interval_secs: u64) -> SlackPollingStream {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let slack = self;
+            let mut oldest = Some(format!("{}.000000", chrono::Utc::now().timestamp()));
+            tracing::info!("Slack polling loop started on {channel} (dev mode)");
+
+            loop {
+                match slack.fetch_history(&channel, oldest.as_deref()).await {
+                    Ok((messages, newest)) => {
+                        if newest.is_some() {
+                            oldest = newest;
+                        }
+                        for msg in messages {
+                            if tx.send(msg).is_err() {
+                                tracing::info!("Slack polling stopped (receiver dropped)");
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Slack polling error: {e}");
+                        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    }
+                }
+
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs.max(1))).await;
+            }
+        });
+
+        SlackPollingStream { rx }
+    }
+}
+
+/// Who a bot token belongs to.
+#[derive(Debug, Clone)]
+pub struct SlackIdentity {
+    pub user_id: String,
+    pub user: String,
+    pub team_id: String,
+    pub team: String,
+}
+
+/// A message event, with what's needed to reply in its thread.
+#[derive(Debug, Clone)]
+pub struct SlackIncoming {
+    /// `thread_id` is the Slack channel; mentions of the bot are stripped
+    /// from `content`.
+    pub message: IncomingMessage,
+    /// Workspace the event came from.
+    pub team_id: String,
+    /// Timestamp (ID) of the message.
+    pub ts: String,
+    /// Thread the message was posted in, if any.
+    pub thread_ts: Option<String>,
+    /// Direct message to the bot.
+    pub is_direct: bool,
+    /// An `app_mention` event.
+    pub app_mention: bool,
+    /// The text mentions the bot.
+    pub mentions_bot: bool,
+}
+
+impl SlackIncoming {
+    /// Whether the bot should answer: direct messages and mentions always,
+    /// other channel messages only inside a thread the bot is part of.
+    /// Plain `message` events that mention the bot are skipped because the
+    /// matching `app_mention` event answers them.
+    pub fn should_answer(&self, in_bot_thread: bool) -> bool {
+        if self.message.content.is_empty() {
+            return false;
+        }
+        if self.app_mention || self.is_direct {
+            return true;
+        }
+        in_bot_thread && !self.mentions_bot
+    }
+
+    /// Thread to reply in: the message's thread, or a new thread on the
+    /// message in channels. Direct messages are answered inline.
+    pub fn reply_thread(&self) -> Option<&str> {
+        match &self.thread_ts {
+            Some(ts) => Some(ts),
+            None if self.is_direct => None,
+            None => Some(&self.ts),
+        }
+    }
+}
+
+/// Parse an Events API `event_callback` payload (also the payload of a
+/// Socket Mode `events_api` envelope). Bot messages, the bot's own
+/// messages and edits/deletions are skipped.
+pub fn parse_incoming(payload: &serde_json::Value, bot_user_id: &str) -> Option<SlackIncoming> {
+    let event = payload.get("event")?;
+    let event_type = event["type"].as_str()?;
+    if event_type != "message" && event_type != "app_mention" {
+        return None;
+    }
+    if event.get("bot_id").is_some() || event.get("subtype").is_some() {
+        return None;
+    }
+    let user = event["user"].as_str()?;
+    if user == bot_user_id {
+        return None;
+    }
+
+    let mention = format!("<@{bot_user_id}>");
+    let text = event["text"].as_str().unwrap_or("");
+    let mentions_bot = !bot_user_id.is_empty() && text.contains(&mention);
+    let content = if bot_user_id.is_empty() { text.to_string() } else { text.replace(&mention, "") };
+    let is_direct = event["channel_type"].as_str() == Some("im");
+    let thread_ts = event["thread_ts"].as_str().map(String::from);
+
+    Some(SlackIncoming {
+        message: IncomingMessage {
+            channel: "slack".into(),
+            thread_id: event["channel"].as_str().unwrap_or("").into(),
+            sender_id: user.into(),
+            sender_name: None,
+            content: content.trim().to_string(),
+            thread_type: if is_direct { ThreadType::Direct } else { ThreadType::Group },
+            timestamp: chrono::Utc::now(),
+            reply_to: thread_ts.clone(),
+            images: vec![],
+        },
+        team_id: payload["team_id"].as_str().unwrap_or("").into(),
+        ts: event["ts"].as_str().unwrap_or("").into(),
+        thread_ts,
+        is_direct,
+        app_mention: event_type == "app_mention",
+        mentions_bot,
+    })
+}
+
+/// Verify an Events API request: `X-Slack-Signature` must be
+/// `v0=` + hex HMAC-SHA256 of `v0:{timestamp}:{body}` with the signing
+/// secret, and `X-Slack-Request-Timestamp` within five minutes.
+pub fn verify_signature(signing_secret: &str, timestamp: &str, body: &str, signature: &str) -> bool {
+    use hmac::{Hmac, Mac};
+
+    if signing_secret.is_empty() {
+        return false;
+    }
+    let Ok(ts) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if (chrono::Utc::now().timestamp() - ts).abs() > MAX_REQUEST_AGE_SECS {
+        return false;
+    }
+    let Some(hex) = signature.strip_prefix("v0=") else {
+        return false;
+    };
+    let Some(expected) = decode_hex(hex) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<sha2::Sha256>::new_from_slice(signing_secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(format!("v0:{timestamp}:{body}").as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Stream of message events from Socket Mode.
+pub struct SlackSocketStream {
+    rx: tokio::sync::mpsc::UnboundedReceiver<SlackIncoming>,
+}
+
+impl Stream for SlackSocketStream {
+    type Item = SlackIncoming;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Unpin for SlackSocketStream {}
+
+/// Stream of incoming Slack messages from the dev-mode polling loop.
+pub struct SlackPollingStream {
+    rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
+}
+
+impl Stream for SlackPollingStream {
+    type Item = IncomingMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+#[async_trait]
+impl Channel for SlackChannel {
+    fn name(&self) -> &str { "slack" }
+
+    async fn connect(&mut self) -> Result<()> {
+        if self.config.bot_token.is_empty() {
+            return Err(BizClawError::Channel("Slack bot_token required".into()));
+        }
+        // Verify token with auth.test
+        let me = self.auth_test().await?;
+
         self.connected = true;
-        tracing::info!("💬 Slack connected as: {}", body["user"].as_str().unwrap_or("bot"));
+        tracing::info!("💬 Slack connected as: {}", me.user);
         Ok(())
     }
 
@@ -240,6 +644,81 @@ mod tests {
         assert!(msg.content.contains("help me"));
     }
 
+    #[test]
+    fn test_parse_history_oldest_first() {
+        let channel = SlackChannel::new(SlackConfig::default());
+        let body = serde_json::json!({
+            "ok": true,
+            "messages": [
+                {"type": "message", "user": "U2", "text": "second", "ts": "1700000002.000200"},
+                {"type": "message", "user": "U1", "text": "first", "ts": "1700000001.000100"},
+                {"type": "message", "bot_id": "B1", "text": "bot", "ts": "1700000000.000000"}
+            ]
+        });
+        let (msgs, newest) = channel.parse_history("C123", &body);
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].content, "first");
+        assert_eq!(msgs[1].thread_id, "C123");
+        assert_eq!(newest.as_deref(), Some("1700000002.000200"));
+    }
+
+    #[test]
+    fn test_parse_incoming_mentions_and_threads() {
+        let mention = serde_json::json!({
+            "team_id": "T1",
+            "event": {"type": "app_mention", "channel": "C1", "user": "U1", "text": "<@UBOT> status?", "ts": "1.1"}
+        });
+        let incoming = parse_incoming(&mention, "UBOT").unwrap();
+        assert_eq!(incoming.message.content, "status?");
+        assert_eq!(incoming.team_id, "T1");
+        assert!(incoming.should_answer(false));
+        // Answered in a new thread on the message
+        assert_eq!(incoming.reply_thread(), Some("1.1"));
+
+        // The plain message event for the same mention is left to app_mention
+        let mut message = mention.clone();
+        message["event"]["type"] = "message".into();
+        assert!(!parse_incoming(&message, "UBOT").unwrap().should_answer(true));
+
+        let reply = serde_json::json!({
+            "event": {"type": "message", "channel": "C1", "user": "U1", "text": "and now?", "ts": "1.2", "thread_ts": "1.1"}
+        });
+        let reply = parse_incoming(&reply, "UBOT").unwrap();
+        assert!(!reply.should_answer(false));
+        assert!(reply.should_answer(true));
+        assert_eq!(reply.reply_thread(), Some("1.1"));
+
+        let dm = serde_json::json!({
+            "event": {"type": "message", "channel": "D1", "channel_type": "im", "user": "U1", "text": "hi", "ts": "2.1"}
+        });
+        let dm = parse_incoming(&dm, "UBOT").unwrap();
+        assert!(dm.should_answer(false));
+        assert_eq!(dm.reply_thread(), None);
+
+        let own = serde_json::json!({"event": {"type": "message", "channel": "C1", "user": "UBOT", "text": "x"}});
+        assert!(parse_incoming(&own, "UBOT").is_none());
+        let edited = serde_json::json!({"event": {"type": "message", "subtype": "message_changed", "channel": "C1"}});
+        assert!(parse_incoming(&edited, "UBOT").is_none());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        use hmac::{Hmac, Mac};
+
+        let ts = chrono::Utc::now().timestamp().to_string();
+        let body = r#"{"type":"event_callback"}"#;
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(format!("v0:{ts}:{body}").as_bytes());
+        let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+        let signature = format!("v0={hex}");
+
+        assert!(verify_signature("secret", &ts, body, &signature));
+        assert!(!verify_signature("other", &ts, body, &signature));
+        assert!(!verify_signature("secret", &ts, "{}", &signature));
+        assert!(!verify_signature("secret", "1000", body, &signature));
+        assert!(!verify_signature("", &ts, body, &signature));
+    }
+
     #[test]
     fn test_ignore_non_message_events() {
         let channel = SlackChannel::new(SlackConfig::default());
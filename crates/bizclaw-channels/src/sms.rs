@@ -0,0 +1,197 @@
+//! SMS via Twilio — send texts and read the inbound webhook.
+//!
+//! Shared by the gateway's SMS webhook handler and the scheduler's
+//! notification dispatch. Twilio posts inbound texts as form fields and
+//! signs them with the account's auth token (`X-Twilio-Signature`).
+
+use std::collections::HashMap;
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::types::IncomingMessage;
+
+use crate::telegram::split_text;
+
+/// Twilio REST API base URL.
+const TWILIO_API: &str = "https://api.twilio.com/2010-04-01";
+
+/// Longest body Twilio accepts in one message (sent as several segments).
+pub const MAX_SMS_LEN: usize = 1600;
+
+/// Empty TwiML answer — replies are sent through the REST API instead.
+pub const EMPTY_TWIML: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Response></Response>"#;
+
+/// Twilio client for one account and sending number.
+#[derive(Clone)]
+pub struct TwilioClient {
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    client: reqwest::Client,
+}
+
+/// A text received on the webhook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundSms {
+    /// Twilio message SID (`SM…`).
+    pub sid: String,
+    /// Sender's phone number.
+    pub from: String,
+    /// Twilio number the text was sent to.
+    pub to: String,
+    pub body: String,
+}
+
+impl InboundSms {
+    /// Read a webhook's form fields; `None` without a sender or body.
+    pub fn parse(form: &HashMap<String, String>) -> Option<Self> {
+        let field = |key: &str| form.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+        let (from, body) = (field("From"), field("Body"));
+        if from.is_empty() || body.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sid: field("MessageSid"),
+            from,
+            to: field("To"),
+            body,
+        })
+    }
+
+    pub fn to_incoming(&self) -> IncomingMessage {
+        IncomingMessage {
+            channel: "sms".into(),
+            thread_id: self.from.clone(),
+            sender_id: self.from.clone(),
+            sender_name: None,
+            content: self.body.clone(),
+            thread_type: bizclaw_core::types::ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: (!self.sid.is_empty()).then(|| self.sid.clone()),
+            images: vec![],
+        }
+    }
+}
+
+impl TwilioClient {
+    pub fn new(account_sid: &str, auth_token: &str, from: &str) -> Self {
+        Self {
+            account_sid: account_sid.to_string(),
+            auth_token: auth_token.to_string(),
+            from: from.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Same account, sending from another of its numbers.
+    pub fn with_from(&self, from: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Send a text, split into several messages past [`MAX_SMS_LEN`].
+    /// Returns the message SIDs.
+    pub async fn send_sms(&self, to: &str, body: &str) -> Result<Vec<String>> {
+        let mut sids = Vec::new();
+        for chunk in split_text(body, MAX_SMS_LEN) {
+            sids.push(self.send_one(to, &chunk).await?);
+        }
+        Ok(sids)
+    }
+
+    async fn send_one(&self, to: &str, body: &str) -> Result<String> {
+        let url = format!("{TWILIO_API}/Accounts/{}/Messages.json", self.account_sid);
+        let resp = self
+            .client
+            .post(&url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[("To", to), ("From", self.from.as_str()), ("Body", body)])
+            .timeout(std::time::Duration::from_secs(15))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Twilio request failed: {e}")))?;
+        let status = resp.status();
+        let json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Twilio response invalid: {e}")))?;
+        if !status.is_success() {
+            return Err(BizClawError::Channel(format!(
+                "Twilio API error {status}: {}",
+                json["message"].as_str().unwrap_or("unknown error")
+            )));
+        }
+        Ok(json["sid"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+/// Check `X-Twilio-Signature`: base64 HMAC-SHA1 of the webhook URL followed
+/// by every form field's name and value, sorted by name.
+pub fn verify_signature(auth_token: &str, url: &str, form: &HashMap<String, String>, signature: &str) -> bool {
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+
+    if auth_token.is_empty() || signature.is_empty() {
+        return false;
+    }
+    let Ok(expected) = base64::engine::general_purpose::STANDARD.decode(signature) else {
+        return false;
+    };
+    let mut fields: Vec<_> = form.iter().collect();
+    fields.sort();
+    let mut data = url.to_string();
+    for (key, value) in fields {
+        data.push_str(key);
+        data.push_str(value);
+    }
+    let Ok(mut mac) = Hmac::<sha1::Sha1>::new_from_slice(auth_token.as_bytes()) else {
+        return false;
+    };
+    mac.update(data.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_inbound() {
+        let sms = InboundSms::parse(&form(&[
+            ("MessageSid", "SM123"),
+            ("From", "+84901234567"),
+            ("To", "+15005550006"),
+            ("Body", " Lịch hẹn mai? "),
+        ]))
+        .unwrap();
+        assert_eq!(sms.body, "Lịch hẹn mai?");
+        let incoming = sms.to_incoming();
+        assert_eq!(incoming.channel, "sms");
+        assert_eq!(incoming.thread_id, "+84901234567");
+        assert_eq!(incoming.reply_to.as_deref(), Some("SM123"));
+
+        assert!(InboundSms::parse(&form(&[("From", "+1"), ("Body", "")])).is_none());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // Example from Twilio's webhook security docs
+        let url = "https://mycompany.com/myapp.php?foo=1&bar=2";
+        let params = form(&[
+            ("CallSid", "CA1234567890ABCDE"),
+            ("Caller", "+12349013030"),
+            ("Digits", "1234"),
+            ("From", "+12349013030"),
+            ("To", "+18005551212"),
+        ]);
+        let token = "12345";
+        assert!(verify_signature(token, url, &params, "0/KCTR6DLpKmkAf8muzZqo1nDgQ="));
+        assert!(!verify_signature(token, "https://mycompany.com/other", &params, "0/KCTR6DLpKmkAf8muzZqo1nDgQ="));
+        assert!(!verify_signature("", url, &params, "0/KCTR6DLpKmkAf8muzZqo1nDgQ="));
+    }
+}
@@ -1,14 +1,31 @@
 //! Telegram Bot channel — long polling + message sending via Bot API.
+//!
+//! Agent answers are sent as MarkdownV2 (falling back to plain text when
+//! Telegram rejects the markup), split to fit the message limit. An answer
+//! can also carry inline buttons — `[button: Label]` lines — and files:
+//! markdown links or images pointing at local files are uploaded. Button
+//! presses come back as [`IncomingMessage`]s with a JSON `button_pressed`
+//! event as content.
 
 use async_trait::async_trait;
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Channel;
-use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use bizclaw_core::types::{ImageInput, IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+/// Longest text Telegram accepts in one message.
+const MAX_MESSAGE_LEN: usize = 4096;
+/// Answers are cut shorter to leave room for MarkdownV2 escapes.
+const CHUNK_LEN: usize = 3500;
+/// Callback data of buttons the agent asked for starts with this.
+pub const BUTTON_PREFIX: &str = "btn:";
+/// Telegram limits callback data to 64 bytes.
+const MAX_CALLBACK_DATA: usize = 64;
+
 /// Telegram channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
@@ -27,6 +44,7 @@ fn default_poll_interval() -> u64 {
 }
 
 /// Telegram Bot channel with polling loop.
+#[derive(Clone)]
 pub struct TelegramChannel {
     config: TelegramConfig,
     client: reqwest::Client,
@@ -59,7 +77,7 @@ impl TelegramChannel {
             .query(&[
                 ("offset", (self.last_update_id + 1).to_string()),
                 ("timeout", "30".into()),
-                ("allowed_updates", "[\"message\"]".into()),
+                ("allowed_updates", "[\"message\",\"callback_query\"]".into()),
             ])
             .send()
             .await
@@ -84,14 +102,164 @@ impl TelegramChannel {
         Ok(updates)
     }
 
-    /// Send a text message.
+    /// Send an agent answer: formatted, split into several messages when
+    /// long, with its `[button: ...]` lines as an inline keyboard on the
+    /// last message and its local files uploaded after the text.
     pub async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
-        let body = serde_json::json!({
+        let reply = RichReply::parse(text);
+        let mut chunks = split_text(&reply.text, CHUNK_LEN);
+        chunks.retain(|c| !c.trim().is_empty());
+        if chunks.is_empty() && !reply.buttons.is_empty() {
+            chunks.push("👇".into());
+        }
+        let last = chunks.len().saturating_sub(1);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut body = serde_json::json!({ "chat_id": chat_id });
+            if i == last && !reply.buttons.is_empty() {
+                body["reply_markup"] = reply.keyboard();
+            }
+            self.post_formatted("sendMessage", body, chunk).await?;
+        }
+        for path in &reply.attachments {
+            self.send_file(chat_id, path, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a formatted text message and return its `message_id` (for
+    /// later edits).
+    pub async fn send_message_with_id(&self, chat_id: i64, text: &str) -> Result<i64> {
+        let message = self
+            .post_formatted("sendMessage", serde_json::json!({ "chat_id": chat_id }), text)
+            .await?;
+        Ok(message["message_id"].as_i64().unwrap_or_default())
+    }
+
+    /// Upload a local file: images as photos, anything else as a document.
+    pub async fn send_file(&self, chat_id: i64, path: &Path, caption: Option<&str>) -> Result<()> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Read {}: {e}", path.display())))?;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".into());
+        let (method, field) = if is_image(path) {
+            ("sendPhoto", "photo")
+        } else {
+            ("sendDocument", "document")
+        };
+        let mut fields = vec![("chat_id", chat_id.to_string())];
+        if let Some(caption) = caption {
+            fields.push(("caption", caption.to_string()));
+        }
+        let (content_type, body) = multipart_body(&fields, field, &file_name, "application/octet-stream", &bytes);
+
+        let response = self
+            .client
+            .post(self.api_url(method))
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("{method} failed: {e}")))?;
+        let result: TelegramApiResponse<serde_json::Value> = response
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Invalid {method} response: {e}")))?;
+        if !result.ok {
+            return Err(BizClawError::Channel(format!(
+                "{method} failed: {}",
+                result.description.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Call `method` with `text` as MarkdownV2; if Telegram can't parse
+    /// the markup, send it again as plain text. Returns the result.
+    async fn post_formatted(
+        &self,
+        method: &str,
+        mut body: serde_json::Value,
+        text: &str,
+    ) -> Result<serde_json::Value> {
+        let formatted = to_markdown_v2(text);
+        if formatted.chars().count() <= MAX_MESSAGE_LEN {
+            body["text"] = formatted.into();
+            body["parse_mode"] = "MarkdownV2".into();
+            match self.call(method, &body).await {
+                Err(BizClawError::Channel(e)) if e.contains("can't parse entities") => {
+                    tracing::debug!("Telegram rejected MarkdownV2, sending plain text: {e}");
+                }
+                result => return result,
+            }
+            body.as_object_mut().map(|b| b.remove("parse_mode"));
+        }
+        body["text"] = text.chars().take(MAX_MESSAGE_LEN).collect::<String>().into();
+        self.call(method, &body).await
+    }
+
+    /// Call a Bot API method, returning its `result`.
+    async fn call(&self, method: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .post(self.api_url(method))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("{method} failed: {e}")))?;
+
+        let result: TelegramApiResponse<serde_json::Value> = response
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Invalid {method} response: {e}")))?;
+
+        if !result.ok {
+            return Err(BizClawError::Channel(format!(
+                "{method} failed: {}",
+                result.description.unwrap_or_default()
+            )));
+        }
+        Ok(result.result.unwrap_or_default())
+    }
+
+    /// Send a plain-text message with one row of inline buttons, given as
+    /// (label, callback data) pairs. Returns the message id.
+    pub async fn send_message_with_buttons(
+        &self,
+        chat_id: i64,
+        text: &str,
+        buttons: &[(&str, &str)],
+    ) -> Result<i64> {
+        let row: Vec<serde_json::Value> = buttons
+            .iter()
+            .map(|(label, data)| serde_json::json!({"text": label, "callback_data": data}))
+            .collect();
+        self.post_message(serde_json::json!({
             "chat_id": chat_id,
             "text": text,
-            "parse_mode": "Markdown",
+            "reply_markup": {"inline_keyboard": [row]},
+        }))
+        .await
+    }
+
+    /// Acknowledge an inline button press, showing `text` as a toast.
+    pub async fn answer_callback_query(&self, callback_query_id: &str, text: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "callback_query_id": callback_query_id,
+            "text": text,
         });
+        self.client
+            .post(self.api_url("answerCallbackQuery"))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("answerCallbackQuery failed: {e}")))?;
+        Ok(())
+    }
 
+    async fn post_message(&self, body: serde_json::Value) -> Result<i64> {
         let response = self
             .client
             .post(self.api_url("sendMessage"))
@@ -111,7 +279,19 @@ impl TelegramChannel {
                 result.description.unwrap_or_default()
             )));
         }
-        Ok(())
+        Ok(result
+            .result
+            .and_then(|m| m["message_id"].as_i64())
+            .unwrap_or_default())
+    }
+
+    /// Replace the text of a previously sent message.
+    pub async fn edit_message_text(&self, chat_id: i64, message_id: i64, text: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+        });
+        self.post_formatted("editMessageText", body, text).await.map(|_| ())
     }
 
     /// Send typing indicator.
@@ -129,6 +309,61 @@ impl TelegramChannel {
         Ok(())
     }
 
+    /// Download the photo in `update` (largest size) as a base64 image.
+    /// Returns no images for text messages or when the download fails.
+    pub async fn fetch_images(&self, update: &TelegramUpdate) -> Vec<ImageInput> {
+        let Some(photo) = update
+            .message
+            .as_ref()
+            .and_then(|m| m.photo.as_ref())
+            .and_then(|sizes| sizes.iter().max_by_key(|p| p.width * p.height))
+        else {
+            return vec![];
+        };
+        match self.download_file(&photo.file_id).await {
+            Ok(bytes) => {
+                use base64::Engine;
+                let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                vec![ImageInput::base64("image/jpeg", data)]
+            }
+            Err(e) => {
+                tracing::warn!("Telegram photo download failed: {e}");
+                vec![]
+            }
+        }
+    }
+
+    /// Download a file by id (getFile, then the file endpoint).
+    async fn download_file(&self, file_id: &str) -> Result<Vec<u8>> {
+        let body: TelegramApiResponse<TelegramFile> = self
+            .client
+            .get(self.api_url("getFile"))
+            .query(&[("file_id", file_id)])
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Telegram getFile failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Invalid getFile response: {e}")))?;
+        let path = body
+            .result
+            .and_then(|f| f.file_path)
+            .ok_or_else(|| BizClawError::Channel("File not available".into()))?;
+
+        let url = format!("https://api.telegram.org/file/bot{}/{path}", self.config.bot_token);
+        let bytes = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| BizClawError::Channel(format!("Telegram file download failed: {e}")))?
+            .bytes()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Telegram file download failed: {e}")))?;
+        Ok(bytes.to_vec())
+    }
+
     /// Get bot info.
     pub async fn get_me(&self) -> Result<TelegramUser> {
         let response = self
@@ -158,11 +393,16 @@ impl TelegramChannel {
                 match channel.get_updates().await {
                     Ok(updates) => {
                         for update in updates {
-                            if let Some(msg) = update.to_incoming()
-                                && tx.send(msg).is_err() {
+                            if let Some(query) = &update.callback_query {
+                                let _ = channel.answer_callback_query(&query.id, "").await;
+                            }
+                            if let Some(mut msg) = update.to_incoming() {
+                                msg.images = channel.fetch_images(&update).await;
+                                if tx.send(msg).is_err() {
                                     tracing::info!("Telegram polling stopped (receiver dropped)");
                                     return;
                                 }
+                            }
                         }
                     }
                     Err(e) => {
@@ -258,6 +498,19 @@ pub struct TelegramApiResponse<T> {
 pub struct TelegramUpdate {
     pub update_id: i64,
     pub message: Option<TelegramMessage>,
+    /// An inline button press.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_query: Option<TelegramCallbackQuery>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramCallbackQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    /// The message carrying the pressed button.
+    pub message: Option<TelegramMessage>,
+    /// `callback_data` of the pressed button.
+    pub data: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -268,6 +521,27 @@ pub struct TelegramMessage {
     pub text: Option<String>,
     pub date: i64,
     pub reply_to_message: Option<Box<TelegramMessage>>,
+    /// Sizes of an attached photo, smallest first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<Vec<TelegramPhotoSize>>,
+    /// Caption of a photo message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    /// Inline keyboard attached to the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramPhotoSize {
+    pub file_id: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramFile {
+    pub file_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -287,11 +561,58 @@ pub struct TelegramChat {
     pub title: Option<String>,
 }
 
+impl TelegramCallbackQuery {
+    /// The label of a pressed agent button (`None` for other buttons, such
+    /// as approval prompts).
+    pub fn button_label(&self) -> Option<String> {
+        let data = self.data.as_deref()?.strip_prefix(BUTTON_PREFIX)?;
+        // The label on the keyboard is complete; the data may be cut short
+        let label = self
+            .message
+            .as_ref()
+            .and_then(|m| m.reply_markup.as_ref())
+            .and_then(|markup| markup["inline_keyboard"].as_array())
+            .into_iter()
+            .flatten()
+            .flat_map(|row| row.as_array().into_iter().flatten())
+            .find(|button| button["callback_data"].as_str() == self.data.as_deref())
+            .and_then(|button| button["text"].as_str())
+            .unwrap_or(data);
+        Some(label.to_string())
+    }
+}
+
 impl TelegramUpdate {
-    /// Convert to BizClaw IncomingMessage.
+    /// Convert to BizClaw IncomingMessage. Photo messages carry their
+    /// caption as content; attach the photo with `TelegramChannel::fetch_images`.
+    /// A pressed agent button becomes a `button_pressed` event, e.g.
+    /// `{"event":"button_pressed","button":"Yes"}`.
     pub fn to_incoming(&self) -> Option<IncomingMessage> {
+        if let Some(query) = &self.callback_query {
+            let label = query.button_label()?;
+            let chat = &query.message.as_ref()?.chat;
+            return Some(IncomingMessage {
+                channel: "telegram".into(),
+                thread_id: chat.id.to_string(),
+                sender_id: query.from.id.to_string(),
+                sender_name: Some(query.from.first_name.clone()),
+                content: serde_json::json!({"event": "button_pressed", "button": label}).to_string(),
+                thread_type: match chat.chat_type.as_str() {
+                    "private" => ThreadType::Direct,
+                    _ => ThreadType::Group,
+                },
+                timestamp: chrono::Utc::now(),
+                reply_to: query.message.as_ref().map(|m| m.message_id.to_string()),
+                images: vec![],
+            });
+        }
         let msg = self.message.as_ref()?;
-        let text = msg.text.as_ref()?;
+        let text = match (&msg.text, &msg.caption, &msg.photo) {
+            (Some(text), _, _) => text,
+            (None, Some(caption), Some(_)) => caption,
+            (None, None, Some(_)) => &String::new(),
+            (None, _, None) => return None,
+        };
         let from = msg.from.as_ref()?;
 
         // Skip bot messages
@@ -321,6 +642,408 @@ impl TelegramUpdate {
                 .reply_to_message
                 .as_ref()
                 .map(|r| r.message_id.to_string()),
+            images: vec![],
         })
     }
 }
+
+// --- Rich replies ---
+
+/// An agent answer prepared for Telegram.
+#[derive(Debug, Default, PartialEq)]
+pub struct RichReply {
+    /// The answer without button lines and attached-file links.
+    pub text: String,
+    /// Inline keyboard rows of button labels.
+    pub buttons: Vec<Vec<String>>,
+    /// Local files to upload.
+    pub attachments: Vec<PathBuf>,
+}
+
+impl RichReply {
+    /// Split an agent answer into text, buttons and attachments. Lines made
+    /// only of `[button: Label]` tokens become keyboard rows; markdown
+    /// images and links whose target is an existing local file
+    /// (`/abs/path` or `file:///abs/path`) become attachments — images are
+    /// removed from the text, links keep their label.
+    pub fn parse(answer: &str) -> Self {
+        let button_re = regex::Regex::new(r"\[button:\s*([^\]]+?)\s*\]").unwrap();
+        let file_re = regex::Regex::new(r"(!?)\[([^\]]*)\]\((?:file://)?(/[^)\s]+)\)").unwrap();
+
+        let mut reply = RichReply::default();
+        let mut lines = Vec::new();
+        for line in answer.lines() {
+            let labels: Vec<String> = button_re
+                .captures_iter(line)
+                .map(|c| c[1].to_string())
+                .collect();
+            if !labels.is_empty() && button_re.replace_all(line, "").trim().is_empty() {
+                reply.buttons.push(labels);
+                continue;
+            }
+            let line = file_re.replace_all(line, |c: &regex::Captures| {
+                let path = PathBuf::from(&c[3]);
+                if !path.is_file() {
+                    return c[0].to_string();
+                }
+                let keep = if c[1].is_empty() { c[2].to_string() } else { String::new() };
+                if !reply.attachments.contains(&path) {
+                    reply.attachments.push(path);
+                }
+                keep
+            });
+            lines.push(line.into_owned());
+        }
+        reply.text = lines.join("\n").trim().to_string();
+        reply
+    }
+
+    /// Plain text short enough for a single message (and so for editing
+    /// an existing message into it).
+    pub fn fits_one_message(&self) -> bool {
+        self.buttons.is_empty() && self.attachments.is_empty() && self.text.chars().count() <= CHUNK_LEN
+    }
+
+    /// The buttons as an `inline_keyboard` reply markup.
+    pub fn keyboard(&self) -> serde_json::Value {
+        let rows: Vec<Vec<serde_json::Value>> = self
+            .buttons
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|label| serde_json::json!({"text": label, "callback_data": callback_data(label)}))
+                    .collect()
+            })
+            .collect();
+        serde_json::json!({ "inline_keyboard": rows })
+    }
+}
+
+/// Callback data for a button label, cut to Telegram's 64-byte limit.
+fn callback_data(label: &str) -> String {
+    let mut data = format!("{BUTTON_PREFIX}{label}");
+    let mut end = data.len().min(MAX_CALLBACK_DATA);
+    while !data.is_char_boundary(end) {
+        end -= 1;
+    }
+    data.truncate(end);
+    data
+}
+
+fn is_image(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp")
+}
+
+/// A `multipart/form-data` body with text `fields` and one file of type
+/// `file_type`. Returns the Content-Type header and the body.
+pub(crate) fn multipart_body(
+    fields: &[(&str, String)],
+    file_field: &str,
+    file_name: &str,
+    file_type: &str,
+    bytes: &[u8],
+) -> (String, Vec<u8>) {
+    let boundary = format!("bizclaw-{}", uuid::Uuid::new_v4().simple());
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!("--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n").as_bytes(),
+        );
+    }
+    let file_name = file_name.replace('"', "");
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{file_field}\"; filename=\"{file_name}\"\r\n\
+             Content-Type: {file_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+/// Split `text` into chunks of at most `max` characters at line breaks.
+/// A chunk ending inside a code block closes it and the next reopens it.
+pub fn split_text(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+
+    for line in text.split('\n') {
+        // Over-long lines are cut into pieces of `max` characters
+        let chars: Vec<char> = line.chars().collect();
+        let pieces: Vec<String> = if chars.is_empty() {
+            vec![String::new()]
+        } else {
+            chars.chunks(max.saturating_sub(8).max(1)).map(|c| c.iter().collect()).collect()
+        };
+        for piece in pieces {
+            let needed = current.chars().count() + piece.chars().count() + 1 + if in_code { 4 } else { 0 };
+            if !current.is_empty() && needed > max {
+                if in_code {
+                    current.push_str("\n```");
+                }
+                chunks.push(std::mem::take(&mut current));
+                if in_code {
+                    current.push_str("```\n");
+                }
+            } else if !current.is_empty() {
+                current.push('\n');
+            }
+            current.push_str(&piece);
+        }
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Characters MarkdownV2 requires escaping outside entities.
+const V2_SPECIAL: &str = "_*[]()~`>#+-=|{}.!\\";
+
+fn escape_v2(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if V2_SPECIAL.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape code: only `` ` `` and `\` are special inside code entities.
+fn escape_code(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+/// Convert the Markdown agents usually write into Telegram MarkdownV2:
+/// `**bold**`/`__bold__` → bold, `*italic*` → italic, `~~strike~~`,
+/// inline code, fenced code blocks and `[links](url)` are kept; headings
+/// become bold lines and `-`/`*` bullets become `•`. Everything else is
+/// escaped.
+pub fn to_markdown_v2(text: &str) -> String {
+    let mut out = String::new();
+    let mut lines = text.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            // Fenced code block, copied up to the closing fence
+            out.push_str("```");
+            out.push_str(&escape_code(lang.trim()));
+            out.push('\n');
+            for code in lines.by_ref() {
+                if code.trim_start().starts_with("```") {
+                    break;
+                }
+                out.push_str(&escape_code(code));
+                out.push('\n');
+            }
+            out.push_str("```");
+        } else if let Some(heading) = heading_text(trimmed) {
+            out.push('*');
+            out.push_str(&inline_v2(heading));
+            out.push('*');
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            out.push_str(&line[..line.len() - trimmed.len()]);
+            out.push_str("• ");
+            out.push_str(&inline_v2(item));
+        } else {
+            out.push_str(&inline_v2(line));
+        }
+        if lines.peek().is_some() {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Index of the `)` closing a link target, allowing balanced parentheses
+/// inside it.
+fn closing_paren(target: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in target.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn heading_text(line: &str) -> Option<&str> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) {
+        line[hashes..].strip_prefix(' ').map(str::trim)
+    } else {
+        None
+    }
+}
+
+/// Convert one line of inline Markdown.
+fn inline_v2(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        // Paired markers: (markdown, MarkdownV2)
+        let pair = [("**", "*"), ("__", "*"), ("~~", "~"), ("*", "_")]
+            .into_iter()
+            .find(|(md, _)| rest.starts_with(md));
+        if let Some((md, v2)) = pair
+            && let Some(end) = rest[md.len()..].find(md)
+            && end > 0
+            && !rest[md.len()..].starts_with(' ')
+        {
+            let inner = &rest[md.len()..md.len() + end];
+            out.push_str(v2);
+            out.push_str(&inline_v2(inner));
+            out.push_str(v2);
+            rest = &rest[md.len() * 2 + end..];
+            continue;
+        }
+        if c == '`'
+            && let Some(end) = rest[1..].find('`')
+        {
+            out.push('`');
+            out.push_str(&escape_code(&rest[1..1 + end]));
+            out.push('`');
+            rest = &rest[end + 2..];
+            continue;
+        }
+        if c == '['
+            && let Some(close) = rest.find("](")
+            && let Some(end) = closing_paren(&rest[close + 2..])
+        {
+            let label = &rest[1..close];
+            let url = &rest[close + 2..close + 2 + end];
+            out.push('[');
+            out.push_str(&inline_v2(label));
+            out.push_str("](");
+            out.push_str(&url.replace('\\', "\\\\").replace(')', "\\)"));
+            out.push(')');
+            rest = &rest[close + 3 + end..];
+            continue;
+        }
+        out.push_str(&escape_v2(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(message: serde_json::Value) -> TelegramUpdate {
+        serde_json::from_value(serde_json::json!({"update_id": 1, "message": message})).unwrap()
+    }
+
+    #[test]
+    fn test_photo_message_uses_caption() {
+        let photo = update(serde_json::json!({
+            "message_id": 7,
+            "from": {"id": 42, "is_bot": false, "first_name": "Lan"},
+            "chat": {"id": 42, "type": "private"},
+            "date": 0,
+            "caption": "What plant is this?",
+            "photo": [
+                {"file_id": "small", "width": 90, "height": 60},
+                {"file_id": "large", "width": 1280, "height": 853}
+            ]
+        }));
+        let msg = photo.to_incoming().unwrap();
+        assert_eq!(msg.content, "What plant is this?");
+        assert!(msg.images.is_empty()); // attached by fetch_images
+
+        let sticker = update(serde_json::json!({
+            "message_id": 8,
+            "from": {"id": 42, "is_bot": false, "first_name": "Lan"},
+            "chat": {"id": 42, "type": "private"},
+            "date": 0
+        }));
+        assert!(sticker.to_incoming().is_none());
+    }
+
+    #[test]
+    fn test_markdown_v2_conversion() {
+        assert_eq!(to_markdown_v2("Total: 1.5 (approx)!"), "Total: 1\\.5 \\(approx\\)\\!");
+        assert_eq!(to_markdown_v2("**Done** and *soon*"), "*Done* and _soon_");
+        assert_eq!(to_markdown_v2("run `a_b.sh`"), "run `a_b.sh`");
+        assert_eq!(to_markdown_v2("## Plan\n- step_1"), "*Plan*\n• step\\_1");
+        assert_eq!(to_markdown_v2("[docs](https://x.io/a_(b))"), "[docs](https://x.io/a_(b\\))");
+        assert_eq!(to_markdown_v2("```rust\nlet a = `x`;\n```"), "```rust\nlet a = \\`x\\`;\n```");
+        // An unpaired marker is escaped
+        assert_eq!(to_markdown_v2("2 * 3"), "2 \\* 3");
+    }
+
+    #[test]
+    fn test_split_text_keeps_code_blocks_closed() {
+        assert_eq!(split_text("short", 100), vec!["short"]);
+        let text = format!("intro\n```\n{}\n{}\n```", "a".repeat(30), "b".repeat(30));
+        let chunks = split_text(&text, 50);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 50));
+        // Every chunk has balanced fences
+        assert!(chunks.iter().all(|c| c.matches("```").count() % 2 == 0));
+        let long_line = "x".repeat(120);
+        assert!(split_text(&long_line, 50).iter().all(|c| c.chars().count() <= 50));
+    }
+
+    #[test]
+    fn test_rich_reply_buttons_and_files() {
+        let file = std::env::temp_dir().join(format!("bizclaw-tg-{}.png", std::process::id()));
+        std::fs::write(&file, b"png").unwrap();
+        let answer = format!(
+            "Here is the chart:\n![chart]({})\nSee [the spec](/no/such/file.pdf).\n[button: Yes] [button: No]\n[button: Later]",
+            file.display()
+        );
+        let reply = RichReply::parse(&answer);
+        assert_eq!(reply.text, "Here is the chart:\n\nSee [the spec](/no/such/file.pdf).");
+        assert_eq!(reply.attachments, vec![file.clone()]);
+        assert_eq!(reply.buttons, vec![vec!["Yes".to_string(), "No".to_string()], vec!["Later".to_string()]]);
+        assert_eq!(reply.keyboard()["inline_keyboard"][0][1]["callback_data"], "btn:No");
+        assert!(callback_data(&"é".repeat(40)).len() <= MAX_CALLBACK_DATA);
+        std::fs::remove_file(&file).ok();
+    }
+
+    #[test]
+    fn test_button_press_becomes_event() {
+        let update: TelegramUpdate = serde_json::from_value(serde_json::json!({
+            "update_id": 2,
+            "callback_query": {
+                "id": "q1",
+                "from": {"id": 42, "is_bot": false, "first_name": "Lan"},
+                "data": "btn:Yes",
+                "message": {
+                    "message_id": 9,
+                    "chat": {"id": 42, "type": "private"},
+                    "date": 0,
+                    "text": "Proceed?",
+                    "reply_markup": {"inline_keyboard": [[{"text": "Yes", "callback_data": "btn:Yes"}]]}
+                }
+            }
+        }))
+        .unwrap();
+        let msg = update.to_incoming().unwrap();
+        let event: serde_json::Value = serde_json::from_str(&msg.content).unwrap();
+        assert_eq!(event["event"], "button_pressed");
+        assert_eq!(event["button"], "Yes");
+        assert_eq!(msg.thread_id, "42");
+
+        // Approval buttons aren't agent buttons
+        let mut approval = update.clone();
+        approval.callback_query.as_mut().unwrap().data = Some("approve:abc".into());
+        assert!(approval.to_incoming().is_none());
+    }
+}
@@ -80,6 +80,7 @@ impl WebhookChannel {
             thread_type: ThreadType::Direct,
             timestamp: chrono::Utc::now(),
             reply_to: None,
+            images: vec![],
         })
     }
 }
@@ -2,6 +2,7 @@
 //!
 //! Uses the official WhatsApp Business Platform (Cloud API) for messaging.
 //! Requires: Access Token + Phone Number ID from Meta Business Suite.
+//! Messages are sent through [`WhatsAppClient`].
 
 use async_trait::async_trait;
 use bizclaw_core::error::{BizClawError, Result};
@@ -10,6 +11,8 @@ use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 
+use crate::whatsapp_client::WhatsAppClient;
+
 /// WhatsApp Business channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(Default)]
@@ -30,98 +33,28 @@ pub struct WhatsAppConfig {
 /// WhatsApp Business channel implementation.
 pub struct WhatsAppChannel {
     config: WhatsAppConfig,
-    client: reqwest::Client,
+    client: WhatsAppClient,
     connected: bool,
 }
 
 impl WhatsAppChannel {
     pub fn new(config: WhatsAppConfig) -> Self {
+        let client = WhatsAppClient::new(&config.access_token, &config.phone_number_id);
         Self {
             config,
-            client: reqwest::Client::new(),
+            client,
             connected: false,
         }
     }
 
-    /// Send a text message via WhatsApp Cloud API.
-    async fn send_text_message(&self, to: &str, text: &str) -> Result<String> {
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}/messages",
-            self.config.phone_number_id
-        );
-
-        let body = serde_json::json!({
-            "messaging_product": "whatsapp",
-            "recipient_type": "individual",
-            "to": to,
-            "type": "text",
-            "text": {
-                "preview_url": false,
-                "body": text
-            }
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.access_token),
-            )
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| BizClawError::Channel(format!("WhatsApp API request failed: {e}")))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(BizClawError::Channel(format!(
-                "WhatsApp API error {}: {}",
-                status, error_text
-            )));
-        }
-
-        let result: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| BizClawError::Channel(format!("Invalid WhatsApp response: {e}")))?;
-
-        let msg_id = result["messages"][0]["id"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-
-        tracing::debug!("WhatsApp message sent: {} → {}", msg_id, to);
-        Ok(msg_id)
+    /// The Cloud API client, for template and media messages.
+    pub fn client(&self) -> &WhatsAppClient {
+        &self.client
     }
 
     /// Mark a message as read.
     pub async fn mark_as_read(&self, message_id: &str) -> Result<()> {
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}/messages",
-            self.config.phone_number_id
-        );
-
-        let body = serde_json::json!({
-            "messaging_product": "whatsapp",
-            "status": "read",
-            "message_id": message_id
-        });
-
-        self.client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.access_token),
-            )
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| BizClawError::Channel(format!("WhatsApp mark-read failed: {e}")))?;
-
-        Ok(())
+        self.client.mark_as_read(message_id).await
     }
 }
 
@@ -143,37 +76,12 @@ impl Channel for WhatsAppChannel {
             ));
         }
 
-        // Verify token by checking phone number
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}",
+        self.client.verify().await?;
+        self.connected = true;
+        tracing::info!(
+            "WhatsApp Business: connected (phone_id={})",
             self.config.phone_number_id
         );
-
-        let response = self
-            .client
-            .get(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.config.access_token),
-            )
-            .send()
-            .await
-            .map_err(|e| BizClawError::Channel(format!("WhatsApp verification failed: {e}")))?;
-
-        if response.status().is_success() {
-            self.connected = true;
-            tracing::info!(
-                "WhatsApp Business: connected (phone_id={})",
-                self.config.phone_number_id
-            );
-        } else {
-            let text = response.text().await.unwrap_or_default();
-            return Err(BizClawError::AuthFailed(format!(
-                "WhatsApp token verification failed: {}",
-                text
-            )));
-        }
-
         Ok(())
     }
 
@@ -195,9 +103,9 @@ impl Channel for WhatsAppChannel {
     }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
-        self.send_text_message(&message.thread_id, &message.content)
-            .await?;
-        Ok(())
+        self.client
+            .reply(&message.thread_id, &message.content, message.reply_to.as_deref())
+            .await
     }
 
     async fn send_typing(&self, _thread_id: &str) -> Result<()> {
@@ -0,0 +1,485 @@
+//! WhatsApp Cloud API client — text, template and media messages.
+//!
+//! Shared by the WhatsApp channel, the gateway webhook handler and the
+//! scheduler's notification dispatch. Free-form messages (text, media) can
+//! only be sent within 24 hours of the user's last message; outside that
+//! window WhatsApp only accepts approved template messages.
+
+use std::path::{Path, PathBuf};
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::types::IncomingMessage;
+
+use crate::telegram::split_text;
+
+/// Graph API base URL.
+const GRAPH_API: &str = "https://graph.facebook.com/v21.0";
+
+/// Longest text message body WhatsApp accepts.
+pub const MAX_TEXT_LEN: usize = 4096;
+
+/// WhatsApp Cloud API client for one business phone number.
+#[derive(Clone)]
+pub struct WhatsAppClient {
+    access_token: String,
+    phone_number_id: String,
+    client: reqwest::Client,
+}
+
+/// An approved message template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    /// Template name, as approved in WhatsApp Manager.
+    pub name: String,
+    /// Language code of the approved translation (e.g. `en_US`, `vi`).
+    pub language: String,
+    /// Values for the body's `{{1}}`, `{{2}}`, … placeholders.
+    pub body_params: Vec<String>,
+}
+
+/// Media message types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Document,
+}
+
+impl MediaKind {
+    /// The API's name for the type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Document => "document",
+        }
+    }
+
+    /// Image for image file extensions, document otherwise.
+    pub fn for_path(path: &Path) -> Self {
+        if mime_type(path).starts_with("image/") {
+            Self::Image
+        } else {
+            Self::Document
+        }
+    }
+}
+
+/// Where WhatsApp gets the media from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaSource {
+    /// Public HTTPS URL WhatsApp downloads.
+    Link(String),
+    /// Id returned by [`WhatsAppClient::upload_media`].
+    Id(String),
+}
+
+/// A media message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Media {
+    pub kind: MediaKind,
+    pub source: MediaSource,
+    pub caption: Option<String>,
+    /// File name shown for documents.
+    pub filename: Option<String>,
+}
+
+/// A message received on the webhook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundMessage {
+    /// WhatsApp message id (`wamid.…`).
+    pub id: String,
+    /// Sender's phone number.
+    pub from: String,
+    /// Message text, media caption, or a `[image]` / `[document: name]`
+    /// placeholder for media without a caption.
+    pub text: String,
+    /// Media attached to the message: its type and media id.
+    pub media: Option<(MediaKind, String)>,
+}
+
+impl InboundMessage {
+    pub fn to_incoming(&self) -> IncomingMessage {
+        IncomingMessage {
+            channel: "whatsapp".into(),
+            thread_id: self.from.clone(),
+            sender_id: self.from.clone(),
+            sender_name: None,
+            content: self.text.clone(),
+            thread_type: bizclaw_core::types::ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: Some(self.id.clone()),
+            images: vec![],
+        }
+    }
+}
+
+impl WhatsAppClient {
+    pub fn new(access_token: &str, phone_number_id: &str) -> Self {
+        Self {
+            access_token: access_token.to_string(),
+            phone_number_id: phone_number_id.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Check the token can read the phone number.
+    pub async fn verify(&self) -> Result<()> {
+        let response = self
+            .client
+            .get(format!("{GRAPH_API}/{}", self.phone_number_id))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp verification failed: {e}")))?;
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::AuthFailed(format!(
+                "WhatsApp token verification failed: {text}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Send a text message, split into several if too long. `reply_to`
+    /// quotes a received message. Returns the last message id.
+    pub async fn send_text(&self, to: &str, text: &str, reply_to: Option<&str>) -> Result<String> {
+        let mut id = String::new();
+        for (i, chunk) in split_text(text, MAX_TEXT_LEN).iter().enumerate() {
+            let context = if i == 0 { reply_to } else { None };
+            id = self.post_message(text_payload(to, chunk, context)).await?;
+        }
+        Ok(id)
+    }
+
+    /// Send an approved template message. Works outside the 24-hour window.
+    pub async fn send_template(&self, to: &str, template: &Template) -> Result<String> {
+        self.post_message(template_payload(to, template)).await
+    }
+
+    /// Send an image or document.
+    pub async fn send_media(&self, to: &str, media: &Media) -> Result<String> {
+        self.post_message(media_payload(to, media)).await
+    }
+
+    /// Upload a local file; returns the media id to send it with.
+    pub async fn upload_media(&self, path: &Path) -> Result<String> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Read {}: {e}", path.display())))?;
+        let mime = mime_type(path);
+        let (content_type, body) = crate::telegram::multipart_body(
+            &[("messaging_product", "whatsapp".into()), ("type", mime.into())],
+            "file",
+            &file_name(path),
+            mime,
+            &bytes,
+        );
+        let response = self
+            .client
+            .post(format!("{GRAPH_API}/{}/media", self.phone_number_id))
+            .bearer_auth(&self.access_token)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp media upload failed: {e}")))?;
+        let result = read_response(response).await?;
+        result["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| BizClawError::Channel(format!("WhatsApp media upload: no id in {result}")))
+    }
+
+    /// Upload a local file and send it as an image or document.
+    pub async fn send_file(&self, to: &str, path: &Path, caption: Option<&str>) -> Result<String> {
+        let kind = MediaKind::for_path(path);
+        let id = self.upload_media(path).await?;
+        self.send_media(
+            to,
+            &Media {
+                kind,
+                source: MediaSource::Id(id),
+                caption: caption.map(str::to_string),
+                filename: (kind == MediaKind::Document).then(|| file_name(path)),
+            },
+        )
+        .await
+    }
+
+    /// Send an agent answer: the text, then any local files it links to
+    /// (`![chart](/tmp/chart.png)`, `[report](file:///tmp/q3.pdf)`).
+    pub async fn reply(&self, to: &str, answer: &str, reply_to: Option<&str>) -> Result<()> {
+        let (text, files) = split_attachments(answer);
+        if !text.is_empty() {
+            self.send_text(to, &text, reply_to).await?;
+        }
+        for path in files {
+            self.send_file(to, &path, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark a received message as read.
+    pub async fn mark_as_read(&self, message_id: &str) -> Result<()> {
+        self.client
+            .post(self.messages_url())
+            .bearer_auth(&self.access_token)
+            .json(&serde_json::json!({
+                "messaging_product": "whatsapp",
+                "status": "read",
+                "message_id": message_id
+            }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp mark-read failed: {e}")))?;
+        Ok(())
+    }
+
+    fn messages_url(&self) -> String {
+        format!("{GRAPH_API}/{}/messages", self.phone_number_id)
+    }
+
+    /// POST to the messages endpoint; returns the sent message id.
+    async fn post_message(&self, body: serde_json::Value) -> Result<String> {
+        let response = self
+            .client
+            .post(self.messages_url())
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp API request failed: {e}")))?;
+        let result = read_response(response).await?;
+        let msg_id = result["messages"][0]["id"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        tracing::debug!("WhatsApp {} message sent: {} → {}", body["type"], msg_id, body["to"]);
+        Ok(msg_id)
+    }
+}
+
+async fn read_response(response: reqwest::Response) -> Result<serde_json::Value> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(BizClawError::Channel(format!(
+            "WhatsApp API error {status}: {error_text}"
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| BizClawError::Channel(format!("Invalid WhatsApp response: {e}")))
+}
+
+fn text_payload(to: &str, text: &str, reply_to: Option<&str>) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "messaging_product": "whatsapp",
+        "recipient_type": "individual",
+        "to": to,
+        "type": "text",
+        "text": { "preview_url": false, "body": text },
+    });
+    if let Some(id) = reply_to {
+        body["context"] = serde_json::json!({ "message_id": id });
+    }
+    body
+}
+
+fn template_payload(to: &str, template: &Template) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "messaging_product": "whatsapp",
+        "to": to,
+        "type": "template",
+        "template": {
+            "name": template.name,
+            "language": { "code": template.language },
+        },
+    });
+    if !template.body_params.is_empty() {
+        let params: Vec<serde_json::Value> = template
+            .body_params
+            .iter()
+            .map(|p| serde_json::json!({ "type": "text", "text": template_param(p) }))
+            .collect();
+        body["template"]["components"] = serde_json::json!([{ "type": "body", "parameters": params }]);
+    }
+    body
+}
+
+fn media_payload(to: &str, media: &Media) -> serde_json::Value {
+    let mut object = match &media.source {
+        MediaSource::Link(url) => serde_json::json!({ "link": url }),
+        MediaSource::Id(id) => serde_json::json!({ "id": id }),
+    };
+    if let Some(caption) = &media.caption {
+        object["caption"] = caption.as_str().into();
+    }
+    if media.kind == MediaKind::Document
+        && let Some(filename) = &media.filename
+    {
+        object["filename"] = filename.as_str().into();
+    }
+    let kind = media.kind.as_str();
+    serde_json::json!({
+        "messaging_product": "whatsapp",
+        "recipient_type": "individual",
+        "to": to,
+        "type": kind,
+        kind: object,
+    })
+}
+
+/// Template parameters can't hold newlines, tabs or more than four
+/// consecutive spaces: fold all whitespace runs into single spaces.
+fn template_param(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Messages in a webhook payload. Text, image and document messages are
+/// kept; reactions, statuses and other types are skipped.
+pub fn parse_webhook(body: &serde_json::Value) -> Vec<InboundMessage> {
+    let mut messages = Vec::new();
+    let entries = body["entry"].as_array().into_iter().flatten();
+    let changes = entries.flat_map(|e| e["changes"].as_array().into_iter().flatten());
+    for change in changes {
+        for msg in change["value"]["messages"].as_array().into_iter().flatten() {
+            let (text, media) = match msg["type"].as_str().unwrap_or("") {
+                "text" => (msg["text"]["body"].as_str().unwrap_or("").to_string(), None),
+                "image" => {
+                    let image = &msg["image"];
+                    let text = image["caption"].as_str().unwrap_or("[image]").to_string();
+                    (text, image["id"].as_str().map(|id| (MediaKind::Image, id.to_string())))
+                }
+                "document" => {
+                    let document = &msg["document"];
+                    let text = match document["caption"].as_str() {
+                        Some(caption) => caption.to_string(),
+                        None => format!("[document: {}]", document["filename"].as_str().unwrap_or("file")),
+                    };
+                    (text, document["id"].as_str().map(|id| (MediaKind::Document, id.to_string())))
+                }
+                _ => continue,
+            };
+            if text.is_empty() {
+                continue;
+            }
+            messages.push(InboundMessage {
+                id: msg["id"].as_str().unwrap_or("").to_string(),
+                from: msg["from"].as_str().unwrap_or("").to_string(),
+                text,
+                media,
+            });
+        }
+    }
+    messages
+}
+
+/// Split an answer into text and the existing local files it links to as
+/// markdown images or links (`/abs/path` or `file:///abs/path`). Images are
+/// removed from the text, links keep their label.
+fn split_attachments(answer: &str) -> (String, Vec<PathBuf>) {
+    let file_re = regex::Regex::new(r"(!?)\[([^\]]*)\]\((?:file://)?(/[^)\s]+)\)").unwrap();
+    let mut files = Vec::new();
+    let text = file_re.replace_all(answer, |c: &regex::Captures| {
+        let path = PathBuf::from(&c[3]);
+        if !path.is_file() {
+            return c[0].to_string();
+        }
+        if !files.contains(&path) {
+            files.push(path);
+        }
+        if c[1].is_empty() { c[2].to_string() } else { String::new() }
+    });
+    (text.trim().to_string(), files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".into())
+}
+
+/// MIME type for the media types WhatsApp accepts, by extension.
+fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_and_media_payloads() {
+        let template = Template {
+            name: "task_reminder".into(),
+            language: "vi".into(),
+            body_params: vec!["Backup".into(), "Backup failed\n\n  on  db-1".into()],
+        };
+        let body = template_payload("84901234567", &template);
+        assert_eq!(body["type"], "template");
+        assert_eq!(body["template"]["language"]["code"], "vi");
+        let params = &body["template"]["components"][0]["parameters"];
+        assert_eq!(params[1]["text"], "Backup failed on db-1");
+
+        let media = Media {
+            kind: MediaKind::Document,
+            source: MediaSource::Link("https://example.com/q3.pdf".into()),
+            caption: Some("Q3 report".into()),
+            filename: Some("q3.pdf".into()),
+        };
+        let body = media_payload("84901234567", &media);
+        assert_eq!(body["type"], "document");
+        assert_eq!(body["document"]["link"], "https://example.com/q3.pdf");
+        assert_eq!(body["document"]["filename"], "q3.pdf");
+
+        let body = text_payload("84901234567", "hi", Some("wamid.1"));
+        assert_eq!(body["context"]["message_id"], "wamid.1");
+        assert_eq!(MediaKind::for_path(Path::new("/tmp/chart.PNG")), MediaKind::Image);
+        assert_eq!(MediaKind::for_path(Path::new("/tmp/q3.pdf")), MediaKind::Document);
+    }
+
+    #[test]
+    fn test_parse_webhook_and_attachments() {
+        let body = serde_json::json!({"entry": [{"changes": [{"value": {"messages": [
+            {"id": "wamid.1", "from": "849", "type": "text", "text": {"body": "Xin chào"}},
+            {"id": "wamid.2", "from": "849", "type": "image", "image": {"id": "m1"}},
+            {"id": "wamid.3", "from": "849", "type": "document",
+             "document": {"id": "m2", "filename": "hd.pdf", "caption": "Hóa đơn"}},
+            {"id": "wamid.4", "from": "849", "type": "reaction", "reaction": {"emoji": "👍"}},
+        ]}}]}]});
+        let messages = parse_webhook(&body);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].text, "Xin chào");
+        assert_eq!(messages[1].text, "[image]");
+        assert_eq!(messages[1].media, Some((MediaKind::Image, "m1".into())));
+        assert_eq!(messages[2].text, "Hóa đơn");
+        assert_eq!(messages[2].to_incoming().reply_to.as_deref(), Some("wamid.3"));
+
+        let file = std::env::temp_dir().join(format!("bizclaw-wa-{}.pdf", std::process::id()));
+        std::fs::write(&file, b"%PDF").unwrap();
+        let answer = format!("Here is the [report]({}) and ![x](/no/such.png)", file.display());
+        let (text, files) = split_attachments(&answer);
+        assert_eq!(text, "Here is the report and ![x](/no/such.png)");
+        assert_eq!(files, vec![file.clone()]);
+        std::fs::remove_file(&file).ok();
+    }
+}
@@ -9,4 +9,6 @@ pub mod groups;
 pub mod listener;
 pub mod messaging;
 pub mod models;
+/// Zalo Official Account API client (openapi.zalo.me).
+pub mod oa;
 pub mod session;
@@ -0,0 +1,473 @@
+//! Zalo Official Account API — OA token auth, webhook events, messages.
+//! Based on https://developers.zalo.me/docs/official-account
+//!
+//! Access tokens last 25 hours and are renewed with the refresh token,
+//! which works only once: every refresh returns a new pair, saved to
+//! `token_path` so restarts keep working.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bizclaw_core::config::ZaloOfficialConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::types::{ImageInput, IncomingMessage, ThreadType};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// OA OpenAPI base URL.
+const OA_API_BASE: &str = "https://openapi.zalo.me";
+
+/// Token endpoint for OA access tokens.
+const OA_TOKEN_URL: &str = "https://oauth.zaloapp.com/v4/oa/access_token";
+
+/// Longest text an OA message can hold.
+pub const MAX_TEXT_LEN: usize = 2000;
+
+/// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Webhook requests older than this are rejected (replay protection).
+const MAX_EVENT_AGE_SECS: i64 = 600;
+
+/// OA access and refresh tokens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OaTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix time the access token expires (0 = unknown, refresh first).
+    #[serde(default)]
+    pub expires_at: i64,
+}
+
+/// OA profile from `getoa`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaInfo {
+    pub oa_id: String,
+    pub name: String,
+}
+
+/// An image to send: a public URL or an uploaded attachment.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OaImage {
+    Url(String),
+    AttachmentId(String),
+}
+
+/// A user message from a webhook event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OaIncoming {
+    pub msg_id: String,
+    /// Follower's user id (the reply target).
+    pub user_id: String,
+    pub text: String,
+    /// Image URLs sent with the message.
+    pub images: Vec<String>,
+}
+
+impl OaIncoming {
+    pub fn to_incoming(&self) -> IncomingMessage {
+        IncomingMessage {
+            channel: "zalo".into(),
+            thread_id: self.user_id.clone(),
+            sender_id: self.user_id.clone(),
+            sender_name: None,
+            content: self.text.clone(),
+            thread_type: ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: Some(self.msg_id.clone()),
+            images: self.images.iter().map(ImageInput::url).collect(),
+        }
+    }
+}
+
+/// Zalo OA client. Clones share the tokens.
+#[derive(Clone)]
+pub struct ZaloOaClient {
+    client: reqwest::Client,
+    app_id: String,
+    app_secret: String,
+    token_path: PathBuf,
+    tokens: Arc<Mutex<OaTokens>>,
+}
+
+impl ZaloOaClient {
+    /// Client for the configured OA. Tokens saved at `token_path` by an
+    /// earlier refresh win over the ones in the config.
+    pub fn new(config: &ZaloOfficialConfig) -> Self {
+        let token_path = expand_home(&config.token_path);
+        let tokens = std::fs::read_to_string(&token_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<OaTokens>(&s).ok())
+            .filter(|t| !t.refresh_token.is_empty())
+            .unwrap_or_else(|| OaTokens {
+                access_token: config.access_token.clone(),
+                refresh_token: config.refresh_token.clone(),
+                expires_at: 0,
+            });
+        Self {
+            client: reqwest::Client::new(),
+            app_id: config.app_id.clone(),
+            app_secret: config.app_secret.clone(),
+            token_path,
+            tokens: Arc::new(Mutex::new(tokens)),
+        }
+    }
+
+    /// A valid access token, refreshing it first if it is about to expire.
+    /// Without a refresh token the configured access token is used as is.
+    pub async fn access_token(&self) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        let expiring = tokens.expires_at - chrono::Utc::now().timestamp() < REFRESH_MARGIN_SECS;
+        if (tokens.access_token.is_empty() || expiring) && !tokens.refresh_token.is_empty() {
+            *tokens = self.refresh(&tokens.refresh_token).await?;
+            self.save(&tokens);
+        }
+        if tokens.access_token.is_empty() {
+            return Err(BizClawError::AuthFailed(
+                "Zalo OA: no access_token or refresh_token configured".into(),
+            ));
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    /// Exchange a refresh token for a new token pair.
+    async fn refresh(&self, refresh_token: &str) -> Result<OaTokens> {
+        if self.app_id.is_empty() || self.app_secret.is_empty() {
+            return Err(BizClawError::Config(
+                "Zalo OA: app_id and app_secret are needed to refresh tokens".into(),
+            ));
+        }
+        let response = self
+            .client
+            .post(OA_TOKEN_URL)
+            .header("secret_key", &self.app_secret)
+            .form(&[
+                ("refresh_token", refresh_token),
+                ("app_id", self.app_id.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Zalo OA token refresh failed: {e}")))?;
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Invalid Zalo OA token response: {e}")))?;
+        let tokens = parse_token_response(&body)?;
+        tracing::info!("Zalo OA: access token refreshed");
+        Ok(tokens)
+    }
+
+    fn save(&self, tokens: &OaTokens) {
+        if let Some(dir) = self.token_path.parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        let json = serde_json::to_string_pretty(tokens).unwrap_or_default();
+        if let Err(e) = std::fs::write(&self.token_path, json) {
+            tracing::warn!("Zalo OA: couldn't save tokens to {}: {e}", self.token_path.display());
+        }
+    }
+
+    /// OA profile — also checks the token works.
+    /// API: GET /v2.0/oa/getoa
+    pub async fn get_oa(&self) -> Result<OaInfo> {
+        let token = self.access_token().await?;
+        let response = self
+            .client
+            .get(format!("{OA_API_BASE}/v2.0/oa/getoa"))
+            .header("access_token", token)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("getoa failed: {e}")))?;
+        let data = read_result(response, "getoa").await?;
+        Ok(OaInfo {
+            oa_id: data["oa_id"].as_str().unwrap_or("").to_string(),
+            name: data["name"].as_str().unwrap_or("").to_string(),
+        })
+    }
+
+    /// Send a customer-service text message, split if too long. Returns the
+    /// last message id.
+    /// API: POST /v3.0/oa/message/cs
+    pub async fn send_text(&self, user_id: &str, text: &str) -> Result<String> {
+        let mut msg_id = String::new();
+        for chunk in crate::telegram::split_text(text, MAX_TEXT_LEN) {
+            msg_id = self
+                .send_cs(serde_json::json!({
+                    "recipient": { "user_id": user_id },
+                    "message": { "text": chunk },
+                }))
+                .await?;
+        }
+        Ok(msg_id)
+    }
+
+    /// Send an image with an optional caption.
+    /// API: POST /v3.0/oa/message/cs (media template)
+    pub async fn send_image(&self, user_id: &str, image: &OaImage, caption: Option<&str>) -> Result<String> {
+        self.send_cs(image_payload(user_id, image, caption)).await
+    }
+
+    /// Upload a local image (jpg/png, up to 1 MB); returns its attachment id.
+    /// API: POST /v2.0/oa/upload/image
+    pub async fn upload_image(&self, path: &Path) -> Result<String> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Read {}: {e}", path.display())))?;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image.jpg".into());
+        let file_type = if file_name.to_lowercase().ends_with(".png") { "image/png" } else { "image/jpeg" };
+        let (content_type, body) = crate::telegram::multipart_body(&[], "file", &file_name, file_type, &bytes);
+        let token = self.access_token().await?;
+        let response = self
+            .client
+            .post(format!("{OA_API_BASE}/v2.0/oa/upload/image"))
+            .header("access_token", token)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("OA image upload failed: {e}")))?;
+        let data = read_result(response, "OA image upload").await?;
+        data["attachment_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| BizClawError::Channel("OA image upload: no attachment_id".into()))
+    }
+
+    /// Send an agent answer: the text, then the images it shows as markdown
+    /// images — remote ones by URL, local files uploaded first.
+    pub async fn reply(&self, user_id: &str, answer: &str) -> Result<()> {
+        let (text, images) = split_images(answer);
+        if !text.is_empty() {
+            self.send_text(user_id, &text).await?;
+        }
+        for image in images {
+            let image = match image {
+                OaImage::Url(path) if !path.starts_with("http") => {
+                    OaImage::AttachmentId(self.upload_image(Path::new(&path)).await?)
+                }
+                other => other,
+            };
+            self.send_image(user_id, &image, None).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_cs(&self, body: serde_json::Value) -> Result<String> {
+        let token = self.access_token().await?;
+        let response = self
+            .client
+            .post(format!("{OA_API_BASE}/v3.0/oa/message/cs"))
+            .header("access_token", token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("OA send failed: {e}")))?;
+        let data = read_result(response, "OA send").await?;
+        Ok(data["message_id"].as_str().unwrap_or("").to_string())
+    }
+}
+
+/// The `data` of an OpenAPI response, or its error.
+async fn read_result(response: reqwest::Response, what: &str) -> Result<serde_json::Value> {
+    let result: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| BizClawError::Channel(format!("{what} response error: {e}")))?;
+    if result["error"].as_i64().unwrap_or(-1) != 0 {
+        return Err(BizClawError::Channel(format!(
+            "{what} error {}: {}",
+            result["error"],
+            result["message"].as_str().unwrap_or("unknown")
+        )));
+    }
+    Ok(result["data"].clone())
+}
+
+fn parse_token_response(body: &serde_json::Value) -> Result<OaTokens> {
+    let (Some(access_token), Some(refresh_token)) =
+        (body["access_token"].as_str(), body["refresh_token"].as_str())
+    else {
+        return Err(BizClawError::AuthFailed(format!(
+            "Zalo OA token refresh rejected: {}",
+            body["error_description"].as_str().or(body["error_name"].as_str()).unwrap_or("unknown error")
+        )));
+    };
+    // expires_in comes as a string of seconds
+    let expires_in = body["expires_in"]
+        .as_i64()
+        .or_else(|| body["expires_in"].as_str().and_then(|s| s.parse().ok()))
+        .unwrap_or(90_000);
+    Ok(OaTokens {
+        access_token: access_token.to_string(),
+        refresh_token: refresh_token.to_string(),
+        expires_at: chrono::Utc::now().timestamp() + expires_in,
+    })
+}
+
+fn image_payload(user_id: &str, image: &OaImage, caption: Option<&str>) -> serde_json::Value {
+    let element = match image {
+        OaImage::Url(url) => serde_json::json!({ "media_type": "image", "url": url }),
+        OaImage::AttachmentId(id) => serde_json::json!({ "media_type": "image", "attachment_id": id }),
+    };
+    let mut message = serde_json::json!({
+        "attachment": {
+            "type": "template",
+            "payload": { "template_type": "media", "elements": [element] },
+        },
+    });
+    if let Some(caption) = caption {
+        message["text"] = caption.into();
+    }
+    serde_json::json!({ "recipient": { "user_id": user_id }, "message": message })
+}
+
+/// Split markdown images (`![alt](https://…)`, `![alt](/abs/file.png)`)
+/// out of an answer. Local paths come back as `Url` with the path, and
+/// only when the file exists; others stay in the text.
+fn split_images(answer: &str) -> (String, Vec<OaImage>) {
+    let image_re = regex::Regex::new(r"!\[[^\]]*\]\((?:file://)?((?:https?://|/)[^)\s]+)\)").unwrap();
+    let mut images = Vec::new();
+    let text = image_re.replace_all(answer, |c: &regex::Captures| {
+        let target = &c[1];
+        if !target.starts_with("http") && !Path::new(target).is_file() {
+            return c[0].to_string();
+        }
+        images.push(OaImage::Url(target.to_string()));
+        String::new()
+    });
+    (text.trim().to_string(), images)
+}
+
+/// Check a webhook request's `X-ZEvent-Signature` header:
+/// `mac=sha256(app_id + body + timestamp + oa_secret_key)`, where
+/// `timestamp` is the event's own field (milliseconds).
+pub fn verify_signature(app_id: &str, oa_secret_key: &str, body: &str, signature: &str) -> bool {
+    use sha2::Digest;
+
+    if oa_secret_key.is_empty() {
+        return false;
+    }
+    let Some(mac) = signature.trim().strip_prefix("mac=") else {
+        return false;
+    };
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(body) else {
+        return false;
+    };
+    let timestamp = match &event["timestamp"] {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let Ok(millis) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if (chrono::Utc::now().timestamp_millis() - millis).abs() > MAX_EVENT_AGE_SECS * 1000 {
+        return false;
+    }
+    let digest = sha2::Sha256::digest(format!("{app_id}{body}{timestamp}{oa_secret_key}").as_bytes());
+    let expected: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    expected.eq_ignore_ascii_case(mac)
+}
+
+/// The user message in a webhook event: text, image and file events.
+/// Other events (follow, seen, OA-sent messages…) give `None`.
+pub fn parse_event(event: &serde_json::Value) -> Option<OaIncoming> {
+    let message = &event["message"];
+    let attachments = message["attachments"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let caption = message["text"].as_str().unwrap_or("").trim().to_string();
+    let (text, images) = match event["event_name"].as_str()? {
+        "user_send_text" => (caption, Vec::new()),
+        "user_send_image" => {
+            let images: Vec<String> = attachments
+                .iter()
+                .filter_map(|a| a["payload"]["url"].as_str().map(str::to_string))
+                .collect();
+            let text = if caption.is_empty() { "[image]".to_string() } else { caption };
+            (text, images)
+        }
+        "user_send_file" => {
+            let names: Vec<String> = attachments
+                .iter()
+                .map(|a| {
+                    let payload = &a["payload"];
+                    format!(
+                        "[file: {}]({})",
+                        payload["name"].as_str().unwrap_or("file"),
+                        payload["url"].as_str().unwrap_or("")
+                    )
+                })
+                .collect();
+            (names.join("\n"), Vec::new())
+        }
+        _ => return None,
+    };
+    if text.is_empty() {
+        return None;
+    }
+    Some(OaIncoming {
+        msg_id: message["msg_id"].as_str().unwrap_or("").to_string(),
+        user_id: event["sender"]["id"].as_str()?.to_string(),
+        text,
+        images,
+    })
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME")
+            .map(|h| PathBuf::from(h).join(rest))
+            .unwrap_or_else(|_| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+
+    #[test]
+    fn test_verify_signature_and_parse_event() {
+        let now = chrono::Utc::now().timestamp_millis();
+        let body = format!(
+            r#"{{"app_id":"360","event_name":"user_send_image","timestamp":"{now}",
+"sender":{{"id":"2468"}},"recipient":{{"id":"1357"}},
+"message":{{"msg_id":"m1","attachments":[{{"type":"image","payload":{{"url":"https://z.example/a.jpg"}}}}]}}}}"#
+        );
+        let digest = sha2::Sha256::digest(format!("360{body}{now}oa-secret").as_bytes());
+        let mac: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+        assert!(verify_signature("360", "oa-secret", &body, &format!("mac={mac}")));
+        assert!(!verify_signature("360", "other", &body, &format!("mac={mac}")));
+        assert!(!verify_signature("360", "oa-secret", &body, &mac));
+
+        let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let msg = parse_event(&event).unwrap();
+        assert_eq!(msg.user_id, "2468");
+        assert_eq!(msg.text, "[image]");
+        assert_eq!(msg.to_incoming().images, vec![ImageInput::url("https://z.example/a.jpg")]);
+
+        let follow = serde_json::json!({"event_name": "follow", "follower": {"id": "2468"}});
+        assert!(parse_event(&follow).is_none());
+    }
+
+    #[test]
+    fn test_token_response_and_image_payload() {
+        let body = serde_json::json!({"access_token": "a2", "refresh_token": "r2", "expires_in": "90000"});
+        let tokens = parse_token_response(&body).unwrap();
+        assert_eq!(tokens.refresh_token, "r2");
+        assert!(tokens.expires_at > chrono::Utc::now().timestamp() + 89_000);
+        let err = serde_json::json!({"error": -14014, "error_description": "Invalid refresh token"});
+        assert!(parse_token_response(&err).unwrap_err().to_string().contains("Invalid refresh token"));
+
+        let (text, images) = split_images("Menu hôm nay:\n![menu](https://z.example/menu.jpg)\n![x](/no/such.png)");
+        assert_eq!(text, "Menu hôm nay:\n\n![x](/no/such.png)");
+        assert_eq!(images, vec![OaImage::Url("https://z.example/menu.jpg".into())]);
+
+        let payload = image_payload("2468", &OaImage::AttachmentId("att1".into()), Some("Menu"));
+        assert_eq!(payload["message"]["text"], "Menu");
+        assert_eq!(payload["message"]["attachment"]["payload"]["elements"][0]["attachment_id"], "att1");
+    }
+}
@@ -14,6 +14,7 @@ use tokio_stream::Stream;
 
 use self::client::auth::{ZaloAuth, ZaloCredentials};
 use self::client::messaging::{ThreadType as ZaloThreadType, ZaloMessaging};
+use self::client::oa::ZaloOaClient;
 use self::client::session::SessionManager;
 
 /// Zalo channel implementation — routes to Personal or OA mode.
@@ -24,6 +25,8 @@ pub struct ZaloChannel {
     session: SessionManager,
     connected: bool,
     cookie: Option<String>,
+    /// OA client, in "official" mode.
+    oa: Option<ZaloOaClient>,
 }
 
 impl ZaloChannel {
@@ -46,6 +49,7 @@ impl ZaloChannel {
             session: SessionManager::new(),
             connected: false,
             cookie: None,
+            oa: None,
         }
     }
 
@@ -109,8 +113,11 @@ impl Channel for ZaloChannel {
             }
             "official" => {
                 tracing::info!("Zalo OA: connecting via official API...");
+                let oa = ZaloOaClient::new(&self.config.official);
+                let info = oa.get_oa().await?;
+                self.oa = Some(oa);
                 self.connected = true;
-                tracing::info!("Zalo OA: connected (official API requires Zalo OA token)");
+                tracing::info!("Zalo OA: connected as {} ({})", info.name, info.oa_id);
             }
             _ => {
                 return Err(BizClawError::Config(format!(
@@ -142,6 +149,10 @@ impl Channel for ZaloChannel {
     }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        if let Some(oa) = &self.oa {
+            oa.send_text(&message.thread_id, &message.content).await?;
+            return Ok(());
+        }
         let cookie = self
             .cookie
             .as_ref()
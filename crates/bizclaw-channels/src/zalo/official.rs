@@ -1,35 +1,42 @@
 //! Zalo Official Account mode — uses Zalo OA REST API.
 //!
-//! For business accounts via developers.zalo.me.
+//! For business accounts via developers.zalo.me. Incoming messages arrive
+//! on the gateway webhook (`/api/v1/webhook/zalo`).
 
 use async_trait::async_trait;
-use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::config::ZaloOfficialConfig;
+use bizclaw_core::error::Result;
 use bizclaw_core::traits::Channel;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
 use futures::stream::{self, Stream};
 
-use super::client::business::ZaloBusiness;
+use super::client::oa::{OaImage, ZaloOaClient};
 
-/// Zalo OA channel — uses access token.
+/// Zalo OA channel — uses OA access tokens, refreshed as needed.
 pub struct ZaloOfficialChannel {
-    business: ZaloBusiness,
-    access_token: Option<String>,
+    client: ZaloOaClient,
     connected: bool,
 }
 
 impl ZaloOfficialChannel {
-    pub fn new() -> Self {
+    pub fn new(config: &ZaloOfficialConfig) -> Self {
         Self {
-            business: ZaloBusiness::new(),
-            access_token: None,
+            client: ZaloOaClient::new(config),
             connected: false,
         }
     }
 
-    /// Set access token from OA developer portal.
-    pub fn set_access_token(&mut self, token: &str) {
-        self.access_token = Some(token.to_string());
-        self.connected = true;
+    /// The OA API client.
+    pub fn client(&self) -> &ZaloOaClient {
+        &self.client
+    }
+
+    /// Send an image by URL to a follower.
+    pub async fn send_image(&self, user_id: &str, url: &str, caption: Option<&str>) -> Result<()> {
+        self.client
+            .send_image(user_id, &OaImage::Url(url.to_string()), caption)
+            .await?;
+        Ok(())
     }
 }
 
@@ -40,10 +47,9 @@ impl Channel for ZaloOfficialChannel {
     }
 
     async fn connect(&mut self) -> Result<()> {
-        if self.access_token.is_none() {
-            return Err(BizClawError::AuthFailed("Set access_token first".into()));
-        }
-        tracing::info!("Zalo OA channel connected");
+        let oa = self.client.get_oa().await?;
+        self.connected = true;
+        tracing::info!("Zalo OA channel connected: {} ({})", oa.name, oa.oa_id);
         Ok(())
     }
 
@@ -57,22 +63,12 @@ impl Channel for ZaloOfficialChannel {
     }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
-        let token = self
-            .access_token
-            .as_ref()
-            .ok_or_else(|| BizClawError::Channel("No access token".into()))?;
-        self.business
-            .send_oa_message(&message.thread_id, &message.content, token)
-            .await
+        self.client.send_text(&message.thread_id, &message.content).await?;
+        Ok(())
     }
 
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+        // OA messages arrive via the gateway webhook
         Ok(Box::new(stream::pending()))
     }
 }
-
-impl Default for ZaloOfficialChannel {
-    fn default() -> Self {
-        Self::new()
-    }
-}
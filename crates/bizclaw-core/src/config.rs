@@ -1,6 +1,7 @@
 //! BizClaw configuration system.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use crate::error::Result;
@@ -24,6 +25,13 @@ pub struct LlmConfig {
     /// Generation temperature.
     #[serde(default = "default_temperature")]
     pub temperature: f32,
+    /// Ordered providers to retry on when the primary fails with a
+    /// transient, rate-limit or auth error.
+    #[serde(default)]
+    pub fallback_providers: Vec<FallbackProviderConfig>,
+    /// Retry and circuit-breaker policy for the provider chain.
+    #[serde(default)]
+    pub failover: FailoverConfig,
 }
 
 impl Default for LlmConfig {
@@ -34,6 +42,77 @@ impl Default for LlmConfig {
             api_key: String::new(),
             endpoint: String::new(),
             temperature: default_temperature(),
+            fallback_providers: Vec::new(),
+            failover: FailoverConfig::default(),
+        }
+    }
+}
+
+/// A fallback provider entry in `[LLM] fallback_providers`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FallbackProviderConfig {
+    /// Provider name (same values as `llm.provider`).
+    pub provider: String,
+    /// Model to use on this provider. Empty = the provider's default model.
+    #[serde(default)]
+    pub model: String,
+    /// API key override. Empty = resolve from the provider's env vars.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_key: String,
+    /// Endpoint override. Empty = the provider's default endpoint.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub endpoint: String,
+}
+
+/// `[LLM.failover]` — when enabled, the agent talks to one provider chain
+/// (`provider`, then `fallback_providers` in order) that retries transient
+/// errors with exponential backoff and skips providers whose circuit is open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailoverConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Retries on the same provider after a transient or rate-limit error.
+    #[serde(default = "default_failover_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each retry.
+    #[serde(default = "default_failover_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Upper bound for the retry delay.
+    #[serde(default = "default_failover_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Consecutive failures that open a provider's circuit.
+    #[serde(default = "default_failover_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit waits before letting a trial request through.
+    #[serde(default = "default_failover_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_failover_retries() -> u32 {
+    2
+}
+fn default_failover_backoff_ms() -> u64 {
+    500
+}
+fn default_failover_max_backoff_ms() -> u64 {
+    8000
+}
+fn default_failover_failure_threshold() -> u32 {
+    3
+}
+fn default_failover_cooldown_secs() -> u64 {
+    60
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: default_failover_retries(),
+            backoff_ms: default_failover_backoff_ms(),
+            max_backoff_ms: default_failover_max_backoff_ms(),
+            failure_threshold: default_failover_failure_threshold(),
+            cooldown_secs: default_failover_cooldown_secs(),
         }
     }
 }
@@ -58,9 +137,19 @@ pub struct BizClawConfig {
     pub llm: LlmConfig,
     #[serde(default)]
     pub brain: BrainConfig,
+    /// Supervised external llama.cpp server.
+    #[serde(default)]
+    pub llamacpp: LlamaCppConfig,
     #[serde(default)]
     pub memory: MemoryConfig,
     #[serde(default)]
+    pub rag: RagConfig,
+    #[serde(default)]
+    pub knowledge: KnowledgeConfig,
+    /// Autonomous Hands run by the gateway.
+    #[serde(default)]
+    pub hands: HandsConfig,
+    #[serde(default)]
     pub gateway: GatewayConfig,
     #[serde(default)]
     pub autonomy: AutonomyConfig,
@@ -70,6 +159,9 @@ pub struct BizClawConfig {
     pub tunnel: TunnelConfig,
     #[serde(default)]
     pub secrets: SecretsConfig,
+    /// PII filter for messages sent to cloud providers.
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
     #[serde(default)]
     pub identity: Identity,
     #[serde(default)]
@@ -77,9 +169,28 @@ pub struct BizClawConfig {
     /// MCP server configurations.
     #[serde(default)]
     pub mcp_servers: Vec<McpServerEntry>,
+    /// BizClaw's own tools and agents, served to MCP clients.
+    #[serde(default)]
+    pub mcp_export: McpExportConfig,
     /// Quality Gate — optional evaluator for response review.
     #[serde(default)]
     pub quality_gate: Option<QualityGateConfig>,
+    /// Agent selection for channel messages not bound to an agent.
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Values filled in from `${secret:…}` references, so
+    /// [`BizClawConfig::to_toml`] writes the references back.
+    #[serde(skip)]
+    secret_templates: Vec<SecretTemplate>,
+}
+
+/// A string field filled in from `${secret:…}` references.
+#[derive(Debug, Clone)]
+struct SecretTemplate {
+    /// Dotted path of the field, e.g. `mcp_servers.0.headers.Authorization`.
+    path: String,
+    resolved: String,
+    template: String,
 }
 
 fn default_api_key() -> String {
@@ -105,16 +216,24 @@ impl Default for BizClawConfig {
             default_temperature: default_temperature(),
             llm: LlmConfig::default(),
             brain: BrainConfig::default(),
+            llamacpp: LlamaCppConfig::default(),
             memory: MemoryConfig::default(),
+            rag: RagConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            hands: HandsConfig::default(),
             gateway: GatewayConfig::default(),
             autonomy: AutonomyConfig::default(),
             runtime: RuntimeConfig::default(),
             tunnel: TunnelConfig::default(),
             secrets: SecretsConfig::default(),
+            privacy: PrivacyConfig::default(),
             identity: Identity::default(),
             channel: ChannelConfig::default(),
             mcp_servers: vec![],
+            mcp_export: McpExportConfig::default(),
             quality_gate: None,
+            routing: RoutingConfig::default(),
+            secret_templates: Vec::new(),
         }
     }
 }
@@ -147,13 +266,211 @@ impl BizClawConfig {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let content = toml::to_string_pretty(self).map_err(|e| {
-            crate::error::BizClawError::Config(format!("Failed to serialize config: {e}"))
-        })?;
-        std::fs::write(&path, content)?;
+        std::fs::write(&path, self.to_toml()?)?;
         Ok(())
     }
 
+    /// The config as TOML, with values resolved from secrets written back
+    /// as their `${secret:…}` references.
+    pub fn to_toml(&self) -> Result<String> {
+        let serialize_error =
+            |e: toml::ser::Error| crate::error::BizClawError::Config(format!("Failed to serialize config: {e}"));
+        if self.secret_templates.is_empty() {
+            return toml::to_string_pretty(self).map_err(serialize_error);
+        }
+        let mut value = toml::Value::try_from(self).map_err(serialize_error)?;
+        visit_strings(&mut value, "", &mut |path, s| {
+            // A field changed since loading keeps its new value
+            if let Some(t) = self.secret_templates.iter().find(|t| t.path == path && t.resolved == *s) {
+                *s = t.template.clone();
+            }
+        });
+        toml::to_string_pretty(&value).map_err(serialize_error)
+    }
+
+    /// Replace `${secret:<name>}` references in string values with
+    /// `lookup(name)`. Returns the names `lookup` doesn't know; those
+    /// references are left as written.
+    pub fn resolve_secrets(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Vec<String>> {
+        let config_error = |e: String| crate::error::BizClawError::Config(format!("Failed to resolve secrets: {e}"));
+        let mut value = toml::Value::try_from(&*self).map_err(|e| config_error(e.to_string()))?;
+        let mut templates = std::mem::take(&mut self.secret_templates);
+        let mut missing = Vec::new();
+        visit_strings(&mut value, "", &mut |path, s| {
+            if !s.contains(SECRET_REF_PREFIX) {
+                return;
+            }
+            let resolved = expand_secret_refs(s, &lookup, &mut missing);
+            if resolved != *s && !resolved.is_empty() {
+                templates.push(SecretTemplate {
+                    path: path.to_string(),
+                    resolved: resolved.clone(),
+                    template: std::mem::replace(s, resolved),
+                });
+            }
+        });
+        let mut resolved: Self = value.try_into().map_err(|e: toml::de::Error| config_error(e.to_string()))?;
+        resolved.secret_templates = templates;
+        *self = resolved;
+        missing.sort();
+        missing.dedup();
+        Ok(missing)
+    }
+
+    /// The `${secret:…}` text the field at dotted `path`, now holding
+    /// `value`, was resolved from, or `value` itself.
+    pub fn unresolved<'a>(&'a self, path: &str, value: &'a str) -> &'a str {
+        self.secret_templates
+            .iter()
+            .find(|t| t.path == path && t.resolved == value)
+            .map(|t| t.template.as_str())
+            .unwrap_or(value)
+    }
+
+    /// Fields holding credentials, with the secret name each is stored
+    /// under when moved out of the config file.
+    pub fn secret_fields_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut fields: Vec<(String, &mut String)> = vec![
+            ("api_key".into(), &mut self.api_key),
+            ("llm_api_key".into(), &mut self.llm.api_key),
+        ];
+        for fallback in &mut self.llm.fallback_providers {
+            fields.push((format!("{}_api_key", fallback.provider), &mut fallback.api_key));
+        }
+        let channel = &mut self.channel;
+        if let Some(zalo) = &mut channel.zalo {
+            let official = &mut zalo.official;
+            fields.push(("zalo_app_secret".into(), &mut official.app_secret));
+            fields.push(("zalo_oa_secret_key".into(), &mut official.oa_secret_key));
+            fields.push(("zalo_access_token".into(), &mut official.access_token));
+            fields.push(("zalo_refresh_token".into(), &mut official.refresh_token));
+        }
+        if let Some(telegram) = &mut channel.telegram {
+            fields.push(("telegram_bot_token".into(), &mut telegram.bot_token));
+        }
+        if let Some(discord) = &mut channel.discord {
+            fields.push(("discord_bot_token".into(), &mut discord.bot_token));
+        }
+        if let Some(email) = &mut channel.email {
+            fields.push(("email_password".into(), &mut email.password));
+        }
+        if let Some(whatsapp) = &mut channel.whatsapp {
+            fields.push(("whatsapp_access_token".into(), &mut whatsapp.access_token));
+            fields.push(("whatsapp_verify_token".into(), &mut whatsapp.webhook_verify_token));
+        }
+        if let Some(webhook) = &mut channel.webhook {
+            fields.push(("webhook_secret".into(), &mut webhook.secret));
+        }
+        if let Some(sms) = &mut channel.sms {
+            fields.push(("sms_auth_token".into(), &mut sms.auth_token));
+        }
+        for server in &mut self.mcp_servers {
+            let name: String = server
+                .name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                .collect();
+            fields.push((format!("mcp_{name}_bearer_token"), &mut server.bearer_token));
+            if let Some(oauth) = &mut server.oauth {
+                fields.push((format!("mcp_{name}_client_secret"), &mut oauth.client_secret));
+            }
+        }
+        fields
+    }
+
+    /// Problems that would break the agent or make it misbehave (errors),
+    /// and likely mistakes (warnings). Checks values, not connectivity.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let mut error = |field: &str, message: String| issues.push(ConfigIssue::error(field, message));
+
+        if self.default_provider.trim().is_empty() {
+            error("default_provider", "no provider set".into());
+        }
+        if !(0.0..=2.0).contains(&self.default_temperature) {
+            error("default_temperature", format!("{} is outside 0.0–2.0", self.default_temperature));
+        }
+        let base_url = self.api_base_url.trim();
+        if !base_url.is_empty() && !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            error("api_base_url", format!("'{base_url}' is not an http(s) URL"));
+        }
+
+        if self.brain.max_tokens == 0 {
+            error("brain.max_tokens", "must be at least 1".into());
+        }
+        if self.brain.context_length == 0 {
+            error("brain.context_length", "must be at least 1".into());
+        } else if self.brain.max_tokens >= self.brain.context_length {
+            error(
+                "brain.max_tokens",
+                format!("{} leaves no room for the prompt in a {}-token context", self.brain.max_tokens, self.brain.context_length),
+            );
+        }
+        if !(0.0..=2.0).contains(&self.brain.temperature) {
+            error("brain.temperature", format!("{} is outside 0.0–2.0", self.brain.temperature));
+        }
+
+        if !["sqlite", "none"].contains(&self.memory.backend.as_str()) {
+            error("memory.backend", format!("unknown backend '{}' (sqlite, none)", self.memory.backend));
+        }
+        if !["readonly", "supervised", "full"].contains(&self.autonomy.level.as_str()) {
+            error("autonomy.level", format!("unknown level '{}' (readonly, supervised, full)", self.autonomy.level));
+        }
+        if !["pseudonymize", "redact"].contains(&self.privacy.mode.as_str()) {
+            error("privacy.mode", format!("unknown mode '{}' (pseudonymize, redact)", self.privacy.mode));
+        }
+        for kind in &self.privacy.detect {
+            if !["email", "phone", "id_number", "tax_code", "card"].contains(&kind.as_str()) {
+                error("privacy.detect", format!("unknown kind '{kind}' (email, phone, id_number, tax_code, card)"));
+            }
+        }
+        for (field, value) in [
+            ("autonomy.code.timeout_secs", self.autonomy.code.timeout_secs),
+            ("autonomy.code.cpu_secs", self.autonomy.code.cpu_secs),
+            ("autonomy.code.memory_mb", self.autonomy.code.memory_mb),
+        ] {
+            if value == 0 {
+                error(field, "must be at least 1".into());
+            }
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for (i, server) in self.mcp_servers.iter().enumerate() {
+            let field = format!("mcp_servers[{i}]");
+            if server.name.trim().is_empty() {
+                error(&field, "server has no name".into());
+            } else if !names.insert(server.name.as_str()) {
+                error(&field, format!("duplicate server name '{}'", server.name));
+            }
+            let (command, url) = (server.command.trim(), server.url.trim());
+            if !command.is_empty() && !url.is_empty() {
+                error(&field, format!("server '{}' has both a command and a url", server.name));
+            } else if !url.is_empty() && !url.starts_with("http://") && !url.starts_with("https://") {
+                error(&field, format!("server '{}' url must start with http:// or https://", server.name));
+            } else if server.enabled && command.is_empty() && url.is_empty() {
+                error(&field, format!("server '{}' has no command or url", server.name));
+            }
+        }
+
+        for (i, rule) in self.routing.rules.iter().enumerate() {
+            let field = format!("routing.rules[{i}]");
+            if rule.agent.trim().is_empty() {
+                error(&field, "rule names no agent".into());
+            }
+            if rule.keywords.iter().all(|k| k.trim().is_empty()) {
+                error(&field, format!("rule for '{}' has no keywords", rule.agent));
+            }
+        }
+
+        if self.default_model.trim().is_empty() {
+            issues.push(ConfigIssue::warning("default_model", "no model set, the provider's default is used"));
+        }
+        if self.identity.name.trim().is_empty() {
+            issues.push(ConfigIssue::warning("identity.name", "the agent has no name"));
+        }
+        issues
+    }
+
     /// Get the default config path.
     pub fn default_path() -> PathBuf {
         dirs::home_dir()
@@ -170,6 +487,94 @@ impl BizClawConfig {
     }
 }
 
+/// Start of a reference to a stored secret, `${secret:<name>}`.
+pub const SECRET_REF_PREFIX: &str = "${secret:";
+
+/// The config text referring to secret `name`.
+pub fn secret_ref(name: &str) -> String {
+    format!("{SECRET_REF_PREFIX}{name}}}")
+}
+
+/// `text` with each `${secret:<name>}` replaced by `lookup(name)`; unknown
+/// names are added to `missing` and left as written.
+fn expand_secret_refs(text: &str, lookup: &impl Fn(&str) -> Option<String>, missing: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(SECRET_REF_PREFIX) {
+        let after = &rest[start + SECRET_REF_PREFIX.len()..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = after[..end].trim();
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => {
+                missing.push(name.to_string());
+                out.push_str(&rest[start..start + SECRET_REF_PREFIX.len() + end + 1]);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Call `f` on every string in `value`, recursively, with its dotted path
+/// under `path` (array items by index).
+fn visit_strings(value: &mut toml::Value, path: &str, f: &mut impl FnMut(&str, &mut String)) {
+    let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{path}.{key}") };
+    match value {
+        toml::Value::String(s) => f(path, s),
+        toml::Value::Array(items) => {
+            for (i, v) in items.iter_mut().enumerate() {
+                visit_strings(v, &child(&i.to_string()), f);
+            }
+        }
+        toml::Value::Table(table) => table.iter_mut().for_each(|(k, v)| visit_strings(v, &child(k), f)),
+        _ => {}
+    }
+}
+
+/// A problem found by [`BizClawConfig::validate`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigIssue {
+    /// Dotted path of the field ("brain.max_tokens", "mcp_servers[0]").
+    pub field: String,
+    pub message: String,
+    pub severity: IssueSeverity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The config can't be used as is.
+    Error,
+    Warning,
+}
+
+impl ConfigIssue {
+    pub fn error(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+            severity: IssueSeverity::Error,
+        }
+    }
+
+    pub fn warning(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+            severity: IssueSeverity::Warning,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == IssueSeverity::Error
+    }
+}
+
 /// Brain (local LLM) configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrainConfig {
@@ -193,10 +598,156 @@ pub struct BrainConfig {
     pub top_p: f32,
     #[serde(default)]
     pub json_mode: bool,
+    /// Activation precision for the forward pass.
+    #[serde(default)]
+    pub compute_dtype: ComputeDtype,
+    /// Softmax numerics for attention and sampling.
+    #[serde(default)]
+    pub softmax: SoftmaxMode,
+    /// Storage precision of the KV cache.
+    #[serde(default)]
+    pub kv_cache_dtype: KvCacheDtype,
+    /// Warm-up of the mmapped weights after load.
+    #[serde(default)]
+    pub preload: Preload,
+    /// Memory budget (MB) for local models loaded at once; agents that
+    /// request other `.gguf` models evict the least recently used.
+    /// 0 keeps a single model.
+    #[serde(default)]
+    pub model_pool_mb: u32,
+    /// Prompt tokens processed per batched prefill pass (1 = token by token).
+    #[serde(default = "default_prefill_chunk")]
+    pub prefill_chunk: u32,
+    /// Path to a GBNF grammar that constrains all local generation.
+    #[serde(default)]
+    pub grammar_path: Option<String>,
+    /// Inline GBNF grammar; takes precedence over `grammar_path`.
+    #[serde(default)]
+    pub grammar_string: Option<String>,
+    /// Min-p sampling: drop tokens below `min_p` × the top token's
+    /// probability (0 = off).
+    #[serde(default)]
+    pub min_p: f32,
+    /// Mirostat v2 sampling; replaces top-k/top-p/min-p when set.
+    #[serde(default)]
+    pub mirostat: Option<MirostatConfig>,
+    /// Logit bias by text; every token the text resolves to gets the bias.
+    #[serde(default)]
+    pub logit_bias: HashMap<String, f32>,
+    /// Token IDs that are never sampled.
+    #[serde(default)]
+    pub banned_tokens: Vec<u32>,
+    /// Words or strings whose tokens are never sampled.
+    #[serde(default)]
+    pub banned_strings: Vec<String>,
+    /// Keep generating past `context_length` by evicting old KV entries
+    /// (StreamingLLM); otherwise generation stops when the context is full.
+    #[serde(default)]
+    pub streaming_kv: bool,
+    /// Leading tokens never evicted in streaming mode (attention sinks).
+    #[serde(default = "default_kv_sink_tokens")]
+    pub kv_sink_tokens: u32,
+    /// Without `streaming_kv`, a full context keeps the system prompt and
+    /// drops half of the remaining tokens instead of stopping (as llama.cpp).
+    #[serde(default = "bool_true")]
+    pub context_shift: bool,
+    /// LoRA adapters (GGUF) applied on top of the model, in order.
+    #[serde(default)]
+    pub lora_paths: Vec<String>,
+    /// Strength of every LoRA adapter (1.0 = as trained).
+    #[serde(default = "default_lora_scale")]
+    pub lora_scale: f32,
+    /// Trailing layers whose matmuls run on the GPU (needs a build with the
+    /// `gpu` feature). 0 keeps everything on the CPU.
+    #[serde(default)]
+    pub n_gpu_layers: u32,
     #[serde(default)]
     pub fallback: Option<BrainFallback>,
 }
 
+/// Mirostat v2: adaptively truncates the candidate set so the output's
+/// surprise (−log2 p of sampled tokens) tracks a target.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MirostatConfig {
+    /// Target surprise in bits; lower is more focused.
+    #[serde(default = "default_mirostat_tau")]
+    pub tau: f32,
+    /// Learning rate of the running threshold.
+    #[serde(default = "default_mirostat_eta")]
+    pub eta: f32,
+}
+
+impl Default for MirostatConfig {
+    fn default() -> Self {
+        Self {
+            tau: default_mirostat_tau(),
+            eta: default_mirostat_eta(),
+        }
+    }
+}
+
+fn default_mirostat_tau() -> f32 {
+    5.0
+}
+fn default_mirostat_eta() -> f32 {
+    0.1
+}
+
+/// Activation storage precision for local inference.
+///
+/// `bf16` halves the residual stream and attention gather buffers (dot
+/// products still accumulate in f32) at a small accuracy cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeDtype {
+    #[default]
+    F32,
+    Bf16,
+}
+
+/// Softmax numerics for local inference.
+///
+/// `stable` uses log-sum-exp and recovers from NaN/Inf scores instead of
+/// propagating them (sampling falls back to argmax); `stable_f64` also
+/// accumulates attention scores and normalizers in f64.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftmaxMode {
+    #[default]
+    Fast,
+    Stable,
+    StableF64,
+}
+
+/// KV cache storage precision for local inference.
+///
+/// `f16` halves the cache and `q8_0` (8-bit blocks of 32 with an f16 scale)
+/// shrinks it ~3.8x; entries are dequantized to f32 when attention reads them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KvCacheDtype {
+    #[default]
+    F32,
+    F16,
+    Q8_0,
+}
+
+/// Warm-up of the mmapped model weights after load.
+///
+/// `advise` asks the kernel to read the weights ahead (madvise WILLNEED,
+/// returns immediately); `background` touches every weight page from a
+/// helper thread; `eager` does that touch pass before loading returns, so
+/// the first token never waits on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preload {
+    #[default]
+    None,
+    Advise,
+    Background,
+    Eager,
+}
+
 fn bool_true() -> bool {
     true
 }
@@ -212,6 +763,15 @@ fn default_max_tokens() -> u32 {
 fn default_context_length() -> u32 {
     2048
 }
+fn default_prefill_chunk() -> u32 {
+    64
+}
+fn default_kv_sink_tokens() -> u32 {
+    4
+}
+fn default_lora_scale() -> f32 {
+    1.0
+}
 fn default_cache_dir() -> String {
     "~/.bizclaw/cache".into()
 }
@@ -232,6 +792,25 @@ impl Default for BrainConfig {
             temperature: default_temperature(),
             top_p: default_top_p(),
             json_mode: false,
+            compute_dtype: ComputeDtype::F32,
+            softmax: SoftmaxMode::Fast,
+            kv_cache_dtype: KvCacheDtype::F32,
+            preload: Preload::None,
+            model_pool_mb: 0,
+            prefill_chunk: default_prefill_chunk(),
+            grammar_path: None,
+            grammar_string: None,
+            min_p: 0.0,
+            mirostat: None,
+            logit_bias: HashMap::new(),
+            banned_tokens: Vec::new(),
+            banned_strings: Vec::new(),
+            streaming_kv: false,
+            kv_sink_tokens: default_kv_sink_tokens(),
+            context_shift: true,
+            lora_paths: Vec::new(),
+            lora_scale: default_lora_scale(),
+            n_gpu_layers: 0,
             fallback: None,
         }
     }
@@ -250,12 +829,36 @@ pub struct MemoryConfig {
     pub backend: String,
     #[serde(default = "bool_true")]
     pub auto_save: bool,
+    /// Provider that embeds memories for vector search (`"brain"` runs
+    /// locally); `"none"` keeps search keyword-only.
     #[serde(default = "default_embedding_provider")]
     pub embedding_provider: String,
+    /// Weight of cosine similarity in hybrid search scores.
     #[serde(default = "default_vector_weight")]
     pub vector_weight: f32,
+    /// Weight of the (best-scaled) keyword score in hybrid search scores.
     #[serde(default = "default_keyword_weight")]
     pub keyword_weight: f32,
+    /// Entries kept before the lowest-value ones are merged into
+    /// summaries (0 = unlimited).
+    #[serde(default = "default_memory_max_entries")]
+    pub max_entries: usize,
+    /// Days an entry lives unless its metadata sets `ttl_days` (0 = forever).
+    #[serde(default)]
+    pub ttl_days: u32,
+    /// Age in days at which an entry's importance counts half when pruning.
+    #[serde(default = "default_decay_half_life_days")]
+    pub decay_half_life_days: f32,
+    /// Minutes between pruning runs (0 = never prune).
+    #[serde(default = "default_prune_interval_mins")]
+    pub prune_interval_mins: u64,
+    /// Minutes a chat session's conversation stays loaded after its last
+    /// message (0 = until evicted by `max_sessions`).
+    #[serde(default = "default_session_idle_mins")]
+    pub session_idle_mins: u64,
+    /// Chat sessions each agent keeps loaded (0 = unlimited).
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize,
 }
 
 fn default_memory_backend() -> String {
@@ -270,6 +873,21 @@ fn default_vector_weight() -> f32 {
 fn default_keyword_weight() -> f32 {
     0.3
 }
+fn default_memory_max_entries() -> usize {
+    5000
+}
+fn default_decay_half_life_days() -> f32 {
+    30.0
+}
+fn default_prune_interval_mins() -> u64 {
+    60
+}
+fn default_session_idle_mins() -> u64 {
+    60
+}
+fn default_max_sessions() -> usize {
+    500
+}
 
 impl Default for MemoryConfig {
     fn default() -> Self {
@@ -279,6 +897,239 @@ impl Default for MemoryConfig {
             embedding_provider: default_embedding_provider(),
             vector_weight: default_vector_weight(),
             keyword_weight: default_keyword_weight(),
+            max_entries: default_memory_max_entries(),
+            ttl_days: 0,
+            decay_half_life_days: default_decay_half_life_days(),
+            prune_interval_mins: default_prune_interval_mins(),
+            session_idle_mins: default_session_idle_mins(),
+            max_sessions: default_max_sessions(),
+        }
+    }
+}
+
+/// Retrieval pipeline feeding knowledge-base and memory hits into the prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagConfig {
+    /// Built-in retrievers to query, in priority order (`knowledge`, `memory`).
+    #[serde(default = "default_rag_retrievers")]
+    pub retrievers: Vec<String>,
+    /// Hits requested from each retriever.
+    #[serde(default = "default_rag_per_source")]
+    pub per_source: usize,
+    /// Drop hits scoring below this fraction of their source's best hit.
+    #[serde(default)]
+    pub min_score: f32,
+    /// Word-overlap (Jaccard) at which two hits count as duplicates.
+    #[serde(default = "default_rag_dedup_threshold")]
+    pub dedup_threshold: f32,
+    /// Ask the LLM to reorder and filter hits before injection.
+    #[serde(default)]
+    pub rerank: bool,
+    /// Hits injected into the prompt.
+    #[serde(default = "default_rag_max_passages")]
+    pub max_passages: usize,
+    /// Character budget for all injected hits.
+    #[serde(default = "default_rag_max_chars")]
+    pub max_chars: usize,
+    /// Ask for `[n]` markers citing knowledge passages; cited chunks are
+    /// returned with the answer.
+    #[serde(default = "bool_true")]
+    pub citations: bool,
+}
+
+fn default_rag_retrievers() -> Vec<String> {
+    vec!["knowledge".into(), "memory".into()]
+}
+fn default_rag_per_source() -> usize {
+    5
+}
+fn default_rag_dedup_threshold() -> f32 {
+    0.8
+}
+fn default_rag_max_passages() -> usize {
+    8
+}
+fn default_rag_max_chars() -> usize {
+    3500
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            retrievers: default_rag_retrievers(),
+            per_source: default_rag_per_source(),
+            min_score: 0.0,
+            dedup_threshold: default_rag_dedup_threshold(),
+            rerank: false,
+            max_passages: default_rag_max_passages(),
+            max_chars: default_rag_max_chars(),
+            citations: true,
+        }
+    }
+}
+
+/// Knowledge base ingestion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Folder indexed automatically, e.g. `~/bizclaw/docs`: new and
+    /// changed files are imported, deleted ones removed. Empty = off.
+    #[serde(default)]
+    pub watch_dir: String,
+    /// Seconds between checks of `watch_dir`.
+    #[serde(default = "default_knowledge_watch_interval")]
+    pub watch_interval_secs: u64,
+    /// Folder server-side directory imports may read from, e.g.
+    /// `~/bizclaw/import`. Empty = directory imports off.
+    #[serde(default)]
+    pub import_root: String,
+    /// Target chunk length in characters.
+    #[serde(default = "default_knowledge_chunk_size")]
+    pub chunk_size: usize,
+    /// Characters of a chunk repeated at the start of the next one.
+    #[serde(default = "default_knowledge_chunk_overlap")]
+    pub chunk_overlap: usize,
+    /// Namespaces the agent's knowledge retrieval searches, e.g. its own
+    /// and `default`. Empty = all documents.
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+}
+
+fn default_knowledge_watch_interval() -> u64 {
+    30
+}
+
+fn default_knowledge_chunk_size() -> usize {
+    500
+}
+
+fn default_knowledge_chunk_overlap() -> usize {
+    80
+}
+
+impl Default for KnowledgeConfig {
+    fn default() -> Self {
+        Self {
+            watch_dir: String::new(),
+            watch_interval_secs: default_knowledge_watch_interval(),
+            import_root: String::new(),
+            chunk_size: default_knowledge_chunk_size(),
+            chunk_overlap: default_knowledge_chunk_overlap(),
+            namespaces: vec![],
+        }
+    }
+}
+
+/// Autonomous Hands — scheduled multi-phase playbooks run by agents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandsConfig {
+    /// Hands that run on their schedule, e.g. `["monitor", "research"]`.
+    /// Others only run when triggered through the API.
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    /// Folder of custom hands, one `<name>/HAND.toml` per hand.
+    /// Empty = `~/.bizclaw/hands`.
+    #[serde(default)]
+    pub dir: String,
+    /// Seconds between checks for due hands.
+    #[serde(default = "default_hands_check_interval")]
+    pub check_interval_secs: u64,
+}
+
+fn default_hands_check_interval() -> u64 {
+    60
+}
+
+impl HandsConfig {
+    /// Folder of custom hands, `~/.bizclaw/hands` unless `dir` is set.
+    pub fn dir_path(&self) -> PathBuf {
+        if self.dir.is_empty() {
+            BizClawConfig::home_dir().join("hands")
+        } else {
+            PathBuf::from(&self.dir)
+        }
+    }
+}
+
+impl Default for HandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: vec![],
+            dir: String::new(),
+            check_interval_secs: default_hands_check_interval(),
+        }
+    }
+}
+
+/// External `llama-server` run and supervised by BizClaw; while it runs it
+/// backs the `llamacpp` provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlamaCppConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Path to `llama-server`. Empty = search PATH, then `~/.bizclaw/bin`.
+    #[serde(default)]
+    pub binary_path: String,
+    /// Download the latest llama.cpp release when no binary is found.
+    #[serde(default = "bool_true")]
+    pub auto_download: bool,
+    /// GGUF model to serve. Empty = `brain.model_path`, else the first
+    /// model in `~/.bizclaw/models`.
+    #[serde(default)]
+    pub model_path: String,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_llamacpp_port")]
+    pub port: u16,
+    /// Layers offloaded to the GPU (`-ngl`); 99 = all.
+    #[serde(default = "default_llamacpp_gpu_layers")]
+    pub n_gpu_layers: i32,
+    #[serde(default = "default_context_length")]
+    pub context_length: u32,
+    /// Extra command-line arguments passed through to `llama-server`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Seconds between health checks.
+    #[serde(default = "default_llamacpp_health_interval")]
+    pub health_interval_secs: u64,
+    /// Seconds the server may take to load the model.
+    #[serde(default = "default_llamacpp_startup_timeout")]
+    pub startup_timeout_secs: u64,
+    /// Consecutive failed restarts before giving up.
+    #[serde(default = "default_llamacpp_max_restarts")]
+    pub max_restarts: u32,
+}
+
+fn default_llamacpp_port() -> u16 {
+    8080
+}
+fn default_llamacpp_gpu_layers() -> i32 {
+    99
+}
+fn default_llamacpp_health_interval() -> u64 {
+    10
+}
+fn default_llamacpp_startup_timeout() -> u64 {
+    120
+}
+fn default_llamacpp_max_restarts() -> u32 {
+    5
+}
+
+impl Default for LlamaCppConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            binary_path: String::new(),
+            auto_download: true,
+            model_path: String::new(),
+            host: default_host(),
+            port: default_llamacpp_port(),
+            n_gpu_layers: default_llamacpp_gpu_layers(),
+            context_length: default_context_length(),
+            extra_args: Vec::new(),
+            health_interval_secs: default_llamacpp_health_interval(),
+            startup_timeout_secs: default_llamacpp_startup_timeout(),
+            max_restarts: default_llamacpp_max_restarts(),
         }
     }
 }
@@ -322,6 +1173,126 @@ pub struct AutonomyConfig {
     pub allowed_commands: Vec<String>,
     #[serde(default = "default_forbidden_paths")]
     pub forbidden_paths: Vec<String>,
+    /// Domains web tools may fetch from; each also allows its subdomains
+    /// (empty = any public site).
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Maximum bytes of shell stdout/stderr returned to the agent.
+    #[serde(default = "default_shell_max_output_bytes")]
+    pub shell_max_output_bytes: usize,
+    /// Maximum tool → LLM rounds per message before a final answer is forced.
+    #[serde(default = "default_max_tool_rounds")]
+    pub max_tool_rounds: u32,
+    /// How many times one tool may be called with identical arguments in a
+    /// single message before the agent treats it as a loop (0 = no limit).
+    #[serde(default = "default_max_identical_tool_calls")]
+    pub max_identical_tool_calls: u32,
+    /// Seconds a single tool execution may run before it is cancelled.
+    #[serde(default = "default_tool_timeout_secs")]
+    pub tool_timeout_secs: u64,
+    /// Per-tool overrides of `tool_timeout_secs`, keyed by tool name.
+    #[serde(default)]
+    pub tool_timeouts: HashMap<String, u64>,
+    /// Tools that only run after a human approves the call ("*" = all).
+    #[serde(default)]
+    pub require_approval: Vec<String>,
+    /// Seconds to wait for an approval decision before denying the call.
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
+    /// Sandbox for the `execute_code` tool (`[autonomy.code]`).
+    #[serde(default)]
+    pub code: CodeExecutionConfig,
+    /// Argument checks applied before a tool runs (`[[autonomy.argument_filters]]`).
+    #[serde(default)]
+    pub argument_filters: Vec<ArgumentFilter>,
+}
+
+/// Refuses tool calls whose arguments match a pattern.
+///
+/// ```toml
+/// [[autonomy.argument_filters]]
+/// tool = "shell"
+/// argument = "command"
+/// deny = ["rm\\s+-[a-z]*r[a-z]*f", "mkfs"]
+///
+/// [[autonomy.argument_filters]]
+/// tool = "file"
+/// deny_absolute_paths = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArgumentFilter {
+    /// Tool name the filter applies to ("*" = all tools).
+    pub tool: String,
+    /// Argument to inspect (empty = every string argument).
+    #[serde(default)]
+    pub argument: String,
+    /// Regular expressions that must not match the argument.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Refuse absolute or home-relative paths (`/…`, `~…`, `C:\…`).
+    #[serde(default)]
+    pub deny_absolute_paths: bool,
+}
+
+/// Limits for code the `execute_code` tool runs. Code runs in a fresh
+/// temporary directory with a cleared environment, under these resource
+/// limits, and never when `autonomy.level` is `readonly`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeExecutionConfig {
+    /// Offer the tool at all. Snippets can still read the filesystem and
+    /// reach the network, so it is off by default, and unless
+    /// `autonomy.level` is `full` every run needs human approval.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Languages that may run (python, javascript, ruby, bash, php, go,
+    /// rust, c, typescript).
+    #[serde(default = "default_code_languages")]
+    pub languages: Vec<String>,
+    /// Wall-clock seconds a snippet may run.
+    #[serde(default = "default_code_timeout_secs")]
+    pub timeout_secs: u64,
+    /// CPU seconds a snippet may use.
+    #[serde(default = "default_code_cpu_secs")]
+    pub cpu_secs: u64,
+    /// Memory (data segment) a snippet may use, in MB.
+    #[serde(default = "default_code_memory_mb")]
+    pub memory_mb: u64,
+    /// Largest file a snippet may write, in MB.
+    #[serde(default = "default_code_max_file_mb")]
+    pub max_file_mb: u64,
+    /// Maximum bytes of stdout and of stderr returned to the agent.
+    #[serde(default = "default_shell_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+fn default_code_languages() -> Vec<String> {
+    vec!["python".into(), "javascript".into()]
+}
+fn default_code_timeout_secs() -> u64 {
+    30
+}
+fn default_code_cpu_secs() -> u64 {
+    20
+}
+fn default_code_memory_mb() -> u64 {
+    512
+}
+fn default_code_max_file_mb() -> u64 {
+    16
+}
+
+impl Default for CodeExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: default_code_languages(),
+            timeout_secs: default_code_timeout_secs(),
+            cpu_secs: default_code_cpu_secs(),
+            memory_mb: default_code_memory_mb(),
+            max_file_mb: default_code_max_file_mb(),
+            max_output_bytes: default_shell_max_output_bytes(),
+        }
+    }
 }
 
 fn default_autonomy_level() -> String {
@@ -333,6 +1304,21 @@ fn default_allowed_commands() -> Vec<String> {
         .map(String::from)
         .collect()
 }
+fn default_shell_max_output_bytes() -> usize {
+    64 * 1024
+}
+fn default_max_tool_rounds() -> u32 {
+    5
+}
+fn default_max_identical_tool_calls() -> u32 {
+    2
+}
+fn default_tool_timeout_secs() -> u64 {
+    120
+}
+fn default_approval_timeout_secs() -> u64 {
+    300
+}
 fn default_forbidden_paths() -> Vec<String> {
     vec![
         "/etc", "/root", "/proc", "/sys", "~/.ssh", "~/.gnupg", "~/.aws",
@@ -349,6 +1335,16 @@ impl Default for AutonomyConfig {
             workspace_only: true,
             allowed_commands: default_allowed_commands(),
             forbidden_paths: default_forbidden_paths(),
+            allowed_domains: Vec::new(),
+            shell_max_output_bytes: default_shell_max_output_bytes(),
+            max_tool_rounds: default_max_tool_rounds(),
+            max_identical_tool_calls: default_max_identical_tool_calls(),
+            tool_timeout_secs: default_tool_timeout_secs(),
+            tool_timeouts: HashMap::new(),
+            require_approval: Vec::new(),
+            approval_timeout_secs: default_approval_timeout_secs(),
+            code: CodeExecutionConfig::default(),
+            argument_filters: Vec::new(),
         }
     }
 }
@@ -375,8 +1371,12 @@ impl Default for RuntimeConfig {
 /// Tunnel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TunnelConfig {
+    /// Tunnel provider: "none", "manual", "cloudflared", or "ngrok".
     #[serde(default = "default_tunnel_provider")]
     pub provider: String,
+    /// Public base URL for the "manual" provider (an already-running tunnel).
+    #[serde(default)]
+    pub public_url: String,
 }
 
 fn default_tunnel_provider() -> String {
@@ -387,11 +1387,14 @@ impl Default for TunnelConfig {
     fn default() -> Self {
         Self {
             provider: default_tunnel_provider(),
+            public_url: String::new(),
         }
     }
 }
 
-/// Secrets configuration.
+/// Secrets configuration. Config values can name a stored secret as
+/// `${secret:<name>}`; with `encrypt` the store is encrypted and plaintext
+/// credentials found in the config are moved into it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecretsConfig {
     #[serde(default = "bool_true")]
@@ -404,6 +1407,44 @@ impl Default for SecretsConfig {
     }
 }
 
+/// PII filter applied before messages reach a cloud provider (`[privacy]`).
+/// Local providers (brain, or endpoints on localhost / a private network)
+/// are never filtered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `pseudonymize` swaps each value for a placeholder such as `[EMAIL_1]`
+    /// and restores it in replies; `redact` removes it for good.
+    #[serde(default = "default_privacy_mode")]
+    pub mode: String,
+    /// What to detect: email, phone, id_number (CCCD/CMND/passport),
+    /// tax_code, card.
+    #[serde(default = "default_pii_kinds")]
+    pub detect: Vec<String>,
+    /// Extra regular expressions treated as PII.
+    #[serde(default)]
+    pub custom_patterns: Vec<String>,
+}
+
+fn default_privacy_mode() -> String {
+    "pseudonymize".into()
+}
+fn default_pii_kinds() -> Vec<String> {
+    ["email", "phone", "id_number", "tax_code", "card"].into_iter().map(String::from).collect()
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: default_privacy_mode(),
+            detect: default_pii_kinds(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
 /// Channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ChannelConfig {
@@ -419,6 +1460,32 @@ pub struct ChannelConfig {
     pub whatsapp: Option<WhatsAppChannelConfig>,
     #[serde(default)]
     pub webhook: Option<WebhookChannelConfig>,
+    #[serde(default)]
+    pub sms: Option<SmsChannelConfig>,
+    #[serde(default)]
+    pub middleware: ChannelMiddlewareConfig,
+}
+
+/// Checks every channel message passes through (besides each channel's
+/// own allowlist: `allowed_chat_ids`, `allowed_channel_ids`, Zalo
+/// `allowlist`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelMiddlewareConfig {
+    /// Messages a sender may send per minute (0 = unlimited).
+    #[serde(default)]
+    pub rate_limit_per_minute: u32,
+    /// Messages a sender may send per hour (0 = unlimited).
+    #[serde(default)]
+    pub rate_limit_per_hour: u32,
+    /// Words masked in incoming messages and replies (whole words, any case).
+    #[serde(default)]
+    pub blocked_words: Vec<String>,
+    /// Drop incoming messages with a blocked word instead of masking it.
+    #[serde(default)]
+    pub block_profanity: bool,
+    /// Save every incoming message and reply to memory.
+    #[serde(default)]
+    pub transcripts: bool,
 }
 
 /// Zalo channel configuration.
@@ -431,6 +1498,8 @@ pub struct ZaloChannelConfig {
     #[serde(default)]
     pub personal: ZaloPersonalConfig,
     #[serde(default)]
+    pub official: ZaloOfficialConfig,
+    #[serde(default)]
     pub rate_limit: ZaloRateLimitConfig,
     #[serde(default)]
     pub allowlist: ZaloAllowlistConfig,
@@ -446,6 +1515,7 @@ impl Default for ZaloChannelConfig {
             enabled: false,
             mode: default_zalo_mode(),
             personal: ZaloPersonalConfig::default(),
+            official: ZaloOfficialConfig::default(),
             rate_limit: ZaloRateLimitConfig::default(),
             allowlist: ZaloAllowlistConfig::default(),
         }
@@ -491,6 +1561,46 @@ impl Default for ZaloPersonalConfig {
     }
 }
 
+/// Zalo Official Account ("official" mode) settings, from the app on
+/// developers.zalo.me.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZaloOfficialConfig {
+    #[serde(default)]
+    pub app_id: String,
+    /// App secret key, used to refresh access tokens.
+    #[serde(default)]
+    pub app_secret: String,
+    /// OA secret key, used to verify webhook signatures.
+    #[serde(default)]
+    pub oa_secret_key: String,
+    /// Initial access token (expires after 25 hours).
+    #[serde(default)]
+    pub access_token: String,
+    /// Initial refresh token (valid 3 months, single use).
+    #[serde(default)]
+    pub refresh_token: String,
+    /// Where refreshed tokens are kept, since each refresh token works once.
+    #[serde(default = "default_zalo_token_path")]
+    pub token_path: String,
+}
+
+fn default_zalo_token_path() -> String {
+    "~/.bizclaw/zalo/oa_token.json".into()
+}
+
+impl Default for ZaloOfficialConfig {
+    fn default() -> Self {
+        Self {
+            app_id: String::new(),
+            app_secret: String::new(),
+            oa_secret_key: String::new(),
+            access_token: String::new(),
+            refresh_token: String::new(),
+            token_path: default_zalo_token_path(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZaloRateLimitConfig {
     #[serde(default = "default_max_per_minute")]
@@ -594,6 +1704,21 @@ pub struct WhatsAppChannelConfig {
     pub webhook_verify_token: String,
     #[serde(default)]
     pub business_id: String,
+    /// Phone number scheduler notifications are sent to (empty = none).
+    #[serde(default)]
+    pub notify_to: String,
+    /// Approved template for notifications, with the title and body as its
+    /// two body parameters. Without one, notifications are plain text and
+    /// only reach users who wrote in the last 24 hours.
+    #[serde(default)]
+    pub notify_template: String,
+    /// Language code of the notification template.
+    #[serde(default = "default_whatsapp_template_language")]
+    pub notify_template_language: String,
+}
+
+fn default_whatsapp_template_language() -> String {
+    "en_US".into()
 }
 
 /// Generic Webhook channel configuration.
@@ -611,12 +1736,47 @@ pub struct WebhookChannelConfig {
     pub outbound_url: String,
 }
 
-/// MCP server entry — one per [[mcp_servers]] in config.toml.
+/// SMS channel configuration (Twilio).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmsChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub account_sid: String,
+    #[serde(default)]
+    pub auth_token: String,
+    /// Twilio number replies and notifications are sent from (E.164).
+    #[serde(default)]
+    pub from_number: String,
+    /// Public URL of the SMS webhook, exactly as set in Twilio — requests
+    /// are signature-checked against it. Empty = rebuilt from the request's
+    /// `Host` and `X-Forwarded-Proto` headers.
+    #[serde(default)]
+    pub webhook_url: String,
+    /// Twilio number → agent answering texts sent to it. Texts to other
+    /// numbers go to the default agent.
+    #[serde(default)]
+    pub agents: HashMap<String, String>,
+    /// Phone number scheduler notifications are sent to (empty = none).
+    #[serde(default)]
+    pub notify_to: String,
+}
+
+/// MCP server entry — one per [[mcp_servers]] in config.toml. A local
+/// server is started with `command`; a remote one is reached at `url`.
+///
+/// ```toml
+/// [[mcp_servers]]
+/// name = "crm"
+/// url = "https://mcp.example.com/mcp"
+/// bearer_token = "..."
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerEntry {
     /// Display name for this server.
     pub name: String,
-    /// Command to start the MCP server process.
+    /// Command to start the MCP server process (empty for a remote server).
+    #[serde(default)]
     pub command: String,
     /// Arguments to the command.
     #[serde(default)]
@@ -624,15 +1784,120 @@ pub struct McpServerEntry {
     /// Environment variables to set.
     #[serde(default)]
     pub env: std::collections::HashMap<String, String>,
+    /// URL of a remote server, spoken to over Streamable HTTP, or the
+    /// older HTTP+SSE transport when the server only supports that.
+    #[serde(default)]
+    pub url: String,
+    /// Extra HTTP headers sent to a remote server.
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+    /// Bearer token sent to a remote server (empty = none).
+    #[serde(default)]
+    pub bearer_token: String,
+    /// OAuth client credentials to fetch bearer tokens with, instead of a
+    /// fixed `bearer_token`.
+    #[serde(default)]
+    pub oauth: Option<McpOAuthConfig>,
     /// Whether this server is enabled.
     #[serde(default = "default_mcp_enabled")]
     pub enabled: bool,
 }
 
+/// OAuth 2.0 client-credentials grant for a remote MCP server. Tokens are
+/// fetched from `token_url` and fetched again when they expire or the
+/// server rejects them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpOAuthConfig {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Space-separated scopes to request (empty = the server's default).
+    #[serde(default)]
+    pub scope: String,
+}
+
 fn default_mcp_enabled() -> bool {
     true
 }
 
+/// What the gateway's MCP endpoint (`/mcp`) offers MCP clients such as
+/// Claude Desktop. Clients authenticate with an API key with `chat` scope.
+///
+/// ```toml
+/// [mcp_export]
+/// enabled = true
+/// tools = ["web_search", "document_reader"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpExportConfig {
+    /// Serve `/mcp`; off = the endpoint answers 404.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Built-in tools to offer, by name. Tools that need approval under
+    /// `[autonomy]` are never offered: MCP clients can't wait for one.
+    #[serde(default = "default_export_tools")]
+    pub tools: Vec<String>,
+    /// Offer knowledge base search.
+    #[serde(default = "default_mcp_enabled")]
+    pub knowledge: bool,
+    /// Offer each agent as an `ask_<agent>` tool.
+    #[serde(default = "default_mcp_enabled")]
+    pub agents: bool,
+}
+
+fn default_export_tools() -> Vec<String> {
+    vec!["web_search".into(), "http_request".into(), "document_reader".into()]
+}
+
+impl Default for McpExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tools: default_export_tools(),
+            knowledge: true,
+            agents: true,
+        }
+    }
+}
+
+/// Agent selection for messages from channels not bound to an agent.
+///
+/// ```toml
+/// [routing]
+/// enabled = true
+/// classifier_provider = "openai"
+/// classifier_model = "gpt-4o-mini"
+///
+/// [[routing.rules]]
+/// agent = "sales"
+/// keywords = ["price", "báo giá", "discount"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Pick an agent per message; off = the default agent answers.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Keyword rules, checked in order before classification.
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    /// Provider asked to pick an agent when no rule matches. Empty = no
+    /// LLM classification, the default agent answers.
+    #[serde(default)]
+    pub classifier_provider: String,
+    /// Model for classification — a cheap one is enough.
+    #[serde(default)]
+    pub classifier_model: String,
+}
+
+/// A message containing any of `keywords` goes to `agent`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pub agent: String,
+    /// Words or phrases, matched case-insensitively as whole words.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
 /// Quality Gate configuration — evaluator reviews agent responses.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityGateConfig {
@@ -660,6 +1925,65 @@ mod tests {
         assert_eq!(config.identity.name, "BizClaw");
     }
 
+    fn mcp_entry(name: &str, command: &str, url: &str) -> McpServerEntry {
+        McpServerEntry {
+            name: name.into(),
+            command: command.into(),
+            args: vec![],
+            env: Default::default(),
+            url: url.into(),
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = BizClawConfig::default();
+        assert!(config.validate().iter().all(|i| !i.is_error()), "{:?}", config.validate());
+
+        let mut config = BizClawConfig {
+            default_temperature: 3.0,
+            ..Default::default()
+        };
+        config.brain.max_tokens = config.brain.context_length;
+        config.memory.backend = "redis".into();
+        config.autonomy.code.memory_mb = 0;
+        config.api_base_url = "localhost:8787".into();
+        config.default_model = String::new();
+        config.mcp_servers = vec![
+            mcp_entry("fs", "npx", ""),
+            mcp_entry("fs", "", ""),
+            mcp_entry("crm", "", "https://mcp.example.com/mcp"),
+            mcp_entry("crm2", "", "mcp.example.com"),
+            mcp_entry("crm3", "npx", "https://mcp.example.com/mcp"),
+        ];
+        config.routing.rules = vec![
+            RoutingRule { agent: "sales".into(), keywords: vec!["price".into()] },
+            RoutingRule { agent: "support".into(), keywords: vec![" ".into()] },
+        ];
+        let issues = config.validate();
+        let errors: Vec<&str> = issues.iter().filter(|i| i.is_error()).map(|i| i.field.as_str()).collect();
+        assert_eq!(
+            errors,
+            [
+                "default_temperature",
+                "api_base_url",
+                "brain.max_tokens",
+                "memory.backend",
+                "autonomy.code.memory_mb",
+                "mcp_servers[1]",
+                "mcp_servers[1]",
+                "mcp_servers[3]",
+                "mcp_servers[4]",
+                "routing.rules[1]",
+            ]
+        );
+        assert!(issues.iter().any(|i| i.field == "default_model" && !i.is_error()));
+    }
+
     #[test]
     fn test_config_from_toml() {
         let toml_str = r#"
@@ -687,6 +2011,114 @@ mod tests {
         assert_eq!(config.gateway.port, 3000);
     }
 
+    #[test]
+    fn test_llm_fallback_providers_from_toml() {
+        let toml_str = r#"
+            [LLM]
+            provider = "openai"
+            model = "gpt-4o-mini"
+            fallback_providers = [
+                { provider = "groq", model = "llama-3.3-70b-versatile" },
+                { provider = "ollama" },
+            ]
+        "#;
+        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        let fallbacks = &config.llm.fallback_providers;
+        assert_eq!(fallbacks.len(), 2);
+        assert_eq!(fallbacks[0].provider, "groq");
+        assert_eq!(fallbacks[0].model, "llama-3.3-70b-versatile");
+        assert_eq!(fallbacks[1].provider, "ollama");
+        assert!(fallbacks[1].model.is_empty());
+        assert!(!config.llm.failover.enabled);
+    }
+
+    #[test]
+    fn test_llm_failover_section() {
+        let toml_str = r#"
+            [LLM]
+            provider = "brain"
+            fallback_providers = [{ provider = "ollama" }, { provider = "openai" }]
+
+            [LLM.failover]
+            enabled = true
+            max_retries = 4
+        "#;
+        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        let failover = &config.llm.failover;
+        assert!(failover.enabled);
+        assert_eq!(failover.max_retries, 4);
+        assert_eq!(failover.backoff_ms, 500);
+        assert_eq!(failover.failure_threshold, 3);
+    }
+
+    #[test]
+    fn test_remote_mcp_server_from_toml() {
+        let toml_str = r#"
+            [[mcp_servers]]
+            name = "crm"
+            url = "https://mcp.example.com/mcp"
+            headers = { "X-Tenant" = "acme" }
+
+            [mcp_servers.oauth]
+            token_url = "https://auth.example.com/token"
+            client_id = "bizclaw"
+            client_secret = "s3cret"
+        "#;
+        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        let server = &config.mcp_servers[0];
+        assert!(server.command.is_empty() && server.enabled);
+        assert_eq!(server.headers["X-Tenant"], "acme");
+        let oauth = server.oauth.as_ref().unwrap();
+        assert_eq!(oauth.client_id, "bizclaw");
+        assert!(oauth.scope.is_empty());
+        assert!(config.validate().iter().all(|i| !i.is_error()));
+    }
+
+    #[test]
+    fn test_llamacpp_section_defaults() {
+        let config: BizClawConfig = toml::from_str("[llamacpp]\nenabled = true\n").unwrap();
+        assert!(config.llamacpp.enabled);
+        assert!(config.llamacpp.auto_download);
+        assert_eq!(config.llamacpp.port, 8080);
+        assert_eq!(config.llamacpp.max_restarts, 5);
+    }
+
+    #[test]
+    fn test_secret_refs_resolved_and_written_back() {
+        let toml_str = r#"
+            api_key = "${secret:openai}"
+            [channel.telegram]
+            enabled = true
+            bot_token = "${secret:telegram_bot_token}"
+            [[mcp_servers]]
+            name = "crm"
+            url = "https://crm.example.com/mcp"
+            headers = { Authorization = "Bearer ${secret:crm}" }
+        "#;
+        let mut config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        let missing = config
+            .resolve_secrets(|name| match name {
+                "openai" => Some("sk-123".into()),
+                "crm" => Some("tok".into()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(missing, vec!["telegram_bot_token"]);
+        assert_eq!(config.api_key, "sk-123");
+        assert_eq!(config.mcp_servers[0].headers["Authorization"], "Bearer tok");
+        assert_eq!(config.channel.telegram.as_ref().unwrap().bot_token, "${secret:telegram_bot_token}");
+        assert_eq!(config.unresolved("api_key", "sk-123"), "${secret:openai}");
+        assert_eq!(config.unresolved("default_model", "sk-123"), "sk-123");
+
+        // Only the fields that held a reference get it back
+        config.default_model = "sk-123".into();
+        let saved = config.to_toml().unwrap();
+        assert!(saved.contains("default_model = \"sk-123\""), "{saved}");
+        assert!(saved.contains("api_key = \"${secret:openai}\""), "{saved}");
+        assert!(!saved.contains("Bearer tok") && saved.contains("Bearer ${secret:crm}"), "{saved}");
+    }
+
     #[test]
     fn test_home_dir() {
         let home = BizClawConfig::home_dir();
@@ -88,6 +88,9 @@ pub enum BizClawError {
     #[error("Rate limited: {0}")]
     RateLimited(String),
 
+    #[error("Transient error: {0}")]
+    Transient(String),
+
     // Orchestration errors
     #[error("Delegation error: {0}")]
     Delegation(String),
@@ -138,6 +141,19 @@ impl BizClawError {
     pub fn security(msg: impl Into<String>) -> Self {
         Self::Security(msg.into())
     }
+
+    /// Whether the same request may succeed on another provider.
+    pub fn is_failover_eligible(&self) -> bool {
+        matches!(
+            self,
+            Self::Transient(_) | Self::RateLimited(_) | Self::AuthFailed(_)
+        )
+    }
+
+    /// Whether the same request may succeed on the same provider after a wait.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Transient(_) | Self::RateLimited(_))
+    }
 }
 
 #[cfg(test)]
@@ -165,6 +181,17 @@ mod tests {
         assert!(matches!(e4, BizClawError::Security(_)));
     }
 
+    #[test]
+    fn test_failover_eligible() {
+        assert!(BizClawError::Transient("503".into()).is_failover_eligible());
+        assert!(BizClawError::RateLimited("429".into()).is_failover_eligible());
+        assert!(BizClawError::AuthFailed("401".into()).is_failover_eligible());
+        assert!(!BizClawError::Provider("400".into()).is_failover_eligible());
+        assert!(!BizClawError::Config("bad".into()).is_failover_eligible());
+        assert!(BizClawError::RateLimited("429".into()).is_retryable());
+        assert!(!BizClawError::AuthFailed("401".into()).is_retryable());
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
@@ -204,6 +231,7 @@ mod tests {
             BizClawError::Http("h".into()),
             BizClawError::Timeout("t".into()),
             BizClawError::RateLimited("r".into()),
+            BizClawError::Transient("t".into()),
             BizClawError::Delegation("d".into()),
             BizClawError::AgentNotFound("a".into()),
             BizClawError::NoPermission("n".into()),
@@ -219,8 +247,8 @@ mod tests {
             let display = err.to_string();
             assert!(!display.is_empty(), "Error should have display: {:?}", err);
         }
-        // There should be 31 variants
-        assert_eq!(errors.len(), 31);
+        // There should be 32 variants
+        assert_eq!(errors.len(), 32);
     }
 
     #[test]
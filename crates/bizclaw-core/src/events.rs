@@ -0,0 +1,168 @@
+//! In-process event bus.
+//!
+//! Channels publish the messages they receive, agents publish tool calls and
+//! context compactions, the scheduler publishes finished tasks, workflow
+//! runs and notifications, the gateway publishes channel connections and
+//! agent reloads. Subscribers (the scheduler's workflow rules, dashboards
+//! over `/ws`) listen without the publishers knowing about them.
+//!
+//! Built on `tokio::sync::broadcast`: publishing never blocks, events are
+//! dropped when nobody subscribes, and a subscriber that falls more than
+//! the bus capacity behind skips the oldest events.
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events kept for slow subscribers before they start skipping.
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Something that happened somewhere in the platform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A channel received a message from a user.
+    MessageReceived {
+        /// Channel name: "telegram", "discord", "whatsapp", "email", "webhook"...
+        channel: String,
+        sender: String,
+        chat_id: String,
+        text: String,
+    },
+    /// An agent finished a tool call.
+    ToolExecuted {
+        agent: String,
+        tool: String,
+        success: bool,
+    },
+    /// An agent compacted its conversation to fit the context window.
+    ContextCompacted {
+        agent: String,
+        messages_before: usize,
+        messages_after: usize,
+    },
+    /// A scheduled task ran.
+    TaskFinished {
+        task_id: String,
+        task: String,
+        success: bool,
+    },
+    /// A multi-step workflow run ended.
+    WorkflowFinished {
+        workflow_id: String,
+        workflow: String,
+        /// "success" or "failed".
+        status: String,
+    },
+    /// A notification was raised for the dashboard.
+    NotificationSent {
+        title: String,
+        body: String,
+        /// "low", "normal", "high" or "urgent".
+        priority: String,
+        /// What raised it: "task:<name>", "workflow:<name>"...
+        source: String,
+    },
+    /// A channel connected and is listening for messages.
+    ChannelConnected {
+        /// Channel type: "telegram", "discord", "line"...
+        channel: String,
+        /// Instance name, or the channel type for the config-file channel.
+        instance: String,
+        agent: String,
+    },
+    /// An agent was rebuilt with new config, provider or model.
+    AgentReloaded { agent: String },
+}
+
+impl Event {
+    /// The event's type tag, as serialized ("message_received", ...).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::MessageReceived { .. } => "message_received",
+            Self::ToolExecuted { .. } => "tool_executed",
+            Self::ContextCompacted { .. } => "context_compacted",
+            Self::TaskFinished { .. } => "task_finished",
+            Self::WorkflowFinished { .. } => "workflow_finished",
+            Self::NotificationSent { .. } => "notification_sent",
+            Self::ChannelConnected { .. } => "channel_connected",
+            Self::AgentReloaded { .. } => "agent_reloaded",
+        }
+    }
+}
+
+/// Cheap to clone: every clone publishes to the same subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    /// Bus keeping up to `capacity` events for slow subscribers.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    /// Send `event` to every current subscriber.
+    pub fn publish(&self, event: Event) {
+        tracing::trace!("📡 Event: {}", event.kind());
+        // Err only means nobody is listening
+        let _ = self.tx.send(event);
+    }
+
+    /// Receive every event published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Number of active subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_reaches_every_subscriber() {
+        let bus = EventBus::default();
+        bus.publish(Event::TaskFinished {
+            task_id: "t1".into(),
+            task: "nobody listens".into(),
+            success: true,
+        });
+
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+        let event = Event::MessageReceived {
+            channel: "email".into(),
+            sender: "boss@acme.com".into(),
+            chat_id: "inbox".into(),
+            text: "Invoice attached".into(),
+        };
+        bus.publish(event.clone());
+        assert_eq!(first.recv().await.unwrap(), event);
+        assert_eq!(second.recv().await.unwrap(), event);
+    }
+
+    #[test]
+    fn test_event_serializes_with_type_tag() {
+        let event = Event::ToolExecuted {
+            agent: "sales".into(),
+            tool: "shell".into(),
+            success: false,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.kind());
+        assert_eq!(json["tool"], "shell");
+    }
+}
@@ -5,6 +5,9 @@
 
 pub mod config;
 pub mod error;
+pub mod events;
+pub mod metrics;
+pub mod schema;
 pub mod traits;
 pub mod types;
 
@@ -0,0 +1,227 @@
+//! Process-wide metrics registry.
+//!
+//! Any crate records counters, gauges and histograms by name and labels;
+//! the gateway renders them all in the Prometheus text format on
+//! `/metrics`. Series are created on first use, so nothing has to be
+//! registered up front — [`describe`] only adds the `# HELP` line, and a
+//! metric nothing has recorded yet isn't rendered.
+//!
+//! ```
+//! use bizclaw_core::metrics;
+//! metrics::increment("bizclaw_tool_calls_total", &[("tool", "shell"), ("success", "true")]);
+//! metrics::observe("bizclaw_agent_process_seconds", &[("agent", "default")], 0.42);
+//! assert!(metrics::render().contains("bizclaw_tool_calls_total{"));
+//! ```
+//!
+//! Labels should have a small, bounded set of values (routes, agent and
+//! tool names) — every distinct combination is kept for the life of the
+//! process.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Histogram bucket upper bounds, in seconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(f64),
+    Histogram { buckets: Vec<u64>, sum: f64, count: u64 },
+}
+
+#[derive(Debug)]
+struct Family {
+    kind: Kind,
+    /// Keyed by the rendered label set, e.g. `{agent="default"}`.
+    series: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    families: BTreeMap<String, Family>,
+    help: BTreeMap<String, String>,
+}
+
+fn registry() -> MutexGuard<'static, Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn label_key(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn update(name: &str, kind: Kind, labels: &[(&str, &str)], f: impl FnOnce(&mut Value)) {
+    let mut registry = registry();
+    let family = registry.families.entry(name.to_string()).or_insert_with(|| Family {
+        kind,
+        series: BTreeMap::new(),
+    });
+    if family.kind != kind {
+        tracing::warn!("Metric {name} is a {}, not a {}", family.kind.as_str(), kind.as_str());
+        return;
+    }
+    let value = family.series.entry(label_key(labels)).or_insert_with(|| match kind {
+        Kind::Histogram => Value::Histogram {
+            buckets: vec![0; DEFAULT_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        },
+        _ => Value::Scalar(0.0),
+    });
+    f(value);
+}
+
+/// Set the `# HELP` text of a metric, before or after its first use.
+pub fn describe(name: &str, help: &str) {
+    registry().help.insert(name.to_string(), help.to_string());
+}
+
+/// Add one to a counter.
+pub fn increment(name: &str, labels: &[(&str, &str)]) {
+    add(name, labels, 1.0);
+}
+
+/// Add `delta` to a counter.
+pub fn add(name: &str, labels: &[(&str, &str)], delta: f64) {
+    update(name, Kind::Counter, labels, |v| {
+        if let Value::Scalar(x) = v {
+            *x += delta;
+        }
+    });
+}
+
+/// Set a gauge.
+pub fn set(name: &str, labels: &[(&str, &str)], value: f64) {
+    update(name, Kind::Gauge, labels, |v| *v = Value::Scalar(value));
+}
+
+/// Record one observation (usually seconds) in a histogram.
+pub fn observe(name: &str, labels: &[(&str, &str)], value: f64) {
+    update(name, Kind::Histogram, labels, |v| {
+        if let Value::Histogram { buckets, sum, count } = v {
+            for (bucket, bound) in buckets.iter_mut().zip(DEFAULT_BUCKETS) {
+                if value <= *bound {
+                    *bucket += 1;
+                }
+            }
+            *sum += value;
+            *count += 1;
+        }
+    });
+}
+
+/// Current value of a counter or gauge series, if it exists.
+pub fn value(name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+    let registry = registry();
+    match registry.families.get(name)?.series.get(&label_key(labels))? {
+        Value::Scalar(x) => Some(*x),
+        Value::Histogram { count, .. } => Some(*count as f64),
+    }
+}
+
+/// Every metric in the Prometheus text exposition format (version 0.0.4).
+pub fn render() -> String {
+    let registry = registry();
+    let mut out = String::new();
+    for (name, family) in &registry.families {
+        if let Some(help) = registry.help.get(name) {
+            let _ = writeln!(out, "# HELP {name} {help}");
+        }
+        let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+        for (labels, value) in &family.series {
+            match value {
+                Value::Scalar(x) => {
+                    let _ = writeln!(out, "{name}{labels} {x}");
+                }
+                Value::Histogram { buckets, sum, count } => {
+                    // Bucket labels go after the series' own labels
+                    let inner = labels.trim_start_matches('{').trim_end_matches('}');
+                    let sep = if inner.is_empty() { "" } else { "," };
+                    for (bucket, bound) in buckets.iter().zip(DEFAULT_BUCKETS) {
+                        let _ = writeln!(out, "{name}_bucket{{{inner}{sep}le=\"{bound}\"}} {bucket}");
+                    }
+                    let _ = writeln!(out, "{name}_bucket{{{inner}{sep}le=\"+Inf\"}} {count}");
+                    let _ = writeln!(out, "{name}_sum{labels} {sum}");
+                    let _ = writeln!(out, "{name}_count{labels} {count}");
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_gauges() {
+        increment("test_metrics_requests_total", &[("route", "/a")]);
+        add("test_metrics_requests_total", &[("route", "/a")], 2.0);
+        increment("test_metrics_requests_total", &[("route", "/b")]);
+        set("test_metrics_queue", &[], 7.0);
+        set("test_metrics_queue", &[], 3.0);
+        describe("test_metrics_queue", "Queued items.");
+
+        assert_eq!(value("test_metrics_requests_total", &[("route", "/a")]), Some(3.0));
+        assert_eq!(value("test_metrics_queue", &[]), Some(3.0));
+        // A gauge name can't be reused as a counter
+        increment("test_metrics_queue", &[]);
+        assert_eq!(value("test_metrics_queue", &[]), Some(3.0));
+
+        let text = render();
+        assert!(text.contains("# TYPE test_metrics_requests_total counter"));
+        assert!(text.contains("test_metrics_requests_total{route=\"/a\"} 3"));
+        assert!(text.contains("# HELP test_metrics_queue Queued items."));
+        assert!(text.contains("test_metrics_queue 3"));
+    }
+
+    #[test]
+    fn test_histogram() {
+        observe("test_metrics_latency_seconds", &[("agent", "a")], 0.02);
+        observe("test_metrics_latency_seconds", &[("agent", "a")], 3.0);
+        let text = render();
+        assert!(text.contains("# TYPE test_metrics_latency_seconds histogram"));
+        assert!(text.contains("test_metrics_latency_seconds_bucket{agent=\"a\",le=\"0.01\"} 0"));
+        assert!(text.contains("test_metrics_latency_seconds_bucket{agent=\"a\",le=\"0.025\"} 1"));
+        assert!(text.contains("test_metrics_latency_seconds_bucket{agent=\"a\",le=\"5\"} 2"));
+        assert!(text.contains("test_metrics_latency_seconds_bucket{agent=\"a\",le=\"+Inf\"} 2"));
+        assert!(text.contains("test_metrics_latency_seconds_sum{agent=\"a\"} 3.02"));
+        assert!(text.contains("test_metrics_latency_seconds_count{agent=\"a\"} 2"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(label_key(&[("q", "say \"hi\"\n")]), "{q=\"say \\\"hi\\\"\\n\"}");
+        assert_eq!(label_key(&[]), "");
+    }
+}
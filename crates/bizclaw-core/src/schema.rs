@@ -0,0 +1,142 @@
+//! Minimal JSON Schema validation for model-generated JSON — structured
+//! replies and tool-call arguments.
+//!
+//! Covers the keywords the brain grammar enforces — `type`, `properties`,
+//! `required`, `additionalProperties: false`, `items` and `enum` — plus
+//! `minItems`/`maxItems` and `minimum`/`maximum`. Other keywords are
+//! accepted but not checked.
+
+use serde_json::Value;
+
+/// Schema violations of `value`, as `path: problem` lines (empty = valid).
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(value, schema, "$", &mut errors);
+    errors
+}
+
+fn type_matches(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            errors.push(format!("{path}: expected {}, got {value}", types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!("{path}: {value} is not one of {}", Value::Array(allowed.clone())));
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+            && n < min
+        {
+            errors.push(format!("{path}: {n} is below the minimum {min}"));
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+            && n > max
+        {
+            errors.push(format!("{path}: {n} is above the maximum {max}"));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for name in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(name) = name.as_str()
+                && !object.contains_key(name)
+            {
+                errors.push(format!("{path}: missing required property '{name}'"));
+            }
+        }
+        for (key, item) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(sub) => check(item, sub, &format!("{path}.{key}"), errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{path}: unexpected property '{key}'"));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let Some(array) = value.as_array() {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+            && (array.len() as u64) < min
+        {
+            errors.push(format!("{path}: expected at least {min} items, got {}", array.len()));
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+            && array.len() as u64 > max
+        {
+            errors.push(format!("{path}: expected at most {max} items, got {}", array.len()));
+        }
+        if let Some(items) = schema.get("items") {
+            for (i, item) in array.iter().enumerate() {
+                check(item, items, &format!("{path}[{i}]"), errors);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "priority": {"type": "string", "enum": ["low", "high"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                "score": {"type": "number", "minimum": 0}
+            },
+            "required": ["name", "priority"],
+            "additionalProperties": false
+        });
+        assert!(validate(&json!({"name": "a", "priority": "low", "tags": ["x"]}), &schema).is_empty());
+
+        let errors = validate(
+            &json!({"priority": "urgent", "tags": ["x", 2, "z"], "score": -1, "extra": 1}),
+            &schema,
+        );
+        assert_eq!(
+            errors,
+            [
+                "$: missing required property 'name'",
+                "$: unexpected property 'extra'",
+                "$.priority: \"urgent\" is not one of [\"low\",\"high\"]",
+                "$.score: -1 is below the minimum 0",
+                "$.tags: expected at most 2 items, got 3",
+                "$.tags[1]: expected string, got 2",
+            ]
+        );
+    }
+}
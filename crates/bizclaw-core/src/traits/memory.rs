@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::error::Result;
+use crate::types::Message;
 
 /// A memory entry stored in the backend.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,4 +47,18 @@ pub trait MemoryBackend: Send + Sync {
 
     /// Clear all memories.
     async fn clear(&self) -> Result<()>;
+
+    /// Replace the stored conversation history of `session_id`. Backends
+    /// without persistence keep this default, which stores nothing.
+    async fn save_conversation(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        let _ = (session_id, messages);
+        Ok(())
+    }
+
+    /// Stored conversation history of `session_id`, or `None` for an
+    /// unknown session.
+    async fn load_conversation(&self, session_id: &str) -> Result<Option<Vec<Message>>> {
+        let _ = session_id;
+        Ok(None)
+    }
 }
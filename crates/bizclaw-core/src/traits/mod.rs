@@ -9,6 +9,7 @@ pub mod observer;
 pub mod provider;
 pub mod runtime;
 pub mod security;
+pub mod tokenizer;
 pub mod tool;
 pub mod tunnel;
 
@@ -16,4 +17,5 @@ pub use channel::Channel;
 pub use memory::MemoryBackend;
 pub use provider::Provider;
 pub use security::SecurityPolicy;
+pub use tokenizer::Tokenizer;
 pub use tool::Tool;
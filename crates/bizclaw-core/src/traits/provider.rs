@@ -2,9 +2,14 @@
 
 use async_trait::async_trait;
 
-use crate::error::Result;
+use crate::config::MirostatConfig;
+use crate::error::{BizClawError, Result};
+use crate::traits::tokenizer::{ApproxTokenizer, Tokenizer};
 use crate::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
 
+/// Receives reply text pieces from [`Provider::chat_stream`] as they arrive.
+pub type ChatChunkSink = tokio::sync::mpsc::UnboundedSender<String>;
+
 /// Configuration for generation parameters.
 #[derive(Debug, Clone)]
 pub struct GenerateParams {
@@ -13,6 +18,13 @@ pub struct GenerateParams {
     pub max_tokens: u32,
     pub top_p: f32,
     pub stop: Vec<String>,
+    /// Min-p cutoff override for providers that sample locally.
+    pub min_p: Option<f32>,
+    /// Mirostat v2 override for providers that sample locally.
+    pub mirostat: Option<MirostatConfig>,
+    /// Ask for a JSON reply matching this schema: JSON mode on cloud APIs,
+    /// a schema-constrained grammar on the local brain.
+    pub response_schema: Option<serde_json::Value>,
 }
 
 impl Default for GenerateParams {
@@ -23,6 +35,9 @@ impl Default for GenerateParams {
             max_tokens: 4096,
             top_p: 0.9,
             stop: vec![],
+            min_p: None,
+            mirostat: None,
+            response_schema: None,
         }
     }
 }
@@ -41,9 +56,49 @@ pub trait Provider: Send + Sync {
         params: &GenerateParams,
     ) -> Result<ProviderResponse>;
 
+    /// Like [`Provider::chat`], sending the reply text to `sink` as it is
+    /// generated. Tool-call markup is not streamed; the calls arrive in the
+    /// returned response. Providers without incremental output keep this
+    /// default, which sends the whole reply as one chunk.
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+        sink: &ChatChunkSink,
+    ) -> Result<ProviderResponse> {
+        let response = self.chat(messages, tools, params).await?;
+        if let Some(content) = response.content.as_deref().filter(|c| !c.is_empty()) {
+            let _ = sink.send(content.to_string());
+        }
+        Ok(response)
+    }
+
     /// List available models for this provider.
     async fn list_models(&self) -> Result<Vec<ModelInfo>>;
 
     /// Check if the provider is available and configured.
     async fn health_check(&self) -> Result<bool>;
+
+    /// Tokenizer for context accounting. Providers that know their model's
+    /// vocabulary override this; the default estimates tiktoken-style counts.
+    fn tokenizer(&self) -> std::sync::Arc<dyn Tokenizer> {
+        std::sync::Arc::new(ApproxTokenizer)
+    }
+
+    /// Whether `Message::images` reach the model. Agents describe images in
+    /// text instead when this is false.
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    /// Embed `text` as a vector for semantic search. Providers without an
+    /// embedding backend keep this default, which returns an error.
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let _ = text;
+        Err(BizClawError::Provider(format!(
+            "Provider {} does not support embeddings",
+            self.name()
+        )))
+    }
 }
@@ -2,6 +2,8 @@
 
 use crate::error::Result;
 use async_trait::async_trait;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
 
 /// Security Policy trait — validates commands and file access.
 #[async_trait]
@@ -12,6 +14,80 @@ pub trait SecurityPolicy: Send + Sync {
     /// Check if a file path is accessible.
     async fn check_path(&self, path: &str) -> Result<bool>;
 
+    /// Check if web tools may fetch from `host`.
+    async fn check_domain(&self, _host: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Check a tool call's arguments before it runs. Returns
+    /// `PermissionDenied` with the reason when the call is refused.
+    async fn check_tool_arguments(&self, _tool: &str, _arguments: &serde_json::Value) -> Result<()> {
+        Ok(())
+    }
+
     /// Get the autonomy level.
     fn autonomy_level(&self) -> &str;
 }
+
+/// Most symlinks followed by [`canonicalize_lenient`], as the OS limits it.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Canonicalize `path` even when it does not exist yet: components are
+/// resolved one by one, following every symlink (including dangling ones,
+/// whose target may be created through them), with `.` and `..` folded.
+pub fn canonicalize_lenient(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    // Components still to resolve, the next one last
+    let mut pending: Vec<OsString> = path.components().rev().map(|c| c.as_os_str().to_os_string()).collect();
+    let mut out = PathBuf::new();
+    let mut hops = 0;
+    while let Some(part) = pending.pop() {
+        let Some(component) = Path::new(&part).components().next() else { continue };
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::Normal(name) => {
+                let next = out.join(name);
+                match std::fs::read_link(&next) {
+                    // The target stands in for the link: absolute targets
+                    // restart from the root, relative ones from its folder
+                    Ok(target) if hops < MAX_SYMLINK_HOPS => {
+                        hops += 1;
+                        pending.extend(target.components().rev().map(|c| c.as_os_str().to_os_string()));
+                    }
+                    _ => out = next,
+                }
+            }
+            root => out.push(root),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_lenient() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        assert_eq!(canonicalize_lenient(&dir.join("no/such/../file.txt")), dir.join("no/file.txt"));
+        assert_eq!(canonicalize_lenient(&dir.join("a/../../x")), dir.parent().unwrap().join("x"));
+        #[cfg(unix)]
+        {
+            let link = dir.join(format!("bizclaw_canon_{}", std::process::id()));
+            let _ = std::fs::remove_file(&link);
+            std::os::unix::fs::symlink("/etc", &link).unwrap();
+            assert_eq!(canonicalize_lenient(&link.join("new.conf")), Path::new("/etc/new.conf"));
+            std::fs::remove_file(&link).unwrap();
+            // A dangling link resolves to where a write through it would land
+            std::os::unix::fs::symlink("/etc/bizclaw-missing/x.conf", &link).unwrap();
+            assert_eq!(canonicalize_lenient(&link), Path::new("/etc/bizclaw-missing/x.conf"));
+            std::fs::remove_file(&link).unwrap();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Tokenizer trait — token counting for context accounting.
+
+use crate::types::Message;
+
+/// Counts tokens the way a provider's model does.
+pub trait Tokenizer: Send + Sync {
+    /// Number of tokens `text` encodes to.
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Tokens a chat message costs beyond its content (role and separators).
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Tokens for a whole conversation, including tool calls and per-message
+/// overhead.
+pub fn count_message_tokens(tokenizer: &dyn Tokenizer, messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|m| {
+            let calls: usize = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|tc| {
+                    tokenizer.count_tokens(&tc.function.name)
+                        + tokenizer.count_tokens(&tc.function.arguments)
+                })
+                .sum();
+            MESSAGE_OVERHEAD + tokenizer.count_tokens(&m.content) + calls
+        })
+        .sum()
+}
+
+/// Estimates tiktoken-style (cl100k/o200k) counts without a vocabulary:
+/// text is split like the tiktoken pre-tokenizer and each piece is costed
+/// by script. English words take about one token per 6 letters, digit
+/// runs one per 3 digits, each accented letter (Vietnamese) or CJK
+/// character about one token, and other symbols one per 2 UTF-8 bytes.
+pub struct ApproxTokenizer;
+
+/// Han, kana and Hangul — roughly a token per character.
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF       // Hiragana, Katakana
+        | 0x3400..=0x4DBF     // CJK Extension A
+        | 0x4E00..=0x9FFF     // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF     // Hangul syllables
+        | 0xF900..=0xFAFF     // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF)  // CJK Extensions B+
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphabetic() && !is_cjk(c)
+}
+
+impl Tokenizer for ApproxTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if is_word_char(c) {
+                let (mut ascii, mut other) = (0usize, 0);
+                let mut next = Some(c);
+                while let Some(ch) = next {
+                    if ch.is_ascii() {
+                        ascii += 1;
+                    } else {
+                        other += 1;
+                    }
+                    next = chars.next_if(|&n| is_word_char(n));
+                }
+                tokens += if other == 0 { ascii.div_ceil(6) } else { other + ascii.div_ceil(4) };
+            } else if c.is_ascii_digit() {
+                let mut len = 1usize;
+                while chars.next_if(|n| n.is_ascii_digit()).is_some() {
+                    len += 1;
+                }
+                tokens += len.div_ceil(3);
+            } else if c == ' ' {
+                let mut len = 1usize;
+                while chars.next_if_eq(&' ').is_some() {
+                    len += 1;
+                }
+                // One space is merged into the following word
+                let joins = len == 1 && chars.peek().is_some_and(|n| n.is_alphanumeric());
+                if !joins {
+                    tokens += 1;
+                }
+            } else if c.is_whitespace() {
+                while chars.next_if(|n| n.is_whitespace()).is_some() {}
+                tokens += 1;
+            } else if is_cjk(c) {
+                tokens += 1;
+            } else if c.is_ascii_punctuation() {
+                let mut len = 1usize;
+                while chars.next_if(|n| n.is_ascii_punctuation()).is_some() {
+                    len += 1;
+                }
+                tokens += len.div_ceil(2);
+            } else {
+                tokens += c.len_utf8().div_ceil(2);
+            }
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approx_english() {
+        let t = ApproxTokenizer;
+        assert_eq!(t.count_tokens(""), 0);
+        assert_eq!(t.count_tokens("hello world"), 2);
+        // tiktoken: 10 tokens
+        let n = t.count_tokens("The quick brown fox jumps over the lazy dog.");
+        assert!((9..=12).contains(&n), "{n}");
+    }
+
+    #[test]
+    fn test_approx_cjk_and_vietnamese() {
+        let t = ApproxTokenizer;
+        assert_eq!(t.count_tokens("你好世界"), 4);
+        // Diacritics cost far more than chars / 3 suggests
+        let vi = "Tôi muốn đặt hàng";
+        assert!(t.count_tokens(vi) >= 8, "{}", t.count_tokens(vi));
+    }
+
+    #[test]
+    fn test_count_message_tokens_adds_overhead() {
+        let messages = [Message::system("hello"), Message::user("world")];
+        assert_eq!(count_message_tokens(&ApproxTokenizer, &messages), 2 * MESSAGE_OVERHEAD + 2);
+    }
+}
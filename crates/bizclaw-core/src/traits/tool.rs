@@ -2,8 +2,13 @@
 
 use async_trait::async_trait;
 
-use crate::error::Result;
-use crate::types::{ToolDefinition, ToolResult};
+use crate::error::{BizClawError, Result};
+use crate::types::{ToolDefinition, ToolOutputChunk, ToolResult};
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Sink receiving incremental tool output while a tool runs.
+pub type ToolOutputSink = tokio::sync::mpsc::UnboundedSender<ToolOutputChunk>;
 
 /// Tool trait — every executable tool implements this.
 #[async_trait]
@@ -16,4 +21,32 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with given arguments.
     async fn execute(&self, arguments: &str) -> Result<ToolResult>;
+
+    /// Execute while streaming partial output to `sink`.
+    /// Tools without incremental output just run [`Tool::execute`].
+    async fn execute_streaming(&self, arguments: &str, sink: &ToolOutputSink) -> Result<ToolResult> {
+        let _ = sink;
+        self.execute(arguments).await
+    }
+
+    /// Execute until done or until `cancel` fires, streaming to `sink` if given.
+    /// The default drops the running execution on cancellation, so tools
+    /// only override this to clean up external state first.
+    async fn execute_cancellable(
+        &self,
+        arguments: &str,
+        sink: Option<&ToolOutputSink>,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        let run = async {
+            match sink {
+                Some(sink) => self.execute_streaming(arguments, sink).await,
+                None => self.execute(arguments).await,
+            }
+        };
+        tokio::select! {
+            result = run => result,
+            _ = cancel.cancelled() => Err(BizClawError::Tool(format!("{} cancelled", self.name()))),
+        }
+    }
 }
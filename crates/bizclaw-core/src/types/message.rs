@@ -34,6 +34,71 @@ pub struct Message {
     pub tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<super::ToolCall>>,
+    /// Images for vision-capable models. Providers map these to their own
+    /// multimodal content format.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageInput>,
+}
+
+/// An image attached to a message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ImageInput {
+    /// Remote image the provider fetches itself.
+    Url { url: String },
+    /// Inline image bytes, base64-encoded.
+    Base64 { mime_type: String, data: String },
+    /// Local image file, read and inlined when the request is built.
+    Path { path: String },
+}
+
+impl ImageInput {
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::Url { url: url.into() }
+    }
+
+    pub fn base64(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::Base64 {
+            mime_type: mime_type.into(),
+            data: data.into(),
+        }
+    }
+
+    pub fn path(path: impl Into<String>) -> Self {
+        Self::Path { path: path.into() }
+    }
+
+    /// This image with a `Path` read into `Base64` (MIME type from the file
+    /// extension). Other forms are returned as they are.
+    pub fn inline(&self) -> std::io::Result<Self> {
+        use base64::Engine;
+        let Self::Path { path } = self else {
+            return Ok(self.clone());
+        };
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mime_type = match ext.as_str() {
+            "png" => "image/png",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            _ => "image/jpeg",
+        };
+        let data = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+        Ok(Self::base64(mime_type, data))
+    }
+
+    /// URL form accepted by OpenAI-style `image_url` parts (`data:` URI for
+    /// inline images, `file://` for paths that were not inlined).
+    pub fn to_url(&self) -> String {
+        match self {
+            Self::Url { url } => url.clone(),
+            Self::Base64 { mime_type, data } => format!("data:{mime_type};base64,{data}"),
+            Self::Path { path } => format!("file://{path}"),
+        }
+    }
 }
 
 impl Message {
@@ -44,6 +109,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: vec![],
         }
     }
 
@@ -54,6 +120,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: vec![],
         }
     }
 
@@ -64,6 +131,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: vec![],
         }
     }
 
@@ -74,6 +142,15 @@ impl Message {
             name: None,
             tool_call_id: Some(tool_call_id.into()),
             tool_calls: None,
+            images: vec![],
+        }
+    }
+
+    /// User message with attached images.
+    pub fn user_with_images(content: impl Into<String>, images: Vec<ImageInput>) -> Self {
+        Self {
+            images,
+            ..Self::user(content)
         }
     }
 }
@@ -89,6 +166,9 @@ pub struct IncomingMessage {
     pub thread_type: ThreadType,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub reply_to: Option<String>,
+    /// Photos sent with the message (forwarded to vision-capable models).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageInput>,
 }
 
 /// Outgoing message to a channel.
@@ -179,6 +259,34 @@ mod tests {
         assert_eq!(parsed.role, Role::User);
     }
 
+    #[test]
+    fn test_message_images_serde() {
+        // Text-only messages keep their wire shape.
+        let json = serde_json::to_value(Message::user("hi")).unwrap();
+        assert!(json.get("images").is_none());
+
+        let msg = Message::user_with_images(
+            "what is this?",
+            vec![ImageInput::base64("image/png", "iVBORw0")],
+        );
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["images"][0]["type"], "base64");
+        let parsed: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.images[0].to_url(), "data:image/png;base64,iVBORw0");
+        assert_eq!(ImageInput::url("https://x/y.jpg").to_url(), "https://x/y.jpg");
+    }
+
+    #[test]
+    fn test_image_path_inlined() {
+        let path = std::env::temp_dir().join(format!("bizclaw-img-{}.PNG", std::process::id()));
+        std::fs::write(&path, b"\x89PNG").unwrap();
+
+        let image = ImageInput::path(path.to_string_lossy()).inline().unwrap();
+        assert_eq!(image, ImageInput::base64("image/png", "iVBORw=="));
+        assert!(ImageInput::path("/no/such/file.jpg").inline().is_err());
+        std::fs::remove_file(path).ok();
+    }
+
     #[test]
     fn test_provider_response() {
         let resp = ProviderResponse::text("hello");
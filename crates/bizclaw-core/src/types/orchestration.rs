@@ -1,4 +1,4 @@
-//! Multi-Agent Orchestration types — delegation, teams, handoff, evaluate loop, quality gates.
+//! Multi-Agent Orchestration types — delegation, teams, handoff, evaluate loop, pipelines, quality gates.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -346,6 +346,63 @@ pub struct EvaluateResult {
     pub max_rounds: u32,
 }
 
+// ── Pipelines ──────────────────────────────────────────────
+
+/// An agent-to-agent pipeline, e.g. researcher → writer → reviewer: each
+/// step's output is the next step's input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    /// Input to the first step.
+    pub input: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// One agent in a pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub agent: String,
+    /// What to do with the input, e.g. "Write a blog post from these
+    /// notes". Empty = the input is the whole prompt.
+    #[serde(default)]
+    pub instruction: String,
+    /// Seconds the step may take before the pipeline stops.
+    #[serde(default = "default_pipeline_step_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_pipeline_step_timeout() -> u64 {
+    120
+}
+
+impl PipelineStep {
+    pub fn new(agent: &str, instruction: &str) -> Self {
+        Self {
+            agent: agent.to_string(),
+            instruction: instruction.to_string(),
+            timeout_secs: default_pipeline_step_timeout(),
+        }
+    }
+}
+
+/// Outcome of one pipeline step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStepResult {
+    pub agent: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Outcome of a pipeline run: every step that ran, in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineResult {
+    /// Whether every step succeeded.
+    pub completed: bool,
+    /// Output of the last step that succeeded (the input if none did).
+    pub output: String,
+    pub steps: Vec<PipelineStepResult>,
+}
+
 // ── Quality Gates ──────────────────────────────────────────
 
 /// Type of quality gate hook.
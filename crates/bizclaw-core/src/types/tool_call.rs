@@ -32,3 +32,12 @@ pub struct ToolResult {
     pub output: String,
     pub success: bool,
 }
+
+/// Incremental output emitted by a long-running tool (e.g. shell stdout lines).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOutputChunk {
+    pub tool_name: String,
+    /// Source stream: "stdout" or "stderr".
+    pub stream: String,
+    pub data: String,
+}
@@ -2,12 +2,12 @@
 //!
 //! Architecture: Kotlin/Compose UI → UniFFI → bizclaw-ffi.so
 //!
-//! The FFI surface is intentionally minimal (5 functions)
-//! to keep the FFI surface minimal (5 functions):
+//! The FFI surface is intentionally minimal:
 //! - start_daemon(config, data_dir, host, port)
 //! - stop_daemon()
 //! - get_status() → JSON
 //! - send_message(msg) → JSON
+//! - register_stream_callback(callback) / clear_stream_callback()
 //! - get_version() → String
 //!
 //! ## Safety
@@ -19,16 +19,45 @@
 //! - Binary size: ~8MB stripped (arm64-v8a)
 //! - Cold start: <500ms on mid-range Snapdragon
 
+use bizclaw_agent::orchestrator::Orchestrator;
+use bizclaw_agent::progress::ProgressEvent;
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::traits::tokenizer::{ApproxTokenizer, Tokenizer};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, OnceLock};
-use tokio::sync::watch;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Instant;
+use tokio::sync::{mpsc, watch};
 
 /// Global daemon handle — initialized once via start_daemon().
 static DAEMON: OnceLock<Arc<DaemonHandle>> = OnceLock::new();
 
+/// Where send_message streams its output, if Kotlin registered a callback.
+static STREAM_CALLBACK: RwLock<Option<Arc<dyn StreamCallback>>> = RwLock::new(None);
+
+/// Session the app's conversation is kept in.
+const FFI_SESSION: &str = "android";
+
 struct DaemonHandle {
     shutdown_tx: watch::Sender<bool>,
     runtime: tokio::runtime::Runtime,
+    /// Agents answering send_message.
+    orchestrator: tokio::sync::Mutex<Orchestrator>,
+    /// When the daemon started.
+    started_at: Instant,
+    /// send_message calls served.
+    total_requests: AtomicU64,
+}
+
+/// Receives send_message output as it is generated — implemented on the
+/// Kotlin side (UniFFI callback interface). Called from daemon threads.
+pub trait StreamCallback: Send + Sync {
+    /// A piece of the reply text.
+    fn on_token(&self, token: String);
+    /// A tool-progress event as JSON, e.g.
+    /// `{"type":"tool_started","round":1,"tool":"web_search"}`.
+    fn on_progress(&self, event_json: String);
 }
 
 /// Daemon configuration — passed from Kotlin/Android side.
@@ -76,6 +105,17 @@ pub struct MessageResponse {
     pub tokens_used: u32,
 }
 
+impl MessageResponse {
+    fn failed(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            response: error.into(),
+            agent: String::new(),
+            tokens_used: 0,
+        }
+    }
+}
+
 /// Start the BizClaw daemon as a background Tokio runtime.
 ///
 /// # Safety
@@ -107,12 +147,9 @@ fn start_daemon_inner(config: DaemonConfig) -> Result<(), String> {
         .build()
         .map_err(|e| format!("Failed to create runtime: {e}"))?;
 
-    let (shutdown_tx, _shutdown_rx) = watch::channel(false);
-
-    let handle = Arc::new(DaemonHandle {
-        shutdown_tx,
-        runtime,
-    });
+    let bizclaw_config = load_config(&config.config_path)?;
+    let orchestrator = runtime.block_on(start_agents(bizclaw_config, &config.data_dir))?;
+    let handle = Arc::new(DaemonHandle::new(runtime, orchestrator));
 
     DAEMON
         .set(handle.clone())
@@ -128,11 +165,148 @@ fn start_daemon_inner(config: DaemonConfig) -> Result<(), String> {
     Ok(())
 }
 
+/// Read the config from a file path or inline TOML (defaults when empty).
+fn load_config(source: &str) -> Result<BizClawConfig, String> {
+    let source = source.trim();
+    if source.is_empty() {
+        return Ok(BizClawConfig::default());
+    }
+    let path = Path::new(source);
+    if path.is_file() {
+        return bizclaw_security::secrets::load_config(path).map_err(|e| e.to_string());
+    }
+    toml::from_str(source).map_err(|e| {
+        if source.contains('\n') {
+            format!("Invalid config: {e}")
+        } else {
+            format!("Config file not found: {source}")
+        }
+    })
+}
+
+/// Create the default agent, working in `<data_dir>/workspace`.
+async fn start_agents(config: BizClawConfig, data_dir: &str) -> Result<Orchestrator, String> {
+    let name = config.identity.name.clone();
+    let mut agent = bizclaw_agent::Agent::new_with_mcp(config)
+        .await
+        .map_err(|e| format!("Failed to start agent: {e}"))?;
+    if !data_dir.is_empty() {
+        let workspace = Path::new(data_dir).join("workspace");
+        std::fs::create_dir_all(&workspace).map_err(|e| format!("Failed to create workspace: {e}"))?;
+        agent.set_workspace(workspace);
+    }
+    let mut orchestrator = Orchestrator::new();
+    orchestrator.add_agent(&name, "assistant", "On-device assistant", agent);
+    Ok(orchestrator)
+}
+
+impl DaemonHandle {
+    fn new(runtime: tokio::runtime::Runtime, orchestrator: Orchestrator) -> Self {
+        let (shutdown_tx, _shutdown_rx) = watch::channel(false);
+        Self {
+            shutdown_tx,
+            runtime,
+            orchestrator: tokio::sync::Mutex::new(orchestrator),
+            started_at: Instant::now(),
+            total_requests: AtomicU64::new(0),
+        }
+    }
+
+    fn status(&self) -> DaemonStatus {
+        let running = !*self.shutdown_tx.borrow();
+        let agents = self.orchestrator.blocking_lock().list_agents();
+        DaemonStatus {
+            running,
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            agent_count: agents.len(),
+            // Each agent's current conversation plus the sessions it keeps parked.
+            active_sessions: agents.iter().filter_map(|a| a["sessions"].as_u64()).sum::<u64>() as usize,
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            memory_bytes: estimate_memory(),
+            version: get_version(),
+        }
+    }
+
+    /// Answer `message` with the default agent, streaming to the
+    /// registered callback when there is one.
+    async fn process(&self, message: &str) -> MessageResponse {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let mut orch = self.orchestrator.lock().await.clone().with_session(FFI_SESSION);
+        let Some(agent) = orch.default_agent_name().map(String::from) else {
+            return MessageResponse::failed("No agent loaded");
+        };
+        let callback = STREAM_CALLBACK.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let result = match callback {
+            Some(callback) => stream_to(callback.as_ref(), &mut orch, &agent, message).await,
+            None => orch.send_to(&agent, message).await,
+        };
+        match result {
+            Ok(response) => MessageResponse {
+                success: true,
+                tokens_used: (ApproxTokenizer.count_tokens(message) + ApproxTokenizer.count_tokens(&response)) as u32,
+                response,
+                agent,
+            },
+            Err(e) => MessageResponse {
+                agent,
+                ..MessageResponse::failed(e.to_string())
+            },
+        }
+    }
+}
+
+/// Send `message` to `agent`, passing reply tokens and progress events to
+/// `callback` while it runs.
+async fn stream_to(
+    callback: &dyn StreamCallback,
+    orch: &mut Orchestrator,
+    agent: &str,
+    message: &str,
+) -> bizclaw_core::error::Result<String> {
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel();
+    let run = async move {
+        let result = orch.send_to_streaming(agent, message, &progress_tx, &chunk_tx).await;
+        drop((progress_tx, chunk_tx));
+        result
+    };
+    let forward = async {
+        let (mut progress_open, mut chunks_open) = (true, true);
+        while progress_open || chunks_open {
+            tokio::select! {
+                event = progress_rx.recv(), if progress_open => match event {
+                    Some(event) => callback.on_progress(progress_json(&event)),
+                    None => progress_open = false,
+                },
+                chunk = chunk_rx.recv(), if chunks_open => match chunk {
+                    Some(chunk) => callback.on_token(chunk),
+                    None => chunks_open = false,
+                },
+            }
+        }
+    };
+    let (result, ()) = tokio::join!(run, forward);
+    result
+}
+
+fn progress_json(event: &ProgressEvent) -> String {
+    match event {
+        ProgressEvent::Round { round } => serde_json::json!({ "type": "round", "round": round }),
+        ProgressEvent::ToolStarted { round, tool } => {
+            serde_json::json!({ "type": "tool_started", "round": round, "tool": tool })
+        }
+        ProgressEvent::ToolFinished { round, tool, success } => {
+            serde_json::json!({ "type": "tool_finished", "round": round, "tool": tool, "success": success })
+        }
+    }
+    .to_string()
+}
+
 /// Stop the daemon gracefully.
 pub fn stop_daemon() -> Result<(), String> {
     std::panic::catch_unwind(|| {
         if let Some(handle) = DAEMON.get() {
-            handle.shutdown_tx.send(true).ok();
+            handle.shutdown_tx.send_replace(true);
             tracing::info!("🛑 BizClaw daemon stopping...");
             Ok(())
         } else {
@@ -145,18 +319,9 @@ pub fn stop_daemon() -> Result<(), String> {
 /// Get daemon status as JSON string.
 pub fn get_status() -> String {
     std::panic::catch_unwind(|| {
-        let status = if DAEMON.get().is_some() {
-            DaemonStatus {
-                running: true,
-                uptime_secs: 0, // TODO: track actual uptime
-                agent_count: 0,
-                active_sessions: 0,
-                total_requests: 0,
-                memory_bytes: estimate_memory(),
-                version: get_version(),
-            }
-        } else {
-            DaemonStatus {
+        let status = match DAEMON.get() {
+            Some(handle) => handle.status(),
+            None => DaemonStatus {
                 running: false,
                 uptime_secs: 0,
                 agent_count: 0,
@@ -164,42 +329,37 @@ pub fn get_status() -> String {
                 total_requests: 0,
                 memory_bytes: 0,
                 version: get_version(),
-            }
+            },
         };
         serde_json::to_string(&status).unwrap_or_else(|_| "{}".into())
     })
     .unwrap_or_else(|_| r#"{"running":false,"error":"panic"}"#.into())
 }
 
-/// Send a message to the default agent, get response as JSON.
+/// Send a message to the default agent, get response as JSON. Blocks until
+/// the reply is complete; call it off the UI thread.
 pub fn send_message(message: &str) -> String {
     std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        if let Some(handle) = DAEMON.get() {
-            // Execute on the daemon's runtime
-            let msg = message.to_string();
-            let result = handle.runtime.block_on(async {
-                // TODO: route to actual agent
-                MessageResponse {
-                    success: true,
-                    response: format!("Echo: {}", msg),
-                    agent: "default".into(),
-                    tokens_used: 0,
-                }
-            });
-            serde_json::to_string(&result).unwrap_or_else(|_| "{}".into())
-        } else {
-            serde_json::to_string(&MessageResponse {
-                success: false,
-                response: "Daemon not running".into(),
-                agent: String::new(),
-                tokens_used: 0,
-            })
-            .unwrap_or_else(|_| "{}".into())
-        }
+        let result = match DAEMON.get() {
+            Some(handle) => handle.runtime.block_on(handle.process(message)),
+            None => MessageResponse::failed("Daemon not running"),
+        };
+        serde_json::to_string(&result).unwrap_or_else(|_| "{}".into())
     }))
     .unwrap_or_else(|_| r#"{"success":false,"response":"panic"}"#.into())
 }
 
+/// Stream send_message output (reply tokens, tool progress) to `callback`,
+/// replacing any callback registered before.
+pub fn register_stream_callback(callback: Box<dyn StreamCallback>) {
+    *STREAM_CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::from(callback));
+}
+
+/// Stop streaming send_message output.
+pub fn clear_stream_callback() {
+    *STREAM_CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
 /// Get BizClaw version string.
 pub fn get_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
@@ -289,7 +449,7 @@ pub fn execute_device_action(action_json: &str) -> String {
 /// Rough memory estimate for edge device monitoring.
 fn estimate_memory() -> u64 {
     // On Linux/Android, read /proc/self/status
-    #[cfg(target_os = "linux")]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
     {
         if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
             for line in status.lines() {
@@ -323,6 +483,53 @@ mod tests {
         assert_eq!(parsed["running"], false);
     }
 
+    #[test]
+    fn test_load_config() {
+        let config = load_config("default_provider = \"ollama\"\ndefault_model = \"qwen2.5\"").unwrap();
+        assert_eq!(config.default_provider, "ollama");
+        assert_eq!(config.default_model, "qwen2.5");
+        assert!(load_config("").is_ok());
+        assert!(load_config("/no/such/bizclaw.toml").unwrap_err().contains("not found"));
+
+        let dir = std::env::temp_dir().join(format!("bizclaw_ffi_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("profile=work.toml");
+        std::fs::write(&path, "default_provider = \"groq\"\n[secrets]\nencrypt = false\n").unwrap();
+        assert_eq!(load_config(path.to_str().unwrap()).unwrap().default_provider, "groq");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_progress_json() {
+        let event = ProgressEvent::ToolFinished {
+            round: 2,
+            tool: "web_search".into(),
+            success: true,
+        };
+        let parsed: serde_json::Value = serde_json::from_str(&progress_json(&event)).unwrap();
+        assert_eq!(parsed["type"], "tool_finished");
+        assert_eq!(parsed["tool"], "web_search");
+        assert_eq!(parsed["success"], true);
+    }
+
+    #[test]
+    fn test_status_counts_requests_and_sessions() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let handle = DaemonHandle::new(runtime, Orchestrator::new());
+        let status = handle.status();
+        assert!(status.running);
+        assert_eq!((status.agent_count, status.active_sessions, status.total_requests), (0, 0, 0));
+
+        let reply = handle.runtime.block_on(handle.process("xin chào"));
+        assert!(!reply.success);
+        assert_eq!(reply.response, "No agent loaded");
+        let status = handle.status();
+        assert_eq!((status.active_sessions, status.total_requests), (0, 1));
+
+        handle.shutdown_tx.send_replace(true);
+        assert!(!handle.status().running);
+    }
+
     #[test]
     fn test_send_message_not_running() {
         let resp = send_message("hello");
@@ -0,0 +1,333 @@
+//! API keys — scoped bearer tokens for the gateway REST API.
+//!
+//! The pairing code is the dashboard's login and grants everything. Scripts
+//! and integrations get their own keys instead, sent as
+//! `Authorization: Bearer bzk_…` or `X-API-Key: bzk_…`, each with scopes and
+//! an optional per-minute rate limit:
+//!
+//! - `read`  — GET requests (lists, stats, traces)
+//! - `chat`  — agent chat and structured replies, delegation, pipeline
+//!   runs, `/ws` and `/v1/chat/completions`
+//! - `admin` — everything else: config, providers, channels, keys, audit log…
+//!
+//! Scopes are levels: `admin` includes `chat`, which includes `read`. Keys
+//! are shown once when created; the database keeps only their SHA-256 hash.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, extract::State};
+use sha2::{Digest, Sha256};
+
+use super::db::ApiKeyRecord;
+use super::server::AppState;
+
+/// Every key starts with this, so it can't be mistaken for a pairing code.
+pub const KEY_PREFIX: &str = "bzk_";
+
+/// What a key may do. Ordered: each scope includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Chat,
+    Admin,
+}
+
+impl Scope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "read" => Some(Self::Read),
+            "chat" => Some(Self::Chat),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Chat => "chat",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Scope a request needs.
+    pub fn required_for(method: &Method, path: &str) -> Self {
+        let is_chat = path == "/ws"
+            || path == "/v1/chat/completions"
+            || path == "/mcp"
+            || path == "/api/v1/orchestration/delegate"
+            || path == "/api/v1/pipelines/run"
+            || (path.starts_with("/api/v1/agents/")
+                && (path.ends_with("/chat") || path.ends_with("/chat/stream") || path.ends_with("/structured")));
+        if is_chat {
+            Self::Chat
+        } else if path.starts_with("/api/v1/api-keys")
+            || path.starts_with("/api/v1/audit")
+            || path == "/api/v1/config/full"
+        {
+            // Key management, the audit log and unmasked config stay admin-only, even to read
+            Self::Admin
+        } else if matches!(*method, Method::GET | Method::HEAD) {
+            Self::Read
+        } else {
+            Self::Admin
+        }
+    }
+}
+
+/// Whether a key with `scopes` may make a request needing `required`.
+pub fn grants(scopes: &[String], required: Scope) -> bool {
+    scopes.iter().filter_map(|s| Scope::parse(s)).any(|s| s >= required)
+}
+
+/// SHA-256 of a key, as stored in the database.
+pub fn hash_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// A new random key: `bzk_` + 64 hex characters.
+pub fn generate_key() -> String {
+    format!("{KEY_PREFIX}{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// The API key sent with a request, if any.
+pub fn request_key(headers: &axum::http::HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let header = headers.get("x-api-key").and_then(|v| v.to_str().ok());
+    [bearer, header]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|k| k.starts_with(KEY_PREFIX))
+        .map(str::to_string)
+}
+
+/// Per-key request counts over one-minute windows.
+#[derive(Default)]
+pub struct ApiKeyLimiter {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl ApiKeyLimiter {
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    /// Count a request for `key_id`. Returns the seconds to wait when over
+    /// `per_minute` (0 = unlimited).
+    pub fn check(&self, key_id: &str, per_minute: u32) -> Result<(), u64> {
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (start, count) = windows.entry(key_id.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= Self::WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= per_minute {
+            let wait = Self::WINDOW.saturating_sub(now.duration_since(*start));
+            return Err(wait.as_secs().max(1));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Forget a revoked key's window.
+    pub fn remove(&self, key_id: &str) {
+        self.windows.lock().unwrap().remove(key_id);
+    }
+}
+
+/// Why a key was refused.
+#[derive(Debug, PartialEq)]
+pub enum Denied {
+    UnknownKey,
+    MissingScope(Scope),
+    RateLimited { retry_after: u64 },
+}
+
+impl IntoResponse for Denied {
+    fn into_response(self) -> Response {
+        let (status, error) = match &self {
+            Self::UnknownKey => (StatusCode::UNAUTHORIZED, "Unauthorized — invalid API key".to_string()),
+            Self::MissingScope(scope) => (
+                StatusCode::FORBIDDEN,
+                format!("Forbidden — API key lacks the '{}' scope", scope.as_str()),
+            ),
+            Self::RateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, "API key rate limit exceeded".to_string()),
+        };
+        let mut response = (status, Json(serde_json::json!({"ok": false, "error": error}))).into_response();
+        if let Self::RateLimited { retry_after } = self {
+            response.headers_mut().insert("Retry-After", retry_after.into());
+        }
+        response
+    }
+}
+
+/// Check `key` for a request needing `required`: known, scoped, within its
+/// rate limit. Marks the key as used.
+pub fn authorize(state: &AppState, key: &str, required: Scope) -> Result<ApiKeyRecord, Denied> {
+    let record = state
+        .db
+        .find_api_key(&hash_key(key))
+        .ok()
+        .flatten()
+        .ok_or(Denied::UnknownKey)?;
+    if !grants(&record.scopes, required) {
+        return Err(Denied::MissingScope(required));
+    }
+    state
+        .api_key_limiter
+        .check(&record.id, record.rate_limit)
+        .map_err(|retry_after| Denied::RateLimited { retry_after })?;
+    if let Err(e) = state.db.touch_api_key(&record.id) {
+        tracing::warn!("⚠️ Failed to record API key use: {e}");
+    }
+    Ok(record)
+}
+
+// ─── Key management endpoints (admin) ────────────────────────────────────────
+
+/// GET /api/v1/api-keys — list keys (never the secrets).
+pub async fn list_api_keys(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    match state.db.list_api_keys() {
+        Ok(keys) => Json(serde_json::json!({"ok": true, "keys": keys})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// POST /api/v1/api-keys — `{"name", "scopes": ["read","chat"], "rate_limit": 60}`.
+/// The response holds the key; it can't be shown again.
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let name = body["name"].as_str().unwrap_or("").trim();
+    if name.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Key name is required"}));
+    }
+    let requested: Vec<&str> = body["scopes"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|s| s.as_str()).collect())
+        .unwrap_or_default();
+    if requested.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "At least one scope is required (read, chat, admin)"}));
+    }
+    let mut scopes = Vec::new();
+    for s in requested {
+        let Some(scope) = Scope::parse(s) else {
+            return Json(serde_json::json!({"ok": false, "error": format!("Unknown scope: {s}")}));
+        };
+        if !scopes.contains(&scope.as_str().to_string()) {
+            scopes.push(scope.as_str().to_string());
+        }
+    }
+
+    let key = generate_key();
+    let record = ApiKeyRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        key_hash: hash_key(&key),
+        prefix: key[..KEY_PREFIX.len() + 8].to_string(),
+        scopes,
+        rate_limit: body["rate_limit"].as_u64().unwrap_or(0).min(u32::MAX as u64) as u32,
+        created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        last_used_at: None,
+    };
+    if let Err(e) = state.db.insert_api_key(&record) {
+        return Json(serde_json::json!({"ok": false, "error": e}));
+    }
+    tracing::info!("🔑 API key '{}' created ({})", record.name, record.scopes.join(", "));
+    super::audit::record(&state, "api_key.create", &record.id, None, serde_json::to_value(&record).ok());
+    Json(serde_json::json!({"ok": true, "key": key, "api_key": record}))
+}
+
+/// DELETE /api/v1/api-keys/{id} — revoke a key.
+pub async fn delete_api_key(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let previous = state
+        .db
+        .list_api_keys()
+        .ok()
+        .and_then(|keys| keys.into_iter().find(|k| k.id == id))
+        .and_then(|k| serde_json::to_value(k).ok());
+    match state.db.delete_api_key(&id) {
+        Ok(true) => {
+            state.api_key_limiter.remove(&id);
+            super::audit::record(&state, "api_key.delete", &id, previous, None);
+            tracing::info!("🔑 API key {id} revoked");
+            Json(serde_json::json!({"ok": true}))
+        }
+        Ok(false) => Json(serde_json::json!({"ok": false, "error": "API key not found"})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(Scope::required_for(&Method::GET, "/api/v1/agents"), Scope::Read);
+        assert_eq!(Scope::required_for(&Method::POST, "/api/v1/agents/sales/chat"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::POST, "/api/v1/agents/sales/chat/stream"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::POST, "/api/v1/agents/sales/structured"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::GET, "/ws"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::POST, "/mcp"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::POST, "/api/v1/pipelines/run"), Scope::Chat);
+        assert_eq!(Scope::required_for(&Method::POST, "/api/v1/config/update"), Scope::Admin);
+        assert_eq!(Scope::required_for(&Method::GET, "/api/v1/config/full"), Scope::Admin);
+        assert_eq!(Scope::required_for(&Method::GET, "/api/v1/api-keys"), Scope::Admin);
+        assert_eq!(Scope::required_for(&Method::GET, "/api/v1/audit"), Scope::Admin);
+    }
+
+    #[test]
+    fn test_scopes_are_levels() {
+        let chat = vec!["chat".to_string()];
+        assert!(grants(&chat, Scope::Read));
+        assert!(grants(&chat, Scope::Chat));
+        assert!(!grants(&chat, Scope::Admin));
+        assert!(grants(&["admin".to_string()], Scope::Chat));
+        assert!(!grants(&["bogus".to_string()], Scope::Read));
+    }
+
+    #[test]
+    fn test_request_key() {
+        let key = generate_key();
+        assert_eq!(key.len(), KEY_PREFIX.len() + 64);
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {key}").parse().unwrap());
+        assert_eq!(request_key(&headers).as_deref(), Some(key.as_str()));
+
+        // A bearer pairing code isn't an API key
+        headers.insert("authorization", "Bearer 123456".parse().unwrap());
+        assert_eq!(request_key(&headers), None);
+        headers.insert("x-api-key", key.parse().unwrap());
+        assert_eq!(request_key(&headers).as_deref(), Some(key.as_str()));
+    }
+
+    #[test]
+    fn test_rate_limit_per_key() {
+        let limiter = ApiKeyLimiter::default();
+        assert!(limiter.check("a", 2).is_ok());
+        assert!(limiter.check("a", 2).is_ok());
+        let wait = limiter.check("a", 2).unwrap_err();
+        assert!((1..=60).contains(&wait));
+        assert!(limiter.check("b", 2).is_ok());
+        assert!(limiter.check("c", 0).is_ok());
+        limiter.remove("a");
+        assert!(limiter.check("a", 2).is_ok());
+    }
+}
@@ -0,0 +1,231 @@
+//! Audit log — who changed what, and when.
+//!
+//! Every mutating request that passes the auth middleware runs inside an
+//! audit scope carrying the caller: `dashboard` (pairing code),
+//! `api_key:<name>`, or `anonymous` when no pairing code is set. Handlers
+//! that know what they changed call [`record`] with the old and new values;
+//! requests that don't get one generic entry (`POST /api/v1/hands/{name}/run`)
+//! so nothing goes unlogged. Entries are written after the response, with
+//! its status, to the gateway DB's `audit_log` table.
+//!
+//! Values are stored with secrets (api keys, tokens, passwords) masked.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, State};
+use axum::http::Method;
+use axum::{Json, response::Response};
+use serde_json::Value;
+
+use super::db::{AuditFilter, AuditRecord};
+use super::server::AppState;
+
+tokio::task_local! {
+    static SCOPE: Scope;
+}
+
+/// The request being audited: who sent it, and what handlers recorded.
+struct Scope {
+    actor: String,
+    entries: Mutex<Vec<AuditRecord>>,
+    /// Set by [`skip`]: the request changed nothing.
+    skipped: AtomicBool,
+}
+
+/// Run `next` for an authenticated request, then write its audit entries.
+/// Reads (GET/HEAD) and chat requests aren't audited.
+pub async fn run(
+    state: &AppState,
+    actor: String,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> Response {
+    let required = super::api_keys::Scope::required_for(req.method(), req.uri().path());
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || required == super::api_keys::Scope::Chat
+    {
+        return next.run(req).await;
+    }
+    let action = format!(
+        "{} {}",
+        req.method(),
+        req.extensions()
+            .get::<axum::extract::MatchedPath>()
+            .map_or_else(|| req.uri().path(), |p| p.as_str())
+    );
+    let target = req.uri().path().to_string();
+    let scope = Scope {
+        actor: actor.clone(),
+        entries: Mutex::new(Vec::new()),
+        skipped: AtomicBool::new(false),
+    };
+    let (response, mut entries, skipped) = SCOPE
+        .scope(scope, async {
+            let response = next.run(req).await;
+            SCOPE.with(|s| {
+                let entries = std::mem::take(&mut *s.entries.lock().unwrap());
+                (response, entries, s.skipped.load(Ordering::Relaxed))
+            })
+        })
+        .await;
+
+    let status = response.status().as_u16();
+    if entries.is_empty() && !skipped {
+        entries.push(entry(actor, &action, &target, None, None));
+    }
+    for mut e in entries {
+        e.status = status;
+        if let Err(err) = state.db.insert_audit(&e) {
+            tracing::warn!("⚠️ Audit log write failed ({}): {err}", e.action);
+        }
+    }
+    response
+}
+
+fn entry(actor: String, action: &str, target: &str, old: Option<Value>, new: Option<Value>) -> AuditRecord {
+    let changes = match (&old, &new) {
+        (Some(o), Some(n)) => diff(o, n),
+        (None, Some(n)) => diff(&Value::Null, n),
+        (Some(o), None) => diff(o, &Value::Null),
+        (None, None) => Vec::new(),
+    };
+    AuditRecord {
+        id: 0,
+        actor,
+        action: action.to_string(),
+        target: target.to_string(),
+        status: 0,
+        old_value: old.map(redact),
+        new_value: new.map(redact),
+        changes,
+        created_at: String::new(),
+    }
+}
+
+/// Record a change made by the current request: `action` like
+/// `"agent.update"`, the `target` it applies to, and the value before and
+/// after (`None` for creations and deletions). Outside a request — startup,
+/// background tasks, tests — the entry is written at once as `system`.
+pub fn record(state: &AppState, action: &str, target: &str, old: Option<Value>, new: Option<Value>) {
+    let pending = SCOPE.try_with(|s| {
+        s.entries
+            .lock()
+            .unwrap()
+            .push(entry(s.actor.clone(), action, target, old.clone(), new.clone()));
+    });
+    if pending.is_err()
+        && let Err(e) = state.db.insert_audit(&entry("system".into(), action, target, old, new))
+    {
+        tracing::warn!("⚠️ Audit log write failed ({action}): {e}");
+    }
+}
+
+/// Mark the current request as changing nothing (a dry run), so it gets no
+/// generic entry.
+pub fn skip() {
+    let _ = SCOPE.try_with(|s| s.skipped.store(true, Ordering::Relaxed));
+}
+
+fn is_secret(field: &str) -> bool {
+    let field = field.to_lowercase();
+    ["api_key", "apikey", "secret", "token", "password"].iter().any(|s| field.contains(s))
+}
+
+/// `value` with every non-empty secret field replaced by `"***"`.
+pub fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        Value::String(s) if is_secret(&k) && !s.is_empty() => Value::from("***"),
+                        other => redact(other),
+                    };
+                    (k, v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        other => other,
+    }
+}
+
+/// Changed leaf fields between two JSON values as `{"field", "from", "to"}`
+/// (dotted paths, secrets masked).
+pub fn diff(old: &Value, new: &Value) -> Vec<Value> {
+    fn leaves(prefix: &str, value: &Value, out: &mut std::collections::BTreeMap<String, Value>) {
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    let path = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
+                    leaves(&path, v, out);
+                }
+            }
+            Value::Null if prefix.is_empty() => {}
+            other => {
+                out.insert(prefix.to_string(), other.clone());
+            }
+        }
+    }
+    let (mut before, mut after) = Default::default();
+    leaves("", old, &mut before);
+    leaves("", new, &mut after);
+
+    let show = |field: &str, v: Option<&Value>| match v {
+        Some(v) if is_secret(field) && v.as_str().is_some_and(|s| !s.is_empty()) => Value::from("***"),
+        Some(v) => v.clone(),
+        None => Value::Null,
+    };
+    let fields: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|f| before.get(*f) != after.get(*f))
+        .map(|f| serde_json::json!({"field": f, "from": show(f, before.get(f)), "to": show(f, after.get(f))}))
+        .collect()
+}
+
+// ─── Endpoints (admin) ───────────────────────────────────────────────────────
+
+/// GET /api/v1/audit — `?actor=&action=&target=&since=&until=&limit=&offset=`.
+/// An action ending in `.` matches a prefix (`agent.`).
+pub async fn list_audit(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<AuditFilter>,
+) -> Json<Value> {
+    match state.db.list_audit(&filter) {
+        Ok(entries) => Json(serde_json::json!({"ok": true, "entries": entries})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// GET /api/v1/audit/{id} — one entry.
+pub async fn get_audit(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> Json<Value> {
+    match state.db.get_audit(id) {
+        Ok(Some(entry)) => Json(serde_json::json!({"ok": true, "entry": entry})),
+        Ok(None) => Json(serde_json::json!({"ok": false, "error": "Audit entry not found"})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_redact() {
+        let old = serde_json::json!({"provider": "openai", "channel": {"bot_token": "abc", "enabled": true}});
+        let new = serde_json::json!({"provider": "ollama", "channel": {"bot_token": "xyz", "enabled": true}});
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0], serde_json::json!({"field": "channel.bot_token", "from": "***", "to": "***"}));
+        assert_eq!(changes[1], serde_json::json!({"field": "provider", "from": "openai", "to": "ollama"}));
+
+        // Creation: every field is new
+        let created = diff(&Value::Null, &serde_json::json!({"name": "sales"}));
+        assert_eq!(created, vec![serde_json::json!({"field": "name", "from": null, "to": "sales"})]);
+
+        let redacted = redact(serde_json::json!({"api_key": "sk-1", "nested": [{"password": "p"}], "token": ""}));
+        assert_eq!(redacted, serde_json::json!({"api_key": "***", "nested": [{"password": "***"}], "token": ""}));
+    }
+}
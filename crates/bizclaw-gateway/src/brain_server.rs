@@ -0,0 +1,620 @@
+//! OpenAI-compatible API for the local brain (`bizclaw brain serve`).
+//!
+//! Serves `/v1/chat/completions`, `/v1/completions` and `/v1/models`
+//! straight from [`BrainEngine`](bizclaw_brain::BrainEngine)s — no agent,
+//! tools or memory — so editors and chat UIs that speak the OpenAI API
+//! (Continue, OpenWebUI, ...) can use the Rust engine in place of llama.cpp's
+//! `llama-server`. `"stream": true` answers with server-sent events.
+//!
+//! The `model` field selects a `.gguf` file in the models directory by file
+//! name; any other value gets the default model.
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bizclaw_brain::pool::{ModelPool, SharedEngine};
+use bizclaw_core::error::BizClawError;
+use bizclaw_core::types::{Message, Usage};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+
+/// Shared state of the brain API.
+pub struct BrainServer {
+    pool: Arc<ModelPool>,
+    default_model: PathBuf,
+    models_dir: PathBuf,
+    /// Completion budget for requests without `max_tokens`.
+    default_max_tokens: u32,
+    /// Required `Authorization: Bearer` token, if any.
+    api_key: Option<String>,
+}
+
+impl BrainServer {
+    pub fn new(
+        pool: Arc<ModelPool>,
+        default_model: PathBuf,
+        models_dir: PathBuf,
+        default_max_tokens: u32,
+    ) -> Self {
+        Self {
+            pool,
+            default_model,
+            models_dir,
+            default_max_tokens,
+            api_key: None,
+        }
+    }
+
+    /// Require `Authorization: Bearer <key>` on every `/v1` request.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(key) = &self.api_key else {
+            return true;
+        };
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|token| token.trim() == key)
+    }
+
+    /// The model file for a request: a `.gguf` file name in the models
+    /// directory (paths are not accepted from clients), else the default.
+    fn resolve_model(&self, requested: &str) -> PathBuf {
+        let is_file_name = Path::new(requested).file_name().and_then(|f| f.to_str()) == Some(requested);
+        if is_file_name && requested.ends_with(".gguf") {
+            let path = self.models_dir.join(requested);
+            if path.is_file() {
+                return path;
+            }
+        }
+        self.default_model.clone()
+    }
+
+    /// The loaded engine for `path`, loading it on first use.
+    async fn engine(&self, path: PathBuf) -> Result<SharedEngine, BizClawError> {
+        let pool = Arc::clone(&self.pool);
+        tokio::task::spawn_blocking(move || pool.get(&path))
+            .await
+            .map_err(|e| BizClawError::Brain(format!("Model load task failed: {e}")))?
+    }
+}
+
+/// Routes of the brain API.
+pub fn router(server: Arc<BrainServer>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/completions", post(completions))
+        .with_state(server)
+}
+
+/// Serve the brain API on `host:port` until the process exits.
+pub async fn serve(server: BrainServer, host: &str, port: u16) -> anyhow::Result<()> {
+    let addr = format!("{host}:{port}");
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("🧠 Brain API listening on http://{addr}/v1");
+    axum::serve(listener, router(Arc::new(server))).await?;
+    Ok(())
+}
+
+// ─── Requests ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ChatRequest {
+    #[serde(default)]
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(flatten)]
+    pub options: GenerationOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    /// A string, or an array of `{"type": "text", "text": ...}` parts.
+    #[serde(default)]
+    pub content: Value,
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompletionRequest {
+    #[serde(default)]
+    pub model: String,
+    /// A string, or an array whose first string is used.
+    pub prompt: Value,
+    #[serde(flatten)]
+    pub options: GenerationOptions,
+}
+
+/// Sampling and output fields shared by both endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct GenerationOptions {
+    #[serde(default, alias = "max_completion_tokens")]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub stream: bool,
+    /// A string or a list of strings.
+    #[serde(default)]
+    pub stop: Option<Value>,
+}
+
+impl GenerationOptions {
+    fn stop_sequences(&self) -> Vec<String> {
+        let stops = match &self.stop {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => vec![],
+        };
+        stops.into_iter().filter(|s| !s.is_empty()).collect()
+    }
+}
+
+/// Convert OpenAI chat messages; unknown roles are rejected.
+fn to_messages(messages: &[ChatMessage]) -> Result<Vec<Message>, String> {
+    messages
+        .iter()
+        .map(|m| {
+            let content = match &m.content {
+                Value::String(s) => s.clone(),
+                Value::Null => String::new(),
+                Value::Array(parts) => parts
+                    .iter()
+                    .filter(|p| p["type"] == "text")
+                    .filter_map(|p| p["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                other => return Err(format!("Unsupported message content: {other}")),
+            };
+            Ok(match m.role.as_str() {
+                "system" | "developer" => Message::system(content),
+                "user" => Message::user(content),
+                "assistant" => Message::assistant(content),
+                "tool" => Message::tool(content, m.tool_call_id.clone().unwrap_or_default()),
+                other => return Err(format!("Unknown message role '{other}'")),
+            })
+        })
+        .collect()
+}
+
+// ─── Handlers ────────────────────────────────────────────────────────────────
+
+async fn health() -> Json<Value> {
+    Json(json!({"status": "ok"}))
+}
+
+async fn list_models(State(server): State<Arc<BrainServer>>, headers: HeaderMap) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Invalid API key");
+    }
+    let mut ids: Vec<String> = std::fs::read_dir(&server.models_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("gguf"))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    ids.sort();
+    let default = model_id(&server.default_model);
+    ids.retain(|id| *id != default);
+    ids.insert(0, default);
+
+    let data: Vec<Value> = ids
+        .iter()
+        .map(|id| json!({"id": id, "object": "model", "created": 0, "owned_by": "bizclaw"}))
+        .collect();
+    Json(json!({"object": "list", "data": data})).into_response()
+}
+
+async fn chat_completions(
+    State(server): State<Arc<BrainServer>>,
+    headers: HeaderMap,
+    Json(req): Json<ChatRequest>,
+) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Invalid API key");
+    }
+    match to_messages(&req.messages) {
+        Ok(messages) => complete(&server, Prompt::Chat(messages), &req.model, req.options).await,
+        Err(e) => error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn completions(
+    State(server): State<Arc<BrainServer>>,
+    headers: HeaderMap,
+    Json(req): Json<CompletionRequest>,
+) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Invalid API key");
+    }
+    let prompt = match &req.prompt {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => match items.first().and_then(|v| v.as_str()) {
+            Some(s) => s.to_string(),
+            None => return error(StatusCode::BAD_REQUEST, "prompt must be a string"),
+        },
+        _ => return error(StatusCode::BAD_REQUEST, "prompt must be a string"),
+    };
+    complete(&server, Prompt::Text(prompt), &req.model, req.options).await
+}
+
+// ─── Generation ──────────────────────────────────────────────────────────────
+
+/// What to generate from.
+enum Prompt {
+    /// Rendered with the model's chat template.
+    Chat(Vec<Message>),
+    /// Raw text continuation.
+    Text(String),
+}
+
+/// How a generation ended.
+struct Outcome {
+    /// A stop sequence was produced.
+    stopped: bool,
+    usage: Usage,
+}
+
+impl Outcome {
+    fn finish_reason(&self, max_tokens: u32) -> &'static str {
+        if !self.stopped && self.usage.completion_tokens >= max_tokens {
+            "length"
+        } else {
+            "stop"
+        }
+    }
+}
+
+async fn complete(server: &BrainServer, prompt: Prompt, model: &str, options: GenerationOptions) -> Response {
+    let path = server.resolve_model(model);
+    let engine = match server.engine(path.clone()).await {
+        Ok(engine) => engine,
+        Err(e) => return error(StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+    };
+    let format = Format {
+        chat: matches!(prompt, Prompt::Chat(_)),
+        id: format!("cmpl-{}", uuid::Uuid::new_v4().simple()),
+        created: chrono::Utc::now().timestamp(),
+        model: model_id(&path),
+    };
+    let max_tokens = options.max_tokens.unwrap_or(server.default_max_tokens);
+    let stream = options.stream;
+    let (tx, mut rx) = unbounded_channel();
+    let task = spawn_generation(engine, prompt, options, max_tokens, tx);
+
+    if !stream {
+        let mut text = String::new();
+        while let Some(piece) = rx.recv().await {
+            text.push_str(&piece);
+        }
+        return match task.await {
+            Ok(Ok(outcome)) => Json(format.response(&text, &outcome, max_tokens)).into_response(),
+            Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("Generation task failed: {e}")),
+        };
+    }
+
+    // Dropping the response stream closes `events`, which ends generation
+    let (events, events_rx) = unbounded_channel::<Event>();
+    tokio::spawn(async move {
+        let send = |data: Value| events.send(Event::default().data(data.to_string())).is_ok();
+        if format.chat && !send(format.chunk(json!({"role": "assistant", "content": ""}), None)) {
+            return;
+        }
+        while let Some(piece) = rx.recv().await {
+            if !send(format.chunk(json!(piece), None)) {
+                return;
+            }
+        }
+        let last = match task.await {
+            Ok(Ok(outcome)) => format.chunk(json!({}), Some(outcome.finish_reason(max_tokens))),
+            Ok(Err(e)) => json!({"error": {"message": e.to_string(), "type": "server_error"}}),
+            Err(e) => json!({"error": {"message": e.to_string(), "type": "server_error"}}),
+        };
+        send(last);
+        let _ = events.send(Event::default().data("[DONE]"));
+    });
+    let stream = futures::stream::unfold(events_rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok::<_, Infallible>(event), rx))
+    });
+    Sse::new(stream).into_response()
+}
+
+/// Generate on the blocking pool, sending text to `tx` as it is produced
+/// and cut at the first stop sequence. A closed `tx` ends generation early.
+fn spawn_generation(
+    engine: SharedEngine,
+    prompt: Prompt,
+    options: GenerationOptions,
+    max_tokens: u32,
+    tx: UnboundedSender<String>,
+) -> tokio::task::JoinHandle<bizclaw_core::error::Result<Outcome>> {
+    tokio::task::spawn_blocking(move || {
+        let mut engine = engine.blocking_lock();
+        let mut sampling = engine.sampler_config().cloned().unwrap_or_default();
+        if let Some(t) = options.temperature {
+            sampling.temperature = t;
+        }
+        if let Some(p) = options.top_p {
+            sampling.top_p = p;
+        }
+        let mut stops = StopMatcher::new(options.stop_sequences());
+        let mut on_text = |piece: &str| {
+            let (text, more) = stops.push(piece);
+            (text.is_empty() || tx.send(text).is_ok()) && more
+        };
+        match &prompt {
+            Prompt::Chat(messages) => {
+                engine.chat_streaming(messages, max_tokens, Some(sampling), &mut on_text)?
+            }
+            Prompt::Text(text) => {
+                engine.generate_streaming(text, max_tokens, Some(sampling), &mut on_text)?
+            }
+        };
+        let rest = stops.finish();
+        if !rest.is_empty() {
+            let _ = tx.send(rest);
+        }
+        Ok(Outcome {
+            stopped: stops.hit,
+            usage: engine.last_usage().cloned().unwrap_or(Usage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+            }),
+        })
+    })
+}
+
+/// Cuts streamed text at the first stop sequence, holding back a tail that
+/// could still grow into one.
+struct StopMatcher {
+    stops: Vec<String>,
+    pending: String,
+    hit: bool,
+}
+
+impl StopMatcher {
+    fn new(stops: Vec<String>) -> Self {
+        Self {
+            stops,
+            pending: String::new(),
+            hit: false,
+        }
+    }
+
+    /// Text of `piece` that is safe to send, and whether to keep generating.
+    fn push(&mut self, piece: &str) -> (String, bool) {
+        if self.hit {
+            return (String::new(), false);
+        }
+        self.pending.push_str(piece);
+        if let Some(at) = self.stops.iter().filter_map(|s| self.pending.find(s.as_str())).min() {
+            self.hit = true;
+            let text = self.pending[..at].to_string();
+            self.pending.clear();
+            return (text, false);
+        }
+        let keep = self
+            .stops
+            .iter()
+            .map(|stop| {
+                (1..stop.len())
+                    .rev()
+                    .find(|&n| stop.is_char_boundary(n) && self.pending.ends_with(&stop[..n]))
+                    .unwrap_or(0)
+            })
+            .max()
+            .unwrap_or(0);
+        let ready = self.pending.len() - keep;
+        (self.pending.drain(..ready).collect(), true)
+    }
+
+    /// Held-back text once generation ends without a stop sequence.
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+// ─── Responses ───────────────────────────────────────────────────────────────
+
+/// Shape of the responses for one request.
+struct Format {
+    /// `/v1/chat/completions` rather than `/v1/completions`.
+    chat: bool,
+    id: String,
+    created: i64,
+    model: String,
+}
+
+impl Format {
+    fn body(&self, object: &str, choice: Value) -> Value {
+        json!({
+            "id": self.id,
+            "object": object,
+            "created": self.created,
+            "model": self.model,
+            "choices": [choice],
+        })
+    }
+
+    /// A complete (non-streamed) response.
+    fn response(&self, text: &str, outcome: &Outcome, max_tokens: u32) -> Value {
+        let finish_reason = outcome.finish_reason(max_tokens);
+        let mut body = if self.chat {
+            self.body(
+                "chat.completion",
+                json!({
+                    "index": 0,
+                    "message": {"role": "assistant", "content": text},
+                    "finish_reason": finish_reason,
+                }),
+            )
+        } else {
+            self.body(
+                "text_completion",
+                json!({"index": 0, "text": text, "logprobs": null, "finish_reason": finish_reason}),
+            )
+        };
+        body["usage"] = json!(outcome.usage);
+        body
+    }
+
+    /// One SSE chunk: a chat `delta` object, or completion text.
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        if self.chat {
+            self.body(
+                "chat.completion.chunk",
+                json!({"index": 0, "delta": delta, "finish_reason": finish_reason}),
+            )
+        } else {
+            let delta = json!({"content": delta});
+            let text = delta["content"].as_str().unwrap_or("");
+            self.body(
+                "text_completion",
+                json!({"index": 0, "text": text, "logprobs": null, "finish_reason": finish_reason}),
+            )
+        }
+    }
+}
+
+/// API model id: the file name of the model.
+fn model_id(path: &Path) -> String {
+    path.file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| "local-model".into())
+}
+
+/// An OpenAI-style error body.
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    let kind = match status {
+        StatusCode::BAD_REQUEST => "invalid_request_error",
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        _ => "server_error",
+    };
+    (status, Json(json!({"error": {"message": message.into(), "type": kind}}))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_brain::BrainConfig;
+    use bizclaw_core::types::Role;
+
+    fn server(models_dir: PathBuf) -> BrainServer {
+        let pool = Arc::new(ModelPool::new(BrainConfig::default(), 0));
+        BrainServer::new(pool, models_dir.join("default.gguf"), models_dir, 64)
+    }
+
+    fn message(role: &str, content: Value) -> ChatMessage {
+        ChatMessage {
+            role: role.into(),
+            content,
+            tool_call_id: None,
+        }
+    }
+
+    #[test]
+    fn test_messages_accept_text_parts_and_reject_unknown_roles() {
+        let messages = to_messages(&[
+            message("developer", json!("Be brief.")),
+            message(
+                "user",
+                json!([{"type": "text", "text": "Hi"}, {"type": "image_url"}, {"type": "text", "text": "there"}]),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[1].content, "Hi\nthere");
+
+        assert!(to_messages(&[message("narrator", json!("x"))]).is_err());
+    }
+
+    #[test]
+    fn test_stop_matcher_holds_back_partial_stops() {
+        let mut stops = StopMatcher::new(vec!["</s>".into(), "\n\n".into()]);
+        assert_eq!(stops.push("Hello <"), ("Hello ".to_string(), true));
+        assert_eq!(stops.push("/b>"), ("</b>".to_string(), true));
+        assert_eq!(stops.push(" bye\n"), (" bye".to_string(), true));
+        assert_eq!(stops.push("\nmore"), (String::new(), false));
+        assert!(stops.hit);
+        assert_eq!(stops.push("ignored"), (String::new(), false));
+
+        let mut open = StopMatcher::new(vec!["END".into()]);
+        assert_eq!(open.push("the E"), ("the ".to_string(), true));
+        assert_eq!(open.finish(), "E");
+    }
+
+    #[test]
+    fn test_model_resolves_only_to_files_in_models_dir() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-brain-serve-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("other.gguf"), b"").unwrap();
+        let server = server(dir.clone());
+
+        assert_eq!(server.resolve_model("other.gguf"), dir.join("other.gguf"));
+        assert_eq!(server.resolve_model("gpt-4o"), dir.join("default.gguf"));
+        assert_eq!(server.resolve_model("missing.gguf"), dir.join("default.gguf"));
+        assert_eq!(server.resolve_model("../other.gguf"), dir.join("default.gguf"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_api_key_and_model_listing() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-brain-serve-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.gguf"), b"").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+        let server = Arc::new(server(dir.clone()).with_api_key("secret"));
+
+        let denied = list_models(State(Arc::clone(&server)), HeaderMap::new()).await;
+        assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        let listed = list_models(State(server), headers).await;
+        assert_eq!(listed.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(listed.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let ids: Vec<&str> = body["data"].as_array().unwrap().iter().filter_map(|m| m["id"].as_str()).collect();
+        assert_eq!(ids, ["default.gguf", "b.gguf"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_finish_reason() {
+        let outcome = |stopped, completion_tokens| Outcome {
+            stopped,
+            usage: Usage {
+                prompt_tokens: 3,
+                completion_tokens,
+                total_tokens: 3 + completion_tokens,
+            },
+        };
+        assert_eq!(outcome(false, 64).finish_reason(64), "length");
+        assert_eq!(outcome(false, 10).finish_reason(64), "stop");
+        assert_eq!(outcome(true, 64).finish_reason(64), "stop");
+    }
+}
@@ -162,6 +162,8 @@ function ChatPage({ config, lang }) {
   const [input, setInput] = useState('');
   const [thinking, setThinking] = useState(false);
   const [streamContent, setStreamContent] = useState('');
+  const [progressStatus, setProgressStatus] = useState('');
+  const [toolOutput, setToolOutput] = useState('');
   const [streamReqId, setStreamReqId] = useState(null);
   const [sessions, setSessions] = useState([{ id: 'main', name: 'Main Chat', icon: '🤖', time: 'now', count: 0 }]);
   const [activeSession, setActiveSession] = useState('main');
@@ -191,9 +193,23 @@ function ChatPage({ config, lang }) {
         case 'chat_start':
           setStreamReqId(msg.request_id);
           setStreamContent('');
+          setProgressStatus('');
+          setToolOutput('');
           setThinking(false);
           break;
 
+        case 'chat_progress':
+          // A tool round began: text streamed before it was only narration
+          setProgressStatus(msg.status || '');
+          setToolOutput('');
+          setStreamContent('');
+          break;
+
+        case 'tool_output':
+          // Last lines a running tool (e.g. shell) printed
+          setToolOutput(prev => (prev + (msg.data || '')).split('\n').slice(-8).join('\n'));
+          break;
+
         case 'chat_chunk':
           setStreamContent(prev => prev + (msg.content || ''));
           break;
@@ -202,6 +218,8 @@ function ChatPage({ config, lang }) {
           const fullContent = msg.full_content || '';
           setMessages(prev => [...prev, { type: 'bot', content: fullContent, provider: msg.provider, model: msg.model, mode: msg.mode, context: msg.context }]);
           setStreamContent('');
+          setProgressStatus('');
+          setToolOutput('');
           setStreamReqId(null);
           setThinking(false);
           // Update session count
@@ -218,6 +236,8 @@ function ChatPage({ config, lang }) {
           setMessages(prev => [...prev, { type: 'system', content: '❌ Error: ' + (msg.error || 'Unknown error'), error: true }]);
           setThinking(false);
           setStreamContent('');
+          setProgressStatus('');
+          setToolOutput('');
           setStreamReqId(null);
           break;
 
@@ -384,6 +404,8 @@ function ChatPage({ config, lang }) {
                 ${m.type === 'bot' && m.mode === 'agent' ? html`<div style="font-size:10px;color:var(--text2);margin-top:4px;text-align:right">🧠 Agent${m.context ? ' · ctx:' + m.context.total_tokens : ''}</div>` : ''}
               </div>
             `)}
+            ${progressStatus ? html`<div class="typing" style="white-space:pre-line">${progressStatus}</div>` : ''}
+            ${toolOutput ? html`<pre class="typing" style="font-size:11px;max-height:10em;overflow:auto">${toolOutput}</pre>` : ''}
             ${streamContent ? html`<div class="msg msg-bot">${renderContent(streamContent)}<span class="pulse" style="color:var(--accent2)">▊</span></div>` : ''}
             ${thinking && !streamContent ? html`<div class="typing" style="display:flex;align-items:center;gap:6px">
               <span class="pulse">●</span> ${t('chat.thinking', lang)}...
@@ -484,7 +506,15 @@ function SchedulerPage({ lang }) {
     setLoading(false);
   };
 
-  useEffect(() => { loadData(); }, []);
+  useEffect(() => {
+    loadData();
+    // Live updates pushed over /ws
+    const handler = (e) => {
+      if (['notification_sent', 'task_finished', 'workflow_finished'].includes(e.detail.type)) loadData();
+    };
+    window.addEventListener('bizclaw-event', handler);
+    return () => window.removeEventListener('bizclaw-event', handler);
+  }, []);
 
   const toggleTask = async (id, enabled) => {
     await authFetch('/api/v1/scheduler/tasks/' + id + '/toggle', {
@@ -1182,6 +1212,15 @@ export function App() {
       socket.onmessage = (e) => {
         try {
           const msg = JSON.parse(e.data);
+          // Platform events (notifications, task runs, channels, agents) go to pages
+          if (msg.type === 'event') {
+            const ev = msg.event || {};
+            if (ev.type === 'notification_sent' && window.showToast) {
+              window.showToast('🔔 ' + ev.title, ev.priority === 'urgent' || ev.priority === 'high' ? 'error' : 'info');
+            }
+            window.dispatchEvent(new CustomEvent('bizclaw-event', { detail: ev }));
+            return;
+          }
           // Handle WS messages (for chat)
           window.dispatchEvent(new CustomEvent('ws-message', { detail: msg }));
         } catch (err) { console.error('WS parse:', err); }
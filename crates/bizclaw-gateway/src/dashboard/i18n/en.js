@@ -69,7 +69,7 @@ export const en = {
   // MCP
   'mcp.title':'MCP Servers','mcp.subtitle':'Model Context Protocol — extend your agent with external tools',
   'mcp.add':'Add Server','mcp.total':'Total Servers','mcp.active':'Active',
-  'mcp.protocol':'Protocol','mcp.add_title':'Add MCP Server','mcp.popular':'Popular MCP Servers',
+  'mcp.protocol':'Protocol','mcp.add_title':'Add MCP Server','mcp.url':'Or remote server URL','mcp.token':'Bearer token (remote server)','mcp.popular':'Popular MCP Servers',
   // Multi-Agent
   'agents.title':'Multi-Agent AI','agents.subtitle':'Create and manage multiple AI agents with different roles',
   'agents.create':'Create Agent','agents.total':'Total Agents','agents.default':'Default Agent',
@@ -69,7 +69,7 @@ export const vi = {
   // MCP
   'mcp.title':'Máy chủ MCP','mcp.subtitle':'Model Context Protocol — mở rộng agent với công cụ bên ngoài',
   'mcp.add':'Thêm máy chủ','mcp.total':'Tổng máy chủ','mcp.active':'Đang hoạt động',
-  'mcp.protocol':'Giao thức','mcp.add_title':'Thêm máy chủ MCP','mcp.popular':'MCP phổ biến',
+  'mcp.protocol':'Giao thức','mcp.add_title':'Thêm máy chủ MCP','mcp.url':'Hoặc URL máy chủ từ xa','mcp.token':'Bearer token (máy chủ từ xa)','mcp.popular':'MCP phổ biến',
   // Multi-Agent
   'agents.title':'AI Agent','agents.subtitle':'Tạo và quản lý nhiều Agent AI — mỗi agent có nhà cung cấp & mô hình riêng',
   'agents.create':'Tạo Agent','agents.total':'Tổng Agent','agents.default':'Agent mặc định',
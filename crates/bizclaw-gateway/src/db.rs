@@ -46,10 +46,96 @@ pub struct AgentRecord {
     pub model: String,
     pub system_prompt: String,
     pub enabled: bool,
+    /// Ordered fallback providers (provider + optional model).
+    pub fallback_providers: Vec<bizclaw_core::config::FallbackProviderConfig>,
+    /// Knowledge base namespaces the agent searches (empty = all).
+    pub knowledge_namespaces: Vec<String>,
+    /// MCP prompt templates (`server/prompt`) the agent follows.
+    pub mcp_prompts: Vec<String>,
+    /// Tool rounds per message (`None` = `autonomy.max_tool_rounds`).
+    pub max_tool_rounds: Option<u32>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// API key record — only the key's SHA-256 hash is stored.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    /// First characters of the key, to tell keys apart in listings.
+    pub prefix: String,
+    /// "read", "chat", "admin".
+    pub scopes: Vec<String>,
+    /// Requests per minute (0 = unlimited).
+    pub rate_limit: u32,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// Audit log entry — one change made through the gateway.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuditRecord {
+    pub id: i64,
+    /// "dashboard", "api_key:<name>", "anonymous" or "system".
+    pub actor: String,
+    /// "config.update", "agent.create", "POST /api/v1/hands/{name}/run"...
+    pub action: String,
+    /// What was changed: an agent name, channel, document id or path.
+    pub target: String,
+    /// HTTP status of the request that made the change (0 = not a request).
+    pub status: u16,
+    pub old_value: Option<serde_json::Value>,
+    pub new_value: Option<serde_json::Value>,
+    /// Changed fields as `{"field", "from", "to"}`.
+    pub changes: Vec<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// Filters for [`GatewayDb::list_audit`]; empty fields match everything.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    /// Exact action, or a prefix ending in `.` ("agent.").
+    pub action: Option<String>,
+    pub target: Option<String>,
+    /// `created_at` bounds, "YYYY-MM-DD[ HH:MM:SS]" (UTC).
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Background job — a long operation started from the dashboard or API.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    /// "model_download", "knowledge_crawl", "brain_personalize"...
+    pub kind: String,
+    /// "queued", "running", "succeeded", "failed", "cancelled".
+    pub status: String,
+    pub params: serde_json::Value,
+    /// 0.0–1.0, when the job can tell.
+    pub progress: Option<f64>,
+    /// Latest progress note ("412 MB / 638 MB").
+    pub message: String,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl JobRecord {
+    /// Whether the job has stopped for good.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "succeeded" | "failed" | "cancelled")
+    }
+}
+
 /// Agent-Channel binding.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AgentChannelBinding {
@@ -105,6 +191,10 @@ impl GatewayDb {
                 model TEXT DEFAULT '',
                 system_prompt TEXT DEFAULT '',
                 enabled INTEGER DEFAULT 1,
+                fallback_providers TEXT DEFAULT '[]',
+                knowledge_namespaces TEXT DEFAULT '[]',
+                mcp_prompts TEXT DEFAULT '[]',
+                max_tool_rounds INTEGER,
                 created_at TEXT DEFAULT (datetime('now')),
                 updated_at TEXT DEFAULT (datetime('now'))
             );
@@ -122,6 +212,44 @@ impl GatewayDb {
                 value TEXT DEFAULT '',
                 updated_at TEXT DEFAULT (datetime('now'))
             );
+
+            CREATE TABLE IF NOT EXISTS api_keys (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_hash TEXT NOT NULL UNIQUE,
+                prefix TEXT DEFAULT '',
+                scopes TEXT DEFAULT '[]',
+                rate_limit INTEGER DEFAULT 0,
+                created_at TEXT DEFAULT (datetime('now')),
+                last_used_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                target TEXT DEFAULT '',
+                status INTEGER DEFAULT 0,
+                old_value TEXT,
+                new_value TEXT,
+                changes TEXT DEFAULT '[]',
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_created ON audit_log(created_at);
+
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                params TEXT DEFAULT '{}',
+                progress REAL,
+                message TEXT DEFAULT '',
+                result TEXT,
+                error TEXT,
+                created_at TEXT DEFAULT (datetime('now')),
+                started_at TEXT,
+                finished_at TEXT
+            );
         ").map_err(|e| format!("Migration error: {e}"))?;
         
         // Migration: add new columns to existing providers table
@@ -141,6 +269,50 @@ impl GatewayDb {
                 ALTER TABLE providers ADD COLUMN env_keys_json TEXT DEFAULT '[]';
             ").map_err(|e| format!("Migration add columns: {e}"))?;
         }
+
+        let has_fallbacks: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('agents') WHERE name='fallback_providers'",
+            [], |r| r.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+
+        if !has_fallbacks {
+            conn.execute_batch(
+                "ALTER TABLE agents ADD COLUMN fallback_providers TEXT DEFAULT '[]';",
+            ).map_err(|e| format!("Migration add agent fallbacks: {e}"))?;
+        }
+
+        let has_namespaces: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('agents') WHERE name='knowledge_namespaces'",
+            [], |r| r.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+
+        if !has_namespaces {
+            conn.execute_batch(
+                "ALTER TABLE agents ADD COLUMN knowledge_namespaces TEXT DEFAULT '[]';",
+            ).map_err(|e| format!("Migration add agent knowledge namespaces: {e}"))?;
+        }
+
+        let has_mcp_prompts: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('agents') WHERE name='mcp_prompts'",
+            [], |r| r.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+
+        if !has_mcp_prompts {
+            conn.execute_batch(
+                "ALTER TABLE agents ADD COLUMN mcp_prompts TEXT DEFAULT '[]';",
+            ).map_err(|e| format!("Migration add agent MCP prompts: {e}"))?;
+        }
+
+        let has_max_tool_rounds: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('agents') WHERE name='max_tool_rounds'",
+            [], |r| r.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+
+        if !has_max_tool_rounds {
+            conn.execute_batch(
+                "ALTER TABLE agents ADD COLUMN max_tool_rounds INTEGER;",
+            ).map_err(|e| format!("Migration add agent max tool rounds: {e}"))?;
+        }
         
         Ok(())
     }
@@ -465,12 +637,22 @@ impl GatewayDb {
 
         // Read back using SAME connection — do NOT call self.get_agent() which would deadlock
         conn.query_row(
-            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at FROM agents WHERE name=?1",
+            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at, fallback_providers, knowledge_namespaces, mcp_prompts, max_tool_rounds FROM agents WHERE name=?1",
             params![name],
             |row| Ok(AgentRecord {
                 name: row.get(0)?, role: row.get(1)?, description: row.get(2)?,
                 provider: row.get(3)?, model: row.get(4)?, system_prompt: row.get(5)?,
                 enabled: row.get::<_, i32>(6)? != 0,
+                fallback_providers: parse_fallbacks(row.get(9)?),
+                knowledge_namespaces: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                mcp_prompts: row
+                    .get::<_, Option<String>>(11)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                max_tool_rounds: row.get(12)?,
                 created_at: row.get(7)?, updated_at: row.get(8)?,
             }),
         ).map_err(|e| format!("Get agent after upsert: {e}"))
@@ -480,12 +662,22 @@ impl GatewayDb {
     pub fn get_agent(&self, name: &str) -> Result<AgentRecord, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
         conn.query_row(
-            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at FROM agents WHERE name=?1",
+            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at, fallback_providers, knowledge_namespaces, mcp_prompts, max_tool_rounds FROM agents WHERE name=?1",
             params![name],
             |row| Ok(AgentRecord {
                 name: row.get(0)?, role: row.get(1)?, description: row.get(2)?,
                 provider: row.get(3)?, model: row.get(4)?, system_prompt: row.get(5)?,
                 enabled: row.get::<_, i32>(6)? != 0,
+                fallback_providers: parse_fallbacks(row.get(9)?),
+                knowledge_namespaces: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                mcp_prompts: row
+                    .get::<_, Option<String>>(11)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                max_tool_rounds: row.get(12)?,
                 created_at: row.get(7)?, updated_at: row.get(8)?,
             }),
         ).map_err(|e| format!("Get agent: {e}"))
@@ -495,7 +687,7 @@ impl GatewayDb {
     pub fn list_agents(&self) -> Result<Vec<AgentRecord>, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
         let mut stmt = conn.prepare(
-            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at FROM agents ORDER BY name"
+            "SELECT name, role, description, provider, model, system_prompt, enabled, created_at, updated_at, fallback_providers, knowledge_namespaces, mcp_prompts, max_tool_rounds FROM agents ORDER BY name"
         ).map_err(|e| format!("Prepare: {e}"))?;
 
         let agents = stmt.query_map([], |row| {
@@ -503,6 +695,16 @@ impl GatewayDb {
                 name: row.get(0)?, role: row.get(1)?, description: row.get(2)?,
                 provider: row.get(3)?, model: row.get(4)?, system_prompt: row.get(5)?,
                 enabled: row.get::<_, i32>(6)? != 0,
+                fallback_providers: parse_fallbacks(row.get(9)?),
+                knowledge_namespaces: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                mcp_prompts: row
+                    .get::<_, Option<String>>(11)?
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default(),
+                max_tool_rounds: row.get(12)?,
                 created_at: row.get(7)?, updated_at: row.get(8)?,
             })
         }).map_err(|e| format!("Query: {e}"))?
@@ -511,6 +713,58 @@ impl GatewayDb {
         Ok(agents)
     }
 
+    /// Set the ordered fallback providers for an agent.
+    pub fn set_agent_fallbacks(
+        &self,
+        name: &str,
+        fallbacks: &[bizclaw_core::config::FallbackProviderConfig],
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        // Credentials live in the providers table — persist only provider + model
+        let stored: Vec<serde_json::Value> = fallbacks
+            .iter()
+            .map(|f| serde_json::json!({ "provider": f.provider, "model": f.model }))
+            .collect();
+        let json = serde_json::to_string(&stored).unwrap_or_else(|_| "[]".into());
+        conn.execute(
+            "UPDATE agents SET fallback_providers=?1, updated_at=datetime('now') WHERE name=?2",
+            params![json, name],
+        ).map_err(|e| format!("Set agent fallbacks: {e}"))?;
+        Ok(())
+    }
+
+    /// Set the knowledge base namespaces an agent searches.
+    pub fn set_agent_knowledge_namespaces(&self, name: &str, namespaces: &[String]) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let json = serde_json::to_string(namespaces).unwrap_or_else(|_| "[]".into());
+        conn.execute(
+            "UPDATE agents SET knowledge_namespaces=?1, updated_at=datetime('now') WHERE name=?2",
+            params![json, name],
+        ).map_err(|e| format!("Set agent knowledge namespaces: {e}"))?;
+        Ok(())
+    }
+
+    /// Set the MCP prompt templates (`server/prompt`) an agent follows.
+    pub fn set_agent_mcp_prompts(&self, name: &str, prompts: &[String]) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let json = serde_json::to_string(prompts).unwrap_or_else(|_| "[]".into());
+        conn.execute(
+            "UPDATE agents SET mcp_prompts=?1, updated_at=datetime('now') WHERE name=?2",
+            params![json, name],
+        ).map_err(|e| format!("Set agent MCP prompts: {e}"))?;
+        Ok(())
+    }
+
+    /// Set an agent's tool rounds per message (`None` = the global default).
+    pub fn set_agent_max_tool_rounds(&self, name: &str, rounds: Option<u32>) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute(
+            "UPDATE agents SET max_tool_rounds=?1, updated_at=datetime('now') WHERE name=?2",
+            params![rounds, name],
+        ).map_err(|e| format!("Set agent max tool rounds: {e}"))?;
+        Ok(())
+    }
+
     /// Delete an agent.
     pub fn delete_agent(&self, name: &str) -> Result<(), String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
@@ -598,6 +852,236 @@ impl GatewayDb {
         Ok(())
     }
 
+    // ── API keys ──────────────────────────────
+
+    /// Store a new API key.
+    pub fn insert_api_key(&self, key: &ApiKeyRecord) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let scopes = serde_json::to_string(&key.scopes).unwrap_or_else(|_| "[]".into());
+        conn.execute(
+            "INSERT INTO api_keys (id, name, key_hash, prefix, scopes, rate_limit) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![key.id, key.name, key.key_hash, key.prefix, scopes, key.rate_limit],
+        ).map_err(|e| format!("Insert API key: {e}"))?;
+        Ok(())
+    }
+
+    /// List API keys, newest first.
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKeyRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, key_hash, prefix, scopes, rate_limit, created_at, last_used_at
+             FROM api_keys ORDER BY created_at DESC, rowid DESC"
+        ).map_err(|e| format!("Prepare: {e}"))?;
+        let keys = stmt.query_map([], row_to_api_key)
+            .map_err(|e| format!("Query: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(keys)
+    }
+
+    /// Look up an API key by the hash of its secret.
+    pub fn find_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        match conn.query_row(
+            "SELECT id, name, key_hash, prefix, scopes, rate_limit, created_at, last_used_at
+             FROM api_keys WHERE key_hash=?1",
+            params![key_hash], row_to_api_key,
+        ) {
+            Ok(key) => Ok(Some(key)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("Find API key: {e}")),
+        }
+    }
+
+    /// Record that an API key was just used.
+    pub fn touch_api_key(&self, id: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute("UPDATE api_keys SET last_used_at=datetime('now') WHERE id=?1", params![id])
+            .map_err(|e| format!("Touch API key: {e}"))?;
+        Ok(())
+    }
+
+    /// Revoke an API key. Returns false if it didn't exist.
+    pub fn delete_api_key(&self, id: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let n = conn.execute("DELETE FROM api_keys WHERE id=?1", params![id])
+            .map_err(|e| format!("Delete API key: {e}"))?;
+        Ok(n > 0)
+    }
+
+    // ── Audit log ──────────────────────────────
+
+    /// Append an audit entry; `id` and `created_at` are assigned here.
+    pub fn insert_audit(&self, entry: &AuditRecord) -> Result<i64, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let json = |v: &Option<serde_json::Value>| v.as_ref().map(|v| v.to_string());
+        conn.execute(
+            "INSERT INTO audit_log (actor, action, target, status, old_value, new_value, changes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.actor, entry.action, entry.target, entry.status,
+                json(&entry.old_value), json(&entry.new_value),
+                serde_json::Value::from(entry.changes.clone()).to_string(),
+            ],
+        ).map_err(|e| format!("Insert audit entry: {e}"))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Audit entries matching `filter`, newest first (default 100, at most 1000).
+    pub fn list_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let mut sql = String::from(
+            "SELECT id, actor, action, target, status, old_value, new_value, changes, created_at
+             FROM audit_log WHERE 1=1"
+        );
+        let mut args: Vec<String> = Vec::new();
+        // `clause` has one `?`, numbered here
+        let mut push = |clause: &str, value: &str| {
+            args.push(value.to_string());
+            sql.push_str(&format!(" AND {}", clause.replace('?', &format!("?{}", args.len()))));
+        };
+        let given = |v: &Option<String>| v.clone().filter(|s| !s.is_empty());
+        if let Some(actor) = given(&filter.actor) {
+            push("actor = ?", &actor);
+        }
+        if let Some(action) = given(&filter.action) {
+            if action.ends_with('.') {
+                push("instr(action, ?) = 1", &action);
+            } else {
+                push("action = ?", &action);
+            }
+        }
+        if let Some(target) = given(&filter.target) {
+            push("target = ?", &target);
+        }
+        if let Some(since) = given(&filter.since) {
+            push("created_at >= ?", &since);
+        }
+        if let Some(mut until) = given(&filter.until) {
+            // A bare date includes that whole day
+            if until.len() == 10 {
+                until.push_str(" 23:59:59");
+            }
+            push("created_at <= ?", &until);
+        }
+        let limit = filter.limit.unwrap_or(100).min(1000);
+        sql.push_str(&format!(" ORDER BY id DESC LIMIT {limit} OFFSET {}", filter.offset.unwrap_or(0)));
+
+        let mut stmt = conn.prepare(&sql).map_err(|e| format!("Prepare: {e}"))?;
+        let entries = stmt.query_map(rusqlite::params_from_iter(args.iter()), row_to_audit)
+            .map_err(|e| format!("Query: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(entries)
+    }
+
+    /// One audit entry by id.
+    pub fn get_audit(&self, id: i64) -> Result<Option<AuditRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        match conn.query_row(
+            "SELECT id, actor, action, target, status, old_value, new_value, changes, created_at
+             FROM audit_log WHERE id=?1",
+            params![id], row_to_audit,
+        ) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("Get audit entry: {e}")),
+        }
+    }
+
+    // ── Jobs ──────────────────────────────
+
+    /// Store a new queued job.
+    pub fn insert_job(&self, id: &str, kind: &str, params: &serde_json::Value) -> Result<JobRecord, String> {
+        {
+            let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+            conn.execute(
+                "INSERT INTO jobs (id, kind, status, params) VALUES (?1, ?2, 'queued', ?3)",
+                params![id, kind, params.to_string()],
+            ).map_err(|e| format!("Insert job: {e}"))?;
+        }
+        self.get_job(id)?.ok_or_else(|| format!("Job {id} not stored"))
+    }
+
+    /// Mark a job as running.
+    pub fn start_job(&self, id: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute(
+            "UPDATE jobs SET status='running', started_at=datetime('now') WHERE id=?1 AND status='queued'",
+            params![id],
+        ).map_err(|e| format!("Start job: {e}"))?;
+        Ok(())
+    }
+
+    /// Record a running job's progress.
+    pub fn update_job_progress(&self, id: &str, progress: Option<f64>, message: &str) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute(
+            "UPDATE jobs SET progress=COALESCE(?2, progress), message=?3 WHERE id=?1",
+            params![id, progress, message],
+        ).map_err(|e| format!("Update job: {e}"))?;
+        Ok(())
+    }
+
+    /// Finish a job with `status` ("succeeded", "failed" or "cancelled").
+    /// A job that already finished keeps its first outcome.
+    pub fn finish_job(
+        &self,
+        id: &str,
+        status: &str,
+        result: Option<&serde_json::Value>,
+        error: Option<&str>,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute(
+            "UPDATE jobs SET status=?2, result=?3, error=?4, finished_at=datetime('now'),
+                 progress=CASE WHEN ?2='succeeded' THEN 1.0 ELSE progress END
+             WHERE id=?1 AND status IN ('queued', 'running')",
+            params![id, status, result.map(|r| r.to_string()), error],
+        ).map_err(|e| format!("Finish job: {e}"))?;
+        Ok(())
+    }
+
+    /// One job by id.
+    pub fn get_job(&self, id: &str) -> Result<Option<JobRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        match conn.query_row(
+            "SELECT id, kind, status, params, progress, message, result, error, created_at, started_at, finished_at
+             FROM jobs WHERE id=?1",
+            params![id], row_to_job,
+        ) {
+            Ok(job) => Ok(Some(job)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("Get job: {e}")),
+        }
+    }
+
+    /// Jobs, newest first, optionally of one `kind` and/or `status`.
+    pub fn list_jobs(&self, kind: Option<&str>, status: Option<&str>, limit: usize) -> Result<Vec<JobRecord>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, status, params, progress, message, result, error, created_at, started_at, finished_at
+             FROM jobs WHERE (?1 IS NULL OR kind=?1) AND (?2 IS NULL OR status=?2)
+             ORDER BY created_at DESC, rowid DESC LIMIT ?3"
+        ).map_err(|e| format!("Prepare: {e}"))?;
+        let jobs = stmt.query_map(params![kind, status, limit as i64], row_to_job)
+            .map_err(|e| format!("Query: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(jobs)
+    }
+
+    /// Fail jobs left queued or running by a previous gateway process.
+    /// Returns how many there were.
+    pub fn fail_interrupted_jobs(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock: {e}"))?;
+        conn.execute(
+            "UPDATE jobs SET status='failed', error='Interrupted — the gateway restarted', finished_at=datetime('now')
+             WHERE status IN ('queued', 'running')",
+            [],
+        ).map_err(|e| format!("Fail interrupted jobs: {e}"))
+    }
+
     /// Migrate existing agents.json data into DB.
     pub fn migrate_from_agents_json(&self, agents: &[serde_json::Value]) -> Result<usize, String> {
         let mut count = 0;
@@ -616,6 +1100,58 @@ impl GatewayDb {
     }
 }
 
+fn row_to_api_key(row: &rusqlite::Row) -> rusqlite::Result<ApiKeyRecord> {
+    let scopes: String = row.get(4)?;
+    Ok(ApiKeyRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        key_hash: row.get(2)?,
+        prefix: row.get(3)?,
+        scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+        rate_limit: row.get(5)?,
+        created_at: row.get(6)?,
+        last_used_at: row.get(7)?,
+    })
+}
+
+fn row_to_audit(row: &rusqlite::Row) -> rusqlite::Result<AuditRecord> {
+    let json = |raw: Option<String>| raw.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+    let changes: Option<String> = row.get(7)?;
+    Ok(AuditRecord {
+        id: row.get(0)?,
+        actor: row.get(1)?,
+        action: row.get(2)?,
+        target: row.get(3)?,
+        status: row.get(4)?,
+        old_value: json(row.get(5)?),
+        new_value: json(row.get(6)?),
+        changes: changes.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+        created_at: row.get(8)?,
+    })
+}
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<JobRecord> {
+    let json = |raw: Option<String>| raw.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+    Ok(JobRecord {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        status: row.get(2)?,
+        params: json(row.get(3)?).unwrap_or_default(),
+        progress: row.get(4)?,
+        message: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        result: json(row.get(6)?),
+        error: row.get(7)?,
+        created_at: row.get(8)?,
+        started_at: row.get(9)?,
+        finished_at: row.get(10)?,
+    })
+}
+
+/// Parse the `agents.fallback_providers` JSON column (NULL/invalid → empty).
+fn parse_fallbacks(raw: Option<String>) -> Vec<bizclaw_core::config::FallbackProviderConfig> {
+    raw.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -698,6 +1234,106 @@ mod tests {
         assert!(!openai.is_active);
     }
 
+    #[test]
+    fn test_api_key_crud() {
+        let db = temp_db();
+        let key = ApiKeyRecord {
+            id: "k1".into(),
+            name: "CI bot".into(),
+            key_hash: "abc123".into(),
+            prefix: "bzk_1234".into(),
+            scopes: vec!["read".into(), "chat".into()],
+            rate_limit: 30,
+            created_at: String::new(),
+            last_used_at: None,
+        };
+        db.insert_api_key(&key).unwrap();
+        assert!(db.insert_api_key(&key).is_err());
+
+        let found = db.find_api_key("abc123").unwrap().unwrap();
+        assert_eq!(found.scopes, vec!["read", "chat"]);
+        assert_eq!(found.rate_limit, 30);
+        assert!(found.last_used_at.is_none());
+        db.touch_api_key("k1").unwrap();
+        assert!(db.list_api_keys().unwrap()[0].last_used_at.is_some());
+        assert!(db.find_api_key("nope").unwrap().is_none());
+
+        assert!(db.delete_api_key("k1").unwrap());
+        assert!(!db.delete_api_key("k1").unwrap());
+        assert!(db.list_api_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let db = temp_db();
+        let entry = |actor: &str, action: &str, target: &str| AuditRecord {
+            id: 0,
+            actor: actor.into(),
+            action: action.into(),
+            target: target.into(),
+            status: 200,
+            old_value: None,
+            new_value: Some(serde_json::json!({"name": target})),
+            changes: vec![serde_json::json!({"field": "name", "from": null, "to": target})],
+            created_at: String::new(),
+        };
+        let first = db.insert_audit(&entry("dashboard", "agent.create", "sales")).unwrap();
+        db.insert_audit(&entry("api_key:ci", "agent.update", "sales")).unwrap();
+        db.insert_audit(&entry("dashboard", "config.update", "config")).unwrap();
+
+        let all = db.list_audit(&AuditFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, "config.update");
+        assert_eq!(all[2].new_value, Some(serde_json::json!({"name": "sales"})));
+        assert_eq!(all[2].changes.len(), 1);
+
+        let by = |filter: AuditFilter| db.list_audit(&filter).unwrap().len();
+        assert_eq!(by(AuditFilter { actor: Some("dashboard".into()), ..Default::default() }), 2);
+        assert_eq!(by(AuditFilter { action: Some("agent.".into()), ..Default::default() }), 2);
+        assert_eq!(by(AuditFilter { action: Some("agent".into()), ..Default::default() }), 0);
+        assert_eq!(by(AuditFilter { target: Some("sales".into()), limit: Some(1), ..Default::default() }), 1);
+        assert_eq!(by(AuditFilter { since: Some("2000-01-01".into()), until: Some("2999-12-31".into()), ..Default::default() }), 3);
+        assert_eq!(by(AuditFilter { until: Some("2000-01-01".into()), ..Default::default() }), 0);
+        assert_eq!(by(AuditFilter { offset: Some(2), ..Default::default() }), 1);
+
+        assert_eq!(db.get_audit(first).unwrap().unwrap().actor, "dashboard");
+        assert!(db.get_audit(999).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let db = temp_db();
+        let job = db.insert_job("j1", "model_download", &serde_json::json!({"model": "phi-2"})).unwrap();
+        assert_eq!(job.status, "queued");
+        assert_eq!(job.params["model"], "phi-2");
+
+        db.start_job("j1").unwrap();
+        db.update_job_progress("j1", Some(0.5), "half").unwrap();
+        let job = db.get_job("j1").unwrap().unwrap();
+        assert_eq!((job.status.as_str(), job.progress, job.message.as_str()), ("running", Some(0.5), "half"));
+        assert!(job.started_at.is_some());
+
+        db.finish_job("j1", "succeeded", Some(&serde_json::json!({"bytes": 10})), None).unwrap();
+        // The first outcome sticks
+        db.finish_job("j1", "cancelled", None, None).unwrap();
+        let job = db.get_job("j1").unwrap().unwrap();
+        assert_eq!(job.status, "succeeded");
+        assert_eq!(job.progress, Some(1.0));
+        assert_eq!(job.result, Some(serde_json::json!({"bytes": 10})));
+        assert!(job.is_finished());
+
+        db.insert_job("j2", "agent_reload", &serde_json::json!({})).unwrap();
+        assert_eq!(db.list_jobs(None, None, 10).unwrap().len(), 2);
+        assert_eq!(db.list_jobs(Some("agent_reload"), None, 10).unwrap()[0].id, "j2");
+        assert_eq!(db.list_jobs(None, Some("queued"), 10).unwrap().len(), 1);
+
+        assert_eq!(db.fail_interrupted_jobs().unwrap(), 1);
+        let job = db.get_job("j2").unwrap().unwrap();
+        assert_eq!(job.status, "failed");
+        assert!(job.error.unwrap().contains("restarted"));
+        assert!(db.get_job("nope").unwrap().is_none());
+    }
+
     #[test]
     fn test_agent_crud() {
         let db = temp_db();
@@ -721,6 +1357,66 @@ mod tests {
         assert!(db.get_agent("hr-bot").is_err());
     }
 
+    #[test]
+    fn test_agent_fallbacks() {
+        use bizclaw_core::config::FallbackProviderConfig;
+        let db = temp_db();
+        let a = db.upsert_agent("sales", "assistant", "", "openai", "gpt-4o-mini", "").unwrap();
+        assert!(a.fallback_providers.is_empty());
+
+        db.set_agent_fallbacks("sales", &[
+            FallbackProviderConfig { provider: "groq".into(), model: "llama-3.3-70b-versatile".into(), api_key: "secret".into(), ..Default::default() },
+            FallbackProviderConfig { provider: "ollama".into(), ..Default::default() },
+        ]).unwrap();
+
+        let a = db.get_agent("sales").unwrap();
+        assert_eq!(a.fallback_providers.len(), 2);
+        assert_eq!(a.fallback_providers[0].provider, "groq");
+        assert_eq!(a.fallback_providers[0].model, "llama-3.3-70b-versatile");
+        assert!(a.fallback_providers[0].api_key.is_empty(), "keys must not be persisted on agents");
+        assert_eq!(a.fallback_providers[1].provider, "ollama");
+
+        // Upsert keeps fallbacks
+        let a = db.upsert_agent("sales", "assistant", "v2", "openai", "gpt-4o", "").unwrap();
+        assert_eq!(a.fallback_providers.len(), 2);
+    }
+
+    #[test]
+    fn test_agent_knowledge_namespaces() {
+        let db = temp_db();
+        let a = db.upsert_agent("sales", "assistant", "", "openai", "gpt-4o-mini", "").unwrap();
+        assert!(a.knowledge_namespaces.is_empty());
+
+        db.set_agent_knowledge_namespaces("sales", &["sales".into(), "default".into()]).unwrap();
+        db.upsert_agent("sales", "assistant", "v2", "openai", "gpt-4o", "").unwrap();
+        assert_eq!(db.get_agent("sales").unwrap().knowledge_namespaces, ["sales", "default"]);
+        assert_eq!(db.list_agents().unwrap()[0].knowledge_namespaces.len(), 2);
+    }
+
+    #[test]
+    fn test_agent_mcp_prompts() {
+        let db = temp_db();
+        let a = db.upsert_agent("support", "assistant", "", "openai", "gpt-4o-mini", "").unwrap();
+        assert!(a.mcp_prompts.is_empty());
+
+        db.set_agent_mcp_prompts("support", &["crm/polite".into()]).unwrap();
+        db.upsert_agent("support", "assistant", "v2", "openai", "gpt-4o", "").unwrap();
+        assert_eq!(db.get_agent("support").unwrap().mcp_prompts, ["crm/polite"]);
+    }
+
+    #[test]
+    fn test_agent_max_tool_rounds() {
+        let db = temp_db();
+        let a = db.upsert_agent("ops", "assistant", "", "openai", "gpt-4o-mini", "").unwrap();
+        assert_eq!(a.max_tool_rounds, None);
+
+        db.set_agent_max_tool_rounds("ops", Some(12)).unwrap();
+        db.upsert_agent("ops", "assistant", "v2", "openai", "gpt-4o", "").unwrap();
+        assert_eq!(db.get_agent("ops").unwrap().max_tool_rounds, Some(12));
+        db.set_agent_max_tool_rounds("ops", None).unwrap();
+        assert_eq!(db.list_agents().unwrap()[0].max_tool_rounds, None);
+    }
+
     #[test]
     fn test_agent_channels() {
         let db = temp_db();
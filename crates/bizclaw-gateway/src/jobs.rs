@@ -0,0 +1,276 @@
+//! Background jobs — long gateway operations with an id, progress polling
+//! and cancellation.
+//!
+//! `POST /api/v1/jobs {"kind", "params"}` queues a job and returns its
+//! record at once; `GET /api/v1/jobs/{id}` polls it and
+//! `POST /api/v1/jobs/{id}/cancel` stops it. Kinds:
+//!
+//! - `model_download`    — `{"model"}`: catalog name or `.gguf` URL, into `~/.bizclaw/models`
+//! - `knowledge_crawl`   — the body of `POST /api/v1/knowledge/crawl`
+//! - `brain_personalize` — the body of `POST /api/v1/brain/personalize`
+//! - `agent_broadcast`   — `{"message"}`, sent to every agent
+//! - `agent_pipeline`    — the body of `POST /api/v1/pipelines/run`
+//! - `agent_reload`      — rebuild the default agent on the current config
+//!
+//! Crawl, personalize, broadcast and pipelines also accept `"background": true` on
+//! their own endpoints. At most [`MAX_CONCURRENT_JOBS`] run at once; the
+//! rest wait as `queued`. Jobs are stored in the gateway DB, so their
+//! outcome survives restarts; jobs cut short by a restart are marked failed.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde_json::Value;
+use tokio::sync::{Notify, Semaphore};
+
+use super::db::JobRecord;
+use super::server::AppState;
+
+/// Jobs that run at the same time.
+pub const MAX_CONCURRENT_JOBS: usize = 2;
+
+/// Job kinds [`start`] accepts.
+pub const JOB_KINDS: &[&str] = &[
+    "model_download",
+    "knowledge_crawl",
+    "brain_personalize",
+    "agent_broadcast",
+    "agent_pipeline",
+    "agent_reload",
+];
+
+/// Cancel signals of unfinished jobs, and the slots they run in.
+pub struct JobQueue {
+    cancels: Mutex<HashMap<String, Arc<Notify>>>,
+    slots: Semaphore,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            cancels: Mutex::new(HashMap::new()),
+            slots: Semaphore::new(MAX_CONCURRENT_JOBS),
+        }
+    }
+}
+
+/// Handle a running job uses to report progress.
+pub struct JobContext {
+    pub id: String,
+    state: Arc<AppState>,
+}
+
+impl JobContext {
+    /// Record progress (0.0–1.0, if known) and a short note.
+    pub fn progress(&self, progress: Option<f64>, message: &str) {
+        if let Err(e) = self.state.db.update_job_progress(&self.id, progress, message) {
+            tracing::warn!("⚠️ Job {} progress not saved: {e}", self.id);
+        }
+    }
+}
+
+/// Queue `run` as a job of `kind`. The job is cancelled by dropping its
+/// future, wherever it is waiting.
+pub fn spawn<F, Fut>(state: &Arc<AppState>, kind: &str, params: Value, run: F) -> Result<JobRecord, String>
+where
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, String>> + Send + 'static,
+{
+    let id = uuid::Uuid::new_v4().to_string();
+    let job = state.db.insert_job(&id, kind, &params)?;
+    let cancel = Arc::new(Notify::new());
+    state.jobs.cancels.lock().unwrap().insert(id.clone(), cancel.clone());
+    tracing::info!("🧰 Job {id} ({kind}) queued");
+
+    let state = state.clone();
+    let kind = kind.to_string();
+    tokio::spawn(async move {
+        let work = async {
+            let _slot = state.jobs.slots.acquire().await;
+            if let Err(e) = state.db.start_job(&id) {
+                tracing::warn!("⚠️ Job {id} start not saved: {e}");
+            }
+            run(JobContext { id: id.clone(), state: state.clone() }).await
+        };
+        let outcome = tokio::select! {
+            _ = cancel.notified() => None,
+            outcome = work => Some(outcome),
+        };
+        let saved = match &outcome {
+            None => state.db.finish_job(&id, "cancelled", None, None),
+            Some(Ok(result)) => state.db.finish_job(&id, "succeeded", Some(result), None),
+            Some(Err(e)) => state.db.finish_job(&id, "failed", None, Some(e)),
+        };
+        if let Err(e) = saved {
+            tracing::warn!("⚠️ Job {id} outcome not saved: {e}");
+        }
+        match outcome {
+            None => tracing::info!("🧰 Job {id} ({kind}) cancelled"),
+            Some(Ok(_)) => tracing::info!("🧰 Job {id} ({kind}) succeeded"),
+            Some(Err(e)) => tracing::warn!("🧰 Job {id} ({kind}) failed: {e}"),
+        }
+        state.jobs.cancels.lock().unwrap().remove(&id);
+    });
+    Ok(job)
+}
+
+/// Stop an unfinished job. Returns false if it isn't queued or running.
+pub fn cancel(state: &AppState, id: &str) -> bool {
+    let Some(signal) = state.jobs.cancels.lock().unwrap().remove(id) else {
+        return false;
+    };
+    // Mark it now, so a poll right after this sees the cancellation
+    if let Err(e) = state.db.finish_job(id, "cancelled", None, None) {
+        tracing::warn!("⚠️ Job {id} cancellation not saved: {e}");
+    }
+    signal.notify_one();
+    true
+}
+
+/// Start a job of one of the [`JOB_KINDS`].
+pub fn start(state: &Arc<AppState>, kind: &str, params: Value) -> Result<JobRecord, String> {
+    match kind {
+        "model_download" => {
+            let model = params["model"].as_str().unwrap_or("").trim().to_string();
+            let (url, filename) = bizclaw_brain::download::resolve(&model)
+                .ok_or_else(|| format!("Unknown model '{model}' — use a catalog name or a .gguf URL"))?;
+            let dest = bizclaw_core::BizClawConfig::home_dir().join("models").join(&filename);
+            spawn(state, kind, params, move |ctx| async move {
+                if dest.exists() {
+                    return Ok(serde_json::json!({"path": dest, "already_downloaded": true}));
+                }
+                let mut last_pct = None;
+                let bytes = bizclaw_brain::download::download(&url, &dest, &mut |done, total| {
+                    let mb = |b: u64| b as f64 / 1024.0 / 1024.0;
+                    match total.filter(|t| *t > 0) {
+                        Some(total) => {
+                            let pct = done * 100 / total;
+                            if last_pct != Some(pct) {
+                                last_pct = Some(pct);
+                                let note = format!("{:.1} MB / {:.1} MB", mb(done), mb(total));
+                                ctx.progress(Some(done as f64 / total as f64), &note);
+                            }
+                        }
+                        None => ctx.progress(None, &format!("{:.1} MB", mb(done))),
+                    }
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+                Ok(serde_json::json!({"path": dest, "bytes": bytes}))
+            })
+        }
+        "knowledge_crawl" => {
+            if params["url"].as_str().unwrap_or("").is_empty() {
+                return Err("Missing 'url'".into());
+            }
+            let body = params.clone();
+            let state2 = state.clone();
+            spawn(state, kind, params, move |ctx| async move {
+                ctx.progress(None, &format!("Crawling {}", body["url"].as_str().unwrap_or("")));
+                let summary = super::routes::crawl(&state2, &body).await?;
+                serde_json::to_value(summary).map_err(|e| e.to_string())
+            })
+        }
+        "brain_personalize" => {
+            if params["about_user"].as_str().unwrap_or("").is_empty() {
+                return Err("Please describe yourself (about_user)".into());
+            }
+            let body = params.clone();
+            let state2 = state.clone();
+            spawn(state, kind, params, move |_| async move {
+                super::routes::personalize(&state2, &body)
+                    .await
+                    .map_err(|e| e["error"].as_str().unwrap_or("Personalization failed").to_string())
+            })
+        }
+        "agent_broadcast" => {
+            let message = params["message"].as_str().unwrap_or("").to_string();
+            if message.is_empty() {
+                return Err("Empty message".into());
+            }
+            let state2 = state.clone();
+            spawn(state, kind, params, move |_| async move {
+                Ok(serde_json::json!({"responses": super::routes::broadcast(&state2, &message).await}))
+            })
+        }
+        "agent_pipeline" => {
+            let config = super::routes::pipeline_config(&params)?;
+            let state2 = state.clone();
+            spawn(state, kind, params, move |ctx| async move {
+                ctx.progress(None, &format!("Running {} step(s)", config.steps.len()));
+                let result = super::routes::run_pipeline(&state2, &config).await?;
+                serde_json::to_value(result).map_err(|e| e.to_string())
+            })
+        }
+        "agent_reload" => {
+            let state2 = state.clone();
+            spawn(state, kind, params, move |ctx| async move {
+                ctx.progress(None, "Starting the agent and its MCP servers");
+                let cfg = state2.full_config.lock().unwrap().clone();
+                let agent = super::routes::build_default_agent(&state2, cfg).await?;
+                let provider = agent.provider_name().to_string();
+                super::routes::install_default_agent(&state2, agent).await;
+                Ok(serde_json::json!({"provider": provider}))
+            })
+        }
+        other => Err(format!("Unknown job kind '{other}' (one of: {})", JOB_KINDS.join(", "))),
+    }
+}
+
+/// [`start`] as an endpoint response: `{"ok", "job"}` or `{"ok": false, "error"}`.
+pub fn start_response(state: &Arc<AppState>, kind: &str, params: Value) -> Json<Value> {
+    match start(state, kind, params) {
+        Ok(job) => Json(serde_json::json!({"ok": true, "job": job})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+/// POST /api/v1/jobs — `{"kind", "params"}`.
+pub async fn create_job(State(state): State<Arc<AppState>>, Json(body): Json<Value>) -> Json<Value> {
+    let kind = body["kind"].as_str().unwrap_or("").to_string();
+    let params = match body.get("params") {
+        Some(p) if p.is_object() => p.clone(),
+        _ => serde_json::json!({}),
+    };
+    start_response(&state, &kind, params)
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct JobsQuery {
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// GET /api/v1/jobs — `?kind=&status=&limit=`, newest first.
+pub async fn list_jobs(State(state): State<Arc<AppState>>, Query(q): Query<JobsQuery>) -> Json<Value> {
+    let limit = q.limit.unwrap_or(50).min(500);
+    match state.db.list_jobs(q.kind.as_deref(), q.status.as_deref(), limit) {
+        Ok(jobs) => Json(serde_json::json!({"ok": true, "jobs": jobs})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// GET /api/v1/jobs/{id} — status, progress and, once finished, the result.
+pub async fn get_job(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Json<Value> {
+    match state.db.get_job(&id) {
+        Ok(Some(job)) => Json(serde_json::json!({"ok": true, "job": job})),
+        Ok(None) => Json(serde_json::json!({"ok": false, "error": "Job not found"})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// POST /api/v1/jobs/{id}/cancel
+pub async fn cancel_job(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Json<Value> {
+    if cancel(&state, &id) {
+        Json(serde_json::json!({"ok": true, "message": format!("Job {id} cancelled")}))
+    } else {
+        Json(serde_json::json!({"ok": false, "error": "Job not found or already finished"}))
+    }
+}
@@ -1,11 +1,17 @@
 //! # BizClaw Gateway
 //! HTTP/WebSocket gateway API with embedded web dashboard.
 
+pub mod api_keys;
+pub mod audit;
+pub mod brain_server;
 pub mod dashboard;
 pub mod db;
+pub mod jobs;
+pub mod mcp_export;
 pub mod openai_compat;
 pub mod routes;
 pub mod server;
+pub mod workspace;
 pub mod ws;
 
 use bizclaw_core::config::GatewayConfig;
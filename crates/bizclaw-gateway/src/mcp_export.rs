@@ -0,0 +1,204 @@
+//! MCP endpoint — BizClaw's tools, knowledge and agents for MCP clients.
+//!
+//! `POST /mcp` speaks Streamable HTTP (JSON replies, no server-initiated
+//! stream), so Claude Desktop and other MCP clients can use a running
+//! gateway; clients that only launch stdio servers run `bizclaw mcp`,
+//! which relays to it. `[mcp_export]` decides what's offered: the listed
+//! built-in tools, `search_knowledge`, and an `ask_<agent>` tool per agent.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use bizclaw_agent::orchestrator::Orchestrator;
+use bizclaw_core::config::McpExportConfig;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_mcp::McpServer;
+use serde_json::{Value, json};
+
+use super::server::AppState;
+
+/// Session agents keep their conversation with MCP clients in.
+const MCP_SESSION: &str = "mcp";
+
+/// Answer a JSON-RPC message from an MCP client.
+pub async fn mcp_endpoint(State(state): State<Arc<AppState>>, body: String) -> Response {
+    let (config, autonomy) = {
+        let full = state.full_config.lock().unwrap();
+        (full.mcp_export.clone(), full.autonomy.clone())
+    };
+    if !config.enabled {
+        return (StatusCode::NOT_FOUND, "MCP endpoint disabled — set [mcp_export] enabled = true").into_response();
+    }
+    // Calls are checked against the tool schemas and `[[autonomy.argument_filters]]`
+    let server = McpServer::new(exported_tools(&state, &config).await)
+        .with_security(Arc::new(bizclaw_security::DefaultSecurityPolicy::new(autonomy)));
+    match server.handle_text(&body).await {
+        Some(answer) => Json(answer).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+/// Tools offered to MCP clients under `config`, sandboxed by `[autonomy]`;
+/// [`mcp_endpoint`] applies its argument filters to every call.
+/// Tools that need approval are left out — there's no one to ask.
+async fn exported_tools(state: &AppState, config: &McpExportConfig) -> Vec<Box<dyn Tool>> {
+    let (secured, autonomy) = {
+        let full = state.full_config.lock().unwrap();
+        (bizclaw_agent::secured_tools(&full), full.autonomy.clone())
+    };
+    let mut tools: Vec<Box<dyn Tool>> = secured
+        .into_tools()
+        .into_iter()
+        .filter(|tool| config.tools.iter().any(|name| name == tool.name()))
+        .filter(|tool| {
+            let gated = bizclaw_agent::approval::required(&autonomy, tool.name());
+            if gated {
+                tracing::warn!("MCP export skips '{}': it requires approval", tool.name());
+            }
+            !gated
+        })
+        .collect();
+    if config.knowledge && state.knowledge.lock().await.is_some() {
+        tools.push(Box::new(KnowledgeSearchTool {
+            knowledge: state.knowledge.clone(),
+        }));
+    }
+    if config.agents {
+        let agents = state.orchestrator.lock().await.list_agents();
+        for agent in agents {
+            let name = agent["name"].as_str().unwrap_or_default().to_string();
+            tools.push(Box::new(AgentTool {
+                tool_name: agent_tool_name(&name),
+                description: format!(
+                    "Ask the BizClaw agent '{name}' ({}): {}",
+                    agent["role"].as_str().unwrap_or_default(),
+                    agent["description"].as_str().unwrap_or_default()
+                ),
+                agent: name,
+                orchestrator: state.orchestrator.clone(),
+            }));
+        }
+    }
+    tools
+}
+
+/// `ask_<agent>`, with characters MCP tool names don't allow replaced.
+fn agent_tool_name(agent: &str) -> String {
+    let name: String = agent
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .take(60)
+        .collect();
+    format!("ask_{name}")
+}
+
+/// An agent as a tool: the client's message goes to the agent, and its
+/// reply comes back.
+struct AgentTool {
+    tool_name: String,
+    agent: String,
+    description: String,
+    orchestrator: Arc<tokio::sync::Mutex<Orchestrator>>,
+}
+
+#[async_trait]
+impl Tool for AgentTool {
+    fn name(&self) -> &str {
+        &self.tool_name
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.tool_name.clone(),
+            description: self.description.clone(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {"type": "string", "description": "Message for the agent"}
+                },
+                "required": ["message"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> bizclaw_core::error::Result<ToolResult> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or_default();
+        let message = args["message"].as_str().unwrap_or_default();
+        let mut orch = self.orchestrator.lock().await.clone().with_session(MCP_SESSION);
+        let (output, success) = match orch.send_to(&self.agent, message).await {
+            Ok(reply) => (reply, true),
+            Err(e) => (format!("Agent error: {e}"), false),
+        };
+        Ok(ToolResult {
+            tool_call_id: String::new(),
+            output,
+            success,
+        })
+    }
+}
+
+/// Knowledge base search, for clients to read what agents answer from.
+struct KnowledgeSearchTool {
+    knowledge: Arc<tokio::sync::Mutex<Option<bizclaw_knowledge::KnowledgeStore>>>,
+}
+
+#[async_trait]
+impl Tool for KnowledgeSearchTool {
+    fn name(&self) -> &str {
+        "search_knowledge"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "search_knowledge".into(),
+            description: "Search BizClaw's knowledge base of uploaded documents".into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "What to search for"},
+                    "limit": {"type": "integer", "description": "Most results (default 5, max 10)"},
+                    "namespace": {"type": "string", "description": "Only documents in this namespace"}
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> bizclaw_core::error::Result<ToolResult> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or_default();
+        let query = args["query"].as_str().unwrap_or_default();
+        let limit = args["limit"].as_u64().unwrap_or(5) as usize;
+        let namespaces: Vec<String> = args["namespace"].as_str().map(str::to_string).into_iter().collect();
+
+        let kb = self.knowledge.lock().await;
+        let results = kb
+            .as_ref()
+            .map(|store| store.search_in(query, limit, &namespaces))
+            .unwrap_or_default();
+        let output = if results.is_empty() {
+            format!("No knowledge found for '{query}'")
+        } else {
+            results
+                .iter()
+                .map(|r| {
+                    let mut source = r.doc_name.clone();
+                    if !r.heading_path.is_empty() {
+                        source = format!("{source} › {}", r.heading_path.join(" › "));
+                    }
+                    format!("[{source}]\n{}", r.content)
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+        Ok(ToolResult {
+            tool_call_id: String::new(),
+            output,
+            success: true,
+        })
+    }
+}
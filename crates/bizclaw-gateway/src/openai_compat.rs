@@ -3,10 +3,21 @@
 //! Any tool/app that supports OpenAI API (Cursor, Continue, Aider, LibreChat, etc.)
 //! can use BizClaw as a proxy by pointing to `http://localhost:3579/v1`.
 //!
-//! Authentication: `Authorization: Bearer <pairing-code>` or `api-key` header.
+//! The `model` field names an orchestrator agent (`/v1/models` lists them);
+//! unknown names go to the default agent. `stream: true` returns SSE
+//! `chat.completion.chunk` events. Requests with `tools` pass through: the
+//! model's tool calls come back to the client instead of running in the agent.
+//!
+//! Authentication: `Authorization: Bearer <pairing-code>` or `x-api-key` header,
+//! or a gateway API key (`bzk_…`, see [`crate::api_keys`]) with the `chat`
+//! scope (`read` is enough for `/v1/models`).
 
 use axum::extract::State;
 use axum::{Json, http::StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use bizclaw_core::traits::provider::ChatChunkSink;
+use bizclaw_core::types::{Message, ToolCall, ToolDefinition};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -21,7 +32,7 @@ pub struct ChatCompletionRequest {
     pub messages: Vec<ChatMessage>,
     #[serde(default)]
     pub temperature: Option<f64>,
-    #[serde(default)]
+    #[serde(default, alias = "max_completion_tokens")]
     pub max_tokens: Option<u32>,
     #[serde(default)]
     pub stream: Option<bool>,
@@ -29,14 +40,100 @@ pub struct ChatCompletionRequest {
     pub top_p: Option<f64>,
     #[serde(default)]
     pub stop: Option<Vec<String>>,
+    /// Client-side tools. When given, the agent's own tool loop is skipped:
+    /// the model sees the whole `messages` history and its tool calls are
+    /// returned for the client to run.
+    #[serde(default)]
+    pub tools: Vec<ToolSpec>,
+    /// End-user id. Each user gets their own conversation with the agent;
+    /// requests without one share the default session.
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ChatMessage {
     pub role: String,
-    pub content: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A string, or an array of `{"type": "text", "text": ...}` parts.
+    #[serde(default)]
+    pub content: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// Text of the message, with content parts joined.
+    pub fn text(&self) -> String {
+        match &self.content {
+            Value::String(s) => s.clone(),
+            Value::Array(parts) => parts
+                .iter()
+                .filter(|p| p["type"] == "text")
+                .filter_map(|p| p["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        }
+    }
+}
+
+/// An OpenAI tool definition: `{"type": "function", "function": {...}}`.
+#[derive(Debug, Deserialize)]
+pub struct ToolSpec {
+    #[serde(default)]
+    pub r#type: String,
+    pub function: FunctionSpec,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FunctionSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub parameters: Value,
+}
+
+/// Convert OpenAI chat messages, keeping tool calls and results; unknown
+/// roles are rejected.
+fn to_messages(messages: &[ChatMessage]) -> Result<Vec<Message>, String> {
+    messages
+        .iter()
+        .map(|m| {
+            let content = m.text();
+            Ok(match m.role.as_str() {
+                "system" | "developer" => Message::system(content),
+                "user" => Message::user(content),
+                "assistant" => {
+                    let mut message = Message::assistant(content);
+                    message.tool_calls = m.tool_calls.clone().filter(|calls| !calls.is_empty());
+                    message
+                }
+                "tool" => Message::tool(content, m.tool_call_id.clone().unwrap_or_default()),
+                other => return Err(format!("Unknown message role '{other}'")),
+            })
+        })
+        .collect()
+}
+
+fn to_tool_definitions(tools: &[ToolSpec]) -> Vec<ToolDefinition> {
+    tools
+        .iter()
+        .filter(|t| t.r#type.is_empty() || t.r#type == "function")
+        .map(|t| ToolDefinition {
+            name: t.function.name.clone(),
+            description: t.function.description.clone(),
+            parameters: if t.function.parameters.is_null() {
+                json!({"type": "object", "properties": {}})
+            } else {
+                t.function.parameters.clone()
+            },
+        })
+        .collect()
 }
 
 #[derive(Debug, Serialize)]
@@ -95,8 +192,12 @@ fn extract_api_key(headers: &axum::http::HeaderMap) -> Option<String> {
     None
 }
 
-/// Validate API key against pairing code. Returns true if valid.
-fn validate_key(state: &AppState, key: &str) -> bool {
+/// Validate API key against the gateway's API keys (needing `scope`) or the
+/// pairing code. Returns true if valid.
+fn validate_key(state: &AppState, key: &str, scope: super::api_keys::Scope) -> bool {
+    if key.starts_with(super::api_keys::KEY_PREFIX) {
+        return super::api_keys::authorize(state, key, scope).is_ok();
+    }
     let stored = state.pairing_code.lock().unwrap().clone();
     // Constant-time comparison
     if key.len() != stored.len() {
@@ -111,71 +212,107 @@ fn validate_key(state: &AppState, key: &str) -> bool {
 
 // ─── POST /v1/chat/completions ───────────────────────────────────────────────
 
-pub async fn chat_completions(
-    State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
-    Json(req): Json<ChatCompletionRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    // Auth check
-    let key = extract_api_key(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
-    if !validate_key(&state, &key) {
-        return Err(StatusCode::UNAUTHORIZED);
+/// What an agent answered.
+struct Reply {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    citations: Vec<bizclaw_agent::rag::Citation>,
+}
+
+impl Reply {
+    fn error(e: impl std::fmt::Display) -> Self {
+        Self {
+            content: format!("Error: {e}"),
+            tool_calls: vec![],
+            citations: vec![],
+        }
     }
 
-    let start = std::time::Instant::now();
+    fn finish_reason(&self) -> &'static str {
+        if self.tool_calls.is_empty() { "stop" } else { "tool_calls" }
+    }
+}
 
-    // Route "model" field to agent name — if model matches an agent, use it
-    // Otherwise use the default agent
-    let user_content = req.messages
-        .iter()
-        .rev()
-        .find(|m| m.role == "user")
-        .and_then(|m| m.content.as_deref())
-        .unwrap_or("");
-
-    let response_text = {
-        // Try to find agent by model name first
-        let mut orch = state.orchestrator.lock().await;
-        if let Some(agent) = orch.get_agent_mut(&req.model) {
-            // Use the named agent
-            match agent.process(user_content).await {
-                Ok(r) => r,
-                Err(e) => format!("Error: {e}"),
-            }
-        } else {
-            // Fallback to default agent
-            drop(orch);
-            let mut agent_lock = state.agent.lock().await;
-            if let Some(agent) = agent_lock.as_mut() {
-                match agent.process(user_content).await {
-                    Ok(r) => r,
-                    Err(e) => format!("Error: {e}"),
-                }
-            } else {
-                return Err(StatusCode::SERVICE_UNAVAILABLE);
-            }
+/// Text sent to the agent, for token estimates: the last user message, or
+/// the whole history when the client runs the tools.
+fn prompt_text(req: &ChatCompletionRequest) -> String {
+    if req.tools.is_empty() {
+        req.messages.iter().rev().find(|m| m.role == "user").map(|m| m.text()).unwrap_or_default()
+    } else {
+        req.messages.iter().map(|m| m.text()).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Answer on the agent named by `model` (the default agent otherwise),
+/// streaming reply text to `chunks`. `None` when there's no agent.
+async fn run_agent(state: &AppState, req: &ChatCompletionRequest, chunks: Option<&ChatChunkSink>) -> Option<Reply> {
+    let orch = state.orchestrator.lock().await.clone();
+    if let Some(mut agent) = orch.get_agent_mut(&req.model).await {
+        return Some(run_on(&mut agent, req, chunks).await);
+    }
+    let mut agent_lock = state.agent.lock().await;
+    let agent = agent_lock.as_mut()?;
+    Some(run_on(agent, req, chunks).await)
+}
+
+async fn run_on(agent: &mut bizclaw_agent::Agent, req: &ChatCompletionRequest, chunks: Option<&ChatChunkSink>) -> Reply {
+    match req.user.as_deref().filter(|u| !u.is_empty()) {
+        Some(user) => agent.switch_session(&format!("openai:{user}")).await,
+        None => agent.switch_session(bizclaw_agent::sessions::DEFAULT_SESSION).await,
+    }
+    // Client-side tools: one model call over the client's history
+    if !req.tools.is_empty() {
+        let messages = match to_messages(&req.messages) {
+            Ok(messages) => messages,
+            Err(e) => return Reply::error(e),
+        };
+        let tools = to_tool_definitions(&req.tools);
+        let temperature = req.temperature.map(|t| t as f32);
+        return match agent.complete(&messages, &tools, temperature, req.max_tokens, chunks).await {
+            Ok(resp) => Reply {
+                content: resp.content.unwrap_or_default(),
+                tool_calls: resp.tool_calls,
+                citations: vec![],
+            },
+            Err(e) => Reply::error(e),
+        };
+    }
+
+    // The agent keeps the conversation and runs its own tools
+    let user_content = prompt_text(req);
+    let result = match chunks {
+        Some(chunks) => {
+            let (progress, _) = tokio::sync::mpsc::unbounded_channel();
+            agent.process_streaming(&user_content, &progress, chunks).await
         }
+        None => agent.process(&user_content).await,
     };
+    match result {
+        Ok(content) => Reply {
+            content,
+            tool_calls: vec![],
+            citations: agent.last_citations().to_vec(),
+        },
+        Err(e) => Reply::error(e),
+    }
+}
 
-    let elapsed = start.elapsed();
-    let est_prompt_tokens = (user_content.len() / 4) as u32;
-    let est_completion_tokens = (response_text.len() / 4) as u32;
-
-    // Record trace
+/// Record the call in the traces and the activity feed.
+fn record_completion(state: &AppState, model: &str, prompt_tokens: u32, completion_tokens: u32, tool_calls: usize, elapsed: std::time::Duration) {
     {
         let trace = LlmTrace {
             id: uuid::Uuid::new_v4().to_string(),
             timestamp: chrono::Utc::now(),
-            model: req.model.clone(),
+            model: model.to_string(),
             provider: "bizclaw".into(),
-            prompt_tokens: est_prompt_tokens,
-            completion_tokens: est_completion_tokens,
-            total_tokens: est_prompt_tokens + est_completion_tokens,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
             latency_ms: elapsed.as_millis() as u64,
-            cost_usd: estimate_cost(&req.model, est_prompt_tokens, est_completion_tokens),
+            cost_usd: estimate_cost(model, prompt_tokens, completion_tokens),
             cache_hit: false,
             status: "ok".into(),
-            tool_calls: 0,
+            tool_calls: tool_calls as u32,
             error: None,
         };
         let mut traces = state.traces.lock().unwrap();
@@ -189,32 +326,143 @@ pub async fn chat_completions(
     // Broadcast activity event via WebSocket
     let _ = state.activity_tx.send(ActivityEvent {
         event_type: "llm.completed".into(),
-        agent: req.model.clone(),
-        detail: format!("{}tok in {}ms", est_prompt_tokens + est_completion_tokens, elapsed.as_millis()),
+        agent: model.to_string(),
+        detail: format!("{}tok in {}ms", prompt_tokens + completion_tokens, elapsed.as_millis()),
         timestamp: chrono::Utc::now(),
     });
+}
+
+/// Tool calls in a streamed `delta`: each with its index in the list.
+fn tool_call_deltas(calls: &[ToolCall]) -> Value {
+    Value::Array(
+        calls
+            .iter()
+            .enumerate()
+            .map(|(index, call)| {
+                json!({
+                    "index": index,
+                    "id": call.id,
+                    "type": "function",
+                    "function": {"name": call.function.name, "arguments": call.function.arguments},
+                })
+            })
+            .collect(),
+    )
+}
+
+pub async fn chat_completions(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ChatCompletionRequest>,
+) -> Result<Response, StatusCode> {
+    // Auth check
+    let key = extract_api_key(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    if !validate_key(&state, &key, super::api_keys::Scope::Chat) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let id = format!("chatcmpl-{}", &uuid::Uuid::new_v4().simple().to_string()[..24]);
+    let created = chrono::Utc::now().timestamp();
+    let est_prompt_tokens = (prompt_text(&req).len() / 4) as u32;
+
+    if req.stream.unwrap_or(false) {
+        return Ok(stream_completion(state, req, id, created, est_prompt_tokens));
+    }
 
+    let start = std::time::Instant::now();
+    let reply = run_agent(&state, &req, None).await.ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let est_completion_tokens = (reply.content.len() / 4) as u32;
+    record_completion(&state, &req.model, est_prompt_tokens, est_completion_tokens, reply.tool_calls.len(), start.elapsed());
+
+    let mut message = json!({"role": "assistant", "content": reply.content});
+    if !reply.tool_calls.is_empty() {
+        if reply.content.is_empty() {
+            message["content"] = Value::Null;
+        }
+        message["tool_calls"] = json!(reply.tool_calls);
+    }
     let response = json!({
-        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().to_string().replace("-", "")[..24].to_string()),
+        "id": id,
         "object": "chat.completion",
-        "created": chrono::Utc::now().timestamp(),
+        "created": created,
         "model": req.model,
         "choices": [{
             "index": 0,
-            "message": {
-                "role": "assistant",
-                "content": response_text,
-            },
-            "finish_reason": "stop"
+            "message": message,
+            "finish_reason": reply.finish_reason(),
         }],
         "usage": {
             "prompt_tokens": est_prompt_tokens,
             "completion_tokens": est_completion_tokens,
             "total_tokens": est_prompt_tokens + est_completion_tokens,
+        },
+        // Extension: knowledge chunks the answer cites
+        "citations": reply.citations,
+    });
+
+    Ok(Json(response).into_response())
+}
+
+/// `stream: true` — `chat.completion.chunk` events as the reply is
+/// generated, tool calls in the last delta, then `[DONE]`.
+fn stream_completion(state: Arc<AppState>, req: ChatCompletionRequest, id: String, created: i64, prompt_tokens: u32) -> Response {
+    let model = req.model.clone();
+    let chunk = move |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        })
+    };
+
+    let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let start = std::time::Instant::now();
+    let task = tokio::spawn(async move {
+        let reply = run_agent(&state, &req, Some(&chunk_tx)).await;
+        if let Some(reply) = &reply {
+            let completion_tokens = (reply.content.len() / 4) as u32;
+            record_completion(&state, &req.model, prompt_tokens, completion_tokens, reply.tool_calls.len(), start.elapsed());
         }
+        reply
     });
 
-    Ok(Json(response))
+    // Dropping the response stream closes `events`; the agent still finishes its turn
+    let (events, events_rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    tokio::spawn(async move {
+        let send = |data: Value| events.send(Event::default().data(data.to_string())).is_ok();
+        if !send(chunk(json!({"role": "assistant", "content": ""}), None)) {
+            return;
+        }
+        let mut streamed = false;
+        while let Some(piece) = chunk_rx.recv().await {
+            streamed = true;
+            if !send(chunk(json!({"content": piece}), None)) {
+                return;
+            }
+        }
+        let last = match task.await {
+            Ok(Some(reply)) => {
+                // Errors aren't streamed by the provider; send them as text
+                if !streamed && !reply.content.is_empty() {
+                    send(chunk(json!({"content": reply.content}), None));
+                }
+                if !reply.tool_calls.is_empty() {
+                    send(chunk(json!({"tool_calls": tool_call_deltas(&reply.tool_calls)}), None));
+                }
+                chunk(json!({}), Some(reply.finish_reason()))
+            }
+            Ok(None) => json!({"error": {"message": "No agent available", "type": "server_error"}}),
+            Err(e) => json!({"error": {"message": format!("Agent task failed: {e}"), "type": "server_error"}}),
+        };
+        send(last);
+        let _ = events.send(Event::default().data("[DONE]"));
+    });
+    let stream = futures::stream::unfold(events_rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok::<_, std::convert::Infallible>(event), rx))
+    });
+    Sse::new(stream).into_response()
 }
 
 // ─── GET /v1/models ──────────────────────────────────────────────────────────
@@ -225,7 +473,7 @@ pub async fn list_models(
 ) -> Result<Json<Value>, StatusCode> {
     // Auth check
     let key = extract_api_key(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
-    if !validate_key(&state, &key) {
+    if !validate_key(&state, &key, super::api_keys::Scope::Read) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
@@ -385,3 +633,55 @@ pub async fn list_activity(
         "total": events.len(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::Role;
+
+    #[test]
+    fn test_tool_passthrough_request() {
+        let req: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "sales",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": [{"type": "text", "text": "Weather in Hanoi?"}]},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1", "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Hanoi\"}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "31°C, sunny"},
+            ],
+            "tools": [{"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}],
+            "stream": true,
+        }))
+        .unwrap();
+        assert_eq!(prompt_text(&req), "Be brief.\nWeather in Hanoi?\n\n31°C, sunny");
+
+        let messages = to_messages(&req.messages).unwrap();
+        assert_eq!(messages[1].content, "Weather in Hanoi?");
+        assert_eq!(messages[2].tool_calls.as_ref().unwrap()[0].function.name, "get_weather");
+        assert_eq!(messages[3].role, Role::Tool);
+        assert_eq!(messages[3].tool_call_id.as_deref(), Some("call_1"));
+
+        let tools = to_tool_definitions(&req.tools);
+        assert_eq!(tools[0].name, "get_weather");
+        assert_eq!(tools[0].parameters["type"], "object");
+
+        let bad = ChatMessage { role: "robot".into(), content: json!("hi"), name: None, tool_calls: None, tool_call_id: None };
+        assert!(to_messages(&[bad]).is_err());
+    }
+
+    #[test]
+    fn test_tool_call_deltas_are_indexed() {
+        let call = |id: &str| ToolCall {
+            id: id.into(),
+            r#type: "function".into(),
+            function: bizclaw_core::types::FunctionCall { name: "lookup".into(), arguments: "{}".into() },
+        };
+        let deltas = tool_call_deltas(&[call("a"), call("b")]);
+        assert_eq!(deltas[1]["index"], 1);
+        assert_eq!(deltas[1]["id"], "b");
+        assert_eq!(deltas[0]["function"]["name"], "lookup");
+    }
+}
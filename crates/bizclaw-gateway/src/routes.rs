@@ -91,6 +91,78 @@ fn apply_provider_config_from_db(
     }
 }
 
+/// Fill fallback provider credentials/endpoints from the DB providers table.
+pub(crate) fn apply_fallback_config_from_db(
+    db: &GatewayDb,
+    config: &mut bizclaw_core::config::BizClawConfig,
+) {
+    for fb in config.llm.fallback_providers.iter_mut() {
+        let Ok(db_provider) = db.get_provider(&fb.provider) else {
+            continue;
+        };
+        if fb.api_key.is_empty() {
+            fb.api_key = db_provider.api_key;
+        }
+        if fb.endpoint.is_empty()
+            && (db_provider.provider_type == "local" || db_provider.provider_type == "proxy")
+        {
+            fb.endpoint = db_provider.base_url;
+        }
+    }
+}
+
+/// Parse a `fallback_providers` request field: provider names or `{provider, model}` objects.
+fn parse_fallback_providers(
+    value: &serde_json::Value,
+) -> Option<Vec<bizclaw_core::config::FallbackProviderConfig>> {
+    let items = value.as_array()?;
+    Some(
+        items
+            .iter()
+            .filter_map(|v| match v {
+                serde_json::Value::String(p) => Some(bizclaw_core::config::FallbackProviderConfig {
+                    provider: p.clone(),
+                    ..Default::default()
+                }),
+                other => serde_json::from_value(other.clone()).ok(),
+            })
+            .filter(|f| !f.provider.is_empty())
+            .collect(),
+    )
+}
+
+/// Parse a list of names, e.g. knowledge namespaces or MCP prompts (blank
+/// entries dropped).
+fn parse_names(value: &serde_json::Value) -> Option<Vec<String>> {
+    let items = value.as_array()?;
+    Some(
+        items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|ns| ns.trim().to_string())
+            .filter(|ns| !ns.is_empty())
+            .collect(),
+    )
+}
+
+/// Parse an agent's `max_tool_rounds`: a positive number sets it, `null`
+/// goes back to `autonomy.max_tool_rounds`, absent leaves it alone.
+fn parse_max_tool_rounds(value: Option<&serde_json::Value>) -> Option<Option<u32>> {
+    match value? {
+        serde_json::Value::Null => Some(None),
+        v => v.as_u64().filter(|&n| n > 0).map(|n| Some(n.min(u32::MAX as u64) as u32)),
+    }
+}
+
+/// The `namespace` a knowledge request writes to.
+fn namespace_param(body: &serde_json::Value) -> &str {
+    body["namespace"]
+        .as_str()
+        .map(str::trim)
+        .filter(|ns| !ns.is_empty())
+        .unwrap_or(bizclaw_knowledge::DEFAULT_NAMESPACE)
+}
+
 /// Health check endpoint.
 pub async fn health_check() -> Json<serde_json::Value> {
     Json(serde_json::json!({
@@ -100,6 +172,44 @@ pub async fn health_check() -> Json<serde_json::Value> {
     }))
 }
 
+/// Prometheus scrape endpoint — everything in [`bizclaw_core::metrics`]:
+/// HTTP requests per route, agent processing time, tool calls, provider
+/// errors, scheduler tasks and brain throughput. Gauges that describe
+/// current state are refreshed here, at scrape time.
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl axum::response::IntoResponse {
+    use bizclaw_core::metrics;
+    metrics::set("bizclaw_uptime_seconds", &[], state.start_time.elapsed().as_secs_f64());
+    metrics::set("bizclaw_agents", &[], state.orchestrator.lock().await.agent_count() as f64);
+    state.scheduler.lock().await.record_metrics();
+    for (name, help) in METRIC_HELP {
+        metrics::describe(name, help);
+    }
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        metrics::render(),
+    )
+}
+
+const METRIC_HELP: &[(&str, &str)] = &[
+    ("bizclaw_http_requests_total", "HTTP requests by method, route and status."),
+    ("bizclaw_http_request_duration_seconds", "HTTP request latency by method and route."),
+    ("bizclaw_agent_process_seconds", "Time an agent takes to answer a message."),
+    ("bizclaw_tool_calls_total", "Tool calls made by agents."),
+    ("bizclaw_provider_errors_total", "Failed LLM provider calls, fallbacks included."),
+    ("bizclaw_scheduler_task_runs_total", "Scheduled task runs by outcome."),
+    ("bizclaw_scheduler_task_seconds", "Scheduled task run time."),
+    ("bizclaw_scheduler_tasks", "Scheduled tasks."),
+    ("bizclaw_scheduler_tasks_enabled", "Enabled scheduled tasks."),
+    ("bizclaw_scheduler_tasks_retrying", "Tasks waiting to retry after a failure."),
+    ("bizclaw_scheduler_tasks_failed", "Tasks that failed after all retries."),
+    ("bizclaw_scheduler_retries", "Retry attempts across all tasks."),
+    ("bizclaw_brain_prompt_tokens_total", "Prompt tokens processed by the local brain."),
+    ("bizclaw_brain_generated_tokens_total", "Tokens generated by the local brain."),
+    ("bizclaw_brain_tokens_per_second", "Decode speed of the last local brain generation."),
+    ("bizclaw_uptime_seconds", "Gateway uptime."),
+    ("bizclaw_agents", "Agents in the orchestrator."),
+];
+
 /// System information endpoint.
 pub async fn system_info(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     let uptime = state.start_time.elapsed();
@@ -165,6 +275,7 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<serde_json::
         },
         "tunnel": {
             "provider": cfg.tunnel.provider,
+            "public_url": cfg.tunnel.public_url,
         },
         "secrets": {
             "encrypt": cfg.secrets.encrypt,
@@ -174,9 +285,18 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<serde_json::
             for (k, v) in &s.env {
                 masked_env.insert(k.clone(), mask_secret(v));
             }
+            let masked_headers: std::collections::HashMap<_, _> =
+                s.headers.iter().map(|(k, v)| (k.clone(), mask_secret(v))).collect();
             serde_json::json!({
                 "name": s.name, "command": s.command,
-                "args": s.args, "env": masked_env, "enabled": s.enabled,
+                "args": s.args, "env": masked_env,
+                "url": s.url, "headers": masked_headers,
+                "bearer_token": mask_secret(&s.bearer_token),
+                "oauth": s.oauth.as_ref().map(|o| serde_json::json!({
+                    "token_url": o.token_url, "client_id": o.client_id,
+                    "client_secret": mask_secret(&o.client_secret), "scope": o.scope,
+                })),
+                "enabled": s.enabled,
             })
         }).collect::<Vec<_>>(),
         "channels": {
@@ -194,6 +314,10 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<serde_json::
                 "imei": z.personal.imei,
                 "self_listen": z.personal.self_listen,
                 "auto_reconnect": z.personal.auto_reconnect,
+                "app_id": z.official.app_id,
+                "app_secret": mask_secret(&z.official.app_secret),
+                "oa_secret_key": mask_secret(&z.official.oa_secret_key),
+                "refresh_token_set": !z.official.refresh_token.is_empty(),
             })),
             "discord": cfg.channel.discord.as_ref().map(|d| serde_json::json!({
                 "enabled": d.enabled,
@@ -215,6 +339,19 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<serde_json::
                 "phone_number_id": w.phone_number_id,
                 "access_token": mask_secret(&w.access_token),
                 "business_id": w.business_id,
+                "notify_to": w.notify_to,
+                "notify_template": w.notify_template,
+                "notify_template_language": w.notify_template_language,
+            })),
+            "sms": cfg.channel.sms.as_ref().map(|s| serde_json::json!({
+                "enabled": s.enabled,
+                "account_sid": s.account_sid,
+                "auth_token": mask_secret(&s.auth_token),
+                "auth_token_set": !s.auth_token.is_empty(),
+                "from_number": s.from_number,
+                "webhook_url": s.webhook_url,
+                "agents": s.agents,
+                "notify_to": s.notify_to,
             })),
             "webhook": cfg.channel.webhook.as_ref().map(|wh| serde_json::json!({
                 "enabled": wh.enabled,
@@ -229,7 +366,7 @@ pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<serde_json::
 /// Get full config as TOML string for export/display.
 pub async fn get_full_config(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     let cfg = state.full_config.lock().unwrap();
-    let toml_str = toml::to_string_pretty(&*cfg).unwrap_or_default();
+    let toml_str = cfg.to_toml().unwrap_or_default();
     Json(serde_json::json!({
         "ok": true,
         "toml": toml_str,
@@ -237,13 +374,16 @@ pub async fn get_full_config(State(state): State<Arc<AppState>>) -> Json<serde_j
     }))
 }
 
-/// Update config fields via JSON body.
-pub async fn update_config(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let mut cfg = state.full_config.lock().unwrap();
+/// How long a config update waits for the agent to come up on the new
+/// config before rolling back.
+const AGENT_RELOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
+/// Apply the fields of an update_config request to `cfg`. `Err` names
+/// the field that didn't parse.
+fn apply_config_update(
+    cfg: &mut bizclaw_core::config::BizClawConfig,
+    req: &serde_json::Value,
+) -> Result<(), (&'static str, String)> {
     // Update top-level fields + sync to LLM section
     // CRITICAL: create_provider() reads llm.* FIRST, so both must be in sync
     if let Some(v) = req.get("default_provider").and_then(|v| v.as_str()) {
@@ -323,18 +463,156 @@ pub async fn update_config(
     }
 
     // Update MCP servers
-    if let Some(mcp) = req.get("mcp_servers")
-        && let Ok(servers) =
-            serde_json::from_value::<Vec<bizclaw_core::config::McpServerEntry>>(mcp.clone())
-        {
-            cfg.mcp_servers = servers;
-        }
+    if let Some(mcp) = req.get("mcp_servers") {
+        cfg.mcp_servers = serde_json::from_value(mcp.clone()).map_err(|e| ("mcp_servers", format!("invalid MCP servers: {e}")))?;
+    }
 
-    // Save to disk
-    let content = toml::to_string_pretty(&*cfg).unwrap_or_default();
-    let new_cfg = cfg.clone();
+    // Update agent routing
+    if let Some(routing) = req.get("routing") {
+        cfg.routing = serde_json::from_value(routing.clone()).map_err(|e| ("routing", format!("invalid routing: {e}")))?;
+    }
+
+    Ok(())
+
+}
+
+/// Changed config fields as `{"field", "from", "to"}`, secrets masked.
+fn config_changes(old: &bizclaw_core::config::BizClawConfig, new: &bizclaw_core::config::BizClawConfig) -> Vec<serde_json::Value> {
+    super::audit::diff(
+        &serde_json::to_value(old).unwrap_or_default(),
+        &serde_json::to_value(new).unwrap_or_default(),
+    )
+}
+
+/// Replace `path` with `content` in one step (write a temp file, rename).
+fn write_atomic(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension(format!("toml.{}.tmp", uuid::Uuid::new_v4().simple()));
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Build the default agent on `cfg` (with its MCP servers), giving up after
+/// [`AGENT_RELOAD_TIMEOUT`].
+pub(crate) async fn build_default_agent(
+    state: &AppState,
+    cfg: bizclaw_core::config::BizClawConfig,
+) -> Result<bizclaw_agent::Agent, String> {
+    let mut agent = match tokio::time::timeout(AGENT_RELOAD_TIMEOUT, bizclaw_agent::Agent::new_with_mcp(cfg)).await {
+        Ok(result) => result.map_err(|e| e.to_string())?,
+        Err(_) => return Err(format!("timed out after {}s", AGENT_RELOAD_TIMEOUT.as_secs())),
+    };
+    agent.register_tool(Box::new(bizclaw_tools::schedule::ScheduleTool::new(state.scheduler.clone(), None)));
+    agent.set_event_bus(state.events.clone(), "default");
+    Ok(agent)
+}
+
+/// Replace the running default agent with `agent`.
+pub(crate) async fn install_default_agent(state: &AppState, agent: bizclaw_agent::Agent) {
+    tracing::info!(
+        "🔄 Agent re-initialized: provider={}, tools={}",
+        agent.provider_name(),
+        agent.tool_count()
+    );
+    *state.agent.lock().await = Some(agent);
+    state.events.publish(bizclaw_core::events::Event::AgentReloaded { agent: "default".into() });
+}
+
+/// Update config fields, then reload the default agent on the new config.
+///
+/// The result is validated first: with errors nothing is saved. With
+/// `"dry_run": true` only the diagnostics and changes are returned. If the
+/// agent can't be rebuilt on the saved config, the previous file and
+/// in-memory config are restored and the old agent keeps running.
+pub async fn update_config(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let dry_run = req["dry_run"].as_bool().unwrap_or(false);
+    let old_cfg = state.full_config.lock().unwrap().clone();
+    let mut new_cfg = old_cfg.clone();
+
+    let mut issues = Vec::new();
+    if let Err((field, e)) = apply_config_update(&mut new_cfg, &req) {
+        issues.push(bizclaw_core::config::ConfigIssue::error(field, e));
+    }
+    issues.extend(new_cfg.validate());
+    if !new_cfg.default_provider.is_empty() && state.db.get_provider(&new_cfg.default_provider).is_err() {
+        issues.push(bizclaw_core::config::ConfigIssue::warning(
+            "default_provider",
+            format!("'{}' is not a configured provider", new_cfg.default_provider),
+        ));
+    }
+    if let Err(e) = new_cfg.to_toml() {
+        issues.push(bizclaw_core::config::ConfigIssue::error("config", format!("can't be written as TOML: {e}")));
+    }
+    let has_errors = issues.iter().any(|i| i.is_error());
+    let changes = config_changes(&old_cfg, &new_cfg);
+
+    if dry_run {
+        super::audit::skip();
+        return Json(serde_json::json!({
+            "ok": !has_errors,
+            "dry_run": true,
+            "issues": issues,
+            "changes": changes,
+        }));
+    }
+    if has_errors {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": "Config has errors — nothing was saved",
+            "issues": issues,
+        }));
+    }
+
+    // Save to disk, keeping the previous file for rollback
+    let content = match bizclaw_security::secrets::config_toml(&new_cfg, &state.config_path) {
+        Ok(content) => content,
+        Err(e) => return internal_error("gateway", e),
+    };
+    let previous = std::fs::read_to_string(&state.config_path).ok();
+    if let Err(e) = write_atomic(&state.config_path, &content) {
+        return internal_error("gateway", e);
+    }
+    *state.full_config.lock().unwrap() = new_cfg.clone();
+    tracing::info!("✅ Config saved to {}", state.config_path.display());
+
+    // Re-initialize Agent with new config; the old agent serves until it's ready
+    let new_agent = match build_default_agent(&state, new_cfg.clone()).await {
+        Ok(agent) => agent,
+        Err(e) => {
+            tracing::warn!("⚠️ Agent re-init failed: {e} — rolling back config");
+            let restored = match &previous {
+                Some(previous) => write_atomic(&state.config_path, previous),
+                None => std::fs::remove_file(&state.config_path),
+            };
+            if let Err(e) = restored {
+                tracing::error!("[gateway] Config rollback failed: {e}");
+            }
+            *state.full_config.lock().unwrap() = old_cfg;
+            return Json(serde_json::json!({
+                "ok": false,
+                "error": format!("Agent re-init failed: {e} — previous config restored"),
+                "rolled_back": true,
+                "issues": issues,
+            }));
+        }
+    };
+    install_default_agent(&state, new_agent).await;
+    state
+        .orchestrator
+        .lock()
+        .await
+        .set_router(bizclaw_agent::routing::Router::from_config(&new_cfg));
+    super::audit::record(
+        &state,
+        "config.update",
+        "config",
+        serde_json::to_value(&old_cfg).ok(),
+        serde_json::to_value(&new_cfg).ok(),
+    );
 
-    // Build sync data for platform DB import
+    // Write config_sync.json for platform DB import
     let sync_data = serde_json::json!({
         "default_provider": new_cfg.default_provider,
         "default_model": new_cfg.default_model,
@@ -351,43 +629,20 @@ pub async fn update_config(
         "brain.temperature": new_cfg.brain.temperature,
         "updated_at": chrono::Utc::now().to_rfc3339(),
     });
-
-    drop(cfg); // Release lock before file write + agent reinit
-
-    match std::fs::write(&state.config_path, &content) {
-        Ok(_) => {
-            tracing::info!("✅ Config saved to {}", state.config_path.display());
-
-            // Write config_sync.json for platform DB import
-            if let Some(parent) = state.config_path.parent() {
-                let sync_path = parent.join("config_sync.json");
-                if let Ok(json) = serde_json::to_string_pretty(&sync_data) {
-                    std::fs::write(&sync_path, json).ok();
-                    tracing::info!("📋 Config sync file written to {}", sync_path.display());
-                }
-            }
-
-            // Re-initialize Agent with new config (async, don't block response)
-            let agent_lock = state.agent.clone();
-            tokio::spawn(async move {
-                match bizclaw_agent::Agent::new_with_mcp(new_cfg).await {
-                    Ok(new_agent) => {
-                        let mut guard = agent_lock.lock().await;
-                        tracing::info!(
-                            "🔄 Agent re-initialized: provider={}, tools={}",
-                            new_agent.provider_name(),
-                            new_agent.tool_count()
-                        );
-                        *guard = Some(new_agent);
-                    }
-                    Err(e) => tracing::warn!("⚠️ Agent re-init failed: {e}"),
-                }
-            });
-
-            Json(serde_json::json!({"ok": true, "message": "Config saved — agent reloading"}))
+    if let Some(parent) = state.config_path.parent() {
+        let sync_path = parent.join("config_sync.json");
+        if let Ok(json) = serde_json::to_string_pretty(&sync_data) {
+            std::fs::write(&sync_path, json).ok();
+            tracing::info!("📋 Config sync file written to {}", sync_path.display());
         }
-        Err(e) => internal_error("gateway", e),
     }
+
+    Json(serde_json::json!({
+        "ok": true,
+        "message": "Config saved — agent reloaded",
+        "issues": issues,
+        "changes": changes,
+    }))
 }
 
 /// Update channel config.
@@ -404,6 +659,10 @@ pub async fn update_channel(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
     let mut cfg = state.full_config.lock().unwrap();
+    let section = |cfg: &bizclaw_core::config::BizClawConfig| {
+        serde_json::to_value(&cfg.channel).ok().and_then(|c| c.get(channel_type).cloned())
+    };
+    let old_section = section(&cfg);
 
     match channel_type {
         "telegram" => {
@@ -446,7 +705,26 @@ pub async fn update_channel(
             if let Some(v) = req.get("imei").and_then(|v| v.as_str()) {
                 zalo_cfg.personal.imei = v.to_string();
             }
+            if let Some(v) = req.get("mode").and_then(|v| v.as_str()) {
+                zalo_cfg.mode = v.to_string();
+            }
+            // OA credentials — masked values keep the saved secret
+            let official = &mut zalo_cfg.official;
+            for (key, field) in [
+                ("app_id", &mut official.app_id),
+                ("app_secret", &mut official.app_secret),
+                ("oa_secret_key", &mut official.oa_secret_key),
+                ("access_token", &mut official.access_token),
+                ("refresh_token", &mut official.refresh_token),
+            ] {
+                if let Some(v) = req.get(key).and_then(|v| v.as_str())
+                    && !v.contains('•')
+                {
+                    *field = v.to_string();
+                }
+            }
             cfg.channel.zalo = Some(zalo_cfg);
+            *state.zalo_oa.lock().unwrap() = None;
         }
         "discord" => {
             let token_val = req.get("bot_token").and_then(|v| v.as_str()).unwrap_or("");
@@ -534,6 +812,21 @@ pub async fn update_channel(
             } else {
                 token_val.to_string()
             };
+            let str_or = |key: &str, old: Option<String>| {
+                req.get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .or(old)
+                    .unwrap_or_default()
+            };
+            let old = cfg.channel.whatsapp.clone();
+            let notify_to = str_or("notify_to", old.as_ref().map(|w| w.notify_to.clone()));
+            let notify_template =
+                str_or("notify_template", old.as_ref().map(|w| w.notify_template.clone()));
+            let notify_template_language = str_or(
+                "notify_template_language",
+                old.map(|w| w.notify_template_language),
+            );
             cfg.channel.whatsapp = Some(bizclaw_core::config::WhatsAppChannelConfig {
                 enabled,
                 phone_number_id: phone_val,
@@ -546,6 +839,13 @@ pub async fn update_channel(
                     .and_then(|v| v.as_str())
                     .unwrap_or("")
                     .to_string(),
+                notify_to,
+                notify_template,
+                notify_template_language: if notify_template_language.is_empty() {
+                    "en_US".into()
+                } else {
+                    notify_template_language
+                },
             });
         }
         "webhook" => {
@@ -562,15 +862,81 @@ pub async fn update_channel(
                 outbound_url,
             });
         }
+        "sms" => {
+            let old = cfg.channel.sms.clone().unwrap_or_default();
+            let str_or = |key: &str, old: String| {
+                req.get(key)
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.trim().to_string())
+                    .unwrap_or(old)
+            };
+            // Bindings as {"+1555…": "agent"} or "+1555…=agent, +1666…=other"
+            let agents = match req.get("agents") {
+                Some(serde_json::Value::Object(map)) => map
+                    .iter()
+                    .filter_map(|(number, agent)| Some((number.trim().to_string(), agent.as_str()?.trim().to_string())))
+                    .collect(),
+                Some(serde_json::Value::String(list)) => list
+                    .split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(number, agent)| (number.trim().to_string(), agent.trim().to_string()))
+                    .filter(|(number, agent)| !number.is_empty() && !agent.is_empty())
+                    .collect(),
+                _ => old.agents,
+            };
+            cfg.channel.sms = Some(bizclaw_core::config::SmsChannelConfig {
+                enabled,
+                account_sid: str_or("account_sid", old.account_sid),
+                // Masked (unchanged) tokens keep the saved one
+                auth_token: match req.get("auth_token").and_then(|v| v.as_str()) {
+                    Some(token) if !token.contains('•') => token.trim().to_string(),
+                    _ => old.auth_token,
+                },
+                from_number: str_or("from_number", old.from_number),
+                webhook_url: str_or("webhook_url", old.webhook_url),
+                agents,
+                notify_to: str_or("notify_to", old.notify_to),
+            });
+        }
+        "middleware" => {
+            let mw = &mut cfg.channel.middleware;
+            let number = |key: &str, old: u32| {
+                req.get(key)
+                    .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok())))
+                    .map(|n| n as u32)
+                    .unwrap_or(old)
+            };
+            mw.rate_limit_per_minute = number("rate_limit_per_minute", mw.rate_limit_per_minute);
+            mw.rate_limit_per_hour = number("rate_limit_per_hour", mw.rate_limit_per_hour);
+            match req.get("blocked_words") {
+                Some(serde_json::Value::String(words)) => {
+                    mw.blocked_words = words.split(',').map(|w| w.trim().to_string()).filter(|w| !w.is_empty()).collect();
+                }
+                Some(serde_json::Value::Array(words)) => {
+                    mw.blocked_words = words.iter().filter_map(|w| w.as_str()).map(String::from).collect();
+                }
+                _ => {}
+            }
+            if let Some(v) = req.get("block_profanity").and_then(|v| v.as_bool()) {
+                mw.block_profanity = v;
+            }
+            if let Some(v) = req.get("transcripts").and_then(|v| v.as_bool()) {
+                mw.transcripts = v;
+            }
+        }
         _ => {
             return Json(
                 serde_json::json!({"ok": false, "error": format!("Unknown channel: {channel_type}")}),
             );
         }
     }
+    state.channel_pipeline.set_stages(channel_middleware(&cfg));
 
     // Save to disk
-    let content = toml::to_string_pretty(&*cfg).unwrap_or_default();
+    let content = match bizclaw_security::secrets::config_toml(&cfg, &state.config_path) {
+        Ok(content) => content,
+        Err(e) => return internal_error("gateway", e),
+    };
     match std::fs::write(&state.config_path, &content) {
         Ok(_) => {
             // Also save channels as standalone JSON for platform DB sync on restart
@@ -610,6 +976,7 @@ pub async fn update_channel(
                 let sync_path = parent.join("channels_sync.json");
                 std::fs::write(&sync_path, serde_json::to_string_pretty(&channels_json).unwrap_or_default()).ok();
             }
+            super::audit::record(&state, "channel.update", channel_type, old_section, section(&cfg));
             Json(serde_json::json!({"ok": true, "message": format!("{channel_type} config saved")}))
         }
         Err(e) => internal_error("gateway", e),
@@ -649,6 +1016,15 @@ fn save_channel_instances(state: &AppState, instances: &[serde_json::Value]) {
     }
 }
 
+/// Channel types that can be connected from the channel registry, with
+/// their config fields.
+pub async fn list_channel_types(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "ok": true,
+        "types": state.channel_registry.channel_types(),
+    }))
+}
+
 /// List all channel instances (secrets masked for frontend display).
 pub async fn list_channel_instances(
     State(state): State<Arc<AppState>>,
@@ -657,10 +1033,16 @@ pub async fn list_channel_instances(
     // Mask sensitive fields before sending to frontend
     let masked: Vec<serde_json::Value> = instances.iter().map(|inst| {
         let mut masked_inst = inst.clone();
+        // Registered channel types declare their own secret fields
+        let registry_secrets: Vec<String> = inst["channel_type"]
+            .as_str()
+            .and_then(|t| state.channel_registry.get(t))
+            .map(|f| f.config_schema().into_iter().filter(|field| field.secret).map(|field| field.key).collect())
+            .unwrap_or_default();
         if let Some(cfg) = masked_inst.get_mut("config").and_then(|c| c.as_object_mut()) {
             let sensitive_keys = ["bot_token", "access_token", "webhook_secret", "smtp_pass", "app_token"];
-            for key in &sensitive_keys {
-                if let Some(val) = cfg.get(*key).and_then(|v| v.as_str())
+            for key in sensitive_keys.iter().copied().chain(registry_secrets.iter().map(String::as_str)) {
+                if let Some(val) = cfg.get(key).and_then(|v| v.as_str())
                     && !val.is_empty() {
                         cfg.insert(key.to_string(), serde_json::json!(mask_secret(val)));
                     }
@@ -710,13 +1092,17 @@ pub async fn save_channel_instance(
     });
 
     // Update existing or insert new
-    if let Some(pos) = instances.iter().position(|i| i["id"].as_str() == Some(&instance_id)) {
-        instances[pos] = instance.clone();
-    } else {
-        instances.push(instance.clone());
-    }
+    let previous = match instances.iter().position(|i| i["id"].as_str() == Some(&instance_id)) {
+        Some(pos) => Some(std::mem::replace(&mut instances[pos], instance.clone())),
+        None => {
+            instances.push(instance.clone());
+            None
+        }
+    };
 
     save_channel_instances(&state, &instances);
+    let action = if previous.is_some() { "channel_instance.update" } else { "channel_instance.create" };
+    super::audit::record(&state, action, &instance_id, previous, Some(instance.clone()));
 
     // Also sync primary (first enabled) of this type to config.toml
     // This makes the first enabled instance of each type "active"
@@ -748,8 +1134,12 @@ pub async fn save_channel_instance(
             }
             _ => {} // Other types handled as-is
         }
-        let content = toml::to_string_pretty(&*full_cfg).unwrap_or_default();
-        std::fs::write(&state.config_path, &content).ok();
+        match bizclaw_security::secrets::config_toml(&full_cfg, &state.config_path) {
+            Ok(content) => {
+                std::fs::write(&state.config_path, &content).ok();
+            }
+            Err(e) => tracing::error!("[gateway] Not saving config: {e}"),
+        }
         drop(full_cfg);
     }
 
@@ -757,24 +1147,19 @@ pub async fn save_channel_instance(
     let cfg = state.full_config.lock().unwrap();
     if let Some(parent) = state.config_path.parent() {
         let channels_json = serde_json::json!({
-            "telegram": cfg.channel.telegram.as_ref().map(|t| serde_json::json!({"enabled": t.enabled, "bot_token": t.bot_token, "allowed_chat_ids": t.allowed_chat_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")})),
-            "webhook": cfg.channel.webhook.as_ref().map(|wh| serde_json::json!({"enabled": wh.enabled, "secret": wh.secret, "outbound_url": wh.outbound_url})),
+            "telegram": cfg.channel.telegram.as_ref().map(|t| serde_json::json!({"enabled": t.enabled, "bot_token": cfg.unresolved("channel.telegram.bot_token", &t.bot_token), "allowed_chat_ids": t.allowed_chat_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")})),
+            "webhook": cfg.channel.webhook.as_ref().map(|wh| serde_json::json!({"enabled": wh.enabled, "secret": cfg.unresolved("channel.webhook.secret", &wh.secret), "outbound_url": wh.outbound_url})),
         });
         std::fs::write(parent.join("channels_sync.json"), serde_json::to_string_pretty(&channels_json).unwrap_or_default()).ok();
     }
     drop(cfg);
 
-    // Auto-connect Telegram if agent_name + bot_token provided
-    if enabled && channel_type == "telegram" && !agent_name.is_empty() {
-        let bot_token = config.get("bot_token").and_then(|v| v.as_str()).unwrap_or("").to_string();
-        if !bot_token.is_empty() {
-            let s = state.clone();
-            let an = agent_name.clone();
-            let iid = instance_id.clone();
-            tokio::spawn(async move {
-                spawn_telegram_polling(s, an, bot_token, iid).await;
-            });
-        }
+    // Connect right away when bound to an agent
+    if enabled && !agent_name.is_empty() {
+        let (s, inst) = (state.clone(), instance.clone());
+        tokio::spawn(async move {
+            connect_channel_instance(&s, &inst).await;
+        });
     }
 
     Json(serde_json::json!({
@@ -789,18 +1174,128 @@ pub async fn delete_channel_instance(
     axum::extract::Path(id): axum::extract::Path<String>,
 ) -> Json<serde_json::Value> {
     let mut instances = load_channel_instances(&state);
-    let before = instances.len();
-    instances.retain(|i| i["id"].as_str() != Some(&id));
-    if instances.len() == before {
+    let Some(pos) = instances.iter().position(|i| i["id"].as_str() == Some(&id)) else {
         return Json(serde_json::json!({"ok": false, "error": "Instance not found"}));
-    }
+    };
+    let removed = instances.remove(pos);
     save_channel_instances(&state, &instances);
+    super::audit::record(&state, "channel_instance.delete", &id, Some(removed), None);
+    if let Some(stop) = state.adapter_channels.lock().await.remove(&id) {
+        stop.notify_one();
+    }
     Json(serde_json::json!({"ok": true, "message": "Instance deleted"}))
 }
 
+/// Announce a message a channel received to workflow rules and other listeners.
+fn publish_message(state: &AppState, channel: &str, sender: &str, chat_id: &str, text: &str) {
+    state.events.publish(bizclaw_core::events::Event::MessageReceived {
+        channel: channel.to_string(),
+        sender: sender.to_string(),
+        chat_id: chat_id.to_string(),
+        text: text.to_string(),
+    });
+}
+
+/// Channel middleware stages for `config`. Transcripts go to the
+/// configured memory backend.
+pub(crate) fn channel_middleware(
+    config: &bizclaw_core::config::BizClawConfig,
+) -> Vec<Arc<dyn bizclaw_channels::middleware::Middleware>> {
+    let memory = if config.channel.middleware.transcripts {
+        match bizclaw_memory::create_memory(&config.memory, None) {
+            Ok(memory) => Some(Arc::from(memory)),
+            Err(e) => {
+                tracing::warn!("[channels] Transcripts disabled, no memory backend: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    bizclaw_channels::middleware::stages_from_config(&config.channel, memory)
+}
+
+/// Pass an incoming message through the channel middleware, then publish
+/// it. A dropped message gives `Err` with the notice to send back, if any.
+async fn accept_message(
+    state: &AppState,
+    msg: bizclaw_core::types::IncomingMessage,
+) -> std::result::Result<bizclaw_core::types::IncomingMessage, Option<String>> {
+    match state.channel_pipeline.inbound(msg).await {
+        Ok(msg) => {
+            let sender = msg.sender_name.as_deref().unwrap_or(&msg.sender_id);
+            publish_message(state, &msg.channel, sender, &msg.thread_id, &msg.content);
+            Ok(msg)
+        }
+        Err(rejected) => {
+            tracing::info!("[channels] Message dropped by {}: {}", rejected.stage, rejected.reason);
+            Err(rejected.notice)
+        }
+    }
+}
+
+/// Pass a reply through the channel middleware; `None` when it is dropped.
+async fn filter_reply(state: &AppState, channel: &str, thread_id: &str, text: String) -> Option<String> {
+    let reply = bizclaw_core::types::OutgoingMessage {
+        thread_id: thread_id.to_string(),
+        content: text,
+        thread_type: bizclaw_core::types::ThreadType::Direct,
+        reply_to: None,
+    };
+    match state.channel_pipeline.outbound(channel, reply).await {
+        Ok(reply) => Some(reply.content),
+        Err(rejected) => {
+            tracing::info!("[{channel}] Reply dropped by {}: {}", rejected.stage, rejected.reason);
+            None
+        }
+    }
+}
+
+/// Session of a chat on a channel — each chat keeps its own conversation
+/// with an agent.
+fn chat_session(channel: &str, chat: &str) -> String {
+    format!("{channel}:{chat}")
+}
+
+/// Answer a message from a channel not bound to an agent: with `[routing]`
+/// rules or a classifier set up, the agent the orchestrator routes it to;
+/// otherwise the default agent. Returns the reply and cited knowledge.
+async fn answer_unbound(
+    state: &AppState,
+    channel: &str,
+    sender: &str,
+    content: &str,
+    images: Vec<bizclaw_core::types::ImageInput>,
+) -> (String, Vec<bizclaw_agent::rag::Citation>) {
+    let session = chat_session(channel, sender);
+    {
+        let mut orch = state.orchestrator.lock().await.clone().with_session(session.as_str());
+        if orch.routing_enabled()
+            && let Some(route) = orch.route(content).await
+        {
+            tracing::info!("[{channel}] Routed to agent '{}' ({:?})", route.agent, route.via);
+            return match orch.send_to_with_images(&route.agent, content, images, None).await {
+                Ok(r) => (r, orch.last_citations().to_vec()),
+                Err(e) => (format!("Error: {e}"), vec![]),
+            };
+        }
+    }
+    let mut agent = state.agent.lock().await;
+    match agent.as_mut() {
+        Some(agent) => {
+            agent.switch_session(&session).await;
+            match agent.process_with_images(content, images, None).await {
+                Ok(r) => (r, agent.last_citations().to_vec()),
+                Err(e) => (format!("Error: {e}"), vec![]),
+            }
+        }
+        None => ("Agent not available".to_string(), vec![]),
+    }
+}
+
 /// Webhook inbound — receives external messages, routes to bound agent, replies.
 /// POST /api/v1/webhook/inbound
-/// Body: {"content": "message", "sender_id": "user1", "thread_id": "optional"}
+/// Body: {"content": "message", "sender_id": "user1", "thread_id": "optional", "channel": "optional"}
 /// Header: X-Webhook-Signature (optional HMAC-SHA256)
 pub async fn webhook_inbound(
     State(state): State<Arc<AppState>>,
@@ -854,22 +1349,46 @@ pub async fn webhook_inbound(
     }
 
     tracing::info!("[webhook] {} → agent '{}': {}", sender, agent_name, safe_truncate(&content, 100));
+    // Forwarders (e.g. a mail relay) may name the real source channel
+    let source = json["channel"].as_str().filter(|c| !c.is_empty()).unwrap_or("webhook");
+    let thread_id = json["thread_id"].as_str().unwrap_or("webhook").to_string();
+    let incoming = bizclaw_core::types::IncomingMessage {
+        channel: source.to_string(),
+        thread_id: thread_id.clone(),
+        sender_id: sender.clone(),
+        sender_name: None,
+        content,
+        thread_type: bizclaw_core::types::ThreadType::Direct,
+        timestamp: chrono::Utc::now(),
+        reply_to: None,
+        images: vec![],
+    };
+    let content = match accept_message(&state, incoming).await {
+        Ok(msg) => msg.content,
+        Err(notice) => {
+            return Json(serde_json::json!({
+                "ok": false,
+                "error": notice.unwrap_or_else(|| "Message rejected".into()),
+            }));
+        }
+    };
 
     // Route to agent
     let response = {
-        let mut orch = state.orchestrator.lock().await;
+        let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session(source, &sender));
         match orch.send_to(&agent_name, &content).await {
             Ok(r) => r,
             Err(e) => format!("⚠️ Agent error: {e}"),
         }
     };
+    let response = filter_reply(&state, source, &thread_id, response).await.unwrap_or_default();
 
     // Also forward reply to outbound URL if configured
-    if !outbound_url.is_empty() {
+    if !outbound_url.is_empty() && !response.is_empty() {
         let reply_body = serde_json::json!({
             "content": response,
             "sender_id": agent_name,
-            "thread_id": json["thread_id"].as_str().unwrap_or("webhook"),
+            "thread_id": thread_id,
             "in_reply_to": content,
         });
         let client = reqwest::Client::new();
@@ -885,6 +1404,190 @@ pub async fn webhook_inbound(
     }))
 }
 
+/// Route a Telegram message to an agent and send the reply.
+///
+/// With `show_progress`, a placeholder message is sent first and edited as
+/// tool-round progress events arrive, then replaced by the final answer.
+async fn telegram_reply(
+    state: &AppState,
+    channel: &bizclaw_channels::telegram::TelegramChannel,
+    agent_name: &str,
+    chat_id: i64,
+    text: &str,
+    images: Vec<bizclaw_core::types::ImageInput>,
+    show_progress: bool,
+) {
+    let placeholder = if show_progress {
+        let view = bizclaw_agent::progress::ProgressView::new();
+        channel.send_message_with_id(chat_id, &view.render()).await.ok()
+    } else {
+        None
+    };
+
+    let response = match placeholder {
+        Some(message_id) => {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let run = async move {
+                send_with_approval_prompts(state, channel, agent_name, chat_id, text, images, Some(&tx)).await
+                // `tx` drops here, ending the render loop
+            };
+            let render = async {
+                let mut view = bizclaw_agent::progress::ProgressView::new();
+                while let Some(event) = rx.recv().await {
+                    view.apply(&event);
+                    let _ = channel.edit_message_text(chat_id, message_id, &view.render()).await;
+                }
+            };
+            let (result, ()) = tokio::join!(run, render);
+            let response = result.unwrap_or_else(|e| format!("⚠️ Agent error: {e}"));
+            let Some(response) = filter_reply(state, "telegram", &chat_id.to_string(), response).await else {
+                return;
+            };
+            // Collapse the progress message into the final answer, unless
+            // the answer needs several messages, buttons or files
+            if bizclaw_channels::telegram::RichReply::parse(&response).fits_one_message()
+                && channel.edit_message_text(chat_id, message_id, &response).await.is_ok()
+            {
+                return;
+            }
+            response
+        }
+        None => {
+            let response = send_with_approval_prompts(state, channel, agent_name, chat_id, text, images, None)
+                .await
+                .unwrap_or_else(|e| format!("⚠️ Agent error: {e}"));
+            let Some(response) = filter_reply(state, "telegram", &chat_id.to_string(), response).await else {
+                return;
+            };
+            response
+        }
+    };
+
+    if let Err(e) = channel.send_message(chat_id, &response).await {
+        tracing::error!("[telegram] Reply failed: {e}");
+    }
+}
+
+/// Run the agent turn for a Telegram message. While it runs, the agent's
+/// tool calls waiting for approval are posted to `chat_id` with
+/// Approve/Deny buttons. Other agents run concurrently, so their requests
+/// (and those of agents it calls) stay in the dashboard's queue.
+async fn send_with_approval_prompts(
+    state: &AppState,
+    channel: &bizclaw_channels::telegram::TelegramChannel,
+    agent_name: &str,
+    chat_id: i64,
+    text: &str,
+    images: Vec<bizclaw_core::types::ImageInput>,
+    progress: Option<&bizclaw_agent::progress::ProgressSink>,
+) -> bizclaw_core::error::Result<String> {
+    let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session("telegram", &chat_id.to_string()));
+    let mut requests = state.approvals.subscribe();
+    let run = orch.send_to_with_images(agent_name, text, images, progress);
+    tokio::pin!(run);
+    loop {
+        tokio::select! {
+            result = &mut run => return result,
+            Ok(request) = requests.recv() => {
+                if request.agent != agent_name {
+                    continue;
+                }
+                let prompt = format!(
+                    "🛂 Agent '{}' wants to run {}:\n{}",
+                    request.agent,
+                    request.tool,
+                    safe_truncate(&request.arguments, 1500)
+                );
+                let approve = format!("approve:{}", request.id);
+                let deny = format!("deny:{}", request.id);
+                let buttons = [("✅ Approve", approve.as_str()), ("❌ Deny", deny.as_str())];
+                if let Err(e) = channel.send_message_with_buttons(chat_id, &prompt, &buttons).await {
+                    tracing::warn!("[telegram] Approval prompt failed: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Handle one Telegram update for `agent_name`. Approval button presses are
+/// answered right away; messages get their reply in a spawned task so
+/// polling continues, since the reply may wait on a button in this chat.
+async fn telegram_update(
+    state: &Arc<AppState>,
+    channel: &bizclaw_channels::telegram::TelegramChannel,
+    agent_name: &str,
+    update: bizclaw_channels::telegram::TelegramUpdate,
+    show_progress: bool,
+) {
+    if let Some(query) = &update.callback_query {
+        // Agent buttons are answered like messages; the rest are approvals
+        if query.button_label().is_none() {
+            telegram_approval_pressed(state, channel, query).await;
+            return;
+        }
+        let _ = channel.answer_callback_query(&query.id, "").await;
+    }
+    let Some(mut msg) = update.to_incoming() else {
+        return;
+    };
+    let chat_id: i64 = msg.thread_id.parse().unwrap_or(0);
+    msg = match accept_message(state, msg).await {
+        Ok(msg) => msg,
+        Err(notice) => {
+            if let Some(notice) = notice {
+                let _ = channel.send_message(chat_id, &notice).await;
+            }
+            return;
+        }
+    };
+    msg.images = channel.fetch_images(&update).await;
+    let sender = msg.sender_name.clone().unwrap_or_default();
+
+    tracing::info!("[telegram] {} → agent '{}': {}", sender, agent_name, safe_truncate(&msg.content, 100));
+    let _ = channel.send_typing(chat_id).await;
+
+    let (state, channel, agent_name) = (state.clone(), channel.clone(), agent_name.to_string());
+    tokio::spawn(async move {
+        telegram_reply(&state, &channel, &agent_name, chat_id, &msg.content, msg.images, show_progress).await;
+    });
+}
+
+/// Apply an Approve/Deny button press from an approval prompt.
+async fn telegram_approval_pressed(
+    state: &AppState,
+    channel: &bizclaw_channels::telegram::TelegramChannel,
+    query: &bizclaw_channels::telegram::TelegramCallbackQuery,
+) {
+    use bizclaw_agent::approval::Decision;
+    let Some((action, id)) = query.data.as_deref().and_then(|d| d.split_once(':')) else {
+        return;
+    };
+    let decision = match action {
+        "approve" => Decision::Approve,
+        "deny" => Decision::Deny(format!("denied on Telegram by {}", query.from.first_name)),
+        _ => return,
+    };
+    let note = match (decision == Decision::Approve, state.approvals.decide(id, decision)) {
+        (_, false) => "This request was already decided or has expired.",
+        (true, true) => "✅ Approved",
+        (false, true) => "❌ Denied",
+    };
+    let _ = channel.answer_callback_query(&query.id, note).await;
+    if let Some(message) = &query.message {
+        let text = format!("{}\n\n{note}", message.text.as_deref().unwrap_or_default());
+        let _ = channel.edit_message_text(message.chat.id, message.message_id, &text).await;
+    }
+}
+
+/// Tell dashboards a channel instance is connected and listening.
+fn publish_channel_connected(state: &AppState, channel: &str, instance: &str, agent: &str) {
+    state.events.publish(bizclaw_core::events::Event::ChannelConnected {
+        channel: channel.into(),
+        instance: instance.into(),
+        agent: agent.into(),
+    });
+}
+
 /// Spawn a Telegram polling loop that routes messages to a specific agent.
 /// Reused by both save_channel_instance (manual) and auto_connect_channels (startup).
 pub async fn spawn_telegram_polling(
@@ -892,6 +1595,7 @@ pub async fn spawn_telegram_polling(
     agent_name: String,
     bot_token: String,
     instance_id: String,
+    show_progress: bool,
 ) {
     // Disconnect existing bot for this agent if any
     {
@@ -919,6 +1623,7 @@ pub async fn spawn_telegram_polling(
         }
     };
     tracing::info!("[telegram] @{} connected → agent '{}' (instance: {})", bot_username, agent_name, instance_id);
+    publish_channel_connected(&state, "telegram", &instance_id, &agent_name);
 
     // Spawn polling loop
     let stop = Arc::new(tokio::sync::Notify::new());
@@ -946,27 +1651,7 @@ pub async fn spawn_telegram_polling(
                     match result {
                         Ok(updates) => {
                             for update in updates {
-                                if let Some(msg) = update.to_incoming() {
-                                    let chat_id: i64 = msg.thread_id.parse().unwrap_or(0);
-                                    let sender = msg.sender_name.clone().unwrap_or_default();
-                                    let text = msg.content.clone();
-
-                                    tracing::info!("[telegram] {} → agent '{}': {}", sender, agent_name_clone, safe_truncate(&text, 100));
-                                    let _ = channel.send_typing(chat_id).await;
-
-                                    // Route to agent
-                                    let response = {
-                                        let mut orch = state_clone.orchestrator.lock().await;
-                                        match orch.send_to(&agent_name_clone, &text).await {
-                                            Ok(r) => r,
-                                            Err(e) => format!("⚠️ Agent error: {e}"),
-                                        }
-                                    };
-
-                                    if let Err(e) = channel.send_message(chat_id, &response).await {
-                                        tracing::error!("[telegram] Reply failed: {e}");
-                                    }
-                                }
+                                telegram_update(&state_clone, &channel, &agent_name_clone, update, show_progress).await;
                             }
                         }
                         Err(e) => {
@@ -993,75 +1678,552 @@ pub async fn spawn_telegram_polling(
     }
 }
 
-/// Spawn a Discord Gateway listener that routes messages to a specific agent.
+/// Spawn a Discord Gateway listener that routes messages and slash
+/// commands to a specific agent. Mirrors [`spawn_telegram_polling`];
+/// returns the bot's username.
 pub async fn spawn_discord_gateway(
     state: Arc<AppState>,
     agent_name: String,
     bot_token: String,
     instance_id: String,
-) {
+) -> Result<String, String> {
     use futures::StreamExt;
+    use bizclaw_channels::discord::{DiscordChannel, DiscordConfig, DiscordEvent};
 
-    let discord = bizclaw_channels::discord::DiscordChannel::new(
-        bizclaw_channels::discord::DiscordConfig {
-            bot_token: bot_token.clone(),
-            enabled: true,
-            intents: 33281, // GUILDS | GUILD_MESSAGES | MESSAGE_CONTENT
-        },
-    );
-
-    // Verify bot token
-    match discord.get_me().await {
-        Ok(me) => {
-            tracing::info!("[discord] Bot {} connected → agent '{}' (instance: {})",
-                me.username, agent_name, instance_id);
-        }
-        Err(e) => {
+    // Disconnect existing bot for this agent if any
+    {
+        let mut bots = state.discord_bots.lock().await;
+        if let Some(existing) = bots.remove(&agent_name) {
+            existing.abort_handle.notify_one();
+            tracing::info!("[discord] Disconnecting existing bot for agent '{}'", agent_name);
+            tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+        }
+    }
+
+    // Verify bot token
+    let discord = DiscordChannel::new(DiscordConfig::new(bot_token.clone()));
+    let me = match discord.get_me().await {
+        Ok(me) => me,
+        Err(e) => {
             tracing::error!("[discord] Bot token invalid for instance '{}': {}", instance_id, e);
-            return;
+            return Err(format!("Invalid bot token: {e}"));
         }
+    };
+    tracing::info!("[discord] Bot {} connected → agent '{}' (instance: {})", me.username, agent_name, instance_id);
+    publish_channel_connected(&state, "discord", &instance_id, &agent_name);
+
+    // The application ID of a bot equals its user ID
+    if let Err(e) = discord
+        .register_commands(&me.id, &bizclaw_channels::discord::slash_commands())
+        .await
+    {
+        tracing::warn!("[discord] Slash command registration failed: {e}");
     }
 
-    let gateway = discord.start_gateway();
+    // Spawn gateway loop
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let stop_rx = stop.clone();
     let state_clone = state.clone();
     let agent_name_clone = agent_name.clone();
 
     tokio::spawn(async move {
-        let mut stream = gateway;
-        let reply_client = bizclaw_channels::discord::DiscordChannel::new(
-            bizclaw_channels::discord::DiscordConfig {
-                bot_token: bot_token.clone(),
-                enabled: true,
-                intents: 33281,
+        let mut stream = DiscordChannel::new(DiscordConfig::new(bot_token.clone())).start_gateway_events();
+        let reply_client = Arc::new(DiscordChannel::new(DiscordConfig::new(bot_token)));
+
+        loop {
+            tokio::select! {
+                _ = stop_rx.notified() => {
+                    // Dropping the stream stops the Gateway connection
+                    tracing::info!("[discord] Gateway stopped for agent '{}'", agent_name_clone);
+                    break;
+                }
+                event = stream.next() => {
+                    let Some(event) = event else {
+                        tracing::warn!("[discord] Gateway stream ended for agent '{}'", agent_name_clone);
+                        break;
+                    };
+                    // Each message is answered on its own task so a slow
+                    // reply doesn't hold up the Gateway
+                    let state = state_clone.clone();
+                    let client = reply_client.clone();
+                    let agent_name = agent_name_clone.clone();
+                    tokio::spawn(async move {
+                        match event {
+                            DiscordEvent::Message(incoming) => discord_message(&state, &client, &agent_name, incoming).await,
+                            DiscordEvent::Interaction(interaction) => discord_interaction(&state, &client, &agent_name, interaction).await,
+                        }
+                    });
+                }
+            }
+        }
+    });
+
+    // Save state
+    {
+        let mut bots = state.discord_bots.lock().await;
+        bots.insert(
+            agent_name.clone(),
+            super::server::DiscordBotState {
+                bot_username: me.username.clone(),
+                abort_handle: stop,
             },
         );
+    }
+    Ok(me.username)
+}
+
+/// Answer one Discord message. In servers the bot only answers when
+/// mentioned or inside a thread; a mention outside a thread opens a thread
+/// on the message and the conversation continues there.
+async fn discord_message(
+    state: &AppState,
+    client: &bizclaw_channels::discord::DiscordChannel,
+    agent_name: &str,
+    incoming: bizclaw_channels::discord::DiscordIncoming,
+) {
+    let is_guild = incoming.guild_id.is_some();
+    if incoming.message.content.is_empty() || (is_guild && !incoming.in_thread && !incoming.mentions_bot) {
+        return;
+    }
+    let channel_id = incoming.message.thread_id.clone();
+    let msg = match accept_message(state, incoming.message).await {
+        Ok(msg) => msg,
+        Err(notice) => {
+            if let Some(notice) = notice {
+                let _ = client.send_message(&channel_id, &notice).await;
+            }
+            return;
+        }
+    };
+    let text = msg.content.clone();
+    let sender = msg.sender_name.clone().unwrap_or_default();
 
-        while let Some(msg) = stream.next().await {
-            let channel_id = msg.thread_id.clone();
-            let text = msg.content.clone();
-            let sender = msg.sender_name.clone().unwrap_or_default();
+    tracing::info!("[discord] {} → agent '{}': {}", sender, agent_name, safe_truncate(&text, 100));
 
-            tracing::info!("[discord] {} → agent '{}': {}", sender, agent_name_clone, safe_truncate(&text, 100));
+    let mut reply_channel = msg.thread_id.clone();
+    if is_guild && !incoming.in_thread {
+        match client.create_thread(&msg.thread_id, &incoming.message_id, safe_truncate(&text, 80)).await {
+            Ok(thread_id) => reply_channel = thread_id,
+            Err(e) => tracing::warn!("[discord] Could not open a thread: {e}"),
+        }
+    }
 
-            // Send typing indicator
-            let _ = reply_client.send_typing_indicator(&channel_id).await;
+    // Send typing indicator
+    let _ = client.send_typing_indicator(&reply_channel).await;
 
-            // Route to agent
-            let response = {
-                let mut orch = state_clone.orchestrator.lock().await;
-                match orch.send_to(&agent_name_clone, &text).await {
-                    Ok(r) => r,
-                    Err(e) => format!("⚠️ Agent error: {e}"),
+    // Route to agent
+    let response = {
+        let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session("discord", &reply_channel));
+        match orch.send_to(agent_name, &text).await {
+            Ok(r) => r,
+            Err(e) => format!("⚠️ Agent error: {e}"),
+        }
+    };
+
+    // Reply via Discord
+    let Some(response) = filter_reply(state, "discord", &reply_channel, response).await else {
+        return;
+    };
+    if let Err(e) = client.send_message(&reply_channel, &response).await {
+        tracing::error!("[discord] Reply failed: {e}");
+    }
+}
+
+/// Answer a slash command: `/ask <prompt>` asks the agent, `/new` clears
+/// its conversation.
+async fn discord_interaction(
+    state: &AppState,
+    client: &bizclaw_channels::discord::DiscordChannel,
+    agent_name: &str,
+    interaction: bizclaw_channels::discord::DiscordInteraction,
+) {
+    // Discord wants an answer within 3 seconds; agents take longer
+    if let Err(e) = client.defer_interaction(&interaction).await {
+        tracing::error!("[discord] Interaction ack failed: {e}");
+        return;
+    }
+
+    let response = match interaction.command.as_str() {
+        "ask" => {
+            let incoming = bizclaw_core::types::IncomingMessage {
+                channel: "discord".into(),
+                thread_id: interaction.channel_id.clone(),
+                sender_id: interaction.user_id.clone(),
+                sender_name: Some(interaction.username.clone()),
+                content: interaction.option("prompt").unwrap_or_default().to_string(),
+                thread_type: bizclaw_core::types::ThreadType::Group,
+                timestamp: chrono::Utc::now(),
+                reply_to: None,
+                images: vec![],
+            };
+            match accept_message(state, incoming).await {
+                Ok(msg) => {
+                    let prompt = msg.content;
+                    tracing::info!("[discord] /ask {} → agent '{}': {}", interaction.username, agent_name, safe_truncate(&prompt, 100));
+                    let response = {
+                        let mut orch = state
+                            .orchestrator
+                            .lock()
+                            .await
+                            .clone()
+                            .with_session(chat_session("discord", &interaction.channel_id));
+                        match orch.send_to(agent_name, &prompt).await {
+                            Ok(r) => r,
+                            Err(e) => format!("⚠️ Agent error: {e}"),
+                        }
+                    };
+                    filter_reply(state, "discord", &interaction.channel_id, response).await.unwrap_or_default()
+                }
+                Err(notice) => notice.unwrap_or_else(|| "🚫 Message not accepted.".into()),
+            }
+        }
+        "new" => {
+            let orch = state.orchestrator.lock().await.clone();
+            match orch.get_agent_mut(agent_name).await {
+                Some(mut agent) => {
+                    agent.switch_session(&chat_session("discord", &interaction.channel_id)).await;
+                    agent.clear_conversation();
+                    "🆕 Started a new conversation.".to_string()
+                }
+                None => format!("⚠️ Agent '{agent_name}' not found"),
+            }
+        }
+        other => format!("Unknown command /{other}"),
+    };
+
+    if let Err(e) = client.edit_interaction_response(&interaction, &response).await {
+        tracing::error!("[discord] Interaction reply failed: {e}");
+    }
+}
+
+/// Connect a Slack workspace bot to a specific agent. With an app token
+/// (`xapp-...`) events arrive over Socket Mode; otherwise Slack posts them
+/// to `/api/v1/slack/events`, verified with the signing secret. Mirrors
+/// [`spawn_telegram_polling`]; returns the bot's username.
+pub async fn spawn_slack_bot(
+    state: Arc<AppState>,
+    agent_name: String,
+    config: &serde_json::Value,
+    instance_id: String,
+) -> Result<String, String> {
+    use futures::StreamExt;
+    use bizclaw_channels::slack::{SlackChannel, SlackConfig};
+
+    let field = |key: &str| config[key].as_str().unwrap_or("").trim().to_string();
+    let slack_config = SlackConfig {
+        bot_token: field("bot_token"),
+        app_token: field("app_token"),
+        signing_secret: field("signing_secret"),
+        ..Default::default()
+    };
+    if slack_config.bot_token.is_empty() {
+        return Err("bot_token is required".into());
+    }
+    let socket_mode = !slack_config.app_token.is_empty();
+    if !socket_mode && slack_config.signing_secret.is_empty() {
+        return Err("app_token (Socket Mode) or signing_secret (Events API) is required".into());
+    }
+    let allowed_channels: Vec<String> = field("allowed_channel_ids")
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    // Disconnect existing bot for this agent if any
+    {
+        let mut bots = state.slack_bots.lock().await;
+        if let Some(existing) = bots.remove(&agent_name) {
+            existing.abort_handle.notify_one();
+            tracing::info!("[slack] Disconnecting existing bot for agent '{}'", agent_name);
+            tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+        }
+    }
+
+    // Verify bot token
+    let client = SlackChannel::new(slack_config.clone());
+    let me = match client.auth_test().await {
+        Ok(me) => me,
+        Err(e) => {
+            tracing::error!("[slack] Bot token invalid for instance '{}': {}", instance_id, e);
+            return Err(format!("Invalid bot token: {e}"));
+        }
+    };
+    tracing::info!("[slack] {} ({}) connected → agent '{}' (instance: {}, {})",
+        me.user, me.team, agent_name, instance_id, if socket_mode { "Socket Mode" } else { "Events API" });
+    publish_channel_connected(&state, "slack", &instance_id, &agent_name);
+
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let bot = super::server::SlackBotState {
+        bot_username: me.user.clone(),
+        bot_user_id: me.user_id.clone(),
+        team_id: me.team_id.clone(),
+        team: me.team.clone(),
+        socket_mode,
+        signing_secret: slack_config.signing_secret.clone(),
+        allowed_channels,
+        client: Arc::new(client),
+        threads: Arc::new(std::sync::Mutex::new(std::collections::HashSet::new())),
+        abort_handle: stop.clone(),
+    };
+
+    if socket_mode {
+        let state_clone = state.clone();
+        let agent_name_clone = agent_name.clone();
+        let bot_clone = bot.clone();
+        tokio::spawn(async move {
+            let mut stream = SlackChannel::new(slack_config).start_socket_mode(bot_clone.bot_user_id.clone());
+            loop {
+                tokio::select! {
+                    _ = stop.notified() => {
+                        // Dropping the stream closes the socket
+                        tracing::info!("[slack] Socket Mode stopped for agent '{}'", agent_name_clone);
+                        break;
+                    }
+                    incoming = stream.next() => {
+                        let Some(incoming) = incoming else { break };
+                        let state = state_clone.clone();
+                        let bot = bot_clone.clone();
+                        let agent_name = agent_name_clone.clone();
+                        tokio::spawn(async move { slack_message(&state, &bot, &agent_name, incoming).await });
+                    }
                 }
+            }
+        });
+    }
+
+    // Save state
+    state.slack_bots.lock().await.insert(agent_name, bot);
+    Ok(me.user)
+}
+
+/// Answer one Slack message in its thread (see `SlackIncoming::should_answer`).
+async fn slack_message(
+    state: &AppState,
+    bot: &super::server::SlackBotState,
+    agent_name: &str,
+    incoming: bizclaw_channels::slack::SlackIncoming,
+) {
+    let channel_id = incoming.message.thread_id.clone();
+    let thread_key = |ts: &str| format!("{channel_id}:{ts}");
+    let in_bot_thread = incoming
+        .thread_ts
+        .as_deref()
+        .is_some_and(|ts| bot.threads.lock().unwrap().contains(&thread_key(ts)));
+    if !incoming.should_answer(in_bot_thread) {
+        return;
+    }
+    if !incoming.is_direct && !bot.allowed_channels.is_empty() && !bot.allowed_channels.contains(&channel_id) {
+        return;
+    }
+
+    // Follow-ups in this thread are answered without a mention
+    let reply_thread = incoming.reply_thread().map(String::from);
+    let text = match accept_message(state, incoming.message).await {
+        Ok(msg) => msg.content,
+        Err(notice) => {
+            if let Some(notice) = notice {
+                let _ = bot.client.post_message(&channel_id, &notice, reply_thread.as_deref()).await;
+            }
+            return;
+        }
+    };
+    tracing::info!("[slack] → agent '{}': {}", agent_name, safe_truncate(&text, 100));
+    if let Some(ts) = &reply_thread {
+        bot.threads.lock().unwrap().insert(thread_key(ts));
+    }
+
+    // Route to agent
+    let response = {
+        let chat = reply_thread.as_deref().unwrap_or(&channel_id);
+        let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session("slack", chat));
+        match orch.send_to(agent_name, &text).await {
+            Ok(r) => r,
+            Err(e) => format!("⚠️ Agent error: {e}"),
+        }
+    };
+
+    let Some(response) = filter_reply(state, "slack", &channel_id, response).await else {
+        return;
+    };
+    if let Err(e) = bot.client.post_message(&channel_id, &response, reply_thread.as_deref()).await {
+        tracing::error!("[slack] Reply failed: {e}");
+    }
+}
+
+/// Slack Events API endpoint — answers the URL verification challenge and
+/// routes message events to the agent bound to the workspace.
+/// POST /api/v1/slack/events
+pub async fn slack_events(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+    body: String,
+) -> Json<serde_json::Value> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    let timestamp = header("x-slack-request-timestamp");
+    let signature = header("x-slack-signature");
+    let Ok(payload) = serde_json::from_str::<serde_json::Value>(&body) else {
+        return Json(serde_json::json!({"ok": false, "error": "Invalid JSON"}));
+    };
+
+    // Bots whose signing secret matches the request
+    let bots: Vec<(String, super::server::SlackBotState)> = state
+        .slack_bots
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, bot)| bizclaw_channels::slack::verify_signature(&bot.signing_secret, &timestamp, &body, &signature))
+        .map(|(agent, bot)| (agent.clone(), bot.clone()))
+        .collect();
+    if bots.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Invalid Slack signature"}));
+    }
+
+    match payload["type"].as_str().unwrap_or("") {
+        "url_verification" => Json(serde_json::json!({"challenge": payload["challenge"]})),
+        "event_callback" => {
+            // Slack retries when the answer takes over 3s — the first
+            // delivery is already being handled
+            if !header("x-slack-retry-num").is_empty() {
+                return Json(serde_json::json!({"ok": true}));
+            }
+            let team_id = payload["team_id"].as_str().unwrap_or("");
+            let Some((agent_name, bot)) = bots.into_iter().find(|(_, bot)| bot.team_id == team_id) else {
+                return Json(serde_json::json!({"ok": false, "error": "No agent bound to this workspace"}));
             };
+            if let Some(incoming) = bizclaw_channels::slack::parse_incoming(&payload, &bot.bot_user_id) {
+                let state = state.clone();
+                tokio::spawn(async move { slack_message(&state, &bot, &agent_name, incoming).await });
+            }
+            Json(serde_json::json!({"ok": true}))
+        }
+        _ => Json(serde_json::json!({"ok": true})),
+    }
+}
+
+/// Start an email inbox listener that routes new mail to a specific agent
+/// and replies in the same thread. Mirrors [`spawn_telegram_polling`];
+/// returns the mailbox address.
+pub async fn spawn_email_listener(
+    state: Arc<AppState>,
+    agent_name: String,
+    config: &serde_json::Value,
+    instance_id: String,
+) -> Result<String, String> {
+    use futures::StreamExt;
+    use bizclaw_channels::email::{EmailChannel, EmailConfig};
+    use bizclaw_core::traits::Channel;
+
+    // Accepts the dashboard's smtp_user / smtp_pass names too
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| config[*k].as_str().filter(|v| !v.trim().is_empty()))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let port = |key: &str, default: u16| {
+        config[key]
+            .as_u64()
+            .map(|p| p as u16)
+            .or_else(|| config[key].as_str().and_then(|p| p.trim().parse().ok()))
+            .unwrap_or(default)
+    };
+    let defaults = EmailConfig::default();
+    let email_config = EmailConfig {
+        imap_host: field(&["imap_host"]),
+        imap_port: port("imap_port", defaults.imap_port),
+        smtp_host: field(&["smtp_host"]),
+        smtp_port: port("smtp_port", defaults.smtp_port),
+        email: field(&["email", "smtp_user"]),
+        password: field(&["password", "smtp_pass"]),
+        display_name: Some(field(&["display_name"])).filter(|n| !n.is_empty()),
+        ..defaults
+    };
+    if email_config.imap_host.is_empty() || email_config.email.is_empty() || email_config.password.is_empty() {
+        return Err("imap_host, email and password are required".into());
+    }
+
+    // Disconnect existing listener for this agent if any
+    {
+        let mut listeners = state.email_listeners.lock().await;
+        if let Some(existing) = listeners.remove(&agent_name) {
+            existing.abort_handle.notify_one();
+            tracing::info!("[email] Disconnecting existing inbox for agent '{}'", agent_name);
+        }
+    }
+
+    // Verify the login
+    let mut channel = EmailChannel::new(email_config);
+    if let Err(e) = channel.connect().await {
+        tracing::error!("[email] Login failed for instance '{}': {}", instance_id, e);
+        return Err(format!("Login failed: {e}"));
+    }
+    let address = channel.address().to_string();
+    tracing::info!("[email] {} connected → agent '{}' (instance: {})", address, agent_name, instance_id);
+    publish_channel_connected(&state, "email", &instance_id, &agent_name);
+
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let stop_rx = stop.clone();
+    let state_clone = state.clone();
+    let agent_name_clone = agent_name.clone();
+
+    tokio::spawn(async move {
+        let mut stream = channel.start_listener();
+        let channel = Arc::new(channel);
+        loop {
+            tokio::select! {
+                _ = stop_rx.notified() => {
+                    // Dropping the stream ends the IMAP session
+                    tracing::info!("[email] Listener stopped for agent '{}'", agent_name_clone);
+                    break;
+                }
+                msg = stream.next() => {
+                    let Some(msg) = msg else { break };
+                    let state = state_clone.clone();
+                    let channel = channel.clone();
+                    let agent_name = agent_name_clone.clone();
+                    tokio::spawn(async move {
+                        let sender = msg.sender_id.clone();
+                        let thread_id = msg.thread_id.clone();
+                        let msg = match accept_message(&state, msg).await {
+                            Ok(msg) => msg,
+                            Err(notice) => {
+                                if let Some(notice) = notice {
+                                    let _ = channel.reply(&thread_id, &notice).await;
+                                }
+                                return;
+                            }
+                        };
+                        tracing::info!("[email] {} → agent '{}': {}", sender, agent_name, safe_truncate(&msg.content, 100));
 
-            // Reply via Discord
-            if let Err(e) = reply_client.send_message(&channel_id, &response).await {
-                tracing::error!("[discord] Reply failed: {e}");
+                        let response = {
+                            let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session("email", &sender));
+                            match orch.send_to(&agent_name, &msg.content).await {
+                                Ok(r) => r,
+                                Err(e) => format!("⚠️ Agent error: {e}"),
+                            }
+                        };
+                        let Some(response) = filter_reply(&state, "email", &thread_id, response).await else {
+                            return;
+                        };
+                        if let Err(e) = channel.reply(&thread_id, &response).await {
+                            tracing::error!("[email] Reply to {sender} failed: {e}");
+                        }
+                    });
+                }
             }
         }
-        tracing::warn!("[discord] Gateway stream ended for agent '{}'", agent_name_clone);
     });
+
+    // Save state
+    state.email_listeners.lock().await.insert(
+        agent_name,
+        super::server::EmailListenerState {
+            address: address.clone(),
+            abort_handle: stop,
+        },
+    );
+    Ok(address)
 }
 
 /// Auto-connect all enabled channel instances on startup.
@@ -1133,46 +2295,8 @@ pub async fn auto_connect_channels(state: Arc<AppState>) {
 
     // ── Connect all enabled instances ──
     for inst in &instances {
-        let enabled = inst["enabled"].as_bool().unwrap_or(false);
-        if !enabled { continue; }
-        let channel_type = inst["channel_type"].as_str().unwrap_or("");
-        let agent_name = inst["agent_name"].as_str().unwrap_or("");
-        let instance_id = inst["id"].as_str().unwrap_or("");
-        let cfg = &inst["config"];
-
-        match channel_type {
-            "telegram" if !agent_name.is_empty() => {
-                let bot_token = cfg["bot_token"].as_str().unwrap_or("").to_string();
-                if !bot_token.is_empty() {
-                    spawn_telegram_polling(
-                        state.clone(),
-                        agent_name.to_string(),
-                        bot_token,
-                        instance_id.to_string(),
-                    ).await;
-                    connected += 1;
-                }
-            }
-            "discord" if !agent_name.is_empty() => {
-                let bot_token = cfg["bot_token"].as_str().unwrap_or("").to_string();
-                if !bot_token.is_empty() {
-                    let s = state.clone();
-                    let an = agent_name.to_string();
-                    let iid = instance_id.to_string();
-                    tokio::spawn(async move {
-                        spawn_discord_gateway(s, an, bot_token, iid).await;
-                    });
-                    connected += 1;
-                }
-            }
-            "webhook" if !agent_name.is_empty() => {
-                // Webhook is passive — inbound via /api/v1/webhook/inbound
-                // No polling needed, just log that it's ready
-                tracing::info!("[webhook] Instance '{}' bound to agent '{}' — ready for inbound at /api/v1/webhook/inbound",
-                    inst["name"].as_str().unwrap_or(instance_id), agent_name);
-                connected += 1;
-            }
-            _ => {}
+        if inst["enabled"].as_bool().unwrap_or(false) && connect_channel_instance(&state, inst).await {
+            connected += 1;
         }
     }
     if connected > 0 {
@@ -1180,54 +2304,194 @@ pub async fn auto_connect_channels(state: Arc<AppState>) {
     }
 }
 
-/// List available providers (from DB) — fully self-describing, no hardcoded metadata.
-pub async fn list_providers(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    let cfg = state.full_config.lock().unwrap();
-    let active = cfg.default_provider.clone();
-    drop(cfg);
-    
-    match state.db.list_providers(&active) {
-        Ok(providers) => {
-            let provider_json: Vec<serde_json::Value> = providers.iter().map(|p| {
-                serde_json::json!({
-                    "name": p.name,
-                    "label": p.label,
-                    "icon": p.icon,
-                    "type": p.provider_type,
-                    "status": if p.is_active { "active" } else { "available" },
-                    "models": p.models,
-                    "api_key_set": !p.api_key.is_empty(),
-                    "base_url": p.base_url,
-                    "chat_path": p.chat_path,
-                    "models_path": p.models_path,
-                    "auth_style": p.auth_style,
-                    "env_keys": p.env_keys,
-                    "enabled": p.enabled,
-                })
-            }).collect();
-            Json(serde_json::json!({ "providers": provider_json }))
-        }
-        Err(e) => Json(serde_json::json!({ "ok": false, "error": format!("DB error: {e}") })),
+/// Connect one channel instance to its bound agent. Types without a
+/// dedicated connector are built from the channel registry. Returns false
+/// when the instance can't be connected.
+pub async fn connect_channel_instance(state: &Arc<AppState>, inst: &serde_json::Value) -> bool {
+    let channel_type = inst["channel_type"].as_str().unwrap_or("").to_string();
+    let agent_name = inst["agent_name"].as_str().unwrap_or("").to_string();
+    let instance_id = inst["id"].as_str().unwrap_or("").to_string();
+    let cfg = inst["config"].clone();
+    if agent_name.is_empty() {
+        return false;
     }
-}
 
-/// Create or update a provider — accepts all self-describing fields.
-pub async fn create_provider(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let name = body["name"].as_str().unwrap_or("").trim();
-    if name.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "Provider name is required"}));
-    }
-    let label = body["label"].as_str().unwrap_or(name);
-    let icon = body["icon"].as_str().unwrap_or("🤖");
-    let provider_type = body["type"].as_str().unwrap_or("cloud");
-    let api_key = body["api_key"].as_str().unwrap_or("");
-    let base_url = body["base_url"].as_str().unwrap_or("");
-    let chat_path = body["chat_path"].as_str().unwrap_or("/chat/completions");
-    let models_path = body["models_path"].as_str().unwrap_or("/models");
-    let auth_style = body["auth_style"].as_str().unwrap_or("bearer");
+    match channel_type.as_str() {
+        "telegram" => {
+            let bot_token = cfg["bot_token"].as_str().unwrap_or("").to_string();
+            if bot_token.is_empty() {
+                return false;
+            }
+            let show_progress = cfg["show_progress"].as_bool().unwrap_or(false);
+            tokio::spawn(spawn_telegram_polling(state.clone(), agent_name, bot_token, instance_id, show_progress));
+        }
+        "discord" => {
+            let bot_token = cfg["bot_token"].as_str().unwrap_or("").to_string();
+            if bot_token.is_empty() {
+                return false;
+            }
+            let s = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = spawn_discord_gateway(s, agent_name, bot_token, instance_id).await {
+                    tracing::warn!("[discord] Not connected: {e}");
+                }
+            });
+        }
+        "slack" => {
+            let s = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = spawn_slack_bot(s, agent_name, &cfg, instance_id).await {
+                    tracing::warn!("[slack] Not connected: {e}");
+                }
+            });
+        }
+        "email" => {
+            let s = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = spawn_email_listener(s, agent_name, &cfg, instance_id).await {
+                    tracing::warn!("[email] Not connected: {e}");
+                }
+            });
+        }
+        "webhook" => {
+            // Webhook is passive — inbound via /api/v1/webhook/inbound
+            tracing::info!("[webhook] Instance '{}' bound to agent '{}' — ready for inbound at /api/v1/webhook/inbound",
+                inst["name"].as_str().unwrap_or(&instance_id), agent_name);
+        }
+        _ => {
+            if let Err(e) = spawn_registry_channel(state.clone(), agent_name, &channel_type, &cfg, instance_id).await {
+                tracing::warn!("[{channel_type}] Not connected: {e}");
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Build a channel from the registry, connect it and answer the messages it
+/// receives with the bound agent.
+pub async fn spawn_registry_channel(
+    state: Arc<AppState>,
+    agent_name: String,
+    channel_type: &str,
+    cfg: &serde_json::Value,
+    instance_id: String,
+) -> std::result::Result<(), String> {
+    let mut channel = state.channel_registry.create(channel_type, cfg).map_err(|e| e.to_string())?;
+    channel.connect().await.map_err(|e| e.to_string())?;
+    let mut incoming = channel.listen().await.map_err(|e| e.to_string())?;
+    let channel: Arc<dyn bizclaw_core::traits::Channel> = Arc::from(channel);
+    tracing::info!("[{channel_type}] Instance '{}' connected → agent '{}'", instance_id, agent_name);
+    publish_channel_connected(&state, channel_type, &instance_id, &agent_name);
+
+    // Disconnect the previous run of this instance, if any
+    let stop = Arc::new(tokio::sync::Notify::new());
+    if let Some(existing) = state.adapter_channels.lock().await.insert(instance_id.clone(), stop.clone()) {
+        existing.notify_one();
+    }
+
+    let channel_type = channel_type.to_string();
+    tokio::spawn(async move {
+        use futures::StreamExt;
+        loop {
+            let msg = tokio::select! {
+                _ = stop.notified() => {
+                    tracing::info!("[{channel_type}] Instance '{}' stopped", instance_id);
+                    break;
+                }
+                msg = incoming.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+            };
+            let (thread_id, thread_type) = (msg.thread_id.clone(), msg.thread_type.clone());
+            let msg = match accept_message(&state, msg).await {
+                Ok(msg) => msg,
+                Err(notice) => {
+                    if let Some(notice) = notice {
+                        let _ = channel.send(bizclaw_core::types::OutgoingMessage {
+                            thread_id,
+                            content: notice,
+                            thread_type,
+                            reply_to: None,
+                        }).await;
+                    }
+                    continue;
+                }
+            };
+            tracing::info!("[{channel_type}] {} → agent '{}': {}", msg.sender_id, agent_name, safe_truncate(&msg.content, 100));
+            let response = {
+                let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session(&channel_type, &thread_id));
+                match orch.send_to(&agent_name, &msg.content).await {
+                    Ok(r) => r,
+                    Err(e) => format!("⚠️ Agent error: {e}"),
+                }
+            };
+            let Some(response) = filter_reply(&state, &channel_type, &msg.thread_id, response).await else {
+                continue;
+            };
+            let reply = bizclaw_core::types::OutgoingMessage {
+                thread_id: msg.thread_id,
+                content: response,
+                thread_type: msg.thread_type,
+                reply_to: msg.reply_to,
+            };
+            if let Err(e) = channel.send(reply).await {
+                tracing::error!("[{channel_type}] Reply failed: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// List available providers (from DB) — fully self-describing, no hardcoded metadata.
+pub async fn list_providers(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let cfg = state.full_config.lock().unwrap();
+    let active = cfg.default_provider.clone();
+    drop(cfg);
+    
+    match state.db.list_providers(&active) {
+        Ok(providers) => {
+            let provider_json: Vec<serde_json::Value> = providers.iter().map(|p| {
+                serde_json::json!({
+                    "name": p.name,
+                    "label": p.label,
+                    "icon": p.icon,
+                    "type": p.provider_type,
+                    "status": if p.is_active { "active" } else { "available" },
+                    "models": p.models,
+                    "api_key_set": !p.api_key.is_empty(),
+                    "base_url": p.base_url,
+                    "chat_path": p.chat_path,
+                    "models_path": p.models_path,
+                    "auth_style": p.auth_style,
+                    "env_keys": p.env_keys,
+                    "enabled": p.enabled,
+                })
+            }).collect();
+            Json(serde_json::json!({ "providers": provider_json }))
+        }
+        Err(e) => Json(serde_json::json!({ "ok": false, "error": format!("DB error: {e}") })),
+    }
+}
+
+/// Create or update a provider — accepts all self-describing fields.
+pub async fn create_provider(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let name = body["name"].as_str().unwrap_or("").trim();
+    if name.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Provider name is required"}));
+    }
+    let label = body["label"].as_str().unwrap_or(name);
+    let icon = body["icon"].as_str().unwrap_or("🤖");
+    let provider_type = body["type"].as_str().unwrap_or("cloud");
+    let api_key = body["api_key"].as_str().unwrap_or("");
+    let base_url = body["base_url"].as_str().unwrap_or("");
+    let chat_path = body["chat_path"].as_str().unwrap_or("/chat/completions");
+    let models_path = body["models_path"].as_str().unwrap_or("/models");
+    let auth_style = body["auth_style"].as_str().unwrap_or("bearer");
     let env_keys: Vec<String> = body["env_keys"].as_array()
         .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
         .unwrap_or_default();
@@ -1235,17 +2499,28 @@ pub async fn create_provider(
         .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
         .unwrap_or_default();
 
+    let previous = state.db.get_provider(name).ok();
     match state.db.upsert_provider(
         name, label, icon, provider_type, api_key, base_url,
         chat_path, models_path, auth_style, &env_keys, &models,
     ) {
-        Ok(p) => Json(serde_json::json!({
-            "ok": true,
-            "provider": {
-                "name": p.name, "label": p.label, "icon": p.icon,
-                "type": p.provider_type, "base_url": p.base_url, "models": p.models
-            },
-        })),
+        Ok(p) => {
+            let action = if previous.is_some() { "provider.update" } else { "provider.create" };
+            super::audit::record(
+                &state,
+                action,
+                name,
+                previous.and_then(|p| serde_json::to_value(p).ok()),
+                serde_json::to_value(&p).ok(),
+            );
+            Json(serde_json::json!({
+                "ok": true,
+                "provider": {
+                    "name": p.name, "label": p.label, "icon": p.icon,
+                    "type": p.provider_type, "base_url": p.base_url, "models": p.models
+                },
+            }))
+        }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
     }
 }
@@ -1255,8 +2530,12 @@ pub async fn delete_provider(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(name): axum::extract::Path<String>,
 ) -> Json<serde_json::Value> {
+    let previous = state.db.get_provider(&name).ok().and_then(|p| serde_json::to_value(p).ok());
     match state.db.delete_provider(&name) {
-        Ok(()) => Json(serde_json::json!({"ok": true, "message": format!("Provider '{}' deleted", name)})),
+        Ok(()) => {
+            super::audit::record(&state, "provider.delete", &name, previous, None);
+            Json(serde_json::json!({"ok": true, "message": format!("Provider '{}' deleted", name)}))
+        }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
     }
 }
@@ -1270,8 +2549,13 @@ pub async fn update_provider(
     let api_key = body["api_key"].as_str();
     let base_url = body["base_url"].as_str();
     
+    let snapshot = |state: &AppState| state.db.get_provider(&name).ok().and_then(|p| serde_json::to_value(p).ok());
+    let previous = snapshot(&state);
     match state.db.update_provider_config(&name, api_key, base_url) {
-        Ok(()) => Json(serde_json::json!({"ok": true, "message": format!("Provider '{}' updated", name)})),
+        Ok(()) => {
+            super::audit::record(&state, "provider.update", &name, previous, snapshot(&state));
+            Json(serde_json::json!({"ok": true, "message": format!("Provider '{}' updated", name)}))
+        }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
     }
 }
@@ -1475,6 +2759,7 @@ pub async fn list_channels(State(state): State<Arc<AppState>>) -> Json<serde_jso
             {"name": "email", "type": "messaging", "status": if cfg.channel.email.as_ref().is_some_and(|e| e.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.email.is_some()},
             {"name": "webhook", "type": "api", "status": if cfg.channel.webhook.as_ref().is_some_and(|wh| wh.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.webhook.is_some()},
             {"name": "whatsapp", "type": "messaging", "status": if cfg.channel.whatsapp.as_ref().is_some_and(|w| w.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.whatsapp.is_some()},
+            {"name": "sms", "type": "messaging", "status": if cfg.channel.sms.as_ref().is_some_and(|s| s.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.sms.is_some()},
         ]
     }))
 }
@@ -1574,11 +2859,25 @@ pub async fn brain_scan_models(State(state): State<Arc<AppState>>) -> Json<serde
                             .map(|n| n.to_string_lossy().to_string())
                             .unwrap_or_default();
 
+                        let info = if ext == "gguf" {
+                            bizclaw_brain::gguf::inspect(&abs).ok()
+                        } else {
+                            None
+                        };
+
                         found_models.push(serde_json::json!({
                             "name": name,
                             "path": abs.display().to_string(),
                             "size": size_str,
                             "size_bytes": size_bytes,
+                            "quant": info.as_ref().and_then(|i| i.quant.clone()),
+                            "quant_mix": info.as_ref().and_then(|i| i.quant_mix.clone()),
+                            "model_name": info.as_ref().and_then(|i| i.name.clone()),
+                            "architecture": info.as_ref().and_then(|i| i.architecture.clone()),
+                            "parameters": info.as_ref().map(|i| i.parameters),
+                            "context_length": info.as_ref().and_then(|i| i.context_length),
+                            "vocab_size": info.as_ref().and_then(|i| i.vocab_size),
+                            "chat_format": info.as_ref().map(|i| i.chat_format),
                         }));
                     }
             }
@@ -1676,91 +2975,201 @@ pub async fn whatsapp_webhook(
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    // Extract messages and spawn processing in background
-    // (WhatsApp expects quick 200 OK response)
-    let entry = &body["entry"];
-    if let Some(entries) = entry.as_array() {
-        for entry in entries {
-            if let Some(changes) = entry["changes"].as_array() {
-                for change in changes {
-                    let value = &change["value"];
-                    if let Some(messages) = value["messages"].as_array() {
-                        for msg in messages {
-                            let msg_type = msg["type"].as_str().unwrap_or("");
-                            if msg_type != "text" {
-                                continue;
-                            }
+    // Spawn processing in background (WhatsApp expects quick 200 OK response)
+    for msg in bizclaw_channels::whatsapp_client::parse_webhook(&body) {
+        tracing::info!("[whatsapp] Message from {}: {}", msg.from, msg.text);
 
-                            let from = msg["from"].as_str().unwrap_or("").to_string();
-                            let text = msg["text"]["body"].as_str().unwrap_or("").to_string();
-                            let msg_id = msg["id"].as_str().unwrap_or("").to_string();
-
-                            if text.is_empty() {
-                                continue;
-                            }
+        // Get WhatsApp config for reply
+        let client = {
+            let cfg = state.full_config.lock().unwrap();
+            cfg.channel.whatsapp.as_ref().map(|wa| {
+                bizclaw_channels::whatsapp_client::WhatsAppClient::new(&wa.access_token, &wa.phone_number_id)
+            })
+        };
 
-                            tracing::info!("[whatsapp] Message from {from}: {text}");
-
-                            // Get WhatsApp config for reply
-                            let wa_config = {
-                                let cfg = state.full_config.lock().unwrap();
-                                cfg.channel.whatsapp.clone()
-                            };
-
-                            // Spawn background task for agent processing + reply
-                            let agent_lock = state.agent.clone();
-                            tokio::spawn(async move {
-                                // Process through Agent Engine
-                                let response = {
-                                    let mut agent = agent_lock.lock().await;
-                                    if let Some(agent) = agent.as_mut() {
-                                        match agent.process(&text).await {
-                                            Ok(r) => r,
-                                            Err(e) => format!("Error: {e}"),
-                                        }
-                                    } else {
-                                        "Agent not available".to_string()
-                                    }
-                                };
-
-                                // Send reply via WhatsApp Cloud API
-                                if let Some(wa_cfg) = wa_config {
-                                    let url = format!(
-                                        "https://graph.facebook.com/v21.0/{}/messages",
-                                        wa_cfg.phone_number_id
-                                    );
-                                    let reply = serde_json::json!({
-                                        "messaging_product": "whatsapp",
-                                        "to": from,
-                                        "type": "text",
-                                        "text": { "body": response },
-                                        "context": { "message_id": msg_id },
-                                    });
-                                    let client = reqwest::Client::new();
-                                    if let Err(e) = client
-                                        .post(&url)
-                                        .header(
-                                            "Authorization",
-                                            format!("Bearer {}", wa_cfg.access_token),
-                                        )
-                                        .json(&reply)
-                                        .send()
-                                        .await
-                                    {
-                                        tracing::error!("[whatsapp] Reply failed: {e}");
-                                    }
-                                }
-                            });
-                        }
+        // Spawn background task for agent processing + reply
+        let state = state.clone();
+        tokio::spawn(async move {
+            let text = match accept_message(&state, msg.to_incoming()).await {
+                Ok(incoming) => incoming.content,
+                Err(notice) => {
+                    if let (Some(notice), Some(client)) = (notice, &client) {
+                        let _ = client.send_text(&msg.from, &notice, Some(&msg.id)).await;
                     }
+                    return;
                 }
+            };
+
+            // Process through the routed or default agent
+            let (response, _) = answer_unbound(&state, "whatsapp", &msg.from, &text, vec![]).await;
+
+            // Reply via WhatsApp Cloud API — text plus any files the answer links
+            let Some(response) = filter_reply(&state, "whatsapp", &msg.from, response).await else {
+                return;
+            };
+            if let Some(client) = client
+                && let Err(e) = client.reply(&msg.from, &response, Some(&msg.id)).await
+            {
+                tracing::error!("[whatsapp] Reply failed: {e}");
             }
-        }
+        });
     }
 
     Json(serde_json::json!({"status": "ok"}))
 }
 
+/// The URLs a request may have been sent to, rebuilt from its `Host` and
+/// path: with the scheme from `X-Forwarded-Proto`, or both when unknown.
+fn request_urls(headers: &axum::http::HeaderMap, uri: &axum::http::Uri) -> Vec<String> {
+    let header = |name| headers.get(name).and_then(|v: &axum::http::HeaderValue| v.to_str().ok());
+    let Some(host) = header("x-forwarded-host").or(header("host")) else {
+        return vec![];
+    };
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let schemes = match header("x-forwarded-proto") {
+        Some(proto) => vec![proto],
+        None => vec!["https", "http"],
+    };
+    schemes.into_iter().map(|scheme| format!("{scheme}://{host}{path}")).collect()
+}
+
+/// Twilio SMS webhook (POST) — receives texts. Every request must carry a
+/// valid `X-Twilio-Signature` for `webhook_url`, or for the URL it was sent
+/// to when that is unset; texts to a Twilio number bound in `agents` go to
+/// that agent, others to the default agent.
+pub async fn sms_webhook(
+    State(state): State<Arc<AppState>>,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+    axum::Form(form): axum::Form<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    use bizclaw_channels::sms;
+
+    let twiml = || ([(axum::http::header::CONTENT_TYPE, "text/xml")], sms::EMPTY_TWIML).into_response();
+    let sms_cfg = {
+        let cfg = state.full_config.lock().unwrap();
+        match cfg.channel.sms.as_ref() {
+            Some(s) if s.enabled => s.clone(),
+            _ => return (axum::http::StatusCode::NOT_FOUND, "SMS channel not configured").into_response(),
+        }
+    };
+    let signature = headers
+        .get("x-twilio-signature")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let urls = match sms_cfg.webhook_url.as_str() {
+        "" => request_urls(&headers, &uri),
+        url => vec![url.to_string()],
+    };
+    if !urls.iter().any(|url| sms::verify_signature(&sms_cfg.auth_token, url, &form, signature)) {
+        return (axum::http::StatusCode::FORBIDDEN, "Invalid Twilio signature").into_response();
+    }
+    let Some(msg) = sms::InboundSms::parse(&form) else {
+        return twiml();
+    };
+
+    tracing::info!("[sms] Message from {}: {}", msg.from, safe_truncate(&msg.body, 100));
+    // Reply from the number that was texted
+    let from = if msg.to.is_empty() { &sms_cfg.from_number } else { &msg.to };
+    let client = sms::TwilioClient::new(&sms_cfg.account_sid, &sms_cfg.auth_token, from);
+    let bound_agent = sms_cfg.agents.get(&msg.to).cloned();
+
+    // Answer in the background — Twilio expects a quick response
+    let state = state.clone();
+    tokio::spawn(async move {
+        let incoming = match accept_message(&state, msg.to_incoming()).await {
+            Ok(incoming) => incoming,
+            Err(notice) => {
+                if let Some(notice) = notice {
+                    let _ = client.send_sms(&msg.from, &notice).await;
+                }
+                return;
+            }
+        };
+        let response = match &bound_agent {
+            Some(agent_name) => {
+                let mut orch = state.orchestrator.lock().await.clone().with_session(chat_session("sms", &msg.from));
+                match orch.send_to(agent_name, &incoming.content).await {
+                    Ok(r) => r,
+                    Err(e) => format!("⚠️ Agent error: {e}"),
+                }
+            }
+            None => answer_unbound(&state, "sms", &msg.from, &incoming.content, vec![]).await.0,
+        };
+        let Some(response) = filter_reply(&state, "sms", &msg.from, response).await else {
+            return;
+        };
+        if let Err(e) = client.send_sms(&msg.from, &response).await {
+            tracing::error!("[sms] Reply failed: {e}");
+        }
+    });
+
+    twiml()
+}
+
+/// Zalo OA webhook handler (POST) — receives follower messages. Requests
+/// must carry a valid `X-ZEvent-Signature` made with the OA secret key.
+pub async fn zalo_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+    body: String,
+) -> Json<serde_json::Value> {
+    use bizclaw_channels::zalo::client::oa;
+
+    let official = {
+        let cfg = state.full_config.lock().unwrap();
+        match cfg.channel.zalo.as_ref() {
+            Some(z) if z.enabled && z.mode == "official" => z.official.clone(),
+            _ => return Json(serde_json::json!({"ok": false, "error": "Zalo OA not configured"})),
+        }
+    };
+    let signature = headers
+        .get("x-zevent-signature")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !oa::verify_signature(&official.app_id, &official.oa_secret_key, &body, signature) {
+        return Json(serde_json::json!({"ok": false, "error": "Invalid Zalo signature"}));
+    }
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(&body) else {
+        return Json(serde_json::json!({"ok": false, "error": "Invalid JSON"}));
+    };
+    let Some(msg) = oa::parse_event(&event) else {
+        return Json(serde_json::json!({"ok": true}));
+    };
+
+    tracing::info!("[zalo-oa] Message from {}: {}", msg.user_id, msg.text);
+    let client = state
+        .zalo_oa
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| oa::ZaloOaClient::new(&official))
+        .clone();
+
+    // Answer in the background — Zalo expects a quick 200 OK
+    let state = state.clone();
+    tokio::spawn(async move {
+        let incoming = match accept_message(&state, msg.to_incoming()).await {
+            Ok(incoming) => incoming,
+            Err(notice) => {
+                if let Some(notice) = notice {
+                    let _ = client.send_text(&msg.user_id, &notice).await;
+                }
+                return;
+            }
+        };
+        let (response, _) = answer_unbound(&state, "zalo", &msg.user_id, &incoming.content, incoming.images).await;
+        let Some(response) = filter_reply(&state, "zalo", &msg.user_id, response).await else {
+            return;
+        };
+        if let Err(e) = client.reply(&msg.user_id, &response).await {
+            tracing::error!("[zalo-oa] Reply failed: {e}");
+        }
+    });
+
+    Json(serde_json::json!({"ok": true}))
+}
+
 // ---- Generic Webhook Inbound API ----
 
 /// Generic webhook inbound handler (POST).
@@ -1844,18 +3253,8 @@ pub async fn _webhook_inbound_legacy(
 
     tracing::info!("[webhook] Inbound from {sender_id} (thread={thread_id}): {content}");
 
-    // Process through Agent Engine
-    let response = {
-        let mut agent = state.agent.lock().await;
-        if let Some(agent) = agent.as_mut() {
-            match agent.process(&content).await {
-                Ok(r) => r,
-                Err(e) => format!("Error: {e}"),
-            }
-        } else {
-            "Agent not available".to_string()
-        }
-    };
+    // Process through the routed or default agent
+    let (response, citations) = answer_unbound(&state, "webhook", &sender_id, &content, vec![]).await;
 
     // Optionally send reply to outbound URL
     if !outbound_url.is_empty() {
@@ -1878,6 +3277,7 @@ pub async fn _webhook_inbound_legacy(
     Json(serde_json::json!({
         "ok": true,
         "response": response,
+        "citations": citations,
         "thread_id": thread_id,
     }))
 }
@@ -1958,14 +3358,14 @@ pub async fn scheduler_add_task(
     let task_type = body["task_type"].as_str()
         .or_else(|| body["type"].as_str())
         .unwrap_or("cron");
+    let cron_expr = body["cron"].as_str().or_else(|| body["expression"].as_str());
 
     let mut task = match task_type {
-        "cron" => {
-            let expr = body["cron"].as_str()
-                .or_else(|| body["expression"].as_str())
-                .unwrap_or("0 * * * *");
-            bizclaw_scheduler::Task::cron(name, expr, action)
+        "reboot" | "@reboot" => bizclaw_scheduler::Task::reboot(name, action),
+        "cron" if cron_expr.map(str::trim) == Some("@reboot") => {
+            bizclaw_scheduler::Task::reboot(name, action)
         }
+        "cron" => bizclaw_scheduler::Task::cron(name, cron_expr.unwrap_or("0 * * * *"), action),
         "once" => {
             let at = chrono::Utc::now()
                 + chrono::Duration::seconds(body["delay_secs"].as_i64().unwrap_or(60));
@@ -1973,7 +3373,23 @@ pub async fn scheduler_add_task(
         }
         _ => {
             let secs = body["interval_secs"].as_u64().unwrap_or(300);
-            bizclaw_scheduler::Task::interval(name, secs, action)
+            match body["anchor"].as_str().filter(|s| !s.is_empty()) {
+                Some(anchor) => match chrono::DateTime::parse_from_rfc3339(anchor) {
+                    Ok(anchor) => bizclaw_scheduler::Task::interval_anchored(
+                        name,
+                        secs,
+                        anchor.with_timezone(&chrono::Utc),
+                        action,
+                    ),
+                    Err(e) => {
+                        return Json(serde_json::json!({
+                            "ok": false,
+                            "error": format!("Invalid 'anchor' (expected RFC 3339): {e}")
+                        }));
+                    }
+                },
+                None => bizclaw_scheduler::Task::interval(name, secs, action),
+            }
         }
     };
 
@@ -2007,956 +3423,2737 @@ pub async fn scheduler_toggle_task(
     Json(serde_json::json!({"ok": true, "enabled": enabled}))
 }
 
-/// Get notification history.
-pub async fn scheduler_notifications(
+/// Execution history of a scheduled task, newest first.
+pub async fn scheduler_task_runs(
     State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
 ) -> Json<serde_json::Value> {
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(20)
+        .clamp(1, 100);
     let engine = state.scheduler.lock().await;
-    let history: Vec<_> = engine
-        .router
-        .history()
-        .iter()
-        .map(|n| {
-            serde_json::json!({
-                "title": n.title,
-                "body": n.body,
-                "source": n.source,
-                "priority": format!("{:?}", n.priority),
-                "timestamp": n.timestamp.to_rfc3339(),
-            })
-        })
-        .collect();
-    Json(serde_json::json!({"ok": true, "notifications": history}))
+    let Some(task) = engine.list_tasks().iter().find(|t| t.id == id) else {
+        return Json(serde_json::json!({"ok": false, "error": format!("Task '{id}' not found")}));
+    };
+    let runs = engine.task_runs(&id, limit);
+    Json(serde_json::json!({
+        "ok": true,
+        "task_id": id,
+        "name": task.name,
+        "runs": runs,
+        "count": runs.len(),
+    }))
 }
 
-// ---- Knowledge Base API ----
+// ---- Workflows API ----
 
-/// Search the knowledge base.
-pub async fn knowledge_search(
+/// Workflow definition from a request body: `{"toml": "..."}`,
+/// `{"definition": {...}}` or the definition itself.
+fn workflow_from_body(body: &serde_json::Value) -> Result<bizclaw_scheduler::Workflow, String> {
+    if let Some(text) = body["toml"].as_str() {
+        bizclaw_scheduler::Workflow::parse(text)
+    } else if body["definition"].is_object() {
+        bizclaw_scheduler::Workflow::from_value(body["definition"].clone())
+    } else {
+        bizclaw_scheduler::Workflow::from_value(body.clone())
+    }
+}
+
+/// List multi-step workflows.
+pub async fn workflows_list(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    let workflows = engine.db().map(|db| db.load_workflows()).unwrap_or_default();
+    Json(serde_json::json!({"ok": true, "workflows": workflows, "count": workflows.len()}))
+}
+
+/// Create a workflow from a TOML or JSON definition.
+pub async fn workflow_create(
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let query = body["query"].as_str().unwrap_or("");
-    let limit = body["limit"].as_u64().unwrap_or(5) as usize;
-
-    let kb = state.knowledge.lock().await;
-    match kb.as_ref() {
-        Some(store) => {
-            let results = store.search(query, limit);
-            let items: Vec<_> = results
-                .iter()
-                .map(|r| {
-                    serde_json::json!({
-                        "doc_name": r.doc_name,
-                        "content": r.content,
-                        "score": r.score,
-                        "chunk_idx": r.chunk_idx,
-                    })
-                })
-                .collect();
-            Json(serde_json::json!({"ok": true, "results": items, "count": items.len()}))
-        }
-        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
+    let mut workflow = match workflow_from_body(&body) {
+        Ok(w) => w,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    if workflow.id.trim().is_empty() {
+        workflow.id = format!("flow-{}", uuid::Uuid::new_v4().simple());
     }
-}
-
-/// List all knowledge documents.
-pub async fn knowledge_list_docs(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    let kb = state.knowledge.lock().await;
-    match kb.as_ref() {
-        Some(store) => {
-            let docs: Vec<_> = store.list_documents().iter().map(|(id, name, source, chunks)| {
-                serde_json::json!({"id": id, "name": name, "source": source, "chunks": chunks})
-            }).collect();
-            let (total_docs, total_chunks) = store.stats();
-            Json(serde_json::json!({
-                "ok": true, "documents": docs,
-                "total_docs": total_docs, "total_chunks": total_chunks
-            }))
-        }
-        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
+    let engine = state.scheduler.lock().await;
+    let Some(db) = engine.db() else {
+        return Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"}));
+    };
+    if db.get_workflow(&workflow.id).is_some() {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": format!("Workflow '{}' already exists — use PUT to replace it", workflow.id)
+        }));
+    }
+    match db.save_workflow(&workflow) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "id": workflow.id, "workflow": workflow})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
     }
 }
 
-/// Add a document to the knowledge base.
-pub async fn knowledge_add_doc(
+/// Get a workflow definition.
+pub async fn workflow_get(
     State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
+    axum::extract::Path(id): axum::extract::Path<String>,
 ) -> Json<serde_json::Value> {
-    let name = body["name"].as_str().unwrap_or("unnamed.txt");
-    let content = body["content"].as_str().unwrap_or("");
-    let source = body["source"].as_str().unwrap_or("api");
-
-    let kb = state.knowledge.lock().await;
-    match kb.as_ref() {
-        Some(store) => match store.add_document(name, content, source) {
-            Ok(chunks) => Json(serde_json::json!({"ok": true, "chunks": chunks})),
-            Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
-        },
-        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
+    let engine = state.scheduler.lock().await;
+    match engine.db().and_then(|db| db.get_workflow(&id)) {
+        Some(workflow) => Json(serde_json::json!({"ok": true, "workflow": workflow})),
+        None => Json(serde_json::json!({"ok": false, "error": format!("Workflow '{id}' not found")})),
     }
 }
 
-/// Remove a document from the knowledge base.
-pub async fn knowledge_remove_doc(
+/// Replace a workflow definition.
+pub async fn workflow_update(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<i64>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let kb = state.knowledge.lock().await;
-    match kb.as_ref() {
-        Some(store) => match store.remove_document(id) {
-            Ok(()) => Json(serde_json::json!({"ok": true})),
-            Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
-        },
-        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
+    let mut workflow = match workflow_from_body(&body) {
+        Ok(w) => w,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    workflow.id = id.clone();
+    let engine = state.scheduler.lock().await;
+    let Some(db) = engine.db() else {
+        return Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"}));
+    };
+    if db.get_workflow(&id).is_none() {
+        return Json(serde_json::json!({"ok": false, "error": format!("Workflow '{id}' not found")}));
+    }
+    match db.save_workflow(&workflow) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "workflow": workflow})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
     }
 }
 
-// ---- Multi-Agent Orchestrator API ----
-
-/// List all agents in the orchestrator.
-pub async fn list_agents(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    let orch = state.orchestrator.lock().await;
-    let mut agents = orch.list_agents();
+/// Delete a workflow and its run history.
+pub async fn workflow_delete(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    match engine.db().map(|db| db.delete_workflow(&id)) {
+        Some(Ok(deleted)) => Json(serde_json::json!({"ok": deleted})),
+        Some(Err(e)) => Json(serde_json::json!({"ok": false, "error": e})),
+        None => Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"})),
+    }
+}
 
-    // Load channel bindings and attach to each agent
-    let bindings_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("agent-channels.json");
-    let bindings: serde_json::Value = if bindings_path.exists() {
-        std::fs::read_to_string(&bindings_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
+/// Run a workflow now with optional `inputs`, and record the run.
+pub async fn workflow_run(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    body: Option<Json<serde_json::Value>>,
+) -> Json<serde_json::Value> {
+    let Some(workflow) = state.scheduler.lock().await.db().and_then(|db| db.get_workflow(&id)) else {
+        return Json(serde_json::json!({"ok": false, "error": format!("Workflow '{id}' not found")}));
     };
+    let inputs: std::collections::BTreeMap<String, String> = body
+        .and_then(|Json(b)| b["inputs"].as_object().cloned())
+        .map(|inputs| {
+            inputs
+                .into_iter()
+                .map(|(k, v)| (k, v.as_str().map(String::from).unwrap_or_else(|| v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
 
-    for agent in agents.iter_mut() {
-        if let Some(name) = agent["name"].as_str() {
-            let ch = bindings.get(name).cloned().unwrap_or(serde_json::json!([]));
-            agent.as_object_mut().map(|o| o.insert("channels".into(), ch));
+    // Run without holding the scheduler lock
+    let mut run = workflow_runner(state.orchestrator.clone()).run(&workflow, inputs).await;
+    state.scheduler.lock().await.finish_workflow_run(&workflow, &mut run);
+    Json(serde_json::json!({"ok": run.status == "success", "run": run}))
+}
+
+/// Workflow runner sending `agent_prompt` steps through the orchestrator.
+pub(crate) fn workflow_runner(
+    orchestrator: Arc<tokio::sync::Mutex<bizclaw_agent::orchestrator::Orchestrator>>,
+) -> bizclaw_scheduler::WorkflowRunner {
+    bizclaw_scheduler::WorkflowRunner::new().with_agent(move |agent: Option<String>, prompt: String| {
+        let orchestrator = orchestrator.clone();
+        async move {
+            let mut orch = orchestrator.lock().await.clone();
+            match agent {
+                Some(name) => orch.send_to(&name, &prompt).await,
+                None => orch.send(&prompt).await,
+            }
+            .map_err(|e| e.to_string())
         }
-    }
+    })
+}
 
-    Json(serde_json::json!({
-        "ok": true,
-        "agents": agents,
-        "total": orch.agent_count(),
-        "default": orch.default_agent_name(),
-        "recent_messages": orch.recent_messages(10),
-    }))
+/// Execution history of a workflow, newest first.
+pub async fn workflow_runs(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(20)
+        .clamp(1, 100);
+    let engine = state.scheduler.lock().await;
+    let runs = engine.db().map(|db| db.workflow_runs(&id, limit)).unwrap_or_default();
+    Json(serde_json::json!({"ok": true, "workflow_id": id, "runs": runs, "count": runs.len()}))
 }
 
+/// List enabled workflow rules (event → action automations).
+pub async fn workflow_rules_list(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    let rules = engine.db().map(|db| db.load_workflow_rules()).unwrap_or_default();
+    Json(serde_json::json!({"ok": true, "rules": rules, "count": rules.len()}))
+}
 
-/// Create a new named agent.
-pub async fn create_agent(
+/// Create a workflow rule, e.g. run a workflow when an email from a sender arrives.
+/// Body: {"name", "trigger_type", "trigger_config", "action_type", "action_config",
+/// "cooldown_secs"?, "priority"?}
+pub async fn workflow_rule_create(
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let name = body["name"].as_str().unwrap_or("agent");
-    let role = body["role"].as_str().unwrap_or("assistant");
-    let description = body["description"].as_str().unwrap_or("A helpful AI agent");
-
-    // Use current config as base, optionally override provider/model
-    let mut agent_config = state.full_config.lock().unwrap().clone();
-    if let Some(provider) = body["provider"].as_str()
-        && !provider.is_empty() {
-            agent_config.default_provider = provider.to_string();
-            agent_config.llm.provider = provider.to_string(); // sync
-        }
-    if let Some(model) = body["model"].as_str()
-        && !model.is_empty() {
-            agent_config.default_model = model.to_string();
-            agent_config.llm.model = model.to_string(); // sync
-        }
-    if let Some(persona) = body["persona"].as_str() {
-        agent_config.identity.persona = persona.to_string();
+    let name = body["name"].as_str().unwrap_or("").trim();
+    let trigger_type = body["trigger_type"].as_str().unwrap_or("");
+    let action_type = body["action_type"].as_str().unwrap_or("");
+    if name.is_empty() || trigger_type.is_empty() || action_type.is_empty() {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": "'name', 'trigger_type' and 'action_type' are required"
+        }));
     }
-    if let Some(sys_prompt) = body["system_prompt"].as_str() {
-        agent_config.identity.system_prompt = sys_prompt.to_string();
+    let mut rule = bizclaw_scheduler::persistence::WorkflowRule::new(
+        name,
+        trigger_type,
+        body["trigger_config"].clone(),
+        action_type,
+        body["action_config"].clone(),
+    );
+    rule.description = body["description"].as_str().unwrap_or("").to_string();
+    if let Some(cooldown) = body["cooldown_secs"].as_u64() {
+        rule.cooldown_secs = cooldown;
+    }
+    if let Some(priority) = body["priority"].as_i64() {
+        rule.priority = priority as i32;
     }
-    agent_config.identity.name = name.to_string();
 
-    // Critical: inject per-provider API key and base_url from DB
-    // This enables agents to use different providers (e.g. Ollama, DeepSeek)
-    // without needing the global config to match.
-    apply_provider_config_from_db(&state.db, &mut agent_config);
+    let engine = state.scheduler.lock().await;
+    match engine.db().map(|db| db.save_workflow_rule(&rule)) {
+        Some(Ok(())) => Json(serde_json::json!({"ok": true, "rule": rule})),
+        Some(Err(e)) => Json(serde_json::json!({"ok": false, "error": e})),
+        None => Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"})),
+    }
+}
 
-    // Use sync Agent::new() — MCP tools are shared at orchestrator level
-    match bizclaw_agent::Agent::new(agent_config) {
-        Ok(agent) => {
-            let provider = agent.provider_name().to_string();
-            let model = agent.model_name().to_string();
-            let system_prompt = agent.system_prompt().to_string();
-            let mut orch = state.orchestrator.lock().await;
-            orch.add_agent(name, role, description, agent);
-            // Persist to SQLite DB
-            if let Err(e) = state.db.upsert_agent(name, role, description, &provider, &model, &system_prompt) {
-                tracing::warn!("DB persist failed for agent '{}': {}", name, e);
-            }
-            // Also save to legacy agents.json for backward compatibility
-            let agents_path = state.config_path.parent()
-                .unwrap_or(std::path::Path::new("."))
-                .join("agents.json");
-            orch.save_agents_metadata(&agents_path);
-            tracing::info!("🤖 Agent '{}' created (role={})", name, role);
-            Json(serde_json::json!({
-                "ok": true,
-                "name": name,
-                "role": role,
-                "total_agents": orch.agent_count(),
-            }))
-        }
-        Err(e) => Json(serde_json::json!({
-            "ok": false,
-            "error": format!("Failed to create agent: {e}"),
-        })),
+/// Delete a workflow rule.
+pub async fn workflow_rule_delete(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    match engine.db().map(|db| db.delete_workflow_rule(&id)) {
+        Some(Ok(())) => Json(serde_json::json!({"ok": true})),
+        Some(Err(e)) => Json(serde_json::json!({"ok": false, "error": e})),
+        None => Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"})),
     }
 }
 
-/// Delete a named agent.
-pub async fn delete_agent(
+/// Notifications that failed every delivery attempt, newest first.
+pub async fn scheduler_dead_letters(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(name): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
 ) -> Json<serde_json::Value> {
-    let mut orch = state.orchestrator.lock().await;
-    let removed = orch.remove_agent(&name);
-    if removed {
-        // Delete from SQLite DB
-        if let Err(e) = state.db.delete_agent(&name) {
-            tracing::warn!("DB delete failed for agent '{}': {}", name, e);
-        }
-        // Also update legacy agents.json
-        let agents_path = state.config_path.parent()
-            .unwrap_or(std::path::Path::new("."))
-            .join("agents.json");
-        orch.save_agents_metadata(&agents_path);
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(50)
+        .clamp(1, 500);
+    let engine = state.scheduler.lock().await;
+    let dead = engine.db().map(|db| db.dead_letters(limit)).unwrap_or_default();
+    Json(serde_json::json!({"ok": true, "notifications": dead, "count": dead.len()}))
+}
+
+/// Queue a dead-lettered notification for delivery again.
+pub async fn scheduler_replay_dead_letter(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    match engine.db().map(|db| db.replay_dead_letter(id)) {
+        Some(Ok(true)) => Json(serde_json::json!({"ok": true, "id": id})),
+        Some(Ok(false)) => Json(serde_json::json!({"ok": false, "error": format!("No dead-lettered notification {id}")})),
+        Some(Err(e)) => Json(serde_json::json!({"ok": false, "error": e})),
+        None => Json(serde_json::json!({"ok": false, "error": "Scheduler database not available"})),
     }
-    Json(serde_json::json!({
-        "ok": removed,
-        "message": if removed { format!("Agent '{}' removed", name) } else { format!("Agent '{}' not found", name) },
-    }))
 }
 
-/// Update an existing agent's metadata.
-pub async fn update_agent(
+/// List Hands with their schedule and last run.
+pub async fn hands_list(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let registry = state.hands.registry().lock().await;
+    let hands: Vec<_> = registry
+        .list()
+        .iter()
+        .map(|h| {
+            serde_json::json!({
+                "name": h.manifest.name,
+                "label": h.manifest.label,
+                "icon": h.manifest.icon,
+                "description": h.manifest.description,
+                "agent": h.manifest.agent,
+                "schedule": h.manifest.schedule.to_string(),
+                "enabled": h.manifest.enabled,
+                "status": h.status,
+                "phases": h.manifest.phases.iter().map(|p| &p.name).collect::<Vec<_>>(),
+                "last_run": h.last_run,
+                "next_run": h.next_run,
+                "run_count": h.run_count,
+                "total_tokens": h.total_tokens,
+                "total_cost_usd": h.total_cost_usd,
+                "last_error": h.last_error,
+            })
+        })
+        .collect();
+    Json(serde_json::json!({"ok": true, "hands": hands, "count": hands.len()}))
+}
+
+/// Hands installed from git or zip (`bizclaw hands install`).
+pub async fn hands_installed(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let dir = state.full_config.lock().unwrap().hands.dir_path();
+    let registry = state.hands.registry().lock().await;
+    let hands: Vec<_> = bizclaw_hands::install::installed_hands(&dir)
+        .into_iter()
+        .map(|h| {
+            let enabled = registry.get(&h.name).is_some_and(|hand| hand.manifest.enabled);
+            serde_json::json!({
+                "name": h.name,
+                "label": h.label,
+                "version": h.version,
+                "description": h.description,
+                "source": h.source,
+                "enabled": enabled,
+            })
+        })
+        .collect();
+    Json(serde_json::json!({"ok": true, "hands": hands, "count": hands.len(), "dir": dir}))
+}
+
+/// Schedule a Hand, remembered in `[hands] enabled`.
+pub async fn hand_enable(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(name): axum::extract::Path<String>,
-    Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let role = body["role"].as_str();
-    let description = body["description"].as_str();
-    let provider = body["provider"].as_str();
-    let model = body["model"].as_str();
-    let system_prompt = body["system_prompt"].as_str();
+    set_hand_enabled(&state, &name, true).await
+}
 
-    // Phase 1: Update basic metadata + check if re-creation needed
-    let mut needs_recreate = false;
-    {
-        let mut orch = state.orchestrator.lock().await;
-        let updated = orch.update_agent(&name, role, description);
-        if !updated {
-            return Json(serde_json::json!({"ok": false, "message": format!("Agent '{}' not found", name)}));
-        }
-        // Only re-create if provider or model ACTUALLY CHANGED (not just present)
-        if let Some(agent) = orch.get_agent_mut(&name) {
-            let cur_provider = agent.provider_name().to_string();
-            let cur_model = agent.model_name().to_string();
-            if let Some(p) = provider
-                && !p.is_empty() && p != cur_provider { needs_recreate = true; }
-            if let Some(m) = model
-                && !m.is_empty() && m != cur_model { needs_recreate = true; }
-            // Update system prompt directly on live agent (no re-creation needed)
-            if !needs_recreate
-                && let Some(sp) = system_prompt
-                    && !sp.is_empty() && sp != agent.system_prompt() {
-                        agent.set_system_prompt(sp);
-                        tracing::info!("📝 update_agent '{}' — system_prompt updated in-place", name);
-                    }
-        }
+/// Stop scheduling a Hand; it can still be run through the API.
+pub async fn hand_disable(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    set_hand_enabled(&state, &name, false).await
+}
 
-    } // lock released here
+async fn set_hand_enabled(state: &AppState, name: &str, enabled: bool) -> Json<serde_json::Value> {
+    let mut registry = state.hands.registry().lock().await;
+    let found = if enabled { registry.enable(name) } else { registry.disable(name) };
+    if !found {
+        return Json(serde_json::json!({"ok": false, "error": format!("Hand '{name}' not found")}));
+    }
+    let mut cfg = state.full_config.lock().unwrap();
+    cfg.hands.enabled.retain(|n| n != name);
+    if enabled {
+        cfg.hands.enabled.push(name.to_string());
+    }
+    let content = match bizclaw_security::secrets::config_toml(&cfg, &state.config_path) {
+        Ok(content) => content,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Save config: {e}")})),
+    };
+    match std::fs::write(&state.config_path, &content) {
+        Ok(_) => Json(serde_json::json!({"ok": true, "name": name, "enabled": enabled})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": format!("Save config: {e}")})),
+    }
+}
 
-    // Phase 2: Re-create agent ONLY if provider/model actually changed
-    if needs_recreate {
+/// Uninstall a Hand installed from git or zip.
+pub async fn hand_uninstall(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let dir = state.full_config.lock().unwrap().hands.dir_path();
+    match state.hands.registry().lock().await.uninstall(&name, &dir) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "name": name})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
 
-        let mut agent_config = state.full_config.lock().unwrap().clone();
-        {
-            let mut orch = state.orchestrator.lock().await;
-            if let Some(agent) = orch.get_agent_mut(&name) {
-                agent_config.default_provider = agent.provider_name().to_string();
-                agent_config.default_model = agent.model_name().to_string();
-                agent_config.identity.system_prompt = agent.system_prompt().to_string();
-            }
-        } // lock released before potentially slow await
-
-        if let Some(p) = provider
-            && !p.is_empty() {
-                agent_config.default_provider = p.to_string();
-                agent_config.llm.provider = p.to_string(); // sync
-            }
-        if let Some(m) = model
-            && !m.is_empty() {
-                agent_config.default_model = m.to_string();
-                agent_config.llm.model = m.to_string(); // sync
-            }
-        if let Some(sp) = system_prompt {
-            agent_config.identity.system_prompt = sp.to_string();
-        }
-        agent_config.identity.name = name.clone();
+/// Run a Hand now, whatever its schedule.
+pub async fn hand_run(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    match state.hands.run_now(&name).await {
+        Ok(run) => Json(serde_json::json!({"ok": run.status != bizclaw_hands::HandStatus::Failed, "run": run})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
 
-        // Critical: inject per-provider API key from DB
-        apply_provider_config_from_db(&state.db, &mut agent_config);
+/// Approve the phase a Hand is waiting on and finish its run.
+pub async fn hand_approve(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    match state.hands.approve(&name).await {
+        Ok(run) => Json(serde_json::json!({"ok": run.status != bizclaw_hands::HandStatus::Failed, "run": run})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
 
-        // Re-create agent with sync Agent::new() — fast, no MCP hang
-        match bizclaw_agent::Agent::new(agent_config) {
-            Ok(new_agent) => {
-                let mut orch = state.orchestrator.lock().await;
-                let role_str = role.unwrap_or("assistant").to_string();
-                let desc_str = description.unwrap_or("").to_string();
-                let agents_list = orch.list_agents();
-                let current = agents_list.iter().find(|a| a["name"].as_str() == Some(&name));
-                let final_role = if role.is_some() { role_str.clone() } else {
-                    current.and_then(|a| a["role"].as_str()).unwrap_or("assistant").to_string()
-                };
-                let final_desc = if description.is_some() { desc_str.clone() } else {
-                    current.and_then(|a| a["description"].as_str()).unwrap_or("").to_string()
+/// Hand runner executing phases on orchestrator agents, limited to each
+/// phase's tools and the run's tool call budget, and writing phase results
+/// to the daily memory log.
+pub(crate) fn hand_runner(
+    orchestrator: Arc<tokio::sync::Mutex<bizclaw_agent::orchestrator::Orchestrator>>,
+    registry: Arc<tokio::sync::Mutex<bizclaw_hands::HandRegistry>>,
+    tick_interval_secs: u64,
+) -> bizclaw_hands::HandRunner {
+    bizclaw_hands::HandRunner::new(registry, tick_interval_secs)
+        .with_executor(move |request: bizclaw_hands::PhaseRequest| {
+            let orchestrator = orchestrator.clone();
+            async move {
+                let mut orch = orchestrator.lock().await.clone();
+                let agent = if request.agent.is_empty() {
+                    orch.default_agent_name().map(String::from).ok_or("No agent to run hands")?
+                } else {
+                    request.agent
                 };
-                orch.remove_agent(&name);
-                orch.add_agent(&name, &final_role, &final_desc, new_agent);
-                tracing::info!("🔄 Agent '{}' re-created with new provider/model", name);
+                if !orch.has_agent(&agent) {
+                    return Err(format!("Agent '{agent}' not found"));
+                }
+                let max_tool_calls = request.max_tool_calls.map(|max| max as usize);
+                let text = orch
+                    .send_to_restricted(&agent, &request.prompt, request.allowed_tools, max_tool_calls)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let tool_calls = orch.last_tool_calls() as u32;
+                Ok(bizclaw_hands::runner::PhaseOutput { text, tool_calls })
             }
-            Err(e) => {
-                tracing::warn!("⚠️ Agent '{}' re-create failed: {}", name, e);
+        })
+        .with_journal(|hand, phase| {
+            let title = format!("{} {} — {}", hand.manifest.icon, hand.manifest.label, phase.name);
+            let body = match (&phase.output, &phase.error) {
+                (_, Some(error)) => format!("❌ {error}"),
+                (Some(output), None) => output.clone(),
+                (None, None) => String::new(),
+            };
+            if let Err(e) = bizclaw_memory::brain::DailyLogManager::default().save_entry(&title, &body) {
+                tracing::warn!("⚠️ Failed to log hand phase: {e}");
             }
-        }
-    }
-
-    // Phase 3: Persist to DB — always save metadata/prompt even without re-creation
-    // Use DB record as fallback (NOT hardcoded "openai") to preserve user's provider choice
-    {
-        let db_agent = state.db.get_agent(&name).ok();
-        let orch = state.orchestrator.lock().await;
-        let agents_list = orch.list_agents();
-        let current = agents_list.iter().find(|a| a["name"].as_str() == Some(&name));
-        let final_role = current.and_then(|a| a["role"].as_str()).unwrap_or("assistant");
-        let final_desc = current.and_then(|a| a["description"].as_str()).unwrap_or("");
-        // Provider fallback chain: explicit request → DB record → orchestrator live state → ""
-        let final_provider = provider.unwrap_or_else(|| {
-            current.and_then(|a| a["provider"].as_str())
-                .filter(|p| !p.is_empty())
-                .or_else(|| db_agent.as_ref().map(|a| a.provider.as_str()).filter(|p| !p.is_empty()))
-                .unwrap_or("")
-        });
-        let final_model = model.unwrap_or_else(|| {
-            current.and_then(|a| a["model"].as_str())
-                .filter(|m| !m.is_empty())
-                .or_else(|| db_agent.as_ref().map(|a| a.model.as_str()).filter(|m| !m.is_empty()))
-                .unwrap_or("")
-        });
-        let final_prompt = system_prompt.unwrap_or_else(|| {
-            current.and_then(|a| a["system_prompt"].as_str())
-                .or_else(|| db_agent.as_ref().map(|a| a.system_prompt.as_str()))
-                .unwrap_or("")
-        });
-        if let Err(e) = state.db.upsert_agent(&name, final_role, final_desc, final_provider, final_model, final_prompt) {
-            tracing::warn!("DB persist failed for agent '{}': {}", name, e);
-        }
-    }
-
-    // Persist to legacy agents.json
-    {
-        let orch = state.orchestrator.lock().await;
-        let agents_path = state.config_path.parent()
-            .unwrap_or(std::path::Path::new("."))
-            .join("agents.json");
-        orch.save_agents_metadata(&agents_path);
-    }
+        })
+}
 
-    Json(serde_json::json!({
-        "ok": true,
-        "message": format!("Agent '{}' updated", name),
-    }))
+/// Get notification history.
+pub async fn scheduler_notifications(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let engine = state.scheduler.lock().await;
+    let history: Vec<_> = engine
+        .router
+        .history()
+        .iter()
+        .map(|n| {
+            serde_json::json!({
+                "title": n.title,
+                "body": n.body,
+                "source": n.source,
+                "priority": format!("{:?}", n.priority),
+                "timestamp": n.timestamp.to_rfc3339(),
+            })
+        })
+        .collect();
+    Json(serde_json::json!({"ok": true, "notifications": history}))
 }
 
-/// Chat with a specific agent.
-pub async fn agent_chat(
+// ---- Knowledge Base API ----
+
+/// Search the knowledge base.
+pub async fn knowledge_search(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(name): axum::extract::Path<String>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let message = body["message"].as_str().unwrap_or("");
-    if message.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "Empty message"}));
+    let query = body["query"].as_str().unwrap_or("");
+    let limit = body["limit"].as_u64().unwrap_or(5) as usize;
+    // `namespaces` (list) or `namespace`; all documents when neither is given
+    let namespaces = parse_names(&body["namespaces"])
+        .or_else(|| body["namespace"].as_str().map(|ns| vec![ns.to_string()]))
+        .unwrap_or_default();
+
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => {
+            let results = store.search_in(query, limit, &namespaces);
+            let items: Vec<_> = results
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "doc_name": r.doc_name,
+                        "content": r.content,
+                        "score": r.score,
+                        "chunk_idx": r.chunk_idx,
+                        "title": r.title,
+                        "heading_path": r.heading_path,
+                    })
+                })
+                .collect();
+            Json(serde_json::json!({"ok": true, "results": items, "count": items.len()}))
+        }
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
     }
+}
 
-    let mut orch = state.orchestrator.lock().await;
-    match orch.send_to(&name, message).await {
-        Ok(response) => Json(serde_json::json!({
-            "ok": true,
-            "agent": name,
-            "response": response,
-        })),
-        Err(e) => {
-            tracing::error!("[agent_chat:{name}] {e}");
+/// List all knowledge documents.
+pub async fn knowledge_list_docs(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => {
+            let docs: Vec<_> = store.list_documents().iter().map(|(id, name, source, chunks, namespace)| {
+                serde_json::json!({"id": id, "name": name, "source": source, "chunks": chunks, "namespace": namespace})
+            }).collect();
+            let (total_docs, total_chunks) = store.stats();
             Json(serde_json::json!({
-                "ok": false,
-                "error": "Agent processing failed",
+                "ok": true, "documents": docs,
+                "total_docs": total_docs, "total_chunks": total_chunks
             }))
         }
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
     }
 }
 
-/// Broadcast message to all agents.
-pub async fn agent_broadcast(
+/// What a knowledge import changed, for the audit log (counts, not content).
+fn import_audit(summary: &bizclaw_knowledge::ImportSummary, source: &str, namespace: &str) -> serde_json::Value {
+    serde_json::json!({
+        "source": source,
+        "namespace": namespace,
+        "added": summary.added,
+        "updated": summary.updated,
+        "removed": summary.removed,
+        "failed": summary.failed,
+        "total_chunks": summary.total_chunks,
+    })
+}
+
+/// Add a document to the knowledge base.
+pub async fn knowledge_add_doc(
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let message = body["message"].as_str().unwrap_or("");
-    if message.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "Empty message"}));
+    let name = body["name"].as_str().unwrap_or("unnamed.txt");
+    let content = body["content"].as_str().unwrap_or("");
+    let source = body["source"].as_str().unwrap_or("api");
+
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => match store.add_document(name, content, source, namespace_param(&body)) {
+            Ok(chunks) => {
+                super::audit::record(&state, "knowledge.add", name, None, Some(serde_json::json!({
+                    "name": name,
+                    "source": source,
+                    "namespace": namespace_param(&body),
+                    "bytes": content.len(),
+                    "chunks": chunks,
+                })));
+                Json(serde_json::json!({"ok": true, "chunks": chunks}))
+            }
+            Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+        },
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
     }
+}
 
-    let mut orch = state.orchestrator.lock().await;
-    let results = orch.broadcast(message).await;
-    let responses: Vec<serde_json::Value> = results
-        .into_iter()
-        .map(|(name, result)| match result {
-            Ok(response) => serde_json::json!({
-                "agent": name,
-                "ok": true,
-                "response": response,
-            }),
+/// Batch-import documents into the knowledge base in one transaction.
+///
+/// Body: `{"documents": [{"name", "content"}], "source"}` or `{"path": "/srv/wiki"}`
+/// to import a folder on the gateway host. Unchanged files are skipped.
+pub async fn knowledge_import_dir(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    let path = body["path"].as_str().unwrap_or("");
+    let mut docs: Vec<(String, String)> = body["documents"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|d| Some((d["name"].as_str()?.to_string(), d["content"].as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !path.is_empty() {
+        match importable_under_root(&state, path).await {
+            Ok((dir, files)) => docs.extend(bizclaw_knowledge::watch::read_files(&dir, files)),
             Err(e) => {
-                tracing::error!("[broadcast:{name}] {e}");
-                serde_json::json!({
-                    "agent": name,
-                    "ok": false,
-                    "error": "Agent processing failed",
-                })
+                return (axum::http::StatusCode::FORBIDDEN, Json(serde_json::json!({"ok": false, "error": e})))
+                    .into_response();
             }
-        })
-        .collect();
+        }
+    }
+    if docs.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "No documents to import"})).into_response();
+    }
 
-    Json(serde_json::json!({
-        "ok": true,
-        "responses": responses,
-    }))
+    let default_source = if path.is_empty() { "import" } else { path };
+    let source = body["source"].as_str().unwrap_or(default_source);
+
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => match store.import_documents(&docs, source, namespace_param(&body)) {
+            Ok(summary) => {
+                let change = import_audit(&summary, source, namespace_param(&body));
+                super::audit::record(&state, "knowledge.import", source, None, Some(change));
+                Json(serde_json::json!({"ok": true, "summary": summary}))
+            }
+            Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+        },
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
+    }
+    .into_response()
 }
 
-// ---- Telegram Bot ↔ Agent API ----
+/// The importable files of directory `path`, which must lie under
+/// `knowledge.import_root`. Every file must resolve inside the root and
+/// pass the security policy's `check_path`, or nothing is imported.
+async fn importable_under_root(
+    state: &AppState,
+    path: &str,
+) -> Result<(std::path::PathBuf, Vec<std::path::PathBuf>), String> {
+    use bizclaw_core::traits::SecurityPolicy;
+    use bizclaw_knowledge::watch::{expand_home, importable_files};
+
+    let (import_root, autonomy) = {
+        let cfg = state.full_config.lock().unwrap();
+        (cfg.knowledge.import_root.clone(), cfg.autonomy.clone())
+    };
+    if import_root.is_empty() {
+        return Err("Directory imports are off: set knowledge.import_root".into());
+    }
+    let root = expand_home(&import_root)
+        .canonicalize()
+        .map_err(|_| format!("Import root not found: {import_root}"))?;
+    let dir = expand_home(path)
+        .canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| format!("Not a directory: {path}"))?;
+    if !dir.starts_with(&root) {
+        return Err(format!("'{path}' is outside the import root"));
+    }
+    let policy = bizclaw_security::DefaultSecurityPolicy::new(autonomy);
+    let files = importable_files(&dir);
+    for file in &files {
+        let resolved = file.canonicalize().map_err(|e| format!("{}: {e}", file.display()))?;
+        if !resolved.starts_with(&root) || !policy.check_path(&resolved.to_string_lossy()).await.unwrap_or(false) {
+            return Err(format!("'{}' is forbidden", file.display()));
+        }
+    }
+    Ok((dir, files))
+}
 
-/// Connect a Telegram bot to a specific agent.
-/// Verifies the bot token, then spawns a polling loop.
-pub async fn connect_telegram(
+/// Crawl a URL into the knowledge base, optionally following same-site
+/// links `depth` hops deep. Pages are named by their URL.
+///
+/// With `"background": true` this runs as a `knowledge_crawl` job.
+pub async fn knowledge_crawl(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(agent_name): axum::extract::Path<String>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let bot_token = body["bot_token"].as_str().unwrap_or("").trim().to_string();
-    if bot_token.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "bot_token is required"}));
+    if body["url"].as_str().unwrap_or("").is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Missing 'url'"}));
+    }
+    if body["background"].as_bool() == Some(true) {
+        return super::jobs::start_response(&state, "knowledge_crawl", body);
     }
+    match crawl(&state, &body).await {
+        Ok(summary) => Json(serde_json::json!({"ok": true, "summary": summary})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
 
-    // Check agent exists
-    {
-        let orch = state.orchestrator.lock().await;
-        let agents = orch.list_agents();
-        if !agents
-            .iter()
-            .any(|a| a["name"].as_str() == Some(&agent_name))
-        {
-            return Json(
-                serde_json::json!({"ok": false, "error": format!("Agent '{}' not found", agent_name)}),
-            );
-        }
-    }
-
-    // Already connected? Disconnect first
-    {
-        let mut bots = state.telegram_bots.lock().await;
-        if let Some(existing) = bots.remove(&agent_name) {
-            existing.abort_handle.notify_one();
-            tracing::info!(
-                "[telegram] Disconnecting existing bot for agent '{}'",
-                agent_name
-            );
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
-    }
-
-    // Verify bot token
-    let tg = bizclaw_channels::telegram::TelegramChannel::new(
-        bizclaw_channels::telegram::TelegramConfig {
-            bot_token: bot_token.clone(),
-            enabled: true,
-            poll_interval: 1,
-        },
-    );
-    let bot_info = match tg.get_me().await {
-        Ok(me) => me,
-        Err(e) => {
-            return Json(
-                serde_json::json!({"ok": false, "error": format!("Invalid bot token: {e}")}),
-            );
-        }
+/// Crawl `body["url"]` into the knowledge base (see [`knowledge_crawl`]).
+pub(crate) async fn crawl(state: &AppState, body: &serde_json::Value) -> Result<bizclaw_knowledge::ImportSummary, String> {
+    let url = body["url"].as_str().filter(|u| !u.is_empty()).ok_or("Missing 'url'")?;
+    let defaults = bizclaw_knowledge::crawl::CrawlOptions::default();
+    let opts = bizclaw_knowledge::crawl::CrawlOptions {
+        max_depth: body["depth"].as_u64().map_or(defaults.max_depth, |d| d.min(3) as usize),
+        max_pages: body["max_pages"].as_u64().map_or(defaults.max_pages, |n| n.clamp(1, 200) as usize),
+        ..defaults
     };
-    let bot_username = bot_info.username.clone().unwrap_or_default();
-    tracing::info!(
-        "[telegram] Bot @{} verified for agent '{}'",
-        bot_username,
-        agent_name
-    );
-
-    // Spawn polling loop
-    let stop = Arc::new(tokio::sync::Notify::new());
-    let stop_rx = stop.clone();
-    let state_clone = state.clone();
-    let agent_name_clone = agent_name.clone();
-    let bot_token_clone = bot_token.clone();
-
-    tokio::spawn(async move {
-        let mut channel = bizclaw_channels::telegram::TelegramChannel::new(
-            bizclaw_channels::telegram::TelegramConfig {
-                bot_token: bot_token_clone,
-                enabled: true,
-                poll_interval: 1,
-            },
-        );
-        tracing::info!(
-            "[telegram] Polling started for agent '{}'",
-            agent_name_clone
-        );
+    let summary = bizclaw_knowledge::crawl::crawl_url(&state.knowledge, url, namespace_param(body), &opts).await?;
+    let change = import_audit(&summary, url, namespace_param(body));
+    super::audit::record(state, "knowledge.crawl", url, None, Some(change));
+    Ok(summary)
+}
 
-        loop {
-            tokio::select! {
-                _ = stop_rx.notified() => {
-                    tracing::info!("[telegram] Polling stopped for agent '{}'", agent_name_clone);
-                    break;
-                }
-                result = channel.get_updates() => {
-                    match result {
-                        Ok(updates) => {
-                            for update in updates {
-                                if let Some(msg) = update.to_incoming() {
-                                    let chat_id: i64 = msg.thread_id.parse().unwrap_or(0);
-                                    let sender = msg.sender_name.clone().unwrap_or_default();
-                                    let text = msg.content.clone();
-
-                                    tracing::info!("[telegram] {} → agent '{}': {}", sender, agent_name_clone, safe_truncate(&text, 100));
-
-                                    // Send typing indicator
-                                    let _ = channel.send_typing(chat_id).await;
-
-                                    // Route to agent
-                                    let response = {
-                                        let mut orch = state_clone.orchestrator.lock().await;
-                                        match orch.send_to(&agent_name_clone, &text).await {
-                                            Ok(r) => r,
-                                            Err(e) => format!("⚠️ Agent error: {e}"),
-                                        }
-                                    };
-
-                                    // Reply via Telegram
-                                    if let Err(e) = channel.send_message(chat_id, &response).await {
-                                        tracing::error!("[telegram] Reply failed: {e}");
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("[telegram] Polling error for '{}': {e}", agent_name_clone);
-                            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                        }
-                    }
-                }
+/// Upload documents (PDF, DOCX, HTML or text) as multipart file fields,
+/// converted to text and imported. An optional `source` field labels them.
+pub async fn knowledge_upload(
+    State(state): State<Arc<AppState>>,
+    mut multipart: axum::extract::Multipart,
+) -> Json<serde_json::Value> {
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut source = "upload".to_string();
+    let mut namespace = bizclaw_knowledge::DEFAULT_NAMESPACE.to_string();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Invalid upload: {e}")})),
+        };
+        if field.name() == Some("source") {
+            if let Ok(text) = field.text().await {
+                source = text;
             }
+            continue;
+        }
+        if field.name() == Some("namespace") {
+            if let Ok(text) = field.text().await
+                && !text.trim().is_empty()
+            {
+                namespace = text.trim().to_string();
+            }
+            continue;
+        }
+        // Keep only the base name of client-supplied paths
+        let name = field
+            .file_name()
+            .and_then(|n| n.rsplit(['/', '\\']).next())
+            .unwrap_or("upload.txt")
+            .to_string();
+        match field.bytes().await {
+            Ok(bytes) => files.push((name, bytes.to_vec())),
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Invalid upload: {e}")})),
         }
-    });
-
-    // Save state
-    {
-        let mut bots = state.telegram_bots.lock().await;
-        bots.insert(
-            agent_name.clone(),
-            super::server::TelegramBotState {
-                bot_token: bot_token.clone(),
-                bot_username: bot_username.clone(),
-                abort_handle: stop,
-            },
-        );
+    }
+    if files.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "No files uploaded"}));
     }
 
-    Json(serde_json::json!({
-        "ok": true,
-        "agent": agent_name,
-        "bot_username": bot_username,
-        "message": format!("@{} connected to agent '{}'", bot_username, agent_name),
-    }))
-}
-
-/// Disconnect Telegram bot from an agent.
-pub async fn disconnect_telegram(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(agent_name): axum::extract::Path<String>,
-) -> Json<serde_json::Value> {
-    let mut bots = state.telegram_bots.lock().await;
-    if let Some(bot) = bots.remove(&agent_name) {
-        bot.abort_handle.notify_one();
-        tracing::info!(
-            "[telegram] @{} disconnected from agent '{}'",
-            bot.bot_username,
-            agent_name
-        );
-        Json(serde_json::json!({
-            "ok": true,
-            "message": format!("@{} disconnected from agent '{}'", bot.bot_username, agent_name),
-        }))
-    } else {
-        Json(
-            serde_json::json!({"ok": false, "error": format!("No Telegram bot connected to agent '{}'", agent_name)}),
-        )
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => match store.import_files(&files, &source, &namespace) {
+            Ok(summary) => {
+                let change = import_audit(&summary, &source, &namespace);
+                super::audit::record(&state, "knowledge.upload", &source, None, Some(change));
+                Json(serde_json::json!({"ok": true, "summary": summary}))
+            }
+            Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+        },
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
     }
 }
 
-/// Get Telegram bot status for an agent.
-pub async fn telegram_status(
+/// Remove a document from the knowledge base.
+pub async fn knowledge_remove_doc(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(agent_name): axum::extract::Path<String>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Json<serde_json::Value> {
-    let bots = state.telegram_bots.lock().await;
-    if let Some(bot) = bots.get(&agent_name) {
-        Json(serde_json::json!({
-            "ok": true,
-            "connected": true,
-            "bot_username": bot.bot_username,
-            "agent": agent_name,
-        }))
-    } else {
-        Json(serde_json::json!({
-            "ok": true,
-            "connected": false,
-            "agent": agent_name,
-        }))
+    let kb = state.knowledge.lock().await;
+    match kb.as_ref() {
+        Some(store) => {
+            let doc = store.list_documents().into_iter().find(|d| d.0 == id).map(|(_, name, source, chunks, namespace)| {
+                serde_json::json!({"name": name, "source": source, "namespace": namespace, "chunks": chunks})
+            });
+            match store.remove_document(id) {
+                Ok(()) => {
+                    super::audit::record(&state, "knowledge.remove", &id.to_string(), doc, None);
+                    Json(serde_json::json!({"ok": true}))
+                }
+                Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+            }
+        }
+        None => Json(serde_json::json!({"ok": false, "error": "Knowledge base not available"})),
     }
 }
 
-// ---- Brain Workspace API ----
+// ---- Multi-Agent Orchestrator API ----
 
-/// List all brain files in the workspace.
-/// If `?tenant=slug` provided, uses per-tenant workspace.
-pub async fn brain_list_files(
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<serde_json::Value> {
-    let ws = match params.get("tenant") {
-        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
-        _ => bizclaw_memory::brain::BrainWorkspace::default(),
+/// List all agents in the orchestrator.
+pub async fn list_agents(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let orch = state.orchestrator.lock().await;
+    let mut agents = orch.list_agents();
+
+    // Load channel bindings and attach to each agent
+    let bindings_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("agent-channels.json");
+    let bindings: serde_json::Value = if bindings_path.exists() {
+        std::fs::read_to_string(&bindings_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(serde_json::json!({}))
+    } else {
+        serde_json::json!({})
     };
-    let _ = ws.initialize(); // ensure files exist
-    let files = ws.list_files();
-    let base_dir = ws.base_dir().display().to_string();
+
+    for agent in agents.iter_mut() {
+        if let Some(name) = agent["name"].as_str() {
+            let ch = bindings.get(name).cloned().unwrap_or(serde_json::json!([]));
+            agent.as_object_mut().map(|o| o.insert("channels".into(), ch));
+        }
+    }
+
     Json(serde_json::json!({
         "ok": true,
-        "files": files,
-        "base_dir": base_dir,
-        "count": files.len(),
+        "agents": agents,
+        "total": orch.agent_count(),
+        "default": orch.default_agent_name(),
+        "recent_messages": orch.recent_messages(10),
     }))
 }
 
-/// Read a specific brain file.
-pub async fn brain_read_file(
-    axum::extract::Path(filename): axum::extract::Path<String>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<serde_json::Value> {
-    let ws = match params.get("tenant") {
-        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
-        _ => bizclaw_memory::brain::BrainWorkspace::default(),
-    };
-    match ws.read_file(&filename) {
-        Some(content) => Json(serde_json::json!({
-            "ok": true, "filename": filename, "content": content, "size": content.len(),
-        })),
-        None => {
-            Json(serde_json::json!({"ok": false, "error": format!("File not found: {filename}")}))
-        }
-    }
-}
 
-/// Write (create/update) a brain file.
-pub async fn brain_write_file(
-    axum::extract::Path(filename): axum::extract::Path<String>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+/// Create a new named agent.
+pub async fn create_agent(
+    State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let ws = match params.get("tenant") {
-        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
-        _ => bizclaw_memory::brain::BrainWorkspace::default(),
-    };
-    let content = body["content"].as_str().unwrap_or("");
-    match ws.write_file(&filename, content) {
-        Ok(()) => Json(serde_json::json!({"ok": true, "message": format!("Saved: {filename}")})),
-        Err(e) => internal_error("gateway", e),
-    }
-}
+    let name = body["name"].as_str().unwrap_or("agent");
+    let role = body["role"].as_str().unwrap_or("assistant");
+    let description = body["description"].as_str().unwrap_or("A helpful AI agent");
 
-/// Delete a brain file.
-pub async fn brain_delete_file(
-    axum::extract::Path(filename): axum::extract::Path<String>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<serde_json::Value> {
-    let ws = match params.get("tenant") {
+    // Use current config as base, optionally override provider/model
+    let mut agent_config = state.full_config.lock().unwrap().clone();
+    if let Some(provider) = body["provider"].as_str()
+        && !provider.is_empty() {
+            agent_config.default_provider = provider.to_string();
+            agent_config.llm.provider = provider.to_string(); // sync
+        }
+    if let Some(model) = body["model"].as_str()
+        && !model.is_empty() {
+            agent_config.default_model = model.to_string();
+            agent_config.llm.model = model.to_string(); // sync
+        }
+    if let Some(persona) = body["persona"].as_str() {
+        agent_config.identity.persona = persona.to_string();
+    }
+    if let Some(sys_prompt) = body["system_prompt"].as_str() {
+        agent_config.identity.system_prompt = sys_prompt.to_string();
+    }
+    agent_config.identity.name = name.to_string();
+    let fallbacks = parse_fallback_providers(&body["fallback_providers"]);
+    if let Some(ref fbs) = fallbacks {
+        agent_config.llm.fallback_providers = fbs.clone();
+    }
+    let namespaces = parse_names(&body["knowledge_namespaces"]);
+    if let Some(ref ns) = namespaces {
+        agent_config.knowledge.namespaces = ns.clone();
+    }
+    let max_tool_rounds = parse_max_tool_rounds(body.get("max_tool_rounds"));
+    if let Some(Some(rounds)) = max_tool_rounds {
+        agent_config.autonomy.max_tool_rounds = rounds;
+    }
+    let mcp_prompts = parse_names(&body["mcp_prompts"]);
+
+    // Critical: inject per-provider API key and base_url from DB
+    // This enables agents to use different providers (e.g. Ollama, DeepSeek)
+    // without needing the global config to match.
+    apply_provider_config_from_db(&state.db, &mut agent_config);
+    apply_fallback_config_from_db(&state.db, &mut agent_config);
+
+    // Use sync Agent::new() — MCP tools are shared at orchestrator level
+    match bizclaw_agent::Agent::new(agent_config) {
+        Ok(mut agent) => {
+            agent.set_knowledge(state.knowledge.clone());
+            super::workspace::attach(&state.config_path, name, &mut agent);
+            agent.register_tool(Box::new(bizclaw_tools::schedule::ScheduleTool::new(
+                state.scheduler.clone(),
+                Some(name.to_string()),
+            )));
+            let provider = agent.provider_name().to_string();
+            let model = agent.model_name().to_string();
+            let system_prompt = agent.system_prompt().to_string();
+            let mut orch = {
+                let mut orch = state.orchestrator.lock().await;
+                orch.add_agent(name, role, description, agent);
+                orch.clone()
+            };
+            // Waits for busy agents, so outside the orchestrator lock
+            orch.enable_delegation().await;
+            let mut warning = None;
+            if let Some(ref prompts) = mcp_prompts
+                && let Err(e) = orch.set_agent_prompts(name, prompts).await {
+                    warning = Some(format!("MCP prompts not set: {e}"));
+                }
+            // Persist to SQLite DB
+            if let Err(e) = state.db.upsert_agent(name, role, description, &provider, &model, &system_prompt) {
+                tracing::warn!("DB persist failed for agent '{}': {}", name, e);
+            }
+            if let Some(ref fbs) = fallbacks
+                && let Err(e) = state.db.set_agent_fallbacks(name, fbs) {
+                    tracing::warn!("DB persist fallbacks failed for agent '{}': {}", name, e);
+                }
+            if let Some(ref ns) = namespaces
+                && let Err(e) = state.db.set_agent_knowledge_namespaces(name, ns) {
+                    tracing::warn!("DB persist knowledge namespaces failed for agent '{}': {}", name, e);
+                }
+            if let Some(rounds) = max_tool_rounds
+                && let Err(e) = state.db.set_agent_max_tool_rounds(name, rounds) {
+                    tracing::warn!("DB persist max tool rounds failed for agent '{}': {}", name, e);
+                }
+            if let Some(ref prompts) = mcp_prompts
+                && warning.is_none()
+                && let Err(e) = state.db.set_agent_mcp_prompts(name, prompts) {
+                    tracing::warn!("DB persist MCP prompts failed for agent '{}': {}", name, e);
+                }
+            // Also save to legacy agents.json for backward compatibility
+            let agents_path = state.config_path.parent()
+                .unwrap_or(std::path::Path::new("."))
+                .join("agents.json");
+            orch.save_agents_metadata(&agents_path);
+            tracing::info!("🤖 Agent '{}' created (role={})", name, role);
+            super::audit::record(&state, "agent.create", name, None, agent_snapshot(&state, name));
+            Json(serde_json::json!({
+                "ok": true,
+                "name": name,
+                "role": role,
+                "total_agents": orch.agent_count(),
+                "warning": warning,
+            }))
+        }
+        Err(e) => Json(serde_json::json!({
+            "ok": false,
+            "error": format!("Failed to create agent: {e}"),
+        })),
+    }
+}
+
+/// An agent's stored record, for the audit log.
+fn agent_snapshot(state: &AppState, name: &str) -> Option<serde_json::Value> {
+    state.db.get_agent(name).ok().and_then(|a| serde_json::to_value(a).ok())
+}
+
+/// Delete a named agent.
+pub async fn delete_agent(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let previous = agent_snapshot(&state, &name);
+    let mut orch = state.orchestrator.lock().await;
+    let removed = orch.remove_agent(&name);
+    if removed {
+        super::audit::record(&state, "agent.delete", &name, previous, None);
+        // Delete from SQLite DB
+        if let Err(e) = state.db.delete_agent(&name) {
+            tracing::warn!("DB delete failed for agent '{}': {}", name, e);
+        }
+        // Also update legacy agents.json
+        let agents_path = state.config_path.parent()
+            .unwrap_or(std::path::Path::new("."))
+            .join("agents.json");
+        orch.save_agents_metadata(&agents_path);
+    }
+    Json(serde_json::json!({
+        "ok": removed,
+        "message": if removed { format!("Agent '{}' removed", name) } else { format!("Agent '{}' not found", name) },
+    }))
+}
+
+/// Tool calls waiting for a human decision.
+pub async fn list_approvals(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    // Reads the queue directly: the waiting agent holds the orchestrator lock.
+    let pending = state.approvals.pending();
+    Json(serde_json::json!({
+        "ok": true,
+        "approvals": pending,
+    }))
+}
+
+/// Approve or deny a waiting tool call: `{"approve": bool, "reason"?: string}`.
+pub async fn decide_approval(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    use bizclaw_agent::approval::Decision;
+    let decision = if body["approve"].as_bool().unwrap_or(false) {
+        Decision::Approve
+    } else {
+        Decision::Deny(body["reason"].as_str().unwrap_or("denied from the dashboard").to_string())
+    };
+    let decided = state.approvals.decide(&id, decision);
+    Json(serde_json::json!({
+        "ok": decided,
+        "message": if decided { format!("Approval '{id}' decided") } else { format!("Approval '{id}' not found or already decided") },
+    }))
+}
+
+/// Update an existing agent's metadata.
+pub async fn update_agent(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let role = body["role"].as_str();
+    let description = body["description"].as_str();
+    let provider = body["provider"].as_str();
+    let model = body["model"].as_str();
+    let system_prompt = body["system_prompt"].as_str();
+    let fallbacks = parse_fallback_providers(&body["fallback_providers"]);
+    let namespaces = parse_names(&body["knowledge_namespaces"]);
+    let max_tool_rounds = parse_max_tool_rounds(body.get("max_tool_rounds"));
+    let mcp_prompts = parse_names(&body["mcp_prompts"]);
+    let previous = agent_snapshot(&state, &name);
+
+    // Phase 1: Update basic metadata + check if re-creation needed
+    let mut needs_recreate = fallbacks.is_some();
+    {
+        let mut orch = state.orchestrator.lock().await.clone();
+        let updated = orch.update_agent(&name, role, description).await;
+        if !updated {
+            return Json(serde_json::json!({"ok": false, "message": format!("Agent '{}' not found", name)}));
+        }
+        // Only re-create if provider or model ACTUALLY CHANGED (not just present)
+        if let Some(mut agent) = orch.get_agent_mut(&name).await {
+            let cur_provider = agent.provider_name().to_string();
+            let cur_model = agent.model_name().to_string();
+            if let Some(p) = provider
+                && !p.is_empty() && p != cur_provider { needs_recreate = true; }
+            if let Some(m) = model
+                && !m.is_empty() && m != cur_model { needs_recreate = true; }
+            if let Some(ref ns) = namespaces {
+                agent.set_knowledge_namespaces(ns.clone());
+            }
+            if let Some(rounds) = max_tool_rounds {
+                let global = state.full_config.lock().unwrap().autonomy.max_tool_rounds;
+                agent.set_max_tool_rounds(rounds.unwrap_or(global));
+            }
+            // Update system prompt directly on live agent (no re-creation needed)
+            if !needs_recreate
+                && let Some(sp) = system_prompt
+                    && !sp.is_empty() && sp != agent.system_prompt() {
+                        agent.set_system_prompt(sp);
+                        tracing::info!("📝 update_agent '{}' — system_prompt updated in-place", name);
+                    }
+        }
+        if let Some(ref prompts) = mcp_prompts
+            && let Err(e) = orch.set_agent_prompts(&name, prompts).await {
+                return Json(serde_json::json!({"ok": false, "message": format!("MCP prompts not set: {e}")}));
+            }
+
+    } // lock released here
+
+    // Phase 2: Re-create agent ONLY if provider/model actually changed
+    if needs_recreate {
+
+        let mut agent_config = state.full_config.lock().unwrap().clone();
+        {
+            let orch = state.orchestrator.lock().await.clone();
+            if let Some(agent) = orch.get_agent_mut(&name).await {
+                agent_config.default_provider = agent.provider_name().to_string();
+                agent_config.default_model = agent.model_name().to_string();
+                agent_config.identity.system_prompt = agent.system_prompt().to_string();
+                agent_config.knowledge.namespaces = agent.knowledge_namespaces().to_vec();
+                agent_config.autonomy.max_tool_rounds = agent.max_tool_rounds();
+            }
+        } // lock released before potentially slow await
+
+        if let Some(p) = provider
+            && !p.is_empty() {
+                agent_config.default_provider = p.to_string();
+                agent_config.llm.provider = p.to_string(); // sync
+            }
+        if let Some(m) = model
+            && !m.is_empty() {
+                agent_config.default_model = m.to_string();
+                agent_config.llm.model = m.to_string(); // sync
+            }
+        if let Some(sp) = system_prompt {
+            agent_config.identity.system_prompt = sp.to_string();
+        }
+        agent_config.identity.name = name.clone();
+        agent_config.llm.fallback_providers = match &fallbacks {
+            Some(fbs) => fbs.clone(),
+            None => state.db.get_agent(&name).map(|a| a.fallback_providers).unwrap_or_default(),
+        };
+
+        // Critical: inject per-provider API key from DB
+        apply_provider_config_from_db(&state.db, &mut agent_config);
+        apply_fallback_config_from_db(&state.db, &mut agent_config);
+
+        // Re-create agent with sync Agent::new() — fast, no MCP hang
+        match bizclaw_agent::Agent::new(agent_config) {
+            Ok(mut new_agent) => {
+                new_agent.set_knowledge(state.knowledge.clone());
+                super::workspace::attach(&state.config_path, &name, &mut new_agent);
+                new_agent.register_tool(Box::new(bizclaw_tools::schedule::ScheduleTool::new(
+                    state.scheduler.clone(),
+                    Some(name.clone()),
+                )));
+                let mut orch = state.orchestrator.lock().await;
+                let role_str = role.unwrap_or("assistant").to_string();
+                let desc_str = description.unwrap_or("").to_string();
+                let agents_list = orch.list_agents();
+                let current = agents_list.iter().find(|a| a["name"].as_str() == Some(&name));
+                let final_role = if role.is_some() { role_str.clone() } else {
+                    current.and_then(|a| a["role"].as_str()).unwrap_or("assistant").to_string()
+                };
+                let final_desc = if description.is_some() { desc_str.clone() } else {
+                    current.and_then(|a| a["description"].as_str()).unwrap_or("").to_string()
+                };
+                orch.remove_agent(&name);
+                orch.add_agent(&name, &final_role, &final_desc, new_agent);
+                let mut shared = orch.clone();
+                drop(orch);
+                // Waits for busy agents, so outside the orchestrator lock
+                shared.enable_delegation().await;
+                let prompts = match &mcp_prompts {
+                    Some(prompts) => prompts.clone(),
+                    None => state.db.get_agent(&name).map(|a| a.mcp_prompts).unwrap_or_default(),
+                };
+                if let Err(e) = shared.set_agent_prompts(&name, &prompts).await {
+                    tracing::warn!("⚠️ Agent '{}' MCP prompts not restored: {}", name, e);
+                }
+                tracing::info!("🔄 Agent '{}' re-created with new provider/model", name);
+                state.events.publish(bizclaw_core::events::Event::AgentReloaded { agent: name.clone() });
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Agent '{}' re-create failed: {}", name, e);
+            }
+        }
+    }
+
+    // Phase 3: Persist to DB — always save metadata/prompt even without re-creation
+    // Use DB record as fallback (NOT hardcoded "openai") to preserve user's provider choice
+    {
+        let db_agent = state.db.get_agent(&name).ok();
+        let orch = state.orchestrator.lock().await;
+        let agents_list = orch.list_agents();
+        let current = agents_list.iter().find(|a| a["name"].as_str() == Some(&name));
+        let final_role = current.and_then(|a| a["role"].as_str()).unwrap_or("assistant");
+        let final_desc = current.and_then(|a| a["description"].as_str()).unwrap_or("");
+        // Provider fallback chain: explicit request → DB record → orchestrator live state → ""
+        let final_provider = provider.unwrap_or_else(|| {
+            current.and_then(|a| a["provider"].as_str())
+                .filter(|p| !p.is_empty())
+                .or_else(|| db_agent.as_ref().map(|a| a.provider.as_str()).filter(|p| !p.is_empty()))
+                .unwrap_or("")
+        });
+        let final_model = model.unwrap_or_else(|| {
+            current.and_then(|a| a["model"].as_str())
+                .filter(|m| !m.is_empty())
+                .or_else(|| db_agent.as_ref().map(|a| a.model.as_str()).filter(|m| !m.is_empty()))
+                .unwrap_or("")
+        });
+        let final_prompt = system_prompt.unwrap_or_else(|| {
+            current.and_then(|a| a["system_prompt"].as_str())
+                .or_else(|| db_agent.as_ref().map(|a| a.system_prompt.as_str()))
+                .unwrap_or("")
+        });
+        if let Err(e) = state.db.upsert_agent(&name, final_role, final_desc, final_provider, final_model, final_prompt) {
+            tracing::warn!("DB persist failed for agent '{}': {}", name, e);
+        }
+        if let Some(ref fbs) = fallbacks
+            && let Err(e) = state.db.set_agent_fallbacks(&name, fbs) {
+                tracing::warn!("DB persist fallbacks failed for agent '{}': {}", name, e);
+            }
+        if let Some(ref ns) = namespaces
+            && let Err(e) = state.db.set_agent_knowledge_namespaces(&name, ns) {
+                tracing::warn!("DB persist knowledge namespaces failed for agent '{}': {}", name, e);
+            }
+        if let Some(rounds) = max_tool_rounds
+            && let Err(e) = state.db.set_agent_max_tool_rounds(&name, rounds) {
+                tracing::warn!("DB persist max tool rounds failed for agent '{}': {}", name, e);
+            }
+        if let Some(ref prompts) = mcp_prompts
+            && let Err(e) = state.db.set_agent_mcp_prompts(&name, prompts) {
+                tracing::warn!("DB persist MCP prompts failed for agent '{}': {}", name, e);
+            }
+    }
+
+    // Persist to legacy agents.json
+    {
+        let orch = state.orchestrator.lock().await;
+        let agents_path = state.config_path.parent()
+            .unwrap_or(std::path::Path::new("."))
+            .join("agents.json");
+        orch.save_agents_metadata(&agents_path);
+    }
+    super::audit::record(&state, "agent.update", &name, previous, agent_snapshot(&state, &name));
+
+    Json(serde_json::json!({
+        "ok": true,
+        "message": format!("Agent '{}' updated", name),
+    }))
+}
+
+/// Chat with a specific agent. An optional `session_id` keeps the
+/// conversation apart from other chats with the agent.
+pub async fn agent_chat(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let message = body["message"].as_str().unwrap_or("");
+    if message.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Empty message"}));
+    }
+
+    let mut orch = state.orchestrator.lock().await.clone();
+    if let Some(session) = body["session_id"].as_str() {
+        orch = orch.with_session(session);
+    }
+    match orch.send_to(&name, message).await {
+        Ok(response) => Json(serde_json::json!({
+            "ok": true,
+            "agent": name,
+            "response": response,
+            "citations": orch.last_citations(),
+        })),
+        Err(e) => {
+            tracing::error!("[agent_chat:{name}] {e}");
+            Json(serde_json::json!({
+                "ok": false,
+                "error": "Agent processing failed",
+            }))
+        }
+    }
+}
+
+/// Chat with an agent over Server-Sent Events: `progress` events with the
+/// tool-round status, `chunk` events with reply text as the provider
+/// generates it, then `done` with the final response and citations (or
+/// `error`). The agent runs in its own task, so a slow client never holds
+/// up the agent. Takes an optional `session_id` like [`agent_chat`].
+pub async fn agent_chat_stream(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    use axum::response::sse::{Event, Sse};
+
+    let message = body["message"].as_str().unwrap_or("").to_string();
+    if message.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Empty message"})).into_response();
+    }
+
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let orchestrator = state.orchestrator.clone();
+    let agent_name = name.clone();
+    let session = body["session_id"].as_str().map(str::to_string);
+    let task = tokio::spawn(async move {
+        let mut orch = orchestrator.lock().await.clone();
+        if let Some(session) = session {
+            orch = orch.with_session(session);
+        }
+        let response = orch.send_to_streaming(&agent_name, &message, &progress_tx, &chunk_tx).await?;
+        Ok::<_, bizclaw_core::error::BizClawError>((response, serde_json::json!(orch.last_citations())))
+    });
+
+    // Dropping the response stream closes `events`; the agent still finishes its turn
+    let (events, events_rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    tokio::spawn(async move {
+        let send = |kind: &str, data: serde_json::Value| {
+            events.send(Event::default().event(kind).data(data.to_string())).is_ok()
+        };
+        let mut view = bizclaw_agent::progress::ProgressView::new();
+        loop {
+            let sent = tokio::select! {
+                Some(event) = progress_rx.recv() => {
+                    view.apply(&event);
+                    send("progress", serde_json::json!({"status": view.render()}))
+                }
+                Some(chunk) = chunk_rx.recv() => send("chunk", serde_json::json!({"content": chunk})),
+                else => break,
+            };
+            if !sent {
+                return;
+            }
+        }
+        match task.await {
+            Ok(Ok((response, citations))) => {
+                send("done", serde_json::json!({"agent": name, "response": response, "citations": citations}));
+            }
+            Ok(Err(e)) => {
+                tracing::error!("[agent_chat_stream:{name}] {e}");
+                send("error", serde_json::json!({"error": "Agent processing failed"}));
+            }
+            Err(e) => {
+                send("error", serde_json::json!({"error": format!("Agent task failed: {e}")}));
+            }
+        }
+    });
+    let stream = futures::stream::unfold(events_rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok::<_, std::convert::Infallible>(event), rx))
+    });
+    Sse::new(stream).into_response()
+}
+
+/// Ask an agent for a JSON reply matching `schema`, in the conversation
+/// `session` names (the default session otherwise).
+pub async fn agent_structured(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let message = body["message"].as_str().unwrap_or("");
+    if message.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Empty message"}));
+    }
+    if !body["schema"].is_object() {
+        return Json(serde_json::json!({"ok": false, "error": "schema must be a JSON schema object"}));
+    }
+
+    let orch = state.orchestrator.lock().await.clone();
+    let Some(mut agent) = orch.get_agent_mut(&name).await else {
+        return Json(serde_json::json!({"ok": false, "error": format!("Agent '{name}' not found")}));
+    };
+    let session = body["session"].as_str().unwrap_or(bizclaw_agent::sessions::DEFAULT_SESSION);
+    agent.switch_session(session).await;
+    match agent.process_structured(message, &body["schema"]).await {
+        Ok(data) => Json(serde_json::json!({
+            "ok": true,
+            "agent": name,
+            "data": data,
+        })),
+        Err(e) => {
+            tracing::error!("[agent_structured:{name}] {e}");
+            Json(serde_json::json!({
+                "ok": false,
+                "error": e.to_string(),
+            }))
+        }
+    }
+}
+
+/// Broadcast message to all agents.
+///
+/// With `"background": true` this runs as an `agent_broadcast` job.
+pub async fn agent_broadcast(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let message = body["message"].as_str().unwrap_or("");
+    if message.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "Empty message"}));
+    }
+    if body["background"].as_bool() == Some(true) {
+        return super::jobs::start_response(&state, "agent_broadcast", body);
+    }
+
+    Json(serde_json::json!({
+        "ok": true,
+        "responses": broadcast(&state, message).await,
+    }))
+}
+
+/// Send `message` to every agent; one `{"agent", "ok", "response"|"error"}` each.
+pub(crate) async fn broadcast(state: &AppState, message: &str) -> Vec<serde_json::Value> {
+    let mut orch = state.orchestrator.lock().await.clone();
+    let results = orch.broadcast(message).await;
+    results
+        .into_iter()
+        .map(|(name, result)| match result {
+            Ok(response) => serde_json::json!({
+                "agent": name,
+                "ok": true,
+                "response": response,
+            }),
+            Err(e) => {
+                tracing::error!("[broadcast:{name}] {e}");
+                serde_json::json!({
+                    "agent": name,
+                    "ok": false,
+                    "error": "Agent processing failed",
+                })
+            }
+        })
+        .collect()
+}
+
+// ---- Telegram Bot ↔ Agent API ----
+
+/// Connect a Telegram bot to a specific agent.
+/// Verifies the bot token, then spawns a polling loop.
+pub async fn connect_telegram(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let bot_token = body["bot_token"].as_str().unwrap_or("").trim().to_string();
+    if bot_token.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "bot_token is required"}));
+    }
+    let show_progress = body["show_progress"].as_bool().unwrap_or(false);
+
+    // Check agent exists
+    {
+        let orch = state.orchestrator.lock().await;
+        let agents = orch.list_agents();
+        if !agents
+            .iter()
+            .any(|a| a["name"].as_str() == Some(&agent_name))
+        {
+            return Json(
+                serde_json::json!({"ok": false, "error": format!("Agent '{}' not found", agent_name)}),
+            );
+        }
+    }
+
+    // Already connected? Disconnect first
+    {
+        let mut bots = state.telegram_bots.lock().await;
+        if let Some(existing) = bots.remove(&agent_name) {
+            existing.abort_handle.notify_one();
+            tracing::info!(
+                "[telegram] Disconnecting existing bot for agent '{}'",
+                agent_name
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        }
+    }
+
+    // Verify bot token
+    let tg = bizclaw_channels::telegram::TelegramChannel::new(
+        bizclaw_channels::telegram::TelegramConfig {
+            bot_token: bot_token.clone(),
+            enabled: true,
+            poll_interval: 1,
+        },
+    );
+    let bot_info = match tg.get_me().await {
+        Ok(me) => me,
+        Err(e) => {
+            return Json(
+                serde_json::json!({"ok": false, "error": format!("Invalid bot token: {e}")}),
+            );
+        }
+    };
+    let bot_username = bot_info.username.clone().unwrap_or_default();
+    tracing::info!(
+        "[telegram] Bot @{} verified for agent '{}'",
+        bot_username,
+        agent_name
+    );
+
+    // Spawn polling loop
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let stop_rx = stop.clone();
+    let state_clone = state.clone();
+    let agent_name_clone = agent_name.clone();
+    let bot_token_clone = bot_token.clone();
+
+    tokio::spawn(async move {
+        let mut channel = bizclaw_channels::telegram::TelegramChannel::new(
+            bizclaw_channels::telegram::TelegramConfig {
+                bot_token: bot_token_clone,
+                enabled: true,
+                poll_interval: 1,
+            },
+        );
+        tracing::info!(
+            "[telegram] Polling started for agent '{}'",
+            agent_name_clone
+        );
+
+        loop {
+            tokio::select! {
+                _ = stop_rx.notified() => {
+                    tracing::info!("[telegram] Polling stopped for agent '{}'", agent_name_clone);
+                    break;
+                }
+                result = channel.get_updates() => {
+                    match result {
+                        Ok(updates) => {
+                            for update in updates {
+                                telegram_update(&state_clone, &channel, &agent_name_clone, update, show_progress).await;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("[telegram] Polling error for '{}': {e}", agent_name_clone);
+                            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    // Save state
+    {
+        let mut bots = state.telegram_bots.lock().await;
+        bots.insert(
+            agent_name.clone(),
+            super::server::TelegramBotState {
+                bot_token: bot_token.clone(),
+                bot_username: bot_username.clone(),
+                abort_handle: stop,
+            },
+        );
+    }
+
+    Json(serde_json::json!({
+        "ok": true,
+        "agent": agent_name,
+        "bot_username": bot_username,
+        "message": format!("@{} connected to agent '{}'", bot_username, agent_name),
+    }))
+}
+
+/// Disconnect Telegram bot from an agent.
+pub async fn disconnect_telegram(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let mut bots = state.telegram_bots.lock().await;
+    if let Some(bot) = bots.remove(&agent_name) {
+        bot.abort_handle.notify_one();
+        tracing::info!(
+            "[telegram] @{} disconnected from agent '{}'",
+            bot.bot_username,
+            agent_name
+        );
+        Json(serde_json::json!({
+            "ok": true,
+            "message": format!("@{} disconnected from agent '{}'", bot.bot_username, agent_name),
+        }))
+    } else {
+        Json(
+            serde_json::json!({"ok": false, "error": format!("No Telegram bot connected to agent '{}'", agent_name)}),
+        )
+    }
+}
+
+/// Get Telegram bot status for an agent.
+pub async fn telegram_status(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let bots = state.telegram_bots.lock().await;
+    if let Some(bot) = bots.get(&agent_name) {
+        Json(serde_json::json!({
+            "ok": true,
+            "connected": true,
+            "bot_username": bot.bot_username,
+            "agent": agent_name,
+        }))
+    } else {
+        Json(serde_json::json!({
+            "ok": true,
+            "connected": false,
+            "agent": agent_name,
+        }))
+    }
+}
+
+// ---- Discord Bot ↔ Agent API ----
+
+/// Connect a Discord bot to a specific agent.
+/// Verifies the bot token, registers slash commands, then opens the Gateway.
+pub async fn connect_discord(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let bot_token = body["bot_token"].as_str().unwrap_or("").trim().to_string();
+    if bot_token.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "bot_token is required"}));
+    }
+
+    // Check agent exists
+    {
+        let orch = state.orchestrator.lock().await;
+        if !orch
+            .list_agents()
+            .iter()
+            .any(|a| a["name"].as_str() == Some(&agent_name))
+        {
+            return Json(
+                serde_json::json!({"ok": false, "error": format!("Agent '{}' not found", agent_name)}),
+            );
+        }
+    }
+
+    match spawn_discord_gateway(state.clone(), agent_name.clone(), bot_token, format!("api_{agent_name}")).await {
+        Ok(bot_username) => Json(serde_json::json!({
+            "ok": true,
+            "agent": agent_name,
+            "bot_username": bot_username,
+            "message": format!("{} connected to agent '{}'", bot_username, agent_name),
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// Disconnect Discord bot from an agent.
+pub async fn disconnect_discord(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let mut bots = state.discord_bots.lock().await;
+    if let Some(bot) = bots.remove(&agent_name) {
+        bot.abort_handle.notify_one();
+        tracing::info!("[discord] {} disconnected from agent '{}'", bot.bot_username, agent_name);
+        Json(serde_json::json!({
+            "ok": true,
+            "message": format!("{} disconnected from agent '{}'", bot.bot_username, agent_name),
+        }))
+    } else {
+        Json(
+            serde_json::json!({"ok": false, "error": format!("No Discord bot connected to agent '{}'", agent_name)}),
+        )
+    }
+}
+
+/// Get Discord bot status for an agent.
+pub async fn discord_status(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let bots = state.discord_bots.lock().await;
+    match bots.get(&agent_name) {
+        Some(bot) => Json(serde_json::json!({
+            "ok": true,
+            "connected": true,
+            "bot_username": bot.bot_username,
+            "agent": agent_name,
+        })),
+        None => Json(serde_json::json!({
+            "ok": true,
+            "connected": false,
+            "agent": agent_name,
+        })),
+    }
+}
+
+// ---- Slack Bot ↔ Agent API ----
+
+/// Connect a Slack workspace bot to a specific agent.
+/// Body: `bot_token` plus `app_token` (Socket Mode) or `signing_secret`
+/// (Events API), optionally `allowed_channel_ids`.
+pub async fn connect_slack(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    // Check agent exists
+    {
+        let orch = state.orchestrator.lock().await;
+        if !orch
+            .list_agents()
+            .iter()
+            .any(|a| a["name"].as_str() == Some(&agent_name))
+        {
+            return Json(
+                serde_json::json!({"ok": false, "error": format!("Agent '{}' not found", agent_name)}),
+            );
+        }
+    }
+
+    match spawn_slack_bot(state.clone(), agent_name.clone(), &body, format!("api_{agent_name}")).await {
+        Ok(bot_username) => Json(serde_json::json!({
+            "ok": true,
+            "agent": agent_name,
+            "bot_username": bot_username,
+            "message": format!("@{} connected to agent '{}'", bot_username, agent_name),
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// Disconnect Slack bot from an agent.
+pub async fn disconnect_slack(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let mut bots = state.slack_bots.lock().await;
+    if let Some(bot) = bots.remove(&agent_name) {
+        bot.abort_handle.notify_one();
+        tracing::info!("[slack] @{} disconnected from agent '{}'", bot.bot_username, agent_name);
+        Json(serde_json::json!({
+            "ok": true,
+            "message": format!("@{} disconnected from agent '{}'", bot.bot_username, agent_name),
+        }))
+    } else {
+        Json(
+            serde_json::json!({"ok": false, "error": format!("No Slack bot connected to agent '{}'", agent_name)}),
+        )
+    }
+}
+
+/// Get Slack bot status for an agent.
+pub async fn slack_status(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let bots = state.slack_bots.lock().await;
+    match bots.get(&agent_name) {
+        Some(bot) => Json(serde_json::json!({
+            "ok": true,
+            "connected": true,
+            "bot_username": bot.bot_username,
+            "team": bot.team,
+            "mode": if bot.socket_mode { "socket" } else { "events" },
+            "agent": agent_name,
+        })),
+        None => Json(serde_json::json!({
+            "ok": true,
+            "connected": false,
+            "agent": agent_name,
+        })),
+    }
+}
+
+// ---- Email Inbox ↔ Agent API ----
+
+/// Connect an email inbox to a specific agent.
+/// Body: `imap_host`, `smtp_host`, `email`, `password`, optional ports and
+/// `display_name`.
+pub async fn connect_email(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    // Check agent exists
+    {
+        let orch = state.orchestrator.lock().await;
+        if !orch
+            .list_agents()
+            .iter()
+            .any(|a| a["name"].as_str() == Some(&agent_name))
+        {
+            return Json(
+                serde_json::json!({"ok": false, "error": format!("Agent '{}' not found", agent_name)}),
+            );
+        }
+    }
+
+    match spawn_email_listener(state.clone(), agent_name.clone(), &body, format!("api_{agent_name}")).await {
+        Ok(address) => Json(serde_json::json!({
+            "ok": true,
+            "agent": agent_name,
+            "address": address,
+            "message": format!("{} connected to agent '{}'", address, agent_name),
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// Disconnect the email inbox from an agent.
+pub async fn disconnect_email(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let mut listeners = state.email_listeners.lock().await;
+    if let Some(listener) = listeners.remove(&agent_name) {
+        listener.abort_handle.notify_one();
+        tracing::info!("[email] {} disconnected from agent '{}'", listener.address, agent_name);
+        Json(serde_json::json!({
+            "ok": true,
+            "message": format!("{} disconnected from agent '{}'", listener.address, agent_name),
+        }))
+    } else {
+        Json(
+            serde_json::json!({"ok": false, "error": format!("No inbox connected to agent '{}'", agent_name)}),
+        )
+    }
+}
+
+/// Get email inbox status for an agent.
+pub async fn email_status(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(agent_name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let listeners = state.email_listeners.lock().await;
+    match listeners.get(&agent_name) {
+        Some(listener) => Json(serde_json::json!({
+            "ok": true,
+            "connected": true,
+            "address": listener.address,
+            "agent": agent_name,
+        })),
+        None => Json(serde_json::json!({
+            "ok": true,
+            "connected": false,
+            "agent": agent_name,
+        })),
+    }
+}
+
+// ---- Brain Workspace API ----
+
+/// List all brain files in the workspace.
+/// If `?tenant=slug` provided, uses per-tenant workspace.
+pub async fn brain_list_files(
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let ws = match params.get("tenant") {
+        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
+        _ => bizclaw_memory::brain::BrainWorkspace::default(),
+    };
+    let _ = ws.initialize(); // ensure files exist
+    let files = ws.list_files();
+    let base_dir = ws.base_dir().display().to_string();
+    Json(serde_json::json!({
+        "ok": true,
+        "files": files,
+        "base_dir": base_dir,
+        "count": files.len(),
+    }))
+}
+
+/// Read a specific brain file.
+pub async fn brain_read_file(
+    axum::extract::Path(filename): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let ws = match params.get("tenant") {
+        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
+        _ => bizclaw_memory::brain::BrainWorkspace::default(),
+    };
+    match ws.read_file(&filename) {
+        Some(content) => Json(serde_json::json!({
+            "ok": true, "filename": filename, "content": content, "size": content.len(),
+        })),
+        None => {
+            Json(serde_json::json!({"ok": false, "error": format!("File not found: {filename}")}))
+        }
+    }
+}
+
+/// Write (create/update) a brain file.
+pub async fn brain_write_file(
+    axum::extract::Path(filename): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let ws = match params.get("tenant") {
+        Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
+        _ => bizclaw_memory::brain::BrainWorkspace::default(),
+    };
+    let content = body["content"].as_str().unwrap_or("");
+    match ws.write_file(&filename, content) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "message": format!("Saved: {filename}")})),
+        Err(e) => internal_error("gateway", e),
+    }
+}
+
+/// Delete a brain file.
+pub async fn brain_delete_file(
+    axum::extract::Path(filename): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let ws = match params.get("tenant") {
         Some(slug) if !slug.is_empty() => bizclaw_memory::brain::BrainWorkspace::for_tenant(slug),
         _ => bizclaw_memory::brain::BrainWorkspace::default(),
     };
-    match ws.delete_file(&filename) {
-        Ok(true) => {
-            Json(serde_json::json!({"ok": true, "message": format!("Deleted: {filename}")}))
+    match ws.delete_file(&filename) {
+        Ok(true) => {
+            Json(serde_json::json!({"ok": true, "message": format!("Deleted: {filename}")}))
+        }
+        Ok(false) => Json(serde_json::json!({"ok": false, "error": "File not found"})),
+        Err(e) => internal_error("gateway", e),
+    }
+}
+
+/// Brain Personalization — AI generates SOUL.md, IDENTITY.md, USER.md from user description.
+///
+/// With `"background": true` this runs as a `brain_personalize` job.
+pub async fn brain_personalize(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    if body["about_user"].as_str().unwrap_or("").is_empty() {
+        return Json(
+            serde_json::json!({"ok": false, "error": "Please describe yourself (about_user)"}),
+        );
+    }
+    if body["background"].as_bool() == Some(true) {
+        return super::jobs::start_response(&state, "brain_personalize", body);
+    }
+    match personalize(&state, &body).await {
+        Ok(result) => {
+            let mut response = serde_json::json!({"ok": true});
+            if let (Some(response), Some(result)) = (response.as_object_mut(), result.as_object()) {
+                response.extend(result.clone());
+            }
+            Json(response)
+        }
+        Err(e) => Json(e),
+    }
+}
+
+/// Generate and save the brain files for a `brain_personalize` request.
+/// Returns `{"saved", "files"}`, or the error response.
+pub(crate) async fn personalize(
+    state: &AppState,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let about_user = body["about_user"].as_str().unwrap_or("");
+    let agent_vibe = body["agent_vibe"]
+        .as_str()
+        .unwrap_or("helpful and professional");
+    let agent_name = body["agent_name"].as_str().unwrap_or("BizClaw Agent");
+    let language = body["language"].as_str().unwrap_or("vi");
+    let tenant = body["tenant"].as_str().unwrap_or("");
+
+    if about_user.is_empty() {
+        return Err(serde_json::json!({"ok": false, "error": "Please describe yourself (about_user)"}));
+    }
+
+    // Build the AI prompt
+    let prompt = format!(
+        r#"You are a configuration assistant. Based on the user's description below, generate personalized brain files for an AI agent.
+
+User describes themselves: "{about_user}"
+Desired agent personality/vibe: "{agent_vibe}"
+Agent name: "{agent_name}"
+Language: "{language}"
+
+Generate EXACTLY these 3 files. Output as JSON with keys "soul", "identity", "user". Each value is the markdown content for that file.
+
+SOUL.md should define the agent's personality, tone, and behavioral rules based on the desired vibe.
+IDENTITY.md should define the agent's name, role, and style.
+USER.md should capture key facts about the user for personalization.
+
+Output ONLY valid JSON, no markdown fences."#
+    );
+
+    // Send to agent
+    let mut agent_lock = state.agent.lock().await;
+    let response = match agent_lock.as_mut() {
+        Some(agent) => match agent.process(&prompt).await {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(serde_json::json!({"ok": false, "error": format!("AI error: {e}")}));
+            }
+        },
+        None => {
+            return Err(serde_json::json!({"ok": false, "error": "Agent not available — configure provider first"}));
+        }
+    };
+    drop(agent_lock);
+
+    // Parse AI response as JSON
+    let clean = response
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let parsed: serde_json::Value = match serde_json::from_str(clean) {
+        Ok(v) => v,
+        Err(_) => {
+            // Fallback: try to extract JSON from response
+            let start = clean.find('{').unwrap_or(0);
+            let end = clean.rfind('}').map(|i| i + 1).unwrap_or(clean.len());
+            match serde_json::from_str(&clean[start..end]) {
+                Ok(v) => v,
+                Err(e) => {
+                    return Err(serde_json::json!({
+                        "ok": false,
+                        "error": format!("Failed to parse AI response: {e}"),
+                        "raw": response,
+                    }));
+                }
+            }
+        }
+    };
+
+    // Save to workspace
+    let ws = if tenant.is_empty() {
+        bizclaw_memory::brain::BrainWorkspace::default()
+    } else {
+        bizclaw_memory::brain::BrainWorkspace::for_tenant(tenant)
+    };
+    let _ = ws.initialize();
+
+    let mut saved = Vec::new();
+    for (key, filename) in &[
+        ("soul", "SOUL.md"),
+        ("identity", "IDENTITY.md"),
+        ("user", "USER.md"),
+    ] {
+        if let Some(content) = parsed[key].as_str()
+            && ws.write_file(filename, content).is_ok() {
+                saved.push(*filename);
+            }
+    }
+
+    tracing::info!("🎨 Brain personalized: {} files saved", saved.len());
+    Ok(serde_json::json!({
+        "saved": saved,
+        "files": {
+            "soul": parsed["soul"].as_str().unwrap_or(""),
+            "identity": parsed["identity"].as_str().unwrap_or(""),
+            "user": parsed["user"].as_str().unwrap_or(""),
+        },
+    }))
+}
+
+// ---- System Health Check ----
+
+/// Comprehensive health check — verify API keys, config, workspace, connectivity.
+pub async fn system_health_check(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    // Extract all needed values from config — drop guard before any .await
+    let (provider, api_key_empty, model_empty, model_info, config_path_display) = {
+        let cfg = state.full_config.lock().unwrap();
+        (
+            cfg.default_provider.clone(),
+            cfg.api_key.is_empty(),
+            cfg.default_model.is_empty(),
+            format!("{}/{}", cfg.default_provider, cfg.default_model),
+            state.config_path.display().to_string(),
+        )
+    };
+
+    let mut checks: Vec<serde_json::Value> = Vec::new();
+    let mut pass_count = 0;
+    let mut fail_count = 0;
+
+    // 1. Config file
+    let config_ok = state.config_path.exists();
+    checks.push(serde_json::json!({"name": "Config File", "status": if config_ok {"pass"} else {"fail"}, "detail": config_path_display}));
+    if config_ok {
+        pass_count += 1;
+    } else {
+        fail_count += 1;
+    }
+
+    // 2. Provider API key
+    let key_ok = match provider.as_str() {
+        "ollama" | "brain" | "llamacpp" => true,
+        _ => !api_key_empty,
+    };
+    let key_detail = if key_ok {
+        format!("{provider}: configured")
+    } else {
+        format!("{provider}: API key missing!")
+    };
+    checks.push(serde_json::json!({"name": "API Key", "status": if key_ok {"pass"} else {"fail"}, "detail": key_detail}));
+    if key_ok {
+        pass_count += 1;
+    } else {
+        fail_count += 1;
+    }
+
+    // 3. Model configured
+    checks.push(serde_json::json!({"name": "Model", "status": if !model_empty {"pass"} else {"warn"}, "detail": model_info}));
+    if !model_empty {
+        pass_count += 1;
+    } else {
+        fail_count += 1;
+    }
+
+    // 4. Brain workspace
+    let brain_ws = bizclaw_memory::brain::BrainWorkspace::default();
+    let brain_status = brain_ws.status();
+    let brain_files_exist = brain_status.iter().filter(|(_, exists, _)| *exists).count();
+    let brain_ok = brain_files_exist >= 3;
+    checks.push(serde_json::json!({"name": "Brain Workspace", "status": if brain_ok {"pass"} else {"warn"}, "detail": format!("{}/{} files", brain_files_exist, brain_status.len())}));
+    if brain_ok {
+        pass_count += 1;
+    } else {
+        fail_count += 1;
+    }
+
+    // 5. Ollama (if local provider)
+    let ollama_check = if provider == "ollama" {
+        match reqwest::Client::new()
+            .get("http://localhost:11434/api/tags")
+            .timeout(std::time::Duration::from_secs(3))
+            .send()
+            .await
+        {
+            Ok(r) if r.status().is_success() => {
+                pass_count += 1;
+                serde_json::json!({"name": "Ollama Server", "status": "pass", "detail": "Running on localhost:11434"})
+            }
+            _ => {
+                fail_count += 1;
+                serde_json::json!({"name": "Ollama Server", "status": "fail", "detail": "Not reachable at localhost:11434"})
+            }
+        }
+    } else {
+        pass_count += 1;
+        serde_json::json!({"name": "Ollama Server", "status": "skip", "detail": format!("Not needed for {provider}")})
+    };
+    checks.push(ollama_check);
+
+    // 6. Agent ready
+    let agent_ready = state.agent.lock().await.is_some();
+    checks.push(serde_json::json!({"name": "Agent Engine", "status": if agent_ready {"pass"} else {"fail"}, "detail": if agent_ready {"Initialized and ready"} else {"Not initialized"}}));
+    if agent_ready {
+        pass_count += 1;
+    } else {
+        fail_count += 1;
+    }
+
+    // 7. Memory backend
+    checks.push(
+        serde_json::json!({"name": "Memory Backend", "status": "pass", "detail": "SQLite FTS5"}),
+    );
+    pass_count += 1;
+
+    let total = pass_count + fail_count;
+    let score = if total > 0 {
+        (pass_count * 100) / total
+    } else {
+        0
+    };
+    let overall = if fail_count == 0 {
+        "healthy"
+    } else if fail_count <= 2 {
+        "degraded"
+    } else {
+        "critical"
+    };
+
+    Json(serde_json::json!({
+        "ok": fail_count == 0,
+        "status": overall,
+        "score": format!("{}/{}", pass_count, total),
+        "score_pct": score,
+        "checks": checks,
+        "pass": pass_count,
+        "fail": fail_count,
+    }))
+}
+
+// ═══════════════════════════════════════════════════════
+// Gallery API — Manage skill templates
+// ═══════════════════════════════════════════════════════
+
+/// List all gallery skills (built-in + user-created).
+pub async fn gallery_list(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let gallery_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("gallery.json");
+
+    // Load built-in skills from embedded data
+    let builtin: Vec<serde_json::Value> = serde_json::from_str(
+        include_str!("../../../data/gallery-skills.json")
+    ).unwrap_or_default();
+
+    // Load user-created skills
+    let user_skills: Vec<serde_json::Value> = if gallery_path.exists() {
+        std::fs::read_to_string(&gallery_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    // Check which skills have attached MD files
+    let skills_dir = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("skills");
+
+    let mut all_skills: Vec<serde_json::Value> = builtin.into_iter()
+        .map(|mut s| { s.as_object_mut().map(|o| o.insert("source".into(), "builtin".into())); s })
+        .collect();
+
+    for mut s in user_skills {
+        s.as_object_mut().map(|o| o.insert("source".into(), "user".into()));
+        all_skills.push(s);
+    }
+
+    // Check for attached MD files
+    for skill in &mut all_skills {
+        if let Some(id) = skill["id"].as_str() {
+            let md_path = skills_dir.join(format!("{}.md", id));
+            if md_path.exists() {
+                skill.as_object_mut().map(|o| o.insert("has_md".into(), true.into()));
+            }
+        }
+    }
+
+    Json(serde_json::json!({
+        "ok": true,
+        "skills": all_skills,
+        "total": all_skills.len(),
+    }))
+}
+
+/// Create a custom gallery skill.
+pub async fn gallery_create(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let gallery_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("gallery.json");
+
+    let mut skills: Vec<serde_json::Value> = if gallery_path.exists() {
+        std::fs::read_to_string(&gallery_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let id = body["id"].as_str().unwrap_or("custom").to_string();
+
+    // Check for duplicate
+    if skills.iter().any(|s| s["id"].as_str() == Some(&id)) {
+        return Json(serde_json::json!({"ok": false, "error": format!("Skill '{}' already exists", id)}));
+    }
+
+    skills.push(body.clone());
+
+    if let Ok(json) = serde_json::to_string_pretty(&skills) {
+        let _ = std::fs::write(&gallery_path, json);
+    }
+
+    Json(serde_json::json!({"ok": true, "id": id, "total": skills.len()}))
+}
+
+/// Delete a custom gallery skill.
+pub async fn gallery_delete(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let gallery_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("gallery.json");
+
+    let mut skills: Vec<serde_json::Value> = if gallery_path.exists() {
+        std::fs::read_to_string(&gallery_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let before = skills.len();
+    skills.retain(|s| s["id"].as_str() != Some(&id));
+    let removed = before != skills.len();
+
+    if removed {
+        if let Ok(json) = serde_json::to_string_pretty(&skills) {
+            let _ = std::fs::write(&gallery_path, json);
+        }
+        // Also remove any attached MD file
+        let skills_dir = state.config_path.parent()
+            .unwrap_or(std::path::Path::new("."))
+            .join("skills");
+        let md_path = skills_dir.join(format!("{}.md", id));
+        let _ = std::fs::remove_file(md_path);
+    }
+
+    Json(serde_json::json!({"ok": removed, "id": id}))
+}
+
+/// Upload an MD file for a gallery skill.
+pub async fn gallery_upload_md(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    body: String,
+) -> Json<serde_json::Value> {
+    let skills_dir = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("skills");
+    let _ = std::fs::create_dir_all(&skills_dir);
+
+    let md_path = skills_dir.join(format!("{}.md", id));
+    match std::fs::write(&md_path, &body) {
+        Ok(_) => {
+            tracing::info!("📄 Uploaded skill MD: {}.md ({} bytes)", id, body.len());
+            Json(serde_json::json!({
+                "ok": true,
+                "id": id,
+                "size": body.len(),
+                "path": md_path.display().to_string(),
+            }))
+        }
+        Err(e) => internal_error("gateway", e),
+    }
+}
+
+/// Get the MD content for a gallery skill.
+pub async fn gallery_get_md(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let skills_dir = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("skills");
+    let md_path = skills_dir.join(format!("{}.md", id));
+
+    if md_path.exists() {
+        let content = std::fs::read_to_string(&md_path).unwrap_or_default();
+        Json(serde_json::json!({"ok": true, "id": id, "content": content}))
+    } else {
+        Json(serde_json::json!({"ok": false, "error": "MD file not found"}))
+    }
+}
+
+// ═══════════════════════════════════════════════════════
+// Agent-Channel Binding API
+// ═══════════════════════════════════════════════════════
+
+/// Bind an agent to one or more channels.
+pub async fn agent_bind_channels(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let channels = body["channels"].as_array()
+        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    // Store binding in agent-channels.json
+    let bindings_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("agent-channels.json");
+
+    let mut bindings: serde_json::Map<String, serde_json::Value> = if bindings_path.exists() {
+        std::fs::read_to_string(&bindings_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    } else {
+        serde_json::Map::new()
+    };
+
+    let previous = bindings.insert(name.clone(), serde_json::json!(channels));
+
+    if let Ok(json) = serde_json::to_string_pretty(&serde_json::Value::Object(bindings.clone())) {
+        let _ = std::fs::write(&bindings_path, json);
+    }
+    super::audit::record(&state, "agent.bind_channels", &name, previous, Some(serde_json::json!(channels)));
+
+    tracing::info!("🔗 Agent '{}' bound to channels: {:?}", name, channels);
+
+    Json(serde_json::json!({
+        "ok": true,
+        "agent": name,
+        "channels": channels,
+    }))
+}
+
+/// Get channel bindings for all agents.
+pub async fn agent_channel_bindings(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let bindings_path = state.config_path.parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("agent-channels.json");
+
+    let bindings: serde_json::Value = if bindings_path.exists() {
+        std::fs::read_to_string(&bindings_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(serde_json::json!({}))
+    } else {
+        serde_json::json!({})
+    };
+
+    Json(serde_json::json!({
+        "ok": true,
+        "bindings": bindings,
+    }))
+}
+
+// ---- Orchestration API ----
+
+/// Delegate a task from one agent to another.
+/// POST /api/v1/orchestration/delegate
+/// Body: {"from_agent": "a", "to_agent": "b", "task": "...", "mode": "sync|async"}
+pub async fn orch_delegate(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let from = body["from_agent"].as_str().unwrap_or("");
+    let to = body["to_agent"].as_str().unwrap_or("");
+    let task = body["task"].as_str().unwrap_or("");
+
+    if from.is_empty() || to.is_empty() || task.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "from_agent, to_agent, and task are required"}));
+    }
+
+    let mode = match body["mode"].as_str().unwrap_or("sync") {
+        "async" => bizclaw_core::types::DelegationMode::Async,
+        _ => bizclaw_core::types::DelegationMode::Sync,
+    };
+
+    let mut orch = state.orchestrator.lock().await.clone();
+    match orch.delegate_with_mode(from, to, task, mode).await {
+        Ok(response) => Json(serde_json::json!({
+            "ok": true,
+            "from": from,
+            "to": to,
+            "response": safe_truncate(&response, 5000),
+        })),
+        Err(e) => {
+            tracing::error!("[orch_delegate] {e}");
+            internal_error("delegation", e)
+        }
+    }
+}
+
+/// Which agent a message would be routed to, without sending it.
+/// POST /api/v1/orchestration/route
+/// Body: {"message": "..."}
+pub async fn orch_route(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let message = body["message"].as_str().unwrap_or("");
+    if message.trim().is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "message is required"}));
+    }
+    let orch = state.orchestrator.lock().await.clone();
+    match orch.route(message).await {
+        Some(route) => Json(serde_json::json!({
+            "ok": true,
+            "routing_enabled": orch.routing_enabled(),
+            "route": route,
+        })),
+        None => Json(serde_json::json!({"ok": false, "error": "No agents to route to"})),
+    }
+}
+
+/// Run an agent-to-agent pipeline, each step's output feeding the next.
+/// POST /api/v1/pipelines/run
+/// Body: {"input": "...", "steps": [{"agent", "instruction", "timeout_secs"}]}
+///
+/// Returns every step's output or error. With `"background": true` this
+/// runs as an `agent_pipeline` job.
+pub async fn pipeline_run(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let config = match pipeline_config(&body) {
+        Ok(config) => config,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    if body["background"].as_bool() == Some(true) {
+        return super::jobs::start_response(&state, "agent_pipeline", body);
+    }
+    match run_pipeline(&state, &config).await {
+        Ok(result) => Json(serde_json::json!({"ok": true, "pipeline": result})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// The pipeline described by a `/pipelines/run` body.
+pub(crate) fn pipeline_config(body: &serde_json::Value) -> Result<bizclaw_core::types::PipelineConfig, String> {
+    let config: bizclaw_core::types::PipelineConfig =
+        serde_json::from_value(body.clone()).map_err(|e| format!("Invalid pipeline: {e}"))?;
+    if config.input.trim().is_empty() {
+        return Err("input is required".into());
+    }
+    if config.steps.is_empty() {
+        return Err("steps are required".into());
+    }
+    Ok(config)
+}
+
+/// Run `config` on the orchestrator's agents.
+pub(crate) async fn run_pipeline(
+    state: &AppState,
+    config: &bizclaw_core::types::PipelineConfig,
+) -> Result<bizclaw_core::types::PipelineResult, String> {
+    let agents: Vec<&str> = config.steps.iter().map(|s| s.agent.as_str()).collect();
+    tracing::info!("🔗 Pipeline: {}", agents.join(" → "));
+    let mut orch = state.orchestrator.lock().await.clone();
+    orch.run_pipeline(config).await.map_err(|e| e.to_string())
+}
+
+/// Handoff conversation from one agent to another.
+/// POST /api/v1/orchestration/handoff
+pub async fn orch_handoff(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let from = body["from_agent"].as_str().unwrap_or("");
+    let to = body["to_agent"].as_str().unwrap_or("");
+    let session = body["session_id"].as_str().unwrap_or("default");
+    let reason = body["reason"].as_str();
+
+    if from.is_empty() || to.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "from_agent and to_agent required"}));
+    }
+
+    let mut orch = state.orchestrator.lock().await;
+    match orch.handoff(from, to, session, reason).await {
+        Ok(()) => Json(serde_json::json!({"ok": true, "from": from, "to": to, "session": session})),
+        Err(e) => {
+            tracing::error!("[orch_handoff] {e}");
+            internal_error("handoff", e)
+        }
+    }
+}
+
+/// Clear handoff for a session.
+/// DELETE /api/v1/orchestration/handoff/{session_id}
+pub async fn orch_clear_handoff(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let orch = state.orchestrator.lock().await;
+    match orch.clear_handoff(&session_id).await {
+        Ok(()) => Json(serde_json::json!({"ok": true, "session": session_id})),
+        Err(e) => internal_error("clear_handoff", e),
+    }
+}
+
+/// Run evaluate loop between two agents.
+/// POST /api/v1/orchestration/evaluate
+pub async fn orch_evaluate(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let generator = body["generator"].as_str().unwrap_or("");
+    let evaluator = body["evaluator"].as_str().unwrap_or("");
+    let task = body["task"].as_str().unwrap_or("");
+    let pass_criteria = body["pass_criteria"].as_str().unwrap_or("high quality output");
+    let max_rounds = body["max_rounds"].as_u64().unwrap_or(3) as u32;
+
+    if generator.is_empty() || evaluator.is_empty() || task.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "generator, evaluator, and task required"}));
+    }
+
+    let config = bizclaw_core::types::EvaluateConfig {
+        generator: generator.to_string(),
+        evaluator: evaluator.to_string(),
+        task: task.to_string(),
+        pass_criteria: pass_criteria.to_string(),
+        max_rounds,
+    };
+
+    let mut orch = state.orchestrator.lock().await.clone();
+    match orch.evaluate_loop(&config).await {
+        Ok(result) => Json(serde_json::json!({
+            "ok": true,
+            "approved": result.approved,
+            "output": result.output,
+            "feedback": result.feedback,
+            "rounds_used": result.rounds_used,
+            "max_rounds": result.max_rounds,
+        })),
+        Err(e) => {
+            tracing::error!("[orch_evaluate] {e}");
+            internal_error("evaluate", e)
         }
-        Ok(false) => Json(serde_json::json!({"ok": false, "error": "File not found"})),
-        Err(e) => internal_error("gateway", e),
     }
 }
 
-/// Brain Personalization — AI generates SOUL.md, IDENTITY.md, USER.md from user description.
-pub async fn brain_personalize(
+/// Create a permission link between agents.
+/// POST /api/v1/orchestration/links
+pub async fn orch_create_link(
     State(state): State<Arc<AppState>>,
     Json(body): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let about_user = body["about_user"].as_str().unwrap_or("");
-    let agent_vibe = body["agent_vibe"]
-        .as_str()
-        .unwrap_or("helpful and professional");
-    let agent_name = body["agent_name"].as_str().unwrap_or("BizClaw Agent");
-    let language = body["language"].as_str().unwrap_or("vi");
-    let tenant = body["tenant"].as_str().unwrap_or("");
+    let source = body["source"].as_str().unwrap_or("");
+    let target = body["target"].as_str().unwrap_or("");
+    let direction = match body["direction"].as_str().unwrap_or("outbound") {
+        "inbound" => bizclaw_core::types::LinkDirection::Inbound,
+        "bidirectional" => bizclaw_core::types::LinkDirection::Bidirectional,
+        _ => bizclaw_core::types::LinkDirection::Outbound,
+    };
 
-    if about_user.is_empty() {
-        return Json(
-            serde_json::json!({"ok": false, "error": "Please describe yourself (about_user)"}),
-        );
+    if source.is_empty() || target.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "source and target required"}));
     }
 
-    // Build the AI prompt
-    let prompt = format!(
-        r#"You are a configuration assistant. Based on the user's description below, generate personalized brain files for an AI agent.
-
-User describes themselves: "{about_user}"
-Desired agent personality/vibe: "{agent_vibe}"
-Agent name: "{agent_name}"
-Language: "{language}"
-
-Generate EXACTLY these 3 files. Output as JSON with keys "soul", "identity", "user". Each value is the markdown content for that file.
-
-SOUL.md should define the agent's personality, tone, and behavioral rules based on the desired vibe.
-IDENTITY.md should define the agent's name, role, and style.
-USER.md should capture key facts about the user for personalization.
-
-Output ONLY valid JSON, no markdown fences."#
-    );
-
-    // Send to agent
-    let mut agent_lock = state.agent.lock().await;
-    let response = match agent_lock.as_mut() {
-        Some(agent) => match agent.process(&prompt).await {
-            Ok(r) => r,
-            Err(e) => {
-                return Json(serde_json::json!({"ok": false, "error": format!("AI error: {e}")}));
-            }
-        },
-        None => {
-            return Json(
-                serde_json::json!({"ok": false, "error": "Agent not available — configure provider first"}),
-            );
-        }
-    };
-    drop(agent_lock);
+    let orch = state.orchestrator.lock().await;
+    match orch.create_link(source, target, direction).await {
+        Ok(link) => Json(serde_json::json!({"ok": true, "id": link.id})),
+        Err(e) => internal_error("create_link", e),
+    }
+}
 
-    // Parse AI response as JSON
-    let clean = response
-        .trim()
-        .trim_start_matches("```json")
-        .trim_start_matches("```")
-        .trim_end_matches("```")
-        .trim();
-    let parsed: serde_json::Value = match serde_json::from_str(clean) {
-        Ok(v) => v,
-        Err(_) => {
-            // Fallback: try to extract JSON from response
-            let start = clean.find('{').unwrap_or(0);
-            let end = clean.rfind('}').map(|i| i + 1).unwrap_or(clean.len());
-            match serde_json::from_str(&clean[start..end]) {
-                Ok(v) => v,
-                Err(e) => {
-                    return Json(serde_json::json!({
-                        "ok": false,
-                        "error": format!("Failed to parse AI response: {e}"),
-                        "raw": response,
-                    }));
-                }
-            }
+/// List all agent permission links.
+/// GET /api/v1/orchestration/links
+pub async fn orch_list_links(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let orch = state.orchestrator.lock().await;
+    match orch.list_links().await {
+        Ok(links) => {
+            let items: Vec<serde_json::Value> = links.iter().map(|l| serde_json::json!({
+                "id": l.id,
+                "source": l.source_agent,
+                "target": l.target_agent,
+                "direction": l.direction.to_string(),
+                "max_concurrent": l.max_concurrent,
+            })).collect();
+            Json(serde_json::json!({"ok": true, "links": items, "count": items.len()}))
         }
-    };
-
-    // Save to workspace
-    let ws = if tenant.is_empty() {
-        bizclaw_memory::brain::BrainWorkspace::default()
-    } else {
-        bizclaw_memory::brain::BrainWorkspace::for_tenant(tenant)
-    };
-    let _ = ws.initialize();
-
-    let mut saved = Vec::new();
-    for (key, filename) in &[
-        ("soul", "SOUL.md"),
-        ("identity", "IDENTITY.md"),
-        ("user", "USER.md"),
-    ] {
-        if let Some(content) = parsed[key].as_str()
-            && ws.write_file(filename, content).is_ok() {
-                saved.push(*filename);
-            }
+        Err(e) => internal_error("list_links", e),
     }
+}
 
-    tracing::info!("🎨 Brain personalized: {} files saved", saved.len());
-    Json(serde_json::json!({
-        "ok": true,
-        "saved": saved,
-        "files": {
-            "soul": parsed["soul"].as_str().unwrap_or(""),
-            "identity": parsed["identity"].as_str().unwrap_or(""),
-            "user": parsed["user"].as_str().unwrap_or(""),
-        },
-    }))
+/// Delete a permission link.
+/// DELETE /api/v1/orchestration/links/{id}
+pub async fn orch_delete_link(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let orch = state.orchestrator.lock().await;
+    match orch.delete_link(&id).await {
+        Ok(()) => Json(serde_json::json!({"ok": true})),
+        Err(e) => internal_error("delete_link", e),
+    }
 }
 
-// ---- System Health Check ----
+/// List delegation history.
+/// GET /api/v1/orchestration/delegations?agent=name&limit=20
+pub async fn orch_list_delegations(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let agent = params.get("agent").map(|s| s.as_str()).unwrap_or("*");
+    let limit = params.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(20);
 
-/// Comprehensive health check — verify API keys, config, workspace, connectivity.
-pub async fn system_health_check(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    // Extract all needed values from config — drop guard before any .await
-    let (provider, api_key_empty, model_empty, model_info, config_path_display) = {
-        let cfg = state.full_config.lock().unwrap();
-        (
-            cfg.default_provider.clone(),
-            cfg.api_key.is_empty(),
-            cfg.default_model.is_empty(),
-            format!("{}/{}", cfg.default_provider, cfg.default_model),
-            state.config_path.display().to_string(),
-        )
+    let store = &state.orch_store;
+    let delegations = if agent == "*" {
+        // Get all — use traces as proxy (list_delegations requires agent)
+        store.list_delegations("", limit).await.unwrap_or_default()
+    } else {
+        store.list_delegations(agent, limit).await.unwrap_or_default()
     };
 
-    let mut checks: Vec<serde_json::Value> = Vec::new();
-    let mut pass_count = 0;
-    let mut fail_count = 0;
+    let items: Vec<serde_json::Value> = delegations.iter().map(|d| serde_json::json!({
+        "id": d.id,
+        "from": d.from_agent,
+        "to": d.to_agent,
+        "task": safe_truncate(&d.task, 200),
+        "status": format!("{:?}", d.status),
+        "mode": format!("{:?}", d.mode),
+        "created_at": d.created_at.to_rfc3339(),
+    })).collect();
 
-    // 1. Config file
-    let config_ok = state.config_path.exists();
-    checks.push(serde_json::json!({"name": "Config File", "status": if config_ok {"pass"} else {"fail"}, "detail": config_path_display}));
-    if config_ok {
-        pass_count += 1;
-    } else {
-        fail_count += 1;
-    }
+    Json(serde_json::json!({"ok": true, "delegations": items, "count": items.len()}))
+}
 
-    // 2. Provider API key
-    let key_ok = match provider.as_str() {
-        "ollama" | "brain" | "llamacpp" => true,
-        _ => !api_key_empty,
-    };
-    let key_detail = if key_ok {
-        format!("{provider}: configured")
-    } else {
-        format!("{provider}: API key missing!")
-    };
-    checks.push(serde_json::json!({"name": "API Key", "status": if key_ok {"pass"} else {"fail"}, "detail": key_detail}));
-    if key_ok {
-        pass_count += 1;
-    } else {
-        fail_count += 1;
-    }
+/// List LLM traces (observability).
+/// GET /api/v1/orchestration/traces?limit=50
+pub async fn orch_list_traces(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let limit = params.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(50);
 
-    // 3. Model configured
-    checks.push(serde_json::json!({"name": "Model", "status": if !model_empty {"pass"} else {"warn"}, "detail": model_info}));
-    if !model_empty {
-        pass_count += 1;
-    } else {
-        fail_count += 1;
-    }
+    let orch = state.orchestrator.lock().await;
+    let traces = orch.list_traces(limit).await.unwrap_or_default();
 
-    // 4. Brain workspace
-    let brain_ws = bizclaw_memory::brain::BrainWorkspace::default();
-    let brain_status = brain_ws.status();
-    let brain_files_exist = brain_status.iter().filter(|(_, exists, _)| *exists).count();
-    let brain_ok = brain_files_exist >= 3;
-    checks.push(serde_json::json!({"name": "Brain Workspace", "status": if brain_ok {"pass"} else {"warn"}, "detail": format!("{}/{} files", brain_files_exist, brain_status.len())}));
-    if brain_ok {
-        pass_count += 1;
-    } else {
-        fail_count += 1;
-    }
+    let items: Vec<serde_json::Value> = traces.iter().map(|t| serde_json::json!({
+        "id": t.id,
+        "agent": t.agent_name,
+        "provider": t.provider,
+        "model": t.model,
+        "tokens": t.total_tokens,
+        "latency_ms": t.latency_ms,
+        "cache_hit": t.cache_hit,
+        "status": t.status,
+        "created_at": t.created_at.to_rfc3339(),
+    })).collect();
 
-    // 5. Ollama (if local provider)
-    let ollama_check = if provider == "ollama" {
-        match reqwest::Client::new()
-            .get("http://localhost:11434/api/tags")
-            .timeout(std::time::Duration::from_secs(3))
-            .send()
-            .await
-        {
-            Ok(r) if r.status().is_success() => {
-                pass_count += 1;
-                serde_json::json!({"name": "Ollama Server", "status": "pass", "detail": "Running on localhost:11434"})
-            }
-            _ => {
-                fail_count += 1;
-                serde_json::json!({"name": "Ollama Server", "status": "fail", "detail": "Not reachable at localhost:11434"})
-            }
-        }
-    } else {
-        pass_count += 1;
-        serde_json::json!({"name": "Ollama Server", "status": "skip", "detail": format!("Not needed for {provider}")})
-    };
-    checks.push(ollama_check);
+    Json(serde_json::json!({"ok": true, "traces": items, "count": items.len()}))
+}
 
-    // 6. Agent ready
-    let agent_ready = state.agent.lock().await.is_some();
-    checks.push(serde_json::json!({"name": "Agent Engine", "status": if agent_ready {"pass"} else {"fail"}, "detail": if agent_ready {"Initialized and ready"} else {"Not initialized"}}));
-    if agent_ready {
-        pass_count += 1;
-    } else {
-        fail_count += 1;
-    }
+// ═══ MCP Servers API ═══
+pub async fn mcp_list_servers(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    // Connection state from the orchestrator's shared pool, once connected
+    let pooled = state
+        .orchestrator
+        .lock()
+        .await
+        .mcp_pool()
+        .map(|pool| pool.status())
+        .unwrap_or_default();
+    let config = state.full_config.lock().unwrap();
+    let servers: Vec<serde_json::Value> = config.mcp_servers.iter().map(|s| {
+        let conn = pooled.iter().find(|p| p["name"].as_str() == Some(&s.name));
+        let status = match conn {
+            _ if !s.enabled => "disabled",
+            Some(c) if c["connected"] == true => "connected",
+            Some(_) => "failed",
+            None => "configured",
+        };
+        serde_json::json!({
+            "name": s.name,
+            "transport": if s.url.is_empty() { "stdio" } else { "http" },
+            "command": s.command,
+            "args": s.args,
+            "url": s.url,
+            "enabled": s.enabled,
+            "tools_count": conn.map_or(serde_json::json!(0), |c| c["tools_count"].clone()),
+            "resources_count": conn.map_or(serde_json::json!(0), |c| c["resources_count"].clone()),
+            "prompts_count": conn.map_or(serde_json::json!(0), |c| c["prompts_count"].clone()),
+            "status": status,
+            "error": conn.map_or(serde_json::Value::Null, |c| c["error"].clone()),
+        })
+    }).collect();
+    Json(serde_json::json!({"ok": true, "servers": servers, "count": servers.len()}))
+}
 
-    // 7. Memory backend
-    checks.push(
-        serde_json::json!({"name": "Memory Backend", "status": "pass", "detail": "SQLite FTS5"}),
-    );
-    pass_count += 1;
+/// Resources of the connected MCP servers, which agents search for context.
+pub async fn mcp_list_resources(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let resources = state
+        .orchestrator
+        .lock()
+        .await
+        .mcp_pool()
+        .map(|pool| pool.resources())
+        .unwrap_or_default();
+    Json(serde_json::json!({"ok": true, "resources": resources, "count": resources.len()}))
+}
 
-    let total = pass_count + fail_count;
-    let score = if total > 0 {
-        (pass_count * 100) / total
-    } else {
-        0
+/// Read an MCP resource now: `?server=<name>&uri=<uri>`.
+pub async fn mcp_read_resource(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let (Some(server), Some(uri)) = (params.get("server"), params.get("uri")) else {
+        return Json(serde_json::json!({"ok": false, "error": "server and uri are required"}));
     };
-    let overall = if fail_count == 0 {
-        "healthy"
-    } else if fail_count <= 2 {
-        "degraded"
-    } else {
-        "critical"
+    let pool = state.orchestrator.lock().await.mcp_pool().cloned();
+    let Some(pool) = pool else {
+        return Json(serde_json::json!({"ok": false, "error": "No MCP servers connected"}));
     };
+    match pool.read_resource(server, uri).await {
+        Ok(text) => Json(serde_json::json!({"ok": true, "server": server, "uri": uri, "text": text})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
 
-    Json(serde_json::json!({
-        "ok": fail_count == 0,
-        "status": overall,
-        "score": format!("{}/{}", pass_count, total),
-        "score_pct": score,
-        "checks": checks,
-        "pass": pass_count,
-        "fail": fail_count,
-    }))
+/// Prompt templates of the connected MCP servers, selectable per agent as
+/// `mcp_prompts` (`server/prompt`).
+pub async fn mcp_list_prompts(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let prompts = state
+        .orchestrator
+        .lock()
+        .await
+        .mcp_pool()
+        .map(|pool| pool.prompts())
+        .unwrap_or_default();
+    let prompts: Vec<serde_json::Value> = prompts
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "id": format!("{}/{}", p.server_name, p.name),
+                "server": p.server_name,
+                "name": p.name,
+                "description": p.description,
+                "arguments": p.arguments,
+            })
+        })
+        .collect();
+    Json(serde_json::json!({"ok": true, "prompts": prompts, "count": prompts.len()}))
 }
 
 #[cfg(test)]
@@ -2974,6 +6171,7 @@ mod tests {
             start_time: std::time::Instant::now(),
             pairing_code: Arc::new(Mutex::new(String::new())),
             auth_failures: Arc::new(tokio::sync::Mutex::new((0, std::time::Instant::now()))),
+            api_key_limiter: Arc::new(crate::api_keys::ApiKeyLimiter::default()),
             agent: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
             orchestrator: std::sync::Arc::new(tokio::sync::Mutex::new(
                 bizclaw_agent::orchestrator::Orchestrator::new(),
@@ -2985,14 +6183,25 @@ mod tests {
             )),
             knowledge: Arc::new(tokio::sync::Mutex::new(None)),
             telegram_bots: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+            discord_bots: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+            slack_bots: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+            email_listeners: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+            zalo_oa: Arc::new(std::sync::Mutex::new(None)),
+            channel_pipeline: Arc::new(bizclaw_channels::middleware::Pipeline::default()),
+            channel_registry: Arc::new(bizclaw_channels::registry::ChannelRegistry::with_builtin()),
+            adapter_channels: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
             db: Arc::new(crate::db::GatewayDb::open(std::path::Path::new(":memory:")).unwrap()),
-            orch_store: {
-                let store = Arc::new(bizclaw_db::SqliteStore::in_memory().unwrap());
-                store
-            },
+            orch_store: Arc::new(bizclaw_db::SqliteStore::in_memory().unwrap()),
             traces: Arc::new(Mutex::new(Vec::new())),
             activity_tx,
             activity_log: Arc::new(Mutex::new(Vec::new())),
+            approvals: bizclaw_agent::approval::ApprovalQueue::new(),
+            events: bizclaw_core::events::EventBus::default(),
+            hands: bizclaw_hands::HandRunner::new(
+                Arc::new(tokio::sync::Mutex::new(bizclaw_hands::HandRegistry::with_defaults())),
+                60,
+            ),
+            jobs: Arc::new(crate::jobs::JobQueue::default()),
         }))
     }
 
@@ -3005,6 +6214,24 @@ mod tests {
         assert_eq!(json["status"], "ok");
     }
 
+    #[tokio::test]
+    async fn test_metrics() {
+        use axum::response::IntoResponse;
+        bizclaw_core::metrics::increment("bizclaw_tool_calls_total", &[("tool", "shell"), ("success", "true")]);
+        let response = metrics(test_state()).await.into_response();
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("# TYPE bizclaw_uptime_seconds gauge"));
+        assert!(text.contains("bizclaw_agents 0"));
+        assert!(text.contains("bizclaw_scheduler_tasks "));
+        assert!(text.contains("# HELP bizclaw_tool_calls_total Tool calls made by agents."));
+        assert!(text.contains("bizclaw_tool_calls_total{tool=\"shell\",success=\"true\"}"));
+    }
+
     #[tokio::test]
     async fn test_system_info() {
         let result = system_info(test_state()).await;
@@ -3068,13 +6295,52 @@ mod tests {
         }));
         let result = update_config(test_state(), body).await;
         let json = result.0;
-        assert!(json["ok"].as_bool().unwrap());
+        assert!(json["ok"].as_bool().unwrap(), "{json}");
+        assert!(json["changes"].as_array().unwrap().iter().any(|c| c["field"] == "default_model" && c["to"] == "llama3.2"));
 
         // Verify updated
-        let config_result = get_config(test_state()).await;
+        let _config_result = get_config(test_state()).await;
         // Note: test_state creates fresh state each time, so only in-memory update is tested
     }
 
+    #[tokio::test]
+    async fn test_update_config_dry_run_and_validation() {
+        let state = test_state();
+        let json = update_config(state.clone(), Json(serde_json::json!({
+            "dry_run": true,
+            "default_model": "llama3.2",
+            "api_key": "sk-new",
+        })))
+        .await
+        .0;
+        assert_eq!(json["dry_run"], true);
+        assert!(json["ok"].as_bool().unwrap(), "{json}");
+        let changes = json["changes"].as_array().unwrap();
+        assert!(changes.iter().any(|c| c["field"] == "api_key" && c["to"] == "***"));
+        // Nothing applied
+        assert_ne!(state.full_config.lock().unwrap().default_model, "llama3.2");
+
+        let json = update_config(state.clone(), Json(serde_json::json!({
+            "default_temperature": 5.0,
+            "brain": {"max_tokens": 999999},
+        })))
+        .await
+        .0;
+        assert_eq!(json["ok"], false);
+        let fields: Vec<&str> = json["issues"].as_array().unwrap().iter().filter_map(|i| i["field"].as_str()).collect();
+        assert!(fields.contains(&"default_temperature") && fields.contains(&"brain.max_tokens"), "{json}");
+        assert!((state.full_config.lock().unwrap().default_temperature - 5.0).abs() > 0.1);
+    }
+
+    #[tokio::test]
+    async fn test_update_config_rolls_back_when_agent_fails() {
+        let state = test_state();
+        let json = update_config(state.clone(), Json(serde_json::json!({"default_provider": "no-such-provider"}))).await.0;
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["rolled_back"], true, "{json}");
+        assert_ne!(state.full_config.lock().unwrap().default_provider, "no-such-provider");
+    }
+
     // ---- Multi-Agent ----
 
     #[tokio::test]
@@ -3086,6 +6352,39 @@ mod tests {
         assert!(json["agents"].is_array());
     }
 
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        use crate::api_keys::{self, Denied, Scope};
+        let state = test_state();
+        let created = api_keys::create_api_key(
+            state.clone(),
+            Json(serde_json::json!({"name": "ci", "scopes": ["chat"], "rate_limit": 2})),
+        )
+        .await
+        .0;
+        assert!(created["ok"].as_bool().unwrap(), "{created}");
+        let key = created["key"].as_str().unwrap();
+        let id = created["api_key"]["id"].as_str().unwrap().to_string();
+        assert!(created["api_key"].get("key_hash").is_none());
+
+        assert!(api_keys::authorize(&state, key, Scope::Chat).is_ok());
+        assert_eq!(
+            api_keys::authorize(&state, key, Scope::Admin).unwrap_err(),
+            Denied::MissingScope(Scope::Admin)
+        );
+        assert!(api_keys::authorize(&state, key, Scope::Read).is_ok());
+        assert!(matches!(
+            api_keys::authorize(&state, key, Scope::Read),
+            Err(Denied::RateLimited { .. })
+        ));
+
+        let bad = api_keys::create_api_key(state.clone(), Json(serde_json::json!({"name": "x", "scopes": ["root"]}))).await.0;
+        assert_eq!(bad["ok"], false);
+
+        assert!(api_keys::delete_api_key(state.clone(), axum::extract::Path(id)).await.0["ok"].as_bool().unwrap());
+        assert_eq!(api_keys::authorize(&state, key, Scope::Read).unwrap_err(), Denied::UnknownKey);
+    }
+
     #[tokio::test]
     async fn test_create_agent() {
         let state = test_state();
@@ -3127,7 +6426,7 @@ mod tests {
             "role": "assistant",
             "description": "Original desc"
         }));
-        create_agent(state.clone(), body).await;
+        let _ = create_agent(state.clone(), body).await;
 
         // Update
         let update_body = Json(serde_json::json!({
@@ -3144,6 +6443,22 @@ mod tests {
         assert!(json["ok"].as_bool().unwrap());
     }
 
+    #[tokio::test]
+    async fn test_agent_max_tool_rounds() {
+        let state = test_state();
+        let global = state.full_config.lock().unwrap().autonomy.max_tool_rounds;
+        let body = Json(serde_json::json!({"name": "researcher", "max_tool_rounds": 12}));
+        assert!(create_agent(state.clone(), body).await.0["ok"].as_bool().unwrap());
+        assert_eq!(list_agents(state.clone()).await.0["agents"][0]["max_tool_rounds"], 12);
+        assert_eq!(state.db.get_agent("researcher").unwrap().max_tool_rounds, Some(12));
+
+        let path = axum::extract::Path("researcher".to_string());
+        let body = Json(serde_json::json!({"max_tool_rounds": null}));
+        assert!(update_agent(state.clone(), path, body).await.0["ok"].as_bool().unwrap());
+        assert_eq!(list_agents(state.clone()).await.0["agents"][0]["max_tool_rounds"], global);
+        assert_eq!(state.db.get_agent("researcher").unwrap().max_tool_rounds, None);
+    }
+
     #[tokio::test]
     async fn test_update_nonexistent_agent() {
         let body = Json(serde_json::json!({"role": "coder"}));
@@ -3166,7 +6481,7 @@ mod tests {
             "role": "assistant",
             "description": "To be deleted"
         }));
-        create_agent(state.clone(), body).await;
+        let _ = create_agent(state.clone(), body).await;
 
         // Delete
         let result = delete_agent(state.clone(), axum::extract::Path("deleteme".to_string())).await;
@@ -3178,541 +6493,793 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_delete_nonexistent_agent() {
-        let result = delete_agent(test_state(), axum::extract::Path("ghost".to_string())).await;
-        assert!(!result.0["ok"].as_bool().unwrap());
-    }
-
-    // ---- Telegram Bot Status ----
-
-    #[tokio::test]
-    async fn test_telegram_status_not_connected() {
-        let result =
-            telegram_status(test_state(), axum::extract::Path("some-agent".to_string())).await;
-        let json = result.0;
-        assert!(json["ok"].as_bool().unwrap());
-        assert!(!json["connected"].as_bool().unwrap());
-    }
-
-    // ---- Knowledge Base ----
-
-    #[tokio::test]
-    async fn test_knowledge_list_docs_no_store() {
-        let result = knowledge_list_docs(test_state()).await;
-        let json = result.0;
-        // Should handle gracefully when no KB initialized
-        assert!(json.is_object());
-    }
-
-    #[tokio::test]
-    async fn test_knowledge_search_no_store() {
-        let body = Json(serde_json::json!({"query": "test"}));
-        let result = knowledge_search(test_state(), body).await;
-        let json = result.0;
-        assert!(json.is_object());
+    async fn test_mcp_endpoint_exports_tools_and_agents() {
+        let state = test_state();
+        let call = |body: serde_json::Value| crate::mcp_export::mcp_endpoint(state.clone(), body.to_string());
+        let list = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        assert_eq!(call(list.clone()).await.status(), axum::http::StatusCode::NOT_FOUND);
+
+        state.full_config.lock().unwrap().mcp_export.enabled = true;
+        let _ = create_agent(state.clone(), Json(serde_json::json!({"name": "sales team", "description": "Quotes"}))).await;
+        let body = axum::body::to_bytes(call(list).await.into_body(), usize::MAX).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["web_search", "http_request", "document_reader", "ask_sales_team"]);
+
+        // Tools that need approval in chat aren't exported
+        state.full_config.lock().unwrap().autonomy.require_approval = vec!["http_request".into()];
+        let list = serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+        let body = axum::body::to_bytes(call(list).await.into_body(), usize::MAX).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["web_search", "document_reader", "ask_sales_team"]);
+
+        // Calls go through the schema and argument filter checks
+        state.full_config.lock().unwrap().autonomy.argument_filters = vec![bizclaw_core::config::ArgumentFilter {
+            tool: "document_reader".into(),
+            argument: String::new(),
+            deny: Vec::new(),
+            deny_absolute_paths: true,
+        }];
+        let read = serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": {"name": "document_reader", "arguments": {"action": "read_file", "path": "/etc/passwd"}}});
+        let body = axum::body::to_bytes(call(read).await.into_body(), usize::MAX).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(reply["error"]["message"].as_str().unwrap().starts_with("Denied"), "{reply}");
+        let invalid = serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+            "params": {"name": "ask_sales_team", "arguments": {}}});
+        let body = axum::body::to_bytes(call(invalid).await.into_body(), usize::MAX).await.unwrap();
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(reply["error"]["code"], -32602, "{reply}");
+
+        let notification = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert_eq!(call(notification).await.status(), axum::http::StatusCode::ACCEPTED);
     }
 
-    // ---- Scheduler ----
-
     #[tokio::test]
-    async fn test_scheduler_list_tasks() {
-        let result = scheduler_list_tasks(test_state()).await;
-        let json = result.0;
-        assert!(json["ok"].as_bool().unwrap());
-        assert!(json["tasks"].is_array());
+    async fn test_agent_changes_are_audited() {
+        let state = test_state();
+        let _ = create_agent(state.clone(), Json(serde_json::json!({"name": "audited", "description": "v1"}))).await;
+        let _ = update_agent(
+            state.clone(),
+            axum::extract::Path("audited".to_string()),
+            Json(serde_json::json!({"description": "v2"})),
+        )
+        .await;
+        let _ = delete_agent(state.clone(), axum::extract::Path("audited".to_string())).await;
+
+        let filter = crate::db::AuditFilter {
+            action: Some("agent.".into()),
+            ..Default::default()
+        };
+        let entries = state.db.list_audit(&filter).unwrap();
+        let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["agent.delete", "agent.update", "agent.create"]);
+        assert!(entries.iter().all(|e| e.actor == "system" && e.target == "audited"));
+        let update = &entries[1];
+        assert!(update.changes.contains(&serde_json::json!({"field": "description", "from": "v1", "to": "v2"})));
+        assert!(entries[0].new_value.is_none());
+        assert_eq!(entries[0].old_value.as_ref().unwrap()["description"], "v2");
     }
 
     #[tokio::test]
-    async fn test_scheduler_notifications() {
-        let result = scheduler_notifications(test_state()).await;
-        let json = result.0;
-        assert!(json["ok"].as_bool().unwrap());
-    }
-}
-
-// ═══════════════════════════════════════════════════════
-// Gallery API — Manage skill templates
-// ═══════════════════════════════════════════════════════
-
-/// List all gallery skills (built-in + user-created).
-pub async fn gallery_list(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    let gallery_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("gallery.json");
-
-    // Load built-in skills from embedded data
-    let builtin: Vec<serde_json::Value> = serde_json::from_str(
-        include_str!("../../../data/gallery-skills.json")
-    ).unwrap_or_default();
-
-    // Load user-created skills
-    let user_skills: Vec<serde_json::Value> = if gallery_path.exists() {
-        std::fs::read_to_string(&gallery_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
-
-    // Check which skills have attached MD files
-    let skills_dir = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("skills");
-
-    let mut all_skills: Vec<serde_json::Value> = builtin.into_iter()
-        .map(|mut s| { s.as_object_mut().map(|o| o.insert("source".into(), "builtin".into())); s })
-        .collect();
-
-    for mut s in user_skills {
-        s.as_object_mut().map(|o| o.insert("source".into(), "user".into()));
-        all_skills.push(s);
-    }
-
-    // Check for attached MD files
-    for skill in &mut all_skills {
-        if let Some(id) = skill["id"].as_str() {
-            let md_path = skills_dir.join(format!("{}.md", id));
-            if md_path.exists() {
-                skill.as_object_mut().map(|o| o.insert("has_md".into(), true.into()));
-            }
-        }
-    }
-
-    Json(serde_json::json!({
-        "ok": true,
-        "skills": all_skills,
-        "total": all_skills.len(),
-    }))
-}
-
-/// Create a custom gallery skill.
-pub async fn gallery_create(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let gallery_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("gallery.json");
-
-    let mut skills: Vec<serde_json::Value> = if gallery_path.exists() {
-        std::fs::read_to_string(&gallery_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    async fn test_audit_scope_records_actor_and_status() {
+        use tower::ServiceExt;
+        let state = test_state();
+        let app = crate::server::build_router_from_arc(state.0.clone());
+        let post = |uri: &str, body: serde_json::Value| {
+            axum::http::Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post("/api/v1/agents", serde_json::json!({"name": "via-http"})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        // A handler without its own entry gets a generic one
+        let _ = app
+            .clone()
+            .oneshot(post("/api/v1/approvals/nope", serde_json::json!({"approve": true})))
+            .await
+            .unwrap();
+        // Dry runs and reads aren't changes
+        let _ = app
+            .clone()
+            .oneshot(post("/api/v1/config/update", serde_json::json!({"dry_run": true})))
+            .await
+            .unwrap();
+        let _ = app
+            .oneshot(axum::http::Request::get("/api/v1/agents").body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
 
-    let id = body["id"].as_str().unwrap_or("custom").to_string();
+        let entries = state.db.list_audit(&Default::default()).unwrap();
+        assert_eq!(entries.len(), 2, "{entries:?}");
+        assert_eq!(entries[0].action, "POST /api/v1/approvals/{id}");
+        assert_eq!(entries[0].target, "/api/v1/approvals/nope");
+        assert_eq!(entries[1].action, "agent.create");
+        assert_eq!(entries[1].actor, "anonymous");
+        assert_eq!(entries[1].status, 200);
 
-    // Check for duplicate
-    if skills.iter().any(|s| s["id"].as_str() == Some(&id)) {
-        return Json(serde_json::json!({"ok": false, "error": format!("Skill '{}' already exists", id)}));
+        let listed = crate::audit::list_audit(
+            state.clone(),
+            axum::extract::Query(crate::db::AuditFilter {
+                target: Some("via-http".into()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .0;
+        assert_eq!(listed["entries"].as_array().unwrap().len(), 1);
+        let id = entries[1].id;
+        let one = crate::audit::get_audit(state.clone(), axum::extract::Path(id)).await.0;
+        assert_eq!(one["entry"]["action"], "agent.create");
     }
 
-    skills.push(body.clone());
-
-    if let Ok(json) = serde_json::to_string_pretty(&skills) {
-        let _ = std::fs::write(&gallery_path, json);
+    #[tokio::test]
+    async fn test_delete_nonexistent_agent() {
+        let result = delete_agent(test_state(), axum::extract::Path("ghost".to_string())).await;
+        assert!(!result.0["ok"].as_bool().unwrap());
     }
 
-    Json(serde_json::json!({"ok": true, "id": id, "total": skills.len()}))
-}
+    // ---- Telegram Bot Status ----
 
-/// Delete a custom gallery skill.
-pub async fn gallery_delete(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<serde_json::Value> {
-    let gallery_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("gallery.json");
+    #[tokio::test]
+    async fn test_telegram_status_not_connected() {
+        let result =
+            telegram_status(test_state(), axum::extract::Path("some-agent".to_string())).await;
+        let json = result.0;
+        assert!(json["ok"].as_bool().unwrap());
+        assert!(!json["connected"].as_bool().unwrap());
+    }
 
-    let mut skills: Vec<serde_json::Value> = if gallery_path.exists() {
-        std::fs::read_to_string(&gallery_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    // ---- Discord Bot Status ----
 
-    let before = skills.len();
-    skills.retain(|s| s["id"].as_str() != Some(&id));
-    let removed = before != skills.len();
+    #[tokio::test]
+    async fn test_discord_connect_validation_and_status() {
+        let state = test_state();
+        let missing = connect_discord(
+            state.clone(),
+            axum::extract::Path("some-agent".to_string()),
+            Json(serde_json::json!({})),
+        )
+        .await;
+        assert_eq!(missing.0["error"], "bot_token is required");
+        let no_agent = connect_discord(
+            state.clone(),
+            axum::extract::Path("ghost".to_string()),
+            Json(serde_json::json!({"bot_token": "x"})),
+        )
+        .await;
+        assert!(no_agent.0["error"].as_str().unwrap().contains("not found"));
 
-    if removed {
-        if let Ok(json) = serde_json::to_string_pretty(&skills) {
-            let _ = std::fs::write(&gallery_path, json);
-        }
-        // Also remove any attached MD file
-        let skills_dir = state.config_path.parent()
-            .unwrap_or(std::path::Path::new("."))
-            .join("skills");
-        let md_path = skills_dir.join(format!("{}.md", id));
-        let _ = std::fs::remove_file(md_path);
+        let status = discord_status(state.clone(), axum::extract::Path("some-agent".to_string())).await;
+        assert!(!status.0["connected"].as_bool().unwrap());
+        let disconnected = disconnect_discord(state, axum::extract::Path("some-agent".to_string())).await;
+        assert_eq!(disconnected.0["ok"], false);
     }
 
-    Json(serde_json::json!({"ok": removed, "id": id}))
-}
-
-/// Upload an MD file for a gallery skill.
-pub async fn gallery_upload_md(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-    body: String,
-) -> Json<serde_json::Value> {
-    let skills_dir = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("skills");
-    let _ = std::fs::create_dir_all(&skills_dir);
+    // ---- Slack ----
 
-    let md_path = skills_dir.join(format!("{}.md", id));
-    match std::fs::write(&md_path, &body) {
-        Ok(_) => {
-            tracing::info!("📄 Uploaded skill MD: {}.md ({} bytes)", id, body.len());
-            Json(serde_json::json!({
-                "ok": true,
-                "id": id,
-                "size": body.len(),
-                "path": md_path.display().to_string(),
-            }))
-        }
-        Err(e) => internal_error("gateway", e),
+    #[tokio::test]
+    async fn test_slack_connect_validation_and_events_signature() {
+        let state = test_state();
+        let no_token = spawn_slack_bot(state.0.clone(), "a".into(), &serde_json::json!({}), "i".into()).await;
+        assert_eq!(no_token.unwrap_err(), "bot_token is required");
+        let no_mode = spawn_slack_bot(
+            state.0.clone(),
+            "a".into(),
+            &serde_json::json!({"bot_token": "xoxb-1"}),
+            "i".into(),
+        )
+        .await;
+        assert!(no_mode.unwrap_err().contains("signing_secret"));
+
+        let status = slack_status(state.clone(), axum::extract::Path("a".to_string())).await;
+        assert!(!status.0["connected"].as_bool().unwrap());
+        // No workspace connected: nothing can verify the request
+        let events = slack_events(
+            state,
+            axum::http::HeaderMap::new(),
+            r#"{"type":"url_verification","challenge":"c"}"#.into(),
+        )
+        .await;
+        assert_eq!(events.0["error"], "Invalid Slack signature");
     }
-}
 
-/// Get the MD content for a gallery skill.
-pub async fn gallery_get_md(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<serde_json::Value> {
-    let skills_dir = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("skills");
-    let md_path = skills_dir.join(format!("{}.md", id));
+    // ---- Email ----
 
-    if md_path.exists() {
-        let content = std::fs::read_to_string(&md_path).unwrap_or_default();
-        Json(serde_json::json!({"ok": true, "id": id, "content": content}))
-    } else {
-        Json(serde_json::json!({"ok": false, "error": "MD file not found"}))
+    #[tokio::test]
+    async fn test_email_connect_requires_settings() {
+        let state = test_state();
+        let err = spawn_email_listener(
+            state.0.clone(),
+            "a".into(),
+            &serde_json::json!({"imap_host": "imap.acme.test", "smtp_user": "bot@acme.test"}),
+            "i".into(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("password"));
+        let status = email_status(state, axum::extract::Path("a".to_string())).await;
+        assert!(!status.0["connected"].as_bool().unwrap());
     }
-}
 
-// ═══════════════════════════════════════════════════════
-// Agent-Channel Binding API
-// ═══════════════════════════════════════════════════════
+    // ---- Zalo OA ----
 
-/// Bind an agent to one or more channels.
-pub async fn agent_bind_channels(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(name): axum::extract::Path<String>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let channels = body["channels"].as_array()
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect::<Vec<_>>())
-        .unwrap_or_default();
+    #[tokio::test]
+    async fn test_zalo_webhook_requires_official_mode_and_signature() {
+        let state = test_state();
+        let body = r#"{"event_name":"user_send_text","sender":{"id":"1"},"message":{"text":"hi"}}"#;
+        let result = zalo_webhook(state.clone(), axum::http::HeaderMap::new(), body.into()).await;
+        assert_eq!(result.0["error"], "Zalo OA not configured");
 
-    // Store binding in agent-channels.json
-    let bindings_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("agent-channels.json");
+        {
+            let mut cfg = state.full_config.lock().unwrap();
+            let mut zalo = bizclaw_core::config::ZaloChannelConfig {
+                enabled: true,
+                mode: "official".into(),
+                ..Default::default()
+            };
+            zalo.official.app_id = "360".into();
+            zalo.official.oa_secret_key = "oa-secret".into();
+            cfg.channel.zalo = Some(zalo);
+        }
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-zevent-signature", "mac=00".parse().unwrap());
+        let result = zalo_webhook(state.clone(), headers, body.into()).await;
+        assert_eq!(result.0["error"], "Invalid Zalo signature");
+        assert!(state.zalo_oa.lock().unwrap().is_none());
+    }
 
-    let mut bindings: serde_json::Map<String, serde_json::Value> = if bindings_path.exists() {
-        std::fs::read_to_string(&bindings_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        serde_json::Map::new()
-    };
+    // ---- SMS ----
+
+    #[tokio::test]
+    async fn test_sms_webhook_always_checks_signature() {
+        let state = test_state();
+        state.full_config.lock().unwrap().channel.sms = Some(bizclaw_core::config::SmsChannelConfig {
+            enabled: true,
+            auth_token: "12345".into(),
+            ..Default::default()
+        });
+        // Example from Twilio's webhook security docs
+        let form: std::collections::HashMap<String, String> = [
+            ("CallSid", "CA1234567890ABCDE"),
+            ("Caller", "+12349013030"),
+            ("Digits", "1234"),
+            ("From", "+12349013030"),
+            ("To", "+18005551212"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let uri: axum::http::Uri = "/myapp.php?foo=1&bar=2".parse().unwrap();
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("host", "mycompany.com".parse().unwrap());
 
-    bindings.insert(name.clone(), serde_json::json!(channels));
+        let forged = sms_webhook(state.clone(), uri.clone(), headers.clone(), axum::Form(form.clone())).await;
+        assert_eq!(forged.status(), axum::http::StatusCode::FORBIDDEN);
 
-    if let Ok(json) = serde_json::to_string_pretty(&serde_json::Value::Object(bindings.clone())) {
-        let _ = std::fs::write(&bindings_path, json);
+        headers.insert("x-twilio-signature", "0/KCTR6DLpKmkAf8muzZqo1nDgQ=".parse().unwrap());
+        let signed = sms_webhook(state, uri, headers, axum::Form(form)).await;
+        assert_eq!(signed.status(), axum::http::StatusCode::OK);
     }
 
-    tracing::info!("🔗 Agent '{}' bound to channels: {:?}", name, channels);
-
-    Json(serde_json::json!({
-        "ok": true,
-        "agent": name,
-        "channels": channels,
-    }))
-}
+    // ---- Knowledge Base ----
 
-/// Get channel bindings for all agents.
-pub async fn agent_channel_bindings(
-    State(state): State<Arc<AppState>>,
-) -> Json<serde_json::Value> {
-    let bindings_path = state.config_path.parent()
-        .unwrap_or(std::path::Path::new("."))
-        .join("agent-channels.json");
+    #[tokio::test]
+    async fn test_knowledge_list_docs_no_store() {
+        let result = knowledge_list_docs(test_state()).await;
+        let json = result.0;
+        // Should handle gracefully when no KB initialized
+        assert!(json.is_object());
+    }
 
-    let bindings: serde_json::Value = if bindings_path.exists() {
-        std::fs::read_to_string(&bindings_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
-    };
+    #[tokio::test]
+    async fn test_knowledge_search_no_store() {
+        let body = Json(serde_json::json!({"query": "test"}));
+        let result = knowledge_search(test_state(), body).await;
+        let json = result.0;
+        assert!(json.is_object());
+    }
 
-    Json(serde_json::json!({
-        "ok": true,
-        "bindings": bindings,
-    }))
-}
+    #[tokio::test]
+    async fn test_knowledge_import_dir_batch() {
+        let state = test_state();
+        *state.knowledge.lock().await = Some(
+            bizclaw_knowledge::KnowledgeStore::open(std::path::Path::new(":memory:")).unwrap(),
+        );
 
-// ---- Orchestration API ----
+        let dir = std::env::temp_dir().join(format!("bizclaw-kb-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("hr")).unwrap();
+        std::fs::write(dir.join("intro.md"), "# Intro\nCompany handbook.").unwrap();
+        std::fs::write(dir.join("hr/leave.txt"), "Annual leave is 12 days. ".repeat(30)).unwrap();
+        std::fs::write(dir.join("hr/it.md"), "## IT\nVPN setup guide.").unwrap();
+        std::fs::write(dir.join("logo.png"), [0u8, 1, 2]).unwrap(); // skipped: not text
+
+        let import = |body: serde_json::Value| {
+            let state = state.clone();
+            async move {
+                let response = knowledge_import_dir(state, Json(body)).await;
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+        let body = serde_json::json!({"path": dir.to_string_lossy()});
+
+        // No import root configured: directory imports are off
+        let (status, _) = import(body.clone()).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+
+        state.full_config.lock().unwrap().knowledge.import_root = dir.to_string_lossy().into();
+        let (status, _) = import(serde_json::json!({"path": std::env::temp_dir().to_string_lossy()})).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+        let (status, _) = import(serde_json::json!({"path": dir.join("hr/..").join("..").to_string_lossy()})).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+
+        let (_, json) = import(body.clone()).await;
+        assert_eq!(json["ok"], true);
+        let summary = &json["summary"];
+        assert_eq!(summary["added"], 3);
+        assert_eq!(summary["files"].as_array().unwrap().len(), 3);
+        let per_file: u64 = summary["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["chunks"].as_u64().unwrap())
+            .sum();
+        assert_eq!(summary["total_chunks"].as_u64().unwrap(), per_file);
+        assert!(per_file >= 4);
+
+        // Re-import: every file unchanged
+        let (_, json) = import(body.clone()).await;
+        assert_eq!(json["summary"]["unchanged"], 3);
+        assert_eq!(json["summary"]["total_chunks"], 0);
+
+        // A symlink leading out of the root blocks the import
+        #[cfg(unix)]
+        {
+            let outside = std::env::temp_dir().join(format!("bizclaw-kb-outside-{}.toml", uuid::Uuid::new_v4()));
+            std::fs::write(&outside, "api_key = \"sk-secret\"").unwrap();
+            std::os::unix::fs::symlink(&outside, dir.join("hr/config.toml")).unwrap();
+            let (status, _) = import(body).await;
+            assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+            std::fs::remove_file(dir.join("hr/config.toml")).unwrap();
+            let _ = std::fs::remove_file(outside);
+        }
 
-/// Delegate a task from one agent to another.
-/// POST /api/v1/orchestration/delegate
-/// Body: {"from_agent": "a", "to_agent": "b", "task": "...", "mode": "sync|async"}
-pub async fn orch_delegate(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let from = body["from_agent"].as_str().unwrap_or("");
-    let to = body["to_agent"].as_str().unwrap_or("");
-    let task = body["task"].as_str().unwrap_or("");
+        let docs = knowledge_list_docs(state).await.0;
+        assert_eq!(docs["total_docs"], 3);
 
-    if from.is_empty() || to.is_empty() || task.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "from_agent, to_agent, and task are required"}));
+        let _ = std::fs::remove_dir_all(dir);
     }
 
-    let mode = match body["mode"].as_str().unwrap_or("sync") {
-        "async" => bizclaw_core::types::DelegationMode::Async,
-        _ => bizclaw_core::types::DelegationMode::Sync,
-    };
+    #[tokio::test]
+    async fn test_knowledge_upload_multipart() {
+        use axum::extract::FromRequest;
+        let state = test_state();
+        *state.knowledge.lock().await = Some(
+            bizclaw_knowledge::KnowledgeStore::open(std::path::Path::new(":memory:")).unwrap(),
+        );
 
-    let mut orch = state.orchestrator.lock().await;
-    match orch.delegate_with_mode(from, to, task, mode).await {
-        Ok(response) => Json(serde_json::json!({
-            "ok": true,
-            "from": from,
-            "to": to,
-            "response": safe_truncate(&response, 5000),
-        })),
-        Err(e) => {
-            tracing::error!("[orch_delegate] {e}");
-            internal_error("delegation", e)
-        }
+        let body = concat!(
+            "--X\r\nContent-Disposition: form-data; name=\"source\"\r\n\r\nhr-portal\r\n",
+            "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"docs/leave.html\"\r\n",
+            "Content-Type: text/html\r\n\r\n<html><body><nav>Menu</nav><p>Annual leave is 12 days.</p></body></html>\r\n",
+            "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"scan.pdf\"\r\n\r\nnot a pdf\r\n",
+            "--X--\r\n",
+        );
+        let request = axum::http::Request::builder()
+            .header("content-type", "multipart/form-data; boundary=X")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let multipart = axum::extract::Multipart::from_request(request, &()).await.unwrap();
+
+        let json = knowledge_upload(state.clone(), multipart).await.0;
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["summary"]["added"], 1);
+        assert_eq!(json["summary"]["failed"], 1);
+
+        let docs = knowledge_list_docs(state.clone()).await.0;
+        assert_eq!(docs["documents"][0]["name"], "leave.html");
+        assert_eq!(docs["documents"][0]["source"], "hr-portal");
+        let kb = state.knowledge.lock().await;
+        let hits = kb.as_ref().unwrap().search("annual leave", 5);
+        assert!(!hits.is_empty() && !hits[0].content.contains("Menu"));
     }
-}
 
-/// Handoff conversation from one agent to another.
-/// POST /api/v1/orchestration/handoff
-pub async fn orch_handoff(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let from = body["from_agent"].as_str().unwrap_or("");
-    let to = body["to_agent"].as_str().unwrap_or("");
-    let session = body["session_id"].as_str().unwrap_or("default");
-    let reason = body["reason"].as_str();
+    // ---- Scheduler ----
 
-    if from.is_empty() || to.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "from_agent and to_agent required"}));
+    #[tokio::test]
+    async fn test_scheduler_list_tasks() {
+        let result = scheduler_list_tasks(test_state()).await;
+        let json = result.0;
+        assert!(json["ok"].as_bool().unwrap());
+        assert!(json["tasks"].is_array());
     }
 
-    let mut orch = state.orchestrator.lock().await;
-    match orch.handoff(from, to, session, reason).await {
-        Ok(()) => Json(serde_json::json!({"ok": true, "from": from, "to": to, "session": session})),
-        Err(e) => {
-            tracing::error!("[orch_handoff] {e}");
-            internal_error("handoff", e)
-        }
+    #[tokio::test]
+    async fn test_scheduler_notifications() {
+        let result = scheduler_notifications(test_state()).await;
+        let json = result.0;
+        assert!(json["ok"].as_bool().unwrap());
     }
-}
 
-/// Clear handoff for a session.
-/// DELETE /api/v1/orchestration/handoff/{session_id}
-pub async fn orch_clear_handoff(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(session_id): axum::extract::Path<String>,
-) -> Json<serde_json::Value> {
-    let orch = state.orchestrator.lock().await;
-    match orch.clear_handoff(&session_id).await {
-        Ok(()) => Json(serde_json::json!({"ok": true, "session": session_id})),
-        Err(e) => internal_error("clear_handoff", e),
+    #[tokio::test]
+    async fn test_hands_list_and_run() {
+        let state = test_state();
+        let listed = hands_list(state.clone()).await;
+        assert_eq!(listed.0["count"], 7);
+
+        // No agent connected: the run is recorded as failed
+        let run = hand_run(state.clone(), axum::extract::Path("monitor".into())).await;
+        assert_eq!(run.0["ok"], false);
+        assert_eq!(run.0["run"]["status"], "failed");
+        let approve = hand_approve(state.clone(), axum::extract::Path("monitor".into())).await;
+        assert!(approve.0["error"].as_str().unwrap().contains("not awaiting approval"));
+        let missing = hand_run(state, axum::extract::Path("nope".into())).await;
+        assert_eq!(missing.0["ok"], false);
     }
-}
 
-/// Run evaluate loop between two agents.
-/// POST /api/v1/orchestration/evaluate
-pub async fn orch_evaluate(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let generator = body["generator"].as_str().unwrap_or("");
-    let evaluator = body["evaluator"].as_str().unwrap_or("");
-    let task = body["task"].as_str().unwrap_or("");
-    let pass_criteria = body["pass_criteria"].as_str().unwrap_or("high quality output");
-    let max_rounds = body["max_rounds"].as_u64().unwrap_or(3) as u32;
+    #[tokio::test]
+    async fn test_hand_enable_disable() {
+        let state = test_state();
+        let enabled = hand_enable(state.clone(), axum::extract::Path("monitor".into())).await;
+        assert_eq!(enabled.0["ok"], true);
+        assert!(state.full_config.lock().unwrap().hands.enabled.contains(&"monitor".to_string()));
+
+        let disabled = hand_disable(state.clone(), axum::extract::Path("monitor".into())).await;
+        assert_eq!(disabled.0["ok"], true);
+        assert!(state.full_config.lock().unwrap().hands.enabled.is_empty());
+        let listed = hands_list(state.clone()).await;
+        let monitor = listed.0["hands"].as_array().unwrap().iter().find(|h| h["name"] == "monitor").cloned().unwrap();
+        assert_eq!(monitor["enabled"], false);
+
+        let missing = hand_enable(state.clone(), axum::extract::Path("nope".into())).await;
+        assert_eq!(missing.0["ok"], false);
+        let builtin = hand_uninstall(state, axum::extract::Path("monitor".into())).await;
+        assert_eq!(builtin.0["ok"], false);
+    }
 
-    if generator.is_empty() || evaluator.is_empty() || task.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "generator, evaluator, and task required"}));
+    #[tokio::test]
+    async fn test_scheduler_dead_letters() {
+        let state = test_state();
+        let listed = scheduler_dead_letters(
+            state.clone(),
+            axum::extract::Query(std::collections::HashMap::new()),
+        )
+        .await;
+        assert_eq!(listed.0["ok"], true);
+        let replay = scheduler_replay_dead_letter(state, axum::extract::Path(-1)).await;
+        assert_eq!(replay.0["ok"], false);
     }
 
-    let config = bizclaw_core::types::EvaluateConfig {
-        generator: generator.to_string(),
-        evaluator: evaluator.to_string(),
-        task: task.to_string(),
-        pass_criteria: pass_criteria.to_string(),
-        max_rounds,
-    };
+    #[tokio::test]
+    async fn test_scheduler_add_reboot_and_anchored_tasks() {
+        let state = test_state();
+        let result = scheduler_add_task(
+            state.clone(),
+            Json(serde_json::json!({"name": "boot", "action": "up", "cron": "@reboot"})),
+        )
+        .await;
+        let boot_id = result.0["id"].as_str().unwrap().to_string();
 
-    let mut orch = state.orchestrator.lock().await;
-    match orch.evaluate_loop(&config).await {
-        Ok(result) => Json(serde_json::json!({
-            "ok": true,
-            "approved": result.approved,
-            "output": result.output,
-            "feedback": result.feedback,
-            "rounds_used": result.rounds_used,
-            "max_rounds": result.max_rounds,
-        })),
-        Err(e) => {
-            tracing::error!("[orch_evaluate] {e}");
-            internal_error("evaluate", e)
-        }
+        let result = scheduler_add_task(
+            state.clone(),
+            Json(serde_json::json!({
+                "name": "hourly", "action": "tick", "task_type": "interval",
+                "interval_secs": 3600, "anchor": "2026-01-01T00:00:00Z"
+            })),
+        )
+        .await;
+        let hourly_id = result.0["id"].as_str().unwrap().to_string();
+
+        let bad = scheduler_add_task(
+            state.clone(),
+            Json(serde_json::json!({
+                "name": "bad", "action": "x", "task_type": "interval", "anchor": "noon"
+            })),
+        )
+        .await;
+        assert_eq!(bad.0["ok"], false);
+
+        let engine = state.scheduler.lock().await;
+        let boot = engine.list_tasks().iter().find(|t| t.id == boot_id).unwrap();
+        assert!(matches!(boot.task_type, bizclaw_scheduler::TaskType::Reboot));
+        let hourly = engine.list_tasks().iter().find(|t| t.id == hourly_id).unwrap();
+        let next = hourly.next_run.unwrap();
+        assert_eq!(next.format("%M:%S").to_string(), "00:00");
+        drop(engine);
+
+        // Shared test store — don't leak tasks into other tests.
+        let mut engine = state.scheduler.lock().await;
+        engine.remove_task(&boot_id);
+        engine.remove_task(&hourly_id);
     }
-}
 
-/// Create a permission link between agents.
-/// POST /api/v1/orchestration/links
-pub async fn orch_create_link(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
-    let source = body["source"].as_str().unwrap_or("");
-    let target = body["target"].as_str().unwrap_or("");
-    let direction = match body["direction"].as_str().unwrap_or("outbound") {
-        "inbound" => bizclaw_core::types::LinkDirection::Inbound,
-        "bidirectional" => bizclaw_core::types::LinkDirection::Bidirectional,
-        _ => bizclaw_core::types::LinkDirection::Outbound,
-    };
+    #[tokio::test]
+    async fn test_workflow_crud_and_run() {
+        let state = test_state();
+        let toml = r#"
+id = "test-greeting-flow"
+name = "Greeting"
+
+[[steps]]
+id = "greet"
+action = "set"
+value = "Hello {{who}}"
+output = "greeting"
+
+[[steps]]
+id = "check"
+action = "if"
+condition = { var = "who", operator = "==", value = "team" }
+then = [{ id = "announce", action = "notify", message = "{{greeting}}!" }]
+"#;
+        let created = workflow_create(state.clone(), Json(serde_json::json!({"toml": toml}))).await;
+        assert_eq!(created.0["ok"], true, "{}", created.0);
+        let again = workflow_create(state.clone(), Json(serde_json::json!({"toml": toml}))).await;
+        assert!(again.0["error"].as_str().unwrap().contains("already exists"));
+        let invalid = workflow_create(state.clone(), Json(serde_json::json!({"name": "x", "steps": []}))).await;
+        assert_eq!(invalid.0["ok"], false);
+
+        let id = "test-greeting-flow".to_string();
+        let result = workflow_run(
+            state.clone(),
+            axum::extract::Path(id.clone()),
+            Some(Json(serde_json::json!({"inputs": {"who": "team"}}))),
+        )
+        .await;
+        let run = &result.0["run"];
+        assert_eq!(run["status"], "success");
+        assert_eq!(run["vars"]["announce.output"], "Hello team!");
 
-    if source.is_empty() || target.is_empty() {
-        return Json(serde_json::json!({"ok": false, "error": "source and target required"}));
-    }
+        let runs = workflow_runs(
+            state.clone(),
+            axum::extract::Path(id.clone()),
+            axum::extract::Query(std::collections::HashMap::new()),
+        )
+        .await;
+        assert_eq!(runs.0["runs"][0]["id"], run["id"]);
 
-    let orch = state.orchestrator.lock().await;
-    match orch.create_link(source, target, direction).await {
-        Ok(link) => Json(serde_json::json!({"ok": true, "id": link.id})),
-        Err(e) => internal_error("create_link", e),
+        let deleted = workflow_delete(state.clone(), axum::extract::Path(id.clone())).await;
+        assert_eq!(deleted.0["ok"], true);
+        assert_eq!(workflow_get(state, axum::extract::Path(id)).await.0["ok"], false);
     }
-}
 
-/// List all agent permission links.
-/// GET /api/v1/orchestration/links
-pub async fn orch_list_links(
-    State(state): State<Arc<AppState>>,
-) -> Json<serde_json::Value> {
-    let orch = state.orchestrator.lock().await;
-    match orch.list_links().await {
-        Ok(links) => {
-            let items: Vec<serde_json::Value> = links.iter().map(|l| serde_json::json!({
-                "id": l.id,
-                "source": l.source_agent,
-                "target": l.target_agent,
-                "direction": l.direction.to_string(),
-                "max_concurrent": l.max_concurrent,
-            })).collect();
-            Json(serde_json::json!({"ok": true, "links": items, "count": items.len()}))
-        }
-        Err(e) => internal_error("list_links", e),
+    #[tokio::test]
+    async fn test_workflow_rule_runs_on_channel_message() {
+        let state = test_state();
+        let toml = r#"
+id = "test-rule-flow"
+name = "Acme mail"
+
+[[steps]]
+id = "note"
+action = "notify"
+message = "Mail from {{event.sender}}"
+"#;
+        let created = workflow_create(state.clone(), Json(serde_json::json!({"toml": toml}))).await;
+        assert_eq!(created.0["ok"], true, "{}", created.0);
+        let rule = workflow_rule_create(
+            state.clone(),
+            Json(serde_json::json!({
+                "name": "acme mail",
+                "trigger_type": "any_message",
+                "trigger_config": {"channels": ["email"], "senders": ["@acme.test"]},
+                "action_type": "run_workflow",
+                "action_config": {"workflow_id": "test-rule-flow"}
+            })),
+        )
+        .await;
+        assert_eq!(rule.0["ok"], true, "{}", rule.0);
+        let rule_id = rule.0["rule"]["id"].as_str().unwrap().to_string();
+        let listed = workflow_rules_list(state.clone()).await;
+        assert!(listed.0["rules"].as_array().unwrap().iter().any(|r| r["id"] == rule_id.as_str()));
+
+        let event = bizclaw_core::events::Event::MessageReceived {
+            channel: "email".into(),
+            sender: "ceo@acme.test".into(),
+            chat_id: "inbox".into(),
+            text: "Quarterly numbers".into(),
+        };
+        let runner = workflow_runner(state.orchestrator.clone());
+        let ran = bizclaw_scheduler::triggers::handle_event(&state.scheduler, &runner, &event).await;
+        assert_eq!(ran, 1);
+        let runs = workflow_runs(
+            state.clone(),
+            axum::extract::Path("test-rule-flow".into()),
+            axum::extract::Query(std::collections::HashMap::new()),
+        )
+        .await;
+        assert_eq!(runs.0["runs"][0]["vars"]["note.output"], "Mail from ceo@acme.test");
+
+        assert_eq!(workflow_rule_delete(state.clone(), axum::extract::Path(rule_id)).await.0["ok"], true);
+        let _ = workflow_delete(state, axum::extract::Path("test-rule-flow".into())).await;
     }
-}
 
-/// Delete a permission link.
-/// DELETE /api/v1/orchestration/links/{id}
-pub async fn orch_delete_link(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<serde_json::Value> {
-    let orch = state.orchestrator.lock().await;
-    match orch.delete_link(&id).await {
-        Ok(()) => Json(serde_json::json!({"ok": true})),
-        Err(e) => internal_error("delete_link", e),
+    #[tokio::test]
+    async fn test_background_jobs() {
+        let state = test_state();
+        let poll = |id: String| {
+            let state = state.clone();
+            async move {
+                for _ in 0..100 {
+                    let job = crate::jobs::get_job(state.clone(), axum::extract::Path(id.clone())).await.0;
+                    if !matches!(job["job"]["status"].as_str(), Some("queued" | "running")) {
+                        return job["job"].clone();
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+                panic!("job {id} didn't finish");
+            }
+        };
+
+        // A broadcast to no agents finishes at once
+        let created = agent_broadcast(state.clone(), Json(serde_json::json!({"message": "hi", "background": true}))).await;
+        assert_eq!(created.0["ok"], true, "{}", created.0);
+        assert_eq!(created.0["job"]["status"], "queued");
+        let job = poll(created.0["job"]["id"].as_str().unwrap().to_string()).await;
+        assert_eq!(job["status"], "succeeded");
+        assert_eq!(job["result"]["responses"], serde_json::json!([]));
+
+        // A slow job is stopped by cancel
+        let slow = crate::jobs::spawn(&state.0, "test_sleep", serde_json::json!({}), |ctx| async move {
+            ctx.progress(Some(0.1), "sleeping");
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(serde_json::json!({}))
+        })
+        .unwrap();
+        let cancelled = crate::jobs::cancel_job(state.clone(), axum::extract::Path(slow.id.clone())).await;
+        assert_eq!(cancelled.0["ok"], true, "{}", cancelled.0);
+        assert_eq!(poll(slow.id.clone()).await["status"], "cancelled");
+        let again = crate::jobs::cancel_job(state.clone(), axum::extract::Path(slow.id)).await;
+        assert_eq!(again.0["ok"], false);
+
+        let unknown = crate::jobs::create_job(state.clone(), Json(serde_json::json!({"kind": "mine_bitcoin"}))).await;
+        assert_eq!(unknown.0["ok"], false);
+        assert!(unknown.0["error"].as_str().unwrap().contains("model_download"));
+        let bad_model = crate::jobs::create_job(
+            state.clone(),
+            Json(serde_json::json!({"kind": "model_download", "params": {"model": "gpt-17"}})),
+        )
+        .await;
+        assert_eq!(bad_model.0["ok"], false);
+
+        let listed = crate::jobs::list_jobs(
+            state.clone(),
+            axum::extract::Query(crate::jobs::JobsQuery { kind: Some("agent_broadcast".into()), ..Default::default() }),
+        )
+        .await;
+        assert_eq!(listed.0["jobs"].as_array().unwrap().len(), 1);
     }
-}
 
-/// List delegation history.
-/// GET /api/v1/orchestration/delegations?agent=name&limit=20
-pub async fn orch_list_delegations(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<serde_json::Value> {
-    let agent = params.get("agent").map(|s| s.as_str()).unwrap_or("*");
-    let limit = params.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(20);
+    #[tokio::test]
+    async fn test_agent_workspace_files() {
+        use tower::ServiceExt;
+        let state = test_state();
+        let created = create_agent(state.clone(), Json(serde_json::json!({"name": "ws-files"}))).await;
+        assert_eq!(created.0["ok"], true, "{}", created.0);
+        let app = crate::server::build_router_from_arc(state.0.clone());
+        let send = |request: axum::http::Request<axum::body::Body>| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let headers = response.headers().clone();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (headers, body)
+            }
+        };
+        let json = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap();
 
-    let store = &state.orch_store;
-    let delegations = if agent == "*" {
-        // Get all — use traces as proxy (list_delegations requires agent)
-        store.list_delegations("", limit).await.unwrap_or_default()
-    } else {
-        store.list_delegations(agent, limit).await.unwrap_or_default()
-    };
+        let upload = concat!(
+            "--X\r\nContent-Disposition: form-data; name=\"dir\"\r\n\r\nreports\r\n",
+            "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"../../q3.csv\"\r\n\r\nregion,total\r\nnorth,12\r\n",
+            "--X--\r\n",
+        );
+        let (_, body) = send(
+            axum::http::Request::post("/api/v1/agents/ws-files/files")
+                .header("content-type", "multipart/form-data; boundary=X")
+                .body(axum::body::Body::from(upload))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json(&body)["files"][0]["path"], "reports/q3.csv", "{}", json(&body));
 
-    let items: Vec<serde_json::Value> = delegations.iter().map(|d| serde_json::json!({
-        "id": d.id,
-        "from": d.from_agent,
-        "to": d.to_agent,
-        "task": safe_truncate(&d.task, 200),
-        "status": format!("{:?}", d.status),
-        "mode": format!("{:?}", d.mode),
-        "created_at": d.created_at.to_rfc3339(),
-    })).collect();
+        let (_, body) = send(axum::http::Request::get("/api/v1/agents/ws-files/files").body(axum::body::Body::empty()).unwrap()).await;
+        assert_eq!(json(&body)["files"][0]["size"], 22);
 
-    Json(serde_json::json!({"ok": true, "delegations": items, "count": items.len()}))
-}
+        let (headers, body) = send(
+            axum::http::Request::get("/api/v1/agents/ws-files/files/download?path=reports/q3.csv")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(&body[..], b"region,total\r\nnorth,12");
+        assert_eq!(headers["content-disposition"], "attachment; filename=\"q3.csv\"");
+
+        // Outside the workspace and absolute paths, even with workspace_only
+        // off, and the secret store inside it
+        state.full_config.lock().unwrap().autonomy.workspace_only = false;
+        let workspace = crate::workspace::workspace_dir(&state.config_path, "ws-files");
+        std::fs::write(workspace.join("secrets.key"), "key").unwrap();
+        for path in ["../../test_config.toml", "/etc/hostname", "/tmp/test_config.toml", "secrets.key"] {
+            let (_, body) = send(
+                axum::http::Request::get(format!("/api/v1/agents/ws-files/files/download?path={path}"))
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await;
+            assert_eq!(json(&body)["ok"], false, "{path}");
+        }
+        std::fs::remove_file(workspace.join("secrets.key")).unwrap();
 
-/// List LLM traces (observability).
-/// GET /api/v1/orchestration/traces?limit=50
-pub async fn orch_list_traces(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<serde_json::Value> {
-    let limit = params.get("limit").and_then(|s| s.parse::<usize>().ok()).unwrap_or(50);
+        let (_, body) = send(
+            axum::http::Request::delete("/api/v1/agents/ws-files/files?path=reports")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json(&body)["ok"], true);
+        let (_, body) = send(axum::http::Request::get("/api/v1/agents/ws-files/files").body(axum::body::Body::empty()).unwrap()).await;
+        assert_eq!(json(&body)["files"], serde_json::json!([]));
+        let (_, body) = send(axum::http::Request::get("/api/v1/agents/nobody/files").body(axum::body::Body::empty()).unwrap()).await;
+        assert_eq!(json(&body)["ok"], false);
 
-    let orch = state.orchestrator.lock().await;
-    let traces = orch.list_traces(limit).await.unwrap_or_default();
+        let _ = delete_agent(state, axum::extract::Path("ws-files".to_string())).await;
+    }
 
-    let items: Vec<serde_json::Value> = traces.iter().map(|t| serde_json::json!({
-        "id": t.id,
-        "agent": t.agent_name,
-        "provider": t.provider,
-        "model": t.model,
-        "tokens": t.total_tokens,
-        "latency_ms": t.latency_ms,
-        "cache_hit": t.cache_hit,
-        "status": t.status,
-        "created_at": t.created_at.to_rfc3339(),
-    })).collect();
+    #[tokio::test]
+    async fn test_orchestration_route() {
+        let state = test_state();
+        for name in ["route-support", "route-sales"] {
+            let created = create_agent(state.clone(), Json(serde_json::json!({"name": name}))).await;
+            assert_eq!(created.0["ok"], true, "{}", created.0);
+        }
+        state.orchestrator.lock().await.set_router(bizclaw_agent::routing::Router::new(vec![
+            bizclaw_core::config::RoutingRule {
+                agent: "route-sales".into(),
+                keywords: vec!["báo giá".into()],
+            },
+        ]));
 
-    Json(serde_json::json!({"ok": true, "traces": items, "count": items.len()}))
-}
+        let routed = orch_route(state.clone(), Json(serde_json::json!({"message": "Gửi báo giá giúp tôi"}))).await;
+        assert_eq!(routed.0["route"], serde_json::json!({"agent": "route-sales", "via": "rule", "keyword": "báo giá"}));
+        let routed = orch_route(state.clone(), Json(serde_json::json!({"message": ""}))).await;
+        assert_eq!(routed.0["ok"], false);
 
-// ═══ MCP Servers API ═══
-pub async fn mcp_list_servers(
-    State(state): State<Arc<AppState>>,
-) -> Json<serde_json::Value> {
-    let config = state.full_config.lock().unwrap();
-    let servers: Vec<serde_json::Value> = config.mcp_servers.iter().map(|s| {
-        serde_json::json!({
-            "name": s.name,
-            "transport": "stdio",
-            "command": s.command,
-            "args": s.args,
-            "enabled": s.enabled,
-            "tools_count": 0,
-            "status": if s.enabled { "configured" } else { "disabled" },
-        })
-    }).collect();
-    Json(serde_json::json!({"ok": true, "servers": servers, "count": servers.len()}))
+        for name in ["route-support", "route-sales"] {
+            let _ = delete_agent(state.clone(), axum::extract::Path(name.to_string())).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_run_validation() {
+        let state = test_state();
+        let run = |body: serde_json::Value| pipeline_run(state.clone(), Json(body));
+        let res = run(serde_json::json!({"input": "", "steps": [{"agent": "a"}]})).await;
+        assert_eq!(res.0["error"], "input is required");
+        let res = run(serde_json::json!({"input": "Q3", "steps": []})).await;
+        assert_eq!(res.0["error"], "steps are required");
+        let res = run(serde_json::json!({"input": "Q3", "steps": [{"instruction": "no agent"}]})).await;
+        assert!(res.0["error"].as_str().unwrap().starts_with("Invalid pipeline"));
+        let res = run(serde_json::json!({"input": "Q3", "steps": [{"agent": "nobody"}]})).await;
+        assert_eq!(res.0["ok"], false);
+        assert!(res.0["error"].as_str().unwrap().contains("nobody"));
+    }
 }
@@ -25,9 +25,13 @@ pub struct AppState {
     pub pairing_code: Arc<Mutex<String>>,
     /// Brute-force protection — (failed_count, last_failed_at)
     pub auth_failures: Arc<tokio::sync::Mutex<(u32, std::time::Instant)>>,
+    /// Per-minute request counts of API keys with a rate limit.
+    pub api_key_limiter: Arc<super::api_keys::ApiKeyLimiter>,
     /// The Agent engine — handles chat with tools, memory, and all providers.
     pub agent: Arc<tokio::sync::Mutex<Option<bizclaw_agent::Agent>>>,
-    /// Multi-Agent Orchestrator — manages multiple named agents.
+    /// Multi-Agent Orchestrator — manages multiple named agents. Send
+    /// messages through a clone taken out of the lock, so agents answer
+    /// concurrently and a slow one doesn't hold up the others.
     pub orchestrator: Arc<tokio::sync::Mutex<bizclaw_agent::orchestrator::Orchestrator>>,
     /// Scheduler engine — manages scheduled tasks and notifications.
     pub scheduler: Arc<tokio::sync::Mutex<bizclaw_scheduler::SchedulerEngine>>,
@@ -35,6 +39,22 @@ pub struct AppState {
     pub knowledge: Arc<tokio::sync::Mutex<Option<bizclaw_knowledge::KnowledgeStore>>>,
     /// Active Telegram bot polling tasks — maps agent_name → abort handle.
     pub telegram_bots: Arc<tokio::sync::Mutex<HashMap<String, TelegramBotState>>>,
+    /// Active Discord Gateway connections — maps agent_name → abort handle.
+    pub discord_bots: Arc<tokio::sync::Mutex<HashMap<String, DiscordBotState>>>,
+    /// Connected Slack workspace bots — maps agent_name → bot.
+    pub slack_bots: Arc<tokio::sync::Mutex<HashMap<String, SlackBotState>>>,
+    /// Email inbox listeners — maps agent_name → abort handle.
+    pub email_listeners: Arc<tokio::sync::Mutex<HashMap<String, EmailListenerState>>>,
+    /// Zalo OA client ("official" mode) — built on the first webhook event,
+    /// reset when the Zalo config is saved.
+    pub zalo_oa: Arc<std::sync::Mutex<Option<bizclaw_channels::zalo::client::oa::ZaloOaClient>>>,
+    /// Middleware every channel message and reply passes through — rebuilt
+    /// when channel settings are saved.
+    pub channel_pipeline: Arc<bizclaw_channels::middleware::Pipeline>,
+    /// Channel types connectable by name from channel instances.
+    pub channel_registry: Arc<bizclaw_channels::registry::ChannelRegistry>,
+    /// Running registry-built channels — maps instance id → abort handle.
+    pub adapter_channels: Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Notify>>>>,
     /// Per-tenant SQLite database for persistent CRUD (providers, agents, channels, settings).
     pub db: Arc<super::db::GatewayDb>,
     /// Orchestration DataStore — delegations, teams, handoffs, traces.
@@ -45,6 +65,15 @@ pub struct AppState {
     pub activity_tx: tokio::sync::broadcast::Sender<super::openai_compat::ActivityEvent>,
     /// Activity log — keeps recent events for REST polling.
     pub activity_log: Arc<Mutex<Vec<super::openai_compat::ActivityEvent>>>,
+    /// Tool calls waiting for a human decision (`autonomy.require_approval`).
+    pub approvals: bizclaw_agent::approval::ApprovalQueue,
+    /// Event bus — channels publish received messages, agents tool calls,
+    /// the scheduler finished tasks; workflow rules subscribe.
+    pub events: bizclaw_core::events::EventBus,
+    /// Autonomous Hands — scheduled multi-phase playbooks run on agents.
+    pub hands: bizclaw_hands::HandRunner,
+    /// Background jobs (model downloads, crawls, broadcasts, …).
+    pub jobs: Arc<super::jobs::JobQueue>,
 }
 
 /// State for an active Telegram bot connected to an agent.
@@ -55,6 +84,38 @@ pub struct TelegramBotState {
     pub abort_handle: Arc<tokio::sync::Notify>,
 }
 
+/// State for an active Discord bot connected to an agent.
+#[derive(Clone)]
+pub struct DiscordBotState {
+    pub bot_username: String,
+    pub abort_handle: Arc<tokio::sync::Notify>,
+}
+
+/// State for a Slack workspace bot connected to an agent.
+#[derive(Clone)]
+pub struct SlackBotState {
+    pub bot_username: String,
+    pub bot_user_id: String,
+    pub team_id: String,
+    pub team: String,
+    /// Events arrive over Socket Mode rather than the Events API.
+    pub socket_mode: bool,
+    pub signing_secret: String,
+    /// Channels the bot answers in (empty = all). DMs are always answered.
+    pub allowed_channels: Vec<String>,
+    pub client: Arc<bizclaw_channels::slack::SlackChannel>,
+    /// Threads the bot replied in (`channel:thread_ts`), answered without a mention.
+    pub threads: Arc<Mutex<std::collections::HashSet<String>>>,
+    pub abort_handle: Arc<tokio::sync::Notify>,
+}
+
+/// State for an email inbox connected to an agent.
+#[derive(Clone)]
+pub struct EmailListenerState {
+    pub address: String,
+    pub abort_handle: Arc<tokio::sync::Notify>,
+}
+
 /// Serve the NEW Preact-based dashboard (no-cache to prevent stale JS after deploys).
 async fn dashboard_page() -> axum::response::Response {
     axum::response::Response::builder()
@@ -95,16 +156,21 @@ async fn dashboard_static(
     }
 }
 
-/// Pairing code auth middleware — validates X-Pairing-Code header or ?code= query.
+/// Auth middleware — validates an API key (`Authorization: Bearer bzk_…` or
+/// `X-API-Key`, checked against its scopes and rate limit), or the pairing
+/// code from the X-Pairing-Code header or ?code= query. Authenticated
+/// requests run inside an audit scope (see [`super::audit`]).
 async fn require_pairing(
     State(state): State<Arc<AppState>>,
     req: axum::http::Request<axum::body::Body>,
     next: axum::middleware::Next,
 ) -> axum::response::Response {
-    // If no pairing code configured, allow all
+    let api_key = super::api_keys::request_key(req.headers());
+
+    // If no pairing code configured, allow all (API keys still get their scopes)
     let expected = state.pairing_code.lock().unwrap().clone();
-    if expected.is_empty() {
-        return next.run(req).await;
+    if expected.is_empty() && api_key.is_none() {
+        return super::audit::run(&state, "anonymous".into(), req, next).await;
     }
 
     // Brute-force protection: lock out after 5 failed attempts for 60s
@@ -123,6 +189,22 @@ async fn require_pairing(
         }
     }
 
+    if let Some(key) = api_key {
+        let required = super::api_keys::Scope::required_for(req.method(), req.uri().path());
+        return match super::api_keys::authorize(&state, &key, required) {
+            Ok(record) => super::audit::run(&state, format!("api_key:{}", record.name), req, next).await,
+            Err(denied) => {
+                if denied == super::api_keys::Denied::UnknownKey {
+                    let mut failures = state.auth_failures.lock().await;
+                    failures.0 += 1;
+                    failures.1 = std::time::Instant::now();
+                    tracing::warn!("[security] Unknown API key, failed attempt #{}", failures.0);
+                }
+                axum::response::IntoResponse::into_response(denied)
+            }
+        };
+    }
+
     // Check header first
     let from_header = req
         .headers()
@@ -133,7 +215,8 @@ async fn require_pairing(
         // Reset failures on success
         let mut failures = state.auth_failures.lock().await;
         *failures = (0, std::time::Instant::now());
-        return next.run(req).await;
+        drop(failures);
+        return super::audit::run(&state, "dashboard".into(), req, next).await;
     }
 
     // Check query param ?code=
@@ -141,7 +224,7 @@ async fn require_pairing(
         for pair in query.split('&') {
             if let Some(code) = pair.strip_prefix("code=")
                 && constant_time_eq(code, &expected) {
-                    return next.run(req).await;
+                    return super::audit::run(&state, "dashboard".into(), req, next).await;
                 }
         }
     }
@@ -213,6 +296,33 @@ async fn security_headers(
     response
 }
 
+/// Metrics middleware — request count and latency per route. Labels use the
+/// route pattern (`/api/v1/agents/{name}`), not the path, so ids and names
+/// don't multiply the series.
+async fn record_http_metrics(
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let started = std::time::Instant::now();
+    let response = next.run(req).await;
+    let status = response.status().as_u16().to_string();
+    bizclaw_core::metrics::increment(
+        "bizclaw_http_requests_total",
+        &[("method", &method), ("route", &route), ("status", &status)],
+    );
+    bizclaw_core::metrics::observe(
+        "bizclaw_http_request_duration_seconds",
+        &[("method", &method), ("route", &route)],
+        started.elapsed().as_secs_f64(),
+    );
+    response
+}
+
 /// Build the Axum router with all routes.
 pub fn build_router(state: AppState) -> Router {
     build_router_from_arc(Arc::new(state))
@@ -220,12 +330,28 @@ pub fn build_router(state: AppState) -> Router {
 
 pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
 
-    // Protected routes — require valid pairing code
+    // Protected routes — require valid pairing code or a scoped API key
     let protected = Router::new()
         .route("/api/v1/info", get(super::routes::system_info))
+        // API keys (admin scope)
+        .route(
+            "/api/v1/api-keys",
+            get(super::api_keys::list_api_keys).post(super::api_keys::create_api_key),
+        )
+        .route(
+            "/api/v1/api-keys/{id}",
+            axum::routing::delete(super::api_keys::delete_api_key),
+        )
         .route("/api/v1/config", get(super::routes::get_config))
         .route("/api/v1/config/update", post(super::routes::update_config))
+        // Audit log (admin scope)
+        .route("/api/v1/audit", get(super::audit::list_audit))
+        .route("/api/v1/audit/{id}", get(super::audit::get_audit))
         .route("/api/v1/config/full", get(super::routes::get_full_config))
+        // Background jobs
+        .route("/api/v1/jobs", get(super::jobs::list_jobs).post(super::jobs::create_job))
+        .route("/api/v1/jobs/{id}", get(super::jobs::get_job))
+        .route("/api/v1/jobs/{id}/cancel", post(super::jobs::cancel_job))
         .route("/api/v1/providers", get(super::routes::list_providers))
         .route("/api/v1/providers", post(super::routes::create_provider))
         .route("/api/v1/providers/{name}", put(super::routes::update_provider))
@@ -237,6 +363,7 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             post(super::routes::update_channel),
         )
         // Multi-instance channel management
+        .route("/api/v1/channel-types", get(super::routes::list_channel_types))
         .route("/api/v1/channel-instances", get(super::routes::list_channel_instances))
         .route("/api/v1/channel-instances", post(super::routes::save_channel_instance))
         .route("/api/v1/channel-instances/{id}", axum::routing::delete(super::routes::delete_channel_instance))
@@ -263,6 +390,51 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             "/api/v1/scheduler/tasks/{id}/toggle",
             post(super::routes::scheduler_toggle_task),
         )
+        .route(
+            "/api/v1/scheduler/tasks/{id}/runs",
+            get(super::routes::scheduler_task_runs),
+        )
+        .route(
+            "/api/v1/workflows",
+            get(super::routes::workflows_list).post(super::routes::workflow_create),
+        )
+        .route(
+            "/api/v1/workflows/{id}",
+            get(super::routes::workflow_get)
+                .put(super::routes::workflow_update)
+                .delete(super::routes::workflow_delete),
+        )
+        .route(
+            "/api/v1/workflows/{id}/run",
+            post(super::routes::workflow_run),
+        )
+        .route(
+            "/api/v1/workflows/{id}/runs",
+            get(super::routes::workflow_runs),
+        )
+        .route(
+            "/api/v1/workflow-rules",
+            get(super::routes::workflow_rules_list).post(super::routes::workflow_rule_create),
+        )
+        .route(
+            "/api/v1/workflow-rules/{id}",
+            axum::routing::delete(super::routes::workflow_rule_delete),
+        )
+        .route("/api/v1/hands", get(super::routes::hands_list))
+        .route("/api/v1/hands/installed", get(super::routes::hands_installed))
+        .route("/api/v1/hands/{name}", axum::routing::delete(super::routes::hand_uninstall))
+        .route("/api/v1/hands/{name}/enable", post(super::routes::hand_enable))
+        .route("/api/v1/hands/{name}/disable", post(super::routes::hand_disable))
+        .route("/api/v1/hands/{name}/run", post(super::routes::hand_run))
+        .route("/api/v1/hands/{name}/approve", post(super::routes::hand_approve))
+        .route(
+            "/api/v1/scheduler/dead-letters",
+            get(super::routes::scheduler_dead_letters),
+        )
+        .route(
+            "/api/v1/scheduler/dead-letters/{id}/replay",
+            post(super::routes::scheduler_replay_dead_letter),
+        )
         .route(
             "/api/v1/scheduler/notifications",
             get(super::routes::scheduler_notifications),
@@ -284,7 +456,21 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             "/api/v1/knowledge/documents/{id}",
             axum::routing::delete(super::routes::knowledge_remove_doc),
         )
+        .route(
+            "/api/v1/knowledge/import-dir",
+            post(super::routes::knowledge_import_dir),
+        )
+        .route(
+            "/api/v1/knowledge/upload",
+            post(super::routes::knowledge_upload),
+        )
+        .route(
+            "/api/v1/knowledge/crawl",
+            post(super::routes::knowledge_crawl),
+        )
         // Multi-Agent Orchestrator API
+        .route("/api/v1/approvals", get(super::routes::list_approvals))
+        .route("/api/v1/approvals/{id}", post(super::routes::decide_approval))
         .route("/api/v1/agents", get(super::routes::list_agents))
         .route("/api/v1/agents", post(super::routes::create_agent))
         .route(
@@ -296,12 +482,33 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             "/api/v1/agents/{name}/chat",
             post(super::routes::agent_chat),
         )
+        .route(
+            "/api/v1/agents/{name}/chat/stream",
+            post(super::routes::agent_chat_stream),
+        )
+        .route(
+            "/api/v1/agents/{name}/structured",
+            post(super::routes::agent_structured),
+        )
         .route(
             "/api/v1/agents/broadcast",
             post(super::routes::agent_broadcast),
         )
+        // Agent workspace files
+        .route(
+            "/api/v1/agents/{name}/files",
+            get(super::workspace::list_files)
+                .post(super::workspace::upload_files)
+                .delete(super::workspace::delete_file)
+                .layer(DefaultBodyLimit::max(super::workspace::MAX_UPLOAD_BYTES)),
+        )
+        .route(
+            "/api/v1/agents/{name}/files/download",
+            get(super::workspace::download_file),
+        )
         // Orchestration API
         .route("/api/v1/orchestration/delegate", post(super::routes::orch_delegate))
+        .route("/api/v1/orchestration/route", post(super::routes::orch_route))
         .route("/api/v1/orchestration/handoff", post(super::routes::orch_handoff))
         .route("/api/v1/orchestration/handoff/{session_id}", axum::routing::delete(super::routes::orch_clear_handoff))
         .route("/api/v1/orchestration/evaluate", post(super::routes::orch_evaluate))
@@ -309,6 +516,8 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
         .route("/api/v1/orchestration/links/{id}", axum::routing::delete(super::routes::orch_delete_link))
         .route("/api/v1/orchestration/delegations", get(super::routes::orch_list_delegations))
         .route("/api/v1/orchestration/traces", get(super::routes::orch_list_traces))
+        // Agent pipelines (researcher → writer → reviewer)
+        .route("/api/v1/pipelines/run", post(super::routes::pipeline_run))
         // Gallery API
         .route("/api/v1/gallery", get(super::routes::gallery_list))
         .route("/api/v1/gallery", post(super::routes::gallery_create))
@@ -346,6 +555,45 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             "/api/v1/agents/{name}/telegram",
             get(super::routes::telegram_status),
         )
+        // Discord Bot ↔ Agent API
+        .route(
+            "/api/v1/agents/{name}/discord",
+            post(super::routes::connect_discord),
+        )
+        .route(
+            "/api/v1/agents/{name}/discord",
+            axum::routing::delete(super::routes::disconnect_discord),
+        )
+        .route(
+            "/api/v1/agents/{name}/discord",
+            get(super::routes::discord_status),
+        )
+        // Slack Bot ↔ Agent API
+        .route(
+            "/api/v1/agents/{name}/slack",
+            post(super::routes::connect_slack),
+        )
+        .route(
+            "/api/v1/agents/{name}/slack",
+            axum::routing::delete(super::routes::disconnect_slack),
+        )
+        .route(
+            "/api/v1/agents/{name}/slack",
+            get(super::routes::slack_status),
+        )
+        // Email Inbox ↔ Agent API
+        .route(
+            "/api/v1/agents/{name}/email",
+            post(super::routes::connect_email),
+        )
+        .route(
+            "/api/v1/agents/{name}/email",
+            axum::routing::delete(super::routes::disconnect_email),
+        )
+        .route(
+            "/api/v1/agents/{name}/email",
+            get(super::routes::email_status),
+        )
         // Brain Workspace API
         .route("/api/v1/brain/files", get(super::routes::brain_list_files))
         .route(
@@ -373,7 +621,14 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
         .route("/api/v1/activity", get(super::openai_compat::list_activity))
         // MCP Servers API (stub — returns configured MCP servers)
         .route("/api/v1/mcp/servers", get(super::routes::mcp_list_servers))
+        .route("/api/v1/mcp/resources", get(super::routes::mcp_list_resources))
+        .route("/api/v1/mcp/resources/read", get(super::routes::mcp_read_resource))
+        .route("/api/v1/mcp/prompts", get(super::routes::mcp_list_prompts))
+        // MCP endpoint — BizClaw's tools and agents for MCP clients ([mcp_export])
+        .route("/mcp", post(super::mcp_export::mcp_endpoint))
         .route("/ws", get(super::ws::ws_handler))
+        // Prometheus scrape endpoint (read scope)
+        .route("/metrics", get(super::routes::metrics))
         .route_layer(axum::middleware::from_fn_with_state(
             shared.clone(),
             require_pairing,
@@ -383,7 +638,7 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
     let public = Router::new()
         .route("/", get(dashboard_page))
         .route("/legacy", get(legacy_dashboard_page))
-        .route("/static/dashboard/*path", get(dashboard_static))
+        .route("/static/dashboard/{*path}", get(dashboard_static))
         .route("/health", get(super::routes::health_check))
         .route("/api/v1/verify-pairing", post(verify_pairing))
         // WhatsApp webhook — must be public for Meta verification
@@ -391,8 +646,14 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             "/api/v1/webhook/whatsapp",
             get(super::routes::whatsapp_webhook_verify).post(super::routes::whatsapp_webhook),
         )
+        // Zalo OA webhook — public, auth via the OA secret key signature
+        .route("/api/v1/webhook/zalo", post(super::routes::zalo_webhook))
+        // Twilio SMS webhook — public, auth via the X-Twilio-Signature header
+        .route("/api/v1/webhook/sms", post(super::routes::sms_webhook))
         // Webhook inbound — public, auth via HMAC signature in header
         .route("/api/v1/webhook/inbound", post(super::routes::webhook_inbound))
+        // Slack Events API — public, auth via the workspace's signing secret
+        .route("/api/v1/slack/events", post(super::routes::slack_events))
         // OpenAI-Compatible API — public with own auth (Bearer token)
         .route("/v1/chat/completions", post(super::openai_compat::chat_completions))
         .route("/v1/models", get(super::openai_compat::list_models));
@@ -430,6 +691,7 @@ pub fn build_router_from_arc(shared: Arc<AppState>) -> Router {
             }
         })
         .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(record_http_metrics))
         // Security headers
         .layer(axum::middleware::from_fn(security_headers))
         // H1 FIX: Limit request body size (5MB — allows file uploads for knowledge base)
@@ -444,15 +706,19 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
         .map(PathBuf::from)
         .unwrap_or_else(|_| BizClawConfig::default_path());
     let full_config = if config_path.exists() {
-        BizClawConfig::load_from(&config_path).unwrap_or_default()
+        bizclaw_security::secrets::load_config(&config_path)
+            .inspect_err(|e| tracing::error!("Config {}: {e}", config_path.display()))
+            .unwrap_or_default()
     } else {
         BizClawConfig::default()
     };
 
     // Create the Agent engine (sync — no MCP to avoid startup hang)
-    let agent: Option<bizclaw_agent::Agent> =
+    let mut agent: Option<bizclaw_agent::Agent> =
         match bizclaw_agent::Agent::new(full_config.clone()) {
-            Ok(a) => {
+            Ok(mut a) => {
+                // Pick up the conversation from before the restart
+                a.set_session("default").await;
                 let tool_count = a.tool_count();
                 tracing::info!(
                     "✅ Agent engine initialized (provider={}, tools={})",
@@ -474,12 +740,19 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
         .parent()
         .unwrap_or(std::path::Path::new("."))
         .join("scheduler");
-    let scheduler = bizclaw_scheduler::SchedulerEngine::new(&sched_dir);
+    let mut scheduler = bizclaw_scheduler::SchedulerEngine::new(&sched_dir);
+    let events = bizclaw_core::events::EventBus::default();
+    scheduler.set_event_bus(events.clone());
+    scheduler.set_notify_targets(bizclaw_scheduler::dispatch::targets_from_config(&full_config));
     let task_count = scheduler.task_count();
     if task_count > 0 {
         tracing::info!("⏰ Scheduler loaded: {} task(s)", task_count);
     }
     let scheduler = Arc::new(tokio::sync::Mutex::new(scheduler));
+    // Let the agent schedule reminders and tasks from conversation
+    if let Some(agent) = agent.as_mut() {
+        agent.register_tool(Box::new(bizclaw_tools::schedule::ScheduleTool::new(scheduler.clone(), None)));
+    }
 
     // Initialize Knowledge Base
     let kb_path = config_path
@@ -487,7 +760,10 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
         .unwrap_or(std::path::Path::new("."))
         .join("knowledge.db");
     let knowledge = match bizclaw_knowledge::KnowledgeStore::open(&kb_path) {
-        Ok(kb) => {
+        Ok(mut kb) => {
+            kb.set_chunking(bizclaw_knowledge::chunker::ChunkOptions::from_config(
+                &full_config.knowledge,
+            ));
             let (docs, chunks) = kb.stats();
             if docs > 0 {
                 tracing::info!("📚 Knowledge base: {} documents, {} chunks", docs, chunks);
@@ -499,6 +775,14 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
             None
         }
     };
+    let knowledge = Arc::new(tokio::sync::Mutex::new(knowledge));
+    if !full_config.knowledge.watch_dir.is_empty() {
+        bizclaw_knowledge::watch::spawn_watcher(
+            knowledge.clone(),
+            bizclaw_knowledge::watch::expand_home(&full_config.knowledge.watch_dir),
+            std::time::Duration::from_secs(full_config.knowledge.watch_interval_secs),
+        );
+    }
 
     // Initialize Gateway DB (per-tenant SQLite)
     let db_path = config_path
@@ -516,6 +800,11 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
             super::db::GatewayDb::open(std::path::Path::new(":memory:")).unwrap()
         }
     };
+    match gateway_db.fail_interrupted_jobs() {
+        Ok(0) => {}
+        Ok(n) => tracing::warn!("🧰 {n} background job(s) interrupted by the last shutdown marked failed"),
+        Err(e) => tracing::warn!("⚠️ Failed to clean up interrupted jobs: {e}"),
+    }
     let gateway_db = Arc::new(gateway_db);
 
     // Initialize Orchestration DataStore (SQLite — same directory as gateway.db)
@@ -563,7 +852,7 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
         }
     }
 
-    // Restore agents from DB (using sync Agent::new — no MCP to avoid startup hang)
+    // Restore agents from DB (using sync Agent::new — MCP tools come from the shared pool)
     let db_agents = gateway_db.list_agents().unwrap_or_default();
     if !db_agents.is_empty() {
         tracing::info!(
@@ -585,6 +874,16 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
                 agent_cfg.identity.system_prompt = agent_rec.system_prompt.clone();
             }
             agent_cfg.identity.name = agent_rec.name.clone();
+            if !agent_rec.fallback_providers.is_empty() {
+                agent_cfg.llm.fallback_providers = agent_rec.fallback_providers.clone();
+            }
+            if !agent_rec.knowledge_namespaces.is_empty() {
+                agent_cfg.knowledge.namespaces = agent_rec.knowledge_namespaces.clone();
+            }
+            if let Some(rounds) = agent_rec.max_tool_rounds {
+                agent_cfg.autonomy.max_tool_rounds = rounds;
+            }
+            super::routes::apply_fallback_config_from_db(&gateway_db, &mut agent_cfg);
 
             // Inject per-provider API key and base_url from DB
             // This enables agents to use different providers (e.g. Ollama, DeepSeek)
@@ -606,9 +905,15 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
                 }
             }
 
-            // Use sync Agent::new() for fast startup — MCP tools loaded lazily on first chat
+            // Use sync Agent::new() for fast startup — MCP tools added once the pool connects
             match bizclaw_agent::Agent::new(agent_cfg) {
-                Ok(agent) => {
+                Ok(mut agent) => {
+                    agent.set_knowledge(knowledge.clone());
+                    super::workspace::attach(&config_path, &agent_rec.name, &mut agent);
+                    agent.register_tool(Box::new(bizclaw_tools::schedule::ScheduleTool::new(
+                        scheduler.clone(),
+                        Some(agent_rec.name.clone()),
+                    )));
                     orchestrator.add_agent(&agent_rec.name, &agent_rec.role, &agent_rec.description, agent);
                     tracing::info!("  ✅ Agent '{}' restored ({})", agent_rec.name, agent_rec.role);
                 }
@@ -618,6 +923,18 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
             }
         }
     }
+    // Let agents call each other as tools (`call_agent`)
+    orchestrator.enable_delegation().await;
+    // Pick agents for messages from channels not bound to one
+    orchestrator.set_router(bizclaw_agent::routing::Router::from_config(&full_config));
+    // Hold `autonomy.require_approval` tool calls until a human decides
+    let approvals = bizclaw_agent::approval::ApprovalQueue::new();
+    orchestrator.set_approvals(approvals.clone()).await;
+    orchestrator.set_event_bus(events.clone()).await;
+    if let Some(agent) = agent.as_mut() {
+        agent.set_approvals(approvals.clone(), "default");
+        agent.set_event_bus(events.clone(), "default");
+    }
     tracing::info!(
         "🤖 Multi-Agent Orchestrator initialized ({} agents)",
         orchestrator.agent_count()
@@ -626,6 +943,33 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
     // Wrap orchestrator in Arc for shared access
     let orchestrator_arc = Arc::new(tokio::sync::Mutex::new(orchestrator));
 
+    // Connect each MCP server once, in the background so startup doesn't
+    // wait on it; every agent gets the pooled tools and resources, then the
+    // prompt templates it was given
+    let mcp_configs: Vec<bizclaw_mcp::McpServerConfig> =
+        full_config.mcp_servers.iter().map(Into::into).collect();
+    if !mcp_configs.is_empty() {
+        let orch = orchestrator_arc.clone();
+        let agent_prompts: Vec<(String, Vec<String>)> = db_agents
+            .iter()
+            .filter(|a| !a.mcp_prompts.is_empty())
+            .map(|a| (a.name.clone(), a.mcp_prompts.clone()))
+            .collect();
+        tokio::spawn(async move {
+            let pool = bizclaw_mcp::McpPool::connect(&mcp_configs).await;
+            let orch = {
+                let mut orch = orch.lock().await;
+                orch.set_mcp_pool(Arc::new(pool)).await;
+                orch.clone()
+            };
+            for (name, prompts) in agent_prompts {
+                if let Err(e) = orch.set_agent_prompts(&name, &prompts).await {
+                    tracing::warn!("⚠️ Agent '{name}' MCP prompts not restored: {e}");
+                }
+            }
+        });
+    }
+
     // Spawn scheduler background loop with Agent integration (check every 30 seconds)
     let sched_clone = scheduler.clone();
     let orch_for_sched = orchestrator_arc.clone();
@@ -635,7 +979,7 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
             move |prompt: String| {
                 let orch = orch_for_sched.clone();
                 async move {
-                    let mut o = orch.lock().await;
+                    let mut o = orch.lock().await.clone();
                     o.send(&prompt).await.map_err(|e| e.to_string())
                 }
             },
@@ -644,8 +988,38 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
         .await;
     });
 
+    // Deliver queued notifications, retrying failed sends
+    tokio::spawn(bizclaw_scheduler::dispatch::spawn_dispatcher(scheduler.clone(), 30));
+
+    // Run workflow rules on channel messages and finished tasks
+    tokio::spawn(bizclaw_scheduler::triggers::spawn_event_triggers(
+        scheduler.clone(),
+        events.clone(),
+        super::routes::workflow_runner(orchestrator_arc.clone()),
+    ));
+
+    // Autonomous Hands: built-ins plus custom hands, scheduled when enabled
+    let mut hand_registry = bizclaw_hands::HandRegistry::with_defaults();
+    hand_registry.load_dir(&full_config.hands.dir_path());
+    let hand_names: Vec<String> = hand_registry.list().iter().map(|h| h.manifest.name.clone()).collect();
+    for name in hand_names {
+        if !full_config.hands.enabled.contains(&name) {
+            hand_registry.disable(&name);
+        }
+    }
+    let hands = super::routes::hand_runner(
+        orchestrator_arc.clone(),
+        Arc::new(tokio::sync::Mutex::new(hand_registry)),
+        full_config.hands.check_interval_secs.max(1),
+    );
+    hands.clone().spawn();
+
     let (activity_tx, _rx) = tokio::sync::broadcast::channel::<super::openai_compat::ActivityEvent>(256);
 
+    let channel_pipeline = Arc::new(bizclaw_channels::middleware::Pipeline::new(
+        super::routes::channel_middleware(&full_config),
+    ));
+
     let state = AppState {
         gateway_config: config.clone(),
         full_config: Arc::new(Mutex::new(full_config)),
@@ -664,16 +1038,28 @@ pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
             String::new()
         })),
         auth_failures: Arc::new(tokio::sync::Mutex::new((0, std::time::Instant::now()))),
+        api_key_limiter: Arc::new(super::api_keys::ApiKeyLimiter::default()),
         agent: Arc::new(tokio::sync::Mutex::new(agent)),
         orchestrator: orchestrator_arc.clone(),
         scheduler,
-        knowledge: Arc::new(tokio::sync::Mutex::new(knowledge)),
+        knowledge,
         telegram_bots: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        discord_bots: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        slack_bots: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        email_listeners: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        zalo_oa: Arc::new(std::sync::Mutex::new(None)),
+        channel_pipeline,
+        channel_registry: Arc::new(bizclaw_channels::registry::ChannelRegistry::with_builtin()),
+        adapter_channels: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
         db: gateway_db,
         orch_store,
         traces: Arc::new(Mutex::new(Vec::new())),
         activity_tx: activity_tx.clone(),
         activity_log: Arc::new(Mutex::new(Vec::new())),
+        approvals,
+        events,
+        hands,
+        jobs: Arc::new(super::jobs::JobQueue::default()),
     };
 
     let state_arc = Arc::new(state);
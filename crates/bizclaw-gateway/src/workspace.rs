@@ -0,0 +1,376 @@
+//! Agent workspaces — a directory per agent for the files it works on.
+//!
+//! Agent `sales` gets `<config dir>/workspaces/sales/`. Files uploaded there
+//! are visible to its tools, and with `autonomy.workspace_only` (the
+//! default) its shell runs inside it, so the reports and CSVs it produces
+//! land there to be downloaded.
+//!
+//! Paths are relative to the workspace; absolute paths, `..` and symlinks
+//! leading out of it are refused, whatever `workspace_only` says — a
+//! read-scope key must not reach the gateway's config or secrets. Uploads
+//! stay in the workspace too, within [`MAX_UPLOAD_BYTES`] per request and
+//! [`MAX_WORKSPACE_BYTES`] per agent.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::{Multipart, Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::Value;
+
+use super::server::AppState;
+
+/// Largest upload request (all files together).
+pub const MAX_UPLOAD_BYTES: usize = 20 * 1024 * 1024;
+
+/// Most an agent's workspace may hold through uploads.
+pub const MAX_WORKSPACE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// The workspace directory of `agent` (not created).
+pub fn workspace_dir(config_path: &Path, agent: &str) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("workspaces")
+        .join(agent)
+}
+
+/// Whether `agent` can name a directory: one plain path component.
+fn valid_name(agent: &str) -> bool {
+    let mut parts = Path::new(agent).components();
+    matches!(parts.next(), Some(Component::Normal(_))) && parts.next().is_none()
+}
+
+/// Create `agent`'s workspace and run its shell there (see
+/// [`bizclaw_agent::Agent::set_workspace`]).
+pub fn attach(config_path: &Path, name: &str, agent: &mut bizclaw_agent::Agent) {
+    if !valid_name(name) {
+        tracing::warn!("⚠️ Agent '{name}' gets no workspace: the name isn't a valid directory name");
+        return;
+    }
+    let dir = workspace_dir(config_path, name);
+    match std::fs::create_dir_all(&dir) {
+        Ok(()) => agent.set_workspace(dir),
+        Err(e) => tracing::warn!("⚠️ Workspace for agent '{name}' not created: {e}"),
+    }
+}
+
+/// `path` as a plain relative path, or why it isn't one.
+fn relative(path: &str) -> Result<PathBuf, String> {
+    let mut out = PathBuf::new();
+    for part in Path::new(path.trim()).components() {
+        match part {
+            Component::Normal(p) => out.push(p),
+            Component::CurDir => {}
+            _ => return Err(format!("Path '{path}' must stay inside the workspace")),
+        }
+    }
+    Ok(out)
+}
+
+/// The workspace of an existing agent, created if missing.
+async fn agent_workspace(state: &AppState, name: &str) -> Result<PathBuf, String> {
+    let exists = state
+        .orchestrator
+        .lock()
+        .await
+        .list_agents()
+        .iter()
+        .any(|a| a["name"].as_str() == Some(name));
+    if !exists {
+        return Err(format!("Agent '{name}' not found"));
+    }
+    if !valid_name(name) {
+        return Err(format!("Agent '{name}' has no workspace"));
+    }
+    let dir = workspace_dir(&state.config_path, name);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Create workspace: {e}"))?;
+    dir.canonicalize().map_err(|e| format!("Workspace: {e}"))
+}
+
+/// An existing file or directory in `root` named by `path`, with symlinks
+/// resolved and still inside `root`.
+fn resolve_in(root: &Path, path: &str) -> Result<PathBuf, String> {
+    let resolved = root
+        .join(relative(path)?)
+        .canonicalize()
+        .map_err(|_| format!("'{path}' not found"))?;
+    if !resolved.starts_with(root) {
+        return Err(format!("Path '{path}' must stay inside the workspace"));
+    }
+    Ok(resolved)
+}
+
+/// A file to download from the workspace. The config file and the secret
+/// store are never served, wherever they are.
+fn resolve_download(state: &AppState, root: &Path, path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve_in(root, path)?;
+    let is_config = state.config_path.canonicalize().is_ok_and(|config| config == resolved);
+    let is_secret = resolved
+        .file_name()
+        .is_some_and(|name| name == "secrets.key" || name == "secrets.enc");
+    if is_config || is_secret {
+        return Err(format!("Path '{path}' is forbidden"));
+    }
+    Ok(resolved)
+}
+
+/// Every file under `dir` as `(relative path, size, modified)`, sorted.
+fn walk(root: &Path, dir: &Path, out: &mut Vec<(String, u64, String)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Not following symlinked directories, which may loop or lead out
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            walk(root, &path, out);
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_file() {
+            let modified = meta
+                .modified()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+                .unwrap_or_default();
+            let rel = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            out.push((rel, meta.len(), modified));
+        }
+    }
+}
+
+fn list(root: &Path) -> Vec<(String, u64, String)> {
+    let mut files = Vec::new();
+    walk(root, root, &mut files);
+    files.sort();
+    files
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("txt" | "log" | "md") => "text/plain; charset=utf-8",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("json") => "application/json",
+        Some("pdf") => "application/pdf",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct FileQuery {
+    pub path: String,
+}
+
+/// GET /api/v1/agents/{name}/files — every file in the workspace.
+pub async fn list_files(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<Value> {
+    let root = match agent_workspace(&state, &name).await {
+        Ok(root) => root,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    let files = list(&root);
+    let used: u64 = files.iter().map(|f| f.1).sum();
+    let files: Vec<Value> = files
+        .into_iter()
+        .map(|(path, size, modified)| serde_json::json!({"path": path, "size": size, "modified": modified}))
+        .collect();
+    Json(serde_json::json!({
+        "ok": true,
+        "workspace": root,
+        "files": files,
+        "used_bytes": used,
+        "limit_bytes": MAX_WORKSPACE_BYTES,
+    }))
+}
+
+/// POST /api/v1/agents/{name}/files — multipart file fields, stored under
+/// an optional `dir` field (relative). Existing files are replaced.
+pub async fn upload_files(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    mut multipart: Multipart,
+) -> Json<Value> {
+    let root = match agent_workspace(&state, &name).await {
+        Ok(root) => root,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    let mut dir = PathBuf::new();
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Invalid upload: {e}")})),
+        };
+        if field.name() == Some("dir") {
+            match relative(&field.text().await.unwrap_or_default()) {
+                Ok(d) => dir = d,
+                Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+            }
+            continue;
+        }
+        // Keep only the base name of client-supplied paths
+        let file_name = field
+            .file_name()
+            .and_then(|n| n.rsplit(['/', '\\']).next())
+            .filter(|n| !n.is_empty() && *n != "." && *n != "..")
+            .unwrap_or("upload.bin")
+            .to_string();
+        match field.bytes().await {
+            Ok(bytes) => files.push((dir.join(file_name), bytes.to_vec())),
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Invalid upload: {e}")})),
+        }
+    }
+    if files.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "No files uploaded"}));
+    }
+
+    let existing = list(&root);
+    let replaced: u64 = files
+        .iter()
+        .filter_map(|(rel, _)| existing.iter().find(|f| Path::new(&f.0) == rel.as_path()).map(|f| f.1))
+        .sum();
+    let used = existing.iter().map(|f| f.1).sum::<u64>() - replaced;
+    let added: u64 = files.iter().map(|(_, b)| b.len() as u64).sum();
+    if used + added > MAX_WORKSPACE_BYTES {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": format!("Workspace full: {used} of {MAX_WORKSPACE_BYTES} bytes used, upload is {added}"),
+        }));
+    }
+
+    let mut saved = Vec::new();
+    for (rel, bytes) in &files {
+        let dest = root.join(rel);
+        if let Some(parent) = dest.parent() {
+            // A symlinked directory could lead out of the workspace
+            let created = std::fs::create_dir_all(parent).and_then(|_| parent.canonicalize());
+            if !created.is_ok_and(|p| p.starts_with(&root)) {
+                return Json(serde_json::json!({"ok": false, "error": format!("Can't write '{}'", rel.display())}));
+            }
+        }
+        // Writing through a symlink could too
+        if dest.symlink_metadata().is_ok_and(|m| !m.is_file()) {
+            return Json(serde_json::json!({"ok": false, "error": format!("Can't write '{}'", rel.display())}));
+        }
+        if let Err(e) = tokio::fs::write(&dest, bytes).await {
+            return Json(serde_json::json!({"ok": false, "error": format!("Write '{}': {e}", rel.display())}));
+        }
+        saved.push(serde_json::json!({"path": rel.to_string_lossy().replace('\\', "/"), "size": bytes.len()}));
+    }
+    tracing::info!("📁 {} file(s) uploaded to agent '{name}' workspace", saved.len());
+    super::audit::record(&state, "workspace.upload", &name, None, Some(serde_json::json!({"files": saved})));
+    Json(serde_json::json!({"ok": true, "files": saved}))
+}
+
+/// GET /api/v1/agents/{name}/files/download?path= — the file's contents as
+/// an attachment.
+pub async fn download_file(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Query(q): Query<FileQuery>,
+) -> Response {
+    let file = match agent_workspace(&state, &name).await {
+        Ok(root) => resolve_download(&state, &root, &q.path),
+        Err(e) => Err(e),
+    };
+    let file = match file {
+        Ok(file) if file.is_file() => file,
+        Ok(_) => return Json(serde_json::json!({"ok": false, "error": format!("'{}' is not a file", q.path)})).into_response(),
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})).into_response(),
+    };
+    match tokio::fs::read(&file).await {
+        Ok(bytes) => {
+            let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or("download").replace('"', "");
+            (
+                [
+                    (axum::http::header::CONTENT_TYPE, content_type(&file).to_string()),
+                    (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+                ],
+                bytes,
+            )
+                .into_response()
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": format!("Read: {e}")})).into_response(),
+    }
+}
+
+/// DELETE /api/v1/agents/{name}/files?path= — remove a file or directory
+/// from the workspace.
+pub async fn delete_file(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Query(q): Query<FileQuery>,
+) -> Json<Value> {
+    let target = match agent_workspace(&state, &name).await {
+        Ok(root) => match resolve_in(&root, &q.path) {
+            Ok(target) if target == root => Err("Can't delete the workspace itself".to_string()),
+            other => other,
+        },
+        Err(e) => Err(e),
+    };
+    let target = match target {
+        Ok(target) => target,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    let removed = if target.is_dir() {
+        tokio::fs::remove_dir_all(&target).await
+    } else {
+        tokio::fs::remove_file(&target).await
+    };
+    match removed {
+        Ok(()) => {
+            super::audit::record(&state, "workspace.delete", &name, Some(serde_json::json!({"path": q.path})), None);
+            Json(serde_json::json!({"ok": true}))
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": format!("Delete: {e}")})),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_paths_stay_inside() {
+        assert_eq!(relative("reports/q3.csv").unwrap(), PathBuf::from("reports/q3.csv"));
+        assert_eq!(relative("./a/./b").unwrap(), PathBuf::from("a/b"));
+        assert_eq!(relative("").unwrap(), PathBuf::new());
+        assert!(relative("../secrets").is_err());
+        assert!(relative("a/../../b").is_err());
+        assert!(relative("/etc/passwd").is_err());
+        assert!(valid_name("sales"));
+        assert!(!valid_name("../sales") && !valid_name("a/b") && !valid_name(""));
+    }
+
+    #[test]
+    fn test_symlinks_out_of_the_workspace_are_refused() {
+        let base = std::env::temp_dir().join(format!("bizclaw-ws-{}", uuid::Uuid::new_v4()));
+        let root = base.join("agent");
+        std::fs::create_dir_all(root.join("out")).unwrap();
+        std::fs::write(base.join("secret.txt"), "s").unwrap();
+        std::fs::write(root.join("out/report.csv"), "a,b").unwrap();
+        let root = root.canonicalize().unwrap();
+
+        assert!(resolve_in(&root, "out/report.csv").is_ok());
+        assert!(resolve_in(&root, "missing.csv").unwrap_err().contains("not found"));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(base.join("secret.txt"), root.join("link.txt")).unwrap();
+            assert!(resolve_in(&root, "link.txt").unwrap_err().contains("inside the workspace"));
+        }
+        assert_eq!(list(&root).iter().filter(|f| f.0 == "out/report.csv").count(), 1);
+        assert_eq!(content_type(Path::new("out/report.csv")), "text/csv; charset=utf-8");
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
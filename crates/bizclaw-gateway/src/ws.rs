@@ -8,8 +8,13 @@
 //! Protocol:
 //! → Client sends: {"type":"chat","content":"...","stream":true}
 //! ← Server sends: {"type":"chat_start","request_id":"..."}
+//! ← Server sends: {"type":"chat_progress","request_id":"...","status":"🧠 Working… (round 1)"}
 //! ← Server sends: {"type":"chat_chunk","request_id":"...","content":"token","index":0}
 //! ← Server sends: {"type":"chat_done","request_id":"...","total_tokens":42}
+//!
+//! Platform events (notifications, finished tasks, channel connections,
+//! agent reloads...) are pushed as they happen, between chat replies:
+//! ← Server sends: {"type":"event","event":{"type":"notification_sent","title":"...",...}}
 
 use super::server::AppState;
 use axum::{
@@ -102,8 +107,31 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
         serde_json::json!({"role": "system", "content": "Bạn là BizClaw AI Assistant. Trả lời ngắn gọn, hữu ích bằng tiếng Việt. Nếu user nói tiếng Anh thì trả lời tiếng Anh."}),
     ];
 
+    let mut events = state.events.subscribe();
+
     // Message loop
-    while let Some(msg) = socket.recv().await {
+    loop {
+        let msg = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            event = events.recv() => {
+                match event {
+                    Ok(event) => {
+                        let push = serde_json::json!({"type": "event", "event": event});
+                        if send_json(&mut socket, &push).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("WS client skipped {skipped} events");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+                continue;
+            }
+        };
         match msg {
             Ok(Message::Text(text)) => {
                 let json = match serde_json::from_str::<serde_json::Value>(&text) {
@@ -176,32 +204,58 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                             )
                             .await;
 
-                            let result = {
-                                let mut agent = state.agent.lock().await;
-                                if let Some(agent) = agent.as_mut() {
-                                    // Connect knowledge base for RAG
-                                    agent.set_knowledge(state.knowledge.clone());
-                                    Some(agent.process(&content).await)
-                                } else {
-                                    None
-                                }
-                            };
-
-                            // Get context stats after processing
-                            let ctx_stats = {
-                                let agent = state.agent.lock().await;
-                                agent.as_ref().map(|a| a.context_stats().clone())
-                            };
-
-                            match result {
-                                Some(Ok(response)) => {
-                                    if stream {
-                                        // Emit as rapid chunks for streaming UX
-                                        let chunk_size = 8; // chars per chunk
-                                        let chars: Vec<char> = response.chars().collect();
-                                        let mut idx: u64 = 0;
-                                        for chunk in chars.chunks(chunk_size) {
-                                            let text: String = chunk.iter().collect();
+                            let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+                            let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+                            let (tool_tx, mut tool_rx) =
+                                tokio::sync::mpsc::unbounded_channel::<bizclaw_core::types::ToolOutputChunk>();
+                            let (agent_lock, knowledge, message) =
+                                (state.agent.clone(), state.knowledge.clone(), content.clone());
+                            // Generate in a task so the agent lock is held only while it works
+                            let task = tokio::spawn(async move {
+                                let mut agent = agent_lock.lock().await;
+                                let agent = agent.as_mut()?;
+                                // Channel messages switch sessions on the same agent
+                                agent.switch_session(bizclaw_agent::sessions::DEFAULT_SESSION).await;
+                                // Connect knowledge base for RAG
+                                agent.set_knowledge(knowledge);
+                                agent.set_tool_output_sink(Some(tool_tx));
+                                let result = agent.process_streaming(&message, &progress_tx, &chunk_tx).await;
+                                agent.set_tool_output_sink(None);
+                                Some((result, agent.context_stats().clone(), agent.last_citations().to_vec()))
+                            });
+
+                            // Forward tool-round progress, tool output and provider deltas as they arrive
+                            let mut view = bizclaw_agent::progress::ProgressView::new();
+                            let mut idx: u64 = 0;
+                            loop {
+                                tokio::select! {
+                                    Some(event) = progress_rx.recv() => {
+                                        view.apply(&event);
+                                        let _ = send_json(
+                                            &mut socket,
+                                            &serde_json::json!({
+                                                "type": "chat_progress",
+                                                "request_id": &request_id,
+                                                "status": view.render(),
+                                            }),
+                                        )
+                                        .await;
+                                    }
+                                    Some(output) = tool_rx.recv() => {
+                                        let _ = send_json(
+                                            &mut socket,
+                                            &serde_json::json!({
+                                                "type": "tool_output",
+                                                "request_id": &request_id,
+                                                "tool": output.tool_name,
+                                                "stream": output.stream,
+                                                "data": output.data,
+                                            }),
+                                        )
+                                        .await;
+                                    }
+                                    Some(text) = chunk_rx.recv() => {
+                                        if stream {
                                             let _ = send_json(
                                                 &mut socket,
                                                 &serde_json::json!({
@@ -214,6 +268,14 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                                             .await;
                                             idx += 1;
                                         }
+                                    }
+                                    else => break,
+                                }
+                            }
+
+                            match task.await.ok().flatten() {
+                                Some((Ok(response), ctx_stats, citations)) => {
+                                    if stream {
                                         let _ = send_json(
                                             &mut socket,
                                             &serde_json::json!({
@@ -223,6 +285,7 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                                                 "full_content": &response,
                                                 "mode": "agent",
                                                 "context": ctx_stats,
+                                                "citations": &citations,
                                             }),
                                         )
                                         .await;
@@ -246,12 +309,13 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                                                 "request_id": &request_id,
                                                 "full_content": &response,
                                                 "mode": "agent",
+                                                "citations": &citations,
                                             }),
                                         )
                                         .await;
                                     }
                                 }
-                                Some(Err(e)) => {
+                                Some((Err(e), _, _)) => {
                                     let _ = send_json(
                                         &mut socket,
                                         &serde_json::json!({
@@ -53,10 +53,49 @@ fn default_true() -> bool {
     true
 }
 
+/// Per-run limits for a Hand — the `[budget]` table of guardrails.toml.
+/// A run that goes over any of them is aborted.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct HandBudget {
+    /// Max LLM tokens per run (estimated).
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Max LLM cost per run (USD, estimated).
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Max tool calls per run.
+    #[serde(default)]
+    pub max_tool_calls: Option<u32>,
+    /// Max wall-clock time per run (seconds), on top of the manifest's
+    /// `max_runtime_secs`.
+    #[serde(default)]
+    pub max_runtime_secs: Option<u64>,
+}
+
+impl HandBudget {
+    /// Which limit a run using `tokens`, `cost_usd` and `tool_calls` went
+    /// over, if any.
+    pub fn exceeded(&self, tokens: u64, cost_usd: f64, tool_calls: u32) -> Option<String> {
+        if let Some(max) = self.max_tokens.filter(|max| tokens > *max) {
+            return Some(format!("token budget exceeded: {tokens} of {max}"));
+        }
+        if let Some(max) = self.max_cost_usd.filter(|max| cost_usd > *max) {
+            return Some(format!("cost budget exceeded: ${cost_usd:.4} of ${max:.4}"));
+        }
+        if let Some(max) = self.max_tool_calls.filter(|max| tool_calls > *max) {
+            return Some(format!("tool call budget exceeded: {tool_calls} of {max}"));
+        }
+        None
+    }
+}
+
 /// Guardrail configuration for a Hand.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GuardrailConfig {
+    #[serde(default)]
     pub rules: Vec<Guardrail>,
+    #[serde(default)]
+    pub budget: HandBudget,
 }
 
 impl GuardrailConfig {
@@ -116,6 +155,7 @@ mod tests {
                     enabled: true,
                 },
             ],
+            ..Default::default()
         };
 
         assert!(config.check_tool("shell").is_some());
@@ -134,8 +174,26 @@ mod tests {
                 action: GuardrailAction::NotifyAndContinue,
                 enabled: true,
             }],
+            ..Default::default()
         };
         assert!(config.check_cost(1.5).is_some());
         assert!(config.check_cost(0.5).is_none());
     }
+
+    #[test]
+    fn test_budget_from_toml() {
+        let config: GuardrailConfig = toml::from_str(
+            r#"
+[budget]
+max_tokens = 20000
+max_tool_calls = 10
+"#,
+        )
+        .unwrap();
+        assert!(config.rules.is_empty());
+        let budget = &config.budget;
+        assert_eq!(budget.exceeded(20000, 5.0, 10), None);
+        assert!(budget.exceeded(20001, 0.0, 0).unwrap().starts_with("token budget"));
+        assert!(budget.exceeded(0, 0.0, 11).unwrap().starts_with("tool call budget"));
+    }
 }
@@ -6,6 +6,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::guardrails::GuardrailConfig;
 use crate::manifest::HandManifest;
 
 /// Hand execution status.
@@ -49,6 +50,8 @@ pub struct HandPhase {
     pub output: Option<String>,
     pub error: Option<String>,
     pub tokens_used: u64,
+    #[serde(default)]
+    pub tool_calls: u32,
 }
 
 /// Execution result from a single Hand run.
@@ -62,13 +65,33 @@ pub struct HandRunResult {
     pub phases: Vec<HandPhase>,
     pub total_tokens: u64,
     pub total_cost_usd: f64,
+    #[serde(default)]
+    pub total_tool_calls: u32,
+    /// Why the run was aborted, when it went over the hand's budget.
+    #[serde(default)]
+    pub budget_exceeded: Option<String>,
     pub summary: String,
 }
 
+/// A run stopped before a phase that needs human approval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PausedRun {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Phases finished before the pause.
+    pub phases: Vec<HandPhase>,
+}
+
 /// A Hand instance — wraps manifest + runtime state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hand {
     pub manifest: HandManifest,
+    /// Multi-phase playbook from system_prompt.md, given to every phase.
+    #[serde(default)]
+    pub playbook: String,
+    /// Approval gates from guardrails.toml.
+    #[serde(default)]
+    pub guardrails: GuardrailConfig,
     pub status: HandStatus,
     pub last_run: Option<DateTime<Utc>>,
     pub next_run: Option<DateTime<Utc>>,
@@ -77,14 +100,19 @@ pub struct Hand {
     pub total_cost_usd: f64,
     pub last_error: Option<String>,
     pub history: Vec<HandRunResult>,
+    /// Set while the hand is awaiting approval.
+    #[serde(default)]
+    pub paused: Option<PausedRun>,
 }
 
 impl Hand {
     /// Create a new Hand from a manifest.
     pub fn new(manifest: HandManifest) -> Self {
         let enabled = manifest.enabled;
-        Self {
+        let mut hand = Self {
             manifest,
+            playbook: String::new(),
+            guardrails: GuardrailConfig::default(),
             status: if enabled {
                 HandStatus::Idle
             } else {
@@ -97,12 +125,30 @@ impl Hand {
             total_cost_usd: 0.0,
             last_error: None,
             history: Vec::new(),
-        }
+            paused: None,
+        };
+        hand.schedule_next(Utc::now());
+        hand
+    }
+
+    /// Work out `next_run` from the schedule, counting from `after`.
+    pub fn schedule_next(&mut self, after: DateTime<Utc>) {
+        use crate::manifest::HandSchedule;
+        self.next_run = match &self.manifest.schedule {
+            HandSchedule::Cron(expr) => bizclaw_scheduler::cron::next_run_from_cron(expr, after),
+            HandSchedule::Interval(secs) => Some(after + chrono::Duration::seconds(*secs as i64)),
+            HandSchedule::Once | HandSchedule::Manual => None,
+        };
     }
 
     /// Check if this hand should execute based on its schedule.
     pub fn should_run(&self, now: DateTime<Utc>) -> bool {
-        if self.status == HandStatus::Disabled || self.status == HandStatus::Running {
+        if !self.manifest.enabled
+            || matches!(
+                self.status,
+                HandStatus::Disabled | HandStatus::Running | HandStatus::AwaitingApproval
+            )
+        {
             return false;
         }
         match &self.manifest.schedule {
@@ -114,19 +160,16 @@ impl Hand {
                     None => true, // Never run yet
                 }
             }
-            crate::manifest::HandSchedule::Cron(_expr) => {
-                // TODO: proper cron parsing — for now, use interval fallback
-                match self.last_run {
-                    Some(last) => (now - last).num_seconds() >= 3600,
-                    None => true,
-                }
-            }
+            // Cron hands wait for their next slot (none if the expression is invalid)
+            crate::manifest::HandSchedule::Cron(_) => self.next_run.is_some_and(|next| next <= now),
         }
     }
 
     /// Record a completed run.
     pub fn record_run(&mut self, result: HandRunResult) {
         self.last_run = Some(result.completed_at);
+        self.schedule_next(result.completed_at);
+        self.paused = None;
         self.run_count += 1;
         self.total_tokens += result.total_tokens;
         self.total_cost_usd += result.total_cost_usd;
@@ -184,6 +227,7 @@ mod tests {
                 timeout_secs: 60,
                 requires_approval: false,
             }],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 600,
@@ -209,6 +253,20 @@ mod tests {
         assert!(!hand.should_run(Utc::now()));
     }
 
+    #[test]
+    fn test_cron_hand_waits_for_next_slot() {
+        let mut manifest = test_manifest();
+        manifest.schedule = HandSchedule::Cron("0 6 * * *".into());
+        let hand = Hand::new(manifest);
+        let next = hand.next_run.unwrap();
+        assert!(!hand.should_run(Utc::now()));
+        assert!(hand.should_run(next));
+
+        let mut manifest = test_manifest();
+        manifest.schedule = HandSchedule::Cron("not cron".into());
+        assert!(!Hand::new(manifest).should_run(Utc::now() + chrono::Duration::days(2)));
+    }
+
     #[test]
     fn test_hand_record_run() {
         let mut hand = Hand::new(test_manifest());
@@ -221,6 +279,8 @@ mod tests {
             phases: vec![],
             total_tokens: 1500,
             total_cost_usd: 0.003,
+            total_tool_calls: 4,
+            budget_exceeded: None,
             summary: "Test completed".into(),
         };
         hand.record_run(result);
@@ -0,0 +1,314 @@
+//! Hand installation — add custom hands from a git repository or a zip
+//! archive (local file or URL).
+//!
+//! The source must hold a `HAND.toml`, at its root or in a single top-level
+//! folder. Only the hand's own files are copied into `<hands dir>/<name>/`:
+//! `HAND.toml`, `system_prompt.md`, `SKILL.md` and `guardrails.toml`. The
+//! source is remembered in `.source` so installed hands can be listed.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::guardrails::GuardrailConfig;
+use crate::manifest::HandManifest;
+
+/// Files copied from a hand source.
+const HAND_FILES: &[&str] = &["HAND.toml", "system_prompt.md", "SKILL.md", "guardrails.toml"];
+
+/// Where an installed hand came from.
+const SOURCE_FILE: &str = ".source";
+
+/// Largest zip downloaded from a URL.
+const MAX_ZIP_BYTES: usize = 20 * 1024 * 1024;
+
+/// A hand installed into the hands folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledHand {
+    pub name: String,
+    pub label: String,
+    pub version: String,
+    pub description: String,
+    /// Git URL or zip it was installed from (empty if copied by hand).
+    pub source: String,
+    pub path: PathBuf,
+}
+
+/// Install the hand at `source` into `hands_dir`, replacing an installed
+/// hand of the same name. `builtin` names can't be taken.
+pub async fn install_hand(source: &str, hands_dir: &Path, builtin: &[String]) -> Result<HandManifest, String> {
+    std::fs::create_dir_all(hands_dir).map_err(|e| format!("Create {}: {e}", hands_dir.display()))?;
+    let staging = hands_dir.join(format!(".staging-{}", uuid::Uuid::new_v4()));
+    let result = install_from(source, &staging, hands_dir, builtin).await;
+    std::fs::remove_dir_all(&staging).ok();
+    result
+}
+
+async fn install_from(source: &str, staging: &Path, hands_dir: &Path, builtin: &[String]) -> Result<HandManifest, String> {
+    fetch(source, staging).await?;
+    let root = hand_root(staging).ok_or("No HAND.toml found in the source")?;
+    // Decide which files are copied before reading any of them
+    let mut files = Vec::new();
+    for file in HAND_FILES {
+        if staged_file(staging, &root.join(file))? {
+            files.push(*file);
+        }
+    }
+    let manifest = validate(&root)?;
+    if builtin.contains(&manifest.name) {
+        return Err(format!("'{}' is a built-in hand", manifest.name));
+    }
+
+    let target = hands_dir.join(&manifest.name);
+    if target.exists() {
+        std::fs::remove_dir_all(&target).map_err(|e| format!("Replace {}: {e}", target.display()))?;
+    }
+    std::fs::create_dir_all(&target).map_err(|e| format!("Create {}: {e}", target.display()))?;
+    for file in files {
+        std::fs::copy(root.join(file), target.join(file)).map_err(|e| format!("Copy {file}: {e}"))?;
+    }
+    std::fs::write(target.join(SOURCE_FILE), source).map_err(|e| format!("Write {SOURCE_FILE}: {e}"))?;
+    tracing::info!("🤚 Installed hand {} {} from {source}", manifest.icon, manifest.label);
+    Ok(manifest)
+}
+
+/// Whether `path` is a file to copy: absent is `false`; a symlink, a
+/// folder or anything resolving outside `staging` is refused, so a source
+/// can't pull host files (config, keys) into the hand.
+fn staged_file(staging: &Path, path: &Path) -> Result<bool, String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(format!("Read {name}: {e}")),
+    };
+    if !meta.file_type().is_file() {
+        return Err(format!("{name} is not a regular file"));
+    }
+    let resolved = path.canonicalize().map_err(|e| format!("Read {name}: {e}"))?;
+    let staging = staging.canonicalize().map_err(|e| format!("Read {}: {e}", staging.display()))?;
+    if !resolved.starts_with(&staging) {
+        return Err(format!("{name} is outside the hand source"));
+    }
+    Ok(true)
+}
+
+/// Remove the installed hand `name` from `hands_dir`.
+pub fn uninstall_hand(name: &str, hands_dir: &Path) -> Result<(), String> {
+    if !valid_name(name) {
+        return Err(format!("Invalid hand name '{name}'"));
+    }
+    let dir = hands_dir.join(name);
+    if !dir.join("HAND.toml").is_file() {
+        return Err(format!("Hand '{name}' is not installed"));
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| format!("Remove {}: {e}", dir.display()))
+}
+
+/// Hands installed in `hands_dir`, by name.
+pub fn installed_hands(hands_dir: &Path) -> Vec<InstalledHand> {
+    let Ok(entries) = std::fs::read_dir(hands_dir) else {
+        return Vec::new();
+    };
+    let mut hands: Vec<InstalledHand> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let manifest = HandManifest::load(&path.join("HAND.toml")).ok()?;
+            Some(InstalledHand {
+                name: manifest.name,
+                label: manifest.label,
+                version: manifest.version,
+                description: manifest.description,
+                source: std::fs::read_to_string(path.join(SOURCE_FILE)).unwrap_or_default(),
+                path,
+            })
+        })
+        .collect();
+    hands.sort_by(|a, b| a.name.cmp(&b.name));
+    hands
+}
+
+/// Put the contents of `source` into `dest`: zip archives (path or URL)
+/// are extracted, anything else is cloned with git.
+async fn fetch(source: &str, dest: &Path) -> Result<(), String> {
+    let is_url = source.starts_with("http://") || source.starts_with("https://");
+    if !source.to_lowercase().ends_with(".zip") {
+        // "--" so a source can't pass itself off as a git option
+        let output = tokio::process::Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", "--", source])
+            .arg(dest)
+            .output()
+            .await
+            .map_err(|e| format!("Run git: {e}"))?;
+        if !output.status.success() {
+            return Err(format!("git clone failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        return Ok(());
+    }
+
+    let bytes = if is_url {
+        let mut response = reqwest::get(source).await.map_err(|e| format!("Download {source}: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!("Download {source}: HTTP {}", response.status()));
+        }
+        let too_big = || format!("Download {source}: larger than {} MiB", MAX_ZIP_BYTES / (1024 * 1024));
+        if response.content_length().is_some_and(|len| len > MAX_ZIP_BYTES as u64) {
+            return Err(too_big());
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("Download {source}: {e}"))? {
+            if bytes.len() + chunk.len() > MAX_ZIP_BYTES {
+                return Err(too_big());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        bytes
+    } else {
+        std::fs::read(source).map_err(|e| format!("Read {source}: {e}"))?
+    };
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| format!("Open zip: {e}"))?;
+    archive.extract(dest).map_err(|e| format!("Extract zip: {e}"))
+}
+
+/// The folder holding HAND.toml: `dir` itself or one folder below it.
+fn hand_root(dir: &Path) -> Option<PathBuf> {
+    if dir.join("HAND.toml").is_file() {
+        return Some(dir.to_path_buf());
+    }
+    let mut roots = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join("HAND.toml").is_file());
+    let root = roots.next()?;
+    // Several hands in one source are ambiguous
+    roots.next().is_none().then_some(root)
+}
+
+/// Check the hand's manifest and guardrails parse and its name is usable.
+fn validate(root: &Path) -> Result<HandManifest, String> {
+    let manifest = HandManifest::load(&root.join("HAND.toml"))?;
+    if !valid_name(&manifest.name) {
+        return Err(format!(
+            "Invalid hand name '{}': use lowercase letters, digits, '-' and '_'",
+            manifest.name
+        ));
+    }
+    if manifest.phases.is_empty() {
+        return Err(format!("Hand '{}' has no phases", manifest.name));
+    }
+    let guardrails = root.join("guardrails.toml");
+    if guardrails.is_file() {
+        GuardrailConfig::load(&guardrails)?;
+    }
+    Ok(manifest)
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const MANIFEST: &str = r#"
+name = "invoices"
+label = "Invoice Hand"
+description = "Chase unpaid invoices"
+schedule = "manual"
+
+[[phases]]
+name = "collect"
+description = "List unpaid invoices"
+"#;
+
+    fn write_zip(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_install_from_zip_and_uninstall() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-hand-install-{}", std::process::id()));
+        let hands_dir = dir.join("hands");
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("invoices.zip");
+        write_zip(
+            &archive,
+            &[
+                ("invoices-main/HAND.toml", MANIFEST),
+                ("invoices-main/system_prompt.md", "Be polite."),
+                ("invoices-main/README.md", "not copied"),
+            ],
+        );
+
+        let source = archive.to_string_lossy().to_string();
+        let manifest = install_hand(&source, &hands_dir, &[]).await.unwrap();
+        assert_eq!(manifest.name, "invoices");
+        assert!(hands_dir.join("invoices/system_prompt.md").is_file());
+        assert!(!hands_dir.join("invoices/README.md").exists());
+
+        let installed = installed_hands(&hands_dir);
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].source, source);
+        assert!(install_hand(&source, &hands_dir, &["invoices".into()]).await.is_err());
+
+        uninstall_hand("invoices", &hands_dir).unwrap();
+        assert!(installed_hands(&hands_dir).is_empty());
+        assert!(uninstall_hand("invoices", &hands_dir).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_install_rejects_invalid_hand() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-hand-invalid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("bad.zip");
+        write_zip(&archive, &[("HAND.toml", &MANIFEST.replace("invoices\"", "../etc\""))]);
+        let err = install_hand(&archive.to_string_lossy(), &dir.join("hands"), &[]).await.unwrap_err();
+        assert!(err.contains("Invalid hand name"));
+
+        write_zip(&archive, &[("notes.txt", "no manifest")]);
+        assert!(install_hand(&archive.to_string_lossy(), &dir.join("hands"), &[]).await.is_err());
+        // Staging folders are cleaned up
+        assert_eq!(std::fs::read_dir(dir.join("hands")).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_install_refuses_symlinked_files() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-hand-symlink-{}", std::process::id()));
+        let source = dir.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(dir.join("secrets.key"), "host secret").unwrap();
+        std::fs::write(source.join("HAND.toml"), MANIFEST).unwrap();
+        std::os::unix::fs::symlink(dir.join("secrets.key"), source.join("system_prompt.md")).unwrap();
+        let staging = dir.join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::os::unix::fs::symlink(&source, staging.join("hand")).unwrap();
+
+        // A symlinked file, and a regular file reached through a symlinked folder
+        let err = staged_file(&source, &source.join("system_prompt.md")).unwrap_err();
+        assert!(err.contains("not a regular file"), "{err}");
+        let err = staged_file(&staging, &staging.join("hand/HAND.toml")).unwrap_err();
+        assert!(err.contains("outside the hand source"), "{err}");
+        assert!(staged_file(&source, &source.join("HAND.toml")).unwrap());
+        assert!(!staged_file(&source, &source.join("SKILL.md")).unwrap());
+
+        let err = install_hand("--upload-pack=touch /tmp/pwned", &dir.join("hands"), &[]).await.unwrap_err();
+        assert!(err.contains("git clone failed"), "{err}");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
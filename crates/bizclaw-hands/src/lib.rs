@@ -25,16 +25,25 @@
 //! | 🔄 Sync           | Every 30min | Cross-system data synchronization |
 //! | 📧 Outreach       | Daily 9:00  | Email outreach automation         |
 //! | 🛡️ Security       | Every 1h    | Security scanning & reporting     |
+//!
+//! Custom hands are installed from a git repository or zip archive with
+//! `bizclaw hands install <source>`.
+//!
+//! The gateway loads the built-ins plus custom hands from `[hands] dir`,
+//! schedules those listed in `[hands] enabled`, runs each phase on an
+//! orchestrator agent and writes phase results to the daily memory log.
 
 pub mod hand;
 pub mod manifest;
 pub mod guardrails;
+pub mod install;
 pub mod registry;
 pub mod runner;
 pub mod skills;
 
-pub use hand::{Hand, HandStatus, HandPhase};
+pub use hand::{Hand, HandStatus, HandPhase, HandRunResult, PausedRun};
 pub use manifest::HandManifest;
-pub use guardrails::{Guardrail, GuardrailAction};
+pub use guardrails::{Guardrail, GuardrailAction, GuardrailConfig, HandBudget};
+pub use install::InstalledHand;
 pub use registry::HandRegistry;
-pub use runner::HandRunner;
+pub use runner::{HandRunner, PhaseOutput, PhaseRequest};
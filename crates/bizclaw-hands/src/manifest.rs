@@ -76,6 +76,9 @@ pub struct HandManifest {
     pub schedule: HandSchedule,
     /// Phases in the multi-phase playbook.
     pub phases: Vec<PhaseManifest>,
+    /// Orchestrator agent that runs the phases (empty = default agent).
+    #[serde(default)]
+    pub agent: String,
     /// LLM provider to use (empty = use default).
     #[serde(default)]
     pub provider: String,
@@ -1,7 +1,9 @@
 //! Hand Registry — manages all registered Hands.
 
 use std::collections::HashMap;
+use std::path::Path;
 
+use crate::guardrails::GuardrailConfig;
 use crate::hand::Hand;
 use crate::manifest::{HandManifest, HandSchedule, PhaseManifest};
 
@@ -29,9 +31,54 @@ impl HandRegistry {
 
     /// Register a Hand from its manifest.
     pub fn register(&mut self, manifest: HandManifest) {
-        let name = manifest.name.clone();
-        tracing::info!("🤚 Registered hand: {} {}", manifest.icon, manifest.label);
-        self.hands.insert(name, Hand::new(manifest));
+        self.insert(Hand::new(manifest));
+    }
+
+    /// Register a Hand, replacing any hand of the same name.
+    pub fn insert(&mut self, hand: Hand) {
+        tracing::info!("🤚 Registered hand: {} {}", hand.manifest.icon, hand.manifest.label);
+        self.hands.insert(hand.manifest.name.clone(), hand);
+    }
+
+    /// Install a hand from a git URL or zip into `dir` and register it.
+    /// Returns its name.
+    pub async fn install(&mut self, source: &str, dir: &Path) -> Result<String, String> {
+        let builtin: Vec<String> = builtin_hands().into_iter().map(|m| m.name).collect();
+        let manifest = crate::install::install_hand(source, dir, &builtin).await?;
+        self.insert(load_hand(&dir.join(&manifest.name))?);
+        Ok(manifest.name)
+    }
+
+    /// Delete the installed hand `name` from `dir` and unregister it.
+    /// Built-in hands can only be disabled.
+    pub fn uninstall(&mut self, name: &str, dir: &Path) -> Result<(), String> {
+        crate::install::uninstall_hand(name, dir)?;
+        self.hands.remove(name);
+        Ok(())
+    }
+
+    /// Register custom hands from `dir`, one folder per hand holding
+    /// `HAND.toml` plus optional `system_prompt.md` and `guardrails.toml`.
+    /// Returns how many loaded; broken hands are skipped with a warning.
+    pub fn load_dir(&mut self, dir: &Path) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut loaded = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.join("HAND.toml").is_file() {
+                continue;
+            }
+            match load_hand(&path) {
+                Ok(hand) => {
+                    self.insert(hand);
+                    loaded += 1;
+                }
+                Err(e) => tracing::warn!("⚠️ Skipping hand {}: {e}", path.display()),
+            }
+        }
+        loaded
     }
 
     /// Get a hand by name.
@@ -85,6 +132,19 @@ impl Default for HandRegistry {
     }
 }
 
+/// Load one hand folder.
+fn load_hand(dir: &Path) -> Result<Hand, String> {
+    let mut hand = Hand::new(HandManifest::load(&dir.join("HAND.toml"))?);
+    let guardrails = dir.join("guardrails.toml");
+    if guardrails.is_file() {
+        hand.guardrails = GuardrailConfig::load(&guardrails)?;
+    }
+    if let Ok(playbook) = std::fs::read_to_string(dir.join("system_prompt.md")) {
+        hand.playbook = playbook;
+    }
+    Ok(hand)
+}
+
 /// 7 built-in Hands.
 fn builtin_hands() -> Vec<HandManifest> {
     vec![
@@ -118,6 +178,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: false,
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 1800,
@@ -154,6 +215,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: false,
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 900,
@@ -190,6 +252,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: true, // Human reviews before publishing
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 1800,
@@ -219,6 +282,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: false,
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 120,
@@ -255,6 +319,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: true, // Requires approval for data pushes
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 600,
@@ -291,6 +356,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: false,
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 900,
@@ -327,6 +393,7 @@ fn builtin_hands() -> Vec<HandManifest> {
                     requires_approval: false,
                 },
             ],
+            agent: String::new(),
             provider: String::new(),
             model: String::new(),
             max_runtime_secs: 1200,
@@ -355,6 +422,68 @@ mod tests {
         assert!(names.contains(&"security"));
     }
 
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-hands-{}", std::process::id()));
+        let hand_dir = dir.join("invoices");
+        std::fs::create_dir_all(&hand_dir).unwrap();
+        std::fs::create_dir_all(dir.join("not-a-hand")).unwrap();
+        std::fs::write(
+            hand_dir.join("HAND.toml"),
+            r#"
+name = "invoices"
+label = "Invoice Hand"
+description = "Chase unpaid invoices"
+schedule = "manual"
+agent = "finance"
+
+[[phases]]
+name = "collect"
+description = "List unpaid invoices"
+"#,
+        )
+        .unwrap();
+        std::fs::write(hand_dir.join("system_prompt.md"), "Be polite.").unwrap();
+        std::fs::write(
+            hand_dir.join("guardrails.toml"),
+            r#"
+[[rules]]
+name = "no_shell"
+description = "Never run shell"
+trigger = { tool_use = "shell" }
+action = "block"
+"#,
+        )
+        .unwrap();
+
+        let mut reg = HandRegistry::new();
+        assert_eq!(reg.load_dir(&dir), 1);
+        let hand = reg.get("invoices").unwrap();
+        assert_eq!(hand.manifest.agent, "finance");
+        assert_eq!(hand.playbook, "Be polite.");
+        assert!(hand.guardrails.check_tool("shell").is_some());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_unregisters() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-hands-uninstall-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("invoices")).unwrap();
+        std::fs::write(
+            dir.join("invoices/HAND.toml"),
+            "name = \"invoices\"\nlabel = \"Invoices\"\ndescription = \"x\"\nschedule = \"manual\"\nphases = []\n",
+        )
+        .unwrap();
+        let mut reg = HandRegistry::with_defaults();
+        reg.load_dir(&dir);
+        assert_eq!(reg.count(), 8);
+
+        assert!(reg.uninstall("monitor", &dir).is_err());
+        reg.uninstall("invoices", &dir).unwrap();
+        assert_eq!(reg.count(), 7);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_registry_enable_disable() {
         let mut reg = HandRegistry::with_defaults();
@@ -1,19 +1,60 @@
 //! Hand Runner — executes Hands on their schedules.
 //!
 //! The runner is a background loop that checks all registered Hands,
-//! triggers those that are due, and manages their lifecycle.
+//! triggers those that are due, and manages their lifecycle. Phases run
+//! through an executor callback (the gateway hands them to an orchestrator
+//! agent), so this crate doesn't depend on the agent crate.
 
 use chrono::Utc;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::hand::{Hand, HandPhase, HandRunResult, HandStatus};
+use crate::guardrails::GuardrailAction;
+use crate::hand::{Hand, HandPhase, HandRunResult, HandStatus, PausedRun};
 use crate::registry::HandRegistry;
 
+/// Output of a phase, or why it failed.
+pub type PhaseFuture = Pin<Box<dyn Future<Output = Result<PhaseOutput, String>> + Send>>;
+type Executor = Arc<dyn Fn(PhaseRequest) -> PhaseFuture + Send + Sync>;
+type Journal = Arc<dyn Fn(&Hand, &HandPhase) + Send + Sync>;
+
+/// One playbook phase, ready for an agent.
+#[derive(Debug, Clone)]
+pub struct PhaseRequest {
+    pub hand: String,
+    /// Agent that runs the phase (empty = default agent).
+    pub agent: String,
+    pub phase: String,
+    pub prompt: String,
+    /// Tools the phase may use, without the ones guardrails block.
+    pub allowed_tools: Vec<String>,
+    /// Tool calls left in the run's budget (None = unlimited).
+    pub max_tool_calls: Option<u32>,
+}
+
+/// What an agent produced for a phase.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseOutput {
+    pub text: String,
+    /// Tool calls the agent made, counting refused ones.
+    pub tool_calls: u32,
+}
+
+impl From<String> for PhaseOutput {
+    fn from(text: String) -> Self {
+        Self { text, tool_calls: 0 }
+    }
+}
+
 /// The Hand Runner — background loop that drives all Hands.
+#[derive(Clone)]
 pub struct HandRunner {
     registry: Arc<Mutex<HandRegistry>>,
     tick_interval_secs: u64,
+    executor: Option<Executor>,
+    journal: Option<Journal>,
 }
 
 impl HandRunner {
@@ -22,9 +63,35 @@ impl HandRunner {
         Self {
             registry,
             tick_interval_secs,
+            executor: None,
+            journal: None,
         }
     }
 
+    /// Run phases with `executor`. Without one every phase fails.
+    pub fn with_executor<F, Fut>(mut self, executor: F) -> Self
+    where
+        F: Fn(PhaseRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<PhaseOutput, String>> + Send + 'static,
+    {
+        self.executor = Some(Arc::new(move |request| Box::pin(executor(request))));
+        self
+    }
+
+    /// Call `journal` with every finished phase, e.g. to write a daily log.
+    pub fn with_journal<F>(mut self, journal: F) -> Self
+    where
+        F: Fn(&Hand, &HandPhase) + Send + Sync + 'static,
+    {
+        self.journal = Some(Arc::new(journal));
+        self
+    }
+
+    /// The registry this runner drives.
+    pub fn registry(&self) -> &Arc<Mutex<HandRegistry>> {
+        &self.registry
+    }
+
     /// Start the background runner loop.
     /// This spawns a tokio task that checks and executes Hands.
     pub fn spawn(self) -> tokio::task::JoinHandle<()> {
@@ -43,98 +110,293 @@ impl HandRunner {
     /// Single tick — check all Hands and execute those that are due.
     async fn tick(&self) {
         let now = Utc::now();
+        let due_hands: Vec<String> = {
+            let registry = self.registry.lock().await;
+            registry
+                .list()
+                .iter()
+                .filter(|h| h.should_run(now))
+                .map(|h| h.manifest.name.clone())
+                .collect()
+        };
+
+        for hand_name in due_hands {
+            if let Err(e) = self.run(&hand_name, false).await {
+                tracing::warn!("⚠️ Hand {hand_name} not run: {e}");
+            }
+        }
+    }
+
+    /// Run a hand now, whatever its schedule.
+    pub async fn run_now(&self, name: &str) -> Result<HandRunResult, String> {
+        self.run(name, false).await
+    }
+
+    /// Approve the phase a hand is waiting on and finish its run.
+    pub async fn approve(&self, name: &str) -> Result<HandRunResult, String> {
+        self.run(name, true).await
+    }
+
+    /// Execute a hand without holding the registry lock, then record the run.
+    async fn run(&self, name: &str, approve: bool) -> Result<HandRunResult, String> {
+        let hand = {
+            let mut registry = self.registry.lock().await;
+            let hand = registry
+                .get_mut(name)
+                .ok_or_else(|| format!("hand '{name}' not found"))?;
+            let awaiting = hand.status == HandStatus::AwaitingApproval;
+            if hand.status == HandStatus::Running {
+                return Err(format!("hand '{name}' is already running"));
+            }
+            if awaiting != approve {
+                return Err(if approve {
+                    format!("hand '{name}' is not awaiting approval")
+                } else {
+                    format!("hand '{name}' is awaiting approval")
+                });
+            }
+            hand.status = HandStatus::Running;
+            hand.clone()
+        };
+
+        tracing::info!(
+            "🤚 Executing hand: {} {}",
+            hand.manifest.icon,
+            hand.manifest.label
+        );
+        let result = self.execute_hand(&hand, approve).await;
+
         let mut registry = self.registry.lock().await;
+        if let Some(hand) = registry.get_mut(name) {
+            if result.status == HandStatus::AwaitingApproval {
+                hand.status = HandStatus::AwaitingApproval;
+                hand.paused = Some(PausedRun {
+                    run_id: result.run_id.clone(),
+                    started_at: result.started_at,
+                    phases: result
+                        .phases
+                        .iter()
+                        .filter(|p| p.status == HandStatus::Completed)
+                        .cloned()
+                        .collect(),
+                });
+            } else {
+                hand.record_run(result.clone());
+                if !hand.manifest.enabled {
+                    hand.status = HandStatus::Disabled;
+                }
+            }
+            tracing::info!("🤚 Hand {} finished: {}", hand.manifest.label, result.summary);
+        }
+        Ok(result)
+    }
 
-        // Collect names of hands that should run
-        let due_hands: Vec<String> = registry
-            .list()
-            .iter()
-            .filter(|h| h.should_run(now))
-            .map(|h| h.manifest.name.clone())
-            .collect();
+    /// Execute a Hand's multi-phase playbook, resuming its paused run when
+    /// `approved`.
+    ///
+    /// Each phase gets the playbook and the earlier phases' output, may only
+    /// use its allowed tools minus those guardrails block, and stops for
+    /// approval when it (or one of its tools) requires it. The run is
+    /// aborted once it goes over the hand's budget.
+    async fn execute_hand(&self, hand: &Hand, approved: bool) -> HandRunResult {
+        let (run_id, started, mut phases) = match (&hand.paused, approved) {
+            (Some(paused), true) => (paused.run_id.clone(), paused.started_at, paused.phases.clone()),
+            _ => (uuid::Uuid::new_v4().to_string()[..8].to_string(), Utc::now(), Vec::new()),
+        };
+        let resume_at = phases.len();
+        let budget = &hand.guardrails.budget;
+        let max_runtime = budget
+            .max_runtime_secs
+            .map_or(hand.manifest.max_runtime_secs, |secs| secs.min(hand.manifest.max_runtime_secs));
+        // Time spent waiting for approval doesn't count
+        let deadline = Utc::now() + chrono::Duration::seconds(max_runtime as i64);
+        let mut total_tokens: u64 = phases.iter().map(|p| p.tokens_used).sum();
+        let mut total_tool_calls: u32 = phases.iter().map(|p| p.tool_calls).sum();
+        let mut budget_exceeded = None;
+        let mut status = HandStatus::Completed;
 
-        for hand_name in due_hands {
-            if let Some(hand) = registry.get_mut(&hand_name) {
-                tracing::info!(
-                    "🤚 Executing hand: {} {}",
-                    hand.manifest.icon,
-                    hand.manifest.label
-                );
-                hand.status = HandStatus::Running;
-
-                // Execute each phase
-                let result = execute_hand(hand).await;
-                hand.record_run(result);
-
-                tracing::info!(
-                    "🤚 Hand {} completed: {}",
-                    hand.manifest.label,
-                    hand.status
-                );
+        for (index, phase_manifest) in hand.manifest.phases.iter().enumerate().skip(resume_at) {
+            let allowed_tools: Vec<String> = phase_manifest
+                .allowed_tools
+                .iter()
+                .filter(|t| hand.guardrails.check_tool(t).is_none())
+                .cloned()
+                .collect();
+            let needs_approval = phase_manifest.requires_approval
+                || allowed_tools.iter().any(|t| hand.guardrails.requires_approval(t));
+            // Only the phase the run paused on counts as approved
+            if needs_approval && !(approved && index == resume_at) {
+                phases.push(HandPhase {
+                    name: phase_manifest.name.clone(),
+                    status: HandStatus::AwaitingApproval,
+                    started_at: None,
+                    completed_at: None,
+                    output: None,
+                    error: None,
+                    tokens_used: 0,
+                    tool_calls: 0,
+                });
+                status = HandStatus::AwaitingApproval;
+                break;
+            }
+
+            let phase_start = Utc::now();
+            let prompt = phase_prompt(hand, index, &phases);
+            let remaining = (deadline - phase_start).num_seconds();
+            let runtime_exceeded = format!("run time budget exceeded: {max_runtime}s");
+            let outcome = if remaining <= 0 {
+                budget_exceeded = Some(runtime_exceeded.clone());
+                Err(runtime_exceeded)
+            } else if let Some(executor) = &self.executor {
+                let timeout = phase_manifest.timeout_secs.min(remaining as u64);
+                let request = PhaseRequest {
+                    hand: hand.manifest.name.clone(),
+                    agent: hand.manifest.agent.clone(),
+                    phase: phase_manifest.name.clone(),
+                    prompt: prompt.clone(),
+                    allowed_tools,
+                    max_tool_calls: budget.max_tool_calls.map(|max| max.saturating_sub(total_tool_calls)),
+                };
+                match tokio::time::timeout(std::time::Duration::from_secs(timeout), executor(request)).await {
+                    Ok(outcome) => outcome,
+                    // Cut short by the run's deadline rather than the phase's own timeout
+                    Err(_) if timeout < phase_manifest.timeout_secs => {
+                        budget_exceeded = Some(runtime_exceeded.clone());
+                        Err(runtime_exceeded)
+                    }
+                    Err(_) => Err(format!("Timed out after {timeout}s")),
+                }
+            } else {
+                Err("No agent connected to run hands".into())
+            };
+
+            // Rough estimate: ~4 characters per token
+            let tokens_used = ((prompt.len() + outcome.as_ref().map_or(0, |o| o.text.len())) / 4) as u64;
+            total_tokens += tokens_used;
+            let tool_calls = outcome.as_ref().map_or(0, |o| o.tool_calls);
+            total_tool_calls += tool_calls;
+            let (output, error) = match outcome {
+                Ok(output) => (Some(output.text), None),
+                Err(e) => (None, Some(e)),
+            };
+            let mut phase = HandPhase {
+                name: phase_manifest.name.clone(),
+                status: if error.is_some() {
+                    HandStatus::Failed
+                } else {
+                    HandStatus::Completed
+                },
+                started_at: Some(phase_start),
+                completed_at: Some(Utc::now()),
+                output,
+                error,
+                tokens_used,
+                tool_calls,
+            };
+
+            let cost = estimate_hand_cost(total_tokens, &hand.manifest.model);
+            if budget_exceeded.is_none() {
+                budget_exceeded = budget.exceeded(total_tokens, cost, total_tool_calls);
+                if let Some(reason) = &budget_exceeded {
+                    phase.status = HandStatus::Failed;
+                    phase.error = Some(format!("Aborted: {reason}"));
+                }
+            }
+            if let Some(guardrail) = hand.guardrails.check_cost(cost) {
+                match guardrail.action {
+                    GuardrailAction::Block | GuardrailAction::RequireApproval => {
+                        phase.status = HandStatus::Failed;
+                        phase.error = Some(format!(
+                            "Stopped by guardrail '{}': cost ${cost:.4}",
+                            guardrail.name
+                        ));
+                    }
+                    _ => tracing::warn!(
+                        "💸 Hand {} passed guardrail '{}': cost ${cost:.4}",
+                        hand.manifest.name,
+                        guardrail.name
+                    ),
+                }
             }
+
+            if let Some(journal) = &self.journal {
+                journal(hand, &phase);
+            }
+            let failed = phase.status == HandStatus::Failed;
+            phases.push(phase);
+            if failed {
+                status = HandStatus::Failed;
+                break;
+            }
+        }
+
+        let completed = Utc::now();
+        if let Some(reason) = &budget_exceeded {
+            tracing::warn!("💸 Hand {} aborted: {reason}", hand.manifest.name);
+        }
+        let summary = match (&status, phases.last()) {
+            (HandStatus::Failed, Some(phase)) if budget_exceeded.is_some() => format!(
+                "{} aborted in phase '{}': {}",
+                hand.manifest.label,
+                phase.name,
+                budget_exceeded.as_deref().unwrap_or_default()
+            ),
+            (HandStatus::AwaitingApproval, Some(phase)) => format!(
+                "{} is awaiting approval before phase '{}'",
+                hand.manifest.label, phase.name
+            ),
+            (HandStatus::Failed, Some(phase)) => format!(
+                "{} failed in phase '{}': {}",
+                hand.manifest.label,
+                phase.name,
+                phase.error.as_deref().unwrap_or("unknown error")
+            ),
+            _ => format!(
+                "{} completed all {} phases in {:.1}s",
+                hand.manifest.label,
+                hand.manifest.phases.len(),
+                (completed - started).num_milliseconds() as f64 / 1000.0
+            ),
+        };
+
+        HandRunResult {
+            hand_name: hand.manifest.name.clone(),
+            run_id,
+            started_at: started,
+            completed_at: completed,
+            status,
+            phases,
+            total_tokens,
+            total_cost_usd: estimate_hand_cost(total_tokens, &hand.manifest.model),
+            total_tool_calls,
+            budget_exceeded,
+            summary,
         }
     }
 }
 
-/// Execute a single Hand's multi-phase playbook.
-///
-/// In a full implementation, each phase would:
-/// 1. Build a system prompt from the Hand's playbook
-/// 2. Call the LLM provider
-/// 3. Execute tool calls
-/// 4. Check guardrails before sensitive actions
-/// 5. Pass phase output to the next phase
-///
-/// For now, this creates a placeholder result.
-/// TODO: Integrate with bizclaw-agent for actual LLM execution.
-async fn execute_hand(hand: &Hand) -> HandRunResult {
-    let started = Utc::now();
-    let run_id = uuid::Uuid::new_v4().to_string()[..8].to_string();
-
-    let mut phases = Vec::new();
-    let mut total_tokens = 0u64;
-
-    for phase_manifest in &hand.manifest.phases {
-        let phase_start = Utc::now();
-
-        // TODO: Actual LLM execution per phase
-        // For now, simulate with placeholder
-        let est_tokens = 500u64;
-        total_tokens += est_tokens;
-
-        phases.push(HandPhase {
-            name: phase_manifest.name.clone(),
-            status: HandStatus::Completed,
-            started_at: Some(phase_start),
-            completed_at: Some(Utc::now()),
-            output: Some(format!("Phase '{}' executed successfully", phase_manifest.name)),
-            error: None,
-            tokens_used: est_tokens,
-        });
-
-        // Respect individual phase timeouts
-        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-    }
-
-    let completed = Utc::now();
-    let cost = estimate_hand_cost(total_tokens, &hand.manifest.model);
-
-    HandRunResult {
-        hand_name: hand.manifest.name.clone(),
-        run_id,
-        started_at: started,
-        completed_at: completed,
-        status: HandStatus::Completed,
-        phases,
-        total_tokens,
-        total_cost_usd: cost,
-        summary: format!(
-            "{} completed all {} phases in {:.1}s",
-            hand.manifest.label,
-            hand.manifest.phases.len(),
-            (completed - started).num_milliseconds() as f64 / 1000.0
-        ),
+/// Prompt for phase `index`: the hand's purpose, its playbook, the phase,
+/// and what earlier phases produced.
+fn phase_prompt(hand: &Hand, index: usize, done: &[HandPhase]) -> String {
+    let manifest = &hand.manifest;
+    let phase = &manifest.phases[index];
+    let mut prompt = format!("You are the {}. {}\n", manifest.label, manifest.description);
+    if !hand.playbook.trim().is_empty() {
+        prompt.push_str(&format!("\n{}\n", hand.playbook.trim()));
     }
+    prompt.push_str(&format!(
+        "\nPhase {}/{}: {} — {}\n",
+        index + 1,
+        manifest.phases.len(),
+        phase.name,
+        phase.description
+    ));
+    for earlier in done {
+        if let Some(output) = &earlier.output {
+            prompt.push_str(&format!("\n### Result of phase {}\n{output}\n", earlier.name));
+        }
+    }
+    prompt
 }
 
 /// Estimate cost for hand execution based on model.
@@ -154,6 +416,67 @@ fn estimate_hand_cost(tokens: u64, model: &str) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::guardrails::{Guardrail, GuardrailConfig, GuardrailTrigger};
+    use crate::manifest::{HandManifest, HandSchedule, PhaseManifest};
+
+    fn phase(name: &str, tools: &[&str], requires_approval: bool) -> PhaseManifest {
+        PhaseManifest {
+            name: name.into(),
+            description: format!("Do the {name}"),
+            allowed_tools: tools.iter().map(|t| t.to_string()).collect(),
+            timeout_secs: 60,
+            requires_approval,
+        }
+    }
+
+    fn runner_with(phases: Vec<PhaseManifest>) -> (HandRunner, Arc<std::sync::Mutex<Vec<PhaseRequest>>>) {
+        let mut hand = Hand::new(HandManifest {
+            name: "outreach".into(),
+            label: "Outreach Hand".into(),
+            icon: "📧".into(),
+            description: "Email leads".into(),
+            version: "1.0.0".into(),
+            schedule: HandSchedule::Manual,
+            phases,
+            agent: "sales".into(),
+            provider: String::new(),
+            model: String::new(),
+            max_runtime_secs: 600,
+            enabled: true,
+            notify_channels: vec![],
+        });
+        hand.playbook = "Never promise discounts.".into();
+        hand.guardrails = GuardrailConfig {
+            budget: Default::default(),
+            rules: vec![
+                Guardrail {
+                    name: "no_shell".into(),
+                    description: "Block shell".into(),
+                    trigger: GuardrailTrigger::ToolUse("shell".into()),
+                    action: GuardrailAction::Block,
+                    enabled: true,
+                },
+                Guardrail {
+                    name: "approve_email".into(),
+                    description: "Approve outgoing email".into(),
+                    trigger: GuardrailTrigger::ToolUse("email".into()),
+                    action: GuardrailAction::RequireApproval,
+                    enabled: true,
+                },
+            ],
+        };
+        let mut registry = HandRegistry::new();
+        registry.insert(hand);
+
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let runner = HandRunner::new(Arc::new(Mutex::new(registry)), 60).with_executor(move |request: PhaseRequest| {
+            let output = format!("{} done", request.phase);
+            seen.lock().unwrap().push(request);
+            async move { Ok(PhaseOutput { text: output, tool_calls: 2 }) }
+        });
+        (runner, requests)
+    }
 
     #[test]
     fn test_cost_estimation() {
@@ -161,4 +484,96 @@ mod tests {
         assert!(estimate_hand_cost(1000, "gpt-4o") > 0.005);
         assert!(estimate_hand_cost(1000, "deepseek-chat") < 0.005);
     }
+
+    #[tokio::test]
+    async fn test_phases_run_through_executor() {
+        let (runner, requests) = runner_with(vec![
+            phase("research", &["web_search", "shell"], false),
+            phase("draft", &["file"], false),
+        ]);
+        let journaled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = journaled.clone();
+        let runner = runner.with_journal(move |hand, phase| {
+            log.lock().unwrap().push(format!("{}/{}", hand.manifest.name, phase.name));
+        });
+
+        let result = runner.run_now("outreach").await.unwrap();
+        assert_eq!(result.status, HandStatus::Completed);
+        assert_eq!(result.phases[1].output.as_deref(), Some("draft done"));
+        assert!(result.total_tokens > 0);
+
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests[0].agent, "sales");
+        assert_eq!(requests[0].allowed_tools, vec!["web_search"]);
+        assert!(requests[0].prompt.contains("Never promise discounts."));
+        assert!(requests[1].prompt.contains("research done"));
+        assert_eq!(*journaled.lock().unwrap(), vec!["outreach/research", "outreach/draft"]);
+
+        let registry = runner.registry().lock().await;
+        let hand = registry.get("outreach").unwrap();
+        assert_eq!(hand.run_count, 1);
+        assert_eq!(hand.status, HandStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_approval_pauses_and_resumes() {
+        let (runner, requests) = runner_with(vec![
+            phase("research", &["web_search"], false),
+            phase("send", &["email"], false),
+        ]);
+
+        let paused = runner.run_now("outreach").await.unwrap();
+        assert_eq!(paused.status, HandStatus::AwaitingApproval);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(runner.run_now("outreach").await.is_err());
+
+        let result = runner.approve("outreach").await.unwrap();
+        assert_eq!(result.status, HandStatus::Completed);
+        assert_eq!(result.run_id, paused.run_id);
+        assert_eq!(result.phases.len(), 2);
+        assert_eq!(requests.lock().unwrap()[1].phase, "send");
+        assert!(runner.approve("outreach").await.is_err());
+
+        let registry = runner.registry().lock().await;
+        let hand = registry.get("outreach").unwrap();
+        assert_eq!(hand.run_count, 1);
+        assert!(hand.paused.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_aborted_over_budget() {
+        let (runner, requests) = runner_with(vec![
+            phase("research", &["web_search"], false),
+            phase("draft", &["file"], false),
+            phase("review", &["file"], false),
+        ]);
+        runner.registry().lock().await.get_mut("outreach").unwrap().guardrails.budget.max_tool_calls = Some(3);
+
+        let result = runner.run_now("outreach").await.unwrap();
+        assert_eq!(result.status, HandStatus::Failed);
+        assert_eq!(result.total_tool_calls, 4);
+        assert_eq!(result.budget_exceeded.as_deref(), Some("tool call budget exceeded: 4 of 3"));
+        assert_eq!(result.phases.len(), 2);
+        assert!(result.summary.contains("aborted in phase 'draft'"));
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests[0].max_tool_calls, Some(3));
+        assert_eq!(requests[1].max_tool_calls, Some(1));
+
+        let registry = runner.registry().lock().await;
+        let hand = registry.get("outreach").unwrap();
+        assert_eq!(hand.last_error.as_deref(), Some("Aborted: tool call budget exceeded: 4 of 3"));
+    }
+
+    #[tokio::test]
+    async fn test_phase_fails_without_executor() {
+        let mut registry = HandRegistry::new();
+        registry.register(HandRegistry::with_defaults().get("monitor").unwrap().manifest.clone());
+        let runner = HandRunner::new(Arc::new(Mutex::new(registry)), 60);
+
+        let result = runner.run_now("monitor").await.unwrap();
+        assert_eq!(result.status, HandStatus::Failed);
+        assert_eq!(result.phases.len(), 1);
+        let registry = runner.registry().lock().await;
+        assert!(registry.get("monitor").unwrap().last_error.is_some());
+    }
 }
@@ -1,63 +1,311 @@
 //! Document chunker — splits documents into search-friendly chunks.
-//! Designed for minimal memory: processes line-by-line, never loads full doc.
+//!
+//! Chunks follow the document's structure: Markdown headings start a new
+//! chunk, paragraphs (and code blocks) are kept whole when they fit, and
+//! oversized ones are split at line, then sentence, then word boundaries.
+//! Consecutive chunks of a section share a few trailing sentences so a
+//! passage cut at a boundary is still found whole.
 
-/// Split text into chunks of approximately `max_chars` characters.
-/// Breaks at paragraph boundaries and word boundaries.
+use std::path::Path;
+
+use bizclaw_core::config::KnowledgeConfig;
+
+/// Chunk size and overlap, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Target maximum chunk length (at least 100).
+    pub max_chars: usize,
+    /// Trailing text of a chunk repeated at the start of the next one in
+    /// the same section (at most half of `max_chars`).
+    pub overlap: usize,
+}
+
+impl ChunkOptions {
+    pub fn from_config(config: &KnowledgeConfig) -> Self {
+        Self {
+            max_chars: config.chunk_size,
+            overlap: config.chunk_overlap,
+        }
+    }
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self::from_config(&KnowledgeConfig::default())
+    }
+}
+
+/// A chunk and where it sits in its document, for citations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub content: String,
+    /// Document title: the first `# ` heading, else the file name.
+    pub title: String,
+    /// Headings enclosing the chunk, outermost first.
+    pub heading_path: Vec<String>,
+}
+
+/// Split text into chunks of approximately `max_chars` characters, without
+/// overlap. Breaks at paragraph, sentence and word boundaries.
 pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
-    let max_chars = max_chars.max(100); // Min 100 chars
+    let opts = ChunkOptions { max_chars, overlap: 0 };
+    chunk_document(text, "", &opts)
+        .into_iter()
+        .map(|c| c.content)
+        .collect()
+}
+
+/// Split a document into chunks, following Markdown headings for `.md`
+/// files. Other formats are converted with [`extract_text`] first.
+pub fn chunk_document(content: &str, filename: &str, opts: &ChunkOptions) -> Vec<Chunk> {
+    let max_chars = opts.max_chars.max(100);
+    let overlap = opts.overlap.min(max_chars / 2);
+    let ext = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    let (h1, sections) = if matches!(ext.as_str(), "md" | "markdown") {
+        markdown_sections(content)
+    } else {
+        (None, vec![(Vec::new(), extract_text(content, filename))])
+    };
+    let title = h1.unwrap_or_else(|| {
+        Path::new(filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
     let mut chunks = Vec::new();
-    let mut current = String::new();
+    for (heading_path, body) in sections {
+        for content in pack(&body, max_chars, overlap) {
+            chunks.push(Chunk {
+                content,
+                title: title.clone(),
+                heading_path: heading_path.clone(),
+            });
+        }
+    }
+    chunks
+}
 
-    for line in text.lines() {
-        let line = line.trim();
+/// A section's heading path and text.
+type Section = (Vec<String>, String);
 
-        // Empty line = paragraph break → natural chunk boundary
-        if line.is_empty() && !current.is_empty() && current.len() > max_chars / 2 {
-            chunks.push(std::mem::take(&mut current));
-            continue;
+/// Markdown split at its headings: the first `# ` heading, and each
+/// non-empty section with its text (markup stripped, code blocks as is),
+/// starting with its own heading so that it is searchable too.
+fn markdown_sections(content: &str) -> (Option<String>, Vec<Section>) {
+    let mut h1 = None;
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut sections = Vec::new();
+    let mut body = String::new();
+    let mut in_code = false;
+
+    let flush = |headings: &[(usize, String)], body: &mut String, sections: &mut Vec<Section>| {
+        if body.trim().is_empty() {
+            body.clear();
+            return;
         }
+        let path: Vec<String> = headings.iter().map(|(_, h)| h.clone()).collect();
+        let text = match path.last() {
+            Some(heading) => format!("{heading}\n{}", body.trim_end()),
+            None => body.trim_end().to_string(),
+        };
+        sections.push((path, text));
+        body.clear();
+    };
 
-        // If the line itself is longer than max_chars, split by words
-        if line.len() > max_chars {
-            // Flush current buffer first
-            if !current.is_empty() {
-                chunks.push(std::mem::take(&mut current));
+    for line in content.lines() {
+        if is_fence(line) {
+            in_code = !in_code;
+        } else if !in_code && let Some((level, heading)) = heading(line) {
+            flush(&headings, &mut body, &mut sections);
+            while headings.last().is_some_and(|(l, _)| *l >= level) {
+                headings.pop();
             }
-            // Split long line by words
-            for word in line.split_whitespace() {
-                if !current.is_empty() && current.len() + word.len() + 1 > max_chars {
-                    chunks.push(std::mem::take(&mut current));
-                }
-                if !current.is_empty() {
-                    current.push(' ');
-                }
-                current.push_str(word);
+            if level == 1 && h1.is_none() {
+                h1 = Some(heading.clone());
             }
+            headings.push((level, heading));
             continue;
         }
+        body.push_str(if in_code || is_fence(line) { line } else { strip_markdown_line(line) });
+        body.push('\n');
+    }
+    flush(&headings, &mut body, &mut sections);
+    (h1, sections)
+}
+
+/// Level and text of an ATX heading line (`## Title`).
+fn heading(line: &str) -> Option<(usize, String)> {
+    let line = line.trim();
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    let text = line[level..].strip_prefix(' ')?.trim().trim_end_matches('#').trim();
+    ((1..=6).contains(&level) && !text.is_empty()).then(|| (level, text.to_string()))
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// A Markdown line without heading, list and quote markers.
+fn strip_markdown_line(line: &str) -> &str {
+    line.trim_start_matches('#')
+        .trim_start_matches('*')
+        .trim_start_matches('-')
+        .trim_start_matches('>')
+        .trim()
+}
 
-        // If adding this line would exceed max_chars, flush current chunk
-        if !current.is_empty() && current.len() + line.len() + 1 > max_chars {
-            chunks.push(std::mem::take(&mut current));
+/// Pack the paragraphs of `text` into chunks of at most `max_chars`,
+/// starting each chunk with up to `overlap` characters of the previous one.
+fn pack(text: &str, max_chars: usize, overlap: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Whether `current` holds anything besides the overlap carried over
+    let mut fresh = false;
+    for block in blocks(text) {
+        for (sep, piece) in split_block(&block, max_chars) {
+            if current.len() + sep.len() + piece.len() > max_chars {
+                if fresh {
+                    let tail = overlap_tail(&current, overlap).to_string();
+                    chunks.push(std::mem::replace(&mut current, tail));
+                }
+                if current.len() + sep.len() + piece.len() > max_chars {
+                    current.clear();
+                }
+            }
+            if !current.is_empty() {
+                current.push_str(sep);
+            }
+            current.push_str(&piece);
+            fresh = true;
         }
+    }
+    if fresh {
+        chunks.push(current);
+    }
+    chunks
+}
 
+/// Paragraphs of `text`; fenced code blocks are kept whole.
+fn blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        if is_fence(line) {
+            in_code = !in_code;
+        }
+        let line = if in_code { line.trim_end() } else { line.trim() };
+        if line.is_empty() && !in_code {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
         if !current.is_empty() {
             current.push('\n');
         }
         current.push_str(line);
     }
-
-    // Don't forget the last chunk
     if !current.is_empty() {
-        chunks.push(current);
+        blocks.push(current);
     }
+    blocks
+}
 
-    chunks
+/// `block` in pieces of at most `max_chars` — whole, by line, by sentence,
+/// then by word — each with the separator joining it to the one before.
+fn split_block(block: &str, max_chars: usize) -> Vec<(&'static str, String)> {
+    if block.len() <= max_chars {
+        return vec![("\n\n", block.to_string())];
+    }
+    let mut pieces = Vec::new();
+    for (i, line) in block.lines().enumerate() {
+        let line_sep = if i == 0 { "\n\n" } else { "\n" };
+        if line.len() <= max_chars {
+            pieces.push((line_sep, line.to_string()));
+            continue;
+        }
+        for (j, sentence) in sentences(line).into_iter().enumerate() {
+            let mut sep = if j == 0 { line_sep } else { " " };
+            if sentence.len() <= max_chars {
+                pieces.push((sep, sentence.to_string()));
+                continue;
+            }
+            let mut part = String::new();
+            for word in sentence.split_whitespace() {
+                if !part.is_empty() && part.len() + word.len() + 1 > max_chars {
+                    pieces.push((sep, std::mem::take(&mut part)));
+                    sep = " ";
+                }
+                if !part.is_empty() {
+                    part.push(' ');
+                }
+                part.push_str(word);
+            }
+            if !part.is_empty() {
+                pieces.push((sep, part));
+            }
+        }
+    }
+    pieces
+}
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。')
+}
+
+/// `text` split after sentence-ending punctuation.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if is_sentence_end(c) && chars.peek().is_some_and(|(_, next)| next.is_whitespace()) {
+            let end = i + c.len_utf8();
+            out.push(text[start..end].trim());
+            start = end;
+        }
+    }
+    out.push(text[start..].trim());
+    out.retain(|s| !s.is_empty());
+    out
+}
+
+/// The whole trailing sentences of `chunk` that fit in `overlap`
+/// characters, or trailing words when the last sentence is longer.
+fn overlap_tail(chunk: &str, overlap: usize) -> &str {
+    if overlap == 0 {
+        return "";
+    }
+    let min_start = chunk.len().saturating_sub(overlap);
+    let mut last = '\n'; // last non-whitespace character
+    let mut in_gap = false;
+    let mut newline = false;
+    let mut word = None;
+    for (i, c) in chunk.char_indices() {
+        if c.is_whitespace() {
+            in_gap = true;
+            newline |= c == '\n';
+            continue;
+        }
+        if in_gap && i >= min_start {
+            if newline || is_sentence_end(last) {
+                return &chunk[i..];
+            }
+            word.get_or_insert(i);
+        }
+        in_gap = false;
+        newline = false;
+        last = c;
+    }
+    word.map_or("", |i| &chunk[i..])
 }
 
 /// Extract plain text from common file formats.
-/// Supports: .txt, .md, .json, .toml, .yaml, .csv, .log
-/// For Pi: no heavy PDF/DOCX parsing — keep it simple.
+/// Supports: .txt, .md, .json, .toml, .yaml, .csv, .log, .html
+/// PDF/DOCX uploads are converted to text first (see [`crate::extract`]).
 pub fn extract_text(content: &str, filename: &str) -> String {
     let ext = filename.rsplit('.').next().unwrap_or("txt").to_lowercase();
     match ext.as_str() {
@@ -65,13 +313,7 @@ pub fn extract_text(content: &str, filename: &str) -> String {
             // Strip markdown syntax for better search
             content
                 .lines()
-                .map(|l| {
-                    l.trim_start_matches('#')
-                        .trim_start_matches('*')
-                        .trim_start_matches('-')
-                        .trim_start_matches('>')
-                        .trim()
-                })
+                .map(strip_markdown_line)
                 .collect::<Vec<_>>()
                 .join("\n")
         }
@@ -83,6 +325,7 @@ pub fn extract_text(content: &str, filename: &str) -> String {
                 content.to_string()
             }
         }
+        "html" | "htm" => crate::extract::html_text(content),
         _ => content.to_string(),
     }
 }
@@ -120,7 +363,7 @@ mod tests {
     fn test_chunk_paragraphs() {
         let text = "Paragraph one line one.\nParagraph one line two.\n\nParagraph two line one.\nParagraph two line two.";
         let chunks = chunk_text(text, 100);
-        assert!(chunks.len() >= 1);
+        assert!(!chunks.is_empty());
     }
 
     #[test]
@@ -142,6 +385,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chunk_markdown_headings() {
+        let md = "# Handbook\nIntro.\n\n## Leave\n- Annual leave is 12 days.\n\n### Sick leave\nBring a note.\n\
+                  ```\n# not a heading\n\nstill code\n```\n## Remote\nAsk your manager.";
+        let chunks = chunk_document(md, "wiki/policies.md", &ChunkOptions::default());
+        let paths: Vec<_> = chunks.iter().map(|c| c.heading_path.join(" > ")).collect();
+        assert_eq!(
+            paths,
+            ["Handbook", "Handbook > Leave", "Handbook > Leave > Sick leave", "Handbook > Remote"]
+        );
+        assert!(chunks.iter().all(|c| c.title == "Handbook"));
+        assert_eq!(chunks[1].content, "Leave\nAnnual leave is 12 days.");
+        assert!(chunks[2].content.contains("# not a heading\n\nstill code"));
+
+        let plain = chunk_document("No headings here.", "notes/todo.txt", &ChunkOptions::default());
+        assert_eq!(plain[0].title, "todo");
+        assert!(plain[0].heading_path.is_empty());
+    }
+
+    #[test]
+    fn test_chunk_overlap_at_sentences() {
+        let text: String = (0..30).map(|i| format!("Sentence number {i} is here. ")).collect();
+        let opts = ChunkOptions { max_chars: 200, overlap: 60 };
+        let chunks = chunk_document(&text, "a.txt", &opts);
+        assert!(chunks.len() >= 4);
+        for pair in chunks.windows(2) {
+            let (prev, next) = (&pair[0].content, &pair[1].content);
+            assert!(prev.len() <= 200 && next.len() <= 200);
+            // The next chunk starts with whole sentences from the end of this one
+            let first = next.split(". ").next().unwrap();
+            assert!(first.starts_with("Sentence number"));
+            assert!(prev.contains(first));
+        }
+    }
+
     #[test]
     fn test_extract_markdown() {
         let md = "# Title\n## Sub\n- item\n> quote";
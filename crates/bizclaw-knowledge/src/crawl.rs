@@ -0,0 +1,322 @@
+//! Web crawler — fetches a page, and optionally the same-site pages it
+//! links to, keeps the readable text and imports each page under its URL
+//! so that answers can cite it. Like the browse tool, it checks every
+//! redirect hop against the SSRF block list, honours robots.txt and caps
+//! the bytes read per page.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use bizclaw_tools::browse::{Robots, read_capped};
+use bizclaw_tools::http_request::{PublicResolver, is_url_blocked};
+use reqwest::Url;
+
+use crate::extract::{extract_file, html_text, unescape};
+use crate::store::{ImportSummary, KnowledgeStore};
+
+/// Linked files that are never pages.
+const ASSET_EXTS: &[&str] = &[
+    "css", "js", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp", "woff", "woff2", "ttf", "zip", "gz",
+    "mp3", "mp4", "webm", "xml", "rss",
+];
+
+/// Redirects followed for one page.
+const MAX_REDIRECTS: usize = 5;
+/// Most bytes of a page read; larger pages are skipped.
+const MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Most bytes of a robots.txt read.
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+/// How far a crawl goes.
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// Link hops followed from the start page (0 = that page only).
+    pub max_depth: usize,
+    /// Pages fetched at most.
+    pub max_pages: usize,
+    /// Timeout of each request.
+    pub timeout: Duration,
+    /// Fetch hosts on this machine or a private network, e.g. an intranet
+    /// wiki. Off by default.
+    pub allow_private: bool,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 0,
+            max_pages: 50,
+            timeout: Duration::from_secs(15),
+            allow_private: false,
+        }
+    }
+}
+
+/// Fetch `url` and, up to `max_depth` hops away, the pages on the same
+/// host that it links to. Returns each page's URL and text; pages that
+/// fail or have no text are skipped with a warning.
+pub async fn fetch_pages(url: &str, opts: &CrawlOptions) -> Result<Vec<(String, String)>, String> {
+    let mut start = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+    if !matches!(start.scheme(), "http" | "https") {
+        return Err(format!("Only http(s) URLs can be crawled: {url}"));
+    }
+    start.set_fragment(None);
+    let mut client = reqwest::Client::builder()
+        .timeout(opts.timeout)
+        .user_agent(concat!("BizClaw/", env!("CARGO_PKG_VERSION")))
+        // Redirects are followed by hand so each hop is checked
+        .redirect(reqwest::redirect::Policy::none());
+    if !opts.allow_private {
+        // Names resolving to internal addresses fail to connect
+        client = client.dns_resolver(std::sync::Arc::new(PublicResolver));
+    }
+    let client = client.build().map_err(|e| format!("HTTP client error: {e}"))?;
+    let mut fetcher = Fetcher {
+        client,
+        allow_private: opts.allow_private,
+        robots: HashMap::new(),
+    };
+
+    let mut queue = VecDeque::from([(start.clone(), 0)]);
+    let mut seen = HashSet::from([start.to_string()]);
+    let mut pages = Vec::new();
+    let mut fetched = 0;
+    while let Some((page, depth)) = queue.pop_front() {
+        if fetched >= opts.max_pages {
+            break;
+        }
+        fetched += 1;
+        let (text, links) = match fetcher.page(&page).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                tracing::warn!("🕸️ Skipping {page}: {e}");
+                continue;
+            }
+        };
+        if depth < opts.max_depth {
+            for link in links {
+                if link.host_str() == start.host_str() && seen.insert(link.to_string()) {
+                    queue.push_back((link, depth + 1));
+                }
+            }
+        }
+        if !text.trim().is_empty() {
+            pages.push((page.to_string(), text));
+        }
+    }
+    if pages.is_empty() {
+        return Err(format!("No text found at {url}"));
+    }
+    tracing::info!("🕸️ Crawled {url}: {} page(s) with text of {fetched} fetched", pages.len());
+    Ok(pages)
+}
+
+/// Crawl `url` (see [`fetch_pages`]) and import the pages into `store`'s
+/// `namespace`, named by their URL, with `url` as the source. Pages are
+/// fetched without holding the lock; re-crawling updates changed pages in
+/// place.
+pub async fn crawl_url(
+    store: &tokio::sync::Mutex<Option<KnowledgeStore>>,
+    url: &str,
+    namespace: &str,
+    opts: &CrawlOptions,
+) -> Result<ImportSummary, String> {
+    let pages = fetch_pages(url, opts).await?;
+    let guard = store.lock().await;
+    let kb = guard.as_ref().ok_or("Knowledge base not available")?;
+    kb.import_documents(&pages, url, namespace)
+}
+
+/// Fetches pages for one crawl, remembering each site's robots.txt.
+struct Fetcher {
+    client: reqwest::Client,
+    allow_private: bool,
+    /// Parsed robots.txt per origin (`scheme://host:port`).
+    robots: HashMap<String, Robots>,
+}
+
+impl Fetcher {
+    /// Why `url` may not be fetched, if it may not.
+    async fn check(&mut self, url: &Url) -> Option<String> {
+        if !self.allow_private
+            && let Some(reason) = is_url_blocked(url.as_str())
+        {
+            return Some(reason);
+        }
+        let origin = url.origin().ascii_serialization();
+        if !self.robots.contains_key(&origin) {
+            let text = match self.client.get(format!("{origin}/robots.txt")).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    let (body, _) = read_capped(resp, MAX_ROBOTS_BYTES).await.unwrap_or_default();
+                    String::from_utf8_lossy(&body).into_owned()
+                }
+                _ => String::new(),
+            };
+            self.robots.insert(origin.clone(), Robots::parse(&text));
+        }
+        if !self.robots[&origin].allows(url.path()) {
+            return Some(format!("robots.txt disallows {}", url.path()));
+        }
+        None
+    }
+
+    /// Readable text and outgoing links of one page.
+    async fn page(&mut self, url: &Url) -> Result<(String, Vec<Url>), String> {
+        let mut url = url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            if let Some(reason) = self.check(&url).await {
+                return Err(format!("blocked: {reason}"));
+            }
+            let resp = self.client.get(url.clone()).send().await.map_err(|e| e.to_string())?;
+            let status = resp.status();
+            if status.is_redirection() {
+                let location = resp
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .ok_or_else(|| format!("HTTP {status} without a Location"))?;
+                url = url.join(location).map_err(|e| format!("bad redirect '{location}': {e}"))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(format!("HTTP {status}"));
+            }
+            let content_type = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_lowercase();
+            let (bytes, cut) = read_capped(resp, MAX_PAGE_BYTES).await?;
+            if cut {
+                return Err(format!("larger than {} MB", MAX_PAGE_BYTES / (1024 * 1024)));
+            }
+            // Links are relative to where redirects ended up
+            return parse_page(&content_type, &bytes, &url);
+        }
+        Err(format!("more than {MAX_REDIRECTS} redirects"))
+    }
+}
+
+/// Readable text and outgoing links of a fetched page.
+fn parse_page(content_type: &str, bytes: &[u8], base: &Url) -> Result<(String, Vec<Url>), String> {
+    if content_type.contains("html") {
+        let html = String::from_utf8_lossy(bytes);
+        Ok((html_text(&html), links(&html, base)))
+    } else if content_type.contains("pdf") {
+        Ok((extract_file("page.pdf", bytes)?, vec![]))
+    } else if content_type.is_empty() || content_type.starts_with("text/") {
+        Ok((String::from_utf8_lossy(bytes).into_owned(), vec![]))
+    } else {
+        Err(format!("unsupported content type '{content_type}'"))
+    }
+}
+
+/// Absolute http(s) links in `html`, without fragments or asset files.
+fn links(html: &str, base: &Url) -> Vec<Url> {
+    // ASCII-only lowering keeps byte offsets valid in `html`
+    let lower = html.to_ascii_lowercase();
+    let mut found: Vec<Url> = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find("href=") {
+        let start = pos + offset + "href=".len();
+        pos = start;
+        let value = match html[start..].chars().next() {
+            Some(quote @ ('"' | '\'')) => html[start + 1..].split(quote).next().unwrap_or(""),
+            _ => html[start..]
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        let Ok(mut link) = base.join(unescape(value).trim()) else { continue };
+        let ext = link.path().rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+        if !matches!(link.scheme(), "http" | "https") || ext.is_some_and(|e| ASSET_EXTS.contains(&e.as_str())) {
+            continue;
+        }
+        link.set_fragment(None);
+        if !found.contains(&link) {
+            found.push(link);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_links_resolve_and_skip_assets() {
+        let base = Url::parse("https://docs.example.com/guide/start.html").unwrap();
+        let html = r#"<link href="/style.css"><a href="leave.html#annual">Leave</a>
+            <a href='https://docs.example.com/faq?a=1&amp;b=2'>FAQ</a> <a href=../about>About</a>
+            <a href="mailto:hr@example.com">Mail</a> <a href="leave.html">Again</a>"#;
+        let found: Vec<String> = links(html, &base).iter().map(Url::to_string).collect();
+        assert_eq!(
+            found,
+            [
+                "https://docs.example.com/guide/leave.html",
+                "https://docs.example.com/faq?a=1&b=2",
+                "https://docs.example.com/about",
+            ]
+        );
+    }
+
+    /// Serve `pages` (path, html) over HTTP until the test ends.
+    async fn serve(pages: &'static [(&'static str, &'static str)]) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let response = match pages.iter().find(|(p, _)| *p == path) {
+                    Some((_, html)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{html}",
+                        html.len()
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_crawl_follows_links_to_depth() {
+        static PAGES: &[(&str, &str)] = &[
+            ("/", r#"<body><nav><a href="/nav-only">Menu</a></nav><p>Welcome to the handbook.</p>
+                <a href="/leave">Leave</a> <a href="/missing">Gone</a> <a href="https://elsewhere.test/x">Out</a>
+                <a href="/private/salaries">Salaries</a></body>"#),
+            ("/private/salaries", "<body><p>Salary table.</p></body>"),
+            ("/robots.txt", "User-agent: *\nDisallow: /private/"),
+            ("/leave", r#"<body><p>Annual leave is 12 days.</p><a href="/leave/carry-over">More</a></body>"#),
+            ("/leave/carry-over", "<body><p>Unused days carry over.</p></body>"),
+        ];
+        let base = serve(PAGES).await;
+        let store = tokio::sync::Mutex::new(Some(KnowledgeStore::open(Path::new(":memory:")).unwrap()));
+
+        // Local addresses are refused unless allowed
+        let opts = CrawlOptions { max_depth: 1, ..Default::default() };
+        assert!(crawl_url(&store, &format!("{base}/"), "docs", &opts).await.is_err());
+
+        let opts = CrawlOptions { allow_private: true, ..opts };
+        let summary = crawl_url(&store, &format!("{base}/"), "docs", &opts).await.unwrap();
+        // Start page and /leave; /missing 404s, /private is disallowed by
+        // robots.txt, carry-over is 2 hops away
+        assert_eq!(summary.added, 2);
+
+        let guard = store.lock().await;
+        let kb = guard.as_ref().unwrap();
+        let hits = kb.search("annual leave", 5);
+        assert_eq!(hits[0].doc_name, format!("{base}/leave"));
+        assert!(kb.search("carry", 5).is_empty());
+        assert!(kb.search("salary", 5).is_empty());
+        assert!(kb.list_documents().iter().all(|(_, _, source, _, ns)| *source == format!("{base}/") && ns == "docs"));
+    }
+}
@@ -0,0 +1,171 @@
+//! Text extraction for uploaded office documents and web pages.
+//!
+//! - **PDF** — text layer via `pdf-extract` (scanned PDFs have none)
+//! - **DOCX** — paragraphs from `word/document.xml`
+//! - **HTML** — readable text: `<article>`/`<main>` when present, without
+//!   scripts, styles and page chrome
+
+use std::io::Read;
+
+/// Plain text of an uploaded file, by extension. Text formats are decoded
+/// as UTF-8 and go through [`crate::chunker::extract_text`] at indexing.
+pub fn extract_file(filename: &str, bytes: &[u8]) -> Result<String, String> {
+    let ext = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    let text = match ext.as_str() {
+        "pdf" => pdf_text(bytes)?,
+        "docx" => docx_text(bytes)?,
+        "html" | "htm" => html_text(&String::from_utf8_lossy(bytes)),
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|_| format!("'{filename}' is not UTF-8 text or a supported document type"))?,
+    };
+    if text.trim().is_empty() {
+        return Err(format!("No text found in '{filename}'"));
+    }
+    Ok(text)
+}
+
+fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    // pdf-extract panics on some malformed files
+    std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(bytes))
+        .map_err(|_| "Failed to parse PDF: malformed file".to_string())?
+        .map_err(|e| format!("Failed to parse PDF: {e}"))
+}
+
+fn docx_text(bytes: &[u8]) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Invalid DOCX archive: {e}"))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|_| "Not a valid DOCX file (missing word/document.xml)".to_string())?
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read DOCX: {e}"))?;
+
+    let mut text = String::new();
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else { break };
+        let tag = &rest[start + 1..start + len];
+        let name = tag.trim_start_matches('/').split([' ', '/']).next().unwrap_or("");
+        rest = &rest[start + len + 1..];
+        match name {
+            // Text run content up to its closing tag
+            "w:t" if !tag.starts_with('/') && !tag.ends_with('/') => {
+                let end = rest.find("</w:t>").unwrap_or(rest.len());
+                text.push_str(&unescape(&rest[..end]));
+                rest = &rest[end..];
+            }
+            "w:tab" => text.push('\t'),
+            "w:br" | "w:cr" => text.push('\n'),
+            "w:p" if tag.starts_with('/') => text.push('\n'),
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+/// Readable text of an HTML page.
+pub fn html_text(html: &str) -> String {
+    // ASCII-only lowering keeps byte offsets valid in `html`
+    let lower = html.to_ascii_lowercase();
+    // Prefer the main content when the page marks it
+    let body = ["article", "main", "body"]
+        .iter()
+        .find_map(|tag| {
+            let open = lower.find(&format!("<{tag}"))?;
+            let close = lower.rfind(&format!("</{tag}>"))?;
+            (close > open).then(|| &html[open..close])
+        })
+        .unwrap_or(html);
+    let lower_body = body.to_ascii_lowercase();
+
+    const SKIP: &[&str] = &["script", "style", "noscript", "nav", "header", "footer", "aside", "form", "svg"];
+    const BLOCK: &[&str] = &[
+        "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "section", "blockquote", "pre", "table",
+    ];
+
+    let mut text = String::new();
+    let mut i = 0;
+    while let Some(offset) = body[i..].find('<') {
+        text.push_str(&unescape(&body[i..i + offset]));
+        let start = i + offset;
+        let Some(len) = body[start..].find('>') else {
+            i = body.len();
+            break;
+        };
+        let tag = &lower_body[start + 1..start + len];
+        let name = tag.trim_start_matches('/').split([' ', '/', '\t', '\n']).next().unwrap_or("");
+        i = start + len + 1;
+        if !tag.starts_with('/') && SKIP.contains(&name) {
+            // Jump past the whole element
+            i = lower_body[i..]
+                .find(&format!("</{name}>"))
+                .map_or(body.len(), |end| i + end + name.len() + 3);
+        } else if BLOCK.contains(&name) {
+            text.push('\n');
+        }
+    }
+    text.push_str(&unescape(&body[i..]));
+
+    // Collapse whitespace, keeping one blank line between blocks
+    let mut out = String::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+        } else {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out.trim().to_string()
+}
+
+pub(crate) fn unescape(s: &str) -> String {
+    s.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_html_keeps_article_text_only() {
+        let html = r#"<html><head><title>T</title><style>p{}</style></head><body>
+            <nav><a href="/">Home</a></nav>
+            <article><h1>Nghỉ phép</h1><p>Mỗi năm 12 ngày &amp; thêm 1 ngày</p>
+            <script>track()</script><p>Báo trước 3 ngày.</p></article>
+            <footer>© 2025</footer></body></html>"#;
+        assert_eq!(html_text(html), "Nghỉ phép\n\nMỗi năm 12 ngày & thêm 1 ngày\n\nBáo trước 3 ngày.");
+    }
+
+    #[test]
+    fn test_docx_paragraphs() {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        zip.start_file("word/document.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(
+            br#"<w:document><w:body><w:p><w:r><w:t>Hello</w:t></w:r><w:r><w:t xml:space="preserve"> world</w:t></w:r></w:p><w:p><w:r><w:t>A &amp; B</w:t><w:tab/><w:t>C</w:t></w:r></w:p></w:body></w:document>"#,
+        )
+        .unwrap();
+        zip.finish().unwrap();
+        let text = extract_file("policy.docx", buf.get_ref()).unwrap();
+        assert_eq!(text, "Hello world\nA & B\tC\n");
+    }
+
+    #[test]
+    fn test_rejects_binary_and_empty() {
+        assert!(extract_file("image.png", &[0xff, 0xd8, 0xff, 0x00]).is_err());
+        assert!(extract_file("broken.pdf", b"%PDF-1.4 garbage").is_err());
+        assert!(extract_file("empty.html", b"<html><script>x()</script></html>").is_err());
+    }
+}
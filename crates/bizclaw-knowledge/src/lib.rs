@@ -6,8 +6,11 @@
 //! ## Design
 //! - **SQLite FTS5** for full-text search (built-in, zero setup)
 //! - **BM25 scoring** — relevance ranking without embeddings
-//! - **Chunking** — split documents into ~500 char chunks
+//! - **Chunking** — ~500 char chunks along headings and paragraphs, with overlap
 //! - **File-based** — documents stored as-is, index in SQLite
+//! - **Uploads** — PDF, DOCX and HTML converted to text on import
+//! - **Watched folder** — files dropped into a folder are indexed automatically
+//! - **Web crawl** — pages fetched from a URL, cited by their address
 //! - RAM: ~2MB for 1000 document chunks
 //!
 //! ## How it works
@@ -24,8 +27,11 @@
 //! ```
 
 pub mod chunker;
+pub mod crawl;
+pub mod extract;
 pub mod search;
 pub mod store;
+pub mod watch;
 
 pub use search::SearchResult;
-pub use store::KnowledgeStore;
+pub use store::{DEFAULT_NAMESPACE, ImportResult, ImportStatus, ImportSummary, KnowledgeStore};
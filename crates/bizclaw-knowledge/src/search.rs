@@ -13,12 +13,26 @@ pub struct SearchResult {
     pub content: String,
     /// BM25 relevance score (lower = more relevant in SQLite FTS5).
     pub score: f64,
+    /// Document title (first `# ` heading, else the file name).
+    #[serde(default)]
+    pub title: String,
+    /// Headings enclosing the chunk, outermost first.
+    #[serde(default)]
+    pub heading_path: Vec<String>,
 }
 
 impl SearchResult {
+    /// Where the chunk comes from: `doc.md › Section › Subsection`.
+    pub fn citation(&self) -> String {
+        std::iter::once(self.doc_name.as_str())
+            .chain(self.heading_path.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" › ")
+    }
+
     /// Format as context for the Agent system prompt.
     pub fn as_context(&self) -> String {
-        format!("[📄 {}] {}", self.doc_name, self.content)
+        format!("[📄 {}] {}", self.citation(), self.content)
     }
 }
 
@@ -2,15 +2,82 @@
 //! No vector DB, no embeddings — just BM25 relevance scoring.
 //! This is intentionally lightweight for 512MB RAM devices.
 
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 
-use crate::chunker;
+use crate::chunker::{self, ChunkOptions};
 use crate::search::SearchResult;
 
+/// Outcome of one document in a batch import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Added,
+    Updated,
+    Unchanged,
+    Removed,
+    Failed,
+}
+
+/// Per-file result of a batch import.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    pub name: String,
+    pub status: ImportStatus,
+    pub chunks: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Aggregated result of a batch import.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub files: Vec<ImportResult>,
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+    pub total_chunks: usize,
+}
+
+impl ImportSummary {
+    fn record(&mut self, name: &str, result: Result<(ImportStatus, usize), String>) {
+        let (status, chunks, error) = match result {
+            Ok((status, chunks)) => (status, chunks, None),
+            Err(e) => (ImportStatus::Failed, 0, Some(e)),
+        };
+        match status {
+            ImportStatus::Added => self.added += 1,
+            ImportStatus::Updated => self.updated += 1,
+            ImportStatus::Unchanged => self.unchanged += 1,
+            ImportStatus::Removed => self.removed += 1,
+            ImportStatus::Failed => self.failed += 1,
+        }
+        self.total_chunks += chunks;
+        self.files.push(ImportResult {
+            name: name.to_string(),
+            status,
+            chunks,
+            error,
+        });
+    }
+}
+
+/// Namespace of documents added without one.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Hex SHA-256 of document content.
+fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
 /// Knowledge store backed by SQLite FTS5.
 pub struct KnowledgeStore {
     conn: Connection,
+    chunking: ChunkOptions,
 }
 
 impl KnowledgeStore {
@@ -27,7 +94,9 @@ impl KnowledgeStore {
                 name TEXT NOT NULL,
                 source TEXT DEFAULT '',
                 created_at TEXT DEFAULT (datetime('now')),
-                chunk_count INTEGER DEFAULT 0
+                chunk_count INTEGER DEFAULT 0,
+                content_hash TEXT DEFAULT '',
+                namespace TEXT DEFAULT 'default'
             );
 
             -- FTS5 virtual table for full-text search with BM25
@@ -38,6 +107,15 @@ impl KnowledgeStore {
                 tokenize='unicode61'
             );
 
+            -- Title and heading path of each chunk, for citations
+            CREATE TABLE IF NOT EXISTS chunk_meta (
+                doc_id INTEGER NOT NULL,
+                chunk_idx INTEGER NOT NULL,
+                title TEXT DEFAULT '',
+                heading_path TEXT DEFAULT '[]',
+                PRIMARY KEY (doc_id, chunk_idx)
+            );
+
             -- Metadata for quick stats
             CREATE TABLE IF NOT EXISTS kb_meta (
                 key TEXT PRIMARY KEY,
@@ -47,8 +125,44 @@ impl KnowledgeStore {
         )
         .map_err(|e| format!("Schema error: {e}"))?;
 
+        // Migration: content_hash for skipping unchanged re-imports
+        let has_hash: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='content_hash'",
+                [],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap_or(0)
+            > 0;
+        if !has_hash {
+            conn.execute_batch("ALTER TABLE documents ADD COLUMN content_hash TEXT DEFAULT '';")
+                .map_err(|e| format!("Migration error: {e}"))?;
+        }
+
+        // Migration: namespaces (per agent or tenant); old documents are shared
+        let has_namespace: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='namespace'",
+                [],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap_or(0)
+            > 0;
+        if !has_namespace {
+            conn.execute_batch("ALTER TABLE documents ADD COLUMN namespace TEXT DEFAULT 'default';")
+                .map_err(|e| format!("Migration error: {e}"))?;
+        }
+
         tracing::debug!("📚 Knowledge store opened: {}", path.display());
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            chunking: ChunkOptions::default(),
+        })
+    }
+
+    /// Chunk size and overlap for documents indexed from now on.
+    pub fn set_chunking(&mut self, chunking: ChunkOptions) {
+        self.chunking = chunking;
     }
 
     /// Default knowledge base path.
@@ -57,21 +171,135 @@ impl KnowledgeStore {
         home.join(".bizclaw").join("knowledge.db")
     }
 
-    /// Add a document to the knowledge base.
+    /// Add a document to the knowledge base in `namespace`.
     /// Automatically chunks and indexes the content.
-    pub fn add_document(&self, name: &str, content: &str, source: &str) -> Result<usize, String> {
-        // Extract text based on file extension
-        let text = chunker::extract_text(content, name);
+    pub fn add_document(
+        &self,
+        name: &str,
+        content: &str,
+        source: &str,
+        namespace: &str,
+    ) -> Result<usize, String> {
+        let chunk_count = self.index_document(name, content, source, namespace, &content_hash(content))?;
+        tracing::info!("📄 Added '{}' → {} chunks indexed", name, chunk_count);
+        Ok(chunk_count)
+    }
 
-        // Chunk the text
-        let chunks = chunker::chunk_text(&text, 500);
+    /// Import many documents into `namespace` in a single transaction.
+    ///
+    /// Documents already indexed under the same name + source with identical
+    /// content are skipped; changed ones are re-indexed in place.
+    pub fn import_documents(
+        &self,
+        docs: &[(String, String)],
+        source: &str,
+        namespace: &str,
+    ) -> Result<ImportSummary, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Begin import error: {e}"))?;
+
+        let mut summary = ImportSummary::default();
+        for (name, content) in docs {
+            let hash = content_hash(content);
+            let existing: Option<(i64, String)> = self
+                .conn
+                .query_row(
+                    "SELECT id, content_hash FROM documents WHERE name = ?1 AND source = ?2 AND namespace = ?3",
+                    params![name, source, namespace],
+                    |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default())),
+                )
+                .optional()
+                .map_err(|e| format!("Lookup doc error: {e}"))?;
+
+            let result = match existing {
+                Some((_, ref old)) if *old == hash => Ok((ImportStatus::Unchanged, 0)),
+                Some((id, _)) => self
+                    .remove_document(id)
+                    .and_then(|_| self.index_document(name, content, source, namespace, &hash))
+                    .map(|n| (ImportStatus::Updated, n)),
+                None => self
+                    .index_document(name, content, source, namespace, &hash)
+                    .map(|n| (ImportStatus::Added, n)),
+            };
+            summary.record(name, result);
+        }
+
+        tx.commit().map_err(|e| format!("Commit import error: {e}"))?;
+        tracing::info!(
+            "📚 Imported {} docs: {} added, {} updated, {} unchanged, {} failed ({} chunks)",
+            docs.len(),
+            summary.added,
+            summary.updated,
+            summary.unchanged,
+            summary.failed,
+            summary.total_chunks
+        );
+        Ok(summary)
+    }
+
+    /// Import uploaded files (PDF, DOCX, HTML or text), converting each to
+    /// text first. Files without extractable text are reported as failed.
+    pub fn import_files(
+        &self,
+        files: &[(String, Vec<u8>)],
+        source: &str,
+        namespace: &str,
+    ) -> Result<ImportSummary, String> {
+        let mut docs = Vec::new();
+        let mut failed = Vec::new();
+        for (name, bytes) in files {
+            match crate::extract::extract_file(name, bytes) {
+                Ok(text) => docs.push((name.clone(), text)),
+                Err(e) => failed.push((name, e)),
+            }
+        }
+        let mut summary = self.import_documents(&docs, source, namespace)?;
+        for (name, e) in failed {
+            summary.record(name, Err(e));
+        }
+        Ok(summary)
+    }
+
+    /// Make the documents under `source` in `namespace` match `docs`:
+    /// import new and changed ones and remove those no longer present (see
+    /// [`crate::watch`]).
+    pub fn sync_documents(
+        &self,
+        docs: &[(String, String)],
+        source: &str,
+        namespace: &str,
+    ) -> Result<ImportSummary, String> {
+        let mut summary = self.import_documents(docs, source, namespace)?;
+        for (id, name, doc_source, _, doc_namespace) in self.list_documents() {
+            if doc_source == source && doc_namespace == namespace && !docs.iter().any(|(n, _)| *n == name) {
+                let result = self.remove_document(id).map(|_| (ImportStatus::Removed, 0));
+                summary.record(&name, result);
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Chunk and index one document, returning the chunk count.
+    fn index_document(
+        &self,
+        name: &str,
+        content: &str,
+        source: &str,
+        namespace: &str,
+        hash: &str,
+    ) -> Result<usize, String> {
+        // Extract text based on file extension and chunk it by structure
+        let chunks = chunker::chunk_document(content, name, &self.chunking);
         let chunk_count = chunks.len();
 
         // Insert document record
         self.conn
             .execute(
-                "INSERT INTO documents (name, source, chunk_count) VALUES (?1, ?2, ?3)",
-                params![name, source, chunk_count as i64],
+                "INSERT INTO documents (name, source, chunk_count, content_hash, namespace)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![name, source, chunk_count as i64, hash, namespace],
             )
             .map_err(|e| format!("Insert doc error: {e}"))?;
 
@@ -82,17 +310,28 @@ impl KnowledgeStore {
             self.conn
                 .execute(
                     "INSERT INTO chunks (doc_id, chunk_idx, content) VALUES (?1, ?2, ?3)",
-                    params![doc_id.to_string(), idx.to_string(), chunk],
+                    params![doc_id.to_string(), idx.to_string(), chunk.content],
                 )
                 .map_err(|e| format!("Insert chunk error: {e}"))?;
+            let heading_path = serde_json::to_string(&chunk.heading_path).unwrap_or_default();
+            self.conn
+                .execute(
+                    "INSERT INTO chunk_meta (doc_id, chunk_idx, title, heading_path) VALUES (?1, ?2, ?3, ?4)",
+                    params![doc_id, idx as i64, chunk.title, heading_path],
+                )
+                .map_err(|e| format!("Insert chunk meta error: {e}"))?;
         }
 
-        tracing::info!("📄 Added '{}' → {} chunks indexed", name, chunk_count);
         Ok(chunk_count)
     }
 
     /// Search the knowledge base using BM25 ranking.
     pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.search_in(query, limit, &[])
+    }
+
+    /// Search only documents in `namespaces` (all when empty).
+    pub fn search_in(&self, query: &str, limit: usize, namespaces: &[String]) -> Vec<SearchResult> {
         let limit = limit.min(10); // Max 10 results
 
         // Clean query for FTS5
@@ -107,10 +346,14 @@ impl KnowledgeStore {
 
         // FTS5 search with BM25 scoring
         let mut stmt = match self.conn.prepare(
-            "SELECT c.doc_id, c.chunk_idx, c.content, d.name, bm25(chunks) as score
+            "SELECT c.doc_id, c.chunk_idx, c.content, d.name, bm25(chunks) as score,
+                    m.title, m.heading_path
              FROM chunks c
              JOIN documents d ON d.id = CAST(c.doc_id AS INTEGER)
+             LEFT JOIN chunk_meta m
+               ON m.doc_id = CAST(c.doc_id AS INTEGER) AND m.chunk_idx = CAST(c.chunk_idx AS INTEGER)
              WHERE chunks MATCH ?1
+               AND (?3 = '[]' OR d.namespace IN (SELECT value FROM json_each(?3)))
              ORDER BY score
              LIMIT ?2",
         ) {
@@ -121,12 +364,19 @@ impl KnowledgeStore {
             }
         };
 
-        let results = stmt.query_map(params![clean_query, limit as i64], |row| {
+        let namespaces = serde_json::to_string(namespaces).unwrap_or_else(|_| "[]".into());
+        let results = stmt.query_map(params![clean_query, limit as i64, namespaces], |row| {
             Ok(SearchResult {
                 doc_name: row.get(3)?,
                 chunk_idx: row.get::<_, String>(1)?.parse().unwrap_or(0),
                 content: row.get(2)?,
                 score: row.get(4)?,
+                // Documents indexed before chunk metadata have none
+                title: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                heading_path: row
+                    .get::<_, Option<String>>(6)?
+                    .and_then(|p| serde_json::from_str(&p).ok())
+                    .unwrap_or_default(),
             })
         });
 
@@ -139,11 +389,11 @@ impl KnowledgeStore {
         }
     }
 
-    /// List all documents.
-    pub fn list_documents(&self) -> Vec<(i64, String, String, i64)> {
+    /// List all documents: id, name, source, chunk count and namespace.
+    pub fn list_documents(&self) -> Vec<(i64, String, String, i64, String)> {
         let mut stmt = match self
             .conn
-            .prepare("SELECT id, name, source, chunk_count FROM documents ORDER BY id DESC") {
+            .prepare("SELECT id, name, source, chunk_count, namespace FROM documents ORDER BY id DESC") {
                 Ok(s) => s,
                 Err(e) => {
                     tracing::warn!("list_documents prepare error: {e}");
@@ -152,7 +402,13 @@ impl KnowledgeStore {
             };
 
         stmt.query_map([], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get::<_, Option<String>>(4)?.unwrap_or_else(|| DEFAULT_NAMESPACE.into()),
+            ))
         })
         .map(|rows| rows.filter_map(|r| r.ok()).collect())
         .unwrap_or_default()
@@ -167,6 +423,10 @@ impl KnowledgeStore {
             )
             .map_err(|e| format!("Delete chunks error: {e}"))?;
 
+        self.conn
+            .execute("DELETE FROM chunk_meta WHERE doc_id = ?1", params![doc_id])
+            .map_err(|e| format!("Delete chunk meta error: {e}"))?;
+
         self.conn
             .execute("DELETE FROM documents WHERE id = ?1", params![doc_id])
             .map_err(|e| format!("Delete doc error: {e}"))?;
@@ -187,3 +447,101 @@ impl KnowledgeStore {
         (doc_count as usize, chunk_count as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> KnowledgeStore {
+        KnowledgeStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn doc(name: &str, content: &str) -> (String, String) {
+        (name.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_import_batch_and_dedup() {
+        let store = temp_store();
+        let long = "Remote work policy paragraph. ".repeat(40); // > 500 chars → multiple chunks
+        let batch = vec![
+            doc("wiki/onboarding.md", "# Onboarding\nWelcome to the team."),
+            doc("wiki/remote.txt", &long),
+            doc("wiki/faq.md", "## FAQ\nHow do I request leave?"),
+        ];
+
+        let first = store.import_documents(&batch, "wiki", DEFAULT_NAMESPACE).unwrap();
+        assert_eq!(first.added, 3);
+        assert_eq!(first.files.len(), 3);
+        let per_file: usize = first.files.iter().map(|f| f.chunks).sum();
+        assert_eq!(first.total_chunks, per_file);
+        assert!(first.files[1].chunks >= 2);
+        assert_eq!(store.stats(), (3, first.total_chunks));
+
+        // Same batch again: everything unchanged, nothing re-indexed
+        let second = store.import_documents(&batch, "wiki", DEFAULT_NAMESPACE).unwrap();
+        assert_eq!(second.unchanged, 3);
+        assert_eq!(second.total_chunks, 0);
+        assert_eq!(store.stats(), (3, first.total_chunks));
+
+        // One file edited: re-indexed in place, others skipped
+        let mut edited = batch.clone();
+        edited[2].1 = "## FAQ\nHow do I request leave?\nAsk HR.".into();
+        let third = store.import_documents(&edited, "wiki", DEFAULT_NAMESPACE).unwrap();
+        assert_eq!((third.updated, third.unchanged), (1, 2));
+        assert_eq!(third.files[2].status, ImportStatus::Updated);
+        assert_eq!(store.stats().0, 3);
+        assert!(!store.search("HR", 5).is_empty());
+    }
+
+    #[test]
+    fn test_search_returns_heading_path() {
+        let store = temp_store();
+        let md = "# Handbook\nWelcome.\n\n## Leave\nAnnual leave is 12 days.";
+        store.add_document("handbook.md", md, "api", DEFAULT_NAMESPACE).unwrap();
+        let results = store.search("annual", 5);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Handbook");
+        assert_eq!(results[0].heading_path, ["Handbook", "Leave"]);
+        assert_eq!(results[0].citation(), "handbook.md › Handbook › Leave");
+
+        let id = store.list_documents()[0].0;
+        store.remove_document(id).unwrap();
+        let meta: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM chunk_meta", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(meta, 0);
+    }
+
+    #[test]
+    fn test_search_scoped_to_namespaces() {
+        let store = temp_store();
+        store.add_document("sales.md", "Discount policy: 10% for members", "api", "sales").unwrap();
+        store.add_document("hr.md", "Leave policy: 12 days", "api", "hr").unwrap();
+        store.add_document("faq.md", "Company policy overview", "api", DEFAULT_NAMESPACE).unwrap();
+
+        let names = |ns: &[&str]| {
+            let ns: Vec<String> = ns.iter().map(|n| n.to_string()).collect();
+            let mut names: Vec<String> = store.search_in("policy", 10, &ns).into_iter().map(|r| r.doc_name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&["sales"]), ["sales.md"]);
+        assert_eq!(names(&["hr", "default"]), ["faq.md", "hr.md"]);
+        assert_eq!(names(&[]).len(), 3);
+
+        // The same name in another namespace is a separate document
+        let summary = store.import_documents(&[doc("faq.md", "Company policy overview")], "api", "hr").unwrap();
+        assert_eq!(summary.added, 1);
+        assert_eq!(store.list_documents()[0].4, "hr");
+    }
+
+    #[test]
+    fn test_add_document_still_indexes() {
+        let store = temp_store();
+        let chunks = store.add_document("a.txt", "hello knowledge", "api", DEFAULT_NAMESPACE).unwrap();
+        assert_eq!(chunks, 1);
+        assert_eq!(store.search("knowledge", 5).len(), 1);
+    }
+}
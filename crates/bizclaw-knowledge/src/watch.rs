@@ -0,0 +1,180 @@
+//! Watched folders — files dropped into a directory are indexed
+//! automatically, changed files re-indexed and deleted files removed.
+//!
+//! Polls instead of using OS file events: a scan only stats files, and
+//! files are read and extracted only after something changed.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::store::{DEFAULT_NAMESPACE, KnowledgeStore};
+
+/// Formats picked up from folders.
+pub const IMPORT_EXTS: &[&str] = &[
+    "md", "markdown", "txt", "json", "toml", "yaml", "yml", "csv", "log", "rst", "html", "htm", "pdf", "docx",
+];
+
+/// Importable files under `root` (skipping hidden ones), sorted.
+pub fn importable_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            let ext = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if IMPORT_EXTS.contains(&ext.as_str()) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Text of the importable files under `root`, named by their relative
+/// path. Files whose text can't be extracted are skipped with a warning.
+pub fn collect_files(root: &Path) -> Vec<(String, String)> {
+    read_files(root, importable_files(root))
+}
+
+/// Text of `files`, named by their path relative to `root`. Files whose
+/// text can't be extracted are skipped with a warning.
+pub fn read_files(root: &Path, files: Vec<PathBuf>) -> Vec<(String, String)> {
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+            let content = std::fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| crate::extract::extract_file(&name, &bytes));
+            content
+                .inspect_err(|e| tracing::warn!("Skipping {name}: {e}"))
+                .ok()
+                .map(|content| (name, content))
+        })
+        .collect()
+}
+
+/// Path, size and modification time of each importable file — changes
+/// when a file is added, edited or deleted.
+fn fingerprint(root: &Path) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    importable_files(root)
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok();
+            let len = meta.as_ref().map_or(0, |m| m.len());
+            let modified = meta.and_then(|m| m.modified().ok());
+            (path, len, modified)
+        })
+        .collect()
+}
+
+/// `path` with a leading `~/` replaced by the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(path), |home| home.join(rest)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Keep `store` in sync with `dir`, checking every `interval`. The folder
+/// is created if missing, and synced once at start. Files go to the
+/// default namespace.
+pub fn spawn_watcher(
+    store: Arc<tokio::sync::Mutex<Option<KnowledgeStore>>>,
+    dir: PathBuf,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("📂 Cannot watch {}: {e}", dir.display());
+            return;
+        }
+        tracing::info!("📂 Watching {} for knowledge documents", dir.display());
+        let mut last = None;
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(100)));
+        loop {
+            ticker.tick().await;
+            let scan_dir = dir.clone();
+            let Ok(current) = tokio::task::spawn_blocking(move || fingerprint(&scan_dir)).await else {
+                continue;
+            };
+            if last.as_ref() == Some(&current) {
+                continue;
+            }
+            let read_dir = dir.clone();
+            let Ok(docs) = tokio::task::spawn_blocking(move || collect_files(&read_dir)).await else {
+                continue;
+            };
+            let guard = store.lock().await;
+            let Some(kb) = guard.as_ref() else { return };
+            match kb.sync_documents(&docs, &dir.to_string_lossy(), DEFAULT_NAMESPACE) {
+                Ok(summary) if summary.added + summary.updated + summary.removed > 0 => tracing::info!(
+                    "📂 {}: {} added, {} updated, {} removed",
+                    dir.display(),
+                    summary.added,
+                    summary.updated,
+                    summary.removed
+                ),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("📂 Sync of {} failed: {e}", dir.display());
+                    continue; // retry on the next tick
+                }
+            }
+            last = Some(current);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watcher_adds_updates_and_removes() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-kb-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("leave.md"), "# Leave\nAnnual leave is 12 days.").unwrap();
+        std::fs::write(dir.join(".draft.md"), "hidden remote draft").unwrap();
+
+        let store = Arc::new(tokio::sync::Mutex::new(Some(
+            KnowledgeStore::open(Path::new(":memory:")).unwrap(),
+        )));
+        let watcher = spawn_watcher(store.clone(), dir.clone(), Duration::from_millis(100));
+        let settled = |query: &'static str| {
+            let store = store.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                let guard = store.lock().await;
+                let kb = guard.as_ref().unwrap();
+                (kb.list_documents().len(), kb.search(query, 5).len())
+            }
+        };
+        assert_eq!(settled("remote").await, (1, 0));
+
+        std::fs::write(dir.join("remote.txt"), "Remote work needs manager approval.").unwrap();
+        assert_eq!(settled("remote").await, (2, 1));
+
+        std::fs::write(dir.join("leave.md"), "# Leave\nAnnual leave is fifteen days from 2026.").unwrap();
+        assert_eq!(settled("fifteen").await, (2, 1));
+
+        std::fs::remove_file(dir.join("remote.txt")).unwrap();
+        assert_eq!(settled("remote").await, (1, 0));
+
+        watcher.abort();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
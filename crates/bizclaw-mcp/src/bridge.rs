@@ -55,9 +55,18 @@ impl Tool for McpToolBridge {
         let args: serde_json::Value =
             serde_json::from_str(arguments).unwrap_or(serde_json::json!({}));
 
-        // Call the MCP tool
+        // Call the MCP tool, restarting the server once if its process died
         let mut client = self.client.lock().await;
-        match client.call_tool(&self.info.name, args).await {
+        let mut result = client.call_tool(&self.info.name, args.clone()).await;
+        if result.is_err() && !client.is_connected() {
+            tracing::warn!("🔄 MCP server '{}' is down, reconnecting", self.info.server_name);
+            client.disconnect().await;
+            result = match client.connect().await {
+                Ok(()) => client.call_tool(&self.info.name, args).await,
+                Err(e) => Err(format!("reconnect failed: {e}")),
+            };
+        }
+        match result {
             Ok(output) => Ok(ToolResult {
                 tool_call_id: String::new(),
                 output,
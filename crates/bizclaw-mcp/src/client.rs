@@ -1,14 +1,21 @@
 //! MCP Client — connects to an MCP server, discovers tools, and calls them.
+//! Servers that declare the capabilities also have their resources and
+//! prompts listed, to be read and fetched on demand.
 
-use crate::transport::StdioTransport;
+use crate::http::HttpTransport;
+use crate::transport::{StdioTransport, Transport};
 use crate::types::*;
 
 /// MCP Client — manages connection to a single MCP server.
 pub struct McpClient {
     pub name: String,
     config: McpServerConfig,
-    transport: Option<StdioTransport>,
+    transport: Option<Transport>,
     tools: Vec<McpToolInfo>,
+    resources: Vec<McpResourceInfo>,
+    prompts: Vec<McpPromptInfo>,
+    /// Capabilities the server declared when initializing.
+    capabilities: serde_json::Value,
     next_id: u64,
 }
 
@@ -20,11 +27,15 @@ impl McpClient {
             config,
             transport: None,
             tools: vec![],
+            resources: vec![],
+            prompts: vec![],
+            capabilities: serde_json::Value::Null,
             next_id: 1,
         }
     }
 
-    /// Connect to the MCP server — spawn process + initialize + discover tools.
+    /// Connect to the MCP server — spawn the process (or reach the remote
+    /// URL) + initialize + discover tools.
     pub async fn connect(&mut self) -> Result<(), String> {
         if !self.config.enabled {
             return Err(format!("MCP server '{}' is disabled", self.name));
@@ -32,10 +43,15 @@ impl McpClient {
 
         tracing::info!("🔗 Connecting to MCP server '{}'...", self.name);
 
-        // Spawn the server process
-        let transport =
-            StdioTransport::spawn(&self.config.command, &self.config.args, &self.config.env)
-                .await?;
+        // Spawn the server process, or talk to a remote one
+        let transport = if self.config.url.is_empty() {
+            Transport::Stdio(
+                StdioTransport::spawn(&self.config.command, &self.config.args, &self.config.env)
+                    .await?,
+            )
+        } else {
+            Transport::Http(HttpTransport::new(&self.config)?)
+        };
         self.transport = Some(transport);
 
         // Initialize the MCP session
@@ -44,10 +60,25 @@ impl McpClient {
         // Discover available tools
         self.discover_tools().await?;
 
+        // Resources and prompts are optional extras: a server failing to
+        // list them still serves its tools
+        if self.capabilities.get("resources").is_some()
+            && let Err(e) = self.discover_resources().await
+        {
+            tracing::warn!("⚠️ MCP server '{}' resources unavailable: {e}", self.name);
+        }
+        if self.capabilities.get("prompts").is_some()
+            && let Err(e) = self.discover_prompts().await
+        {
+            tracing::warn!("⚠️ MCP server '{}' prompts unavailable: {e}", self.name);
+        }
+
         tracing::info!(
-            "✅ MCP server '{}' connected — {} tools available",
+            "✅ MCP server '{}' connected — {} tools, {} resources, {} prompts available",
             self.name,
-            self.tools.len()
+            self.tools.len(),
+            self.resources.len(),
+            self.prompts.len()
         );
 
         Ok(())
@@ -80,6 +111,11 @@ impl McpClient {
                 err.message, err.code
             ));
         }
+        self.capabilities = res
+            .result
+            .map(|r| r["capabilities"].clone())
+            .unwrap_or_default();
+        let transport = self.transport.as_mut().ok_or("Not connected")?;
 
         // Send initialized notification (no response expected, but send via request for simplicity)
         let notify = JsonRpcRequest::new(id2, "notifications/initialized", None);
@@ -127,6 +163,99 @@ impl McpClient {
         Ok(())
     }
 
+    /// Discover resources from the MCP server.
+    async fn discover_resources(&mut self) -> Result<(), String> {
+        let result = self.call("resources/list", None).await?;
+        let list: ResourcesListResult =
+            serde_json::from_value(result).map_err(|e| format!("Parse resources error: {e}"))?;
+        self.resources = list
+            .resources
+            .into_iter()
+            .map(|r| McpResourceInfo {
+                name: r.name.unwrap_or_else(|| r.uri.clone()),
+                uri: r.uri,
+                description: r.description.unwrap_or_default(),
+                mime_type: r.mime_type,
+                server_name: self.name.clone(),
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Discover prompt templates from the MCP server.
+    async fn discover_prompts(&mut self) -> Result<(), String> {
+        let result = self.call("prompts/list", None).await?;
+        let list: PromptsListResult =
+            serde_json::from_value(result).map_err(|e| format!("Parse prompts error: {e}"))?;
+        self.prompts = list
+            .prompts
+            .into_iter()
+            .map(|p| McpPromptInfo {
+                name: p.name,
+                description: p.description.unwrap_or_default(),
+                arguments: p.arguments,
+                server_name: self.name.clone(),
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Read a resource's text contents (binary contents are skipped).
+    pub async fn read_resource(&mut self, uri: &str) -> Result<String, String> {
+        let result = self
+            .call("resources/read", Some(serde_json::json!({ "uri": uri })))
+            .await
+            .map_err(|e| format!("Resource '{uri}' error: {e}"))?;
+        let read: ResourceReadResult = serde_json::from_value(result)
+            .map_err(|e| format!("Parse resource error: {e}"))?;
+        Ok(read
+            .contents
+            .into_iter()
+            .filter_map(|c| c.text)
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Fetch a prompt template filled in with `arguments`, as the text of
+    /// its messages.
+    pub async fn get_prompt(
+        &mut self,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<String, String> {
+        let result = self
+            .call(
+                "prompts/get",
+                Some(serde_json::json!({ "name": name, "arguments": arguments })),
+            )
+            .await
+            .map_err(|e| format!("Prompt '{name}' error: {e}"))?;
+        let prompt: PromptGetResult =
+            serde_json::from_value(result).map_err(|e| format!("Parse prompt error: {e}"))?;
+        Ok(prompt
+            .messages
+            .into_iter()
+            .filter_map(|m| m.content.text)
+            .collect::<Vec<_>>()
+            .join("\n\n"))
+    }
+
+    /// Send a request and return its result, turning JSON-RPC errors into
+    /// `Err`.
+    async fn call(
+        &mut self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, String> {
+        let id = self.next_id();
+        let transport = self.transport.as_mut().ok_or("MCP server not connected")?;
+        let res = transport.request(&JsonRpcRequest::new(id, method, params)).await?;
+        if let Some(err) = res.error {
+            return Err(format!("{} (code {})", err.message, err.code));
+        }
+        Ok(res.result.unwrap_or_default())
+    }
+
     /// Call a tool on the MCP server.
     pub async fn call_tool(
         &mut self,
@@ -194,6 +323,16 @@ impl McpClient {
         &self.tools
     }
 
+    /// Get discovered resources.
+    pub fn resources(&self) -> &[McpResourceInfo] {
+        &self.resources
+    }
+
+    /// Get discovered prompt templates.
+    pub fn prompts(&self) -> &[McpPromptInfo] {
+        &self.prompts
+    }
+
     /// Check if connected and alive.
     pub fn is_connected(&mut self) -> bool {
         self.transport.as_mut().is_some_and(|t| t.is_alive())
@@ -206,6 +345,8 @@ impl McpClient {
         }
         self.transport = None;
         self.tools.clear();
+        self.resources.clear();
+        self.prompts.clear();
     }
 
     fn next_id(&mut self) -> u64 {
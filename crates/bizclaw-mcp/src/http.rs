@@ -0,0 +1,604 @@
+//! HTTP transport for remote MCP servers.
+//!
+//! Speaks Streamable HTTP: every JSON-RPC message is POSTed to the server
+//! URL and the reply comes back as JSON or as a `text/event-stream`. A
+//! server that rejects the first POST with 400/404/405 only speaks the
+//! older HTTP+SSE transport — a long-lived GET event stream whose
+//! `endpoint` event names the URL to POST to, with replies arriving on the
+//! stream. Requests carry a bearer token, either fixed or fetched with the
+//! OAuth client-credentials grant and fetched again on expiry or a 401.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bizclaw_core::config::McpOAuthConfig;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Response, StatusCode, Url};
+use tokio::sync::oneshot;
+
+use crate::types::{JsonRpcRequest, JsonRpcResponse, McpServerConfig};
+
+/// How long a request may take, including opening an HTTP+SSE stream.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Header carrying the session a Streamable HTTP server assigned.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Requests awaiting their response on an HTTP+SSE stream, by id.
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
+
+/// HTTP transport — JSON-RPC with a remote MCP server.
+pub struct HttpTransport {
+    http: reqwest::Client,
+    url: Url,
+    /// Configured extra headers, sent with every request.
+    headers: HeaderMap,
+    auth: Credentials,
+    /// Session the server assigned in its `Mcp-Session-Id` header.
+    session_id: Option<String>,
+    /// Event stream of a server speaking only HTTP+SSE.
+    sse: Option<SseStream>,
+    /// Whether the server has answered yet — until then a 4xx may mean it
+    /// only speaks HTTP+SSE.
+    answered: bool,
+    /// Cleared when the server forgets our session.
+    alive: bool,
+}
+
+impl HttpTransport {
+    /// Transport for the server at `config.url`. Nothing is sent until the
+    /// first request.
+    pub fn new(config: &McpServerConfig) -> Result<Self, String> {
+        let url = Url::parse(&config.url)
+            .map_err(|e| format!("Invalid MCP server URL '{}': {e}", config.url))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header name '{name}': {e}"))?;
+            let value =
+                HeaderValue::from_str(value).map_err(|e| format!("Invalid value for header '{name}': {e}"))?;
+            headers.insert(name, value);
+        }
+        let auth = match &config.oauth {
+            Some(oauth) => Credentials::OAuth {
+                config: oauth.clone(),
+                token: None,
+            },
+            None if !config.bearer_token.is_empty() => Credentials::Bearer(config.bearer_token.clone()),
+            None => Credentials::None,
+        };
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("HTTP client error: {e}"))?;
+        Ok(Self {
+            http,
+            url,
+            headers,
+            auth,
+            session_id: None,
+            sse: None,
+            answered: false,
+            alive: true,
+        })
+    }
+
+    /// Send a JSON-RPC request and wait for its response.
+    pub(crate) async fn request(&mut self, req: &JsonRpcRequest) -> Result<JsonRpcResponse, String> {
+        tokio::time::timeout(REQUEST_TIMEOUT, self.exchange(req))
+            .await
+            .unwrap_or_else(|_| Err(format!("MCP server response timeout ({}s)", REQUEST_TIMEOUT.as_secs())))
+    }
+
+    async fn exchange(&mut self, req: &JsonRpcRequest) -> Result<JsonRpcResponse, String> {
+        if self.sse.is_some() {
+            return self.sse_request(req).await;
+        }
+        let resp = self.post(self.url.clone(), req).await?;
+        let status = resp.status();
+        if !self.answered && matches!(status.as_u16(), 400 | 404 | 405) {
+            tracing::debug!("MCP server at {} answered HTTP {status}, trying HTTP+SSE", self.url);
+            self.sse = Some(self.open_sse().await?);
+            return self.sse_request(req).await;
+        }
+        if status == StatusCode::NOT_FOUND && self.session_id.is_some() {
+            self.alive = false;
+            return Err("MCP server ended the session".into());
+        }
+        if !status.is_success() {
+            return Err(http_error(resp).await);
+        }
+        self.answered = true;
+        if let Some(session) = resp.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            self.session_id = Some(session.to_string());
+        }
+
+        let is_stream = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if is_stream {
+            return read_stream_response(resp, req.id).await;
+        }
+        let body = resp.text().await.map_err(|e| format!("Read error: {e}"))?;
+        if body.trim().is_empty() {
+            return Err(format!("MCP server sent no response to '{}'", req.method));
+        }
+        serde_json::from_str(&body).map_err(|e| format!("Parse response error: {e} — raw: {}", body.trim()))
+    }
+
+    /// POST `req` to `url`, fetching a new OAuth token once if the server
+    /// rejects the current one.
+    async fn post(&mut self, url: Url, req: &JsonRpcRequest) -> Result<Response, String> {
+        let mut retried = false;
+        loop {
+            let mut builder = self
+                .http
+                .post(url.clone())
+                .headers(self.headers.clone())
+                .header(ACCEPT, "application/json, text/event-stream")
+                .json(req);
+            if let Some(token) = self.auth.token(&self.http).await? {
+                builder = builder.bearer_auth(token);
+            }
+            if let Some(session) = &self.session_id {
+                builder = builder.header(SESSION_HEADER, session);
+            }
+            let resp = builder.send().await.map_err(|e| format!("HTTP error: {e}"))?;
+            if resp.status() == StatusCode::UNAUTHORIZED && !retried && self.auth.invalidate() {
+                retried = true;
+                continue;
+            }
+            return Ok(resp);
+        }
+    }
+
+    /// Open the HTTP+SSE event stream and wait for its `endpoint` event.
+    async fn open_sse(&mut self) -> Result<SseStream, String> {
+        let mut builder = self
+            .http
+            .get(self.url.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "text/event-stream");
+        if let Some(token) = self.auth.token(&self.http).await? {
+            builder = builder.bearer_auth(token);
+        }
+        let mut resp = builder.send().await.map_err(|e| format!("HTTP error: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(http_error(resp).await);
+        }
+        let mut parser = SseParser::default();
+        let endpoint = loop {
+            let chunk = resp
+                .chunk()
+                .await
+                .map_err(|e| format!("Read error: {e}"))?
+                .ok_or("MCP event stream closed before naming its endpoint")?;
+            if let Some(event) = parser.feed(&chunk).into_iter().find(|e| e.event == "endpoint") {
+                break self
+                    .url
+                    .join(event.data.trim())
+                    .map_err(|e| format!("Invalid MCP endpoint '{}': {e}", event.data))?;
+            }
+        };
+        let pending = Pending::default();
+        let reader = tokio::spawn(dispatch_responses(resp, parser, pending.clone()));
+        Ok(SseStream {
+            endpoint,
+            pending,
+            reader,
+        })
+    }
+
+    /// POST `req` to the HTTP+SSE endpoint and wait for its response on
+    /// the event stream.
+    async fn sse_request(&mut self, req: &JsonRpcRequest) -> Result<JsonRpcResponse, String> {
+        let sse = self.sse.as_ref().ok_or("Not connected")?;
+        if sse.reader.is_finished() {
+            self.alive = false;
+            return Err("MCP event stream closed".into());
+        }
+        let (endpoint, pending) = (sse.endpoint.clone(), sse.pending.clone());
+        let (tx, rx) = oneshot::channel();
+        pending.lock().unwrap_or_else(|e| e.into_inner()).insert(req.id, tx);
+
+        let sent = match self.post(endpoint, req).await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(http_error(resp).await),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&req.id);
+            return Err(e);
+        }
+        rx.await.map_err(|_| "MCP event stream closed".to_string())
+    }
+
+    /// Whether the server can still be reached — false once it ended our
+    /// session or closed its event stream.
+    pub fn is_alive(&self) -> bool {
+        self.alive && self.sse.as_ref().is_none_or(|sse| !sse.reader.is_finished())
+    }
+
+    /// End the session: close the event stream, and tell a Streamable HTTP
+    /// server the session is over.
+    pub async fn shutdown(&mut self) {
+        self.sse = None;
+        if let Some(session) = self.session_id.take() {
+            let delete = self
+                .http
+                .delete(self.url.clone())
+                .headers(self.headers.clone())
+                .header(SESSION_HEADER, session)
+                .timeout(Duration::from_secs(5));
+            let delete = match self.auth.token(&self.http).await {
+                Ok(Some(token)) => delete.bearer_auth(token),
+                _ => delete,
+            };
+            let _ = delete.send().await;
+        }
+        self.answered = false;
+        self.alive = true;
+    }
+}
+
+/// Event stream of an HTTP+SSE server.
+struct SseStream {
+    /// Where to POST messages, from the stream's `endpoint` event.
+    endpoint: Url,
+    pending: Pending,
+    reader: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for SseStream {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Hand each response arriving on an HTTP+SSE stream to the request
+/// awaiting it. Server requests and notifications are ignored.
+async fn dispatch_responses(mut resp: Response, mut parser: SseParser, pending: Pending) {
+    while let Ok(Some(chunk)) = resp.chunk().await {
+        for event in parser.feed(&chunk) {
+            if event.event != "message" {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(&event.data) else {
+                continue;
+            };
+            let Some(id) = message["id"].as_u64().filter(|_| message.get("method").is_none()) else {
+                continue;
+            };
+            let waiter = pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+            if let (Some(waiter), Ok(response)) = (waiter, serde_json::from_value(message)) {
+                let _ = waiter.send(response);
+            }
+        }
+    }
+    tracing::warn!("⚠️ MCP event stream closed");
+    // Fail the requests still waiting
+    pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Read a Streamable HTTP reply stream until the response to request `id`.
+async fn read_stream_response(mut resp: Response, id: u64) -> Result<JsonRpcResponse, String> {
+    let mut parser = SseParser::default();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| format!("Read error: {e}"))? {
+        for event in parser.feed(&chunk) {
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(&event.data) else {
+                continue;
+            };
+            if message.get("method").is_none() && message["id"].as_u64() == Some(id) {
+                return serde_json::from_value(message).map_err(|e| format!("Parse response error: {e}"));
+            }
+        }
+    }
+    Err("MCP server closed the stream without a response".into())
+}
+
+/// Error for a failed HTTP response, with the start of its body.
+async fn http_error(resp: Response) -> String {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    let body: String = body.trim().chars().take(200).collect();
+    if body.is_empty() {
+        format!("HTTP {status}")
+    } else {
+        format!("HTTP {status}: {body}")
+    }
+}
+
+/// How requests to a remote server are authorized.
+enum Credentials {
+    None,
+    Bearer(String),
+    OAuth {
+        config: McpOAuthConfig,
+        /// Current token and when to fetch a new one.
+        token: Option<(String, Instant)>,
+    },
+}
+
+impl Credentials {
+    /// Bearer token to send, fetching an OAuth token when there is none
+    /// or it's about to expire.
+    async fn token(&mut self, http: &reqwest::Client) -> Result<Option<String>, String> {
+        match self {
+            Credentials::None => Ok(None),
+            Credentials::Bearer(token) => Ok(Some(token.clone())),
+            Credentials::OAuth { config, token } => {
+                if let Some((current, renew_at)) = token
+                    && Instant::now() < *renew_at
+                {
+                    return Ok(Some(current.clone()));
+                }
+                let (fresh, renew_at) = fetch_token(http, config).await?;
+                *token = Some((fresh.clone(), renew_at));
+                Ok(Some(fresh))
+            }
+        }
+    }
+
+    /// Drop a rejected OAuth token. Returns whether a new one can be fetched.
+    fn invalidate(&mut self) -> bool {
+        match self {
+            Credentials::OAuth { token, .. } => {
+                *token = None;
+                true
+            }
+            Credentials::None | Credentials::Bearer(_) => false,
+        }
+    }
+}
+
+/// Fetch a token with the client-credentials grant, to be renewed a minute
+/// before it expires (an hour when the server doesn't say).
+async fn fetch_token(http: &reqwest::Client, config: &McpOAuthConfig) -> Result<(String, Instant), String> {
+    let mut form = vec![
+        ("grant_type", "client_credentials"),
+        ("client_id", config.client_id.as_str()),
+        ("client_secret", config.client_secret.as_str()),
+    ];
+    if !config.scope.is_empty() {
+        form.push(("scope", config.scope.as_str()));
+    }
+    let resp = http
+        .post(&config.token_url)
+        .form(&form)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("OAuth token request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("OAuth token request failed: {}", http_error(resp).await));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("OAuth token response error: {e}"))?;
+    let token = body["access_token"]
+        .as_str()
+        .ok_or("OAuth token response has no access_token")?;
+    let lifetime = body["expires_in"].as_u64().unwrap_or(3600);
+    Ok((
+        token.to_string(),
+        Instant::now() + Duration::from_secs(lifetime.saturating_sub(60)),
+    ))
+}
+
+/// One server-sent event.
+struct SseEvent {
+    /// Event type, `message` unless the server named one.
+    event: String,
+    data: String,
+}
+
+/// Incremental `text/event-stream` parser.
+#[derive(Default)]
+struct SseParser {
+    /// Bytes of the current, unfinished line.
+    line: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the stream, returning the events it completes.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.line).trim_end_matches('\r').to_string();
+            self.line.clear();
+            if line.is_empty() {
+                let event = std::mem::take(&mut self.event);
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: if event.is_empty() { "message".into() } else { event },
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {} // comments, id, retry
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[test]
+    fn test_sse_parser_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b": keep-alive\n\nevent: endpoint\nda").is_empty());
+        let events = parser.feed(b"ta: /messages?s=1\r\n\r\ndata: {\"a\":\ndata: 1}\n\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].event.as_str(), events[0].data.as_str()), ("endpoint", "/messages?s=1"));
+        assert_eq!((events[1].event.as_str(), events[1].data.as_str()), ("message", "{\"a\":\n1}"));
+    }
+
+    /// Read one HTTP request: request line, headers and body.
+    async fn read_request(socket: &mut TcpStream) -> (String, String, String) {
+        let mut buf = Vec::new();
+        let mut chunk = [0; 4096];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap_or(0);
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                if body.len() >= length || n == 0 {
+                    let line = head.lines().next().unwrap_or_default().to_string();
+                    return (line, head.to_lowercase(), body.to_string());
+                }
+            }
+            if n == 0 {
+                return Default::default();
+            }
+        }
+    }
+
+    fn respond(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn remote(url: String) -> McpServerConfig {
+        McpServerConfig {
+            name: "remote".into(),
+            command: String::new(),
+            args: vec![],
+            env: Default::default(),
+            url,
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }
+    }
+
+    fn request(id: u64, method: &str) -> JsonRpcRequest {
+        JsonRpcRequest::new(id, method, None)
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_session_and_oauth() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let tokens_issued = Arc::new(Mutex::new(0));
+        let issued = tokens_issued.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (line, head, body) = read_request(&mut socket).await;
+                let response = if line.starts_with("POST /token") {
+                    assert!(body.contains("grant_type=client_credentials"), "{body}");
+                    let mut issued = issued.lock().unwrap();
+                    *issued += 1;
+                    respond("200 OK", "", &format!(r#"{{"access_token":"tok-{issued}","expires_in":3600}}"#))
+                } else if !head.contains("authorization: bearer tok-2") {
+                    // The first token is revoked
+                    respond("401 Unauthorized", "", "")
+                } else if body.contains("\"initialize\"") {
+                    respond(
+                        "200 OK",
+                        "Content-Type: application/json\r\nMcp-Session-Id: s-42\r\n",
+                        r#"{"jsonrpc":"2.0","id":1,"result":{}}"#,
+                    )
+                } else if !head.contains("mcp-session-id: s-42") {
+                    respond("400 Bad Request", "", "missing session")
+                } else {
+                    let stream = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                                  data: {\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"tools\":[]}}\n\n";
+                    respond("200 OK", "Content-Type: text/event-stream\r\n", stream)
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut config = remote(format!("{base}/mcp"));
+        config.oauth = Some(McpOAuthConfig {
+            token_url: format!("{base}/token"),
+            client_id: "bizclaw".into(),
+            client_secret: "s3cret".into(),
+            scope: String::new(),
+        });
+        let mut transport = HttpTransport::new(&config).unwrap();
+        let init = transport.request(&request(1, "initialize")).await.unwrap();
+        assert!(init.result.is_some());
+        assert_eq!(transport.session_id.as_deref(), Some("s-42"));
+        let tools = transport.request(&request(2, "tools/list")).await.unwrap();
+        assert_eq!(tools.result.unwrap()["tools"], serde_json::json!([]));
+        assert_eq!(*tokens_issued.lock().unwrap(), 2);
+        assert!(transport.is_alive());
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_http_sse() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let events_rx = Arc::new(tokio::sync::Mutex::new(events_rx));
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (line, head, body) = read_request(&mut socket).await;
+                assert!(head.contains("authorization: bearer fixed"), "{head}");
+                if line.starts_with("GET /sse") {
+                    // The event stream: endpoint first, then replies to POSTs
+                    let events_rx = events_rx.clone();
+                    tokio::spawn(async move {
+                        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n";
+                        let _ = socket.write_all(head.as_bytes()).await;
+                        let _ = socket.write_all(b"event: endpoint\ndata: /messages?session=7\n\n").await;
+                        while let Some(event) = events_rx.lock().await.recv().await {
+                            let _ = socket.write_all(event.as_bytes()).await;
+                        }
+                    });
+                    continue;
+                }
+                let response = if line.starts_with("POST /messages?session=7") {
+                    let id = serde_json::from_str::<serde_json::Value>(&body).unwrap()["id"].clone();
+                    let _ = events_tx.send(format!(
+                        "event: message\ndata: {{\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{{\"echo\":{id}}}}}\n\n"
+                    ));
+                    respond("202 Accepted", "", "")
+                } else {
+                    respond("405 Method Not Allowed", "", "")
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut config = remote(format!("{base}/sse"));
+        config.bearer_token = "fixed".into();
+        let mut transport = HttpTransport::new(&config).unwrap();
+        for id in 1..=2 {
+            let response = transport.request(&request(id, "ping")).await.unwrap();
+            assert_eq!(response.result.unwrap()["echo"], id);
+        }
+        assert!(transport.sse.is_some() && transport.is_alive());
+        transport.shutdown().await;
+        assert!(transport.sse.is_none());
+    }
+}
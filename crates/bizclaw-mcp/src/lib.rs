@@ -1,21 +1,29 @@
 //! # BizClaw MCP Client
 //!
 //! Model Context Protocol (MCP) client implementation.
-//! Connects to external MCP servers via stdio (JSON-RPC 2.0)
-//! and exposes their tools to the BizClaw Agent.
+//! Connects to external MCP servers via stdio or HTTP (JSON-RPC 2.0)
+//! and exposes their tools, resources and prompts to the BizClaw Agent.
 //!
 //! ## Architecture
 //! ```text
-//! Agent → McpClient → spawn(command, args)
-//!                     ↕ JSON-RPC 2.0 (stdio)
-//!                     MCP Server (any language)
+//! Agent → McpClient → spawn(command, args)      or   url
+//!                     ↕ JSON-RPC 2.0 (stdio)         ↕ Streamable HTTP / HTTP+SSE
+//!                     MCP Server (any language)      Remote MCP Server
 //! ```
+//!
+//! It also works the other way round: [`McpServer`] serves BizClaw's own
+//! tools and agents to MCP clients (see [`server`]).
 
 pub mod bridge;
 pub mod client;
+pub mod http;
+pub mod pool;
+pub mod server;
 pub mod transport;
 pub mod types;
 
 pub use bridge::McpToolBridge;
 pub use client::McpClient;
-pub use types::{McpServerConfig, McpToolInfo};
+pub use pool::McpPool;
+pub use server::McpServer;
+pub use types::{McpPromptInfo, McpResourceInfo, McpServerConfig, McpToolInfo};
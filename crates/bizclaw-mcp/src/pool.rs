@@ -0,0 +1,252 @@
+//! MCP connection pool — one connection per server, shared by every agent.
+//!
+//! Connecting MCP servers per agent would spawn one server process per
+//! agent. The pool connects each configured server once; the tool bridges
+//! it hands out all share that connection, and reconnect it when the
+//! server process has died (see [`McpToolBridge`]). A server that fails
+//! to connect at first is reported by [`McpPool::status`] and contributes
+//! no tools.
+//!
+//! Servers' text resources are read once at connect and kept, so agents
+//! can search them as knowledge context without a round trip per message;
+//! prompt templates are fetched when an agent selects them.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use bizclaw_core::traits::Tool;
+
+use crate::bridge::McpToolBridge;
+use crate::client::McpClient;
+use crate::types::{McpPromptInfo, McpResourceInfo, McpServerConfig, McpToolInfo};
+
+/// How long a single server may take to start and list its tools.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most resources read per server at connect.
+const MAX_CACHED_RESOURCES: usize = 50;
+
+/// Most characters of a resource's text kept for retrieval.
+const MAX_RESOURCE_CHARS: usize = 200_000;
+
+/// A configured server and its shared connection.
+struct PooledServer {
+    config: McpServerConfig,
+    client: Arc<Mutex<McpClient>>,
+    /// Tools listed when the pool connected.
+    tools: Vec<McpToolInfo>,
+    /// Resources listed when the pool connected.
+    resources: Vec<McpResourceInfo>,
+    /// Text of the resources read at connect, by resource.
+    resource_texts: Vec<(McpResourceInfo, String)>,
+    /// Prompt templates listed when the pool connected.
+    prompts: Vec<McpPromptInfo>,
+    /// Why connecting failed.
+    error: Option<String>,
+}
+
+/// Shared MCP connections for all agents of an orchestrator.
+#[derive(Default)]
+pub struct McpPool {
+    servers: Vec<PooledServer>,
+}
+
+impl McpPool {
+    /// Connect every enabled server in `configs`, each at most once.
+    pub async fn connect(configs: &[McpServerConfig]) -> Self {
+        let mut servers = Vec::new();
+        for config in configs {
+            if !config.enabled {
+                tracing::debug!("⏭️ MCP server '{}' disabled, skipping", config.name);
+                continue;
+            }
+            if servers.iter().any(|s: &PooledServer| s.config.name == config.name) {
+                tracing::warn!("⚠️ MCP server '{}' configured twice, using the first", config.name);
+                continue;
+            }
+            let mut client = McpClient::new(config.clone());
+            let error = connect_client(&mut client).await.err();
+            let resource_texts = read_resources(&mut client).await;
+            servers.push(PooledServer {
+                config: config.clone(),
+                tools: client.tools().to_vec(),
+                resources: client.resources().to_vec(),
+                resource_texts,
+                prompts: client.prompts().to_vec(),
+                client: Arc::new(Mutex::new(client)),
+                error,
+            });
+        }
+        let connected = servers.iter().filter(|s| s.error.is_none()).count();
+        if !servers.is_empty() {
+            tracing::info!("🔗 MCP pool: {connected}/{} server(s) connected", servers.len());
+        }
+        Self { servers }
+    }
+
+    /// Tool bridges for every connected server, sharing the pool's
+    /// connections. Call once per agent.
+    pub fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.servers
+            .iter()
+            .flat_map(|server| McpToolBridge::from_client(server.client.clone(), &server.tools))
+            .collect()
+    }
+
+    /// Resources of every connected server.
+    pub fn resources(&self) -> Vec<McpResourceInfo> {
+        self.servers.iter().flat_map(|s| s.resources.clone()).collect()
+    }
+
+    /// Text of the resources read at connect, for retrieval.
+    pub fn resource_texts(&self) -> impl Iterator<Item = (&McpResourceInfo, &str)> {
+        self.servers
+            .iter()
+            .flat_map(|s| s.resource_texts.iter().map(|(info, text)| (info, text.as_str())))
+    }
+
+    /// Prompt templates of every connected server.
+    pub fn prompts(&self) -> Vec<McpPromptInfo> {
+        self.servers.iter().flat_map(|s| s.prompts.clone()).collect()
+    }
+
+    /// Read resource `uri` of server `server` now.
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<String, String> {
+        self.client(server)?.lock().await.read_resource(uri).await
+    }
+
+    /// Fetch prompt template `name` of server `server`, filled in with
+    /// `arguments`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<String, String> {
+        self.client(server)?.lock().await.get_prompt(name, arguments).await
+    }
+
+    fn client(&self, server: &str) -> Result<&Arc<Mutex<McpClient>>, String> {
+        self.servers
+            .iter()
+            .find(|s| s.config.name == server)
+            .map(|s| &s.client)
+            .ok_or_else(|| format!("Unknown MCP server '{server}'"))
+    }
+
+    /// Connection state of each server: name, connected, tool, resource
+    /// and prompt counts, and why connecting failed.
+    pub fn status(&self) -> Vec<serde_json::Value> {
+        self.servers
+            .iter()
+            .map(|server| {
+                // A server busy with a tool call is up
+                let connected = server.client.try_lock().map_or(true, |mut c| c.is_connected());
+                serde_json::json!({
+                    "name": server.config.name,
+                    "connected": connected,
+                    "tools_count": server.tools.len(),
+                    "resources_count": server.resources.len(),
+                    "prompts_count": server.prompts.len(),
+                    "error": server.error,
+                })
+            })
+            .collect()
+    }
+
+    /// Number of servers in the pool, connected or not.
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Whether the pool has no servers.
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+}
+
+/// Text of up to [`MAX_CACHED_RESOURCES`] of `client`'s resources, each
+/// cut to [`MAX_RESOURCE_CHARS`]. Resources that can't be read or have no
+/// text are left out.
+async fn read_resources(client: &mut McpClient) -> Vec<(McpResourceInfo, String)> {
+    let resources: Vec<McpResourceInfo> = client
+        .resources()
+        .iter()
+        .take(MAX_CACHED_RESOURCES)
+        .cloned()
+        .collect();
+    let mut texts = Vec::new();
+    for resource in resources {
+        let read = tokio::time::timeout(CONNECT_TIMEOUT, client.read_resource(&resource.uri)).await;
+        match read {
+            Ok(Ok(text)) if !text.trim().is_empty() => {
+                let text = text.chars().take(MAX_RESOURCE_CHARS).collect();
+                texts.push((resource, text));
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!("⚠️ MCP server '{}': {e}", client.name),
+            Err(_) => tracing::warn!("⚠️ MCP server '{}': reading '{}' timed out", client.name, resource.uri),
+        }
+    }
+    texts
+}
+
+/// Connect `client`, giving up after [`CONNECT_TIMEOUT`].
+async fn connect_client(client: &mut McpClient) -> Result<(), String> {
+    let name = client.name.clone();
+    let result = match tokio::time::timeout(CONNECT_TIMEOUT, client.connect()).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", CONNECT_TIMEOUT.as_secs())),
+    };
+    if let Err(e) = &result {
+        tracing::warn!("⚠️ MCP server '{name}' failed to connect: {e}");
+        client.disconnect().await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_resources_cached_and_prompts_fetched() {
+        // A stdio MCP server answering in order: initialize, initialized,
+        // tools/list, resources/list, prompts/list, resources/read, then
+        // every prompts/get.
+        let script = r#"read l; echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"tools":{},"resources":{},"prompts":{}}}}'
+read l; echo '{"jsonrpc":"2.0","id":2,"result":{}}'
+read l; echo '{"jsonrpc":"2.0","id":3,"result":{"tools":[]}}'
+read l; echo '{"jsonrpc":"2.0","id":4,"result":{"resources":[{"uri":"file:///faq.md","name":"FAQ","mimeType":"text/markdown"}]}}'
+read l; echo '{"jsonrpc":"2.0","id":5,"result":{"prompts":[{"name":"polite","description":"Polite tone","arguments":[{"name":"lang"}]}]}}'
+read l; echo '{"jsonrpc":"2.0","id":6,"result":{"contents":[{"uri":"file:///faq.md","text":"Returns are free within 30 days."}]}}'
+while read l; do echo '{"jsonrpc":"2.0","id":0,"result":{"messages":[{"role":"user","content":{"type":"text","text":"Always answer politely."}}]}}'; done"#;
+        let pool = McpPool::connect(&[McpServerConfig {
+            name: "docs".into(),
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            env: Default::default(),
+            url: String::new(),
+            headers: Default::default(),
+            bearer_token: String::new(),
+            oauth: None,
+            enabled: true,
+        }])
+        .await;
+
+        let status = pool.status();
+        assert_eq!(status[0]["resources_count"], 1);
+        assert_eq!(status[0]["prompts_count"], 1);
+        let (resource, text) = pool.resource_texts().next().unwrap();
+        assert_eq!(resource.name, "FAQ");
+        assert_eq!(resource.server_name, "docs");
+        assert_eq!(text, "Returns are free within 30 days.");
+        assert_eq!(pool.prompts()[0].arguments[0].name, "lang");
+
+        let prompt = pool.get_prompt("docs", "polite", serde_json::json!({})).await.unwrap();
+        assert_eq!(prompt, "Always answer politely.");
+        assert!(pool.get_prompt("other", "polite", serde_json::json!({})).await.is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! MCP server — BizClaw's own tools, served to MCP clients.
+//!
+//! [`McpServer`] answers JSON-RPC messages from clients such as Claude
+//! Desktop with a set of [`Tool`]s; the gateway hands it the built-in
+//! tools, knowledge search and agents wrapped as tools, and serves it over
+//! Streamable HTTP at `/mcp`. Clients that only launch stdio servers run
+//! `bizclaw mcp`, which relays stdio to that endpoint ([`relay_stdio`]).
+
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use bizclaw_core::traits::{SecurityPolicy, Tool};
+
+/// Protocol versions understood, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A tool call refused by the security policy's argument filters.
+const REFUSED: i64 = -32000;
+
+/// MCP server over a set of tools.
+pub struct McpServer {
+    tools: Vec<Box<dyn Tool>>,
+    security: Option<Arc<dyn SecurityPolicy>>,
+}
+
+impl McpServer {
+    pub fn new(tools: Vec<Box<dyn Tool>>) -> Self {
+        Self { tools, security: None }
+    }
+
+    /// Check tool call arguments with `security`'s argument filters, as
+    /// agents do.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Answer a JSON-RPC message or batch. Notifications get no answer.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        match message {
+            Value::Array(batch) => {
+                let mut answers = Vec::new();
+                for message in batch {
+                    answers.extend(self.handle_one(message).await);
+                }
+                (!answers.is_empty()).then_some(Value::Array(answers))
+            }
+            message => self.handle_one(message).await,
+        }
+    }
+
+    /// Answer raw JSON-RPC text, reporting text that isn't JSON as a parse
+    /// error.
+    pub async fn handle_text(&self, text: &str) -> Option<Value> {
+        match serde_json::from_str(text) {
+            Ok(message) => self.handle(message).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &format!("Parse error: {e}"))),
+        }
+    }
+
+    async fn handle_one(&self, message: Value) -> Option<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // A response to a request we never sent, or garbage
+            return message
+                .get("id")
+                .map(|id| error_response(id.clone(), INVALID_REQUEST, "Invalid request"));
+        };
+        // Requests carry an id; notifications (initialized, cancelled) don't
+        let id = message.get("id")?.clone();
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.list_tools() })),
+            "tools/call" => self.call_tool(params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = PROTOCOL_VERSIONS
+            .iter()
+            .find(|v| **v == requested)
+            .unwrap_or(&PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": "bizclaw", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn list_tools(&self) -> Vec<Value> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                let definition = tool.definition();
+                json!({
+                    "name": definition.name,
+                    "description": definition.description,
+                    "inputSchema": definition.parameters,
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"]
+            .as_str()
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.name() == name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool: {name}")))?;
+        let arguments = match &params["arguments"] {
+            Value::Null => json!({}),
+            arguments => arguments.clone(),
+        };
+        // The same checks an agent's tool call goes through before it runs
+        let problems = bizclaw_core::schema::validate(&arguments, &tool.definition().parameters);
+        if !problems.is_empty() {
+            return Err((INVALID_PARAMS, format!("Invalid arguments for {name}: {}", problems.join("; "))));
+        }
+        if let Some(security) = &self.security
+            && let Err(e) = security.check_tool_arguments(name, &arguments).await
+        {
+            tracing::warn!("🛡️ MCP call to '{name}' refused by argument filter: {e}");
+            return Err((REFUSED, format!("Denied: {e}")));
+        }
+        tracing::info!("🔧 MCP client called '{name}'");
+        let (text, is_error) = match tool.execute(&arguments.to_string()).await {
+            Ok(result) => (result.output, !result.success),
+            Err(e) => (e.to_string(), true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        }))
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Relay an MCP client on stdin/stdout to the MCP endpoint of a running
+/// BizClaw gateway, authenticating with `api_key`. Returns when stdin
+/// closes.
+pub async fn relay_stdio(url: &str, api_key: Option<&str>) -> Result<(), String> {
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    relay(stdin, tokio::io::stdout(), url, api_key).await
+}
+
+async fn relay<R, W>(reader: R, mut writer: W, url: &str, api_key: Option<&str>) -> Result<(), String>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let http = reqwest::Client::new();
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await.map_err(|e| format!("Read error: {e}"))? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let id = serde_json::from_str::<Value>(&line)
+            .ok()
+            .and_then(|m| m.get("id").cloned());
+        let mut request = http
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream")
+            .body(line);
+        if let Some(key) = api_key {
+            request = request.bearer_auth(key);
+        }
+        let answer = match request.send().await {
+            Ok(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+            Ok(resp) => {
+                let status = resp.status();
+                id.map(|id| error_response(id, INVALID_REQUEST, &format!("Gateway answered HTTP {status}")).to_string())
+                    .unwrap_or_default()
+            }
+            Err(e) => id
+                .map(|id| error_response(id, INVALID_REQUEST, &format!("Gateway unreachable: {e}")).to_string())
+                .unwrap_or_default(),
+        };
+        let answer = answer.trim();
+        if answer.is_empty() {
+            continue;
+        }
+        writer
+            .write_all(format!("{answer}\n").as_bytes())
+            .await
+            .map_err(|e| format!("Write error: {e}"))?;
+        writer.flush().await.map_err(|e| format!("Flush error: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::types::{ToolDefinition, ToolResult};
+
+    struct Echo;
+
+    #[async_trait]
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "echo".into(),
+                description: "Echo the text back".into(),
+                parameters: json!({"type": "object", "properties": {"text": {"type": "string"}}}),
+            }
+        }
+
+        async fn execute(&self, arguments: &str) -> bizclaw_core::error::Result<ToolResult> {
+            let args: Value = serde_json::from_str(arguments).unwrap_or_default();
+            Ok(ToolResult {
+                tool_call_id: String::new(),
+                output: args["text"].as_str().unwrap_or_default().to_string(),
+                success: true,
+            })
+        }
+    }
+
+    fn server() -> McpServer {
+        McpServer::new(vec![Box::new(Echo)])
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_tool_call() {
+        let server = server();
+        let init = server
+            .handle(json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05"}}))
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert!(init["result"]["capabilities"]["tools"].is_object());
+        assert!(server.handle(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await.is_none());
+
+        let list = server.handle(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"})).await.unwrap();
+        assert_eq!(list["result"]["tools"][0]["name"], "echo");
+        assert_eq!(list["result"]["tools"][0]["inputSchema"]["type"], "object");
+
+        let call = server
+            .handle(json!({"jsonrpc": "2.0", "id": "c", "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "xin chào"}}}))
+            .await
+            .unwrap();
+        assert_eq!(call["id"], "c");
+        assert_eq!(call["result"]["content"][0]["text"], "xin chào");
+        assert_eq!(call["result"]["isError"], false);
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let server = server();
+        let unknown = server
+            .handle(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "shell"}}))
+            .await
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+        let invalid = server
+            .handle(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": 5}}}))
+            .await
+            .unwrap();
+        assert_eq!(invalid["error"]["code"], INVALID_PARAMS);
+        assert!(invalid["error"]["message"].as_str().unwrap().contains("text"), "{invalid}");
+        let missing = server.handle(json!({"jsonrpc": "2.0", "id": 2, "method": "resources/list"})).await.unwrap();
+        assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(server.handle_text("{oops").await.unwrap()["error"]["code"], PARSE_ERROR);
+
+        let batch = server
+            .handle(json!([
+                {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+                {"jsonrpc": "2.0", "method": "notifications/cancelled"},
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(batch.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_relay_reports_unreachable_gateway() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"ping\"}\n{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n";
+        let mut output = Vec::new();
+        relay(&input[..], &mut output, "http://127.0.0.1:9/mcp", None).await.unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let answers: Vec<Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0]["id"], 7);
+        assert!(answers[0]["error"]["message"].as_str().unwrap().contains("unreachable"));
+    }
+}
@@ -1,12 +1,46 @@
-//! Stdio transport for MCP — spawns a child process and communicates via JSON-RPC.
+//! Transports for MCP — a local server spawned as a child process speaking
+//! JSON-RPC over stdio, or a remote server over HTTP ([`HttpTransport`]).
 
 use std::collections::HashMap;
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
 
+use crate::http::HttpTransport;
 use crate::types::{JsonRpcRequest, JsonRpcResponse};
 
+/// Connection to an MCP server.
+pub enum Transport {
+    Stdio(StdioTransport),
+    Http(HttpTransport),
+}
+
+impl Transport {
+    /// Send a JSON-RPC request and wait for its response.
+    pub(crate) async fn request(&mut self, req: &JsonRpcRequest) -> Result<JsonRpcResponse, String> {
+        match self {
+            Transport::Stdio(t) => t.request(req).await,
+            Transport::Http(t) => t.request(req).await,
+        }
+    }
+
+    /// Check if the server can still be reached.
+    pub fn is_alive(&mut self) -> bool {
+        match self {
+            Transport::Stdio(t) => t.is_alive(),
+            Transport::Http(t) => t.is_alive(),
+        }
+    }
+
+    /// Stop the server process, or end the remote session.
+    pub async fn shutdown(&mut self) {
+        match self {
+            Transport::Stdio(t) => t.shutdown().await,
+            Transport::Http(t) => t.shutdown().await,
+        }
+    }
+}
+
 /// Stdio transport — manages a child process for JSON-RPC communication.
 pub struct StdioTransport {
     child: Child,
@@ -8,7 +8,8 @@ use std::collections::HashMap;
 pub struct McpServerConfig {
     /// Display name for this server.
     pub name: String,
-    /// Command to start the MCP server process.
+    /// Command to start the MCP server process (empty for a remote server).
+    #[serde(default)]
     pub command: String,
     /// Arguments to the command.
     #[serde(default)]
@@ -16,6 +17,18 @@ pub struct McpServerConfig {
     /// Environment variables to set.
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// URL of a remote server, used instead of `command`.
+    #[serde(default)]
+    pub url: String,
+    /// Extra HTTP headers sent to a remote server.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Bearer token sent to a remote server (empty = none).
+    #[serde(default)]
+    pub bearer_token: String,
+    /// OAuth client credentials to fetch bearer tokens with.
+    #[serde(default)]
+    pub oauth: Option<bizclaw_core::config::McpOAuthConfig>,
     /// Whether this server is enabled.
     #[serde(default = "default_true")]
     pub enabled: bool,
@@ -25,6 +38,22 @@ fn default_true() -> bool {
     true
 }
 
+impl From<&bizclaw_core::config::McpServerEntry> for McpServerConfig {
+    fn from(entry: &bizclaw_core::config::McpServerEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            command: entry.command.clone(),
+            args: entry.args.clone(),
+            env: entry.env.clone(),
+            url: entry.url.clone(),
+            headers: entry.headers.clone(),
+            bearer_token: entry.bearer_token.clone(),
+            oauth: entry.oauth.clone(),
+            enabled: entry.enabled,
+        }
+    }
+}
+
 /// Tool information discovered from an MCP server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpToolInfo {
@@ -37,6 +66,43 @@ pub struct McpToolInfo {
     pub server_name: String,
 }
 
+/// Resource (a file, record or document) offered by an MCP server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceInfo {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// Which MCP server this resource belongs to.
+    #[serde(default)]
+    pub server_name: String,
+}
+
+/// Prompt template offered by an MCP server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPromptInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+    /// Which MCP server this prompt belongs to.
+    #[serde(default)]
+    pub server_name: String,
+}
+
+/// Argument a prompt template takes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
 // ── JSON-RPC 2.0 types ────────────────────────────────
 
 /// JSON-RPC 2.0 request.
@@ -110,3 +176,62 @@ pub(crate) struct ToolCallContent {
     #[serde(default)]
     pub text: Option<String>,
 }
+
+/// MCP resources/list response.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ResourcesListResult {
+    pub resources: Vec<McpResourceDef>,
+}
+
+/// MCP resource definition from the server.
+#[derive(Debug, Deserialize)]
+pub(crate) struct McpResourceDef {
+    pub uri: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", default)]
+    pub mime_type: Option<String>,
+}
+
+/// MCP resources/read result.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ResourceReadResult {
+    pub contents: Vec<ResourceContent>,
+}
+
+/// MCP resource content item; binary (`blob`) contents carry no text.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ResourceContent {
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// MCP prompts/list response.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PromptsListResult {
+    pub prompts: Vec<McpPromptDef>,
+}
+
+/// MCP prompt definition from the server.
+#[derive(Debug, Deserialize)]
+pub(crate) struct McpPromptDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+/// MCP prompts/get result.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PromptGetResult {
+    pub messages: Vec<PromptMessage>,
+}
+
+/// MCP prompt message.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PromptMessage {
+    pub content: ToolCallContent,
+}
@@ -286,6 +286,11 @@ impl DailyLogManager {
     /// Save a compaction summary to today's daily log.
     /// Multiple compactions stack in the same file.
     pub fn save_compaction(&self, summary: &str) -> Result<()> {
+        self.save_entry("Compaction", summary)
+    }
+
+    /// Append a `## {title} at HH:MM:SS UTC` section to today's daily log.
+    pub fn save_entry(&self, title: &str, body: &str) -> Result<()> {
         std::fs::create_dir_all(&self.memory_dir).map_err(|e| {
             bizclaw_core::error::BizClawError::Memory(format!("Create memory dir: {e}"))
         })?;
@@ -294,7 +299,7 @@ impl DailyLogManager {
         let file_path = self.memory_dir.join(format!("{today}.md"));
 
         let timestamp = chrono::Utc::now().format("%H:%M:%S UTC").to_string();
-        let entry = format!("\n---\n## Compaction at {timestamp}\n\n{summary}\n",);
+        let entry = format!("\n---\n## {title} at {timestamp}\n\n{body}\n",);
 
         // Append to existing file or create new
         use std::io::Write;
@@ -314,7 +319,7 @@ impl DailyLogManager {
         write!(file, "{entry}")
             .map_err(|e| bizclaw_core::error::BizClawError::Memory(format!("Write entry: {e}")))?;
 
-        tracing::info!("📝 Compaction summary saved to memory/{today}.md");
+        tracing::info!("📝 {title} saved to memory/{today}.md");
         Ok(())
     }
 
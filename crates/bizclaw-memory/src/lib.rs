@@ -3,17 +3,29 @@
 
 pub mod brain;
 pub mod noop;
+pub mod retention;
 pub mod sqlite;
 pub mod vector;
 
 use bizclaw_core::config::MemoryConfig;
 use bizclaw_core::error::Result;
-use bizclaw_core::traits::MemoryBackend;
+use bizclaw_core::traits::{MemoryBackend, Provider};
 
-/// Create a memory backend from configuration.
-pub fn create_memory(config: &MemoryConfig) -> Result<Box<dyn MemoryBackend>> {
+/// Create a memory backend from configuration. With an `embedder` the
+/// SQLite backend adds vector search, weighted by `vector_weight` and
+/// `keyword_weight`.
+pub fn create_memory(
+    config: &MemoryConfig,
+    embedder: Option<Box<dyn Provider>>,
+) -> Result<Box<dyn MemoryBackend>> {
     match config.backend.as_str() {
-        "sqlite" => Ok(Box::new(sqlite::SqliteMemory::new()?)),
+        "sqlite" => {
+            let memory = sqlite::SqliteMemory::new()?;
+            Ok(Box::new(match embedder {
+                Some(embedder) => memory.with_embedder(embedder, config.vector_weight, config.keyword_weight)?,
+                None => memory,
+            }))
+        }
         "none" => Ok(Box::new(noop::NoopMemory)),
         other => Err(bizclaw_core::error::BizClawError::Memory(format!(
             "Unknown memory backend: {other}"
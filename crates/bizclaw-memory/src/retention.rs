@@ -0,0 +1,253 @@
+//! Memory retention — importance scoring, TTL/decay and pruning.
+//!
+//! Each entry carries an `importance` (0..1) in its metadata, scored at save
+//! time by [`importance`] unless the caller (or an LLM) already tagged it.
+//! An entry's value decays with age: `importance * 0.5^(age / half_life)`.
+//! [`prune`] deletes expired entries and, once the store is over
+//! `max_entries`, collapses the lowest-value ones into summary entries.
+
+use bizclaw_core::config::MemoryConfig;
+use bizclaw_core::error::Result;
+use bizclaw_core::traits::memory::{MemoryBackend, MemoryEntry};
+use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::types::Message;
+
+/// Entries merged into one summary.
+const MERGE_BATCH: usize = 25;
+
+/// Retention settings, from `[memory]`.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Entries kept before low-value ones are merged (0 = unlimited).
+    pub max_entries: usize,
+    /// Default time to live; entries may override it with `ttl_days` in
+    /// their metadata (0 = keep).
+    pub ttl_days: u32,
+    /// Age at which an entry's value halves.
+    pub half_life_days: f32,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &MemoryConfig) -> Self {
+        Self {
+            max_entries: config.max_entries,
+            ttl_days: config.ttl_days,
+            half_life_days: config.decay_half_life_days,
+        }
+    }
+
+    /// Whether `entry` has outlived its TTL at `now`.
+    pub fn is_expired(&self, entry: &MemoryEntry, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let ttl_days = entry.metadata["ttl_days"].as_u64().unwrap_or(self.ttl_days as u64);
+        ttl_days > 0 && now - entry.created_at > chrono::Duration::days(ttl_days as i64)
+    }
+
+    /// Importance of `entry` decayed by its age at `now`.
+    pub fn value(&self, entry: &MemoryEntry, now: chrono::DateTime<chrono::Utc>) -> f32 {
+        let importance = entry.metadata["importance"]
+            .as_f64()
+            .map(|v| v as f32)
+            .unwrap_or_else(|| importance(&entry.content));
+        if self.half_life_days <= 0.0 {
+            return importance;
+        }
+        let age_days = (now - entry.created_at).num_seconds().max(0) as f32 / 86_400.0;
+        importance * 0.5f32.powf(age_days / self.half_life_days)
+    }
+}
+
+/// Heuristic importance of a memory (0..1): explicit "remember" requests,
+/// personal facts and preferences, and concrete details (numbers, dates,
+/// contacts) score higher; short small talk scores lower.
+pub fn importance(content: &str) -> f32 {
+    let lower = content.to_lowercase();
+    let mut score: f32 = 0.3;
+    const REMEMBER: &[&str] = &["remember", "don't forget", "note that", "ghi nhớ", "nhớ giúp"];
+    const PERSONAL: &[&str] = &[
+        "my name", "i am", "i'm", "i prefer", "i like", "i don't like", "always", "never",
+        "tên tôi", "tôi thích", "tôi là",
+    ];
+    if REMEMBER.iter().any(|k| lower.contains(k)) {
+        score += 0.4;
+    }
+    if PERSONAL.iter().any(|k| lower.contains(k)) {
+        score += 0.2;
+    }
+    if content.chars().any(|c| c.is_ascii_digit()) || content.contains('@') {
+        score += 0.1;
+    }
+    if content.len() < 40 {
+        score -= 0.15;
+    }
+    score.clamp(0.0, 1.0)
+}
+
+/// What a [`prune`] run changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Entries deleted for outliving their TTL.
+    pub expired: usize,
+    /// Low-value entries collapsed into summaries.
+    pub merged: usize,
+    /// Summary entries written.
+    pub summaries: usize,
+}
+
+/// Delete expired entries, then collapse the lowest-value entries into
+/// summaries until roughly `max_entries` remain. Summaries are written by
+/// `summarizer` (provider, model) when given, otherwise by joining the
+/// start of each entry.
+pub async fn prune(
+    memory: &dyn MemoryBackend,
+    policy: &RetentionPolicy,
+    summarizer: Option<(&dyn Provider, &str)>,
+) -> Result<PruneReport> {
+    let now = chrono::Utc::now();
+    let mut report = PruneReport::default();
+    let mut live = Vec::new();
+    for entry in memory.list(Some(i64::MAX as usize)).await? {
+        if policy.is_expired(&entry, now) {
+            memory.delete(&entry.id).await?;
+            report.expired += 1;
+        } else {
+            live.push(entry);
+        }
+    }
+    if policy.max_entries == 0 || live.len() <= policy.max_entries {
+        return Ok(report);
+    }
+
+    // Keep the most valuable 90%, leaving room for the summaries
+    live.sort_by(|a, b| {
+        policy
+            .value(b, now)
+            .partial_cmp(&policy.value(a, now))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let excess = live.split_off(policy.max_entries * 9 / 10);
+    for batch in excess.chunks(MERGE_BATCH) {
+        let summary = summarize(batch, summarizer).await;
+        let importance = batch
+            .iter()
+            .map(|e| policy.value(e, now))
+            .fold(0.0, f32::max);
+        memory
+            .save(MemoryEntry {
+                id: uuid::Uuid::new_v4().to_string(),
+                content: summary,
+                metadata: serde_json::json!({
+                    "kind": "summary",
+                    "merged": batch.len(),
+                    "importance": importance,
+                }),
+                embedding: None,
+                created_at: batch.iter().map(|e| e.created_at).max().unwrap_or(now),
+                updated_at: now,
+            })
+            .await?;
+        for entry in batch {
+            memory.delete(&entry.id).await?;
+        }
+        report.merged += batch.len();
+        report.summaries += 1;
+    }
+    tracing::info!(
+        "🧹 Memory pruned: {} expired, {} merged into {} summaries",
+        report.expired,
+        report.merged,
+        report.summaries
+    );
+    Ok(report)
+}
+
+/// Summary text for a batch of entries.
+async fn summarize(batch: &[MemoryEntry], summarizer: Option<(&dyn Provider, &str)>) -> String {
+    let notes: Vec<String> = batch
+        .iter()
+        .map(|e| format!("- {}", e.content.replace('\n', " ")))
+        .collect();
+    if let Some((provider, model)) = summarizer {
+        let prompt = format!(
+            "Condense these older memory notes into a short list of the facts worth keeping. \
+             Drop small talk.\n\n{}",
+            notes.join("\n")
+        );
+        let params = GenerateParams {
+            model: model.to_string(),
+            temperature: 0.2,
+            max_tokens: 512,
+            ..Default::default()
+        };
+        match provider.chat(&[Message::user(&prompt)], &[], &params).await {
+            Ok(response) if response.content.as_deref().is_some_and(|c| !c.trim().is_empty()) => {
+                return format!(
+                    "[Summary of {} older memories]\n{}",
+                    batch.len(),
+                    response.content.unwrap_or_default().trim()
+                );
+            }
+            Ok(_) => tracing::warn!("Memory summarizer returned nothing, joining entries instead"),
+            Err(e) => tracing::warn!("Memory summarizer failed, joining entries instead: {e}"),
+        }
+    }
+    let lines: Vec<String> = notes
+        .iter()
+        .map(|n| match n.char_indices().nth(160) {
+            Some((i, _)) => format!("{}...", &n[..i]),
+            None => n.clone(),
+        })
+        .collect();
+    format!("[Summary of {} older memories]\n{}", batch.len(), lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::tests::entry;
+
+    #[test]
+    fn test_importance_heuristic() {
+        assert!(importance("Please remember my name is Lan, phone 0901234567") > 0.8);
+        assert!(importance("User: hi\nAssistant: hello") < 0.3);
+    }
+
+    #[test]
+    fn test_ttl_and_decay() {
+        let policy = RetentionPolicy { max_entries: 0, ttl_days: 30, half_life_days: 10.0 };
+        let now = chrono::Utc::now();
+        let mut old = entry("old", "an ordinary note about the weather today");
+        old.created_at = now - chrono::Duration::days(10);
+        old.metadata = serde_json::json!({"importance": 0.8});
+        assert!((policy.value(&old, now) - 0.4).abs() < 1e-3);
+        assert!(!policy.is_expired(&old, now));
+        old.metadata["ttl_days"] = serde_json::json!(5);
+        assert!(policy.is_expired(&old, now));
+    }
+
+    #[tokio::test]
+    async fn test_prune_expires_and_merges_low_value_entries() {
+        let dir = std::env::temp_dir().join(format!("bizclaw_prune_{}", uuid::Uuid::new_v4()));
+        let memory = crate::sqlite::SqliteMemory::open(&dir.join("memory.db")).unwrap();
+        let now = chrono::Utc::now();
+        for i in 0..12 {
+            let mut e = entry(&format!("e{i}"), &format!("note {i}"));
+            e.metadata = serde_json::json!({"importance": i as f64 / 12.0});
+            memory.save(e).await.unwrap();
+        }
+        let mut stale = entry("stale", "expired note");
+        stale.created_at = now - chrono::Duration::days(40);
+        memory.save(stale).await.unwrap();
+
+        let policy = RetentionPolicy { max_entries: 10, ttl_days: 30, half_life_days: 30.0 };
+        let report = prune(&memory, &policy, None).await.unwrap();
+        assert_eq!(report, PruneReport { expired: 1, merged: 3, summaries: 1 });
+
+        let remaining = memory.list(None).await.unwrap();
+        assert_eq!(remaining.len(), 10);
+        assert!(memory.get("e0").await.unwrap().is_none());
+        assert!(memory.get("e11").await.unwrap().is_some());
+        let summary = remaining.iter().find(|e| e.metadata["kind"] == "summary").unwrap();
+        assert!(summary.content.contains("- note 0"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
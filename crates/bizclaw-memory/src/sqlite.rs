@@ -1,22 +1,39 @@
 //! SQLite memory backend with FTS5 full-text search and session support.
+//!
+//! With an embedder attached ([`SqliteMemory::with_embedder`]) entries are
+//! also embedded on save and search fuses keyword and vector scores.
 
 use async_trait::async_trait;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::memory::{MemoryBackend, MemoryEntry, MemorySearchResult};
-use rusqlite::Connection;
+use bizclaw_core::traits::provider::Provider;
+use bizclaw_core::types::Message;
+use rusqlite::{Connection, OptionalExtension};
 use std::sync::Mutex;
 
+use crate::vector::VectorStore;
+
 pub struct SqliteMemory {
     conn: Mutex<Connection>,
+    /// Embeds entries and queries; search is keyword-only without one.
+    embedder: Option<Box<dyn Provider>>,
+    /// Flat index over the stored embeddings.
+    vectors: Mutex<VectorStore>,
+    vector_weight: f32,
+    keyword_weight: f32,
 }
 
 impl SqliteMemory {
     pub fn new() -> Result<Self> {
-        let db_path = bizclaw_core::config::BizClawConfig::home_dir().join("memory.db");
+        Self::open(&bizclaw_core::config::BizClawConfig::home_dir().join("memory.db"))
+    }
+
+    /// Open (or create) the memory database at `db_path`.
+    pub fn open(db_path: &std::path::Path) -> Result<Self> {
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let conn = Connection::open(&db_path)
+        let conn = Connection::open(db_path)
             .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
 
         // Main table with session support
@@ -60,6 +77,16 @@ impl SqliteMemory {
         )
         .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
 
+        // Full message history per session, restored after restarts
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                session_id TEXT PRIMARY KEY,
+                messages TEXT NOT NULL,
+                updated_at TEXT DEFAULT (datetime('now'))
+            );",
+        )
+        .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+
         // Ensure default session exists
         conn.execute(
             "INSERT OR IGNORE INTO sessions (id, name) VALUES ('default', 'Default')",
@@ -69,9 +96,48 @@ impl SqliteMemory {
 
         Ok(Self {
             conn: Mutex::new(conn),
+            embedder: None,
+            vectors: Mutex::new(VectorStore::new()),
+            vector_weight: 0.0,
+            keyword_weight: 1.0,
         })
     }
 
+    /// Embed entries with `embedder` and rank search results by
+    /// `vector_weight * cosine + keyword_weight * keyword score` (keyword
+    /// scores scaled to 0..1). Loads the embeddings already stored.
+    pub fn with_embedder(
+        mut self,
+        embedder: Box<dyn Provider>,
+        vector_weight: f32,
+        keyword_weight: f32,
+    ) -> Result<Self> {
+        let mut vectors = VectorStore::new();
+        {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, content, metadata, created_at, updated_at, embedding FROM memories WHERE embedding IS NOT NULL",
+                )
+                .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+            let rows = stmt
+                .query_map([], |row| Ok((entry_from_row(row)?, row.get::<_, Vec<u8>>(5)?)))
+                .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+            for (entry, blob) in rows.filter_map(|r| r.ok()) {
+                vectors.add(entry, embedding_from_blob(&blob));
+            }
+        }
+        tracing::info!("🧭 Vector memory: {} embedded entries via {}", vectors.len(), embedder.name());
+        self.vectors = Mutex::new(vectors);
+        self.embedder = Some(embedder);
+        self.vector_weight = vector_weight;
+        self.keyword_weight = keyword_weight;
+        Ok(self)
+    }
+
     /// Get conversation count across all sessions.
     pub fn conversation_count(&self) -> usize {
         let conn = match self.conn.lock() {
@@ -112,57 +178,9 @@ impl SqliteMemory {
         .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
         Ok(())
     }
-}
-
-#[async_trait]
-impl MemoryBackend for SqliteMemory {
-    fn name(&self) -> &str {
-        "sqlite"
-    }
 
-    async fn save(&self, entry: MemoryEntry) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
-
-        // Extract session_id from metadata or use default
-        let session_id = entry
-            .metadata
-            .get("session_id")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default")
-            .to_string();
-
-        conn.execute(
-            "INSERT OR REPLACE INTO memories (id, session_id, content, metadata, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![
-                entry.id,
-                session_id,
-                entry.content,
-                entry.metadata.to_string(),
-                entry.created_at.to_rfc3339(),
-                entry.updated_at.to_rfc3339(),
-            ],
-        ).map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
-
-        // Index in FTS5 for fast search
-        conn.execute(
-            "INSERT OR REPLACE INTO memories_fts (id, content) VALUES (?1, ?2)",
-            rusqlite::params![entry.id, entry.content],
-        )
-        .ok(); // Don't fail on FTS insert error
-
-        // Update session message count
-        conn.execute(
-            "UPDATE sessions SET message_count = message_count + 1, updated_at = datetime('now') WHERE id = ?1",
-            rusqlite::params![session_id],
-        ).ok();
-
-        Ok(())
-    }
-
-    async fn search(&self, query: &str, limit: usize) -> Result<Vec<MemorySearchResult>> {
+    /// FTS5 search with BM25 ranking, falling back to a LIKE scan.
+    fn keyword_search(&self, query: &str, limit: usize) -> Result<Vec<MemorySearchResult>> {
         let conn = self
             .conn
             .lock()
@@ -285,6 +303,94 @@ impl MemoryBackend for SqliteMemory {
             .collect();
         Ok(results)
     }
+}
+
+#[async_trait]
+impl MemoryBackend for SqliteMemory {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn save(&self, mut entry: MemoryEntry) -> Result<()> {
+        if let Some(embedder) = &self.embedder
+            && entry.embedding.is_none()
+        {
+            match embedder.embed(&entry.content).await {
+                Ok(embedding) => entry.embedding = Some(embedding),
+                Err(e) => tracing::warn!("Memory entry {} saved without embedding: {e}", entry.id),
+            }
+        }
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+
+        // Extract session_id from metadata or use default
+        let session_id = entry
+            .metadata
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("default")
+            .to_string();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO memories (id, session_id, content, metadata, embedding, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                entry.id,
+                session_id,
+                entry.content,
+                entry.metadata.to_string(),
+                entry.embedding.as_deref().map(embedding_to_blob),
+                entry.created_at.to_rfc3339(),
+                entry.updated_at.to_rfc3339(),
+            ],
+        ).map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+
+        // Index in FTS5 for fast search
+        conn.execute(
+            "INSERT OR REPLACE INTO memories_fts (id, content) VALUES (?1, ?2)",
+            rusqlite::params![entry.id, entry.content],
+        )
+        .ok(); // Don't fail on FTS insert error
+
+        // Update session message count
+        conn.execute(
+            "UPDATE sessions SET message_count = message_count + 1, updated_at = datetime('now') WHERE id = ?1",
+            rusqlite::params![session_id],
+        ).ok();
+        drop(conn);
+
+        if self.embedder.is_some() {
+            let mut vectors = self.vectors.lock().unwrap_or_else(|e| e.into_inner());
+            vectors.remove(&entry.id);
+            if let Some(embedding) = entry.embedding.clone() {
+                vectors.add(entry, embedding);
+            }
+        }
+        Ok(())
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<MemorySearchResult>> {
+        let Some(embedder) = &self.embedder else {
+            return self.keyword_search(query, limit);
+        };
+        // Rank a wider pool from each side so fusion can reorder them
+        let pool = limit.saturating_mul(4);
+        let keyword = self.keyword_search(query, pool)?;
+        let query_embedding = match embedder.embed(query).await {
+            Ok(embedding) => embedding,
+            Err(e) => {
+                tracing::warn!("Query embedding failed, using keyword search only: {e}");
+                return Ok(keyword.into_iter().take(limit).collect());
+            }
+        };
+        let vector = self
+            .vectors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .search(&query_embedding, pool);
+        Ok(fuse(keyword, vector, self.keyword_weight, self.vector_weight, limit))
+    }
 
     async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
         let conn = self
@@ -327,6 +433,7 @@ impl MemoryBackend for SqliteMemory {
             rusqlite::params![id],
         )
         .ok();
+        self.vectors.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
         Ok(())
     }
 
@@ -381,6 +488,160 @@ impl MemoryBackend for SqliteMemory {
         conn.execute("DELETE FROM memories", [])
             .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
         conn.execute("DELETE FROM memories_fts", []).ok();
+        self.vectors.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        Ok(())
+    }
+
+    async fn save_conversation(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        let json = serde_json::to_string(messages)?;
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO conversations (session_id, messages, updated_at) VALUES (?1, ?2, datetime('now'))",
+            rusqlite::params![session_id, json],
+        )
+        .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+        conn.execute(
+            "INSERT OR IGNORE INTO sessions (id, name) VALUES (?1, ?1)",
+            rusqlite::params![session_id],
+        )
+        .ok();
         Ok(())
     }
+
+    async fn load_conversation(&self, session_id: &str) -> Result<Option<Vec<Message>>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT messages FROM conversations WHERE session_id = ?1",
+                rusqlite::params![session_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| bizclaw_core::error::BizClawError::Memory(e.to_string()))?;
+        json.map(|j| serde_json::from_str(&j).map_err(Into::into)).transpose()
+    }
+}
+
+/// Entry from a `SELECT id, content, metadata, created_at, updated_at` row.
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<MemoryEntry> {
+    let timestamp = |i: usize| {
+        row.get::<_, String>(i)
+            .ok()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|d| d.with_timezone(&chrono::Utc))
+            .unwrap_or_default()
+    };
+    Ok(MemoryEntry {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        metadata: row
+            .get::<_, String>(2)
+            .map(|s| serde_json::from_str(&s).unwrap_or_default())
+            .unwrap_or_default(),
+        embedding: None,
+        created_at: timestamp(3),
+        updated_at: timestamp(4),
+    })
+}
+
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn embedding_from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Merge keyword and vector hits by entry ID. Keyword scores are scaled by
+/// the best one so both sides range over 0..1 before weighting.
+fn fuse(
+    keyword: Vec<MemorySearchResult>,
+    vector: Vec<MemorySearchResult>,
+    keyword_weight: f32,
+    vector_weight: f32,
+    limit: usize,
+) -> Vec<MemorySearchResult> {
+    let best = keyword.iter().map(|r| r.score).fold(0.0, f32::max);
+    let mut fused: Vec<MemorySearchResult> = keyword
+        .into_iter()
+        .map(|r| MemorySearchResult {
+            score: if best > 0.0 { keyword_weight * r.score / best } else { 0.0 },
+            entry: r.entry,
+        })
+        .collect();
+    for hit in vector.into_iter().filter(|r| r.score > 0.0) {
+        let score = vector_weight * hit.score;
+        match fused.iter_mut().find(|r| r.entry.id == hit.entry.id) {
+            Some(existing) => existing.score += score,
+            None => fused.push(MemorySearchResult { entry: hit.entry, score }),
+        }
+    }
+    fused.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    fused.truncate(limit);
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::tests::{LetterEmbedder, entry};
+
+    #[tokio::test]
+    async fn test_conversation_round_trip() {
+        let dir = std::env::temp_dir().join(format!("bizclaw_conv_{}", uuid::Uuid::new_v4()));
+        let memory = SqliteMemory::open(&dir.join("memory.db")).unwrap();
+        assert!(memory.load_conversation("s1").await.unwrap().is_none());
+
+        let messages = vec![Message::user("hi"), Message::assistant("hello"), Message::tool("42", "c1")];
+        memory.save_conversation("s1", &messages).await.unwrap();
+        memory.save_conversation("s2", &messages[..1]).await.unwrap();
+
+        let restored = memory.load_conversation("s1").await.unwrap().unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored[1].content, "hello");
+        assert_eq!(restored[2].tool_call_id.as_deref(), Some("c1"));
+        assert_eq!(memory.load_conversation("s2").await.unwrap().unwrap().len(), 1);
+        assert!(memory.list_sessions().iter().any(|(id, _, _)| id == "s1"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_finds_what_keywords_miss() {
+        let dir = std::env::temp_dir().join(format!("bizclaw_hybrid_{}", uuid::Uuid::new_v4()));
+        let db = dir.join("memory.db");
+        let memory = SqliteMemory::open(&db).unwrap().with_embedder(Box::new(LetterEmbedder), 0.7, 0.3).unwrap();
+        memory.save(entry("a", "aaaa banana")).await.unwrap();
+        memory.save(entry("o", "oooo")).await.unwrap();
+
+        // No keyword match: only the vector side finds "oooo"
+        let results = memory.search("ooo", 1).await.unwrap();
+        assert_eq!(results[0].entry.id, "o");
+        // A keyword hit outranks a closer vector
+        let results = memory.search("banana", 2).await.unwrap();
+        assert_eq!(results[0].entry.id, "a");
+
+        // Embeddings persist and are reloaded
+        drop(memory);
+        let reopened = SqliteMemory::open(&db).unwrap().with_embedder(Box::new(LetterEmbedder), 1.0, 0.0).unwrap();
+        assert_eq!(reopened.vectors.lock().unwrap().len(), 2);
+        reopened.delete("o").await.unwrap();
+        assert!(reopened.search("ooo", 1).await.unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_fuse_weights_scaled_keyword_and_vector_scores() {
+        let hit = |id: &str, score| MemorySearchResult { entry: entry(id, id), score };
+        let fused = fuse(vec![hit("k", 8.0), hit("both", 4.0)], vec![hit("both", 0.9), hit("v", 0.8)], 0.5, 0.5, 3);
+        let ranked: Vec<_> = fused.iter().map(|r| (r.entry.id.as_str(), r.score)).collect();
+        assert_eq!(ranked, [("both", 0.7), ("k", 0.5), ("v", 0.4)]);
+    }
 }
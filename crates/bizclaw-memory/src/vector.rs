@@ -1,9 +1,13 @@
 //! In-memory vector search engine for semantic memory.
 //!
-//! Uses cosine similarity for nearest-neighbor search.
-//! Phase 3: Will integrate with bizclaw-brain for embeddings.
+//! Uses cosine similarity (SIMD dot products from `bizclaw-brain`) over a
+//! flat index. Text is embedded through any [`Provider`]; the local brain
+//! provider keeps it offline.
 
+use bizclaw_brain::simd::dot_product_simd;
+use bizclaw_core::error::Result;
 use bizclaw_core::traits::memory::{MemoryEntry, MemorySearchResult};
+use bizclaw_core::traits::provider::Provider;
 
 /// Simple in-memory vector store using cosine similarity.
 pub struct VectorStore {
@@ -20,6 +24,25 @@ impl VectorStore {
         self.entries.push((entry, embedding));
     }
 
+    /// Embed `entry.content` with `provider` and add it.
+    pub async fn add_text(&mut self, provider: &dyn Provider, mut entry: MemoryEntry) -> Result<()> {
+        let embedding = provider.embed(&entry.content).await?;
+        entry.embedding = Some(embedding.clone());
+        self.add(entry, embedding);
+        Ok(())
+    }
+
+    /// Embed `query` with `provider` and search by cosine similarity.
+    pub async fn search_text(
+        &self,
+        provider: &dyn Provider,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MemorySearchResult>> {
+        let query_embedding = provider.embed(query).await?;
+        Ok(self.search(&query_embedding, limit))
+    }
+
     /// Search by cosine similarity against a query embedding.
     pub fn search(&self, query_embedding: &[f32], limit: usize) -> Vec<MemorySearchResult> {
         let mut scored: Vec<(f32, &MemoryEntry)> = self
@@ -43,6 +66,11 @@ impl VectorStore {
             .collect()
     }
 
+    /// Remove the entry with `id`, if present.
+    pub fn remove(&mut self, id: &str) {
+        self.entries.retain(|(entry, _)| entry.id != id);
+    }
+
     /// Number of stored vectors.
     pub fn len(&self) -> usize {
         self.entries.len()
@@ -71,22 +99,13 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
 
-    let mut dot = 0.0f32;
-    let mut norm_a = 0.0f32;
-    let mut norm_b = 0.0f32;
-
-    for (x, y) in a.iter().zip(b.iter()) {
-        dot += x * y;
-        norm_a += x * x;
-        norm_b += y * y;
-    }
-
-    let denom = norm_a.sqrt() * norm_b.sqrt();
+    let dot = dot_product_simd(a, b);
+    let denom = dot_product_simd(a, a).sqrt() * dot_product_simd(b, b).sqrt();
     if denom == 0.0 { 0.0 } else { dot / denom }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[test]
@@ -112,4 +131,56 @@ mod tests {
         let sim = cosine_similarity(&a, &b);
         assert!((sim + 1.0).abs() < 1e-6);
     }
+
+    /// Embeds text as counts of a few marker letters.
+    pub(crate) struct LetterEmbedder;
+
+    #[async_trait::async_trait]
+    impl Provider for LetterEmbedder {
+        fn name(&self) -> &str {
+            "letters"
+        }
+
+        async fn chat(
+            &self,
+            _messages: &[bizclaw_core::types::Message],
+            _tools: &[bizclaw_core::types::ToolDefinition],
+            _params: &bizclaw_core::traits::provider::GenerateParams,
+        ) -> Result<bizclaw_core::types::ProviderResponse> {
+            unimplemented!()
+        }
+
+        async fn list_models(&self) -> Result<Vec<bizclaw_core::types::ModelInfo>> {
+            Ok(vec![])
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            Ok("aeiou".chars().map(|c| text.matches(c).count() as f32).collect())
+        }
+    }
+
+    pub(crate) fn entry(id: &str, content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: id.into(),
+            content: content.into(),
+            metadata: serde_json::Value::Null,
+            embedding: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_text_search_through_provider() {
+        let mut store = VectorStore::new();
+        store.add_text(&LetterEmbedder, entry("a", "aaaa")).await.unwrap();
+        store.add_text(&LetterEmbedder, entry("o", "oooo")).await.unwrap();
+        let results = store.search_text(&LetterEmbedder, "ooo!", 1).await.unwrap();
+        assert_eq!(results[0].entry.id, "o");
+        assert!(results[0].entry.embedding.is_some());
+    }
 }
@@ -1,26 +1,39 @@
 use async_trait::async_trait;
 use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::provider::{GenerateParams, Provider};
-use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, Role, ToolDefinition};
-use tokio::sync::Mutex;
+use bizclaw_core::traits::provider::{ChatChunkSink, GenerateParams, Provider};
+use bizclaw_core::traits::tokenizer::{ApproxTokenizer, Tokenizer};
+use bizclaw_core::types::{
+    FunctionCall, Message, ModelInfo, ProviderResponse, Role, ToolCall, ToolDefinition,
+};
+use bizclaw_brain::pool::{ModelPool, SharedEngine};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Loaded models shared by every brain provider in the process, so agents
+/// on the same model share one engine and agents on different models
+/// don't reload each other's.
+static SHARED_POOL: OnceLock<Arc<ModelPool>> = OnceLock::new();
 
 pub struct BrainProvider {
-    engine: Mutex<bizclaw_brain::BrainEngine>,
+    pool: Arc<ModelPool>,
+    /// `brain.model_path`, else the first model in the models directory.
+    default_model: PathBuf,
+    models_dir: PathBuf,
 }
 
 impl BrainProvider {
     pub fn new(config: &BizClawConfig) -> Result<Self> {
-        let brain_config = bizclaw_brain::BrainConfig {
-            threads: config.brain.threads,
-            max_tokens: config.brain.max_tokens,
-            context_length: config.brain.context_length,
-            temperature: config.brain.temperature,
-            top_p: config.brain.top_p,
-            json_mode: config.brain.json_mode,
-        };
+        let brain_config = bizclaw_brain::BrainConfig::from(&config.brain);
 
-        let mut engine = bizclaw_brain::BrainEngine::new(brain_config);
+        // The first provider's config sizes the pool for the whole process
+        let pool = Arc::clone(SHARED_POOL.get_or_init(|| {
+            Arc::new(ModelPool::new(
+                brain_config,
+                config.brain.model_pool_mb as usize * 1024 * 1024,
+            ))
+        }));
 
         // Try to load model from configured path
         let model_dir = BizClawConfig::home_dir().join("models");
@@ -32,8 +45,8 @@ impl BrainProvider {
         };
 
         if model_path.exists() {
-            match engine.load_model(&model_path) {
-                Ok(()) => {
+            match pool.get(&model_path) {
+                Ok(_) => {
                     tracing::info!("Brain provider: model loaded from {}", model_path.display())
                 }
                 Err(e) => tracing::warn!("Brain provider: failed to load model: {e}"),
@@ -46,8 +59,289 @@ impl BrainProvider {
         }
 
         Ok(Self {
-            engine: Mutex::new(engine),
+            pool,
+            default_model: model_path,
+            models_dir: model_dir,
+        })
+    }
+
+    /// The model file for a request: `requested` when it names a `.gguf`
+    /// file (a path, or a file in the models directory), else the default.
+    fn resolve_model(&self, requested: &str) -> PathBuf {
+        resolve_model_path(requested, &self.models_dir).unwrap_or_else(|| self.default_model.clone())
+    }
+
+    /// The loaded engine for `path`, loading it on first use.
+    async fn engine(&self, path: PathBuf) -> Result<SharedEngine> {
+        if !path.exists() {
+            return Err(BizClawError::Brain(format!(
+                "No model at {}. Place a .gguf file in ~/.bizclaw/models/ or set brain.model_path in config.",
+                path.display()
+            )));
+        }
+        // Loading maps the file and allocates the KV cache; keep it off the runtime
+        let pool = Arc::clone(&self.pool);
+        tokio::task::spawn_blocking(move || pool.get(&path))
+            .await
+            .map_err(|e| BizClawError::Brain(format!("Model load task failed: {e}")))?
+    }
+}
+
+/// `requested` as a model file: an existing `.gguf` path, or the name of a
+/// `.gguf` file in `models_dir`.
+fn resolve_model_path(requested: &str, models_dir: &Path) -> Option<PathBuf> {
+    if !requested.ends_with(".gguf") {
+        return None;
+    }
+    let direct = PathBuf::from(requested);
+    if direct.exists() {
+        return Some(direct);
+    }
+    let in_dir = models_dir.join(requested);
+    in_dir.exists().then_some(in_dir)
+}
+
+/// The engine's sampler config with the per-call `min_p` / `mirostat`
+/// overrides applied, or `None` when the call overrides nothing.
+fn sampling_overrides(
+    base: Option<&bizclaw_brain::sampler::SamplerConfig>,
+    params: &GenerateParams,
+) -> Option<bizclaw_brain::sampler::SamplerConfig> {
+    if params.min_p.is_none() && params.mirostat.is_none() {
+        return None;
+    }
+    let mut config = base.cloned().unwrap_or_default();
+    if let Some(min_p) = params.min_p {
+        config.min_p = min_p;
+    }
+    if params.mirostat.is_some() {
+        config.mirostat = params.mirostat;
+    }
+    Some(config)
+}
+
+/// Generation budget for a request; 0 means the provider default.
+fn max_tokens(params: &GenerateParams) -> u32 {
+    if params.max_tokens > 0 {
+        params.max_tokens
+    } else {
+        256
+    }
+}
+
+/// Tool-use instructions in the Hermes format, which Qwen2.5, Hermes and
+/// most other tool-tuned GGUF models were trained on.
+const TOOLS_PROMPT_HEAD: &str = "# Tools\n\nYou may call one or more functions to assist with the user query.\n\n\
+You are provided with function signatures within <tools></tools> XML tags:\n<tools>";
+const TOOLS_PROMPT_TAIL: &str = "</tools>\n\nFor each function call, return a json object with function name \
+and arguments within <tool_call></tool_call> XML tags:\n<tool_call>\n\
+{\"name\": <function-name>, \"arguments\": <args-json-object>}\n</tool_call>";
+const CALL_OPEN: &str = "<tool_call>";
+const CALL_CLOSE: &str = "</tool_call>";
+
+/// `messages` rewritten for prompted function calling: tool signatures join
+/// the system prompt, earlier calls are written back as `<tool_call>`
+/// blocks, and tool results become `<tool_response>` user turns.
+fn tool_prompt_messages(messages: &[Message], tools: &[ToolDefinition]) -> Vec<Message> {
+    let mut out = Vec::with_capacity(messages.len() + 1);
+    let mut rest = messages;
+    if !tools.is_empty() {
+        let mut prompt = TOOLS_PROMPT_HEAD.to_string();
+        for tool in tools {
+            let signature = json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                },
+            });
+            prompt.push_str(&format!("\n{signature}"));
+        }
+        prompt.push('\n');
+        prompt.push_str(TOOLS_PROMPT_TAIL);
+        match messages.first() {
+            Some(first) if first.role == Role::System => {
+                out.push(Message::system(format!("{}\n\n{prompt}", first.content)));
+                rest = &messages[1..];
+            }
+            _ => out.push(Message::system(prompt)),
+        }
+    }
+
+    let mut after_tool = false;
+    for msg in rest {
+        match msg.role {
+            Role::Tool => {
+                let response = format!("<tool_response>\n{}\n</tool_response>", msg.content);
+                match out.last_mut() {
+                    // Results of one round share a turn
+                    Some(prev) if after_tool => prev.content.push_str(&format!("\n{response}")),
+                    _ => out.push(Message::user(response)),
+                }
+                after_tool = true;
+                continue;
+            }
+            Role::Assistant if msg.tool_calls.as_ref().is_some_and(|c| !c.is_empty()) => {
+                let mut content = msg.content.trim().to_string();
+                for call in msg.tool_calls.iter().flatten() {
+                    let arguments = serde_json::from_str(&call.function.arguments)
+                        .unwrap_or_else(|_| Value::String(call.function.arguments.clone()));
+                    let call = json!({"name": call.function.name, "arguments": arguments});
+                    if !content.is_empty() {
+                        content.push('\n');
+                    }
+                    content.push_str(&format!("{CALL_OPEN}\n{call}\n{CALL_CLOSE}"));
+                }
+                out.push(Message::assistant(content));
+            }
+            _ => out.push(msg.clone()),
+        }
+        after_tool = false;
+    }
+    out
+}
+
+/// The response for a generated reply. With `tools` offered, `<tool_call>`
+/// blocks (the last may be unterminated) become [`ToolCall`]s, as does a
+/// reply that is nothing but a JSON call to one of the tools.
+fn tool_response(text: &str, tools: &[ToolDefinition]) -> ProviderResponse {
+    if tools.is_empty() {
+        return ProviderResponse::text(text);
+    }
+    let mut calls = Vec::new();
+    let mut content = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(CALL_OPEN) {
+        content.push_str(&rest[..start]);
+        let body = &rest[start + CALL_OPEN.len()..];
+        let (call, after) = match body.find(CALL_CLOSE) {
+            Some(end) => (&body[..end], &body[end + CALL_CLOSE.len()..]),
+            None => (body, ""),
+        };
+        match function_call(call) {
+            Some(function) => calls.push(function),
+            None => content.push_str(&rest[start..rest.len() - after.len()]),
+        }
+        rest = after;
+    }
+    content.push_str(rest);
+    if calls.is_empty()
+        && let Some(function) = function_call(text)
+        && tools.iter().any(|t| t.name == function.name)
+    {
+        calls.push(function);
+        content.clear();
+    }
+    if calls.is_empty() {
+        return ProviderResponse::text(text);
+    }
+
+    let calls = calls
+        .into_iter()
+        .map(|function| ToolCall {
+            id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+            r#type: "function".into(),
+            function,
         })
+        .collect();
+    let mut response = ProviderResponse::with_tool_calls(calls);
+    let content = content.trim();
+    response.content = (!content.is_empty()).then(|| content.to_string());
+    response
+}
+
+/// A `{"name": ..., "arguments": {...}}` call, optionally in a code fence.
+fn function_call(text: &str) -> Option<FunctionCall> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|t| t.strip_suffix("```"))
+        .unwrap_or(text);
+    let value: Value = serde_json::from_str(text.trim()).ok()?;
+    let name = value.get("name")?.as_str()?.to_string();
+    let arguments = match value.get("arguments").or_else(|| value.get("parameters")) {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "{}".into(),
+    };
+    Some(FunctionCall { name, arguments })
+}
+
+/// Streams reply text while holding back prompted tool calls, which reach
+/// the caller as [`ToolCall`]s instead.
+struct ToolCallFilter {
+    /// Tools were offered, so the reply may contain calls.
+    active: bool,
+    /// Received but unsent: a possible start of `<tool_call>`.
+    pending: String,
+    /// Inside a call, or a reply that opened like a bare JSON call; nothing
+    /// more is streamed until the reply is parsed.
+    held: bool,
+    sent: String,
+}
+
+impl ToolCallFilter {
+    fn new(active: bool) -> Self {
+        Self {
+            active,
+            pending: String::new(),
+            held: false,
+            sent: String::new(),
+        }
+    }
+
+    /// Text of `piece` that is safe to stream now.
+    fn push(&mut self, piece: &str) -> Option<String> {
+        if !self.active {
+            return Some(piece.to_string());
+        }
+        if self.held {
+            return None;
+        }
+        self.pending.push_str(piece);
+        if self.sent.is_empty() {
+            let start = self.pending.trim_start();
+            if start.starts_with('{') || start.starts_with("```") {
+                self.held = true;
+            }
+            if start.is_empty() || self.held {
+                return None;
+            }
+            self.pending = start.to_string();
+        }
+        let ready = match self.pending.find(CALL_OPEN) {
+            Some(at) => {
+                self.held = true;
+                at
+            }
+            // Keep a trailing "<tool_c" until the next piece decides it
+            None => {
+                let partial = (1..CALL_OPEN.len())
+                    .rev()
+                    .find(|&n| self.pending.ends_with(&CALL_OPEN[..n]))
+                    .unwrap_or(0);
+                self.pending.len() - partial
+            }
+        };
+        let text: String = self.pending.drain(..ready).collect();
+        if text.is_empty() {
+            return None;
+        }
+        self.sent.push_str(&text);
+        Some(text)
+    }
+
+    /// Text held back as a possible call that turned out not to be one,
+    /// once the reply has been parsed into `response`.
+    fn finish(&self, response: &ProviderResponse) -> Option<String> {
+        if !self.active || !response.tool_calls.is_empty() {
+            return None;
+        }
+        let content = response.content.as_deref()?.trim_start();
+        let rest = content.strip_prefix(self.sent.as_str())?;
+        (!rest.is_empty()).then(|| rest.to_string())
     }
 }
 
@@ -79,39 +373,78 @@ impl Provider for BrainProvider {
     async fn chat(
         &self,
         messages: &[Message],
-        _tools: &[ToolDefinition],
+        tools: &[ToolDefinition],
         params: &GenerateParams,
     ) -> Result<ProviderResponse> {
-        if !self.engine.lock().await.is_loaded() {
-            return Err(BizClawError::Brain(
-                "No model loaded. Place a .gguf file in ~/.bizclaw/models/ or set brain.model_path in config.".into()
-            ));
-        }
+        let engine = self.engine(self.resolve_model(&params.model)).await?;
+        let messages = tool_prompt_messages(messages, tools);
 
-        // Format messages into a chat prompt (Llama-style)
-        let prompt = format_chat_prompt(messages);
+        let mut engine = engine.lock().await;
+        let sampling = sampling_overrides(engine.sampler_config(), params);
 
-        let max_tokens = if params.max_tokens > 0 {
-            params.max_tokens
-        } else {
-            256
+        // Formatted with the model's own chat template
+        let response = match &params.response_schema {
+            Some(schema) => engine.chat_with_schema(&messages, max_tokens(params), sampling, schema)?,
+            None => engine.chat_with_sampling(&messages, max_tokens(params), sampling)?,
         };
+        Ok(tool_response(&response, tools))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+        sink: &ChatChunkSink,
+    ) -> Result<ProviderResponse> {
+        let engine = self.engine(self.resolve_model(&params.model)).await?;
+        let messages = tool_prompt_messages(messages, tools);
+        let params = params.clone();
+        let chunks = sink.clone();
+        let mut filter = ToolCallFilter::new(!tools.is_empty());
+
+        // Decoding blocks for the whole reply; chunks go out as they are sampled
+        let (text, filter) = tokio::task::spawn_blocking(move || {
+            let mut engine = engine.blocking_lock();
+            let sampling = sampling_overrides(engine.sampler_config(), &params);
+            // A dropped receiver ends generation early
+            let text = engine.chat_streaming(&messages, max_tokens(&params), sampling, &mut |piece| {
+                match filter.push(piece) {
+                    Some(text) => chunks.send(text).is_ok(),
+                    None => true,
+                }
+            })?;
+            Ok::<_, BizClawError>((text, filter))
+        })
+        .await
+        .map_err(|e| BizClawError::Brain(format!("Generation task failed: {e}")))??;
 
-        let response = self.engine.lock().await.generate(&prompt, max_tokens)?;
-        Ok(ProviderResponse::text(response))
+        let response = tool_response(&text, tools);
+        if let Some(rest) = filter.finish(&response) {
+            let _ = sink.send(rest);
+        }
+        Ok(response)
     }
 
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         let mut models = vec![];
 
-        if let Some(info) = self.engine.lock().await.model_info() {
-            models.push(ModelInfo {
-                id: "local-model".into(),
-                name: info,
-                provider: "brain".into(),
-                context_length: 2048,
-                max_output_tokens: Some(256),
-            });
+        for path in self.pool.loaded() {
+            let Some(engine) = self.pool.peek(&path) else { continue };
+            let info = engine.lock().await.model_info();
+            if let Some(info) = info {
+                let id = path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "local-model".into());
+                models.push(ModelInfo {
+                    id,
+                    name: info,
+                    provider: "brain".into(),
+                    context_length: 2048,
+                    max_output_tokens: Some(256),
+                });
+            }
         }
 
         // List available models in ~/.bizclaw/models/
@@ -156,30 +489,162 @@ impl Provider for BrainProvider {
     }
 
     async fn health_check(&self) -> Result<bool> {
-        Ok(self.engine.lock().await.is_loaded())
+        Ok(self.pool.contains(&self.default_model))
+    }
+
+    /// The default model's own tokenizer once it is loaded (and not busy).
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        self.pool
+            .peek(&self.default_model)
+            .and_then(|engine| engine.try_lock().ok()?.tokenizer())
+            .map(|t| t as Arc<dyn Tokenizer>)
+            .unwrap_or_else(|| Arc::new(ApproxTokenizer))
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let engine = self.engine(self.default_model.clone()).await?;
+        let mut engine = engine.lock().await;
+        engine.embed(text)
     }
 }
 
-/// Format messages into a LLaMA-style chat prompt.
-fn format_chat_prompt(messages: &[Message]) -> String {
-    let mut prompt = String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for msg in messages {
-        match msg.role {
-            Role::System => {
-                prompt.push_str(&format!("[INST] <<SYS>>\n{}\n<</SYS>>\n\n", msg.content));
-            }
-            Role::User => {
-                prompt.push_str(&format!("{} [/INST]", msg.content));
-            }
-            Role::Assistant => {
-                prompt.push_str(&format!(" {} </s><s>[INST] ", msg.content));
-            }
-            Role::Tool => {
-                prompt.push_str(&format!("Tool result: {} [/INST]", msg.content));
-            }
+    #[test]
+    fn test_resolve_model_path() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-models-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model = dir.join("qwen2-0.5b.gguf");
+        std::fs::write(&model, b"GGUF").unwrap();
+
+        assert_eq!(resolve_model_path("qwen2-0.5b.gguf", &dir), Some(model.clone()));
+        let absolute = model.display().to_string();
+        assert_eq!(resolve_model_path(&absolute, Path::new("/nonexistent")), Some(model));
+        // Not a model file, or not on disk: the provider's default applies
+        assert_eq!(resolve_model_path("gpt-4o", &dir), None);
+        assert_eq!(resolve_model_path("missing.gguf", &dir), None);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn tool(name: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.into(),
+            description: format!("{name} tool"),
+            parameters: json!({"type": "object", "properties": {"query": {"type": "string"}}}),
         }
     }
 
-    prompt
+    #[test]
+    fn test_tool_prompt_messages() {
+        let call = ToolCall {
+            id: "call_1".into(),
+            r#type: "function".into(),
+            function: FunctionCall {
+                name: "web_search".into(),
+                arguments: r#"{"query":"rust"}"#.into(),
+            },
+        };
+        let messages = vec![
+            Message::system("You are helpful."),
+            Message::user("search rust"),
+            Message {
+                tool_calls: Some(vec![call]),
+                ..Message::assistant("")
+            },
+            Message::tool("result one", "call_1"),
+            Message::tool("result two", "call_2"),
+        ];
+        let out = tool_prompt_messages(&messages, &[tool("web_search")]);
+        assert_eq!(out.len(), 4);
+        assert!(out[0].content.starts_with("You are helpful.\n\n# Tools"));
+        assert!(out[0].content.contains(r#""name":"web_search""#));
+        assert_eq!(
+            out[2].content,
+            "<tool_call>\n{\"arguments\":{\"query\":\"rust\"},\"name\":\"web_search\"}\n</tool_call>"
+        );
+        // Results of one round share a user turn
+        assert_eq!(out[3].role, Role::User);
+        assert_eq!(
+            out[3].content,
+            "<tool_response>\nresult one\n</tool_response>\n<tool_response>\nresult two\n</tool_response>"
+        );
+
+        // Without tools the history is still rewritten, but no prompt is added
+        let out = tool_prompt_messages(&messages[1..], &[]);
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[0].content, "search rust");
+    }
+
+    #[test]
+    fn test_tool_response_parses_calls() {
+        let tools = [tool("web_search"), tool("shell")];
+        let text = "Let me check.\n<tool_call>\n{\"name\": \"web_search\", \"arguments\": {\"query\": \"bizclaw\"}}\n</tool_call>\n<tool_call>\n{\"name\": \"shell\", \"arguments\": \"{\\\"command\\\": \\\"ls\\\"}\"}";
+        let response = tool_response(text, &tools);
+        assert_eq!(response.content.as_deref(), Some("Let me check."));
+        assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+        let calls: Vec<_> = response
+            .tool_calls
+            .iter()
+            .map(|c| (c.function.name.as_str(), c.function.arguments.as_str()))
+            .collect();
+        // The second block is unterminated and its arguments are a JSON string
+        assert_eq!(
+            calls,
+            [("web_search", r#"{"query":"bizclaw"}"#), ("shell", r#"{"command": "ls"}"#)]
+        );
+        assert_ne!(response.tool_calls[0].id, response.tool_calls[1].id);
+
+        // A bare JSON call to an offered tool, in a code fence
+        let fenced = "```json\n{\"name\": \"shell\", \"arguments\": {}}\n```";
+        let response = tool_response(fenced, &tools);
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.content, None);
+
+        // Plain JSON answers, broken blocks and tool-less requests stay text
+        for (text, tools) in [
+            (r#"{"name": "Ada", "age": 36}"#, &tools[..]),
+            ("<tool_call>not json</tool_call>", &tools[..]),
+            ("<tool_call>{\"name\": \"shell\"}</tool_call>", &[][..]),
+        ] {
+            let response = tool_response(text, tools);
+            assert!(response.tool_calls.is_empty(), "{text}");
+            assert_eq!(response.content.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_tool_call_filter_holds_back_calls() {
+        let stream = |active: bool, pieces: &[&str]| {
+            let mut filter = ToolCallFilter::new(active);
+            let sent: String = pieces.iter().filter_map(|p| filter.push(p)).collect();
+            (sent, filter)
+        };
+        let tools = [tool("web_search")];
+
+        let pieces = ["Sure", ", let me <", "tool_c", "all>\n{\"name\": \"web_search\"}", "</tool_call>"];
+        let (sent, filter) = stream(true, &pieces);
+        assert_eq!(sent, "Sure, let me ");
+        assert_eq!(filter.finish(&tool_response(&pieces.concat(), &tools)), None);
+
+        // A "<" that is not a tag is released by the next piece
+        let pieces = ["\n a <", "b> c"];
+        let (sent, filter) = stream(true, &pieces);
+        assert_eq!(sent, "a <b> c");
+        assert_eq!(filter.finish(&tool_response(&pieces.concat(), &tools)), None);
+
+        // Leading JSON is held until parsed; not a call, so it is sent at the end
+        let pieces = ["{\"answer\"", ": 42}"];
+        let (sent, filter) = stream(true, &pieces);
+        assert_eq!(sent, "");
+        let rest = filter.finish(&tool_response(&pieces.concat(), &tools));
+        assert_eq!(rest.as_deref(), Some(r#"{"answer": 42}"#));
+
+        // Without tools everything passes straight through
+        let (sent, filter) = stream(false, &["<tool_call>", "{}"]);
+        assert_eq!(sent, "<tool_call>{}");
+        assert_eq!(filter.finish(&ProviderResponse::text(sent.clone())), None);
+    }
 }
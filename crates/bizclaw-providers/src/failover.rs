@@ -1,18 +1,26 @@
 //! Provider Failover — automatic fallback when primary provider fails.
 //!
 //! Lightweight failover chain: try primary → fallback₁ → fallback₂.
-//! No heavyweight circuit breaker, no thread pools.
+//! Transient and rate-limit errors are retried on the same provider with
+//! exponential backoff first; a provider that keeps failing has its circuit
+//! opened and is skipped until its cool-down expires. A streamed reply that
+//! fails after text reached the caller is neither retried nor failed over,
+//! so nothing is shown twice. No thread pools.
 //! RAM: ~100 bytes per provider entry.
 
 use async_trait::async_trait;
+use bizclaw_core::config::{BizClawConfig, FailoverConfig};
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::traits::provider::{ChatChunkSink, GenerateParams, Provider};
+use bizclaw_core::traits::tokenizer::{ApproxTokenizer, Tokenizer};
 use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// Per-provider health tracking (64 bytes).
 struct ProviderSlot {
     provider: Box<dyn Provider>,
+    /// Model to request from this provider (None = the caller's model).
+    model: Option<String>,
     /// Consecutive failure count.
     failures: AtomicU32,
     /// Timestamp of last failure (unix secs, 0 = never failed).
@@ -24,9 +32,10 @@ struct ProviderSlot {
 }
 
 impl ProviderSlot {
-    fn new(provider: Box<dyn Provider>) -> Self {
+    fn new(provider: Box<dyn Provider>, model: Option<String>) -> Self {
         Self {
             provider,
+            model,
             failures: AtomicU32::new(0),
             last_failure: AtomicU64::new(0),
             max_failures: 3,
@@ -66,6 +75,7 @@ impl ProviderSlot {
 /// Failover provider — tries providers in order, skipping unhealthy ones.
 pub struct FailoverProvider {
     slots: Vec<ProviderSlot>,
+    policy: FailoverConfig,
 }
 
 impl FailoverProvider {
@@ -74,7 +84,8 @@ impl FailoverProvider {
     pub fn new(providers: Vec<Box<dyn Provider>>) -> Self {
         assert!(!providers.is_empty(), "Need at least one provider");
         Self {
-            slots: providers.into_iter().map(ProviderSlot::new).collect(),
+            slots: providers.into_iter().map(|p| ProviderSlot::new(p, None)).collect(),
+            policy: FailoverConfig::default(),
         }
     }
 
@@ -83,41 +94,56 @@ impl FailoverProvider {
         Self::new(vec![primary, fallback])
     }
 
-    /// Number of providers in the chain.
-    pub fn chain_len(&self) -> usize {
-        self.slots.len()
+    /// Build the chain from `[LLM]`: the primary provider, then each of
+    /// `fallback_providers` (skipping entries that fail to initialize), with
+    /// the `[LLM.failover]` policy.
+    pub fn from_config(config: &BizClawConfig) -> Result<Self> {
+        let mut chain = Self::new(vec![crate::create_provider(config)?])
+            .with_policy(config.llm.failover.clone());
+        for fb in &config.llm.fallback_providers {
+            match crate::create_fallback_provider(config, fb) {
+                Ok((provider, model)) => chain.slots.push(ProviderSlot::new(provider, Some(model))),
+                Err(e) => tracing::warn!("⚠️ Fallback provider '{}' unavailable: {e}", fb.provider),
+            }
+        }
+        Ok(chain)
     }
 
-    /// Get health status of all providers.
-    pub fn health_status(&self) -> Vec<(&str, bool, u32)> {
-        self.slots
-            .iter()
-            .map(|s| {
-                (
-                    s.provider.name(),
-                    s.is_healthy(),
-                    s.failures.load(Ordering::Relaxed),
-                )
-            })
-            .collect()
+    /// Use `policy` for retries and circuit breaking.
+    pub fn with_policy(mut self, policy: FailoverConfig) -> Self {
+        for slot in &mut self.slots {
+            slot.max_failures = policy.failure_threshold.max(1);
+            slot.cooldown_secs = policy.cooldown_secs;
+        }
+        self.policy = policy;
+        self
     }
-}
 
-#[async_trait]
-impl Provider for FailoverProvider {
-    fn name(&self) -> &str {
-        // Return primary provider name
-        self.slots
-            .first()
-            .map(|s| s.provider.name())
-            .unwrap_or("failover")
+    /// Delay before retry number `retry` (0-based).
+    fn backoff(&self, retry: u32) -> std::time::Duration {
+        let ms = self
+            .policy
+            .backoff_ms
+            .saturating_mul(1u64 << retry.min(16))
+            .min(self.policy.max_backoff_ms);
+        std::time::Duration::from_millis(ms)
     }
 
-    async fn chat(
+    /// Number of providers in the chain.
+    pub fn chain_len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Send the request to each healthy provider in order until one
+    /// succeeds (streaming to `sink` if given), retrying transient errors
+    /// and tracking failures. Fallbacks get tools per
+    /// [`crate::fallback_tools`].
+    async fn first_success(
         &self,
         messages: &[Message],
         tools: &[ToolDefinition],
         params: &GenerateParams,
+        sink: Option<&ChatChunkSink>,
     ) -> Result<ProviderResponse> {
         let mut last_error = None;
 
@@ -131,7 +157,37 @@ impl Provider for FailoverProvider {
                 continue;
             }
 
-            match slot.provider.chat(messages, tools, params).await {
+            let slot_params;
+            let params = match &slot.model {
+                Some(model) => {
+                    slot_params = GenerateParams { model: model.clone(), ..params.clone() };
+                    &slot_params
+                }
+                None => params,
+            };
+            let tools = if idx == 0 { tools } else { crate::fallback_tools(slot.provider.as_ref(), tools) };
+            let mut retry = 0;
+            let (result, emitted) = loop {
+                let (result, emitted) = match sink {
+                    Some(sink) => stream_attempt(slot.provider.as_ref(), messages, tools, params, sink).await,
+                    None => (slot.provider.chat(messages, tools, params).await, false),
+                };
+                match result {
+                    Err(e) if !emitted && e.is_retryable() && retry < self.policy.max_retries => {
+                        let delay = self.backoff(retry);
+                        tracing::debug!(
+                            "⏳ {} failed ({e}), retrying in {}ms",
+                            slot.provider.name(),
+                            delay.as_millis()
+                        );
+                        tokio::time::sleep(delay).await;
+                        retry += 1;
+                    }
+                    result => break (result, emitted),
+                }
+            };
+
+            match result {
                 Ok(response) => {
                     if idx > 0 {
                         tracing::info!(
@@ -151,6 +207,10 @@ impl Provider for FailoverProvider {
                         slot.failures.load(Ordering::Relaxed),
                         e
                     );
+                    // The caller already has part of this reply
+                    if emitted {
+                        return Err(e);
+                    }
                     last_error = Some(e);
                 }
             }
@@ -161,6 +221,84 @@ impl Provider for FailoverProvider {
         }))
     }
 
+    /// Get health status of all providers.
+    pub fn health_status(&self) -> Vec<(&str, bool, u32)> {
+        self.slots
+            .iter()
+            .map(|s| {
+                (
+                    s.provider.name(),
+                    s.is_healthy(),
+                    s.failures.load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Stream one attempt at a reply to `sink`. Also returns whether any text
+/// reached it.
+async fn stream_attempt(
+    provider: &dyn Provider,
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    params: &GenerateParams,
+    sink: &ChatChunkSink,
+) -> (Result<ProviderResponse>, bool) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let run = async move {
+        let result = provider.chat_stream(messages, tools, params, &tx).await;
+        drop(tx);
+        result
+    };
+    let forward = async {
+        let mut emitted = false;
+        while let Some(chunk) = rx.recv().await {
+            emitted |= !chunk.is_empty();
+            let _ = sink.send(chunk);
+        }
+        emitted
+    };
+    tokio::join!(run, forward)
+}
+
+#[async_trait]
+impl Provider for FailoverProvider {
+    fn name(&self) -> &str {
+        // Return primary provider name
+        self.slots
+            .first()
+            .map(|s| s.provider.name())
+            .unwrap_or("failover")
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+    ) -> Result<ProviderResponse> {
+        self.first_success(messages, tools, params, None).await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+        sink: &ChatChunkSink,
+    ) -> Result<ProviderResponse> {
+        self.first_success(messages, tools, params, Some(sink)).await
+    }
+
+    fn tokenizer(&self) -> std::sync::Arc<dyn Tokenizer> {
+        // Count as the primary provider does
+        match self.slots.first() {
+            Some(slot) => slot.provider.tokenizer(),
+            None => std::sync::Arc::new(ApproxTokenizer),
+        }
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         // Aggregate models from all healthy providers
         let mut all = Vec::new();
@@ -183,6 +321,15 @@ impl Provider for FailoverProvider {
         }
         Ok(false)
     }
+
+    fn supports_vision(&self) -> bool {
+        self.slots[0].provider.supports_vision()
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        // Primary only: vectors from different models are not comparable
+        self.slots[0].provider.embed(text).await
+    }
 }
 
 #[cfg(test)]
@@ -225,4 +372,204 @@ mod tests {
         failures.store(0, Ordering::Relaxed); // success reset
         assert!(is_healthy()); // back to 0
     }
+
+    struct Fixed(Option<&'static str>);
+
+    #[async_trait]
+    impl Provider for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            self.0
+                .map(ProviderResponse::text)
+                .ok_or_else(|| BizClawError::Provider("down".into()))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(self.0.is_some())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_fails_over() {
+        let chain = FailoverProvider::with_fallback(Box::new(Fixed(None)), Box::new(Fixed(Some("hi"))));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let response = chain
+            .chat_stream(&[Message::user("hello")], &[], &GenerateParams::default(), &tx)
+            .await
+            .unwrap();
+        assert_eq!(response.content.as_deref(), Some("hi"));
+        // Providers without incremental output send the reply as one chunk
+        drop(tx);
+        assert_eq!(rx.recv().await.as_deref(), Some("hi"));
+        assert_eq!(rx.recv().await, None);
+        assert_eq!(chain.health_status()[0].2, 1);
+    }
+
+    /// Fails with `error` for the first `failures` calls, then echoes the model.
+    struct Flaky {
+        failures: u32,
+        error: fn() -> BizClawError,
+        calls: std::sync::Arc<AtomicU32>,
+    }
+
+    impl Flaky {
+        fn boxed(failures: u32, error: fn() -> BizClawError) -> (Box<dyn Provider>, std::sync::Arc<AtomicU32>) {
+            let calls = std::sync::Arc::new(AtomicU32::new(0));
+            (Box::new(Self { failures, error, calls: calls.clone() }), calls)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            params: &GenerateParams,
+        ) -> Result<ProviderResponse> {
+            if self.calls.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Err((self.error)());
+            }
+            Ok(ProviderResponse::text(&params.model))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn fast_policy() -> FailoverConfig {
+        FailoverConfig { enabled: true, backoff_ms: 1, max_backoff_ms: 2, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_retried_on_same_provider() {
+        let (primary, calls) = Flaky::boxed(2, || BizClawError::Transient("503".into()));
+        let chain = FailoverProvider::with_fallback(primary, Box::new(Fixed(Some("fallback"))))
+            .with_policy(fast_policy());
+        let params = GenerateParams { model: "m".into(), ..Default::default() };
+
+        let response = chain.chat(&[Message::user("hi")], &[], &params).await.unwrap();
+        assert_eq!(response.content.as_deref(), Some("m"));
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(chain.health_status()[0].2, 0);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_after_threshold() {
+        let (primary, calls) = Flaky::boxed(u32::MAX, || BizClawError::AuthFailed("401".into()));
+        let chain = FailoverProvider::with_fallback(primary, Box::new(Fixed(Some("fallback"))))
+            .with_policy(FailoverConfig { failure_threshold: 2, ..fast_policy() });
+        let params = GenerateParams::default();
+
+        for _ in 0..3 {
+            let response = chain.chat(&[Message::user("hi")], &[], &params).await.unwrap();
+            assert_eq!(response.content.as_deref(), Some("fallback"));
+        }
+        // Auth errors aren't retried, and the open circuit skipped the third request.
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(!chain.health_status()[0].1);
+    }
+
+    /// Streams "Hel" and then fails.
+    struct CutOff;
+
+    #[async_trait]
+    impl Provider for CutOff {
+        fn name(&self) -> &str {
+            "cut-off"
+        }
+        async fn chat(&self, _: &[Message], _: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+            Err(BizClawError::Transient("reset".into()))
+        }
+        async fn chat_stream(
+            &self,
+            _: &[Message],
+            _: &[ToolDefinition],
+            _: &GenerateParams,
+            sink: &ChatChunkSink,
+        ) -> Result<ProviderResponse> {
+            let _ = sink.send("Hel".into());
+            Err(BizClawError::Transient("reset".into()))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_not_failed_over_after_output() {
+        let chain = FailoverProvider::with_fallback(Box::new(CutOff), Box::new(Fixed(Some("hi"))))
+            .with_policy(fast_policy());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = chain.chat_stream(&[Message::user("hello")], &[], &GenerateParams::default(), &tx).await;
+        assert!(result.is_err());
+        drop(tx);
+        assert_eq!(rx.recv().await.as_deref(), Some("Hel"));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    /// Named "brain"; replies with the number of tools it was offered.
+    struct Brain;
+
+    #[async_trait]
+    impl Provider for Brain {
+        fn name(&self) -> &str {
+            "brain"
+        }
+        async fn chat(&self, _: &[Message], tools: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+            Ok(ProviderResponse::text(tools.len().to_string()))
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_brain_fallback_gets_no_tools() {
+        let tools = [ToolDefinition {
+            name: "shell".into(),
+            description: String::new(),
+            parameters: serde_json::json!({}),
+        }];
+        let params = GenerateParams::default();
+        let chain = FailoverProvider::with_fallback(Box::new(Fixed(None)), Box::new(Brain));
+        let response = chain.chat(&[Message::user("hi")], &tools, &params).await.unwrap();
+        assert_eq!(response.content.as_deref(), Some("0"));
+
+        let alone = FailoverProvider::new(vec![Box::new(Brain)]);
+        let response = alone.chat(&[Message::user("hi")], &tools, &params).await.unwrap();
+        assert_eq!(response.content.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let chain = FailoverProvider::new(vec![Box::new(Fixed(Some("x")))]).with_policy(FailoverConfig {
+            backoff_ms: 500,
+            max_backoff_ms: 3000,
+            ..Default::default()
+        });
+        let delays: Vec<u128> = (0..4).map(|r| chain.backoff(r).as_millis()).collect();
+        assert_eq!(delays, [500, 1000, 2000, 3000]);
+    }
 }
@@ -9,11 +9,13 @@
 pub mod brain;
 pub mod failover;
 pub mod openai_compatible;
+pub mod privacy;
 pub mod provider_registry;
 
-use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::config::{BizClawConfig, FallbackProviderConfig};
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Provider;
+use bizclaw_core::types::ToolDefinition;
 
 /// Create a provider from configuration.
 ///
@@ -28,26 +30,80 @@ pub fn create_provider(config: &BizClawConfig) -> Result<Box<dyn Provider>> {
         config.default_provider.as_str()
     };
 
-    match provider_name {
-        // Local GGUF engine — not OpenAI-compatible
-        "brain" => Ok(Box::new(brain::BrainProvider::new(config)?)),
+    let provider = match provider_name {
+        // Local GGUF engine — not OpenAI-compatible, never filtered
+        "brain" => return Ok(Box::new(brain::BrainProvider::new(config)?)),
 
         // Custom endpoint: "custom:https://my-server.com/v1"
-        other if other.starts_with("custom:") => Ok(Box::new(
-            openai_compatible::OpenAiCompatibleProvider::custom(other, config)?,
-        )),
+        other if other.starts_with("custom:") => {
+            openai_compatible::OpenAiCompatibleProvider::custom(other, config)?
+        }
 
         // All known OpenAI-compatible providers
         _ => {
             let registry = provider_registry::get_provider_config(provider_name)
                 .ok_or_else(|| BizClawError::ProviderNotFound(provider_name.into()))?;
-            Ok(Box::new(
-                openai_compatible::OpenAiCompatibleProvider::from_registry(registry, config)?,
-            ))
+            openai_compatible::OpenAiCompatibleProvider::from_registry(registry, config)?
         }
+    };
+
+    // `[privacy]` keeps PII away from providers outside this network.
+    if config.privacy.enabled && !privacy::is_local_url(provider.base_url()) {
+        return Ok(Box::new(privacy::PrivacyProvider::new(Box::new(provider), &config.privacy)));
+    }
+    Ok(Box::new(provider))
+}
+
+/// Create the provider an agent talks to: with `[LLM.failover]` enabled, a
+/// [`failover::FailoverProvider`] over the primary and its fallbacks (if
+/// any), so even a lone primary gets retries and a circuit breaker;
+/// otherwise the primary alone.
+pub fn create_provider_chain(config: &BizClawConfig) -> Result<Box<dyn Provider>> {
+    if config.llm.failover.enabled {
+        Ok(Box::new(failover::FailoverProvider::from_config(config)?))
+    } else {
+        create_provider(config)
     }
 }
 
+/// Create a fallback provider, returning it with the model to request.
+///
+/// The fallback inherits the base config but never its credentials or
+/// endpoint — those belong to the primary provider.
+pub fn create_fallback_provider(
+    config: &BizClawConfig,
+    fallback: &FallbackProviderConfig,
+) -> Result<(Box<dyn Provider>, String)> {
+    let mut cfg = config.clone();
+    cfg.default_provider = fallback.provider.clone();
+    cfg.llm.provider = fallback.provider.clone();
+    cfg.api_key = fallback.api_key.clone();
+    cfg.llm.api_key = fallback.api_key.clone();
+    cfg.api_base_url = fallback.endpoint.clone();
+    cfg.llm.endpoint = fallback.endpoint.clone();
+    cfg.llm.fallback_providers.clear();
+
+    let model = if !fallback.model.is_empty() {
+        fallback.model.clone()
+    } else {
+        provider_registry::get_provider_config(&fallback.provider)
+            .and_then(|p| p.default_models.first())
+            .map(|m| m.id.to_string())
+            .unwrap_or_else(|| config.llm.model.clone())
+    };
+    cfg.default_model = model.clone();
+    cfg.llm.model = model.clone();
+
+    Ok((create_provider(&cfg)?, model))
+}
+
+/// Tools to offer `provider` when it stands in as a fallback: none for the
+/// local brain, whose small context can't hold tool schemas meant for a
+/// cloud model.
+pub fn fallback_tools<'a>(provider: &dyn Provider, tools: &'a [ToolDefinition]) -> &'a [ToolDefinition] {
+    if provider.name() == "brain" { &[] } else { tools }
+}
+
 /// List all available provider names.
 pub fn available_providers() -> Vec<&'static str> {
     let mut names = provider_registry::all_provider_names();
@@ -10,7 +10,7 @@ use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::provider::{GenerateParams, Provider};
 use bizclaw_core::types::{
-    FunctionCall, Message, ModelInfo, ProviderResponse, ToolCall, ToolDefinition, Usage,
+    FunctionCall, ImageInput, Message, ModelInfo, ProviderResponse, ToolCall, ToolDefinition, Usage,
 };
 use serde_json::{Value, json};
 
@@ -37,11 +37,17 @@ pub struct OpenAiCompatibleProvider {
 }
 
 impl OpenAiCompatibleProvider {
+    /// Base URL requests go to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     /// Create from a known provider config + BizClawConfig.
     ///
     /// Resolution order:
     /// - API key: `config.llm.api_key` > `config.api_key` > env vars > empty
-    /// - Base URL: `config.llm.endpoint` > env override > registry default
+    /// - Base URL: `config.llm.endpoint` > env override > managed llama-server
+    ///   (`llamacpp` only) > registry default
     pub fn from_registry(registry: &ProviderConfig, config: &BizClawConfig) -> Result<Self> {
         // Resolve API key: config.llm.api_key > config.api_key > env vars > empty
         let api_key = if !config.llm.api_key.is_empty() {
@@ -56,7 +62,7 @@ impl OpenAiCompatibleProvider {
                 .unwrap_or_default()
         };
 
-        // Resolve base URL: config.llm.endpoint > env override > registry default
+        // Resolve base URL: config.llm.endpoint > env override > managed server > registry default
         let base_url = if !config.llm.endpoint.is_empty() {
             config.llm.endpoint.clone()
         } else {
@@ -71,6 +77,11 @@ impl OpenAiCompatibleProvider {
                         Some(format!("{}/v1", val.trim_end_matches('/')))
                     }
                 })
+                .or_else(|| {
+                    (registry.name == "llamacpp")
+                        .then(bizclaw_brain::llamacpp::managed_base_url)
+                        .flatten()
+                })
                 .unwrap_or_else(|| registry.base_url.to_string())
         };
 
@@ -133,26 +144,19 @@ impl OpenAiCompatibleProvider {
             _ => req,
         }
     }
-}
 
-#[async_trait]
-impl Provider for OpenAiCompatibleProvider {
-    fn name(&self) -> &str {
-        &self.name
+    fn is_anthropic(&self) -> bool {
+        self.name == "anthropic" || self.base_url.contains("anthropic")
     }
 
-    async fn chat(
+    /// Build the chat completion request body.
+    fn build_body(
         &self,
         messages: &[Message],
         tools: &[ToolDefinition],
         params: &GenerateParams,
-    ) -> Result<ProviderResponse> {
-        // For providers that require auth, check API key
-        if self.auth_style != AuthStyle::None && self.api_key.is_empty() {
-            return Err(BizClawError::ApiKeyMissing(self.name.clone()));
-        }
-
-        let is_anthropic = self.name == "anthropic" || self.base_url.contains("anthropic");
+    ) -> Value {
+        let is_anthropic = self.is_anthropic();
 
         // Build request body — standard OpenAI format
         let mut body = json!({
@@ -178,7 +182,7 @@ impl Provider for OpenAiCompatibleProvider {
                         "cache_control": { "type": "ephemeral" }
                     }));
                 } else {
-                    non_system_msgs.push(serde_json::to_value(msg).unwrap_or_default());
+                    non_system_msgs.push(wire_message(msg, true));
                 }
             }
 
@@ -191,7 +195,8 @@ impl Provider for OpenAiCompatibleProvider {
                 "🧊 Anthropic prompt caching enabled (system blocks with cache_control)"
             );
         } else {
-            body["messages"] = serde_json::to_value(messages).unwrap_or_default();
+            body["messages"] =
+                Value::Array(messages.iter().map(|m| wire_message(m, false)).collect());
         }
 
         // Add tools if present
@@ -217,6 +222,87 @@ impl Provider for OpenAiCompatibleProvider {
             body["tools"] = Value::Array(tool_defs);
         }
 
+        // JSON mode; the schema itself travels in the prompt, since
+        // `json_object` is what OpenAI-compatible servers broadly accept
+        if params.response_schema.is_some() && !is_anthropic {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+
+        body
+    }
+}
+
+/// Serialize a message for the request body. Attached images become
+/// multimodal content parts: `image_url` for OpenAI-compatible APIs
+/// (Gemini's included — base64 is sent as a `data:` URI), or `image`
+/// blocks for Anthropic. Local files are inlined as base64; unreadable ones
+/// are left out.
+fn wire_message(msg: &Message, is_anthropic: bool) -> Value {
+    let mut value = serde_json::to_value(msg).unwrap_or_default();
+    if msg.images.is_empty() {
+        return value;
+    }
+    value.as_object_mut().map(|m| m.remove("images"));
+
+    let mut parts = Vec::with_capacity(msg.images.len() + 1);
+    if !msg.content.is_empty() {
+        parts.push(json!({"type": "text", "text": msg.content}));
+    }
+    for image in &msg.images {
+        let image = match image.inline() {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::warn!("⚠️ Skipping unreadable image {}: {e}", image.to_url());
+                continue;
+            }
+        };
+        parts.push(match (is_anthropic, &image) {
+            (false, _) => json!({"type": "image_url", "image_url": {"url": image.to_url()}}),
+            (true, ImageInput::Url { url }) => {
+                json!({"type": "image", "source": {"type": "url", "url": url}})
+            }
+            (true, ImageInput::Base64 { mime_type, data }) => json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": mime_type, "data": data}
+            }),
+            // Inlined above
+            (true, ImageInput::Path { .. }) => continue,
+        });
+    }
+    value["content"] = Value::Array(parts);
+    value
+}
+
+/// Classify a non-success HTTP status so callers can decide whether to fail over.
+fn status_error(name: &str, status: reqwest::StatusCode, text: &str) -> BizClawError {
+    let msg = format!("{name} API error {status}: {text}");
+    match status.as_u16() {
+        401 | 403 => BizClawError::AuthFailed(msg),
+        429 => BizClawError::RateLimited(msg),
+        408 | 500..=599 => BizClawError::Transient(msg),
+        _ => BizClawError::Provider(msg),
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+    ) -> Result<ProviderResponse> {
+        // For providers that require auth, check API key
+        if self.auth_style != AuthStyle::None && self.api_key.is_empty() {
+            return Err(BizClawError::ApiKeyMissing(self.name.clone()));
+        }
+
+        let mut body = self.build_body(messages, tools, params);
+
         // Send request
         let url = format!("{}{}", self.base_url, self.chat_path);
         let req = self
@@ -227,7 +313,7 @@ impl Provider for OpenAiCompatibleProvider {
         let req = self.apply_auth(req);
 
         let resp = req.send().await.map_err(|e| {
-            BizClawError::Http(format!("{} connection failed ({}): {}", self.name, url, e))
+            BizClawError::Transient(format!("{} connection failed ({}): {}", self.name, url, e))
         })?;
 
         if !resp.status().is_success() {
@@ -287,10 +373,7 @@ impl Provider for OpenAiCompatibleProvider {
                 });
             }
 
-            return Err(BizClawError::Provider(format!(
-                "{} API error {}: {}",
-                self.name, status, text
-            )));
+            return Err(status_error(&self.name, status, &text));
         }
 
         // Parse response — standard OpenAI format
@@ -386,4 +469,81 @@ impl Provider for OpenAiCompatibleProvider {
         let resp = self.client.get(&url).send().await;
         Ok(resp.is_ok())
     }
+
+    fn supports_vision(&self) -> bool {
+        // Image parts are sent on every endpoint; models without vision reject them
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider_registry::get_provider_config;
+
+    fn provider(name: &str) -> OpenAiCompatibleProvider {
+        let registry = get_provider_config(name).unwrap();
+        OpenAiCompatibleProvider::from_registry(registry, &BizClawConfig::default()).unwrap()
+    }
+
+    fn vision_messages() -> Vec<Message> {
+        vec![
+            Message::system("You describe images."),
+            Message::user_with_images(
+                "What is in this photo?",
+                vec![
+                    ImageInput::base64("image/jpeg", "/9j/4AAQ"),
+                    ImageInput::url("https://example.com/cat.png"),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_openai_image_parts() {
+        let body = provider("openai").build_body(&vision_messages(), &[], &GenerateParams::default());
+        let user = &body["messages"][1];
+        assert!(user.get("images").is_none());
+        assert_eq!(user["content"][0], json!({"type": "text", "text": "What is in this photo?"}));
+        assert_eq!(
+            user["content"][1],
+            json!({"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,/9j/4AAQ"}})
+        );
+        assert_eq!(user["content"][2]["image_url"]["url"], "https://example.com/cat.png");
+        // Text-only messages keep a plain string content.
+        assert_eq!(body["messages"][0]["content"], "You describe images.");
+    }
+
+    #[test]
+    fn test_gemini_image_parts() {
+        // Gemini is served through its OpenAI-compatible endpoint, which takes
+        // inline images as `data:` URIs in `image_url` parts.
+        let body = provider("gemini").build_body(&vision_messages(), &[], &GenerateParams::default());
+        let parts = body["messages"][1]["content"].as_array().unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[1]["type"], "image_url");
+        assert_eq!(parts[1]["image_url"]["url"], "data:image/jpeg;base64,/9j/4AAQ");
+    }
+
+    #[test]
+    fn test_unreadable_image_file_left_out() {
+        let messages = vec![Message::user_with_images("see", vec![ImageInput::path("/no/such/photo.jpg")])];
+        let body = provider("openai").build_body(&messages, &[], &GenerateParams::default());
+        assert_eq!(body["messages"][0]["content"], json!([{"type": "text", "text": "see"}]));
+    }
+
+    #[test]
+    fn test_anthropic_image_blocks() {
+        let body =
+            provider("anthropic").build_body(&vision_messages(), &[], &GenerateParams::default());
+        let user = &body["messages"][0];
+        assert_eq!(
+            user["content"][1],
+            json!({"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "/9j/4AAQ"}})
+        );
+        assert_eq!(
+            user["content"][2]["source"],
+            json!({"type": "url", "url": "https://example.com/cat.png"})
+        );
+    }
 }
@@ -0,0 +1,458 @@
+//! Privacy filter — keeps PII out of requests to cloud providers.
+//!
+//! [`PrivacyProvider`] wraps a cloud provider. Before each request every
+//! message is scanned for emails, phone numbers, ID numbers (Vietnamese
+//! CCCD/CMND and passports), tax codes (MST) and card numbers. In
+//! `pseudonymize` mode each value becomes a stable placeholder such as
+//! `[PHONE_1]`; the mapping lives only for that one request, and its reply,
+//! tool-call arguments and streamed text get the original values back. In `redact`
+//! mode values become `[PHONE]` and are never restored.
+
+use async_trait::async_trait;
+use bizclaw_core::config::PrivacyConfig;
+use bizclaw_core::error::Result;
+use bizclaw_core::traits::provider::{ChatChunkSink, GenerateParams, Provider};
+use bizclaw_core::traits::tokenizer::Tokenizer;
+use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+/// Longest placeholder a streamed chunk may end in the middle of.
+const MAX_PLACEHOLDER_LEN: usize = 24;
+
+/// One kind of PII: a pattern (its first group, when it has one, is the
+/// value to hide) and an optional check that weeds out false matches.
+struct Detector {
+    label: &'static str,
+    re: Regex,
+    check: Option<fn(&str) -> bool>,
+}
+
+/// Placeholders handed out for one request, both ways.
+#[derive(Default)]
+pub struct Vault {
+    by_value: HashMap<String, String>,
+    by_placeholder: HashMap<String, String>,
+    counts: HashMap<&'static str, usize>,
+}
+
+/// Finds PII in text and swaps it for placeholders.
+pub struct PiiFilter {
+    detectors: Vec<Detector>,
+    pseudonymize: bool,
+}
+
+impl PiiFilter {
+    pub fn new(config: &PrivacyConfig) -> Self {
+        let wants = |kind: &str| config.detect.iter().any(|k| k == kind);
+        let detector = |label, pattern: &str, check| Detector {
+            label,
+            re: Regex::new(pattern).expect("built-in PII pattern"),
+            check,
+        };
+        let mut detectors = Vec::new();
+        if wants("email") {
+            detectors.push(detector(
+                "EMAIL",
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b",
+                None,
+            ));
+        }
+        if wants("card") {
+            detectors.push(detector("CARD", r"\b\d(?:[ -]?\d){12,18}\b", Some(luhn_valid)));
+        }
+        if wants("id_number") {
+            // CCCD: 12 digits starting with a province code (001–096).
+            detectors.push(detector("ID", r"\b0\d{11}\b", Some(cccd_province_valid)));
+            // CMND (9 digits) or passport (letter + 7 digits) after a label.
+            detectors.push(detector(
+                "ID",
+                r"(?i)(?:cmnd|cmt|cccd|chứng minh nhân dân|căn cước|hộ chiếu|passport)\D{0,15}?\b(\d{9}|[a-z]\d{7})\b",
+                None,
+            ));
+        }
+        if wants("tax_code") {
+            detectors.push(detector(
+                "TAX_CODE",
+                r"(?i)(?:mst|mã số thuế|tax code|tax id)\D{0,15}?\b(\d{10}(?:-\d{3})?)\b",
+                None,
+            ));
+            detectors.push(detector("TAX_CODE", r"\b\d{10}-\d{3}\b", None));
+        }
+        if wants("phone") {
+            // Vietnamese mobile (0 + 9 digits) and landline (02x + 8 digits),
+            // with +84/84 prefixes and space, dot or dash separators.
+            detectors.push(detector(
+                "PHONE",
+                r"(?:\+84[ .-]?|\b84|\b0)(?:[35789]\d(?:[ .-]?\d){7}|2\d(?:[ .-]?\d){8})\b",
+                None,
+            ));
+            detectors.push(detector("PHONE", r"\+\d(?:[ .-]?\d){7,14}\b", None));
+        }
+        for pattern in &config.custom_patterns {
+            match Regex::new(pattern) {
+                Ok(re) => detectors.push(Detector { label: "PII", re, check: None }),
+                Err(e) => tracing::warn!("Privacy: ignoring invalid pattern '{pattern}': {e}"),
+            }
+        }
+        Self {
+            detectors,
+            pseudonymize: config.mode != "redact",
+        }
+    }
+
+    /// `text` with every detected value replaced by its placeholder,
+    /// recorded in `vault`.
+    pub fn redact(&self, text: &str, vault: &mut Vault) -> String {
+        let mut text = text.to_string();
+        for detector in &self.detectors {
+            if !detector.re.is_match(&text) {
+                continue;
+            }
+            text = detector
+                .re
+                .replace_all(&text, |caps: &Captures| {
+                    let whole = caps.get(0).expect("group 0");
+                    let value = caps.get(1).unwrap_or(whole);
+                    if detector.check.is_some_and(|check| !check(value.as_str())) {
+                        return whole.as_str().to_string();
+                    }
+                    let start = value.start() - whole.start();
+                    let end = value.end() - whole.start();
+                    let m = whole.as_str();
+                    format!("{}{}{}", &m[..start], self.placeholder(vault, detector.label, value.as_str()), &m[end..])
+                })
+                .into_owned();
+        }
+        text
+    }
+
+    /// `text` with the placeholders in `vault` swapped back for the values
+    /// they stand for.
+    pub fn rehydrate(&self, text: &str, vault: &Vault) -> String {
+        if !self.pseudonymize || !text.contains('[') {
+            return text.to_string();
+        }
+        let mut text = text.to_string();
+        for (placeholder, value) in &vault.by_placeholder {
+            if text.contains(placeholder.as_str()) {
+                text = text.replace(placeholder.as_str(), value);
+            }
+        }
+        text
+    }
+
+    /// Like [`PiiFilter::rehydrate`] for JSON tool arguments, so restored
+    /// values are escaped properly.
+    fn rehydrate_json(&self, arguments: &str, vault: &Vault) -> String {
+        if !self.pseudonymize || !arguments.contains('[') {
+            return arguments.to_string();
+        }
+        fn visit(filter: &PiiFilter, vault: &Vault, value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::String(s) => *s = filter.rehydrate(s, vault),
+                serde_json::Value::Array(items) => items.iter_mut().for_each(|v| visit(filter, vault, v)),
+                serde_json::Value::Object(map) => map.values_mut().for_each(|v| visit(filter, vault, v)),
+                _ => {}
+            }
+        }
+        match serde_json::from_str::<serde_json::Value>(arguments) {
+            Ok(mut value) => {
+                visit(self, vault, &mut value);
+                value.to_string()
+            }
+            Err(_) => self.rehydrate(arguments, vault),
+        }
+    }
+
+    fn placeholder(&self, vault: &mut Vault, label: &'static str, value: &str) -> String {
+        if !self.pseudonymize {
+            return format!("[{label}]");
+        }
+        if let Some(existing) = vault.by_value.get(value) {
+            return existing.clone();
+        }
+        let count = vault.counts.entry(label).or_default();
+        *count += 1;
+        let placeholder = format!("[{label}_{count}]");
+        vault.by_value.insert(value.to_string(), placeholder.clone());
+        vault.by_placeholder.insert(placeholder.clone(), value.to_string());
+        placeholder
+    }
+}
+
+/// Luhn checksum, so long order or account numbers aren't taken for cards.
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn cccd_province_valid(number: &str) -> bool {
+    number.get(..3).and_then(|p| p.parse::<u32>().ok()).is_some_and(|p| (1..=96).contains(&p))
+}
+
+/// Whether `url` points at this machine or a private network, where
+/// messages never leave the premises.
+pub fn is_local_url(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    }
+    .to_lowercase();
+    if host == "localhost" || host.ends_with(".localhost") || host.ends_with(".local") {
+        return true;
+    }
+    match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified(),
+        Ok(std::net::IpAddr::V6(ip)) => {
+            ip.is_loopback() || ip.is_unspecified() || (ip.segments()[0] & 0xfe00) == 0xfc00
+        }
+        Err(_) => false,
+    }
+}
+
+/// A provider whose requests pass through a [`PiiFilter`].
+pub struct PrivacyProvider {
+    inner: Box<dyn Provider>,
+    filter: PiiFilter,
+}
+
+impl PrivacyProvider {
+    pub fn new(inner: Box<dyn Provider>, config: &PrivacyConfig) -> Self {
+        Self {
+            inner,
+            filter: PiiFilter::new(config),
+        }
+    }
+
+    fn redact_messages(&self, messages: &[Message], vault: &mut Vault) -> Vec<Message> {
+        messages
+            .iter()
+            .map(|m| {
+                let mut m = m.clone();
+                m.content = self.filter.redact(&m.content, vault);
+                for call in m.tool_calls.iter_mut().flatten() {
+                    call.function.arguments = self.filter.redact(&call.function.arguments, vault);
+                }
+                m
+            })
+            .collect()
+    }
+
+    fn rehydrate_response(&self, mut response: ProviderResponse, vault: &Vault) -> ProviderResponse {
+        response.content = response.content.map(|c| self.filter.rehydrate(&c, vault));
+        for call in &mut response.tool_calls {
+            call.function.arguments = self.filter.rehydrate_json(&call.function.arguments, vault);
+        }
+        response
+    }
+}
+
+#[async_trait]
+impl Provider for PrivacyProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+    ) -> Result<ProviderResponse> {
+        let mut vault = Vault::default();
+        let messages = self.redact_messages(messages, &mut vault);
+        let response = self.inner.chat(&messages, tools, params).await?;
+        Ok(self.rehydrate_response(response, &vault))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+        sink: &ChatChunkSink,
+    ) -> Result<ProviderResponse> {
+        let mut vault = Vault::default();
+        let messages = self.redact_messages(messages, &mut vault);
+        let vault = &vault;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let messages = &messages;
+        let run = async move {
+            let result = self.inner.chat_stream(messages, tools, params, &tx).await;
+            drop(tx);
+            result
+        };
+        // Hold back a trailing `[…` until we know whether it is a placeholder.
+        let forward = async {
+            let mut pending = String::new();
+            while let Some(chunk) = rx.recv().await {
+                pending.push_str(&chunk);
+                let cut = match pending.rfind('[') {
+                    Some(open) if !pending[open..].contains(']') && pending.len() - open < MAX_PLACEHOLDER_LEN => open,
+                    _ => pending.len(),
+                };
+                if cut > 0 {
+                    let _ = sink.send(self.filter.rehydrate(&pending[..cut], vault));
+                    pending.drain(..cut);
+                }
+            }
+            if !pending.is_empty() {
+                let _ = sink.send(self.filter.rehydrate(&pending, vault));
+            }
+        };
+        let (result, ()) = tokio::join!(run, forward);
+        Ok(self.rehydrate_response(result?, vault))
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.inner.list_models().await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    fn tokenizer(&self) -> std::sync::Arc<dyn Tokenizer> {
+        self.inner.tokenizer()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.inner.embed(&self.filter.redact(text, &mut Vault::default())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::{FunctionCall, ToolCall};
+
+    fn filter(mode: &str) -> PiiFilter {
+        PiiFilter::new(&PrivacyConfig {
+            enabled: true,
+            mode: mode.into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_detects_vietnamese_pii() {
+        let f = filter("pseudonymize");
+        let text = "Anh Nam, email nam.nguyen@congty.vn, SĐT 0912 345 678 hoặc +84 28 3822 1234, \
+                    CCCD 001203004567, CMND: 123456789, MST 0101234567-001, thẻ 4111 1111 1111 1111.";
+        let mut vault = Vault::default();
+        let redacted = f.redact(text, &mut vault);
+        for value in [
+            "nam.nguyen@congty.vn",
+            "0912 345 678",
+            "28 3822 1234",
+            "001203004567",
+            "123456789",
+            "0101234567-001",
+            "4111 1111 1111 1111",
+        ] {
+            assert!(!redacted.contains(value), "{value} leaked: {redacted}");
+        }
+        assert!(redacted.contains("[EMAIL_1]") && redacted.contains("[PHONE_2]"), "{redacted}");
+        assert!(redacted.contains("CMND: [ID_2]"), "{redacted}");
+        assert_eq!(f.rehydrate(&redacted, &vault), text);
+        assert_eq!(f.rehydrate(&redacted, &Vault::default()), redacted);
+    }
+
+    #[test]
+    fn test_leaves_ordinary_numbers() {
+        let f = filter("pseudonymize");
+        let text = "Đơn hàng 1234567890123 trị giá 2500000 đồng, mã 999203004567.";
+        assert_eq!(f.redact(text, &mut Vault::default()), text);
+    }
+
+    #[test]
+    fn test_redact_mode_is_irreversible() {
+        let f = filter("redact");
+        let mut vault = Vault::default();
+        let redacted = f.redact("Gọi 0987654321 hoặc 0987654321", &mut vault);
+        assert_eq!(redacted, "Gọi [PHONE] hoặc [PHONE]");
+        assert_eq!(f.rehydrate(&redacted, &vault), redacted);
+    }
+
+    #[test]
+    fn test_is_local_url() {
+        for url in ["http://localhost:11434/v1", "http://127.0.0.1:8080", "http://192.168.1.5/v1", "http://[::1]:8000", "http://gpu.local/v1"] {
+            assert!(is_local_url(url), "{url}");
+        }
+        for url in ["https://api.openai.com/v1", "https://user@8.8.8.8/v1", "https://localhost.example.com"] {
+            assert!(!is_local_url(url), "{url}");
+        }
+    }
+
+    /// Replies with the last message, calls a tool with it, and records
+    /// what it was sent.
+    struct Echo(std::sync::Arc<std::sync::Mutex<Vec<Message>>>);
+
+    #[async_trait]
+    impl Provider for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+        async fn chat(&self, messages: &[Message], _: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+            *self.0.lock().unwrap() = messages.to_vec();
+            let last = messages.last().map(|m| m.content.clone()).unwrap_or_default();
+            let mut response = ProviderResponse::text(format!("Đã nhận: {last}"));
+            response.tool_calls = vec![ToolCall {
+                id: "c1".into(),
+                r#type: "function".into(),
+                function: FunctionCall {
+                    name: "send_email".into(),
+                    arguments: serde_json::json!({ "to": last }).to_string(),
+                },
+            }];
+            Ok(response)
+        }
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![])
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_redacts_and_rehydrates() {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let provider = PrivacyProvider::new(Box::new(Echo(seen.clone())), &PrivacyConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        let messages = [Message::user("lan@example.com")];
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let response = provider.chat_stream(&messages, &[], &GenerateParams::default(), &tx).await.unwrap();
+        drop(tx);
+
+        assert_eq!(seen.lock().unwrap()[0].content, "[EMAIL_1]");
+        assert_eq!(response.content.as_deref(), Some("Đã nhận: lan@example.com"));
+        assert_eq!(response.tool_calls[0].function.arguments, r#"{"to":"lan@example.com"}"#);
+        let mut streamed = String::new();
+        while let Some(chunk) = rx.recv().await {
+            streamed.push_str(&chunk);
+        }
+        assert_eq!(streamed, "Đã nhận: lan@example.com");
+
+        // Another request's placeholders aren't this one's to restore.
+        let response = provider.chat(&[Message::user("[EMAIL_1]")], &[], &GenerateParams::default()).await.unwrap();
+        assert_eq!(response.content.as_deref(), Some("Đã nhận: [EMAIL_1]"));
+    }
+}
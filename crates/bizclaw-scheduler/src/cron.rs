@@ -1,11 +1,11 @@
 //! Lightweight cron expression parser.
 //! Supports: "MIN HOUR DOM MON DOW" (5-field, no seconds)
-//! Wildcards: *, */N, N
-//! Example: "0 8 * * *" = every day at 8:00
+//! Wildcards: *, */N, N, N-M, N,M
+//! Example: "0 8 * * *" = every day at 8:00, "0 9 * * 1-5" = weekdays at 9:00
 //!
 //! Designed for simplicity — no cron crate dependency.
 
-use chrono::{DateTime, Duration, Timelike, Utc};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
 
 /// Parse a simple cron expression and compute the next run time.
 pub fn next_run_from_cron(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
@@ -22,7 +22,8 @@ pub fn next_run_from_cron(expression: &str, after: DateTime<Utc>) -> Option<Date
     let hour_spec = parts[1];
     let _dom_spec = parts[2]; // Day of month (simplified: only * supported)
     let _mon_spec = parts[3]; // Month (simplified: only * supported)
-    let _dow_spec = parts[4]; // Day of week (simplified: only * supported)
+    // Day of week: 0-6 from Sunday, 7 is Sunday too
+    let days: Vec<u32> = parse_field(parts[4], 0, 7)?.into_iter().map(|d| d % 7).collect();
 
     // Parse minute
     let minutes = parse_field(minute_spec, 0, 59)?;
@@ -33,12 +34,13 @@ pub fn next_run_from_cron(expression: &str, after: DateTime<Utc>) -> Option<Date
     // Zero out seconds
     candidate = candidate.with_second(0).unwrap_or(candidate);
 
-    // Try up to 48 hours ahead
-    for _ in 0..(48 * 60) {
+    // Try up to 8 days ahead (weekly schedules)
+    for _ in 0..(8 * 24 * 60) {
         let m = candidate.minute();
         let h = candidate.hour();
+        let d = candidate.weekday().num_days_from_sunday();
 
-        if minutes.contains(&m) && hours.contains(&h) {
+        if minutes.contains(&m) && hours.contains(&h) && days.contains(&d) {
             return Some(candidate);
         }
         candidate += Duration::minutes(1);
@@ -70,6 +72,15 @@ fn parse_field(field: &str, min: u32, max: u32) -> Option<Vec<u32>> {
             .map(|v| v.into_iter().filter(|x| *x >= min && *x <= max).collect());
     }
 
+    // Range: "1-5"
+    if let Some((from, to)) = field.split_once('-') {
+        let (from, to): (u32, u32) = (from.trim().parse().ok()?, to.trim().parse().ok()?);
+        if from > to || from < min || to > max {
+            return None;
+        }
+        return Some((from..=to).collect());
+    }
+
     // Single number
     let n: u32 = field.parse().ok()?;
     if n >= min && n <= max {
@@ -107,6 +118,19 @@ mod tests {
         assert_eq!(next.minute(), 15);
     }
 
+    #[test]
+    fn test_day_of_week() {
+        // 2026-02-22 is a Sunday
+        let after = Utc.with_ymd_and_hms(2026, 2, 22, 10, 0, 0).unwrap();
+        let next = next_run_from_cron("0 9 * * 1", after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 2, 23, 9, 0, 0).unwrap());
+        let next = next_run_from_cron("30 8 * * 6,7", after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 2, 28, 8, 30, 0).unwrap());
+        let friday = Utc.with_ymd_and_hms(2026, 2, 27, 18, 0, 0).unwrap();
+        let next = next_run_from_cron("0 9 * * 1-5", friday).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap());
+    }
+
     #[test]
     fn test_invalid_expression() {
         let after = Utc::now();
@@ -1,7 +1,25 @@
 //! Notification dispatch — actually sends notifications to configured channels.
-//! Supports: Telegram Bot API, Discord Webhook, HTTP Webhook, Dashboard WebSocket.
+//! Supports: Telegram Bot API, Discord Webhook, WhatsApp Cloud API, Twilio SMS,
+//! HTTP Webhook, Dashboard WebSocket.
+//!
+//! Notifications queue in the scheduler database (see
+//! [`SchedulerEngine::notify`]). The dispatcher loop sends due ones; failed
+//! sends are retried with exponential backoff, and once out of attempts they
+//! move to the dead-letter queue until replayed.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use bizclaw_channels::sms::TwilioClient;
+use bizclaw_channels::whatsapp_client::{Template, WhatsAppClient};
+use tokio::sync::Mutex;
 
 use super::notify::{NotifyPriority, Notification};
+use crate::engine::SchedulerEngine;
+use crate::tasks::RetryPolicy;
+
+/// Queued notifications sent per dispatcher pass.
+const BATCH_SIZE: usize = 50;
 
 /// Notification target configuration.
 #[derive(Debug, Clone)]
@@ -15,12 +33,30 @@ pub enum NotifyTarget {
     Discord {
         webhook_url: String,
     },
+    /// WhatsApp Cloud API. With a `template`, sent as that approved
+    /// template (title and body as its parameters), which reaches users
+    /// outside the 24-hour window; otherwise as plain text.
+    WhatsApp {
+        access_token: String,
+        phone_number_id: String,
+        to: String,
+        template: Option<String>,
+        language: String,
+    },
+    /// SMS via Twilio, as plain text.
+    Sms {
+        account_sid: String,
+        auth_token: String,
+        from: String,
+        to: String,
+    },
     /// Generic HTTP webhook — POST with JSON body.
     Webhook {
         url: String,
         headers: Vec<(String, String)>,
     },
-    /// Dashboard WebSocket broadcast (handled at gateway level).
+    /// Dashboard WebSocket broadcast: [`SchedulerEngine::notify`] publishes
+    /// an `Event::NotificationSent` that the gateway pushes over `/ws`.
     Dashboard,
 }
 
@@ -34,12 +70,19 @@ pub async fn dispatch(notification: &Notification, target: &NotifyTarget) -> Res
         NotifyTarget::Discord { webhook_url } => {
             send_discord(webhook_url, notification).await
         }
+        NotifyTarget::WhatsApp { access_token, phone_number_id, to, template, language } => {
+            let client = WhatsAppClient::new(access_token, phone_number_id);
+            send_whatsapp(&client, to, template.as_deref(), language, notification).await
+        }
+        NotifyTarget::Sms { account_sid, auth_token, from, to } => {
+            let client = TwilioClient::new(account_sid, auth_token, from);
+            send_sms(&client, to, notification).await
+        }
         NotifyTarget::Webhook { url, headers } => {
             send_webhook(url, headers, notification).await
         }
         NotifyTarget::Dashboard => {
-            // Dashboard notifications are handled at gateway level (WebSocket broadcast).
-            // This is just a marker — the gateway intercepts this.
+            // Published on the event bus when queued; nothing to send here.
             tracing::debug!("📊 Dashboard notification recorded: {}", notification.title);
             Ok(())
         }
@@ -125,6 +168,41 @@ async fn send_discord(webhook_url: &str, notification: &Notification) -> Result<
     }
 }
 
+/// Send notification via WhatsApp, as a template message when one is set.
+async fn send_whatsapp(
+    client: &WhatsAppClient,
+    to: &str,
+    template: Option<&str>,
+    language: &str,
+    notification: &Notification,
+) -> Result<(), String> {
+    let sent = match template {
+        Some(name) => {
+            let template = Template {
+                name: name.to_string(),
+                language: language.to_string(),
+                body_params: vec![notification.title.clone(), notification.body.clone()],
+            };
+            client.send_template(to, &template).await
+        }
+        None => {
+            let text = format!("*{}*\n\n{}\n\n_Source: {}_", notification.title, notification.body, notification.source);
+            client.send_text(to, &text, None).await
+        }
+    };
+    sent.map_err(|e| format!("WhatsApp send failed: {e}"))?;
+    tracing::info!("✅ WhatsApp notification sent: {}", notification.title);
+    Ok(())
+}
+
+/// Send notification as a text message.
+async fn send_sms(client: &TwilioClient, to: &str, notification: &Notification) -> Result<(), String> {
+    let text = format!("{}\n{}", notification.title, notification.body);
+    client.send_sms(to, &text).await.map_err(|e| format!("SMS send failed: {e}"))?;
+    tracing::info!("✅ SMS notification sent: {}", notification.title);
+    Ok(())
+}
+
 /// Send notification via generic HTTP webhook.
 async fn send_webhook(
     url: &str,
@@ -183,6 +261,77 @@ pub async fn dispatch_all(
     results
 }
 
+/// Retries for failed deliveries: 1, 2, 4, 8, 16 minutes apart, then the
+/// notification is dead-lettered.
+pub fn delivery_retry() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 5,
+        base_delay_secs: 60,
+        backoff_multiplier: 2.0,
+        max_delay_secs: 3600,
+    }
+}
+
+/// Send every due queued notification once. Failures are rescheduled per
+/// `retry`, or dead-lettered when it allows no more. Returns how many were sent.
+pub async fn deliver_due(engine: &Mutex<SchedulerEngine>, retry: &RetryPolicy) -> usize {
+    let (due, targets) = {
+        let engine = engine.lock().await;
+        let Some(db) = engine.db() else {
+            return 0;
+        };
+        (db.due_notifications(Utc::now(), BATCH_SIZE), engine.notify_targets().to_vec())
+    };
+
+    let mut sent = 0;
+    // Send without holding the scheduler lock
+    for queued in due {
+        let result = match targets.iter().find(|(name, _)| *name == queued.channel) {
+            Some((_, target)) => dispatch(&queued.notification, target).await,
+            None => Err(format!("No '{}' notification target configured", queued.channel)),
+        };
+        let engine = engine.lock().await;
+        let Some(db) = engine.db() else {
+            break;
+        };
+        let recorded = match &result {
+            Ok(()) => {
+                sent += 1;
+                db.mark_notification_sent(queued.id, &queued.channel)
+            }
+            Err(e) => {
+                let retry_at = retry
+                    .next_delay(queued.attempts)
+                    .map(|secs| Utc::now() + chrono::Duration::seconds(secs as i64));
+                if retry_at.is_none() {
+                    tracing::warn!(
+                        "📭 Notification '{}' to {} dead-lettered after {} attempts: {e}",
+                        queued.notification.title,
+                        queued.channel,
+                        queued.attempts + 1
+                    );
+                }
+                db.mark_notification_failed(queued.id, e, retry_at)
+            }
+        };
+        if let Err(e) = recorded {
+            tracing::warn!("⚠️ Failed to update notification {}: {e}", queued.id);
+        }
+    }
+    sent
+}
+
+/// Deliver queued notifications every `check_interval_secs`, retrying
+/// failures with [`delivery_retry`].
+pub async fn spawn_dispatcher(engine: Arc<Mutex<SchedulerEngine>>, check_interval_secs: u64) {
+    let retry = delivery_retry();
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(check_interval_secs));
+    loop {
+        interval.tick().await;
+        deliver_due(&engine, &retry).await;
+    }
+}
+
 /// Build NotifyTargets from BizClaw channel config.
 /// Called at server init to configure notification dispatch.
 pub fn targets_from_config(config: &bizclaw_core::config::BizClawConfig) -> Vec<(String, NotifyTarget)> {
@@ -203,6 +352,29 @@ pub fn targets_from_config(config: &bizclaw_core::config::BizClawConfig) -> Vec<
             }
         }
 
+    // WhatsApp
+    if let Some(wa) = &config.channel.whatsapp
+        && wa.enabled && !wa.access_token.is_empty() && !wa.notify_to.is_empty() {
+            targets.push(("whatsapp".to_string(), NotifyTarget::WhatsApp {
+                access_token: wa.access_token.clone(),
+                phone_number_id: wa.phone_number_id.clone(),
+                to: wa.notify_to.clone(),
+                template: (!wa.notify_template.is_empty()).then(|| wa.notify_template.clone()),
+                language: wa.notify_template_language.clone(),
+            }));
+        }
+
+    // SMS
+    if let Some(sms) = &config.channel.sms
+        && sms.enabled && !sms.account_sid.is_empty() && !sms.notify_to.is_empty() {
+            targets.push(("sms".to_string(), NotifyTarget::Sms {
+                account_sid: sms.account_sid.clone(),
+                auth_token: sms.auth_token.clone(),
+                from: sms.from_number.clone(),
+                to: sms.notify_to.clone(),
+            }));
+        }
+
     // Webhook
     if let Some(wh) = &config.channel.webhook
         && wh.enabled && !wh.outbound_url.is_empty() {
@@ -217,3 +389,103 @@ pub fn targets_from_config(config: &bizclaw_core::config::BizClawConfig) -> Vec<
 
     targets
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::NotifyRouter;
+
+    #[tokio::test]
+    async fn test_failed_delivery_retries_then_dead_letters() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-dispatch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let engine = Mutex::new(SchedulerEngine::new(&dir));
+        {
+            let mut eng = engine.lock().await;
+            eng.set_notify_targets(vec![
+                // Nothing listens on the discard port
+                ("webhook".into(), NotifyTarget::Webhook { url: "http://127.0.0.1:9/hook".into(), headers: vec![] }),
+                ("dashboard".into(), NotifyTarget::Dashboard),
+            ]);
+            eng.notify(NotifyRouter::create("Backup", "Backup failed", "test", NotifyPriority::Urgent));
+            assert_eq!(eng.router.history().len(), 1);
+        }
+
+        // First failure is rescheduled with backoff
+        assert_eq!(deliver_due(&engine, &delivery_retry()).await, 0);
+        let id = {
+            let eng = engine.lock().await;
+            let db = eng.db().unwrap();
+            assert!(db.due_notifications(Utc::now(), 10).is_empty());
+            assert!(db.dead_letters(10).is_empty());
+            let later = db.due_notifications(Utc::now() + chrono::Duration::minutes(2), 10);
+            assert_eq!(later.len(), 1);
+            assert_eq!(later[0].attempts, 1);
+            assert_eq!(later[0].notification.priority, NotifyPriority::Urgent);
+            later[0].id
+        };
+
+        // Out of attempts: dead-lettered, then replayed
+        engine.lock().await.db().unwrap().mark_notification_failed(id, "down", Some(Utc::now())).unwrap();
+        assert_eq!(deliver_due(&engine, &RetryPolicy::none()).await, 0);
+        let eng = engine.lock().await;
+        let db = eng.db().unwrap();
+        let dead = db.dead_letters(10);
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].channel, "webhook");
+        assert_eq!(dead[0].attempts, 3);
+        assert!(dead[0].last_error.as_deref().unwrap().contains("Webhook"));
+        assert!(db.replay_dead_letter(dead[0].id).unwrap());
+        assert!(!db.replay_dead_letter(dead[0].id).unwrap());
+        assert_eq!(db.due_notifications(Utc::now(), 10)[0].attempts, 0);
+        drop(eng);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_whatsapp_target_from_config() {
+        let mut config = bizclaw_core::config::BizClawConfig::default();
+        let whatsapp: bizclaw_core::config::WhatsAppChannelConfig = toml::from_str(
+            r#"
+enabled = true
+access_token = "EAAG"
+phone_number_id = "1055"
+notify_to = "84901234567"
+notify_template = "task_alert"
+"#,
+        )
+        .unwrap();
+        config.channel.whatsapp = Some(whatsapp);
+        let targets = targets_from_config(&config);
+        let Some((_, NotifyTarget::WhatsApp { to, template, language, .. })) =
+            targets.iter().find(|(name, _)| name == "whatsapp")
+        else {
+            panic!("no whatsapp target");
+        };
+        assert_eq!(to, "84901234567");
+        assert_eq!(template.as_deref(), Some("task_alert"));
+        assert_eq!(language, "en_US");
+
+        config.channel.whatsapp.as_mut().unwrap().notify_to.clear();
+        assert!(!targets_from_config(&config).iter().any(|(name, _)| name == "whatsapp"));
+    }
+
+    #[test]
+    fn test_sms_target_from_config() {
+        let mut config = bizclaw_core::config::BizClawConfig::default();
+        config.channel.sms = Some(bizclaw_core::config::SmsChannelConfig {
+            enabled: true,
+            account_sid: "AC123".into(),
+            auth_token: "secret".into(),
+            from_number: "+15005550006".into(),
+            notify_to: "+84901234567".into(),
+            ..Default::default()
+        });
+        let targets = targets_from_config(&config);
+        let Some((_, NotifyTarget::Sms { from, to, .. })) = targets.iter().find(|(name, _)| name == "sms") else {
+            panic!("no sms target");
+        };
+        assert_eq!(from, "+15005550006");
+        assert_eq!(to, "+84901234567");
+    }
+}
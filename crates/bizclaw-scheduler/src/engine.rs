@@ -13,18 +13,28 @@
 use std::path::Path;
 use std::sync::Arc;
 
+use bizclaw_core::events::{Event, EventBus};
 use chrono::Utc;
 use tokio::sync::Mutex;
 
 use crate::cron;
-use crate::notify::{NotifyPriority, NotifyRouter};
+use crate::dispatch::NotifyTarget;
+use crate::flow::{Workflow, WorkflowRun};
+use crate::notify::{Notification, NotifyPriority, NotifyRouter};
+use crate::persistence::{SchedulerDb, TaskRun};
 use crate::store::TaskStore;
-use crate::tasks::{Task, TaskAction, TaskStatus, TaskType};
+use crate::tasks::{Task, TaskAction, TaskStatus, TaskType, next_aligned_run};
 
 /// The scheduler engine — manages tasks and triggers them.
 pub struct SchedulerEngine {
     tasks: Vec<Task>,
     store: TaskStore,
+    /// Run history and workflows (`scheduler.db` next to the task store).
+    db: Option<SchedulerDb>,
+    /// Where finished tasks and workflow runs are announced.
+    events: Option<EventBus>,
+    /// Channels notifications are delivered to, by name.
+    notify_targets: Vec<(String, NotifyTarget)>,
     pub router: NotifyRouter,
     /// Callback: triggered when a task fires. Returns the notification body.
     /// In practice, this sends a prompt to the Agent or fires a webhook.
@@ -37,14 +47,21 @@ impl SchedulerEngine {
     pub fn new(store_dir: &Path) -> Self {
         let store = TaskStore::new(store_dir);
         let tasks = store.load();
+        let db = SchedulerDb::open(&store_dir.join("scheduler.db"))
+            .inspect_err(|e| tracing::warn!("⚠️ Scheduler database unavailable: {e}"))
+            .ok();
         let mut engine = Self {
             tasks,
             store,
+            db,
+            events: None,
+            notify_targets: Vec::new(),
             router: NotifyRouter::new(),
             on_trigger: None,
         };
         // Compute next_run for all cron tasks
         engine.recompute_cron_times();
+        engine.arm_reboot_tasks();
         engine
     }
 
@@ -61,6 +78,63 @@ impl SchedulerEngine {
         self.on_trigger = Some(Arc::new(f));
     }
 
+    /// Publish finished tasks and workflow runs to `bus`.
+    pub fn set_event_bus(&mut self, bus: EventBus) {
+        self.events = Some(bus);
+    }
+
+    /// The bus events are published to, if set.
+    pub fn event_bus(&self) -> Option<&EventBus> {
+        self.events.as_ref()
+    }
+
+    fn publish(&self, event: Event) {
+        if let Some(bus) = &self.events {
+            bus.publish(event);
+        }
+    }
+
+    /// Deliver notifications to `targets` (see [`crate::dispatch::targets_from_config`]).
+    pub fn set_notify_targets(&mut self, targets: Vec<(String, NotifyTarget)>) {
+        self.notify_targets = targets;
+    }
+
+    /// Channels notifications are delivered to.
+    pub fn notify_targets(&self) -> &[(String, NotifyTarget)] {
+        &self.notify_targets
+    }
+
+    /// Record `notification` and queue it for delivery to every target.
+    /// The dashboard isn't queued: it gets an `Event::NotificationSent`
+    /// on the event bus instead.
+    pub fn notify(&mut self, notification: Notification) {
+        if let Some(db) = &self.db {
+            for (name, target) in &self.notify_targets {
+                if matches!(target, NotifyTarget::Dashboard) {
+                    continue;
+                }
+                if let Err(e) = db.save_notification(
+                    &notification.title,
+                    &notification.body,
+                    notification.priority.as_str(),
+                    &notification.source,
+                    Some(name),
+                ) {
+                    tracing::warn!("⚠️ Failed to queue notification for {name}: {e}");
+                }
+            }
+        }
+        if self.notify_targets.iter().any(|(_, t)| matches!(t, NotifyTarget::Dashboard)) {
+            self.publish(Event::NotificationSent {
+                title: notification.title.clone(),
+                body: notification.body.clone(),
+                priority: notification.priority.as_str().to_string(),
+                source: notification.source.clone(),
+            });
+        }
+        self.router.record(notification);
+    }
+
     /// Add a new task.
     pub fn add_task(&mut self, task: Task) {
         tracing::info!("📅 Task added: '{}' ({})", task.name, task.id);
@@ -74,6 +148,11 @@ impl SchedulerEngine {
         let len = self.tasks.len();
         self.tasks.retain(|t| t.id != id);
         if self.tasks.len() < len {
+            if let Some(db) = &self.db
+                && let Err(e) = db.delete_task_runs(id)
+            {
+                tracing::warn!("⚠️ Failed to delete run history of task {id}: {e}");
+            }
             self.save();
             true
         } else {
@@ -86,6 +165,58 @@ impl SchedulerEngine {
         &self.tasks
     }
 
+    /// Record that task `id` ran from `started_at` until now.
+    pub fn record_run(
+        &self,
+        id: &str,
+        started_at: chrono::DateTime<Utc>,
+        attempt: u32,
+        result: &Result<String, String>,
+    ) {
+        if let Some(db) = &self.db
+            && let Err(e) = db.record_run(id, started_at, Utc::now(), attempt, result)
+        {
+            tracing::warn!("⚠️ Failed to record run of task {id}: {e}");
+        }
+    }
+
+    /// Most recent runs of task `id`, newest first.
+    pub fn task_runs(&self, id: &str, limit: usize) -> Vec<TaskRun> {
+        self.db.as_ref().map(|db| db.task_runs(id, limit)).unwrap_or_default()
+    }
+
+    /// Deliver the notifications of a finished `run` of `workflow`, record
+    /// the run (stored workflows only) and announce it.
+    pub fn finish_workflow_run(&mut self, workflow: &Workflow, run: &mut WorkflowRun) {
+        for step in run.steps.iter().filter(|s| s.kind == "notify" && s.status == "success") {
+            let notification = NotifyRouter::create(
+                &workflow.name,
+                step.output.as_deref().unwrap_or(""),
+                "workflow",
+                NotifyPriority::Normal,
+            );
+            self.notify(notification);
+        }
+        if !workflow.id.is_empty()
+            && let Some(db) = &self.db
+        {
+            match db.record_workflow_run(run) {
+                Ok(run_id) => run.id = run_id,
+                Err(e) => tracing::warn!("⚠️ Failed to record run of workflow '{}': {e}", workflow.id),
+            }
+        }
+        self.publish(Event::WorkflowFinished {
+            workflow_id: workflow.id.clone(),
+            workflow: workflow.name.clone(),
+            status: run.status.clone(),
+        });
+    }
+
+    /// The scheduler database (run history, workflows), if it opened.
+    pub fn db(&self) -> Option<&SchedulerDb> {
+        self.db.as_ref()
+    }
+
     /// Get mutable access to tasks (for retry status updates).
     pub fn tasks_mut(&mut self) -> &mut Vec<Task> {
         &mut self.tasks
@@ -114,6 +245,7 @@ impl SchedulerEngine {
     /// Now also handles RetryPending tasks whose retry_at has elapsed.
     pub fn tick(&mut self) -> Vec<(String, String)> {
         let mut triggered = Vec::new();
+        let mut reminders = Vec::new();
         let now = Utc::now();
 
         for task in self.tasks.iter_mut() {
@@ -149,10 +281,15 @@ impl SchedulerEngine {
                 }
             };
 
-            // Record notification
+            // Reminders are delivered to the notification channels; other
+            // actions only show in the history
             let notification =
                 NotifyRouter::create(&task.name, &body, "scheduler", NotifyPriority::Normal);
-            self.router.record(notification);
+            if matches!(task.action, TaskAction::Notify(_)) {
+                reminders.push(notification);
+            } else {
+                self.router.record(notification);
+            }
 
             triggered.push((task.name.clone(), body));
             task.status = TaskStatus::Completed;
@@ -164,8 +301,16 @@ impl SchedulerEngine {
                     task.status = TaskStatus::Disabled;
                     task.next_run = None;
                 }
-                TaskType::Interval { every_secs } => {
-                    task.next_run = Some(now + chrono::Duration::seconds(*every_secs as i64));
+                TaskType::Interval { every_secs, anchor } => {
+                    task.next_run = Some(match anchor {
+                        Some(anchor) => next_aligned_run(*anchor, *every_secs, now),
+                        None => now + chrono::Duration::seconds(*every_secs as i64),
+                    });
+                    task.status = TaskStatus::Pending;
+                }
+                TaskType::Reboot => {
+                    // Stays enabled; re-armed on the next engine startup.
+                    task.next_run = None;
                     task.status = TaskStatus::Pending;
                 }
                 TaskType::Cron { expression } => {
@@ -175,6 +320,9 @@ impl SchedulerEngine {
             }
         }
 
+        for notification in reminders {
+            self.notify(notification);
+        }
         if !triggered.is_empty() {
             self.save();
         }
@@ -194,6 +342,16 @@ impl SchedulerEngine {
         }
     }
 
+    /// Schedule every enabled `@reboot` task to fire on the first tick.
+    fn arm_reboot_tasks(&mut self) {
+        let now = Utc::now();
+        for task in self.tasks.iter_mut() {
+            if matches!(task.task_type, TaskType::Reboot) && task.enabled {
+                task.next_run = Some(now);
+            }
+        }
+    }
+
     /// Save tasks to disk.
     pub fn save(&self) {
         if let Err(e) = self.store.save(&self.tasks) {
@@ -211,6 +369,19 @@ impl SchedulerEngine {
         self.router.history().len()
     }
 
+    /// Set the scheduler gauges in [`bizclaw_core::metrics`]: tasks,
+    /// enabled tasks, tasks waiting to retry and permanently failed tasks.
+    pub fn record_metrics(&self) {
+        use bizclaw_core::metrics;
+        let stats = self.retry_stats();
+        let enabled = self.tasks.iter().filter(|t| t.enabled).count();
+        metrics::set("bizclaw_scheduler_tasks", &[], self.tasks.len() as f64);
+        metrics::set("bizclaw_scheduler_tasks_enabled", &[], enabled as f64);
+        metrics::set("bizclaw_scheduler_tasks_retrying", &[], stats.retrying as f64);
+        metrics::set("bizclaw_scheduler_tasks_failed", &[], stats.permanently_failed as f64);
+        metrics::set("bizclaw_scheduler_retries", &[], stats.total_retries as f64);
+    }
+
     /// Get retry statistics.
     pub fn retry_stats(&self) -> RetryStats {
         let mut stats = RetryStats::default();
@@ -293,11 +464,11 @@ pub async fn spawn_scheduler_with_agent<F, Fut>(
         let triggered_tasks = {
             let mut eng = engine.lock().await;
             // Collect task info before tick modifies them
-            let tasks: Vec<(String, String, TaskAction)> = eng
+            let tasks: Vec<(String, String, TaskAction, u32)> = eng
                 .list_tasks()
                 .iter()
                 .filter(|t| t.should_run())
-                .map(|t| (t.id.clone(), t.name.clone(), t.action.clone()))
+                .map(|t| (t.id.clone(), t.name.clone(), t.action.clone(), t.fail_count + 1))
                 .collect();
 
             // Run the tick to update task states
@@ -306,7 +477,8 @@ pub async fn spawn_scheduler_with_agent<F, Fut>(
         };
 
         // Execute each triggered action with retry support
-        for (task_id, task_name, action) in &triggered_tasks {
+        for (task_id, task_name, action, attempt) in &triggered_tasks {
+            let started_at = Utc::now();
             let execution_result: Result<String, String> = match action {
                 TaskAction::AgentPrompt(prompt) => {
                     tracing::info!(
@@ -342,6 +514,19 @@ pub async fn spawn_scheduler_with_agent<F, Fut>(
 
             // Handle result with retry logic
             let mut eng = engine.lock().await;
+            eng.record_run(task_id, started_at, *attempt, &execution_result);
+            eng.publish(Event::TaskFinished {
+                task_id: task_id.clone(),
+                task: task_name.clone(),
+                success: execution_result.is_ok(),
+            });
+            let outcome = if execution_result.is_ok() { "success" } else { "failure" };
+            bizclaw_core::metrics::increment("bizclaw_scheduler_task_runs_total", &[("outcome", outcome)]);
+            bizclaw_core::metrics::observe(
+                "bizclaw_scheduler_task_seconds",
+                &[("outcome", outcome)],
+                (Utc::now() - started_at).num_milliseconds() as f64 / 1000.0,
+            );
             if let Some(task) = eng.tasks_mut().iter_mut().find(|t| t.id == *task_id) {
                 match execution_result {
                     Ok(response) => {
@@ -371,7 +556,7 @@ pub async fn spawn_scheduler_with_agent<F, Fut>(
                                 "scheduler",
                                 NotifyPriority::Urgent,
                             );
-                            eng.router.record(notification);
+                            eng.notify(notification);
                         }
                     }
                 }
@@ -517,4 +702,77 @@ mod tests {
 
         std::fs::remove_dir_all(&dir).ok();
     }
+
+    #[test]
+    fn test_dashboard_notifications_are_published() {
+        let dir = std::env::temp_dir().join("bizclaw-test-dashboard-notify");
+        let mut engine = SchedulerEngine::new(&dir);
+        let bus = EventBus::default();
+        let mut rx = bus.subscribe();
+        engine.set_event_bus(bus);
+
+        // No dashboard target: nothing published
+        engine.notify(NotifyRouter::create("Quiet", "no dashboard", "test", NotifyPriority::Low));
+        assert!(rx.try_recv().is_err());
+
+        engine.set_notify_targets(vec![("dashboard".into(), NotifyTarget::Dashboard)]);
+        engine.notify(NotifyRouter::create("Backup", "Backup failed", "task:backup", NotifyPriority::Urgent));
+        match rx.try_recv().unwrap() {
+            Event::NotificationSent { title, priority, source, .. } => {
+                assert_eq!(title, "Backup");
+                assert_eq!(priority, "urgent");
+                assert_eq!(source, "task:backup");
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_reboot_fires_once_at_startup() {
+        let dir = std::env::temp_dir().join("bizclaw-test-reboot");
+        std::fs::remove_dir_all(&dir).ok();
+        {
+            let mut engine = SchedulerEngine::new(&dir);
+            engine.add_task(Task::reboot("warmup", TaskAction::Notify("booted".into())));
+            // Added at runtime — waits for the next startup.
+            assert!(engine.tick().is_empty());
+        }
+
+        let mut engine = SchedulerEngine::new(&dir);
+        let triggered = engine.tick();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].1, "booted");
+        assert!(engine.tick().is_empty());
+        assert!(engine.list_tasks()[0].enabled);
+
+        // Fires again after the next restart.
+        let mut engine = SchedulerEngine::new(&dir);
+        assert_eq!(engine.tick().len(), 1);
+        assert!(engine.tick().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_anchored_interval_aligns_to_boundary() {
+        let dir = std::env::temp_dir().join("bizclaw-test-anchored");
+        let mut engine = SchedulerEngine::new(&dir);
+        let anchor = Utc::now() - chrono::Duration::days(3) + chrono::Duration::seconds(17);
+        let mut task =
+            Task::interval_anchored("hourly", 3600, anchor, TaskAction::Notify("tick".into()));
+        let initial = task.next_run.unwrap();
+        assert_eq!((initial - anchor).num_seconds() % 3600, 0);
+        assert!(initial > Utc::now());
+
+        // Fire late — the next run still lands on the anchor grid.
+        task.next_run = Some(Utc::now() - chrono::Duration::seconds(1234));
+        engine.add_task(task);
+        assert_eq!(engine.tick().len(), 1);
+        let next = engine.list_tasks()[0].next_run.unwrap();
+        assert_eq!((next - anchor).num_seconds() % 3600, 0);
+        assert!(next > Utc::now() && next <= Utc::now() + chrono::Duration::seconds(3600));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
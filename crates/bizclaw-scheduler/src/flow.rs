@@ -0,0 +1,569 @@
+//! Multi-step workflows — ordered steps whose outputs are bound to named
+//! variables, `if` steps that branch on earlier results, and retries per
+//! step. Definitions load from TOML or JSON:
+//!
+//! ```toml
+//! name = "Nightly sales digest"
+//!
+//! [vars]
+//! region = "north"
+//!
+//! [[steps]]
+//! id = "fetch"
+//! action = "webhook"
+//! url = "https://erp.example.com/sales?region={{region}}"
+//! output = "sales"
+//! continue_on_error = true
+//! retry = { max_retries = 2, base_delay_secs = 10 }
+//!
+//! [[steps]]
+//! id = "check"
+//! action = "if"
+//! condition = { var = "fetch.status", operator = "==", value = "success" }
+//! then = [{ id = "digest", action = "agent_prompt", prompt = "Summarize: {{sales}}", output = "digest" }]
+//! else = [{ id = "alert", action = "notify", message = "Sales export failed: {{fetch.error}}" }]
+//! ```
+//!
+//! Every step also sets `<id>.status` (`success`/`failed`), `<id>.output`
+//! and `<id>.error`. `{{name}}` in step fields is replaced by the variable.
+
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::tasks::RetryPolicy;
+
+/// Comparison operators of [`StepCondition`].
+const OPERATORS: &[&str] = &[
+    "==", "!=", ">", ">=", "<", "<=", "contains", "not_contains", "empty", "not_empty",
+];
+
+/// A multi-step workflow definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    /// Unique ID (assigned on save when empty).
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Initial variables; run inputs override them.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// One step of a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    /// Unique within the workflow; names the step's result variables.
+    pub id: String,
+    #[serde(flatten)]
+    pub action: StepAction,
+    /// Variable the output is stored in on success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Retries on failure (default: none).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Keep going when the step fails, e.g. to branch on `<id>.status`.
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+/// What a step does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Send a prompt to an agent (None = default agent); output is the answer.
+    AgentPrompt {
+        prompt: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent: Option<String>,
+    },
+    /// Call a URL; output is the response body.
+    Webhook {
+        url: String,
+        #[serde(default = "default_method")]
+        method: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+        #[serde(default)]
+        headers: Vec<(String, String)>,
+    },
+    /// Send a notification; output is the message.
+    Notify { message: String },
+    /// Output `value` — for building variables from others.
+    Set { value: String },
+    /// Run `then` or `else` depending on `condition`.
+    If {
+        condition: StepCondition,
+        #[serde(default)]
+        then: Vec<WorkflowStep>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<WorkflowStep>,
+    },
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+impl StepAction {
+    /// Action name as written in definitions.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::AgentPrompt { .. } => "agent_prompt",
+            Self::Webhook { .. } => "webhook",
+            Self::Notify { .. } => "notify",
+            Self::Set { .. } => "set",
+            Self::If { .. } => "if",
+        }
+    }
+}
+
+/// A test on a variable: `var operator value`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepCondition {
+    pub var: String,
+    /// `==`, `!=`, `>`, `>=`, `<`, `<=` (numeric), `contains`,
+    /// `not_contains`, `empty` or `not_empty`.
+    #[serde(default = "default_operator")]
+    pub operator: String,
+    #[serde(default)]
+    pub value: serde_json::Value,
+}
+
+fn default_operator() -> String {
+    "==".into()
+}
+
+impl StepCondition {
+    /// Whether the condition holds for `vars`.
+    pub fn evaluate(&self, vars: &BTreeMap<String, String>) -> bool {
+        let actual = vars.get(&self.var).map(String::as_str).unwrap_or("");
+        let expected = match &self.value {
+            serde_json::Value::String(s) => render(s, vars),
+            serde_json::Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        let numbers = || Some((actual.trim().parse::<f64>().ok()?, expected.trim().parse::<f64>().ok()?));
+        match self.operator.as_str() {
+            "==" => actual == expected,
+            "!=" => actual != expected,
+            "contains" => actual.contains(&expected),
+            "not_contains" => !actual.contains(&expected),
+            "empty" => actual.trim().is_empty(),
+            "not_empty" => !actual.trim().is_empty(),
+            ">" => numbers().is_some_and(|(a, b)| a > b),
+            ">=" => numbers().is_some_and(|(a, b)| a >= b),
+            "<" => numbers().is_some_and(|(a, b)| a < b),
+            "<=" => numbers().is_some_and(|(a, b)| a <= b),
+            _ => false,
+        }
+    }
+}
+
+impl Workflow {
+    /// Parse a definition from JSON (starting with `{`) or TOML.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let workflow: Self = if text.trim_start().starts_with('{') {
+            serde_json::from_str(text).map_err(|e| format!("Invalid workflow JSON: {e}"))?
+        } else {
+            toml::from_str(text).map_err(|e| format!("Invalid workflow TOML: {e}"))?
+        };
+        workflow.validate()?;
+        Ok(workflow)
+    }
+
+    /// Parse a definition from a JSON value.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        let workflow: Self = serde_json::from_value(value).map_err(|e| format!("Invalid workflow: {e}"))?;
+        workflow.validate()?;
+        Ok(workflow)
+    }
+
+    /// Check names, step IDs and conditions.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Workflow needs a name".into());
+        }
+        if self.steps.is_empty() {
+            return Err(format!("Workflow '{}' has no steps", self.name));
+        }
+        let mut ids = HashSet::new();
+        let mut pending: Vec<&WorkflowStep> = self.steps.iter().collect();
+        while let Some(step) = pending.pop() {
+            if step.id.trim().is_empty() {
+                return Err(format!("A '{}' step has no id", step.action.kind()));
+            }
+            if !ids.insert(step.id.as_str()) {
+                return Err(format!("Duplicate step id '{}'", step.id));
+            }
+            if let StepAction::If { condition, then, otherwise } = &step.action {
+                if !OPERATORS.contains(&condition.operator.as_str()) {
+                    return Err(format!(
+                        "Step '{}': unknown operator '{}' (expected one of {})",
+                        step.id,
+                        condition.operator,
+                        OPERATORS.join(", ")
+                    ));
+                }
+                pending.extend(then.iter().chain(otherwise));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of one step in a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRun {
+    pub step_id: String,
+    /// Step action (`agent_prompt`, `webhook`, `notify`, `set`, `if`).
+    pub kind: String,
+    /// "success" or "failed".
+    pub status: String,
+    pub attempts: u32,
+    /// Output, or the branch taken (`then`/`else`) for `if` steps.
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// One execution of a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    /// Assigned when the run is recorded.
+    #[serde(default)]
+    pub id: i64,
+    pub workflow_id: String,
+    /// "success" or "failed".
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Steps in the order they ran.
+    pub steps: Vec<StepRun>,
+    /// Variables at the end of the run.
+    pub vars: BTreeMap<String, String>,
+    /// Why the run stopped, when it failed.
+    pub error: Option<String>,
+}
+
+/// Runs agent prompts: (agent name, prompt) → answer.
+pub type AgentFn =
+    Arc<dyn Fn(Option<String>, String) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send>> + Send + Sync>;
+
+/// Executes workflows. Agent prompts go through a callback so the
+/// scheduler doesn't depend on the agent crate.
+pub struct WorkflowRunner {
+    agent: Option<AgentFn>,
+    http: reqwest::Client,
+}
+
+impl Default for WorkflowRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkflowRunner {
+    /// Runner without an agent; `agent_prompt` steps fail.
+    pub fn new() -> Self {
+        Self {
+            agent: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Send `agent_prompt` steps to `agent`.
+    pub fn with_agent<F, Fut>(mut self, agent: F) -> Self
+    where
+        F: Fn(Option<String>, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.agent = Some(Arc::new(move |name, prompt| Box::pin(agent(name, prompt))));
+        self
+    }
+
+    /// Run `workflow` with `inputs` added to its variables. Stops at the
+    /// first failed step unless it has `continue_on_error`.
+    pub async fn run(&self, workflow: &Workflow, inputs: BTreeMap<String, String>) -> WorkflowRun {
+        let started_at = Utc::now();
+        let mut vars = workflow.vars.clone();
+        vars.extend(inputs);
+        let mut steps = Vec::new();
+        let mut error = None;
+
+        // Depth-first over branches without recursion
+        let mut stack = vec![workflow.steps.iter()];
+        while let Some(pending) = stack.last_mut() {
+            let Some(step) = pending.next() else {
+                stack.pop();
+                continue;
+            };
+            let step_start = Utc::now();
+            let (result, attempts) = match &step.action {
+                StepAction::If { condition, then, otherwise } => {
+                    let taken = condition.evaluate(&vars);
+                    stack.push(if taken { then.iter() } else { otherwise.iter() });
+                    (Ok(if taken { "then" } else { "else" }.to_string()), 1)
+                }
+                action => self.execute_with_retry(step, action, &vars).await,
+            };
+
+            vars.insert(format!("{}.status", step.id), status(&result).into());
+            match &result {
+                Ok(output) => {
+                    vars.insert(format!("{}.output", step.id), output.clone());
+                    if let Some(name) = &step.output {
+                        vars.insert(name.clone(), output.clone());
+                    }
+                }
+                Err(e) => {
+                    vars.insert(format!("{}.error", step.id), e.clone());
+                }
+            }
+            steps.push(StepRun {
+                step_id: step.id.clone(),
+                kind: step.action.kind().into(),
+                status: status(&result).into(),
+                attempts,
+                output: result.as_ref().ok().cloned(),
+                error: result.as_ref().err().cloned(),
+                started_at: step_start,
+                finished_at: Utc::now(),
+            });
+            if let Err(e) = result
+                && !step.continue_on_error
+            {
+                error = Some(format!("Step '{}' failed: {e}", step.id));
+                break;
+            }
+        }
+
+        let run = WorkflowRun {
+            id: 0,
+            workflow_id: workflow.id.clone(),
+            status: if error.is_some() { "failed" } else { "success" }.into(),
+            started_at,
+            finished_at: Utc::now(),
+            steps,
+            vars,
+            error,
+        };
+        tracing::info!(
+            "🧩 Workflow '{}' {} after {} step(s)",
+            workflow.name,
+            run.status,
+            run.steps.len()
+        );
+        run
+    }
+
+    /// Execute a step, retrying per its policy. Returns the result and the
+    /// number of attempts.
+    async fn execute_with_retry(
+        &self,
+        step: &WorkflowStep,
+        action: &StepAction,
+        vars: &BTreeMap<String, String>,
+    ) -> (Result<String, String>, u32) {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let result = self.execute(action, vars).await;
+            let Err(e) = &result else {
+                return (result, attempts);
+            };
+            match step.retry.as_ref().and_then(|r| r.next_delay(attempts - 1)) {
+                Some(delay) => {
+                    tracing::warn!("🔄 Workflow step '{}' failed, retry in {delay}s: {e}", step.id);
+                    tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+                }
+                None => return (result, attempts),
+            }
+        }
+    }
+
+    async fn execute(&self, action: &StepAction, vars: &BTreeMap<String, String>) -> Result<String, String> {
+        match action {
+            StepAction::AgentPrompt { prompt, agent } => {
+                let Some(call) = &self.agent else {
+                    return Err("No agent available".into());
+                };
+                call(agent.clone(), render(prompt, vars)).await
+            }
+            StepAction::Webhook { url, method, body, headers } => {
+                let url = render(url, vars);
+                let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|_| format!("Invalid HTTP method '{method}'"))?;
+                let mut req = self
+                    .http
+                    .request(method, &url)
+                    .timeout(std::time::Duration::from_secs(30));
+                for (key, value) in headers {
+                    req = req.header(key.as_str(), render(value, vars));
+                }
+                if let Some(body) = body {
+                    req = req.header("Content-Type", "application/json").body(render(body, vars));
+                }
+                let resp = req.send().await.map_err(|e| format!("Request to {url} failed: {e}"))?;
+                let status = resp.status();
+                let text = resp.text().await.unwrap_or_default();
+                if status.is_success() {
+                    Ok(text)
+                } else {
+                    Err(format!("HTTP {status} from {url}: {}", text.chars().take(200).collect::<String>()))
+                }
+            }
+            StepAction::Notify { message } => Ok(render(message, vars)),
+            StepAction::Set { value } => Ok(render(value, vars)),
+            StepAction::If { .. } => Ok(String::new()),
+        }
+    }
+}
+
+fn status(result: &Result<String, String>) -> &'static str {
+    if result.is_ok() { "success" } else { "failed" }
+}
+
+/// `template` with each `{{name}}` replaced by variable `name`; unknown
+/// names are left as they are.
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else { break };
+        let name = rest[start + 2..start + len].trim();
+        out.push_str(&rest[..start]);
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const DIGEST: &str = r#"
+name = "Digest"
+
+[vars]
+region = "north"
+
+[[steps]]
+id = "fetch"
+action = "agent_prompt"
+prompt = "Sales for {{region}}"
+output = "sales"
+continue_on_error = true
+retry = { max_retries = 2, base_delay_secs = 0 }
+
+[[steps]]
+id = "check"
+action = "if"
+condition = { var = "fetch.status", operator = "==", value = "success" }
+then = [{ id = "digest", action = "set", value = "Digest: {{sales}}", output = "digest" }]
+else = [{ id = "alert", action = "notify", message = "Export failed: {{fetch.error}}" }]
+"#;
+
+    /// Runner whose agent fails `failures` times, then answers.
+    fn flaky_runner(failures: u32) -> WorkflowRunner {
+        let calls = Arc::new(AtomicU32::new(0));
+        WorkflowRunner::new().with_agent(move |_agent, prompt| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if call < failures {
+                    Err("timeout".to_string())
+                } else {
+                    Ok(format!("42 orders ({prompt})"))
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_variables_retry_and_then_branch() {
+        let workflow = Workflow::parse(DIGEST).unwrap();
+        let run = flaky_runner(1).run(&workflow, BTreeMap::new()).await;
+        assert_eq!(run.status, "success");
+        assert_eq!(run.steps[0].attempts, 2);
+        let ids: Vec<&str> = run.steps.iter().map(|s| s.step_id.as_str()).collect();
+        assert_eq!(ids, ["fetch", "check", "digest"]);
+        assert_eq!(run.vars["digest"], "Digest: 42 orders (Sales for north)");
+    }
+
+    #[tokio::test]
+    async fn test_else_branch_after_failure() {
+        let workflow = Workflow::parse(DIGEST).unwrap();
+        let inputs = BTreeMap::from([("region".to_string(), "south".to_string())]);
+        let run = flaky_runner(10).run(&workflow, inputs).await;
+        assert_eq!(run.status, "success");
+        assert_eq!(run.steps[0].attempts, 3);
+        assert_eq!(run.steps[1].output.as_deref(), Some("else"));
+        assert_eq!(run.steps[2].output.as_deref(), Some("Export failed: timeout"));
+        assert!(!run.vars.contains_key("digest"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_stops_run() {
+        let workflow = Workflow::from_value(serde_json::json!({
+            "name": "Stops",
+            "steps": [
+                {"id": "ask", "action": "agent_prompt", "prompt": "hi"},
+                {"id": "after", "action": "set", "value": "never"}
+            ]
+        }))
+        .unwrap();
+        let run = WorkflowRunner::new().run(&workflow, BTreeMap::new()).await;
+        assert_eq!(run.status, "failed");
+        assert_eq!(run.steps.len(), 1);
+        assert_eq!(run.error.as_deref(), Some("Step 'ask' failed: No agent available"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let bad = |json: serde_json::Value| Workflow::from_value(json).unwrap_err();
+        assert!(bad(serde_json::json!({"name": "x", "steps": []})).contains("no steps"));
+        assert!(bad(serde_json::json!({"name": "x", "steps": [
+            {"id": "a", "action": "set", "value": "1"},
+            {"id": "b", "action": "if", "condition": {"var": "a.output"}, "then": [{"id": "a", "action": "set", "value": "2"}]}
+        ]}))
+        .contains("Duplicate step id 'a'"));
+        assert!(bad(serde_json::json!({"name": "x", "steps": [
+            {"id": "b", "action": "if", "condition": {"var": "v", "operator": "~"}}
+        ]}))
+        .contains("unknown operator"));
+        assert!(Workflow::parse("name = \"x\"\nsteps = \"nope\"").is_err());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let vars = BTreeMap::from([
+            ("count".to_string(), "12".to_string()),
+            ("text".to_string(), "all good".to_string()),
+        ]);
+        let cond = |var: &str, operator: &str, value: serde_json::Value| {
+            StepCondition { var: var.into(), operator: operator.into(), value }.evaluate(&vars)
+        };
+        assert!(cond("count", ">", serde_json::json!(10)));
+        assert!(!cond("count", "<=", serde_json::json!("11")));
+        assert!(!cond("text", ">", serde_json::json!(1)));
+        assert!(cond("text", "contains", serde_json::json!("good")));
+        assert!(cond("missing", "empty", serde_json::Value::Null));
+        assert!(cond("count", "==", serde_json::json!("{{count}}")));
+        assert_eq!(render("{{ text }} / {{unknown}}", &vars), "all good / {{unknown}}");
+    }
+}
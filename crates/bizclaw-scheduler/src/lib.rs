@@ -7,8 +7,11 @@
 //! - No external dependencies (no Redis, no RabbitMQ)
 //! - SQLite persistence — survives restarts
 //! - Tokio timers only — zero overhead when idle
-//! - Notification routing + dispatch — actually sends to channels
+//! - Notification routing + dispatch — actually sends to channels, retries
+//!   failures with backoff and keeps undeliverable ones as dead letters
 //! - Workflow engine — trigger→condition→action automation
+//! - Multi-step workflows — variables, if/else branches, retries per step
+//! - Natural-language schedules — "every Monday at 9" → cron task
 //!
 //! ## Architecture
 //! ```text
@@ -19,29 +22,37 @@
 //!   └── on trigger → NotificationRouter → Dispatch
 //!                      ├── Telegram (sendMessage)
 //!                      ├── Discord (webhook)
+//!                      ├── WhatsApp (template / text)
+//!                      ├── SMS (Twilio)
 //!                      ├── Webhook (HTTP POST)
 //!                      └── Dashboard (WebSocket)
 //!
 //! Workflow Engine
 //!   ├── Event (message, schedule, metric) → evaluate rules
 //!   ├── Matching rules → generate actions
-//!   └── Actions: agent_prompt, notify, webhook, delegate
+//!   ├── Actions: agent_prompt, notify, webhook, delegate, run_workflow
+//!   └── Event bus (message received, task finished) → triggers → rules
 //! ```
 
 pub mod cron;
 pub mod dispatch;
 pub mod engine;
+pub mod flow;
 pub mod lanes;
+pub mod natural;
 pub mod notify;
 pub mod persistence;
 pub mod store;
 pub mod tasks;
+pub mod triggers;
 pub mod workflow;
 
 pub use engine::{RetryStats, SchedulerEngine};
+pub use flow::{Workflow, WorkflowRun, WorkflowRunner, WorkflowStep};
 pub use lanes::{Lane, LaneScheduler, LaneStats, LaneTask};
+pub use natural::{Schedule, parse_schedule};
 pub use notify::{Notification, NotifyChannel, NotifyRouter};
-pub use persistence::SchedulerDb;
+pub use persistence::{QueuedNotification, SchedulerDb, TaskRun};
 pub use store::TaskStore;
 pub use tasks::{RetryPolicy, Task, TaskStatus, TaskType};
 pub use workflow::{WorkflowAction, WorkflowEngine, WorkflowEvent};
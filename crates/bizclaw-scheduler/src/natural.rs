@@ -0,0 +1,437 @@
+//! Natural-language schedules — "every Monday at 9", "daily at 17:30",
+//! "every 30 minutes", "tomorrow at 8am", "in 2 hours" — turned into cron,
+//! interval or one-time tasks.
+//!
+//! Times are read in the user's timezone (`utc_offset_mins`) and converted
+//! to UTC, which the engine runs on. Common Vietnamese phrasings ("thứ 2
+//! lúc 9h", "mỗi ngày") are understood too.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+
+use crate::cron;
+use crate::tasks::{Task, TaskAction, TaskType};
+
+const DAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+/// Time of day used when a recurring or dated schedule names none.
+const DEFAULT_TIME: (u32, u32) = (9, 0);
+
+/// Vietnamese phrases rewritten to the English words parsed below.
+const ALIASES: &[(&str, &str)] = &[
+    ("hàng ngày", "every day"),
+    ("hằng ngày", "every day"),
+    ("mỗi ngày", "every day"),
+    ("hàng tuần", "every week"),
+    ("mỗi tuần", "every week"),
+    ("thứ hai", "monday"),
+    ("thứ 2", "monday"),
+    ("thứ ba", "tuesday"),
+    ("thứ 3", "tuesday"),
+    ("thứ tư", "wednesday"),
+    ("thứ 4", "wednesday"),
+    ("thứ năm", "thursday"),
+    ("thứ 5", "thursday"),
+    ("thứ sáu", "friday"),
+    ("thứ 6", "friday"),
+    ("thứ bảy", "saturday"),
+    ("thứ 7", "saturday"),
+    ("chủ nhật", "sunday"),
+    ("ngày mai", "tomorrow"),
+    ("hôm nay", "today"),
+    ("mỗi", "every"),
+    ("lúc", "at"),
+    ("sau", "in"),
+    ("phút", "minutes"),
+    ("tiếng", "hours"),
+];
+
+/// A parsed schedule.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub task_type: TaskType,
+    /// Restatement of the schedule in the user's timezone, for confirming
+    /// it with them.
+    pub summary: String,
+}
+
+impl Schedule {
+    /// A task running `action` on this schedule, with its first run set.
+    pub fn into_task(self, name: &str, action: TaskAction) -> Task {
+        match self.task_type {
+            TaskType::Once { at } => Task::once(name, at, action),
+            TaskType::Cron { expression } => {
+                let mut task = Task::cron(name, &expression, action);
+                task.next_run = cron::next_run_from_cron(&expression, Utc::now());
+                task
+            }
+            TaskType::Interval { every_secs, anchor: Some(anchor) } => {
+                Task::interval_anchored(name, every_secs, anchor, action)
+            }
+            TaskType::Interval { every_secs, anchor: None } => Task::interval(name, every_secs, action),
+            TaskType::Reboot => Task::reboot(name, action),
+        }
+    }
+}
+
+/// Parse `text` into a schedule, relative to `now`, reading times at UTC
+/// offset `utc_offset_mins`.
+pub fn parse_schedule(text: &str, now: DateTime<Utc>, utc_offset_mins: i32) -> Result<Schedule, String> {
+    let words = normalize(text);
+    let has = |w: &str| words.iter().any(|x| x == w);
+    let tz = format_offset(utc_offset_mins);
+    let offset = Duration::minutes(utc_offset_mins as i64);
+    let local_now = now + offset;
+    let time = find_time(&words);
+    let (hour, minute) = time.unwrap_or(DEFAULT_TIME);
+    let clock = format!("{hour:02}:{minute:02}");
+
+    let mut days: Vec<u32> = words
+        .iter()
+        .filter_map(|w| DAYS.iter().position(|d| w.trim_end_matches('s') == d.trim_end_matches('s')))
+        .map(|d| d as u32)
+        .collect();
+    if has("weekday") || has("weekdays") {
+        days.extend(1..=5);
+    }
+    if has("weekend") || has("weekends") {
+        days.extend([0, 6]);
+    }
+    days.sort_unstable();
+    days.dedup();
+
+    let recurring = has("every") || has("daily") || has("weekly") || has("hourly");
+    if recurring {
+        // "every 2 hours", "every minute", "every 3 days"
+        let step = words
+            .iter()
+            .position(|w| w == "every")
+            .and_then(|i| {
+                let count = words.get(i + 1)?.parse::<u64>().ok();
+                let unit = unit_secs(words.get(i + 1 + usize::from(count.is_some()))?)?;
+                Some((count.unwrap_or(1), unit))
+            });
+
+        if has("hourly") {
+            return Ok(interval(3600, None, "every hour".into()));
+        }
+        if !days.is_empty() {
+            let names: Vec<String> = days.iter().map(|&d| capitalize(DAYS[d as usize])).collect();
+            return Ok(Schedule {
+                task_type: TaskType::Cron {
+                    expression: cron_at(hour, minute, &days, utc_offset_mins),
+                },
+                summary: format!("every {} at {clock} ({tz})", names.join(", ")),
+            });
+        }
+        match step {
+            Some((n, secs)) if secs < 86_400 => {
+                if n == 0 {
+                    return Err("The interval must be at least 1".into());
+                }
+                let unit = if secs == 60 { "minute" } else { "hour" };
+                let summary = if n == 1 { format!("every {unit}") } else { format!("every {n} {unit}s") };
+                return Ok(interval(n * secs, None, summary));
+            }
+            Some((1, 86_400)) => return Ok(daily(hour, minute, utc_offset_mins, &clock, &tz)),
+            Some((n, 86_400)) if n > 1 => {
+                // Every N days at the time, starting at its next occurrence
+                let first = next_local(local_now, hour, minute) - offset;
+                let summary = format!("every {n} days at {clock} ({tz})");
+                return Ok(interval(n * 86_400, Some(first), summary));
+            }
+            Some((1, 604_800)) => {}
+            _ if has("daily") => return Ok(daily(hour, minute, utc_offset_mins, &clock, &tz)),
+            _ if !has("weekly") => {
+                return Err(format!("Could not tell how often to repeat '{text}'. {EXAMPLES}"));
+            }
+            _ => {}
+        }
+        // "every week" / "weekly": on today's weekday
+        let day = local_now.weekday().num_days_from_sunday();
+        return Ok(Schedule {
+            task_type: TaskType::Cron {
+                expression: cron_at(hour, minute, &[day], utc_offset_mins),
+            },
+            summary: format!("every {} at {clock} ({tz})", capitalize(DAYS[day as usize])),
+        });
+    }
+
+    // "in 2 hours", "in 15 minutes"
+    if let Some(i) = words.iter().position(|w| w == "in")
+        && let Some(n) = words.get(i + 1).and_then(|w| w.parse::<i64>().ok())
+        && let Some(secs) = words.get(i + 2).and_then(|w| unit_secs(w))
+    {
+        let at = now + Duration::seconds(n * secs as i64);
+        return once(at, offset, &tz);
+    }
+
+    // A date, or just a time (today, or tomorrow once it has passed)
+    let date = if has("tomorrow") {
+        Some(local_now.date_naive() + Duration::days(1))
+    } else if has("today") {
+        Some(local_now.date_naive())
+    } else if let Some(date) = words.iter().find_map(|w| NaiveDate::parse_from_str(w, "%Y-%m-%d").ok()) {
+        Some(date)
+    } else if let Some(&day) = days.first() {
+        let ahead = (day + 7 - local_now.weekday().num_days_from_sunday()) % 7;
+        let date = local_now.date_naive() + Duration::days(ahead as i64);
+        // Later today, or the same weekday next week
+        Some(if ahead == 0 && local_time(date, hour, minute) <= local_now {
+            date + Duration::days(7)
+        } else {
+            date
+        })
+    } else {
+        None
+    };
+    let at = match (date, time) {
+        (Some(date), _) => local_time(date, hour, minute),
+        (None, Some(_)) => next_local(local_now, hour, minute),
+        (None, None) => return Err(format!("Could not understand the schedule '{text}'. {EXAMPLES}")),
+    };
+    if at <= local_now {
+        return Err(format!("'{text}' is in the past"));
+    }
+    once(at - offset, offset, &tz)
+}
+
+const EXAMPLES: &str =
+    "Try e.g. 'every Monday at 9:00', 'daily at 17:30', 'every 30 minutes', 'tomorrow at 8am' or 'in 2 hours'.";
+
+/// Lowercased words, punctuation dropped and aliases rewritten.
+fn normalize(text: &str) -> Vec<String> {
+    let cleaned: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if matches!(c, ',' | ';' | '!' | '?' | '"') { ' ' } else { c })
+        .collect();
+    let mut padded = format!(" {} ", cleaned.split_whitespace().collect::<Vec<_>>().join(" "));
+    for (from, to) in ALIASES {
+        padded = padded.replace(&format!(" {from} "), &format!(" {to} "));
+    }
+    padded
+        .split_whitespace()
+        .map(|w| w.trim_end_matches('.').to_string())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Seconds in a unit word.
+fn unit_secs(word: &str) -> Option<u64> {
+    match word {
+        "minute" | "minutes" | "min" | "mins" => Some(60),
+        "hour" | "hours" | "hr" | "hrs" | "h" => Some(3600),
+        "day" | "days" => Some(86_400),
+        "week" | "weeks" => Some(604_800),
+        _ => None,
+    }
+}
+
+/// Time of day in `words`: after "at", or any word that reads as a time
+/// on its own ("9am", "17:30", "9h30", "noon").
+fn find_time(words: &[String]) -> Option<(u32, u32)> {
+    let next = |i: usize| words.get(i + 1).map(String::as_str);
+    words
+        .iter()
+        .enumerate()
+        .filter(|(i, w)| *w == "at" && next(*i).is_some())
+        .find_map(|(i, _)| parse_time(&words[i + 1], next(i + 1), true))
+        .or_else(|| {
+            words
+                .iter()
+                .enumerate()
+                .find_map(|(i, w)| parse_time(w, next(i), false))
+        })
+}
+
+/// Parse one time word (and an "am"/"pm" after it). A bare hour counts
+/// only when `bare` (the word followed "at").
+fn parse_time(word: &str, next: Option<&str>, bare: bool) -> Option<(u32, u32)> {
+    match word {
+        "noon" => return Some((12, 0)),
+        "midnight" => return Some((0, 0)),
+        _ => {}
+    }
+    let (digits, suffix) = if let Some(d) = word.strip_suffix("am") {
+        (d, Some("am"))
+    } else if let Some(d) = word.strip_suffix("pm") {
+        (d, Some("pm"))
+    } else {
+        (word, next.filter(|n| matches!(*n, "am" | "pm")))
+    };
+    let (hour, minute) = match digits.split_once([':', 'h']) {
+        Some((h, "")) => (h, "0"),
+        Some((h, m)) => (h, m),
+        None if bare || suffix.is_some() => (digits, "0"),
+        None => return None,
+    };
+    let (mut hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    match suffix {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some("pm") if hour < 12 => hour += 12,
+        Some("am") if hour == 12 => hour = 0,
+        _ => {}
+    }
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+/// Cron expression for `hour:minute` local time on `days` (all days when
+/// empty), shifted to UTC — which may move it to a neighbouring day.
+fn cron_at(hour: u32, minute: u32, days: &[u32], utc_offset_mins: i32) -> String {
+    let utc = (hour * 60 + minute) as i32 - utc_offset_mins;
+    let shift = utc.div_euclid(1440);
+    let mins = utc.rem_euclid(1440);
+    let dow = if days.is_empty() {
+        "*".to_string()
+    } else {
+        let mut shifted: Vec<i32> = days.iter().map(|&d| (d as i32 + shift).rem_euclid(7)).collect();
+        shifted.sort_unstable();
+        shifted.iter().map(i32::to_string).collect::<Vec<_>>().join(",")
+    };
+    format!("{} {} * * {dow}", mins % 60, mins / 60)
+}
+
+fn daily(hour: u32, minute: u32, utc_offset_mins: i32, clock: &str, tz: &str) -> Schedule {
+    Schedule {
+        task_type: TaskType::Cron {
+            expression: cron_at(hour, minute, &[], utc_offset_mins),
+        },
+        summary: format!("every day at {clock} ({tz})"),
+    }
+}
+
+fn interval(every_secs: u64, anchor: Option<DateTime<Utc>>, summary: String) -> Schedule {
+    Schedule {
+        task_type: TaskType::Interval { every_secs, anchor },
+        summary,
+    }
+}
+
+fn once(at: DateTime<Utc>, offset: Duration, tz: &str) -> Result<Schedule, String> {
+    Ok(Schedule {
+        task_type: TaskType::Once { at },
+        summary: format!("once at {} ({tz})", (at + offset).format("%Y-%m-%d %H:%M")),
+    })
+}
+
+/// `date` at `hour:minute`, as a local timestamp.
+fn local_time(date: NaiveDate, hour: u32, minute: u32) -> DateTime<Utc> {
+    date.and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default())
+        .and_utc()
+}
+
+/// Next local `hour:minute` after `local_now`: today or tomorrow.
+fn next_local(local_now: DateTime<Utc>, hour: u32, minute: u32) -> DateTime<Utc> {
+    let today = local_time(local_now.date_naive(), hour, minute);
+    if today > local_now { today } else { today + Duration::days(1) }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// "UTC+07:00" for an offset in minutes.
+pub fn format_offset(utc_offset_mins: i32) -> String {
+    let sign = if utc_offset_mins < 0 { '-' } else { '+' };
+    let mins = utc_offset_mins.abs();
+    format!("UTC{sign}{:02}:{:02}", mins / 60, mins % 60)
+}
+
+/// Parse "+07:00", "-0530" or "7" into minutes east of UTC.
+pub fn parse_offset(text: &str) -> Option<i32> {
+    let text = text.trim().trim_start_matches("UTC").trim_start_matches("utc");
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (hours, mins) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let (hours, mins): (i32, i32) = (hours.parse().ok()?, mins.parse().ok()?);
+    (hours <= 14 && mins < 60).then_some(sign * (hours * 60 + mins))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Sunday 2026-02-22 10:00 UTC.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 2, 22, 10, 0, 0).unwrap()
+    }
+
+    fn cron_of(text: &str, offset: i32) -> String {
+        match parse_schedule(text, now(), offset).unwrap().task_type {
+            TaskType::Cron { expression } => expression,
+            other => panic!("expected cron for '{text}', got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_weekly_and_daily_cron() {
+        let s = parse_schedule("remind me every Monday at 9 to send the report", now(), 0).unwrap();
+        assert_eq!(s.summary, "every Monday at 09:00 (UTC+00:00)");
+        assert_eq!(cron_of("every Monday at 9", 0), "0 9 * * 1");
+        assert_eq!(cron_of("every weekday at 5:30pm", 0), "30 17 * * 1,2,3,4,5");
+        assert_eq!(cron_of("daily at 17:30", 0), "30 17 * * *");
+        assert_eq!(cron_of("every day", 0), "0 9 * * *");
+        assert_eq!(cron_of("mỗi thứ 2 lúc 9h", 0), "0 9 * * 1");
+        // 08:00 Monday in UTC+7 is 01:00 Monday UTC; 06:00 is Sunday 23:00
+        assert_eq!(cron_of("every Monday at 8am", 420), "0 1 * * 1");
+        assert_eq!(cron_of("every Monday and Friday at 6:00", 420), "0 23 * * 0,4");
+    }
+
+    #[test]
+    fn test_intervals() {
+        let every = |text: &str| match parse_schedule(text, now(), 0).unwrap().task_type {
+            TaskType::Interval { every_secs, anchor } => (every_secs, anchor),
+            other => panic!("expected interval, got {other:?}"),
+        };
+        assert_eq!(every("every 30 minutes"), (1800, None));
+        assert_eq!(every("every hour"), (3600, None));
+        assert_eq!(every("every 2 days at 8:00").0, 172_800);
+        assert_eq!(
+            every("every 2 days at 8:00").1,
+            Some(Utc.with_ymd_and_hms(2026, 2, 23, 8, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_one_time() {
+        let at = |text: &str, offset: i32| match parse_schedule(text, now(), offset).unwrap().task_type {
+            TaskType::Once { at } => at,
+            other => panic!("expected once, got {other:?}"),
+        };
+        assert_eq!(at("in 2 hours", 0), now() + Duration::hours(2));
+        assert_eq!(at("tomorrow at 8am", 0), Utc.with_ymd_and_hms(2026, 2, 23, 8, 0, 0).unwrap());
+        // 09:00 has passed today, so the next one is tomorrow
+        assert_eq!(at("at 9:00", 0), Utc.with_ymd_and_hms(2026, 2, 23, 9, 0, 0).unwrap());
+        assert_eq!(at("on Sunday at noon", 0), Utc.with_ymd_and_hms(2026, 2, 22, 12, 0, 0).unwrap());
+        assert_eq!(at("2026-03-01 at 14:00", 420), Utc.with_ymd_and_hms(2026, 3, 1, 7, 0, 0).unwrap());
+        let s = parse_schedule("ngày mai lúc 8h", now(), 420).unwrap();
+        assert_eq!(s.summary, "once at 2026-02-23 08:00 (UTC+07:00)");
+    }
+
+    #[test]
+    fn test_rejects_unclear_or_past() {
+        assert!(parse_schedule("sometime soon", now(), 0).is_err());
+        assert!(parse_schedule("every now and then", now(), 0).is_err());
+        assert!(parse_schedule("2026-01-01 at 9", now(), 0).unwrap_err().contains("past"));
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(parse_offset("+07:00"), Some(420));
+        assert_eq!(parse_offset("-0530"), Some(-330));
+        assert_eq!(parse_offset("UTC+7"), Some(420));
+        assert_eq!(parse_offset("abc"), None);
+        assert_eq!(format_offset(-330), "UTC-05:30");
+    }
+}
@@ -1,5 +1,6 @@
 //! Notification system — routes messages to the best available channel.
-//! Lightweight: no queues, no Redis. Just pick a channel and send.
+//! Lightweight: no Redis. Deliveries queue in the scheduler database and
+//! are retried with backoff (see `dispatch`).
 
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +28,28 @@ pub enum NotifyPriority {
     Urgent,
 }
 
+impl NotifyPriority {
+    /// Lowercase name, as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+
+    /// Parse a stored name (any case); unknown names are `Normal`.
+    pub fn parse(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "low" => Self::Low,
+            "high" => Self::High,
+            "urgent" => Self::Urgent,
+            _ => Self::Normal,
+        }
+    }
+}
+
 /// Available notification channels.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotifyChannel {
@@ -1,10 +1,22 @@
-//! SQLite-backed persistence for Scheduler tasks, Plans, and Workflow rules.
+//! SQLite-backed persistence for Scheduler tasks, Plans, Workflow rules and
+//! multi-step workflows.
 //! Replaces JSON file store — survives restarts, supports concurrent access.
 
+use crate::flow::{Workflow, WorkflowRun};
+use crate::notify::{Notification, NotifyPriority};
 use crate::tasks::{RetryPolicy, Task, TaskAction, TaskStatus, TaskType};
 use chrono::{DateTime, Utc};
 use std::path::Path;
 
+/// Runs kept per task; older ones are dropped as new ones are recorded.
+const RUNS_KEPT: i64 = 100;
+
+/// Characters of a run's output kept.
+const OUTPUT_EXCERPT_CHARS: usize = 1000;
+
+/// Delivered notifications kept; pending and dead ones are never trimmed.
+const SENT_NOTIFICATIONS_KEPT: i64 = 500;
+
 /// SQLite-backed persistence store for all scheduler data.
 pub struct SchedulerDb {
     conn: rusqlite::Connection,
@@ -14,6 +26,9 @@ impl SchedulerDb {
     /// Open or create the scheduler database.
     pub fn open(path: &Path) -> Result<Self, String> {
         let conn = rusqlite::Connection::open(path).map_err(|e| format!("DB open: {e}"))?;
+        // Gateway handlers and the scheduler loop share the file
+        conn.busy_timeout(std::time::Duration::from_secs(5))
+            .map_err(|e| format!("DB open: {e}"))?;
         let db = Self { conn };
         db.migrate()?;
         Ok(db)
@@ -31,7 +46,7 @@ impl SchedulerDb {
                 action_type TEXT NOT NULL,      -- 'agent_prompt', 'notify', 'webhook'
                 action_data TEXT NOT NULL,       -- JSON payload
                 task_type TEXT NOT NULL,         -- 'once', 'cron', 'interval'
-                task_type_data TEXT NOT NULL,    -- JSON: {at:...} or {expression:...} or {every_secs:...}
+                task_type_data TEXT NOT NULL,    -- JSON: {at:...} or {expression:...} or {every_secs, anchor?} or {}
                 status TEXT NOT NULL DEFAULT 'pending',
                 notify_via TEXT,
                 agent_name TEXT,                 -- which agent runs the task
@@ -95,10 +110,42 @@ impl SchedulerDb {
                 priority TEXT NOT NULL DEFAULT 'normal',
                 source TEXT NOT NULL,
                 channel TEXT,
-                status TEXT NOT NULL DEFAULT 'pending',  -- pending, sent, failed
+                status TEXT NOT NULL DEFAULT 'pending',  -- pending, sent, dead
                 created_at TEXT NOT NULL,
                 sent_at TEXT
             );
+
+            -- Execution history of scheduler tasks
+            CREATE TABLE IF NOT EXISTS task_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                finished_at TEXT NOT NULL,
+                status TEXT NOT NULL,            -- 'success', 'failed'
+                attempt INTEGER NOT NULL DEFAULT 1,
+                output TEXT,                     -- start of the output
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_runs_task ON task_runs(task_id, id);
+
+            -- Multi-step workflow definitions
+            CREATE TABLE IF NOT EXISTS workflows (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                definition TEXT NOT NULL,        -- JSON
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Execution history of workflows
+            CREATE TABLE IF NOT EXISTS workflow_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                workflow_id TEXT NOT NULL,
+                status TEXT NOT NULL,            -- 'success', 'failed'
+                started_at TEXT NOT NULL,
+                run TEXT NOT NULL                -- JSON: steps, variables, error
+            );
+            CREATE INDEX IF NOT EXISTS idx_workflow_runs ON workflow_runs(workflow_id, id);
          ",
             )
             .map_err(|e| format!("Migration: {e}"))?;
@@ -113,6 +160,10 @@ impl SchedulerDb {
         let _ = self.conn.execute("ALTER TABLE scheduler_tasks ADD COLUMN retry_base_delay INTEGER NOT NULL DEFAULT 30", []);
         let _ = self.conn.execute("ALTER TABLE scheduler_tasks ADD COLUMN retry_backoff REAL NOT NULL DEFAULT 2.0", []);
         let _ = self.conn.execute("ALTER TABLE scheduler_tasks ADD COLUMN retry_max_delay INTEGER NOT NULL DEFAULT 300", []);
+        // Notification delivery retries (v3)
+        let _ = self.conn.execute("ALTER TABLE notifications ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE notifications ADD COLUMN next_attempt_at TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE notifications ADD COLUMN last_error TEXT", []);
 
         Ok(())
     }
@@ -134,9 +185,14 @@ impl SchedulerDb {
             TaskType::Cron { expression } => {
                 ("cron", serde_json::json!({"expression": expression}))
             }
-            TaskType::Interval { every_secs } => {
-                ("interval", serde_json::json!({"every_secs": every_secs}))
-            }
+            TaskType::Interval { every_secs, anchor } => (
+                "interval",
+                serde_json::json!({
+                    "every_secs": every_secs,
+                    "anchor": anchor.map(|a| a.to_rfc3339()),
+                }),
+            ),
+            TaskType::Reboot => ("reboot", serde_json::json!({})),
         };
         let status = match &task.status {
             TaskStatus::Pending => "pending",
@@ -247,8 +303,13 @@ impl SchedulerDb {
                             .unwrap_or("0 * * * *")
                             .to_string(),
                     },
+                    "reboot" => TaskType::Reboot,
                     _ => TaskType::Interval {
                         every_secs: type_data["every_secs"].as_u64().unwrap_or(3600),
+                        anchor: type_data["anchor"]
+                            .as_str()
+                            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                            .map(|d| d.with_timezone(&Utc)),
                     },
                 };
 
@@ -321,7 +382,7 @@ impl SchedulerDb {
         self.conn
             .execute("DELETE FROM scheduler_tasks WHERE id = ?1", [id])
             .map_err(|e| format!("Delete task: {e}"))?;
-        Ok(())
+        self.delete_task_runs(id)
     }
 
     /// Save all tasks (batch).
@@ -433,6 +494,180 @@ impl SchedulerDb {
         Ok(())
     }
 
+    // ─── Task Runs ──────────────────────────────────────────
+
+    /// Record one execution of task `task_id` with its result.
+    pub fn record_run(
+        &self,
+        task_id: &str,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        attempt: u32,
+        result: &Result<String, String>,
+    ) -> Result<i64, String> {
+        let (status, output, error) = match result {
+            Ok(output) => ("success", Some(excerpt(output)), None),
+            Err(e) => ("failed", None, Some(e.as_str())),
+        };
+        self.conn
+            .execute(
+                "INSERT INTO task_runs (task_id, started_at, finished_at, status, attempt, output, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    task_id,
+                    started_at.to_rfc3339(),
+                    finished_at.to_rfc3339(),
+                    status,
+                    attempt,
+                    output,
+                    error
+                ],
+            )
+            .map_err(|e| format!("Record run: {e}"))?;
+        let id = self.conn.last_insert_rowid();
+        self.conn
+            .execute(
+                "DELETE FROM task_runs WHERE task_id = ?1 AND id NOT IN
+                 (SELECT id FROM task_runs WHERE task_id = ?1 ORDER BY id DESC LIMIT ?2)",
+                rusqlite::params![task_id, RUNS_KEPT],
+            )
+            .map_err(|e| format!("Trim runs: {e}"))?;
+        Ok(id)
+    }
+
+    /// Most recent runs of task `task_id`, newest first.
+    pub fn task_runs(&self, task_id: &str, limit: usize) -> Vec<TaskRun> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT id, task_id, started_at, finished_at, status, attempt, output, error
+             FROM task_runs WHERE task_id = ?1 ORDER BY id DESC LIMIT ?2",
+        ) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        let parse = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_default()
+        };
+        stmt.query_map(rusqlite::params![task_id, limit as i64], |row| {
+            Ok(TaskRun {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                started_at: parse(row.get(2)?),
+                finished_at: parse(row.get(3)?),
+                status: row.get(4)?,
+                attempt: row.get(5)?,
+                output: row.get(6)?,
+                error: row.get(7)?,
+            })
+        })
+        .ok()
+        .map(|r| r.filter_map(|x| x.ok()).collect())
+        .unwrap_or_default()
+    }
+
+    /// Delete the run history of task `task_id`.
+    pub fn delete_task_runs(&self, task_id: &str) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM task_runs WHERE task_id = ?1", [task_id])
+            .map_err(|e| format!("Delete runs: {e}"))?;
+        Ok(())
+    }
+
+    // ─── Multi-step Workflows ───────────────────────────────
+
+    /// Save (insert or replace) a workflow definition.
+    pub fn save_workflow(&self, workflow: &Workflow) -> Result<(), String> {
+        let definition = serde_json::to_string(workflow).map_err(|e| format!("Serialize workflow: {e}"))?;
+        let now = Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "INSERT INTO workflows (id, name, definition, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(id) DO UPDATE SET name = ?2, definition = ?3, updated_at = ?4",
+                rusqlite::params![workflow.id, workflow.name, definition, now],
+            )
+            .map_err(|e| format!("Save workflow: {e}"))?;
+        Ok(())
+    }
+
+    /// Load all workflow definitions, by name.
+    pub fn load_workflows(&self) -> Vec<Workflow> {
+        let mut stmt = match self.conn.prepare("SELECT definition FROM workflows ORDER BY name") {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| row.get::<_, String>(0))
+            .ok()
+            .map(|r| r.filter_map(|x| serde_json::from_str(&x.ok()?).ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Load one workflow definition.
+    pub fn get_workflow(&self, id: &str) -> Option<Workflow> {
+        self.conn
+            .query_row("SELECT definition FROM workflows WHERE id = ?1", [id], |row| {
+                row.get::<_, String>(0)
+            })
+            .ok()
+            .and_then(|d| serde_json::from_str(&d).ok())
+    }
+
+    /// Delete a workflow and its run history.
+    pub fn delete_workflow(&self, id: &str) -> Result<bool, String> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM workflows WHERE id = ?1", [id])
+            .map_err(|e| format!("Delete workflow: {e}"))?;
+        self.conn
+            .execute("DELETE FROM workflow_runs WHERE workflow_id = ?1", [id])
+            .map_err(|e| format!("Delete workflow runs: {e}"))?;
+        Ok(deleted > 0)
+    }
+
+    /// Record a workflow run; returns its ID.
+    pub fn record_workflow_run(&self, run: &WorkflowRun) -> Result<i64, String> {
+        let json = serde_json::to_string(run).map_err(|e| format!("Serialize run: {e}"))?;
+        self.conn
+            .execute(
+                "INSERT INTO workflow_runs (workflow_id, status, started_at, run) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![run.workflow_id, run.status, run.started_at.to_rfc3339(), json],
+            )
+            .map_err(|e| format!("Record workflow run: {e}"))?;
+        let id = self.conn.last_insert_rowid();
+        self.conn
+            .execute(
+                "DELETE FROM workflow_runs WHERE workflow_id = ?1 AND id NOT IN
+                 (SELECT id FROM workflow_runs WHERE workflow_id = ?1 ORDER BY id DESC LIMIT ?2)",
+                rusqlite::params![run.workflow_id, RUNS_KEPT],
+            )
+            .map_err(|e| format!("Trim workflow runs: {e}"))?;
+        Ok(id)
+    }
+
+    /// Most recent runs of workflow `id`, newest first.
+    pub fn workflow_runs(&self, id: &str, limit: usize) -> Vec<WorkflowRun> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT id, run FROM workflow_runs WHERE workflow_id = ?1 ORDER BY id DESC LIMIT ?2",
+        ) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map(rusqlite::params![id, limit as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .ok()
+        .map(|r| {
+            r.filter_map(|x| {
+                let (id, json) = x.ok()?;
+                let mut run: WorkflowRun = serde_json::from_str(&json).ok()?;
+                run.id = id;
+                Some(run)
+            })
+            .collect()
+        })
+        .unwrap_or_default()
+    }
+
     // ─── Notifications ──────────────────────────────────────
 
     /// Save a notification.
@@ -458,13 +693,108 @@ impl SchedulerDb {
     pub fn mark_notification_sent(&self, id: i64, channel: &str) -> Result<(), String> {
         self.conn
             .execute(
-                "UPDATE notifications SET status = 'sent', channel = ?1, sent_at = ?2 WHERE id = ?3",
+                "UPDATE notifications SET status = 'sent', channel = ?1, sent_at = ?2, attempts = attempts + 1
+                 WHERE id = ?3",
                 rusqlite::params![channel, Utc::now().to_rfc3339(), id],
             )
             .map_err(|e| format!("Mark sent: {e}"))?;
+        self.conn
+            .execute(
+                "DELETE FROM notifications WHERE status = 'sent' AND id NOT IN
+                 (SELECT id FROM notifications WHERE status = 'sent' ORDER BY id DESC LIMIT ?1)",
+                [SENT_NOTIFICATIONS_KEPT],
+            )
+            .map_err(|e| format!("Trim notifications: {e}"))?;
+        Ok(())
+    }
+
+    /// Record a failed delivery attempt. The notification is tried again at
+    /// `retry_at`, or moved to the dead-letter queue when that is None.
+    pub fn mark_notification_failed(
+        &self,
+        id: i64,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        let status = if retry_at.is_some() { "pending" } else { "dead" };
+        self.conn
+            .execute(
+                "UPDATE notifications SET status = ?1, attempts = attempts + 1, last_error = ?2,
+                 next_attempt_at = ?3 WHERE id = ?4",
+                rusqlite::params![status, error, retry_at.map(|t| t.to_rfc3339()), id],
+            )
+            .map_err(|e| format!("Mark failed: {e}"))?;
         Ok(())
     }
 
+    /// Pending notifications whose next attempt is due at `now`, oldest first.
+    pub fn due_notifications(&self, now: DateTime<Utc>, limit: usize) -> Vec<QueuedNotification> {
+        self.query_notifications(
+            "WHERE status = 'pending' AND (next_attempt_at IS NULL OR next_attempt_at <= ?1)
+             ORDER BY id LIMIT ?2",
+            rusqlite::params![now.to_rfc3339(), limit as i64],
+        )
+    }
+
+    /// Notifications that ran out of delivery attempts, newest first.
+    pub fn dead_letters(&self, limit: usize) -> Vec<QueuedNotification> {
+        self.query_notifications(
+            "WHERE status = 'dead' ORDER BY id DESC LIMIT ?1",
+            rusqlite::params![limit as i64],
+        )
+    }
+
+    /// Queue dead notification `id` for delivery again, with a fresh set of
+    /// attempts. Returns false if no dead notification has that id.
+    pub fn replay_dead_letter(&self, id: i64) -> Result<bool, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE notifications SET status = 'pending', attempts = 0, next_attempt_at = NULL
+                 WHERE id = ?1 AND status = 'dead'",
+                [id],
+            )
+            .map_err(|e| format!("Replay notification: {e}"))?;
+        Ok(changed > 0)
+    }
+
+    fn query_notifications(&self, filter: &str, params: impl rusqlite::Params) -> Vec<QueuedNotification> {
+        let sql = format!(
+            "SELECT id, title, body, priority, source, channel, status, created_at, attempts,
+                    next_attempt_at, last_error
+             FROM notifications {filter}"
+        );
+        let mut stmt = match self.conn.prepare(&sql) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        let parse = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_default()
+        };
+        stmt.query_map(params, |row| {
+            Ok(QueuedNotification {
+                id: row.get(0)?,
+                notification: Notification {
+                    title: row.get(1)?,
+                    body: row.get(2)?,
+                    priority: NotifyPriority::parse(&row.get::<_, String>(3)?),
+                    source: row.get(4)?,
+                    timestamp: parse(row.get(7)?),
+                },
+                channel: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                status: row.get(6)?,
+                attempts: row.get(8)?,
+                next_attempt_at: row.get::<_, Option<String>>(9)?.map(parse),
+                last_error: row.get(10)?,
+            })
+        })
+        .ok()
+        .map(|r| r.filter_map(|x| x.ok()).collect())
+        .unwrap_or_default()
+    }
+
     /// Get pending notifications.
     pub fn pending_notifications(&self) -> Vec<(i64, String, String, String, String)> {
         let mut stmt = match self.conn.prepare(
@@ -515,6 +845,48 @@ impl SchedulerDb {
     }
 }
 
+/// First `OUTPUT_EXCERPT_CHARS` characters of `output`.
+fn excerpt(output: &str) -> String {
+    match output.char_indices().nth(OUTPUT_EXCERPT_CHARS) {
+        Some((i, _)) => format!("{}...", &output[..i]),
+        None => output.to_string(),
+    }
+}
+
+/// One execution of a scheduled task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub id: i64,
+    pub task_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// "success" or "failed".
+    pub status: String,
+    /// 1 for the scheduled run, higher for retries.
+    pub attempt: u32,
+    /// Start of the output, on success.
+    pub output: Option<String>,
+    /// Error message, on failure.
+    pub error: Option<String>,
+}
+
+/// A notification queued for delivery to one channel.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedNotification {
+    pub id: i64,
+    /// Delivery target name ("telegram", "webhook", ...).
+    pub channel: String,
+    #[serde(flatten)]
+    pub notification: Notification,
+    /// "pending", "sent" or "dead".
+    pub status: String,
+    /// Delivery attempts so far.
+    pub attempts: u32,
+    /// When a pending notification is tried next (None = now).
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
 // ─── Workflow Rule data model ──────────────────────────────────
 
 /// A workflow rule: when trigger matches → execute action.
@@ -527,12 +899,13 @@ pub struct WorkflowRule {
     pub trigger_type: String,
     /// Trigger configuration (JSON)
     /// - message_keyword: {"keywords": ["urgent", "help"], "channels": ["telegram", "zalo"]}
+    /// - any_message: {"channels": ["email"], "senders": ["@acme.com"]} (both optional)
     /// - schedule: {"cron": "0 9 * * 1"} (Monday 9am)
     /// - channel_event: {"event": "new_member", "channel": "telegram"}
     /// - threshold: {"metric": "unanswered_messages", "operator": ">", "value": 10}
     /// - time_based: {"after_minutes": 30, "condition": "no_response"}
     pub trigger_config: serde_json::Value,
-    /// Action type: "agent_prompt", "notify", "webhook", "delegate", "send_message"
+    /// Action type: "agent_prompt", "notify", "webhook", "delegate", "send_message", "run_workflow"
     pub action_type: String,
     /// Action configuration (JSON)
     /// - agent_prompt: {"agent": "sales-bot", "prompt": "Summarize unanswered messages"}
@@ -540,6 +913,7 @@ pub struct WorkflowRule {
     /// - webhook: {"url": "https://...", "method": "POST", "body": "..."}
     /// - delegate: {"from_agent": "monitor", "to_agent": "sales", "task": "..."}
     /// - send_message: {"channel": "telegram", "chat_id": "...", "message": "..."}
+    /// - run_workflow: {"workflow_id": "flow-...", "inputs": {"subject": "{{event.text}}"}}
     pub action_config: serde_json::Value,
     pub enabled: bool,
     pub priority: i32,
@@ -627,6 +1001,70 @@ mod tests {
         std::fs::remove_dir_all(&dir).ok();
     }
 
+    #[test]
+    fn test_task_runs_history() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-sched-runs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        let db = SchedulerDb::open(&dir.join("runs.db")).unwrap();
+        let start = Utc::now();
+
+        db.record_run("nightly", start, start, 1, &Err("HTTP 500".into())).unwrap();
+        db.record_run("nightly", start, start, 2, &Ok("x".repeat(1500))).unwrap();
+        db.record_run("other", start, start, 1, &Ok("done".into())).unwrap();
+
+        let runs = db.task_runs("nightly", 10);
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].status.as_str(), runs[0].attempt), ("success", 2));
+        assert_eq!(runs[0].output.as_ref().unwrap().chars().count(), OUTPUT_EXCERPT_CHARS + 3);
+        assert_eq!(runs[1].error.as_deref(), Some("HTTP 500"));
+        assert_eq!(db.task_runs("nightly", 1).len(), 1);
+
+        for _ in 0..RUNS_KEPT {
+            db.record_run("other", start, start, 1, &Ok("done".into())).unwrap();
+        }
+        assert_eq!(db.task_runs("other", 500).len(), RUNS_KEPT as usize);
+
+        db.delete_task("nightly").unwrap();
+        assert!(db.task_runs("nightly", 10).is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_workflows_and_runs() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-sched-flows-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        let db = SchedulerDb::open(&dir.join("flows.db")).unwrap();
+        let mut workflow = Workflow::parse(
+            "id = \"digest\"\nname = \"Digest\"\n[[steps]]\nid = \"a\"\naction = \"set\"\nvalue = \"1\"",
+        )
+        .unwrap();
+        db.save_workflow(&workflow).unwrap();
+        workflow.name = "Daily digest".into();
+        db.save_workflow(&workflow).unwrap();
+        assert_eq!(db.load_workflows().len(), 1);
+        assert_eq!(db.get_workflow("digest").unwrap().name, "Daily digest");
+
+        let now = Utc::now();
+        let run = WorkflowRun {
+            id: 0,
+            workflow_id: "digest".into(),
+            status: "failed".into(),
+            started_at: now,
+            finished_at: now,
+            steps: vec![],
+            vars: Default::default(),
+            error: Some("Step 'a' failed".into()),
+        };
+        let id = db.record_workflow_run(&run).unwrap();
+        let runs = db.workflow_runs("digest", 5);
+        assert_eq!((runs[0].id, runs[0].error.as_deref()), (id, Some("Step 'a' failed")));
+
+        assert!(db.delete_workflow("digest").unwrap());
+        assert!(db.get_workflow("digest").is_none());
+        assert!(db.workflow_runs("digest", 5).is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_workflow_rule_cooldown() {
         let mut rule = WorkflowRule::new(
@@ -13,6 +13,7 @@ use serde::{Deserialize, Serialize};
 /// Retry policy — lightweight, configurable per-task.
 /// Controls exponential backoff behavior on task failure.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RetryPolicy {
     /// Max retry attempts (0 = no retry, default = 3)
     pub max_retries: u32,
@@ -130,8 +131,27 @@ pub enum TaskType {
     Once { at: DateTime<Utc> },
     /// Run on a cron schedule (lightweight cron expression).
     Cron { expression: String },
-    /// Run every N seconds.
-    Interval { every_secs: u64 },
+    /// Run every N seconds. With an `anchor`, runs land on
+    /// `anchor + k * every_secs` boundaries instead of drifting with fire time.
+    Interval {
+        every_secs: u64,
+        #[serde(default)]
+        anchor: Option<DateTime<Utc>>,
+    },
+    /// Run once each time the scheduler engine starts (`@reboot`).
+    Reboot,
+}
+
+/// Next `anchor + k * every_secs` boundary strictly after `now`.
+pub fn next_aligned_run(
+    anchor: DateTime<Utc>,
+    every_secs: u64,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let every = every_secs.max(1) as i64;
+    let elapsed = (now - anchor).num_seconds();
+    let periods = elapsed.div_euclid(every) + 1;
+    anchor + chrono::Duration::seconds(periods * every)
 }
 
 /// Task status.
@@ -179,7 +199,10 @@ impl Task {
             id: uuid_v4(),
             name: name.to_string(),
             action,
-            task_type: TaskType::Interval { every_secs },
+            task_type: TaskType::Interval {
+                every_secs,
+                anchor: None,
+            },
             status: TaskStatus::Pending,
             notify_via: None,
             agent_name: None,
@@ -195,6 +218,32 @@ impl Task {
         }
     }
 
+    /// Create an interval task aligned to `anchor` (e.g. every 3600s anchored
+    /// at midnight fires on the hour).
+    pub fn interval_anchored(
+        name: &str,
+        every_secs: u64,
+        anchor: DateTime<Utc>,
+        action: TaskAction,
+    ) -> Self {
+        let mut task = Self::interval(name, every_secs, action);
+        task.task_type = TaskType::Interval {
+            every_secs,
+            anchor: Some(anchor),
+        };
+        task.next_run = Some(next_aligned_run(anchor, every_secs, Utc::now()));
+        task
+    }
+
+    /// Create a task that fires once per scheduler startup.
+    /// `next_run` stays unset until the engine arms it at startup.
+    pub fn reboot(name: &str, action: TaskAction) -> Self {
+        let mut task = Self::interval(name, 0, action);
+        task.task_type = TaskType::Reboot;
+        task.next_run = None;
+        task
+    }
+
     /// Create a cron-scheduled task.
     pub fn cron(name: &str, expression: &str, action: TaskAction) -> Self {
         Self {
@@ -412,6 +461,23 @@ mod tests {
         assert!(!task.should_run());
     }
 
+    #[test]
+    fn test_next_aligned_run() {
+        let anchor = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let now = DateTime::parse_from_rfc3339("2026-03-05T10:17:42Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let next = next_aligned_run(anchor, 3600, now);
+        assert_eq!(next.to_rfc3339(), "2026-03-05T11:00:00+00:00");
+        // Exactly on a boundary → the following one.
+        assert_eq!(next_aligned_run(anchor, 3600, next), next + chrono::Duration::hours(1));
+        // Anchor in the future still aligns to its grid.
+        let future = now + chrono::Duration::seconds(90);
+        assert_eq!(next_aligned_run(future, 60, now), future - chrono::Duration::seconds(60));
+    }
+
     #[test]
     fn test_backward_compatible_deserialize() {
         // Old format without retry fields — should still deserialize with defaults
@@ -434,5 +500,9 @@ mod tests {
         assert_eq!(task.retry.max_retries, 3); // default
         assert_eq!(task.fail_count, 0); // default
         assert_eq!(task.last_error, None); // default
+        assert!(matches!(
+            task.task_type,
+            TaskType::Interval { every_secs: 60, anchor: None }
+        ));
     }
 }
@@ -0,0 +1,208 @@
+//! Event triggers — feed events from the bus to the workflow rules and run
+//! the actions they match, e.g. "when an email from @acme.com arrives, run
+//! workflow Y".
+//!
+//! Rules are read from the scheduler database on every event, so rules
+//! added or disabled through the API apply immediately. Supported actions:
+//! - `run_workflow`: {"workflow_id": "...", "inputs": {"name": "value"}}
+//! - `agent_prompt`, `notify`, `webhook`: run as a one-step workflow
+//!
+//! Every run gets the trigger as variables: `event.type`, `event.channel`,
+//! and each field of the event data (`event.sender`, `event.text`, ...).
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use bizclaw_core::events::{Event, EventBus};
+use tokio::sync::{Mutex, broadcast};
+
+use crate::engine::SchedulerEngine;
+use crate::flow::{Workflow, WorkflowRunner};
+use crate::persistence::SchedulerDb;
+use crate::workflow::{WorkflowAction, WorkflowEngine, WorkflowEvent};
+
+/// Rule actions that run as a single workflow step.
+const STEP_ACTIONS: &[&str] = &["agent_prompt", "notify", "webhook"];
+
+/// Listen on `bus` until it closes, running the rule actions each event
+/// matches with `runner`.
+pub async fn spawn_event_triggers(engine: Arc<Mutex<SchedulerEngine>>, bus: EventBus, runner: WorkflowRunner) {
+    let mut rx = bus.subscribe();
+    tracing::info!("⚡ Workflow rules listening for events");
+    loop {
+        match rx.recv().await {
+            Ok(event) => {
+                handle_event(&engine, &runner, &event).await;
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("⚠️ Workflow rules fell behind, skipped {skipped} events");
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Run the actions of every enabled rule matching `event`. Returns how
+/// many ran.
+pub async fn handle_event(engine: &Mutex<SchedulerEngine>, runner: &WorkflowRunner, event: &Event) -> usize {
+    let Some(event) = WorkflowEvent::from_bus(event) else {
+        return 0;
+    };
+    let planned: Vec<(Workflow, BTreeMap<String, String>)> = {
+        let engine = engine.lock().await;
+        let Some(db) = engine.db() else {
+            return 0;
+        };
+        let rules = WorkflowEngine::new(db.load_workflow_rules());
+        rules
+            .evaluate(&event)
+            .iter()
+            .filter_map(|action| match action_workflow(action, db) {
+                Ok(planned) => {
+                    if let Err(e) = db.record_workflow_trigger(&action.rule_id) {
+                        tracing::warn!("⚠️ Failed to record trigger of rule '{}': {e}", action.rule_name);
+                    }
+                    Some(planned)
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ Rule '{}' not run: {e}", action.rule_name);
+                    None
+                }
+            })
+            .collect()
+    };
+
+    // Run without holding the scheduler lock
+    for (workflow, inputs) in &planned {
+        let mut run = runner.run(workflow, inputs.clone()).await;
+        tracing::info!("⚡ Workflow '{}' triggered by {}: {}", workflow.name, event.event_type, run.status);
+        engine.lock().await.finish_workflow_run(workflow, &mut run);
+    }
+    planned.len()
+}
+
+/// The workflow a rule action runs, with its inputs.
+fn action_workflow(action: &WorkflowAction, db: &SchedulerDb) -> Result<(Workflow, BTreeMap<String, String>), String> {
+    let mut inputs = event_vars(&action.trigger_event);
+    if action.action_type == "run_workflow" {
+        let id = action.config["workflow_id"].as_str().unwrap_or("");
+        let workflow = db.get_workflow(id).ok_or_else(|| format!("workflow '{id}' not found"))?;
+        if let Some(extra) = action.config["inputs"].as_object() {
+            for (name, value) in extra {
+                inputs.insert(name.clone(), value.as_str().map(String::from).unwrap_or_else(|| value.to_string()));
+            }
+        }
+        return Ok((workflow, inputs));
+    }
+    if !STEP_ACTIONS.contains(&action.action_type.as_str()) {
+        return Err(format!("action '{}' can't run from events", action.action_type));
+    }
+    let mut step = match &action.config {
+        serde_json::Value::Object(config) => config.clone(),
+        _ => serde_json::Map::new(),
+    };
+    step.insert("id".into(), "action".into());
+    step.insert("action".into(), action.action_type.clone().into());
+    // No id: ad-hoc runs aren't recorded
+    let workflow = Workflow::from_value(serde_json::json!({"name": action.rule_name, "steps": [step]}))?;
+    Ok((workflow, inputs))
+}
+
+/// `event.type`, `event.channel` and `event.<field>` for each data field.
+fn event_vars(event: &WorkflowEvent) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::from([
+        ("event.type".to_string(), event.event_type.clone()),
+        ("event.channel".to_string(), event.source.clone()),
+    ]);
+    if let Some(data) = event.data.as_object() {
+        for (name, value) in data {
+            let value = value.as_str().map(String::from).unwrap_or_else(|| value.to_string());
+            vars.insert(format!("event.{name}"), value);
+        }
+    }
+    vars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::WorkflowRule;
+
+    fn email(sender: &str) -> Event {
+        Event::MessageReceived {
+            channel: "email".into(),
+            sender: sender.into(),
+            chat_id: "inbox".into(),
+            text: "Invoice #42 attached".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_from_sender_runs_workflow() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-triggers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let engine = Mutex::new(SchedulerEngine::new(&dir));
+        let bus = EventBus::default();
+        let mut finished = bus.subscribe();
+        {
+            let mut eng = engine.lock().await;
+            eng.set_event_bus(bus.clone());
+            let db = eng.db().unwrap();
+            let workflow = Workflow::parse(
+                r#"
+                id = "flow-invoices"
+                name = "File invoice"
+                [[steps]]
+                id = "note"
+                action = "notify"
+                message = "{{event.sender}} sent {{subject}}"
+                "#,
+            )
+            .unwrap();
+            db.save_workflow(&workflow).unwrap();
+            let rule = WorkflowRule::new(
+                "acme invoices",
+                "any_message",
+                serde_json::json!({"channels": ["email"], "senders": ["@acme.com"]}),
+                "run_workflow",
+                serde_json::json!({"workflow_id": "flow-invoices", "inputs": {"subject": "{{event.text}}"}}),
+            );
+            db.save_workflow_rule(&rule).unwrap();
+        }
+
+        let runner = WorkflowRunner::new();
+        assert_eq!(handle_event(&engine, &runner, &email("someone@other.com")).await, 0);
+        assert_eq!(handle_event(&engine, &runner, &email("billing@acme.com")).await, 1);
+        // Cooldown: the same rule doesn't fire again right away
+        assert_eq!(handle_event(&engine, &runner, &email("billing@acme.com")).await, 0);
+
+        let eng = engine.lock().await;
+        let runs = eng.db().unwrap().workflow_runs("flow-invoices", 10);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].vars["note.output"], "billing@acme.com sent Invoice #42 attached");
+        assert_eq!(eng.router.history().len(), 1);
+        assert!(matches!(finished.try_recv(), Ok(Event::WorkflowFinished { status, .. }) if status == "success"));
+        drop(eng);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_step_action_becomes_one_step_workflow() {
+        let action = WorkflowAction {
+            rule_id: "r1".into(),
+            rule_name: "ping".into(),
+            action_type: "notify".into(),
+            config: serde_json::json!({"message": "New mail from {{event.sender}}"}),
+            trigger_event: WorkflowEvent::from_bus(&email("a@b.com")).unwrap(),
+        };
+        let db = SchedulerDb::open(std::path::Path::new(":memory:")).unwrap();
+        let (workflow, inputs) = action_workflow(&action, &db).unwrap();
+        assert!(workflow.id.is_empty());
+        assert_eq!(workflow.steps[0].action.kind(), "notify");
+        assert_eq!(inputs["event.sender"], "a@b.com");
+        assert_eq!(inputs["event.channel"], "email");
+
+        let delegate = WorkflowAction { action_type: "delegate".into(), ..action };
+        assert!(action_workflow(&delegate, &db).is_err());
+    }
+}
@@ -14,6 +14,7 @@
 //! ```
 
 use crate::persistence::{SchedulerDb, WorkflowRule};
+use bizclaw_core::events::Event;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
@@ -54,6 +55,23 @@ impl WorkflowEvent {
         }
     }
 
+    /// The rule event for a bus event: received messages and finished
+    /// tasks. Other bus events don't trigger rules.
+    pub fn from_bus(event: &Event) -> Option<Self> {
+        match event {
+            Event::MessageReceived { channel, sender, chat_id, text } => {
+                Some(Self::message(channel, sender, text, chat_id))
+            }
+            Event::TaskFinished { task_id, task, success } => {
+                let mut event = Self::schedule(task);
+                event.data["task_id"] = task_id.clone().into();
+                event.data["success"] = (*success).into();
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+
     /// Create a metric event.
     pub fn metric(name: &str, value: f64) -> Self {
         Self {
@@ -161,7 +179,7 @@ impl WorkflowEngine {
             "threshold" => self.matches_threshold(rule, event),
             "schedule" => event.event_type == "schedule",
             "startup" => event.event_type == "startup",
-            "any_message" => event.event_type == "message",
+            "any_message" => event.event_type == "message" && self.matches_message_filters(rule, event),
             _ => false,
         }
     }
@@ -182,18 +200,10 @@ impl WorkflowEngine {
             })
             .unwrap_or_default();
 
-        if keywords.is_empty() {
+        if keywords.is_empty() || !self.matches_message_filters(rule, event) {
             return false;
         }
 
-        // Check channel filter
-        if let Some(channels) = rule.trigger_config["channels"].as_array() {
-            let allowed: Vec<&str> = channels.iter().filter_map(|v| v.as_str()).collect();
-            if !allowed.is_empty() && !allowed.contains(&event.source.as_str()) {
-                return false;
-            }
-        }
-
         // Match mode: "any" (default) or "all"
         let mode = rule.trigger_config["match_mode"]
             .as_str()
@@ -204,6 +214,24 @@ impl WorkflowEngine {
         }
     }
 
+    /// Optional message filters: `channels` (exact names) and `senders`
+    /// (case-insensitive substrings, so "@acme.com" matches a whole domain).
+    fn matches_message_filters(&self, rule: &WorkflowRule, event: &WorkflowEvent) -> bool {
+        let list = |key: &str| -> Vec<String> {
+            rule.trigger_config[key]
+                .as_array()
+                .map(|arr| arr.iter().filter_map(|v| v.as_str()).map(str::to_lowercase).collect())
+                .unwrap_or_default()
+        };
+        let channels = list("channels");
+        if !channels.is_empty() && !channels.contains(&event.source.to_lowercase()) {
+            return false;
+        }
+        let senders = list("senders");
+        let sender = event.data["sender"].as_str().unwrap_or("").to_lowercase();
+        senders.is_empty() || senders.iter().any(|s| sender.contains(s.as_str()))
+    }
+
     /// Match: channel event (new member, bot added to group, etc.).
     fn matches_channel_event(&self, rule: &WorkflowRule, event: &WorkflowEvent) -> bool {
         if event.event_type != "channel_event" {
@@ -308,6 +336,30 @@ mod tests {
         assert!(engine.evaluate(&event2).is_empty());
     }
 
+    #[test]
+    fn test_message_from_sender() {
+        let rule = WorkflowRule::new(
+            "acme-mail",
+            "any_message",
+            serde_json::json!({"channels": ["email"], "senders": ["@Acme.com"]}),
+            "run_workflow",
+            serde_json::json!({"workflow_id": "flow-1"}),
+        );
+        let engine = WorkflowEngine::new(vec![rule]);
+        let from = |channel: &str, sender: &str| {
+            let bus = Event::MessageReceived {
+                channel: channel.into(),
+                sender: sender.into(),
+                chat_id: "inbox".into(),
+                text: "Invoice".into(),
+            };
+            engine.evaluate(&WorkflowEvent::from_bus(&bus).unwrap()).len()
+        };
+        assert_eq!(from("email", "billing@acme.com"), 1);
+        assert_eq!(from("email", "spam@other.com"), 0);
+        assert_eq!(from("telegram", "billing@acme.com"), 0);
+    }
+
     #[test]
     fn test_threshold_match() {
         let rule = WorkflowRule::new(
@@ -105,6 +105,7 @@ mod tests {
             allowed_commands: commands.iter().map(|s| s.to_string()).collect(),
             forbidden_paths: paths.iter().map(|s| s.to_string()).collect(),
             workspace_only: false,
+            ..Default::default()
         }
     }
 
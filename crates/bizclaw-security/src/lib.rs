@@ -7,17 +7,69 @@ pub mod secrets;
 
 use async_trait::async_trait;
 use bizclaw_core::config::AutonomyConfig;
-use bizclaw_core::error::Result;
+use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::SecurityPolicy;
+use bizclaw_core::traits::security::canonicalize_lenient;
+use regex::Regex;
+use std::path::Path;
 
 /// Default security policy based on configuration.
 pub struct DefaultSecurityPolicy {
     config: AutonomyConfig,
+    filters: Vec<CompiledFilter>,
+}
+
+/// An `[[autonomy.argument_filters]]` entry with its patterns compiled.
+struct CompiledFilter {
+    tool: String,
+    argument: String,
+    deny: Vec<Regex>,
+    deny_absolute_paths: bool,
 }
 
 impl DefaultSecurityPolicy {
     pub fn new(config: AutonomyConfig) -> Self {
-        Self { config }
+        let filters = config
+            .argument_filters
+            .iter()
+            .map(|f| CompiledFilter {
+                tool: f.tool.clone(),
+                argument: f.argument.clone(),
+                deny: f
+                    .deny
+                    .iter()
+                    .filter_map(|p| match Regex::new(p) {
+                        Ok(re) => Some(re),
+                        Err(e) => {
+                            tracing::warn!("Security: ignoring invalid argument filter '{}': {}", p, e);
+                            None
+                        }
+                    })
+                    .collect(),
+                deny_absolute_paths: f.deny_absolute_paths,
+            })
+            .collect();
+        Self { config, filters }
+    }
+}
+
+/// Whether `value` names an absolute or home-relative path.
+fn is_absolute_path(value: &str) -> bool {
+    let v = value.trim();
+    let b = v.as_bytes();
+    v.starts_with('/')
+        || v.starts_with('~')
+        || v.starts_with('\\')
+        || (b.len() >= 3 && b[0].is_ascii_alphabetic() && b[1] == b':' && (b[2] == b'\\' || b[2] == b'/'))
+}
+
+/// Every string inside `value`, recursively.
+fn collect_strings<'a>(value: &'a serde_json::Value, out: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => out.push(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| collect_strings(v, out)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| collect_strings(v, out)),
+        _ => {}
     }
 }
 
@@ -33,10 +85,17 @@ impl SecurityPolicy for DefaultSecurityPolicy {
     }
 
     async fn check_path(&self, path: &str) -> Result<bool> {
+        // Compare whole components on both the literal and the canonical
+        // path, so `..` and symlinks cannot step around a forbidden entry.
         let expanded = shellexpand::tilde(path).to_string();
+        let expanded = Path::new(&expanded);
+        let canonical = canonicalize_lenient(expanded);
         let forbidden = self.config.forbidden_paths.iter().any(|p| {
             let exp = shellexpand::tilde(p).to_string();
-            expanded.starts_with(&exp)
+            let exp = Path::new(&exp);
+            expanded.starts_with(exp)
+                || canonical.starts_with(exp)
+                || canonical.starts_with(canonicalize_lenient(exp))
         });
         if forbidden {
             tracing::warn!("Security: path '{}' is forbidden", path);
@@ -44,7 +103,115 @@ impl SecurityPolicy for DefaultSecurityPolicy {
         Ok(!forbidden)
     }
 
+    async fn check_domain(&self, host: &str) -> Result<bool> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let allowed = self.config.allowed_domains.is_empty()
+            || self.config.allowed_domains.iter().any(|d| {
+                let d = d.trim().trim_start_matches("*.").to_lowercase();
+                d == "*" || host == d || host.ends_with(&format!(".{d}"))
+            });
+        if !allowed {
+            tracing::warn!("Security: domain '{}' not in allowed domains", host);
+        }
+        Ok(allowed)
+    }
+
+    async fn check_tool_arguments(&self, tool: &str, arguments: &serde_json::Value) -> Result<()> {
+        for filter in self.filters.iter().filter(|f| f.tool == "*" || f.tool == tool) {
+            let mut values = Vec::new();
+            if filter.argument.is_empty() {
+                collect_strings(arguments, &mut values);
+            } else if let Some(v) = arguments.get(&filter.argument) {
+                collect_strings(v, &mut values);
+            }
+            for value in values {
+                if let Some(re) = filter.deny.iter().find(|re| re.is_match(value)) {
+                    tracing::warn!("Security: {} argument matched deny pattern '{}'", tool, re);
+                    return Err(BizClawError::PermissionDenied(format!(
+                        "{tool} arguments match the denied pattern '{re}'"
+                    )));
+                }
+                if filter.deny_absolute_paths && is_absolute_path(value) {
+                    tracing::warn!("Security: {} argument uses absolute path '{}'", tool, value);
+                    return Err(BizClawError::PermissionDenied(format!(
+                        "{tool} may not use absolute paths ('{value}')"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn autonomy_level(&self) -> &str {
         &self.config.level
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_domain() {
+        let open = DefaultSecurityPolicy::new(AutonomyConfig::default());
+        assert!(open.check_domain("anything.example").await.unwrap());
+
+        let policy = DefaultSecurityPolicy::new(AutonomyConfig {
+            allowed_domains: vec!["vnexpress.net".into(), "*.gov.vn".into()],
+            ..Default::default()
+        });
+        assert!(policy.check_domain("vnexpress.net").await.unwrap());
+        assert!(policy.check_domain("E.VnExpress.net.").await.unwrap());
+        assert!(policy.check_domain("chinhphu.gov.vn").await.unwrap());
+        assert!(!policy.check_domain("notvnexpress.net").await.unwrap());
+        assert!(!policy.check_domain("example.com").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_path_components() {
+        let policy = DefaultSecurityPolicy::new(AutonomyConfig {
+            forbidden_paths: vec!["/etc".into()],
+            ..Default::default()
+        });
+        assert!(!policy.check_path("/etc/passwd").await.unwrap());
+        assert!(!policy.check_path("/tmp/../etc/shadow").await.unwrap());
+        assert!(policy.check_path("/etcetera/notes.txt").await.unwrap());
+        assert!(policy.check_path("/tmp/notes.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_tool_arguments() {
+        use bizclaw_core::config::ArgumentFilter;
+        use serde_json::json;
+
+        let policy = DefaultSecurityPolicy::new(AutonomyConfig {
+            argument_filters: vec![
+                ArgumentFilter {
+                    tool: "shell".into(),
+                    argument: "command".into(),
+                    deny: vec![r"rm\s+-[a-z]*r[a-z]*f".into(), "[invalid".into()],
+                    ..Default::default()
+                },
+                ArgumentFilter {
+                    tool: "*".into(),
+                    deny_absolute_paths: true,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        assert!(policy.check_tool_arguments("shell", &json!({"command": "ls -la"})).await.is_ok());
+        assert!(matches!(
+            policy.check_tool_arguments("shell", &json!({"command": "rm -rf build"})).await,
+            Err(BizClawError::PermissionDenied(_))
+        ));
+        assert!(policy.check_tool_arguments("file", &json!({"path": "notes/a.md"})).await.is_ok());
+        for path in ["/etc/passwd", "~/.ssh/id_rsa", "C:\\Windows\\win.ini"] {
+            assert!(
+                policy.check_tool_arguments("file", &json!({"action": "read", "path": path})).await.is_err(),
+                "{path}"
+            );
+        }
+        assert!(policy.check_tool_arguments("glob", &json!({"patterns": ["/root/*"]})).await.is_err());
+    }
+}
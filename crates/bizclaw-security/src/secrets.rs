@@ -1,23 +1,33 @@
 //! Encrypted secrets management.
 //!
 //! Provides secure storage and retrieval of API keys, tokens, and
-//! other sensitive configuration values using AES-256-ECB encryption
-//! with a machine-specific key derived from hostname + username.
+//! other sensitive configuration values. With `[secrets] encrypt` the
+//! store is sealed with AES-256-GCM under a random machine key kept in
+//! `secrets.key` (mode 0600) next to it. Config values refer to secrets as
+//! `${secret:<name>}`; [`load_config`] resolves them and moves plaintext
+//! credentials out of the config file into the store.
+//!
+//! Stores written by older versions (AES-256-ECB under a key derived from
+//! hostname + username) are still read, and rewritten on the next save.
 
 use aes::Aes256;
-use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
+use aes::cipher::{BlockDecrypt, KeyInit, generic_array::GenericArray};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use bizclaw_core::config::{BizClawConfig, SECRET_REF_PREFIX, secret_ref};
 use bizclaw_core::error::{BizClawError, Result};
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Marks a store sealed with AES-256-GCM; base64 of nonce + ciphertext follows.
+const GCM_HEADER: &str = "bizclaw-secrets:v2:";
+
 /// Manages encrypted secrets stored on disk.
 pub struct SecretStore {
     secrets: HashMap<String, String>,
     secrets_path: PathBuf,
     encrypt: bool,
-    key: [u8; 32],
 }
 
 impl SecretStore {
@@ -28,10 +38,25 @@ impl SecretStore {
             secrets: HashMap::new(),
             secrets_path,
             encrypt,
-            key: derive_machine_key(),
         }
     }
 
+    /// Open the store in `dir` (`secrets.enc`), loading it if it exists.
+    pub fn open(dir: &Path, encrypt: bool) -> Result<Self> {
+        let mut store = Self {
+            secrets: HashMap::new(),
+            secrets_path: dir.join("secrets.enc"),
+            encrypt,
+        };
+        store.load()?;
+        Ok(store)
+    }
+
+    /// The machine key file, next to the store.
+    fn key_path(&self) -> PathBuf {
+        self.secrets_path.with_file_name("secrets.key")
+    }
+
     /// Load secrets from disk.
     pub fn load(&mut self) -> Result<()> {
         if !self.secrets_path.exists() {
@@ -39,18 +64,30 @@ impl SecretStore {
         }
 
         let content = std::fs::read_to_string(&self.secrets_path)?;
+        let content = content.trim();
 
-        let json_str = if self.encrypt {
-            // Decrypt from base64 → AES-256 → JSON
+        let json_str = if let Some(sealed) = content.strip_prefix(GCM_HEADER) {
+            let sealed = BASE64
+                .decode(sealed)
+                .map_err(|e| BizClawError::Security(format!("Base64 decode failed: {e}")))?;
+            let key = read_key(&self.key_path())?.ok_or_else(|| {
+                BizClawError::Security(format!(
+                    "Secrets are encrypted but the key {} is missing",
+                    self.key_path().display()
+                ))
+            })?;
+            open_gcm(&sealed, &key)?
+        } else if content.starts_with('{') {
+            content.to_string()
+        } else {
+            // Store written by an older version: AES-256-ECB, machine-derived key.
             let encrypted = BASE64
-                .decode(content.trim())
+                .decode(content)
                 .map_err(|e| BizClawError::Security(format!("Base64 decode failed: {e}")))?;
-            let decrypted = decrypt_aes256(&encrypted, &self.key);
+            let decrypted = decrypt_aes256(&encrypted, &derive_machine_key());
             String::from_utf8(decrypted).map_err(|e| {
                 BizClawError::Security(format!("Decryption produced invalid UTF-8: {e}"))
             })?
-        } else {
-            content
         };
 
         self.secrets = serde_json::from_str(&json_str)
@@ -73,33 +110,20 @@ impl SecretStore {
         let json = serde_json::to_string_pretty(&self.secrets)?;
 
         let content = if self.encrypt {
-            // Encrypt: JSON → AES-256 → base64
-            let encrypted = encrypt_aes256(json.as_bytes(), &self.key);
-            BASE64.encode(&encrypted)
+            let key = match read_key(&self.key_path())? {
+                Some(key) => key,
+                None => {
+                    let key: [u8; 32] = rand::random();
+                    write_private(&self.key_path(), &BASE64.encode(key))?;
+                    key
+                }
+            };
+            format!("{GCM_HEADER}{}", BASE64.encode(seal_gcm(json.as_bytes(), &key)?))
         } else {
             json
         };
 
-        // Set restrictive permissions on Unix (0600)
-        #[cfg(unix)]
-        {
-            use std::io::Write;
-            use std::os::unix::fs::OpenOptionsExt;
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .mode(0o600)
-                .open(&self.secrets_path)?;
-            file.write_all(content.as_bytes())?;
-            Ok(())
-        }
-
-        #[cfg(not(unix))]
-        {
-            std::fs::write(&self.secrets_path, content)?;
-            Ok(())
-        }
+        write_private(&self.secrets_path, &content)
     }
 
     /// Get a secret value.
@@ -128,13 +152,160 @@ impl SecretStore {
             secrets: HashMap::new(),
             secrets_path: path.to_path_buf(),
             encrypt: false,
-            key: derive_machine_key(),
         };
         store.load()?;
         Ok(store)
     }
 }
 
+/// Move plaintext credentials in `config` into `store`, replacing them with
+/// `${secret:<name>}` references. Returns the names stored.
+pub fn migrate_plaintext(config: &mut BizClawConfig, store: &mut SecretStore) -> Vec<String> {
+    let mut moved = Vec::new();
+    for (name, value) in config.secret_fields_mut() {
+        if value.is_empty() || value.contains(SECRET_REF_PREFIX) {
+            continue;
+        }
+        // Reuse a secret holding the same value; never overwrite a different one.
+        let mut unique = name.clone();
+        let mut n = 1;
+        while store.get(&unique).is_some_and(|v| v != value.as_str()) {
+            n += 1;
+            unique = format!("{name}_{n}");
+        }
+        store.set(&unique, value);
+        *value = secret_ref(&unique);
+        moved.push(unique);
+    }
+    moved
+}
+
+/// `config` as TOML to write to `path`. With `[secrets] encrypt`, its
+/// plaintext credentials are moved into the store next to `path` first, so
+/// new credentials never reach the file in plaintext.
+pub fn config_toml(config: &BizClawConfig, path: &Path) -> Result<String> {
+    if !config.secrets.encrypt {
+        return config.to_toml();
+    }
+    let mut config = config.clone();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut store = SecretStore::open(dir, true)?;
+    if !migrate_plaintext(&mut config, &mut store).is_empty() {
+        store.save()?;
+    }
+    config.to_toml()
+}
+
+/// Load the config at `path` with its `${secret:…}` references resolved
+/// from the store next to it. With `[secrets] encrypt`, plaintext
+/// credentials are first moved into the store and the file rewritten.
+pub fn load_config(path: &Path) -> Result<BizClawConfig> {
+    let mut config = BizClawConfig::load_from(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut store = SecretStore::open(dir, config.secrets.encrypt)?;
+
+    if config.secrets.encrypt {
+        let moved = migrate_plaintext(&mut config, &mut store);
+        if !moved.is_empty() {
+            store.save()?;
+            let tmp = path.with_extension("toml.migrate.tmp");
+            let written = config
+                .to_toml()
+                .and_then(|content| Ok(std::fs::write(&tmp, content)?))
+                .and_then(|_| Ok(std::fs::rename(&tmp, path)?));
+            match written {
+                Ok(()) => tracing::info!(
+                    "🔐 Moved {} plaintext secrets into {}: {}",
+                    moved.len(),
+                    store.secrets_path.display(),
+                    moved.join(", ")
+                ),
+                Err(e) => tracing::warn!("Secrets stored, but rewriting {} failed: {e}", path.display()),
+            }
+        }
+    }
+
+    for name in config.resolve_secrets(|name| store.get(name).map(String::from))? {
+        tracing::warn!("Config refers to unknown secret '{name}'");
+    }
+    Ok(config)
+}
+
+/// Write `content` to `path`, readable only by the owner on Unix.
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    // Set restrictive permissions on Unix (0600)
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Read the base64 machine key at `path`, if present.
+fn read_key(path: &Path) -> Result<Option<[u8; 32]>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let encoded = std::fs::read_to_string(path)?;
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| BizClawError::Security(format!("Invalid secrets key: {e}")))?;
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| BizClawError::Security("Secrets key must be 32 bytes".into()))?;
+    Ok(Some(key))
+}
+
+fn gcm_key(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key)
+        .map_err(|_| BizClawError::Security("Invalid AES-256-GCM key".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// AES-256-GCM encrypt under a random nonce; returns nonce + ciphertext + tag.
+fn seal_gcm(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let mut sealed = data.to_vec();
+    gcm_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+        .map_err(|_| BizClawError::Security("Encryption failed".into()))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt and authenticate the output of [`seal_gcm`].
+fn open_gcm(data: &[u8], key: &[u8; 32]) -> Result<String> {
+    if data.len() < NONCE_LEN {
+        return Err(BizClawError::Security("Secrets file is truncated".into()));
+    }
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| BizClawError::Security("Invalid nonce".into()))?;
+    let mut buf = sealed.to_vec();
+    let plain = gcm_key(key)?
+        .open_in_place(nonce, Aad::empty(), &mut buf)
+        .map_err(|_| {
+            BizClawError::Security("Secrets failed to decrypt — wrong key or tampered file".into())
+        })?;
+    String::from_utf8(plain.to_vec())
+        .map_err(|e| BizClawError::Security(format!("Decryption produced invalid UTF-8: {e}")))
+}
+
 /// Derive a machine-specific AES-256 key from hostname + username.
 fn derive_machine_key() -> [u8; 32] {
     let hostname = hostname::get()
@@ -152,8 +323,10 @@ fn derive_machine_key() -> [u8; 32] {
     key
 }
 
-/// AES-256-ECB encrypt with PKCS7 padding.
+/// AES-256-ECB encrypt with PKCS7 padding (the legacy store format).
+#[cfg(test)]
 fn encrypt_aes256(data: &[u8], key: &[u8; 32]) -> Vec<u8> {
+    use aes::cipher::BlockEncrypt;
     let cipher = Aes256::new(GenericArray::from_slice(key));
     let block_size = 16;
 
@@ -172,7 +345,7 @@ fn encrypt_aes256(data: &[u8], key: &[u8; 32]) -> Vec<u8> {
     encrypted
 }
 
-/// AES-256-ECB decrypt with PKCS7 unpadding.
+/// AES-256-ECB decrypt with PKCS7 unpadding (the legacy store format).
 fn decrypt_aes256(data: &[u8], key: &[u8; 32]) -> Vec<u8> {
     let cipher = Aes256::new(GenericArray::from_slice(key));
     let block_size = 16;
@@ -229,4 +402,71 @@ mod tests {
         assert_eq!(store.remove("api_key"), Some("sk-test-12345".into()));
         assert_eq!(store.get("api_key"), None);
     }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-secrets-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_gcm_store_roundtrip_and_legacy_read() {
+        let dir = temp_dir();
+        let mut store = SecretStore::open(&dir, true).unwrap();
+        store.set("bot_token", "123456:ABC-DEF");
+        store.save().unwrap();
+
+        let on_disk = std::fs::read_to_string(dir.join("secrets.enc")).unwrap();
+        assert!(on_disk.starts_with(GCM_HEADER));
+        assert!(!on_disk.contains("123456"));
+        let reopened = SecretStore::open(&dir, true).unwrap();
+        assert_eq!(reopened.get("bot_token"), Some("123456:ABC-DEF"));
+
+        // A tampered file is refused rather than misread.
+        let mut sealed = BASE64.decode(on_disk.trim_start_matches(GCM_HEADER)).unwrap();
+        *sealed.last_mut().unwrap() ^= 1;
+        std::fs::write(dir.join("secrets.enc"), format!("{GCM_HEADER}{}", BASE64.encode(sealed))).unwrap();
+        assert!(SecretStore::open(&dir, true).is_err());
+
+        // Stores from older versions still load.
+        let legacy = encrypt_aes256(br#"{"api_key":"sk-old"}"#, &derive_machine_key());
+        std::fs::write(dir.join("secrets.enc"), BASE64.encode(legacy)).unwrap();
+        assert_eq!(SecretStore::open(&dir, true).unwrap().get("api_key"), Some("sk-old"));
+    }
+
+    #[test]
+    fn test_load_config_migrates_plaintext() {
+        let dir = temp_dir();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "api_key = \"sk-plain\"\n[channel.telegram]\nenabled = true\nbot_token = \"123:XYZ\"\n",
+        )
+        .unwrap();
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.api_key, "sk-plain");
+        assert_eq!(config.channel.telegram.as_ref().unwrap().bot_token, "123:XYZ");
+
+        let rewritten = std::fs::read_to_string(&path).unwrap();
+        assert!(rewritten.contains("${secret:api_key}"), "{rewritten}");
+        assert!(rewritten.contains("${secret:telegram_bot_token}"), "{rewritten}");
+        assert!(!rewritten.contains("sk-plain") && !rewritten.contains("123:XYZ"));
+        let store = std::fs::read_to_string(dir.join("secrets.enc")).unwrap();
+        assert!(!store.contains("sk-plain"));
+
+        // Loading again resolves the references without moving anything.
+        let again = load_config(&path).unwrap();
+        assert_eq!(again.api_key, "sk-plain");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), rewritten);
+
+        // A credential set later is stored before the config is written.
+        let mut updated = again;
+        updated.channel.telegram.as_mut().unwrap().bot_token = "456:NEW".into();
+        let saved = config_toml(&updated, &path).unwrap();
+        assert!(!saved.contains("456:NEW") && saved.contains("${secret:telegram_bot_token_2}"), "{saved}");
+        assert!(saved.contains("${secret:api_key}"), "{saved}");
+        let store = SecretStore::open(&dir, true).unwrap();
+        assert_eq!(store.get("telegram_bot_token_2"), Some("456:NEW"));
+    }
 }
@@ -0,0 +1,526 @@
+//! Browse tool — fetch a web page and return its readable text.
+//!
+//! Every URL, each redirect hop included, passes the SSRF checks of
+//! `http_request` (the host as parsed, and every address it resolves to)
+//! and the security policy's `check_domain` gate
+//! (`autonomy.allowed_domains`), and must be allowed by the site's
+//! robots.txt. Bodies are cut at [`MAX_BODY_BYTES`]; HTML is reduced to
+//! its main content, with scripts, navigation and other boilerplate dropped.
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{SecurityPolicy, Tool};
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::http_request::{PublicResolver, is_url_blocked};
+
+/// User agent sent with requests.
+const USER_AGENT: &str = "BizClaw/1.0 (+https://github.com/xdevweb3/ateclaw)";
+/// Name robots.txt groups are matched against.
+const ROBOTS_AGENT: &str = "bizclaw";
+/// Most bytes of a page read.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+/// Most bytes of a robots.txt read.
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+/// Characters of text returned unless the call asks otherwise.
+const DEFAULT_MAX_CHARS: usize = 8_000;
+/// Most characters of text a call may ask for.
+const MAX_CHARS: usize = 50_000;
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 5;
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+pub struct BrowseTool {
+    client: reqwest::Client,
+    security: Option<Arc<dyn SecurityPolicy>>,
+    /// Parsed robots.txt per origin (`scheme://host:port`).
+    robots: Mutex<HashMap<String, Arc<Robots>>>,
+}
+
+impl BrowseTool {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            // Redirects are followed by hand so each hop is checked
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            security: None,
+            robots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Gate fetched domains through a security policy.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Why `url` may not be fetched, if it may not.
+    async fn check(&self, url: &reqwest::Url) -> Option<String> {
+        if let Some(reason) = is_url_blocked(url.as_str()) {
+            return Some(reason);
+        }
+        let host = url.host_str().unwrap_or_default();
+        if let Some(security) = &self.security
+            && !security.check_domain(host).await.unwrap_or(false)
+        {
+            return Some(format!("Domain '{host}' is not in autonomy.allowed_domains"));
+        }
+        if !self.robots_for(url).await.allows(url.path()) {
+            return Some(format!("{host}'s robots.txt disallows {}", url.path()));
+        }
+        None
+    }
+
+    /// robots.txt rules for `url`'s site, fetched once per origin. A site
+    /// without a readable robots.txt allows everything.
+    async fn robots_for(&self, url: &reqwest::Url) -> Arc<Robots> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(robots) = self.robots.lock().unwrap_or_else(|e| e.into_inner()).get(&origin) {
+            return robots.clone();
+        }
+        let text = match self.client.get(format!("{origin}/robots.txt")).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                let (body, _) = read_capped(resp, MAX_ROBOTS_BYTES).await.unwrap_or_default();
+                String::from_utf8_lossy(&body).into_owned()
+            }
+            _ => String::new(),
+        };
+        let robots = Arc::new(Robots::parse(&text));
+        self.robots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(origin, robots.clone());
+        robots
+    }
+
+    /// Fetch `url`, following checked redirects. Returns the final URL,
+    /// content type, body (at most [`MAX_BODY_BYTES`]) and whether the
+    /// body was cut.
+    async fn fetch(&self, url: &str) -> std::result::Result<(reqwest::Url, String, Vec<u8>, bool), String> {
+        let mut url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+        for _ in 0..=MAX_REDIRECTS {
+            if let Some(reason) = self.check(&url).await {
+                return Err(format!("Blocked: {reason}"));
+            }
+            let response = self
+                .client
+                .get(url.clone())
+                .header("Accept", "text/html,application/xhtml+xml,text/plain;q=0.9,*/*;q=0.5")
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {e}"))?;
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .ok_or_else(|| format!("HTTP {status} without a Location"))?;
+                url = url.join(location).map_err(|e| format!("Bad redirect '{location}': {e}"))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(format!("HTTP {status} fetching {url}"));
+            }
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|c| c.to_str().ok())
+                .unwrap_or("text/html")
+                .to_lowercase();
+            let (body, cut) = read_capped(response, MAX_BODY_BYTES).await?;
+            return Ok((url, content_type, body, cut));
+        }
+        Err(format!("More than {MAX_REDIRECTS} redirects"))
+    }
+}
+
+/// Read at most `max` bytes of `response`'s body. Returns the bytes and
+/// whether the body was cut.
+pub async fn read_capped(mut response: reqwest::Response, max: usize) -> std::result::Result<(Vec<u8>, bool), String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Read body failed: {e}"))? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= max {
+            body.truncate(max);
+            return Ok((body, true));
+        }
+    }
+    Ok((body, false))
+}
+
+impl Default for BrowseTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for BrowseTool {
+    fn name(&self) -> &str {
+        "browse"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "browse".into(),
+            description: "Open a web page and read its main text, without menus, ads or scripts. \
+                          Use after web_search to read a result, or to look up a known URL."
+                .into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "Page URL (http or https)"
+                    },
+                    "max_chars": {
+                        "type": "integer",
+                        "description": "Most characters of text to return (default 8000, max 50000)"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let url = args["url"]
+            .as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'url'".into()))?;
+        let max_chars = args["max_chars"]
+            .as_u64()
+            .map_or(DEFAULT_MAX_CHARS, |n| (n as usize).clamp(200, MAX_CHARS));
+
+        let (final_url, content_type, body, cut) = match self.fetch(url).await {
+            Ok(page) => page,
+            Err(e) => {
+                return Ok(ToolResult {
+                    tool_call_id: String::new(),
+                    output: e,
+                    success: false,
+                });
+            }
+        };
+        let body = String::from_utf8_lossy(&body);
+        let (title, text) = if content_type.contains("html") {
+            readable(&body)
+        } else if content_type.starts_with("text/") || content_type.contains("json") || content_type.contains("xml") {
+            (String::new(), body.trim().to_string())
+        } else {
+            return Ok(ToolResult {
+                tool_call_id: String::new(),
+                output: format!("{final_url} is not a web page ({content_type}); use document_reader for documents"),
+                success: false,
+            });
+        };
+
+        let mut output = String::new();
+        if !title.is_empty() {
+            output.push_str(&format!("# {title}\n"));
+        }
+        output.push_str(&format!("{final_url}\n\n"));
+        let total = text.chars().count();
+        output.extend(text.chars().take(max_chars));
+        if total > max_chars || cut {
+            output.push_str(&format!("\n\n[truncated, {total} characters of text read]"));
+        }
+        Ok(ToolResult {
+            tool_call_id: String::new(),
+            output,
+            success: true,
+        })
+    }
+}
+
+/// robots.txt rules that apply to BizClaw: `(allow, pattern)`.
+#[derive(Debug, Default)]
+pub struct Robots {
+    rules: Vec<(bool, String)>,
+}
+
+impl Robots {
+    /// Rules of the groups naming BizClaw, or else of the `*` groups.
+    pub fn parse(text: &str) -> Self {
+        let mut named = Vec::new();
+        let mut any = Vec::new();
+        let mut has_named_group = false;
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+            match key.as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    let agent = value.to_lowercase();
+                    has_named_group |= agent == ROBOTS_AGENT;
+                    agents.push(agent);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_string());
+                    if agents.iter().any(|a| a == ROBOTS_AGENT) {
+                        named.push(rule.clone());
+                    }
+                    if agents.iter().any(|a| a == "*") {
+                        any.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self {
+            rules: if has_named_group { named } else { any },
+        }
+    }
+
+    /// Whether `path` may be fetched: the longest matching rule decides,
+    /// Allow winning ties.
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_match(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Whether robots.txt `pattern` (with `*` wildcards and a `$` end anchor)
+/// matches the start of `path`.
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern must end the path
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+static TITLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+/// Elements that never hold the page's content.
+static BOILERPLATE: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        "script", "style", "noscript", "svg", "template", "iframe", "nav", "header", "footer",
+        "aside", "form", "button", "select",
+    ]
+    .iter()
+    .map(|tag| Regex::new(&format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>")).unwrap())
+    .collect()
+});
+static HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<h([1-6])\b[^>]*>").unwrap());
+static LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<li\b[^>]*>").unwrap());
+static BLOCK_END: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<(br|hr|/p|/div|/h[1-6]|/li|/tr|/section|/article|/blockquote|/pre|/table|/ul|/ol|/dd|/dt)\b[^>]*>")
+        .unwrap()
+});
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static SPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[ \t\u{a0}]+").unwrap());
+
+/// Title and main text of an HTML page: the `<article>` or `<main>`
+/// element when there is one, without boilerplate elements, as lines of
+/// plain text with Markdown-style headings and list items.
+fn readable(html: &str) -> (String, String) {
+    let title = TITLE
+        .captures(html)
+        .map(|c| clean_line(&decode_entities(&c[1])))
+        .unwrap_or_default();
+    let mut html = COMMENT.replace_all(html, "").into_owned();
+    for element in BOILERPLATE.iter() {
+        html = element.replace_all(&html, "\n").into_owned();
+    }
+    let main = element_content(&html, "article")
+        .or_else(|| element_content(&html, "main"))
+        .or_else(|| element_content(&html, "body"))
+        .unwrap_or(&html);
+
+    let text = HEADING.replace_all(main, |c: &regex::Captures| {
+        let level: usize = c[1].parse().unwrap_or(1);
+        format!("\n\n{} ", "#".repeat(level))
+    });
+    let text = LIST_ITEM.replace_all(&text, "\n- ");
+    let text = BLOCK_END.replace_all(&text, "\n");
+    let text = TAG.replace_all(&text, "");
+    let text = decode_entities(&text);
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines().map(clean_line) {
+        // Keep paragraph breaks, but only one blank line in a row
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        if line != "-" {
+            lines.push(line);
+        }
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    (title, lines.join("\n"))
+}
+
+/// What's between the first `<tag ...>` and the last `</tag>` of `html`.
+fn element_content<'a>(html: &'a str, tag: &str) -> Option<&'a str> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find(&format!("<{tag}"))?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = lower.rfind(&format!("</{tag}"))?;
+    (end > start).then(|| &html[start..end])
+}
+
+fn clean_line(line: &str) -> String {
+    SPACES.replace_all(line, " ").trim().to_string()
+}
+
+/// Decode the HTML entities pages commonly use.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let decoded = rest[1..].find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..=end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "copy" => Some('©'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readable_keeps_main_content() {
+        let html = r#"<html><head><title>Giờ mở cửa &amp; liên hệ</title>
+            <script>track()</script><style>p{}</style></head>
+            <body><header><nav><a href="/">Home</a> | <a href="/shop">Shop</a></nav></header>
+            <article><h1>Opening hours</h1>
+            <p>We open <b>9am</b>&nbsp;to 5pm.</p><!-- hidden -->
+            <ul><li>Mon&ndash;Fri</li><li>Sat &#8211; half day</li></ul>
+            <aside>Related posts</aside></article>
+            <footer>© 2026 Shop</footer></body></html>"#;
+        let (title, text) = readable(html);
+        assert_eq!(title, "Giờ mở cửa & liên hệ");
+        assert_eq!(text, "# Opening hours\n\nWe open 9am to 5pm.\n\n- Mon–Fri\n\n- Sat – half day");
+    }
+
+    #[test]
+    fn test_robots_rules() {
+        let robots = Robots::parse(
+            "User-agent: *\nDisallow: /private\nAllow: /private/faq\n\n\
+             User-agent: GPTBot\nDisallow: /\n",
+        );
+        assert!(robots.allows("/"));
+        assert!(!robots.allows("/private/orders"));
+        assert!(robots.allows("/private/faq.html"));
+
+        // A group naming BizClaw replaces the * rules
+        let robots = Robots::parse("User-agent: *\nDisallow: /\n\nUser-agent: bizclaw\nDisallow: /*.pdf$\n");
+        assert!(robots.allows("/docs/"));
+        assert!(!robots.allows("/docs/price.pdf"));
+        assert!(robots.allows("/docs/price.pdf.html"));
+        assert!(Robots::parse("User-agent: *\nDisallow:\n").allows("/anything"));
+    }
+
+    struct OnlyDomain(&'static str);
+
+    #[async_trait]
+    impl SecurityPolicy for OnlyDomain {
+        async fn check_command(&self, _command: &str) -> Result<bool> {
+            Ok(false)
+        }
+        async fn check_path(&self, _path: &str) -> Result<bool> {
+            Ok(false)
+        }
+        async fn check_domain(&self, host: &str) -> Result<bool> {
+            Ok(host == self.0)
+        }
+        fn autonomy_level(&self) -> &str {
+            "supervised"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocked_urls_not_fetched() {
+        let tool = BrowseTool::new().with_security(Arc::new(OnlyDomain("example.com")));
+        let private = tool.execute(r#"{"url": "http://192.168.1.1/admin"}"#).await.unwrap();
+        assert!(!private.success);
+        assert!(private.output.starts_with("Blocked"));
+
+        let other = tool.execute(r#"{"url": "https://evil.test/page"}"#).await.unwrap();
+        assert!(!other.success);
+        assert!(other.output.contains("allowed_domains"));
+        assert!(tool.execute(r#"{"max_chars": 10}"#).await.is_err());
+    }
+}
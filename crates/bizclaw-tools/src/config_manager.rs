@@ -1,6 +1,7 @@
 //! Config Manager tool — read/write config.toml at runtime
 
 use async_trait::async_trait;
+use bizclaw_core::config::SECRET_REF_PREFIX;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
@@ -99,14 +100,14 @@ impl Tool for ConfigManagerTool {
                 })?;
 
                 // Navigate dot-separated key path
-                let value = get_nested_value(&json, key);
+                let value = get_nested_value(&json, key).map(|v| mask_value(key, v));
 
                 Ok(ToolResult {
                     tool_call_id: String::new(),
-                    output: match value {
+                    output: match &value {
                         Some(v) => format!(
                             "{key} = {}",
-                            serde_json::to_string_pretty(&v).unwrap_or_default()
+                            serde_json::to_string_pretty(v).unwrap_or_default()
                         ),
                         None => format!("Key '{key}' not found in config"),
                     },
@@ -247,14 +248,38 @@ fn collect_keys(json: &serde_json::Value, prefix: &str) -> Vec<String> {
     keys
 }
 
+/// Whether a config key or line names a credential.
+fn is_sensitive(text: &str) -> bool {
+    ["api_key", "password", "secret", "token"]
+        .iter()
+        .any(|word| text.contains(word))
+}
+
+/// `value` with credentials masked when `key` names one. References to
+/// stored secrets (`${secret:…}`) are shown as they are.
+fn mask_value(key: &str, value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s)
+            if is_sensitive(key) && !s.is_empty() && !s.contains(SECRET_REF_PREFIX) =>
+        {
+            serde_json::Value::String("••••••••".into())
+        }
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), mask_value(&format!("{key}.{k}"), v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| mask_value(key, v)).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
 fn mask_secrets(content: &str) -> String {
     let mut masked = String::new();
     for line in content.lines() {
-        if line.contains("api_key")
-            || line.contains("password")
-            || line.contains("secret")
-            || line.contains("token")
-        {
+        if is_sensitive(line) && !line.contains(SECRET_REF_PREFIX) {
             if let Some(eq_pos) = line.find('=') {
                 masked.push_str(&line[..eq_pos + 1]);
                 masked.push_str(" \"••••••••\"");
@@ -268,3 +293,27 @@ fn mask_secrets(content: &str) -> String {
     }
     masked
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_masked_references_shown() {
+        let channel = serde_json::json!({
+            "enabled": true,
+            "bot_token": "123:XYZ",
+            "webhook_secret": "${secret:webhook_secret}",
+        });
+        let masked = mask_value("channel.telegram", &channel);
+        assert_eq!(masked["bot_token"], "••••••••");
+        assert_eq!(masked["webhook_secret"], "${secret:webhook_secret}");
+        assert_eq!(masked["enabled"], true);
+
+        let content = "api_key = \"sk-1\"\nbot_token = \"${secret:telegram_bot_token}\"\n";
+        assert_eq!(
+            mask_secrets(content),
+            "api_key = \"••••••••\"\nbot_token = \"${secret:telegram_bot_token}\"\n"
+        );
+    }
+}
@@ -0,0 +1,875 @@
+//! CSV/XLSX query tool — answer questions about a spreadsheet without the
+//! LLM reading the whole file.
+//!
+//! Loads a CSV (comma, semicolon or tab separated) or an Excel sheet and
+//! runs one action over it: `describe` (columns and a preview), `stats`
+//! (per-column statistics), `filter` (matching rows), `group` (aggregates
+//! per group) or `pivot` (one column's values spread across the columns).
+//! Results come back as Markdown tables. Relative paths resolve against the
+//! workspace; with a workspace set, files outside it are refused.
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{SecurityPolicy, Tool};
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::path_guard::PathGuard;
+
+/// Largest file the tool loads.
+const MAX_FILE_BYTES: u64 = 50 * 1024 * 1024;
+/// Rows returned when the call gives no `limit`.
+const DEFAULT_LIMIT: usize = 50;
+/// Most rows a single result may show.
+const MAX_LIMIT: usize = 500;
+/// Most distinct values a pivot may spread across columns.
+const MAX_PIVOT_COLUMNS: usize = 50;
+
+pub struct CsvQueryTool {
+    guard: PathGuard,
+}
+
+impl CsvQueryTool {
+    pub fn new() -> Self {
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Resolve relative paths against `dir` and refuse files outside it.
+    pub fn with_workspace(mut self, dir: PathBuf) -> Self {
+        self.guard = self.guard.with_workspace(dir);
+        self
+    }
+
+    /// Gate file paths through a security policy.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.guard = self.guard.with_security(security);
+        self
+    }
+
+    async fn resolve_path(&self, path: &str) -> Result<PathBuf> {
+        let resolved = self.guard.resolve(path).await?;
+        resolved.canonicalize().map_err(|e| {
+            BizClawError::Tool(format!("File not found '{}': {e}", resolved.display()))
+        })
+    }
+}
+
+impl Default for CsvQueryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A loaded sheet: header names and string cells.
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn from_records(mut records: Vec<Vec<String>>) -> Self {
+        if records.is_empty() {
+            return Self {
+                headers: vec![],
+                rows: vec![],
+            };
+        }
+        let first = records.remove(0);
+        let width = records
+            .iter()
+            .map(|r| r.len())
+            .chain(std::iter::once(first.len()))
+            .max()
+            .unwrap_or(0);
+        let headers = (0..width)
+            .map(|i| match first.get(i).map(|h| h.trim()) {
+                Some(h) if !h.is_empty() => h.to_string(),
+                _ => format!("column_{}", i + 1),
+            })
+            .collect();
+        let rows = records
+            .into_iter()
+            .filter(|r| r.iter().any(|c| !c.trim().is_empty()))
+            .map(|mut r| {
+                r.resize(width, String::new());
+                r
+            })
+            .collect();
+        Self { headers, rows }
+    }
+
+    /// Index of column `name`, matched case-insensitively.
+    fn column(&self, name: &str) -> Result<usize> {
+        let name = name.trim();
+        self.headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                BizClawError::Tool(format!(
+                    "Unknown column '{}'. Columns: {}",
+                    name,
+                    self.headers.join(", ")
+                ))
+            })
+    }
+}
+
+/// Parse CSV text, detecting the delimiter from the header line.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let header = text.lines().next().unwrap_or("");
+    let delimiter = [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap_or(',');
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Read the named sheet (or the first) of an Excel workbook.
+fn read_workbook(path: &Path, sheet: Option<&str>) -> Result<Vec<Vec<String>>> {
+    use calamine::{Data, Reader, open_workbook_auto};
+
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| BizClawError::Tool(format!("Failed to open workbook: {e}")))?;
+    let names = workbook.sheet_names().to_owned();
+    let name = match sheet {
+        Some(s) => names
+            .iter()
+            .find(|n| n.eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| {
+                BizClawError::Tool(format!("Unknown sheet '{}'. Sheets: {}", s, names.join(", ")))
+            })?,
+        None => names
+            .first()
+            .cloned()
+            .ok_or_else(|| BizClawError::Tool("Workbook has no sheets".into()))?,
+    };
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e| BizClawError::Tool(format!("Failed to read sheet '{name}': {e}")))?;
+
+    Ok(range
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Data::String(s) => s.to_string(),
+                    Data::Float(f) => format_number(*f),
+                    Data::Int(i) => i.to_string(),
+                    Data::Bool(b) => b.to_string(),
+                    Data::Empty => String::new(),
+                    Data::Error(e) => format!("Error({e})"),
+                    Data::DateTime(v) if v.is_datetime() => excel_date(v.as_f64()),
+                    Data::DateTime(v) => format_number(v.as_f64()),
+                    Data::DateTimeIso(v) => v.to_string(),
+                    Data::DurationIso(v) => v.to_string(),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Render an Excel date serial (days since 1899-12-30) as ISO text.
+fn excel_date(serial: f64) -> String {
+    let base = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let secs = (serial * 86_400.0).round() as i64;
+    let dt = base + chrono::Duration::seconds(secs);
+    if secs % 86_400 == 0 {
+        dt.format("%Y-%m-%d").to_string()
+    } else {
+        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+/// Parse a cell as a number, allowing thousands separators and a currency
+/// or percent sign.
+fn parse_number(cell: &str) -> Option<f64> {
+    let s = cell.trim();
+    if s.is_empty() {
+        return None;
+    }
+    let cleaned: String = s
+        .trim_start_matches(['$', '€', '£'])
+        .trim_end_matches(['%', '₫'])
+        .chars()
+        .filter(|c| *c != ',' && *c != '_' && !c.is_whitespace())
+        .collect();
+    cleaned.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        let s = format!("{n:.2}");
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Compare cells numerically when both are numbers, else as text.
+fn compare_cells(a: &str, b: &str) -> std::cmp::Ordering {
+    match (parse_number(a), parse_number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// One `{column, op, value}` condition of a `filter`/`group` call.
+struct Condition {
+    column: usize,
+    op: String,
+    value: String,
+}
+
+impl Condition {
+    fn matches(&self, row: &[String]) -> bool {
+        let cell = row[self.column].trim();
+        let value = self.value.trim();
+        let ord = || compare_cells(cell, value);
+        match self.op.as_str() {
+            "eq" => ord().is_eq(),
+            "ne" => !ord().is_eq(),
+            "gt" => ord().is_gt(),
+            "gte" => ord().is_ge(),
+            "lt" => ord().is_lt(),
+            "lte" => ord().is_le(),
+            "contains" => cell.to_lowercase().contains(&value.to_lowercase()),
+            "empty" => cell.is_empty(),
+            "not_empty" => !cell.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+fn parse_conditions(table: &Table, args: &serde_json::Value) -> Result<Vec<Condition>> {
+    let Some(filters) = args["filters"].as_array() else {
+        return Ok(vec![]);
+    };
+    filters
+        .iter()
+        .map(|f| {
+            let column = table.column(f["column"].as_str().unwrap_or(""))?;
+            let op = f["op"].as_str().unwrap_or("eq").to_string();
+            if ![
+                "eq", "ne", "gt", "gte", "lt", "lte", "contains", "empty", "not_empty",
+            ]
+            .contains(&op.as_str())
+            {
+                return Err(BizClawError::Tool(format!("Unknown filter op '{op}'")));
+            }
+            let value = match &f["value"] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                v => v.to_string(),
+            };
+            Ok(Condition { column, op, value })
+        })
+        .collect()
+}
+
+/// Running aggregate over one column's cells.
+#[derive(Default, Clone)]
+struct Accumulator {
+    rows: usize,
+    numbers: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, cell: &str) {
+        self.rows += 1;
+        if let Some(n) = parse_number(cell) {
+            self.numbers += 1;
+            self.sum += n;
+            self.min = Some(self.min.map_or(n, |m| m.min(n)));
+            self.max = Some(self.max.map_or(n, |m| m.max(n)));
+        }
+    }
+
+    fn value(&self, func: &str) -> String {
+        match func {
+            "count" => self.rows.to_string(),
+            "sum" => format_number(self.sum),
+            "avg" if self.numbers > 0 => format_number(self.sum / self.numbers as f64),
+            "min" => self.min.map(format_number).unwrap_or_default(),
+            "max" => self.max.map(format_number).unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+const AGG_FUNCS: [&str; 5] = ["count", "sum", "avg", "min", "max"];
+
+fn check_agg(func: &str) -> Result<()> {
+    if AGG_FUNCS.contains(&func) {
+        Ok(())
+    } else {
+        Err(BizClawError::Tool(format!(
+            "Unknown aggregate '{}'. Use: {}",
+            func,
+            AGG_FUNCS.join(", ")
+        )))
+    }
+}
+
+/// Escape a cell for a Markdown table.
+fn md_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn markdown_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = format!(
+        "| {} |\n|{}|\n",
+        headers.iter().map(|h| md_cell(h)).collect::<Vec<_>>().join(" | "),
+        headers.iter().map(|_| "---").collect::<Vec<_>>().join("|")
+    );
+    for row in rows {
+        out.push_str(&format!(
+            "| {} |\n",
+            row.iter().map(|c| md_cell(c)).collect::<Vec<_>>().join(" | ")
+        ));
+    }
+    out
+}
+
+/// Sort `rows` by output column `sort_by` and keep the first `limit`,
+/// noting how many were left out.
+fn sort_and_limit(
+    headers: &[String],
+    mut rows: Vec<Vec<String>>,
+    args: &serde_json::Value,
+) -> Result<(Vec<Vec<String>>, String)> {
+    if let Some(sort_by) = args["sort_by"].as_str() {
+        let idx = headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(sort_by.trim()))
+            .ok_or_else(|| {
+                BizClawError::Tool(format!(
+                    "Cannot sort by '{}'. Result columns: {}",
+                    sort_by,
+                    headers.join(", ")
+                ))
+            })?;
+        rows.sort_by(|a, b| compare_cells(&a[idx], &b[idx]));
+        if args["descending"].as_bool().unwrap_or(false) {
+            rows.reverse();
+        }
+    }
+    let limit = args["limit"]
+        .as_u64()
+        .map(|l| l as usize)
+        .unwrap_or(DEFAULT_LIMIT)
+        .clamp(1, MAX_LIMIT);
+    let total = rows.len();
+    rows.truncate(limit);
+    let note = if total > limit {
+        format!("\n_Showing {limit} of {total} rows._")
+    } else {
+        String::new()
+    };
+    Ok((rows, note))
+}
+
+fn describe(table: &Table) -> String {
+    let preview: Vec<Vec<String>> = table.rows.iter().take(5).cloned().collect();
+    format!(
+        "{} rows × {} columns: {}\n\nFirst rows:\n\n{}",
+        table.rows.len(),
+        table.headers.len(),
+        table.headers.join(", "),
+        markdown_table(&table.headers, &preview)
+    )
+}
+
+fn stats(table: &Table, columns: &[usize]) -> String {
+    let headers: Vec<String> = [
+        "column", "type", "non-empty", "distinct", "min", "max", "mean", "sum", "top values",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let rows = columns
+        .iter()
+        .map(|&c| {
+            let mut acc = Accumulator::default();
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for row in &table.rows {
+                let cell = row[c].trim();
+                if cell.is_empty() {
+                    continue;
+                }
+                acc.add(cell);
+                *counts.entry(cell).or_default() += 1;
+            }
+            let numeric = acc.rows > 0 && acc.numbers == acc.rows;
+            let mut top: Vec<(&str, usize)> = counts.iter().map(|(k, v)| (*k, *v)).collect();
+            top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            let top = top
+                .iter()
+                .take(3)
+                .map(|(k, v)| format!("{k} ({v})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let num = |func: &str| if numeric { acc.value(func) } else { String::new() };
+            vec![
+                table.headers[c].clone(),
+                if numeric { "number" } else { "text" }.to_string(),
+                acc.rows.to_string(),
+                counts.len().to_string(),
+                num("min"),
+                num("max"),
+                num("avg"),
+                num("sum"),
+                if numeric { String::new() } else { top },
+            ]
+        })
+        .collect::<Vec<_>>();
+    format!("{} rows\n\n{}", table.rows.len(), markdown_table(&headers, &rows))
+}
+
+fn filter(table: &Table, conditions: &[Condition], args: &serde_json::Value) -> Result<String> {
+    let columns: Vec<usize> = match args["columns"].as_array() {
+        Some(cols) if !cols.is_empty() => cols
+            .iter()
+            .map(|c| table.column(c.as_str().unwrap_or("")))
+            .collect::<Result<_>>()?,
+        _ => (0..table.headers.len()).collect(),
+    };
+    let headers: Vec<String> = columns.iter().map(|&c| table.headers[c].clone()).collect();
+    let rows: Vec<Vec<String>> = table
+        .rows
+        .iter()
+        .filter(|r| conditions.iter().all(|c| c.matches(r)))
+        .map(|r| columns.iter().map(|&c| r[c].clone()).collect())
+        .collect();
+    let matched = rows.len();
+    let (rows, note) = sort_and_limit(&headers, rows, args)?;
+    Ok(format!(
+        "{matched} matching rows\n\n{}{note}",
+        markdown_table(&headers, &rows)
+    ))
+}
+
+fn group(table: &Table, conditions: &[Condition], args: &serde_json::Value) -> Result<String> {
+    let keys: Vec<usize> = match &args["group_by"] {
+        serde_json::Value::String(s) => vec![table.column(s)?],
+        serde_json::Value::Array(cols) if !cols.is_empty() => cols
+            .iter()
+            .map(|c| table.column(c.as_str().unwrap_or("")))
+            .collect::<Result<_>>()?,
+        _ => return Err(BizClawError::Tool("'group' needs 'group_by'".into())),
+    };
+    let mut aggregates: Vec<(Option<usize>, String)> = Vec::new();
+    for agg in args["aggregates"].as_array().into_iter().flatten() {
+        let func = agg["func"].as_str().unwrap_or("count").to_lowercase();
+        check_agg(&func)?;
+        let column = match agg["column"].as_str() {
+            Some(c) => Some(table.column(c)?),
+            None if func == "count" => None,
+            None => return Err(BizClawError::Tool(format!("'{func}' needs a 'column'"))),
+        };
+        aggregates.push((column, func));
+    }
+    if aggregates.is_empty() {
+        aggregates.push((None, "count".into()));
+    }
+
+    let mut groups: BTreeMap<Vec<String>, Vec<Accumulator>> = BTreeMap::new();
+    for row in table.rows.iter().filter(|r| conditions.iter().all(|c| c.matches(r))) {
+        let key = keys.iter().map(|&k| row[k].trim().to_string()).collect();
+        let accs = groups
+            .entry(key)
+            .or_insert_with(|| vec![Accumulator::default(); aggregates.len()]);
+        for (acc, (column, _)) in accs.iter_mut().zip(&aggregates) {
+            match column {
+                Some(c) if row[*c].trim().is_empty() => {}
+                Some(c) => acc.add(&row[*c]),
+                None => acc.add(""),
+            }
+        }
+    }
+
+    let mut headers: Vec<String> = keys.iter().map(|&k| table.headers[k].clone()).collect();
+    headers.extend(aggregates.iter().map(|(column, func)| match column {
+        Some(c) => format!("{func}({})", table.headers[*c]),
+        None => func.clone(),
+    }));
+    let rows: Vec<Vec<String>> = groups
+        .into_iter()
+        .map(|(mut key, accs)| {
+            key.extend(accs.iter().zip(&aggregates).map(|(a, (_, f))| a.value(f)));
+            key
+        })
+        .collect();
+    let count = rows.len();
+    let (rows, note) = sort_and_limit(&headers, rows, args)?;
+    Ok(format!(
+        "{count} groups\n\n{}{note}",
+        markdown_table(&headers, &rows)
+    ))
+}
+
+fn pivot(table: &Table, conditions: &[Condition], args: &serde_json::Value) -> Result<String> {
+    let row_col = table.column(args["pivot_row"].as_str().unwrap_or(""))?;
+    let col_col = table.column(args["pivot_column"].as_str().unwrap_or(""))?;
+    let func = args["func"].as_str().unwrap_or("sum").to_lowercase();
+    check_agg(&func)?;
+    let value_col = match args["value_column"].as_str() {
+        Some(c) => Some(table.column(c)?),
+        None if func == "count" => None,
+        None => return Err(BizClawError::Tool(format!("'{func}' needs a 'value_column'"))),
+    };
+
+    let mut cells: BTreeMap<(String, String), Accumulator> = BTreeMap::new();
+    let mut row_keys: Vec<String> = Vec::new();
+    let mut col_keys: Vec<String> = Vec::new();
+    for row in table.rows.iter().filter(|r| conditions.iter().all(|c| c.matches(r))) {
+        let r = row[row_col].trim().to_string();
+        let c = row[col_col].trim().to_string();
+        if !row_keys.contains(&r) {
+            row_keys.push(r.clone());
+        }
+        if !col_keys.contains(&c) {
+            col_keys.push(c.clone());
+        }
+        let acc = cells.entry((r, c)).or_default();
+        match value_col {
+            Some(v) if !row[v].trim().is_empty() => acc.add(&row[v]),
+            Some(_) => {}
+            None => acc.add(""),
+        }
+    }
+    if col_keys.len() > MAX_PIVOT_COLUMNS {
+        return Err(BizClawError::Tool(format!(
+            "'{}' has {} distinct values; pivot supports at most {}. Filter first or use 'group'.",
+            table.headers[col_col],
+            col_keys.len(),
+            MAX_PIVOT_COLUMNS
+        )));
+    }
+    row_keys.sort_by(|a, b| compare_cells(a, b));
+    col_keys.sort_by(|a, b| compare_cells(a, b));
+
+    let mut headers = vec![table.headers[row_col].clone()];
+    headers.extend(col_keys.iter().cloned());
+    let rows: Vec<Vec<String>> = row_keys
+        .iter()
+        .map(|r| {
+            let mut out = vec![r.clone()];
+            out.extend(col_keys.iter().map(|c| {
+                cells
+                    .get(&(r.clone(), c.clone()))
+                    .map(|a| a.value(&func))
+                    .unwrap_or_default()
+            }));
+            out
+        })
+        .collect();
+    let label = match value_col {
+        Some(v) => format!("{func}({})", table.headers[v]),
+        None => func.clone(),
+    };
+    let (rows, note) = sort_and_limit(&headers, rows, args)?;
+    Ok(format!(
+        "{label} by {} × {}\n\n{}{note}",
+        table.headers[row_col],
+        table.headers[col_col],
+        markdown_table(&headers, &rows)
+    ))
+}
+
+#[async_trait]
+impl Tool for CsvQueryTool {
+    fn name(&self) -> &str {
+        "csv_query"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "csv_query".into(),
+            description: "Query a CSV or Excel file without reading it whole: describe its columns, get column statistics, filter rows, group with aggregates, or build a pivot table. Results are Markdown tables. Start with 'describe' to learn the column names.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["describe", "stats", "filter", "group", "pivot"],
+                        "description": "describe (columns + first rows), stats (per-column statistics), filter (matching rows), group (aggregates per group), pivot (row × column table)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "CSV/XLSX/XLS file, relative to the workspace"
+                    },
+                    "sheet": {
+                        "type": "string",
+                        "description": "Excel sheet name (default: first sheet)"
+                    },
+                    "columns": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "stats/filter: columns to include (default: all)"
+                    },
+                    "filters": {
+                        "type": "array",
+                        "description": "Row conditions, all must match (filter, group, pivot)",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "column": { "type": "string" },
+                                "op": {
+                                    "type": "string",
+                                    "enum": ["eq", "ne", "gt", "gte", "lt", "lte", "contains", "empty", "not_empty"]
+                                },
+                                "value": { "type": ["string", "number"] }
+                            },
+                            "required": ["column"]
+                        }
+                    },
+                    "group_by": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "group: columns to group by"
+                    },
+                    "aggregates": {
+                        "type": "array",
+                        "description": "group: aggregates per group (default: count)",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "column": { "type": "string" },
+                                "func": { "type": "string", "enum": ["count", "sum", "avg", "min", "max"] }
+                            },
+                            "required": ["func"]
+                        }
+                    },
+                    "pivot_row": { "type": "string", "description": "pivot: column whose values become rows" },
+                    "pivot_column": { "type": "string", "description": "pivot: column whose values become columns" },
+                    "value_column": { "type": "string", "description": "pivot: column to aggregate" },
+                    "func": {
+                        "type": "string",
+                        "enum": ["count", "sum", "avg", "min", "max"],
+                        "description": "pivot: aggregate (default: sum)"
+                    },
+                    "sort_by": { "type": "string", "description": "Result column to sort by" },
+                    "descending": { "type": "boolean", "description": "Sort descending" },
+                    "limit": { "type": "integer", "description": "Max rows shown (default: 50, max: 500)" }
+                },
+                "required": ["action", "path"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let action = args["action"].as_str().unwrap_or("describe");
+        let path_str = args["path"]
+            .as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'path'".into()))?;
+
+        let path = self.resolve_path(path_str).await?;
+        let size = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| BizClawError::Tool(e.to_string()))?
+            .len();
+        if size > MAX_FILE_BYTES {
+            return Err(BizClawError::Tool(format!(
+                "File is {} MB; csv_query loads at most {} MB",
+                size / (1024 * 1024),
+                MAX_FILE_BYTES / (1024 * 1024)
+            )));
+        }
+
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let records = match ext.as_str() {
+            "csv" | "tsv" | "txt" => {
+                let bytes = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| BizClawError::Tool(e.to_string()))?;
+                parse_csv(&String::from_utf8_lossy(&bytes))
+            }
+            "xlsx" | "xlsm" | "xls" | "xlsb" | "ods" => {
+                let sheet = args["sheet"].as_str().map(String::from);
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || read_workbook(&path, sheet.as_deref()))
+                    .await
+                    .map_err(|e| BizClawError::Tool(e.to_string()))??
+            }
+            _ => {
+                return Err(BizClawError::Tool(format!(
+                    "Unsupported file type '{ext}'. Use CSV, TSV, XLSX, XLS or ODS."
+                )));
+            }
+        };
+        let table = Table::from_records(records);
+        if table.headers.is_empty() {
+            return Err(BizClawError::Tool(format!("'{path_str}' is empty")));
+        }
+
+        let conditions = parse_conditions(&table, &args)?;
+        let output = match action {
+            "describe" => describe(&table),
+            "stats" => {
+                let columns: Vec<usize> = match args["columns"].as_array() {
+                    Some(cols) if !cols.is_empty() => cols
+                        .iter()
+                        .map(|c| table.column(c.as_str().unwrap_or("")))
+                        .collect::<Result<_>>()?,
+                    _ => (0..table.headers.len()).collect(),
+                };
+                stats(&table, &columns)
+            }
+            "filter" => filter(&table, &conditions, &args)?,
+            "group" => group(&table, &conditions, &args)?,
+            "pivot" => pivot(&table, &conditions, &args)?,
+            other => {
+                return Err(BizClawError::Tool(format!(
+                    "Unknown action '{other}'. Use: describe, stats, filter, group, pivot"
+                )));
+            }
+        };
+
+        Ok(ToolResult {
+            tool_call_id: String::new(),
+            output,
+            success: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = "region;product;amount;month\n\
+        North;\"Tea; green\";1,200;Jan\n\
+        South;Coffee;800;Jan\n\
+        North;Coffee;300;Feb\n\
+        South;Coffee;;Feb\n";
+
+    fn workspace() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-csv-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("sales.csv"), SALES).unwrap();
+        dir
+    }
+
+    async fn run(tool: &CsvQueryTool, args: serde_json::Value) -> String {
+        tool.execute(&args.to_string()).await.unwrap().output
+    }
+
+    #[test]
+    fn test_parse_csv_quotes_and_delimiter() {
+        let table = Table::from_records(parse_csv(SALES));
+        assert_eq!(table.headers, vec!["region", "product", "amount", "month"]);
+        assert_eq!(table.rows.len(), 4);
+        assert_eq!(table.rows[0][1], "Tea; green");
+        assert_eq!(parse_number(&table.rows[0][2]), Some(1200.0));
+
+        let quoted = parse_csv("a,b\n\"say \"\"hi\"\"\",\"multi\nline\"\n");
+        assert_eq!(quoted[1], vec!["say \"hi\"", "multi\nline"]);
+    }
+
+    #[tokio::test]
+    async fn test_group_filter_and_pivot() {
+        let ws = workspace();
+        let tool = CsvQueryTool::new().with_workspace(ws.clone());
+
+        let out = run(&tool, serde_json::json!({
+            "action": "group", "path": "sales.csv", "group_by": ["region"],
+            "aggregates": [{"func": "count"}, {"column": "amount", "func": "sum"}],
+            "sort_by": "sum(amount)", "descending": true
+        }))
+        .await;
+        assert!(out.contains("| region | count | sum(amount) |"), "{out}");
+        assert!(out.contains("| North | 2 | 1500 |\n| South | 2 | 800 |"), "{out}");
+
+        let out = run(&tool, serde_json::json!({
+            "action": "filter", "path": "sales.csv", "columns": ["product", "amount"],
+            "filters": [{"column": "Amount", "op": "gte", "value": 500}]
+        }))
+        .await;
+        assert!(out.starts_with("2 matching rows"), "{out}");
+        assert!(out.contains("| Tea; green | 1,200 |"), "{out}");
+
+        let out = run(&tool, serde_json::json!({
+            "action": "pivot", "path": "sales.csv", "pivot_row": "region",
+            "pivot_column": "month", "value_column": "amount"
+        }))
+        .await;
+        assert!(out.contains("| region | Feb | Jan |"), "{out}");
+        assert!(out.contains("| North | 300 | 1200 |"), "{out}");
+
+        let out = run(&tool, serde_json::json!({"action": "stats", "path": "sales.csv", "columns": ["amount", "region"]})).await;
+        assert!(out.contains("| amount | number | 3 | 3 | 300 | 1200 | 766.67 | 2300 |  |"), "{out}");
+        assert!(out.contains("North (2), South (2)"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn test_outside_workspace_and_unknown_column() {
+        let ws = workspace();
+        let outside = workspace();
+        let tool = CsvQueryTool::new().with_workspace(ws.clone());
+
+        let err = tool
+            .execute(&serde_json::json!({"action": "describe", "path": outside.join("sales.csv")}).to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the workspace"), "{err}");
+
+        let err = tool
+            .execute(&serde_json::json!({"action": "group", "path": "sales.csv", "group_by": ["city"]}).to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Columns: region, product, amount, month"), "{err}");
+    }
+}
@@ -7,11 +7,23 @@ use std::fs;
 use std::io::Read;
 use std::path::Path;
 
-pub struct DocumentReaderTool;
+use crate::path_guard::PathGuard;
+
+pub struct DocumentReaderTool {
+    guard: PathGuard,
+}
 
 impl DocumentReaderTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Confine paths to a workspace and security policy.
+    pub fn with_path_guard(mut self, guard: PathGuard) -> Self {
+        self.guard = guard;
+        self
     }
 
     fn read_pdf(&self, path: &Path) -> Result<String> {
@@ -135,7 +147,7 @@ impl Tool for DocumentReaderTool {
                     },
                     "path": {
                         "type": "string",
-                        "description": "Absolute path to the document file on disk."
+                        "description": "Path to the document file on disk."
                     }
                 },
                 "required": ["action", "path"]
@@ -162,7 +174,8 @@ impl Tool for DocumentReaderTool {
             ));
         }
 
-        let path = Path::new(path_str);
+        let resolved = self.guard.resolve(path_str).await?;
+        let path = resolved.as_path();
         if !path.exists() {
             return Err(bizclaw_core::error::BizClawError::Tool(format!(
                 "File not found: {}",
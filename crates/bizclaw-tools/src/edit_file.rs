@@ -5,11 +5,23 @@ use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 
-pub struct EditFileTool;
+use crate::path_guard::PathGuard;
+
+pub struct EditFileTool {
+    guard: PathGuard,
+}
 
 impl EditFileTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Confine paths to a workspace and security policy.
+    pub fn with_path_guard(mut self, guard: PathGuard) -> Self {
+        self.guard = guard;
+        self
     }
 }
 
@@ -68,9 +80,10 @@ impl Tool for EditFileTool {
             .as_str()
             .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'new_text'".into()))?;
         let dry_run = args["dry_run"].as_bool().unwrap_or(false);
+        let resolved = self.guard.resolve(path).await?;
 
         // Read current content
-        let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+        let content = tokio::fs::read_to_string(&resolved).await.map_err(|e| {
             bizclaw_core::error::BizClawError::Tool(format!("Failed to read {path}: {e}"))
         })?;
 
@@ -98,7 +111,7 @@ impl Tool for EditFileTool {
 
         // Replace
         let new_content = content.replace(old_text, new_text);
-        tokio::fs::write(path, &new_content).await.map_err(|e| {
+        tokio::fs::write(&resolved, &new_content).await.map_err(|e| {
             bizclaw_core::error::BizClawError::Tool(format!("Failed to write {path}: {e}"))
         })?;
 
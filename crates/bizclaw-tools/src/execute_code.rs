@@ -1,17 +1,53 @@
 //! Execute Code tool — run code in various languages
 //!
-//! Supports: Python, JavaScript/Node, Ruby, Go, Rust, C, PHP, Bash
+//! Supports: Python, JavaScript/Node, Ruby, Go, Rust, C, PHP, Bash, TypeScript.
+//! Off unless `[autonomy.code] enabled` is set, and only the languages listed
+//! there may run. Each snippet runs in a fresh temporary directory with a
+//! cleared environment, under CPU, memory and file-size rlimits and a
+//! wall-clock timeout, and never when the autonomy level is `readonly`.
+//! This is not isolation: snippets can read files and use the network.
 
 use async_trait::async_trait;
-use bizclaw_core::error::Result;
-use bizclaw_core::traits::Tool;
+use bizclaw_core::config::CodeExecutionConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{SecurityPolicy, Tool};
 use bizclaw_core::types::{ToolDefinition, ToolResult};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
-pub struct ExecuteCodeTool;
+pub struct ExecuteCodeTool {
+    config: CodeExecutionConfig,
+    security: Option<Arc<dyn SecurityPolicy>>,
+}
 
 impl ExecuteCodeTool {
     pub fn new() -> Self {
-        Self
+        Self::with_config(CodeExecutionConfig::default())
+    }
+
+    pub fn with_config(config: CodeExecutionConfig) -> Self {
+        Self {
+            config,
+            security: None,
+        }
+    }
+
+    /// Refuse to run code when the policy's autonomy level is `readonly`.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Canonical names of the configured languages this tool knows.
+    fn allowed_languages(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = Vec::new();
+        for config in self.config.languages.iter().filter_map(|l| get_lang_config(l)) {
+            if !names.contains(&config.name) {
+                names.push(config.name);
+            }
+        }
+        names
     }
 }
 
@@ -22,6 +58,7 @@ impl Default for ExecuteCodeTool {
 }
 
 struct LangConfig {
+    name: &'static str,
     command: &'static str,
     args: Vec<String>,
     extension: &'static str,
@@ -31,54 +68,63 @@ struct LangConfig {
 fn get_lang_config(language: &str) -> Option<LangConfig> {
     match language.to_lowercase().as_str() {
         "python" | "py" | "python3" => Some(LangConfig {
+            name: "python",
             command: "python3",
             args: vec![],
             extension: "py",
             needs_compile: false,
         }),
         "javascript" | "js" | "node" => Some(LangConfig {
+            name: "javascript",
             command: "node",
             args: vec![],
             extension: "js",
             needs_compile: false,
         }),
         "ruby" | "rb" => Some(LangConfig {
+            name: "ruby",
             command: "ruby",
             args: vec![],
             extension: "rb",
             needs_compile: false,
         }),
         "bash" | "sh" | "shell" => Some(LangConfig {
+            name: "bash",
             command: "bash",
             args: vec![],
             extension: "sh",
             needs_compile: false,
         }),
         "php" => Some(LangConfig {
+            name: "php",
             command: "php",
             args: vec![],
             extension: "php",
             needs_compile: false,
         }),
         "go" | "golang" => Some(LangConfig {
+            name: "go",
             command: "go",
             args: vec!["run".to_string()],
             extension: "go",
             needs_compile: false,
         }),
         "rust" | "rs" => Some(LangConfig {
+            name: "rust",
             command: "rustc",
             args: vec![],
             extension: "rs",
             needs_compile: true,
         }),
         "c" => Some(LangConfig {
+            name: "c",
             command: "gcc",
-            args: vec!["-o".to_string()],
+            args: vec![],
             extension: "c",
             needs_compile: true,
         }),
         "typescript" | "ts" => Some(LangConfig {
+            name: "typescript",
             command: "npx",
             args: vec!["tsx".to_string()],
             extension: "ts",
@@ -88,6 +134,167 @@ fn get_lang_config(language: &str) -> Option<LangConfig> {
     }
 }
 
+/// Output of a sandboxed process that finished before its timeout.
+struct SandboxRun {
+    status: std::process::ExitStatus,
+    stdout: String,
+    stderr: String,
+    truncated: bool,
+}
+
+/// Read a child stream, keeping at most `max` bytes and draining the rest
+/// so the child never blocks on a full pipe.
+async fn read_capped<R: AsyncRead + Unpin>(stream: Option<R>, max: usize) -> (String, bool) {
+    let Some(mut stream) = stream else {
+        return (String::new(), false);
+    };
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let room = max.saturating_sub(kept.len());
+                if n > room {
+                    truncated = true;
+                }
+                kept.extend_from_slice(&buf[..n.min(room)]);
+            }
+        }
+    }
+    (String::from_utf8_lossy(&kept).into_owned(), truncated)
+}
+
+/// Apply the configured rlimits in the child before it execs.
+#[cfg(unix)]
+fn apply_limits(cpu_secs: u64, memory_mb: u64, max_file_mb: u64) -> std::io::Result<()> {
+    let mb = 1024 * 1024;
+    let limits = [
+        // The hard CPU limit sits one second above the soft one so the
+        // process gets SIGXCPU first and SIGKILL only if it ignores it.
+        (libc::RLIMIT_CPU, cpu_secs, cpu_secs + 1),
+        (libc::RLIMIT_DATA, memory_mb * mb, memory_mb * mb),
+        (libc::RLIMIT_FSIZE, max_file_mb * mb, max_file_mb * mb),
+        (libc::RLIMIT_CORE, 0, 0),
+    ];
+    for (resource, soft, hard) in limits {
+        let limit = libc::rlimit {
+            rlim_cur: soft as libc::rlim_t,
+            rlim_max: hard as libc::rlim_t,
+        };
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+impl ExecuteCodeTool {
+    /// Run `program` in `dir` with a cleared environment and the configured
+    /// limits. Returns `Ok(None)` when the wall-clock timeout killed it.
+    async fn run_sandboxed(
+        &self,
+        program: &str,
+        args: &[String],
+        dir: &Path,
+        timeout_secs: u64,
+    ) -> std::io::Result<Option<SandboxRun>> {
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(args)
+            .current_dir(dir)
+            .env_clear()
+            .env(
+                "PATH",
+                std::env::var("PATH").unwrap_or_else(|_| "/usr/local/bin:/usr/bin:/bin".into()),
+            )
+            .env("HOME", dir)
+            .env("TMPDIR", dir)
+            .env("LANG", "C.UTF-8")
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        #[cfg(unix)]
+        {
+            let (cpu, mem, file) = (
+                self.config.cpu_secs,
+                self.config.memory_mb,
+                self.config.max_file_mb,
+            );
+            // Own process group, so a timeout also kills anything it spawned.
+            cmd.process_group(0);
+            // SAFETY: `apply_limits` only calls the async-signal-safe `setrlimit`.
+            unsafe {
+                cmd.pre_exec(move || apply_limits(cpu, mem, file));
+            }
+        }
+
+        let mut child = cmd.spawn()?;
+        let pid = child.id();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let max = self.config.max_output_bytes;
+
+        let finished = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), async {
+            let ((stdout, out_cut), (stderr, err_cut)) =
+                tokio::join!(read_capped(stdout, max), read_capped(stderr, max));
+            child.wait().await.map(|status| SandboxRun {
+                status,
+                stdout,
+                stderr,
+                truncated: out_cut || err_cut,
+            })
+        })
+        .await;
+
+        match finished {
+            Ok(run) => run.map(Some),
+            Err(_) => {
+                #[cfg(unix)]
+                if let Some(pid) = pid {
+                    unsafe {
+                        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+                    }
+                }
+                #[cfg(not(unix))]
+                let _ = pid;
+                let _ = child.start_kill();
+                let _ = child.wait().await;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Describe how a sandboxed process ended when a limit stopped it.
+fn limit_note(status: &std::process::ExitStatus) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        match status.signal() {
+            Some(libc::SIGXCPU) | Some(libc::SIGKILL) => Some("CPU time limit reached"),
+            Some(libc::SIGXFSZ) => Some("file size limit reached"),
+            _ => None,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+fn failed(output: String) -> ToolResult {
+    ToolResult {
+        tool_call_id: String::new(),
+        output,
+        success: false,
+    }
+}
+
 #[async_trait]
 impl Tool for ExecuteCodeTool {
     fn name(&self) -> &str {
@@ -95,15 +302,21 @@ impl Tool for ExecuteCodeTool {
     }
 
     fn definition(&self) -> ToolDefinition {
+        let languages = self.allowed_languages();
         ToolDefinition {
             name: "execute_code".into(),
-            description: "Execute code in various programming languages. Writes code to a temp file, runs it, and returns stdout/stderr. Supports: python, javascript, ruby, bash, php, go, rust, c, typescript.".into(),
+            description: format!(
+                "Execute code in a sandbox with resource limits ({}s, {} MB memory). Writes code to a temp file, runs it, and returns stdout/stderr. Supports: {}.",
+                self.config.timeout_secs,
+                self.config.memory_mb,
+                languages.join(", ")
+            ),
             parameters: serde_json::json!({
                 "type": "object",
                 "properties": {
                     "language": {
                         "type": "string",
-                        "enum": ["python", "javascript", "ruby", "bash", "php", "go", "rust", "c", "typescript"],
+                        "enum": languages,
                         "description": "Programming language"
                     },
                     "code": {
@@ -112,7 +325,7 @@ impl Tool for ExecuteCodeTool {
                     },
                     "timeout_secs": {
                         "type": "integer",
-                        "description": "Execution timeout in seconds (default: 30, max: 120)"
+                        "description": format!("Execution timeout in seconds (default and max: {})", self.config.timeout_secs)
                     }
                 },
                 "required": ["language", "code"]
@@ -121,146 +334,139 @@ impl Tool for ExecuteCodeTool {
     }
 
     async fn execute(&self, arguments: &str) -> Result<ToolResult> {
-        let args: serde_json::Value = serde_json::from_str(arguments)
-            .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
+        let args: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| BizClawError::Tool(e.to_string()))?;
 
         let language = args["language"]
             .as_str()
-            .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'language'".into()))?;
+            .ok_or_else(|| BizClawError::Tool("Missing 'language'".into()))?;
         let code = args["code"]
             .as_str()
-            .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'code'".into()))?;
-        let timeout = args["timeout_secs"].as_u64().unwrap_or(30).min(120);
-
-        let config = get_lang_config(language)
-            .ok_or_else(|| bizclaw_core::error::BizClawError::Tool(
-                format!("Unsupported language: {}. Supported: python, javascript, ruby, bash, php, go, rust, c, typescript", language)
-            ))?;
-
-        // Write code to temp file
-        let temp_dir = std::env::temp_dir().join("bizclaw_exec");
-        tokio::fs::create_dir_all(&temp_dir).await.map_err(|e| {
-            bizclaw_core::error::BizClawError::Tool(format!("Create temp dir: {e}"))
-        })?;
-
-        let file_name = format!(
-            "exec_{}.{}",
-            &uuid::Uuid::new_v4().to_string()[..8],
-            config.extension
-        );
-        let file_path = temp_dir.join(&file_name);
-        tokio::fs::write(&file_path, code).await.map_err(|e| {
-            bizclaw_core::error::BizClawError::Tool(format!("Write temp file: {e}"))
-        })?;
-
-        let start = std::time::Instant::now();
+            .ok_or_else(|| BizClawError::Tool("Missing 'code'".into()))?;
+        let timeout = args["timeout_secs"]
+            .as_u64()
+            .unwrap_or(self.config.timeout_secs)
+            .clamp(1, self.config.timeout_secs.max(1));
 
-        let output = if config.needs_compile {
-            // Compile then run
-            let out_path = temp_dir.join(format!(
-                "exec_{}",
-                &uuid::Uuid::new_v4().to_string()[..8]
+        if !self.config.enabled {
+            return Ok(failed(
+                "Code execution is off; set [autonomy.code] enabled = true".into(),
             ));
+        }
+        if let Some(security) = &self.security
+            && security.autonomy_level() == "readonly"
+        {
+            return Ok(failed(
+                "Code execution is disabled in readonly autonomy mode".into(),
+            ));
+        }
 
-            let compile_output = if config.command == "rustc" {
-                tokio::process::Command::new(config.command)
-                    .arg(file_path.to_str().unwrap())
-                    .arg("-o")
-                    .arg(out_path.to_str().unwrap())
-                    .output()
-                    .await
-            } else {
-                // gcc
-                tokio::process::Command::new(config.command)
-                    .arg(file_path.to_str().unwrap())
-                    .arg("-o")
-                    .arg(out_path.to_str().unwrap())
-                    .output()
-                    .await
-            };
-
-            match compile_output {
-                Ok(co) if !co.status.success() => {
-                    let stderr = String::from_utf8_lossy(&co.stderr);
-                    let _ = tokio::fs::remove_file(&file_path).await;
-                    return Ok(ToolResult {
-                        tool_call_id: String::new(),
-                        output: format!("Compilation failed:\n{}", stderr),
-                        success: false,
-                    });
+        let allowed = self.allowed_languages();
+        let config = match get_lang_config(language) {
+            Some(config) if allowed.contains(&config.name) => config,
+            _ => {
+                return Ok(failed(format!(
+                    "Language '{}' is not enabled. Allowed: {}",
+                    language,
+                    allowed.join(", ")
+                )));
+            }
+        };
+
+        // Fresh working directory per run, removed afterwards.
+        let dir = std::env::temp_dir()
+            .join("bizclaw_exec")
+            .join(uuid::Uuid::new_v4().to_string());
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Create temp dir: {e}")))?;
+
+        let result = self.run_in(&dir, &config, code, timeout).await;
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        result
+    }
+}
+
+impl ExecuteCodeTool {
+    async fn run_in(
+        &self,
+        dir: &Path,
+        config: &LangConfig,
+        code: &str,
+        timeout: u64,
+    ) -> Result<ToolResult> {
+        let file_path = dir.join(format!("main.{}", config.extension));
+        tokio::fs::write(&file_path, code)
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Write temp file: {e}")))?;
+        let file = file_path.to_string_lossy().to_string();
+
+        let start = std::time::Instant::now();
+
+        let (program, run_args) = if config.needs_compile {
+            // Compile then run, both inside the sandbox.
+            let out_path = dir.join("main").to_string_lossy().to_string();
+            let compile_args = vec![file, "-o".to_string(), out_path.clone()];
+            match self
+                .run_sandboxed(config.command, &compile_args, dir, timeout)
+                .await
+            {
+                Ok(Some(co)) if !co.status.success() => {
+                    return Ok(failed(format!("Compilation failed:\n{}", co.stderr)));
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return Ok(failed(format!(
+                        "⏰ Compilation timed out after {}s",
+                        timeout
+                    )));
                 }
                 Err(e) => {
-                    let _ = tokio::fs::remove_file(&file_path).await;
-                    return Ok(ToolResult {
-                        tool_call_id: String::new(),
-                        output: format!("Compiler not found ({}): {}", config.command, e),
-                        success: false,
-                    });
+                    return Ok(failed(format!(
+                        "Compiler not found ({}): {}",
+                        config.command, e
+                    )));
                 }
-                _ => {}
             }
-
-            let run = tokio::time::timeout(
-                std::time::Duration::from_secs(timeout),
-                tokio::process::Command::new(out_path.to_str().unwrap()).output(),
-            )
-            .await;
-
-            let _ = tokio::fs::remove_file(&file_path).await;
-            let _ = tokio::fs::remove_file(&out_path).await;
-            run
+            (out_path, vec![])
         } else {
-            // Interpreted — just run
-            let mut cmd_args: Vec<String> = config.args;
-            cmd_args.push(file_path.to_str().unwrap().to_string());
-
-            let run = tokio::time::timeout(
-                std::time::Duration::from_secs(timeout),
-                tokio::process::Command::new(config.command)
-                    .args(&cmd_args)
-                    .output(),
-            )
-            .await;
-
-            let _ = tokio::fs::remove_file(&file_path).await;
-            run
+            let mut cmd_args = config.args.clone();
+            if config.command == "node" {
+                // V8 reserves its heap up front; size it to the memory limit.
+                cmd_args.push(format!("--max-old-space-size={}", self.config.memory_mb));
+            }
+            cmd_args.push(file);
+            (config.command.to_string(), cmd_args)
         };
 
+        let run = self.run_sandboxed(&program, &run_args, dir, timeout).await;
         let elapsed = start.elapsed();
 
-        match output {
-            Ok(Ok(o)) => {
-                let stdout = String::from_utf8_lossy(&o.stdout);
-                let stderr = String::from_utf8_lossy(&o.stderr);
-
+        match run {
+            Ok(Some(o)) => {
                 let mut result = format!(
                     "Language: {} | Exit: {} | Time: {:.1}s\n",
-                    language,
+                    config.name,
                     o.status.code().unwrap_or(-1),
                     elapsed.as_secs_f64()
                 );
 
-                if !stdout.is_empty() {
-                    let stdout_display = if stdout.len() > 5000 {
-                        format!(
-                            "{}...\n[truncated, {} bytes total]",
-                            &stdout[..5000],
-                            stdout.len()
-                        )
-                    } else {
-                        stdout.to_string()
-                    };
-                    result.push_str(&format!("\nSTDOUT:\n{}", stdout_display));
+                if let Some(note) = limit_note(&o.status) {
+                    result.push_str(&format!("⚠️ Stopped: {note}\n"));
+                }
+                if !o.stdout.is_empty() {
+                    result.push_str(&format!("\nSTDOUT:\n{}", o.stdout));
+                }
+                if !o.stderr.is_empty() {
+                    result.push_str(&format!("\nSTDERR:\n{}", o.stderr));
                 }
-                if !stderr.is_empty() {
-                    let stderr_display = if stderr.len() > 2000 {
-                        format!("{}...\n[truncated]", &stderr[..2000])
-                    } else {
-                        stderr.to_string()
-                    };
-                    result.push_str(&format!("\nSTDERR:\n{}", stderr_display));
+                if o.truncated {
+                    result.push_str(&format!(
+                        "\n... [output truncated at {} bytes]",
+                        self.config.max_output_bytes
+                    ));
                 }
-                if stdout.is_empty() && stderr.is_empty() {
+                if o.stdout.is_empty() && o.stderr.is_empty() {
                     result.push_str("\n(no output)");
                 }
 
@@ -270,19 +476,111 @@ impl Tool for ExecuteCodeTool {
                     success: o.status.success(),
                 })
             }
-            Ok(Err(e)) => Ok(ToolResult {
-                tool_call_id: String::new(),
-                output: format!(
-                    "Execution failed — '{}' not found or not executable: {}",
-                    config.command, e
-                ),
-                success: false,
-            }),
-            Err(_) => Ok(ToolResult {
-                tool_call_id: String::new(),
-                output: format!("⏰ Execution timed out after {}s", timeout),
-                success: false,
-            }),
+            Ok(None) => Ok(failed(format!("⏰ Execution timed out after {}s", timeout))),
+            Err(e) => Ok(failed(format!(
+                "Execution failed — '{}' not found or not executable: {}",
+                program, e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Level(&'static str);
+
+    #[async_trait]
+    impl SecurityPolicy for Level {
+        async fn check_command(&self, _command: &str) -> Result<bool> {
+            Ok(true)
+        }
+        async fn check_path(&self, _path: &str) -> Result<bool> {
+            Ok(true)
+        }
+        fn autonomy_level(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn has_python() -> bool {
+        std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_ok()
+    }
+
+    fn enabled() -> CodeExecutionConfig {
+        CodeExecutionConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn args(language: &str, code: &str) -> String {
+        serde_json::json!({ "language": language, "code": code }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_python_runs_in_clean_dir() {
+        if !has_python() {
+            return;
+        }
+        let tool = ExecuteCodeTool::with_config(enabled()).with_security(Arc::new(Level("supervised")));
+        let result = tool
+            .execute(&args(
+                "python",
+                "import os\nprint('hi', len(os.listdir('.')), os.environ.get('SECRET'))",
+            ))
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("hi 1 None"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_memory_and_time_limits() {
+        if !has_python() {
+            return;
         }
+        let tool = ExecuteCodeTool::with_config(CodeExecutionConfig {
+            timeout_secs: 1,
+            memory_mb: 64,
+            ..enabled()
+        });
+
+        let result = tool
+            .execute(&args("python", "x = bytearray(512 * 1024 * 1024)\nprint('allocated')"))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("MemoryError"), "{}", result.output);
+
+        let result = tool
+            .execute(&args("python", "import time\ntime.sleep(30)"))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("timed out after 1s"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_readonly_and_unlisted_language_refused() {
+        let result = ExecuteCodeTool::new().execute(&args("python", "print(1)")).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("off"), "{}", result.output);
+
+        let tool = ExecuteCodeTool::with_config(enabled()).with_security(Arc::new(Level("readonly")));
+        let result = tool.execute(&args("python", "print(1)")).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("readonly"));
+
+        let tool = ExecuteCodeTool::with_config(enabled());
+        let result = tool.execute(&args("ruby", "puts 1")).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("not enabled"));
+        let enum_langs = &tool.definition().parameters["properties"]["language"]["enum"];
+        assert_eq!(enum_langs, &serde_json::json!(["python", "javascript"]));
     }
 }
@@ -5,11 +5,23 @@ use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 
-pub struct FileTool;
+use crate::path_guard::PathGuard;
+
+pub struct FileTool {
+    guard: PathGuard,
+}
 
 impl FileTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Confine paths to a workspace and security policy.
+    pub fn with_path_guard(mut self, guard: PathGuard) -> Self {
+        self.guard = guard;
+        self
     }
 }
 
@@ -55,10 +67,11 @@ impl Tool for FileTool {
         let path = args["path"]
             .as_str()
             .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'path'".into()))?;
+        let resolved = self.guard.resolve(path).await?;
 
         let result = match action {
             "read" => {
-                let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+                let content = tokio::fs::read_to_string(&resolved).await.map_err(|e| {
                     bizclaw_core::error::BizClawError::Tool(format!("Read failed: {e}"))
                 })?;
 
@@ -103,12 +116,12 @@ impl Tool for FileTool {
             "write" => {
                 let content = args["content"].as_str().unwrap_or("");
                 // Create parent directories if needed
-                if let Some(parent) = std::path::Path::new(path).parent() {
+                if let Some(parent) = resolved.parent() {
                     tokio::fs::create_dir_all(parent).await.map_err(|e| {
                         bizclaw_core::error::BizClawError::Tool(format!("Create dir: {e}"))
                     })?;
                 }
-                tokio::fs::write(path, content)
+                tokio::fs::write(&resolved, content)
                     .await
                     .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
                 format!("Written {} bytes to {path}", content.len())
@@ -119,7 +132,7 @@ impl Tool for FileTool {
                 let mut file = tokio::fs::OpenOptions::new()
                     .create(true)
                     .append(true)
-                    .open(path)
+                    .open(&resolved)
                     .await
                     .map_err(|e| {
                         bizclaw_core::error::BizClawError::Tool(format!("Open failed: {e}"))
@@ -130,7 +143,7 @@ impl Tool for FileTool {
                 format!("Appended {} bytes to {path}", content.len())
             }
             "list" => {
-                let mut entries_result = tokio::fs::read_dir(path)
+                let mut entries_result = tokio::fs::read_dir(&resolved)
                     .await
                     .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
 
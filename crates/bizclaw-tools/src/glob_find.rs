@@ -6,11 +6,23 @@ use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 use std::path::Path;
 
-pub struct GlobTool;
+use crate::path_guard::PathGuard;
+
+pub struct GlobTool {
+    guard: PathGuard,
+}
 
 impl GlobTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Confine paths to a workspace and security policy.
+    pub fn with_path_guard(mut self, guard: PathGuard) -> Self {
+        self.guard = guard;
+        self
     }
 }
 
@@ -69,7 +81,8 @@ impl Tool for GlobTool {
         };
 
         // Use walkdir to find files matching the pattern
-        let base = Path::new(directory);
+        let resolved = self.guard.resolve(directory).await?;
+        let base = resolved.as_path();
         let mut results = Vec::new();
 
         // Walk directory tree
@@ -155,8 +168,10 @@ fn walk_recursive(
                 continue;
             }
         }
+        // Symlinked directories are listed but not followed out of the tree.
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
         result.push(path.clone());
-        if path.is_dir() {
+        if is_dir {
             walk_recursive(&path, depth + 1, max_depth, result);
         }
     }
@@ -6,11 +6,23 @@ use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 use std::path::Path;
 
-pub struct GrepTool;
+use crate::path_guard::PathGuard;
+
+pub struct GrepTool {
+    guard: PathGuard,
+}
 
 impl GrepTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            guard: PathGuard::new(),
+        }
+    }
+
+    /// Confine paths to a workspace and security policy.
+    pub fn with_path_guard(mut self, guard: PathGuard) -> Self {
+        self.guard = guard;
+        self
     }
 }
 
@@ -81,7 +93,8 @@ impl Tool for GrepTool {
             bizclaw_core::error::BizClawError::Tool(format!("Invalid pattern: {e}"))
         })?;
 
-        let root = Path::new(path);
+        let resolved = self.guard.resolve(path).await?;
+        let root = resolved.as_path();
         let mut matches = Vec::new();
 
         if root.is_file() {
@@ -187,9 +200,11 @@ fn search_dir(
             }
         }
 
-        if path.is_dir() {
+        // Symlinks are skipped so a search cannot follow them out of the tree.
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
             search_dir(&path, re, include, matches, max, depth + 1, max_depth);
-        } else if path.is_file() {
+        } else if file_type.is_file() {
             // Check extension filter
             if let Some(ext_filter) = include {
                 if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
+use std::net::{IpAddr, SocketAddr};
 
 pub struct HttpRequestTool;
 
@@ -70,42 +71,19 @@ impl Tool for HttpRequestTool {
         let timeout = args["timeout_secs"].as_u64().unwrap_or(15);
 
         // Safety check: block requests to internal/private/metadata endpoints (SSRF protection)
-        let lower_url = url.to_lowercase();
-        // Must be HTTP(S)
-        if !lower_url.starts_with("http://") && !lower_url.starts_with("https://") {
+        if let Some(reason) = is_url_blocked(url) {
             return Ok(ToolResult {
                 tool_call_id: String::new(),
-                output: "Blocked: Only HTTP/HTTPS schemes allowed".into(),
+                output: format!("Blocked: {reason}"),
                 success: false,
             });
         }
-        // Block private/internal destinations
-        let blocked_patterns = [
-            "127.0.0.1", "localhost", "0.0.0.0", "[::1]", "[::0]",
-            "169.254.", "metadata.google", "metadata.aws",
-            "10.", "192.168.",
-            // 172.16.0.0/12
-            "172.16.", "172.17.", "172.18.", "172.19.",
-            "172.20.", "172.21.", "172.22.", "172.23.",
-            "172.24.", "172.25.", "172.26.", "172.27.",
-            "172.28.", "172.29.", "172.30.", "172.31.",
-        ];
-        // Extract host portion (after ://)
-        let host_part = lower_url.split("://").nth(1).unwrap_or("");
-        let host = host_part.split('/').next().unwrap_or("");
-        let host_no_port = host.split(':').next().unwrap_or("");
-        if blocked_patterns.iter().any(|p| host_no_port.contains(p)) {
-            return Ok(ToolResult {
-                tool_call_id: String::new(),
-                output: format!("Blocked: Cannot access internal/private network ({host_no_port})"),
-                success: false,
-            });
-        }
-
 
         let client = reqwest::Client::builder()
             .user_agent("BizClaw/1.0")
             .timeout(std::time::Duration::from_secs(timeout))
+            // Redirects may lead anywhere; names are checked as they resolve
+            .dns_resolver(std::sync::Arc::new(PublicResolver))
             .build()
             .map_err(|e| bizclaw_core::error::BizClawError::Tool(format!("Client error: {e}")))?;
 
@@ -196,29 +174,71 @@ impl Tool for HttpRequestTool {
     }
 }
 
-/// Check if a URL is blocked by SSRF protection.
-/// Extracted for testability.
+/// Cloud metadata hostnames, refused along with everything under them.
+const METADATA_HOSTS: &[&str] = &["metadata.google.internal", "metadata.aws.amazon.com", "metadata.goog"];
+
+/// Check if a URL is blocked by SSRF protection: a scheme other than
+/// http(s), or a host that is internal — `localhost`, a cloud metadata
+/// name, or an IP address [`is_ip_blocked`] refuses, in any notation the
+/// URL parser accepts (`[::1]`, `127.0.0.2`, `2130706433`, `0x7f.1`).
+/// Names that resolve to internal addresses are caught by [`PublicResolver`].
 pub fn is_url_blocked(url: &str) -> Option<String> {
-    let lower_url = url.to_lowercase();
-    if !lower_url.starts_with("http://") && !lower_url.starts_with("https://") {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return Some("Invalid URL".into());
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
         return Some("Only HTTP/HTTPS schemes allowed".into());
     }
-    let blocked_patterns = [
-        "127.0.0.1", "localhost", "0.0.0.0", "[::1]", "[::0]",
-        "169.254.", "metadata.google", "metadata.aws",
-        "10.",  "192.168.",
-        "172.16.", "172.17.", "172.18.", "172.19.",
-        "172.20.", "172.21.", "172.22.", "172.23.",
-        "172.24.", "172.25.", "172.26.", "172.27.",
-        "172.28.", "172.29.", "172.30.", "172.31.",
-    ];
-    let host_part = lower_url.split("://").nth(1).unwrap_or("");
-    let host = host_part.split('/').next().unwrap_or("");
-    let host_no_port = host.split(':').next().unwrap_or("");
-    if blocked_patterns.iter().any(|p| host_no_port.contains(p)) {
-        return Some(format!("Cannot access internal/private network ({host_no_port})"));
+    let blocked = match parsed.host() {
+        Some(url::Host::Ipv4(ip)) => is_ip_blocked(ip.into()),
+        Some(url::Host::Ipv6(ip)) => is_ip_blocked(ip.into()),
+        Some(url::Host::Domain(name)) => {
+            let name = name.trim_end_matches('.');
+            name == "localhost"
+                || name.ends_with(".localhost")
+                || METADATA_HOSTS
+                    .iter()
+                    .any(|h| name == *h || name.ends_with(&format!(".{h}")))
+        }
+        None => return Some("URL has no host".into()),
+    };
+    blocked.then(|| {
+        format!("Cannot access internal/private network ({})", parsed.host_str().unwrap_or_default())
+    })
+}
+
+/// Whether `ip` is internal: loopback, private, link-local (cloud metadata
+/// lives at 169.254.169.254), unique-local, unspecified or broadcast.
+/// IPv4-mapped IPv6 addresses are judged as the IPv4 address.
+pub fn is_ip_blocked(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_ip_blocked(IpAddr::V4(ip)),
+            None => ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local(),
+        },
+    }
+}
+
+/// DNS resolver for clients that must stay off internal networks: a name
+/// resolving to any address [`is_ip_blocked`] refuses fails to connect.
+/// Checking at resolution time covers every redirect hop and DNS rebinding.
+pub struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|a| is_ip_blocked(a.ip())) {
+                return Err(format!("{host} resolves to internal address {}", addr.ip()).into());
+            }
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
     }
-    None
 }
 
 #[cfg(test)]
@@ -241,13 +261,41 @@ mod tests {
 
     #[test]
     fn test_block_loopback_ipv6() {
-        // Note: IPv6 in URL uses brackets, but our host extraction splits on ':',
-        // so [::1] becomes "[" after split — the pattern "[::1]" won't match.
-        // We test that the SSRF checker at least blocks the scheme check for non-http.
-        // For full IPv6 support, a proper URL parser would be needed.
-        // For now, verify localhost and 127.0.0.1 cover the common cases.
-        assert!(is_url_blocked("http://localhost/").is_some());
-        assert!(is_url_blocked("http://127.0.0.1/").is_some());
+        assert!(is_url_blocked("http://[::1]:8080/").is_some());
+        assert!(is_url_blocked("http://[::]/").is_some());
+        assert!(is_url_blocked("http://[::ffff:127.0.0.1]/").is_some());
+        assert!(is_url_blocked("http://[fd00::1]/").is_some());
+        assert!(is_url_blocked("http://[fe80::1]/").is_some());
+        assert!(is_url_blocked("http://[2606:4700::1111]/").is_none());
+    }
+
+    #[test]
+    fn test_block_other_ip_notations() {
+        for url in [
+            "http://127.0.0.2/",
+            "http://2130706433/",
+            "http://0x7f.1/",
+            "http://0177.0.0.1/",
+            "http://LOCALHOST./",
+            "http://app.localhost/",
+        ] {
+            assert!(is_url_blocked(url).is_some(), "Should block {url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_refuses_internal_names() {
+        use reqwest::dns::Resolve;
+        let name: reqwest::dns::Name = "localhost".parse().unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+
+        let client = reqwest::Client::builder()
+            .dns_resolver(std::sync::Arc::new(PublicResolver))
+            .no_proxy()
+            .build()
+            .unwrap();
+        let err = client.get("http://localhost:9/").send().await.unwrap_err();
+        assert!(format!("{err:?}").contains("internal address"), "{err:?}");
     }
 
     #[test]
@@ -342,7 +390,7 @@ mod tests {
 
     #[test]
     fn test_default_impl() {
-        let tool = HttpRequestTool::default();
+        let tool = HttpRequestTool;
         assert_eq!(tool.name(), "http_request");
     }
 
@@ -11,18 +11,23 @@
 //! | grep | Search file contents with regex |
 //! | web_search | DuckDuckGo search (no key needed) |
 //! | http_request | Make HTTP requests to APIs |
+//! | browse | Read web pages as clean text (robots.txt-aware) |
 //! | config_manager | Read/write config.toml at runtime |
 //! | memory_search | Search past conversation memory |
-//! | execute_code | Run code in 9 languages |
+//! | execute_code | Run code in a resource-limited sandbox |
 //! | plan | Structured task decomposition |
 //! | session_context | Session self-awareness for agent |
 //! | group_summarizer | Buffer + summarize group messages |
 //! | calendar | Google Calendar integration |
 //! | document_reader | Offline PDF/DOCX/XLSX/CSV reader |
+//! | csv_query | Stats, filters, grouping and pivots over CSV/XLSX |
+//! | schedule | Reminders and recurring tasks from plain-language schedules |
 //! + MCP server tools (dynamic)
 
+pub mod browse;
 pub mod calendar;
 pub mod config_manager;
+pub mod csv_query;
 pub mod document_reader;
 pub mod edit_file;
 pub mod execute_code;
@@ -33,9 +38,11 @@ pub mod group_summarizer;
 pub mod http_request;
 pub mod memory_search;
 pub mod orchestration;
+pub mod path_guard;
 pub mod plan_tool;
 pub mod plan_store;
 pub mod registry;
+pub mod schedule;
 pub mod session_context;
 pub mod shell;
 pub mod web_search;
@@ -56,6 +63,19 @@ impl ToolRegistry {
         self.tools.push(tool);
     }
 
+    /// Replace the tool with the same name, or register it if absent.
+    pub fn replace(&mut self, tool: Box<dyn Tool>) {
+        match self.tools.iter().position(|t| t.name() == tool.name()) {
+            Some(i) => self.tools[i] = tool,
+            None => self.tools.push(tool),
+        }
+    }
+
+    /// Unregister the tool called `name`, if any.
+    pub fn remove(&mut self, name: &str) {
+        self.tools.retain(|t| t.name() != name);
+    }
+
     pub fn get(&self, name: &str) -> Option<&dyn Tool> {
         self.tools
             .iter()
@@ -63,6 +83,32 @@ impl ToolRegistry {
             .map(|t| t.as_ref())
     }
 
+    /// Check model-generated `arguments` against the parameter schema of
+    /// tool `name` before it runs. The error is a JSON message for the model
+    /// listing the problems and the expected schema, so it can fix the call.
+    pub fn check_arguments(&self, name: &str, arguments: &str) -> std::result::Result<(), String> {
+        let Some(tool) = self.get(name) else {
+            return Ok(());
+        };
+        let schema = tool.definition().parameters;
+        let arguments = if arguments.trim().is_empty() { "{}" } else { arguments };
+        let problems = match serde_json::from_str::<serde_json::Value>(arguments) {
+            Ok(value) => bizclaw_core::schema::validate(&value, &schema),
+            Err(e) => vec![format!("$: arguments are not valid JSON ({e})")],
+        };
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(serde_json::json!({
+            "error": "invalid_arguments",
+            "tool": name,
+            "problems": problems,
+            "parameters": schema,
+            "hint": "Fix the arguments to match the parameters schema and call the tool again.",
+        })
+        .to_string())
+    }
+
     pub fn list(&self) -> Vec<bizclaw_core::types::ToolDefinition> {
         self.tools.iter().map(|t| t.definition()).collect()
     }
@@ -83,6 +129,7 @@ impl ToolRegistry {
         // Search & network tools
         reg.register(Box::new(web_search::WebSearchTool::new()));
         reg.register(Box::new(http_request::HttpRequestTool::new()));
+        reg.register(Box::new(browse::BrowseTool::new()));
         // Config & code tools
         reg.register(Box::new(config_manager::ConfigManagerTool::new()));
         reg.register(Box::new(execute_code::ExecuteCodeTool::new()));
@@ -96,6 +143,7 @@ impl ToolRegistry {
             calendar::CalendarConfig::default(),
         )));
         reg.register(Box::new(document_reader::DocumentReaderTool::new()));
+        reg.register(Box::new(csv_query::CsvQueryTool::new()));
         reg
     }
 
@@ -114,6 +162,16 @@ impl ToolRegistry {
         self.register(Box::new(session_context::SessionContextTool::new(info)));
     }
 
+    /// Register the schedule tool, adding tasks to `scheduler` that run on
+    /// `agent_name`.
+    pub fn register_schedule(
+        &mut self,
+        scheduler: std::sync::Arc<tokio::sync::Mutex<bizclaw_scheduler::SchedulerEngine>>,
+        agent_name: Option<String>,
+    ) {
+        self.register(Box::new(schedule::ScheduleTool::new(scheduler, agent_name)));
+    }
+
     /// Register multiple tools at once (e.g., from MCP bridge).
     pub fn register_many(&mut self, tools: Vec<Box<dyn Tool>>) {
         for tool in tools {
@@ -131,6 +189,11 @@ impl ToolRegistry {
     pub fn tool_names(&self) -> Vec<String> {
         self.tools.iter().map(|t| t.name().to_string()).collect()
     }
+
+    /// Take the registered tools out of the registry.
+    pub fn into_tools(self) -> Vec<Box<dyn Tool>> {
+        self.tools
+    }
 }
 
 impl Default for ToolRegistry {
@@ -143,6 +206,23 @@ impl Default for ToolRegistry {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_arguments_against_schema() {
+        let reg = ToolRegistry::with_defaults();
+        assert!(reg.check_arguments("web_search", r#"{"query": "rust"}"#).is_ok());
+        assert!(reg.check_arguments("unknown_tool", "{}").is_ok());
+
+        let err = reg.check_arguments("web_search", r#"{"query": 42}"#).unwrap_err();
+        let err: serde_json::Value = serde_json::from_str(&err).unwrap();
+        assert_eq!(err["error"], "invalid_arguments");
+        assert_eq!(err["problems"][0], "$.query: expected string, got 42");
+        assert_eq!(err["parameters"]["required"][0], "query");
+
+        let err = reg.check_arguments("web_search", "").unwrap_err();
+        assert!(err.contains("missing required property 'query'"));
+        assert!(reg.check_arguments("web_search", "{query").unwrap_err().contains("not valid JSON"));
+    }
+
     #[test]
     fn test_registry_with_defaults() {
         let reg = ToolRegistry::with_defaults();
@@ -154,15 +234,18 @@ mod tests {
         assert!(reg.get("grep").is_some());
         assert!(reg.get("web_search").is_some());
         assert!(reg.get("http_request").is_some());
+        assert!(reg.get("browse").is_some());
         assert!(reg.get("config_manager").is_some());
         assert!(reg.get("execute_code").is_some());
         assert!(reg.get("plan").is_some());
         assert!(reg.get("group_summarizer").is_some());
         assert!(reg.get("calendar").is_some());
         assert!(reg.get("document_reader").is_some());
+        assert!(reg.get("csv_query").is_some());
         // These require shared state, registered separately
         assert!(reg.get("memory_search").is_none());
         assert!(reg.get("session_context").is_none());
+        assert!(reg.get("schedule").is_none());
         assert!(reg.get("nonexistent").is_none());
     }
 
@@ -0,0 +1,124 @@
+//! Path guard shared by the file tools.
+//!
+//! Resolves a path a tool was asked to touch: relative paths join the
+//! workspace, the result is canonicalized (following `..` and symlinks, even
+//! for files that do not exist yet) and must stay inside the workspace, then
+//! the security policy's `check_path` gets the final say. A guard with no
+//! workspace and no policy passes paths through unchanged.
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::SecurityPolicy;
+use bizclaw_core::traits::security::canonicalize_lenient;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Clone, Default)]
+pub struct PathGuard {
+    workspace: Option<PathBuf>,
+    security: Option<Arc<dyn SecurityPolicy>>,
+}
+
+impl PathGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve relative paths against `dir` and refuse paths outside it.
+    pub fn with_workspace(mut self, dir: PathBuf) -> Self {
+        self.workspace = Some(dir);
+        self
+    }
+
+    /// Gate paths through a security policy.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Resolve `path` or refuse it with `PermissionDenied`.
+    pub async fn resolve(&self, path: &str) -> Result<PathBuf> {
+        if self.workspace.is_none() && self.security.is_none() {
+            return Ok(PathBuf::from(path));
+        }
+        let requested = match &self.workspace {
+            Some(ws) if Path::new(path).is_relative() => ws.join(path),
+            _ => PathBuf::from(path),
+        };
+        let resolved = canonicalize_lenient(&requested);
+
+        if let Some(ws) = &self.workspace
+            && !resolved.starts_with(canonicalize_lenient(ws))
+        {
+            return Err(BizClawError::PermissionDenied(format!(
+                "'{path}' is outside the workspace"
+            )));
+        }
+        if let Some(security) = &self.security
+            && !security.check_path(&resolved.to_string_lossy()).await?
+        {
+            return Err(BizClawError::PermissionDenied(format!(
+                "'{path}' is forbidden"
+            )));
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct DenySecrets;
+
+    #[async_trait]
+    impl SecurityPolicy for DenySecrets {
+        async fn check_command(&self, _command: &str) -> Result<bool> {
+            Ok(true)
+        }
+        async fn check_path(&self, path: &str) -> Result<bool> {
+            Ok(!Path::new(path).components().any(|c| c.as_os_str() == "secrets"))
+        }
+        fn autonomy_level(&self) -> &str {
+            "supervised"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_inside_workspace() {
+        let ws = std::env::temp_dir().join(format!("bizclaw_guard_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(ws.join("docs")).unwrap();
+        let root = ws.canonicalize().unwrap();
+        let guard = PathGuard::new()
+            .with_workspace(ws.clone())
+            .with_security(Arc::new(DenySecrets));
+
+        assert_eq!(guard.resolve("docs/new.md").await.unwrap(), root.join("docs/new.md"));
+        assert_eq!(guard.resolve("docs/../a.txt").await.unwrap(), root.join("a.txt"));
+        for escape in ["../outside.txt", "docs/../../outside.txt", "/etc/passwd"] {
+            assert!(
+                matches!(guard.resolve(escape).await, Err(BizClawError::PermissionDenied(_))),
+                "{escape}"
+            );
+        }
+        assert!(guard.resolve("secrets/key.txt").await.is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/etc", ws.join("etc_link")).unwrap();
+            assert!(guard.resolve("etc_link/passwd").await.is_err());
+            // Dangling links: writing through one would create its target
+            let outside = std::env::temp_dir().join(format!("bizclaw_guard_out_{}", uuid::Uuid::new_v4()));
+            std::os::unix::fs::symlink(&outside, ws.join("out")).unwrap();
+            assert!(guard.resolve("out").await.is_err());
+            std::os::unix::fs::symlink("docs/later.md", ws.join("later")).unwrap();
+            assert_eq!(guard.resolve("later").await.unwrap(), root.join("docs/later.md"));
+        }
+
+        assert_eq!(
+            PathGuard::new().resolve("../anything").await.unwrap(),
+            PathBuf::from("../anything")
+        );
+        std::fs::remove_dir_all(&ws).ok();
+    }
+}
@@ -0,0 +1,317 @@
+//! Schedule tool — lets agents create, list and cancel scheduled tasks from
+//! conversation ("remind me every Monday at 9 to send the report").
+//!
+//! Creating takes two calls: `create` parses the schedule into a draft and
+//! returns a preview to read back to the user; only `confirm` saves it.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_scheduler::natural::{format_offset, parse_offset};
+use bizclaw_scheduler::tasks::TaskAction;
+use bizclaw_scheduler::{SchedulerEngine, Task};
+
+/// Unconfirmed drafts kept per tool.
+const MAX_DRAFTS: usize = 10;
+
+pub struct ScheduleTool {
+    scheduler: Arc<tokio::sync::Mutex<SchedulerEngine>>,
+    /// Agent that runs the prompt tasks it creates (None = default agent).
+    agent_name: Option<String>,
+    /// Timezone times are read in when the call gives none.
+    utc_offset_mins: i32,
+    drafts: tokio::sync::Mutex<Vec<Task>>,
+}
+
+impl ScheduleTool {
+    /// Tool adding tasks to `scheduler` for `agent_name`, reading times in
+    /// the server's timezone.
+    pub fn new(scheduler: Arc<tokio::sync::Mutex<SchedulerEngine>>, agent_name: Option<String>) -> Self {
+        Self {
+            scheduler,
+            agent_name,
+            utc_offset_mins: chrono::Local::now().offset().local_minus_utc() / 60,
+            drafts: tokio::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Read times at `utc_offset_mins` by default.
+    pub fn with_utc_offset(mut self, utc_offset_mins: i32) -> Self {
+        self.utc_offset_mins = utc_offset_mins;
+        self
+    }
+
+    async fn create(&self, args: &serde_json::Value) -> Result<ToolResult> {
+        let when = required(args, "when")?;
+        let message = required(args, "message")?;
+        let offset = match args["utc_offset"].as_str().filter(|s| !s.is_empty()) {
+            Some(text) => match parse_offset(text) {
+                Some(offset) => offset,
+                None => return Ok(failure(format!("Invalid utc_offset '{text}', expected e.g. '+07:00'."))),
+            },
+            None => self.utc_offset_mins,
+        };
+        let schedule = match bizclaw_scheduler::parse_schedule(when, chrono::Utc::now(), offset) {
+            Ok(schedule) => schedule,
+            Err(e) => return Ok(failure(e)),
+        };
+
+        let prompt = args["kind"].as_str() == Some("prompt");
+        let action = if prompt {
+            TaskAction::AgentPrompt(message.to_string())
+        } else {
+            TaskAction::Notify(message.to_string())
+        };
+        let name = args["name"]
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| message.chars().take(60).collect());
+        let summary = schedule.summary.clone();
+        let mut task = schedule.into_task(&name, action);
+        task.agent_name = self.agent_name.clone();
+        let deliver_to = args["deliver_to"].as_str().filter(|s| !s.is_empty()).map(String::from);
+        task.deliver_to = deliver_to.clone();
+        task.notify_via = deliver_to;
+
+        let first_run = task
+            .next_run
+            .map(|at| {
+                let local = at + chrono::Duration::minutes(offset as i64);
+                format!("{} ({})", local.format("%a %Y-%m-%d %H:%M"), format_offset(offset))
+            })
+            .unwrap_or_else(|| "unknown".into());
+        let output = format!(
+            "Draft {} '{name}': {summary}, first run {first_run}.\n\
+             Not scheduled yet — read this back to the user and ask them to confirm. \
+             Once they agree, call schedule with action \"confirm\" and id \"{}\".",
+            if prompt { "task" } else { "reminder" },
+            task.id
+        );
+
+        let mut drafts = self.drafts.lock().await;
+        drafts.push(task);
+        if drafts.len() > MAX_DRAFTS {
+            drafts.remove(0);
+        }
+        Ok(success(output))
+    }
+
+    async fn confirm(&self, id: &str) -> Result<ToolResult> {
+        let task = {
+            let mut drafts = self.drafts.lock().await;
+            match drafts.iter().position(|t| t.id == id) {
+                Some(i) => drafts.remove(i),
+                None => {
+                    return Ok(failure(format!(
+                        "No draft '{id}'. Create it again with action \"create\"."
+                    )));
+                }
+            }
+        };
+        let name = task.name.clone();
+        self.scheduler.lock().await.add_task(task);
+        Ok(success(format!("Scheduled '{name}' (task id {id}).")))
+    }
+
+    async fn cancel(&self, id: &str) -> Result<ToolResult> {
+        {
+            let mut drafts = self.drafts.lock().await;
+            let len = drafts.len();
+            drafts.retain(|t| t.id != id);
+            if drafts.len() < len {
+                return Ok(success(format!("Draft '{id}' discarded.")));
+            }
+        }
+        let mut scheduler = self.scheduler.lock().await;
+        let name = scheduler
+            .list_tasks()
+            .iter()
+            .find(|t| t.id == id && self.owns(t))
+            .map(|t| t.name.clone());
+        match name {
+            Some(name) => {
+                scheduler.remove_task(id);
+                Ok(success(format!("Cancelled '{name}' ({id}).")))
+            }
+            None => Ok(failure(format!("No scheduled task '{id}'. Use action \"list\" to see task ids."))),
+        }
+    }
+
+    async fn list(&self) -> Result<ToolResult> {
+        let scheduler = self.scheduler.lock().await;
+        let lines: Vec<String> = scheduler
+            .list_tasks()
+            .iter()
+            .filter(|t| self.owns(t))
+            .map(|t| {
+                let next = t.next_run.map_or_else(|| "-".into(), |at| at.format("%Y-%m-%d %H:%M UTC").to_string());
+                let state = if t.enabled { "" } else { " [disabled]" };
+                format!("- {} '{}' next: {next}{state}", t.id, t.name)
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok(success("No scheduled tasks.".into()));
+        }
+        Ok(success(format!("Scheduled tasks:\n{}", lines.join("\n"))))
+    }
+
+    /// Tasks an agent may see and cancel: its own, or all for the default.
+    fn owns(&self, task: &Task) -> bool {
+        self.agent_name.is_none() || task.agent_name == self.agent_name
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleTool {
+    fn name(&self) -> &str {
+        "schedule"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "schedule".into(),
+            description: "Create, list or cancel scheduled reminders and recurring tasks. \
+                \"create\" only drafts the task and returns a preview: read it back to the user \
+                and call \"confirm\" with the draft id only after they agree."
+                .into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["create", "confirm", "cancel", "list"],
+                        "description": "create a draft, confirm a draft, cancel a task or draft, or list tasks"
+                    },
+                    "when": {
+                        "type": "string",
+                        "description": "For create: the schedule in plain words, e.g. 'every Monday at 9:00', 'daily at 17:30', 'every 30 minutes', 'tomorrow at 8am', 'in 2 hours'"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "For create: the reminder text, or the prompt to run"
+                    },
+                    "kind": {
+                        "type": "string",
+                        "enum": ["reminder", "prompt"],
+                        "description": "reminder sends the message as-is (default); prompt runs it through the agent and sends the answer"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Short task name (default: start of the message)"
+                    },
+                    "deliver_to": {
+                        "type": "string",
+                        "description": "Where to deliver: 'telegram:<chat_id>', 'email:<address>', 'webhook:<url>' or 'dashboard'"
+                    },
+                    "utc_offset": {
+                        "type": "string",
+                        "description": "The user's UTC offset, e.g. '+07:00' (default: server timezone)"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "For confirm: the draft id. For cancel: a task or draft id"
+                    }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| BizClawError::Tool(e.to_string()))?;
+        match args["action"].as_str().unwrap_or("") {
+            "create" => self.create(&args).await,
+            "confirm" => self.confirm(required(&args, "id")?).await,
+            "cancel" => self.cancel(required(&args, "id")?).await,
+            "list" => self.list().await,
+            other => Err(BizClawError::Tool(format!("Unknown action: {other}"))),
+        }
+    }
+}
+
+fn required<'a>(args: &'a serde_json::Value, key: &str) -> Result<&'a str> {
+    args[key]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+}
+
+fn success(output: String) -> ToolResult {
+    ToolResult {
+        tool_call_id: String::new(),
+        output,
+        success: true,
+    }
+}
+
+fn failure(output: String) -> ToolResult {
+    ToolResult {
+        tool_call_id: String::new(),
+        output,
+        success: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool() -> (ScheduleTool, Arc<tokio::sync::Mutex<SchedulerEngine>>) {
+        let dir = std::env::temp_dir().join(format!("bizclaw-schedule-tool-{}", uuid::Uuid::new_v4()));
+        let scheduler = Arc::new(tokio::sync::Mutex::new(SchedulerEngine::new(&dir)));
+        let tool = ScheduleTool::new(scheduler.clone(), Some("assistant".into())).with_utc_offset(420);
+        (tool, scheduler)
+    }
+
+    #[tokio::test]
+    async fn test_create_waits_for_confirmation() {
+        let (tool, scheduler) = tool();
+        let draft = tool
+            .execute(r#"{"action": "create", "when": "every Monday at 9", "message": "Send the weekly report"}"#)
+            .await
+            .unwrap();
+        assert!(draft.success);
+        assert!(draft.output.contains("every Monday at 09:00 (UTC+07:00)"));
+        assert_eq!(scheduler.lock().await.task_count(), 0);
+
+        let id = draft.output.rsplit('"').nth(1).unwrap().to_string();
+        let done = tool
+            .execute(&serde_json::json!({"action": "confirm", "id": id}).to_string())
+            .await
+            .unwrap();
+        assert!(done.success, "{}", done.output);
+        {
+            let engine = scheduler.lock().await;
+            let task = &engine.list_tasks()[0];
+            assert_eq!(task.agent_name.as_deref(), Some("assistant"));
+            // 09:00 in UTC+7 is 02:00 UTC
+            assert!(matches!(&task.task_type, bizclaw_scheduler::TaskType::Cron { expression } if expression == "0 2 * * 1"));
+        }
+        assert!(tool.execute(r#"{"action": "list"}"#).await.unwrap().output.contains(&id));
+
+        let cancelled = tool
+            .execute(&serde_json::json!({"action": "cancel", "id": id}).to_string())
+            .await
+            .unwrap();
+        assert!(cancelled.success);
+        assert_eq!(scheduler.lock().await.task_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unclear_schedule_and_unknown_draft() {
+        let (tool, _) = tool();
+        let result = tool
+            .execute(r#"{"action": "create", "when": "whenever", "message": "x"}"#)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(!tool.execute(r#"{"action": "confirm", "id": "nope"}"#).await.unwrap().success);
+        assert!(tool.execute(r#"{"action": "create", "when": "daily"}"#).await.is_err());
+    }
+}
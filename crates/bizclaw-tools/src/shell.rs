@@ -1,15 +1,174 @@
 //! Shell command execution tool.
+//!
+//! Commands pass the security policy's `check_command` gate, run in a
+//! working directory confined to the workspace, and stream stdout/stderr
+//! line by line when the caller provides an output sink.
 
 use async_trait::async_trait;
-use bizclaw_core::error::Result;
-use bizclaw_core::traits::Tool;
-use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::tool::ToolOutputSink;
+use bizclaw_core::traits::{SecurityPolicy, Tool};
+use bizclaw_core::types::{ToolDefinition, ToolOutputChunk, ToolResult};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 
-pub struct ShellTool;
+/// Most bytes of one output line read at a time; longer lines arrive in
+/// pieces.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// Shell tool configuration.
+#[derive(Debug, Clone)]
+pub struct ShellConfig {
+    /// Workspace root. When set, `workdir` must resolve inside it and
+    /// relative paths are resolved against it.
+    pub workspace: Option<PathBuf>,
+    /// Maximum bytes of stdout + stderr kept in the result.
+    pub max_output_bytes: usize,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            workspace: None,
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+pub struct ShellTool {
+    config: ShellConfig,
+    security: Option<Arc<dyn SecurityPolicy>>,
+}
 
 impl ShellTool {
     pub fn new() -> Self {
-        Self
+        Self::with_config(ShellConfig::default())
+    }
+
+    pub fn with_config(config: ShellConfig) -> Self {
+        Self {
+            config,
+            security: None,
+        }
+    }
+
+    /// Gate commands and working directories through a security policy.
+    pub fn with_security(mut self, security: Arc<dyn SecurityPolicy>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Resolve and validate the requested working directory.
+    async fn resolve_workdir(&self, workdir: Option<&str>) -> Result<Option<PathBuf>> {
+        let requested = match (workdir, &self.config.workspace) {
+            (None, None) => return Ok(None),
+            (None, Some(ws)) => ws.clone(),
+            (Some(dir), Some(ws)) if Path::new(dir).is_relative() => ws.join(dir),
+            (Some(dir), _) => PathBuf::from(dir),
+        };
+
+        let resolved = requested.canonicalize().map_err(|e| {
+            BizClawError::Tool(format!("Invalid workdir '{}': {e}", requested.display()))
+        })?;
+
+        if let Some(ws) = &self.config.workspace {
+            let root = ws.canonicalize().unwrap_or_else(|_| ws.clone());
+            if !resolved.starts_with(&root) {
+                return Err(BizClawError::PermissionDenied(format!(
+                    "workdir '{}' is outside the workspace",
+                    resolved.display()
+                )));
+            }
+        }
+
+        if let Some(security) = &self.security
+            && !security.check_path(&resolved.to_string_lossy()).await?
+        {
+            return Err(BizClawError::PermissionDenied(format!(
+                "workdir '{}' is forbidden",
+                resolved.display()
+            )));
+        }
+
+        Ok(Some(resolved))
+    }
+
+    async fn run(&self, arguments: &str, sink: Option<&ToolOutputSink>) -> Result<ToolResult> {
+        let args: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| BizClawError::Tool(e.to_string()))?;
+
+        let command = args["command"]
+            .as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'command'".into()))?;
+
+        if let Some(security) = &self.security
+            && !security.check_command(command).await?
+        {
+            return Ok(ToolResult {
+                tool_call_id: String::new(),
+                output: format!("Permission denied: '{command}'"),
+                success: false,
+            });
+        }
+
+        let workdir = match self.resolve_workdir(args["workdir"].as_str()).await {
+            Ok(dir) => dir,
+            Err(e) => {
+                return Ok(ToolResult {
+                    tool_call_id: String::new(),
+                    output: e.to_string(),
+                    success: false,
+                });
+            }
+        };
+
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        if let Some(dir) = &workdir {
+            cmd.current_dir(dir);
+        }
+        cmd.stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn().map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let budget = OutputBudget::new(self.config.max_output_bytes);
+        let (stdout, stderr) = tokio::join!(
+            collect_stream(stdout, "stdout", &budget, sink),
+            collect_stream(stderr, "stderr", &budget, sink),
+        );
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+
+        let truncated = if budget.exhausted() {
+            format!(
+                "\n... [output truncated at {} bytes]",
+                self.config.max_output_bytes
+            )
+        } else {
+            String::new()
+        };
+
+        let output = if status.success() {
+            format!("{stdout}{truncated}")
+        } else {
+            format!(
+                "STDOUT:\n{stdout}\nSTDERR:\n{stderr}{truncated}\nExit code: {}",
+                status.code().unwrap_or(-1)
+            )
+        };
+
+        Ok(ToolResult {
+            tool_call_id: String::new(),
+            output,
+            success: status.success(),
+        })
     }
 }
 
@@ -19,6 +178,84 @@ impl Default for ShellTool {
     }
 }
 
+/// Byte budget shared by stdout and stderr readers.
+struct OutputBudget {
+    remaining: std::sync::atomic::AtomicUsize,
+    exhausted: std::sync::atomic::AtomicBool,
+}
+
+impl OutputBudget {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            remaining: std::sync::atomic::AtomicUsize::new(max_bytes),
+            exhausted: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// Reserve up to `want` bytes; returns how many may be kept.
+    fn take(&self, want: usize) -> usize {
+        use std::sync::atomic::Ordering;
+        let mut granted = 0;
+        let _ = self
+            .remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                granted = want.min(left);
+                Some(left - granted)
+            });
+        if granted < want {
+            self.exhausted.store(true, Ordering::SeqCst);
+        }
+        granted
+    }
+
+    fn exhausted(&self) -> bool {
+        self.exhausted.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// Read a child stream line by line (in pieces of at most
+/// [`MAX_LINE_BYTES`]), forwarding to the sink and keeping what fits in the
+/// budget. The stream is always drained so the child never blocks.
+async fn collect_stream<R: AsyncRead + Unpin>(
+    stream: Option<R>,
+    name: &str,
+    budget: &OutputBudget,
+    sink: Option<&ToolOutputSink>,
+) -> String {
+    let Some(stream) = stream else {
+        return String::new();
+    };
+    let mut reader = BufReader::new(stream);
+    let mut collected = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        match (&mut reader).take(MAX_LINE_BYTES).read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                if let Some(sink) = sink {
+                    let _ = sink.send(ToolOutputChunk {
+                        tool_name: "shell".into(),
+                        stream: name.into(),
+                        data: line.to_string(),
+                    });
+                }
+                let keep = budget.take(line.len());
+                if keep > 0 {
+                    let mut end = keep;
+                    while !line.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    collected.push_str(&line[..end]);
+                }
+            }
+        }
+    }
+    collected
+}
+
 #[async_trait]
 impl Tool for ShellTool {
     fn name(&self) -> &str {
@@ -38,7 +275,7 @@ impl Tool for ShellTool {
                     },
                     "workdir": {
                         "type": "string",
-                        "description": "Working directory (optional)"
+                        "description": "Working directory, relative to the workspace (optional)"
                     }
                 },
                 "required": ["command"]
@@ -47,42 +284,129 @@ impl Tool for ShellTool {
     }
 
     async fn execute(&self, arguments: &str) -> Result<ToolResult> {
-        let args: serde_json::Value = serde_json::from_str(arguments)
-            .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
+        self.run(arguments, None).await
+    }
 
-        let command = args["command"]
-            .as_str()
-            .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'command'".into()))?;
+    async fn execute_streaming(&self, arguments: &str, sink: &ToolOutputSink) -> Result<ToolResult> {
+        self.run(arguments, Some(sink)).await
+    }
+}
 
-        let workdir = args["workdir"].as_str();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut cmd = tokio::process::Command::new("sh");
-        cmd.arg("-c").arg(command);
-        if let Some(dir) = workdir {
-            cmd.current_dir(dir);
+    struct AllowOnly(&'static str);
+
+    #[async_trait]
+    impl SecurityPolicy for AllowOnly {
+        async fn check_command(&self, command: &str) -> Result<bool> {
+            Ok(command.split_whitespace().next() == Some(self.0))
         }
+        async fn check_path(&self, _path: &str) -> Result<bool> {
+            Ok(true)
+        }
+        fn autonomy_level(&self) -> &str {
+            "supervised"
+        }
+    }
 
-        let output = cmd
-            .output()
+    fn workspace() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-shell-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_workdir_outside_workspace_denied() {
+        let ws = workspace();
+        let tool = ShellTool::with_config(ShellConfig {
+            workspace: Some(ws.clone()),
+            ..Default::default()
+        });
+
+        let result = tool
+            .execute(r#"{"command": "pwd", "workdir": "/"}"#)
             .await
-            .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("outside the workspace"));
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let result = tool
+            .execute(r#"{"command": "pwd", "workdir": "../"}"#)
+            .await
+            .unwrap();
+        assert!(!result.success);
 
-        let result = if output.status.success() {
-            stdout
-        } else {
-            format!(
-                "STDOUT:\n{stdout}\nSTDERR:\n{stderr}\nExit code: {}",
-                output.status.code().unwrap_or(-1)
-            )
-        };
+        let result = tool
+            .execute(r#"{"command": "pwd", "workdir": "sub"}"#)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.trim().ends_with("sub"));
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output: result,
-            success: output.status.success(),
-        })
+        let _ = std::fs::remove_dir_all(ws);
+    }
+
+    #[tokio::test]
+    async fn test_output_cap_truncates() {
+        let tool = ShellTool::with_config(ShellConfig {
+            workspace: None,
+            max_output_bytes: 100,
+        });
+        let result = tool
+            .execute(r#"{"command": "yes hello | head -n 1000"}"#)
+            .await
+            .unwrap();
+        assert!(result.success);
+        let (kept, marker) = result.output.split_once("\n... [output truncated").unwrap();
+        assert_eq!(kept.len(), 100);
+        assert!(marker.contains("100 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_check_command_gate() {
+        let tool = ShellTool::new().with_security(Arc::new(AllowOnly("echo")));
+        let denied = tool.execute(r#"{"command": "rm -rf /tmp/x"}"#).await.unwrap();
+        assert!(!denied.success);
+        assert!(denied.output.contains("Permission denied"));
+
+        let allowed = tool.execute(r#"{"command": "echo ok"}"#).await.unwrap();
+        assert_eq!(allowed.output.trim(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_streams_lines_to_sink() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let tool = ShellTool::new();
+        let result = tool
+            .execute_streaming(r#"{"command": "echo one; echo two >&2"}"#, &tx)
+            .await
+            .unwrap();
+        assert!(result.success);
+        drop(tx);
+
+        let mut chunks = Vec::new();
+        while let Some(c) = rx.recv().await {
+            chunks.push(c);
+        }
+        assert!(chunks.iter().any(|c| c.stream == "stdout" && c.data == "one\n"));
+        assert!(chunks.iter().any(|c| c.stream == "stderr" && c.data == "two\n"));
+    }
+
+    #[tokio::test]
+    async fn test_long_line_read_in_pieces() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let line = "x".repeat(150 * 1024) + "\n";
+        let budget = OutputBudget::new(16);
+        let kept = collect_stream(Some(line.as_bytes()), "stdout", &budget, Some(&tx)).await;
+        drop(tx);
+
+        assert_eq!(kept, "x".repeat(16));
+        let mut sizes = Vec::new();
+        while let Some(c) = rx.recv().await {
+            sizes.push(c.data.len());
+        }
+        assert_eq!(sizes, [64 * 1024, 64 * 1024, 22 * 1024 + 1]);
     }
 }
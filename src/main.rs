@@ -10,6 +10,7 @@
 //!   bizclaw onboard                    # First-time setup
 //!   bizclaw brain download             # Download local model
 //!   bizclaw config show                # Show configuration
+//!   bizclaw mcp                        # MCP stdio server for Claude Desktop
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -71,12 +72,24 @@ enum Commands {
         action: BrainAction,
     },
 
+    /// Autonomous Hands management
+    Hands {
+        #[command(subcommand)]
+        action: HandsAction,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
 
+    /// Manage stored secrets, referenced from config as ${secret:<name>}
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
     /// Show system info
     Info,
 
@@ -104,6 +117,18 @@ enum Commands {
 
     /// Interactive setup wizard
     Init,
+
+    /// MCP stdio server for clients like Claude Desktop — relays to the
+    /// `/mcp` endpoint of a running gateway (`[mcp_export]`)
+    Mcp {
+        /// Gateway MCP endpoint (default: the configured gateway)
+        #[arg(long)]
+        url: Option<String>,
+
+        /// API key with chat scope (default: $BIZCLAW_API_KEY)
+        #[arg(long)]
+        api_key: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -116,6 +141,16 @@ enum ChannelAction {
     },
     /// List available channels
     List,
+    /// DEV ONLY — receive Discord/Slack/webhook events locally without deploying
+    Dev {
+        /// Local gateway port the tunnel should forward to
+        #[arg(short, long, default_value = "3000")]
+        port: u16,
+
+        /// Slack channel ID to poll (bot token read from SLACK_BOT_TOKEN)
+        #[arg(long)]
+        slack_channel: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -134,6 +169,60 @@ enum BrainAction {
         #[arg(default_value = "Hello, who are you?")]
         prompt: String,
     },
+    /// Show GGUF model details
+    Info {
+        /// Path to a .gguf file
+        model: std::path::PathBuf,
+        /// List every tensor
+        #[arg(long)]
+        tensors: bool,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Measure perplexity over a text file
+    Eval {
+        /// Text corpus to score
+        #[arg(long)]
+        file: std::path::PathBuf,
+        /// GGUF model (default: first model in ~/.bizclaw/models)
+        #[arg(long)]
+        model: Option<std::path::PathBuf>,
+        /// Tokens per scored window
+        #[arg(long, default_value_t = 512)]
+        ctx: u32,
+    },
+    /// Serve the local brain over an OpenAI-compatible API
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8089)]
+        port: u16,
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Default GGUF model (default: brain.model_path, else first model in ~/.bizclaw/models)
+        #[arg(long)]
+        model: Option<std::path::PathBuf>,
+        /// Require this bearer token on API requests
+        #[arg(long)]
+        api_key: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HandsAction {
+    /// Install a hand from a git repository or zip archive (path or URL)
+    Install {
+        /// Git URL, or .zip file / URL holding HAND.toml
+        source: String,
+    },
+    /// Remove an installed hand
+    Uninstall {
+        /// Hand name
+        name: String,
+    },
+    /// List built-in and installed hands
+    List,
 }
 
 #[derive(Subcommand)]
@@ -146,6 +235,178 @@ enum ConfigAction {
     Set { key: String, value: String },
 }
 
+#[derive(Subcommand)]
+enum SecretsAction {
+    /// Store a secret
+    Set {
+        /// Secret name
+        name: String,
+        /// Value (read from stdin when omitted, keeping it out of shell history)
+        value: Option<String>,
+    },
+    /// List secret names
+    List,
+    /// Delete a secret
+    Remove {
+        /// Secret name
+        name: String,
+    },
+}
+
+/// First `.gguf` file in `dir`, if any.
+fn first_gguf_model(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    std::fs::read_dir(dir).ok().and_then(|entries| {
+        entries
+            .filter_map(|e| e.ok())
+            .find(|e| e.path().extension().and_then(|ext| ext.to_str()) == Some("gguf"))
+            .map(|e| e.path())
+    })
+}
+
+/// Start the supervised llama-server when `[llamacpp]` is enabled, so the
+/// `llamacpp` provider connects to it. Keep the handle alive while serving.
+async fn start_llamacpp(
+    config: &bizclaw_core::BizClawConfig,
+) -> Option<bizclaw_brain::llamacpp::LlamaCppManager> {
+    if !config.llamacpp.enabled {
+        return None;
+    }
+    let model = [&config.llamacpp.model_path, &config.brain.model_path]
+        .into_iter()
+        .find(|p| !p.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| first_gguf_model(&bizclaw_core::BizClawConfig::home_dir().join("models")));
+    let Some(model) = model else {
+        println!("   ⚠️ llama-server skipped: no model (set llamacpp.model_path)");
+        return None;
+    };
+    match bizclaw_brain::llamacpp::LlamaCppManager::start(&config.llamacpp, &model).await {
+        Ok(manager) => {
+            println!("   🦙 llama-server: {}", manager.base_url());
+            Some(manager)
+        }
+        Err(e) => {
+            println!("   ⚠️ llama-server not started: {e}");
+            None
+        }
+    }
+}
+
+/// Dev-mode channel listener — tunnels webhooks and polls Discord/Slack
+/// so messages reach a local agent without a public deployment.
+async fn run_channel_dev(
+    config: bizclaw_core::BizClawConfig,
+    port: u16,
+    slack_channel: Option<String>,
+) -> Result<()> {
+    use bizclaw_channels::devmode::{DevTunnel, inbound_strategy};
+    use bizclaw_core::traits::Channel;
+    use bizclaw_core::types::IncomingMessage;
+    use futures::stream::{BoxStream, StreamExt};
+
+    println!("🦀 BizClaw Channel Dev Mode");
+    println!("   ⚠️  DEV ONLY — temporary tunnels and polling shims, not for production.\n");
+
+    let mut tunnel = match DevTunnel::from_config(&config.tunnel, port) {
+        Ok(mut t) => match t.start().await {
+            Ok(url) => {
+                println!("  🌐 Tunnel ({}): {url}", config.tunnel.provider);
+                for (channel, url) in t.webhook_urls() {
+                    println!("     {channel:<9} → {url}");
+                }
+                println!("     (run `bizclaw serve --port {port}` so these routes are served)");
+                Some(t)
+            }
+            Err(e) => {
+                println!("  ⚠️ Tunnel not started: {e}");
+                None
+            }
+        },
+        Err(e) => {
+            println!("  ⬜ Tunnel skipped: {e}");
+            None
+        }
+    };
+
+    let mut streams: Vec<BoxStream<'static, IncomingMessage>> = Vec::new();
+    let mut discord = None;
+    let mut slack = None;
+
+    if let Some(dc) = config.channel.discord.as_ref().filter(|d| d.enabled) {
+        let dc_config = bizclaw_channels::discord::DiscordConfig {
+            bot_token: dc.bot_token.clone(),
+            enabled: true,
+            intents: (1 << 0) | (1 << 9) | (1 << 12) | (1 << 15),
+        };
+        println!("  💬 discord   — {:?}", inbound_strategy("discord").unwrap());
+        let listener = bizclaw_channels::discord::DiscordChannel::new(dc_config.clone());
+        streams.push(listener.start_gateway().boxed());
+        discord = Some(bizclaw_channels::discord::DiscordChannel::new(dc_config));
+    }
+
+    if let Some(channel_id) = slack_channel {
+        let token = std::env::var("SLACK_BOT_TOKEN").unwrap_or_default();
+        if token.is_empty() {
+            println!("  ⚠️ slack skipped: SLACK_BOT_TOKEN not set");
+        } else {
+            let sl_config = bizclaw_channels::slack::SlackConfig {
+                bot_token: token,
+                default_channel: channel_id.clone(),
+                ..Default::default()
+            };
+            println!("  💬 slack     — {:?} ({channel_id})", inbound_strategy("slack").unwrap());
+            let listener = bizclaw_channels::slack::SlackChannel::new(sl_config.clone());
+            streams.push(listener.start_polling(channel_id, 3).boxed());
+            slack = Some(bizclaw_channels::slack::SlackChannel::new(sl_config));
+        }
+    }
+
+    if streams.is_empty() && tunnel.is_none() {
+        println!("\nNothing to do — configure [channel.discord], --slack-channel, or [tunnel].");
+        return Ok(());
+    }
+
+    let mut agent = bizclaw_agent::Agent::new(config)?;
+    let mut incoming = futures::stream::select_all(streams);
+    println!("\nListening. Press Ctrl+C to stop.");
+
+    loop {
+        tokio::select! {
+            msg = incoming.next(), if !incoming.is_empty() => {
+                let Some(msg) = msg else { continue };
+                let reply = match agent.handle_incoming(&msg).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::error!("[dev] agent error: {e}");
+                        continue;
+                    }
+                };
+                let sent = match msg.channel.as_str() {
+                    "discord" => match &discord {
+                        Some(d) => d.send(reply).await,
+                        None => Ok(()),
+                    },
+                    "slack" => match &slack {
+                        Some(s) => s.send(reply).await,
+                        None => Ok(()),
+                    },
+                    _ => Ok(()),
+                };
+                if let Err(e) = sent {
+                    tracing::error!("[dev] reply failed on {}: {e}", msg.channel);
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    if let Some(t) = tunnel.as_mut() {
+        t.stop().await;
+    }
+    println!("\n👋 Dev mode stopped.");
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -156,18 +417,30 @@ async fn main() -> Result<()> {
     } else {
         "bizclaw=info"
     };
+    // stdout carries the protocol when serving MCP
+    let writer = if matches!(cli.command, Commands::Mcp { .. }) {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stderr)
+    } else {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stdout)
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(filter)),
         )
         .with_target(false)
+        .with_writer(writer)
         .init();
 
-    // Load config
-    let mut config = if let Some(path) = &cli.config {
-        bizclaw_core::BizClawConfig::load_from(std::path::Path::new(path))?
+    // Load config, resolving ${secret:…} references
+    let config_path = cli
+        .config
+        .as_ref()
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(bizclaw_core::BizClawConfig::default_path);
+    let mut config = if cli.config.is_some() || config_path.exists() {
+        bizclaw_security::secrets::load_config(&config_path)?
     } else {
-        bizclaw_core::BizClawConfig::load()?
+        bizclaw_core::BizClawConfig::default()
     };
 
     match cli.command {
@@ -185,6 +458,7 @@ async fn main() -> Result<()> {
                 config.default_model = m;
             }
 
+            let _llamacpp = start_llamacpp(&config).await;
             let mut agent = bizclaw_agent::Agent::new(config)?;
 
             if interactive || message.is_none() {
@@ -291,6 +565,12 @@ async fn main() -> Result<()> {
                         }
                     );
                 }
+                ChannelAction::Dev {
+                    port,
+                    slack_channel,
+                } => {
+                    run_channel_dev(config, port, slack_channel).await?;
+                }
             }
         }
 
@@ -305,29 +585,15 @@ async fn main() -> Result<()> {
                     let model_dir = bizclaw_core::BizClawConfig::home_dir().join("models");
                     std::fs::create_dir_all(&model_dir)?;
 
-                    let (url, filename) = match model.as_str() {
-                        "tinyllama-1.1b" | "tinyllama" => (
-                            "https://huggingface.co/TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF/resolve/main/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf",
-                            "tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf",
-                        ),
-                        "phi-2" => (
-                            "https://huggingface.co/TheBloke/phi-2-GGUF/resolve/main/phi-2.Q4_K_M.gguf",
-                            "phi-2.Q4_K_M.gguf",
-                        ),
-                        "llama-3.2-1b" | "llama3.2" => (
-                            "https://huggingface.co/bartowski/Llama-3.2-1B-Instruct-GGUF/resolve/main/Llama-3.2-1B-Instruct-Q4_K_M.gguf",
-                            "Llama-3.2-1B-Instruct-Q4_K_M.gguf",
-                        ),
-                        other if other.starts_with("http") => (other, "custom-model.gguf"),
-                        _ => {
-                            println!("❌ Unknown model: {model}");
-                            println!("   Available: tinyllama-1.1b, phi-2, llama-3.2-1b");
-                            println!("   Or provide a direct URL to a .gguf file");
-                            return Ok(());
-                        }
+                    let Some((url, filename)) = bizclaw_brain::download::resolve(&model) else {
+                        let names: Vec<&str> = bizclaw_brain::download::CATALOG.iter().map(|m| m.name).collect();
+                        println!("❌ Unknown model: {model}");
+                        println!("   Available: {}", names.join(", "));
+                        println!("   Or provide a direct URL to a .gguf file");
+                        return Ok(());
                     };
 
-                    let dest = model_dir.join(filename);
+                    let dest = model_dir.join(&filename);
                     if dest.exists() {
                         println!("✅ Model already downloaded: {}", dest.display());
                         return Ok(());
@@ -339,44 +605,22 @@ async fn main() -> Result<()> {
                     println!();
 
                     // Stream download with progress
-                    let client = reqwest::Client::new();
-                    let response = client
-                        .get(url)
-                        .send()
-                        .await
-                        .map_err(|e| anyhow::anyhow!("Download failed: {e}"))?;
-
-                    let total_size = response.content_length().unwrap_or(0);
-                    println!(
-                        "   Total size: {:.1} MB",
-                        total_size as f64 / 1024.0 / 1024.0
-                    );
-
-                    let mut file = tokio::fs::File::create(&dest).await?;
-                    let mut downloaded: u64 = 0;
-                    let mut stream = response.bytes_stream();
-
-                    use futures::StreamExt;
-                    use tokio::io::AsyncWriteExt;
-
-                    while let Some(chunk) = stream.next().await {
-                        let chunk = chunk.map_err(|e| anyhow::anyhow!("Download error: {e}"))?;
-                        file.write_all(&chunk).await?;
-                        downloaded += chunk.len() as u64;
-
-                        if total_size > 0 {
-                            let pct = (downloaded as f64 / total_size as f64 * 100.0) as u32;
-                            let mb = downloaded as f64 / 1024.0 / 1024.0;
-                            print!(
-                                "\r   ⬇️  {mb:.1} MB / {:.1} MB ({pct}%)",
-                                total_size as f64 / 1024.0 / 1024.0
-                            );
-                            use std::io::Write;
-                            std::io::stdout().flush().ok();
+                    let mut last_pct = None;
+                    bizclaw_brain::download::download(&url, &dest, &mut |downloaded, total| {
+                        let Some(total) = total.filter(|t| *t > 0) else { return };
+                        let pct = (downloaded as f64 / total as f64 * 100.0) as u32;
+                        if last_pct == Some(pct) {
+                            return;
                         }
-                    }
+                        last_pct = Some(pct);
+                        let mb = downloaded as f64 / 1024.0 / 1024.0;
+                        print!("\r   ⬇️  {mb:.1} MB / {:.1} MB ({pct}%)", total as f64 / 1024.0 / 1024.0);
+                        use std::io::Write;
+                        std::io::stdout().flush().ok();
+                    })
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
 
-                    file.flush().await?;
                     println!("\n\n✅ Download complete: {}", dest.display());
                     println!("   Test with: bizclaw brain test \"Hello!\"");
                 }
@@ -411,9 +655,9 @@ async fn main() -> Result<()> {
                     }
 
                     println!("\n📦 Available for download:");
-                    println!("  - tinyllama-1.1b  (~638 MB, recommended for Pi)");
-                    println!("  - phi-2           (~1.6 GB)");
-                    println!("  - llama-3.2-1b    (~750 MB)");
+                    for m in bizclaw_brain::download::CATALOG {
+                        println!("  - {:<15} (~{} MB)", m.name, m.size_mb);
+                    }
                     println!("\n  Use: bizclaw brain download <model-name>");
                 }
                 BrainAction::Test { prompt } => {
@@ -421,14 +665,7 @@ async fn main() -> Result<()> {
 
                     // Try to find and load a model
                     let model_dir = bizclaw_core::BizClawConfig::home_dir().join("models");
-                    let model_path = std::fs::read_dir(&model_dir).ok().and_then(|entries| {
-                        entries
-                            .filter_map(|e| e.ok())
-                            .find(|e| {
-                                e.path().extension().and_then(|ext| ext.to_str()) == Some("gguf")
-                            })
-                            .map(|e| e.path())
-                    });
+                    let model_path = first_gguf_model(&model_dir);
 
                     match model_path {
                         Some(path) => {
@@ -453,13 +690,178 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                BrainAction::Info {
+                    model,
+                    tensors,
+                    json,
+                } => {
+                    let info = bizclaw_brain::gguf::inspect(&model)?;
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&info)?);
+                        return Ok(());
+                    }
+                    let opt = |v: Option<u32>| v.map_or("?".to_string(), |v| v.to_string());
+                    println!("🧠 {}\n", model.display());
+                    println!("   Name:         {}", info.name.as_deref().unwrap_or("?"));
+                    println!("   Architecture: {}", info.architecture.as_deref().unwrap_or("?"));
+                    println!(
+                        "   Parameters:   {:.2}B ({:.1} MB on disk, GGUF v{})",
+                        info.parameters as f64 / 1e9,
+                        info.file_size as f64 / (1024.0 * 1024.0),
+                        info.version
+                    );
+                    println!("   Quant:        {}", info.quant_mix.as_deref().unwrap_or("?"));
+                    println!(
+                        "   Layers:       {} (dim {}, heads {}/{} kv)",
+                        opt(info.block_count),
+                        opt(info.embedding_length),
+                        opt(info.head_count),
+                        opt(info.head_count_kv)
+                    );
+                    println!("   Context:      {}", opt(info.context_length));
+                    println!(
+                        "   Vocab:        {} ({})",
+                        opt(info.vocab_size),
+                        info.tokenizer.as_deref().unwrap_or("?")
+                    );
+                    println!(
+                        "   Chat format:  {}{}",
+                        info.chat_format,
+                        if info.chat_template.is_some() { "" } else { " (no template in file)" }
+                    );
+                    let types: Vec<String> = info
+                        .tensor_types
+                        .iter()
+                        .map(|(t, n)| format!("{t} ×{n}"))
+                        .collect();
+                    println!("   Tensors:      {} ({})", info.tensors.len(), types.join(", "));
+                    if tensors {
+                        println!();
+                        for t in &info.tensors {
+                            println!("   {:<40} {:<8} {:?}", t.name, t.ggml_type, t.dims);
+                        }
+                    }
+                }
+                BrainAction::Eval { file, model, ctx } => {
+                    let model_dir = bizclaw_core::BizClawConfig::home_dir().join("models");
+                    let Some(path) = model.or_else(|| first_gguf_model(&model_dir)) else {
+                        println!("❌ No model found in {}", model_dir.display());
+                        println!("   Run: bizclaw brain download tinyllama-1.1b");
+                        return Ok(());
+                    };
+                    let text = std::fs::read_to_string(&file)?;
+
+                    println!("🧠 Perplexity evaluation");
+                    println!("   Model:  {}", path.display());
+                    println!("   Corpus: {} ({} bytes)", file.display(), text.len());
+                    let mut engine = bizclaw_brain::BrainEngine::new(bizclaw_brain::BrainConfig {
+                        context_length: ctx,
+                        ..Default::default()
+                    });
+                    engine.load_model(&path)?;
+                    if let Some(info) = engine.model_info() {
+                        println!("   Info:   {info}");
+                    }
+
+                    let started = std::time::Instant::now();
+                    let result = engine.evaluate(&text, Some(ctx as usize))?;
+                    println!(
+                        "\n📊 Perplexity: {:.4} (nll {:.4}) over {} tokens in {} windows of {}",
+                        result.perplexity(),
+                        result.nll,
+                        result.tokens,
+                        result.chunks,
+                        result.n_ctx
+                    );
+                    println!("   Took {:.1}s", started.elapsed().as_secs_f64());
+                }
+                BrainAction::Serve {
+                    port,
+                    host,
+                    model,
+                    api_key,
+                } => {
+                    let model_dir = bizclaw_core::BizClawConfig::home_dir().join("models");
+                    let configured = std::path::PathBuf::from(&config.brain.model_path);
+                    let Some(path) = model
+                        .or_else(|| configured.is_file().then_some(configured))
+                        .or_else(|| first_gguf_model(&model_dir))
+                    else {
+                        println!("❌ No model found in {}", model_dir.display());
+                        println!("   Run: bizclaw brain download tinyllama-1.1b");
+                        return Ok(());
+                    };
+
+                    let pool = std::sync::Arc::new(bizclaw_brain::pool::ModelPool::new(
+                        bizclaw_brain::BrainConfig::from(&config.brain),
+                        config.brain.model_pool_mb as usize * 1024 * 1024,
+                    ));
+                    println!("🧠 Loading {}...", path.display());
+                    pool.get(&path)?;
+
+                    let mut server = bizclaw_gateway::brain_server::BrainServer::new(
+                        pool,
+                        path,
+                        model_dir,
+                        config.brain.max_tokens,
+                    );
+                    if let Some(key) = api_key {
+                        server = server.with_api_key(key);
+                    }
+                    println!("🌐 OpenAI-compatible API at http://{host}:{port}/v1");
+                    bizclaw_gateway::brain_server::serve(server, &host, port).await?;
+                }
+            }
+        }
+
+        Commands::Hands { action } => {
+            let hands_dir = config.hands.dir_path();
+            let mut registry = bizclaw_hands::HandRegistry::with_defaults();
+            registry.load_dir(&hands_dir);
+            match action {
+                HandsAction::Install { source } => {
+                    println!("📦 Installing hand from {source}...");
+                    let name = registry
+                        .install(&source, &hands_dir)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Install failed: {e}"))?;
+                    println!("✅ Installed '{name}' into {}", hands_dir.join(&name).display());
+                    println!("   Enable it from the dashboard or add it to [hands] enabled.");
+                }
+                HandsAction::Uninstall { name } => {
+                    registry
+                        .uninstall(&name, &hands_dir)
+                        .map_err(|e| anyhow::anyhow!("Uninstall failed: {e}"))?;
+                    println!("✅ Removed hand '{name}'.");
+                }
+                HandsAction::List => {
+                    println!("🤚 Hands\n");
+                    for hand in registry.list() {
+                        let enabled = config.hands.enabled.contains(&hand.manifest.name);
+                        println!(
+                            "  {} {} {:<10} {} — {}",
+                            if enabled { "✅" } else { "⏹" },
+                            hand.manifest.icon,
+                            hand.manifest.name,
+                            hand.manifest.schedule,
+                            hand.manifest.label
+                        );
+                    }
+                    let installed = bizclaw_hands::install::installed_hands(&hands_dir);
+                    if !installed.is_empty() {
+                        println!("\n📦 Installed:");
+                        for hand in installed {
+                            println!("  - {} v{} ({})", hand.name, hand.version, hand.source);
+                        }
+                    }
+                    println!("\n  Use: bizclaw hands install <git-url|zip>");
+                }
             }
         }
 
         Commands::Config { action } => match action {
             ConfigAction::Show => {
-                let content = toml::to_string_pretty(&config)?;
-                println!("{content}");
+                println!("{}", config.to_toml()?);
             }
             ConfigAction::Reset => {
                 let config = bizclaw_core::BizClawConfig::default();
@@ -472,6 +874,44 @@ async fn main() -> Result<()> {
             }
         },
 
+        Commands::Secrets { action } => {
+            let dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+            let mut store = bizclaw_security::secrets::SecretStore::open(dir, config.secrets.encrypt)?;
+            match action {
+                SecretsAction::Set { name, value } => {
+                    let value = match value {
+                        Some(v) => v,
+                        None => {
+                            let mut line = String::new();
+                            std::io::stdin().read_line(&mut line)?;
+                            line.trim_end_matches(['\r', '\n']).to_string()
+                        }
+                    };
+                    store.set(&name, &value);
+                    store.save()?;
+                    println!("✅ Stored secret '{name}'. Use it in config as \"${{secret:{name}}}\".");
+                }
+                SecretsAction::List => {
+                    let mut names = store.keys();
+                    names.sort();
+                    if names.is_empty() {
+                        println!("No secrets stored.");
+                    }
+                    for name in names {
+                        println!("  {name}");
+                    }
+                }
+                SecretsAction::Remove { name } => {
+                    if store.remove(&name).is_some() {
+                        store.save()?;
+                        println!("🗑️  Removed secret '{name}'.");
+                    } else {
+                        println!("No secret named '{name}'.");
+                    }
+                }
+            }
+        }
+
         Commands::Info => {
             println!("🦀 BizClaw v{}", env!("CARGO_PKG_VERSION"));
             println!(
@@ -510,6 +950,7 @@ async fn main() -> Result<()> {
                 config.default_model = m;
             }
 
+            let _llamacpp = start_llamacpp(&config).await;
             let mut agent = bizclaw_agent::Agent::new(config)?;
 
             println!("🦀 BizClaw v{} — Chat Mode", env!("CARGO_PKG_VERSION"));
@@ -584,6 +1025,8 @@ async fn main() -> Result<()> {
             println!("   │     URL: {}  │", url);
             println!("   └──────────────────────────────────────────────┘");
 
+            let _llamacpp = start_llamacpp(&config).await;
+
             // Start configured channels in background
             // ═══════════════════════════════════════════
             let channel_config = config.channel.clone();
@@ -602,7 +1045,7 @@ async fn main() -> Result<()> {
                     );
                     let cfg_clone = agent_config.clone();
                     tokio::spawn(async move {
-                        run_channel_loop("telegram", tg.start_polling(), cfg_clone).await;
+                        run_channel_loop("telegram", tg.start_polling(), cfg_clone, None).await;
                     });
                 }
 
@@ -619,7 +1062,7 @@ async fn main() -> Result<()> {
                     );
                     let cfg_clone = agent_config.clone();
                     tokio::spawn(async move {
-                        run_channel_loop("discord", dc.start_gateway(), cfg_clone).await;
+                        run_channel_loop("discord", dc.start_gateway(), cfg_clone, None).await;
                     });
                 }
 
@@ -639,8 +1082,10 @@ async fn main() -> Result<()> {
                         },
                     );
                     let cfg_clone = agent_config.clone();
+                    // The clone shares the listener's threads to reply in them
+                    let replier: Box<dyn bizclaw_core::traits::Channel> = Box::new(em.clone());
                     tokio::spawn(async move {
-                        run_channel_loop("email", em.start_polling(), cfg_clone).await;
+                        run_channel_loop("email", em.start_polling(), cfg_clone, Some(replier)).await;
                     });
                 }
 
@@ -691,6 +1136,17 @@ async fn main() -> Result<()> {
         Commands::Init => {
             run_init_wizard().await?;
         }
+
+        Commands::Mcp { url, api_key } => {
+            let url = url.unwrap_or_else(|| {
+                format!("http://{}:{}/mcp", config.gateway.host, config.gateway.port)
+            });
+            let api_key = api_key.or_else(|| std::env::var("BIZCLAW_API_KEY").ok());
+            tracing::info!("🔗 Relaying MCP stdio to {url}");
+            bizclaw_mcp::server::relay_stdio(&url, api_key.as_deref())
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
     }
 
     Ok(())
@@ -830,8 +1286,12 @@ async fn run_init_wizard() -> Result<()> {
     config.default_model = model.clone();
     config.identity.name = bot_name;
 
-    // Save
-    config.save()?;
+    // Save, with the API key moved into the secret store
+    let path = bizclaw_core::BizClawConfig::default_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, bizclaw_security::secrets::config_toml(&config, &path)?)?;
 
     // Create directories
     let home = bizclaw_core::BizClawConfig::home_dir();
@@ -864,7 +1324,14 @@ async fn run_init_wizard() -> Result<()> {
 
 /// Run a channel listener loop — receives messages, routes through Agent, sends replies.
 /// Works for any channel that produces a Stream<Item = IncomingMessage>.
-async fn run_channel_loop<S>(channel_name: &str, mut stream: S, config: bizclaw_core::BizClawConfig)
+/// `replier` sends responses for channels whose replies need state kept by
+/// the listener (email threads).
+async fn run_channel_loop<S>(
+    channel_name: &str,
+    mut stream: S,
+    config: bizclaw_core::BizClawConfig,
+    replier: Option<Box<dyn bizclaw_core::traits::Channel>>,
+)
 where
     S: futures::Stream<Item = bizclaw_core::types::IncomingMessage> + Unpin,
 {
@@ -948,16 +1415,17 @@ where
                         }
                     }
                     "email" => {
-                        // Reply via SMTP (if email channel has send capability)
-                        if let Some(ref _email_cfg) = config.channel.email {
-                            tracing::info!(
-                                "[email] Reply to {}: {}...",
-                                incoming.sender_id,
-                                &response[..response.len().min(60)]
-                            );
-                            // Email replies are handled by the EmailChannel's send() method
-                            // The incoming.sender_id contains the From address
-                            // For now, log the reply — full SMTP send is in EmailChannel
+                        // Reply via SMTP inside the email's thread
+                        if let Some(ref channel) = replier {
+                            let reply = bizclaw_core::types::OutgoingMessage {
+                                thread_id: incoming.thread_id.clone(),
+                                content: response.clone(),
+                                thread_type: incoming.thread_type.clone(),
+                                reply_to: incoming.reply_to.clone(),
+                            };
+                            if let Err(e) = channel.send(reply).await {
+                                tracing::error!("[email] Reply to {} failed: {e}", incoming.sender_id);
+                            }
                         }
                     }
                     _ => {